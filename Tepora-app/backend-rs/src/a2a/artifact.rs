@@ -0,0 +1,276 @@
+//! A2A artifact exchange format.
+//!
+//! Delegated tasks can return files and structured data in addition to
+//! plain text. Artifacts are converted to and from the graph's
+//! `SharedContext` artifacts so both sides of a delegation share one store.
+
+use std::collections::HashMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::graph::state::{Artifact, SharedContext};
+
+/// Metadata key that carries the A2A artifact id inside a shared artifact.
+const META_A2A_ID: &str = "a2a_artifact_id";
+/// Metadata key that carries the artifact name inside a shared artifact.
+const META_A2A_NAME: &str = "a2a_name";
+/// Metadata key that carries the source agent of an incoming artifact.
+const META_A2A_SOURCE: &str = "a2a_source";
+/// Source recorded for artifacts delegated by a WebSocket client.
+pub const CLIENT_SOURCE: &str = "client";
+
+/// One part of an A2A artifact.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ArtifactPart {
+    /// Plain text
+    Text { text: String },
+    /// File payload, either inline (base64) or by reference (URI)
+    File {
+        name: String,
+        mime_type: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bytes_base64: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        uri: Option<String>,
+    },
+    /// Structured JSON data
+    Data { data: Value },
+}
+
+impl ArtifactPart {
+    fn kind(&self) -> &'static str {
+        match self {
+            ArtifactPart::Text { .. } => "text",
+            ArtifactPart::File { .. } => "file",
+            ArtifactPart::Data { .. } => "data",
+        }
+    }
+}
+
+/// An artifact exchanged between agents.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct A2AArtifact {
+    /// Unique artifact identifier
+    pub id: String,
+    /// Human readable name
+    pub name: String,
+    /// Optional description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Content parts
+    pub parts: Vec<ArtifactPart>,
+    /// Arbitrary metadata
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, Value>,
+}
+
+impl A2AArtifact {
+    /// Create an artifact with a fresh id.
+    pub fn new(name: impl Into<String>, parts: Vec<ArtifactPart>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: name.into(),
+            description: None,
+            parts,
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Create a single-part text artifact.
+    pub fn text(name: impl Into<String>, text: impl Into<String>) -> Self {
+        Self::new(name, vec![ArtifactPart::Text { text: text.into() }])
+    }
+
+    /// Create a single-part structured data artifact.
+    pub fn data(name: impl Into<String>, data: Value) -> Self {
+        Self::new(name, vec![ArtifactPart::Data { data }])
+    }
+
+    /// Create a single-part inline file artifact.
+    pub fn file(
+        name: impl Into<String>,
+        mime_type: impl Into<String>,
+        bytes_base64: impl Into<String>,
+    ) -> Self {
+        let name = name.into();
+        Self::new(
+            name.clone(),
+            vec![ArtifactPart::File {
+                name,
+                mime_type: mime_type.into(),
+                bytes_base64: Some(bytes_base64.into()),
+                uri: None,
+            }],
+        )
+    }
+
+    /// Convert a graph artifact into an outgoing A2A artifact.
+    ///
+    /// Content that parses as JSON is sent as a `data` part, everything
+    /// else as a `text` part.
+    pub fn from_shared(artifact: &Artifact) -> Self {
+        let mut metadata = artifact.metadata.clone();
+        let id = metadata
+            .remove(META_A2A_ID)
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let name = metadata
+            .remove(META_A2A_NAME)
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_else(|| artifact.artifact_type.clone());
+
+        let part = match serde_json::from_str::<Value>(&artifact.content) {
+            Ok(data @ (Value::Object(_) | Value::Array(_))) => ArtifactPart::Data { data },
+            _ => ArtifactPart::Text {
+                text: artifact.content.clone(),
+            },
+        };
+        metadata.insert(
+            "artifact_type".to_string(),
+            Value::from(artifact.artifact_type.clone()),
+        );
+
+        Self {
+            id,
+            name,
+            description: None,
+            parts: vec![part],
+            metadata,
+        }
+    }
+
+    /// Convert an incoming A2A artifact into graph artifacts, one per part.
+    pub fn to_shared(&self, source: &str) -> Vec<Artifact> {
+        self.parts
+            .iter()
+            .map(|part| {
+                let mut metadata = self.metadata.clone();
+                metadata.remove("artifact_type");
+                metadata.insert(META_A2A_ID.to_string(), Value::from(self.id.clone()));
+                metadata.insert(META_A2A_NAME.to_string(), Value::from(self.name.clone()));
+                metadata.insert(META_A2A_SOURCE.to_string(), Value::from(source));
+                if let Some(description) = &self.description {
+                    metadata.insert("description".to_string(), Value::from(description.clone()));
+                }
+
+                let content = match part {
+                    ArtifactPart::Text { text } => text.clone(),
+                    ArtifactPart::Data { data } => data.to_string(),
+                    ArtifactPart::File {
+                        name,
+                        mime_type,
+                        bytes_base64,
+                        uri,
+                    } => {
+                        metadata.insert("file_name".to_string(), Value::from(name.clone()));
+                        metadata.insert("mime_type".to_string(), Value::from(mime_type.clone()));
+                        if let Some(uri) = uri {
+                            metadata.insert("uri".to_string(), Value::from(uri.clone()));
+                        }
+                        bytes_base64.clone().unwrap_or_default()
+                    }
+                };
+
+                Artifact {
+                    artifact_type: format!("a2a_{}", part.kind()),
+                    content,
+                    metadata,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Collect outgoing artifacts from a graph run's shared context.
+pub fn collect_outgoing(shared: &SharedContext) -> Vec<A2AArtifact> {
+    shared
+        .artifacts
+        .iter()
+        .map(A2AArtifact::from_shared)
+        .collect()
+}
+
+/// Store incoming artifacts in the shared context, skipping ids already present.
+pub fn store_incoming(
+    shared: &mut SharedContext,
+    source: &str,
+    artifacts: &[A2AArtifact],
+) -> usize {
+    let mut stored = 0;
+    for artifact in artifacts {
+        let already_stored = shared.artifacts.iter().any(|existing| {
+            existing.metadata.get(META_A2A_ID).and_then(|v| v.as_str())
+                == Some(artifact.id.as_str())
+        });
+        if already_stored {
+            continue;
+        }
+        for converted in artifact.to_shared(source) {
+            shared.artifacts.push(converted);
+            stored += 1;
+        }
+    }
+    stored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn file_part_serializes_with_kind_tag() {
+        let artifact = A2AArtifact::file("report.csv", "text/csv", "YSxiCjEsMg==");
+        let value = serde_json::to_value(&artifact).unwrap();
+        assert_eq!(value["parts"][0]["kind"], "file");
+        assert_eq!(value["parts"][0]["mime_type"], "text/csv");
+        assert!(value["parts"][0].get("uri").is_none());
+
+        let parsed: A2AArtifact = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, artifact);
+    }
+
+    #[test]
+    fn shared_json_content_becomes_data_part() {
+        let shared = Artifact {
+            artifact_type: "tool_summary".to_string(),
+            content: r#"{"rows": 3}"#.to_string(),
+            metadata: HashMap::from([("tool".to_string(), json!("native_search"))]),
+        };
+        let artifact = A2AArtifact::from_shared(&shared);
+        assert_eq!(artifact.name, "tool_summary");
+        assert_eq!(
+            artifact.parts,
+            vec![ArtifactPart::Data {
+                data: json!({"rows": 3})
+            }]
+        );
+        assert_eq!(artifact.metadata["artifact_type"], "tool_summary");
+    }
+
+    #[test]
+    fn store_incoming_roundtrips_and_skips_duplicates() {
+        let mut shared = SharedContext::default();
+        let artifact = A2AArtifact::data("plan", json!({"steps": ["a", "b"]}));
+
+        assert_eq!(
+            store_incoming(&mut shared, "planner", std::slice::from_ref(&artifact)),
+            1
+        );
+        assert_eq!(
+            store_incoming(&mut shared, "planner", std::slice::from_ref(&artifact)),
+            0
+        );
+        assert_eq!(shared.artifacts[0].artifact_type, "a2a_data");
+        assert_eq!(shared.artifacts[0].metadata[META_A2A_SOURCE], "planner");
+
+        let outgoing = collect_outgoing(&shared);
+        assert_eq!(outgoing[0].id, artifact.id);
+        assert_eq!(outgoing[0].name, "plan");
+        assert_eq!(outgoing[0].parts, artifact.parts);
+    }
+}
@@ -4,7 +4,9 @@
 //!
 //! Defines message types and structures for inter-agent communication.
 
+mod artifact;
 mod protocol;
 
 // #[allow(unused_imports)]
+pub use artifact::{collect_outgoing, store_incoming, A2AArtifact, ArtifactPart, CLIENT_SOURCE};
 pub use protocol::{A2AMessage, MessageType};
@@ -7,6 +7,9 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::artifact::{self, A2AArtifact};
+use crate::graph::state::SharedContext;
+
 /// Types of A2A messages.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -47,6 +50,9 @@ pub struct A2AMessage {
     pub reply_to: Option<String>,
     /// Optional metadata
     pub metadata: Option<serde_json::Value>,
+    /// Attached artifacts (files, structured data)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<A2AArtifact>,
 }
 
 impl A2AMessage {
@@ -69,6 +75,7 @@ impl A2AMessage {
                 .unwrap_or(0.0),
             reply_to: None,
             metadata: None,
+            artifacts: Vec::new(),
         }
     }
 
@@ -124,6 +131,23 @@ impl A2AMessage {
         self
     }
 
+    /// Attach artifacts.
+    pub fn with_artifacts(mut self, artifacts: Vec<A2AArtifact>) -> Self {
+        self.artifacts.extend(artifacts);
+        self
+    }
+
+    /// Attach every artifact produced by a graph run.
+    pub fn with_shared_artifacts(self, shared: &SharedContext) -> Self {
+        self.with_artifacts(artifact::collect_outgoing(shared))
+    }
+
+    /// Store attached artifacts into a graph's shared context.
+    /// Returns the number of stored entries.
+    pub fn store_artifacts(&self, shared: &mut SharedContext) -> usize {
+        artifact::store_incoming(shared, &self.sender, &self.artifacts)
+    }
+
     /// Serialize to JSON string.
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string(self)
@@ -174,5 +198,24 @@ mod tests {
 
         assert_eq!(parsed.id, msg.id);
         assert_eq!(parsed.message_type, MessageType::Notification);
+        assert!(!json.contains("artifacts"));
+    }
+
+    #[test]
+    fn test_artifact_exchange() {
+        let request = A2AMessage::request("agent1", "agent2", serde_json::json!({"task": "plot"}));
+        let response = A2AMessage::response(&request, "agent2", serde_json::json!({"ok": true}))
+            .with_artifacts(vec![A2AArtifact::file(
+                "chart.png",
+                "image/png",
+                "iVBORw0KGgo=",
+            )]);
+
+        let parsed = A2AMessage::from_json(&response.to_json().unwrap()).unwrap();
+        let mut shared = SharedContext::default();
+        assert_eq!(parsed.store_artifacts(&mut shared), 1);
+        assert_eq!(shared.artifacts[0].artifact_type, "a2a_file");
+        assert_eq!(shared.artifacts[0].metadata["mime_type"], "image/png");
+        assert_eq!(shared.artifacts[0].content, "iVBORw0KGgo=");
     }
 }
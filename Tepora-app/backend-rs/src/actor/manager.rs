@@ -5,6 +5,7 @@ use std::time::{Duration, Instant};
 
 use tokio::sync::{broadcast, mpsc, Mutex};
 
+use crate::infrastructure::observability::latency::{self, LatencyTrace};
 use crate::infrastructure::observability::{RuntimeMetrics, RuntimeMetricsSnapshot};
 use crate::state::AppState;
 
@@ -281,6 +282,16 @@ impl ActorManager {
         }
     }
 
+    /// Log a finished chat request's latency breakdown and add it to the
+    /// runtime metrics.
+    pub fn record_latency(&self, trace: &LatencyTrace, config: &serde_json::Value) {
+        latency::finish(
+            trace,
+            self.runtime_metrics.latency(),
+            latency::slow_threshold_ms(config),
+        );
+    }
+
     pub fn runtime_metrics_snapshot(&self) -> RuntimeMetricsSnapshot {
         self.runtime_metrics.snapshot(10)
     }
@@ -308,6 +319,7 @@ impl ActorManager {
 mod tests {
     use super::*;
 
+    use crate::actor::messages::ClientPresence;
     use tokio::sync::oneshot;
 
     async fn init_state_or_skip() -> Option<Arc<AppState>> {
@@ -569,11 +581,16 @@ mod tests {
             message: "Hello".to_string(),
             mode: "chat".to_string(),
             attachments: vec![],
+            artifacts: vec![],
             search_mode: None,
             thinking_budget: 0,
             agent_id: None,
             agent_mode: None,
             skip_web_search: true,
+            synthesis_mode: None,
+            session_overrides: Default::default(),
+            latency: None,
+            client: ClientPresence::attached(),
         };
 
         manager
@@ -594,7 +611,7 @@ mod tests {
                         SessionEvent::Status { session_id: s, message: _ } if s == session_id => {
                             got_status = true;
                         }
-                        SessionEvent::Token { session_id: s, .. } | SessionEvent::GenerationComplete { session_id: s } if s == session_id => {
+                        SessionEvent::Token { session_id: s, .. } | SessionEvent::GenerationComplete { session_id: s, .. } if s == session_id => {
                             got_token_or_done = true;
                             if got_status && got_token_or_done {
                                 break;
@@ -1,7 +1,34 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
 use serde_json::Value;
 use tokio::sync::oneshot;
 
+use crate::a2a::A2AArtifact;
+use crate::core::config::schema::SessionDefaults;
 use crate::core::security_controls::ToolApprovalResponsePayload;
+use crate::infrastructure::observability::latency::LatencyTrace;
+use crate::llm::outage::ProviderOutage;
+use crate::llm::types::TurnUsage;
+
+/// 送り元の WS 接続がまだ結果を受け取っているか。送信に失敗したら切り離されたとみなし、
+/// 終わった実行を受け取り箱に残す。既定値は切り離し済み。
+#[derive(Debug, Clone, Default)]
+pub struct ClientPresence(Arc<AtomicBool>);
+
+impl ClientPresence {
+    pub fn attached() -> Self {
+        Self(Arc::new(AtomicBool::new(true)))
+    }
+
+    pub fn detach(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
 
 #[derive(Debug)]
 pub enum SessionQuery {
@@ -11,6 +38,8 @@ pub enum SessionQuery {
     },
 }
 
+// メッセージ 1 件につき 1 回しか送らないので、大きい方の変種を箱に入れない
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum SessionCommand {
     ProcessMessage {
@@ -18,11 +47,20 @@ pub enum SessionCommand {
         message: String,
         mode: String,
         attachments: Vec<Value>,
+        /// Artifacts delegated with the message
+        artifacts: Vec<A2AArtifact>,
         search_mode: Option<String>,
         thinking_budget: u8,
         agent_id: Option<String>,
         agent_mode: Option<String>,
         skip_web_search: bool,
+        synthesis_mode: Option<String>,
+        /// Temperature / RAG collections requested by the message
+        session_overrides: SessionDefaults,
+        /// Latency budget of the originating WS message, if traced
+        latency: Option<Arc<LatencyTrace>>,
+        /// Whether the originating WS connection is still listening
+        client: ClientPresence,
     },
     StopGeneration {
         session_id: String,
@@ -56,6 +94,11 @@ pub enum SessionEvent {
         session_id: String,
         message: String,
     },
+    /// 一度繋ぎ直してもモデルのプロバイダーに繋がらなかった
+    ProviderUnavailable {
+        session_id: String,
+        outage: ProviderOutage,
+    },
     NodeCompleted {
         session_id: String,
         #[serde(rename = "nodeId")]
@@ -68,5 +111,6 @@ pub enum SessionEvent {
     },
     GenerationComplete {
         session_id: String,
+        usage: Option<TurnUsage>,
     },
 }
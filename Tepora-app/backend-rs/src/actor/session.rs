@@ -1,22 +1,40 @@
 use serde_json::Value;
 use std::sync::Arc;
 use tokio::sync::{broadcast, mpsc};
+use tokio_util::sync::CancellationToken;
 
-use super::messages::{SessionCommand, SessionEvent};
+use super::messages::{ClientPresence, SessionCommand, SessionEvent};
+use crate::a2a::{collect_outgoing, store_incoming, A2AArtifact, CLIENT_SOURCE};
+use crate::agent::execution::resolve_agent_memory_policy;
+use crate::context::workers::persona_worker::apply_session_persona;
+use crate::context::workers::project_worker::{apply_session_project, apply_session_settings};
+use crate::core::config::schema::SessionDefaults;
+use crate::core::notifications::{BackgroundNotification, NotificationKind};
 use crate::core::security_controls::ToolApprovalResponsePayload;
+use crate::graph::node::GraphError;
+use crate::graph::sanitize::ChunkSanitizer;
+use crate::graph::sentences::SentenceSegmenter;
+use crate::graph::state::SynthesisMode;
 use crate::graph::stream::GraphStreamer;
 use crate::graph::{AgentState, Mode};
+use crate::history::NewInboxItem;
+use crate::infrastructure::observability::latency;
+use crate::llm::cancel as llm_cancel;
 use crate::search::SearchMode;
 use crate::state::AppState;
 
 use std::collections::{HashMap, HashSet};
 
+const INBOX_SUMMARY_MAX_CHARS: usize = 600;
+
 pub struct SessionActor {
     pub session_id: String,
     pub rx: mpsc::Receiver<SessionCommand>,
     pub app_state: Arc<AppState>,
     pub events_tx: broadcast::Sender<SessionEvent>,
     pub current_task: Option<tokio::task::JoinHandle<()>>,
+    /// 実行中のグラフが開いた LLM ストリームの取り消し用
+    pub current_cancel: Option<CancellationToken>,
     pub pending_approvals: Arc<
         tokio::sync::Mutex<
             HashMap<String, tokio::sync::oneshot::Sender<ToolApprovalResponsePayload>>,
@@ -38,6 +56,7 @@ impl SessionActor {
             app_state,
             events_tx,
             current_task: None,
+            current_cancel: None,
             pending_approvals: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
             approved_mcp_tools: Arc::new(tokio::sync::Mutex::new(HashSet::new())),
         }
@@ -52,17 +71,22 @@ impl SessionActor {
                     message,
                     mode,
                     attachments,
+                    artifacts,
                     search_mode,
                     thinking_budget,
                     agent_id,
                     agent_mode,
                     skip_web_search,
+                    synthesis_mode,
+                    session_overrides,
+                    latency,
+                    client,
                     ..
                 } => {
                     // Implement concurrent execution tracking so it can be aborted
-                    if let Some(handle) = self.current_task.take() {
-                        handle.abort();
-                    }
+                    self.stop_current_task();
+                    let cancel = CancellationToken::new();
+                    self.current_cancel = Some(cancel.clone());
 
                     let state_clone = self.app_state.clone();
                     let tx_clone = self.events_tx.clone();
@@ -71,7 +95,7 @@ impl SessionActor {
                     let approved_tools_clone = self.approved_mcp_tools.clone();
 
                     self.current_task = Some(tokio::spawn(async move {
-                        Self::execute_process_message(
+                        let run = Self::execute_process_message(
                             session_clone,
                             state_clone,
                             tx_clone,
@@ -80,20 +104,28 @@ impl SessionActor {
                             message,
                             mode,
                             attachments,
+                            artifacts,
                             search_mode,
                             thinking_budget,
                             agent_id,
                             agent_mode,
                             skip_web_search,
-                        )
-                        .await;
+                            synthesis_mode,
+                            session_overrides,
+                            client,
+                        );
+                        let run = llm_cancel::scoped(cancel, run);
+                        match latency {
+                            Some(trace) => {
+                                trace.mark_started();
+                                latency::scoped(trace, run).await;
+                            }
+                            None => run.await,
+                        }
                     }));
                 }
                 SessionCommand::StopGeneration { .. } => {
-                    // Logic to interrupt the current graph execution if possible
-                    if let Some(handle) = self.current_task.take() {
-                        handle.abort();
-                    }
+                    self.stop_current_task();
                     let _ = self.events_tx.send(SessionEvent::Status {
                         session_id: self.session_id.clone(),
                         message: "Generation stopped".into(),
@@ -133,6 +165,17 @@ impl SessionActor {
         }
     }
 
+    /// 実行中のグラフを止める。先にストリームを取り消して、プロバイダー側の生成
+    /// （llama-server のスロットなど）もすぐに止めさせる。
+    fn stop_current_task(&mut self) {
+        if let Some(cancel) = self.current_cancel.take() {
+            cancel.cancel();
+        }
+        if let Some(handle) = self.current_task.take() {
+            handle.abort();
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn execute_process_message(
         session_id: String,
@@ -147,42 +190,69 @@ impl SessionActor {
         message: String,
         mode_str: String,
         attachments: Vec<Value>,
+        artifacts: Vec<A2AArtifact>,
         search_mode: Option<String>,
         thinking_budget: u8,
         agent_id: Option<String>,
         agent_mode: Option<String>,
         skip_web_search: bool,
+        synthesis_mode: Option<String>,
+        session_overrides: SessionDefaults,
+        client: ClientPresence,
     ) {
         let mode = match mode_str.as_str() {
             "chat" => Mode::Chat,
             "search" => Mode::Search,
             "search_agentic" => Mode::SearchAgentic,
             "agent" => Mode::Agent,
-            _ => Mode::Chat,
+            _ => Mode::from_str(&mode_str),
         };
 
         let mut agent_state = AgentState::new(session_id.clone(), message.clone(), mode);
+        agent_state.requested_mode = mode_str.trim().to_lowercase();
         agent_state.search_attachments = attachments;
+        store_incoming(&mut agent_state.shared_context, CLIENT_SOURCE, &artifacts);
         agent_state.search_mode = SearchMode::from_optional_str(search_mode.as_deref());
         agent_state.thinking_budget = thinking_budget;
         agent_state.agent_id = agent_id.clone();
         agent_state.agent_mode = crate::graph::state::AgentMode::from_str(agent_mode.as_deref());
         agent_state.skip_web_search = skip_web_search;
+        agent_state.synthesis_mode = SynthesisMode::from_optional_str(synthesis_mode.as_deref());
 
         let _ = events_tx.send(SessionEvent::Status {
             session_id: session_id.clone(),
             message: "Processing started".into(),
         });
 
-        let config = app_state
+        let mut config = app_state
             .core()
             .config
             .load_config()
             .unwrap_or_else(|_| serde_json::json!({}));
+        apply_session_project(&app_state, &session_id, &mut config).await;
+        apply_session_settings(&mut config, &session_overrides);
+        let persona_id = apply_session_persona(&app_state, &session_id, &mut config).await;
 
+        let assistant_kwargs = |timestamp: String| {
+            serde_json::json!({
+                "timestamp": timestamp,
+                "mode": mode_str.clone(),
+                "thinking_budget": thinking_budget,
+                "agent_id": agent_id,
+                "agent_mode": agent_mode,
+                "persona_id": persona_id,
+            })
+        };
+        let partial = app_state.runtime().history.partial_message(
+            &session_id,
+            assistant_kwargs(chrono::Utc::now().to_rfc3339()),
+        );
         let mut streamer = GraphStreamer::Actor {
             session_id: session_id.clone(),
             tx: events_tx.clone(),
+            partial: Some(partial.clone()),
+            sentences: SentenceSegmenter::for_config(&config),
+            sanitizer: ChunkSanitizer::default(),
         };
 
         let mut node_ctx = crate::graph::NodeContext {
@@ -193,35 +263,56 @@ impl SessionActor {
             approved_mcp_tools,
         };
 
-        if let Err(e) = app_state
+        let run_result = app_state
             .runtime()
             .graph_runtime
             .run(&mut agent_state, &mut node_ctx, None)
-            .await
-        {
-            let _ = events_tx.send(SessionEvent::Error {
-                session_id: session_id.clone(),
-                message: e.to_string(),
-            });
+            .await;
+        if let Some(trace) = latency::current() {
+            app_state
+                .runtime()
+                .actor_manager
+                .record_latency(&trace, &config);
+        }
+        if let Err(e) = &run_result {
+            let event = match &e.outage {
+                Some(outage) => SessionEvent::ProviderUnavailable {
+                    session_id: session_id.clone(),
+                    outage: outage.clone(),
+                },
+                None => SessionEvent::Error {
+                    session_id: session_id.clone(),
+                    message: e.to_string(),
+                },
+            };
+            let _ = events_tx.send(event);
+        }
+        if matches!(mode, Mode::Agent | Mode::SearchAgentic | Mode::Research) {
+            report_agent_run(
+                &app_state,
+                &session_id,
+                &agent_state,
+                run_result.as_ref().err(),
+                &client,
+            )
+            .await;
         }
 
         let assistant_output = agent_state.output.clone().unwrap_or_default();
-        let timestamp = chrono::Utc::now().to_rfc3339();
-
-        let assistant_kwargs = serde_json::json!({
-            "timestamp": timestamp,
-            "mode": mode_str.clone(),
-            "thinking_budget": thinking_budget,
-            "agent_id": agent_id,
-            "agent_mode": agent_mode,
-        });
 
-        if let Err(e) = app_state
-            .runtime()
-            .history
-            .add_message(&session_id, "ai", &assistant_output, Some(assistant_kwargs))
-            .await
-        {
+        // 失敗時に途中まで保存できていれば、それを未完了のまま残す
+        let saved = if run_result.is_err() && partial.message_id().await.is_some() {
+            partial.flush().await.map(|_| ())
+        } else {
+            partial
+                .finish(
+                    &assistant_output,
+                    assistant_kwargs(chrono::Utc::now().to_rfc3339()),
+                )
+                .await
+                .map(|_| ())
+        };
+        if let Err(e) = saved {
             tracing::error!("Failed to save actor message to history: {}", e);
         }
 
@@ -248,6 +339,19 @@ impl SessionActor {
             .unwrap_or_else(|| "default".to_string());
 
         let legacy_enabled = app_state.is_redesign_enabled("legacy_memory");
+        let memory_policy = resolve_agent_memory_policy(&app_state, agent_id.as_deref());
+        let namespace = memory_policy.namespace_character_id();
+
+        app_state
+            .memory()
+            .session_index
+            .record_exchange_in_background(
+                &app_state.ai().llm,
+                &embedding_model_id,
+                &session_id,
+                &message,
+                &assistant_output,
+            );
 
         let message_text_for_ingest = message.clone();
 
@@ -257,19 +361,22 @@ impl SessionActor {
         });
 
         // Use tokio::spawn to not block the actor, or just await it. Awaiting is fine here since it's already in a spawned task.
-        let _ = app_state
-            .memory()
-            .memory_adapter
-            .ingest_interaction(
-                &session_id,
-                &message_text_for_ingest,
-                &assistant_output,
-                &app_state.ai().llm,
-                &text_model_id,
-                &embedding_model_id,
-                legacy_enabled,
-            )
-            .await;
+        if memory_policy.ingests() {
+            let _ = app_state
+                .memory()
+                .memory_adapter
+                .ingest_interaction(
+                    &session_id,
+                    &message_text_for_ingest,
+                    &assistant_output,
+                    &app_state.ai().llm,
+                    &text_model_id,
+                    &embedding_model_id,
+                    legacy_enabled,
+                    namespace.as_deref(),
+                )
+                .await;
+        }
 
         let _ = events_tx.send(SessionEvent::MemoryGeneration {
             session_id: session_id.clone(),
@@ -278,6 +385,62 @@ impl SessionActor {
 
         let _ = events_tx.send(SessionEvent::GenerationComplete {
             session_id: session_id.clone(),
+            usage: None,
         });
     }
 }
+
+/// エージェント実行は長くかかるので、終わったら完了通知を出す。送り元の接続が
+/// 途中で切れていたら、要約と成果物を受け取り箱に置く（見ていた実行は置かない）。
+async fn report_agent_run(
+    app_state: &AppState,
+    session_id: &str,
+    agent_state: &AgentState,
+    error: Option<&GraphError>,
+    client: &ClientPresence,
+) {
+    let (title, body) = match error {
+        None => (
+            "Agent run finished",
+            "The agent has finished its task.".to_string(),
+        ),
+        Some(err) => ("Agent run failed", err.to_string()),
+    };
+    let notification =
+        BackgroundNotification::new(NotificationKind::AgentRun, error.is_none(), title, &body);
+    app_state
+        .core()
+        .notifications
+        .publish(notification.with_session(session_id));
+    if client.is_attached() {
+        return;
+    }
+
+    let summary = match (error, agent_state.output.as_deref().map(str::trim)) {
+        (None, Some(output)) if !output.is_empty() => truncate_summary(output),
+        _ => body,
+    };
+    let item = NewInboxItem {
+        kind: "agent_run".to_string(),
+        title: title.to_string(),
+        summary,
+        success: error.is_none(),
+        session_id: Some(session_id.to_string()),
+        artifacts: collect_outgoing(&agent_state.shared_context),
+    };
+    if let Err(err) = app_state.runtime().inbox.deposit(item).await {
+        tracing::warn!("Failed to add agent run to inbox: {}", err);
+    }
+}
+
+fn truncate_summary(text: &str) -> String {
+    if text.chars().count() <= INBOX_SUMMARY_MAX_CHARS {
+        return text.to_string();
+    }
+    let mut truncated = text
+        .chars()
+        .take(INBOX_SUMMARY_MAX_CHARS)
+        .collect::<String>();
+    truncated.push('…');
+    truncated
+}
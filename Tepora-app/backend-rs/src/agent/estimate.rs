@@ -0,0 +1,276 @@
+//! 実行計画の事前見積もり。
+//!
+//! プランナーが出した計画のステップごとに、トークン数・所要時間・料金を見積もる。
+//! 1 ステップはエグゼキューターの 1 ラウンド（LLM 呼び出し 1 回とそのツール）とみなし、
+//! プロファイラーに残る直近の実績を平均して使う。実績が無ければ既定値を使う。
+//! 料金は `model_pricing` かモデルカタログ（OpenRouter）に載っているクラウドモデルだけ出し、
+//! ローカルモデルは 0 とする。
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::core::config::schema::{ModelPricing, TeporaConfig};
+use crate::graph::profiler::RoundStats;
+
+/// 実績が無いときの 1 ラウンドの見積もり
+const DEFAULT_ROUND: RoundStats = RoundStats {
+    samples: 0,
+    prompt_tokens: 2_000.0,
+    completion_tokens: 400.0,
+    wall_ms: 15_000.0,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CostEstimate {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub wall_ms: u64,
+    /// 料金が分からないクラウドモデルでは `None`
+    pub cost_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepEstimate {
+    pub index: usize,
+    pub text: String,
+    #[serde(flatten)]
+    pub cost: CostEstimate,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanEstimate {
+    pub model_id: String,
+    pub provider: String,
+    /// このマシン（LAN）の外へ送るモデルか
+    pub cloud: bool,
+    /// `history`（実績の平均）か `default`（既定値）
+    pub basis: &'static str,
+    /// 平均に使った実行の数
+    pub samples: u64,
+    pub steps: Vec<StepEstimate>,
+    pub total: CostEstimate,
+}
+
+/// 計画の Markdown から最上位の箇条書き（`-` / `*` / `+` / `1.` / `1)`）を取り出す。
+/// 箇条書きが無ければ全体を 1 ステップとみなす。
+pub fn parse_plan_steps(plan: &str) -> Vec<String> {
+    let steps = plan
+        .lines()
+        .filter(|line| !line.starts_with("  ") && !line.starts_with('\t'))
+        .filter_map(|line| strip_list_marker(line.trim()))
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    if steps.is_empty() && !plan.trim().is_empty() {
+        return vec![plan.trim().to_string()];
+    }
+    steps
+}
+
+fn strip_list_marker(line: &str) -> Option<&str> {
+    if let Some(rest) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+    {
+        return Some(rest.trim());
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits == 0 {
+        return None;
+    }
+    line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))
+        .map(str::trim)
+}
+
+/// `model_pricing` のモデル ID の料金、レジストリに同期したカタログの料金、
+/// `model_pricing` のローダー名の料金の順に使う。
+pub fn pricing_for(
+    config: &TeporaConfig,
+    model_id: &str,
+    provider: &str,
+    catalog: Option<ModelPricing>,
+) -> Option<ModelPricing> {
+    config
+        .model_pricing
+        .get(model_id)
+        .copied()
+        .or(catalog)
+        .or_else(|| config.model_pricing.get(provider).copied())
+}
+
+pub fn estimate_plan(
+    plan: &str,
+    model_id: &str,
+    provider: &str,
+    cloud: bool,
+    history: Option<RoundStats>,
+    pricing: Option<ModelPricing>,
+) -> PlanEstimate {
+    let round = history.unwrap_or(DEFAULT_ROUND);
+    let per_step = CostEstimate {
+        prompt_tokens: round.prompt_tokens.round() as u64,
+        completion_tokens: round.completion_tokens.round() as u64,
+        wall_ms: round.wall_ms.round() as u64,
+        cost_usd: None,
+    };
+    let cost_of = |estimate: &CostEstimate| match (cloud, pricing) {
+        (false, _) => Some(0.0),
+        (true, Some(pricing)) => Some(
+            (estimate.prompt_tokens as f64 * pricing.input_per_million
+                + estimate.completion_tokens as f64 * pricing.output_per_million)
+                / 1_000_000.0,
+        ),
+        (true, None) => None,
+    };
+
+    let steps = parse_plan_steps(plan)
+        .into_iter()
+        .enumerate()
+        .map(|(index, text)| {
+            let mut cost = per_step.clone();
+            cost.cost_usd = cost_of(&cost);
+            StepEstimate { index, text, cost }
+        })
+        .collect::<Vec<_>>();
+    let mut total = CostEstimate::default();
+    for step in &steps {
+        total.prompt_tokens += step.cost.prompt_tokens;
+        total.completion_tokens += step.cost.completion_tokens;
+        total.wall_ms += step.cost.wall_ms;
+    }
+    total.cost_usd = cost_of(&total);
+
+    PlanEstimate {
+        model_id: model_id.to_string(),
+        provider: provider.to_string(),
+        cloud,
+        basis: if history.is_some() {
+            "history"
+        } else {
+            "default"
+        },
+        samples: round.samples,
+        steps,
+        total,
+    }
+}
+
+/// `agent.plan_approval` が有効なら承認を待つ。`agent.plan_approval_min_cost_usd` が
+/// あれば、見積もりがその額以上（料金不明のクラウドモデルを含む）のときだけ待つ。
+pub fn needs_approval(config: &Value, estimate: &PlanEstimate) -> bool {
+    let agent = config.get("agent");
+    let enabled = agent
+        .and_then(|agent| agent.get("plan_approval"))
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    if !enabled {
+        return false;
+    }
+    match agent
+        .and_then(|agent| agent.get("plan_approval_min_cost_usd"))
+        .and_then(Value::as_f64)
+    {
+        None => true,
+        Some(min_cost) => estimate
+            .total
+            .cost_usd
+            .is_none_or(|cost| estimate.cloud && cost >= min_cost),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const PLAN: &str =
+        "1. Search the docs\n   - fallback: ask the user\n2) Read the config\n- Write the summary";
+
+    #[test]
+    fn steps_are_top_level_list_items() {
+        assert_eq!(
+            parse_plan_steps(PLAN),
+            vec!["Search the docs", "Read the config", "Write the summary"]
+        );
+        assert_eq!(
+            parse_plan_steps("Just answer directly."),
+            vec!["Just answer directly."]
+        );
+    }
+
+    #[test]
+    fn cloud_steps_are_priced_from_history() {
+        let history = RoundStats {
+            samples: 4,
+            prompt_tokens: 1_000.0,
+            completion_tokens: 200.0,
+            wall_ms: 3_000.0,
+        };
+        let pricing = ModelPricing {
+            input_per_million: 3.0,
+            output_per_million: 15.0,
+        };
+        let estimate = estimate_plan(PLAN, "gpt", "openai", true, Some(history), Some(pricing));
+        assert_eq!(estimate.basis, "history");
+        assert_eq!(estimate.steps.len(), 3);
+        assert_eq!(estimate.steps[1].cost.cost_usd, Some(0.006));
+        assert_eq!(estimate.total.wall_ms, 9_000);
+        assert_eq!(estimate.total.prompt_tokens, 3_000);
+        assert!((estimate.total.cost_usd.unwrap() - 0.018).abs() < 1e-9);
+
+        let local = estimate_plan(PLAN, "gemma", "llama_cpp", false, None, None);
+        assert_eq!(local.basis, "default");
+        assert_eq!(local.total.cost_usd, Some(0.0));
+        assert_eq!(
+            estimate_plan(PLAN, "gpt", "openai", true, None, None)
+                .total
+                .cost_usd,
+            None
+        );
+    }
+
+    #[test]
+    fn configured_model_pricing_beats_catalog_and_catalog_beats_loader() {
+        let price = |input: f64| ModelPricing {
+            input_per_million: input,
+            output_per_million: 0.0,
+        };
+        let mut config = TeporaConfig::default();
+        config
+            .model_pricing
+            .insert("openrouter".to_string(), price(1.0));
+        let catalog = Some(price(2.0));
+
+        assert_eq!(pricing_for(&config, "or-x", "openrouter", catalog), catalog);
+        assert_eq!(
+            pricing_for(&config, "or-x", "openrouter", None),
+            Some(price(1.0))
+        );
+        config.model_pricing.insert("or-x".to_string(), price(3.0));
+        assert_eq!(
+            pricing_for(&config, "or-x", "openrouter", catalog),
+            Some(price(3.0))
+        );
+    }
+
+    #[test]
+    fn approval_follows_agent_settings_and_cost_threshold() {
+        let pricing = ModelPricing {
+            input_per_million: 3.0,
+            output_per_million: 15.0,
+        };
+        let cloud = estimate_plan(PLAN, "gpt", "openai", true, None, Some(pricing));
+        let local = estimate_plan(PLAN, "gemma", "llama_cpp", false, None, None);
+
+        assert!(!needs_approval(&json!({}), &cloud));
+        let always = json!({"agent": {"plan_approval": true}});
+        assert!(needs_approval(&always, &local));
+        let threshold =
+            json!({"agent": {"plan_approval": true, "plan_approval_min_cost_usd": 0.01}});
+        assert!(needs_approval(&threshold, &cloud));
+        assert!(!needs_approval(&threshold, &local));
+    }
+}
@@ -0,0 +1,299 @@
+//! Agent self-evaluation.
+//!
+//! `agent_skills.evaluation.enabled` を有効にすると、エージェントの実行後に
+//! professional モデルでタスク達成度を採点し、実行トレース（agent_events）に
+//! 保存する。プロンプト改訂ごとの比較のため SKILL.md のハッシュも記録する。
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::agent::execution::SelectedAgentRuntime;
+use crate::core::errors::ApiError;
+use crate::llm::types::StructuredResponseSpec;
+use crate::llm::{ChatMessage, ChatRequest};
+use crate::models::event::{AgentEvent, AgentEventType};
+use crate::state::AppState;
+
+const EVALUATION_NODE_NAME: &str = "agent_evaluation";
+const MAX_EVALUATED_CHARS: usize = 6000;
+
+const EVALUATION_RUBRIC: &str = "You grade whether an AI agent completed the user's task.\n\
+Score from 0 to 10:\n\
+- 10: fully completed, correct and directly usable\n\
+- 7: completed with minor gaps\n\
+- 4: partially completed or needs substantial follow-up\n\
+- 0: not attempted, wrong, or refused\n\
+Judge only the final answer against the task. Keep the rationale to one or two sentences.";
+
+pub fn evaluation_enabled(config: &Value) -> bool {
+    config
+        .get("agent_skills")
+        .and_then(|skills| skills.get("evaluation"))
+        .and_then(|evaluation| evaluation.get("enabled"))
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// Short hash of the agent prompt so scores can be grouped per revision.
+pub fn prompt_revision(skill_body: &str) -> String {
+    let digest = Sha256::digest(skill_body.trim().as_bytes());
+    hex::encode(&digest[..6])
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EvaluationVerdict {
+    score: f64,
+    completed: bool,
+    #[serde(default)]
+    rationale: String,
+}
+
+fn evaluation_spec() -> StructuredResponseSpec {
+    StructuredResponseSpec {
+        name: "agent_evaluation".to_string(),
+        description: Some("Task completion rubric score".to_string()),
+        schema: json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "score": { "type": "number", "minimum": 0, "maximum": 10 },
+                "completed": { "type": "boolean" },
+                "rationale": { "type": "string" }
+            },
+            "required": ["score", "completed", "rationale"]
+        }),
+    }
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// Grade a finished run and store the verdict as an `evaluation` event.
+pub async fn evaluate_agent_run(
+    state: &AppState,
+    session_id: &str,
+    agent: &SelectedAgentRuntime,
+    task: &str,
+    output: &str,
+    steps: usize,
+) -> Result<(), ApiError> {
+    let model_id = state
+        .ai()
+        .models
+        .resolve_assignment_model_id("professional")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "default".to_string());
+
+    let request = ChatRequest::new(vec![
+        ChatMessage::new_text("system", EVALUATION_RUBRIC),
+        ChatMessage::new_text(
+            "user",
+            format!(
+                "Task:\n{}\n\nAgent answer:\n{}",
+                truncate_chars(task, MAX_EVALUATED_CHARS),
+                truncate_chars(output, MAX_EVALUATED_CHARS)
+            ),
+        ),
+    ])
+    .with_structured_response(evaluation_spec());
+    let verdict: EvaluationVerdict = state.ai().llm.chat_structured(request, &model_id).await?;
+
+    state
+        .runtime()
+        .history
+        .save_agent_event(&AgentEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: session_id.to_string(),
+            node_name: EVALUATION_NODE_NAME.to_string(),
+            event_type: AgentEventType::Evaluation,
+            metadata: json!({
+                "agent_id": agent.id,
+                "revision": prompt_revision(&agent.skill_body),
+                "score": verdict.score.clamp(0.0, 10.0),
+                "completed": verdict.completed,
+                "rationale": verdict.rationale,
+                "steps": steps,
+                "model_id": model_id,
+            }),
+            created_at: Utc::now(),
+        })
+        .await
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RevisionStats {
+    pub revision: String,
+    pub runs: usize,
+    pub mean_score: f64,
+    pub completion_rate: f64,
+    pub mean_steps: f64,
+    pub first_seen: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AgentEvaluationStats {
+    pub agent_id: String,
+    pub current_revision: Option<String>,
+    pub runs: usize,
+    pub mean_score: Option<f64>,
+    pub completion_rate: Option<f64>,
+    pub last_evaluated_at: Option<DateTime<Utc>>,
+    /// Oldest revision first.
+    pub revisions: Vec<RevisionStats>,
+}
+
+#[derive(Default)]
+struct Totals {
+    runs: usize,
+    score: f64,
+    completed: usize,
+    steps: f64,
+}
+
+impl Totals {
+    fn add(&mut self, metadata: &Value) {
+        self.runs += 1;
+        self.score += metadata.get("score").and_then(Value::as_f64).unwrap_or(0.0);
+        if metadata.get("completed").and_then(Value::as_bool) == Some(true) {
+            self.completed += 1;
+        }
+        self.steps += metadata.get("steps").and_then(Value::as_f64).unwrap_or(0.0);
+    }
+
+    fn mean(&self, total: f64) -> f64 {
+        if self.runs == 0 {
+            0.0
+        } else {
+            total / self.runs as f64
+        }
+    }
+}
+
+pub fn aggregate_evaluations(
+    agent_id: &str,
+    current_revision: Option<String>,
+    events: &[AgentEvent],
+) -> AgentEvaluationStats {
+    let mut overall = Totals::default();
+    let mut per_revision: BTreeMap<String, (Totals, RevisionStats)> = BTreeMap::new();
+
+    for event in events {
+        let revision = event
+            .metadata
+            .get("revision")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown")
+            .to_string();
+        overall.add(&event.metadata);
+        let (totals, stats) = per_revision.entry(revision.clone()).or_insert_with(|| {
+            (
+                Totals::default(),
+                RevisionStats {
+                    revision,
+                    ..RevisionStats::default()
+                },
+            )
+        });
+        totals.add(&event.metadata);
+        stats.first_seen = Some(
+            stats
+                .first_seen
+                .map_or(event.created_at, |seen| seen.min(event.created_at)),
+        );
+        stats.last_seen = Some(
+            stats
+                .last_seen
+                .map_or(event.created_at, |seen| seen.max(event.created_at)),
+        );
+    }
+
+    let mut revisions = per_revision
+        .into_values()
+        .map(|(totals, mut stats)| {
+            stats.runs = totals.runs;
+            stats.mean_score = totals.mean(totals.score);
+            stats.completion_rate = totals.mean(totals.completed as f64);
+            stats.mean_steps = totals.mean(totals.steps);
+            stats
+        })
+        .collect::<Vec<_>>();
+    revisions.sort_by_key(|stats| stats.first_seen);
+
+    AgentEvaluationStats {
+        agent_id: agent_id.to_string(),
+        current_revision,
+        runs: overall.runs,
+        mean_score: (overall.runs > 0).then(|| overall.mean(overall.score)),
+        completion_rate: (overall.runs > 0).then(|| overall.mean(overall.completed as f64)),
+        last_evaluated_at: events.iter().map(|event| event.created_at).max(),
+        revisions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn event(revision: &str, score: f64, completed: bool, minutes: i64) -> AgentEvent {
+        AgentEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: "s1".to_string(),
+            node_name: EVALUATION_NODE_NAME.to_string(),
+            event_type: AgentEventType::Evaluation,
+            metadata: json!({
+                "agent_id": "coder",
+                "revision": revision,
+                "score": score,
+                "completed": completed,
+                "steps": 2,
+            }),
+            created_at: DateTime::<Utc>::UNIX_EPOCH + Duration::minutes(minutes),
+        }
+    }
+
+    #[test]
+    fn aggregates_scores_per_revision_in_first_seen_order() {
+        let events = vec![
+            event("new", 9.0, true, 10),
+            event("old", 4.0, false, 1),
+            event("old", 6.0, true, 2),
+        ];
+
+        let stats = aggregate_evaluations("coder", Some("new".to_string()), &events);
+
+        assert_eq!(stats.runs, 3);
+        assert_eq!(stats.mean_score, Some(19.0 / 3.0));
+        assert_eq!(stats.revisions.len(), 2);
+        assert_eq!(stats.revisions[0].revision, "old");
+        assert_eq!(stats.revisions[0].mean_score, 5.0);
+        assert_eq!(stats.revisions[0].completion_rate, 0.5);
+        assert_eq!(stats.revisions[1].revision, "new");
+        assert_eq!(stats.last_evaluated_at, Some(events[0].created_at));
+    }
+
+    #[test]
+    fn empty_history_has_no_scores() {
+        let stats = aggregate_evaluations("coder", None, &[]);
+        assert_eq!(stats.runs, 0);
+        assert!(stats.mean_score.is_none());
+        assert!(stats.revisions.is_empty());
+    }
+
+    #[test]
+    fn revision_changes_with_prompt_only() {
+        assert_eq!(prompt_revision("Do X.\n"), prompt_revision("Do X."));
+        assert_ne!(prompt_revision("Do X."), prompt_revision("Do Y."));
+        assert_eq!(prompt_revision("Do X.").len(), 12);
+    }
+}
@@ -0,0 +1,174 @@
+//! ExclusiveAgentManager — lifecycle view over agents bound to their own model.
+//!
+//! llama-server serves a single model, so an agent with an `agent:<id>`
+//! assignment occupies the exclusive slot while it runs. Switching agents
+//! means a model swap; the manager reports which agent holds the slot and
+//! lets the UI preload or unload it ahead of time.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::core::errors::ApiError;
+use crate::llm::model_resolution::normalize_loader_name;
+use crate::state::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentLoadState {
+    /// No dedicated model; the agent shares the character model.
+    Unbound,
+    /// Served by an external loader (Ollama / LM Studio).
+    External,
+    Loaded,
+    Unloaded,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExclusiveAgentStatus {
+    pub id: String,
+    pub name: String,
+    pub model_id: Option<String>,
+    pub model_name: Option<String>,
+    pub loader: Option<String>,
+    pub load_state: AgentLoadState,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Default)]
+pub struct ExclusiveAgentManager {
+    last_used: Arc<Mutex<HashMap<String, DateTime<Utc>>>>,
+}
+
+impl ExclusiveAgentManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_use(&self, agent_id: &str) {
+        if let Ok(mut last_used) = self.last_used.lock() {
+            last_used.insert(agent_id.to_string(), Utc::now());
+        }
+    }
+
+    pub fn last_used(&self, agent_id: &str) -> Option<DateTime<Utc>> {
+        self.last_used
+            .lock()
+            .ok()
+            .and_then(|last_used| last_used.get(agent_id).copied())
+    }
+
+    pub async fn list(&self, state: &AppState) -> Vec<ExclusiveAgentStatus> {
+        let loaded_model_id = state.ai().llama.loaded_model_id().await;
+        state
+            .ai()
+            .skill_registry
+            .list_all()
+            .into_iter()
+            .filter(|skill| skill.valid)
+            .map(|skill| {
+                self.build_status(state, &skill.id, &skill.name, loaded_model_id.as_deref())
+            })
+            .collect()
+    }
+
+    pub async fn status(
+        &self,
+        state: &AppState,
+        agent_id: &str,
+    ) -> Result<ExclusiveAgentStatus, ApiError> {
+        let skill = state
+            .ai()
+            .skill_registry
+            .get(agent_id)
+            .ok_or_else(|| ApiError::NotFound("Agent Skill not found".to_string()))?;
+        let loaded_model_id = state.ai().llama.loaded_model_id().await;
+        Ok(self.build_status(
+            state,
+            &skill.summary.id,
+            &skill.summary.name,
+            loaded_model_id.as_deref(),
+        ))
+    }
+
+    /// Load the agent's model into the exclusive slot, evicting whatever
+    /// model is resident.
+    pub async fn preload(
+        &self,
+        state: &AppState,
+        agent_id: &str,
+    ) -> Result<ExclusiveAgentStatus, ApiError> {
+        let status = self.status(state, agent_id).await?;
+        let Some(model_id) = status.model_id.as_deref() else {
+            return Err(ApiError::BadRequest(format!(
+                "Agent '{}' has no model assignment",
+                agent_id
+            )));
+        };
+        state.ai().llm.preload(model_id).await?;
+        self.status(state, agent_id).await
+    }
+
+    /// Stop llama-server if it currently holds this agent's model.
+    pub async fn unload(
+        &self,
+        state: &AppState,
+        agent_id: &str,
+    ) -> Result<ExclusiveAgentStatus, ApiError> {
+        let status = self.status(state, agent_id).await?;
+        if status.load_state == AgentLoadState::Loaded {
+            state.ai().llm.shutdown().await?;
+        }
+        self.status(state, agent_id).await
+    }
+
+    fn build_status(
+        &self,
+        state: &AppState,
+        agent_id: &str,
+        name: &str,
+        loaded_model_id: Option<&str>,
+    ) -> ExclusiveAgentStatus {
+        let model = state
+            .ai()
+            .models
+            .resolve_assignment_model(&format!("agent:{}", agent_id))
+            .ok()
+            .flatten();
+        let loader = model.as_ref().map(normalize_loader_name);
+        let load_state = match &model {
+            None => AgentLoadState::Unbound,
+            Some(_) if loader.as_deref() != Some("llama_cpp") => AgentLoadState::External,
+            Some(entry) if loaded_model_id == Some(entry.id.as_str()) => AgentLoadState::Loaded,
+            Some(_) => AgentLoadState::Unloaded,
+        };
+        ExclusiveAgentStatus {
+            id: agent_id.to_string(),
+            name: name.to_string(),
+            model_id: model.as_ref().map(|entry| entry.id.clone()),
+            model_name: model.as_ref().map(|entry| entry.display_name.clone()),
+            loader,
+            load_state,
+            last_used_at: self.last_used(agent_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_use_tracks_latest_timestamp() {
+        let manager = ExclusiveAgentManager::new();
+        assert!(manager.last_used("coder").is_none());
+
+        manager.record_use("coder");
+        let first = manager.last_used("coder").expect("recorded");
+        manager.record_use("coder");
+        assert!(manager.last_used("coder").expect("recorded") >= first);
+        assert!(manager.last_used("writer").is_none());
+    }
+}
@@ -1,13 +1,16 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
-use crate::agent::policy::CustomToolPolicy;
+use crate::agent::policy::{AgentMemoryPolicy, CapabilityGrants, CustomToolPolicy};
 use crate::agent::skill_registry::AgentSkillPackage;
-use crate::core::native_tools::{resolve_tool_alias, NATIVE_TOOLS};
-use crate::llm::types::StructuredResponseSpec;
+use crate::core::config::schema::TeporaConfig;
+use crate::core::native_tools::resolve_tool_alias;
+use crate::llm::types::{NormalizedAssistantTurn, StructuredResponseSpec, ToolSpec};
 use crate::state::AppState;
+use crate::tools::http_api::http_tool_definitions;
+use crate::tools::registry::NATIVE_TOOLS;
 
 #[derive(Debug, Clone)]
 pub struct SelectedAgentRuntime {
@@ -18,6 +21,8 @@ pub struct SelectedAgentRuntime {
     pub resource_prompt: Option<String>,
     pub assigned_model_id: Option<String>,
     pub tool_policy: CustomToolPolicy,
+    /// From `agent_skills.graph_overrides.<id>.max_steps`.
+    pub max_steps: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
@@ -26,6 +31,16 @@ pub enum AgentDecision {
     ToolCall { name: String, args: Value },
 }
 
+impl AgentDecision {
+    /// イベント記録用の種別（構造化出力の `type` と同じ値）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AgentDecision::Final(_) => "final",
+            AgentDecision::ToolCall { .. } => "tool_call",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AgentDecisionPayload {
     #[serde(rename = "type")]
@@ -48,13 +63,14 @@ struct SkillToolPolicy {
     denied_tools: Vec<String>,
     #[serde(default)]
     require_confirmation: Vec<String>,
+    #[serde(default)]
+    capabilities: Option<CapabilityGrants>,
 }
 
 pub fn approval_timeout(config: &Value) -> u64 {
-    config
-        .get("app")
-        .and_then(|v| v.get("tool_approval_timeout"))
-        .and_then(|v| v.as_u64())
+    TeporaConfig::from_value_or_default(config)
+        .app
+        .tool_approval_timeout
         .unwrap_or(300)
 }
 
@@ -70,6 +86,21 @@ pub async fn build_allowed_tool_list(
         mcp_tool_set.insert(tool.name.clone());
         tool_list.push(tool.name);
     }
+    tool_list.extend(
+        state
+            .integration
+            .plugins
+            .tools()
+            .into_iter()
+            .map(|tool| tool.name),
+    );
+    if let Ok(config) = state.core().config.load_config() {
+        tool_list.extend(
+            http_tool_definitions(&config)
+                .into_iter()
+                .map(|tool| tool.name),
+        );
+    }
 
     tool_list.retain(|tool_name| active_policy.is_tool_allowed(tool_name));
     tool_list.sort();
@@ -78,6 +109,42 @@ pub async fn build_allowed_tool_list(
     (tool_list, mcp_tool_set)
 }
 
+/// 許可済みツールの定義を `tool_names` の順に集める（ネイティブのツール呼び出し用）。
+/// 同じ名前があればネイティブ、MCP、プラグイン、HTTP の順に先のものを使う。
+pub async fn build_allowed_tool_specs(state: &AppState, tool_names: &[String]) -> Vec<ToolSpec> {
+    let mut known: HashMap<String, ToolSpec> = HashMap::new();
+    let mut add = |name: String, description: String, parameters: Option<Value>| {
+        known.entry(name.clone()).or_insert_with(|| ToolSpec {
+            name,
+            description,
+            parameters: parameters.unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+        });
+    };
+    for tool in NATIVE_TOOLS {
+        add(
+            tool.name.to_string(),
+            tool.description.to_string(),
+            Some(tool.input_schema()),
+        );
+    }
+    for tool in state.integration.mcp.list_tools().await {
+        add(tool.name, tool.description, tool.input_schema);
+    }
+    for tool in state.integration.plugins.tools() {
+        add(tool.name, tool.description, tool.input_schema);
+    }
+    if let Ok(config) = state.core().config.load_config() {
+        for tool in http_tool_definitions(&config) {
+            add(tool.name, tool.description, Some(tool.params_schema));
+        }
+    }
+
+    tool_names
+        .iter()
+        .filter_map(|name| known.remove(name))
+        .collect()
+}
+
 pub fn choose_agent_from_manager(
     state: &AppState,
     requested_agent_id: Option<&str>,
@@ -120,16 +187,50 @@ fn map_selected_agent(state: &AppState, skill: AgentSkillPackage) -> SelectedAge
         skill_body: skill.skill_body.clone(),
         resource_prompt: crate::agent::skill_registry::build_skill_resource_prompt(&skill),
         assigned_model_id,
-        tool_policy: extract_tool_policy(&skill),
+        tool_policy: extract_tool_policy(
+            &skill,
+            agent_skill_setting(state, "tool_policies", &skill.summary.id),
+        ),
+        max_steps: agent_skill_setting(state, "graph_overrides", &skill.summary.id)
+            .and_then(|overrides| overrides.get("max_steps").and_then(Value::as_u64)),
     }
 }
 
-fn extract_tool_policy(skill: &AgentSkillPackage) -> CustomToolPolicy {
-    let candidate = skill
-        .summary
-        .metadata
-        .get("tool_policy")
+/// Per-agent settings stored in config under `agent_skills.<section>.<id>`.
+/// `tool_policies` entries override the package's own policy.
+pub fn agent_skill_setting(state: &AppState, section: &str, skill_id: &str) -> Option<Value> {
+    state
+        .core()
+        .config
+        .load_config()
+        .ok()?
+        .get("agent_skills")?
+        .get(section)?
+        .get(skill_id)
         .cloned()
+}
+
+/// Memory policy for `agent_id`: `agent_skills.memory_policies.<id>`, then the
+/// package's `memory_policy` metadata. No agent means the shared default.
+pub fn resolve_agent_memory_policy(state: &AppState, agent_id: Option<&str>) -> AgentMemoryPolicy {
+    let Some(agent_id) = agent_id.map(str::trim).filter(|id| !id.is_empty()) else {
+        return AgentMemoryPolicy::default();
+    };
+    agent_skill_setting(state, "memory_policies", agent_id)
+        .or_else(|| {
+            let skill = state.ai().skill_registry.get(agent_id)?;
+            skill.summary.metadata.get("memory_policy").cloned()
+        })
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+fn extract_tool_policy(
+    skill: &AgentSkillPackage,
+    config_override: Option<Value>,
+) -> CustomToolPolicy {
+    let candidate = config_override
+        .or_else(|| skill.summary.metadata.get("tool_policy").cloned())
         .or_else(|| {
             skill
                 .summary
@@ -163,15 +264,27 @@ fn extract_tool_policy(skill: &AgentSkillPackage) -> CustomToolPolicy {
             .iter()
             .map(|tool| resolve_tool_alias(tool))
             .collect(),
+        grants: policy.capabilities.unwrap_or_default(),
     }
 }
 
 pub fn build_agent_chat_config(
     _state: &AppState,
     config: &Value,
-    _selected_agent: Option<&SelectedAgentRuntime>,
+    selected_agent: Option<&SelectedAgentRuntime>,
 ) -> Value {
-    config.clone()
+    let mut config = config.clone();
+    if let Some(max_steps) = selected_agent.and_then(|agent| agent.max_steps) {
+        if let Some(root) = config.as_object_mut() {
+            let app = root
+                .entry("app")
+                .or_insert_with(|| Value::Object(Default::default()));
+            if let Some(app) = app.as_object_mut() {
+                app.insert("graph_recursion_limit".to_string(), json!(max_steps));
+            }
+        }
+    }
+    config
 }
 
 pub fn resolve_execution_model_id(
@@ -272,6 +385,18 @@ pub fn structured_payload_to_agent_decision(
     }
 }
 
+/// ネイティブのツール呼び出しの応答を判断に直す。呼び出しが複数あっても 1 手ずつ進めるので
+/// 最初の 1 つだけ使い、呼び出しが無ければ本文を最終回答とする。
+pub fn tool_turn_to_agent_decision(turn: NormalizedAssistantTurn) -> AgentDecision {
+    match turn.tool_calls.into_iter().next() {
+        Some(call) => AgentDecision::ToolCall {
+            name: call.name,
+            args: call.arguments,
+        },
+        None => AgentDecision::Final(turn.visible_text),
+    }
+}
+
 pub fn format_attachments(config: &Value, attachments: &[Value]) -> Option<String> {
     if attachments.is_empty() {
         return None;
@@ -373,4 +498,35 @@ mod tests {
         assert!(formatted.contains("Attachment: b"));
         assert!(!formatted.contains("Attachment: c"));
     }
+
+    #[test]
+    fn native_tool_turn_uses_the_first_call_or_the_reply_text() {
+        let call = tool_turn_to_agent_decision(NormalizedAssistantTurn {
+            tool_calls: vec![
+                crate::llm::types::ToolCall {
+                    id: "call_0".to_string(),
+                    name: "web_fetch".to_string(),
+                    arguments: json!({ "url": "https://example.com" }),
+                },
+                crate::llm::types::ToolCall {
+                    id: "call_1".to_string(),
+                    name: "search".to_string(),
+                    arguments: json!({}),
+                },
+            ],
+            ..Default::default()
+        });
+        assert!(matches!(
+            &call,
+            AgentDecision::ToolCall { name, args }
+                if name == "web_fetch" && args["url"] == "https://example.com"
+        ));
+        assert_eq!(call.kind(), "tool_call");
+
+        let answer = tool_turn_to_agent_decision(NormalizedAssistantTurn {
+            visible_text: "All done.".to_string(),
+            ..Default::default()
+        });
+        assert!(matches!(answer, AgentDecision::Final(ref text) if text == "All done."));
+    }
 }
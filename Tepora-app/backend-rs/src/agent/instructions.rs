@@ -6,6 +6,7 @@ pub fn build_agent_instructions(
     mode: RequestedAgentMode,
     thinking_mode: bool,
     selected_agent: Option<&SelectedAgentRuntime>,
+    native_tools: bool,
 ) -> String {
     let tools = if tool_names.is_empty() {
         "None (you must solve without tools unless the user asks to change policy)".to_string()
@@ -20,14 +21,21 @@ pub fn build_agent_instructions(
     } else {
         "Thinking mode is disabled. Keep reasoning concise."
     };
+    // ツール呼び出しの口を使えるモデルには構造化出力の書式を求めない
+    let decision_note = if native_tools {
+        "Call one tool at a time through the tool-calling interface.\n\
+When no tool is needed, reply to the user directly."
+    } else {
+        "Return your next action through the structured decision channel.\n\
+Use `type=tool_call` when invoking a tool and `type=final` when responding to the user.\n\
+Do not include extra commentary or alternate formats."
+    };
     format!(
         "You are operating in agent mode ({mode}).\n\
 {selected_agent_text}\n\
 {thinking_note}\n\
 You have access to the following tools: {tools}.\n\
-Return your next action through the structured decision channel.\n\
-Use `type=tool_call` when invoking a tool and `type=final` when responding to the user.\n\
-Do not include extra commentary or alternate formats.",
+{decision_note}",
         mode = mode.as_str()
     )
 }
@@ -1,5 +1,11 @@
+pub mod estimate;
+pub mod evaluation;
+pub mod exclusive;
 pub mod execution;
 pub mod instructions;
 pub mod modes;
+pub mod plan;
 pub mod policy;
+pub mod portable;
+pub mod regression;
 pub mod skill_registry;
@@ -0,0 +1,407 @@
+//! プランナーが立てる構造化された実行計画と、実行前のユーザー編集。
+//!
+//! 計画はステップ（ID・内容・使いそうなツール・先に終えるべきステップ）の並び。
+//! プランナーは JSON で出すよう頼み、崩れていれば Markdown の箇条書きとして読み直す。
+//! 計画を送ってから実行に移るまでの間は [`PlanEdits`] に実行 ID で登録しておき、
+//! `POST /api/runs/:id/plan` で届いた編集版に差し替える。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{Mutex, Notify};
+
+use crate::agent::estimate::parse_plan_steps;
+use crate::core::errors::ApiError;
+
+/// 1 つの計画に置けるステップの上限
+pub const MAX_PLAN_STEPS: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PlanStep {
+    pub id: String,
+    pub text: String,
+    /// 使いそうなツール名。実行を縛るものではない
+    #[serde(default)]
+    pub tool_hints: Vec<String>,
+    /// 先に終えておくステップの ID
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AgentPlan {
+    pub steps: Vec<PlanStep>,
+    /// ユーザーが編集するたびに増える
+    #[serde(default)]
+    pub revision: u32,
+}
+
+impl AgentPlan {
+    /// プランナーの出力を読む。JSON（コードフェンス付きも可）を優先し、
+    /// 読めなければ箇条書きを 1 つずつ前のステップに依存する計画にする。
+    pub fn parse(raw: &str) -> Self {
+        if let Some(plan) = parse_json_plan(raw) {
+            return plan;
+        }
+        let steps = parse_plan_steps(raw)
+            .into_iter()
+            .take(MAX_PLAN_STEPS)
+            .enumerate()
+            .map(|(index, text)| PlanStep {
+                id: step_id(index),
+                text,
+                tool_hints: Vec::new(),
+                depends_on: if index == 0 {
+                    Vec::new()
+                } else {
+                    vec![step_id(index - 1)]
+                },
+            })
+            .collect();
+        Self { steps, revision: 0 }
+    }
+
+    /// エグゼキューターに渡す Markdown。1 ステップ 1 行の箇条書きなので
+    /// 見積もり（`parse_plan_steps`）もそのまま数えられる。
+    pub fn to_markdown(&self) -> String {
+        self.steps
+            .iter()
+            .map(|step| {
+                let mut line = format!("- [{}] {}", step.id, step.text);
+                let mut notes = Vec::new();
+                if !step.tool_hints.is_empty() {
+                    notes.push(format!("tools: {}", step.tool_hints.join(", ")));
+                }
+                if !step.depends_on.is_empty() {
+                    notes.push(format!("after: {}", step.depends_on.join(", ")));
+                }
+                if !notes.is_empty() {
+                    line.push_str(&format!(" ({})", notes.join("; ")));
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// `keep`（0 始まり）のステップだけ残す。消したステップへの依存も外す。
+    pub fn retain_indices(&self, keep: &[usize]) -> Option<Self> {
+        let steps: Vec<PlanStep> = self
+            .steps
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| keep.contains(index))
+            .map(|(_, step)| step.clone())
+            .collect();
+        if steps.is_empty() {
+            return None;
+        }
+        let kept: HashSet<String> = steps.iter().map(|step| step.id.clone()).collect();
+        let steps = steps
+            .into_iter()
+            .map(|mut step| {
+                step.depends_on.retain(|id| kept.contains(id));
+                step
+            })
+            .collect();
+        Some(Self {
+            steps,
+            revision: self.revision,
+        })
+    }
+
+    /// 編集された計画を確かめる。ID は空でなく重複せず、依存先は計画内にあり、循環しないこと。
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if self.steps.is_empty() {
+            return Err(ApiError::BadRequest("Plan has no steps".to_string()));
+        }
+        if self.steps.len() > MAX_PLAN_STEPS {
+            return Err(ApiError::BadRequest(format!(
+                "Plan has more than {MAX_PLAN_STEPS} steps"
+            )));
+        }
+        let mut ids = HashSet::new();
+        for step in &self.steps {
+            if step.id.trim().is_empty() || step.text.trim().is_empty() {
+                return Err(ApiError::BadRequest(
+                    "Plan steps need an id and text".to_string(),
+                ));
+            }
+            if !ids.insert(step.id.as_str()) {
+                return Err(ApiError::BadRequest(format!(
+                    "Duplicate plan step id: {}",
+                    step.id
+                )));
+            }
+        }
+        for step in &self.steps {
+            if let Some(missing) = step.depends_on.iter().find(|id| !ids.contains(id.as_str())) {
+                return Err(ApiError::BadRequest(format!(
+                    "Plan step {} depends on unknown step {}",
+                    step.id, missing
+                )));
+            }
+        }
+        if self.has_cycle() {
+            return Err(ApiError::BadRequest(
+                "Plan step dependencies form a cycle".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn has_cycle(&self) -> bool {
+        let deps: HashMap<&str, &[String]> = self
+            .steps
+            .iter()
+            .map(|step| (step.id.as_str(), step.depends_on.as_slice()))
+            .collect();
+        // 0: 未訪問 / 1: 探索中 / 2: 済み
+        fn visit<'a>(
+            id: &'a str,
+            deps: &HashMap<&'a str, &'a [String]>,
+            marks: &mut HashMap<&'a str, u8>,
+        ) -> bool {
+            match marks.get(id).copied().unwrap_or(0) {
+                1 => return true,
+                2 => return false,
+                _ => {}
+            }
+            marks.insert(id, 1);
+            let cyclic = deps
+                .get(id)
+                .is_some_and(|next| next.iter().any(|dep| visit(dep, deps, marks)));
+            marks.insert(id, 2);
+            cyclic
+        }
+        let mut marks = HashMap::new();
+        self.steps
+            .iter()
+            .any(|step| visit(&step.id, &deps, &mut marks))
+    }
+}
+
+fn step_id(index: usize) -> String {
+    format!("s{}", index + 1)
+}
+
+/// `{"steps": [...]}` か、ステップの配列そのもの。
+fn parse_json_plan(raw: &str) -> Option<AgentPlan> {
+    let start = raw.find(['{', '['])?;
+    let end = raw.rfind(['}', ']'])?;
+    let value: Value = serde_json::from_str(raw.get(start..=end)?).ok()?;
+    let items = match &value {
+        Value::Array(items) => items,
+        Value::Object(map) => map.get("steps")?.as_array()?,
+        _ => return None,
+    };
+    let mut steps = Vec::new();
+    for (index, item) in items.iter().take(MAX_PLAN_STEPS).enumerate() {
+        let text = item
+            .get("text")
+            .or_else(|| item.get("description"))
+            .or_else(|| item.get("step"))
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|text| !text.is_empty())?;
+        let id = item
+            .get("id")
+            .and_then(|id| match id {
+                Value::String(id) => Some(id.trim().to_string()),
+                Value::Number(id) => Some(format!("s{id}")),
+                _ => None,
+            })
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| step_id(index));
+        let strings = |key: &str| -> Vec<String> {
+            item.get(key)
+                .and_then(Value::as_array)
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(|value| match value {
+                            Value::String(value) => Some(value.trim().to_string()),
+                            Value::Number(value) if key == "depends_on" => {
+                                Some(format!("s{value}"))
+                            }
+                            _ => None,
+                        })
+                        .filter(|value| !value.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let tool_hints = if item.get("tool_hints").is_some() {
+            strings("tool_hints")
+        } else {
+            strings("tools")
+        };
+        steps.push(PlanStep {
+            id,
+            text: text.to_string(),
+            tool_hints,
+            depends_on: strings("depends_on"),
+        });
+    }
+    let mut plan = AgentPlan { steps, revision: 0 };
+    if plan.validate().is_err() {
+        // 依存だけ壊れているなら外して使う
+        plan.steps
+            .iter_mut()
+            .for_each(|step| step.depends_on.clear());
+        plan.validate().ok()?;
+    }
+    Some(plan)
+}
+
+struct PendingPlan {
+    plan: AgentPlan,
+    edited: bool,
+    notify: Arc<Notify>,
+}
+
+/// 実行前の計画を実行 ID ごとに預かり、ユーザーの編集を受け付ける。
+#[derive(Clone, Default)]
+pub struct PlanEdits {
+    pending: Arc<Mutex<HashMap<String, PendingPlan>>>,
+}
+
+impl PlanEdits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 編集を受け付け始める。
+    pub async fn open(&self, run_id: &str, plan: &AgentPlan) {
+        self.pending.lock().await.insert(
+            run_id.to_string(),
+            PendingPlan {
+                plan: plan.clone(),
+                edited: false,
+                notify: Arc::new(Notify::new()),
+            },
+        );
+    }
+
+    /// 編集版に差し替える。その実行がもう計画を締め切っていれば `NotFound`。
+    pub async fn submit(&self, run_id: &str, mut plan: AgentPlan) -> Result<AgentPlan, ApiError> {
+        plan.validate()?;
+        let mut pending = self.pending.lock().await;
+        let entry = pending
+            .get_mut(run_id)
+            .ok_or_else(|| ApiError::NotFound("Run is not waiting for plan edits".to_string()))?;
+        plan.revision = entry.plan.revision + 1;
+        entry.plan = plan.clone();
+        entry.edited = true;
+        entry.notify.notify_one();
+        Ok(plan)
+    }
+
+    /// 編集が届くか `timeout` が過ぎるまで待つ。編集済みなら即座に返る。
+    pub async fn wait_for_edit(&self, run_id: &str, timeout: Duration) {
+        let notify = {
+            let pending = self.pending.lock().await;
+            match pending.get(run_id) {
+                Some(entry) if !entry.edited => entry.notify.clone(),
+                _ => return,
+            }
+        };
+        let _ = tokio::time::timeout(timeout, notify.notified()).await;
+    }
+
+    /// 編集済みなら編集版を返す。受付は続ける。
+    pub async fn edited(&self, run_id: &str) -> Option<AgentPlan> {
+        let pending = self.pending.lock().await;
+        pending
+            .get(run_id)
+            .filter(|entry| entry.edited)
+            .map(|entry| entry.plan.clone())
+    }
+
+    /// 受付を締め切る。
+    pub async fn close(&self, run_id: &str) {
+        self.pending.lock().await.remove(run_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_plans_are_read_and_markdown_falls_back_to_a_chain() {
+        let raw = "```json\n{\"steps\": [\
+            {\"id\": \"s1\", \"text\": \"Search the docs\", \"tools\": [\"web_search\"]},\
+            {\"id\": \"s2\", \"text\": \"Summarize\", \"depends_on\": [\"s1\"]}\
+        ]}\n```";
+        let plan = AgentPlan::parse(raw);
+        assert_eq!(plan.steps.len(), 2);
+        assert_eq!(plan.steps[0].tool_hints, vec!["web_search"]);
+        assert_eq!(plan.steps[1].depends_on, vec!["s1"]);
+        assert_eq!(
+            plan.to_markdown(),
+            "- [s1] Search the docs (tools: web_search)\n- [s2] Summarize (after: s1)"
+        );
+        assert_eq!(parse_plan_steps(&plan.to_markdown()).len(), 2);
+
+        let plan = AgentPlan::parse("- Read the config\n- Fix the bug\n- Run tests");
+        let ids: Vec<&str> = plan.steps.iter().map(|step| step.id.as_str()).collect();
+        assert_eq!(ids, vec!["s1", "s2", "s3"]);
+        assert_eq!(plan.steps[2].depends_on, vec!["s2"]);
+
+        let trimmed = plan.retain_indices(&[0, 2]).unwrap();
+        assert_eq!(trimmed.steps.len(), 2);
+        assert!(trimmed.steps[1].depends_on.is_empty());
+    }
+
+    #[test]
+    fn invalid_edits_are_rejected() {
+        let step = |id: &str, deps: &[&str]| PlanStep {
+            id: id.to_string(),
+            text: format!("step {id}"),
+            tool_hints: Vec::new(),
+            depends_on: deps.iter().map(|dep| dep.to_string()).collect(),
+        };
+        let plan = |steps| AgentPlan { steps, revision: 0 };
+        assert!(plan(vec![step("a", &[]), step("b", &["a"])])
+            .validate()
+            .is_ok());
+        assert!(plan(Vec::new()).validate().is_err());
+        assert!(plan(vec![step("a", &[]), step("a", &[])])
+            .validate()
+            .is_err());
+        assert!(plan(vec![step("a", &["missing"])]).validate().is_err());
+        assert!(plan(vec![step("a", &["b"]), step("b", &["a"])])
+            .validate()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn edits_replace_the_plan_while_the_run_is_open() {
+        let edits = PlanEdits::new();
+        let original = AgentPlan::parse("- one\n- two");
+        assert!(edits.submit("run", original.clone()).await.is_err());
+
+        edits.open("run", &original).await;
+        let waiter = {
+            let edits = edits.clone();
+            tokio::spawn(async move {
+                edits.wait_for_edit("run", Duration::from_secs(5)).await;
+                edits.edited("run").await
+            })
+        };
+        let mut edited = original.clone();
+        edited.steps.truncate(1);
+        let stored = edits.submit("run", edited).await.unwrap();
+        assert_eq!(stored.revision, 1);
+        let received = waiter.await.unwrap().unwrap();
+        assert_eq!(received.steps.len(), 1);
+
+        edits.close("run").await;
+        assert!(edits.submit("run", original).await.is_err());
+    }
+}
@@ -1,11 +1,68 @@
 use std::collections::HashSet;
 
+use serde::{Deserialize, Serialize};
+
+use crate::context::pipeline_context::MemoryChunk;
+use crate::core::native_tools::{native_tool_capability, resolve_tool_alias, ToolCapability};
+use crate::memory::in_recall_namespace;
+
+pub use crate::memory::AGENT_MEMORY_NAMESPACE_PREFIX;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilesystemGrant {
+    None,
+    Read,
+    #[default]
+    Write,
+}
+
+/// Capabilities an agent may use, independent of the tool lists.
+///
+/// Tools without a known capability (MCP tools) can do anything, so they
+/// require every grant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityGrants {
+    #[serde(default = "default_network")]
+    pub network: bool,
+    #[serde(default)]
+    pub filesystem: FilesystemGrant,
+}
+
+fn default_network() -> bool {
+    true
+}
+
+impl Default for CapabilityGrants {
+    fn default() -> Self {
+        Self {
+            network: true,
+            filesystem: FilesystemGrant::Write,
+        }
+    }
+}
+
+impl CapabilityGrants {
+    pub fn permits(&self, capability: Option<ToolCapability>) -> bool {
+        match capability {
+            Some(ToolCapability::Local) => true,
+            Some(ToolCapability::Network) => self.network,
+            Some(ToolCapability::FilesystemRead) => self.filesystem >= FilesystemGrant::Read,
+            Some(ToolCapability::FilesystemWrite) => self.filesystem >= FilesystemGrant::Write,
+            Some(ToolCapability::Process) | None => {
+                self.network && self.filesystem == FilesystemGrant::Write
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CustomToolPolicy {
     pub allow_all: bool,
     pub allowed_tools: HashSet<String>,
     pub denied_tools: HashSet<String>,
     pub require_confirmation: HashSet<String>,
+    pub grants: CapabilityGrants,
 }
 
 impl CustomToolPolicy {
@@ -15,20 +72,209 @@ impl CustomToolPolicy {
             allowed_tools: HashSet::new(),
             denied_tools: HashSet::new(),
             require_confirmation: HashSet::new(),
+            grants: CapabilityGrants::default(),
         }
     }
 
     pub fn is_tool_allowed(&self, tool_name: &str) -> bool {
-        if self.denied_tools.contains(tool_name) {
+        let tool_name = resolve_tool_alias(tool_name);
+        if self.denied_tools.contains(&tool_name) {
+            return false;
+        }
+        if !self.grants.permits(native_tool_capability(&tool_name)) {
             return false;
         }
         if self.allow_all {
             return true;
         }
-        self.allowed_tools.contains(tool_name)
+        self.allowed_tools.contains(&tool_name)
     }
 
     pub fn requires_confirmation(&self, tool_name: &str) -> bool {
-        self.require_confirmation.contains(tool_name)
+        self.require_confirmation
+            .contains(&resolve_tool_alias(tool_name))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentMemoryMode {
+    /// No retrieval and no episode extraction.
+    None,
+    /// Only memories from the current session; nothing is written to
+    /// long-term memory.
+    Session,
+    #[default]
+    Persistent,
+}
+
+/// How an agent reads and writes episodic memory.
+///
+/// Persistent agents with a `namespace` keep their episodes apart from the
+/// shared character memory; everyone else never sees namespaced episodes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentMemoryPolicy {
+    #[serde(default)]
+    pub mode: AgentMemoryMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retrieval_k: Option<usize>,
+    /// Added to relevance in proportion to memory strength, which decays
+    /// with age.
+    #[serde(default)]
+    pub recency_boost: f32,
+}
+
+impl AgentMemoryPolicy {
+    pub fn retrieves(&self) -> bool {
+        self.mode != AgentMemoryMode::None
+    }
+
+    pub fn ingests(&self) -> bool {
+        self.mode == AgentMemoryMode::Persistent
+    }
+
+    /// `character_id` to store new episodes under, when namespaced.
+    pub fn namespace_character_id(&self) -> Option<String> {
+        self.namespace
+            .as_deref()
+            .map(str::trim)
+            .filter(|ns| !ns.is_empty())
+            .map(|ns| format!("{}{}", AGENT_MEMORY_NAMESPACE_PREFIX, ns))
+    }
+
+    pub fn apply(&self, session_id: &str, chunks: &mut Vec<MemoryChunk>) {
+        let namespace = self.namespace_character_id();
+        chunks.retain(|chunk| {
+            (self.mode != AgentMemoryMode::Session || chunk.session_id == session_id)
+                && in_recall_namespace(chunk.character_id.as_deref(), namespace.as_deref())
+        });
+        if self.recency_boost > 0.0 {
+            for chunk in chunks.iter_mut() {
+                chunk.relevance_score += self.recency_boost * chunk.strength as f32;
+            }
+            chunks.sort_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+        }
+        if let Some(k) = self.retrieval_k {
+            chunks.truncate(k);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::native_tools::{NATIVE_RAG_SEARCH, NATIVE_SEARCH};
+    use crate::memory::{MemoryLayer, MemoryScope};
+
+    #[test]
+    fn grants_cap_tools_even_when_listed() {
+        let policy = CustomToolPolicy {
+            allow_all: false,
+            allowed_tools: [NATIVE_SEARCH, NATIVE_RAG_SEARCH, "shell"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            grants: CapabilityGrants {
+                network: false,
+                filesystem: FilesystemGrant::Read,
+            },
+            ..CustomToolPolicy::allow_all_policy()
+        };
+
+        assert!(policy.is_tool_allowed("rag_search"));
+        assert!(!policy.is_tool_allowed("web_search"));
+        assert!(!policy.is_tool_allowed("shell"));
+        assert!(!policy.is_tool_allowed("native_web_fetch"));
+    }
+
+    #[test]
+    fn default_policy_allows_everything() {
+        let policy = CustomToolPolicy::allow_all_policy();
+        assert!(policy.is_tool_allowed("web_search"));
+        assert!(policy.is_tool_allowed("some_mcp_tool"));
+    }
+
+    fn chunk(
+        session_id: &str,
+        character_id: Option<&str>,
+        score: f32,
+        strength: f64,
+    ) -> MemoryChunk {
+        MemoryChunk {
+            content: format!("{session_id}:{score}"),
+            relevance_score: score,
+            source: String::new(),
+            strength,
+            memory_layer: MemoryLayer::SML,
+            scope: MemoryScope::Char,
+            session_id: session_id.to_string(),
+            character_id: character_id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn memory_policy_isolates_namespaces_and_sessions() {
+        let chunks = vec![
+            chunk("s1", Some("bunny_girl"), 0.9, 1.0),
+            chunk("s2", Some("agent:scratch"), 0.8, 1.0),
+            chunk("s2", Some("bunny_girl"), 0.7, 1.0),
+        ];
+
+        let mut shared = chunks.clone();
+        AgentMemoryPolicy::default().apply("s1", &mut shared);
+        assert_eq!(shared.len(), 2);
+
+        let mut namespaced = chunks.clone();
+        AgentMemoryPolicy {
+            namespace: Some("scratch".to_string()),
+            ..AgentMemoryPolicy::default()
+        }
+        .apply("s1", &mut namespaced);
+        assert_eq!(namespaced.len(), 1);
+        assert_eq!(namespaced[0].character_id.as_deref(), Some("agent:scratch"));
+
+        let mut session = chunks;
+        let policy = AgentMemoryPolicy {
+            mode: AgentMemoryMode::Session,
+            ..AgentMemoryPolicy::default()
+        };
+        policy.apply("s2", &mut session);
+        assert_eq!(session.len(), 1);
+        assert!(!policy.ingests());
+    }
+
+    #[test]
+    fn memory_policy_boosts_recent_memories_before_truncating() {
+        let mut chunks = vec![chunk("s1", None, 0.9, 0.1), chunk("s1", None, 0.8, 1.0)];
+        AgentMemoryPolicy {
+            retrieval_k: Some(1),
+            recency_boost: 0.5,
+            ..AgentMemoryPolicy::default()
+        }
+        .apply("s1", &mut chunks);
+        assert_eq!(chunks.len(), 1);
+        assert!((chunks[0].relevance_score - 1.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn recency_boost_outside_unit_range_fails_validation() {
+        use crate::core::config::validation::validate_config;
+
+        let config = |boost: f64| {
+            serde_json::json!({
+                "agent_skills": { "memory_policies": { "coder": { "recency_boost": boost } } }
+            })
+        };
+        assert!(validate_config(&config(0.0)).is_ok());
+        assert!(validate_config(&config(1.0)).is_ok());
+        let err = validate_config(&config(1.5)).unwrap_err().to_string();
+        assert!(
+            err.contains("agent_skills.memory_policies.coder.recency_boost"),
+            "{}",
+            err
+        );
+        assert!(validate_config(&config(-0.1)).is_err());
     }
 }
@@ -0,0 +1,501 @@
+//! Shareable single-file agent definitions.
+//!
+//! An agent file bundles the skill package (SKILL.md and its files) with the
+//! settings that normally live in config: the tool policy, graph overrides
+//! and a hint about which model the agent was tuned for.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::agent::execution::agent_skill_setting;
+use crate::agent::skill_registry::{AgentSkillPackage, AgentSkillSaveRequest, SkillFileEntry};
+use crate::core::errors::ApiError;
+use crate::core::native_tools::{native_tool_capability, resolve_tool_alias};
+use crate::models::types::ModelEntry;
+use crate::state::AppState;
+
+pub const AGENT_FILE_FORMAT: &str = "tepora.agent";
+pub const AGENT_FILE_VERSION: u64 = 1;
+
+/// Model the exported agent was assigned to. Used only as a hint on import.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelHint {
+    pub display_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+}
+
+impl ModelHint {
+    fn from_entry(entry: &ModelEntry) -> Self {
+        Self {
+            display_name: entry.display_name.clone(),
+            model_id: Some(entry.id.clone()),
+            repo_id: entry.repo_id.clone(),
+            filename: Some(entry.filename.clone()).filter(|name| !name.is_empty()),
+        }
+    }
+
+    fn matches(&self, entry: &ModelEntry) -> bool {
+        if self.model_id.as_deref() == Some(entry.id.as_str()) {
+            return true;
+        }
+        match (&self.repo_id, &self.filename) {
+            (Some(repo), Some(file)) => {
+                entry.repo_id.as_deref() == Some(repo.as_str()) && &entry.filename == file
+            }
+            (None, Some(file)) => &entry.filename == file,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentDefinitionFile {
+    pub format: String,
+    pub version: u64,
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Full SKILL.md, frontmatter included.
+    pub prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub openai_yaml: Option<String>,
+    /// Tool policy in the same shape as `agent_skills.tool_policies.<id>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_hint: Option<ModelHint>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph_overrides: Option<Value>,
+    /// Same shape as `agent_skills.memory_policies.<id>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_policy: Option<Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_mcp_servers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<SkillFileEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scripts: Vec<SkillFileEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assets: Vec<SkillFileEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub other_files: Vec<SkillFileEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentImportOutcome {
+    pub skill: AgentSkillPackage,
+    pub warnings: Vec<String>,
+}
+
+/// JSON Schema for [`AgentDefinitionFile`].
+pub const AGENT_FILE_SCHEMA_JSON: &str = r##"{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "AgentDefinitionFile",
+  "type": "object",
+  "required": ["format", "version", "id", "name", "prompt"],
+  "properties": {
+    "format": { "const": "tepora.agent" },
+    "version": { "type": "integer", "minimum": 1, "maximum": 1 },
+    "id": { "type": "string", "pattern": "^[A-Za-z0-9_-]{1,64}$" },
+    "name": { "type": "string", "minLength": 1 },
+    "description": { "type": "string" },
+    "prompt": { "type": "string", "minLength": 1 },
+    "openai_yaml": { "type": ["string", "null"] },
+    "tools": {
+      "type": ["object", "null"],
+      "properties": {
+        "allow_all": { "type": "boolean" },
+        "allowed_tools": { "type": "array", "items": { "type": "string" } },
+        "denied_tools": { "type": "array", "items": { "type": "string" } },
+        "require_confirmation": { "type": "array", "items": { "type": "string" } },
+        "capabilities": {
+          "type": "object",
+          "properties": {
+            "network": { "type": "boolean" },
+            "filesystem": { "enum": ["none", "read", "write"] }
+          },
+          "additionalProperties": false
+        }
+      },
+      "additionalProperties": false
+    },
+    "model_hint": {
+      "type": ["object", "null"],
+      "required": ["display_name"],
+      "properties": {
+        "display_name": { "type": "string" },
+        "model_id": { "type": "string" },
+        "repo_id": { "type": "string" },
+        "filename": { "type": "string" }
+      }
+    },
+    "graph_overrides": {
+      "type": ["object", "null"],
+      "properties": {
+        "max_steps": { "type": "integer", "minimum": 1, "maximum": 50 }
+      },
+      "additionalProperties": false
+    },
+    "memory_policy": {
+      "type": ["object", "null"],
+      "properties": {
+        "mode": { "enum": ["none", "session", "persistent"] },
+        "namespace": { "type": "string", "pattern": "^[A-Za-z0-9_-]{1,64}$" },
+        "retrieval_k": { "type": "integer", "minimum": 0, "maximum": 50 },
+        "recency_boost": { "type": "number", "minimum": 0, "maximum": 1 }
+      },
+      "additionalProperties": false
+    },
+    "required_mcp_servers": { "type": "array", "items": { "type": "string" } },
+    "references": { "$ref": "#/definitions/files" },
+    "scripts": { "$ref": "#/definitions/files" },
+    "assets": { "$ref": "#/definitions/files" },
+    "other_files": { "$ref": "#/definitions/files" }
+  },
+  "definitions": {
+    "files": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "required": ["path", "kind", "content"],
+        "properties": {
+          "path": { "type": "string" },
+          "kind": { "type": "string" },
+          "content": { "type": "string" },
+          "encoding": { "type": "string" }
+        }
+      }
+    }
+  }
+}"##;
+
+pub fn validate_agent_file_json(value: &Value) -> Result<(), Vec<String>> {
+    let schema_json: Value = serde_json::from_str(AGENT_FILE_SCHEMA_JSON)
+        .expect("Hardcoded AGENT_FILE_SCHEMA_JSON is invalid");
+    let validator =
+        jsonschema::validator_for(&schema_json).expect("Failed to compile AGENT_FILE_SCHEMA_JSON");
+    let errors = validator
+        .iter_errors(value)
+        .map(|err| format!("{} at '{}'", err, err.instance_path()))
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+pub async fn export_agent(
+    state: &AppState,
+    agent_id: &str,
+) -> Result<AgentDefinitionFile, ApiError> {
+    let skill = state
+        .ai()
+        .skill_registry
+        .get(agent_id)
+        .ok_or_else(|| ApiError::NotFound("Agent Skill not found".to_string()))?;
+
+    let tools = agent_skill_setting(state, "tool_policies", agent_id)
+        .or_else(|| skill.summary.metadata.get("tool_policy").cloned());
+    let graph_overrides = agent_skill_setting(state, "graph_overrides", agent_id);
+    let memory_policy = agent_skill_setting(state, "memory_policies", agent_id)
+        .or_else(|| skill.summary.metadata.get("memory_policy").cloned());
+    let model_hint = state
+        .ai()
+        .models
+        .resolve_assignment_model(&format!("agent:{}", agent_id))
+        .ok()
+        .flatten()
+        .map(|entry| ModelHint::from_entry(&entry));
+
+    let mut required_mcp_servers = BTreeSet::new();
+    for tool in policy_tool_names(tools.as_ref()) {
+        if native_tool_capability(&tool).is_some() {
+            continue;
+        }
+        if let Ok(server) = state.integration.mcp.server_name_for_tool(&tool).await {
+            required_mcp_servers.insert(server);
+        }
+    }
+
+    Ok(AgentDefinitionFile {
+        format: AGENT_FILE_FORMAT.to_string(),
+        version: AGENT_FILE_VERSION,
+        id: skill.summary.id.clone(),
+        name: skill.summary.name.clone(),
+        description: skill.summary.description.clone(),
+        prompt: skill.skill_markdown,
+        openai_yaml: skill.openai_yaml,
+        tools,
+        model_hint,
+        graph_overrides,
+        memory_policy,
+        required_mcp_servers: required_mcp_servers.into_iter().collect(),
+        references: skill.references,
+        scripts: skill.scripts,
+        assets: skill.assets,
+        other_files: skill.other_files,
+    })
+}
+
+pub async fn import_agent(
+    state: &AppState,
+    payload: Value,
+    overwrite: bool,
+) -> Result<AgentImportOutcome, ApiError> {
+    validate_agent_file_json(&payload).map_err(|errors| {
+        ApiError::BadRequest(format!("Invalid agent file: {}", errors.join("; ")))
+    })?;
+    let file: AgentDefinitionFile = serde_json::from_value(payload)
+        .map_err(|err| ApiError::BadRequest(format!("Invalid agent file: {}", err)))?;
+
+    let registry = &state.ai().skill_registry;
+    if !overwrite && registry.get(&file.id).is_some() {
+        return Err(ApiError::Conflict(format!(
+            "Agent '{}' already exists",
+            file.id
+        )));
+    }
+
+    // Config settings are easy to put back, so write them first and restore
+    // them if the package cannot be saved.
+    let settings = [
+        ("tool_policies", file.tools.clone()),
+        ("graph_overrides", file.graph_overrides.clone()),
+        ("memory_policies", file.memory_policy.clone()),
+    ];
+    let previous = settings
+        .iter()
+        .map(|(section, _)| (*section, agent_skill_setting(state, section, &file.id)))
+        .collect::<Vec<_>>();
+    write_agent_skill_settings(state, &file.id, Vec::from(settings))?;
+
+    let saved = registry.save_package(AgentSkillSaveRequest {
+        id: file.id.clone(),
+        root_path: None,
+        skill_markdown: file.prompt.clone(),
+        openai_yaml: file.openai_yaml.clone(),
+        references: file.references.clone(),
+        scripts: file.scripts.clone(),
+        assets: file.assets.clone(),
+        other_files: file.other_files.clone(),
+    });
+    let skill = match saved {
+        Ok(skill) => skill,
+        Err(err) => {
+            if let Err(restore_err) = write_agent_skill_settings(state, &file.id, previous) {
+                tracing::warn!(
+                    agent_id = %file.id,
+                    "Failed to restore agent settings after a failed import: {}",
+                    restore_err
+                );
+            }
+            return Err(err);
+        }
+    };
+
+    let mut warnings = Vec::new();
+    if let Some(hint) = &file.model_hint {
+        warnings.extend(apply_model_hint(state, &file.id, hint));
+    }
+    let configured_servers = state.integration.mcp.get_config().await.mcp_servers;
+    for server in &file.required_mcp_servers {
+        if !configured_servers.contains_key(server) {
+            warnings.push(format!(
+                "MCP server '{}' is required by this agent but is not installed",
+                server
+            ));
+        }
+    }
+
+    Ok(AgentImportOutcome { skill, warnings })
+}
+
+/// Assign the hinted model when it is installed; otherwise return a warning.
+fn apply_model_hint(state: &AppState, agent_id: &str, hint: &ModelHint) -> Option<String> {
+    let models = &state.ai().models;
+    let installed = models
+        .list_models()
+        .ok()
+        .and_then(|entries| entries.into_iter().find(|entry| hint.matches(entry)));
+    let Some(entry) = installed else {
+        let source = hint
+            .repo_id
+            .as_deref()
+            .map(|repo| format!(" ({repo})"))
+            .unwrap_or_default();
+        return Some(format!(
+            "Model '{}'{} is recommended for this agent but is not installed",
+            hint.display_name, source
+        ));
+    };
+
+    let assignment_key = format!("agent:{}", agent_id);
+    if let Err(err) = models.set_assignment_model(&assignment_key, &entry.id) {
+        return Some(format!(
+            "Could not assign model '{}' to the agent: {}",
+            entry.display_name, err
+        ));
+    }
+    None
+}
+
+fn write_agent_skill_settings(
+    state: &AppState,
+    agent_id: &str,
+    settings: Vec<(&str, Option<Value>)>,
+) -> Result<(), ApiError> {
+    state.core().config.modify_config(|config| {
+        for (section, value) in settings {
+            set_agent_skill_setting(config, section, agent_id, value)?;
+        }
+        Ok(())
+    })
+}
+
+fn set_agent_skill_setting(
+    config: &mut Value,
+    section: &str,
+    agent_id: &str,
+    value: Option<Value>,
+) -> Result<(), ApiError> {
+    let root = config
+        .as_object_mut()
+        .ok_or_else(|| ApiError::internal("config root is not an object"))?;
+    let skills = root
+        .entry("agent_skills")
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| {
+            ApiError::BadRequest("config 'agent_skills' is not an object".to_string())
+        })?;
+    let entries = skills
+        .entry(section)
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "config 'agent_skills.{}' is not an object",
+                section
+            ))
+        })?;
+    match value.filter(|value| !value.is_null()) {
+        Some(value) => {
+            entries.insert(agent_id.to_string(), value);
+        }
+        None => {
+            entries.remove(agent_id);
+        }
+    }
+    Ok(())
+}
+
+fn policy_tool_names(policy: Option<&Value>) -> Vec<String> {
+    policy
+        .and_then(|policy| policy.get("allowed_tools"))
+        .and_then(Value::as_array)
+        .map(|tools| {
+            tools
+                .iter()
+                .filter_map(Value::as_str)
+                .map(resolve_tool_alias)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn minimal_file() -> Value {
+        json!({
+            "format": "tepora.agent",
+            "version": 1,
+            "id": "writer",
+            "name": "Writer",
+            "prompt": "---\nname: Writer\ndescription: Writes\n---\nWrite well.",
+            "tools": {
+                "allowed_tools": ["rag_search"],
+                "capabilities": {"network": false, "filesystem": "none"}
+            },
+            "graph_overrides": {"max_steps": 4}
+        })
+    }
+
+    #[test]
+    fn schema_accepts_minimal_agent_file() {
+        assert!(validate_agent_file_json(&minimal_file()).is_ok());
+        let parsed: AgentDefinitionFile = serde_json::from_value(minimal_file()).unwrap();
+        assert_eq!(
+            policy_tool_names(parsed.tools.as_ref()),
+            ["native_rag_search"]
+        );
+    }
+
+    #[test]
+    fn schema_rejects_unsafe_ids_and_unknown_fields() {
+        let mut file = minimal_file();
+        file["id"] = json!("../escape");
+        file["graph_overrides"] = json!({"max_steps": 4, "entry": "x"});
+        let errors = validate_agent_file_json(&file).unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn model_hint_matches_by_id_or_file() {
+        let entry: ModelEntry = serde_json::from_value(json!({
+            "id": "m1",
+            "display_name": "Gemma",
+            "role": "text",
+            "file_size": 1,
+            "filename": "gemma.gguf",
+            "source": "hf",
+            "file_path": "/models/gemma.gguf",
+            "repo_id": "google/gemma",
+            "added_at": "2026-01-01"
+        }))
+        .unwrap();
+        let by_file = ModelHint {
+            display_name: "Gemma".to_string(),
+            model_id: Some("other".to_string()),
+            repo_id: Some("google/gemma".to_string()),
+            filename: Some("gemma.gguf".to_string()),
+        };
+        assert!(by_file.matches(&entry));
+        let wrong_repo = ModelHint {
+            repo_id: Some("someone/else".to_string()),
+            ..by_file.clone()
+        };
+        assert!(!wrong_repo.matches(&entry));
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn failed_import_leaves_config_untouched() {
+        use crate::test_support::{init_state_with_config, ENV_LOCK};
+
+        let _lock = ENV_LOCK.lock();
+        let skills_root = tempfile::tempdir().unwrap();
+        let (_sandbox, _env_guard, state) = init_state_with_config(&format!(
+            "agent_skills:\n  roots:\n    - path: '{}'\n      enabled: true\n",
+            skills_root.path().display()
+        ))
+        .await;
+        let mut file = minimal_file();
+        file["prompt"] = json!("no frontmatter");
+
+        assert!(import_agent(&state, file, false).await.is_err());
+        assert!(agent_skill_setting(&state, "tool_policies", "writer").is_none());
+        assert!(agent_skill_setting(&state, "graph_overrides", "writer").is_none());
+    }
+}
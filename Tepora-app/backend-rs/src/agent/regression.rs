@@ -0,0 +1,703 @@
+//! Prompt / agent regression suites.
+//!
+//! `<user_data_dir>/evals/*.yaml` に置いたスイートを `POST /api/evals/run` で
+//! 現在の設定のまま流し、各ケースの応答を contains / regex / LLM 判定で確かめる。
+//! 結果は `eval_runs` に残し、スイートごとの合格率の推移を出す。
+//! ケースは 1 ターンの応答だけを見る（ツール・記憶・RAG は通さない）。
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::agent::evaluation::prompt_revision;
+use crate::core::config::personas::active_persona_id;
+use crate::core::errors::ApiError;
+use crate::history::{EvalRunRecord, NewEvalRun};
+use crate::llm::types::StructuredResponseSpec;
+use crate::llm::{ChatMessage, ChatRequest};
+use crate::state::AppState;
+
+pub const EVALS_DIR: &str = "evals";
+const MAX_RECORDED_OUTPUT_CHARS: usize = 2000;
+const MAX_JUDGED_CHARS: usize = 6000;
+
+const JUDGE_RUBRIC: &str = "You check whether an assistant reply meets a criterion.\n\
+Answer passed=true only if the reply clearly satisfies the criterion for the given input.\n\
+Keep the rationale to one sentence.";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalSuite {
+    /// 省略時はファイル名
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Agent Skill の ID。`persona` とはどちらか一方
+    #[serde(default)]
+    pub agent: Option<String>,
+    /// ペルソナ ID。どちらも無ければ実行時のアクティブなペルソナ
+    #[serde(default)]
+    pub persona: Option<String>,
+    pub cases: Vec<EvalCase>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalCase {
+    pub id: String,
+    pub input: String,
+    #[serde(default)]
+    pub expect: EvalExpectation,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalExpectation {
+    #[serde(default)]
+    pub contains: Vec<String>,
+    #[serde(default)]
+    pub not_contains: Vec<String>,
+    #[serde(default)]
+    pub regex: Vec<String>,
+    /// LLM（professional モデル）に判定させる合格条件
+    #[serde(default)]
+    pub judge: Option<String>,
+}
+
+impl EvalExpectation {
+    fn is_empty(&self) -> bool {
+        self.contains.is_empty()
+            && self.not_contains.is_empty()
+            && self.regex.is_empty()
+            && self.judge.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvalCheck {
+    /// contains / not_contains / regex / judge
+    pub kind: &'static str,
+    pub expected: String,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalCaseResult {
+    pub id: String,
+    pub passed: bool,
+    pub output: String,
+    pub checks: Vec<EvalCheck>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalSuiteRun {
+    pub run_id: i64,
+    pub suite: String,
+    pub target_kind: &'static str,
+    pub target_id: String,
+    pub revision: String,
+    pub model_id: String,
+    pub passed: usize,
+    pub total: usize,
+    pub pass_rate: f64,
+    pub cases: Vec<EvalCaseResult>,
+    pub created_at: String,
+}
+
+/// Parse one suite file. `fallback_name` (the file stem) is used when `name` is omitted.
+pub fn parse_suite(fallback_name: &str, yaml: &str) -> Result<EvalSuite, ApiError> {
+    let mut suite: EvalSuite = serde_yaml::from_str(yaml)
+        .map_err(|err| ApiError::BadRequest(format!("Invalid eval suite: {}", err)))?;
+    if suite.name.trim().is_empty() {
+        suite.name = fallback_name.to_string();
+    }
+    suite.name = suite.name.trim().to_string();
+    if suite.agent.is_some() && suite.persona.is_some() {
+        return Err(ApiError::BadRequest(format!(
+            "Eval suite '{}' must bind either an agent or a persona, not both",
+            suite.name
+        )));
+    }
+    if suite.cases.is_empty() {
+        return Err(ApiError::BadRequest(format!(
+            "Eval suite '{}' has no cases",
+            suite.name
+        )));
+    }
+    let mut seen = HashSet::new();
+    for case in &suite.cases {
+        if case.id.trim().is_empty() || !seen.insert(case.id.as_str()) {
+            return Err(ApiError::BadRequest(format!(
+                "Eval suite '{}' has an empty or duplicate case id '{}'",
+                suite.name, case.id
+            )));
+        }
+        if case.expect.is_empty() {
+            return Err(ApiError::BadRequest(format!(
+                "Eval case '{}' has no expectations",
+                case.id
+            )));
+        }
+        for pattern in &case.expect.regex {
+            Regex::new(pattern).map_err(|err| {
+                ApiError::BadRequest(format!("Eval case '{}': invalid regex: {}", case.id, err))
+            })?;
+        }
+    }
+    Ok(suite)
+}
+
+/// Every `*.yaml` / `*.yml` suite in `dir`, sorted by file name. Files that fail
+/// to parse are returned separately so one broken suite does not hide the rest.
+pub fn load_suites(dir: &Path) -> (Vec<EvalSuite>, Vec<(String, String)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return (Vec::new(), Vec::new());
+    };
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("yaml" | "yml")
+            )
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut suites = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(ApiError::internal)
+            .and_then(|yaml| parse_suite(&stem, &yaml));
+        match parsed {
+            Ok(suite) => suites.push(suite),
+            Err(err) => errors.push((file_name, err.to_string())),
+        }
+    }
+    (suites, errors)
+}
+
+pub fn suites_dir(state: &AppState) -> std::path::PathBuf {
+    state.core().paths.user_data_dir.join(EVALS_DIR)
+}
+
+/// System prompt and model the suite runs against.
+struct EvalTarget {
+    kind: &'static str,
+    id: String,
+    system_prompt: String,
+    model_id: String,
+    /// Agent はエージェント統計と揃えて SKILL.md 本文、ペルソナは組み立てたプロンプトのハッシュ
+    revision: String,
+}
+
+fn resolve_target(
+    state: &AppState,
+    config: &Value,
+    suite: &EvalSuite,
+) -> Result<EvalTarget, ApiError> {
+    if let Some(agent_id) = suite.agent.as_deref() {
+        let agent = crate::agent::execution::resolve_selected_agent(state, Some(agent_id))
+            .ok_or_else(|| ApiError::NotFound(format!("Agent Skill '{}' not found", agent_id)))?;
+        let system_prompt = [
+            Some(agent.skill_body.trim()),
+            agent.resource_prompt.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+        let model_id = match agent.assigned_model_id {
+            Some(model_id) => model_id,
+            None => professional_model_id(state),
+        };
+        return Ok(EvalTarget {
+            kind: "agent",
+            revision: prompt_revision(&agent.skill_body),
+            id: agent.id,
+            system_prompt,
+            model_id,
+        });
+    }
+
+    let persona_id = suite
+        .persona
+        .clone()
+        .unwrap_or_else(|| active_persona_id(config).to_string());
+    let persona = state
+        .core()
+        .config
+        .get_persona(&persona_id)?
+        .ok_or_else(|| ApiError::NotFound(format!("Persona '{}' not found", persona_id)))?;
+    let mut system_prompt = persona.system_prompt.clone().unwrap_or_default();
+    let rules = persona
+        .style_rules
+        .iter()
+        .map(|rule| rule.trim())
+        .filter(|rule| !rule.is_empty())
+        .map(|rule| format!("- {rule}"))
+        .collect::<Vec<_>>();
+    if !rules.is_empty() {
+        system_prompt = format!(
+            "{}\n\nStyle rules:\n{}",
+            system_prompt.trim(),
+            rules.join("\n")
+        );
+    }
+    let model_role = persona
+        .model_role
+        .as_deref()
+        .map(str::trim)
+        .filter(|role| !role.is_empty())
+        .unwrap_or(&persona_id);
+    let model_id = state
+        .ai()
+        .models
+        .resolve_character_model_id(Some(model_role))?
+        .unwrap_or_else(|| "default".to_string());
+    let system_prompt = system_prompt.trim().to_string();
+    Ok(EvalTarget {
+        kind: "persona",
+        id: persona_id,
+        revision: prompt_revision(&system_prompt),
+        system_prompt,
+        model_id,
+    })
+}
+
+fn professional_model_id(state: &AppState) -> String {
+    state
+        .ai()
+        .models
+        .resolve_assignment_model_id("professional")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "default".to_string())
+}
+
+/// contains / not_contains / regex. `parse_suite` has already validated the patterns.
+pub fn static_checks(expect: &EvalExpectation, output: &str) -> Vec<EvalCheck> {
+    let mut checks = Vec::new();
+    for needle in &expect.contains {
+        checks.push(EvalCheck {
+            kind: "contains",
+            expected: needle.clone(),
+            passed: output.contains(needle.as_str()),
+            detail: None,
+        });
+    }
+    for needle in &expect.not_contains {
+        checks.push(EvalCheck {
+            kind: "not_contains",
+            expected: needle.clone(),
+            passed: !output.contains(needle.as_str()),
+            detail: None,
+        });
+    }
+    for pattern in &expect.regex {
+        let passed = Regex::new(pattern)
+            .map(|regex| regex.is_match(output))
+            .unwrap_or(false);
+        checks.push(EvalCheck {
+            kind: "regex",
+            expected: pattern.clone(),
+            passed,
+            detail: None,
+        });
+    }
+    checks
+}
+
+#[derive(Debug, Deserialize)]
+struct JudgeVerdict {
+    passed: bool,
+    #[serde(default)]
+    rationale: String,
+}
+
+fn judge_spec() -> StructuredResponseSpec {
+    StructuredResponseSpec {
+        name: "eval_judge".to_string(),
+        description: Some("Whether the reply meets the criterion".to_string()),
+        schema: json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "passed": { "type": "boolean" },
+                "rationale": { "type": "string" }
+            },
+            "required": ["passed", "rationale"]
+        }),
+    }
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+async fn judge(state: &AppState, criterion: &str, input: &str, output: &str) -> EvalCheck {
+    let request = ChatRequest::new(vec![
+        ChatMessage::new_text("system", JUDGE_RUBRIC),
+        ChatMessage::new_text(
+            "user",
+            format!(
+                "Criterion:\n{}\n\nInput:\n{}\n\nReply:\n{}",
+                criterion,
+                truncate_chars(input, MAX_JUDGED_CHARS),
+                truncate_chars(output, MAX_JUDGED_CHARS)
+            ),
+        ),
+    ])
+    .with_structured_response(judge_spec());
+    let verdict = state
+        .ai()
+        .llm
+        .chat_structured::<JudgeVerdict>(request, &professional_model_id(state))
+        .await;
+    let (passed, detail) = match verdict {
+        Ok(verdict) => (verdict.passed, verdict.rationale),
+        Err(err) => (false, format!("judge failed: {}", err)),
+    };
+    EvalCheck {
+        kind: "judge",
+        expected: criterion.to_string(),
+        passed,
+        detail: (!detail.trim().is_empty()).then_some(detail),
+    }
+}
+
+async fn run_case(
+    state: &AppState,
+    config: &Value,
+    target: &EvalTarget,
+    case: &EvalCase,
+) -> EvalCaseResult {
+    let mut messages = Vec::new();
+    if !target.system_prompt.is_empty() {
+        messages.push(ChatMessage::new_text("system", &target.system_prompt));
+    }
+    messages.push(ChatMessage::new_text("user", &case.input));
+    let request = ChatRequest::new(messages).with_config(config);
+
+    let output = match state.ai().llm.chat(request, &target.model_id).await {
+        Ok(output) => output,
+        Err(err) => {
+            return EvalCaseResult {
+                id: case.id.clone(),
+                passed: false,
+                output: String::new(),
+                checks: Vec::new(),
+                error: Some(err.to_string()),
+            }
+        }
+    };
+
+    let mut checks = static_checks(&case.expect, &output);
+    if let Some(criterion) = case.expect.judge.as_deref() {
+        checks.push(judge(state, criterion, &case.input, &output).await);
+    }
+    EvalCaseResult {
+        id: case.id.clone(),
+        passed: checks.iter().all(|check| check.passed),
+        output: truncate_chars(&output, MAX_RECORDED_OUTPUT_CHARS),
+        checks,
+        error: None,
+    }
+}
+
+/// Run every case (or only `case_ids`) in order and record the result.
+pub async fn run_suite(
+    state: &AppState,
+    config: &Value,
+    suite: &EvalSuite,
+    case_ids: Option<&[String]>,
+) -> Result<EvalSuiteRun, ApiError> {
+    let target = resolve_target(state, config, suite)?;
+    let mut cases = Vec::new();
+    for case in &suite.cases {
+        if case_ids.is_some_and(|ids| !ids.contains(&case.id)) {
+            continue;
+        }
+        cases.push(run_case(state, config, &target, case).await);
+    }
+    if cases.is_empty() {
+        return Err(ApiError::BadRequest(format!(
+            "No matching cases in eval suite '{}'",
+            suite.name
+        )));
+    }
+
+    let passed = cases.iter().filter(|case| case.passed).count();
+    let total = cases.len();
+    let record = state
+        .runtime()
+        .history
+        .add_eval_run(&NewEvalRun {
+            suite: suite.name.clone(),
+            target_kind: target.kind.to_string(),
+            target_id: target.id.clone(),
+            revision: target.revision.clone(),
+            model_id: target.model_id.clone(),
+            passed: passed as i64,
+            total: total as i64,
+            cases: serde_json::to_value(&cases).map_err(ApiError::internal)?,
+        })
+        .await?;
+
+    Ok(EvalSuiteRun {
+        run_id: record.id,
+        suite: suite.name.clone(),
+        target_kind: target.kind,
+        target_id: target.id,
+        revision: target.revision,
+        model_id: target.model_id,
+        passed,
+        total,
+        pass_rate: passed as f64 / total as f64,
+        cases,
+        created_at: record.created_at,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvalRunPoint {
+    pub run_id: i64,
+    pub revision: String,
+    pub model_id: String,
+    pub passed: i64,
+    pub total: i64,
+    pub pass_rate: f64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvalCaseStats {
+    pub id: String,
+    pub runs: usize,
+    pub passed: usize,
+    pub pass_rate: f64,
+    /// 直近の実行で合格したか
+    pub last_passed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvalSuiteHistory {
+    pub suite: String,
+    pub latest_pass_rate: Option<f64>,
+    /// Oldest run first.
+    pub runs: Vec<EvalRunPoint>,
+    pub cases: Vec<EvalCaseStats>,
+}
+
+fn ratio(passed: f64, total: f64) -> f64 {
+    if total == 0.0 {
+        0.0
+    } else {
+        passed / total
+    }
+}
+
+/// Group recorded runs (oldest first) into per-suite pass-rate history.
+pub fn pass_rate_history(records: &[EvalRunRecord]) -> Vec<EvalSuiteHistory> {
+    let mut suites: BTreeMap<&str, (Vec<EvalRunPoint>, BTreeMap<String, EvalCaseStats>)> =
+        BTreeMap::new();
+    for record in records {
+        let (runs, cases) = suites.entry(record.suite.as_str()).or_default();
+        runs.push(EvalRunPoint {
+            run_id: record.id,
+            revision: record.revision.clone(),
+            model_id: record.model_id.clone(),
+            passed: record.passed,
+            total: record.total,
+            pass_rate: ratio(record.passed as f64, record.total as f64),
+            created_at: record.created_at.clone(),
+        });
+        for case in record.cases.as_array().into_iter().flatten() {
+            let Some(id) = case.get("id").and_then(Value::as_str) else {
+                continue;
+            };
+            let passed = case.get("passed").and_then(Value::as_bool) == Some(true);
+            let stats = cases
+                .entry(id.to_string())
+                .or_insert_with(|| EvalCaseStats {
+                    id: id.to_string(),
+                    runs: 0,
+                    passed: 0,
+                    pass_rate: 0.0,
+                    last_passed: false,
+                });
+            stats.runs += 1;
+            stats.passed += usize::from(passed);
+            stats.last_passed = passed;
+        }
+    }
+
+    suites
+        .into_iter()
+        .map(|(suite, (runs, cases))| EvalSuiteHistory {
+            suite: suite.to_string(),
+            latest_pass_rate: runs.last().map(|run| run.pass_rate),
+            runs,
+            cases: cases
+                .into_values()
+                .map(|mut stats| {
+                    stats.pass_rate = ratio(stats.passed as f64, stats.runs as f64);
+                    stats
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUITE: &str = r#"
+description: Greeting tone
+persona: shigure
+cases:
+  - id: greets
+    input: "こんにちは"
+    expect:
+      contains: ["こんにちは"]
+      not_contains: ["As an AI"]
+      regex: ["(?i)^\\S"]
+  - id: polite
+    input: "Say goodbye"
+    expect:
+      judge: "Says goodbye politely"
+"#;
+
+    #[test]
+    fn parses_suite_and_defaults_name_to_file_stem() {
+        let suite = parse_suite("tone", SUITE).unwrap();
+        assert_eq!(suite.name, "tone");
+        assert_eq!(suite.persona.as_deref(), Some("shigure"));
+        assert_eq!(suite.cases.len(), 2);
+        assert_eq!(
+            suite.cases[1].expect.judge.as_deref(),
+            Some("Says goodbye politely")
+        );
+    }
+
+    #[test]
+    fn rejects_ambiguous_or_broken_suites() {
+        let both = "agent: coder\npersona: shigure\ncases:\n  - {id: a, input: x, expect: {contains: [x]}}\n";
+        assert!(parse_suite("s", both).is_err());
+        let duplicate = "cases:\n  - {id: a, input: x, expect: {contains: [x]}}\n  - {id: a, input: y, expect: {contains: [y]}}\n";
+        assert!(parse_suite("s", duplicate).is_err());
+        let no_checks = "cases:\n  - {id: a, input: x}\n";
+        assert!(parse_suite("s", no_checks).is_err());
+        let bad_regex = "cases:\n  - {id: a, input: x, expect: {regex: ['(']}}\n";
+        assert!(parse_suite("s", bad_regex).is_err());
+        let typo = "cases:\n  - {id: a, input: x, expect: {contain: [x]}}\n";
+        assert!(parse_suite("s", typo).is_err());
+    }
+
+    #[test]
+    fn static_checks_report_each_expectation() {
+        let expect = EvalExpectation {
+            contains: vec!["hello".to_string()],
+            not_contains: vec!["sorry".to_string()],
+            regex: vec![r"\d{3}".to_string()],
+            judge: None,
+        };
+        let checks = static_checks(&expect, "hello, call 555");
+        assert!(checks.iter().all(|check| check.passed));
+
+        let checks = static_checks(&expect, "sorry, no");
+        assert_eq!(
+            checks
+                .iter()
+                .map(|check| (check.kind, check.passed))
+                .collect::<Vec<_>>(),
+            vec![
+                ("contains", false),
+                ("not_contains", false),
+                ("regex", false)
+            ]
+        );
+    }
+
+    #[test]
+    fn history_tracks_pass_rates_per_suite_and_case() {
+        let record = |id, suite: &str, passed, cases: Value| EvalRunRecord {
+            id,
+            suite: suite.to_string(),
+            target_kind: "persona".to_string(),
+            target_id: "shigure".to_string(),
+            revision: format!("rev{id}"),
+            model_id: "default".to_string(),
+            passed,
+            total: 2,
+            cases,
+            created_at: format!("2024-01-0{id}T00:00:00Z"),
+        };
+        let records = vec![
+            record(
+                1,
+                "tone",
+                2,
+                json!([{"id": "a", "passed": true}, {"id": "b", "passed": true}]),
+            ),
+            record(2, "coder", 0, json!([{"id": "x", "passed": false}])),
+            record(
+                3,
+                "tone",
+                1,
+                json!([{"id": "a", "passed": true}, {"id": "b", "passed": false}]),
+            ),
+        ];
+
+        let history = pass_rate_history(&records);
+        assert_eq!(history.len(), 2);
+        let tone = history.iter().find(|suite| suite.suite == "tone").unwrap();
+        assert_eq!(tone.latest_pass_rate, Some(0.5));
+        assert_eq!(
+            tone.runs
+                .iter()
+                .map(|run| run.pass_rate)
+                .collect::<Vec<_>>(),
+            vec![1.0, 0.5]
+        );
+        let b = tone.cases.iter().find(|case| case.id == "b").unwrap();
+        assert_eq!((b.runs, b.passed, b.pass_rate), (2, 1, 0.5));
+        assert!(!b.last_passed);
+    }
+
+    #[test]
+    fn loads_yaml_files_and_reports_broken_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("tone.yaml"), SUITE).unwrap();
+        std::fs::write(dir.path().join("broken.yml"), "cases: nope").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let (suites, errors) = load_suites(dir.path());
+        assert_eq!(suites.len(), 1);
+        assert_eq!(suites[0].name, "tone");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "broken.yml");
+    }
+}
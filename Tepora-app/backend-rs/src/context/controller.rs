@@ -7,6 +7,8 @@ use super::controller_blocks::{
     collect_blocks as collect_context_blocks, compress_blocks as compress_context_blocks,
     dedupe_blocks as dedupe_context_blocks, drop_blocks as drop_context_blocks,
 };
+pub use super::controller_budget::ContextCompositionReport;
+use super::controller_budget::{apply_budget, AppliedSummary, TruncationEvent};
 use super::controller_recipe::window_recipe_for_mode;
 use super::controller_render::render_blocks_static;
 pub(crate) use super::controller_render::render_untrusted_xml_element;
@@ -121,8 +123,7 @@ pub(super) struct ContextRenderDiagnostics {
     pub(super) estimation_source: String,
     pub(super) rendered_message_count: usize,
     pub(super) context_block_count: usize,
-    pub(super) dropped_blocks: Vec<String>,
-    pub(super) compressed_blocks: Vec<String>,
+    pub(super) truncations: Vec<TruncationEvent>,
 }
 
 pub struct ContextController {
@@ -141,11 +142,37 @@ impl ContextController {
     }
 
     pub fn render(&self, ctx: &PipelineContext) -> Vec<ChatMessage> {
+        self.render_with_report(ctx).0
+    }
+
+    /// Render messages together with the per-section budget breakdown.
+    pub fn render_with_report(
+        &self,
+        ctx: &PipelineContext,
+    ) -> (Vec<ChatMessage>, ContextCompositionReport) {
         let mut blocks = self.collect_blocks(ctx);
         let mut diagnostics = ContextRenderDiagnostics::default();
         self.dedupe_blocks(&mut blocks);
         self.compress_blocks(&mut blocks, &mut diagnostics);
+        let mut report = apply_budget(
+            ctx,
+            self.budget.available_input_budget(),
+            &self.estimator,
+            &mut blocks,
+            &mut diagnostics,
+        );
         self.drop_blocks(&mut blocks, &mut diagnostics);
+        report.summaries = blocks
+            .iter()
+            .filter(|block| {
+                block.source_key == "conversation_summary"
+                    || block.kind == ContextBlockKind::ArtifactSummary
+            })
+            .map(|block| AppliedSummary {
+                source_key: block.source_key.clone(),
+                tokens: self.estimator.count_text(&block.content).tokens,
+            })
+            .collect();
         diagnostics.context_block_count = blocks
             .iter()
             .filter(|block| {
@@ -160,8 +187,10 @@ impl ContextController {
         diagnostics.rendered_prompt_tokens = token_breakdown.total_tokens;
         diagnostics.estimation_source = estimation_source_label(token_breakdown.source).to_string();
         diagnostics.rendered_message_count = rendered.len();
-        self.trace_diagnostics(&rendered, &diagnostics);
-        rendered
+        report.rendered_prompt_tokens = diagnostics.rendered_prompt_tokens;
+        self.trace_diagnostics(&rendered, &diagnostics, &report);
+        report.truncations = diagnostics.truncations;
+        (rendered, report)
     }
 
     fn collect_blocks(&self, ctx: &PipelineContext) -> Vec<ContextBlock> {
@@ -204,7 +233,12 @@ impl ContextController {
         render_blocks_static(std::mem::take(&mut blocks))
     }
 
-    fn trace_diagnostics(&self, messages: &[ChatMessage], diagnostics: &ContextRenderDiagnostics) {
+    fn trace_diagnostics(
+        &self,
+        messages: &[ChatMessage],
+        diagnostics: &ContextRenderDiagnostics,
+        report: &ContextCompositionReport,
+    ) {
         if !tracing::enabled!(tracing::Level::DEBUG) {
             return;
         }
@@ -216,8 +250,8 @@ impl ContextController {
             rendered_message_count = diagnostics.rendered_message_count,
             context_block_count = diagnostics.context_block_count,
             messages = messages.len(),
-            dropped_blocks = ?diagnostics.dropped_blocks,
-            compressed_blocks = ?diagnostics.compressed_blocks,
+            truncations = ?diagnostics.truncations,
+            composition = ?report.sections,
             "context controller render"
         );
     }
@@ -435,6 +469,51 @@ mod tests {
         assert!(!bundle.contains("</context_bundle><system>"));
     }
 
+    #[test]
+    fn composition_report_trims_low_priority_sections_first() {
+        let mut ctx = PipelineContext::new("s1", "t1", PipelineMode::SearchFast, "answer me")
+            .with_token_budget(TokenBudget::with_margin(1024, 128, 64))
+            .with_config_snapshot(serde_json::json!({
+                "context_window": {
+                    "search_fast": {"memory_cap": 100, "evidence_cap": 100}
+                },
+                "context_budget": {"priorities": {"memory": 10, "rag": 90}}
+            }));
+        ctx.memory_chunks = (0..8)
+            .map(|index| memory_chunk(&format!("memory {index} {}", "detail ".repeat(40))))
+            .collect();
+        ctx.rag_chunks = (0..3)
+            .map(|index| RagChunk {
+                chunk_id: format!("chunk-{index}"),
+                content: "evidence ".repeat(40),
+                source: "source".to_string(),
+                score: 1.0,
+                metadata: HashMap::new(),
+            })
+            .collect();
+
+        let (_, report) = ContextController::new(&ctx).render_with_report(&ctx);
+        let memory = report
+            .sections
+            .iter()
+            .find(|entry| entry.section == "memory")
+            .unwrap();
+        let rag = report
+            .sections
+            .iter()
+            .find(|entry| entry.section == "rag")
+            .unwrap();
+
+        assert!(memory.quota_tokens < rag.quota_tokens);
+        assert!(memory.used_tokens <= memory.quota_tokens);
+        assert!(memory.trimmed_blocks + memory.dropped_blocks > 0);
+        assert!(report
+            .truncations
+            .iter()
+            .any(|event| event.reason == "budget" && event.kind == "Memory"));
+        assert!(report.rendered_prompt_tokens > 0);
+    }
+
     #[test]
     fn rendered_messages_respect_budget_after_xml_wrapping() {
         let mut ctx = PipelineContext::new("s1", "t1", PipelineMode::SearchFast, "answer me")
@@ -458,4 +537,33 @@ mod tests {
 
         assert!(rendered.total_tokens <= ctx.token_budget.available_input_budget());
     }
+
+    fn turn_context(turn: &str, input: &str, memory: &str, packet: &str) -> PipelineContext {
+        let mut ctx = PipelineContext::new("s1", turn, PipelineMode::Chat, input)
+            .with_token_budget(TokenBudget::with_margin(4096, 128, 64));
+        ctx.add_system_part("base_system", "You are Tepora.", 200);
+        ctx.add_volatile_system_part("executor_task_packet", packet, 130);
+        ctx.add_system_part("persona_style", "Style rules:\n- Be brief.", 190);
+        ctx.add_system_part("available_tools", "[Available Tools]\nweb_search", 80);
+        ctx.memory_chunks = vec![memory_chunk(memory)];
+        ctx
+    }
+
+    #[test]
+    fn system_prefix_is_stable_across_turns() {
+        let first = turn_context("t1", "hello", "likes tea", "Goal: greet");
+        let second = turn_context("t2", "what next?", "asked about tea", "Goal: plan");
+
+        let first_messages = ContextController::new(&first).render(&first);
+        let second_messages = ContextController::new(&second).render(&second);
+        let first_system = &first_messages[0].content;
+        let second_system = &second_messages[0].content;
+
+        let stable_prefix =
+            "You are Tepora.\n\nStyle rules:\n- Be brief.\n\n[Available Tools]\nweb_search";
+        assert!(first_system.starts_with(stable_prefix));
+        assert!(second_system.starts_with(stable_prefix));
+        assert!(first_system.ends_with("Goal: greet"));
+        assert!(second_system.ends_with("Goal: plan"));
+    }
 }
@@ -4,6 +4,7 @@ use super::controller::{
     ContextBlock, ContextBlockKind, ContextRenderDiagnostics, TokenCountBreakdown,
     TokenEstimateSource, TokenEstimator, WindowRecipe,
 };
+use super::controller_budget::TruncationEvent;
 use super::controller_render::{
     normalize_key, prompt_score, render_blocks_static, render_local_context, render_memory_card,
     summarize_artifact, trim_to_tokens,
@@ -48,6 +49,24 @@ pub(super) fn collect_blocks(
         });
     }
 
+    if let Some(summary) = ctx
+        .conversation_summary
+        .as_deref()
+        .filter(|value| !value.trim().is_empty())
+    {
+        blocks.push(ContextBlock {
+            kind: ContextBlockKind::LocalContext,
+            role: "system".to_string(),
+            source_key: "conversation_summary".to_string(),
+            content: format!(
+                "[Conversation So Far]\n{}",
+                trim_to_tokens(summary.trim(), 256, estimator)
+            ),
+            required: false,
+            score: 420.0,
+        });
+    }
+
     if !ctx.local_context.is_empty() {
         let rendered = render_local_context(&ctx.local_context, 160, estimator);
         if !rendered.trim().is_empty() {
@@ -265,12 +284,13 @@ pub(super) fn compress_blocks(
             if blocks[index].required {
                 continue;
             }
-            let original = blocks[index].content.clone();
             let body = trim_to_tokens(&blocks[index].content, target_each.max(32), estimator);
-            if body != original {
-                diagnostics.compressed_blocks.push(format!(
-                    "{:?}:{}",
-                    blocks[index].kind, blocks[index].source_key
+            if body != blocks[index].content {
+                diagnostics.truncations.push(TruncationEvent::trimmed(
+                    "compression",
+                    &blocks[index],
+                    estimator.count_text(&blocks[index].content).tokens,
+                    estimator.count_text(&body).tokens,
                 ));
             }
             blocks[index].content = body;
@@ -293,9 +313,11 @@ pub(super) fn drop_blocks(
             break;
         };
         let removed = blocks.remove(index);
-        diagnostics
-            .dropped_blocks
-            .push(format!("{:?}:{}", removed.kind, removed.source_key));
+        diagnostics.truncations.push(TruncationEvent::dropped(
+            "overflow",
+            &removed,
+            estimator.count_text(&removed.content).tokens,
+        ));
     }
 }
 
@@ -309,7 +331,7 @@ fn enforce_per_kind_caps(
     for (kind, share) in &recipe.caps {
         let cap = available.saturating_mul(*share) / 100;
         if cap == 0 {
-            remove_optional_blocks_of_kind(blocks, *kind, diagnostics, "disabled");
+            remove_optional_blocks_of_kind(blocks, *kind, estimator, diagnostics, "disabled");
             continue;
         }
         loop {
@@ -331,9 +353,11 @@ fn enforce_per_kind_caps(
                 break;
             };
             let removed = blocks.remove(index);
-            diagnostics
-                .dropped_blocks
-                .push(format!("cap:{:?}:{}", removed.kind, removed.source_key));
+            diagnostics.truncations.push(TruncationEvent::dropped(
+                "cap",
+                &removed,
+                estimator.count_text(&removed.content).tokens,
+            ));
         }
     }
 }
@@ -383,16 +407,18 @@ fn total_message_tokens(
 fn remove_optional_blocks_of_kind(
     blocks: &mut Vec<ContextBlock>,
     kind: ContextBlockKind,
+    estimator: &TokenEstimator,
     diagnostics: &mut ContextRenderDiagnostics,
-    reason: &str,
+    reason: &'static str,
 ) {
     let mut index = 0;
     while index < blocks.len() {
         if blocks[index].kind == kind && !blocks[index].required {
             let removed = blocks.remove(index);
-            diagnostics.dropped_blocks.push(format!(
-                "{}:{:?}:{}",
-                reason, removed.kind, removed.source_key
+            diagnostics.truncations.push(TruncationEvent::dropped(
+                reason,
+                &removed,
+                estimator.count_text(&removed.content).tokens,
             ));
         } else {
             index += 1;
@@ -0,0 +1,414 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::controller::{ContextBlock, ContextBlockKind, ContextRenderDiagnostics, TokenEstimator};
+use super::controller_render::trim_to_tokens;
+use super::pipeline_context::PipelineContext;
+
+/// Blocks trimmed below this size are dropped instead of kept as stubs.
+const MIN_TRIMMED_TOKENS: usize = 16;
+
+/// Budget sections roughly follow the worker that produced the content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetSection {
+    Memory,
+    LocalContext,
+    InteractionTail,
+    Search,
+    Rag,
+    Artifacts,
+    Thinking,
+}
+
+impl BudgetSection {
+    const ALL: [BudgetSection; 7] = [
+        BudgetSection::Memory,
+        BudgetSection::LocalContext,
+        BudgetSection::InteractionTail,
+        BudgetSection::Search,
+        BudgetSection::Rag,
+        BudgetSection::Artifacts,
+        BudgetSection::Thinking,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BudgetSection::Memory => "memory",
+            BudgetSection::LocalContext => "local_context",
+            BudgetSection::InteractionTail => "interaction_tail",
+            BudgetSection::Search => "search",
+            BudgetSection::Rag => "rag",
+            BudgetSection::Artifacts => "artifacts",
+            BudgetSection::Thinking => "thinking",
+        }
+    }
+
+    fn default_priority(self) -> u64 {
+        match self {
+            BudgetSection::Rag => 70,
+            BudgetSection::Memory => 60,
+            BudgetSection::Search => 60,
+            BudgetSection::LocalContext => 50,
+            BudgetSection::InteractionTail => 40,
+            BudgetSection::Artifacts => 40,
+            BudgetSection::Thinking => 20,
+        }
+    }
+
+    fn of(block: &ContextBlock) -> Option<Self> {
+        match block.kind {
+            ContextBlockKind::System | ContextBlockKind::UserInput => None,
+            ContextBlockKind::Memory => Some(BudgetSection::Memory),
+            ContextBlockKind::LocalContext => Some(BudgetSection::LocalContext),
+            ContextBlockKind::InteractionTail => Some(BudgetSection::InteractionTail),
+            ContextBlockKind::Evidence if block.source_key.starts_with("search:") => {
+                Some(BudgetSection::Search)
+            }
+            ContextBlockKind::Evidence => Some(BudgetSection::Rag),
+            ContextBlockKind::ArtifactSummary => Some(BudgetSection::Artifacts),
+            ContextBlockKind::AppThinkingDigest | ContextBlockKind::ModelThinkingDigest => {
+                Some(BudgetSection::Thinking)
+            }
+        }
+    }
+}
+
+/// Priority weights read from `context_budget.priorities`.
+#[derive(Debug, Clone)]
+pub(super) struct BudgetPolicy {
+    enabled: bool,
+    priorities: BTreeMap<BudgetSection, u64>,
+}
+
+impl BudgetPolicy {
+    pub(super) fn from_config(config: &Value) -> Self {
+        let section = config.get("context_budget");
+        let enabled = section
+            .and_then(|v| v.get("enabled"))
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+        let configured = section.and_then(|v| v.get("priorities"));
+        let priorities = BudgetSection::ALL
+            .into_iter()
+            .map(|kind| {
+                let priority = configured
+                    .and_then(|v| v.get(kind.as_str()))
+                    .and_then(Value::as_u64)
+                    .unwrap_or_else(|| kind.default_priority());
+                (kind, priority)
+            })
+            .collect();
+        Self {
+            enabled,
+            priorities,
+        }
+    }
+
+    fn priority(&self, section: BudgetSection) -> u64 {
+        self.priorities.get(&section).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompositionEntry {
+    pub section: String,
+    pub priority: u64,
+    pub requested_tokens: usize,
+    pub quota_tokens: usize,
+    pub used_tokens: usize,
+    pub trimmed_blocks: usize,
+    pub dropped_blocks: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SystemPartUsage {
+    pub label: String,
+    pub priority: u8,
+    pub tokens: usize,
+}
+
+/// Per-turn breakdown of where the prompt budget went.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ContextCompositionReport {
+    pub available_tokens: usize,
+    pub reserved_tokens: usize,
+    pub rendered_prompt_tokens: usize,
+    pub system_parts: Vec<SystemPartUsage>,
+    pub sections: Vec<CompositionEntry>,
+    pub truncations: Vec<TruncationEvent>,
+    pub summaries: Vec<AppliedSummary>,
+}
+
+/// A block that was shortened or removed while fitting the prompt.
+#[derive(Debug, Clone, Serialize)]
+pub struct TruncationEvent {
+    /// `trimmed` or `dropped`.
+    pub action: &'static str,
+    /// The pass that touched the block: `compression`, `budget`, `cap`,
+    /// `disabled` or `overflow`.
+    pub reason: &'static str,
+    pub kind: String,
+    pub source_key: String,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+}
+
+impl TruncationEvent {
+    pub(super) fn trimmed(
+        reason: &'static str,
+        block: &ContextBlock,
+        tokens_before: usize,
+        tokens_after: usize,
+    ) -> Self {
+        Self {
+            action: "trimmed",
+            reason,
+            kind: format!("{:?}", block.kind),
+            source_key: block.source_key.clone(),
+            tokens_before,
+            tokens_after,
+        }
+    }
+
+    pub(super) fn dropped(
+        reason: &'static str,
+        block: &ContextBlock,
+        tokens_before: usize,
+    ) -> Self {
+        Self {
+            action: "dropped",
+            reason,
+            kind: format!("{:?}", block.kind),
+            source_key: block.source_key.clone(),
+            tokens_before,
+            tokens_after: 0,
+        }
+    }
+}
+
+/// Summarized content that made it into the prompt in place of the original.
+#[derive(Debug, Clone, Serialize)]
+pub struct AppliedSummary {
+    pub source_key: String,
+    pub tokens: usize,
+}
+
+/// Split `available` across sections by priority weight.
+///
+/// Sections that ask for less than their share keep only what they asked
+/// for, and the surplus is redistributed to the remaining sections.
+pub(super) fn allocate_quotas(
+    available: usize,
+    demands: &BTreeMap<BudgetSection, usize>,
+    policy: &BudgetPolicy,
+) -> BTreeMap<BudgetSection, usize> {
+    let mut quotas = BTreeMap::new();
+    let mut pending = demands
+        .iter()
+        .filter(|(_, demand)| **demand > 0)
+        .map(|(section, demand)| (*section, *demand))
+        .collect::<Vec<_>>();
+    let mut remaining = available;
+
+    for (section, _) in pending.iter().filter(|(s, _)| policy.priority(*s) == 0) {
+        quotas.insert(*section, 0);
+    }
+    pending.retain(|(section, _)| policy.priority(*section) > 0);
+
+    loop {
+        let weight_total: u64 = pending.iter().map(|(s, _)| policy.priority(*s)).sum();
+        if pending.is_empty() || weight_total == 0 {
+            break;
+        }
+
+        let share = |section: BudgetSection| {
+            (remaining as u128 * policy.priority(section) as u128 / weight_total as u128) as usize
+        };
+        let satisfied = pending
+            .iter()
+            .filter(|(section, demand)| *demand <= share(*section))
+            .copied()
+            .collect::<Vec<_>>();
+
+        if satisfied.is_empty() {
+            for (section, _) in &pending {
+                quotas.insert(*section, share(*section));
+            }
+            break;
+        }
+
+        for (section, demand) in satisfied {
+            quotas.insert(section, demand);
+            remaining = remaining.saturating_sub(demand);
+            pending.retain(|(s, _)| *s != section);
+        }
+    }
+
+    quotas
+}
+
+/// Trim optional blocks so each section fits its priority-based quota and
+/// return a composition report for the run trace.
+pub(super) fn apply_budget(
+    ctx: &PipelineContext,
+    available: usize,
+    estimator: &TokenEstimator,
+    blocks: &mut Vec<ContextBlock>,
+    diagnostics: &mut ContextRenderDiagnostics,
+) -> ContextCompositionReport {
+    let policy = BudgetPolicy::from_config(ctx.config());
+
+    let reserved_tokens: usize = blocks
+        .iter()
+        .filter(|block| BudgetSection::of(block).is_none() || block.required)
+        .map(|block| estimator.count_text(&block.content).tokens)
+        .sum();
+    let section_budget = available.saturating_sub(reserved_tokens);
+
+    let mut demands = BTreeMap::new();
+    for block in blocks.iter().filter(|block| !block.required) {
+        if let Some(section) = BudgetSection::of(block) {
+            *demands.entry(section).or_insert(0usize) +=
+                estimator.count_text(&block.content).tokens;
+        }
+    }
+
+    let quotas = if policy.enabled {
+        allocate_quotas(section_budget, &demands, &policy)
+    } else {
+        demands.clone()
+    };
+
+    let mut entries: BTreeMap<BudgetSection, CompositionEntry> = BTreeMap::new();
+    for (section, demand) in &demands {
+        let quota = quotas.get(section).copied().unwrap_or(0);
+        let entry = entries.entry(*section).or_default();
+        entry.section = section.as_str().to_string();
+        entry.priority = policy.priority(*section);
+        entry.requested_tokens = *demand;
+        entry.quota_tokens = quota;
+
+        if quota >= *demand {
+            continue;
+        }
+
+        let mut index = 0;
+        while index < blocks.len() {
+            let block = &blocks[index];
+            if block.required || BudgetSection::of(block) != Some(*section) {
+                index += 1;
+                continue;
+            }
+            let tokens = estimator.count_text(&block.content).tokens;
+            let target = tokens.saturating_mul(quota) / (*demand).max(1);
+            if target < MIN_TRIMMED_TOKENS {
+                let removed = blocks.remove(index);
+                diagnostics
+                    .truncations
+                    .push(TruncationEvent::dropped("budget", &removed, tokens));
+                entry.dropped_blocks += 1;
+                continue;
+            }
+            let trimmed = trim_to_tokens(&block.content, target, estimator);
+            if trimmed != block.content {
+                diagnostics.truncations.push(TruncationEvent::trimmed(
+                    "budget",
+                    block,
+                    tokens,
+                    estimator.count_text(&trimmed).tokens,
+                ));
+                entry.trimmed_blocks += 1;
+                blocks[index].content = trimmed;
+            }
+            index += 1;
+        }
+    }
+
+    for block in blocks.iter() {
+        if let Some(section) = BudgetSection::of(block) {
+            if let Some(entry) = entries.get_mut(&section) {
+                entry.used_tokens += estimator.count_text(&block.content).tokens;
+            }
+        }
+    }
+
+    ContextCompositionReport {
+        available_tokens: available,
+        reserved_tokens,
+        rendered_prompt_tokens: 0,
+        system_parts: ctx
+            .ordered_system_parts()
+            .into_iter()
+            .map(|part| SystemPartUsage {
+                label: part.label.clone(),
+                priority: part.priority,
+                tokens: estimator.count_text(&part.content).tokens,
+            })
+            .collect(),
+        sections: entries.into_values().collect(),
+        // Filled in by the controller once the remaining passes have run.
+        truncations: Vec::new(),
+        summaries: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn demands(items: &[(BudgetSection, usize)]) -> BTreeMap<BudgetSection, usize> {
+        items.iter().copied().collect()
+    }
+
+    #[test]
+    fn quotas_follow_priority_weights_when_everything_overflows() {
+        let policy = BudgetPolicy::from_config(&json!({
+            "context_budget": {"priorities": {"memory": 30, "rag": 10}}
+        }));
+        let quotas = allocate_quotas(
+            400,
+            &demands(&[(BudgetSection::Memory, 1_000), (BudgetSection::Rag, 1_000)]),
+            &policy,
+        );
+        assert_eq!(quotas[&BudgetSection::Memory], 300);
+        assert_eq!(quotas[&BudgetSection::Rag], 100);
+    }
+
+    #[test]
+    fn surplus_from_small_sections_is_redistributed() {
+        let policy = BudgetPolicy::from_config(&json!({
+            "context_budget": {"priorities": {"memory": 50, "rag": 50}}
+        }));
+        let quotas = allocate_quotas(
+            400,
+            &demands(&[(BudgetSection::Memory, 50), (BudgetSection::Rag, 1_000)]),
+            &policy,
+        );
+        assert_eq!(quotas[&BudgetSection::Memory], 50);
+        assert_eq!(quotas[&BudgetSection::Rag], 350);
+    }
+
+    #[test]
+    fn zero_priority_section_gets_no_quota() {
+        let policy = BudgetPolicy::from_config(&json!({
+            "context_budget": {"priorities": {"thinking": 0}}
+        }));
+        let quotas = allocate_quotas(
+            400,
+            &demands(&[(BudgetSection::Thinking, 10), (BudgetSection::Memory, 10)]),
+            &policy,
+        );
+        assert_eq!(quotas[&BudgetSection::Thinking], 0);
+        assert_eq!(quotas[&BudgetSection::Memory], 10);
+    }
+
+    #[test]
+    fn disabled_policy_is_read_from_config() {
+        assert!(BudgetPolicy::from_config(&json!({})).enabled);
+        assert!(!BudgetPolicy::from_config(&json!({"context_budget": {"enabled": false}})).enabled);
+    }
+}
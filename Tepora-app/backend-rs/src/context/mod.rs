@@ -1,5 +1,6 @@
 pub mod controller;
 mod controller_blocks;
+mod controller_budget;
 mod controller_recipe;
 mod controller_render;
 mod controller_tokens;
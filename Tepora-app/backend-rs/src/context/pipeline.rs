@@ -1,16 +1,25 @@
 use super::pipeline_context::{ModelTokenizerSpec, PipelineContext, PipelineMode, TokenBudget};
 use super::worker::WorkerPipeline;
 use super::workers::character_worker::CharacterWorker;
+use super::workers::language_worker::LanguageWorker;
 use super::workers::memory_worker::MemoryWorker;
+use super::workers::persona_worker::{apply_session_persona, PersonaWorker};
+use super::workers::plugin_worker::PluginWorker;
+use super::workers::project_worker::{apply_session_project, ProjectWorker};
 use super::workers::rag_worker::RagWorker;
+use super::workers::script_worker::ScriptWorker;
 use super::workers::search_worker::SearchWorker;
+use super::workers::summary_worker::SummaryWorker;
 use super::workers::system_worker::SystemWorker;
 use super::workers::tool_worker::ToolWorker;
+use crate::core::config::personas::model_profile_id;
 use crate::core::errors::ApiError;
+use crate::infrastructure::observability::latency;
 use crate::llm::ChatMessage;
 use crate::state::AppState;
 use serde_json::Value;
 use std::sync::Arc;
+use std::time::Instant;
 
 pub struct ContextResult {
     pub messages: Vec<ChatMessage>,
@@ -26,7 +35,72 @@ impl ContextPipeline {
         mode: PipelineMode,
         skip_web_search: bool,
     ) -> Result<PipelineContext, ApiError> {
-        let config = state.core().config.load_config().unwrap_or_default();
+        Self::build(
+            state,
+            session_id,
+            user_input,
+            mode,
+            skip_web_search,
+            None,
+            false,
+        )
+        .await
+    }
+
+    /// Same as [`Self::build_v4`], applying the selected agent's memory policy.
+    pub async fn build_v4_for_agent(
+        state: &Arc<AppState>,
+        session_id: &str,
+        user_input: &str,
+        mode: PipelineMode,
+        skip_web_search: bool,
+        agent_id: Option<&str>,
+    ) -> Result<PipelineContext, ApiError> {
+        Self::build(
+            state,
+            session_id,
+            user_input,
+            mode,
+            skip_web_search,
+            agent_id,
+            false,
+        )
+        .await
+    }
+
+    /// Run every worker as for a real turn, without LLM calls or persistence.
+    pub async fn build_preview(
+        state: &Arc<AppState>,
+        session_id: &str,
+        user_input: &str,
+        mode: PipelineMode,
+        skip_web_search: bool,
+    ) -> Result<PipelineContext, ApiError> {
+        Self::build(
+            state,
+            session_id,
+            user_input,
+            mode,
+            skip_web_search,
+            None,
+            true,
+        )
+        .await
+    }
+
+    async fn build(
+        state: &Arc<AppState>,
+        session_id: &str,
+        user_input: &str,
+        mode: PipelineMode,
+        skip_web_search: bool,
+        agent_id: Option<&str>,
+        dry_run: bool,
+    ) -> Result<PipelineContext, ApiError> {
+        let started = Instant::now();
+        let mut config = state.core().config.load_config().unwrap_or_default();
+        apply_session_project(state, session_id, &mut config).await;
+        apply_session_persona(state, session_id, &mut config).await;
         let token_budget = resolve_token_budget(state, &config, mode);
         let tokenizer_spec = resolve_tokenizer_spec(state, &config);
 
@@ -38,20 +112,32 @@ impl ContextPipeline {
         )
         .with_config_snapshot(config.clone())
         .with_token_budget(token_budget)
-        .with_tokenizer_spec(tokenizer_spec);
+        .with_tokenizer_spec(tokenizer_spec)
+        .with_dry_run(dry_run)
+        .with_agent_id(agent_id);
 
         let pipeline = WorkerPipeline::new()
             .add_worker(Box::new(SystemWorker))
             .add_worker(Box::new(CharacterWorker))
+            .add_worker(Box::new(PersonaWorker))
+            .add_worker(Box::new(LanguageWorker::new(skip_web_search)))
+            .add_worker(Box::new(ProjectWorker))
             .add_worker(Box::new(MemoryWorker::default()))
+            .add_worker(Box::new(SummaryWorker))
             .add_worker(Box::new(ToolWorker))
             .add_worker(Box::new(SearchWorker::new(skip_web_search)))
-            .add_worker(Box::new(RagWorker::default()));
+            .add_worker(Box::new(RagWorker::default()))
+            .add_worker(Box::new(PluginWorker))
+            .add_worker(Box::new(ScriptWorker));
 
         pipeline
             .run(&mut pipeline_ctx, state)
             .await
             .map_err(|e| ApiError::Internal(format!("Pipeline failed: {e}")))?;
+        if !dry_run {
+            latency::record_context_build(started.elapsed());
+            latency::record_context_tokens(pipeline_ctx.estimate_tokens());
+        }
 
         Ok(pipeline_ctx)
     }
@@ -79,10 +165,7 @@ fn resolve_token_budget(state: &Arc<AppState>, config: &Value, mode: PipelineMod
 }
 
 fn resolve_context_length(state: &Arc<AppState>, config: &Value) -> usize {
-    let active_character = config
-        .get("active_character")
-        .or_else(|| config.get("active_agent_profile"))
-        .and_then(|value| value.as_str());
+    let active_character = model_profile_id(config);
 
     let context_from_registry = state
         .ai()
@@ -124,10 +207,7 @@ fn clamp(value: usize, min: usize, max: usize) -> usize {
 }
 
 fn resolve_tokenizer_spec(state: &Arc<AppState>, config: &Value) -> ModelTokenizerSpec {
-    let active_character = config
-        .get("active_character")
-        .or_else(|| config.get("active_agent_profile"))
-        .and_then(|value| value.as_str());
+    let active_character = model_profile_id(config);
     let config_model = config
         .get("models")
         .or_else(|| config.get("models_gguf"))
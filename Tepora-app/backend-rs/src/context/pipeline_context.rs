@@ -9,6 +9,7 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::prompt::SystemPromptLayout;
 use crate::infrastructure::episodic_store::MemoryScope;
 use crate::llm::ChatMessage;
 use crate::memory::MemoryLayer;
@@ -152,6 +153,10 @@ pub struct SystemPart {
     pub label: String,
     pub content: String,
     pub priority: u8,
+    /// Per-turn content (e.g. the executor task packet). Volatile parts are
+    /// rendered after every stable part so the cached prompt prefix survives.
+    #[serde(default)]
+    pub volatile: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -242,6 +247,19 @@ pub struct PipelineArtifact {
     pub metadata: HashMap<String, Value>,
 }
 
+/// Languages chosen by the language worker (ISO 639-1 codes).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LanguageContext {
+    /// Language the user wrote in, when it could be detected
+    pub detected: Option<String>,
+    /// Language the answer is requested in
+    pub reply: Option<String>,
+    /// Language web searches are issued in
+    pub search: Option<String>,
+    /// User message translated into the search language
+    pub search_query: Option<String>,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ReasoningState {
     pub app_thinking_raw: Vec<String>,
@@ -258,10 +276,13 @@ pub struct PipelineContext {
     pub stage: PipelineStage,
     pub config_snapshot: Value,
     pub system_parts: Vec<SystemPart>,
+    pub system_layout: SystemPromptLayout,
     pub character: Option<CharacterConfig>,
     pub user_input: String,
     pub working_memory: HashMap<String, Value>,
     pub local_context: LocalContext,
+    pub conversation_summary: Option<String>,
+    pub language: Option<LanguageContext>,
     pub interaction_tail: Option<InteractionTail>,
     pub memory_chunks: Vec<MemoryChunk>,
     pub search_results: Vec<SearchResult>,
@@ -273,6 +294,10 @@ pub struct PipelineContext {
     pub reasoning: ReasoningState,
     pub token_budget: TokenBudget,
     pub tokenizer_spec: ModelTokenizerSpec,
+    /// Preview run: workers must not call the LLM or persist state.
+    pub dry_run: bool,
+    /// Agent the context is built for; selects its memory policy.
+    pub agent_id: Option<String>,
 }
 
 impl PipelineContext {
@@ -289,10 +314,13 @@ impl PipelineContext {
             stage: PipelineStage::Main,
             config_snapshot: Value::Null,
             system_parts: Vec::new(),
+            system_layout: SystemPromptLayout::default(),
             character: None,
             user_input: user_input.into(),
             working_memory: HashMap::new(),
             local_context: LocalContext::default(),
+            conversation_summary: None,
+            language: None,
             interaction_tail: None,
             memory_chunks: Vec::new(),
             search_results: Vec::new(),
@@ -304,6 +332,8 @@ impl PipelineContext {
             reasoning: ReasoningState::default(),
             token_budget: TokenBudget::default(),
             tokenizer_spec: ModelTokenizerSpec::default(),
+            dry_run: false,
+            agent_id: None,
         }
     }
 
@@ -318,6 +348,7 @@ impl PipelineContext {
     }
 
     pub fn with_config_snapshot(mut self, config_snapshot: Value) -> Self {
+        self.system_layout = SystemPromptLayout::from_config(&config_snapshot);
         self.config_snapshot = config_snapshot;
         self
     }
@@ -327,6 +358,16 @@ impl PipelineContext {
         self
     }
 
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn with_agent_id(mut self, agent_id: Option<&str>) -> Self {
+        self.agent_id = agent_id.map(str::to_string);
+        self
+    }
+
     pub fn with_messages(mut self, messages: Vec<ChatMessage>) -> Self {
         self.interaction_tail = if messages.is_empty() {
             None
@@ -340,14 +381,42 @@ impl PipelineContext {
         super::controller::ContextController::new(self).render(self)
     }
 
+    /// Token usage per context section for the current render.
+    pub fn composition_report(&self) -> super::controller::ContextCompositionReport {
+        super::controller::ContextController::new(self)
+            .render_with_report(self)
+            .1
+    }
+
     pub fn config(&self) -> &Value {
         &self.config_snapshot
     }
 
-    pub(crate) fn build_system_prompt(&self) -> String {
-        let mut parts = self.system_parts.clone();
-        parts.sort_by_key(|b| std::cmp::Reverse(b.priority));
+    /// Query to seed web searches with: the translated user message when the
+    /// language worker produced one, otherwise the message as typed.
+    pub fn search_query(&self) -> &str {
+        self.language
+            .as_ref()
+            .and_then(|language| language.search_query.as_deref())
+            .unwrap_or(&self.user_input)
+    }
+
+    /// System parts in render order: stable parts by priority (ties broken
+    /// by label), then volatile parts. Keeping this deterministic lets
+    /// llama.cpp reuse the KV cache for the shared prompt prefix.
+    pub(crate) fn ordered_system_parts(&self) -> Vec<&SystemPart> {
+        let mut parts = self.system_parts.iter().collect::<Vec<_>>();
+        parts.sort_by(|a, b| {
+            a.volatile
+                .cmp(&b.volatile)
+                .then_with(|| b.priority.cmp(&a.priority))
+                .then_with(|| a.label.cmp(&b.label))
+        });
         parts
+    }
+
+    pub(crate) fn build_system_prompt(&self) -> String {
+        self.ordered_system_parts()
             .iter()
             .map(|p| p.content.clone())
             .collect::<Vec<_>>()
@@ -391,10 +460,32 @@ impl PipelineContext {
         content: impl Into<String>,
         priority: u8,
     ) {
+        self.push_system_part(label.into(), content.into(), priority, false);
+    }
+
+    /// Like [`add_system_part`](Self::add_system_part), but for content that
+    /// changes every turn and therefore must not break the cached prefix.
+    pub fn add_volatile_system_part(
+        &mut self,
+        label: impl Into<String>,
+        content: impl Into<String>,
+        priority: u8,
+    ) {
+        self.push_system_part(label.into(), content.into(), priority, true);
+    }
+
+    fn push_system_part(&mut self, label: String, content: String, priority: u8, volatile: bool) {
+        let Some((content, priority)) = self.system_layout.shape(&label, content, priority) else {
+            return;
+        };
+        if content.trim().is_empty() {
+            return;
+        }
         self.system_parts.push(SystemPart {
-            label: label.into(),
-            content: content.into(),
+            label,
+            content,
             priority,
+            volatile,
         });
     }
 
@@ -522,4 +613,20 @@ mod tests {
         assert!(high_pos < mid_pos);
         assert!(mid_pos < low_pos);
     }
+
+    #[test]
+    fn test_system_parts_order_is_deterministic() {
+        let mut a = PipelineContext::new("s1", "t1", PipelineMode::Chat, "input");
+        a.add_volatile_system_part("task", "task packet", 250);
+        a.add_system_part("b_part", "B", 100);
+        a.add_system_part("a_part", "A", 100);
+
+        let mut b = PipelineContext::new("s1", "t2", PipelineMode::Chat, "input");
+        b.add_system_part("a_part", "A", 100);
+        b.add_system_part("b_part", "B", 100);
+        b.add_volatile_system_part("task", "task packet", 250);
+
+        assert_eq!(a.build_system_prompt(), "A\n\nB\n\ntask packet");
+        assert_eq!(a.build_system_prompt(), b.build_system_prompt());
+    }
 }
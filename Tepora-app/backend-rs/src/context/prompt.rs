@@ -1,3 +1,6 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Deserialize;
 use serde_json::Value;
 
 pub fn extract_system_prompt(config: &Value) -> Option<String> {
@@ -13,3 +16,317 @@ pub fn extract_system_prompt(config: &Value) -> Option<String> {
         .and_then(|v| v.as_str())
         .map(|s| s.to_string())
 }
+
+/// Building blocks of the system prompt, configured under `system_prompt.blocks`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptBlockKind {
+    Base,
+    Persona,
+    Mode,
+    Tools,
+    MemoryPolicy,
+    Datetime,
+    Custom,
+}
+
+impl PromptBlockKind {
+    pub const NAMES: [&'static str; 7] = [
+        "base",
+        "persona",
+        "mode",
+        "tools",
+        "memory_policy",
+        "datetime",
+        "custom",
+    ];
+
+    /// System part label produced for this block.
+    fn label(self) -> &'static str {
+        match self {
+            PromptBlockKind::Base => "base_system",
+            PromptBlockKind::Persona => "persona_style",
+            PromptBlockKind::Mode => "mode_context",
+            PromptBlockKind::Tools => "available_tools",
+            PromptBlockKind::MemoryPolicy => "memory_policy",
+            PromptBlockKind::Datetime => "datetime",
+            PromptBlockKind::Custom => "custom",
+        }
+    }
+
+    fn default_priority(self) -> u8 {
+        match self {
+            PromptBlockKind::Base => 200,
+            PromptBlockKind::Persona => 190,
+            PromptBlockKind::MemoryPolicy => 170,
+            PromptBlockKind::Custom => 160,
+            PromptBlockKind::Datetime => 155,
+            PromptBlockKind::Mode => 150,
+            PromptBlockKind::Tools => 80,
+        }
+    }
+
+    /// Template used when the block does not define one. `{{content}}` is
+    /// the text the owning worker generated.
+    pub fn default_template(self) -> &'static str {
+        match self {
+            PromptBlockKind::MemoryPolicy => {
+                "Memory cards are background notes about the user. Use them when relevant, do not quote them verbatim, and prefer the user's latest statements when they conflict."
+            }
+            PromptBlockKind::Datetime => "Current date: {{date}} ({{weekday}})",
+            _ => "{{content}}",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PromptBlockSpec {
+    kind: PromptBlockKind,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    #[serde(default)]
+    template: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// A configured block after ordering has been resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptBlock {
+    pub kind: PromptBlockKind,
+    pub label: String,
+    pub priority: u8,
+    pub enabled: bool,
+    pub template: Option<String>,
+}
+
+/// Ordered, templated system prompt layout.
+///
+/// Workers keep adding parts under their usual labels; the layout decides
+/// whether a labelled part is rendered, where it goes, and how its text is
+/// templated. Parts without a matching block pass through unchanged.
+#[derive(Debug, Clone)]
+pub struct SystemPromptLayout {
+    blocks: Vec<PromptBlock>,
+    variables: BTreeMap<String, String>,
+}
+
+const DEFAULT_LAYOUT: [PromptBlockKind; 4] = [
+    PromptBlockKind::Base,
+    PromptBlockKind::Persona,
+    PromptBlockKind::Mode,
+    PromptBlockKind::Tools,
+];
+
+impl Default for SystemPromptLayout {
+    fn default() -> Self {
+        Self::from_specs(
+            DEFAULT_LAYOUT
+                .iter()
+                .map(|kind| PromptBlockSpec {
+                    kind: *kind,
+                    id: None,
+                    enabled: true,
+                    template: None,
+                })
+                .collect(),
+            BTreeMap::new(),
+        )
+    }
+}
+
+impl SystemPromptLayout {
+    pub fn from_config(config: &Value) -> Self {
+        let section = config.get("system_prompt");
+        let variables = section
+            .and_then(|v| v.get("variables"))
+            .and_then(Value::as_object)
+            .map(|vars| {
+                vars.iter()
+                    .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                    .collect::<BTreeMap<_, _>>()
+            })
+            .unwrap_or_default();
+        let specs = section
+            .and_then(|v| v.get("blocks"))
+            .and_then(|v| serde_json::from_value::<Vec<PromptBlockSpec>>(v.clone()).ok());
+
+        match specs {
+            Some(specs) => Self::from_specs(specs, variables),
+            None => Self {
+                variables,
+                ..Self::default()
+            },
+        }
+    }
+
+    /// Blocks keep the priority band of the kinds they replace: the default
+    /// priorities of the listed kinds are redistributed in list order, so
+    /// node-specific parts (agent instructions etc.) stay where they were.
+    fn from_specs(specs: Vec<PromptBlockSpec>, variables: BTreeMap<String, String>) -> Self {
+        let mut priorities = specs
+            .iter()
+            .map(|spec| spec.kind.default_priority())
+            .collect::<Vec<_>>();
+        priorities.sort_unstable_by(|a, b| b.cmp(a));
+        for index in 1..priorities.len() {
+            priorities[index] = priorities[index].min(priorities[index - 1].saturating_sub(1));
+        }
+
+        let mut seen = HashMap::new();
+        let blocks = specs
+            .into_iter()
+            .zip(priorities)
+            .filter_map(|(spec, priority)| {
+                let label = match (spec.kind, spec.id.as_deref()) {
+                    (PromptBlockKind::Custom, Some(id)) if !id.trim().is_empty() => {
+                        format!("custom:{}", id.trim())
+                    }
+                    (kind, _) => kind.label().to_string(),
+                };
+                // First definition wins for duplicated labels.
+                if seen.insert(label.clone(), ()).is_some() {
+                    return None;
+                }
+                Some(PromptBlock {
+                    kind: spec.kind,
+                    label,
+                    priority,
+                    enabled: spec.enabled,
+                    template: spec.template.filter(|t| !t.trim().is_empty()),
+                })
+            })
+            .collect();
+
+        Self { blocks, variables }
+    }
+
+    pub fn blocks(&self) -> &[PromptBlock] {
+        &self.blocks
+    }
+
+    pub fn set_variable(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.variables.insert(key.into(), value.into());
+    }
+
+    /// Resolve a worker's part against the layout. Returns `None` when the
+    /// block is disabled or left out of a configured layout. Blocks without
+    /// an owning worker (memory policy, date/time, custom) are added with
+    /// empty content and rendered from their template here.
+    pub fn shape(&self, label: &str, content: String, priority: u8) -> Option<(String, u8)> {
+        let Some(block) = self.blocks.iter().find(|block| block.label == label) else {
+            if self.is_managed_label(label) {
+                return None;
+            }
+            return Some((content, priority));
+        };
+        if !block.enabled {
+            return None;
+        }
+        let template = block
+            .template
+            .as_deref()
+            .unwrap_or_else(|| block.kind.default_template());
+        Some((self.render(template, &content), block.priority))
+    }
+
+    /// Render a block's template (or its default) with the layout variables.
+    pub fn render(&self, template: &str, content: &str) -> String {
+        render_template(template, |name| match name {
+            "content" => Some(content.to_string()),
+            other => self.variables.get(other).cloned(),
+        })
+    }
+
+    /// Labels of built-in blocks are dropped when the layout omits them.
+    fn is_managed_label(&self, label: &str) -> bool {
+        DEFAULT_LAYOUT.iter().any(|kind| kind.label() == label)
+    }
+}
+
+/// Replace `{{name}}` placeholders. Unknown names are left as-is so a typo
+/// is visible in the rendered prompt instead of silently disappearing.
+fn render_template(template: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let name = after[..end].trim();
+        match lookup(name) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn default_layout_keeps_existing_priorities() {
+        let layout = SystemPromptLayout::from_config(&json!({}));
+        assert_eq!(
+            layout.shape("base_system", "base".to_string(), 200),
+            Some(("base".to_string(), 200))
+        );
+        assert_eq!(
+            layout.shape("available_tools", "tools".to_string(), 80),
+            Some(("tools".to_string(), 80))
+        );
+        assert_eq!(
+            layout.shape("agent_skill", "skill".to_string(), 145),
+            Some(("skill".to_string(), 145))
+        );
+    }
+
+    #[test]
+    fn configured_order_and_templates_are_applied() {
+        let layout = SystemPromptLayout::from_config(&json!({
+            "system_prompt": {
+                "variables": {"user_name": "Aki"},
+                "blocks": [
+                    {"kind": "tools", "template": "Tools for {{user_name}}:\n{{content}}"},
+                    {"kind": "base"},
+                    {"kind": "custom", "id": "house", "template": "Call me {{user_name}}. {{unknown}}"},
+                    {"kind": "mode", "enabled": false}
+                ]
+            }
+        }));
+
+        let (tools, tools_priority) = layout
+            .shape("available_tools", "web_search".to_string(), 80)
+            .unwrap();
+        let (_, base_priority) = layout
+            .shape("base_system", "base".to_string(), 200)
+            .unwrap();
+        assert_eq!(tools, "Tools for Aki:\nweb_search");
+        assert!(tools_priority > base_priority);
+        assert!(layout
+            .shape("mode_context", "mode".to_string(), 150)
+            .is_none());
+        assert!(layout
+            .shape("persona_style", "style".to_string(), 190)
+            .is_none());
+
+        let custom = &layout.blocks()[2];
+        assert_eq!(custom.label, "custom:house");
+        assert_eq!(
+            layout.render(custom.template.as_deref().unwrap(), ""),
+            "Call me Aki. {{unknown}}"
+        );
+    }
+}
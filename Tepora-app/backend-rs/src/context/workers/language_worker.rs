@@ -0,0 +1,412 @@
+//! LanguageWorker — Detects the user's language and aligns replies and searches.
+//!
+//! The language is guessed from the writing system, and for Latin-script text
+//! from common function words. Each persona decides, via
+//! `characters.<id>.language`, which language it answers in (`reply`) and which
+//! language web searches are issued in (`search`). When the search language
+//! differs from the user's, the user message is translated once here so the
+//! search nodes can query sources in that language (e.g. English sources for a
+//! Japanese question).
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::context::pipeline_context::{LanguageContext, PipelineContext};
+use crate::context::worker::{ContextWorker, WorkerError};
+use crate::core::config::personas::active_persona_id;
+use crate::core::errors::ApiError;
+use crate::llm::{ChatMessage, ChatRequest};
+use crate::state::AppState;
+
+/// Languages the worker can name in instructions.
+const LANGUAGE_NAMES: &[(&str, &str)] = &[
+    ("ja", "Japanese"),
+    ("en", "English"),
+    ("zh", "Chinese"),
+    ("ko", "Korean"),
+    ("ru", "Russian"),
+    ("ar", "Arabic"),
+    ("he", "Hebrew"),
+    ("th", "Thai"),
+    ("hi", "Hindi"),
+    ("el", "Greek"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("de", "German"),
+    ("pt", "Portuguese"),
+    ("it", "Italian"),
+];
+
+/// Frequent function words per Latin-script language. Each must be a whole word.
+const LATIN_MARKERS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "is", "are", "what", "how", "of", "to", "in", "with", "for", "you",
+            "can", "does", "this",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "los", "las", "que", "de", "y", "es", "por", "para", "una", "cómo", "qué",
+            "con",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "des", "est", "et", "que", "une", "pour", "avec", "comment", "dans",
+            "je", "vous",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "ein", "eine", "wie", "ich", "mit", "für",
+            "was", "sie",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "os", "as", "que", "de", "e", "é", "para", "com", "uma", "não", "como", "do", "da",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "lo", "gli", "che", "di", "e", "è", "per", "con", "una", "non", "come", "del",
+            "della",
+        ],
+    ),
+];
+
+const TRANSLATION_MAX_TOKENS: i32 = 160;
+
+pub fn language_name(code: &str) -> &str {
+    LANGUAGE_NAMES
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, name)| *name)
+        .unwrap_or(code)
+}
+
+/// Best guess at the language of `text` as an ISO 639-1 code.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut kana = 0usize;
+    let mut han = 0usize;
+    let mut hangul = 0usize;
+    let mut cyrillic = 0usize;
+    let mut arabic = 0usize;
+    let mut hebrew = 0usize;
+    let mut thai = 0usize;
+    let mut devanagari = 0usize;
+    let mut greek = 0usize;
+    let mut latin = 0usize;
+    for ch in text.chars() {
+        match ch as u32 {
+            0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => kana += 1,
+            0x4E00..=0x9FFF | 0x3400..=0x4DBF => han += 1,
+            0xAC00..=0xD7AF | 0x1100..=0x11FF | 0x3130..=0x318F => hangul += 1,
+            0x0400..=0x04FF => cyrillic += 1,
+            0x0600..=0x06FF => arabic += 1,
+            0x0590..=0x05FF => hebrew += 1,
+            0x0E00..=0x0E7F => thai += 1,
+            0x0900..=0x097F => devanagari += 1,
+            0x0370..=0x03FF => greek += 1,
+            _ if ch.is_alphabetic() && (ch.is_ascii() || (ch as u32) < 0x0250) => latin += 1,
+            _ => {}
+        }
+    }
+    // Japanese mixes kana into kanji text; Han without kana is Chinese
+    if kana > 0 && kana + han >= latin {
+        return Some("ja");
+    }
+    let scripts = [
+        (han, "zh"),
+        (hangul, "ko"),
+        (cyrillic, "ru"),
+        (arabic, "ar"),
+        (hebrew, "he"),
+        (thai, "th"),
+        (devanagari, "hi"),
+        (greek, "el"),
+    ];
+    if let Some((count, code)) = scripts.iter().max_by_key(|(count, _)| *count) {
+        if *count > 0 && *count >= latin {
+            return Some(code);
+        }
+    }
+    if latin == 0 {
+        return None;
+    }
+    detect_latin_language(text)
+}
+
+fn detect_latin_language(text: &str) -> Option<&'static str> {
+    let words: Vec<String> = text
+        .split(|ch: char| !ch.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let (code, hits) = LATIN_MARKERS
+        .iter()
+        .map(|(code, markers)| {
+            let hits = words
+                .iter()
+                .filter(|word| markers.contains(&word.as_str()))
+                .count();
+            (*code, hits)
+        })
+        .max_by_key(|(_, hits)| *hits)?;
+    // A single shared word ("de", "e") is not enough evidence
+    (hits >= 2 || (hits == 1 && words.len() <= 3)).then_some(code)
+}
+
+/// How a persona chooses a language.
+#[derive(Debug, Clone, PartialEq)]
+enum LanguageChoice {
+    /// The language the user wrote in
+    Auto,
+    /// Leave it to the model (reply only)
+    Off,
+    Fixed(String),
+}
+
+impl LanguageChoice {
+    fn parse(value: Option<&Value>) -> Self {
+        match value
+            .and_then(Value::as_str)
+            .map(|value| value.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("auto") | Some("user") => Self::Auto,
+            Some("off") | Some("none") => Self::Off,
+            Some(code) => Self::Fixed(code.to_string()),
+        }
+    }
+
+    fn resolve(&self, detected: Option<&str>) -> Option<String> {
+        match self {
+            Self::Auto => detected.map(str::to_string),
+            Self::Off => None,
+            Self::Fixed(code) => Some(code.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct LanguageSettings {
+    reply: LanguageChoice,
+    search: LanguageChoice,
+}
+
+impl LanguageSettings {
+    /// `characters.<active persona>.language`
+    fn from_config(config: &Value) -> Self {
+        let section = config
+            .get("characters")
+            .and_then(|characters| characters.get(active_persona_id(config)))
+            .and_then(|persona| persona.get("language"));
+        Self {
+            reply: LanguageChoice::parse(section.and_then(|section| section.get("reply"))),
+            search: LanguageChoice::parse(section.and_then(|section| section.get("search"))),
+        }
+    }
+}
+
+fn plan_language(config: &Value, user_input: &str) -> LanguageContext {
+    let settings = LanguageSettings::from_config(config);
+    let detected = detect_language(user_input).map(str::to_string);
+    let reply = settings.reply.resolve(detected.as_deref());
+    let search = settings.search.resolve(detected.as_deref());
+    LanguageContext {
+        detected,
+        reply,
+        search,
+        search_query: None,
+    }
+}
+
+/// Worker that picks reply and search languages for the turn.
+pub struct LanguageWorker {
+    /// Whether web search has been explicitly disabled for this turn.
+    skip_web_search: bool,
+}
+
+impl LanguageWorker {
+    pub fn new(skip_web_search: bool) -> Self {
+        Self { skip_web_search }
+    }
+}
+
+impl Default for LanguageWorker {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+#[async_trait]
+impl ContextWorker for LanguageWorker {
+    fn name(&self) -> &str {
+        "language"
+    }
+
+    async fn execute(
+        &self,
+        ctx: &mut PipelineContext,
+        state: &Arc<AppState>,
+    ) -> Result<(), WorkerError> {
+        let mut language = plan_language(ctx.config(), &ctx.user_input);
+        if language.reply.is_none() && language.search.is_none() {
+            return Err(WorkerError::skipped(
+                "language",
+                "language could not be detected",
+            ));
+        }
+
+        if let Some(reply) = language.reply.as_deref() {
+            ctx.add_system_part(
+                "language_reply",
+                format!(
+                    "Reply in {} unless the user explicitly asks for another language.",
+                    language_name(reply)
+                ),
+                185,
+            );
+        }
+
+        let allow_search = ctx
+            .config()
+            .get("privacy")
+            .and_then(|v| v.get("allow_web_search"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let translate_search = ctx.mode.has_web_search()
+            && allow_search
+            && !self.skip_web_search
+            && language.search.is_some()
+            && language.search != language.detected;
+        if translate_search {
+            let search = language.search.clone().unwrap_or_default();
+            ctx.add_system_part(
+                "language_search",
+                format!(
+                    "Write web search queries in {} to reach the best sources.",
+                    language_name(&search)
+                ),
+                120,
+            );
+            if !ctx.dry_run {
+                match translate_query(state, ctx.config(), &ctx.user_input, &search).await {
+                    Ok(query) if !query.trim().is_empty() => {
+                        language.search_query = Some(query.trim().to_string());
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        tracing::warn!("LanguageWorker: query translation failed: {}", err);
+                    }
+                }
+            }
+        }
+
+        ctx.language = Some(language);
+        Ok(())
+    }
+}
+
+async fn translate_query(
+    state: &Arc<AppState>,
+    config: &Value,
+    text: &str,
+    target: &str,
+) -> Result<String, ApiError> {
+    let active_character = config
+        .get("active_character")
+        .or_else(|| config.get("active_agent_profile"))
+        .and_then(Value::as_str);
+    let models = &state.ai().models;
+    let model_id = models
+        .resolve_character_model_id(active_character)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "default".to_string());
+    let messages = vec![
+        ChatMessage::new_text(
+            "system",
+            format!(
+                "Rewrite the user's message as a concise web search query in {}. \
+                 Output only the query.",
+                language_name(target)
+            ),
+        ),
+        ChatMessage::new_text("user", text),
+    ];
+    let mut request = ChatRequest::new(messages).without_continuation();
+    request.max_tokens = Some(TRANSLATION_MAX_TOKENS);
+    request.temperature = Some(0.0);
+    let query = state.ai().llm.chat(request, &model_id).await?;
+    Ok(query
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim_matches('"')
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn languages_are_detected_from_script_and_function_words() {
+        assert_eq!(detect_language("東京の天気はどうですか？"), Some("ja"));
+        assert_eq!(detect_language("Rustの所有権について教えて"), Some("ja"));
+        assert_eq!(detect_language("今天天气怎么样"), Some("zh"));
+        assert_eq!(detect_language("오늘 날씨 어때요"), Some("ko"));
+        assert_eq!(detect_language("Какая сегодня погода?"), Some("ru"));
+        assert_eq!(
+            detect_language("What is the weather like in Tokyo today?"),
+            Some("en")
+        );
+        assert_eq!(
+            detect_language("¿Cómo está el tiempo en Madrid para el fin de semana?"),
+            Some("es")
+        );
+        assert_eq!(
+            detect_language("Comment est la météo dans les Alpes?"),
+            Some("fr")
+        );
+        assert_eq!(detect_language("12345 !!"), None);
+        assert_eq!(detect_language("Tokyo Kyoto Osaka Nagoya"), None);
+    }
+
+    #[test]
+    fn persona_settings_choose_reply_and_search_languages() {
+        let config = json!({
+            "active_character": "guide",
+            "characters": {
+                "guide": {"language": {"reply": "auto", "search": "en"}},
+                "quiet": {"language": {"reply": "off"}}
+            }
+        });
+        let plan = plan_language(&config, "京都のおすすめの寺を教えて");
+        assert_eq!(plan.detected.as_deref(), Some("ja"));
+        assert_eq!(plan.reply.as_deref(), Some("ja"));
+        assert_eq!(plan.search.as_deref(), Some("en"));
+
+        let mut quiet = config.clone();
+        quiet["active_character"] = json!("quiet");
+        let plan = plan_language(&quiet, "京都のおすすめの寺を教えて");
+        assert_eq!(plan.reply, None);
+        assert_eq!(plan.search.as_deref(), Some("ja"));
+
+        let plan = plan_language(&json!({}), "What should I see in Kyoto?");
+        assert_eq!(plan.reply.as_deref(), Some("en"));
+        assert_eq!(plan.search.as_deref(), Some("en"));
+    }
+}
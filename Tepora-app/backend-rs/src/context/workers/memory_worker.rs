@@ -6,12 +6,15 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use serde_json::Value;
 
+use crate::agent::execution::resolve_agent_memory_policy;
 use crate::context::pipeline_context::{
     InteractionTail, LocalContext, MemoryChunk, PipelineContext,
 };
 use crate::context::worker::{ContextWorker, WorkerError};
+use crate::core::config::schema::TeporaConfig;
 use crate::history::HistoryMessage;
 use crate::llm::ChatMessage;
+use crate::memory::extract_time_range;
 use crate::state::AppState;
 
 pub struct MemoryWorker {
@@ -53,9 +56,18 @@ impl ContextWorker for MemoryWorker {
 
         ctx.interaction_tail = extract_interaction_tail(&history_messages);
 
-        if state.memory().memory_service.enabled() && !ctx.user_input.trim().is_empty() {
+        let memory_policy = resolve_agent_memory_policy(state, ctx.agent_id.as_deref());
+        // 想起は埋め込みモデルを呼び、想起した記憶の強度も更新するのでプレビューでは行わない
+        if !ctx.dry_run
+            && memory_policy.retrieves()
+            && state.memory().memory_service.enabled()
+            && !ctx.user_input.trim().is_empty()
+        {
             if let Some(embedding_model_id) = resolve_embedding_model_id(state) {
                 let legacy_enabled = state.is_redesign_enabled("legacy_memory");
+                // 「先週の火曜日に話したこと」のような質問は作成日時でも絞り込む
+                let time_range =
+                    extract_time_range(&ctx.user_input, chrono::Local::now().fixed_offset());
 
                 match state
                     .memory()
@@ -68,6 +80,8 @@ impl ContextWorker for MemoryWorker {
                         legacy_enabled,
                         ctx.mode,
                         ctx.stage,
+                        time_range.as_ref(),
+                        memory_policy.namespace_character_id().as_deref(),
                     )
                     .await
                 {
@@ -85,6 +99,7 @@ impl ContextWorker for MemoryWorker {
                                 character_id: memory.character_id,
                             })
                             .collect();
+                        memory_policy.apply(&ctx.session_id, &mut ctx.memory_chunks);
                     }
                     Err(err) => {
                         tracing::warn!("MemoryWorker: failed to retrieve EM memory: {}", err);
@@ -320,10 +335,10 @@ fn extract_entities(input: &str, limit: usize) -> Vec<String> {
 }
 
 fn configured_history_limit(config: &Value, fallback: i64) -> i64 {
-    config
-        .get("app")
-        .and_then(|v| v.get("history_limit"))
-        .and_then(|v| v.as_i64())
+    TeporaConfig::from_value_or_default(config)
+        .app
+        .history_limit
+        .and_then(|limit| i64::try_from(limit).ok())
         .unwrap_or(fallback)
 }
 
@@ -508,7 +523,7 @@ mod tests {
         ContextConfig, KnowledgeChunk, KnowledgeHit, KnowledgePort, KnowledgeSource,
     };
     use crate::infrastructure::episodic_store::{MemoryAdapter, MemoryScope};
-    use crate::memory::RetrievedMemory;
+    use crate::memory::{RetrievedMemory, TimeRange};
     use crate::models::types::{ModelEntry, ModelRegistry};
     use crate::test_support::ENV_LOCK;
     use std::sync::atomic::{AtomicBool, Ordering};
@@ -543,6 +558,7 @@ mod tests {
             _text_model_id: &str,
             _embedding_model_id: &str,
             _legacy_enabled: bool,
+            _namespace: Option<&str>,
         ) -> Result<(), ApiError> {
             Ok(())
         }
@@ -556,6 +572,8 @@ mod tests {
             legacy_enabled: bool,
             _mode: PipelineMode,
             _stage: PipelineStage,
+            _time_range: Option<&TimeRange>,
+            _namespace: Option<&str>,
         ) -> Result<Vec<RetrievedMemory>, ApiError> {
             self.called.store(true, Ordering::SeqCst);
             self.last_legacy_flag
@@ -736,25 +754,35 @@ mod tests {
                 new_paths_arc.clone(),
                 config.clone(),
             )),
+            notifications: crate::core::notifications::NotificationHub::new(config.clone()),
+            health: crate::core::health::HealthMonitor::new(),
+            utilization: crate::core::utilization::UtilizationMonitor::new(),
+            integrity: crate::core::snapshots::IntegrityReport::default(),
         });
         let ai = Arc::new(crate::state::AppAiState {
             llama: llama.clone(),
             llm: llm.clone(),
             models: models.clone(),
             skill_registry: skill_registry.clone(),
+            exclusive_agents: crate::agent::exclusive::ExclusiveAgentManager::new(),
         });
         let integration = Arc::new(crate::state::AppIntegrationState {
             mcp: mcp.clone(),
             mcp_registry: mcp_registry.clone(),
+            plugins: crate::plugins::PluginManager::new(&new_paths_arc, config.clone()),
+            scripts: crate::scripting::ScriptManager::new(&new_paths_arc, config.clone()),
+            desktop: crate::core::desktop_bridge::DesktopBridge::new(),
         });
+        let project_history =
+            crate::workspace::ProjectHistoryStore::new(history.clone(), current_project_id.clone());
         let runtime = Arc::new(crate::state::AppRuntimeState {
-            history: crate::workspace::ProjectHistoryStore::new(
-                history.clone(),
-                current_project_id.clone(),
-            ),
+            history: project_history.clone(),
             graph_runtime: graph_runtime.clone(),
             rate_limiters: rate_limiters.clone(),
             actor_manager: actor_manager.clone(),
+            inbox: crate::core::inbox::Inbox::new(project_history),
+            chat_queue: crate::core::chat_queue::ChatQueue::new(),
+            plan_edits: crate::agent::plan::PlanEdits::new(),
         });
         let memory = Arc::new(crate::state::AppMemoryState {
             memory_service: memory_service.clone(),
@@ -767,6 +795,13 @@ mod tests {
             knowledge_use_case: Arc::new(KnowledgeUseCase::new(
                 adapter.clone() as Arc<dyn KnowledgePort>
             )),
+            session_index: Arc::new(
+                crate::rag::SessionIndex::with_path(
+                    new_paths_arc.user_data_dir.join("session_index.db"),
+                )
+                .await
+                .unwrap(),
+            ),
         });
 
         let workspace = Arc::new(crate::state::AppWorkspaceState {
@@ -815,6 +850,7 @@ mod tests {
                     format: Some("gguf".to_string()),
                     tokenizer_path: None,
                     tokenizer_format: None,
+                    pricing: None,
                 }],
                 role_assignments: std::iter::once(("embedding".to_string(), "embed-1".to_string()))
                     .collect(),
@@ -926,5 +962,24 @@ mod tests {
             assert!(adapter.called.load(Ordering::SeqCst));
             assert!(!adapter.last_legacy_flag.load(Ordering::SeqCst));
         }
+
+        {
+            adapter.called.store(false, Ordering::SeqCst);
+            let state_arc = Arc::new((*base_state).clone());
+            let mut preview = PipelineContext::new(
+                "test_session",
+                "test_turn",
+                crate::context::pipeline_context::PipelineMode::Chat,
+                "Hello query",
+            )
+            .with_dry_run(true);
+
+            MemoryWorker::new(10)
+                .execute(&mut preview, &state_arc)
+                .await
+                .unwrap();
+
+            assert!(!adapter.called.load(Ordering::SeqCst));
+        }
     }
 }
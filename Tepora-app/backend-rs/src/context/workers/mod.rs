@@ -1,8 +1,14 @@
 //! Worker modules for context enrichment.
 
 pub mod character_worker;
+pub mod language_worker;
 pub mod memory_worker;
+pub mod persona_worker;
+pub mod plugin_worker;
+pub mod project_worker;
 pub mod rag_worker;
+pub mod script_worker;
 pub mod search_worker;
+pub mod summary_worker;
 pub mod system_worker;
 pub mod tool_worker;
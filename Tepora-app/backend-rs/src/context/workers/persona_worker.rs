@@ -0,0 +1,125 @@
+//! PersonaWorker — Applies the active persona's style rules.
+//!
+//! The active persona is `active_character` unless the session pinned a
+//! different one via the `switch_persona` WS command, or the session defaults
+//! name one (apply the project first so its defaults count). The override is
+//! applied to the config snapshot so every worker and node sees the same
+//! persona for the turn.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::context::pipeline_context::PipelineContext;
+use crate::context::worker::{ContextWorker, WorkerError};
+use crate::context::workers::project_worker::session_defaults;
+use crate::core::config::personas::{active_persona_id, apply_persona_override};
+use crate::state::AppState;
+
+/// Apply the session's pinned persona (if any and still present) to `config`
+/// and return the persona id that is active for the session.
+pub async fn apply_session_persona(
+    state: &AppState,
+    session_id: &str,
+    config: &mut Value,
+) -> String {
+    if !session_id.is_empty() {
+        match state
+            .runtime()
+            .history
+            .get_session_persona(session_id)
+            .await
+        {
+            Ok(Some(persona_id)) => {
+                if !apply_persona_override(config, &persona_id) {
+                    tracing::debug!(
+                        "Session {} pins unknown persona '{}'; using active_character",
+                        session_id,
+                        persona_id
+                    );
+                }
+            }
+            Ok(None) => {
+                if let Some(persona_id) = session_defaults(config).persona_id {
+                    if !apply_persona_override(config, &persona_id) {
+                        tracing::debug!(
+                            "Default persona '{}' is unknown; using active_character",
+                            persona_id
+                        );
+                    }
+                }
+            }
+            Err(err) => {
+                tracing::warn!("Failed to load session persona for {}: {}", session_id, err);
+            }
+        }
+    }
+    active_persona_id(config).to_string()
+}
+
+fn style_rules_prompt(config: &Value) -> Option<String> {
+    let rules = config
+        .get("characters")
+        .and_then(|characters| characters.get(active_persona_id(config)))
+        .and_then(|persona| persona.get("style_rules"))
+        .and_then(Value::as_array)?
+        .iter()
+        .filter_map(Value::as_str)
+        .map(str::trim)
+        .filter(|rule| !rule.is_empty())
+        .map(|rule| format!("- {rule}"))
+        .collect::<Vec<_>>();
+    if rules.is_empty() {
+        return None;
+    }
+    Some(format!("Style rules:\n{}", rules.join("\n")))
+}
+
+pub struct PersonaWorker;
+
+#[async_trait]
+impl ContextWorker for PersonaWorker {
+    fn name(&self) -> &str {
+        "persona"
+    }
+
+    async fn execute(
+        &self,
+        ctx: &mut PipelineContext,
+        _state: &Arc<AppState>,
+    ) -> Result<(), WorkerError> {
+        let Some(prompt) = style_rules_prompt(ctx.config()) else {
+            return Err(WorkerError::skipped(
+                "persona",
+                "active persona has no style rules",
+            ));
+        };
+        ctx.add_system_part("persona_style", prompt, 190);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn style_rules_follow_the_active_persona() {
+        let mut config = json!({
+            "active_character": "a",
+            "characters": {
+                "a": {"style_rules": []},
+                "b": {"style_rules": ["Use short sentences.", " ", "End with a question."]}
+            }
+        });
+        assert!(style_rules_prompt(&config).is_none());
+
+        assert!(apply_persona_override(&mut config, "b"));
+        assert_eq!(
+            style_rules_prompt(&config).as_deref(),
+            Some("Style rules:\n- Use short sentences.\n- End with a question.")
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! PluginWorker - Runs context workers registered by WASM plugins.
+//!
+//! Each plugin worker receives the turn's session, mode and user input and may
+//! return a block of text for the system prompt. The text may change every
+//! turn, so it is added as a volatile part. A failing plugin is logged and
+//! skipped; it never fails the turn.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::context::pipeline_context::PipelineContext;
+use crate::context::worker::{ContextWorker, WorkerError};
+use crate::state::AppState;
+
+pub struct PluginWorker;
+
+#[async_trait]
+impl ContextWorker for PluginWorker {
+    fn name(&self) -> &str {
+        "plugin"
+    }
+
+    async fn execute(
+        &self,
+        ctx: &mut PipelineContext,
+        state: &Arc<AppState>,
+    ) -> Result<(), WorkerError> {
+        // プラグインが何をするかはホストから分からないので、プレビューでは呼ばない
+        if ctx.dry_run {
+            return Err(WorkerError::skipped(
+                "plugin",
+                "dry run does not run plugins",
+            ));
+        }
+        let plugins = &state.integration().plugins;
+        let workers = plugins.context_workers();
+        if workers.is_empty() {
+            return Err(WorkerError::skipped("plugin", "no plugin context workers"));
+        }
+
+        let input = json!({
+            "session_id": ctx.session_id,
+            "mode": ctx.mode,
+            "user_input": ctx.user_input,
+        });
+        for (plugin_id, worker) in workers {
+            match plugins
+                .run_context_worker(&plugin_id, &worker.name, input.clone())
+                .await
+            {
+                Ok(Some(text)) => ctx.add_volatile_system_part(
+                    format!("plugin:{}:{}", plugin_id, worker.name),
+                    text,
+                    worker.priority,
+                ),
+                Ok(None) => {}
+                Err(err) => tracing::warn!(
+                    plugin = %plugin_id,
+                    worker = %worker.name,
+                    "Plugin context worker failed: {}",
+                    err
+                ),
+            }
+        }
+        Ok(())
+    }
+}
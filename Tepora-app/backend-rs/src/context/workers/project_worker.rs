@@ -0,0 +1,283 @@
+//! ProjectWorker — Injects the session's project context.
+//!
+//! Each session belongs to a project. The project's settings are applied to
+//! the config snapshot for the turn (under `project`) so the chat node, the
+//! file tools and `rag_search` all see the same binding: file tools are
+//! narrowed to the project's folders, `rag_search` may query the project's
+//! collections, and the default model replaces the character model.
+//!
+//! Session defaults (mode, persona, RAG collections, temperature) are resolved
+//! here as well: the global `session_defaults`, then the project's overrides,
+//! then whatever the message itself asked for. The result for the turn is
+//! written under `session`.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::context::pipeline_context::PipelineContext;
+use crate::context::worker::{ContextWorker, WorkerError};
+use crate::core::config::schema::SessionDefaults;
+use crate::history::ProjectSettings;
+use crate::state::AppState;
+use crate::tools::filesystem::workspace_roots;
+
+/// Load the session's project settings and apply them to `config`.
+pub async fn apply_session_project(
+    state: &AppState,
+    session_id: &str,
+    config: &mut Value,
+) -> Option<ProjectSettings> {
+    if session_id.is_empty() {
+        return None;
+    }
+    let history = &state.runtime().history;
+    let settings = match history.get_session_project_id(session_id).await {
+        Ok(Some(project_id)) => history.get_project_settings(&project_id).await,
+        Ok(None) => return None,
+        Err(err) => Err(err),
+    };
+    match settings {
+        Ok(settings) => {
+            apply_project_settings(config, &settings);
+            Some(settings)
+        }
+        Err(err) => {
+            tracing::warn!(
+                "Failed to load project settings for {}: {}",
+                session_id,
+                err
+            );
+            None
+        }
+    }
+}
+
+/// Apply `settings` to this config snapshot only.
+pub fn apply_project_settings(config: &mut Value, settings: &ProjectSettings) {
+    if !settings.workspace_folders.is_empty() {
+        let narrowed = narrowed_workspace_roots(config, &settings.workspace_folders);
+        if let Some(tools) = config.get_mut("tools").and_then(Value::as_object_mut) {
+            tools.insert("workspace_roots".to_string(), Value::Array(narrowed));
+        }
+    }
+    if let Some(root) = config.as_object_mut() {
+        root.insert(
+            "project".to_string(),
+            json!({
+                "id": settings.project_id,
+                "pinned_context": settings.pinned_context,
+                "rag_collections": settings.rag_collections,
+                "default_model_id": settings.default_model_id,
+                "session_defaults": settings.session_defaults,
+            }),
+        );
+    }
+}
+
+/// Session defaults of this snapshot: global `session_defaults` with the
+/// project's overrides applied (when `apply_project_settings` ran).
+pub fn session_defaults(config: &Value) -> SessionDefaults {
+    let parse = |value: Option<&Value>| -> SessionDefaults {
+        value
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default()
+    };
+    let global = parse(config.get("session_defaults"));
+    let project = parse(
+        config
+            .get("project")
+            .and_then(|project| project.get("session_defaults")),
+    );
+    global.overlay(&project)
+}
+
+/// Session defaults for `session_id`, including its project's overrides.
+pub async fn session_defaults_for(state: &AppState, session_id: &str) -> SessionDefaults {
+    let mut config = state.core().config.load_config().unwrap_or_default();
+    apply_session_project(state, session_id, &mut config).await;
+    session_defaults(&config)
+}
+
+/// Write the settings in effect for this turn under `session`: the session
+/// defaults with the message's `overrides` on top.
+pub fn apply_session_settings(config: &mut Value, overrides: &SessionDefaults) -> SessionDefaults {
+    let settings = session_defaults(config).overlay(overrides);
+    if let Some(root) = config.as_object_mut() {
+        root.insert("session".to_string(), json!(settings));
+    }
+    settings
+}
+
+/// Project folders that sit inside a configured root keep that root's
+/// `read_only` flag; folders outside every root are dropped.
+fn narrowed_workspace_roots(config: &Value, folders: &[String]) -> Vec<Value> {
+    let roots = workspace_roots(config);
+    folders
+        .iter()
+        .filter_map(|folder| {
+            let path = std::fs::canonicalize(Path::new(folder.trim())).ok()?;
+            let root = roots.iter().find(|root| path.starts_with(&root.path))?;
+            Some(json!({
+                "path": path.to_string_lossy(),
+                "read_only": root.read_only,
+            }))
+        })
+        .collect()
+}
+
+/// Whether `folder` is inside one of `tools.workspace_roots`.
+pub fn is_within_workspace_roots(config: &Value, folder: &str) -> bool {
+    !narrowed_workspace_roots(config, &[folder.to_string()]).is_empty()
+}
+
+/// Collections `rag_search` may query: the project's, plus those selected
+/// for the session by `apply_session_settings`.
+pub fn project_rag_collections(config: &Value) -> Vec<&str> {
+    fn list(value: Option<&Value>) -> Vec<&str> {
+        value
+            .and_then(|value| value.get("rag_collections"))
+            .and_then(Value::as_array)
+            .map(|items| items.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default()
+    }
+    let mut collections = list(config.get("project"));
+    for collection in list(config.get("session")) {
+        if !collections.contains(&collection) {
+            collections.push(collection);
+        }
+    }
+    collections
+}
+
+pub fn project_default_model_id(config: &Value) -> Option<&str> {
+    config
+        .get("project")
+        .and_then(|project| project.get("default_model_id"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|model_id| !model_id.is_empty())
+}
+
+fn pinned_context_prompt(config: &Value) -> Option<String> {
+    let pinned = config
+        .get("project")
+        .and_then(|project| project.get("pinned_context"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|text| !text.is_empty())?;
+    Some(format!("Project context:\n{pinned}"))
+}
+
+pub struct ProjectWorker;
+
+#[async_trait]
+impl ContextWorker for ProjectWorker {
+    fn name(&self) -> &str {
+        "project"
+    }
+
+    async fn execute(
+        &self,
+        ctx: &mut PipelineContext,
+        _state: &Arc<AppState>,
+    ) -> Result<(), WorkerError> {
+        let Some(prompt) = pinned_context_prompt(ctx.config()) else {
+            return Err(WorkerError::skipped(
+                "project",
+                "project has no pinned context",
+            ));
+        };
+        ctx.add_system_part("project_context", prompt, 180);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_settings_narrow_roots_and_expose_collections() {
+        let root = tempfile::tempdir().unwrap();
+        let inside = root.path().join("docs");
+        std::fs::create_dir_all(&inside).unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let mut config = json!({
+            "tools": {
+                "workspace_roots": [{ "path": root.path().to_string_lossy(), "read_only": true }]
+            }
+        });
+
+        apply_project_settings(
+            &mut config,
+            &ProjectSettings {
+                project_id: "p1".to_string(),
+                pinned_context: " Ship by Friday. ".to_string(),
+                rag_collections: vec!["specs".to_string()],
+                workspace_folders: vec![
+                    inside.to_string_lossy().to_string(),
+                    outside.path().to_string_lossy().to_string(),
+                ],
+                default_model_id: Some("qwen".to_string()),
+                ..ProjectSettings::default()
+            },
+        );
+
+        let roots = workspace_roots(&config);
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].path, std::fs::canonicalize(&inside).unwrap());
+        assert!(roots[0].read_only);
+        assert_eq!(project_rag_collections(&config), vec!["specs"]);
+        assert_eq!(project_default_model_id(&config), Some("qwen"));
+        assert_eq!(
+            pinned_context_prompt(&config).as_deref(),
+            Some("Project context:\nShip by Friday.")
+        );
+    }
+
+    #[test]
+    fn session_settings_layer_global_project_and_message() {
+        let mut config = json!({
+            "session_defaults": {
+                "mode": "search",
+                "persona_id": "bunny",
+                "rag_collections": ["notes"],
+                "temperature": 0.7
+            }
+        });
+        assert_eq!(session_defaults(&json!({})).mode(), "chat");
+        assert_eq!(session_defaults(&config).mode(), "search");
+
+        apply_project_settings(
+            &mut config,
+            &ProjectSettings {
+                project_id: "p1".to_string(),
+                rag_collections: vec!["specs".to_string()],
+                session_defaults: SessionDefaults {
+                    mode: Some("agent".to_string()),
+                    temperature: Some(0.2),
+                    ..SessionDefaults::default()
+                },
+                ..ProjectSettings::default()
+            },
+        );
+        let defaults = session_defaults(&config);
+        assert_eq!(defaults.mode(), "agent");
+        assert_eq!(defaults.persona_id.as_deref(), Some("bunny"));
+        assert_eq!(defaults.temperature, Some(0.2));
+
+        let applied = apply_session_settings(
+            &mut config,
+            &SessionDefaults {
+                temperature: Some(1.1),
+                ..SessionDefaults::default()
+            },
+        );
+        assert_eq!(applied.temperature, Some(1.1));
+        assert_eq!(config["session"]["temperature"], json!(1.1));
+        assert_eq!(project_rag_collections(&config), vec!["specs", "notes"]);
+    }
+}
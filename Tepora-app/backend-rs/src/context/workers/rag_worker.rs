@@ -44,6 +44,12 @@ impl ContextWorker for RagWorker {
         if !ctx.mode.has_rag() {
             return Err(WorkerError::skipped("rag", "mode does not use RAG"));
         }
+        if ctx.dry_run {
+            return Err(WorkerError::skipped(
+                "rag",
+                "dry run does not call the embedding model",
+            ));
+        }
 
         let query = ctx.user_input.trim();
         if query.is_empty() {
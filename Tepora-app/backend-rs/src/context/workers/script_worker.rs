@@ -0,0 +1,59 @@
+//! ScriptWorker - Runs the `before_prompt_build` hook of enabled Rhai scripts.
+//!
+//! Text a script passes to `add_context` becomes a volatile system part
+//! labelled with the script id. Hook failures are logged by the script
+//! manager and never fail the turn.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::context::pipeline_context::PipelineContext;
+use crate::context::worker::{ContextWorker, WorkerError};
+use crate::state::AppState;
+
+const SCRIPT_CONTEXT_PRIORITY: u8 = 40;
+
+pub struct ScriptWorker;
+
+#[async_trait]
+impl ContextWorker for ScriptWorker {
+    fn name(&self) -> &str {
+        "script"
+    }
+
+    async fn execute(
+        &self,
+        ctx: &mut PipelineContext,
+        state: &Arc<AppState>,
+    ) -> Result<(), WorkerError> {
+        if ctx.dry_run {
+            return Err(WorkerError::skipped(
+                "script",
+                "dry run does not run scripts",
+            ));
+        }
+        let input = json!({
+            "session_id": ctx.session_id,
+            "mode": ctx.mode,
+            "user_input": ctx.user_input,
+        });
+        let parts = state
+            .integration()
+            .scripts
+            .before_prompt_build(state, &ctx.session_id, input)
+            .await;
+        if parts.is_empty() {
+            return Err(WorkerError::skipped("script", "no script context"));
+        }
+        for (script_id, text) in parts {
+            ctx.add_volatile_system_part(
+                format!("script:{}", script_id),
+                text,
+                SCRIPT_CONTEXT_PRIORITY,
+            );
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
 //! SearchWorker — Performs web search and injects results into the pipeline.
 //!
 //! Wraps the existing `tools::search` module and `tools::reranker` into a
-//! `ContextWorker`.  Shared setting: WebSearch on/off applies to both
+//! `ContextWorker`, then lifts domains that past answers actually cited
+//! (`search::source_quality`).  Shared setting: WebSearch on/off applies to both
 //! SearchMode and AgentMode.
 
 use std::sync::Arc;
@@ -10,6 +11,7 @@ use async_trait::async_trait;
 
 use crate::context::pipeline_context::PipelineContext;
 use crate::context::worker::{ContextWorker, WorkerError};
+use crate::search::source_quality::rank_with_learned_quality;
 use crate::state::AppState;
 use crate::tools::reranker::rerank_search_results_with_embeddings;
 use crate::tools::search;
@@ -47,6 +49,12 @@ impl ContextWorker for SearchWorker {
         if !ctx.mode.has_web_search() {
             return Err(WorkerError::skipped("search", "mode does not use search"));
         }
+        if ctx.dry_run {
+            return Err(WorkerError::skipped(
+                "search",
+                "dry run does not search the web",
+            ));
+        }
 
         let config = ctx.config();
 
@@ -64,13 +72,13 @@ impl ContextWorker for SearchWorker {
             ));
         }
 
-        // Perform the search
-        match search::perform_search(config, &ctx.user_input).await {
+        // Perform the search, in the persona's search language when translated
+        let query = ctx.search_query();
+        match search::perform_search(config, query).await {
             Ok(results) => {
                 let reranked =
-                    rerank_search_results_with_embeddings(state, config, &ctx.user_input, results)
-                        .await;
-                ctx.search_results = reranked;
+                    rerank_search_results_with_embeddings(state, config, query, results).await;
+                ctx.search_results = rank_with_learned_quality(state, config, reranked).await;
             }
             Err(err) => {
                 tracing::error!("SearchWorker: search failed: {}", err);
@@ -0,0 +1,258 @@
+//! SummaryWorker - Maintains a rolling "conversation so far" summary.
+//!
+//! Turns that fall outside the recent history window are folded into a
+//! per-session summary by the professional model instead of being silently
+//! dropped. The summary is persisted and only extended when enough new turns
+//! have overflowed.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::context::pipeline_context::PipelineContext;
+use crate::context::worker::{ContextWorker, WorkerError};
+use crate::core::errors::ApiError;
+use crate::history::HistoryMessage;
+use crate::llm::{ChatMessage, ChatRequest};
+use crate::state::AppState;
+
+const SUMMARY_SYSTEM_PROMPT: &str = "You maintain a running summary of a conversation between a user and an assistant. Merge the previous summary with the new turns into one concise summary. Keep facts, decisions, user preferences, open tasks, and names. Drop greetings and filler. Write in the language the conversation uses. Output only the summary text.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SummarySettings {
+    enabled: bool,
+    keep_recent: i64,
+    batch: usize,
+    max_tokens: i32,
+}
+
+impl SummarySettings {
+    fn from_config(config: &Value) -> Self {
+        let app = config.get("app");
+        let read_u64 = |key: &str| app.and_then(|v| v.get(key)).and_then(Value::as_u64);
+        Self {
+            enabled: app
+                .and_then(|v| v.get("conversation_summary_enabled"))
+                .and_then(Value::as_bool)
+                .unwrap_or(true),
+            keep_recent: read_u64("conversation_summary_keep_recent").unwrap_or(12) as i64,
+            batch: read_u64("conversation_summary_batch").unwrap_or(8) as usize,
+            max_tokens: read_u64("conversation_summary_max_tokens").unwrap_or(384) as i32,
+        }
+    }
+}
+
+pub struct SummaryWorker;
+
+#[async_trait]
+impl ContextWorker for SummaryWorker {
+    fn name(&self) -> &str {
+        "summary"
+    }
+
+    async fn execute(
+        &self,
+        ctx: &mut PipelineContext,
+        state: &Arc<AppState>,
+    ) -> Result<(), WorkerError> {
+        let settings = SummarySettings::from_config(ctx.config());
+        let history = &state.runtime().history;
+
+        let stored = history
+            .get_session_summary(&ctx.session_id)
+            .await
+            .map_err(|e| {
+                WorkerError::retryable("summary", format!("Failed to load summary: {e}"))
+            })?;
+        let mut summary = stored.as_ref().map(|s| s.summary.clone());
+
+        if settings.enabled {
+            let recent = history
+                .get_history(&ctx.session_id, settings.keep_recent.max(1))
+                .await
+                .map_err(|e| {
+                    WorkerError::retryable("summary", format!("Failed to load history: {e}"))
+                })?;
+
+            if let Some(boundary_id) = recent.first().map(|message| message.id) {
+                let covered_id = stored.as_ref().map(|s| s.covered_message_id).unwrap_or(0);
+                let overflowed = history
+                    .get_messages_between(&ctx.session_id, covered_id, boundary_id)
+                    .await
+                    .map_err(|e| {
+                        WorkerError::retryable("summary", format!("Failed to load history: {e}"))
+                    })?;
+
+                if !ctx.dry_run && overflowed.len() >= settings.batch.max(1) {
+                    match summarize(
+                        state,
+                        ctx.config(),
+                        summary.as_deref(),
+                        &overflowed,
+                        settings,
+                    )
+                    .await
+                    {
+                        Ok(updated) if !updated.trim().is_empty() => {
+                            let last_id = overflowed.last().map(|m| m.id).unwrap_or(covered_id);
+                            if let Err(err) = history
+                                .save_session_summary(&ctx.session_id, updated.trim(), last_id)
+                                .await
+                            {
+                                tracing::warn!("SummaryWorker: failed to persist summary: {}", err);
+                            }
+                            summary = Some(updated.trim().to_string());
+                        }
+                        Ok(_) => {}
+                        Err(err) => {
+                            tracing::warn!("SummaryWorker: summarization failed: {}", err);
+                        }
+                    }
+                }
+            }
+        }
+
+        ctx.conversation_summary = summary.filter(|text| !text.trim().is_empty());
+        Ok(())
+    }
+}
+
+async fn summarize(
+    state: &Arc<AppState>,
+    config: &Value,
+    previous: Option<&str>,
+    overflowed: &[HistoryMessage],
+    settings: SummarySettings,
+) -> Result<String, ApiError> {
+    let model_id = resolve_summary_model_id(state, config);
+    let mut request =
+        ChatRequest::new(build_summary_messages(previous, overflowed)).without_continuation();
+    request.max_tokens = Some(settings.max_tokens);
+    request.temperature = Some(0.2);
+    state.ai().llm.chat(request, &model_id).await
+}
+
+fn resolve_summary_model_id(state: &Arc<AppState>, config: &Value) -> String {
+    let active_character = config
+        .get("active_character")
+        .or_else(|| config.get("active_agent_profile"))
+        .and_then(|v| v.as_str());
+    let models = &state.ai().models;
+
+    models
+        .resolve_assignment_model_id("professional")
+        .ok()
+        .flatten()
+        .or_else(|| {
+            models
+                .resolve_character_model_id(active_character)
+                .ok()
+                .flatten()
+        })
+        .unwrap_or_else(|| "default".to_string())
+}
+
+fn build_summary_messages(
+    previous: Option<&str>,
+    overflowed: &[HistoryMessage],
+) -> Vec<ChatMessage> {
+    let transcript = overflowed
+        .iter()
+        .filter(|message| !message.content.trim().is_empty())
+        .map(|message| {
+            let speaker = match message.message_type.as_str() {
+                "ai" | "assistant" => "Assistant",
+                "tool" => "Tool",
+                "system" => "System",
+                _ => "User",
+            };
+            format!("{}: {}", speaker, message.content.trim())
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let previous = previous
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .unwrap_or("(none)");
+
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: SUMMARY_SYSTEM_PROMPT.to_string(),
+            multimodal_parts: None,
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Previous summary:\n{}\n\nNew turns:\n{}\n\nUpdated summary:",
+                previous, transcript
+            ),
+            multimodal_parts: None,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(id: i64, role: &str, content: &str) -> HistoryMessage {
+        HistoryMessage {
+            id,
+            session_id: "s1".to_string(),
+            message_type: role.to_string(),
+            content: content.to_string(),
+            created_at: String::new(),
+            additional_kwargs: None,
+        }
+    }
+
+    #[test]
+    fn settings_fall_back_to_defaults() {
+        let settings = SummarySettings::from_config(&json!({}));
+        assert!(settings.enabled);
+        assert_eq!(settings.keep_recent, 12);
+        assert_eq!(settings.batch, 8);
+
+        let settings = SummarySettings::from_config(&json!({
+            "app": {
+                "conversation_summary_enabled": false,
+                "conversation_summary_keep_recent": 4,
+                "conversation_summary_batch": 2,
+                "conversation_summary_max_tokens": 128
+            }
+        }));
+        assert!(!settings.enabled);
+        assert_eq!(settings.keep_recent, 4);
+        assert_eq!(settings.batch, 2);
+        assert_eq!(settings.max_tokens, 128);
+    }
+
+    #[test]
+    fn summary_prompt_merges_previous_summary_and_transcript() {
+        let messages = build_summary_messages(
+            Some("User is planning a trip to Kyoto."),
+            &[
+                message(1, "human", "Book a ryokan for two nights."),
+                message(2, "ai", "Sure, any budget?"),
+                message(3, "human", "   "),
+            ],
+        );
+
+        assert_eq!(messages.len(), 2);
+        let body = &messages[1].content;
+        assert!(body.contains("User is planning a trip to Kyoto."));
+        assert!(body.contains("User: Book a ryokan for two nights."));
+        assert!(body.contains("Assistant: Sure, any budget?"));
+        assert_eq!(body.matches("User:").count(), 1);
+    }
+
+    #[test]
+    fn summary_prompt_marks_missing_previous_summary() {
+        let messages = build_summary_messages(None, &[message(1, "human", "hi")]);
+        assert!(messages[1].content.contains("Previous summary:\n(none)"));
+    }
+}
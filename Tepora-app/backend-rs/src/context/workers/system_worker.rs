@@ -1,11 +1,15 @@
 //! SystemWorker — Builds the system prompt from the active character config.
+//!
+//! Block order, templates and on/off switches come from `system_prompt.blocks`
+//! (see [`SystemPromptLayout`]). This worker fills the template variables and
+//! emits the blocks that no other worker owns.
 
 use std::sync::Arc;
 
 use async_trait::async_trait;
 
 use crate::context::pipeline_context::PipelineContext;
-use crate::context::prompt::extract_system_prompt;
+use crate::context::prompt::{extract_system_prompt, PromptBlockKind};
 use crate::context::worker::{ContextWorker, WorkerError};
 use crate::state::AppState;
 
@@ -42,11 +46,34 @@ impl ContextWorker for SystemWorker {
             .unwrap_or("bunny_girl");
         let character = config
             .get("characters")
-            .and_then(|characters| characters.get(active_character));
+            .and_then(|characters| characters.get(active_character))
+            .cloned();
+        let character_name = character
+            .as_ref()
+            .and_then(|character| character.get("name"))
+            .and_then(|value| value.as_str())
+            .unwrap_or("Tepora")
+            .to_string();
+        let system_prompt =
+            extract_system_prompt(config).filter(|prompt| !prompt.trim().is_empty());
+        let mode_name = serde_json::to_value(ctx.mode)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default();
+
+        let now = chrono::Local::now();
+        let session_id = ctx.session_id.clone();
+        let persona_id = active_character.to_string();
+        let layout = &mut ctx.system_layout;
+        layout.set_variable("character_name", character_name);
+        layout.set_variable("persona_id", persona_id);
+        layout.set_variable("mode", mode_name);
+        layout.set_variable("session_id", session_id);
+        layout.set_variable("date", now.format("%Y-%m-%d").to_string());
+        layout.set_variable("weekday", now.format("%A").to_string());
+        layout.set_variable("time", now.format("%H:%M").to_string());
 
-        if let Some(system_prompt) =
-            extract_system_prompt(config).filter(|prompt| !prompt.trim().is_empty())
-        {
+        if let Some(system_prompt) = system_prompt {
             ctx.add_system_part("base_system", system_prompt, 200);
         } else if let Some(character) = character {
             let name = character
@@ -103,6 +130,30 @@ impl ContextWorker for SystemWorker {
 
         ctx.add_system_part("mode_context", mode_context, 150);
 
+        // Blocks rendered purely from their templates.
+        let standalone = ctx
+            .system_layout
+            .blocks()
+            .iter()
+            .filter(|block| {
+                matches!(
+                    block.kind,
+                    PromptBlockKind::MemoryPolicy
+                        | PromptBlockKind::Datetime
+                        | PromptBlockKind::Custom
+                )
+            })
+            .map(|block| (block.kind, block.label.clone(), block.priority))
+            .collect::<Vec<_>>();
+        for (kind, label, priority) in standalone {
+            if kind == PromptBlockKind::Datetime {
+                // The clock changes every turn; keep it out of the cached prefix.
+                ctx.add_volatile_system_part(label, String::new(), priority);
+            } else {
+                ctx.add_system_part(label, String::new(), priority);
+            }
+        }
+
         Ok(())
     }
 }
@@ -1,15 +1,22 @@
 //! ToolWorker — Injects available tool definitions into the pipeline.
 //!
-//! Collects native tools and MCP tools and adds their definitions to the
-//! `PipelineContext` so the LLM knows which tools are available.
+//! Collects native (from `tools::registry`), MCP and plugin tools and adds
+//! their definitions to the `PipelineContext` so the LLM knows which tools are
+//! available.
+//!
+//! Also owns tool-result folding: oversized outputs are reduced to a JSON
+//! skeleton (all keys, sampled arrays) or head/tail lines before they go back
+//! into the prompt, and `expand_tool_result` returns a requested section.
 
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use serde_json::{Map, Value};
 
 use crate::context::pipeline_context::PipelineContext;
 use crate::context::worker::{ContextWorker, WorkerError};
 use crate::state::AppState;
+use crate::tools::registry::{ToolGroup, NATIVE_TOOLS};
 
 /// Worker that injects tool definitions.
 pub struct ToolWorker;
@@ -51,52 +58,43 @@ impl ContextWorker for ToolWorker {
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
 
-        // 1. Native tools — web_search, fetch_url
-        if ctx.mode.has_web_search() && !isolation {
-            tool_definitions.push(
-                "web_search(query: string) — Searches the web and returns results.".to_string(),
-            );
-            tool_definitions
-                .push("fetch_url(url: string) — Fetches content from a URL.".to_string());
-        }
-
-        // 2. RAG tools
-        if ctx.mode.has_rag() {
-            tool_definitions.push(
-                "rag_search(query: string, limit?: int) — Searches the RAG store for similar content.".to_string(),
-            );
-            tool_definitions.push(
-                "rag_ingest(content: string, source: string) — Ingests content into the RAG store."
-                    .to_string(),
-            );
-            tool_definitions.push(
-                "rag_text_search(pattern: string, limit?: int) — Searches RAG chunks by text pattern.".to_string(),
-            );
-            tool_definitions.push(
-                "rag_get_chunk(chunk_id: string) — Retrieves a specific RAG chunk.".to_string(),
-            );
-            tool_definitions.push(
-                "rag_get_chunk_window(chunk_id: string, chars?: int) — Retrieves neighboring chunks around one chunk.".to_string(),
-            );
-            tool_definitions.push(
-                "rag_clear_session(session_id?: string) — Clears all RAG chunks in a session."
-                    .to_string(),
-            );
-            tool_definitions.push(
-                "rag_reindex(embedding_model?: string) — Rebuilds the RAG index after embedding model changes.".to_string(),
-            );
-        }
+        // 1. Native tools from the registry, grouped by what the mode allows
+        let web = ctx.mode.has_web_search() && !isolation;
+        let rag = ctx.mode.has_rag();
+        tool_definitions.extend(
+            NATIVE_TOOLS
+                .iter()
+                .filter(|tool| match tool.group {
+                    ToolGroup::Web => web,
+                    ToolGroup::Rag => rag,
+                    ToolGroup::Utility => true,
+                    // Files, shell and desktop tools are offered to agents only
+                    ToolGroup::Files | ToolGroup::Shell | ToolGroup::Desktop => false,
+                })
+                .map(|tool| tool.prompt_signature()),
+        );
 
-        // 3. MCP tools — enumerate available servers and their tools
-        let mcp_tools = if isolation {
+        // 2. MCP tools — enumerate available servers and their tools
+        let mut mcp_tools = if isolation {
             Vec::new()
         } else {
             state.integration.mcp.list_tools().await
         };
+        // MCP servers report tools in arbitrary order; sort to keep the prompt prefix stable.
+        mcp_tools.sort_by(|a, b| a.name.cmp(&b.name));
         for tool in mcp_tools {
             tool_definitions.push(format!("mcp:{} — {}", tool.name, tool.description));
         }
 
+        // 3. Plugin tools (sandboxed WASM)
+        if !isolation {
+            let mut plugin_tools = state.integration.plugins.tools();
+            plugin_tools.sort_by(|a, b| a.name.cmp(&b.name));
+            for tool in plugin_tools {
+                tool_definitions.push(format!("{} — {}", tool.name, tool.description));
+            }
+        }
+
         // Inject tool information into the context
         if !tool_definitions.is_empty() {
             let tools_text = tool_definitions.join("\n");
@@ -110,3 +108,334 @@ impl ContextWorker for ToolWorker {
         Ok(())
     }
 }
+
+const FOLD_STRING_CHARS: usize = 200;
+const FOLD_MIN_STRING_CHARS: usize = 40;
+const FOLD_MAX_DEPTH: usize = 6;
+
+/// Limits for folding tool output, read from `tools.result_fold_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolFoldSettings {
+    pub max_chars: usize,
+    pub head_lines: usize,
+    pub tail_lines: usize,
+    pub array_sample: usize,
+}
+
+impl Default for ToolFoldSettings {
+    fn default() -> Self {
+        Self {
+            max_chars: 4_000,
+            head_lines: 40,
+            tail_lines: 20,
+            array_sample: 3,
+        }
+    }
+}
+
+impl ToolFoldSettings {
+    pub fn from_config(config: &Value) -> Self {
+        let defaults = Self::default();
+        let tools = config.get("tools");
+        let read = |key: &str, fallback: usize| {
+            tools
+                .and_then(|v| v.get(key))
+                .and_then(Value::as_u64)
+                .map(|v| v as usize)
+                .unwrap_or(fallback)
+        };
+        Self {
+            max_chars: read("result_fold_max_chars", defaults.max_chars),
+            head_lines: read("result_fold_head_lines", defaults.head_lines),
+            tail_lines: read("result_fold_tail_lines", defaults.tail_lines),
+            array_sample: read("result_fold_array_sample", defaults.array_sample).max(1),
+        }
+    }
+}
+
+/// Fold `output` to fit `settings.max_chars`. Small outputs pass through.
+pub fn fold_tool_output(result_id: &str, output: &str, settings: &ToolFoldSettings) -> String {
+    let total_chars = output.chars().count();
+    if total_chars <= settings.max_chars {
+        return output.to_string();
+    }
+
+    let (body, hint) = match serde_json::from_str::<Value>(output.trim()) {
+        Ok(value) if value.is_object() || value.is_array() => (
+            fold_json_text(&value, "", settings),
+            "a JSON pointer such as /items/5",
+        ),
+        _ => (
+            fold_text(output, settings),
+            "a line range such as lines:120-160",
+        ),
+    };
+
+    format!(
+        "{body}\n[Folded tool result {result_id}: {total_chars} chars total. Call expand_tool_result with result_id \"{result_id}\" and section set to {hint} to read more.]"
+    )
+}
+
+/// Return one section of a full tool output: a JSON pointer (`/a/0`) or a
+/// 1-based inclusive line range (`lines:10-20`).
+pub fn expand_tool_section(
+    output: &str,
+    section: &str,
+    settings: &ToolFoldSettings,
+) -> Result<String, String> {
+    let section = section.trim();
+
+    if let Some(range) = section.strip_prefix("lines:") {
+        let (start, end) = parse_line_range(range)
+            .ok_or_else(|| format!("Invalid line range '{range}'; use lines:<start>-<end>"))?;
+        let lines = output.lines().collect::<Vec<_>>();
+        if start > lines.len() {
+            return Err(format!(
+                "Line {start} is past the end of the result ({} lines)",
+                lines.len()
+            ));
+        }
+        let end = end.min(lines.len());
+        let text = lines[start - 1..end].join("\n");
+        return Ok(truncate_with_note(
+            &text,
+            settings.max_chars,
+            "narrow the line range to see the rest",
+        ));
+    }
+
+    if section.is_empty() || section.starts_with('/') {
+        let value = serde_json::from_str::<Value>(output.trim())
+            .map_err(|_| "Result is not JSON; use a line range such as lines:1-40".to_string())?;
+        let target = value
+            .pointer(section)
+            .ok_or_else(|| format!("No value at JSON pointer '{section}'"))?;
+        let text = serde_json::to_string_pretty(target).unwrap_or_default();
+        if text.chars().count() <= settings.max_chars {
+            return Ok(text);
+        }
+        return Ok(fold_json_text(target, section, settings));
+    }
+
+    Err(format!(
+        "Unknown section '{section}'; use a JSON pointer (/key/0) or lines:<start>-<end>"
+    ))
+}
+
+fn parse_line_range(range: &str) -> Option<(usize, usize)> {
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (start.trim().parse().ok()?, end.trim().parse().ok()?),
+        None => {
+            let line = range.trim().parse().ok()?;
+            (line, line)
+        }
+    };
+    (start >= 1 && end >= start).then_some((start, end))
+}
+
+/// Render a JSON skeleton, shrinking samples until it fits.
+fn fold_json_text(value: &Value, base_pointer: &str, settings: &ToolFoldSettings) -> String {
+    let mut sample = settings.array_sample;
+    let mut string_chars = FOLD_STRING_CHARS;
+    loop {
+        let folded = fold_json(value, base_pointer, sample, string_chars, 0);
+        let text = serde_json::to_string_pretty(&folded).unwrap_or_default();
+        let exhausted = sample == 1 && string_chars == FOLD_MIN_STRING_CHARS;
+        if text.chars().count() <= settings.max_chars || exhausted {
+            return truncate_with_note(&text, settings.max_chars, "expand a narrower pointer");
+        }
+        sample = (sample / 2).max(1);
+        string_chars = (string_chars / 2).max(FOLD_MIN_STRING_CHARS);
+    }
+}
+
+fn fold_json(
+    value: &Value,
+    pointer: &str,
+    sample: usize,
+    string_chars: usize,
+    depth: usize,
+) -> Value {
+    match value {
+        Value::Object(map) if depth >= FOLD_MAX_DEPTH => Value::String(format!(
+            "{{… {} keys, expand {}}}",
+            map.len(),
+            display_pointer(pointer)
+        )),
+        Value::Object(map) => {
+            let folded = map
+                .iter()
+                .map(|(key, item)| {
+                    let child = format!("{pointer}/{}", escape_pointer_token(key));
+                    (
+                        key.clone(),
+                        fold_json(item, &child, sample, string_chars, depth + 1),
+                    )
+                })
+                .collect::<Map<_, _>>();
+            Value::Object(folded)
+        }
+        Value::Array(items) if depth >= FOLD_MAX_DEPTH => Value::String(format!(
+            "[… {} items, expand {}]",
+            items.len(),
+            display_pointer(pointer)
+        )),
+        Value::Array(items) => {
+            let mut folded = items
+                .iter()
+                .take(sample)
+                .enumerate()
+                .map(|(index, item)| {
+                    fold_json(
+                        item,
+                        &format!("{pointer}/{index}"),
+                        sample,
+                        string_chars,
+                        depth + 1,
+                    )
+                })
+                .collect::<Vec<_>>();
+            if items.len() > sample {
+                folded.push(Value::String(format!(
+                    "… {} more items ({} total), expand {}/{}",
+                    items.len() - sample,
+                    items.len(),
+                    pointer,
+                    sample
+                )));
+            }
+            Value::Array(folded)
+        }
+        Value::String(text) if text.chars().count() > string_chars => {
+            let head = text.chars().take(string_chars).collect::<String>();
+            Value::String(format!(
+                "{head}… ({} chars, expand {})",
+                text.chars().count(),
+                display_pointer(pointer)
+            ))
+        }
+        other => other.clone(),
+    }
+}
+
+fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn display_pointer(pointer: &str) -> &str {
+    if pointer.is_empty() {
+        "/"
+    } else {
+        pointer
+    }
+}
+
+/// Keep the first and last lines of log-like output.
+fn fold_text(output: &str, settings: &ToolFoldSettings) -> String {
+    let lines = output.lines().collect::<Vec<_>>();
+    if lines.len() > settings.head_lines + settings.tail_lines {
+        let tail_start = lines.len() - settings.tail_lines;
+        let folded = format!(
+            "{}\n… [lines {}-{} folded] …\n{}",
+            lines[..settings.head_lines].join("\n"),
+            settings.head_lines + 1,
+            tail_start,
+            lines[tail_start..].join("\n")
+        );
+        if folded.chars().count() <= settings.max_chars {
+            return folded;
+        }
+    }
+
+    // Few but very long lines: fall back to a character head/tail.
+    let chars = output.chars().collect::<Vec<_>>();
+    let head_len = settings.max_chars * 2 / 3;
+    let tail_len = settings.max_chars / 4;
+    let head = chars[..head_len].iter().collect::<String>();
+    let tail = chars[chars.len() - tail_len..].iter().collect::<String>();
+    format!(
+        "{head}\n… [{} chars folded] …\n{tail}",
+        chars.len() - head_len - tail_len
+    )
+}
+
+fn truncate_with_note(text: &str, max_chars: usize, note: &str) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let kept = text.chars().take(max_chars).collect::<String>();
+    format!("{kept}\n… [truncated; {note}]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn small_settings() -> ToolFoldSettings {
+        ToolFoldSettings {
+            max_chars: 600,
+            head_lines: 3,
+            tail_lines: 2,
+            array_sample: 2,
+        }
+    }
+
+    #[test]
+    fn small_output_is_left_untouched() {
+        let settings = ToolFoldSettings::default();
+        assert_eq!(fold_tool_output("r1", "ok", &settings), "ok");
+    }
+
+    #[test]
+    fn json_folding_keeps_keys_and_samples_arrays() {
+        let items = (0..50)
+            .map(|i| json!({"id": i, "title": format!("item {i}"), "body": "x".repeat(300)}))
+            .collect::<Vec<_>>();
+        let output = json!({"total": 50, "next": null, "items": items}).to_string();
+
+        let folded = fold_tool_output("r7", &output, &small_settings());
+
+        assert!(folded.contains("\"total\": 50"));
+        assert!(folded.contains("\"next\": null"));
+        assert!(folded.contains("more items (50 total), expand /items/"));
+        assert!(folded.contains("result_id \"r7\""));
+        assert!(!folded.contains("item 10"));
+    }
+
+    #[test]
+    fn log_folding_keeps_head_and_tail_lines() {
+        let output = (1..=500)
+            .map(|i| format!("line {i}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let folded = fold_tool_output("r2", &output, &small_settings());
+
+        assert!(folded.starts_with("line 1\nline 2\nline 3\n… [lines 4-498 folded] …"));
+        assert!(folded.contains("line 499\nline 500\n[Folded tool result r2"));
+    }
+
+    #[test]
+    fn expand_returns_requested_sections() {
+        let settings = small_settings();
+        let json_output = json!({"items": [{"id": 1}, {"id": 2, "name": "b"}]}).to_string();
+        assert_eq!(
+            expand_tool_section(&json_output, "/items/1/name", &settings).unwrap(),
+            "\"b\""
+        );
+        assert!(expand_tool_section(&json_output, "/missing", &settings).is_err());
+
+        let log = "a\nb\nc\nd";
+        assert_eq!(
+            expand_tool_section(log, "lines:2-3", &settings).unwrap(),
+            "b\nc"
+        );
+        assert_eq!(
+            expand_tool_section(log, "lines:4-99", &settings).unwrap(),
+            "d"
+        );
+        assert!(expand_tool_section(log, "lines:9-10", &settings).is_err());
+        assert!(expand_tool_section(log, "/0", &settings).is_err());
+    }
+}
@@ -0,0 +1,369 @@
+//! チャット生成の待ち行列。
+//!
+//! 同じセッションへの生成は 1 件ずつ、届いた順に処理する。連投や複数ウィンドウ
+//! からの送信を拒否せずに並べ、トークンのストリームと履歴が混ざらないようにする。
+//! 全体の同時実行数は `app.max_concurrent_generations` で抑え、待っている間は
+//! 何件先に待ちがあるかを呼び出し側に返す（WebSocket の `queue_position` イベント）。
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::Serialize;
+use tokio::sync::watch;
+
+use super::config::schema::AppSettings;
+use super::errors::ApiError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueLimits {
+    pub max_concurrent: usize,
+    pub max_queued_per_session: usize,
+}
+
+impl From<&AppSettings> for QueueLimits {
+    fn from(settings: &AppSettings) -> Self {
+        Self {
+            max_concurrent: settings.max_concurrent_generations.max(1) as usize,
+            max_queued_per_session: settings.max_queued_messages_per_session.max(1) as usize,
+        }
+    }
+}
+
+impl Default for QueueLimits {
+    fn default() -> Self {
+        Self::from(&AppSettings::default())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueScope {
+    /// 同じセッションの先行メッセージを待っている
+    Session,
+    /// 全体の同時実行数の空きを待っている
+    Global,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct QueuePosition {
+    pub scope: QueueScope,
+    /// 自分より先に始まる（または終わる）必要がある要求の数
+    pub position: usize,
+}
+
+pub enum QueueStep {
+    Waiting(QueuePosition),
+    Ready(ChatTicket),
+}
+
+#[derive(Debug)]
+struct Waiter {
+    id: u64,
+    session_id: String,
+}
+
+#[derive(Debug)]
+struct QueueState {
+    next_id: u64,
+    max_concurrent: usize,
+    running: HashSet<String>,
+    waiting: VecDeque<Waiter>,
+}
+
+struct Inner {
+    state: Mutex<QueueState>,
+    changed: watch::Sender<u64>,
+}
+
+impl Inner {
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn notify(&self) {
+        self.changed
+            .send_modify(|version| *version = version.wrapping_add(1));
+    }
+}
+
+#[derive(Clone)]
+pub struct ChatQueue {
+    inner: Arc<Inner>,
+}
+
+impl Default for ChatQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChatQueue {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                state: Mutex::new(QueueState {
+                    next_id: 0,
+                    max_concurrent: QueueLimits::default().max_concurrent,
+                    running: HashSet::new(),
+                    waiting: VecDeque::new(),
+                }),
+                changed: watch::channel(0).0,
+            }),
+        }
+    }
+
+    /// 待ち行列に並ぶ。同じセッションの待ちが上限に達していれば拒否する。
+    /// 上限は並ぶたびに反映するので、設定の変更は次の要求から効く。
+    pub fn enqueue(&self, session_id: &str, limits: QueueLimits) -> Result<QueuedChat, ApiError> {
+        let mut state = self.inner.lock();
+        let queued_for_session = state
+            .waiting
+            .iter()
+            .filter(|waiter| waiter.session_id == session_id)
+            .count();
+        if queued_for_session >= limits.max_queued_per_session {
+            return Err(ApiError::ServiceUnavailable(format!(
+                "Session '{session_id}' already has {queued_for_session} queued messages"
+            )));
+        }
+        let raised = limits.max_concurrent > state.max_concurrent;
+        state.max_concurrent = limits.max_concurrent;
+        let id = state.next_id;
+        state.next_id += 1;
+        state.waiting.push_back(Waiter {
+            id,
+            session_id: session_id.to_string(),
+        });
+        drop(state);
+        if raised {
+            self.inner.notify();
+        }
+
+        Ok(QueuedChat {
+            inner: self.inner.clone(),
+            changes: self.inner.changed.subscribe(),
+            id,
+            session_id: session_id.to_string(),
+            last_position: None,
+            admitted: false,
+        })
+    }
+
+    pub fn running_count(&self) -> usize {
+        self.inner.lock().running.len()
+    }
+
+    pub fn waiting_count(&self) -> usize {
+        self.inner.lock().waiting.len()
+    }
+}
+
+/// 並んでいる 1 件。始まる前に破棄すると列から抜ける。
+pub struct QueuedChat {
+    inner: Arc<Inner>,
+    changes: watch::Receiver<u64>,
+    id: u64,
+    session_id: String,
+    last_position: Option<QueuePosition>,
+    admitted: bool,
+}
+
+impl QueuedChat {
+    /// 順番が来たら `Ready`、待ち位置が変わったら `Waiting` を返す。
+    pub async fn next(&mut self) -> QueueStep {
+        loop {
+            self.changes.borrow_and_update();
+            let position = {
+                let mut state = self.inner.lock();
+                match position_of(&state, self.id) {
+                    None => {
+                        state.waiting.retain(|waiter| waiter.id != self.id);
+                        state.running.insert(self.session_id.clone());
+                        self.admitted = true;
+                        drop(state);
+                        self.inner.notify();
+                        return QueueStep::Ready(ChatTicket {
+                            inner: self.inner.clone(),
+                            session_id: self.session_id.clone(),
+                        });
+                    }
+                    Some(position) => position,
+                }
+            };
+            if self.last_position != Some(position) {
+                self.last_position = Some(position);
+                return QueueStep::Waiting(position);
+            }
+            if self.changes.changed().await.is_err() {
+                // 送信側は `inner` が持っているので閉じることはない
+                continue;
+            }
+        }
+    }
+
+    /// 一度でも待たされたか
+    pub fn was_queued(&self) -> bool {
+        self.last_position.is_some()
+    }
+}
+
+impl Drop for QueuedChat {
+    fn drop(&mut self) {
+        if self.admitted {
+            return;
+        }
+        self.inner
+            .lock()
+            .waiting
+            .retain(|waiter| waiter.id != self.id);
+        self.inner.notify();
+    }
+}
+
+/// 生成中であることを表す。破棄するとセッションと全体の枠を空ける。
+pub struct ChatTicket {
+    inner: Arc<Inner>,
+    session_id: String,
+}
+
+impl Drop for ChatTicket {
+    fn drop(&mut self) {
+        self.inner.lock().running.remove(&self.session_id);
+        self.inner.notify();
+    }
+}
+
+/// `None` なら今すぐ始められる。
+fn position_of(state: &QueueState, id: u64) -> Option<QueuePosition> {
+    let index = state.waiting.iter().position(|waiter| waiter.id == id)?;
+    let me = &state.waiting[index];
+    let ahead = || state.waiting.range(..index);
+
+    let session_ahead = ahead()
+        .filter(|waiter| waiter.session_id == me.session_id)
+        .count()
+        + usize::from(state.running.contains(&me.session_id));
+    if session_ahead > 0 {
+        return Some(QueuePosition {
+            scope: QueueScope::Session,
+            position: session_ahead,
+        });
+    }
+
+    // 自分より前にいて、セッション側の待ちがないものだけが全体の枠を先に取る
+    let mut seen = HashSet::new();
+    let global_ahead = ahead()
+        .filter(|waiter| {
+            seen.insert(waiter.session_id.as_str()) && !state.running.contains(&waiter.session_id)
+        })
+        .count();
+    let free = state.max_concurrent.saturating_sub(state.running.len());
+    if global_ahead < free {
+        None
+    } else {
+        Some(QueuePosition {
+            scope: QueueScope::Global,
+            position: global_ahead + 1 - free,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn limits(max_concurrent: usize) -> QueueLimits {
+        QueueLimits {
+            max_concurrent,
+            max_queued_per_session: 4,
+        }
+    }
+
+    async fn expect_ready(queued: &mut QueuedChat) -> ChatTicket {
+        match tokio::time::timeout(Duration::from_secs(1), queued.next())
+            .await
+            .expect("queue step timed out")
+        {
+            QueueStep::Ready(ticket) => ticket,
+            QueueStep::Waiting(position) => panic!("still waiting: {position:?}"),
+        }
+    }
+
+    async fn expect_waiting(queued: &mut QueuedChat) -> QueuePosition {
+        match tokio::time::timeout(Duration::from_secs(1), queued.next())
+            .await
+            .expect("queue step timed out")
+        {
+            QueueStep::Waiting(position) => position,
+            QueueStep::Ready(_) => panic!("unexpectedly ready"),
+        }
+    }
+
+    #[tokio::test]
+    async fn same_session_is_served_in_order() {
+        let queue = ChatQueue::new();
+        let mut first = queue.enqueue("s1", limits(4)).unwrap();
+        let mut second = queue.enqueue("s1", limits(4)).unwrap();
+        let mut third = queue.enqueue("s1", limits(4)).unwrap();
+
+        let ticket = expect_ready(&mut first).await;
+        assert_eq!(
+            expect_waiting(&mut second).await,
+            QueuePosition {
+                scope: QueueScope::Session,
+                position: 1
+            }
+        );
+        assert_eq!(expect_waiting(&mut third).await.position, 2);
+
+        drop(ticket);
+        let ticket = expect_ready(&mut second).await;
+        assert_eq!(expect_waiting(&mut third).await.position, 1);
+        assert!(second.was_queued());
+        assert_eq!(queue.running_count(), 1);
+        drop(ticket);
+        drop(expect_ready(&mut third).await);
+        assert_eq!(queue.running_count(), 0);
+        assert_eq!(queue.waiting_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn global_limit_queues_other_sessions_and_cancel_frees_slot() {
+        let queue = ChatQueue::new();
+        let mut a = queue.enqueue("a", limits(1)).unwrap();
+        let mut b = queue.enqueue("b", limits(1)).unwrap();
+        let mut c = queue.enqueue("c", limits(1)).unwrap();
+
+        let ticket = expect_ready(&mut a).await;
+        assert_eq!(
+            expect_waiting(&mut b).await,
+            QueuePosition {
+                scope: QueueScope::Global,
+                position: 1
+            }
+        );
+        assert_eq!(expect_waiting(&mut c).await.position, 2);
+
+        // b がウィンドウを閉じて抜けると c が繰り上がる
+        drop(b);
+        assert_eq!(expect_waiting(&mut c).await.position, 1);
+        drop(ticket);
+        drop(expect_ready(&mut c).await);
+    }
+
+    #[test]
+    fn per_session_backlog_is_bounded() {
+        let queue = ChatQueue::new();
+        let limits = QueueLimits {
+            max_concurrent: 1,
+            max_queued_per_session: 2,
+        };
+        let _first = queue.enqueue("s1", limits).unwrap();
+        let _second = queue.enqueue("s1", limits).unwrap();
+        assert!(queue.enqueue("s1", limits).is_err());
+        assert!(queue.enqueue("s2", limits).is_ok());
+    }
+}
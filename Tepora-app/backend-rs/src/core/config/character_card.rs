@@ -0,0 +1,367 @@
+//! Character card import (Tavern V1 / SillyTavern V2 / V3).
+//!
+//! カードは JSON そのもの、または PNG の `tEXt` チャンク（`chara` / `ccv3`）に
+//! base64 で埋め込まれた JSON として配布される。読み込んだカードは
+//! [`Persona`] に変換して `characters.<id>` に保存する。
+
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::personas::{validate_persona_id, Persona};
+use super::service::ConfigService;
+use crate::core::errors::ApiError;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const MAX_DESCRIPTION_CHARS: usize = 200;
+const MAX_SLUG_LEN: usize = 48;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct CharacterCardData {
+    pub name: String,
+    pub description: String,
+    pub personality: String,
+    pub scenario: String,
+    pub first_mes: String,
+    pub mes_example: String,
+    pub creator_notes: String,
+    pub system_prompt: String,
+    pub post_history_instructions: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CharacterCard {
+    /// `chara_card_v2`, `chara_card_v3`, or `v1` for flat legacy cards.
+    pub spec: String,
+    pub data: CharacterCardData,
+    /// Source PNG, kept so it can be saved as the persona avatar.
+    pub png: Option<Vec<u8>>,
+}
+
+impl CharacterCard {
+    pub fn parse(bytes: &[u8]) -> Result<Self, ApiError> {
+        if bytes.starts_with(PNG_SIGNATURE) {
+            let json = extract_png_card_json(bytes)?;
+            let mut card = Self::from_json(&json)?;
+            card.png = Some(bytes.to_vec());
+            return Ok(card);
+        }
+        let value: Value = serde_json::from_slice(bytes)
+            .map_err(|err| ApiError::BadRequest(format!("Invalid character card JSON: {err}")))?;
+        Self::from_json(&value)
+    }
+
+    fn from_json(value: &Value) -> Result<Self, ApiError> {
+        let spec = value
+            .get("spec")
+            .and_then(Value::as_str)
+            .unwrap_or("v1")
+            .to_string();
+        // V2/V3 nest fields under `data`; V1 cards are flat.
+        let data_value = match value.get("data") {
+            Some(data) if data.is_object() => data,
+            _ => value,
+        };
+        let data: CharacterCardData = serde_json::from_value(data_value.clone())
+            .map_err(|err| ApiError::BadRequest(format!("Invalid character card: {err}")))?;
+        if data.name.trim().is_empty() {
+            return Err(ApiError::BadRequest(
+                "Character card has no name".to_string(),
+            ));
+        }
+        Ok(Self {
+            spec,
+            data,
+            png: None,
+        })
+    }
+
+    /// Map card fields onto a persona. Persona id is left empty for the
+    /// store to generate unless the caller sets one.
+    pub fn to_persona(&self) -> Persona {
+        let data = &self.data;
+        let name = data.name.trim().to_string();
+        let expand = |text: &str| expand_macros(text, &name);
+
+        let mut sections = Vec::new();
+        let base = expand(&data.system_prompt.replace("{{original}}", ""));
+        if !base.trim().is_empty() {
+            sections.push(base.trim().to_string());
+        }
+        if !data.description.trim().is_empty() {
+            sections.push(expand(data.description.trim()));
+        }
+        if !data.personality.trim().is_empty() {
+            sections.push(format!("Personality: {}", expand(data.personality.trim())));
+        }
+        if !data.scenario.trim().is_empty() {
+            sections.push(format!("Scenario: {}", expand(data.scenario.trim())));
+        }
+        if !data.mes_example.trim().is_empty() {
+            sections.push(format!(
+                "Example dialogue:\n{}",
+                expand(data.mes_example.trim())
+            ));
+        }
+
+        let style_rules = data
+            .post_history_instructions
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(&expand)
+            .collect();
+        let description = Some(first_chars(
+            data.creator_notes.trim(),
+            MAX_DESCRIPTION_CHARS,
+        ))
+        .filter(|text| !text.is_empty())
+        .or_else(|| {
+            Some(first_chars(data.description.trim(), MAX_DESCRIPTION_CHARS))
+                .filter(|text| !text.is_empty())
+                .map(|text| expand(&text))
+        });
+
+        Persona {
+            id: String::new(),
+            name: name.clone(),
+            description,
+            system_prompt: Some(sections.join("\n\n")).filter(|text| !text.is_empty()),
+            style_rules,
+            greeting: Some(expand(data.first_mes.trim())).filter(|text| !text.is_empty()),
+            traits: data
+                .tags
+                .iter()
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+            ..Persona::default()
+        }
+    }
+}
+
+impl ConfigService {
+    /// Import a card as a persona. Without an explicit id the id is derived
+    /// from the card name; PNG cards also become the persona avatar.
+    pub fn import_character_card(
+        &self,
+        bytes: &[u8],
+        persona_id: Option<&str>,
+        overwrite: bool,
+    ) -> Result<Persona, ApiError> {
+        let card = CharacterCard::parse(bytes)?;
+        let mut persona = card.to_persona();
+        persona.id = persona_id
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| slugify(&persona.name));
+
+        if !persona.id.is_empty() && self.get_persona(&persona.id)?.is_some() && !overwrite {
+            return Err(ApiError::Conflict(format!(
+                "Persona '{}' already exists",
+                persona.id
+            )));
+        }
+        if persona.id.is_empty() {
+            persona.id = format!(
+                "persona_{}",
+                &uuid::Uuid::new_v4().simple().to_string()[..8]
+            );
+        }
+
+        validate_persona_id(&persona.id)?;
+
+        if let Some(png) = &card.png {
+            let dir = self.paths().user_data_dir.join("avatars");
+            std::fs::create_dir_all(&dir).map_err(ApiError::internal)?;
+            let path = dir.join(format!("{}.png", persona.id));
+            std::fs::write(&path, png).map_err(ApiError::internal)?;
+            persona.avatar_path = Some(path.to_string_lossy().to_string());
+        }
+
+        self.save_persona(persona)
+    }
+}
+
+/// ASCII slug of the card name; empty when nothing usable remains.
+fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for ch in name.chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('_') {
+            slug.push('_');
+        }
+        if slug.len() >= MAX_SLUG_LEN {
+            break;
+        }
+    }
+    slug.trim_end_matches('_').to_string()
+}
+
+/// `{{char}}` is the card's own name; `{{user}}` stays generic.
+fn expand_macros(text: &str, name: &str) -> String {
+    text.replace("{{char}}", name)
+        .replace("{{Char}}", name)
+        .replace("<BOT>", name)
+        .replace("{{user}}", "User")
+        .replace("{{User}}", "User")
+        .replace("<USER>", "User")
+}
+
+fn first_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// Find the card payload in the PNG text chunks. `ccv3` wins over `chara`
+/// when both are present, matching SillyTavern.
+fn extract_png_card_json(bytes: &[u8]) -> Result<Value, ApiError> {
+    let mut offset = PNG_SIGNATURE.len();
+    let mut chara = None;
+    let mut ccv3 = None;
+
+    while offset + 8 <= bytes.len() {
+        let length = u32::from_be_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ]) as usize;
+        let kind = &bytes[offset + 4..offset + 8];
+        let data_start = offset + 8;
+        let Some(data_end) = data_start
+            .checked_add(length)
+            .filter(|end| *end <= bytes.len())
+        else {
+            break;
+        };
+        if kind == b"tEXt" {
+            let chunk = &bytes[data_start..data_end];
+            if let Some(split) = chunk.iter().position(|byte| *byte == 0) {
+                let text = &chunk[split + 1..];
+                match &chunk[..split] {
+                    b"chara" => chara = Some(text),
+                    b"ccv3" => ccv3 = Some(text),
+                    _ => {}
+                }
+            }
+        }
+        if kind == b"IEND" {
+            break;
+        }
+        // length + type + data + CRC
+        offset = data_end + 4;
+    }
+
+    let encoded = ccv3.or(chara).ok_or_else(|| {
+        ApiError::BadRequest("PNG does not contain character card data".to_string())
+    })?;
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim_ascii())
+        .map_err(|err| ApiError::BadRequest(format!("Invalid character card encoding: {err}")))?;
+    serde_json::from_slice(&decoded)
+        .map_err(|err| ApiError::BadRequest(format!("Invalid character card JSON: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn png_with_text(chunks: &[(&str, &str)]) -> Vec<u8> {
+        let mut png = PNG_SIGNATURE.to_vec();
+        let mut push_chunk = |kind: &[u8], data: &[u8]| {
+            png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            png.extend_from_slice(kind);
+            png.extend_from_slice(data);
+            png.extend_from_slice(&[0, 0, 0, 0]);
+        };
+        push_chunk(b"IHDR", &[0; 13]);
+        for (keyword, text) in chunks {
+            let mut payload = keyword.as_bytes().to_vec();
+            payload.push(0);
+            payload.extend_from_slice(text.as_bytes());
+            push_chunk(b"tEXt", &payload);
+        }
+        push_chunk(b"IEND", &[]);
+        png
+    }
+
+    fn v2_card() -> Value {
+        json!({
+            "spec": "chara_card_v2",
+            "spec_version": "2.0",
+            "data": {
+                "name": "Mika",
+                "description": "{{char}} is a librarian.",
+                "personality": "calm, curious",
+                "scenario": "{{user}} visits the library.",
+                "first_mes": "Welcome, {{user}}.",
+                "mes_example": "",
+                "creator_notes": "",
+                "system_prompt": "",
+                "post_history_instructions": "Stay in character.\nKeep replies short.",
+                "tags": ["fantasy", " "],
+                "alternate_greetings": [],
+                "extensions": {}
+            }
+        })
+    }
+
+    #[test]
+    fn v2_card_maps_to_persona_fields() {
+        let card = CharacterCard::parse(v2_card().to_string().as_bytes()).unwrap();
+        assert_eq!(card.spec, "chara_card_v2");
+
+        let persona = card.to_persona();
+        assert_eq!(persona.name, "Mika");
+        assert_eq!(
+            persona.system_prompt.as_deref(),
+            Some(
+                "Mika is a librarian.\n\nPersonality: calm, curious\n\nScenario: User visits the library."
+            )
+        );
+        assert_eq!(persona.greeting.as_deref(), Some("Welcome, User."));
+        assert_eq!(persona.style_rules.len(), 2);
+        assert_eq!(persona.traits, vec!["fantasy".to_string()]);
+        assert_eq!(persona.description.as_deref(), Some("Mika is a librarian."));
+    }
+
+    #[test]
+    fn png_card_prefers_ccv3_chunk() {
+        let encode =
+            |value: &Value| base64::engine::general_purpose::STANDARD.encode(value.to_string());
+        let mut v3 = v2_card();
+        v3["spec"] = json!("chara_card_v3");
+        v3["data"]["name"] = json!("Mika v3");
+
+        let png = png_with_text(&[("chara", &encode(&v2_card())), ("ccv3", &encode(&v3))]);
+
+        let card = CharacterCard::parse(&png).unwrap();
+        assert_eq!(card.data.name, "Mika v3");
+        assert!(card.png.is_some());
+    }
+
+    #[test]
+    fn v1_cards_and_missing_data_are_handled() {
+        let v1 = json!({"name": "Old", "description": "d", "first_mes": "hi"});
+        let card = CharacterCard::parse(v1.to_string().as_bytes()).unwrap();
+        assert_eq!(card.spec, "v1");
+        assert_eq!(card.to_persona().greeting.as_deref(), Some("hi"));
+
+        assert!(CharacterCard::parse(&png_with_text(&[("other", "x")])).is_err());
+        assert!(CharacterCard::parse(b"{\"description\": \"no name\"}").is_err());
+    }
+
+    #[test]
+    fn slugify_keeps_ascii_words() {
+        assert_eq!(slugify("Mika the Librarian!"), "mika_the_librarian");
+        assert_eq!(slugify("ミカ"), "");
+    }
+}
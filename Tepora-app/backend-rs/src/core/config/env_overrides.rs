@@ -0,0 +1,196 @@
+//! 環境変数による設定の上書き。
+//!
+//! `TEPORA__SECTION__KEY=value` を `section.key` への上書きとして、ファイルの設定と
+//! プロファイルの上に重ねる。値は YAML のスカラー・配列として解釈し、解釈できなければ
+//! 文字列のまま使う。上書きは保存されないので、コンテナやヘッドレス環境で
+//! データディレクトリに書き込まずに設定できる。
+
+use std::ffi::OsString;
+use std::sync::LazyLock;
+
+use serde_json::{Map, Value};
+
+pub const ENV_OVERRIDE_PREFIX: &str = "TEPORA__";
+const PATH_SEPARATOR: &str = "__";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvOverride {
+    pub var: String,
+    pub path: Vec<String>,
+    pub value: Value,
+}
+
+impl EnvOverride {
+    pub fn dotted_path(&self) -> String {
+        self.path.join(".")
+    }
+}
+
+/// 変数名から上書き対象のパスを取り出す。形式が合わなければ `None`。
+fn parse_path(var: &str) -> Option<Vec<String>> {
+    let rest = var.strip_prefix(ENV_OVERRIDE_PREFIX)?;
+    let path: Vec<String> = rest
+        .split(PATH_SEPARATOR)
+        .map(|segment| segment.to_ascii_lowercase())
+        .collect();
+    if path.iter().any(|segment| segment.is_empty()) {
+        return None;
+    }
+    Some(path)
+}
+
+fn parse_value(raw: &str) -> Value {
+    if raw.trim().is_empty() {
+        return Value::String(raw.to_string());
+    }
+    match serde_yaml::from_str::<Value>(raw) {
+        Ok(Value::Null) | Err(_) => Value::String(raw.to_string()),
+        Ok(value) => value,
+    }
+}
+
+/// 変数名順に並べて返す（同じパスを指す変数があれば後のものが勝つ）。
+pub fn collect_env_overrides(vars: impl IntoIterator<Item = (String, String)>) -> Vec<EnvOverride> {
+    let mut overrides: Vec<EnvOverride> = vars
+        .into_iter()
+        .filter_map(|(var, raw)| {
+            let path = parse_path(&var)?;
+            Some(EnvOverride {
+                value: parse_value(&raw),
+                var,
+                path,
+            })
+        })
+        .collect();
+    overrides.sort_by(|left, right| left.var.cmp(&right.var));
+    overrides
+}
+
+/// UTF-8 でない変数は読めないので警告して飛ばす（`std::env::vars` はここで panic する）。
+fn utf8_vars(
+    vars: impl IntoIterator<Item = (OsString, OsString)>,
+) -> impl Iterator<Item = (String, String)> {
+    vars.into_iter().filter_map(|(var, raw)| {
+        let name = var.to_string_lossy().into_owned();
+        match (var.into_string(), raw.into_string()) {
+            (Ok(var), Ok(raw)) => Some((var, raw)),
+            _ => {
+                tracing::warn!(var = %name, "Skipping environment variable that is not valid UTF-8");
+                None
+            }
+        }
+    })
+}
+
+static PROCESS_ENV_OVERRIDES: LazyLock<Vec<EnvOverride>> =
+    LazyLock::new(|| collect_env_overrides(utf8_vars(std::env::vars_os())));
+
+/// プロセスの環境変数による上書き。環境は起動後に変わらないので最初の呼び出しで一度だけ集める。
+pub fn process_env_overrides() -> &'static [EnvOverride] {
+    &PROCESS_ENV_OVERRIDES
+}
+
+pub fn apply_env_overrides(config: &mut Value, overrides: &[EnvOverride]) {
+    for item in overrides {
+        set_path(config, &item.path, item.value.clone());
+    }
+}
+
+fn set_path(config: &mut Value, path: &[String], value: Value) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut current = config;
+    for segment in parents {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        current = current
+            .as_object_mut()
+            .expect("object ensured")
+            .entry(segment.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+    if !current.is_object() {
+        *current = Value::Object(Map::new());
+    }
+    if let Some(map) = current.as_object_mut() {
+        map.insert(last.clone(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn variables_map_to_nested_keys_with_typed_values() {
+        let overrides = collect_env_overrides(vars(&[
+            ("TEPORA__LLM_MANAGER__PARALLEL_SLOTS", "4"),
+            ("TEPORA__LOADERS__OLLAMA__BASE_URL", "http://ollama:11434"),
+            ("TEPORA__PRIVACY__ALLOW_WEB_SEARCH", "false"),
+            (
+                "TEPORA__SERVER__CORS_ALLOWED_ORIGINS",
+                "[https://a.example]",
+            ),
+            ("TEPORA_PROFILE", "low-vram"),
+            ("TEPORA____BROKEN", "x"),
+        ]));
+        assert_eq!(overrides.len(), 4);
+
+        let mut config = json!({
+            "llm_manager": { "parallel_slots": 1, "stream_channel_buffer": 64 },
+            "privacy": "not-an-object"
+        });
+        apply_env_overrides(&mut config, &overrides);
+
+        assert_eq!(config["llm_manager"]["parallel_slots"], 4);
+        assert_eq!(config["llm_manager"]["stream_channel_buffer"], 64);
+        assert_eq!(
+            config["loaders"]["ollama"]["base_url"],
+            "http://ollama:11434"
+        );
+        assert_eq!(config["privacy"]["allow_web_search"], false);
+        assert_eq!(
+            config["server"]["cors_allowed_origins"],
+            json!(["https://a.example"])
+        );
+    }
+
+    #[test]
+    fn unparseable_or_empty_values_stay_strings() {
+        assert_eq!(parse_value("a: b: c"), json!("a: b: c"));
+        assert_eq!(parse_value(""), json!(""));
+        assert_eq!(parse_value("~"), json!("~"));
+        assert_eq!(parse_value("hello world"), json!("hello world"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_variables_are_skipped() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let vars = vec![
+            (
+                OsString::from("TEPORA__APP__LANGUAGE"),
+                OsString::from("ja"),
+            ),
+            (
+                OsString::from("TEPORA__APP__THEME"),
+                OsString::from_vec(vec![0xff, 0xfe]),
+            ),
+            (OsString::from_vec(vec![b'X', 0x80]), OsString::from("1")),
+        ];
+        let overrides = collect_env_overrides(utf8_vars(vars));
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides[0].dotted_path(), "app.language");
+    }
+}
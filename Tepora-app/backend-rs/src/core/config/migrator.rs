@@ -1,50 +1,137 @@
+use serde::Serialize;
 use serde_json::{Map, Number, Value};
 
 use crate::core::errors::ApiError;
 
 use super::secrets::{materialize_sensitive_references, SecretStore};
 
-pub const CURRENT_SCHEMA_VERSION: u64 = 2;
+pub const CURRENT_CONFIG_VERSION: u64 = 3;
+pub const CONFIG_VERSION_KEY: &str = "config_version";
+/// v2 までのバージョンキー。読み込み時だけ見る
+const LEGACY_VERSION_KEY: &str = "schema_version";
+
+/// 1 回の移行で何をしたか。起動ログと移行前バックアップの判断に使う。
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct MigrationReport {
+    pub from_version: u64,
+    pub to_version: u64,
+    pub steps: Vec<MigrationStepReport>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MigrationStepReport {
+    pub version: u64,
+    pub description: &'static str,
+    pub changes: Vec<String>,
+}
+
+impl MigrationReport {
+    /// バージョンが上がった（＝レイアウトを書き換えた）か
+    pub fn upgraded(&self) -> bool {
+        self.to_version > self.from_version
+    }
+
+    pub fn log(&self) {
+        if !self.upgraded() {
+            return;
+        }
+        tracing::info!(
+            from = self.from_version,
+            to = self.to_version,
+            "Migrated config layout"
+        );
+        for step in &self.steps {
+            if step.changes.is_empty() {
+                tracing::info!(version = step.version, "  {}: no changes", step.description);
+            }
+            for change in &step.changes {
+                tracing::info!(version = step.version, "  {}: {}", step.description, change);
+            }
+        }
+    }
+}
+
+struct MigrationStep {
+    version: u64,
+    description: &'static str,
+    apply: fn(&mut Map<String, Value>) -> Vec<String>,
+}
+
+/// バージョン順に並べること。各ステップは変更内容を人が読める形で返す。
+const MIGRATIONS: &[MigrationStep] = &[
+    MigrationStep {
+        version: 1,
+        description: "versioned layout",
+        apply: |_| Vec::new(),
+    },
+    MigrationStep {
+        version: 2,
+        description: "security defaults",
+        apply: migrate_to_v2,
+    },
+    MigrationStep {
+        version: 3,
+        description: "legacy key names",
+        apply: migrate_to_v3,
+    },
+];
 
 pub fn migrate_to_current(
     config: &mut Value,
     secret_store: &dyn SecretStore,
 ) -> Result<bool, ApiError> {
-    let current_version = parse_schema_version(config)?;
-    if current_version > CURRENT_SCHEMA_VERSION {
+    migrate_with_report(config, secret_store).map(|(changed, _)| changed)
+}
+
+/// `config` を現行レイアウトまで上げる。戻り値の bool は保存し直すべきかどうか。
+pub fn migrate_with_report(
+    config: &mut Value,
+    secret_store: &dyn SecretStore,
+) -> Result<(bool, MigrationReport), ApiError> {
+    let current_version = parse_config_version(config)?;
+    if current_version > CURRENT_CONFIG_VERSION {
         return Err(ApiError::BadRequest(format!(
-            "Unsupported config schema_version={} (current={CURRENT_SCHEMA_VERSION})",
+            "Unsupported config_version={} (current={CURRENT_CONFIG_VERSION})",
             current_version
         )));
     }
 
-    let mut changed = false;
+    let mut report = MigrationReport {
+        from_version: current_version,
+        to_version: CURRENT_CONFIG_VERSION,
+        steps: Vec::new(),
+    };
+    let mut changed = current_version < CURRENT_CONFIG_VERSION;
 
-    if current_version < 1 {
-        changed = true;
-    }
-    if current_version < 2 {
-        changed |= migrate_to_v2(config)?;
+    let Some(root) = ensure_root_object(config) else {
+        return Ok((false, report));
+    };
+    for step in MIGRATIONS
+        .iter()
+        .filter(|step| step.version > current_version)
+    {
+        report.steps.push(MigrationStepReport {
+            version: step.version,
+            description: step.description,
+            changes: (step.apply)(root),
+        });
     }
 
-    set_schema_version(config, CURRENT_SCHEMA_VERSION)?;
+    changed |= set_config_version(config, CURRENT_CONFIG_VERSION)?;
     if materialize_sensitive_references(config, secret_store)? {
         changed = true;
     }
 
-    Ok(changed)
+    Ok((changed, report))
 }
 
-fn migrate_to_v2(config: &mut Value) -> Result<bool, ApiError> {
-    let Some(root) = ensure_root_object(config) else {
-        return Ok(false);
-    };
-    let mut changed = false;
+fn migrate_to_v2(root: &mut Map<String, Value>) -> Vec<String> {
+    let mut changes = Vec::new();
 
     let model_download = ensure_object(root, "model_download");
     if !model_download.contains_key("require_sha256") {
         model_download.insert("require_sha256".to_string(), Value::Bool(true));
-        changed = true;
+        changes.push("set model_download.require_sha256 = true".to_string());
     }
 
     let permissions = ensure_object(root, "permissions");
@@ -53,15 +140,13 @@ fn migrate_to_v2(config: &mut Value) -> Result<bool, ApiError> {
             "default_ttl_seconds".to_string(),
             Value::Number(Number::from(86_400_u64)),
         );
-        changed = true;
+        changes.push("set permissions.default_ttl_seconds = 86400".to_string());
     }
-    if !permissions.contains_key("native_tools") {
-        permissions.insert("native_tools".to_string(), Value::Object(Map::new()));
-        changed = true;
-    }
-    if !permissions.contains_key("mcp_servers") {
-        permissions.insert("mcp_servers".to_string(), Value::Object(Map::new()));
-        changed = true;
+    for key in ["native_tools", "mcp_servers"] {
+        if !permissions.contains_key(key) {
+            permissions.insert(key.to_string(), Value::Object(Map::new()));
+            changes.push(format!("added empty permissions.{}", key));
+        }
     }
 
     let privacy = ensure_object(root, "privacy");
@@ -70,7 +155,7 @@ fn migrate_to_v2(config: &mut Value) -> Result<bool, ApiError> {
             "url_policy_preset".to_string(),
             Value::String("balanced".to_string()),
         );
-        changed = true;
+        changes.push("set privacy.url_policy_preset = balanced".to_string());
     }
     if !privacy.contains_key("lockdown") {
         privacy.insert(
@@ -81,45 +166,119 @@ fn migrate_to_v2(config: &mut Value) -> Result<bool, ApiError> {
                 "reason": null,
             }),
         );
-        changed = true;
+        changes.push("added privacy.lockdown (disabled)".to_string());
     }
 
-    Ok(changed)
+    changes
 }
 
-fn parse_schema_version(config: &Value) -> Result<u64, ApiError> {
-    let Some(root) = config.as_object() else {
-        return Ok(0);
-    };
+/// 読み込み側が互換のために見ている旧名を、現行名へ寄せる。
+fn migrate_to_v3(root: &mut Map<String, Value>) -> Vec<String> {
+    let mut changes = Vec::new();
 
-    let Some(version_value) = root.get("schema_version") else {
-        return Ok(0);
+    rename_key(root, "", "em_llm", "episodic_memory", &mut changes);
+    if let Some(Value::Object(server)) = root.get_mut("server") {
+        rename_key(
+            server,
+            "server.",
+            "allowed_origins",
+            "cors_allowed_origins",
+            &mut changes,
+        );
+    }
+    if let Some(Value::Object(manager)) = root.get_mut("llm_manager") {
+        rename_key(
+            manager,
+            "llm_manager.",
+            "health_check_interval",
+            "health_check_interval_ms",
+            &mut changes,
+        );
+        if let Some(Value::String(loader)) = manager.get_mut("loader") {
+            if let Some(canonical) = canonical_loader_name(loader) {
+                changes.push(format!(
+                    "llm_manager.loader: '{}' -> '{}'",
+                    loader, canonical
+                ));
+                *loader = canonical.to_string();
+            }
+        }
+    }
+    if let Some(Value::Object(loaders)) = root.get_mut("loaders") {
+        let legacy: Vec<(String, &'static str)> = loaders
+            .keys()
+            .filter_map(|name| canonical_loader_name(name).map(|c| (name.clone(), c)))
+            .collect();
+        for (name, canonical) in legacy {
+            rename_key(loaders, "loaders.", &name, canonical, &mut changes);
+        }
+    }
+
+    changes
+}
+
+/// 旧表記のローダー名なら現行名を返す。現行名や未知の名前なら `None`。
+fn canonical_loader_name(name: &str) -> Option<&'static str> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "llama.cpp" | "llamacpp" | "llama-cpp" => Some("llama_cpp"),
+        "lm_studio" | "lm-studio" | "lm studio" => Some("lmstudio"),
+        _ => None,
+    }
+}
+
+/// `from` を `to` に移す。`to` が既にあれば `from` は読まれていないので捨てる。
+fn rename_key(
+    map: &mut Map<String, Value>,
+    prefix: &str,
+    from: &str,
+    to: &str,
+    changes: &mut Vec<String>,
+) {
+    let Some(value) = map.remove(from) else {
+        return;
     };
+    if map.contains_key(to) {
+        changes.push(format!("dropped {prefix}{from} (shadowed by {prefix}{to})"));
+    } else {
+        map.insert(to.to_string(), value);
+        changes.push(format!("renamed {prefix}{from} -> {prefix}{to}"));
+    }
+}
 
-    if version_value.is_null() {
+fn parse_config_version(config: &Value) -> Result<u64, ApiError> {
+    let Some(root) = config.as_object() else {
         return Ok(0);
-    }
+    };
 
-    match version_value.as_u64() {
-        Some(version) => Ok(version),
-        None => Err(ApiError::BadRequest(
-            "config.schema_version must be an unsigned integer".to_string(),
-        )),
+    for key in [CONFIG_VERSION_KEY, LEGACY_VERSION_KEY] {
+        let Some(version_value) = root.get(key) else {
+            continue;
+        };
+        if version_value.is_null() {
+            continue;
+        }
+        return version_value.as_u64().ok_or_else(|| {
+            ApiError::BadRequest(format!("config.{} must be an unsigned integer", key))
+        });
     }
+    Ok(0)
 }
 
-fn set_schema_version(config: &mut Value, version: u64) -> Result<(), ApiError> {
+/// バージョンを書き込み、旧キーを消す。何か変えたら `true`。
+fn set_config_version(config: &mut Value, version: u64) -> Result<bool, ApiError> {
     let Some(root) = ensure_root_object(config) else {
         return Err(ApiError::BadRequest(
             "config root must be an object".to_string(),
         ));
     };
 
-    root.insert(
-        "schema_version".to_string(),
-        Value::Number(Number::from(version)),
-    );
-    Ok(())
+    let mut changed = root.remove(LEGACY_VERSION_KEY).is_some();
+    let next = Value::Number(Number::from(version));
+    if root.get(CONFIG_VERSION_KEY) != Some(&next) {
+        root.insert(CONFIG_VERSION_KEY.to_string(), next);
+        changed = true;
+    }
+    Ok(changed)
 }
 
 fn ensure_root_object(config: &mut Value) -> Option<&mut Map<String, Value>> {
@@ -143,11 +302,11 @@ fn ensure_object<'a>(root: &'a mut Map<String, Value>, key: &str) -> &'a mut Map
 mod tests {
     use serde_json::json;
 
-    use super::{migrate_to_current, CURRENT_SCHEMA_VERSION};
+    use super::{migrate_to_current, migrate_with_report, CURRENT_CONFIG_VERSION};
     use crate::core::config::secrets::{is_keyring_reference, MemorySecretStore};
 
     #[test]
-    fn migrate_v0_to_v2_sets_schema_and_materializes_secrets() {
+    fn migrate_v0_sets_config_version_and_materializes_secrets() {
         let store = MemorySecretStore::default();
         let mut config = json!({
             "llm": {
@@ -158,9 +317,10 @@ mod tests {
         let changed = migrate_to_current(&mut config, &store).expect("migration should succeed");
         assert!(changed);
         assert_eq!(
-            config.get("schema_version").and_then(|v| v.as_u64()),
-            Some(CURRENT_SCHEMA_VERSION)
+            config.get("config_version").and_then(|v| v.as_u64()),
+            Some(CURRENT_CONFIG_VERSION)
         );
+        assert!(config.get("schema_version").is_none());
 
         let reference = config
             .get("llm")
@@ -196,4 +356,52 @@ mod tests {
             Some(false)
         );
     }
+
+    #[test]
+    fn v3_renames_legacy_keys_and_reports_them() {
+        let store = MemorySecretStore::default();
+        let mut config = json!({
+            "schema_version": 2,
+            "em_llm": { "enabled": false },
+            "server": { "allowed_origins": ["http://localhost:5173"] },
+            "llm_manager": { "loader": "llama.cpp", "health_check_interval": 250 },
+            "loaders": { "lm-studio": { "base_url": "http://localhost:1234" } }
+        });
+
+        let (changed, report) =
+            migrate_with_report(&mut config, &store).expect("migration should succeed");
+        assert!(changed);
+        assert_eq!(report.from_version, 2);
+        assert!(report.upgraded());
+        assert_eq!(report.steps.len(), 1);
+        assert_eq!(report.steps[0].changes.len(), 5);
+
+        assert_eq!(config["episodic_memory"]["enabled"], false);
+        assert!(config.get("em_llm").is_none());
+        assert_eq!(
+            config["server"]["cors_allowed_origins"][0],
+            "http://localhost:5173"
+        );
+        assert_eq!(config["llm_manager"]["loader"], "llama_cpp");
+        assert_eq!(config["llm_manager"]["health_check_interval_ms"], 250);
+        assert!(config["loaders"].get("lmstudio").is_some());
+    }
+
+    #[test]
+    fn current_config_is_left_alone() {
+        let store = MemorySecretStore::default();
+        let mut config = json!({ "config_version": CURRENT_CONFIG_VERSION });
+        migrate_to_current(&mut config, &store).expect("first pass");
+        let (changed, report) = migrate_with_report(&mut config, &store).expect("second pass");
+        assert!(!changed);
+        assert!(!report.upgraded());
+        assert!(report.steps.is_empty());
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let store = MemorySecretStore::default();
+        let mut config = json!({ "config_version": CURRENT_CONFIG_VERSION + 1 });
+        assert!(migrate_to_current(&mut config, &store).is_err());
+    }
 }
@@ -1,11 +1,19 @@
+pub mod character_card;
 pub mod defaults;
+pub mod env_overrides;
 pub mod migrator;
 pub mod paths;
+pub mod personas;
+pub mod profiles;
+pub mod schema;
 pub mod secrets;
 pub mod service;
+pub mod users;
 pub mod validation;
 mod validation_primitives;
 mod validation_sections;
+pub mod watch;
 
 pub use paths::AppPaths;
+pub use schema::TeporaConfig;
 pub use service::ConfigService;
@@ -107,10 +107,11 @@ fn discover_user_data_dir() -> PathBuf {
         return PathBuf::from(dir);
     }
 
-    discover_tepora_home().join("default")
+    super::users::user_home(&tepora_home_dir(), &super::users::active_user_id()).join("default")
 }
 
-fn discover_tepora_home() -> PathBuf {
+/// ユーザー一覧を置く、全ユーザー共通のホーム。
+pub fn tepora_home_dir() -> PathBuf {
     if let Ok(dir) = env::var("TEPORA_HOME") {
         return PathBuf::from(dir);
     }
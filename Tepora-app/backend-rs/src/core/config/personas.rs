@@ -0,0 +1,307 @@
+//! Persona store backed by the `characters` section of the config.
+//!
+//! ペルソナは `characters.<id>` に保存され、既存のキャラクター設定と同じ
+//! YAMLを共有する。セッション単位の切り替えは履歴DB側で保持する。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::service::ConfigService;
+use crate::core::errors::ApiError;
+
+pub const DEFAULT_PERSONA_ID: &str = "bunny_girl";
+const MAX_PERSONA_ID_LEN: usize = 64;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Persona {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub style_rules: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub greeting: Option<String>,
+    /// Character profile whose model assignment this persona reuses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_role: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub traits: Vec<String>,
+}
+
+impl Persona {
+    pub fn from_entry(id: &str, entry: &Value) -> Option<Self> {
+        let object = entry.as_object()?;
+        let text = |key: &str| {
+            object
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .filter(|value| !value.trim().is_empty())
+        };
+        let list = |key: &str| {
+            object
+                .get(key)
+                .and_then(Value::as_array)
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+
+        Some(Self {
+            id: id.to_string(),
+            name: text("name").unwrap_or_else(|| id.to_string()),
+            description: text("description"),
+            system_prompt: text("system_prompt").or_else(|| text("prompt")),
+            style_rules: list("style_rules"),
+            greeting: text("greeting"),
+            model_role: text("model_role"),
+            icon: text("icon"),
+            avatar_path: text("avatar_path"),
+            traits: list("traits"),
+        })
+    }
+
+    /// Write persona fields over `existing`, keeping keys the store does not manage.
+    fn merge_into(&self, existing: Option<&Value>) -> Value {
+        let mut entry = existing
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default();
+        let mut set = |key: &str, value: Option<Value>| match value {
+            Some(value) => {
+                entry.insert(key.to_string(), value);
+            }
+            None => {
+                entry.remove(key);
+            }
+        };
+        let text = |value: &Option<String>| value.clone().map(Value::String);
+        let list = |items: &[String]| {
+            (!items.is_empty())
+                .then(|| Value::Array(items.iter().cloned().map(Value::String).collect()))
+        };
+
+        set("name", Some(Value::String(self.name.clone())));
+        set("description", text(&self.description));
+        set("system_prompt", text(&self.system_prompt));
+        set("style_rules", list(&self.style_rules));
+        set("greeting", text(&self.greeting));
+        set("model_role", text(&self.model_role));
+        set("icon", text(&self.icon));
+        set("avatar_path", text(&self.avatar_path));
+        set("traits", list(&self.traits));
+        if self.system_prompt.is_some() {
+            entry.remove("prompt");
+        }
+        Value::Object(entry)
+    }
+}
+
+pub fn active_persona_id(config: &Value) -> &str {
+    config
+        .get("active_character")
+        .or_else(|| config.get("active_agent_profile"))
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_PERSONA_ID)
+}
+
+pub fn persona_exists(config: &Value, persona_id: &str) -> bool {
+    config
+        .get("characters")
+        .and_then(|characters| characters.get(persona_id))
+        .is_some_and(Value::is_object)
+}
+
+/// Point `active_character` at `persona_id` for this config snapshot only.
+pub fn apply_persona_override(config: &mut Value, persona_id: &str) -> bool {
+    if !persona_exists(config, persona_id) {
+        return false;
+    }
+    match config.as_object_mut() {
+        Some(root) => {
+            root.insert(
+                "active_character".to_string(),
+                Value::String(persona_id.to_string()),
+            );
+            true
+        }
+        None => false,
+    }
+}
+
+/// Character profile used for model selection: the persona's `model_role`
+/// when linked, otherwise the persona itself.
+pub fn model_profile_id(config: &Value) -> Option<&str> {
+    let active = config
+        .get("active_character")
+        .or_else(|| config.get("active_agent_profile"))
+        .and_then(Value::as_str)?;
+    config
+        .get("characters")
+        .and_then(|characters| characters.get(active))
+        .and_then(|entry| entry.get("model_role"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|role| !role.is_empty())
+        .or(Some(active))
+}
+
+pub(super) fn validate_persona_id(persona_id: &str) -> Result<(), ApiError> {
+    let valid = !persona_id.is_empty()
+        && persona_id.len() <= MAX_PERSONA_ID_LEN
+        && persona_id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(ApiError::BadRequest(format!(
+            "Invalid persona id '{}': use 1-{} ASCII letters, digits, '_' or '-'",
+            persona_id, MAX_PERSONA_ID_LEN
+        )))
+    }
+}
+
+fn characters_mut(config: &mut Value) -> Result<&mut Map<String, Value>, ApiError> {
+    let root = config
+        .as_object_mut()
+        .ok_or_else(|| ApiError::internal("config root is not an object"))?;
+    root.entry("characters")
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| ApiError::BadRequest("config 'characters' is not an object".to_string()))
+}
+
+impl ConfigService {
+    pub fn list_personas(&self) -> Result<Vec<Persona>, ApiError> {
+        let config = self.load_config()?;
+        let mut personas = config
+            .get("characters")
+            .and_then(Value::as_object)
+            .map(|characters| {
+                characters
+                    .iter()
+                    .filter_map(|(id, entry)| Persona::from_entry(id, entry))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        personas.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(personas)
+    }
+
+    pub fn get_persona(&self, persona_id: &str) -> Result<Option<Persona>, ApiError> {
+        let config = self.load_config()?;
+        Ok(config
+            .get("characters")
+            .and_then(|characters| characters.get(persona_id))
+            .and_then(|entry| Persona::from_entry(persona_id, entry)))
+    }
+
+    /// Create or replace a persona. A missing id is generated from a UUID.
+    pub fn save_persona(&self, mut persona: Persona) -> Result<Persona, ApiError> {
+        if persona.id.trim().is_empty() {
+            persona.id = format!(
+                "persona_{}",
+                &uuid::Uuid::new_v4().simple().to_string()[..8]
+            );
+        }
+        validate_persona_id(&persona.id)?;
+        if persona.name.trim().is_empty() {
+            return Err(ApiError::BadRequest(
+                "Persona name cannot be empty".to_string(),
+            ));
+        }
+        persona.style_rules.retain(|rule| !rule.trim().is_empty());
+        persona.traits.retain(|item| !item.trim().is_empty());
+
+        self.modify_config(|config| {
+            let characters = characters_mut(config)?;
+            let entry = persona.merge_into(characters.get(&persona.id));
+            characters.insert(persona.id.clone(), entry);
+            Ok(())
+        })?;
+        Ok(persona)
+    }
+
+    pub fn delete_persona(&self, persona_id: &str) -> Result<bool, ApiError> {
+        let mut removed = false;
+        self.modify_config(|config| {
+            if active_persona_id(config) == persona_id {
+                return Err(ApiError::Conflict(
+                    "Cannot delete the active persona".to_string(),
+                ));
+            }
+            let characters = characters_mut(config)?;
+            if characters.len() == 1 && characters.contains_key(persona_id) {
+                return Err(ApiError::Conflict(
+                    "Cannot delete the last remaining persona".to_string(),
+                ));
+            }
+            removed = characters.remove(persona_id).is_some();
+            Ok(())
+        })?;
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn persona_roundtrips_through_character_entry() {
+        let persona = Persona {
+            id: "guide".to_string(),
+            name: "Guide".to_string(),
+            system_prompt: Some("You guide.".to_string()),
+            style_rules: vec!["Answer in bullet points.".to_string()],
+            greeting: Some("Hello!".to_string()),
+            model_role: Some("shigure".to_string()),
+            ..Persona::default()
+        };
+        let existing = json!({"name": "Old", "prompt": "legacy", "custom": 1});
+
+        let entry = persona.merge_into(Some(&existing));
+
+        assert_eq!(entry["custom"], json!(1));
+        assert!(entry.get("prompt").is_none());
+        assert_eq!(Persona::from_entry("guide", &entry), Some(persona));
+    }
+
+    #[test]
+    fn override_only_applies_to_known_personas() {
+        let mut config = json!({
+            "active_character": "bunny_girl",
+            "characters": {"bunny_girl": {}, "shigure": {"model_role": "satuki"}}
+        });
+
+        assert!(!apply_persona_override(&mut config, "missing"));
+        assert_eq!(active_persona_id(&config), "bunny_girl");
+        assert_eq!(model_profile_id(&config), Some("bunny_girl"));
+
+        assert!(apply_persona_override(&mut config, "shigure"));
+        assert_eq!(active_persona_id(&config), "shigure");
+        assert_eq!(model_profile_id(&config), Some("satuki"));
+    }
+
+    #[test]
+    fn persona_ids_are_restricted() {
+        assert!(validate_persona_id("my-persona_1").is_ok());
+        assert!(validate_persona_id("../etc").is_err());
+        assert!(validate_persona_id("").is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! 設定プロファイル。
+//!
+//! `profiles.<name>` に書いた差分を、選択中のプロファイルとして基本設定の上に
+//! 重ねる。同じデータディレクトリを使うノート PC とデスクトップで
+//! `n_gpu_layers` やモデル割り当てを切り替えるためのもの。
+//!
+//! 選択の優先順位は、起動時の `TEPORA_PROFILE` > 設定の `active_profile`。
+//! API から切り替えたときは `active_profile` を書き換え、環境変数の指定は解除する。
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use super::service::deep_merge;
+use crate::core::errors::ApiError;
+
+pub const PROFILES_KEY: &str = "profiles";
+pub const ACTIVE_PROFILE_KEY: &str = "active_profile";
+/// プロファイル内でモデルの割り当てを上書きするキー（`assignment_key -> model_id`）
+pub const MODEL_ROLES_KEY: &str = "model_roles";
+pub const PROFILE_ENV_VAR: &str = "TEPORA_PROFILE";
+
+const MAX_PROFILE_NAME_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileSource {
+    Environment,
+    Config,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveProfile {
+    pub name: String,
+    pub source: ProfileSource,
+}
+
+pub fn startup_profile_override() -> Option<String> {
+    std::env::var(PROFILE_ENV_VAR)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+pub fn validate_profile_name(name: &str) -> Result<(), ApiError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_PROFILE_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ApiError::BadRequest(format!(
+            "Invalid profile name '{}': use up to {} letters, digits, '-' or '_'",
+            name, MAX_PROFILE_NAME_LEN
+        )))
+    }
+}
+
+pub fn profile_names(config: &Value) -> Vec<String> {
+    config
+        .get(PROFILES_KEY)
+        .and_then(Value::as_object)
+        .map(|profiles| profiles.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn profile_overlay<'a>(config: &'a Value, name: &str) -> Option<&'a Map<String, Value>> {
+    config
+        .get(PROFILES_KEY)
+        .and_then(|profiles| profiles.get(name))
+        .and_then(Value::as_object)
+}
+
+/// 環境変数の指定があればそれを、無ければ設定の `active_profile` を返す。
+pub fn resolve_active_profile(config: &Value, env_override: Option<&str>) -> Option<ActiveProfile> {
+    if let Some(name) = env_override {
+        return Some(ActiveProfile {
+            name: name.to_string(),
+            source: ProfileSource::Environment,
+        });
+    }
+    config
+        .get(ACTIVE_PROFILE_KEY)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| ActiveProfile {
+            name: name.to_string(),
+            source: ProfileSource::Config,
+        })
+}
+
+/// プロファイルの差分を `config` に重ねる。存在しないプロファイルなら何もしない。
+pub fn apply_profile_overlay(config: &mut Value, name: &str) -> bool {
+    let Some(overlay) = profile_overlay(config, name).cloned() else {
+        return false;
+    };
+    *config = deep_merge(config, &Value::Object(overlay));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> Value {
+        json!({
+            "active_profile": "workstation",
+            "models_gguf": {
+                "text_model": { "path": "models/text.gguf", "n_ctx": 4096, "n_gpu_layers": -1 }
+            },
+            "profiles": {
+                "low-vram": { "models_gguf": { "text_model": { "n_gpu_layers": 8 } } },
+                "workstation": {}
+            }
+        })
+    }
+
+    #[test]
+    fn overlay_is_merged_over_base() {
+        let mut config = sample();
+        assert!(apply_profile_overlay(&mut config, "low-vram"));
+        assert_eq!(config["models_gguf"]["text_model"]["n_gpu_layers"], 8);
+        assert_eq!(config["models_gguf"]["text_model"]["n_ctx"], 4096);
+        assert!(!apply_profile_overlay(&mut config, "missing"));
+    }
+
+    #[test]
+    fn environment_beats_config_selection() {
+        let config = sample();
+        let active = resolve_active_profile(&config, None).unwrap();
+        assert_eq!(active.name, "workstation");
+        assert_eq!(active.source, ProfileSource::Config);
+
+        let active = resolve_active_profile(&config, Some("low-vram")).unwrap();
+        assert_eq!(active.name, "low-vram");
+        assert_eq!(active.source, ProfileSource::Environment);
+        assert!(resolve_active_profile(&json!({}), None).is_none());
+    }
+
+    #[test]
+    fn invalid_overlays_fail_validation_with_profile_name() {
+        use crate::core::config::validation::validate_config;
+
+        assert!(validate_config(&sample()).is_ok());
+        let err = validate_config(&json!({
+            "profiles": { "remote": { "llm_manager": { "parallel_slots": 0 } } }
+        }))
+        .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("Profile 'remote'"), "{}", message);
+        assert!(
+            message.contains("llm_manager.parallel_slots"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn profile_names_are_restricted() {
+        assert!(validate_profile_name("low-vram").is_ok());
+        assert!(validate_profile_name("remote_2").is_ok());
+        assert!(validate_profile_name("").is_err());
+        assert!(validate_profile_name("../etc").is_err());
+    }
+}
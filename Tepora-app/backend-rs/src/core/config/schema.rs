@@ -0,0 +1,865 @@
+//! 設定の型付きビュー。
+//!
+//! `config.yml` は任意のセクションを持てるため保存形式は `Value` のままだが、
+//! 実行時に参照する値はここで構造体に落とし、既定値もここで一元管理する。
+//! 未知のキーは無視する（他のセクションや将来のキーを壊さないため）。
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::core::errors::ApiError;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct TeporaConfig {
+    pub app: AppSettings,
+    pub server: ServerSettings,
+    pub llm_manager: LlmManagerSettings,
+    pub desktop: DesktopSettings,
+    pub notifications: NotificationSettings,
+    pub tts: TtsSettings,
+    pub maintenance: MaintenanceSettings,
+    /// 新しいセッションの既定値。プロジェクト設定の同名項目が上書きする
+    pub session_defaults: SessionDefaults,
+    pub updates: UpdateSettings,
+    pub network: NetworkSettings,
+    pub plugins: PluginsSettings,
+    pub scripting: ScriptingSettings,
+    pub mcp_marketplace: McpMarketplaceSettings,
+    /// モデル ID またはローダー名（`openai` など）ごとの料金。モデル ID が優先
+    pub model_pricing: BTreeMap<String, ModelPricing>,
+    /// ローダー名（`ollama`, `lmstudio` など）ごとの接続設定
+    pub loaders: BTreeMap<String, LoaderSettings>,
+    /// 起動時に重ねるプロファイル名（`TEPORA_PROFILE` が優先）
+    pub active_profile: Option<String>,
+    /// プロファイル名ごとの差分。中身は基本設定と同じ形
+    pub profiles: BTreeMap<String, Value>,
+    /// モデル割り当ての上書き（`assignment_key -> model_id`）
+    pub model_roles: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct AppSettings {
+    /// 1 メッセージの最大バイト数
+    #[schemars(range(min = 1, max = 10_000_000))]
+    pub max_input_length: u64,
+    /// グラフ 1 回の実行で辿れる最大ステップ数
+    #[schemars(range(min = 1, max = 10_000))]
+    pub graph_recursion_limit: u64,
+    /// グラフ全体のタイムアウト（秒）。未設定なら無制限
+    pub graph_execution_timeout: Option<u64>,
+    /// モード名 → 最初に実行するノード。`translate: chat` のように軽いモードを
+    /// ルーターや計画を通さずに走らせる。未指定のモードは `router` から
+    pub graph_entry_points: BTreeMap<String, String>,
+    #[schemars(range(min = 1, max = 86_400))]
+    pub tool_execution_timeout: Option<u64>,
+    #[schemars(range(min = 1, max = 86_400))]
+    pub tool_approval_timeout: Option<u64>,
+    #[schemars(range(min = 1, max = 1_000))]
+    pub history_limit: Option<u64>,
+    /// 受信から完了までがこれ以上かかったチャット要求を警告ログに出す（ミリ秒）
+    #[schemars(range(min = 100, max = 3_600_000))]
+    pub slow_request_warn_ms: u64,
+    /// 最初のトークンがこれ以上来ないとき、プロバイダー・llama-server のスロット・
+    /// 使用率などの診断を採取して実行トレースに付ける（ミリ秒、0 で無効）
+    #[schemars(range(max = 600_000))]
+    pub first_token_watchdog_ms: u64,
+    /// 全セッション合計で同時に走らせるチャット生成の数。超えた分は順番待ちになる
+    #[schemars(range(min = 1, max = 64))]
+    pub max_concurrent_generations: u64,
+    /// 1 セッションで順番待ちにできるメッセージ数。超えると拒否する
+    #[schemars(range(min = 1, max = 100))]
+    pub max_queued_messages_per_session: u64,
+    /// これより長く走っているグラフ実行を打ち切る（秒、0 で無効）。
+    /// 切断を検知できなかった実行が状態を抱えたまま残るのを防ぐ
+    #[schemars(range(max = 604_800))]
+    pub stale_run_max_age_secs: u64,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            max_input_length: 4096,
+            graph_recursion_limit: 50,
+            graph_execution_timeout: None,
+            graph_entry_points: BTreeMap::new(),
+            tool_execution_timeout: None,
+            tool_approval_timeout: None,
+            history_limit: None,
+            slow_request_warn_ms: 8_000,
+            first_token_watchdog_ms: 5_000,
+            max_concurrent_generations: 4,
+            max_queued_messages_per_session: 8,
+            stale_run_max_age_secs: 1_800,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct ServerSettings {
+    /// `TEPORA_HOST` が無いときの待ち受けアドレス
+    pub host: Option<String>,
+    /// `TEPORA_PORT` / `PORT` が無いときの待ち受けポート
+    pub port: Option<u16>,
+    pub cors_allowed_origins: Vec<String>,
+    /// `cors_allowed_origins` の旧名
+    pub allowed_origins: Vec<String>,
+    pub ws_allowed_origins: Vec<String>,
+}
+
+/// デスクトップアプリ（Tauri）側の設定。
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct DesktopSettings {
+    pub quick_ask_enabled: bool,
+    /// クイック質問ウィンドウを開くグローバルショートカット（例: `CommandOrControl+Shift+Space`）
+    pub quick_ask_shortcut: Option<String>,
+}
+
+impl Default for DesktopSettings {
+    fn default() -> Self {
+        Self {
+            quick_ask_enabled: true,
+            quick_ask_shortcut: None,
+        }
+    }
+}
+
+impl DesktopSettings {
+    pub const DEFAULT_QUICK_ASK_SHORTCUT: &'static str = "CommandOrControl+Shift+Space";
+
+    /// 無効化されているか空文字なら `None`。
+    pub fn quick_ask_shortcut(&self) -> Option<&str> {
+        if !self.quick_ask_enabled {
+            return None;
+        }
+        match self.quick_ask_shortcut.as_deref().map(str::trim) {
+            Some("") => None,
+            Some(shortcut) => Some(shortcut),
+            None => Some(Self::DEFAULT_QUICK_ASK_SHORTCUT),
+        }
+    }
+}
+
+/// バックグラウンド処理の完了通知を種類ごとに送るかどうか。
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    pub agent_runs: bool,
+    pub downloads: bool,
+    pub binary_updates: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            agent_runs: true,
+            downloads: true,
+            binary_updates: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+/// 応答の読み上げ。有効にするとストリームに文境界（`sentence`）を混ぜる。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct TtsSettings {
+    pub enabled: bool,
+}
+
+/// DB の定期メンテナンス（整合性チェック・空き領域の回収・統計の更新）。
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct MaintenanceSettings {
+    pub db_auto: bool,
+    /// 自動実行の間隔（日）
+    #[schemars(range(min = 1, max = 365))]
+    pub db_interval_days: u64,
+    /// 会話履歴の月ごとの話題索引を 1 日 1 回更新する
+    pub topics_auto: bool,
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        Self {
+            db_auto: true,
+            db_interval_days: 30,
+            topics_auto: true,
+        }
+    }
+}
+
+/// セッションの既定のモード・ペルソナ・RAG コレクション・温度。
+/// メッセージ側で指定した値がさらに優先される。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct SessionDefaults {
+    /// 未設定なら `chat`
+    pub mode: Option<String>,
+    /// セッションでペルソナを固定していないときに使う。未設定なら `active_character`
+    pub persona_id: Option<String>,
+    /// `rag_search` で参照できる追加のコレクション
+    pub rag_collections: Vec<String>,
+    #[schemars(range(min = 0.0, max = 2.0))]
+    pub temperature: Option<f64>,
+}
+
+impl SessionDefaults {
+    pub const FALLBACK_MODE: &'static str = "chat";
+
+    pub fn mode(&self) -> &str {
+        self.mode
+            .as_deref()
+            .map(str::trim)
+            .filter(|mode| !mode.is_empty())
+            .unwrap_or(Self::FALLBACK_MODE)
+    }
+
+    /// `overrides` で指定された項目だけを差し替える。コレクションは空なら据え置き。
+    pub fn overlay(&self, overrides: &SessionDefaults) -> SessionDefaults {
+        SessionDefaults {
+            mode: overrides.mode.clone().or_else(|| self.mode.clone()),
+            persona_id: overrides
+                .persona_id
+                .clone()
+                .or_else(|| self.persona_id.clone()),
+            rag_collections: if overrides.rag_collections.is_empty() {
+                self.rag_collections.clone()
+            } else {
+                overrides.rag_collections.clone()
+            },
+            temperature: overrides.temperature.or(self.temperature),
+        }
+    }
+}
+
+/// デスクトップ版の自動更新。
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct UpdateSettings {
+    pub channel: UpdateChannel,
+    pub auto_check: bool,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            channel: UpdateChannel::Stable,
+            auto_check: true,
+        }
+    }
+}
+
+/// 外部と通信するサブシステム。オフライン中の個別許可の単位。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkSubsystem {
+    Models,
+    McpRegistry,
+    Search,
+    WebFetch,
+    HttpTools,
+    CloudProviders,
+    BinaryUpdates,
+    /// HTTP で繋ぐ MCP サーバー
+    McpServers,
+}
+
+impl NetworkSubsystem {
+    pub const ALL: [NetworkSubsystem; 8] = [
+        NetworkSubsystem::Models,
+        NetworkSubsystem::McpRegistry,
+        NetworkSubsystem::Search,
+        NetworkSubsystem::WebFetch,
+        NetworkSubsystem::HttpTools,
+        NetworkSubsystem::CloudProviders,
+        NetworkSubsystem::BinaryUpdates,
+        NetworkSubsystem::McpServers,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NetworkSubsystem::Models => "models",
+            NetworkSubsystem::McpRegistry => "mcp_registry",
+            NetworkSubsystem::Search => "search",
+            NetworkSubsystem::WebFetch => "web_fetch",
+            NetworkSubsystem::HttpTools => "http_tools",
+            NetworkSubsystem::CloudProviders => "cloud_providers",
+            NetworkSubsystem::BinaryUpdates => "binary_updates",
+            NetworkSubsystem::McpServers => "mcp_servers",
+        }
+    }
+}
+
+/// オフラインモード。有効な間はローカルホスト以外への通信を止める。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct NetworkSettings {
+    pub offline: bool,
+    /// オフライン中でも外部へ出てよいサブシステム
+    pub allow_while_offline: Vec<NetworkSubsystem>,
+    pub proxy: ProxySettings,
+}
+
+/// 外向き通信のプロキシ。URL は `http://` / `https://` / `socks5://` / `socks5h://`。
+/// 何も設定しなければ環境変数（`HTTPS_PROXY` など）に従う。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct ProxySettings {
+    /// 全サブシステム共通のプロキシ
+    pub url: Option<String>,
+    /// 空でなければ、このホストへの通信だけプロキシを通す（`*.example.com` 可）
+    pub only_hosts: Vec<String>,
+    /// プロキシを通さないホスト（`*.example.com` 可）。ループバックは常に直接
+    pub no_proxy: Vec<String>,
+    /// サブシステムごとの上書き。キーは `allow_while_offline` と同じ名前
+    pub subsystems: BTreeMap<String, SubsystemProxy>,
+    /// クラウドプロバイダーごとの上書き。キーはローダー名（`openai` など）
+    pub providers: BTreeMap<String, SubsystemProxy>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct SubsystemProxy {
+    /// ここだけ別のプロキシを使う
+    pub url: Option<String>,
+    /// `true` なら共通のプロキシも使わず直接つなぐ
+    pub direct: bool,
+}
+
+/// プラグインに与える権限。マニフェストで要求され、ユーザーが付与したものだけが有効になる。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginCapability {
+    /// エージェントが呼べるツールを登録する
+    Tools,
+    /// コンテキストパイプラインにワーカーを追加する
+    ContextWorkers,
+    /// ワークフロー JSON から使えるグラフノードを提供する
+    GraphNodes,
+    /// プラグイン専用のデータディレクトリを読み書きする
+    Storage,
+}
+
+impl PluginCapability {
+    pub const ALL: [PluginCapability; 4] = [
+        PluginCapability::Tools,
+        PluginCapability::ContextWorkers,
+        PluginCapability::GraphNodes,
+        PluginCapability::Storage,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PluginCapability::Tools => "tools",
+            PluginCapability::ContextWorkers => "context_workers",
+            PluginCapability::GraphNodes => "graph_nodes",
+            PluginCapability::Storage => "storage",
+        }
+    }
+}
+
+/// WASM プラグイン。1 回の呼び出しごとの実行量・メモリ・時間に上限を設ける。
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct PluginsSettings {
+    pub enabled: bool,
+    /// プラグイン ID → 付与済みの権限
+    pub grants: BTreeMap<String, Vec<PluginCapability>>,
+    pub fuel_per_call: u64,
+    pub max_memory_mb: u64,
+    pub call_timeout_ms: u64,
+}
+
+impl Default for PluginsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            grants: BTreeMap::new(),
+            fuel_per_call: 1_000_000_000,
+            max_memory_mb: 64,
+            call_timeout_ms: 10_000,
+        }
+    }
+}
+
+/// MCP ストアの掲載元。公式レジストリ、ユーザーが追加したレジストリ、組み込みの厳選リストをまとめて出す。
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct McpMarketplaceSettings {
+    /// 公式レジストリ（registry.modelcontextprotocol.io）を使う
+    pub official: bool,
+    /// 組み込みの厳選リストを使う
+    pub curated: bool,
+    /// 追加のレジストリ。公式と同じ `/v0.1/servers` 形式の URL
+    pub sources: Vec<McpRegistrySource>,
+    /// おすすめとして上に固定するサーバー ID（厳選リストのおすすめに追加）
+    pub recommended: Vec<String>,
+}
+
+impl Default for McpMarketplaceSettings {
+    fn default() -> Self {
+        Self {
+            official: true,
+            curated: true,
+            sources: Vec::new(),
+            recommended: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct McpRegistrySource {
+    /// 一覧に出す掲載元の名前
+    pub name: String,
+    pub url: String,
+}
+
+/// クラウドモデルの料金（USD / 100 万トークン）。実行前の見積もりに使う。
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct ModelPricing {
+    #[schemars(range(min = 0.0))]
+    pub input_per_million: f64,
+    #[schemars(range(min = 0.0))]
+    pub output_per_million: f64,
+}
+
+/// Rhai スクリプトのフック。スクリプトから呼べるツールは `allowed_tools` に限る。
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct ScriptingSettings {
+    pub enabled: bool,
+    pub allowed_tools: Vec<String>,
+    /// 1 回のフック実行で許す演算数
+    #[schemars(range(min = 1_000, max = 100_000_000))]
+    pub max_operations: u64,
+    #[schemars(range(min = 10, max = 60_000))]
+    pub timeout_ms: u64,
+}
+
+impl Default for ScriptingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_tools: Vec::new(),
+            max_operations: 1_000_000,
+            timeout_ms: 2_000,
+        }
+    }
+}
+
+/// ローダープロセスと外部ローダー呼び出しの設定。時間はすべてミリ秒。
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct LlmManagerSettings {
+    pub loader: Option<String>,
+    #[schemars(range(min = 1, max = 3_600_000))]
+    pub process_terminate_timeout: u64,
+    #[schemars(range(min = 1, max = 3_600_000))]
+    pub external_request_timeout_ms: Option<u64>,
+    #[schemars(range(min = 1, max = 3_600_000))]
+    pub stream_idle_timeout_ms: u64,
+    /// 未設定なら 15 秒。`external_request_timeout_ms` が無いときはその代わりにも使う
+    #[schemars(range(min = 1, max = 3_600_000))]
+    pub health_check_timeout: Option<u64>,
+    /// `health_check_interval` の新名。両方あればこちらを使う
+    #[schemars(range(min = 1, max = 3_600_000))]
+    pub health_check_interval_ms: Option<u64>,
+    #[schemars(range(min = 1, max = 3_600_000))]
+    pub health_check_interval: u64,
+    #[schemars(range(min = 1, max = 64))]
+    pub parallel_slots: u64,
+    #[schemars(range(min = 1, max = 65_536))]
+    pub stream_channel_buffer: u64,
+    #[schemars(range(min = 1, max = 65_536))]
+    pub stream_internal_buffer: u64,
+    /// ローダーごとの HTTP クライアントが接続先ホストあたりに保持するアイドル接続数
+    #[schemars(range(min = 0, max = 1_024))]
+    pub http_pool_max_idle_per_host: u64,
+    /// アイドル接続を閉じるまでの時間（ミリ秒）
+    #[schemars(range(min = 1_000, max = 3_600_000))]
+    pub http_pool_idle_timeout_ms: u64,
+    /// TCP keep-alive の間隔（ミリ秒）
+    #[schemars(range(min = 1_000, max = 3_600_000))]
+    pub http_tcp_keepalive_ms: u64,
+    /// 最後の利用からこの時間（ミリ秒）が過ぎたら llama-server を止めて VRAM を空ける。0 で無効
+    #[schemars(range(min = 0, max = 86_400_000))]
+    pub idle_unload_timeout_ms: u64,
+    /// 埋め込みモデルを読み込んでいるときはアイドル停止しない
+    pub keep_embedding_loaded: bool,
+    /// 最大トークン数で止まった応答を続けて生成させる回数。0 で無効
+    #[schemars(range(min = 0, max = 8))]
+    pub max_continuation_rounds: u64,
+    /// プロバイダーへの送信で、一時的な失敗に対する試行回数（初回を含む）。1 で再試行しない
+    #[schemars(range(min = 1, max = 10))]
+    pub retry_max_attempts: u64,
+    /// 再試行の待ち時間の基準（ミリ秒）。試行ごとに倍にし、ジッターを掛ける
+    #[schemars(range(min = 1, max = 60_000))]
+    pub retry_base_delay_ms: u64,
+    /// 再試行の待ち時間の上限（ミリ秒）
+    #[schemars(range(min = 1, max = 300_000))]
+    pub retry_max_delay_ms: u64,
+    /// この回数続けて失敗したプロバイダーは一時的に送信を止める
+    #[schemars(range(min = 1, max = 100))]
+    pub circuit_failure_threshold: u64,
+    /// 送信を止めてから、もう一度試すまでの時間（ミリ秒）
+    #[schemars(range(min = 1_000, max = 3_600_000))]
+    pub circuit_open_ms: u64,
+}
+
+impl Default for LlmManagerSettings {
+    fn default() -> Self {
+        Self {
+            loader: None,
+            process_terminate_timeout: 5_000,
+            external_request_timeout_ms: None,
+            stream_idle_timeout_ms: 60_000,
+            health_check_timeout: None,
+            health_check_interval_ms: None,
+            health_check_interval: 500,
+            parallel_slots: 1,
+            stream_channel_buffer: 128,
+            stream_internal_buffer: 100,
+            http_pool_max_idle_per_host: 8,
+            http_pool_idle_timeout_ms: 90_000,
+            http_tcp_keepalive_ms: 30_000,
+            idle_unload_timeout_ms: 900_000,
+            keep_embedding_loaded: false,
+            max_continuation_rounds: 2,
+            retry_max_attempts: 3,
+            retry_base_delay_ms: 500,
+            retry_max_delay_ms: 8_000,
+            circuit_failure_threshold: 5,
+            circuit_open_ms: 30_000,
+        }
+    }
+}
+
+impl LlmManagerSettings {
+    pub fn process_terminate_timeout(&self) -> Duration {
+        Duration::from_millis(self.process_terminate_timeout)
+    }
+
+    /// 外部ローダーへのリクエスト上限。どちらも未設定なら 120 秒。
+    pub fn external_request_timeout(&self) -> Duration {
+        self.external_request_timeout_ms
+            .or(self.health_check_timeout)
+            .map(|ms| Duration::from_millis(ms.max(1)))
+            .unwrap_or(Duration::from_secs(120))
+    }
+
+    pub fn stream_idle_timeout(&self) -> Duration {
+        Duration::from_millis(self.stream_idle_timeout_ms.max(1))
+    }
+
+    pub fn health_check_timeout(&self) -> Duration {
+        self.health_check_timeout
+            .map(|ms| Duration::from_millis(ms.max(1)))
+            .unwrap_or(Duration::from_secs(15))
+    }
+
+    pub fn health_check_interval(&self) -> Duration {
+        Duration::from_millis(
+            self.health_check_interval_ms
+                .unwrap_or(self.health_check_interval)
+                .max(1),
+        )
+    }
+
+    pub fn parallel_slots(&self) -> usize {
+        self.parallel_slots.clamp(1, 64) as usize
+    }
+
+    pub fn stream_channel_buffer(&self) -> usize {
+        self.stream_channel_buffer.clamp(1, 65_536) as usize
+    }
+
+    pub fn stream_internal_buffer(&self) -> usize {
+        self.stream_internal_buffer.clamp(1, 65_536) as usize
+    }
+
+    pub fn http_pool_max_idle_per_host(&self) -> usize {
+        self.http_pool_max_idle_per_host.min(1_024) as usize
+    }
+
+    pub fn http_pool_idle_timeout(&self) -> Duration {
+        Duration::from_millis(self.http_pool_idle_timeout_ms.clamp(1_000, 3_600_000))
+    }
+
+    pub fn http_tcp_keepalive(&self) -> Duration {
+        Duration::from_millis(self.http_tcp_keepalive_ms.clamp(1_000, 3_600_000))
+    }
+
+    pub fn max_continuation_rounds(&self) -> usize {
+        self.max_continuation_rounds.min(8) as usize
+    }
+
+    pub fn retry_max_attempts(&self) -> u32 {
+        self.retry_max_attempts.clamp(1, 10) as u32
+    }
+
+    pub fn retry_base_delay(&self) -> Duration {
+        Duration::from_millis(self.retry_base_delay_ms.clamp(1, 60_000))
+    }
+
+    pub fn retry_max_delay(&self) -> Duration {
+        Duration::from_millis(self.retry_max_delay_ms.clamp(1, 300_000))
+    }
+
+    pub fn circuit_failure_threshold(&self) -> u32 {
+        self.circuit_failure_threshold.clamp(1, 100) as u32
+    }
+
+    pub fn circuit_open_duration(&self) -> Duration {
+        Duration::from_millis(self.circuit_open_ms.clamp(1_000, 3_600_000))
+    }
+
+    pub fn idle_unload_timeout(&self) -> Option<Duration> {
+        (self.idle_unload_timeout_ms > 0)
+            .then(|| Duration::from_millis(self.idle_unload_timeout_ms.min(86_400_000)))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct LoaderSettings {
+    pub base_url: Option<String>,
+    /// クラウドプロバイダーの API キー（保存時はシークレットストアへ移す）
+    pub api_key: Option<String>,
+    /// Gemini の `safetySettings`。書いた順にそのまま送る
+    pub safety_settings: Option<Vec<SafetySetting>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct SafetySetting {
+    /// 例: `HARM_CATEGORY_HARASSMENT`
+    pub category: String,
+    /// 例: `BLOCK_ONLY_HIGH`
+    pub threshold: String,
+}
+
+impl TeporaConfig {
+    /// `Value` から型付き設定を作る。型が合わないキーはパス付きで報告する。
+    /// 旧形式の `null` や `6.0` のような値は `legacy_compatible` で読める形に直してから見る。
+    pub fn from_value(config: &Value) -> Result<Self, ApiError> {
+        if config.is_null() {
+            return Ok(Self::default());
+        }
+        serde_path_to_error::deserialize(legacy_compatible(config)).map_err(|err| {
+            let path = err.path().to_string();
+            let path = if path == "." {
+                "root".to_string()
+            } else {
+                path
+            };
+            ApiError::BadRequest(format!(
+                "Invalid config at '{}': {}",
+                path,
+                err.into_inner()
+            ))
+        })
+    }
+
+    /// 読み込み失敗時も既定値で動き続けたい呼び出し元向け。
+    pub fn from_value_or_default(config: &Value) -> Self {
+        Self::from_value(config).unwrap_or_else(|err| {
+            tracing::warn!("Falling back to default config values: {}", err);
+            Self::default()
+        })
+    }
+
+    /// `loaders.<name>.base_url`。空なら `default_url`。末尾の `/` は落とす。
+    pub fn loader_base_url(&self, loader: &str, default_url: &str) -> String {
+        self.loaders
+            .get(loader)
+            .and_then(|settings| settings.base_url.as_deref())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| value.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| default_url.to_string())
+    }
+
+    /// `loaders.<name>.api_key`。空なら `None`。
+    pub fn loader_api_key(&self, loader: &str) -> Option<String> {
+        self.loaders
+            .get(loader)
+            .and_then(|settings| settings.api_key.as_deref())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    }
+
+    /// `loaders.<name>.safety_settings`。未設定なら空（プロバイダーの既定）。
+    pub fn loader_safety_settings(&self, loader: &str) -> Vec<SafetySetting> {
+        self.loaders
+            .get(loader)
+            .and_then(|settings| settings.safety_settings.clone())
+            .unwrap_or_default()
+    }
+
+    /// `loaders.<name>` に接続先かキーが書かれているか。クラウドのモデル同期はこれで判断する。
+    pub fn loader_configured(&self, loader: &str) -> bool {
+        self.loaders.get(loader).is_some_and(|settings| {
+            settings
+                .base_url
+                .as_deref()
+                .is_some_and(|url| !url.trim().is_empty())
+                || settings
+                    .api_key
+                    .as_deref()
+                    .is_some_and(|key| !key.trim().is_empty())
+        })
+    }
+}
+
+/// 型付き設定になる前の形の揺れを吸収する。値が `null` のキーは未指定として外し
+/// （既定値が入る）、小数部の無い浮動小数は整数にする。YAML や旧 UI が書いた
+/// `cors_allowed_origins: null` や `history_limit: 6.0` を拒否しないため。
+fn legacy_compatible(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key.clone(), legacy_compatible(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(legacy_compatible).collect()),
+        Value::Number(number) if number.is_f64() => {
+            let float = number.as_f64().unwrap_or(f64::NAN);
+            if float.fract() != 0.0 || !float.is_finite() {
+                value.clone()
+            } else if (0.0..=u64::MAX as f64).contains(&float) {
+                Value::from(float as u64)
+            } else if float >= i64::MIN as f64 {
+                Value::from(float as i64)
+            } else {
+                value.clone()
+            }
+        }
+        _ => value.clone(),
+    }
+}
+
+/// `/api/config/schema` で公開する JSON Schema。
+pub fn config_json_schema() -> Value {
+    serde_json::to_value(schemars::schema_for!(TeporaConfig)).unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn missing_sections_use_defaults() {
+        let config =
+            TeporaConfig::from_value(&json!({ "app": { "max_input_length": 10 } })).expect("parse");
+        assert_eq!(config.app.max_input_length, 10);
+        assert_eq!(config.app.graph_recursion_limit, 50);
+        assert_eq!(config.llm_manager.parallel_slots(), 1);
+        assert_eq!(
+            config.llm_manager.external_request_timeout(),
+            Duration::from_secs(120)
+        );
+        assert_eq!(
+            TeporaConfig::from_value(&Value::Null).unwrap(),
+            TeporaConfig::default()
+        );
+    }
+
+    #[test]
+    fn type_errors_name_the_offending_key() {
+        let err = TeporaConfig::from_value(&json!({
+            "llm_manager": { "parallel_slots": "four" }
+        }))
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("'llm_manager.parallel_slots'"),
+            "{}",
+            err
+        );
+
+        let err = TeporaConfig::from_value(&json!({
+            "loaders": { "ollama": { "base_url": 1 } }
+        }))
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("'loaders.ollama.base_url'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn legacy_nulls_and_integral_floats_are_accepted() {
+        let config = TeporaConfig::from_value(&json!({
+            "server": { "cors_allowed_origins": null },
+            "app": { "history_limit": 6.0, "max_input_length": 2048.0 }
+        }))
+        .expect("legacy config should parse");
+        assert!(config.server.cors_allowed_origins.is_empty());
+        assert_eq!(config.app.history_limit, Some(6));
+        assert_eq!(config.app.max_input_length, 2048);
+
+        let err =
+            TeporaConfig::from_value(&json!({ "app": { "history_limit": 6.5 } })).unwrap_err();
+        assert!(err.to_string().contains("'app.history_limit'"), "{}", err);
+    }
+
+    #[test]
+    fn health_check_interval_prefers_ms_key() {
+        let config = TeporaConfig::from_value(&json!({
+            "llm_manager": { "health_check_interval": 900, "health_check_interval_ms": 250 }
+        }))
+        .unwrap();
+        assert_eq!(
+            config.llm_manager.health_check_interval(),
+            Duration::from_millis(250)
+        );
+    }
+
+    #[test]
+    fn quick_ask_shortcut_defaults_and_can_be_disabled() {
+        let config = TeporaConfig::default();
+        assert_eq!(
+            config.desktop.quick_ask_shortcut(),
+            Some(DesktopSettings::DEFAULT_QUICK_ASK_SHORTCUT)
+        );
+        let config = TeporaConfig::from_value(&json!({
+            "desktop": { "quick_ask_shortcut": "Alt+Space" }
+        }))
+        .unwrap();
+        assert_eq!(config.desktop.quick_ask_shortcut(), Some("Alt+Space"));
+        let config = TeporaConfig::from_value(&json!({
+            "desktop": { "quick_ask_shortcut": " " }
+        }))
+        .unwrap();
+        assert_eq!(config.desktop.quick_ask_shortcut(), None);
+    }
+
+    #[test]
+    fn schema_describes_known_sections() {
+        let schema = config_json_schema();
+        let text = schema.to_string();
+        assert!(text.contains("llm_manager"));
+        assert!(text.contains("max_input_length"));
+    }
+}
@@ -1,3 +1,11 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
@@ -6,7 +14,6 @@ use crate::core::errors::ApiError;
 
 #[cfg(test)]
 use std::collections::HashMap;
-#[cfg(test)]
 use std::sync::Mutex;
 
 const SENSITIVE_PATTERNS: [&str; 18] = [
@@ -52,12 +59,34 @@ pub trait SecretStore: Send + Sync {
 #[derive(Debug, Default)]
 pub struct OsSecretStore;
 
+impl OsSecretStore {
+    fn entry(reference: &str) -> Result<keyring::Entry, ApiError> {
+        let account = reference_to_account(reference)?;
+        keyring::Entry::new(KEYRING_SERVICE, &account).map_err(ApiError::internal)
+    }
+
+    fn write(&self, reference: &str, secret: &str) -> Result<(), ApiError> {
+        Self::entry(reference)?
+            .set_password(secret)
+            .map_err(ApiError::internal)
+    }
+
+    /// 書き込んだ値を読み戻せるか確かめる。永続化しないバックエンド
+    /// （プラットフォームのストアが無いときのモック）を検出するため。
+    fn write_verified(&self, reference: &str, secret: &str) -> Result<(), ApiError> {
+        self.write(reference, secret)?;
+        if self.read_secret(reference)?.as_deref() == Some(secret) {
+            Ok(())
+        } else {
+            Err(ApiError::internal("OS keyring did not persist the secret"))
+        }
+    }
+}
+
 impl SecretStore for OsSecretStore {
     fn store_secret(&self, path: &str, secret: &str) -> Result<String, ApiError> {
         let reference = build_reference(path);
-        let account = reference_to_account(&reference)?;
-        let entry = keyring::Entry::new(KEYRING_SERVICE, &account).map_err(ApiError::internal)?;
-        entry.set_password(secret).map_err(ApiError::internal)?;
+        self.write(&reference, secret)?;
         Ok(reference)
     }
 
@@ -66,9 +95,7 @@ impl SecretStore for OsSecretStore {
             return Ok(Some(reference.to_string()));
         }
 
-        let account = reference_to_account(reference)?;
-        let entry = keyring::Entry::new(KEYRING_SERVICE, &account).map_err(ApiError::internal)?;
-        match entry.get_password() {
+        match Self::entry(reference)?.get_password() {
             Ok(secret) => Ok(Some(secret)),
             Err(keyring::Error::NoEntry) => Ok(None),
             Err(err) => Err(ApiError::internal(err)),
@@ -80,15 +107,249 @@ impl SecretStore for OsSecretStore {
             return Ok(());
         }
 
-        let account = reference_to_account(reference)?;
-        let entry = keyring::Entry::new(KEYRING_SERVICE, &account).map_err(ApiError::internal)?;
-        match entry.delete_credential() {
+        match Self::entry(reference)?.delete_credential() {
             Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
             Err(err) => Err(ApiError::internal(err)),
         }
     }
 }
 
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SecretFile {
+    #[serde(default)]
+    entries: BTreeMap<String, SecretFileEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SecretFileEntry {
+    nonce_hex: String,
+    ciphertext_hex: String,
+}
+
+/// OS キーリングが使えない環境（ヘッドレス Linux など）向けの暗号化ファイル。
+///
+/// 鍵はデータディレクトリ内の `secrets.key`（所有者のみ読み書き可）に置く。
+/// 平文の `secrets.yaml` よりはましだが、キーリングと同等の保護ではないため、
+/// キーリングが使えるようになったら [`FallbackSecretStore::migrate_to_keyring`] で移す。
+pub struct EncryptedFileSecretStore {
+    path: PathBuf,
+    key_path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl EncryptedFileSecretStore {
+    pub fn new(path: PathBuf, key_path: PathBuf) -> Self {
+        Self {
+            path,
+            key_path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn cipher(&self, create: bool) -> Result<Option<Aes256Gcm>, ApiError> {
+        let key_bytes = match fs::read_to_string(&self.key_path) {
+            Ok(raw) => hex::decode(raw.trim()).map_err(ApiError::internal)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound && create => {
+                let key = Aes256Gcm::generate_key(OsRng);
+                write_private_file(&self.key_path, hex::encode(key.as_slice()).as_bytes())?;
+                key.to_vec()
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(ApiError::internal(err)),
+        };
+        if key_bytes.len() != 32 {
+            return Err(ApiError::internal("Secret file key has an invalid length"));
+        }
+        Ok(Some(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(
+            &key_bytes,
+        ))))
+    }
+
+    fn load(&self) -> Result<SecretFile, ApiError> {
+        match fs::read_to_string(&self.path) {
+            Ok(raw) if raw.trim().is_empty() => Ok(SecretFile::default()),
+            Ok(raw) => serde_json::from_str(&raw).map_err(ApiError::internal),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(SecretFile::default()),
+            Err(err) => Err(ApiError::internal(err)),
+        }
+    }
+
+    fn save(&self, file: &SecretFile) -> Result<(), ApiError> {
+        let data = serde_json::to_vec_pretty(file).map_err(ApiError::internal)?;
+        write_private_file(&self.path, &data)
+    }
+
+    fn write(&self, reference: &str, secret: &str) -> Result<(), ApiError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let cipher = self
+            .cipher(true)?
+            .ok_or_else(|| ApiError::internal("Secret file key is unavailable"))?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, secret.as_bytes())
+            .map_err(|_| ApiError::internal("Failed to encrypt secret"))?;
+
+        let mut file = self.load()?;
+        file.entries.insert(
+            reference.to_string(),
+            SecretFileEntry {
+                nonce_hex: hex::encode(nonce),
+                ciphertext_hex: hex::encode(ciphertext),
+            },
+        );
+        self.save(&file)
+    }
+
+    fn references(&self) -> Result<Vec<String>, ApiError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(self.load()?.entries.into_keys().collect())
+    }
+}
+
+impl SecretStore for EncryptedFileSecretStore {
+    fn store_secret(&self, path: &str, secret: &str) -> Result<String, ApiError> {
+        let reference = build_reference(path);
+        self.write(&reference, secret)?;
+        Ok(reference)
+    }
+
+    fn read_secret(&self, reference: &str) -> Result<Option<String>, ApiError> {
+        if !is_keyring_reference(reference) {
+            return Ok(Some(reference.to_string()));
+        }
+
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let file = self.load()?;
+        let Some(entry) = file.entries.get(reference) else {
+            return Ok(None);
+        };
+        let Some(cipher) = self.cipher(false)? else {
+            return Ok(None);
+        };
+        let nonce = hex::decode(&entry.nonce_hex).map_err(ApiError::internal)?;
+        let ciphertext = hex::decode(&entry.ciphertext_hex).map_err(ApiError::internal)?;
+        if nonce.len() != 12 {
+            return Err(ApiError::internal("Secret file entry has an invalid nonce"));
+        }
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| ApiError::internal("Failed to decrypt secret file entry"))?;
+        String::from_utf8(plaintext)
+            .map(Some)
+            .map_err(ApiError::internal)
+    }
+
+    fn delete_secret(&self, reference: &str) -> Result<(), ApiError> {
+        if !is_keyring_reference(reference) {
+            return Ok(());
+        }
+
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = self.load()?;
+        if file.entries.remove(reference).is_some() {
+            self.save(&file)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_private_file(path: &Path, data: &[u8]) -> Result<(), ApiError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(ApiError::internal)?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, data).map_err(ApiError::internal)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&tmp_path, fs::Permissions::from_mode(0o600))
+            .map_err(ApiError::internal)?;
+    }
+    fs::rename(&tmp_path, path).map_err(ApiError::internal)
+}
+
+/// まず OS キーリングを使い、失敗したら暗号化ファイルへ退避するストア。
+/// 参照文字列の形式はどちらも同じなので、設定ファイル側は保存先を意識しない。
+pub struct FallbackSecretStore {
+    keyring: OsSecretStore,
+    file: EncryptedFileSecretStore,
+    warned: AtomicBool,
+}
+
+impl FallbackSecretStore {
+    pub fn new(file: EncryptedFileSecretStore) -> Self {
+        Self {
+            keyring: OsSecretStore,
+            file,
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    pub fn for_data_dir(user_data_dir: &Path) -> Self {
+        Self::new(EncryptedFileSecretStore::new(
+            user_data_dir.join("secrets.enc.json"),
+            user_data_dir.join("secrets.key"),
+        ))
+    }
+
+    fn warn_fallback(&self, err: &ApiError) {
+        if !self.warned.swap(true, Ordering::Relaxed) {
+            tracing::warn!(
+                "OS keyring is unavailable ({}); storing secrets in the encrypted fallback file",
+                err
+            );
+        }
+    }
+
+    /// 暗号化ファイルに退避していた秘密をキーリングへ移す。移せた件数を返す。
+    pub fn migrate_to_keyring(&self) -> Result<usize, ApiError> {
+        let mut migrated = 0usize;
+        for reference in self.file.references()? {
+            let Some(secret) = self.file.read_secret(&reference)? else {
+                continue;
+            };
+            if let Err(err) = self.keyring.write_verified(&reference, &secret) {
+                self.warn_fallback(&err);
+                break;
+            }
+            self.file.delete_secret(&reference)?;
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+}
+
+impl SecretStore for FallbackSecretStore {
+    fn store_secret(&self, path: &str, secret: &str) -> Result<String, ApiError> {
+        let reference = build_reference(path);
+        if let Err(err) = self.keyring.write_verified(&reference, secret) {
+            self.warn_fallback(&err);
+            self.file.write(&reference, secret)?;
+        }
+        Ok(reference)
+    }
+
+    fn read_secret(&self, reference: &str) -> Result<Option<String>, ApiError> {
+        match self.keyring.read_secret(reference) {
+            Ok(Some(secret)) => Ok(Some(secret)),
+            Ok(None) => self.file.read_secret(reference),
+            Err(err) => {
+                self.warn_fallback(&err);
+                self.file.read_secret(reference)
+            }
+        }
+    }
+
+    fn delete_secret(&self, reference: &str) -> Result<(), ApiError> {
+        let keyring_result = self.keyring.delete_secret(reference);
+        self.file.delete_secret(reference)?;
+        if let Err(err) = keyring_result {
+            self.warn_fallback(&err);
+        }
+        Ok(())
+    }
+}
+
 pub fn is_sensitive_key(key: &str) -> bool {
     let key_lower = key.to_lowercase();
     if SENSITIVE_WHITELIST
@@ -103,6 +364,12 @@ pub fn is_sensitive_key(key: &str) -> bool {
         .any(|pattern| key_lower.contains(pattern))
 }
 
+/// 環境変数名向けの判定。`OPENAI_KEY` のような `_KEY` 終わりも秘密として扱う。
+pub fn is_sensitive_env_key(key: &str) -> bool {
+    let key_lower = key.to_lowercase();
+    is_sensitive_key(&key_lower) || key_lower.ends_with("_key") || key_lower == "key"
+}
+
 pub fn is_keyring_reference(value: &str) -> bool {
     value.starts_with(KEYRING_PREFIX)
 }
@@ -358,7 +625,38 @@ impl SecretStore for MemorySecretStore {
 
 #[cfg(test)]
 mod tests {
-    use super::{is_keyring_reference, is_sensitive_key};
+    use super::{
+        is_keyring_reference, is_sensitive_env_key, is_sensitive_key, EncryptedFileSecretStore,
+        SecretStore,
+    };
+
+    #[test]
+    fn encrypted_file_store_round_trips_without_plaintext_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.enc.json");
+        let store = EncryptedFileSecretStore::new(path.clone(), dir.path().join("secrets.key"));
+
+        let reference = store.store_secret("llm.api_key", "sk-very-secret").unwrap();
+        assert!(is_keyring_reference(&reference));
+        assert_eq!(
+            store.read_secret(&reference).unwrap().as_deref(),
+            Some("sk-very-secret")
+        );
+        assert!(!std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("sk-very-secret"));
+
+        store.delete_secret(&reference).unwrap();
+        assert_eq!(store.read_secret(&reference).unwrap(), None);
+    }
+
+    #[test]
+    fn env_keys_ending_in_key_are_sensitive() {
+        assert!(is_sensitive_env_key("OPENAI_API_KEY"));
+        assert!(is_sensitive_env_key("BRAVE_KEY"));
+        assert!(is_sensitive_env_key("GITHUB_PERSONAL_ACCESS_TOKEN"));
+        assert!(!is_sensitive_env_key("LOG_LEVEL"));
+    }
 
     #[test]
     fn sensitive_key_uses_whitelist() {
@@ -1,19 +1,27 @@
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use serde_json::{Map, Value};
 
 use super::defaults::generate_default_characters;
-use super::migrator::migrate_to_current;
+use super::env_overrides::{apply_env_overrides, process_env_overrides};
+use super::migrator::{migrate_to_current, migrate_with_report};
 use super::paths::AppPaths;
+use super::profiles::{
+    apply_profile_overlay, profile_overlay, resolve_active_profile, startup_profile_override,
+    validate_profile_name, ActiveProfile, ACTIVE_PROFILE_KEY,
+};
+use super::schema::TeporaConfig;
 use super::secrets::{
     is_sensitive_key, materialize_sensitive_references, resolve_sensitive_references,
-    rotate_sensitive_references, OsSecretStore, SecretStore,
+    rotate_sensitive_references, FallbackSecretStore, SecretStore,
 };
 use super::validation::validate_config;
+use super::watch::{watch_files, ConfigChangeEvent, ConfigChangeHub, ConfigChangeSource};
 use crate::core::errors::ApiError;
+use crate::core::snapshots::write_with_snapshot;
 
 const REDACT_PLACEHOLDER: &str = "****";
 
@@ -21,13 +29,18 @@ const REDACT_PLACEHOLDER: &str = "****";
 pub struct ConfigService {
     paths: Arc<AppPaths>,
     secret_store: Arc<dyn SecretStore>,
+    changes: Arc<ConfigChangeHub>,
+    /// 起動時に `TEPORA_PROFILE` で指定されたプロファイル
+    profile_override: Arc<RwLock<Option<String>>>,
 }
 
 impl ConfigService {
     pub fn new(paths: Arc<AppPaths>) -> Self {
         Self {
+            secret_store: Arc::new(FallbackSecretStore::for_data_dir(&paths.user_data_dir)),
             paths,
-            secret_store: Arc::new(OsSecretStore),
+            changes: Arc::new(ConfigChangeHub::new()),
+            profile_override: Arc::new(RwLock::new(startup_profile_override())),
         }
     }
 
@@ -36,6 +49,8 @@ impl ConfigService {
         Self {
             paths,
             secret_store,
+            changes: Arc::new(ConfigChangeHub::new()),
+            profile_override: Arc::new(RwLock::new(None)),
         }
     }
 
@@ -65,13 +80,39 @@ impl ConfigService {
         self.paths.user_data_dir.join("config.yml")
     }
 
+    /// 設定以外（MCP の環境変数など）の秘密も同じストアに置く。
+    pub fn secret_store(&self) -> &dyn SecretStore {
+        self.secret_store.as_ref()
+    }
+
     pub fn secrets_path(&self) -> PathBuf {
         self.paths.secrets_path.clone()
     }
 
+    /// 実行時に使う設定。基本設定 < プロファイル < `TEPORA__*` 環境変数の順に重ねる。
     pub fn load_config(&self) -> Result<Value, ApiError> {
+        let mut config = self.load_base_config()?;
+        let mut layered = self.overlay_active_profile(&mut config);
+        let overrides = process_env_overrides();
+        if !overrides.is_empty() {
+            apply_env_overrides(&mut config, overrides);
+            layered = true;
+        }
+        if layered {
+            validate_config(&config).map_err(|err| match err {
+                ApiError::BadRequest(message) if !overrides.is_empty() => ApiError::BadRequest(
+                    format!("{} (check TEPORA__* environment overrides)", message),
+                ),
+                other => other,
+            })?;
+        }
+        Ok(config)
+    }
+
+    /// プロファイルを重ねる前の設定。設定画面の編集対象はこちら。
+    pub fn load_base_config(&self) -> Result<Value, ApiError> {
         let mut storage_config = self.load_storage_config();
-        if migrate_to_current(&mut storage_config, self.secret_store.as_ref())? {
+        if self.migrate_storage(&mut storage_config)? {
             save_config_files(self, &storage_config)?;
         }
 
@@ -83,9 +124,75 @@ impl ConfigService {
         Ok(resolved)
     }
 
+    pub fn active_profile(&self) -> Option<ActiveProfile> {
+        let env_override = self
+            .profile_override
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        resolve_active_profile(&self.load_storage_config(), env_override.as_deref())
+    }
+
+    /// 使うプロファイルを切り替える。`None` なら基本設定だけで動く。
+    /// 選択は `active_profile` として保存し、起動時の環境変数指定は解除する。
+    pub fn select_profile(&self, name: Option<&str>) -> Result<(), ApiError> {
+        if let Some(name) = name {
+            validate_profile_name(name)?;
+            if profile_overlay(&self.load_storage_config(), name).is_none() {
+                return Err(ApiError::NotFound(format!("Profile '{}' not found", name)));
+            }
+        }
+        self.changes.prime(&self.effective_storage_config());
+        *self
+            .profile_override
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = None;
+        self.modify_config(|config| {
+            let Some(root) = config.as_object_mut() else {
+                return Err(ApiError::BadRequest("Config root must be an object".into()));
+            };
+            match name {
+                Some(name) => {
+                    root.insert(ACTIVE_PROFILE_KEY.to_string(), Value::String(name.into()));
+                }
+                None => {
+                    root.remove(ACTIVE_PROFILE_KEY);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn overlay_active_profile(&self, config: &mut Value) -> bool {
+        let Some(active) = self.active_profile() else {
+            return false;
+        };
+        if apply_profile_overlay(config, &active.name) {
+            return true;
+        }
+        tracing::warn!(
+            profile = %active.name,
+            "Selected config profile does not exist; using base config"
+        );
+        false
+    }
+
+    /// 変更通知の比較に使う、プロファイル適用後の保存形式の設定。
+    fn effective_storage_config(&self) -> Value {
+        let mut config = self.load_storage_config();
+        self.overlay_active_profile(&mut config);
+        config
+    }
+
+    /// 型付きの設定。型の合わないキーがあればそのパスを含むエラーを返す。
+    pub fn load_typed(&self) -> Result<TeporaConfig, ApiError> {
+        TeporaConfig::from_value(&self.load_config()?)
+    }
+
     pub fn update_config(&self, config_data: Value, merge: bool) -> Result<(), ApiError> {
         let mut current_storage = self.load_storage_config();
-        let _ = migrate_to_current(&mut current_storage, self.secret_store.as_ref())?;
+        self.changes.prime(&self.effective_storage_config());
+        let _ = self.migrate_storage(&mut current_storage)?;
 
         let restored = restore_redacted_values(&config_data, &current_storage);
         let mut to_save = if merge {
@@ -105,21 +212,110 @@ impl ConfigService {
         validate_config(&resolved_for_validation)?;
 
         save_config_files(self, &to_save)?;
+        self.publish_change(ConfigChangeSource::Api);
+        Ok(())
+    }
+
+    /// Apply an in-place edit to the stored config (keys can be removed,
+    /// unlike with merge updates). The result is validated before saving.
+    pub fn modify_config<F>(&self, apply: F) -> Result<(), ApiError>
+    where
+        F: FnOnce(&mut Value) -> Result<(), ApiError>,
+    {
+        let mut storage_config = self.load_storage_config();
+        self.changes.prime(&self.effective_storage_config());
+        let _ = self.migrate_storage(&mut storage_config)?;
+        ensure_default_characters(&mut storage_config);
+
+        apply(&mut storage_config)?;
+
+        let mut resolved_for_validation = storage_config.clone();
+        resolve_sensitive_references(&mut resolved_for_validation, self.secret_store.as_ref())?;
+        ensure_default_characters(&mut resolved_for_validation);
+        validate_config(&resolved_for_validation)?;
+
+        save_config_files(self, &storage_config)?;
+        self.publish_change(ConfigChangeSource::Api);
         Ok(())
     }
 
     pub fn rotate_secrets(&self) -> Result<usize, ApiError> {
         let mut storage_config = self.load_storage_config();
-        let migrated = migrate_to_current(&mut storage_config, self.secret_store.as_ref())?;
+        self.changes.prime(&self.effective_storage_config());
+        let migrated = self.migrate_storage(&mut storage_config)?;
         let rotated = rotate_sensitive_references(&mut storage_config, self.secret_store.as_ref())?;
 
         if migrated || rotated > 0 {
             save_config_files(self, &storage_config)?;
+            self.publish_change(ConfigChangeSource::Api);
         }
 
         Ok(rotated)
     }
 
+    pub fn subscribe_changes(&self) -> tokio::sync::broadcast::Receiver<ConfigChangeEvent> {
+        self.changes.subscribe()
+    }
+
+    /// 設定ファイルの手動編集を監視する。二度目以降の呼び出しは何もしない。
+    pub fn start_watching(&self) -> Result<(), ApiError> {
+        if self.changes.is_watching() {
+            return Ok(());
+        }
+        self.changes.prime(&self.effective_storage_config());
+        let files = vec![
+            self.config_path(),
+            self.config_write_path(),
+            self.secrets_path(),
+        ];
+        let service = self.clone();
+        let watcher =
+            watch_files(files, move || service.reload_from_disk()).map_err(ApiError::internal)?;
+        self.changes.set_watcher(watcher);
+        Ok(())
+    }
+
+    /// ファイルが編集されたときの読み直し。壊れた YAML や検証エラーの間は
+    /// 通知せず、直った時点で差分を流す。
+    fn reload_from_disk(&self) {
+        for path in [self.config_path(), self.secrets_path()] {
+            if let Err(err) = parse_yaml_file_strict(&path) {
+                tracing::warn!(path = %path.display(), "Ignoring config edit: {}", err);
+                return;
+            }
+        }
+        if let Err(err) = self.load_config() {
+            tracing::warn!("Ignoring config edit that fails validation: {}", err);
+            return;
+        }
+        if let Some(event) = self
+            .changes
+            .publish(self.effective_storage_config(), ConfigChangeSource::File)
+        {
+            tracing::info!(
+                sections = ?event.changed_sections,
+                "Reloaded config after file change"
+            );
+        }
+    }
+
+    /// ファイルから読んだ設定を現行レイアウトへ上げる。レイアウトが変わるときは
+    /// 書き戻す前に元のファイルを退避し、移行内容をログに残す。
+    /// 秘密情報のファイルは平文の写しを増やさないよう退避しない。
+    fn migrate_storage(&self, storage_config: &mut Value) -> Result<bool, ApiError> {
+        let (changed, report) = migrate_with_report(storage_config, self.secret_store.as_ref())?;
+        if report.upgraded() {
+            backup_before_migration(&self.config_path(), report.from_version);
+            report.log();
+        }
+        Ok(changed)
+    }
+
+    fn publish_change(&self, source: ConfigChangeSource) {
+        self.changes
+            .publish(self.effective_storage_config(), source);
+    }
+
     pub fn redact_sensitive_values(&self, value: &Value) -> Value {
         redact_sensitive_values(value)
     }
@@ -148,6 +344,39 @@ fn load_yaml_file(path: &Path) -> Value {
     }
 }
 
+/// `config.yml` -> `config.yml.v2-20250101T000000.bak`
+fn backup_before_migration(path: &Path, from_version: u64) {
+    if !path.exists() {
+        return;
+    }
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return;
+    };
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S");
+    let backup = path.with_file_name(format!("{file_name}.v{from_version}-{stamp}.bak"));
+    match fs::copy(path, &backup) {
+        Ok(_) => tracing::info!(
+            backup = %backup.display(),
+            "Backed up config before migration"
+        ),
+        Err(err) => tracing::warn!(
+            path = %path.display(),
+            "Failed to back up config before migration: {}",
+            err
+        ),
+    }
+}
+
+fn parse_yaml_file_strict(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    serde_yaml::from_str::<Value>(&contents)
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
 fn ensure_default_characters(config: &mut Value) {
     if let Some(obj) = config.as_object_mut() {
         let needs_defaults = match obj.get("characters") {
@@ -173,19 +402,19 @@ fn save_config_files(service: &ConfigService, config: &Value) -> Result<(), ApiE
         let _ = fs::create_dir_all(parent);
     }
     let public_yaml = serde_yaml::to_string(&public_config).map_err(ApiError::internal)?;
-    fs::write(&config_path, public_yaml).map_err(ApiError::internal)?;
+    write_with_snapshot(&config_path, &public_yaml)?;
 
     let secrets_path = service.secrets_path();
     if let Some(parent) = secrets_path.parent() {
         let _ = fs::create_dir_all(parent);
     }
     let secrets_yaml = serde_yaml::to_string(&secrets_config).map_err(ApiError::internal)?;
-    fs::write(&secrets_path, secrets_yaml).map_err(ApiError::internal)?;
+    write_with_snapshot(&secrets_path, &secrets_yaml)?;
 
     Ok(())
 }
 
-fn deep_merge(base: &Value, override_value: &Value) -> Value {
+pub(super) fn deep_merge(base: &Value, override_value: &Value) -> Value {
     match (base, override_value) {
         (Value::Object(base_map), Value::Object(override_map)) => {
             let mut merged: Map<String, Value> = base_map.clone();
@@ -235,6 +464,53 @@ fn split_config(config: &Value) -> (Value, Value) {
     }
 }
 
+/// 2 つの設定の差分を `{path, before, after}` の列で返す（監査ログ用）。
+/// 秘密のキーは値を伏せ、変わったことだけが分かるようにする。
+pub fn config_diff(before: &Value, after: &Value) -> Vec<Value> {
+    let mut changes = Vec::new();
+    diff_walk(before, after, "", false, &mut changes);
+    changes
+}
+
+fn diff_walk(before: &Value, after: &Value, path: &str, sensitive: bool, out: &mut Vec<Value>) {
+    if before == after {
+        return;
+    }
+    if let (Value::Object(old), Value::Object(new)) = (before, after) {
+        let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            let next_path = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{path}.{key}")
+            };
+            diff_walk(
+                old.get(key).unwrap_or(&Value::Null),
+                new.get(key).unwrap_or(&Value::Null),
+                &next_path,
+                sensitive || is_sensitive_key(key),
+                out,
+            );
+        }
+        return;
+    }
+
+    let shown = |value: &Value| {
+        if sensitive && !value.is_null() {
+            Value::String(REDACT_PLACEHOLDER.to_string())
+        } else {
+            redact_sensitive_values(value)
+        }
+    };
+    out.push(serde_json::json!({
+        "path": path,
+        "before": shown(before),
+        "after": shown(after),
+    }));
+}
+
 fn redact_sensitive_values(value: &Value) -> Value {
     match value {
         Value::Object(map) => {
@@ -336,6 +612,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn config_diff_lists_changed_paths_and_hides_secrets() {
+        let before = json!({
+            "llm": { "api_key": "old-key", "model": "a" },
+            "app": { "language": "ja" }
+        });
+        let after = json!({
+            "llm": { "api_key": "new-key", "model": "b" },
+            "app": { "language": "ja", "theme": "dark" }
+        });
+
+        let changes = config_diff(&before, &after);
+        assert_eq!(
+            changes,
+            vec![
+                json!({"path": "app.theme", "before": null, "after": "dark"}),
+                json!({"path": "llm.api_key", "before": "****", "after": "****"}),
+                json!({"path": "llm.model", "before": "a", "after": "b"}),
+            ]
+        );
+        assert!(!serde_json::to_string(&changes).unwrap().contains("new-key"));
+    }
+
     #[test]
     fn split_config_separates_sensitive_values() {
         let input = json!({
@@ -0,0 +1,193 @@
+//! 同じマシンを使う複数ユーザーのプロファイル。
+//!
+//! ユーザーごとに Tepora のホームディレクトリを分け、履歴・記憶・ペルソナ・
+//! プロジェクトを丸ごと別にする。既存の `default` ユーザーは従来どおり
+//! `~/.tepora` を使い、追加したユーザーは `~/.tepora/users/<id>` を使う。
+//!
+//! 選択の優先順位は、起動時の `TEPORA_USER` > `users.json` の `last_user`。
+//! デスクトップシェルはログイン時に選んだユーザーを `last_user` に書いて再起動する。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::errors::ApiError;
+
+pub const USER_ENV_VAR: &str = "TEPORA_USER";
+pub const DEFAULT_USER_ID: &str = "default";
+const USERS_DIR: &str = "users";
+const REGISTRY_FILE: &str = "users.json";
+const MAX_USER_ID_LEN: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserProfile {
+    pub id: String,
+    pub display_name: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserRegistry {
+    #[serde(default)]
+    pub users: Vec<UserProfile>,
+    #[serde(default)]
+    pub last_user: Option<String>,
+}
+
+impl UserRegistry {
+    pub fn path(tepora_home: &Path) -> PathBuf {
+        tepora_home.join(REGISTRY_FILE)
+    }
+
+    /// 読めない・無いときは `default` だけの一覧として扱う。
+    pub fn load(tepora_home: &Path) -> Self {
+        fs::read_to_string(Self::path(tepora_home))
+            .ok()
+            .and_then(|body| serde_json::from_str(&body).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, tepora_home: &Path) -> Result<(), ApiError> {
+        fs::create_dir_all(tepora_home).map_err(ApiError::internal)?;
+        let body = serde_json::to_string_pretty(self).map_err(ApiError::internal)?;
+        fs::write(Self::path(tepora_home), body).map_err(ApiError::internal)
+    }
+
+    /// 登録済みのユーザー。`default` は登録していなくても常に含める。
+    pub fn profiles(&self) -> Vec<UserProfile> {
+        let mut profiles = self.users.clone();
+        if !profiles.iter().any(|user| user.id == DEFAULT_USER_ID) {
+            profiles.insert(
+                0,
+                UserProfile {
+                    id: DEFAULT_USER_ID.to_string(),
+                    display_name: "Default".to_string(),
+                    created_at: String::new(),
+                },
+            );
+        }
+        profiles
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        id == DEFAULT_USER_ID || self.users.iter().any(|user| user.id == id)
+    }
+
+    pub fn create(&mut self, id: &str, display_name: &str) -> Result<UserProfile, ApiError> {
+        validate_user_id(id)?;
+        if self.contains(id) {
+            return Err(ApiError::Conflict(format!("User '{}' already exists", id)));
+        }
+        let display_name = display_name.trim();
+        let profile = UserProfile {
+            id: id.to_string(),
+            display_name: if display_name.is_empty() {
+                id.to_string()
+            } else {
+                display_name.to_string()
+            },
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        self.users.push(profile.clone());
+        Ok(profile)
+    }
+
+    pub fn select(&mut self, id: &str) -> Result<(), ApiError> {
+        if !self.contains(id) {
+            return Err(ApiError::NotFound(format!("User '{}' not found", id)));
+        }
+        self.last_user = Some(id.to_string());
+        Ok(())
+    }
+}
+
+pub fn validate_user_id(id: &str) -> Result<(), ApiError> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_USER_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ApiError::BadRequest(format!(
+            "Invalid user id '{}': use up to {} lowercase letters, digits, '-' or '_'",
+            id, MAX_USER_ID_LEN
+        )))
+    }
+}
+
+/// ユーザーのホームディレクトリ。`default` は共有ホームそのもの。
+pub fn user_home(tepora_home: &Path, id: &str) -> PathBuf {
+    if id == DEFAULT_USER_ID {
+        tepora_home.to_path_buf()
+    } else {
+        tepora_home.join(USERS_DIR).join(id)
+    }
+}
+
+/// 環境変数の指定があればそれを、無ければ最後に選ばれたユーザーを返す。
+pub fn resolve_active_user(registry: &UserRegistry, env_override: Option<&str>) -> String {
+    env_override
+        .map(str::trim)
+        .filter(|id| validate_user_id(id).is_ok())
+        .or(registry.last_user.as_deref())
+        .filter(|id| registry.contains(id))
+        .unwrap_or(DEFAULT_USER_ID)
+        .to_string()
+}
+
+/// このプロセスが使うユーザー。
+pub fn active_user_id() -> String {
+    let registry = UserRegistry::load(&super::paths::tepora_home_dir());
+    resolve_active_user(&registry, std::env::var(USER_ENV_VAR).ok().as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn users_get_separate_homes_and_default_keeps_shared_home() {
+        let home = Path::new("/tmp/tepora");
+        assert_eq!(user_home(home, DEFAULT_USER_ID), home);
+        assert_eq!(user_home(home, "alice"), home.join("users").join("alice"));
+        assert!(validate_user_id("alice_2").is_ok());
+        assert!(validate_user_id("../alice").is_err());
+        assert!(validate_user_id("Alice").is_err());
+    }
+
+    #[test]
+    fn environment_beats_last_user_and_unknown_users_fall_back() {
+        let mut registry = UserRegistry::default();
+        registry.create("alice", "Alice").unwrap();
+        registry.create("bob", "").unwrap();
+        assert!(registry.create("alice", "again").is_err());
+        registry.select("bob").unwrap();
+        assert!(registry.select("carol").is_err());
+
+        assert_eq!(resolve_active_user(&registry, None), "bob");
+        assert_eq!(resolve_active_user(&registry, Some("alice")), "alice");
+        assert_eq!(
+            resolve_active_user(&registry, Some("carol")),
+            DEFAULT_USER_ID
+        );
+        assert_eq!(registry.profiles().len(), 3);
+        assert_eq!(registry.profiles()[2].display_name, "bob");
+    }
+
+    #[test]
+    fn registry_round_trips_through_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut registry = UserRegistry::load(dir.path());
+        assert_eq!(registry.profiles()[0].id, DEFAULT_USER_ID);
+        registry.create("alice", "Alice").unwrap();
+        registry.select("alice").unwrap();
+        registry.save(dir.path()).unwrap();
+
+        let loaded = UserRegistry::load(dir.path());
+        assert_eq!(loaded.last_user.as_deref(), Some("alice"));
+        assert!(loaded.contains("alice"));
+    }
+}
@@ -1,14 +1,23 @@
 use crate::core::errors::ApiError;
-use serde_json::Value;
+use serde_json::{Map, Value};
 
-use super::validation_primitives::expect_optional_object;
+use super::profiles::{validate_profile_name, ACTIVE_PROFILE_KEY, MODEL_ROLES_KEY, PROFILES_KEY};
+use super::schema::TeporaConfig;
+use super::validation_primitives::{
+    config_type_error, expect_optional_object, validate_optional_string_field,
+};
 use super::validation_sections::{
     validate_agent_section, validate_agent_skills_section, validate_app_section,
-    validate_backup_section, validate_characters_section, validate_context_window_section,
-    validate_credentials_section, validate_features_section, validate_llm_defaults_section,
-    validate_llm_manager_section, validate_model_download_section, validate_models_section,
-    validate_permissions_section, validate_privacy_section, validate_quarantine_section,
-    validate_rag_section, validate_search_section, validate_server_section, validate_tools_section,
+    validate_backup_section, validate_characters_section, validate_context_budget_section,
+    validate_context_window_section, validate_credentials_section, validate_desktop_section,
+    validate_features_section, validate_llm_defaults_section, validate_llm_manager_section,
+    validate_maintenance_section, validate_mcp_marketplace_section,
+    validate_model_download_section, validate_models_section, validate_network_section,
+    validate_notifications_section, validate_permissions_section, validate_plugins_section,
+    validate_privacy_section, validate_quarantine_section, validate_rag_section,
+    validate_scripting_section, validate_search_section, validate_server_section,
+    validate_session_defaults_section, validate_system_prompt_section, validate_tools_section,
+    validate_tts_section, validate_updates_section,
 };
 
 pub fn validate_config(config: &Value) -> Result<(), ApiError> {
@@ -68,6 +77,46 @@ pub fn validate_config(config: &Value) -> Result<(), ApiError> {
         validate_quarantine_section(quarantine)?;
     }
 
+    if let Some(desktop) = expect_optional_object(root, "desktop")? {
+        validate_desktop_section(desktop)?;
+    }
+
+    if let Some(notifications) = expect_optional_object(root, "notifications")? {
+        validate_notifications_section(notifications)?;
+    }
+
+    if let Some(updates) = expect_optional_object(root, "updates")? {
+        validate_updates_section(updates)?;
+    }
+
+    if let Some(tts) = expect_optional_object(root, "tts")? {
+        validate_tts_section(tts)?;
+    }
+
+    if let Some(maintenance) = expect_optional_object(root, "maintenance")? {
+        validate_maintenance_section(maintenance)?;
+    }
+
+    if let Some(session_defaults) = expect_optional_object(root, "session_defaults")? {
+        validate_session_defaults_section(session_defaults, "session_defaults")?;
+    }
+
+    if let Some(network) = expect_optional_object(root, "network")? {
+        validate_network_section(network)?;
+    }
+
+    if let Some(plugins) = expect_optional_object(root, "plugins")? {
+        validate_plugins_section(plugins)?;
+    }
+
+    if let Some(marketplace) = expect_optional_object(root, "mcp_marketplace")? {
+        validate_mcp_marketplace_section(marketplace)?;
+    }
+
+    if let Some(scripting) = expect_optional_object(root, "scripting")? {
+        validate_scripting_section(scripting)?;
+    }
+
     let models_key = if root.contains_key("models") {
         "models"
     } else {
@@ -95,5 +144,63 @@ pub fn validate_config(config: &Value) -> Result<(), ApiError> {
         validate_context_window_section(context_window)?;
     }
 
+    if let Some(context_budget) = expect_optional_object(root, "context_budget")? {
+        validate_context_budget_section(context_budget)?;
+    }
+
+    if let Some(system_prompt) = expect_optional_object(root, "system_prompt")? {
+        validate_system_prompt_section(system_prompt)?;
+    }
+
+    validate_profiles(root)?;
+
+    // 個別チェックをすり抜けた型の不一致もここで拾う
+    TeporaConfig::from_value(config)?;
+
+    Ok(())
+}
+
+/// プロファイルは基本設定に重ねた結果が有効な設定になっているかまで確かめる。
+fn validate_profiles(root: &Map<String, Value>) -> Result<(), ApiError> {
+    validate_optional_string_field(root, ACTIVE_PROFILE_KEY, ACTIVE_PROFILE_KEY)?;
+    if let Some(roles) = expect_optional_object(root, MODEL_ROLES_KEY)? {
+        for (key, value) in roles {
+            if !value.is_string() {
+                return Err(config_type_error(
+                    &format!("{}.{}", MODEL_ROLES_KEY, key),
+                    "string",
+                ));
+            }
+        }
+    }
+    let Some(profiles) = expect_optional_object(root, PROFILES_KEY)? else {
+        return Ok(());
+    };
+
+    let mut base = root.clone();
+    base.remove(PROFILES_KEY);
+    base.remove(ACTIVE_PROFILE_KEY);
+    let base = Value::Object(base);
+
+    for (name, overlay) in profiles {
+        let path = format!("{}.{}", PROFILES_KEY, name);
+        validate_profile_name(name)?;
+        let Some(overlay) = overlay.as_object() else {
+            return Err(config_type_error(&path, "object"));
+        };
+        if overlay.contains_key(PROFILES_KEY) || overlay.contains_key(ACTIVE_PROFILE_KEY) {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid config at '{}': profiles cannot nest '{}' or '{}'",
+                path, PROFILES_KEY, ACTIVE_PROFILE_KEY
+            )));
+        }
+        let merged = super::service::deep_merge(&base, &Value::Object(overlay.clone()));
+        validate_config(&merged).map_err(|err| match err {
+            ApiError::BadRequest(message) => {
+                ApiError::BadRequest(format!("Profile '{}': {}", name, message))
+            }
+            other => other,
+        })?;
+    }
     Ok(())
 }
@@ -62,6 +62,28 @@ pub(super) fn validate_number_field(
     Err(config_type_error(path, "number"))
 }
 
+pub(super) fn validate_f64_field(
+    section: &Map<String, Value>,
+    path: &str,
+    key: &str,
+    min: f64,
+    max: f64,
+) -> Result<(), ApiError> {
+    let Some(value) = section.get(key) else {
+        return Ok(());
+    };
+    let Some(number) = value.as_f64() else {
+        return Err(config_type_error(path, "number"));
+    };
+    if !(min..=max).contains(&number) {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid config at '{}': must be between {} and {}",
+            path, min, max
+        )));
+    }
+    Ok(())
+}
+
 pub(super) fn validate_i64_field(
     section: &Map<String, Value>,
     path: &str,
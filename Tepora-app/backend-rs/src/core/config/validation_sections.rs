@@ -1,10 +1,13 @@
+use super::schema::{NetworkSubsystem, PluginCapability};
+use crate::context::prompt::PromptBlockKind;
 use crate::core::errors::ApiError;
 use serde_json::{Map, Value};
 
 use super::validation_primitives::{
-    config_type_error, expect_optional_object, validate_bool_field, validate_i64_field,
-    validate_number_field, validate_optional_string_field, validate_required_string_field,
-    validate_string_array_field, validate_string_enum_field, validate_u64_field,
+    config_type_error, expect_optional_object, validate_bool_field, validate_f64_field,
+    validate_i64_field, validate_number_field, validate_optional_string_field,
+    validate_required_string_field, validate_string_array_field, validate_string_enum_field,
+    validate_u64_field,
 };
 
 pub(super) fn validate_app_section(section: &Map<String, Value>) -> Result<(), ApiError> {
@@ -65,6 +68,41 @@ pub(super) fn validate_app_section(section: &Map<String, Value>) -> Result<(), A
         3_600_000,
     )?;
     validate_u64_field(section, "app.history_limit", "history_limit", 1, 1_000)?;
+    validate_u64_field(
+        section,
+        "app.slow_request_warn_ms",
+        "slow_request_warn_ms",
+        100,
+        3_600_000,
+    )?;
+    validate_u64_field(
+        section,
+        "app.first_token_watchdog_ms",
+        "first_token_watchdog_ms",
+        0,
+        600_000,
+    )?;
+    validate_u64_field(
+        section,
+        "app.max_concurrent_generations",
+        "max_concurrent_generations",
+        1,
+        64,
+    )?;
+    validate_u64_field(
+        section,
+        "app.max_queued_messages_per_session",
+        "max_queued_messages_per_session",
+        1,
+        100,
+    )?;
+    validate_u64_field(
+        section,
+        "app.stale_run_max_age_secs",
+        "stale_run_max_age_secs",
+        0,
+        604_800,
+    )?;
     validate_u64_field(
         section,
         "app.entity_extraction_limit",
@@ -72,6 +110,45 @@ pub(super) fn validate_app_section(section: &Map<String, Value>) -> Result<(), A
         1,
         100,
     )?;
+    validate_bool_field(
+        section,
+        "app.conversation_summary_enabled",
+        "conversation_summary_enabled",
+    )?;
+    validate_u64_field(
+        section,
+        "app.conversation_summary_keep_recent",
+        "conversation_summary_keep_recent",
+        1,
+        1_000,
+    )?;
+    validate_u64_field(
+        section,
+        "app.conversation_summary_batch",
+        "conversation_summary_batch",
+        1,
+        1_000,
+    )?;
+    validate_u64_field(
+        section,
+        "app.conversation_summary_max_tokens",
+        "conversation_summary_max_tokens",
+        32,
+        8_192,
+    )?;
+    if let Some(value) = section.get("graph_entry_points") {
+        let entry_points = value
+            .as_object()
+            .ok_or_else(|| config_type_error("app.graph_entry_points", "object"))?;
+        for (mode, node) in entry_points {
+            if node.as_str().is_none_or(|node| node.trim().is_empty()) {
+                return Err(config_type_error(
+                    &format!("app.graph_entry_points.{}", mode),
+                    "non-empty string",
+                ));
+            }
+        }
+    }
     Ok(())
 }
 
@@ -119,6 +196,13 @@ pub(super) fn validate_llm_manager_section(section: &Map<String, Value>) -> Resu
         1,
         3_600_000,
     )?;
+    validate_u64_field(
+        section,
+        "llm_manager.parallel_slots",
+        "parallel_slots",
+        1,
+        64,
+    )?;
     validate_u64_field(
         section,
         "llm_manager.stream_channel_buffer",
@@ -133,11 +217,87 @@ pub(super) fn validate_llm_manager_section(section: &Map<String, Value>) -> Resu
         1,
         65_536,
     )?;
+    validate_u64_field(
+        section,
+        "llm_manager.http_pool_max_idle_per_host",
+        "http_pool_max_idle_per_host",
+        0,
+        1_024,
+    )?;
+    validate_u64_field(
+        section,
+        "llm_manager.http_pool_idle_timeout_ms",
+        "http_pool_idle_timeout_ms",
+        1_000,
+        3_600_000,
+    )?;
+    validate_u64_field(
+        section,
+        "llm_manager.http_tcp_keepalive_ms",
+        "http_tcp_keepalive_ms",
+        1_000,
+        3_600_000,
+    )?;
+    validate_u64_field(
+        section,
+        "llm_manager.idle_unload_timeout_ms",
+        "idle_unload_timeout_ms",
+        0,
+        86_400_000,
+    )?;
+    validate_bool_field(
+        section,
+        "llm_manager.keep_embedding_loaded",
+        "keep_embedding_loaded",
+    )?;
+    validate_u64_field(
+        section,
+        "llm_manager.max_continuation_rounds",
+        "max_continuation_rounds",
+        0,
+        8,
+    )?;
+    validate_u64_field(
+        section,
+        "llm_manager.retry_max_attempts",
+        "retry_max_attempts",
+        1,
+        10,
+    )?;
+    validate_u64_field(
+        section,
+        "llm_manager.retry_base_delay_ms",
+        "retry_base_delay_ms",
+        1,
+        60_000,
+    )?;
+    validate_u64_field(
+        section,
+        "llm_manager.retry_max_delay_ms",
+        "retry_max_delay_ms",
+        1,
+        300_000,
+    )?;
+    validate_u64_field(
+        section,
+        "llm_manager.circuit_failure_threshold",
+        "circuit_failure_threshold",
+        1,
+        100,
+    )?;
+    validate_u64_field(
+        section,
+        "llm_manager.circuit_open_ms",
+        "circuit_open_ms",
+        1_000,
+        3_600_000,
+    )?;
     Ok(())
 }
 
 pub(super) fn validate_server_section(section: &Map<String, Value>) -> Result<(), ApiError> {
     validate_optional_string_field(section, "server.host", "host")?;
+    validate_u64_field(section, "server.port", "port", 1, 65_535)?;
     validate_string_array_field(section, "server.allowed_origins", "allowed_origins")?;
     validate_string_array_field(
         section,
@@ -163,11 +323,57 @@ pub(super) fn validate_privacy_section(section: &Map<String, Value>) -> Result<(
         validate_optional_string_field(lockdown, "privacy.lockdown.updated_at", "updated_at")?;
         validate_optional_string_field(lockdown, "privacy.lockdown.reason", "reason")?;
     }
+    if let Some(redaction) = expect_optional_object(section, "redaction")? {
+        validate_redaction(redaction)?;
+    }
+    Ok(())
+}
+
+fn validate_redaction(redaction: &Map<String, Value>) -> Result<(), ApiError> {
+    use crate::llm::redaction::REDACTION_TRUST_LEVELS;
+
+    for key in ["enabled", "secrets", "pii", "paths"] {
+        validate_bool_field(redaction, &format!("privacy.redaction.{}", key), key)?;
+    }
+    validate_string_array_field(
+        redaction,
+        "privacy.redaction.custom_patterns",
+        "custom_patterns",
+    )?;
+    for pattern in redaction
+        .get("custom_patterns")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+    {
+        if let Err(err) = regex::Regex::new(pattern) {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid config at 'privacy.redaction.custom_patterns': {}",
+                err
+            )));
+        }
+    }
+    if let Some(trust) = expect_optional_object(redaction, "provider_trust")? {
+        for provider in trust.keys() {
+            validate_string_enum_field(
+                trust,
+                &format!("privacy.redaction.provider_trust.{}", provider),
+                provider,
+                REDACTION_TRUST_LEVELS,
+            )?;
+        }
+    }
     Ok(())
 }
 
 pub(super) fn validate_search_section(section: &Map<String, Value>) -> Result<(), ApiError> {
-    validate_bool_field(section, "search.embedding_rerank", "embedding_rerank")
+    validate_bool_field(section, "search.embedding_rerank", "embedding_rerank")?;
+    validate_number_field(
+        section,
+        "search.source_quality_weight",
+        "source_quality_weight",
+    )
 }
 
 pub(super) fn validate_rag_section(section: &Map<String, Value>) -> Result<(), ApiError> {
@@ -211,6 +417,29 @@ pub(super) fn validate_agent_section(section: &Map<String, Value>) -> Result<(),
         1,
         1_000_000,
     )?;
+    validate_bool_field(section, "agent.plan_approval", "plan_approval")?;
+    validate_u64_field(
+        section,
+        "agent.plan_review_seconds",
+        "plan_review_seconds",
+        0,
+        600,
+    )?;
+    validate_number_field(
+        section,
+        "agent.plan_approval_min_cost_usd",
+        "plan_approval_min_cost_usd",
+    )?;
+    if section
+        .get("plan_approval_min_cost_usd")
+        .and_then(Value::as_f64)
+        .is_some_and(|cost| cost < 0.0)
+    {
+        return Err(config_type_error(
+            "agent.plan_approval_min_cost_usd",
+            "non-negative number",
+        ));
+    }
     Ok(())
 }
 
@@ -219,8 +448,62 @@ pub(super) fn validate_tools_section(section: &Map<String, Value>) -> Result<(),
         section,
         "tools.search_provider",
         "search_provider",
-        &["google", "duckduckgo", "brave", "bing"],
+        crate::tools::search::SEARCH_PROVIDER_NAMES,
+    )?;
+    if let Some(value) = section.get("workspace_roots") {
+        let Some(roots) = value.as_array() else {
+            return Err(config_type_error("tools.workspace_roots", "array"));
+        };
+        for (index, root_value) in roots.iter().enumerate() {
+            let path_prefix = format!("tools.workspace_roots[{}]", index);
+            let root_entry = root_value
+                .as_object()
+                .ok_or_else(|| config_type_error(&path_prefix, "object"))?;
+            validate_required_string_field(root_entry, &format!("{}.path", path_prefix), "path")?;
+            validate_optional_string_field(root_entry, &format!("{}.label", path_prefix), "label")?;
+            validate_bool_field(root_entry, &format!("{}.enabled", path_prefix), "enabled")?;
+            validate_bool_field(
+                root_entry,
+                &format!("{}.read_only", path_prefix),
+                "read_only",
+            )?;
+        }
+    }
+    validate_u64_field(
+        section,
+        "tools.file_read_max_bytes",
+        "file_read_max_bytes",
+        1024,
+        64 * 1024 * 1024,
     )?;
+    if let Some(shell) = expect_optional_object(section, "shell")? {
+        validate_string_array_field(shell, "tools.shell.allowed_binaries", "allowed_binaries")?;
+        validate_string_array_field(
+            shell,
+            "tools.shell.auto_approve_patterns",
+            "auto_approve_patterns",
+        )?;
+        validate_u64_field(shell, "tools.shell.timeout_secs", "timeout_secs", 1, 600)?;
+        validate_u64_field(
+            shell,
+            "tools.shell.max_output_bytes",
+            "max_output_bytes",
+            256,
+            16 * 1024 * 1024,
+        )?;
+    }
+    if let Some(feeds) = expect_optional_object(section, "feeds")? {
+        validate_feeds(feeds)?;
+    }
+    if let Some(http_tools) = expect_optional_object(section, "http_tools")? {
+        validate_http_tools(http_tools)?;
+    }
+    if let Some(search) = expect_optional_object(section, "search")? {
+        validate_search_providers(search)?;
+        validate_optional_string_field(search, "tools.search.searxng_url", "searxng_url")?;
+        validate_u64_field(search, "tools.search.max_results", "max_results", 1, 50)?;
+        validate_u64_field(search, "tools.search.timeout_secs", "timeout_secs", 1, 120)?;
+    }
     validate_optional_string_field(
         section,
         "tools.brave_search_api_key",
@@ -237,6 +520,162 @@ pub(super) fn validate_tools_section(section: &Map<String, Value>) -> Result<(),
         "tools.google_search_engine_id",
         "google_search_engine_id",
     )?;
+    validate_u64_field(
+        section,
+        "tools.result_fold_max_chars",
+        "result_fold_max_chars",
+        256,
+        1_000_000,
+    )?;
+    validate_u64_field(
+        section,
+        "tools.result_fold_head_lines",
+        "result_fold_head_lines",
+        1,
+        10_000,
+    )?;
+    validate_u64_field(
+        section,
+        "tools.result_fold_tail_lines",
+        "result_fold_tail_lines",
+        0,
+        10_000,
+    )?;
+    validate_u64_field(
+        section,
+        "tools.result_fold_array_sample",
+        "result_fold_array_sample",
+        1,
+        100,
+    )?;
+    Ok(())
+}
+
+fn validate_feeds(feeds: &Map<String, Value>) -> Result<(), ApiError> {
+    if let Some(value) = feeds.get("subscriptions") {
+        let Some(subscriptions) = value.as_array() else {
+            return Err(config_type_error("tools.feeds.subscriptions", "array"));
+        };
+        for (index, entry) in subscriptions.iter().enumerate() {
+            let path_prefix = format!("tools.feeds.subscriptions[{}]", index);
+            match entry {
+                Value::String(_) => {}
+                Value::Object(map) => {
+                    validate_required_string_field(map, &format!("{}.url", path_prefix), "url")?;
+                    validate_optional_string_field(
+                        map,
+                        &format!("{}.title", path_prefix),
+                        "title",
+                    )?;
+                    validate_bool_field(map, &format!("{}.enabled", path_prefix), "enabled")?;
+                }
+                _ => return Err(config_type_error(&path_prefix, "string or object")),
+            }
+        }
+    }
+    if let Some(ingest) = expect_optional_object(feeds, "ingest")? {
+        validate_bool_field(ingest, "tools.feeds.ingest.enabled", "enabled")?;
+        validate_u64_field(
+            ingest,
+            "tools.feeds.ingest.interval_minutes",
+            "interval_minutes",
+            5,
+            24 * 60,
+        )?;
+        validate_optional_string_field(ingest, "tools.feeds.ingest.collection", "collection")?;
+    }
+    Ok(())
+}
+
+fn validate_http_tools(http_tools: &Map<String, Value>) -> Result<(), ApiError> {
+    use crate::tools::http_api::{
+        is_valid_http_tool_name, HTTP_TOOL_BODY_MODES, HTTP_TOOL_METHODS,
+    };
+
+    for (name, entry) in http_tools {
+        let path_prefix = format!("tools.http_tools.{}", name);
+        if !is_valid_http_tool_name(name) {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid config at '{}': tool names use letters, digits, '_' or '-' and must not shadow native tools",
+                path_prefix
+            )));
+        }
+        let entry = entry
+            .as_object()
+            .ok_or_else(|| config_type_error(&path_prefix, "object"))?;
+        validate_required_string_field(entry, &format!("{}.url", path_prefix), "url")?;
+        validate_optional_string_field(
+            entry,
+            &format!("{}.description", path_prefix),
+            "description",
+        )?;
+        validate_optional_string_field(entry, &format!("{}.method", path_prefix), "method")?;
+        if let Some(method) = entry.get("method").and_then(Value::as_str) {
+            if !HTTP_TOOL_METHODS.contains(&method.to_ascii_uppercase().as_str()) {
+                return Err(ApiError::BadRequest(format!(
+                    "Invalid config at '{}.method': expected one of {}",
+                    path_prefix,
+                    HTTP_TOOL_METHODS.join(", ")
+                )));
+            }
+        }
+        validate_string_enum_field(
+            entry,
+            &format!("{}.body", path_prefix),
+            "body",
+            HTTP_TOOL_BODY_MODES,
+        )?;
+        if let Some(headers) = expect_optional_object(entry, "headers")? {
+            for key in headers.keys() {
+                validate_required_string_field(
+                    headers,
+                    &format!("{}.headers.{}", path_prefix, key),
+                    key,
+                )?;
+            }
+        }
+        if let Some(schema) = entry.get("params_schema") {
+            if jsonschema::validator_for(schema).is_err() {
+                return Err(config_type_error(
+                    &format!("{}.params_schema", path_prefix),
+                    "valid JSON Schema",
+                ));
+            }
+        }
+        validate_bool_field(entry, &format!("{}.enabled", path_prefix), "enabled")?;
+        validate_u64_field(
+            entry,
+            &format!("{}.timeout_secs", path_prefix),
+            "timeout_secs",
+            1,
+            300,
+        )?;
+        validate_u64_field(
+            entry,
+            &format!("{}.max_response_chars", path_prefix),
+            "max_response_chars",
+            256,
+            200_000,
+        )?;
+    }
+    Ok(())
+}
+
+fn validate_search_providers(search: &Map<String, Value>) -> Result<(), ApiError> {
+    validate_string_array_field(search, "tools.search.providers", "providers")?;
+    let Some(providers) = search.get("providers").and_then(Value::as_array) else {
+        return Ok(());
+    };
+    let allowed = crate::tools::search::SEARCH_PROVIDER_NAMES;
+    for (index, provider) in providers.iter().filter_map(Value::as_str).enumerate() {
+        if !allowed.contains(&provider.trim()) {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid config at 'tools.search.providers[{}]': expected one of {}",
+                index,
+                allowed.join(", ")
+            )));
+        }
+    }
     Ok(())
 }
 
@@ -336,6 +775,235 @@ pub(super) fn validate_quarantine_section(section: &Map<String, Value>) -> Resul
     Ok(())
 }
 
+pub(super) fn validate_desktop_section(section: &Map<String, Value>) -> Result<(), ApiError> {
+    validate_bool_field(section, "desktop.quick_ask_enabled", "quick_ask_enabled")?;
+    validate_optional_string_field(section, "desktop.quick_ask_shortcut", "quick_ask_shortcut")?;
+    Ok(())
+}
+
+pub(super) fn validate_notifications_section(section: &Map<String, Value>) -> Result<(), ApiError> {
+    validate_bool_field(section, "notifications.enabled", "enabled")?;
+    validate_bool_field(section, "notifications.agent_runs", "agent_runs")?;
+    validate_bool_field(section, "notifications.downloads", "downloads")?;
+    validate_bool_field(section, "notifications.binary_updates", "binary_updates")?;
+    Ok(())
+}
+
+pub(super) fn validate_tts_section(section: &Map<String, Value>) -> Result<(), ApiError> {
+    validate_bool_field(section, "tts.enabled", "enabled")
+}
+
+pub(super) fn validate_maintenance_section(section: &Map<String, Value>) -> Result<(), ApiError> {
+    validate_bool_field(section, "maintenance.db_auto", "db_auto")?;
+    validate_u64_field(
+        section,
+        "maintenance.db_interval_days",
+        "db_interval_days",
+        1,
+        365,
+    )?;
+    validate_bool_field(section, "maintenance.topics_auto", "topics_auto")
+}
+
+pub(super) fn validate_session_defaults_section(
+    section: &Map<String, Value>,
+    path_prefix: &str,
+) -> Result<(), ApiError> {
+    validate_optional_string_field(section, &format!("{}.mode", path_prefix), "mode")?;
+    validate_optional_string_field(
+        section,
+        &format!("{}.persona_id", path_prefix),
+        "persona_id",
+    )?;
+    validate_string_array_field(
+        section,
+        &format!("{}.rag_collections", path_prefix),
+        "rag_collections",
+    )?;
+    let temperature = section.get("temperature").filter(|value| !value.is_null());
+    if temperature.is_some_and(|value| value.as_f64().is_none_or(|t| !(0.0..=2.0).contains(&t))) {
+        return Err(config_type_error(
+            &format!("{}.temperature", path_prefix),
+            "number between 0 and 2",
+        ));
+    }
+    Ok(())
+}
+
+pub(super) fn validate_updates_section(section: &Map<String, Value>) -> Result<(), ApiError> {
+    validate_string_enum_field(section, "updates.channel", "channel", &["stable", "beta"])?;
+    validate_bool_field(section, "updates.auto_check", "auto_check")?;
+    Ok(())
+}
+
+pub(super) fn validate_network_section(section: &Map<String, Value>) -> Result<(), ApiError> {
+    validate_bool_field(section, "network.offline", "offline")?;
+    validate_string_array_field(
+        section,
+        "network.allow_while_offline",
+        "allow_while_offline",
+    )?;
+    let names: Vec<&str> = NetworkSubsystem::ALL.iter().map(|s| s.as_str()).collect();
+    if let Some(allowed) = section.get("allow_while_offline").and_then(Value::as_array) {
+        for (index, name) in allowed.iter().filter_map(Value::as_str).enumerate() {
+            if !names.contains(&name) {
+                return Err(ApiError::BadRequest(format!(
+                    "Invalid config at 'network.allow_while_offline[{}]': expected one of {}",
+                    index,
+                    names.join(", ")
+                )));
+            }
+        }
+    }
+    let Some(proxy) = expect_optional_object(section, "proxy")? else {
+        return Ok(());
+    };
+    validate_proxy_url(proxy, "network.proxy.url")?;
+    validate_string_array_field(proxy, "network.proxy.only_hosts", "only_hosts")?;
+    validate_string_array_field(proxy, "network.proxy.no_proxy", "no_proxy")?;
+    for key in ["subsystems", "providers"] {
+        let Some(overrides) = expect_optional_object(proxy, key)? else {
+            continue;
+        };
+        for (name, entry) in overrides {
+            let path = format!("network.proxy.{}.{}", key, name);
+            if key == "subsystems" && !names.contains(&name.as_str()) {
+                return Err(ApiError::BadRequest(format!(
+                    "Invalid config at '{}': expected one of {}",
+                    path,
+                    names.join(", ")
+                )));
+            }
+            let Some(entry) = entry.as_object() else {
+                return Err(config_type_error(&path, "object"));
+            };
+            validate_proxy_url(entry, &format!("{}.url", path))?;
+            validate_bool_field(entry, &format!("{}.direct", path), "direct")?;
+        }
+    }
+    Ok(())
+}
+
+fn validate_proxy_url(section: &Map<String, Value>, path: &str) -> Result<(), ApiError> {
+    validate_optional_string_field(section, path, "url")?;
+    let Some(raw) = section
+        .get("url")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|raw| !raw.is_empty())
+    else {
+        return Ok(());
+    };
+    let valid = reqwest::Url::parse(raw).is_ok_and(|url| {
+        matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h") && url.has_host()
+    });
+    if !valid {
+        return Err(config_type_error(
+            path,
+            "proxy URL (http, https, socks5 or socks5h)",
+        ));
+    }
+    Ok(())
+}
+
+pub(super) fn validate_plugins_section(section: &Map<String, Value>) -> Result<(), ApiError> {
+    validate_bool_field(section, "plugins.enabled", "enabled")?;
+    validate_u64_field(
+        section,
+        "plugins.fuel_per_call",
+        "fuel_per_call",
+        1_000_000,
+        u64::MAX,
+    )?;
+    validate_u64_field(section, "plugins.max_memory_mb", "max_memory_mb", 1, 4096)?;
+    validate_u64_field(
+        section,
+        "plugins.call_timeout_ms",
+        "call_timeout_ms",
+        100,
+        600_000,
+    )?;
+    let Some(grants) = expect_optional_object(section, "grants")? else {
+        return Ok(());
+    };
+    let names: Vec<&str> = PluginCapability::ALL.iter().map(|c| c.as_str()).collect();
+    for (plugin_id, granted) in grants {
+        let path = format!("plugins.grants.{}", plugin_id);
+        let Some(granted) = granted.as_array() else {
+            return Err(config_type_error(&path, "array"));
+        };
+        for (index, name) in granted.iter().enumerate() {
+            if !name.as_str().is_some_and(|name| names.contains(&name)) {
+                return Err(ApiError::BadRequest(format!(
+                    "Invalid config at '{}[{}]': expected one of {}",
+                    path,
+                    index,
+                    names.join(", ")
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn validate_mcp_marketplace_section(
+    section: &Map<String, Value>,
+) -> Result<(), ApiError> {
+    validate_bool_field(section, "mcp_marketplace.official", "official")?;
+    validate_bool_field(section, "mcp_marketplace.curated", "curated")?;
+    validate_string_array_field(section, "mcp_marketplace.recommended", "recommended")?;
+    let Some(sources) = section.get("sources") else {
+        return Ok(());
+    };
+    let Some(sources) = sources.as_array() else {
+        return Err(config_type_error("mcp_marketplace.sources", "array"));
+    };
+    let mut names = Vec::new();
+    for (index, source) in sources.iter().enumerate() {
+        let path = format!("mcp_marketplace.sources[{}]", index);
+        let Some(source) = source.as_object() else {
+            return Err(config_type_error(&path, "object"));
+        };
+        validate_required_string_field(source, &format!("{}.name", path), "name")?;
+        validate_required_string_field(source, &format!("{}.url", path), "url")?;
+        let url = source
+            .get("url")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if !reqwest::Url::parse(url.trim())
+            .is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+        {
+            return Err(config_type_error(&format!("{}.url", path), "http(s) URL"));
+        }
+        let name = source
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if names.contains(&name) || matches!(name, "official" | "curated") {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid config at '{}.name': source name '{}' is reserved or already used",
+                path, name
+            )));
+        }
+        names.push(name);
+    }
+    Ok(())
+}
+
+pub(super) fn validate_scripting_section(section: &Map<String, Value>) -> Result<(), ApiError> {
+    validate_bool_field(section, "scripting.enabled", "enabled")?;
+    validate_string_array_field(section, "scripting.allowed_tools", "allowed_tools")?;
+    validate_u64_field(
+        section,
+        "scripting.max_operations",
+        "max_operations",
+        1_000,
+        100_000_000,
+    )?;
+    validate_u64_field(section, "scripting.timeout_ms", "timeout_ms", 10, 60_000)?;
+    Ok(())
+}
+
 pub(super) fn validate_models_section(
     root: &Map<String, Value>,
     models_key: &str,
@@ -412,6 +1080,28 @@ pub(super) fn validate_characters_section(section: &Map<String, Value>) -> Resul
             &format!("{}.avatar_path", path_prefix),
             "avatar_path",
         )?;
+        validate_string_array_field(
+            entry,
+            &format!("{}.style_rules", path_prefix),
+            "style_rules",
+        )?;
+        validate_optional_string_field(entry, &format!("{}.greeting", path_prefix), "greeting")?;
+        validate_optional_string_field(
+            entry,
+            &format!("{}.model_role", path_prefix),
+            "model_role",
+        )?;
+        if let Some(language) = expect_optional_object(entry, "language")
+            .map_err(|_| config_type_error(&format!("{}.language", path_prefix), "object"))?
+        {
+            for key in ["reply", "search"] {
+                validate_optional_string_field(
+                    language,
+                    &format!("{}.language.{}", path_prefix, key),
+                    key,
+                )?;
+            }
+        }
     }
     Ok(())
 }
@@ -431,6 +1121,94 @@ pub(super) fn validate_agent_skills_section(section: &Map<String, Value>) -> Res
             validate_optional_string_field(root_entry, &format!("{}.label", path_prefix), "label")?;
         }
     }
+    if let Some(policies) = expect_optional_object(section, "tool_policies")
+        .map_err(|_| config_type_error("agent_skills.tool_policies", "object"))?
+    {
+        for (skill_id, policy) in policies {
+            let path_prefix = format!("agent_skills.tool_policies.{}", skill_id);
+            let policy = policy
+                .as_object()
+                .ok_or_else(|| config_type_error(&path_prefix, "object"))?;
+            validate_bool_field(policy, &format!("{}.allow_all", path_prefix), "allow_all")?;
+            for key in ["allowed_tools", "denied_tools", "require_confirmation"] {
+                validate_string_array_field(policy, &format!("{}.{}", path_prefix, key), key)?;
+            }
+            if let Some(capabilities) =
+                expect_optional_object(policy, "capabilities").map_err(|_| {
+                    config_type_error(&format!("{}.capabilities", path_prefix), "object")
+                })?
+            {
+                validate_bool_field(
+                    capabilities,
+                    &format!("{}.capabilities.network", path_prefix),
+                    "network",
+                )?;
+                validate_string_enum_field(
+                    capabilities,
+                    &format!("{}.capabilities.filesystem", path_prefix),
+                    "filesystem",
+                    &["none", "read", "write"],
+                )?;
+            }
+        }
+    }
+    if let Some(overrides) = expect_optional_object(section, "graph_overrides")
+        .map_err(|_| config_type_error("agent_skills.graph_overrides", "object"))?
+    {
+        for (skill_id, entry) in overrides {
+            let path_prefix = format!("agent_skills.graph_overrides.{}", skill_id);
+            let entry = entry
+                .as_object()
+                .ok_or_else(|| config_type_error(&path_prefix, "object"))?;
+            validate_u64_field(
+                entry,
+                &format!("{}.max_steps", path_prefix),
+                "max_steps",
+                1,
+                50,
+            )?;
+        }
+    }
+    if let Some(evaluation) = expect_optional_object(section, "evaluation")
+        .map_err(|_| config_type_error("agent_skills.evaluation", "object"))?
+    {
+        validate_bool_field(evaluation, "agent_skills.evaluation.enabled", "enabled")?;
+    }
+    if let Some(policies) = expect_optional_object(section, "memory_policies")
+        .map_err(|_| config_type_error("agent_skills.memory_policies", "object"))?
+    {
+        for (skill_id, entry) in policies {
+            let path_prefix = format!("agent_skills.memory_policies.{}", skill_id);
+            let entry = entry
+                .as_object()
+                .ok_or_else(|| config_type_error(&path_prefix, "object"))?;
+            validate_string_enum_field(
+                entry,
+                &format!("{}.mode", path_prefix),
+                "mode",
+                &["none", "session", "persistent"],
+            )?;
+            validate_optional_string_field(
+                entry,
+                &format!("{}.namespace", path_prefix),
+                "namespace",
+            )?;
+            validate_u64_field(
+                entry,
+                &format!("{}.retrieval_k", path_prefix),
+                "retrieval_k",
+                0,
+                50,
+            )?;
+            validate_f64_field(
+                entry,
+                &format!("{}.recency_boost", path_prefix),
+                "recency_boost",
+                0.0,
+                1.0,
+            )?;
+        }
+    }
     Ok(())
 }
 
@@ -467,6 +1245,77 @@ pub(super) fn validate_context_window_section(
     validate_context_window_config(section, "context_window")
 }
 
+pub(super) fn validate_context_budget_section(
+    section: &Map<String, Value>,
+) -> Result<(), ApiError> {
+    validate_bool_field(section, "context_budget.enabled", "enabled")?;
+    if let Some(priorities) = expect_optional_object(section, "priorities")
+        .map_err(|_| config_type_error("context_budget.priorities", "object"))?
+    {
+        for (key, _) in priorities {
+            validate_u64_field(
+                priorities,
+                &format!("context_budget.priorities.{}", key),
+                key,
+                0,
+                1_000,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn validate_system_prompt_section(section: &Map<String, Value>) -> Result<(), ApiError> {
+    if let Some(variables) = expect_optional_object(section, "variables")
+        .map_err(|_| config_type_error("system_prompt.variables", "object"))?
+    {
+        for (key, value) in variables {
+            if value.as_str().is_none() {
+                return Err(config_type_error(
+                    &format!("system_prompt.variables.{}", key),
+                    "string",
+                ));
+            }
+        }
+    }
+
+    let Some(blocks) = section.get("blocks") else {
+        return Ok(());
+    };
+    let Some(blocks) = blocks.as_array() else {
+        return Err(config_type_error("system_prompt.blocks", "array"));
+    };
+    for (index, block) in blocks.iter().enumerate() {
+        let path = format!("system_prompt.blocks[{}]", index);
+        let Some(block) = block.as_object() else {
+            return Err(config_type_error(&path, "object"));
+        };
+        validate_required_string_field(block, &format!("{}.kind", path), "kind")?;
+        validate_string_enum_field(
+            block,
+            &format!("{}.kind", path),
+            "kind",
+            &PromptBlockKind::NAMES,
+        )?;
+        validate_optional_string_field(block, &format!("{}.id", path), "id")?;
+        validate_bool_field(block, &format!("{}.enabled", path), "enabled")?;
+        validate_optional_string_field(block, &format!("{}.template", path), "template")?;
+
+        let is_custom = block.get("kind").and_then(Value::as_str) == Some("custom");
+        let has_template = block
+            .get("template")
+            .and_then(Value::as_str)
+            .is_some_and(|template| !template.trim().is_empty());
+        if is_custom && !has_template {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid config at '{}.template': custom blocks require a template",
+                path
+            )));
+        }
+    }
+    Ok(())
+}
+
 fn validate_permission_map(
     root: &Map<String, Value>,
     path: &str,
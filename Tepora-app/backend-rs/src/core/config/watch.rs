@@ -0,0 +1,197 @@
+//! 設定変更の検知と通知。
+//!
+//! API 経由の更新と、`config.yml` / `secrets.yaml` の手動編集の両方を
+//! [`ConfigChangeEvent`] として broadcast する。購読側（CORS、メモリ設定、
+//! WebSocket クライアントなど）はイベントを受けて設定を読み直す。
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+const CHANGE_EVENT_CAPACITY: usize = 32;
+/// エディタの保存は複数イベントに分かれるので、静かになるまで待ってから読む
+const FILE_EVENT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigChangeSource {
+    Api,
+    File,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigChangeEvent {
+    pub revision: u64,
+    pub source: ConfigChangeSource,
+    /// 値が変わったトップレベルのセクション名
+    pub changed_sections: Vec<String>,
+    pub at: DateTime<Utc>,
+}
+
+pub(super) struct ConfigChangeHub {
+    tx: broadcast::Sender<ConfigChangeEvent>,
+    snapshot: Mutex<Option<Value>>,
+    revision: AtomicU64,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl ConfigChangeHub {
+    pub(super) fn new() -> Self {
+        Self {
+            tx: broadcast::channel(CHANGE_EVENT_CAPACITY).0,
+            snapshot: Mutex::new(None),
+            revision: AtomicU64::new(0),
+            watcher: Mutex::new(None),
+        }
+    }
+
+    pub(super) fn subscribe(&self) -> broadcast::Receiver<ConfigChangeEvent> {
+        self.tx.subscribe()
+    }
+
+    /// 比較の基準をまだ持っていなければ `current` を基準にする。
+    pub(super) fn prime(&self, current: &Value) {
+        let mut snapshot = self.snapshot.lock().unwrap_or_else(|e| e.into_inner());
+        if snapshot.is_none() {
+            *snapshot = Some(current.clone());
+        }
+    }
+
+    /// 基準との差分があればイベントを送り、基準を更新する。
+    pub(super) fn publish(
+        &self,
+        current: Value,
+        source: ConfigChangeSource,
+    ) -> Option<ConfigChangeEvent> {
+        let changed_sections = {
+            let mut snapshot = self.snapshot.lock().unwrap_or_else(|e| e.into_inner());
+            let changed = changed_sections(snapshot.as_ref(), &current);
+            *snapshot = Some(current);
+            changed
+        };
+        if changed_sections.is_empty() {
+            return None;
+        }
+        let event = ConfigChangeEvent {
+            revision: self.revision.fetch_add(1, Ordering::Relaxed) + 1,
+            source,
+            changed_sections,
+            at: Utc::now(),
+        };
+        // 購読者がいないのは通常の状態
+        let _ = self.tx.send(event.clone());
+        Some(event)
+    }
+
+    pub(super) fn is_watching(&self) -> bool {
+        self.watcher
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some()
+    }
+
+    pub(super) fn set_watcher(&self, watcher: RecommendedWatcher) {
+        *self.watcher.lock().unwrap_or_else(|e| e.into_inner()) = Some(watcher);
+    }
+}
+
+pub(super) fn changed_sections(before: Option<&Value>, after: &Value) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after_map = after.as_object().unwrap_or(&empty);
+    let keys: BTreeSet<&String> = before.keys().chain(after_map.keys()).collect();
+    keys.into_iter()
+        .filter(|key| before.get(*key) != after_map.get(*key))
+        .cloned()
+        .collect()
+}
+
+/// `files` のいずれかが変わったら、落ち着いたところで `on_change` を呼ぶ。
+pub(super) fn watch_files(
+    files: Vec<PathBuf>,
+    on_change: impl Fn() + Send + 'static,
+) -> notify::Result<RecommendedWatcher> {
+    let (tx, rx) = mpsc::channel::<()>();
+    let targets = files.clone();
+    let mut watcher = RecommendedWatcher::new(
+        move |result: Result<Event, notify::Error>| {
+            let Ok(event) = result else {
+                return;
+            };
+            if event.paths.iter().any(|path| is_target(path, &targets)) {
+                let _ = tx.send(());
+            }
+        },
+        Config::default(),
+    )?;
+
+    let mut dirs: Vec<&Path> = files.iter().filter_map(|file| file.parent()).collect();
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs {
+        if dir.exists() {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+    }
+
+    std::thread::Builder::new()
+        .name("config-watch".to_string())
+        .spawn(move || {
+            while rx.recv().is_ok() {
+                while rx.recv_timeout(FILE_EVENT_DEBOUNCE).is_ok() {}
+                on_change();
+            }
+        })
+        .map_err(|err| notify::Error::generic(&err.to_string()))?;
+
+    Ok(watcher)
+}
+
+fn is_target(path: &Path, targets: &[PathBuf]) -> bool {
+    targets.iter().any(|target| {
+        path == target.as_path()
+            || (path.file_name() == target.file_name() && path.parent() == target.parent())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn only_changed_sections_are_reported() {
+        let before = json!({ "app": { "a": 1 }, "server": { "host": "x" }, "old": true });
+        let after = json!({ "app": { "a": 2 }, "server": { "host": "x" }, "new": 1 });
+        assert_eq!(
+            changed_sections(Some(&before), &after),
+            vec!["app", "new", "old"]
+        );
+    }
+
+    #[test]
+    fn publish_skips_identical_configs() {
+        let hub = ConfigChangeHub::new();
+        let mut rx = hub.subscribe();
+        hub.prime(&json!({ "app": { "a": 1 } }));
+
+        assert!(hub
+            .publish(json!({ "app": { "a": 1 } }), ConfigChangeSource::File)
+            .is_none());
+        let event = hub
+            .publish(json!({ "app": { "a": 3 } }), ConfigChangeSource::Api)
+            .expect("changed");
+        assert_eq!(event.revision, 1);
+        assert_eq!(event.changed_sections, vec!["app"]);
+        assert_eq!(rx.try_recv().unwrap().source, ConfigChangeSource::Api);
+    }
+}
@@ -0,0 +1,355 @@
+//! SQLite の定期メンテナンス。
+//!
+//! 履歴・RAG（全体とプロジェクトごと）・エピソード記憶の各 DB に対して
+//! `integrity_check`、空きページの回収、`ANALYZE` を順に行い、結果をまとめて返す。
+//! 長く使うと DB は断片化して膨らむため、`maintenance.db_auto` が有効なら
+//! `maintenance.db_interval_days` ごとに自動でも走らせる。
+//! `auto_vacuum` が無効な DB は初回だけ `VACUUM` して `INCREMENTAL` に切り替え、
+//! 以後は `incremental_vacuum` で済ませる。
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use sqlx::{ConnectOptions, Connection, Row};
+
+use crate::core::config::AppPaths;
+use crate::core::errors::ApiError;
+use crate::history::NewInboxItem;
+use crate::state::{jobs, AppState};
+
+const MAINTENANCE_TICK: Duration = Duration::from_secs(60 * 60);
+/// `integrity_check` が返す問題の最大件数
+const INTEGRITY_MAX_ERRORS: i64 = 20;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DbMaintenanceReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub databases: Vec<DbReport>,
+}
+
+impl DbMaintenanceReport {
+    pub fn healthy(&self) -> bool {
+        self.databases
+            .iter()
+            .all(|db| db.error.is_none() && db.integrity.iter().all(|line| line == "ok"))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DbReport {
+    /// `history` / `rag` / `rag:<project_id>` / `memory`
+    pub name: String,
+    pub path: String,
+    /// 問題が無ければ `["ok"]`
+    pub integrity: Vec<String>,
+    /// `none` / `full` / `incremental`（メンテナンス後の値）
+    pub auto_vacuum: String,
+    /// 初回の切り替えで `VACUUM` を実行したか
+    pub full_vacuum: bool,
+    pub size_before: u64,
+    pub size_after: u64,
+    pub freelist_before: i64,
+    pub freelist_after: i64,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MaintenanceState {
+    last_run: Option<DateTime<Utc>>,
+}
+
+/// メンテナンス対象の DB。存在するものだけを返す。
+pub fn database_paths(paths: &AppPaths) -> Vec<(String, PathBuf)> {
+    let mut databases = vec![
+        ("history".to_string(), paths.db_path.clone()),
+        ("rag".to_string(), paths.user_data_dir.join("rag.db")),
+        (
+            "memory".to_string(),
+            paths.user_data_dir.join("episodic_memory.db"),
+        ),
+    ];
+    if let Ok(entries) = std::fs::read_dir(paths.tepora_home()) {
+        let mut projects = entries
+            .flatten()
+            .filter(|entry| entry.path() != paths.user_data_dir)
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect::<Vec<_>>();
+        projects.sort();
+        for project_id in projects {
+            databases.push((
+                format!("rag:{}", project_id),
+                paths.project_rag_db_path(&project_id),
+            ));
+        }
+    }
+    databases.retain(|(_, path)| path.is_file());
+    databases
+}
+
+/// 全 DB を順にメンテナンスする。1 つが失敗しても残りは続ける。
+pub async fn run_maintenance(paths: &AppPaths) -> DbMaintenanceReport {
+    let started_at = Utc::now();
+    let mut databases = Vec::new();
+    for (name, path) in database_paths(paths) {
+        let started = std::time::Instant::now();
+        let mut report = DbReport {
+            name,
+            path: path.to_string_lossy().to_string(),
+            size_before: file_size(&path),
+            ..DbReport::default()
+        };
+        if let Err(err) = maintain_database(&path, &mut report).await {
+            tracing::warn!("Database maintenance failed for {}: {}", report.name, err);
+            report.error = Some(err.to_string());
+        }
+        report.size_after = file_size(&path);
+        report.duration_ms = started.elapsed().as_millis() as u64;
+        databases.push(report);
+    }
+    if let Err(err) = save_state(
+        &state_path(&paths.user_data_dir),
+        &MaintenanceState {
+            last_run: Some(started_at),
+        },
+    ) {
+        tracing::warn!("Failed to record database maintenance: {}", err);
+    }
+    DbMaintenanceReport {
+        started_at,
+        finished_at: Utc::now(),
+        databases,
+    }
+}
+
+async fn maintain_database(path: &Path, report: &mut DbReport) -> Result<(), ApiError> {
+    let mut conn = SqliteConnectOptions::new()
+        .filename(path)
+        .busy_timeout(Duration::from_secs(30))
+        .connect()
+        .await
+        .map_err(ApiError::internal)?;
+
+    report.integrity = sqlx::query(&format!("PRAGMA integrity_check({})", INTEGRITY_MAX_ERRORS))
+        .fetch_all(&mut conn)
+        .await
+        .map_err(ApiError::internal)?
+        .iter()
+        .map(|row| row.get::<String, _>(0))
+        .collect();
+    report.freelist_before = pragma_i64(&mut conn, "freelist_count").await?;
+
+    if pragma_i64(&mut conn, "auto_vacuum").await? != 2 {
+        // auto_vacuum の変更は VACUUM を挟まないと反映されない
+        sqlx::query("PRAGMA auto_vacuum = INCREMENTAL")
+            .execute(&mut conn)
+            .await
+            .map_err(ApiError::internal)?;
+        sqlx::query("VACUUM")
+            .execute(&mut conn)
+            .await
+            .map_err(ApiError::internal)?;
+        report.full_vacuum = true;
+    } else {
+        sqlx::query("PRAGMA incremental_vacuum")
+            .execute(&mut conn)
+            .await
+            .map_err(ApiError::internal)?;
+    }
+    sqlx::query("ANALYZE")
+        .execute(&mut conn)
+        .await
+        .map_err(ApiError::internal)?;
+    // WAL に溜まった分を本体へ戻して縮める
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(&mut conn)
+        .await
+        .map_err(ApiError::internal)?;
+
+    report.freelist_after = pragma_i64(&mut conn, "freelist_count").await?;
+    report.auto_vacuum = match pragma_i64(&mut conn, "auto_vacuum").await? {
+        1 => "full",
+        2 => "incremental",
+        _ => "none",
+    }
+    .to_string();
+    conn.close().await.map_err(ApiError::internal)
+}
+
+async fn pragma_i64(conn: &mut SqliteConnection, pragma: &str) -> Result<i64, ApiError> {
+    sqlx::query(&format!("PRAGMA {}", pragma))
+        .fetch_one(conn)
+        .await
+        .map(|row| row.get::<i64, _>(0))
+        .map_err(ApiError::internal)
+}
+
+/// 本体と WAL を合わせたサイズ。
+fn file_size(path: &Path) -> u64 {
+    let wal = PathBuf::from(format!("{}-wal", path.to_string_lossy()));
+    [path, wal.as_path()]
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+fn state_path(user_data_dir: &Path) -> PathBuf {
+    user_data_dir.join("db_maintenance_state.json")
+}
+
+fn load_state(path: &Path) -> MaintenanceState {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(path: &Path, state: &MaintenanceState) -> Result<(), ApiError> {
+    let serialized = serde_json::to_string_pretty(state).map_err(ApiError::internal)?;
+    std::fs::write(path, serialized).map_err(ApiError::internal)
+}
+
+/// `maintenance.db_interval_days` ごとに全 DB をメンテナンスする常駐タスク。
+/// 設定は毎回読み直すので、有効化・間隔変更は再起動なしで反映される。
+pub fn spawn_db_maintenance(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(MAINTENANCE_TICK);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let settings = state
+                .core()
+                .config
+                .load_typed()
+                .unwrap_or_default()
+                .maintenance;
+            if !settings.db_auto {
+                continue;
+            }
+            let paths = state.core().paths.clone();
+            let interval = chrono::Duration::days(settings.db_interval_days as i64);
+            let due = load_state(&state_path(&paths.user_data_dir))
+                .last_run
+                .is_none_or(|last| Utc::now().signed_duration_since(last) >= interval);
+            if !due {
+                continue;
+            }
+            let job_id = uuid::Uuid::new_v4().to_string();
+            jobs::begin(
+                &state,
+                &job_id,
+                jobs::KIND_DB_MAINTENANCE,
+                "Database maintenance",
+                serde_json::Value::Null,
+            )
+            .await;
+            let report = run_maintenance(&paths).await;
+            let healthy = report.healthy();
+            let failure = (!healthy).then_some("Database maintenance found problems");
+            jobs::finish(&state, &job_id, failure).await;
+            if healthy {
+                tracing::info!(
+                    databases = report.databases.len(),
+                    "Scheduled database maintenance finished"
+                );
+            } else {
+                tracing::warn!("Scheduled database maintenance found problems");
+            }
+            let reclaimed = report
+                .databases
+                .iter()
+                .map(|db| db.size_before.saturating_sub(db.size_after))
+                .sum::<u64>();
+            let item = NewInboxItem {
+                kind: "db_maintenance".to_string(),
+                title: if healthy {
+                    "Database maintenance finished".to_string()
+                } else {
+                    "Database maintenance found problems".to_string()
+                },
+                summary: format!(
+                    "Checked {} database(s), reclaimed {} KiB.",
+                    report.databases.len(),
+                    reclaimed / 1024
+                ),
+                success: healthy,
+                ..NewInboxItem::default()
+            };
+            if let Err(err) = state.runtime().inbox.deposit(item).await {
+                tracing::warn!("Failed to add database maintenance to inbox: {}", err);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn maintenance_checks_and_compacts_databases() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().join("data");
+        let paths = AppPaths {
+            project_root: root.clone(),
+            user_data_dir: root.clone(),
+            log_dir: root.clone(),
+            db_path: root.join("tepora_core.db"),
+            secrets_path: root.join("secrets.yaml"),
+        };
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(paths.project_dir("alpha")).unwrap();
+        for path in [paths.db_path.clone(), paths.project_rag_db_path("alpha")] {
+            let mut conn = SqliteConnectOptions::new()
+                .filename(&path)
+                .create_if_missing(true)
+                .connect()
+                .await
+                .unwrap();
+            sqlx::query("CREATE TABLE items (id INTEGER PRIMARY KEY, body TEXT)")
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            for _ in 0..200 {
+                sqlx::query("INSERT INTO items (body) VALUES (?)")
+                    .bind("x".repeat(2_000))
+                    .execute(&mut conn)
+                    .await
+                    .unwrap();
+            }
+            sqlx::query("DELETE FROM items")
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            conn.close().await.unwrap();
+        }
+
+        let report = run_maintenance(&paths).await;
+        assert!(report.healthy());
+        let names = report
+            .databases
+            .iter()
+            .map(|db| db.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["history", "rag:alpha"]);
+        for db in &report.databases {
+            assert_eq!(db.integrity, vec!["ok".to_string()]);
+            assert!(db.full_vacuum);
+            assert_eq!(db.auto_vacuum, "incremental");
+            assert!(db.freelist_before > 0);
+            assert_eq!(db.freelist_after, 0);
+            assert!(db.size_after < db.size_before);
+        }
+        assert!(load_state(&state_path(&root)).last_run.is_some());
+
+        // 2 回目からは incremental_vacuum で済む
+        let again = run_maintenance(&paths).await;
+        assert!(again.databases.iter().all(|db| !db.full_vacuum));
+    }
+}
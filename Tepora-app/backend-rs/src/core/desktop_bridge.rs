@@ -0,0 +1,260 @@
+//! Tauri シェルとのデスクトップ連携（クリップボード・スクリーンショット）。
+//!
+//! バックエンドは OS の画面やクリップボードに直接触れない。デスクトップ版の
+//! シェルはサイドカーの `/api/desktop/bridge` に WebSocket で繋ぎ
+//! （[`DESKTOP_BRIDGE_TOKEN_HEADER`] にシェルだけが持つ鍵を付ける）、
+//! 接続の間だけ [`DesktopBridge::attach`] の受信口が生きる。シェルは要求ごとに
+//! 利用者の同意ダイアログを出してから [`DesktopBridgeReply`] を返す。UI や
+//! ツールからは要求の発行しかできない。シェルが繋がっていない（ブラウザ版・
+//! ヘッドレス起動）場合は `ServiceUnavailable` になる。
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::core::errors::ApiError;
+
+/// 同意ダイアログを含めた応答待ちの上限
+pub const DESKTOP_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+/// スクリーンショット（Base64）の上限
+pub const MAX_SCREENSHOT_BASE64_BYTES: usize = 12 * 1024 * 1024;
+const QUEUE_DEPTH: usize = 4;
+/// シェルが `/api/desktop/bridge` に付ける鍵のヘッダー
+pub const DESKTOP_BRIDGE_TOKEN_HEADER: &str = "x-desktop-bridge-token";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DesktopCapability {
+    ClipboardText,
+    Screenshot,
+}
+
+impl DesktopCapability {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::ClipboardText => "read your clipboard",
+            Self::Screenshot => "capture your screen",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DesktopRequest {
+    pub id: String,
+    pub capability: DesktopCapability,
+    /// 同意ダイアログに表示する理由
+    pub reason: Option<String>,
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DesktopReply {
+    ClipboardText {
+        text: String,
+    },
+    Screenshot {
+        mime_type: String,
+        base64: String,
+        width: u32,
+        height: u32,
+    },
+    Denied,
+    Failed {
+        message: String,
+    },
+}
+
+/// シェルが `/api/desktop/bridge` で返す、`DesktopRequest::id` への応答
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DesktopBridgeReply {
+    pub id: String,
+    pub reply: DesktopReply,
+}
+
+/// シェルが受け取る 1 件分の要求。`respond` を呼ばずに破棄すると失敗扱い。
+#[derive(Debug)]
+pub struct PendingDesktopRequest {
+    pub request: DesktopRequest,
+    responder: oneshot::Sender<DesktopReply>,
+}
+
+impl PendingDesktopRequest {
+    pub fn respond(self, reply: DesktopReply) {
+        let _ = self.responder.send(reply);
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct DesktopBridge {
+    sender: Arc<Mutex<Option<mpsc::Sender<PendingDesktopRequest>>>>,
+}
+
+impl DesktopBridge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// シェル側の受信口を登録する。再登録すると古い受信口は切り離される。
+    pub fn attach(&self) -> mpsc::Receiver<PendingDesktopRequest> {
+        let (tx, rx) = mpsc::channel(QUEUE_DEPTH);
+        *self.sender.lock().unwrap_or_else(|e| e.into_inner()) = Some(tx);
+        rx
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.sender
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_some_and(|tx| !tx.is_closed())
+    }
+
+    pub async fn request(
+        &self,
+        capability: DesktopCapability,
+        reason: Option<String>,
+        session_id: Option<String>,
+    ) -> Result<DesktopReply, ApiError> {
+        self.request_with_timeout(capability, reason, session_id, DESKTOP_REQUEST_TIMEOUT)
+            .await
+    }
+
+    pub async fn request_with_timeout(
+        &self,
+        capability: DesktopCapability,
+        reason: Option<String>,
+        session_id: Option<String>,
+        timeout: Duration,
+    ) -> Result<DesktopReply, ApiError> {
+        let sender = self
+            .sender
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .filter(|tx| !tx.is_closed())
+            .ok_or_else(|| {
+                ApiError::ServiceUnavailable(
+                    "Desktop integration is only available in the desktop app".to_string(),
+                )
+            })?;
+
+        let (responder, reply) = oneshot::channel();
+        let pending = PendingDesktopRequest {
+            request: DesktopRequest {
+                id: uuid::Uuid::new_v4().to_string(),
+                capability,
+                reason,
+                session_id,
+            },
+            responder,
+        };
+        sender.try_send(pending).map_err(|err| match err {
+            mpsc::error::TrySendError::Full(_) => ApiError::TooManyRequests,
+            mpsc::error::TrySendError::Closed(_) => {
+                ApiError::ServiceUnavailable("Desktop shell disconnected".to_string())
+            }
+        })?;
+
+        let reply = match tokio::time::timeout(timeout, reply).await {
+            Ok(Ok(reply)) => reply,
+            Ok(Err(_)) => DesktopReply::Failed {
+                message: "Desktop shell dropped the request".to_string(),
+            },
+            Err(_) => {
+                return Err(ApiError::ServiceUnavailable(
+                    "Timed out waiting for desktop consent".to_string(),
+                ))
+            }
+        };
+
+        let mismatched = matches!(
+            (&reply, capability),
+            (
+                DesktopReply::ClipboardText { .. },
+                DesktopCapability::Screenshot
+            ) | (
+                DesktopReply::Screenshot { .. },
+                DesktopCapability::ClipboardText
+            )
+        );
+        if mismatched {
+            return Err(ApiError::internal(
+                "Desktop shell returned the wrong reply type",
+            ));
+        }
+        if let DesktopReply::Screenshot { base64, .. } = &reply {
+            if base64.len() > MAX_SCREENSHOT_BASE64_BYTES {
+                return Err(ApiError::BadRequest("Screenshot is too large".to_string()));
+            }
+        }
+        Ok(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn request_fails_without_attached_shell() {
+        let bridge = DesktopBridge::new();
+        assert!(!bridge.is_attached());
+        let result = bridge
+            .request(DesktopCapability::ClipboardText, None, None)
+            .await;
+        assert!(matches!(result, Err(ApiError::ServiceUnavailable(_))));
+    }
+
+    #[tokio::test]
+    async fn attached_shell_answers_requests() {
+        let bridge = DesktopBridge::new();
+        let mut rx = bridge.attach();
+        assert!(bridge.is_attached());
+
+        tokio::spawn(async move {
+            while let Some(pending) = rx.recv().await {
+                let reply = match pending.request.capability {
+                    DesktopCapability::ClipboardText => DesktopReply::ClipboardText {
+                        text: "copied".to_string(),
+                    },
+                    DesktopCapability::Screenshot => DesktopReply::Denied,
+                };
+                pending.respond(reply);
+            }
+        });
+
+        let clipboard = bridge
+            .request(DesktopCapability::ClipboardText, None, Some("s1".into()))
+            .await
+            .unwrap();
+        assert_eq!(
+            clipboard,
+            DesktopReply::ClipboardText {
+                text: "copied".to_string()
+            }
+        );
+        let screenshot = bridge
+            .request(DesktopCapability::Screenshot, Some("why".into()), None)
+            .await
+            .unwrap();
+        assert_eq!(screenshot, DesktopReply::Denied);
+    }
+
+    #[tokio::test]
+    async fn unanswered_requests_time_out() {
+        let bridge = DesktopBridge::new();
+        let _rx = bridge.attach();
+        let result = bridge
+            .request_with_timeout(
+                DesktopCapability::Screenshot,
+                None,
+                None,
+                Duration::from_millis(20),
+            )
+            .await;
+        assert!(matches!(result, Err(ApiError::ServiceUnavailable(_))));
+    }
+}
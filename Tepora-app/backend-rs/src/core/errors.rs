@@ -6,6 +6,8 @@ use axum::{
 use serde_json::json;
 use thiserror::Error;
 
+use super::config::schema::NetworkSubsystem;
+
 #[derive(Debug, Error)]
 pub enum ApiError {
     #[error("unauthorized")]
@@ -29,6 +31,15 @@ pub enum ApiError {
     ServiceUnavailable(String),
     #[error("too many requests")]
     TooManyRequests,
+    #[error("offline mode blocks {}", .0.as_str())]
+    Offline(NetworkSubsystem),
+    #[error("model '{model_id}' does not support {}", .missing.join(", "))]
+    MissingCapability {
+        model_id: String,
+        missing: Vec<String>,
+    },
+    #[error("provider '{provider}' is unavailable: {message}")]
+    ProviderUnavailable { provider: String, message: String },
 }
 
 impl ApiError {
@@ -53,9 +64,40 @@ impl IntoResponse for ApiError {
                 StatusCode::TOO_MANY_REQUESTS,
                 "Too many requests. Please try again later.".to_string(),
             ),
+            ApiError::Offline(subsystem) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!(
+                    "Offline mode is on; network access for '{}' is blocked",
+                    subsystem.as_str()
+                ),
+            ),
+            ApiError::MissingCapability { .. } => {
+                (StatusCode::UNPROCESSABLE_ENTITY, self.to_string())
+            }
+            ApiError::ProviderUnavailable { .. } => {
+                (StatusCode::SERVICE_UNAVAILABLE, self.to_string())
+            }
         };
 
-        let body = Json(json!({ "error": message }));
+        // オフラインで止めた場合は、UI が個別許可を尋ねられるよう対象を添える
+        let body = match &self {
+            ApiError::Offline(subsystem) => Json(json!({
+                "error": message,
+                "offline_subsystem": subsystem.as_str(),
+            })),
+            // UI が対応モデルへの切り替えを促せるよう、足りない機能を添える
+            ApiError::MissingCapability { model_id, missing } => Json(json!({
+                "error": message,
+                "model_id": model_id,
+                "missing_capabilities": missing,
+            })),
+            ApiError::ProviderUnavailable { provider, .. } => Json(json!({
+                "error": message,
+                "provider": provider,
+                "hints": crate::llm::outage::remediation_hints(provider),
+            })),
+            _ => Json(json!({ "error": message })),
+        };
         let mut response = (status, body).into_response();
 
         // RFC 7231 準拠: 429 レスポンスに Retry-After ヘッダを付加
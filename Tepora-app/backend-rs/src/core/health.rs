@@ -0,0 +1,218 @@
+//! サブシステムの稼働状況。
+//!
+//! 起動直後と一定間隔で各サブシステム（llama.cpp バイナリ・モデル割り当て・
+//! 埋め込みサーバー・MCP・データベース）を確かめ、最新の結果を保持する。
+//! 状態が変わったサブシステムは WebSocket の `health` イベントとして流す。
+//! 最初の確認が終わるまでは `/api/status` が `initialized: false` を返す。
+
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+const HEALTH_EVENT_CAPACITY: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubsystemHealth {
+    pub name: String,
+    pub status: HealthStatus,
+    pub detail: String,
+    /// 落ちるとアプリ全体が使えないもの。`false` のものは全体を `degraded` 止まりにする
+    pub critical: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+}
+
+impl SubsystemHealth {
+    pub fn new(
+        name: impl Into<String>,
+        status: HealthStatus,
+        detail: impl Into<String>,
+        critical: bool,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            critical,
+            latency_ms: None,
+        }
+    }
+
+    pub fn with_latency(mut self, latency_ms: u64) -> Self {
+        self.latency_ms = Some(latency_ms);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub subsystems: Vec<SubsystemHealth>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl HealthReport {
+    pub fn new(subsystems: Vec<SubsystemHealth>) -> Self {
+        let status = subsystems
+            .iter()
+            .map(|subsystem| match subsystem.status {
+                HealthStatus::Down if !subsystem.critical => HealthStatus::Degraded,
+                status => status,
+            })
+            .max()
+            .unwrap_or(HealthStatus::Ok);
+        Self {
+            status,
+            subsystems,
+            checked_at: Utc::now(),
+        }
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.status != HealthStatus::Ok
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthChange {
+    pub subsystem: String,
+    /// 初回の確認では `None`
+    pub previous: Option<HealthStatus>,
+    pub status: HealthStatus,
+    pub detail: String,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct HealthMonitor {
+    latest: Arc<RwLock<Option<HealthReport>>>,
+    tx: broadcast::Sender<HealthChange>,
+}
+
+impl Default for HealthMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthMonitor {
+    pub fn new() -> Self {
+        Self {
+            latest: Arc::new(RwLock::new(None)),
+            tx: broadcast::channel(HEALTH_EVENT_CAPACITY).0,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<HealthChange> {
+        self.tx.subscribe()
+    }
+
+    /// 最後の確認結果。起動直後でまだ確認していなければ `None`。
+    pub fn latest(&self) -> Option<HealthReport> {
+        self.latest.read().ok().and_then(|latest| latest.clone())
+    }
+
+    /// 結果を保存し、前回から状態が変わったサブシステムを通知して返す。
+    pub fn record(&self, report: HealthReport) -> Vec<HealthChange> {
+        let Ok(mut latest) = self.latest.write() else {
+            return Vec::new();
+        };
+        let changes = report
+            .subsystems
+            .iter()
+            .filter_map(|subsystem| {
+                let previous = latest.as_ref().and_then(|previous| {
+                    previous
+                        .subsystems
+                        .iter()
+                        .find(|candidate| candidate.name == subsystem.name)
+                        .map(|candidate| candidate.status)
+                });
+                (previous != Some(subsystem.status)).then(|| HealthChange {
+                    subsystem: subsystem.name.clone(),
+                    previous,
+                    status: subsystem.status,
+                    detail: subsystem.detail.clone(),
+                    at: report.checked_at,
+                })
+            })
+            .collect::<Vec<_>>();
+        *latest = Some(report);
+        drop(latest);
+
+        for change in &changes {
+            if change.status != HealthStatus::Ok {
+                tracing::warn!(
+                    subsystem = %change.subsystem,
+                    status = ?change.status,
+                    detail = %change.detail,
+                    "Subsystem health changed"
+                );
+            }
+            // 購読者がいないのは WS 未接続時の通常の状態
+            let _ = self.tx.send(change.clone());
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subsystem(name: &str, status: HealthStatus, critical: bool) -> SubsystemHealth {
+        SubsystemHealth::new(name, status, "", critical)
+    }
+
+    #[test]
+    fn overall_status_only_goes_down_for_critical_subsystems() {
+        let report = HealthReport::new(vec![
+            subsystem("database", HealthStatus::Ok, true),
+            subsystem("mcp", HealthStatus::Down, false),
+        ]);
+        assert_eq!(report.status, HealthStatus::Degraded);
+
+        let report = HealthReport::new(vec![
+            subsystem("database", HealthStatus::Down, true),
+            subsystem("mcp", HealthStatus::Ok, false),
+        ]);
+        assert_eq!(report.status, HealthStatus::Down);
+        assert!(!HealthReport::new(vec![subsystem("mcp", HealthStatus::Ok, false)]).is_degraded());
+    }
+
+    #[test]
+    fn monitor_reports_only_changed_subsystems() {
+        let monitor = HealthMonitor::new();
+        let mut events = monitor.subscribe();
+        assert!(monitor.latest().is_none());
+
+        let first = monitor.record(HealthReport::new(vec![
+            subsystem("database", HealthStatus::Ok, true),
+            subsystem("mcp", HealthStatus::Ok, false),
+        ]));
+        assert_eq!(first.len(), 2);
+        assert!(first.iter().all(|change| change.previous.is_none()));
+
+        let second = monitor.record(HealthReport::new(vec![
+            subsystem("database", HealthStatus::Ok, true),
+            subsystem("mcp", HealthStatus::Degraded, false),
+        ]));
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].subsystem, "mcp");
+        assert_eq!(second[0].previous, Some(HealthStatus::Ok));
+
+        let received = std::iter::from_fn(|| events.try_recv().ok()).count();
+        assert_eq!(received, 3);
+        assert_eq!(monitor.latest().unwrap().status, HealthStatus::Degraded);
+    }
+}
@@ -0,0 +1,443 @@
+//! 会話履歴の話題索引づくり（`GET /api/history/topics`）。
+//!
+//! 月ごとにセッションの要旨（ローリング要約か最初のユーザー発言）を埋め込み、
+//! コサイン類似度で貪欲にまとめてから、各まとまりに LLM で短いラベルを付ける。
+//! `maintenance.topics_auto` が有効なら 1 日 1 回、まだ索引の無い月と
+//! 今月・先月を作り直す。過去の月は内容が変わらないので一度作れば残す。
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::core::errors::ApiError;
+use crate::history::{NewTopic, SessionDigest};
+use crate::llm::{ChatMessage, ChatRequest};
+use crate::state::{jobs, AppState};
+use crate::tools::vector_math::cosine_similarity;
+
+const TOPIC_TICK: Duration = Duration::from_secs(60 * 60);
+/// 自動実行の間隔（時間）
+const TOPIC_INTERVAL_HOURS: i64 = 24;
+/// 同じ話題とみなす、話題の重心とのコサイン類似度の下限
+const TOPIC_SIMILARITY_THRESHOLD: f32 = 0.75;
+const EMBED_BATCH_SIZE: usize = 32;
+/// ラベル付けで LLM に見せるセッション数
+const LABEL_SAMPLE_SESSIONS: usize = 8;
+const LABEL_SAMPLE_CHARS: usize = 300;
+const LABEL_MAX_CHARS: usize = 80;
+
+const LABEL_SYSTEM_PROMPT: &str = "You name topics in a user's chat history. \
+Given several conversations that belong together, reply with one short label \
+(at most six words) describing what they are about. Reply with the label only, \
+in the language the conversations are written in.";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TopicIndexReport {
+    pub months: Vec<MonthTopicReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MonthTopicReport {
+    pub month: String,
+    pub sessions: usize,
+    pub topics: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TopicIndexState {
+    last_run: Option<DateTime<Utc>>,
+}
+
+/// 埋め込みを話題ごとにまとめ、大きい話題から順に添字の組を返す。
+/// 各ベクトルは最も近い話題の重心と比べ、`threshold` 未満なら新しい話題を作る。
+pub fn cluster_embeddings(embeddings: &[Vec<f32>], threshold: f32) -> Vec<Vec<usize>> {
+    // 重心は和のまま持つ。コサイン類似度は長さに依らない
+    let mut centroids: Vec<Vec<f32>> = Vec::new();
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    for (index, embedding) in embeddings.iter().enumerate() {
+        let best = centroids
+            .iter()
+            .enumerate()
+            .filter_map(|(cluster, centroid)| {
+                cosine_similarity(embedding, centroid)
+                    .ok()
+                    .map(|score| (cluster, score))
+            })
+            .max_by(|left, right| left.1.total_cmp(&right.1));
+        match best {
+            Some((cluster, score)) if score >= threshold => {
+                for (sum, value) in centroids[cluster].iter_mut().zip(embedding) {
+                    *sum += value;
+                }
+                clusters[cluster].push(index);
+            }
+            _ => {
+                centroids.push(embedding.clone());
+                clusters.push(vec![index]);
+            }
+        }
+    }
+    // 同じ大きさなら先に現れた話題が先
+    clusters.sort_by_key(|members| std::cmp::Reverse(members.len()));
+    clusters
+}
+
+/// 自動実行で作り直す月。索引の無い月と、まだ会話が増えうる今月・先月。
+pub fn months_to_index(
+    session_months: &[String],
+    indexed_months: &[String],
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let current = now.format("%Y-%m").to_string();
+    let previous = if now.month() == 1 {
+        format!("{:04}-12", now.year() - 1)
+    } else {
+        format!("{:04}-{:02}", now.year(), now.month() - 1)
+    };
+    session_months
+        .iter()
+        .filter(|month| {
+            !indexed_months.contains(month) || **month == current || **month == previous
+        })
+        .cloned()
+        .collect()
+}
+
+/// `YYYY-MM` の形か。
+pub fn is_valid_month(month: &str) -> bool {
+    month.len() == 7
+        && chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").is_ok()
+}
+
+/// 指定した月の話題索引を作り直す。1 か月が失敗しても残りは続ける。
+pub async fn rebuild_topics(state: &AppState, months: &[String]) -> TopicIndexReport {
+    let mut reports = Vec::with_capacity(months.len());
+    for month in months {
+        let mut report = MonthTopicReport {
+            month: month.clone(),
+            sessions: 0,
+            topics: 0,
+            error: None,
+        };
+        match rebuild_month(state, month).await {
+            Ok((sessions, topics)) => {
+                report.sessions = sessions;
+                report.topics = topics;
+            }
+            Err(err) => {
+                tracing::warn!(month = %month, "Failed to build topic index: {}", err);
+                report.error = Some(err.to_string());
+            }
+        }
+        reports.push(report);
+    }
+    TopicIndexReport { months: reports }
+}
+
+/// (セッション数, 話題数) を返す。
+async fn rebuild_month(state: &AppState, month: &str) -> Result<(usize, usize), ApiError> {
+    let history = &state.runtime().history;
+    let digests = history.session_digests_for_month(month).await?;
+    if digests.is_empty() {
+        history.replace_month_topics(month, &[]).await?;
+        return Ok((0, 0));
+    }
+    let embedding_model_id = state
+        .ai()
+        .models
+        .resolve_assignment_model_id("embedding")?
+        .ok_or_else(|| ApiError::BadRequest("No embedding model is assigned".to_string()))?;
+    let texts = digests
+        .iter()
+        .map(|digest| digest.text.clone())
+        .collect::<Vec<_>>();
+    let embeddings = state
+        .ai()
+        .llm
+        .embed_batched(&texts, &embedding_model_id, EMBED_BATCH_SIZE)
+        .await?;
+
+    let label_model_id = resolve_label_model_id(state);
+    let mut topics = Vec::new();
+    for members in cluster_embeddings(&embeddings, TOPIC_SIMILARITY_THRESHOLD) {
+        let sessions = members
+            .iter()
+            .map(|index| &digests[*index])
+            .collect::<Vec<_>>();
+        let label = match label_topic(state, &label_model_id, &sessions).await {
+            Ok(label) => label,
+            Err(err) => {
+                tracing::debug!(month = %month, "Falling back to a title for a topic label: {}", err);
+                fallback_label(&sessions)
+            }
+        };
+        topics.push(NewTopic {
+            label,
+            session_ids: sessions
+                .iter()
+                .map(|digest| digest.session_id.clone())
+                .collect(),
+            message_count: sessions.iter().map(|digest| digest.message_count).sum(),
+        });
+    }
+    history.replace_month_topics(month, &topics).await?;
+    Ok((digests.len(), topics.len()))
+}
+
+/// 要約と同じく `professional` 割り当てを優先する。
+fn resolve_label_model_id(state: &AppState) -> String {
+    let models = &state.ai().models;
+    models
+        .resolve_assignment_model_id("professional")
+        .ok()
+        .flatten()
+        .or_else(|| models.resolve_character_model_id(None).ok().flatten())
+        .unwrap_or_else(|| "default".to_string())
+}
+
+async fn label_topic(
+    state: &AppState,
+    model_id: &str,
+    sessions: &[&SessionDigest],
+) -> Result<String, ApiError> {
+    let mut request = ChatRequest::new(build_label_messages(sessions)).without_continuation();
+    request.max_tokens = Some(32);
+    request.temperature = Some(0.2);
+    let reply = state.ai().llm.chat(request, model_id).await?;
+    clean_label(&reply).ok_or_else(|| ApiError::internal("Topic label was empty"))
+}
+
+fn build_label_messages(sessions: &[&SessionDigest]) -> Vec<ChatMessage> {
+    let conversations = sessions
+        .iter()
+        .take(LABEL_SAMPLE_SESSIONS)
+        .map(|digest| {
+            let text = digest.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            format!(
+                "- {}",
+                text.chars().take(LABEL_SAMPLE_CHARS).collect::<String>()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: LABEL_SYSTEM_PROMPT.to_string(),
+            multimodal_parts: None,
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!("Conversations:\n{}\n\nLabel:", conversations),
+            multimodal_parts: None,
+        },
+    ]
+}
+
+/// 1 行目だけを取り、囲みの引用符や `Label:` を外す。
+fn clean_label(reply: &str) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = line
+        .strip_prefix("Label:")
+        .map(str::trim)
+        .unwrap_or(line)
+        .trim_matches(|c: char| matches!(c, '"' | '\'' | '`' | '*' | '「' | '」'))
+        .trim_end_matches(['.', '。'])
+        .trim();
+    if line.is_empty() {
+        return None;
+    }
+    Some(line.chars().take(LABEL_MAX_CHARS).collect())
+}
+
+/// LLM が使えないときは最初のセッションのタイトルか書き出し。
+fn fallback_label(sessions: &[&SessionDigest]) -> String {
+    sessions
+        .iter()
+        .find_map(|digest| digest.title.clone())
+        .or_else(|| {
+            sessions.first().and_then(|digest| {
+                digest
+                    .text
+                    .lines()
+                    .next()
+                    .map(|line| line.chars().take(40).collect())
+            })
+        })
+        .filter(|label: &String| !label.trim().is_empty())
+        .unwrap_or_else(|| "Untitled topic".to_string())
+}
+
+/// ジョブとして記録しながら作り直す。1 か月でも失敗すればジョブは失敗。
+pub async fn run_topic_index_job(
+    state: &AppState,
+    job_id: &str,
+    months: &[String],
+) -> TopicIndexReport {
+    jobs::begin(
+        state,
+        job_id,
+        jobs::KIND_TOPIC_INDEX,
+        "History topic index",
+        json!({ "months": months }),
+    )
+    .await;
+    let report = rebuild_topics(state, months).await;
+    let failure = report
+        .months
+        .iter()
+        .any(|month| month.error.is_some())
+        .then_some("Some months could not be indexed");
+    jobs::finish(state, job_id, failure).await;
+    if let Err(err) = save_state(
+        &state_path(&state.core().paths.user_data_dir),
+        &TopicIndexState {
+            last_run: Some(Utc::now()),
+        },
+    ) {
+        tracing::warn!("Failed to record topic indexing: {}", err);
+    }
+    report
+}
+
+fn state_path(user_data_dir: &Path) -> PathBuf {
+    user_data_dir.join("topic_index_state.json")
+}
+
+fn load_state(path: &Path) -> TopicIndexState {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(path: &Path, state: &TopicIndexState) -> Result<(), ApiError> {
+    let serialized = serde_json::to_string_pretty(state).map_err(ApiError::internal)?;
+    std::fs::write(path, serialized).map_err(ApiError::internal)
+}
+
+/// 1 日 1 回、話題索引を更新する常駐タスク。埋め込みモデルが未割り当てなら何もしない。
+pub fn spawn_topic_indexing(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(TOPIC_TICK);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let enabled = state
+                .core()
+                .config
+                .load_typed()
+                .map(|config| config.maintenance.topics_auto)
+                .unwrap_or_default();
+            if !enabled {
+                continue;
+            }
+            let due = load_state(&state_path(&state.core().paths.user_data_dir))
+                .last_run
+                .is_none_or(|last| {
+                    Utc::now().signed_duration_since(last)
+                        >= chrono::Duration::hours(TOPIC_INTERVAL_HOURS)
+                });
+            let has_embedding_model = matches!(
+                state.ai().models.resolve_assignment_model_id("embedding"),
+                Ok(Some(_))
+            );
+            if !due || !has_embedding_model {
+                continue;
+            }
+            let history = &state.runtime().history;
+            let months = match (
+                history.session_months().await,
+                history.indexed_topic_months().await,
+            ) {
+                (Ok(all), Ok(indexed)) => months_to_index(&all, &indexed, Utc::now()),
+                (Err(err), _) | (_, Err(err)) => {
+                    tracing::warn!("Failed to list months for topic indexing: {}", err);
+                    continue;
+                }
+            };
+            if months.is_empty() {
+                continue;
+            }
+            let job_id = uuid::Uuid::new_v4().to_string();
+            let report = run_topic_index_job(&state, &job_id, &months).await;
+            tracing::info!(
+                months = report.months.len(),
+                topics = report
+                    .months
+                    .iter()
+                    .map(|month| month.topics)
+                    .sum::<usize>(),
+                "Scheduled topic indexing finished"
+            );
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn clustering_groups_similar_vectors_largest_first() {
+        let embeddings = vec![
+            vec![1.0, 0.0, 0.0],
+            vec![0.0, 1.0, 0.0],
+            vec![0.9, 0.1, 0.0],
+            vec![0.0, 0.95, 0.1],
+            vec![0.95, 0.0, 0.05],
+            vec![0.0, 0.0, 1.0],
+        ];
+        let clusters = cluster_embeddings(&embeddings, 0.75);
+        assert_eq!(clusters, vec![vec![0, 2, 4], vec![1, 3], vec![5]]);
+        assert!(cluster_embeddings(&[], 0.75).is_empty());
+    }
+
+    #[test]
+    fn scheduled_months_cover_new_current_and_previous() {
+        let months = ["2025-11", "2025-12", "2026-01", "2026-02"]
+            .map(String::from)
+            .to_vec();
+        let indexed = ["2025-11", "2025-12", "2026-01"].map(String::from).to_vec();
+        let now = Utc.with_ymd_and_hms(2026, 1, 15, 0, 0, 0).unwrap();
+        assert_eq!(
+            months_to_index(&months, &indexed, now),
+            vec!["2025-12", "2026-01", "2026-02"]
+        );
+        assert!(is_valid_month("2026-01"));
+        assert!(!is_valid_month("2026-13"));
+        assert!(!is_valid_month("2026-1"));
+    }
+
+    #[test]
+    fn labels_are_cleaned_and_fall_back_to_titles() {
+        assert_eq!(
+            clean_label("\"Kyoto travel plans.\"\nextra").as_deref(),
+            Some("Kyoto travel plans")
+        );
+        assert_eq!(
+            clean_label("Label: Rust 所有権").as_deref(),
+            Some("Rust 所有権")
+        );
+        assert_eq!(clean_label("  \n"), None);
+
+        let untitled = SessionDigest {
+            session_id: "a".to_string(),
+            title: None,
+            text: "Fix my borrow error\nmore".to_string(),
+            message_count: 2,
+        };
+        let titled = SessionDigest {
+            title: Some("Rust help".to_string()),
+            ..untitled.clone()
+        };
+        assert_eq!(fallback_label(&[&untitled, &titled]), "Rust help");
+        assert_eq!(fallback_label(&[&untitled]), "Fix my borrow error");
+    }
+}
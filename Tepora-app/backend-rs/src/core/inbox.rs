@@ -0,0 +1,82 @@
+//! バックグラウンド実行の受け取り箱。
+//!
+//! エージェント実行や定期取り込みが終わると要約と成果物をここへ置く。
+//! 置いた・既読にしたタイミングで未読数を WebSocket の `inbox` イベントとして
+//! 流し、フロントエンドはそれでバッジを更新する。チャット画面を開いていなくても
+//! 結果を後から読める。
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use super::errors::ApiError;
+use crate::history::{InboxItem, NewInboxItem};
+use crate::workspace::ProjectHistoryStore;
+
+const INBOX_EVENT_CAPACITY: usize = 32;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InboxUpdate {
+    pub unread: i64,
+    /// 新しく届いた項目。既読操作による更新では `None`。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<InboxItem>,
+}
+
+#[derive(Clone)]
+pub struct Inbox {
+    history: ProjectHistoryStore,
+    tx: broadcast::Sender<InboxUpdate>,
+}
+
+impl Inbox {
+    pub fn new(history: ProjectHistoryStore) -> Self {
+        Self {
+            history,
+            tx: broadcast::channel(INBOX_EVENT_CAPACITY).0,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<InboxUpdate> {
+        self.tx.subscribe()
+    }
+
+    pub async fn deposit(&self, item: NewInboxItem) -> Result<InboxItem, ApiError> {
+        let item = self.history.add_inbox_item(&item).await?;
+        self.broadcast(Some(item.clone())).await;
+        Ok(item)
+    }
+
+    pub async fn list(
+        &self,
+        unread_only: bool,
+        limit: i64,
+        before_id: Option<i64>,
+    ) -> Result<Vec<InboxItem>, ApiError> {
+        self.history.list_inbox(unread_only, limit, before_id).await
+    }
+
+    pub async fn unread_count(&self) -> Result<i64, ApiError> {
+        self.history.unread_inbox_count().await
+    }
+
+    /// `ids` が `None` なら未読すべてを既読にする。
+    pub async fn acknowledge(&self, ids: Option<&[i64]>) -> Result<u64, ApiError> {
+        let acknowledged = self.history.acknowledge_inbox(ids).await?;
+        if acknowledged > 0 {
+            self.broadcast(None).await;
+        }
+        Ok(acknowledged)
+    }
+
+    async fn broadcast(&self, item: Option<InboxItem>) {
+        let unread = match self.unread_count().await {
+            Ok(unread) => unread,
+            Err(err) => {
+                tracing::warn!("Failed to count unread inbox items: {}", err);
+                return;
+            }
+        };
+        // 購読者がいなければ誰にも届かないが、それで構わない
+        let _ = self.tx.send(InboxUpdate { unread, item });
+    }
+}
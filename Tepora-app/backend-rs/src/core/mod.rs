@@ -1,11 +1,23 @@
+pub mod chat_queue;
 pub mod config;
+pub mod db_maintenance;
+pub mod desktop_bridge;
 pub mod errors;
+pub mod health;
+pub mod history_topics;
+pub mod inbox;
 pub mod logging;
 pub mod native_tools;
+pub mod net_retry;
+pub mod network;
+pub mod notifications;
 mod pii_detection;
+pub mod safe_mode;
 pub mod security;
 mod security_audit;
 mod security_backup;
 pub mod security_controls;
 mod security_credentials;
 mod security_permissions;
+pub mod snapshots;
+pub mod utilization;
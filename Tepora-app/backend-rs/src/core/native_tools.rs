@@ -1,8 +1,7 @@
 //! ネイティブツールの定数定義
 //!
-//! ネイティブツール名の正準名、説明、エイリアス解決を一元管理する。
-//! `server/handlers/tools.rs`・`agent/execution.rs`・
-//! `agent/skill_registry.rs` はここを参照する。
+//! ネイティブツール名の正準名、必要権限、エイリアス解決を一元管理する。
+//! 説明・引数スキーマ・実行関数は `tools/registry.rs` に登録する。
 
 // --- 正準名定数 ---
 
@@ -15,55 +14,35 @@ pub const NATIVE_RAG_GET_CHUNK: &str = "native_rag_get_chunk";
 pub const NATIVE_RAG_GET_CHUNK_WINDOW: &str = "native_rag_get_chunk_window";
 pub const NATIVE_RAG_CLEAR_SESSION: &str = "native_rag_clear_session";
 pub const NATIVE_RAG_REINDEX: &str = "native_rag_reindex";
+pub const NATIVE_EXPAND_TOOL_RESULT: &str = "native_expand_tool_result";
+pub const NATIVE_FILE_READ: &str = "native_file_read";
+pub const NATIVE_FILE_WRITE: &str = "native_file_write";
+pub const NATIVE_FILE_LIST: &str = "native_file_list";
+pub const NATIVE_RUN_COMMAND: &str = "native_run_command";
+pub const NATIVE_CALCULATE: &str = "native_calculate";
+pub const NATIVE_FETCH_FEED: &str = "native_fetch_feed";
+pub const NATIVE_WIKI_LOOKUP: &str = "native_wiki_lookup";
+pub const NATIVE_READ_CLIPBOARD: &str = "native_read_clipboard";
+pub const NATIVE_SCREENSHOT: &str = "native_screenshot";
 
-// --- ツール定義 ---
+// --- 権限 ---
 
-/// LLM/API 提示用のネイティブツール情報
-#[derive(Debug, Clone, Copy)]
-pub struct NativeTool {
-    pub name: &'static str,
-    pub description: &'static str,
+/// ツール実行に必要な権限（エージェントの capability grant と照合する）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolCapability {
+    /// アプリ内部のデータのみ扱う
+    Local,
+    Network,
+    FilesystemRead,
+    FilesystemWrite,
+    /// 外部プロセスの起動（ネットワークと書き込みの両方を要求する）
+    Process,
 }
 
-/// 全ネイティブツールの一覧（定義順）
-pub const NATIVE_TOOLS: &[NativeTool] = &[
-    NativeTool {
-        name: NATIVE_WEB_FETCH,
-        description: "Fetch content from a URL",
-    },
-    NativeTool {
-        name: NATIVE_SEARCH,
-        description: "Search the web",
-    },
-    NativeTool {
-        name: NATIVE_RAG_SEARCH,
-        description: "Search RAG by embedding similarity",
-    },
-    NativeTool {
-        name: NATIVE_RAG_INGEST,
-        description: "Ingest text into RAG",
-    },
-    NativeTool {
-        name: NATIVE_RAG_TEXT_SEARCH,
-        description: "Search RAG by text pattern",
-    },
-    NativeTool {
-        name: NATIVE_RAG_GET_CHUNK,
-        description: "Get one RAG chunk by ID",
-    },
-    NativeTool {
-        name: NATIVE_RAG_GET_CHUNK_WINDOW,
-        description: "Get neighboring RAG chunks around one chunk",
-    },
-    NativeTool {
-        name: NATIVE_RAG_CLEAR_SESSION,
-        description: "Clear all RAG chunks for a session",
-    },
-    NativeTool {
-        name: NATIVE_RAG_REINDEX,
-        description: "Reindex RAG with a specific embedding model",
-    },
-];
+/// ネイティブツールの必要権限。MCP など未知のツールは `None`。
+pub fn native_tool_capability(name: &str) -> Option<ToolCapability> {
+    crate::tools::registry::find(&resolve_tool_alias(name)).map(|tool| tool.capability)
+}
 
 // --- エイリアス解決 ---
 
@@ -84,8 +63,12 @@ pub fn resolve_tool_alias(raw: &str) -> String {
 
     // Native tool aliases
     match trimmed {
-        "web_search" | "search" => NATIVE_SEARCH.to_string(),
-        "fetch_url" | "fetch" | "web_fetch" => NATIVE_WEB_FETCH.to_string(),
+        "web_search" | "search" | "native_google_search" | "native_duckduckgo" => {
+            NATIVE_SEARCH.to_string()
+        }
+        "fetch_url" | "fetch" | "web_fetch" | "native_fetch" => NATIVE_WEB_FETCH.to_string(),
+        "fetch_feed" | "feed" | "rss" => NATIVE_FETCH_FEED.to_string(),
+        "wiki_lookup" | "wikipedia" | "wiktionary" => NATIVE_WIKI_LOOKUP.to_string(),
         "rag_search" => NATIVE_RAG_SEARCH.to_string(),
         "rag_ingest" => NATIVE_RAG_INGEST.to_string(),
         "rag_text_search" => NATIVE_RAG_TEXT_SEARCH.to_string(),
@@ -93,6 +76,14 @@ pub fn resolve_tool_alias(raw: &str) -> String {
         "rag_get_chunk_window" => NATIVE_RAG_GET_CHUNK_WINDOW.to_string(),
         "rag_clear_session" => NATIVE_RAG_CLEAR_SESSION.to_string(),
         "rag_reindex" => NATIVE_RAG_REINDEX.to_string(),
+        "expand_tool_result" => NATIVE_EXPAND_TOOL_RESULT.to_string(),
+        "file_read" | "read_file" => NATIVE_FILE_READ.to_string(),
+        "file_write" | "write_file" => NATIVE_FILE_WRITE.to_string(),
+        "file_list" | "list_files" | "list_dir" => NATIVE_FILE_LIST.to_string(),
+        "run_command" | "shell" => NATIVE_RUN_COMMAND.to_string(),
+        "calculate" | "calc" | "calculator" => NATIVE_CALCULATE.to_string(),
+        "read_clipboard" | "clipboard" => NATIVE_READ_CLIPBOARD.to_string(),
+        "screenshot" | "capture_screen" => NATIVE_SCREENSHOT.to_string(),
         other => other.to_string(),
     }
 }
@@ -101,24 +92,14 @@ pub fn resolve_tool_alias(raw: &str) -> String {
 mod tests {
     use super::*;
 
-    #[test]
-    fn all_native_tool_names_are_unique() {
-        let names: Vec<_> = NATIVE_TOOLS.iter().map(|t| t.name).collect();
-        let mut unique = names.clone();
-        unique.sort_unstable();
-        unique.dedup();
-        assert_eq!(
-            names.len(),
-            unique.len(),
-            "Duplicate native tool names found"
-        );
-    }
-
     #[test]
     fn resolve_tool_alias_works() {
         assert_eq!(resolve_tool_alias("web_search"), NATIVE_SEARCH);
         assert_eq!(resolve_tool_alias("search"), NATIVE_SEARCH);
         assert_eq!(resolve_tool_alias("fetch_url"), NATIVE_WEB_FETCH);
+        assert_eq!(resolve_tool_alias("calculate"), NATIVE_CALCULATE);
+        assert_eq!(resolve_tool_alias("fetch_feed"), NATIVE_FETCH_FEED);
+        assert_eq!(resolve_tool_alias("wiki_lookup"), NATIVE_WIKI_LOOKUP);
         assert_eq!(resolve_tool_alias("fetch"), NATIVE_WEB_FETCH);
         assert_eq!(resolve_tool_alias("web_fetch"), NATIVE_WEB_FETCH);
         assert_eq!(resolve_tool_alias("rag_search"), NATIVE_RAG_SEARCH);
@@ -137,8 +118,29 @@ mod tests {
             NATIVE_RAG_CLEAR_SESSION
         );
         assert_eq!(resolve_tool_alias("rag_reindex"), NATIVE_RAG_REINDEX);
+        assert_eq!(
+            resolve_tool_alias("expand_tool_result"),
+            NATIVE_EXPAND_TOOL_RESULT
+        );
         assert_eq!(resolve_tool_alias("mcp:server_tool"), "server_tool");
         assert_eq!(resolve_tool_alias("native_search"), NATIVE_SEARCH);
         assert_eq!(resolve_tool_alias("custom_tool"), "custom_tool");
     }
+
+    #[test]
+    fn native_tool_capability_resolves_aliases() {
+        assert_eq!(
+            native_tool_capability("web_search"),
+            Some(ToolCapability::Network)
+        );
+        assert_eq!(
+            native_tool_capability(NATIVE_RAG_SEARCH),
+            Some(ToolCapability::Local)
+        );
+        assert_eq!(
+            native_tool_capability("write_file"),
+            Some(ToolCapability::FilesystemWrite)
+        );
+        assert_eq!(native_tool_capability("mcp:shell"), None);
+    }
 }
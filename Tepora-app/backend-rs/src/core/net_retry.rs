@@ -0,0 +1,287 @@
+//! Hugging Face・GitHub など外部配布元への取得の再試行。
+//!
+//! 一時的な 5xx や 429 でセットアップ全体が失敗しないよう、指数バックオフに
+//! ジッターを足して再試行する。`Retry-After` があればそちらを優先する。
+//! ファイルのダウンロードは途中で切れたら `Range` で続きから取り直し、
+//! サーバーが範囲指定に応じなければ最初から取り直す。
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use reqwest::header::{self, HeaderMap};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use sha2::{Digest, Sha256};
+
+use super::errors::ApiError;
+
+/// `Retry-After` に従って待つ上限。これより長い指定は上限で打ち切る
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// 初回を含む試行回数
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(20),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// `attempt` 回目（0 始まり）の失敗後に待つ時間。上限付きの指数に
+    /// フルジッターを掛け、同時に失敗したクライアントが揃って再送しないようにする。
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let ceiling_ms = ceiling.as_millis() as u64;
+        Duration::from_millis(rand::random_range(ceiling_ms / 2..=ceiling_ms))
+    }
+
+    fn delay_for(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        headers
+            .and_then(retry_after)
+            .map(|delay| delay.min(MAX_RETRY_AFTER))
+            .unwrap_or_else(|| self.backoff(attempt))
+    }
+}
+
+/// 再試行すれば通る見込みのある応答か。
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status.is_server_error() && status != StatusCode::NOT_IMPLEMENTED
+}
+
+fn is_retryable_error(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect() || err.is_request() || err.is_body()
+}
+
+/// `Retry-After` の秒数または HTTP 日付を待ち時間にする。
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let raw = headers.get(header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = raw.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(raw).ok()?;
+    let wait = at.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or_default())
+}
+
+/// `build` で作ったリクエストを送り、一時的な失敗なら待って送り直す。
+/// 再試行しても変わらない応答や、回数を使い切った最後の応答はそのまま返すので、
+/// ステータスの扱いは呼び出し側が決める。
+pub async fn send_with_retry(
+    policy: &RetryPolicy,
+    label: &str,
+    build: impl Fn() -> Result<RequestBuilder, ApiError>,
+) -> Result<Response, ApiError> {
+    let mut attempt = 0;
+    loop {
+        let last = attempt + 1 >= policy.max_attempts;
+        match build()?.send().await {
+            Ok(response) if !last && is_retryable_status(response.status()) => {
+                let delay = policy.delay_for(attempt, Some(response.headers()));
+                tracing::warn!(
+                    request = label,
+                    status = %response.status(),
+                    attempt = attempt + 1,
+                    delay_ms = delay.as_millis() as u64,
+                    "Transient HTTP status; retrying"
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Ok(response) => return Ok(response),
+            Err(err) if !last && is_retryable_error(&err) => {
+                let delay = policy.delay_for(attempt, None);
+                tracing::warn!(
+                    request = label,
+                    error = %err,
+                    attempt = attempt + 1,
+                    delay_ms = delay.as_millis() as u64,
+                    "HTTP request failed; retrying"
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(err) => return Err(ApiError::internal(err)),
+        }
+        attempt += 1;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadedFile {
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// `build` の URL を `target_path` に書き出し、SHA256 を計算する。
+/// 本文の途中で切れたら書けたところから `Range` で続きを取り、`206` で返らなければ
+/// 最初から取り直す。`on_progress` には（取得済みバイト数, 全体のバイト数。不明なら 0）を渡す。
+pub async fn download_with_resume(
+    policy: &RetryPolicy,
+    label: &str,
+    build: impl Fn() -> Result<RequestBuilder, ApiError>,
+    target_path: &Path,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<DownloadedFile, ApiError> {
+    let mut file = fs::File::create(target_path).map_err(ApiError::internal)?;
+    let mut hasher = Sha256::new();
+    let mut downloaded: u64 = 0;
+    let mut attempt = 0;
+    loop {
+        let offset = downloaded;
+        let response = send_with_retry(policy, label, || {
+            let request = build()?;
+            Ok(if offset > 0 {
+                request.header(header::RANGE, format!("bytes={offset}-"))
+            } else {
+                request
+            })
+        })
+        .await?
+        .error_for_status()
+        .map_err(ApiError::internal)?;
+
+        let total = if response.status() == StatusCode::PARTIAL_CONTENT {
+            content_range_total(response.headers())
+                .or_else(|| response.content_length().map(|len| len + offset))
+                .unwrap_or(0)
+        } else {
+            if offset > 0 {
+                // 範囲指定が効かなかったので最初から書き直す
+                tracing::info!(request = label, "Server ignored Range; restarting download");
+                file = fs::File::create(target_path).map_err(ApiError::internal)?;
+                hasher = Sha256::new();
+                downloaded = 0;
+            }
+            response.content_length().unwrap_or(0)
+        };
+
+        let mut stream = response.bytes_stream();
+        let mut interrupted = None;
+        while let Some(chunk) = stream.next().await {
+            let data = match chunk {
+                Ok(data) => data,
+                Err(err) => {
+                    interrupted = Some(err);
+                    break;
+                }
+            };
+            file.write_all(&data).map_err(ApiError::internal)?;
+            hasher.update(&data);
+            downloaded += data.len() as u64;
+            on_progress(downloaded, total);
+        }
+
+        let Some(err) = interrupted else {
+            file.flush().map_err(ApiError::internal)?;
+            return Ok(DownloadedFile {
+                size: downloaded,
+                sha256: hex::encode(hasher.finalize()),
+            });
+        };
+        attempt += 1;
+        if attempt >= policy.max_attempts {
+            return Err(ApiError::internal(err));
+        }
+        let delay = policy.backoff(attempt - 1);
+        tracing::warn!(
+            request = label,
+            error = %err,
+            downloaded,
+            delay_ms = delay.as_millis() as u64,
+            "Download interrupted; resuming"
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// `Content-Range: bytes 100-199/1000` の全体サイズ。
+fn content_range_total(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn backoff_grows_with_jitter_and_stays_under_cap() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+        };
+        for _ in 0..50 {
+            let first = policy.backoff(0);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let third = policy.backoff(2);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+            assert!(policy.backoff(10) <= Duration::from_millis(1000));
+        }
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds_and_dates() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        let policy = RetryPolicy::default();
+        headers.insert(header::RETRY_AFTER, HeaderValue::from_static("3600"));
+        assert_eq!(policy.delay_for(0, Some(&headers)), MAX_RETRY_AFTER);
+    }
+
+    #[test]
+    fn only_transient_statuses_are_retried() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable_status(StatusCode::NOT_IMPLEMENTED));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!is_retryable_status(StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn content_range_total_reads_full_size() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_RANGE,
+            HeaderValue::from_static("bytes 100-199/1000"),
+        );
+        assert_eq!(content_range_total(&headers), Some(1000));
+        headers.insert(
+            header::CONTENT_RANGE,
+            HeaderValue::from_static("bytes 0-9/*"),
+        );
+        assert_eq!(content_range_total(&headers), None);
+    }
+}
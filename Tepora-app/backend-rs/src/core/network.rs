@@ -0,0 +1,516 @@
+//! 外向き HTTP 通信の窓口とオフラインモード。
+//!
+//! モデルのダウンロード・MCP レジストリ・検索・Web 取得・HTTP ツール・
+//! クラウドプロバイダー・llama.cpp の更新は、すべて [`NetClient`] を通して通信する。
+//! オフライン中はここでローカルホスト以外への送信を止めるので、個々の呼び出し側は
+//! 判定を持たない。ユーザーが個別に許可したサブシステムだけは例外として通す。
+//! `network.proxy` のプロキシもここで選ぶ。クライアントは作り直さず、リクエストごとに
+//! その時点の設定から決める。リダイレクト先も 1 回ごとに同じ判定を通す。
+
+use std::net::IpAddr;
+use std::sync::{OnceLock, RwLock};
+
+use reqwest::redirect::Policy;
+use reqwest::{Client, ClientBuilder, IntoUrl, Method, Proxy, RequestBuilder, Url};
+use serde::Serialize;
+
+use super::config::schema::{NetworkSettings, NetworkSubsystem, ProxySettings};
+use super::errors::ApiError;
+
+static POLICY: OnceLock<RwLock<NetworkSettings>> = OnceLock::new();
+
+fn policy() -> &'static RwLock<NetworkSettings> {
+    POLICY.get_or_init(|| RwLock::new(NetworkSettings::default()))
+}
+
+/// 設定の `network` セクションを反映する。起動時と設定変更時に呼ぶ。
+pub fn apply_settings(settings: &NetworkSettings) {
+    let mut current = policy().write().unwrap_or_else(|e| e.into_inner());
+    if current.offline != settings.offline {
+        tracing::info!(offline = settings.offline, "Network mode changed");
+    }
+    *current = settings.clone();
+}
+
+pub fn current_settings() -> NetworkSettings {
+    policy().read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkStatus {
+    pub offline: bool,
+    /// 外部へ出られるサブシステム
+    pub allowed: Vec<NetworkSubsystem>,
+    /// オフラインで止まっているサブシステム
+    pub blocked: Vec<NetworkSubsystem>,
+}
+
+pub fn status() -> NetworkStatus {
+    let settings = current_settings();
+    let (allowed, blocked) = NetworkSubsystem::ALL
+        .into_iter()
+        .partition(|subsystem| settings.allows_remote(*subsystem));
+    NetworkStatus {
+        offline: settings.offline,
+        allowed,
+        blocked,
+    }
+}
+
+impl NetworkSettings {
+    pub fn allows_remote(&self, subsystem: NetworkSubsystem) -> bool {
+        !self.offline || self.allow_while_offline.contains(&subsystem)
+    }
+
+    pub fn permits(&self, subsystem: NetworkSubsystem, url: &Url) -> bool {
+        is_local_url(url) || self.allows_remote(subsystem)
+    }
+}
+
+impl ProxySettings {
+    /// 何か設定されていれば `true`。未設定なら環境変数のプロキシに任せる。
+    pub fn is_configured(&self) -> bool {
+        non_blank(self.url.as_deref()).is_some()
+            || self
+                .subsystems
+                .values()
+                .chain(self.providers.values())
+                .any(|entry| non_blank(entry.url.as_deref()).is_some())
+    }
+
+    /// `subsystem`（クラウドなら `provider` も）から `target` への通信に使うプロキシ。
+    /// `None` は直接接続。プロバイダー、サブシステム、共通の順に上書きする。
+    pub fn proxy_for(
+        &self,
+        subsystem: NetworkSubsystem,
+        provider: Option<&str>,
+        target: &Url,
+    ) -> Option<Url> {
+        if is_local_url(target) {
+            return None;
+        }
+        let host = target.host_str()?;
+        if self
+            .no_proxy
+            .iter()
+            .any(|pattern| host_matches(pattern, host))
+        {
+            return None;
+        }
+        if !self.only_hosts.is_empty()
+            && !self
+                .only_hosts
+                .iter()
+                .any(|pattern| host_matches(pattern, host))
+        {
+            return None;
+        }
+        let overrides = [
+            provider.and_then(|provider| self.providers.get(provider)),
+            self.subsystems.get(subsystem.as_str()),
+        ];
+        for entry in overrides.into_iter().flatten() {
+            if entry.direct {
+                return None;
+            }
+            if let Some(url) = non_blank(entry.url.as_deref()) {
+                return parse_proxy_url(url);
+            }
+        }
+        parse_proxy_url(non_blank(self.url.as_deref())?)
+    }
+}
+
+fn non_blank(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+fn parse_proxy_url(raw: &str) -> Option<Url> {
+    let url = Url::parse(raw).ok()?;
+    matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h").then_some(url)
+}
+
+/// `example.com` は完全一致、`*.example.com` / `.example.com` はそのドメインと配下に一致。
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim().to_ascii_lowercase();
+    let host = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_ascii_lowercase();
+    if pattern == "*" {
+        return true;
+    }
+    match pattern
+        .strip_prefix("*.")
+        .or_else(|| pattern.strip_prefix('.'))
+    {
+        Some(domain) => host == domain || host.ends_with(&format!(".{domain}")),
+        None => !pattern.is_empty() && host == pattern,
+    }
+}
+
+/// `network.proxy` が未設定のときは従来どおり `HTTPS_PROXY` / `HTTP_PROXY` /
+/// `ALL_PROXY` と `NO_PROXY` に従う。
+fn env_proxy(target: &Url) -> Option<Url> {
+    let read = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| std::env::var(key).ok())
+            .filter(|value| !value.trim().is_empty())
+    };
+    let host = target.host_str()?;
+    if let Some(no_proxy) = read(&["NO_PROXY", "no_proxy"]) {
+        if no_proxy
+            .split(',')
+            .any(|pattern| host_matches(pattern, host))
+        {
+            return None;
+        }
+    }
+    let keys: &[&str] = if target.scheme() == "https" {
+        &["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]
+    } else {
+        &["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"]
+    };
+    parse_proxy_url(read(keys)?.trim())
+}
+
+fn resolve_proxy(subsystem: NetworkSubsystem, provider: Option<&str>, target: &Url) -> Option<Url> {
+    let settings = policy().read().unwrap_or_else(|e| e.into_inner());
+    if settings.proxy.is_configured() {
+        settings.proxy.proxy_for(subsystem, provider, target)
+    } else if is_local_url(target) {
+        None
+    } else {
+        env_proxy(target)
+    }
+}
+
+fn with_proxy(
+    builder: ClientBuilder,
+    subsystem: NetworkSubsystem,
+    provider: Option<String>,
+) -> ClientBuilder {
+    builder.proxy(Proxy::custom(move |url| {
+        resolve_proxy(subsystem, provider.as_deref(), url)
+    }))
+}
+
+/// reqwest の既定と同じ上限
+const MAX_REDIRECTS: usize = 10;
+
+/// ローカル宛てからリモートへ飛ばされてもオフラインを破らないよう、転送先ごとに判定する。
+fn checked_redirects(subsystem: NetworkSubsystem) -> Policy {
+    redirect_policy(move |url| ensure_allowed(subsystem, url))
+}
+
+fn redirect_policy(check: impl Fn(&Url) -> Result<(), ApiError> + Send + Sync + 'static) -> Policy {
+    Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+        match check(attempt.url()) {
+            Ok(()) => attempt.follow(),
+            Err(err) => attempt.error(err),
+        }
+    })
+}
+
+/// ループバック宛て（ローカルのローダーなど）はオフラインでも止めない。
+pub fn is_local_url(url: &Url) -> bool {
+    match url.host_str() {
+        Some(host) => {
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            host.eq_ignore_ascii_case("localhost")
+                || host.to_ascii_lowercase().ends_with(".localhost")
+                || host
+                    .parse::<IpAddr>()
+                    .map(|ip| ip.is_loopback())
+                    .unwrap_or(false)
+        }
+        None => false,
+    }
+}
+
+pub fn ensure_allowed(subsystem: NetworkSubsystem, url: &Url) -> Result<(), ApiError> {
+    if current_settings().permits(subsystem, url) {
+        return Ok(());
+    }
+    tracing::debug!(
+        subsystem = subsystem.as_str(),
+        host = url.host_str().unwrap_or_default(),
+        "Blocked outbound request in offline mode"
+    );
+    Err(ApiError::Offline(subsystem))
+}
+
+/// サブシステムに紐づいた HTTP クライアント。リクエストを組み立てる時点で
+/// オフラインモードの判定を行う。
+#[derive(Debug, Clone)]
+pub struct NetClient {
+    inner: Client,
+    subsystem: NetworkSubsystem,
+}
+
+impl NetClient {
+    pub fn new(subsystem: NetworkSubsystem) -> Self {
+        Self::from_builder(subsystem, Client::builder()).unwrap_or_else(|err| {
+            tracing::warn!(
+                "Failed to build HTTP client, proxy settings ignored: {}",
+                err
+            );
+            Self {
+                inner: Client::builder()
+                    .redirect(checked_redirects(subsystem))
+                    .build()
+                    .unwrap_or_default(),
+                subsystem,
+            }
+        })
+    }
+
+    /// タイムアウトなどを付けた `ClientBuilder` から作る。リダイレクトは
+    /// 転送先ごとにオフラインの判定を通して追う。
+    pub fn from_builder(
+        subsystem: NetworkSubsystem,
+        builder: ClientBuilder,
+    ) -> Result<Self, ApiError> {
+        Self::build(
+            subsystem,
+            None,
+            builder.redirect(checked_redirects(subsystem)),
+        )
+    }
+
+    /// リダイレクトを追わない。転送先を呼び出し側が自分で検証するとき（Web 取得）に使う。
+    pub fn without_redirects(
+        subsystem: NetworkSubsystem,
+        builder: ClientBuilder,
+    ) -> Result<Self, ApiError> {
+        Self::build(subsystem, None, builder.redirect(Policy::none()))
+    }
+
+    /// クラウドプロバイダー用。`network.proxy.providers.<provider>` の上書きも見る。
+    pub fn for_provider(provider: &str, builder: ClientBuilder) -> Result<Self, ApiError> {
+        let subsystem = NetworkSubsystem::CloudProviders;
+        Self::build(
+            subsystem,
+            Some(provider.to_string()),
+            builder.redirect(checked_redirects(subsystem)),
+        )
+    }
+
+    fn build(
+        subsystem: NetworkSubsystem,
+        provider: Option<String>,
+        builder: ClientBuilder,
+    ) -> Result<Self, ApiError> {
+        Ok(Self {
+            inner: with_proxy(builder, subsystem, provider)
+                .build()
+                .map_err(ApiError::internal)?,
+            subsystem,
+        })
+    }
+
+    /// リクエストを自分で組み立てるライブラリ（MCP の Streamable HTTP）に渡すクライアント。
+    /// 接続先をいまの設定で確かめてから渡す。リダイレクトの判定はクライアントに残る。
+    pub fn client_for(&self, url: &Url) -> Result<Client, ApiError> {
+        ensure_allowed(self.subsystem, url)?;
+        Ok(self.inner.clone())
+    }
+
+    pub fn get(&self, url: impl IntoUrl) -> Result<RequestBuilder, ApiError> {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post(&self, url: impl IntoUrl) -> Result<RequestBuilder, ApiError> {
+        self.request(Method::POST, url)
+    }
+
+    pub fn head(&self, url: impl IntoUrl) -> Result<RequestBuilder, ApiError> {
+        self.request(Method::HEAD, url)
+    }
+
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> Result<RequestBuilder, ApiError> {
+        let url = url
+            .into_url()
+            .map_err(|err| ApiError::BadRequest(format!("Invalid URL: {}", err)))?;
+        ensure_allowed(self.subsystem, &url)?;
+        Ok(self.inner.request(method, url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(raw: &str) -> Url {
+        Url::parse(raw).unwrap()
+    }
+
+    #[test]
+    fn loopback_hosts_are_local() {
+        assert!(is_local_url(&url("http://localhost:11434/api/tags")));
+        assert!(is_local_url(&url("http://127.0.0.1:8080/health")));
+        assert!(is_local_url(&url("http://[::1]:1234/v1/models")));
+        assert!(!is_local_url(&url("https://huggingface.co/")));
+        assert!(!is_local_url(&url("http://192.168.1.10:8080/")));
+    }
+
+    #[test]
+    fn offline_blocks_remote_unless_subsystem_is_allowed() {
+        let mut settings = NetworkSettings::default();
+        let remote = url("https://registry.modelcontextprotocol.io/v0/servers");
+        assert!(settings.permits(NetworkSubsystem::McpRegistry, &remote));
+
+        settings.offline = true;
+        assert!(!settings.permits(NetworkSubsystem::McpRegistry, &remote));
+        assert!(settings.permits(
+            NetworkSubsystem::CloudProviders,
+            &url("http://localhost:8080")
+        ));
+
+        settings.allow_while_offline = vec![NetworkSubsystem::McpRegistry];
+        assert!(settings.permits(NetworkSubsystem::McpRegistry, &remote));
+        assert!(!settings.permits(NetworkSubsystem::Search, &remote));
+    }
+
+    #[tokio::test]
+    async fn redirects_to_remote_hosts_are_checked_while_offline() {
+        use axum::response::Redirect;
+        use axum::routing::get;
+
+        let app = axum::Router::new()
+            .route("/local", get(|| async { Redirect::temporary("/ok") }))
+            .route("/ok", get(|| async { "ok" }))
+            .route(
+                "/remote",
+                get(|| async { Redirect::temporary("http://203.0.113.1/") }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let settings = NetworkSettings {
+            offline: true,
+            ..NetworkSettings::default()
+        };
+        let client = Client::builder()
+            .redirect(redirect_policy(move |url| {
+                if settings.permits(NetworkSubsystem::McpServers, url) {
+                    Ok(())
+                } else {
+                    Err(ApiError::Offline(NetworkSubsystem::McpServers))
+                }
+            }))
+            .build()
+            .unwrap();
+
+        let local = client
+            .get(format!("http://{addr}/local"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(local.text().await.unwrap(), "ok");
+
+        let err = client
+            .get(format!("http://{addr}/remote"))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_redirect());
+        assert!(format!("{err:?}").contains("Offline(McpServers)"));
+    }
+
+    #[test]
+    fn proxy_is_chosen_per_provider_subsystem_and_host() {
+        let proxy: ProxySettings = serde_json::from_value(serde_json::json!({
+            "url": "http://proxy.corp:8080",
+            "no_proxy": ["*.intranet.corp"],
+            "subsystems": {
+                "models": { "url": "socks5h://socks.corp:1080" },
+                "search": { "direct": true }
+            },
+            "providers": { "openai": { "url": "http://egress.corp:3128" } }
+        }))
+        .unwrap();
+        let hf = url("https://huggingface.co/model.gguf");
+        let via = |subsystem, provider, target: &Url| {
+            proxy
+                .proxy_for(subsystem, provider, target)
+                .map(|url| url.to_string())
+        };
+
+        assert!(proxy.is_configured());
+        assert_eq!(
+            via(NetworkSubsystem::Models, None, &hf).as_deref(),
+            Some("socks5h://socks.corp:1080")
+        );
+        assert_eq!(
+            via(NetworkSubsystem::McpRegistry, None, &hf).as_deref(),
+            Some("http://proxy.corp:8080/")
+        );
+        assert_eq!(via(NetworkSubsystem::Search, None, &hf), None);
+        assert_eq!(
+            via(
+                NetworkSubsystem::CloudProviders,
+                Some("openai"),
+                &url("https://api.openai.com/v1/chat/completions")
+            )
+            .as_deref(),
+            Some("http://egress.corp:3128/")
+        );
+        assert_eq!(
+            via(
+                NetworkSubsystem::Models,
+                None,
+                &url("https://git.intranet.corp/x")
+            ),
+            None
+        );
+        assert_eq!(
+            via(
+                NetworkSubsystem::Models,
+                None,
+                &url("http://127.0.0.1:8080")
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn only_hosts_limits_the_proxy_to_listed_domains() {
+        let proxy = ProxySettings {
+            url: Some("http://proxy.corp:8080".to_string()),
+            only_hosts: vec!["*.github.com".to_string(), "huggingface.co".to_string()],
+            ..ProxySettings::default()
+        };
+        let via = |raw: &str| {
+            proxy
+                .proxy_for(NetworkSubsystem::BinaryUpdates, None, &url(raw))
+                .is_some()
+        };
+        assert!(via("https://api.github.com/repos/x/releases"));
+        assert!(via("https://github.com/x"));
+        assert!(via("https://huggingface.co/x"));
+        assert!(!via("https://cdn-lfs.huggingface.co/x"));
+        assert!(!via("https://example.com/"));
+        assert!(!ProxySettings::default().is_configured());
+    }
+
+    #[test]
+    fn proxy_config_is_validated() {
+        use crate::core::config::validation::validate_config;
+        let with_proxy = |proxy| validate_config(&serde_json::json!({"network": {"proxy": proxy}}));
+
+        assert!(with_proxy(serde_json::json!({
+            "url": "socks5h://127.0.0.1:1080",
+            "subsystems": {"binary_updates": {"direct": true}},
+            "providers": {"anthropic": {"url": "https://proxy.corp"}}
+        }))
+        .is_ok());
+        assert!(with_proxy(serde_json::json!({"url": "ftp://proxy.corp"})).is_err());
+        assert!(with_proxy(serde_json::json!({"subsystems": {"downloads": {}}})).is_err());
+        assert!(with_proxy(serde_json::json!({"no_proxy": "localhost"})).is_err());
+    }
+}
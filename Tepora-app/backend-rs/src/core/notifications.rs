@@ -0,0 +1,119 @@
+//! バックグラウンド処理の完了通知。
+//!
+//! エージェント実行・モデルのダウンロード・llama.cpp バイナリの更新が終わったら
+//! WebSocket の `notification` イベントとして流す。デスクトップ版はこれを受けて、
+//! ウィンドウが隠れているときに OS の通知を出す。種類ごとの送信可否は設定の
+//! `notifications` セクションで決める。
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use super::config::schema::NotificationSettings;
+use super::config::ConfigService;
+
+const NOTIFICATION_CAPACITY: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    AgentRun,
+    Download,
+    BinaryUpdate,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackgroundNotification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    pub success: bool,
+    pub session_id: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+impl BackgroundNotification {
+    pub fn new(
+        kind: NotificationKind,
+        success: bool,
+        title: impl Into<String>,
+        body: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            title: title.into(),
+            body: body.into(),
+            success,
+            session_id: None,
+            at: Utc::now(),
+        }
+    }
+
+    pub fn with_session(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+}
+
+#[derive(Clone)]
+pub struct NotificationHub {
+    tx: broadcast::Sender<BackgroundNotification>,
+    config: ConfigService,
+}
+
+impl NotificationHub {
+    pub fn new(config: ConfigService) -> Self {
+        Self {
+            tx: broadcast::channel(NOTIFICATION_CAPACITY).0,
+            config,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<BackgroundNotification> {
+        self.tx.subscribe()
+    }
+
+    /// 設定で許可されている種類だけ送る。送ったら `true`。
+    pub fn publish(&self, notification: BackgroundNotification) -> bool {
+        let settings = self
+            .config
+            .load_typed()
+            .map(|config| config.notifications)
+            .unwrap_or_default();
+        if !settings.allows(notification.kind) {
+            return false;
+        }
+        // 購読者がいなければ誰にも届かないが、それで構わない
+        let _ = self.tx.send(notification);
+        true
+    }
+}
+
+impl NotificationSettings {
+    pub fn allows(&self, kind: NotificationKind) -> bool {
+        self.enabled
+            && match kind {
+                NotificationKind::AgentRun => self.agent_runs,
+                NotificationKind::Download => self.downloads,
+                NotificationKind::BinaryUpdate => self.binary_updates,
+            }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn per_kind_preferences_are_respected() {
+        let mut settings = NotificationSettings::default();
+        assert!(settings.allows(NotificationKind::Download));
+
+        settings.downloads = false;
+        assert!(!settings.allows(NotificationKind::Download));
+        assert!(settings.allows(NotificationKind::AgentRun));
+
+        settings.enabled = false;
+        assert!(!settings.allows(NotificationKind::AgentRun));
+    }
+}
@@ -0,0 +1,94 @@
+//! セーフモード（`--safe-mode` / `TEPORA_SAFE_MODE=1`）。
+//!
+//! 拡張の設定ミスで起動できなくなったときの復旧用。MCP サーバーへは接続せず、
+//! プラグインを読まず、定期実行の処理を止め、llama.cpp 以外のローダーを使わない。
+//! 設定の編集はそのままできるので、直してから通常どおり起動し直す。
+//! 起動時に一度だけ決まり、実行中は変わらない。
+
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use super::errors::ApiError;
+
+pub const SAFE_MODE_FLAG: &str = "--safe-mode";
+pub const SAFE_MODE_ENV: &str = "TEPORA_SAFE_MODE";
+
+static ACTIVE: OnceLock<SafeModeSource> = OnceLock::new();
+
+/// セーフモードを有効にした指定元
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafeModeSource {
+    Flag,
+    Env,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SafeModeStatus {
+    pub active: bool,
+    pub source: Option<SafeModeSource>,
+}
+
+/// コマンドライン引数と環境変数の値から判定する。フラグを優先する。
+pub fn detect<I, S>(args: I, env_value: Option<&str>) -> Option<SafeModeSource>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    if args.into_iter().any(|arg| arg.as_ref() == SAFE_MODE_FLAG) {
+        return Some(SafeModeSource::Flag);
+    }
+    let value = env_value?.trim().to_ascii_lowercase();
+    matches!(value.as_str(), "1" | "true" | "yes" | "on").then_some(SafeModeSource::Env)
+}
+
+/// 起動時に呼ぶ。2 回目以降は無視する。
+pub fn activate(source: SafeModeSource) {
+    if ACTIVE.set(source).is_ok() {
+        tracing::warn!(
+            source = ?source,
+            "Safe mode is active: MCP, plugins and schedulers are disabled, only llama_cpp models are used"
+        );
+    }
+}
+
+pub fn is_active() -> bool {
+    ACTIVE.get().is_some()
+}
+
+pub fn status() -> SafeModeStatus {
+    SafeModeStatus {
+        active: is_active(),
+        source: ACTIVE.get().copied(),
+    }
+}
+
+/// セーフモード中は llama.cpp 以外のローダーを拒む。
+pub fn ensure_loader_allowed(loader: &str) -> Result<(), ApiError> {
+    if is_active() && loader != "llama_cpp" {
+        return Err(ApiError::ServiceUnavailable(format!(
+            "Safe mode is active: loader '{}' is disabled, only llama_cpp models can be used",
+            loader
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_prefers_the_flag_and_accepts_truthy_env_values() {
+        let no_args: [&str; 0] = [];
+        assert_eq!(
+            detect(["tepora-backend", "--safe-mode"], Some("0")),
+            Some(SafeModeSource::Flag)
+        );
+        assert_eq!(detect(no_args, Some(" 1 ")), Some(SafeModeSource::Env));
+        assert_eq!(detect(no_args, Some("TRUE")), Some(SafeModeSource::Env));
+        assert_eq!(detect(no_args, Some("0")), None);
+        assert_eq!(detect(["--safe"], None), None);
+    }
+}
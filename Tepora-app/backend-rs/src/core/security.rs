@@ -9,6 +9,7 @@ use chrono::{DateTime, Utc};
 use rand::RngCore;
 use subtle::ConstantTimeEq;
 
+use crate::core::config::users::{active_user_id, user_home, validate_user_id};
 use crate::core::errors::ApiError;
 
 const API_KEY_HEADER: &str = "x-api-key";
@@ -22,6 +23,8 @@ const DEFAULT_TOKEN_TTL_DAYS: u64 = 7;
 #[derive(Debug, Clone)]
 pub struct SessionToken {
     value: String,
+    /// トークンを発行したユーザー。値の先頭に `<user>.` として埋め込む
+    scope: String,
     /// トークン発行時刻（有効期限チェック用）
     created_at: DateTime<Utc>,
     /// トークンの有効期限
@@ -33,6 +36,10 @@ impl SessionToken {
         &self.value
     }
 
+    pub fn scope(&self) -> &str {
+        &self.scope
+    }
+
     pub fn is_expired(&self) -> bool {
         Utc::now() >= self.expires_at
     }
@@ -57,9 +64,7 @@ impl SessionToken {
     }
 
     pub fn reissue(&mut self) -> Result<String, ApiError> {
-        let mut token_bytes = [0u8; 32];
-        rand::rng().fill_bytes(&mut token_bytes);
-        let new_token = hex::encode(token_bytes);
+        let new_token = generate_scoped_token(&self.scope);
         self.value = new_token.clone();
 
         let now = Utc::now();
@@ -71,31 +76,19 @@ impl SessionToken {
         self.created_at = now;
         self.expires_at = now + chrono::Duration::days(ttl_days as i64);
 
-        let token_path = session_token_path();
+        let token_path = session_token_path(&self.scope);
         if let Some(parent) = token_path.parent() {
             let _ = fs::create_dir_all(parent);
         }
         fs::write(&token_path, &new_token).map_err(ApiError::internal)?;
-
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            if let Ok(metadata) = fs::metadata(&token_path) {
-                let mut perms = metadata.permissions();
-                perms.set_mode(0o600);
-                let _ = fs::set_permissions(&token_path, perms);
-            }
-        }
-        #[cfg(windows)]
-        {
-            apply_windows_token_acl(&token_path);
-        }
+        restrict_token_file(&token_path);
 
         Ok(new_token)
     }
 }
 
 pub fn init_session_token() -> SessionToken {
+    let scope = active_user_id();
     let now = Utc::now();
     let ttl_days = env::var("TEPORA_TOKEN_TTL_DAYS")
         .ok()
@@ -107,49 +100,98 @@ pub fn init_session_token() -> SessionToken {
         if !token.trim().is_empty() {
             return SessionToken {
                 value: token,
+                scope,
                 created_at: now,
                 expires_at,
             };
         }
     }
 
-    let mut token_bytes = [0u8; 32];
-    rand::rng().fill_bytes(&mut token_bytes);
-    let token = hex::encode(token_bytes);
-    let token_path = session_token_path();
+    let token = generate_scoped_token(&scope);
+    let token_path = session_token_path(&scope);
     if let Some(parent) = token_path.parent() {
         let _ = fs::create_dir_all(parent);
     }
     if let Err(err) = fs::write(&token_path, &token) {
         tracing::warn!("Failed to write session token: {}", err);
     }
-
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        if let Ok(metadata) = fs::metadata(&token_path) {
-            let mut perms = metadata.permissions();
-            perms.set_mode(0o600);
-            let _ = fs::set_permissions(&token_path, perms);
-        }
-    }
-    #[cfg(windows)]
-    {
-        apply_windows_token_acl(&token_path);
-    }
+    restrict_token_file(&token_path);
 
     SessionToken {
         value: token,
+        scope,
         created_at: now,
         expires_at,
     }
 }
 
-fn session_token_path() -> PathBuf {
+fn generate_scoped_token(scope: &str) -> String {
+    let mut token_bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut token_bytes);
+    format!("{}.{}", scope, hex::encode(token_bytes))
+}
+
+/// トークンに埋め込まれたユーザー。環境変数で渡された素のトークンなら `None`。
+pub fn token_scope(token: &str) -> Option<&str> {
+    token
+        .split_once('.')
+        .map(|(scope, _)| scope)
+        .filter(|scope| validate_user_id(scope).is_ok())
+}
+
+/// ユーザーごとのトークンファイル。デスクトップシェルもここから読む。
+pub fn session_token_path(user_id: &str) -> PathBuf {
     let home = env::var("HOME")
         .or_else(|_| env::var("USERPROFILE"))
         .unwrap_or_else(|_| ".".to_string());
-    PathBuf::from(home).join(".tepora").join(".session_token")
+    user_home(&PathBuf::from(home).join(".tepora"), user_id).join(".session_token")
+}
+
+/// デスクトップシェルだけが持つ連携用の鍵。シェルが起動ごとに作り直して
+/// `/api/desktop/bridge` に付けて繋ぎ、バックエンドはこのファイルと照合する。
+/// UI（WebView）からはファイルを読めないので、UI がシェルになりすますことはできない。
+pub fn desktop_bridge_token_path(user_id: &str) -> PathBuf {
+    session_token_path(user_id).with_file_name(".desktop_bridge_token")
+}
+
+/// デスクトップシェル（src-tauri）が起動時に呼ぶ。
+#[allow(dead_code)]
+pub fn issue_desktop_bridge_token(user_id: &str) -> Result<String, ApiError> {
+    let mut token_bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut token_bytes);
+    let token = hex::encode(token_bytes);
+    let path = desktop_bridge_token_path(user_id);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(ApiError::internal)?;
+    }
+    fs::write(&path, &token).map_err(ApiError::internal)?;
+    restrict_token_file(&path);
+    Ok(token)
+}
+
+pub fn verify_desktop_bridge_token(user_id: &str, presented: &str) -> bool {
+    let Ok(expected) = fs::read_to_string(desktop_bridge_token_path(user_id)) else {
+        return false;
+    };
+    let expected = expected.trim();
+    !expected.is_empty() && bool::from(expected.as_bytes().ct_eq(presented.trim().as_bytes()))
+}
+
+/// トークンファイルを本人だけが読めるようにする。
+fn restrict_token_file(path: &std::path::Path) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = fs::metadata(path) {
+            let mut perms = metadata.permissions();
+            perms.set_mode(0o600);
+            let _ = fs::set_permissions(path, perms);
+        }
+    }
+    #[cfg(windows)]
+    {
+        apply_windows_token_acl(path);
+    }
 }
 
 #[cfg(windows)]
@@ -204,6 +246,14 @@ pub fn require_api_key(
     let input = header_value.as_bytes();
     let expected_bytes = expected.value().as_bytes();
     if input.len() != expected_bytes.len() || input.ct_ne(expected_bytes).into() {
+        // 別ユーザーのバックエンドへ向けたトークンは原因が分かるよう記録する
+        if let Some(scope) = token_scope(header_value).filter(|scope| *scope != expected.scope()) {
+            tracing::warn!(
+                token_user = scope,
+                backend_user = expected.scope(),
+                "Rejected a session token issued for another user"
+            );
+        }
         return Err(ApiError::Unauthorized);
     }
 
@@ -242,6 +292,7 @@ mod tests {
         let now = Utc::now();
         SessionToken {
             value: value.to_string(),
+            scope: "default".to_string(),
             created_at: now,
             expires_at: now + Duration::days(DEFAULT_TOKEN_TTL_DAYS as i64),
         }
@@ -252,6 +303,7 @@ mod tests {
         let created_at = now - Duration::days(8);
         SessionToken {
             value: value.to_string(),
+            scope: "default".to_string(),
             created_at,
             expires_at: created_at + Duration::days(DEFAULT_TOKEN_TTL_DAYS as i64),
         }
@@ -357,4 +409,14 @@ mod tests {
 
         assert_eq!(result, None);
     }
+
+    #[test]
+    fn generated_tokens_carry_the_user_scope() {
+        let token = generate_scoped_token("alice");
+        assert_eq!(token_scope(&token), Some("alice"));
+        assert_ne!(token, generate_scoped_token("alice"));
+        // 環境変数で渡された素のトークンはスコープなし
+        assert_eq!(token_scope("test-secret-token"), None);
+        assert!(session_token_path("alice").ends_with("users/alice/.session_token"));
+    }
 }
@@ -1,6 +1,6 @@
 use crate::agent::skill_registry::AgentSkillPackage;
 use crate::core::errors::ApiError;
-use crate::history::{HistoryMessage, SessionInfo};
+use crate::history::{HistoryMessage, ImportMergeReport, SessionInfo};
 use aes_gcm::aead::{Aead, KeyInit};
 use aes_gcm::{Aes256Gcm, Nonce};
 use argon2::Argon2;
@@ -78,6 +78,9 @@ pub struct BackupImportResult {
     pub manifest: BackupManifest,
     pub sessions: usize,
     pub applied: bool,
+    /// 既存の履歴との突き合わせ結果（`verify` では空）
+    #[serde(default)]
+    pub merge: ImportMergeReport,
 }
 
 pub fn build_backup_config(config: &Value, request: &BackupExportRequest) -> Option<Value> {
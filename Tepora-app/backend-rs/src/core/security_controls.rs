@@ -5,8 +5,10 @@ use crate::agent::skill_registry::{SkillExportBundle, SkillRegistry};
 use chrono::Utc;
 use serde_json::{json, Map, Value};
 
+use crate::core::config::migrator::{CONFIG_VERSION_KEY, CURRENT_CONFIG_VERSION};
 use crate::core::config::{AppPaths, ConfigService};
 use crate::core::errors::ApiError;
+use crate::history::{plan_merge, HistoryMessage, ImportMergeReport, SessionInfo};
 use crate::workspace::ProjectHistoryStore;
 
 #[allow(unused_imports)]
@@ -259,7 +261,8 @@ impl SecurityControls {
         let config = self.config.load_config()?;
         let manifest = BackupManifest {
             schema_version: config
-                .get("schema_version")
+                .get(CONFIG_VERSION_KEY)
+                .or_else(|| config.get("schema_version"))
                 .and_then(|value| value.as_u64())
                 .unwrap_or(0),
             exported_at: Utc::now().to_rfc3339(),
@@ -333,7 +336,7 @@ impl SecurityControls {
         let decrypted = decrypt_backup(&request.passphrase, &request.archive)?;
         let payload: BackupPayload =
             serde_json::from_slice(&decrypted).map_err(ApiError::internal)?;
-        if payload.manifest.schema_version > 2 {
+        if payload.manifest.schema_version > CURRENT_CONFIG_VERSION {
             return Err(ApiError::Conflict(format!(
                 "Backup schema_version={} is newer than this app supports",
                 payload.manifest.schema_version
@@ -358,24 +361,37 @@ impl SecurityControls {
                     skills: payload.agent_skills.clone(),
                 })?;
             }
-            for session in &payload.sessions {
-                let _ = history.delete_session(&session.session.id).await;
-                for message in &session.messages {
-                    history
-                        .add_message(
-                            &session.session.id,
-                            &message.message_type,
-                            &message.content,
-                            message.additional_kwargs.clone(),
-                        )
-                        .await?;
-                }
-                if let Some(title) = session.session.title.as_deref() {
-                    let _ = history
-                        .update_session_title(&session.session.id, title)
-                        .await;
+        }
+
+        let mut merge = ImportMergeReport::default();
+        if stage != "verify" {
+            let local = load_local_sessions(history, &payload.sessions).await?;
+            let incoming = payload
+                .sessions
+                .iter()
+                .map(|session| (session.session.clone(), session.messages.clone()))
+                .collect::<Vec<_>>();
+            let plan = plan_merge(&local, &incoming);
+            if stage == "apply" {
+                for action in &plan.actions {
+                    for message in &action.messages {
+                        history
+                            .add_message(
+                                &action.session_id,
+                                &message.message_type,
+                                &message.content,
+                                message.additional_kwargs.clone(),
+                            )
+                            .await?;
+                    }
+                    if let Some(title) = action.title.as_deref() {
+                        let _ = history
+                            .update_session_title(&action.session_id, title)
+                            .await;
+                    }
                 }
             }
+            merge = plan.report;
         }
 
         self.record_audit(
@@ -384,6 +400,8 @@ impl SecurityControls {
             json!({
                 "sessions": payload.sessions.len(),
                 "schema_version": payload.manifest.schema_version,
+                "messages_added": merge.messages_added,
+                "conflicts": merge.conflicts.len(),
             }),
         )?;
         Ok(BackupImportResult {
@@ -391,10 +409,34 @@ impl SecurityControls {
             manifest: payload.manifest,
             sessions: payload.sessions.len(),
             applied: request.stage.eq_ignore_ascii_case("apply"),
+            merge,
         })
     }
 }
 
+/// 取り込み先と突き合わせる既存セッション。現在のプロジェクトに加え、
+/// 同じ ID のセッションが別プロジェクトにあればそれも含める。
+async fn load_local_sessions(
+    history: &ProjectHistoryStore,
+    incoming: &[BackupSession],
+) -> Result<Vec<(SessionInfo, Vec<HistoryMessage>)>, ApiError> {
+    let mut sessions = history.list_sessions().await?;
+    for session in incoming {
+        if sessions.iter().any(|known| known.id == session.session.id) {
+            continue;
+        }
+        if let Some(found) = history.get_session(&session.session.id).await? {
+            sessions.push(found);
+        }
+    }
+    let mut local = Vec::with_capacity(sessions.len());
+    for session in sessions {
+        let messages = history.get_history(&session.id, 0).await?;
+        local.push((session, messages));
+    }
+    Ok(local)
+}
+
 fn ensure_object<'a>(root: &'a mut Map<String, Value>, key: &str) -> &'a mut Map<String, Value> {
     let value = root
         .entry(key.to_string())
@@ -1,10 +1,11 @@
 use chrono::{Duration, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
 use crate::core::errors::ApiError;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum ApprovalDecision {
     Deny,
@@ -95,7 +96,7 @@ pub struct ToolApprovalRequestPayload {
     pub expiry_options: Vec<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
 pub struct ToolApprovalResponsePayload {
     #[serde(default)]
     pub decision: ApprovalDecision,
@@ -103,6 +104,9 @@ pub struct ToolApprovalResponsePayload {
     pub ttl_seconds: Option<u64>,
     #[serde(default)]
     pub approved: Option<bool>,
+    /// `plan_estimate` への返答で残すステップ（0 始まり）。省略時はすべて残す
+    #[serde(rename = "keepSteps", default, skip_serializing_if = "Option::is_none")]
+    pub keep_steps: Option<Vec<usize>>,
 }
 
 impl ToolApprovalResponsePayload {
@@ -111,6 +115,7 @@ impl ToolApprovalResponsePayload {
             decision: ApprovalDecision::Once,
             ttl_seconds: None,
             approved: Some(true),
+            keep_steps: None,
         }
     }
 
@@ -119,6 +124,7 @@ impl ToolApprovalResponsePayload {
             decision: ApprovalDecision::Deny,
             ttl_seconds: None,
             approved: Some(false),
+            keep_steps: None,
         }
     }
 
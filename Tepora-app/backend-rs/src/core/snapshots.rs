@@ -0,0 +1,276 @@
+//! 重要な設定ファイルのスナップショットと起動時の整合性確認。
+//!
+//! 保存は一時ファイルへの書き込みと置き換えで行い、成功するたびに
+//! `<dir>/.snapshots/<name>.<時刻>` へ同じ内容を残す（新しいものから `SNAPSHOT_LIMIT` 件）。
+//! 起動時に読めないファイルを見つけたら、読める最新のスナップショットで置き換え、
+//! 壊れたファイルは `<name>.corrupt.<時刻>` として脇に残す。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::core::errors::ApiError;
+
+const SNAPSHOT_DIR: &str = ".snapshots";
+pub const SNAPSHOT_LIMIT: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileFormat {
+    Yaml,
+    Json,
+}
+
+impl FileFormat {
+    fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yml" | "yaml") => Self::Yaml,
+            _ => Self::Json,
+        }
+    }
+
+    fn parse(self, contents: &str) -> Result<(), String> {
+        if contents.trim().is_empty() {
+            return Err("file is empty".to_string());
+        }
+        match self {
+            Self::Yaml => serde_yaml::from_str::<serde_yaml::Value>(contents)
+                .map_err(|err| err.to_string())
+                .and_then(|value| match value {
+                    serde_yaml::Value::Mapping(_) => Ok(()),
+                    _ => Err("top level is not a mapping".to_string()),
+                }),
+            Self::Json => serde_json::from_str::<serde_json::Value>(contents)
+                .map(|_| ())
+                .map_err(|err| err.to_string()),
+        }
+    }
+}
+
+/// 書き込みが途中で止まっても元のファイルが残るよう、置き換えで保存してスナップショットを残す。
+pub fn write_with_snapshot(path: &Path, contents: &str) -> Result<(), ApiError> {
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let temp = sibling(path, "tmp");
+    fs::write(&temp, contents).map_err(ApiError::internal)?;
+    fs::rename(&temp, path).map_err(|err| {
+        let _ = fs::remove_file(&temp);
+        ApiError::internal(err)
+    })?;
+
+    // スナップショットの失敗で保存そのものは失敗させない
+    if let Err(err) = write_snapshot(path, contents) {
+        tracing::warn!(
+            path = %path.display(),
+            "Failed to write config snapshot: {}",
+            err
+        );
+    }
+    Ok(())
+}
+
+fn write_snapshot(path: &Path, contents: &str) -> std::io::Result<()> {
+    let Some(name) = file_name(path) else {
+        return Ok(());
+    };
+    let dir = snapshot_dir(path);
+    fs::create_dir_all(&dir)?;
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f");
+    fs::write(dir.join(format!("{name}.{stamp}")), contents)?;
+    for stale in list_snapshots(path).into_iter().skip(SNAPSHOT_LIMIT) {
+        let _ = fs::remove_file(stale);
+    }
+    Ok(())
+}
+
+fn snapshot_dir(path: &Path) -> PathBuf {
+    path.parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(SNAPSHOT_DIR)
+}
+
+fn file_name(path: &Path) -> Option<&str> {
+    path.file_name().and_then(|name| name.to_str())
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = file_name(path).unwrap_or("file");
+    path.with_file_name(format!("{name}.{suffix}"))
+}
+
+/// 新しい順のスナップショット。
+fn list_snapshots(path: &Path) -> Vec<PathBuf> {
+    let Some(name) = file_name(path) else {
+        return Vec::new();
+    };
+    let prefix = format!("{name}.");
+    let mut snapshots: Vec<PathBuf> = fs::read_dir(snapshot_dir(path))
+        .ok()
+        .into_iter()
+        .flat_map(|entries| entries.filter_map(Result::ok))
+        .map(|entry| entry.path())
+        .filter(|candidate| {
+            file_name(candidate).is_some_and(|candidate| {
+                candidate
+                    .strip_prefix(&prefix)
+                    .is_some_and(|stamp| stamp.starts_with(|c: char| c.is_ascii_digit()))
+            })
+        })
+        .collect();
+    snapshots.sort_by(|left, right| right.file_name().cmp(&left.file_name()));
+    snapshots
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileIntegrity {
+    Ok,
+    /// 壊れていたのでスナップショットから戻した
+    Restored,
+    /// 壊れていて、戻せるスナップショットもなかった
+    Corrupt,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileCheck {
+    pub path: String,
+    pub status: FileIntegrity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restored_from: Option<String>,
+    /// 壊れていたファイルの退避先
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quarantined_to: Option<String>,
+}
+
+/// 起動時の確認結果。`/api/status` の `integrity` に載る。
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IntegrityReport {
+    pub files: Vec<FileCheck>,
+}
+
+impl IntegrityReport {
+    /// 確認してから読み込むファイル。存在しないものは対象外（既定値で始まる）。
+    pub fn check(&mut self, paths: &[PathBuf]) {
+        for path in paths.iter().filter(|path| path.exists()) {
+            let check = verify_and_restore(path);
+            match check.status {
+                FileIntegrity::Ok => {}
+                FileIntegrity::Restored => tracing::warn!(
+                    path = %check.path,
+                    snapshot = check.restored_from.as_deref().unwrap_or_default(),
+                    "Restored unreadable file from snapshot: {}",
+                    check.error.as_deref().unwrap_or_default()
+                ),
+                FileIntegrity::Corrupt => tracing::error!(
+                    path = %check.path,
+                    "File is unreadable and no usable snapshot exists: {}",
+                    check.error.as_deref().unwrap_or_default()
+                ),
+            }
+            self.files.push(check);
+        }
+    }
+
+    /// 戻せなかったファイルがある。復元できたものは報告だけで劣化扱いにしない
+    pub fn is_degraded(&self) -> bool {
+        self.files
+            .iter()
+            .any(|check| check.status == FileIntegrity::Corrupt)
+    }
+}
+
+fn verify_and_restore(path: &Path) -> FileCheck {
+    let format = FileFormat::of(path);
+    let mut check = FileCheck {
+        path: path.display().to_string(),
+        status: FileIntegrity::Ok,
+        error: None,
+        restored_from: None,
+        quarantined_to: None,
+    };
+    let error = match fs::read_to_string(path) {
+        Ok(contents) => match format.parse(&contents) {
+            Ok(()) => return check,
+            Err(err) => err,
+        },
+        Err(err) => err.to_string(),
+    };
+    check.error = Some(error);
+
+    let snapshot = list_snapshots(path).into_iter().find_map(|snapshot| {
+        let contents = fs::read_to_string(&snapshot).ok()?;
+        format.parse(&contents).ok()?;
+        Some((snapshot, contents))
+    });
+    let Some((snapshot, contents)) = snapshot else {
+        check.status = FileIntegrity::Corrupt;
+        return check;
+    };
+
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S");
+    let quarantine = sibling(path, &format!("corrupt.{stamp}"));
+    if fs::rename(path, &quarantine).is_ok() {
+        check.quarantined_to = Some(quarantine.display().to_string());
+    }
+    match fs::write(path, contents) {
+        Ok(()) => {
+            check.status = FileIntegrity::Restored;
+            check.restored_from = Some(snapshot.display().to_string());
+        }
+        Err(err) => {
+            check.status = FileIntegrity::Corrupt;
+            check.error = Some(format!("restore failed: {err}"));
+        }
+    }
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_rotate_and_restore_a_half_written_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("models.json");
+        for revision in 0..(SNAPSHOT_LIMIT + 2) {
+            write_with_snapshot(&path, &format!("{{\"revision\": {revision}}}")).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        assert_eq!(list_snapshots(&path).len(), SNAPSHOT_LIMIT);
+        assert!(!sibling(&path, "tmp").exists());
+
+        fs::write(&path, "{\"revision\": ").unwrap();
+        let mut report = IntegrityReport::default();
+        report.check(&[path.clone(), dir.path().join("missing.yml")]);
+        assert_eq!(report.files.len(), 1);
+        assert_eq!(report.files[0].status, FileIntegrity::Restored);
+        assert!(!report.is_degraded());
+        let restored = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            restored,
+            format!("{{\"revision\": {}}}", SNAPSHOT_LIMIT + 1)
+        );
+        let quarantined = report.files[0].quarantined_to.clone().unwrap();
+        assert_eq!(fs::read_to_string(quarantined).unwrap(), "{\"revision\": ");
+    }
+
+    #[test]
+    fn unreadable_file_without_snapshot_is_reported_and_left_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.yml");
+        fs::write(&config, "app: [unclosed").unwrap();
+        let healthy = dir.path().join("secrets.yaml");
+        fs::write(&healthy, "llm: {}\n").unwrap();
+
+        let mut report = IntegrityReport::default();
+        report.check(&[config.clone(), healthy]);
+        assert_eq!(report.files[0].status, FileIntegrity::Corrupt);
+        assert_eq!(report.files[1].status, FileIntegrity::Ok);
+        assert!(report.is_degraded());
+        assert_eq!(fs::read_to_string(&config).unwrap(), "app: [unclosed");
+    }
+}
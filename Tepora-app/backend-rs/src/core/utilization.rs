@@ -0,0 +1,327 @@
+//! CPU・メモリ・GPU の使用率。
+//!
+//! 生成が遅い理由（GPU に載り切らず CPU で回っている、VRAM が埋まっている等）を
+//! フロントエンドに見せるためのもの。CPU とメモリ、llama-server プロセスは sysinfo、
+//! GPU は NVIDIA なら `nvidia-smi`（NVML）、macOS なら `ioreg` の Metal 統計から読む。
+//! どちらも無い環境では `gpus` が空になる。
+
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tokio::process::Command;
+use tokio::sync::broadcast;
+
+const UTILIZATION_EVENT_CAPACITY: usize = 8;
+const GPU_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+const MIB: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryUtilization {
+    pub used_bytes: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GpuUtilization {
+    pub name: String,
+    /// `nvml` または `metal`
+    pub source: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utilization_percent: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vram_used_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vram_total_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessUtilization {
+    pub pid: u32,
+    /// マシン全体を 100 とした CPU 使用率
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UtilizationSnapshot {
+    pub cpu_percent: f32,
+    pub memory: MemoryUtilization,
+    pub gpus: Vec<GpuUtilization>,
+    /// llama.cpp ローダーで llama-server が動いているときだけ
+    pub llama_server: Option<ProcessUtilization>,
+    pub sampled_at: DateTime<Utc>,
+}
+
+/// 使用率の採取と WebSocket への配信。CPU 使用率は前回の採取との差分で出るので
+/// `System` を持ち回る。
+#[derive(Clone)]
+pub struct UtilizationMonitor {
+    system: Arc<Mutex<SystemState>>,
+    tx: broadcast::Sender<UtilizationSnapshot>,
+    streaming: Arc<AtomicBool>,
+}
+
+struct SystemState {
+    system: System,
+    /// 直前の採取で見ていたプロセス。変わったら CPU の差分を取り直す
+    primed_pid: Option<Option<u32>>,
+}
+
+impl Default for UtilizationMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UtilizationMonitor {
+    pub fn new() -> Self {
+        Self {
+            system: Arc::new(Mutex::new(SystemState {
+                system: System::new(),
+                primed_pid: None,
+            })),
+            tx: broadcast::channel(UTILIZATION_EVENT_CAPACITY).0,
+            streaming: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<UtilizationSnapshot> {
+        self.tx.subscribe()
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        self.tx.receiver_count() > 0
+    }
+
+    pub fn publish(&self, snapshot: UtilizationSnapshot) {
+        let _ = self.tx.send(snapshot);
+    }
+
+    /// 配信タスクを起動する権利を取る。既に動いていれば `false`。
+    pub fn try_start_stream(&self) -> bool {
+        !self.streaming.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn stream_stopped(&self) {
+        self.streaming.store(false, Ordering::SeqCst);
+    }
+
+    /// 現在の使用率を採取する。`llama_server` は `(pid, model_id)`。
+    pub async fn sample(&self, llama_server: Option<(u32, Option<String>)>) -> UtilizationSnapshot {
+        let pid = llama_server.as_ref().map(|(pid, _)| *pid);
+        let system = self.system.clone();
+        let host = tokio::task::spawn_blocking(move || sample_host(&system, pid));
+        let (host, gpus) = tokio::join!(host, sample_gpus());
+        let (cpu_percent, memory, process) = host.unwrap_or_else(|err| {
+            tracing::debug!("Utilization sampling task failed: {}", err);
+            (
+                0.0,
+                MemoryUtilization {
+                    used_bytes: 0,
+                    total_bytes: 0,
+                },
+                None,
+            )
+        });
+        UtilizationSnapshot {
+            cpu_percent,
+            memory,
+            gpus,
+            llama_server: llama_server
+                .zip(process)
+                .map(|((pid, model_id), (cpu, mem))| ProcessUtilization {
+                    pid,
+                    cpu_percent: cpu,
+                    memory_bytes: mem,
+                    model_id,
+                }),
+            sampled_at: Utc::now(),
+        }
+    }
+}
+
+type HostSample = (f32, MemoryUtilization, Option<(f32, u64)>);
+
+fn sample_host(state: &Mutex<SystemState>, pid: Option<u32>) -> HostSample {
+    let mut state = state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let pids = pid.map(Pid::from_u32).into_iter().collect::<Vec<_>>();
+    let refresh = |system: &mut System| {
+        system.refresh_cpu_usage();
+        if !pids.is_empty() {
+            system.refresh_processes_specifics(
+                ProcessesToUpdate::Some(&pids),
+                true,
+                ProcessRefreshKind::nothing().with_cpu().with_memory(),
+            );
+        }
+    };
+    // CPU 使用率は 2 回の更新の差分なので、初回とプロセスが替わったときは間を空けて 2 回読む
+    if state.primed_pid != Some(pid) {
+        refresh(&mut state.system);
+        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        state.primed_pid = Some(pid);
+    }
+    refresh(&mut state.system);
+    state.system.refresh_memory();
+
+    let system = &state.system;
+    let cores = system.cpus().len().max(1) as f32;
+    let process = pids
+        .first()
+        .and_then(|pid| system.process(*pid))
+        .map(|process| (process.cpu_usage() / cores, process.memory()));
+    (
+        system.global_cpu_usage(),
+        MemoryUtilization {
+            used_bytes: system.used_memory(),
+            total_bytes: system.total_memory(),
+        },
+        process,
+    )
+}
+
+async fn sample_gpus() -> Vec<GpuUtilization> {
+    if let Some(output) = probe(
+        "nvidia-smi",
+        &[
+            "--query-gpu=name,utilization.gpu,memory.used,memory.total",
+            "--format=csv,noheader,nounits",
+        ],
+    )
+    .await
+    {
+        return parse_nvidia_smi(&output);
+    }
+    if cfg!(target_os = "macos") {
+        if let Some(output) = probe(
+            "ioreg",
+            &["-r", "-d", "1", "-w", "0", "-c", "IOAccelerator"],
+        )
+        .await
+        {
+            return parse_ioreg_accelerator(&output).into_iter().collect();
+        }
+    }
+    Vec::new()
+}
+
+async fn probe(program: &str, args: &[&str]) -> Option<String> {
+    which::which(program).ok()?;
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    match tokio::time::timeout(GPU_PROBE_TIMEOUT, output).await {
+        Ok(Ok(output)) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        Ok(Ok(_)) | Ok(Err(_)) => None,
+        Err(_) => {
+            tracing::debug!("{} did not answer within {:?}", program, GPU_PROBE_TIMEOUT);
+            None
+        }
+    }
+}
+
+/// `nvidia-smi --query-gpu=name,utilization.gpu,memory.used,memory.total --format=csv,noheader,nounits`
+fn parse_nvidia_smi(output: &str) -> Vec<GpuUtilization> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+            let [name, utilization, used, total] = fields.as_slice() else {
+                return None;
+            };
+            // 値が取れない項目は "[N/A]" などになる
+            let mib = |value: &str| value.parse::<u64>().ok().map(|mib| mib * MIB);
+            Some(GpuUtilization {
+                name: name.to_string(),
+                source: "nvml",
+                utilization_percent: utilization.parse().ok(),
+                vram_used_bytes: mib(used),
+                vram_total_bytes: mib(total),
+            })
+        })
+        .collect()
+}
+
+/// `ioreg -c IOAccelerator` の `PerformanceStatistics`。Apple Silicon は
+/// ユニファイドメモリなので VRAM の総量は出さない。
+fn parse_ioreg_accelerator(output: &str) -> Option<GpuUtilization> {
+    let utilization = ioreg_number(output, "\"Device Utilization %\"=")?;
+    let name = output
+        .split("\"model\" = \"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .unwrap_or("Apple GPU");
+    Some(GpuUtilization {
+        name: name.to_string(),
+        source: "metal",
+        utilization_percent: Some(utilization as f32),
+        vram_used_bytes: ioreg_number(output, "\"In use system memory\"="),
+        vram_total_bytes: None,
+    })
+}
+
+fn ioreg_number(output: &str, key: &str) -> Option<u64> {
+    let rest = output.split(key).nth(1)?;
+    let digits = rest
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>();
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nvidia_smi_rows_and_missing_values() {
+        let gpus = parse_nvidia_smi(
+            "NVIDIA GeForce RTX 4090, 37, 5120, 24564\nNVIDIA T4, [N/A], 10, 15360\n",
+        );
+        assert_eq!(gpus.len(), 2);
+        assert_eq!(gpus[0].name, "NVIDIA GeForce RTX 4090");
+        assert_eq!(gpus[0].utilization_percent, Some(37.0));
+        assert_eq!(gpus[0].vram_used_bytes, Some(5120 * MIB));
+        assert_eq!(gpus[0].vram_total_bytes, Some(24564 * MIB));
+        assert_eq!(gpus[1].utilization_percent, None);
+        assert!(parse_nvidia_smi("garbage").is_empty());
+    }
+
+    #[test]
+    fn parses_metal_performance_statistics() {
+        let output = r#"+-o AGXAcceleratorG14X  <class AGXAcceleratorG14X>
+    {
+      "model" = "Apple M2 Pro"
+      "PerformanceStatistics" = {"In use system memory"=1843200000,"Device Utilization %"=42,"Renderer Utilization %"=40}
+    }"#;
+        let gpu = parse_ioreg_accelerator(output).unwrap();
+        assert_eq!(gpu.name, "Apple M2 Pro");
+        assert_eq!(gpu.source, "metal");
+        assert_eq!(gpu.utilization_percent, Some(42.0));
+        assert_eq!(gpu.vram_used_bytes, Some(1_843_200_000));
+        assert!(parse_ioreg_accelerator("no accelerator").is_none());
+    }
+
+    #[test]
+    fn only_one_stream_task_runs_at_a_time() {
+        let monitor = UtilizationMonitor::new();
+        assert!(monitor.try_start_stream());
+        assert!(!monitor.try_start_stream());
+        monitor.stream_stopped();
+        assert!(monitor.try_start_stream());
+    }
+}
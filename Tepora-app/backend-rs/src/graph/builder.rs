@@ -3,8 +3,8 @@
 
 use super::node::GraphError;
 use super::nodes::{
-    AgentExecutorNode, AgenticSearchNode, ChatNode, PlannerNode, RouterNode, SearchNode,
-    SupervisorNode, SynthesizerNode, ThinkingNode,
+    AgentExecutorNode, AgenticSearchNode, ChatNode, DeepResearchNode, PlannerNode, RouterNode,
+    SearchNode, SupervisorNode, SynthesizerNode, ThinkingNode,
 };
 use super::runtime::{GraphBuilder, GraphRuntime};
 
@@ -12,19 +12,11 @@ use crate::core::config::ConfigService;
 
 /// Build the main Tepora graph
 pub fn build_tepora_graph(config_service: &ConfigService) -> Result<GraphRuntime, GraphError> {
-    let config = config_service.load_config().unwrap_or_default();
-
-    let max_steps = config
-        .get("app")
-        .and_then(|app| app.get("graph_recursion_limit"))
-        .and_then(|v| v.as_u64())
-        .map(|v| v as usize)
-        .unwrap_or(50);
-
-    let execution_timeout = config
-        .get("app")
-        .and_then(|app| app.get("graph_execution_timeout"))
-        .and_then(|v| v.as_u64())
+    let app = config_service.load_typed().unwrap_or_default().app;
+
+    let max_steps = app.graph_recursion_limit as usize;
+    let execution_timeout = app
+        .graph_execution_timeout
         .map(std::time::Duration::from_secs);
 
     let mut builder = GraphBuilder::new().entry("router").max_steps(max_steps);
@@ -33,7 +25,7 @@ pub fn build_tepora_graph(config_service: &ConfigService) -> Result<GraphRuntime
         builder = builder.timeout(timeout);
     }
 
-    builder
+    let mut runtime = builder
         // Entry point
         .node(Box::new(RouterNode::new()))
         // Chat mode path
@@ -42,6 +34,8 @@ pub fn build_tepora_graph(config_service: &ConfigService) -> Result<GraphRuntime
         // Search mode path (Fast + Agentic)
         .node(Box::new(SearchNode::new()))
         .node(Box::new(AgenticSearchNode::new()))
+        // Research mode path (iterative deep research)
+        .node(Box::new(DeepResearchNode::new()))
         // Agent mode path
         .node(Box::new(SupervisorNode::new()))
         .node(Box::new(PlannerNode::new()))
@@ -52,6 +46,7 @@ pub fn build_tepora_graph(config_service: &ConfigService) -> Result<GraphRuntime
         .conditional_edge("router", "chat", "chat")
         .conditional_edge("router", "search", "search")
         .conditional_edge("router", "search_agentic", "search_agentic")
+        .conditional_edge("router", "deep_research", "deep_research")
         .conditional_edge("router", "supervisor", "supervisor")
         // Thinking -> Chat (default edge)
         .edge("thinking", "chat")
@@ -61,7 +56,22 @@ pub fn build_tepora_graph(config_service: &ConfigService) -> Result<GraphRuntime
         // Planner -> Agent Executor (default edge)
         .edge("planner", "agent_executor")
         // Build the graph
-        .build()
+        .build()?;
+
+    // Named entry points: modes that start past the router
+    for (mode, node_id) in app.graph_entry_points {
+        if runtime.get_node(&node_id).is_none() {
+            tracing::warn!(
+                "Ignoring graph entry point for mode '{}': unknown node '{}'",
+                mode,
+                node_id
+            );
+            continue;
+        }
+        runtime = runtime.with_entry_point(mode.trim().to_lowercase(), node_id);
+    }
+
+    Ok(runtime)
 }
 
 #[cfg(test)]
@@ -101,6 +111,7 @@ mod tests {
             "chat",
             "search",
             "search_agentic",
+            "deep_research",
             "supervisor",
             "planner",
             "agent_executor",
@@ -139,6 +150,10 @@ mod tests {
             graph.get_node("search_agentic").unwrap().id(),
             "search_agentic"
         );
+        assert_eq!(
+            graph.get_node("deep_research").unwrap().id(),
+            "deep_research"
+        );
         assert_eq!(graph.get_node("supervisor").unwrap().id(), "supervisor");
         assert_eq!(graph.get_node("planner").unwrap().id(), "planner");
         assert_eq!(
@@ -1,7 +1,7 @@
 use super::node::{GraphError, Node};
 use super::nodes::{
-    AgentExecutorNode, AgenticSearchNode, ChatNode, PlannerNode, RouterNode, SearchNode,
-    SupervisorNode, SynthesizerNode, ThinkingNode, ToolNode,
+    AgentExecutorNode, AgenticSearchNode, ChatNode, DeepResearchNode, PlannerNode, PluginNode,
+    RouterNode, SearchNode, SupervisorNode, SynthesizerNode, ThinkingNode, ToolNode,
 };
 use super::runtime::{GraphBuilder, GraphRuntime};
 use super::schema::WorkflowDef;
@@ -23,6 +23,7 @@ fn instantiate_node(
         "ChatNode" => Ok(Box::new(ChatNode::new())),
         "SearchNode" => Ok(Box::new(SearchNode::new())),
         "AgenticSearchNode" => Ok(Box::new(AgenticSearchNode::new())),
+        "DeepResearchNode" => Ok(Box::new(DeepResearchNode::new())),
         "SupervisorNode" => Ok(Box::new(SupervisorNode::new())),
         "PlannerNode" => Ok(Box::new(PlannerNode::new())),
         "AgentExecutorNode" => Ok(Box::new(AgentExecutorNode::new())),
@@ -36,6 +37,18 @@ fn instantiate_node(
             let tool_args = _metadata.get("tool_args").cloned().unwrap_or(Value::Null);
             Ok(Box::new(ToolNode::new(tool_name, tool_args)))
         }
+        "PluginNode" => {
+            let field = |key: &str| {
+                _metadata
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+                    .ok_or_else(|| {
+                        GraphError::new("loader", format!("PluginNode requires metadata.{}", key))
+                    })
+            };
+            Ok(Box::new(PluginNode::new(field("plugin")?, field("node")?)))
+        }
         _ => Err(GraphError::new(
             "loader",
             format!(
@@ -2,8 +2,11 @@ pub mod builder;
 pub mod loader;
 pub mod node;
 pub mod nodes;
+pub mod profiler;
 pub mod runtime;
+pub mod sanitize;
 pub mod schema;
+pub mod sentences;
 pub mod state;
 pub mod stream;
 
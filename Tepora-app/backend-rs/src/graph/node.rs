@@ -10,6 +10,7 @@ use tokio::sync::Mutex;
 
 use crate::core::errors::ApiError;
 use crate::core::security_controls::ToolApprovalResponsePayload;
+use crate::llm::outage::ProviderOutage;
 use crate::state::AppState;
 
 use super::state::AgentState;
@@ -52,6 +53,9 @@ pub struct GraphError {
     pub message: String,
     /// Ordered list of node IDs executed before this error, most-recent last.
     pub execution_trace: Vec<String>,
+    /// Set when the model's provider could not be reached, so the caller can
+    /// send `provider_unavailable` instead of a plain error.
+    pub outage: Option<ProviderOutage>,
 }
 
 impl GraphError {
@@ -60,9 +64,17 @@ impl GraphError {
             node_id: node_id.into(),
             message: message.into(),
             execution_trace: Vec::new(),
+            outage: None,
         }
     }
 
+    /// Wrap an error returned by an LLM call, keeping provider outages typed.
+    pub fn from_llm(node_id: impl Into<String>, err: ApiError) -> Self {
+        let mut graph_err = Self::new(node_id, err.to_string());
+        graph_err.outage = ProviderOutage::from_error(&err);
+        graph_err
+    }
+
     /// Append a node ID to the execution trace (called by the runtime as it
     /// unwinds after failure).
     pub fn with_trace_entry(mut self, node_id: impl Into<String>) -> Self {
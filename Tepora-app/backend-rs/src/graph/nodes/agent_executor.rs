@@ -5,13 +5,15 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use serde_json::json;
+use serde_json::{json, Value};
 
+use crate::agent::evaluation::{evaluate_agent_run, evaluation_enabled};
+use crate::agent::exclusive::AgentLoadState;
 use crate::agent::execution::{
     agent_decision_structured_spec, approval_timeout, build_agent_chat_config,
-    build_allowed_tool_list, format_attachments, resolve_execution_model_id,
-    resolve_selected_agent, structured_payload_to_agent_decision, AgentDecision,
-    AgentDecisionPayload,
+    build_allowed_tool_list, build_allowed_tool_specs, format_attachments,
+    resolve_execution_model_id, resolve_selected_agent, structured_payload_to_agent_decision,
+    tool_turn_to_agent_decision, AgentDecision, AgentDecisionPayload,
 };
 use crate::agent::instructions::build_agent_instructions;
 use crate::agent::modes::RequestedAgentMode;
@@ -19,15 +21,23 @@ use crate::agent::policy::CustomToolPolicy;
 use crate::context::controller::render_untrusted_xml_element;
 use crate::context::pipeline::ContextPipeline;
 use crate::context::pipeline_context::PipelineMode;
+use crate::context::workers::tool_worker::{
+    expand_tool_section, fold_tool_output, ToolFoldSettings,
+};
+use crate::core::native_tools::{
+    resolve_tool_alias, NATIVE_EXPAND_TOOL_RESULT, NATIVE_RUN_COMMAND,
+};
 use crate::core::security_controls::{
     ApprovalDecision, PermissionRiskLevel, PermissionScopeKind, ToolApprovalRequestPayload,
 };
 use crate::graph::node::{GraphError, Node, NodeContext, NodeOutput};
 use crate::graph::state::{AgentMode, AgentState, Artifact};
+use crate::llm::redaction::{new_redaction_sink, take_redaction_report};
 use crate::llm::{ChatMessage, ChatRequest};
 use crate::memory::MemoryScope;
 use crate::models::event::{AgentEvent, AgentEventType};
-use crate::tools::execute_tool;
+use crate::tools::execute_tool_with_policy;
+use crate::tools::shell::command_auto_approved;
 
 pub struct AgentExecutorNode {
     max_steps: usize,
@@ -85,6 +95,9 @@ impl Node for AgentExecutorNode {
 
         let selected_agent =
             resolve_selected_agent(ctx.app_state, state.selected_agent_id.as_deref());
+        if let Some(agent) = selected_agent.as_ref() {
+            ctx.app_state.ai().exclusive_agents.record_use(&agent.id);
+        }
         let active_policy = selected_agent
             .as_ref()
             .map(|agent| agent.tool_policy.clone())
@@ -96,6 +109,14 @@ impl Node for AgentExecutorNode {
             .as_ref()
             .map(|agent| agent.name.clone())
             .unwrap_or_else(|| "Default Agent".to_string());
+        let model_id =
+            resolve_execution_model_id(ctx.app_state, ctx.config, selected_agent.as_ref());
+        // ツール呼び出しの口があるモデルにはツール定義を渡し、無ければ構造化出力で判断させる
+        let tool_specs = if ctx.app_state.ai().llm.supports_native_tools(&model_id) {
+            Some(build_allowed_tool_specs(ctx.app_state, &tool_list).await)
+        } else {
+            None
+        };
 
         let requested_mode = requested_mode_from_graph(state.agent_mode);
         let pipeline_mode = pipeline_mode_from_graph(state.agent_mode);
@@ -107,12 +128,13 @@ impl Node for AgentExecutorNode {
             .unwrap_or(true);
         if should_rebuild {
             let app_state = Arc::new(ctx.app_state.clone());
-            let pipeline_ctx = ContextPipeline::build_v4(
+            let pipeline_ctx = ContextPipeline::build_v4_for_agent(
                 &app_state,
                 &state.session_id,
                 &state.input,
                 pipeline_mode,
                 state.skip_web_search,
+                state.selected_agent_id.as_deref(),
             )
             .await
             .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
@@ -137,6 +159,7 @@ impl Node for AgentExecutorNode {
                     requested_mode,
                     state.thinking_budget > 0,
                     selected_agent.as_ref(),
+                    tool_specs.is_some(),
                 ),
                 135,
             );
@@ -154,7 +177,7 @@ impl Node for AgentExecutorNode {
                     HashMap::new(),
                 );
             }
-            staged.add_system_part(
+            staged.add_volatile_system_part(
                 "executor_task_packet",
                 build_executor_task_packet(state, selected_agent.as_ref()),
                 130,
@@ -172,13 +195,12 @@ impl Node for AgentExecutorNode {
 
         let agent_chat_config =
             build_agent_chat_config(ctx.app_state, ctx.config, selected_agent.as_ref());
-        let model_id =
-            resolve_execution_model_id(ctx.app_state, ctx.config, selected_agent.as_ref());
         let max_steps = agent_chat_config
             .get("app")
             .and_then(|v| v.get("graph_recursion_limit"))
             .and_then(|v| v.as_u64())
             .unwrap_or(self.max_steps as u64) as usize;
+        let fold_settings = ToolFoldSettings::from_config(&agent_chat_config);
 
         // 画像添付がある場合はマルチモーダルメッセージ、なければテキストのみ
         let user_message = if !state.image_attachments.is_empty() {
@@ -193,6 +215,26 @@ impl Node for AgentExecutorNode {
         };
         messages.push(user_message);
 
+        if let Some(agent) = selected_agent.as_ref() {
+            let exclusive = ctx
+                .app_state
+                .ai()
+                .exclusive_agents
+                .status(ctx.app_state, &agent.id)
+                .await;
+            if matches!(exclusive, Ok(status) if status.load_state == AgentLoadState::Unloaded) {
+                ctx.sender
+                    .send_activity(
+                        "model_swap",
+                        "processing",
+                        &format!("Loading the model assigned to {}", agent_name),
+                        &agent_name,
+                    )
+                    .await
+                    .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
+            }
+        }
+
         for step in 0..max_steps {
             let step_message = format!("Reasoning step {}/{}", step + 1, max_steps);
             ctx.sender
@@ -200,16 +242,37 @@ impl Node for AgentExecutorNode {
                 .await
                 .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
 
+            let redaction_sink = new_redaction_sink();
             let request = ChatRequest::new(messages.clone())
                 .with_config(&agent_chat_config)
-                .with_structured_response(agent_decision_structured_spec());
-            let decision_payload = ctx
-                .app_state
-                .ai()
-                .llm
-                .chat_structured::<AgentDecisionPayload>(request, &model_id)
-                .await
-                .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
+                .with_cache_key(&state.session_id)
+                .with_redaction_sink(redaction_sink.clone());
+            let decision = match tool_specs.as_ref() {
+                Some(specs) => {
+                    let turn = ctx
+                        .app_state
+                        .ai()
+                        .llm
+                        .chat_normalized(request.with_tools(specs.clone()), &model_id)
+                        .await
+                        .map_err(|err| GraphError::from_llm(self.id(), err))?;
+                    tool_turn_to_agent_decision(turn)
+                }
+                None => {
+                    let payload = ctx
+                        .app_state
+                        .ai()
+                        .llm
+                        .chat_structured::<AgentDecisionPayload>(
+                            request.with_structured_response(agent_decision_structured_spec()),
+                            &model_id,
+                        )
+                        .await
+                        .map_err(|err| GraphError::from_llm(self.id(), err))?;
+                    structured_payload_to_agent_decision(payload)
+                        .map_err(|err| GraphError::new(self.id(), err))?
+                }
+            };
 
             // Log prompting event (note: full token usage depends on extended LLM traits, keeping it simple for now)
             if let Err(e) = ctx
@@ -224,7 +287,9 @@ impl Node for AgentExecutorNode {
                     metadata: json!({
                         "step": step + 1,
                         "model_id": model_id,
-                        "decision_type": decision_payload.action_type,
+                        "decision_type": decision.kind(),
+                        "native_tools": tool_specs.is_some(),
+                        "redaction": take_redaction_report(&redaction_sink),
                     }),
                     created_at: chrono::Utc::now(),
                 })
@@ -233,9 +298,6 @@ impl Node for AgentExecutorNode {
                 tracing::warn!(error = %e, "Failed to save agent event");
             }
 
-            let decision = structured_payload_to_agent_decision(decision_payload)
-                .map_err(|err| GraphError::new(self.id(), err))?;
-
             match decision {
                 AgentDecision::Final(content) => {
                     let final_content = content;
@@ -296,9 +358,51 @@ impl Node for AgentExecutorNode {
                         tracing::warn!(error = %e, "Failed to save agent event");
                     }
 
+                    if let Some(agent) = selected_agent.clone() {
+                        if evaluation_enabled(ctx.config) {
+                            let app_state = ctx.app_state.clone();
+                            let session_id = state.session_id.clone();
+                            let task = state.input.clone();
+                            tokio::spawn(async move {
+                                if let Err(err) = evaluate_agent_run(
+                                    &app_state,
+                                    &session_id,
+                                    &agent,
+                                    &task,
+                                    &final_content,
+                                    step + 1,
+                                )
+                                .await
+                                {
+                                    tracing::warn!(
+                                        agent_id = %agent.id,
+                                        "Agent self-evaluation failed: {}",
+                                        err
+                                    );
+                                }
+                            });
+                        }
+                    }
+
                     return Ok(NodeOutput::Final);
                 }
                 AgentDecision::ToolCall { name, args } => {
+                    // Expanding only re-reads output this run already received,
+                    // so it skips the policy and approval flow.
+                    if resolve_tool_alias(&name) == NATIVE_EXPAND_TOOL_RESULT {
+                        let (kind, content) =
+                            match expand_folded_result(state, &args, &fold_settings) {
+                                Ok(section) => ("result", section),
+                                Err(err) => ("failure", err),
+                            };
+                        messages.push(ChatMessage {
+                            role: "user".to_string(),
+                            content: render_tool_observation(kind, &name, &content),
+                            multimodal_parts: None,
+                        });
+                        continue;
+                    }
+
                     if !active_policy.is_tool_allowed(&name) {
                         let rejection =
                             format!("Tool `{}` is blocked by the selected agent's policy.", name);
@@ -341,7 +445,13 @@ impl Node for AgentExecutorNode {
                         .await
                         .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
 
-                    let mut requires_confirmation = active_policy.requires_confirmation(&name);
+                    // run_command は自動承認パターンに一致しない限り毎回確認する
+                    let command_gate = resolve_tool_alias(&name) == NATIVE_RUN_COMMAND;
+                    let mut requires_confirmation = if command_gate {
+                        !command_auto_approved(ctx.config, &args)
+                    } else {
+                        active_policy.requires_confirmation(&name)
+                    };
                     let (scope_kind, scope_name, risk_level) = if mcp_tool_set.contains(&name) {
                         let policy = ctx
                             .app_state
@@ -401,9 +511,10 @@ impl Node for AgentExecutorNode {
                                 });
                                 continue;
                             }
-                            ApprovalDecision::AlwaysUntilExpiry => {
+                            ApprovalDecision::AlwaysUntilExpiry if !command_gate => {
                                 requires_confirmation = false;
                             }
+                            ApprovalDecision::AlwaysUntilExpiry => {}
                             ApprovalDecision::Once => {}
                         }
                     }
@@ -492,7 +603,8 @@ impl Node for AgentExecutorNode {
                         }
                     }
 
-                    let execution = match execute_tool(
+                    let execution = match execute_tool_with_policy(
+                        &active_policy,
                         Some(ctx.app_state),
                         &agent_chat_config,
                         Some(&ctx.app_state.integration.mcp),
@@ -550,10 +662,18 @@ impl Node for AgentExecutorNode {
                     });
                     state.shared_context.notes.push(tool_summary.clone());
 
-                    messages.push(ChatMessage {
-                        role: "user".to_string(),
-                        content: render_tool_observation("result", &name, &tool_summary),
-                        multimodal_parts: None,
+                    let result_id = format!("r{}", state.tool_results.len() + 1);
+                    let folded_output =
+                        fold_tool_output(&result_id, &execution.output, &fold_settings);
+                    state
+                        .tool_results
+                        .insert(result_id, execution.output.clone());
+
+                    let observation = render_tool_observation("result", &name, &folded_output);
+                    messages.push(if execution.images.is_empty() {
+                        ChatMessage::new_text("user", observation)
+                    } else {
+                        ChatMessage::new_multimodal("user", &observation, &execution.images)
                     });
 
                     ctx.sender
@@ -672,6 +792,23 @@ fn summarize_tool_output(tool_name: &str, output: &str) -> String {
     )
 }
 
+fn expand_folded_result(
+    state: &AgentState,
+    args: &Value,
+    settings: &ToolFoldSettings,
+) -> Result<String, String> {
+    let result_id = args
+        .get("result_id")
+        .and_then(Value::as_str)
+        .ok_or_else(|| "expand_tool_result requires a result_id".to_string())?;
+    let section = args.get("section").and_then(Value::as_str).unwrap_or("");
+    let output = state
+        .tool_results
+        .get(result_id)
+        .ok_or_else(|| format!("Unknown result_id '{result_id}'"))?;
+    expand_tool_section(output, section, settings)
+}
+
 fn render_tool_observation(kind: &str, tool_name: &str, content: &str) -> String {
     render_untrusted_xml_element(
         "tool_observation",
@@ -6,10 +6,14 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use serde_json::json;
 
+use crate::context::controller::{ContextController, TokenEstimator};
 use crate::context::pipeline::ContextPipeline;
 use crate::context::pipeline_context::PipelineMode;
+use crate::context::workers::project_worker::project_default_model_id;
 use crate::graph::node::{GraphError, Node, NodeContext, NodeOutput};
 use crate::graph::state::AgentState;
+use crate::llm::redaction::{new_redaction_sink, take_redaction_report};
+use crate::llm::types::TurnUsage;
 use crate::llm::{ChatMessage, ChatRequest};
 use crate::models::event::{AgentEvent, AgentEventType};
 
@@ -83,11 +87,14 @@ impl Node for ChatNode {
             pipeline_ctx.user_input = state.input.clone();
         }
 
-        let mut messages = if let Some(pipeline_ctx) = state.pipeline_context.as_ref() {
-            ContextPipeline::pipeline_to_context_result(pipeline_ctx).messages
-        } else {
-            state.chat_history.clone()
-        };
+        let (mut messages, composition) =
+            if let Some(pipeline_ctx) = state.pipeline_context.as_ref() {
+                let (messages, report) =
+                    ContextController::new(pipeline_ctx).render_with_report(pipeline_ctx);
+                (messages, Some(report))
+            } else {
+                (state.chat_history.clone(), None)
+            };
 
         // 画像添付がある場合、最後のuserメッセージをマルチモーダルに差し替える
         if !state.image_attachments.is_empty() {
@@ -102,20 +109,38 @@ impl Node for ChatNode {
             }
         }
 
-        let active_character = ctx
-            .config
-            .get("active_character")
-            .or_else(|| ctx.config.get("active_agent_profile"))
-            .and_then(|v| v.as_str());
-        let model_id = ctx
-            .app_state
-            .ai()
-            .models
-            .resolve_character_model_id(active_character)
-            .map_err(|err| GraphError::new(self.id(), err.to_string()))?
-            .unwrap_or_else(|| "default".to_string());
+        let active_character = crate::core::config::personas::model_profile_id(ctx.config);
+        let model_id = match project_default_model_id(ctx.config) {
+            Some(model_id) => model_id.to_string(),
+            None => ctx
+                .app_state
+                .ai()
+                .models
+                .resolve_character_model_id(active_character)
+                .map_err(|err| GraphError::new(self.id(), err.to_string()))?
+                .unwrap_or_else(|| "default".to_string()),
+        };
+
+        let estimator = TokenEstimator::new(
+            state
+                .pipeline_context
+                .as_ref()
+                .map(|pipeline_ctx| pipeline_ctx.tokenizer_spec.clone())
+                .unwrap_or_default(),
+        );
+        let prompt_estimate = match composition.as_ref() {
+            Some(report) => report.rendered_prompt_tokens,
+            None => messages
+                .iter()
+                .map(|message| estimator.count_text(&message.content).tokens)
+                .sum(),
+        };
 
-        let request = ChatRequest::new(messages).with_config(ctx.config);
+        let redaction_sink = new_redaction_sink();
+        let request = ChatRequest::new(messages)
+            .with_config(ctx.config)
+            .with_cache_key(&state.session_id)
+            .with_redaction_sink(redaction_sink.clone());
 
         let mut stream = ctx
             .app_state
@@ -123,13 +148,17 @@ impl Node for ChatNode {
             .llm
             .stream_chat_normalized(request, &model_id)
             .await
-            .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
+            .map_err(|err| GraphError::from_llm(self.id(), err))?;
 
         let mut full_response = String::new();
+        let mut reported_usage = None;
 
         while let Some(chunk_result) = stream.recv().await {
             match chunk_result {
                 Ok(chunk) => {
+                    if chunk.usage.is_some() {
+                        reported_usage = chunk.usage.clone();
+                    }
                     if !chunk.model_thinking.is_empty() {
                         let _ = ctx
                             .sender
@@ -154,15 +183,35 @@ impl Node for ChatNode {
                         .await;
                 }
                 Err(err) => {
-                    let _ = ctx
-                        .sender
-                        .send_json(json!({"type": "error", "message": format!("{}", err)}))
-                        .await;
-                    return Err(GraphError::new(self.id(), err.to_string()));
+                    let err = GraphError::from_llm(self.id(), err);
+                    // 繋がらなかった場合はセッション側が `provider_unavailable` を送る
+                    if err.outage.is_none() {
+                        let _ = ctx
+                            .sender
+                            .send_json(json!({"type": "error", "message": err.message}))
+                            .await;
+                    }
+                    return Err(err);
                 }
             }
         }
 
+        // プロバイダーが使用量を返さなければ見積もりで埋める
+        let usage = TurnUsage::resolve(
+            reported_usage.as_ref(),
+            || prompt_estimate,
+            || estimator.count_text(&full_response).tokens,
+        );
+        if let Err(e) = ctx
+            .app_state
+            .runtime()
+            .history
+            .record_token_usage(&state.session_id, &model_id, &usage)
+            .await
+        {
+            tracing::warn!(error = %e, "Failed to record token usage");
+        }
+
         if let Err(e) = ctx
             .app_state
             .runtime()
@@ -174,7 +223,10 @@ impl Node for ChatNode {
                 event_type: AgentEventType::PromptGenerated,
                 metadata: json!({
                     "model_id": model_id,
-                    "length": full_response.len()
+                    "length": full_response.len(),
+                    "context_composition": composition,
+                    "usage": usage,
+                    "redaction": take_redaction_report(&redaction_sink),
                 }),
                 created_at: chrono::Utc::now(),
             })
@@ -183,7 +235,10 @@ impl Node for ChatNode {
             tracing::warn!(error = %e, "Failed to save agent event");
         }
 
-        let _ = ctx.sender.send_json(json!({"type": "done"})).await;
+        let _ = ctx
+            .sender
+            .send_json(json!({"type": "done", "usage": usage}))
+            .await;
 
         state.output = Some(full_response);
 
@@ -0,0 +1,637 @@
+// Deep Research Node
+// Iterative research loop: generate queries -> search -> read -> find gaps -> refine,
+// bounded by ResearchBudget, ending in a cited report stored as a shared artifact.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::context::pipeline::ContextPipeline;
+use crate::context::pipeline_context::{PipelineContext, PipelineMode, PipelineStage};
+use crate::graph::node::{GraphError, Node, NodeContext, NodeOutput};
+use crate::graph::state::{AgentState, Artifact};
+use crate::llm::types::StructuredResponseSpec;
+use crate::llm::ChatRequest;
+use crate::rag::ChunkSearchResult;
+use crate::search::evidence::{canonical_url, cluster_snippets, EvidencePack};
+use crate::search::research::{
+    next_queries, GapReview, ResearchBudget, ResearchReport, ResearchRound,
+};
+use crate::search::source_quality::{rank_with_learned_quality, record_cited_sources};
+use crate::search::{EvidenceClaim, EvidenceGap, SearchEvidenceState, SearchMode};
+use crate::tools::execute_tool;
+use crate::tools::search::SearchResult;
+
+use super::search::{fetch_pages, search_setting, EVIDENCE_CITATION_INSTRUCTION};
+use super::search_agentic_support::{
+    build_explored_sources, parse_json_payload, sub_query_structured_spec, truncate_text,
+};
+
+pub struct DeepResearchNode;
+
+impl DeepResearchNode {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for DeepResearchNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// ラウンドをまたいで集めた出典
+#[derive(Default)]
+struct Findings {
+    results: Vec<SearchResult>,
+    seen: HashSet<String>,
+    /// URL (RAG の場合はソース名) ごとの本文
+    pages: HashMap<String, String>,
+}
+
+impl Findings {
+    fn evidence_pack(&self, question: &str) -> EvidencePack {
+        EvidencePack::build(
+            question,
+            &cluster_snippets(self.results.clone()),
+            &self.pages,
+        )
+    }
+}
+
+#[async_trait]
+impl Node for DeepResearchNode {
+    fn id(&self) -> &'static str {
+        "deep_research"
+    }
+
+    fn name(&self) -> &'static str {
+        "Deep Research"
+    }
+
+    async fn execute(
+        &self,
+        state: &mut AgentState,
+        ctx: &mut NodeContext<'_>,
+    ) -> Result<NodeOutput, GraphError> {
+        let should_rebuild = state
+            .pipeline_context
+            .as_ref()
+            .map(|pipeline| pipeline.mode != PipelineMode::SearchAgentic)
+            .unwrap_or(true);
+        if should_rebuild {
+            let app_state = Arc::new(ctx.app_state.clone());
+            let pipeline_ctx = ContextPipeline::build_v4(
+                &app_state,
+                &state.session_id,
+                &state.input,
+                PipelineMode::SearchAgentic,
+                true,
+            )
+            .await
+            .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
+            state.pipeline_context = Some(pipeline_ctx);
+        }
+
+        let budget = ResearchBudget::from_config(ctx.config);
+        let web_enabled = self.can_use_web_search(state, ctx);
+
+        self.send_activity(
+            ctx,
+            "research_plan",
+            "processing",
+            "Planning research queries...",
+        )
+        .await;
+        let mut candidates = self.generate_queries(state, ctx).await;
+        self.send_activity(ctx, "research_plan", "done", "Research plan ready")
+            .await;
+
+        let mut asked = HashSet::new();
+        let mut asked_in_order = Vec::new();
+        let mut rounds = Vec::new();
+        let mut findings = Findings::default();
+
+        for round in 1..=budget.max_rounds {
+            let queries = next_queries(candidates, &asked, budget.queries_per_round);
+            if queries.is_empty() {
+                break;
+            }
+            for query in &queries {
+                asked.insert(query.to_lowercase());
+                asked_in_order.push(query.clone());
+            }
+
+            let activity_id = format!("research_round_{round}");
+            self.send_activity(
+                ctx,
+                &activity_id,
+                "processing",
+                format!("Round {round}: searching {} queries...", queries.len()),
+            )
+            .await;
+
+            let remaining = budget.max_sources.saturating_sub(findings.results.len());
+            let new_sources = self
+                .search_round(state, ctx, &queries, web_enabled, remaining, &mut findings)
+                .await;
+
+            let budget_spent =
+                round == budget.max_rounds || findings.results.len() >= budget.max_sources;
+            let review = if budget_spent {
+                GapReview::default()
+            } else {
+                self.review_gaps(state, ctx, &findings, &asked_in_order)
+                    .await
+            };
+
+            self.send_activity(
+                ctx,
+                &activity_id,
+                "done",
+                format!(
+                    "Round {round}: {new_sources} new sources, {} gaps",
+                    review.gaps.len()
+                ),
+            )
+            .await;
+
+            let stop = budget_spent || review.sufficient || review.next_queries.is_empty();
+            rounds.push(ResearchRound {
+                round,
+                queries,
+                new_sources,
+                gaps: review.gaps,
+            });
+            if stop {
+                break;
+            }
+            candidates = review.next_queries;
+        }
+
+        let _ = ctx
+            .sender
+            .send_json(json!({ "type": "search_results", "data": findings.results }))
+            .await;
+
+        self.send_activity(
+            ctx,
+            "research_report",
+            "processing",
+            "Writing research report...",
+        )
+        .await;
+
+        let pack = findings.evidence_pack(&state.input);
+        let (rendered_pack, injected_sources) = pack.render_budgeted(search_setting(
+            ctx.config,
+            "research_evidence_max_chars",
+            8_000,
+            1_000,
+            40_000,
+        ));
+        let open_gaps = rounds
+            .last()
+            .map(|round| round.gaps.clone())
+            .unwrap_or_default();
+        let body = self
+            .write_report(state, ctx, &rendered_pack, &open_gaps)
+            .await?;
+        let report = ResearchReport::new(pack, rounds, body);
+        record_cited_sources(
+            ctx.app_state,
+            &report.sources[..injected_sources],
+            &report.body,
+        )
+        .await;
+
+        state.search_queries = asked_in_order.clone();
+        state.search_results = Some(findings.results.clone());
+        state.search_evidence = SearchEvidenceState {
+            strategy: SearchMode::Deep,
+            query_plan: asked_in_order,
+            explored_sources: build_explored_sources(&state.search_attachments, web_enabled),
+            results: findings.results,
+            claims: report
+                .sources
+                .iter()
+                .filter(|source| report.cited.contains(&source.id))
+                .map(|source| EvidenceClaim {
+                    topic: source.title.clone(),
+                    summary: truncate_text(
+                        source.excerpt.as_deref().unwrap_or(&source.snippet),
+                        180,
+                    ),
+                    citations: vec![source.id.clone(), source.url.clone()],
+                    confidence: 0.7,
+                })
+                .collect(),
+            gaps: open_gaps
+                .iter()
+                .map(|gap| EvidenceGap {
+                    topic: state.input.clone(),
+                    reason: gap.clone(),
+                })
+                .collect(),
+        };
+
+        state.shared_context.artifacts.push(Artifact {
+            artifact_type: "evidence_pack".to_string(),
+            content: rendered_pack,
+            metadata: HashMap::from([(
+                "sources".to_string(),
+                serde_json::to_value(&report.sources).unwrap_or(Value::Null),
+            )]),
+        });
+        state.shared_context.artifacts.push(Artifact {
+            artifact_type: "research_report".to_string(),
+            content: report.render_markdown(),
+            metadata: HashMap::from([
+                (
+                    "rounds".to_string(),
+                    serde_json::to_value(&report.rounds).unwrap_or(Value::Null),
+                ),
+                (
+                    "cited".to_string(),
+                    serde_json::to_value(&report.cited).unwrap_or(Value::Null),
+                ),
+                (
+                    "sources".to_string(),
+                    serde_json::to_value(&report.sources).unwrap_or(Value::Null),
+                ),
+            ]),
+        });
+
+        let _ = ctx
+            .sender
+            .send_json(json!({ "type": "research_report", "data": report }))
+            .await;
+        self.send_activity(ctx, "research_report", "done", "Research report complete")
+            .await;
+        let _ = ctx.sender.send_json(json!({"type": "done"})).await;
+
+        state.output = Some(report.body);
+        Ok(NodeOutput::Final)
+    }
+}
+
+impl DeepResearchNode {
+    async fn generate_queries(&self, state: &AgentState, ctx: &NodeContext<'_>) -> Vec<String> {
+        let mut staged = self.stage_context(state, PipelineStage::SearchQueryGenerate);
+        staged.add_system_part(
+            "query_generation_instruction",
+            concat!(
+                "You are planning a multi-step research task. ",
+                "Generate 2-4 focused search queries that together cover the user request. ",
+                "Return only the structured query array."
+            ),
+            130,
+        );
+        let request = ChatRequest::new(staged.to_messages())
+            .with_config(ctx.config)
+            .with_structured_response(sub_query_structured_spec());
+        let parsed = ctx
+            .app_state
+            .ai()
+            .llm
+            .chat_structured::<Vec<String>>(request, &self.resolve_model_id_best_effort(ctx))
+            .await
+            .unwrap_or_else(|err| {
+                tracing::warn!("Deep research query generation failed: {}", err);
+                Vec::new()
+            });
+
+        let mut queries = vec![state.search_query()];
+        queries.extend(parsed);
+        queries
+    }
+
+    /// 1 ラウンド分の検索と読解。新しく加わった出典の数を返す。
+    async fn search_round(
+        &self,
+        state: &AgentState,
+        ctx: &NodeContext<'_>,
+        queries: &[String],
+        web_enabled: bool,
+        remaining: usize,
+        findings: &mut Findings,
+    ) -> usize {
+        let mut fresh_web = Vec::new();
+        let mut added = 0;
+
+        for query in queries {
+            if web_enabled {
+                let search = execute_tool(
+                    Some(ctx.app_state),
+                    ctx.config,
+                    Some(&ctx.app_state.integration.mcp),
+                    Some(&state.session_id),
+                    "native_search",
+                    &json!({ "query": query, "limit": 5 }),
+                )
+                .await;
+                if let Some(results) = search.ok().and_then(|search| search.search_results) {
+                    // 予算が残り少ないときは、よく引用されてきたドメインから採る
+                    let results =
+                        rank_with_learned_quality(ctx.app_state, ctx.config, results).await;
+                    for result in results {
+                        if added < remaining && findings.seen.insert(canonical_url(&result.url)) {
+                            fresh_web.push(result.clone());
+                            findings.results.push(result);
+                            added += 1;
+                        }
+                    }
+                }
+            }
+
+            // 取り込み済みの資料（添付・過去の取得結果）も出典にする
+            let rag = execute_tool(
+                Some(ctx.app_state),
+                ctx.config,
+                Some(&ctx.app_state.integration.mcp),
+                Some(&state.session_id),
+                "native_rag_search",
+                &json!({ "query": query, "limit": 4 }),
+            )
+            .await;
+            let chunks = rag
+                .ok()
+                .and_then(|rag| parse_json_payload::<Vec<ChunkSearchResult>>(&rag.output))
+                .unwrap_or_default();
+            for item in chunks {
+                let chunk = item.chunk;
+                let page = findings.pages.entry(chunk.source.clone()).or_default();
+                if !page.is_empty() {
+                    page.push_str("\n\n");
+                }
+                page.push_str(&chunk.content);
+                if added < remaining && findings.seen.insert(canonical_url(&chunk.source)) {
+                    findings.results.push(SearchResult {
+                        title: format!("RAG Chunk {}", chunk.chunk_id),
+                        url: chunk.source,
+                        snippet: truncate_text(&chunk.content, 240),
+                    });
+                    added += 1;
+                }
+            }
+        }
+
+        let pages = fetch_pages(
+            ctx.app_state,
+            ctx.config,
+            &state.session_id,
+            &fresh_web,
+            search_setting(ctx.config, "fetch_concurrency", 3, 1, 8),
+            search_setting(ctx.config, "per_domain_fetch_limit", 1, 1, 4),
+        )
+        .await;
+        for result in &fresh_web {
+            let Some(content) = pages.get(&result.url) else {
+                continue;
+            };
+            let _ = execute_tool(
+                Some(ctx.app_state),
+                ctx.config,
+                Some(&ctx.app_state.integration.mcp),
+                Some(&state.session_id),
+                "native_rag_ingest",
+                &json!({
+                    "content": content,
+                    "source": result.url,
+                    "metadata": {
+                        "title": result.title,
+                        "snippet": result.snippet,
+                    }
+                }),
+            )
+            .await;
+        }
+        findings.pages.extend(pages);
+        added
+    }
+
+    /// 集まった証拠で足りない点と次のクエリをモデルに挙げさせる。
+    /// 失敗した場合は「十分」とみなしてループを終える。
+    async fn review_gaps(
+        &self,
+        state: &AgentState,
+        ctx: &NodeContext<'_>,
+        findings: &Findings,
+        asked: &[String],
+    ) -> GapReview {
+        let mut staged = self.stage_context(state, PipelineStage::SearchQueryGenerate);
+        staged.add_system_part(
+            "gap_review_instruction",
+            concat!(
+                "You are reviewing research progress. ",
+                "Compare the evidence pack with the user request and list what is still missing. ",
+                "Propose up to 4 new search queries that would fill those gaps; ",
+                "do not repeat queries that were already asked. ",
+                "Set sufficient to true when the evidence already answers the request."
+            ),
+            130,
+        );
+        staged.add_artifact(
+            "evidence_pack",
+            findings.evidence_pack(&state.input).render(search_setting(
+                ctx.config,
+                "evidence_pack_max_chars",
+                4_000,
+                500,
+                20_000,
+            )),
+            HashMap::new(),
+        );
+        staged.add_artifact(
+            "asked_queries",
+            asked
+                .iter()
+                .map(|query| format!("- {query}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            HashMap::new(),
+        );
+
+        let request = ChatRequest::new(staged.to_messages())
+            .with_config(ctx.config)
+            .with_structured_response(gap_review_structured_spec());
+        ctx.app_state
+            .ai()
+            .llm
+            .chat_structured::<GapReview>(request, &self.resolve_model_id_best_effort(ctx))
+            .await
+            .unwrap_or_else(|err| {
+                tracing::warn!("Deep research gap review failed: {}", err);
+                GapReview {
+                    sufficient: true,
+                    ..GapReview::default()
+                }
+            })
+    }
+
+    async fn write_report(
+        &self,
+        state: &AgentState,
+        ctx: &mut NodeContext<'_>,
+        evidence_pack: &str,
+        open_gaps: &[String],
+    ) -> Result<String, GraphError> {
+        let mut staged = self.stage_context(state, PipelineStage::SearchFinalSynthesis);
+        staged.add_system_part(
+            "research_report_instruction",
+            concat!(
+                "You have finished a multi-round research task. ",
+                "Write a structured report in Markdown with the sections ",
+                "'## Summary', '## Findings' and '## Open Questions'. ",
+                "Ground every finding in the evidence pack and use the user's language."
+            ),
+            130,
+        );
+        staged.add_system_part("evidence_citation", EVIDENCE_CITATION_INSTRUCTION, 130);
+        staged.add_artifact("evidence_pack", evidence_pack, HashMap::new());
+        if !open_gaps.is_empty() {
+            staged.add_artifact(
+                "open_gaps",
+                open_gaps
+                    .iter()
+                    .map(|gap| format!("- {gap}"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                HashMap::new(),
+            );
+        }
+
+        let model_id = self.resolve_model_id(ctx)?;
+        let request = ChatRequest::new(staged.to_messages()).with_config(ctx.config);
+        let mut stream = ctx
+            .app_state
+            .ai()
+            .llm
+            .stream_chat_normalized(request, &model_id)
+            .await
+            .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
+
+        let mut full_response = String::new();
+        while let Some(chunk_result) = stream.recv().await {
+            let chunk = chunk_result.map_err(|err| GraphError::new(self.id(), err.to_string()))?;
+            if !chunk.model_thinking.is_empty() {
+                let _ = ctx
+                    .sender
+                    .send_json(json!({
+                        "type": "thought",
+                        "content": chunk.model_thinking,
+                        "mode": "research",
+                    }))
+                    .await;
+            }
+            if chunk.visible_text.is_empty() {
+                continue;
+            }
+            full_response.push_str(&chunk.visible_text);
+            let _ = ctx
+                .sender
+                .send_json(json!({
+                    "type": "chunk",
+                    "message": chunk.visible_text,
+                    "mode": "research",
+                }))
+                .await;
+        }
+
+        Ok(full_response)
+    }
+
+    fn stage_context(&self, state: &AgentState, stage: PipelineStage) -> PipelineContext {
+        let mut staged = state.pipeline_context.clone().unwrap_or_else(|| {
+            PipelineContext::new(
+                &state.session_id,
+                "",
+                PipelineMode::SearchAgentic,
+                &state.input,
+            )
+        });
+        staged.stage = stage;
+        staged.user_input = state.input.clone();
+        staged
+    }
+
+    fn can_use_web_search(&self, state: &AgentState, ctx: &NodeContext<'_>) -> bool {
+        let privacy = ctx.config.get("privacy");
+        privacy
+            .and_then(|v| v.get("allow_web_search"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+            && !state.skip_web_search
+            && !privacy
+                .and_then(|v| v.get("isolation_mode"))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+    }
+
+    fn configured_active_profile<'a>(&self, ctx: &'a NodeContext<'_>) -> Option<&'a str> {
+        crate::core::config::personas::model_profile_id(ctx.config)
+    }
+
+    fn resolve_model_id_best_effort(&self, ctx: &NodeContext<'_>) -> String {
+        ctx.app_state
+            .ai()
+            .models
+            .resolve_character_model_id(self.configured_active_profile(ctx))
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| "default".to_string())
+    }
+
+    fn resolve_model_id(&self, ctx: &NodeContext<'_>) -> Result<String, GraphError> {
+        ctx.app_state
+            .ai()
+            .models
+            .resolve_character_model_id(self.configured_active_profile(ctx))
+            .map_err(|err| GraphError::new(self.id(), err.to_string()))
+            .map(|model_id| model_id.unwrap_or_else(|| "default".to_string()))
+    }
+
+    async fn send_activity(
+        &self,
+        ctx: &mut NodeContext<'_>,
+        activity_id: &str,
+        status: &str,
+        message: impl Into<String>,
+    ) {
+        let _ = ctx
+            .sender
+            .send_json(json!({
+                "type": "activity",
+                "data": {
+                    "id": activity_id,
+                    "status": status,
+                    "message": message.into(),
+                    "agentName": self.name(),
+                }
+            }))
+            .await;
+    }
+}
+
+fn gap_review_structured_spec() -> StructuredResponseSpec {
+    StructuredResponseSpec {
+        name: "research_gap_review".to_string(),
+        description: Some("Missing evidence and follow-up search queries".to_string()),
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "gaps": { "type": "array", "items": { "type": "string" } },
+                "next_queries": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "maxItems": 4
+                },
+                "sufficient": { "type": "boolean" }
+            },
+            "required": ["gaps", "next_queries", "sufficient"]
+        }),
+    }
+}
@@ -3,7 +3,9 @@
 
 pub mod agent_executor;
 pub mod chat;
+pub mod deep_research;
 pub mod planner;
+pub mod plugin;
 pub mod router;
 pub mod search;
 pub mod search_agentic;
@@ -15,7 +17,9 @@ pub mod tool;
 
 pub use agent_executor::AgentExecutorNode;
 pub use chat::ChatNode;
+pub use deep_research::DeepResearchNode;
 pub use planner::PlannerNode;
+pub use plugin::PluginNode;
 pub use router::RouterNode;
 pub use search::SearchNode;
 pub use search_agentic::AgenticSearchNode;
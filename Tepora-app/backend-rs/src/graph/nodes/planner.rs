@@ -3,15 +3,25 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use serde_json::json;
 
+use crate::agent::estimate::{estimate_plan, needs_approval, pricing_for};
 use crate::agent::execution::{
-    build_agent_chat_config, resolve_execution_model_id, resolve_selected_agent,
+    approval_timeout, build_agent_chat_config, resolve_execution_model_id, resolve_selected_agent,
 };
+use crate::agent::plan::AgentPlan;
 use crate::context::pipeline::ContextPipeline;
 use crate::context::pipeline_context::{PipelineMode, PipelineStage};
+use crate::core::config::schema::TeporaConfig;
+use crate::core::security_controls::ApprovalDecision;
 use crate::graph::node::{GraphError, Node, NodeContext, NodeOutput};
 use crate::graph::state::{AgentMode, AgentState};
 use crate::llm::ChatRequest;
 use std::collections::HashMap;
+use std::time::Duration;
+
+/// `agent.plan_review_seconds` の上限
+const MAX_PLAN_REVIEW_SECS: u64 = 600;
+
+const FALLBACK_PLAN: &str = "- Clarify objective and constraints\n- Gather required evidence\n- Execute tools safely\n- Synthesize final answer";
 
 pub struct PlannerNode;
 
@@ -27,6 +37,136 @@ impl Default for PlannerNode {
     }
 }
 
+impl PlannerNode {
+    /// 計画の見積もりを `plan_estimate` で送る。承認が要る設定なら返答を待ち、
+    /// 却下なら `None`、`keepSteps` があればその分だけに絞った計画を返す。
+    async fn preflight(
+        &self,
+        ctx: &mut NodeContext<'_>,
+        model_id: &str,
+        plan: AgentPlan,
+    ) -> Result<Option<AgentPlan>, GraphError> {
+        let (provider, cloud) = ctx
+            .app_state
+            .ai()
+            .llm
+            .provider_for(model_id)
+            .unwrap_or_else(|_| ("unknown".to_string(), false));
+        let typed = TeporaConfig::from_value_or_default(ctx.config);
+        let catalog_pricing = ctx
+            .app_state
+            .ai()
+            .models
+            .get_model(model_id)
+            .ok()
+            .flatten()
+            .and_then(|entry| entry.pricing);
+        let estimate = estimate_plan(
+            &plan.to_markdown(),
+            model_id,
+            &provider,
+            cloud,
+            ctx.app_state
+                .runtime()
+                .graph_runtime
+                .profiler()
+                .round_stats("agent_executor"),
+            pricing_for(&typed, model_id, &provider, catalog_pricing),
+        );
+        let payload = serde_json::to_value(&estimate)
+            .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
+
+        if !needs_approval(ctx.config, &estimate) {
+            let _ = ctx
+                .sender
+                .send_json(json!({ "type": "plan_estimate", "data": payload }))
+                .await;
+            return Ok(Some(plan));
+        }
+
+        let reply = ctx
+            .sender
+            .request_plan_approval(
+                ctx.pending_approvals.clone(),
+                payload,
+                approval_timeout(ctx.config),
+            )
+            .await
+            .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
+        if matches!(reply.final_decision(), ApprovalDecision::Deny) {
+            return Ok(None);
+        }
+        Ok(match reply.keep_steps.as_deref() {
+            Some(keep) => plan.retain_indices(keep),
+            None => Some(plan),
+        })
+    }
+
+    /// 計画を送り、実行前のユーザー編集を受け付ける。承認待ちの間に届いた編集と、
+    /// `agent.plan_review_seconds` の間に届いた編集を反映した計画を返す。
+    async fn review(
+        &self,
+        ctx: &mut NodeContext<'_>,
+        run_id: Option<&str>,
+        model_id: &str,
+        plan: AgentPlan,
+    ) -> Result<Option<AgentPlan>, GraphError> {
+        let Some(run_id) = run_id else {
+            return self.preflight(ctx, model_id, plan).await;
+        };
+        let edits = ctx.app_state.runtime().plan_edits.clone();
+        edits.open(run_id, &plan).await;
+        send_plan(ctx, Some(run_id), &plan, true).await;
+
+        let reviewed = match self.preflight(ctx, model_id, plan).await {
+            Ok(Some(plan)) => {
+                let review_secs = plan_review_seconds(ctx.config);
+                if review_secs > 0 {
+                    edits
+                        .wait_for_edit(run_id, Duration::from_secs(review_secs))
+                        .await;
+                }
+                // 編集版があれば承認時の `keepSteps` より優先する
+                Ok(Some(edits.edited(run_id).await.unwrap_or(plan)))
+            }
+            other => other,
+        };
+        edits.close(run_id).await;
+
+        if let Ok(Some(plan)) = &reviewed {
+            send_plan(ctx, Some(run_id), plan, false).await;
+        }
+        reviewed
+    }
+}
+
+async fn send_plan(
+    ctx: &mut NodeContext<'_>,
+    run_id: Option<&str>,
+    plan: &AgentPlan,
+    editable: bool,
+) {
+    let _ = ctx
+        .sender
+        .send_json(json!({
+            "type": "plan",
+            "runId": run_id,
+            "data": plan,
+            "editable": editable,
+        }))
+        .await;
+}
+
+/// 計画を送ってから実行に移るまで編集を待つ秒数。既定は 0（待たない）。
+fn plan_review_seconds(config: &serde_json::Value) -> u64 {
+    config
+        .get("agent")
+        .and_then(|agent| agent.get("plan_review_seconds"))
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(0)
+        .min(MAX_PLAN_REVIEW_SECS)
+}
+
 #[async_trait]
 impl Node for PlannerNode {
     fn id(&self) -> &'static str {
@@ -68,12 +208,13 @@ impl Node for PlannerNode {
             .unwrap_or(true);
         if should_rebuild {
             let app_state = Arc::new(ctx.app_state.clone());
-            let pipeline_ctx = ContextPipeline::build_v4(
+            let pipeline_ctx = ContextPipeline::build_v4_for_agent(
                 &app_state,
                 &state.session_id,
                 &state.input,
                 pipeline_mode,
                 state.skip_web_search,
+                state.selected_agent_id.as_deref(),
             )
             .await
             .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
@@ -105,7 +246,7 @@ impl Node for PlannerNode {
             };
             staged.add_system_part(
                 "planner_instruction",
-                "You are a planner for a tool-using AI agent.\nCreate a practical execution plan with up to 6 ordered steps.\nUse concise markdown bullets and include fallback actions.\nDo not add any text before or after the plan.",
+                "You are a planner for a tool-using AI agent.\nCreate a practical execution plan with up to 6 ordered steps, including fallback actions.\nRespond with JSON only, in the form {\"steps\": [{\"id\": \"s1\", \"text\": \"...\", \"tools\": [\"tool_name\"], \"depends_on\": []}]}.\n`tools` lists tools the step is likely to use; `depends_on` lists ids of steps that must finish first.",
                 130,
             );
             staged.add_artifact(
@@ -135,14 +276,38 @@ impl Node for PlannerNode {
             )
             .await
             .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
-        let plan = if plan.trim().is_empty() {
-            "- Clarify objective and constraints\n- Gather required evidence\n- Execute tools safely\n- Synthesize final answer"
-                .to_string()
-        } else {
-            plan.trim().to_string()
+        let plan = match AgentPlan::parse(plan.trim()) {
+            plan if plan.steps.is_empty() => AgentPlan::parse(FALLBACK_PLAN),
+            plan => plan,
+        };
+
+        let run_id = state.run_id.clone();
+        let Some(plan) = self.review(ctx, run_id.as_deref(), &model_id, plan).await? else {
+            let _ = ctx
+                .sender
+                .send_activity(
+                    "generate_order",
+                    "error",
+                    "Execution plan was not approved",
+                    "Planner",
+                )
+                .await;
+            let _ = ctx
+                .sender
+                .send_json(json!({
+                    "type": "chunk",
+                    "message": "The execution plan was not approved, so nothing was run.",
+                    "mode": "agent",
+                    "agentName": "Planner",
+                    "nodeId": self.id(),
+                }))
+                .await;
+            let _ = ctx.sender.send_json(json!({"type": "done"})).await;
+            return Ok(NodeOutput::Final);
         };
 
-        state.shared_context.current_plan = Some(plan);
+        state.shared_context.current_plan = Some(plan.to_markdown());
+        state.shared_context.plan = Some(plan);
 
         let _ = ctx
             .sender
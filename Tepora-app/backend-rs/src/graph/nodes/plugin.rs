@@ -0,0 +1,92 @@
+// Plugin Node
+// Runs a graph node provided by a WASM plugin
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::graph::node::{GraphError, Node, NodeContext, NodeOutput};
+use crate::graph::state::AgentState;
+
+/// Workflow node backed by a plugin. The plugin receives the turn input and
+/// current output, and answers with
+/// `{"output"?: string, "final"?: bool, "branch"?: string, "next"?: string}`.
+pub struct PluginNode {
+    plugin_id: String,
+    node: String,
+}
+
+impl PluginNode {
+    pub fn new(plugin_id: String, node: String) -> Self {
+        Self { plugin_id, node }
+    }
+}
+
+#[async_trait]
+impl Node for PluginNode {
+    fn id(&self) -> &'static str {
+        "plugin"
+    }
+
+    fn name(&self) -> &'static str {
+        "Plugin Node"
+    }
+
+    async fn execute(
+        &self,
+        state: &mut AgentState,
+        ctx: &mut NodeContext<'_>,
+    ) -> Result<NodeOutput, GraphError> {
+        let input = json!({
+            "session_id": state.session_id,
+            "input": state.input,
+            "mode": state.mode.as_str(),
+            "output": state.output,
+            "notes": state.shared_context.notes,
+        });
+        let result = ctx
+            .app_state
+            .integration
+            .plugins
+            .run_node(&self.plugin_id, &self.node, input)
+            .await
+            .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
+        Ok(apply_result(state, &result))
+    }
+}
+
+fn apply_result(state: &mut AgentState, result: &Value) -> NodeOutput {
+    if let Some(output) = result.get("output").and_then(Value::as_str) {
+        state.output = Some(output.to_string());
+    }
+    if result.get("final").and_then(Value::as_bool) == Some(true) {
+        return NodeOutput::Final;
+    }
+    if let Some(branch) = result.get("branch").and_then(Value::as_str) {
+        return NodeOutput::Branch(branch.to_string());
+    }
+    NodeOutput::Continue(
+        result
+            .get("next")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::state::Mode;
+
+    #[test]
+    fn plugin_result_sets_output_and_routing() {
+        let mut state = AgentState::new("s".into(), "hi".into(), Mode::Chat);
+        let output = apply_result(&mut state, &json!({"output": "done", "final": true}));
+        assert!(matches!(output, NodeOutput::Final));
+        assert_eq!(state.output.as_deref(), Some("done"));
+
+        let output = apply_result(&mut state, &json!({"branch": "search"}));
+        assert!(matches!(output, NodeOutput::Branch(b) if b == "search"));
+        let output = apply_result(&mut state, &json!({}));
+        assert!(matches!(output, NodeOutput::Continue(None)));
+    }
+}
@@ -52,6 +52,7 @@ impl Node for RouterNode {
                 }
             }
             Mode::SearchAgentic => "search_agentic",
+            Mode::Research => "deep_research",
             Mode::Agent => "supervisor",
         };
 
@@ -5,16 +5,24 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use serde_json::json;
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use tokio::sync::Semaphore;
 
 use crate::context::pipeline::ContextPipeline;
 use crate::context::pipeline_context::{PipelineMode, RagChunk};
 use crate::graph::node::{GraphError, Node, NodeContext, NodeOutput};
-use crate::graph::state::AgentState;
+use crate::graph::state::{AgentState, Artifact};
 use crate::llm::{ChatMessage, ChatRequest};
 use crate::rag::ChunkSearchResult;
+use crate::search::evidence::{
+    cluster_snippets, dedupe_by_canonical_url, url_domain, EvidencePack,
+};
+use crate::search::source_quality::{rank_with_learned_quality, record_cited_sources};
 use crate::search::{EvidenceClaim, EvidenceGap, SearchEvidenceState, SearchMode};
+use crate::state::AppState;
 use crate::tools::execute_tool;
+use crate::tools::search::SearchResult;
 
 pub struct SearchNode;
 
@@ -80,6 +88,7 @@ impl Node for SearchNode {
 
         let mut web_results = Vec::new();
         let mut web_failed = false;
+        let mut evidence_pack = EvidencePack::default();
 
         if search_enabled && !state.skip_web_search && !isolation {
             let _ = ctx
@@ -101,14 +110,21 @@ impl Node for SearchNode {
                 Some(&ctx.app_state.integration.mcp),
                 Some(&state.session_id),
                 "native_search",
-                &json!({ "query": state.input, "limit": 8 }),
+                &json!({ "query": state.search_query(), "limit": 8 }),
             )
             .await
             {
                 Ok(result) => {
-                    if let Some(results) = result.search_results {
-                        web_results = results;
-                    }
+                    let results =
+                        dedupe_by_canonical_url(result.search_results.unwrap_or_default());
+                    // よく引用されてきたドメインを前に出してから、代表を選ぶ
+                    let results =
+                        rank_with_learned_quality(ctx.app_state, ctx.config, results).await;
+                    let clusters = cluster_snippets(results);
+                    web_results = clusters
+                        .iter()
+                        .map(|cluster| cluster.representative.clone())
+                        .collect();
 
                     if !web_results.is_empty() {
                         let _ = ctx
@@ -117,32 +133,22 @@ impl Node for SearchNode {
                             .await;
                     }
 
-                    let fetch_top_n = ctx
-                        .config
-                        .get("search")
-                        .and_then(|v| v.get("fetch_top_n"))
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(3)
-                        .clamp(1, 8) as usize;
-
-                    for result in web_results.iter().take(fetch_top_n) {
-                        let fetched = execute_tool(
-                            Some(ctx.app_state),
-                            ctx.config,
-                            Some(&ctx.app_state.integration.mcp),
-                            Some(&state.session_id),
-                            "native_web_fetch",
-                            &json!({ "url": result.url }),
-                        )
-                        .await;
+                    let fetch_top_n = search_setting(ctx.config, "fetch_top_n", 3, 1, 8);
+                    let to_fetch = &web_results[..fetch_top_n.min(web_results.len())];
+                    let pages = fetch_pages(
+                        ctx.app_state,
+                        ctx.config,
+                        &state.session_id,
+                        to_fetch,
+                        search_setting(ctx.config, "fetch_concurrency", 3, 1, 8),
+                        search_setting(ctx.config, "per_domain_fetch_limit", 1, 1, 4),
+                    )
+                    .await;
 
-                        let Ok(fetched) = fetched else {
+                    for result in to_fetch {
+                        let Some(content) = pages.get(&result.url) else {
                             continue;
                         };
-                        if fetched.output.trim().is_empty() {
-                            continue;
-                        }
-
                         let _ = execute_tool(
                             Some(ctx.app_state),
                             ctx.config,
@@ -150,7 +156,7 @@ impl Node for SearchNode {
                             Some(&state.session_id),
                             "native_rag_ingest",
                             &json!({
-                                "content": fetched.output,
+                                "content": content,
                                 "source": result.url,
                                 "metadata": {
                                     "title": result.title,
@@ -160,6 +166,8 @@ impl Node for SearchNode {
                         )
                         .await;
                     }
+
+                    evidence_pack = EvidencePack::build(&state.input, &clusters, &pages);
                 }
                 Err(err) => {
                     web_failed = true;
@@ -269,6 +277,36 @@ impl Node for SearchNode {
             pipeline_ctx.user_input = state.input.clone();
         }
 
+        let mut injected_sources = 0;
+        if !evidence_pack.is_empty() {
+            let (rendered, included) = evidence_pack.render_budgeted(search_setting(
+                ctx.config,
+                "evidence_pack_max_chars",
+                4_000,
+                500,
+                20_000,
+            ));
+            injected_sources = included;
+            let metadata = HashMap::from([(
+                "sources".to_string(),
+                serde_json::to_value(&evidence_pack.sources).unwrap_or_default(),
+            )]);
+            if let Some(pipeline_ctx) = state.pipeline_context.as_mut() {
+                pipeline_ctx.add_artifact("evidence_pack", rendered.clone(), metadata.clone());
+                pipeline_ctx.add_system_part(
+                    "evidence_citation",
+                    EVIDENCE_CITATION_INSTRUCTION,
+                    130,
+                );
+            }
+            // SynthesizerNode は共有アーティファクトから同じパックを読む
+            state.shared_context.artifacts.push(Artifact {
+                artifact_type: "evidence_pack".to_string(),
+                content: rendered,
+                metadata,
+            });
+        }
+
         let mut messages = if let Some(pipeline_ctx) = state.pipeline_context.as_ref() {
             ContextPipeline::pipeline_to_context_result(pipeline_ctx).messages
         } else {
@@ -288,13 +326,11 @@ impl Node for SearchNode {
             }
         }
 
-        let request = ChatRequest::new(messages).with_config(ctx.config);
+        let request = ChatRequest::new(messages)
+            .with_config(ctx.config)
+            .with_cache_key(&state.session_id);
 
-        let active_character = ctx
-            .config
-            .get("active_character")
-            .or_else(|| ctx.config.get("active_agent_profile"))
-            .and_then(|v| v.as_str());
+        let active_character = crate::core::config::personas::model_profile_id(ctx.config);
         let model_id = ctx
             .app_state
             .ai()
@@ -346,6 +382,13 @@ impl Node for SearchNode {
 
         let _ = ctx.sender.send_json(json!({"type": "done"})).await;
 
+        record_cited_sources(
+            ctx.app_state,
+            &evidence_pack.sources[..injected_sources],
+            &full_response,
+        )
+        .await;
+
         state.search_results = Some(web_results);
         state.search_evidence = SearchEvidenceState {
             strategy: SearchMode::Quick,
@@ -372,6 +415,58 @@ impl Node for SearchNode {
     }
 }
 
+pub(crate) const EVIDENCE_CITATION_INSTRUCTION: &str =
+    "When you use a source from the evidence pack, cite it inline with its id such as [S1]. Do not cite sources that are not in the pack.";
+
+pub(super) fn search_setting(config: &Value, key: &str, default: u64, min: u64, max: u64) -> usize {
+    config
+        .get("search")
+        .and_then(|v| v.get(key))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(default)
+        .clamp(min, max) as usize
+}
+
+/// 上位の結果を並行して取得する。同じドメインへの同時接続は `per_domain` 件まで。
+pub(super) async fn fetch_pages(
+    app_state: &AppState,
+    config: &Value,
+    session_id: &str,
+    results: &[SearchResult],
+    concurrency: usize,
+    per_domain: usize,
+) -> HashMap<String, String> {
+    let mut gates: HashMap<String, Arc<Semaphore>> = HashMap::new();
+    let fetches = results.iter().map(|result| {
+        let gate = gates
+            .entry(url_domain(&result.url))
+            .or_insert_with(|| Arc::new(Semaphore::new(per_domain)))
+            .clone();
+        async move {
+            let _permit = gate.acquire_owned().await.ok()?;
+            let fetched = execute_tool(
+                Some(app_state),
+                config,
+                Some(&app_state.integration.mcp),
+                Some(session_id),
+                "native_web_fetch",
+                &json!({ "url": result.url }),
+            )
+            .await
+            .ok()?;
+            if fetched.output.trim().is_empty() {
+                return None;
+            }
+            Some((result.url.clone(), fetched.output))
+        }
+    });
+    futures_util::stream::iter(fetches.collect::<Vec<_>>())
+        .buffer_unordered(concurrency)
+        .filter_map(|page| async move { page })
+        .collect()
+        .await
+}
+
 fn parse_json_payload<T>(output: &str) -> Option<T>
 where
     T: serde::de::DeserializeOwned,
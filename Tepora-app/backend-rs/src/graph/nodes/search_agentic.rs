@@ -306,7 +306,7 @@ impl AgenticSearchNode {
                 GraphError::new(self.id(), format!("sub-query generation failed: {err}"))
             })?;
 
-        let mut queries = vec![state.input.clone()];
+        let mut queries = vec![state.search_query()];
         for query in parsed {
             let query = query.trim().to_string();
             if !query.is_empty() && !queries.iter().any(|existing| existing == &query) {
@@ -785,10 +785,7 @@ impl AgenticSearchNode {
     }
 
     fn configured_active_profile<'a>(&self, ctx: &'a NodeContext<'_>) -> Option<&'a str> {
-        ctx.config
-            .get("active_character")
-            .or_else(|| ctx.config.get("active_agent_profile"))
-            .and_then(|v| v.as_str())
+        crate::core::config::personas::model_profile_id(ctx.config)
     }
 
     fn resolve_model_id_best_effort(&self, ctx: &NodeContext<'_>) -> String {
@@ -1,16 +1,29 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use serde_json::json;
+use serde::Deserialize;
+use serde_json::{json, Value};
 
 use crate::agent::execution::{
     build_agent_chat_config, resolve_execution_model_id, resolve_selected_agent,
 };
 use crate::context::pipeline::ContextPipeline;
-use crate::context::pipeline_context::{PipelineArtifact, PipelineMode, PipelineStage};
+use crate::context::pipeline_context::{
+    PipelineArtifact, PipelineContext, PipelineMode, PipelineStage, RagChunk,
+};
 use crate::graph::node::{GraphError, Node, NodeContext, NodeOutput};
-use crate::graph::state::{AgentMode, AgentState};
+use crate::graph::state::{AgentMode, AgentState, SynthesisMode};
+use crate::llm::types::StructuredResponseSpec;
 use crate::llm::ChatRequest;
+use crate::models::types::ModelRuntimeConfig;
+
+const SYNTHESIZER_INSTRUCTION: &str = "Use only summarized artifacts, stable memory, and local context to produce the final user-facing answer. Do not rely on raw tool output or scratchpad text.";
+/// アウトラインの節の上限。小さいモデルでも最後まで書き切れる数にとどめる
+const MAX_OUTLINE_SECTIONS: usize = 6;
+/// 節ごとに引き直す RAG チャンク数
+const SECTION_RAG_CHUNKS: usize = 4;
+const SECTION_EMBED_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 pub struct SynthesizerNode;
 
@@ -26,6 +39,37 @@ impl Default for SynthesizerNode {
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+struct OutlineSection {
+    title: String,
+    #[serde(default)]
+    focus: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Outline {
+    sections: Vec<OutlineSection>,
+}
+
+impl Outline {
+    /// 空の見出しを落とし、節の数を上限までに切り詰める。
+    fn normalized(mut self) -> Self {
+        self.sections
+            .retain(|section| !section.title.trim().is_empty());
+        self.sections.truncate(MAX_OUTLINE_SECTIONS);
+        self
+    }
+
+    fn render(&self) -> String {
+        self.sections
+            .iter()
+            .enumerate()
+            .map(|(index, section)| format!("{}. {}", index + 1, section.title.trim()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
 #[async_trait]
 impl Node for SynthesizerNode {
     fn id(&self) -> &'static str {
@@ -46,18 +90,13 @@ impl Node for SynthesizerNode {
             .clone()
             .unwrap_or_else(|| "Assistant".to_string());
 
-        let _ = ctx
-            .sender
-            .send_json(json!({
-                "type": "activity",
-                "data": {
-                    "id": "synthesize_final_response",
-                    "status": "processing",
-                    "message": "Synthesizing final response",
-                    "agentName": &agent_name
-                }
-            }))
-            .await;
+        self.send_activity(
+            ctx,
+            &agent_name,
+            "processing",
+            "Synthesizing final response",
+        )
+        .await;
 
         let pipeline_mode = match state.agent_mode {
             AgentMode::High => PipelineMode::AgentHigh,
@@ -72,12 +111,13 @@ impl Node for SynthesizerNode {
             .unwrap_or(true);
         if should_rebuild {
             let app_state = Arc::new(ctx.app_state.clone());
-            let pipeline_ctx = ContextPipeline::build_v4(
+            let pipeline_ctx = ContextPipeline::build_v4_for_agent(
                 &app_state,
                 &state.session_id,
                 &state.input,
                 pipeline_mode,
                 state.skip_web_search,
+                state.selected_agent_id.as_deref(),
             )
             .await
             .map_err(|err| GraphError::new(self.id(), err.to_string()))?;
@@ -91,97 +131,422 @@ impl Node for SynthesizerNode {
         let model_id =
             resolve_execution_model_id(ctx.app_state, ctx.config, selected_agent.as_ref());
 
-        let messages = if let Some(pipeline_ctx) = state.pipeline_context.as_ref() {
-            let mut staged = pipeline_ctx.clone();
-            staged.stage = PipelineStage::AgentSynthesizer;
-            staged.artifacts.extend(
-                state
-                    .shared_context
-                    .artifacts
-                    .iter()
-                    .rev()
-                    .take(5)
-                    .map(|artifact| PipelineArtifact {
-                        artifact_type: artifact.artifact_type.clone(),
-                        content: artifact.content.clone(),
-                        metadata: artifact.metadata.clone(),
-                    })
-                    .collect::<Vec<_>>(),
-            );
+        let staged = self.stage_context(state);
+        let outline = match (&staged, state.synthesis_mode) {
+            (Some(staged), SynthesisMode::Outline) => {
+                self.plan_outline(staged, ctx, &agent_chat_config, &model_id)
+                    .await
+            }
+            _ => None,
+        };
+
+        let full_response = match (staged, outline) {
+            (Some(staged), Some(outline)) => {
+                self.write_sections(
+                    &staged,
+                    &outline,
+                    state,
+                    ctx,
+                    &agent_chat_config,
+                    &model_id,
+                    &agent_name,
+                )
+                .await?
+            }
+            (staged, _) => {
+                let messages = match staged {
+                    Some(mut staged) => {
+                        staged.add_system_part(
+                            "synthesizer_instruction",
+                            SYNTHESIZER_INSTRUCTION,
+                            130,
+                        );
+                        staged.to_messages()
+                    }
+                    None => state.chat_history.clone(),
+                };
+                let request = ChatRequest::new(messages)
+                    .with_config(&agent_chat_config)
+                    .with_cache_key(&state.session_id);
+                self.stream_response(ctx, request, &model_id, &agent_name)
+                    .await?
+            }
+        };
+
+        self.send_activity(ctx, &agent_name, "done", "Response complete")
+            .await;
+
+        let _ = ctx.sender.send_json(json!({"type": "done"})).await;
+
+        state.output = Some(full_response);
+        Ok(NodeOutput::Final)
+    }
+}
+
+impl SynthesizerNode {
+    /// 最終回答用に段階を合わせ、共有アーティファクトを足したコンテキスト。
+    fn stage_context(&self, state: &AgentState) -> Option<PipelineContext> {
+        let mut staged = state.pipeline_context.as_ref()?.clone();
+        staged.stage = PipelineStage::AgentSynthesizer;
+        staged.artifacts.extend(
+            state
+                .shared_context
+                .artifacts
+                .iter()
+                .rev()
+                .take(5)
+                .map(|artifact| PipelineArtifact {
+                    artifact_type: artifact.artifact_type.clone(),
+                    content: artifact.content.clone(),
+                    metadata: artifact.metadata.clone(),
+                })
+                .collect::<Vec<_>>(),
+        );
+        if state
+            .shared_context
+            .artifacts
+            .iter()
+            .any(|artifact| artifact.artifact_type == "evidence_pack")
+        {
             staged.add_system_part(
-                "synthesizer_instruction",
-                "Use only summarized artifacts, stable memory, and local context to produce the final user-facing answer. Do not rely on raw tool output or scratchpad text.",
+                "evidence_citation",
+                super::search::EVIDENCE_CITATION_INSTRUCTION,
                 130,
             );
-            staged.to_messages()
-        } else {
-            state.chat_history.clone()
-        };
+        }
+        Some(staged)
+    }
 
-        let request = ChatRequest::new(messages).with_config(&agent_chat_config);
-        let mut stream = ctx
+    /// 一段目: 見出しだけを決める。失敗したら `None` で通常の一括生成に戻す。
+    async fn plan_outline(
+        &self,
+        staged: &PipelineContext,
+        ctx: &mut NodeContext<'_>,
+        agent_chat_config: &Value,
+        model_id: &str,
+    ) -> Option<Outline> {
+        let mut planning = staged.clone();
+        planning.add_system_part(
+            "outline_instruction",
+            format!(
+                concat!(
+                    "Plan the final answer before writing it. ",
+                    "Return 2-{} section titles in reading order, each with a one-sentence focus ",
+                    "describing what the section must cover. Return only the structured outline."
+                ),
+                MAX_OUTLINE_SECTIONS
+            ),
+            130,
+        );
+        let request = ChatRequest::new(planning.to_messages())
+            .with_config(agent_chat_config)
+            .with_structured_response(outline_structured_spec());
+        let outline = match ctx
             .app_state
             .ai()
             .llm
-            .stream_chat_normalized(request, &model_id)
+            .chat_structured::<Outline>(request, model_id)
             .await
-            .map_err(|e| GraphError::new(self.id(), e.to_string()))?;
+        {
+            Ok(outline) => outline.normalized(),
+            Err(err) => {
+                tracing::warn!("Outline synthesis planning failed: {}", err);
+                return None;
+            }
+        };
+        (!outline.sections.is_empty()).then_some(outline)
+    }
 
+    /// 二段目: 節ごとに資料を引き直し、見出しを付けて順に書く。
+    #[allow(clippy::too_many_arguments)]
+    async fn write_sections(
+        &self,
+        staged: &PipelineContext,
+        outline: &Outline,
+        state: &AgentState,
+        ctx: &mut NodeContext<'_>,
+        agent_chat_config: &Value,
+        model_id: &str,
+        agent_name: &str,
+    ) -> Result<String, GraphError> {
+        let total = outline.sections.len();
         let mut full_response = String::new();
 
+        for (index, section) in outline.sections.iter().enumerate() {
+            self.send_activity(
+                ctx,
+                agent_name,
+                "processing",
+                &format!(
+                    "Writing section {}/{}: {}",
+                    index + 1,
+                    total,
+                    section.title.trim()
+                ),
+            )
+            .await;
+
+            let mut section_ctx = staged.clone();
+            let chunks = self
+                .retrieve_for_section(state, ctx, &section_query(section))
+                .await;
+            if !chunks.is_empty() {
+                section_ctx.rag_chunks = chunks;
+            }
+            section_ctx.add_system_part("synthesizer_instruction", SYNTHESIZER_INSTRUCTION, 130);
+            section_ctx.add_system_part(
+                "outline_section_instruction",
+                section_instruction(outline, index),
+                130,
+            );
+
+            let heading = if index == 0 {
+                format!("## {}\n\n", section.title.trim())
+            } else {
+                format!("\n\n## {}\n\n", section.title.trim())
+            };
+            self.send_chunk(ctx, agent_name, &heading).await;
+            full_response.push_str(&heading);
+
+            let request = ChatRequest::new(section_ctx.to_messages())
+                .with_config(agent_chat_config)
+                .with_cache_key(&state.session_id);
+            let body = self
+                .stream_response(ctx, request, model_id, agent_name)
+                .await?;
+            full_response.push_str(body.trim_end());
+        }
+
+        Ok(full_response)
+    }
+
+    /// 節の内容に合わせて RAG を引き直す。埋め込みが使えなければ空を返す。
+    async fn retrieve_for_section(
+        &self,
+        state: &AgentState,
+        ctx: &NodeContext<'_>,
+        query: &str,
+    ) -> Vec<RagChunk> {
+        let Ok(model_cfg) = ModelRuntimeConfig::for_embedding(ctx.config) else {
+            return Vec::new();
+        };
+        let embeddings = match ctx
+            .app_state
+            .ai()
+            .llama
+            .embed(&model_cfg, &[query.to_string()], SECTION_EMBED_TIMEOUT)
+            .await
+        {
+            Ok(embeddings) => embeddings,
+            Err(err) => {
+                tracing::debug!("Section retrieval skipped, embedding unavailable: {}", err);
+                return Vec::new();
+            }
+        };
+        let Some(embedding) = embeddings.first() else {
+            return Vec::new();
+        };
+        match ctx
+            .app_state
+            .memory()
+            .knowledge_use_case
+            .search(embedding, SECTION_RAG_CHUNKS, Some(&state.session_id))
+            .await
+        {
+            Ok(hits) => hits
+                .into_iter()
+                .map(|hit| RagChunk {
+                    chunk_id: hit.chunk_id,
+                    content: hit.content,
+                    source: hit.source,
+                    score: hit.score,
+                    metadata: match hit.metadata {
+                        Some(Value::Object(map)) => map.into_iter().collect(),
+                        _ => HashMap::new(),
+                    },
+                })
+                .collect(),
+            Err(err) => {
+                tracing::debug!("Section retrieval failed: {}", err);
+                Vec::new()
+            }
+        }
+    }
+
+    async fn stream_response(
+        &self,
+        ctx: &mut NodeContext<'_>,
+        request: ChatRequest,
+        model_id: &str,
+        agent_name: &str,
+    ) -> Result<String, GraphError> {
+        let mut stream = ctx
+            .app_state
+            .ai()
+            .llm
+            .stream_chat_normalized(request, model_id)
+            .await
+            .map_err(|err| GraphError::from_llm(self.id(), err))?;
+
+        let mut response = String::new();
         while let Some(chunk_result) = stream.recv().await {
-            match chunk_result {
-                Ok(chunk) => {
-                    if !chunk.model_thinking.is_empty() {
-                        let _ = ctx
-                            .sender
-                            .send_json(json!({
-                                "type": "thought",
-                                "content": chunk.model_thinking,
-                                "mode": "agent",
-                                "agentName": &agent_name,
-                                "nodeId": "synthesize_final_response"
-                            }))
-                            .await;
-                    }
+            let chunk = chunk_result.map_err(|err| GraphError::from_llm(self.id(), err))?;
+            if !chunk.model_thinking.is_empty() {
+                let _ = ctx
+                    .sender
+                    .send_json(json!({
+                        "type": "thought",
+                        "content": chunk.model_thinking,
+                        "mode": "agent",
+                        "agentName": agent_name,
+                        "nodeId": "synthesize_final_response"
+                    }))
+                    .await;
+            }
 
-                    if chunk.visible_text.is_empty() {
-                        continue;
-                    }
-                    full_response.push_str(&chunk.visible_text);
-                    let _ = ctx
-                        .sender
-                        .send_json(json!({
-                            "type": "chunk",
-                            "message": chunk.visible_text,
-                            "mode": "agent",
-                            "agentName": &agent_name,
-                            "nodeId": "synthesize_final_response"
-                        }))
-                        .await;
-                }
-                Err(err) => {
-                    return Err(GraphError::new(self.id(), err.to_string()));
-                }
+            if chunk.visible_text.is_empty() {
+                continue;
             }
+            response.push_str(&chunk.visible_text);
+            self.send_chunk(ctx, agent_name, &chunk.visible_text).await;
         }
+        Ok(response)
+    }
 
+    async fn send_chunk(&self, ctx: &mut NodeContext<'_>, agent_name: &str, text: &str) {
+        let _ = ctx
+            .sender
+            .send_json(json!({
+                "type": "chunk",
+                "message": text,
+                "mode": "agent",
+                "agentName": agent_name,
+                "nodeId": "synthesize_final_response"
+            }))
+            .await;
+    }
+
+    async fn send_activity(
+        &self,
+        ctx: &mut NodeContext<'_>,
+        agent_name: &str,
+        status: &str,
+        message: &str,
+    ) {
         let _ = ctx
             .sender
             .send_json(json!({
                 "type": "activity",
                 "data": {
                     "id": "synthesize_final_response",
-                    "status": "done",
-                    "message": "Response complete",
-                    "agentName": &agent_name
+                    "status": status,
+                    "message": message,
+                    "agentName": agent_name
                 }
             }))
             .await;
+    }
+}
 
-        let _ = ctx.sender.send_json(json!({"type": "done"})).await;
+fn section_query(section: &OutlineSection) -> String {
+    let focus = section.focus.trim();
+    if focus.is_empty() {
+        section.title.trim().to_string()
+    } else {
+        format!("{}: {}", section.title.trim(), focus)
+    }
+}
 
-        state.output = Some(full_response);
-        Ok(NodeOutput::Final)
+fn section_instruction(outline: &Outline, index: usize) -> String {
+    let section = &outline.sections[index];
+    let focus = section.focus.trim();
+    format!(
+        concat!(
+            "You are writing one section of a longer answer that follows this outline:\n{}\n\n",
+            "Write only section {} \"{}\"{}. ",
+            "Do not repeat the heading, do not write the other sections, ",
+            "and do not add an introduction or conclusion for the whole answer."
+        ),
+        outline.render(),
+        index + 1,
+        section.title.trim(),
+        if focus.is_empty() {
+            String::new()
+        } else {
+            format!(", which must cover: {}", focus)
+        }
+    )
+}
+
+fn outline_structured_spec() -> StructuredResponseSpec {
+    StructuredResponseSpec {
+        name: "synthesis_outline".to_string(),
+        description: Some("Section titles for the final answer".to_string()),
+        schema: json!({
+            "type": "object",
+            "properties": {
+                "sections": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "title": { "type": "string" },
+                            "focus": { "type": "string" }
+                        },
+                        "required": ["title", "focus"]
+                    },
+                    "minItems": 1,
+                    "maxItems": MAX_OUTLINE_SECTIONS
+                }
+            },
+            "required": ["sections"]
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline() -> Outline {
+        serde_json::from_value::<Outline>(json!({
+            "sections": [
+                { "title": "Background", "focus": "why the migration started" },
+                { "title": "  ", "focus": "dropped" },
+                { "title": "Results" }
+            ]
+        }))
+        .unwrap()
+        .normalized()
+    }
+
+    #[test]
+    fn outline_drops_blank_titles_and_caps_sections() {
+        assert_eq!(outline().sections.len(), 2);
+
+        let many = Outline {
+            sections: (0..10)
+                .map(|i| OutlineSection {
+                    title: format!("S{}", i),
+                    focus: String::new(),
+                })
+                .collect(),
+        }
+        .normalized();
+        assert_eq!(many.sections.len(), MAX_OUTLINE_SECTIONS);
+    }
+
+    #[test]
+    fn section_prompt_names_outline_and_target_section() {
+        let outline = outline();
+        let instruction = section_instruction(&outline, 1);
+        assert!(instruction.contains("1. Background\n2. Results"));
+        assert!(instruction.contains("Write only section 2 \"Results\"."));
+
+        assert_eq!(
+            section_query(&outline.sections[0]),
+            "Background: why the migration started"
+        );
+        assert_eq!(section_query(&outline.sections[1]), "Results");
     }
 }
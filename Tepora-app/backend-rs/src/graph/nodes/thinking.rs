@@ -10,6 +10,7 @@ use crate::context::pipeline::ContextPipeline;
 use crate::context::pipeline_context::{PipelineContext, PipelineMode};
 use crate::graph::node::{GraphError, Node, NodeContext, NodeOutput};
 use crate::graph::state::AgentState;
+use crate::llm::redaction::{new_redaction_sink, take_redaction_report};
 use crate::llm::ChatRequest;
 use crate::models::event::{AgentEvent, AgentEventType};
 
@@ -119,11 +120,7 @@ impl Node for ThinkingNode {
         .await;
 
         // Resolve model ID
-        let active_character = ctx
-            .config
-            .get("active_character")
-            .or_else(|| ctx.config.get("active_agent_profile"))
-            .and_then(|v| v.as_str());
+        let active_character = crate::core::config::personas::model_profile_id(ctx.config);
         let model_id = ctx
             .app_state
             .ai()
@@ -136,7 +133,10 @@ impl Node for ThinkingNode {
         let final_thought = if num_paths == 1 {
             // Standard CoT (Level 1)
             let thinking_messages = self.thinking_messages(&base_ctx, &state.input, None);
-            let request = ChatRequest::new(thinking_messages).with_config(ctx.config);
+            let redaction_sink = new_redaction_sink();
+            let request = ChatRequest::new(thinking_messages)
+                .with_config(ctx.config)
+                .with_redaction_sink(redaction_sink.clone());
             let response = ctx
                 .app_state
                 .ai()
@@ -147,14 +147,25 @@ impl Node for ThinkingNode {
                     GraphError::new(self.id(), e.to_string())
                 })?;
 
-            if let Err(e) = ctx.app_state.runtime().history.save_agent_event(&AgentEvent {
-                id: uuid::Uuid::new_v4().to_string(),
-                session_id: state.session_id.clone(),
-                node_name: self.id().to_string(),
-                event_type: AgentEventType::PromptGenerated,
-                metadata: json!({"type": "cot", "model_id": model_id, "length": response.len()}),
-                created_at: chrono::Utc::now(),
-            }).await {
+            if let Err(e) = ctx
+                .app_state
+                .runtime()
+                .history
+                .save_agent_event(&AgentEvent {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    session_id: state.session_id.clone(),
+                    node_name: self.id().to_string(),
+                    event_type: AgentEventType::PromptGenerated,
+                    metadata: json!({
+                        "type": "cot",
+                        "model_id": model_id,
+                        "length": response.len(),
+                        "redaction": take_redaction_report(&redaction_sink),
+                    }),
+                    created_at: chrono::Utc::now(),
+                })
+                .await
+            {
                 tracing::warn!(error = %e, "Failed to save agent event");
             }
 
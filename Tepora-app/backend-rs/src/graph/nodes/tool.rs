@@ -4,10 +4,15 @@
 use async_trait::async_trait;
 use serde_json::{json, Value};
 
+use crate::agent::execution::resolve_selected_agent;
+use crate::agent::policy::CustomToolPolicy;
 use crate::context::controller::render_untrusted_xml_element;
+use crate::core::errors::ApiError;
+use crate::core::native_tools::{resolve_tool_alias, NATIVE_RUN_COMMAND};
 use crate::graph::node::{GraphError, Node, NodeContext, NodeOutput};
 use crate::graph::state::AgentState;
-use crate::tools::execute_tool;
+use crate::tools::execute_tool_with_policy;
+use crate::tools::shell::command_auto_approved;
 
 pub struct ToolNode {
     tool_name: String,
@@ -51,16 +56,32 @@ impl Node for ToolNode {
             }))
             .await;
 
-        // Execute tool
-        let result = execute_tool(
-            Some(ctx.app_state),
-            ctx.config,
-            Some(&ctx.app_state.integration.mcp),
-            Some(&state.session_id),
-            &self.tool_name,
-            &self.tool_args,
-        )
-        .await;
+        // The selected agent's policy applies to workflow-defined tool calls too.
+        let policy = resolve_selected_agent(ctx.app_state, state.selected_agent_id.as_deref())
+            .map(|agent| agent.tool_policy)
+            .unwrap_or_else(CustomToolPolicy::allow_all_policy);
+
+        // Workflow nodes have no approval prompt, so only auto-approved
+        // commands may run from here.
+        let result = if resolve_tool_alias(&self.tool_name) == NATIVE_RUN_COMMAND
+            && !command_auto_approved(ctx.config, &self.tool_args)
+        {
+            Err(ApiError::BadRequest(
+                "run_command needs approval; only commands matching tools.shell.auto_approve_patterns can run from workflow nodes"
+                    .to_string(),
+            ))
+        } else {
+            execute_tool_with_policy(
+                &policy,
+                Some(ctx.app_state),
+                ctx.config,
+                Some(&ctx.app_state.integration.mcp),
+                Some(&state.session_id),
+                &self.tool_name,
+                &self.tool_args,
+            )
+            .await
+        };
 
         match result {
             Ok(execution) => {
@@ -73,14 +94,15 @@ impl Node for ToolNode {
                 }
 
                 // Add tool result to scratchpad
-                state.agent_scratchpad.push(crate::llm::ChatMessage {
-                    role: "user".to_string(),
-                    content: render_untrusted_xml_element(
-                        "tool_observation",
-                        &[("kind", "result"), ("tool", self.tool_name.as_str())],
-                        &execution.output,
-                    ),
-                    multimodal_parts: None,
+                let observation = render_untrusted_xml_element(
+                    "tool_observation",
+                    &[("kind", "result"), ("tool", self.tool_name.as_str())],
+                    &execution.output,
+                );
+                state.agent_scratchpad.push(if execution.images.is_empty() {
+                    crate::llm::ChatMessage::new_text("user", observation)
+                } else {
+                    crate::llm::ChatMessage::new_multimodal("user", &observation, &execution.images)
                 });
 
                 let _ = ctx
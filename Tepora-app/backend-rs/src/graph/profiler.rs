@@ -0,0 +1,497 @@
+// Graph run profiler
+// Per-node wall-clock / token / tool-time breakdowns, aggregated per run and per day
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::infrastructure::observability::latency::FirstTokenDiagnostics;
+use crate::llm::types::{NormalizedStreamChunk, TokenUsage};
+
+/// Number of finished runs kept for `GET /api/profiler/runs/:id`
+const MAX_RUNS: usize = 200;
+/// Number of days kept in the daily rollup
+const MAX_DAYS: usize = 30;
+
+tokio::task_local! {
+    static CURRENT_NODE: Arc<NodeCounters>;
+}
+
+/// Counters filled in while a node executes. LLM and tool code report here
+/// through the task-local scope, so nodes need no profiling code of their own.
+#[derive(Debug, Default)]
+pub struct NodeCounters {
+    prompt_tokens: AtomicU64,
+    completion_tokens: AtomicU64,
+    llm_calls: AtomicU64,
+    tool_ms: AtomicU64,
+    tool_calls: AtomicU64,
+}
+
+impl NodeCounters {
+    fn add_usage(&self, usage: &TokenUsage) {
+        self.llm_calls.fetch_add(1, Ordering::Relaxed);
+        self.prompt_tokens
+            .fetch_add(usage.prompt_tokens.unwrap_or(0) as u64, Ordering::Relaxed);
+        self.completion_tokens.fetch_add(
+            usage.completion_tokens.unwrap_or(0) as u64,
+            Ordering::Relaxed,
+        );
+    }
+}
+
+/// Counters of the node being executed on this task, if any.
+pub fn current_scope() -> Option<Arc<NodeCounters>> {
+    CURRENT_NODE.try_with(Arc::clone).ok()
+}
+
+/// Record token usage of a non-streaming LLM call.
+pub fn record_llm_usage(usage: Option<&TokenUsage>) {
+    if let Some(scope) = current_scope() {
+        match usage {
+            Some(usage) => scope.add_usage(usage),
+            None => {
+                scope.llm_calls.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Record time spent in a tool call.
+pub fn record_tool_call(elapsed: Duration) {
+    if let Some(scope) = current_scope() {
+        scope.tool_calls.fetch_add(1, Ordering::Relaxed);
+        scope
+            .tool_ms
+            .fetch_add(elapsed.as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Streams are produced on a spawned task outside the node scope, so relay
+/// them and pick the usage off the final chunk. Outside a graph run the
+/// receiver is returned untouched.
+pub fn observe_stream(
+    mut stream: mpsc::Receiver<Result<NormalizedStreamChunk, crate::core::errors::ApiError>>,
+    buffer: usize,
+) -> mpsc::Receiver<Result<NormalizedStreamChunk, crate::core::errors::ApiError>> {
+    let Some(scope) = current_scope() else {
+        return stream;
+    };
+    let (tx, rx) = mpsc::channel(buffer.max(1));
+    tokio::spawn(async move {
+        let mut usage_seen = false;
+        while let Some(item) = stream.recv().await {
+            if let Ok(chunk) = &item {
+                if let Some(usage) = &chunk.usage {
+                    scope.add_usage(usage);
+                    usage_seen = true;
+                }
+            }
+            if tx.send(item).await.is_err() {
+                break;
+            }
+        }
+        if !usage_seen {
+            scope.llm_calls.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    rx
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct ProfileTotals {
+    pub wall_ms: u64,
+    pub tool_ms: u64,
+    pub tool_calls: u64,
+    pub llm_calls: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl ProfileTotals {
+    fn add(&mut self, other: &ProfileTotals) {
+        self.wall_ms += other.wall_ms;
+        self.tool_ms += other.tool_ms;
+        self.tool_calls += other.tool_calls;
+        self.llm_calls += other.llm_calls;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+    }
+}
+
+/// Average cost of one LLM round of a node, taken from recent runs
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+pub struct RoundStats {
+    /// Number of completed node executions the averages come from
+    pub samples: u64,
+    pub prompt_tokens: f64,
+    pub completion_tokens: f64,
+    /// Wall-clock per round, including the tools it called
+    pub wall_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeProfile {
+    pub node_id: String,
+    pub step: usize,
+    /// Offset from the start of the run
+    pub offset_ms: u64,
+    #[serde(flatten)]
+    pub totals: ProfileTotals,
+    /// Wall-clock time not spent in tools (LLM calls and node logic)
+    pub other_ms: u64,
+    /// False when the run ended (error / timeout / cancel) inside this node
+    pub completed: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOutcome {
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunProfile {
+    pub run_id: String,
+    pub session_id: String,
+    pub mode: String,
+    pub started_at: DateTime<Utc>,
+    pub outcome: RunOutcome,
+    pub error: Option<String>,
+    pub totals: ProfileTotals,
+    pub nodes: Vec<NodeProfile>,
+    /// Snapshot taken when the first token was later than the watchdog threshold
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_token_diagnostics: Option<FirstTokenDiagnostics>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub run_id: String,
+    pub session_id: String,
+    pub mode: String,
+    pub started_at: DateTime<Utc>,
+    pub outcome: RunOutcome,
+    pub totals: ProfileTotals,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyProfile {
+    pub date: NaiveDate,
+    pub runs: u64,
+    pub failed_runs: u64,
+    #[serde(flatten)]
+    pub totals: ProfileTotals,
+}
+
+/// Trace of one run, owned by `GraphRuntime::run` so it survives a timeout
+/// that drops the execution future.
+pub(crate) struct RunTrace {
+    run_id: String,
+    session_id: String,
+    mode: String,
+    started_at: DateTime<Utc>,
+    started: Instant,
+    nodes: Vec<NodeProfile>,
+    current: Option<(String, usize, Instant, Arc<NodeCounters>)>,
+    first_token_diagnostics: Option<FirstTokenDiagnostics>,
+}
+
+impl RunTrace {
+    fn new(run_id: String, session_id: String, mode: String) -> Self {
+        Self {
+            run_id,
+            session_id,
+            mode,
+            started_at: Utc::now(),
+            started: Instant::now(),
+            nodes: Vec::new(),
+            current: None,
+            first_token_diagnostics: None,
+        }
+    }
+
+    /// Open a node; the returned counters must scope its execution.
+    pub(crate) fn enter(&mut self, node_id: &str, step: usize) -> Arc<NodeCounters> {
+        let counters = Arc::new(NodeCounters::default());
+        self.current = Some((
+            node_id.to_string(),
+            step,
+            Instant::now(),
+            Arc::clone(&counters),
+        ));
+        counters
+    }
+
+    pub(crate) fn exit(&mut self) {
+        self.close_current(true);
+    }
+
+    fn close_current(&mut self, completed: bool) {
+        let Some((node_id, step, started, counters)) = self.current.take() else {
+            return;
+        };
+        let wall_ms = started.elapsed().as_millis() as u64;
+        let tool_ms = counters.tool_ms.load(Ordering::Relaxed);
+        self.nodes.push(NodeProfile {
+            node_id,
+            step,
+            offset_ms: started.duration_since(self.started).as_millis() as u64,
+            totals: ProfileTotals {
+                wall_ms,
+                tool_ms,
+                tool_calls: counters.tool_calls.load(Ordering::Relaxed),
+                llm_calls: counters.llm_calls.load(Ordering::Relaxed),
+                prompt_tokens: counters.prompt_tokens.load(Ordering::Relaxed),
+                completion_tokens: counters.completion_tokens.load(Ordering::Relaxed),
+            },
+            other_ms: wall_ms.saturating_sub(tool_ms),
+            completed,
+        });
+    }
+
+    fn finish(mut self, outcome: RunOutcome, error: Option<String>) -> RunProfile {
+        self.close_current(false);
+        let mut totals = ProfileTotals::default();
+        for node in &self.nodes {
+            totals.add(&node.totals);
+        }
+        // Time between nodes (routing, scheduling) belongs to the run as a whole
+        totals.wall_ms = self.started.elapsed().as_millis() as u64;
+        RunProfile {
+            run_id: self.run_id,
+            session_id: self.session_id,
+            mode: self.mode,
+            started_at: self.started_at,
+            outcome,
+            error,
+            totals,
+            nodes: self.nodes,
+            first_token_diagnostics: self.first_token_diagnostics,
+        }
+    }
+}
+
+/// A run being profiled. Dropping it unfinished (the caller cancelled the
+/// generation) still records the nodes executed so far.
+pub(crate) struct ActiveRun<'a> {
+    profiler: &'a GraphProfiler,
+    trace: Option<RunTrace>,
+}
+
+impl ActiveRun<'_> {
+    pub(crate) fn trace(&mut self) -> &mut RunTrace {
+        self.trace.as_mut().expect("trace is present until finish")
+    }
+
+    pub(crate) fn attach_diagnostics(&mut self, diagnostics: Option<FirstTokenDiagnostics>) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.first_token_diagnostics = diagnostics;
+        }
+    }
+
+    pub(crate) fn finish(mut self, outcome: RunOutcome, error: Option<String>) {
+        if let Some(trace) = self.trace.take() {
+            self.profiler.record(trace, outcome, error);
+        }
+    }
+}
+
+impl Drop for ActiveRun<'_> {
+    fn drop(&mut self) {
+        if let Some(trace) = self.trace.take() {
+            self.profiler.record(trace, RunOutcome::Cancelled, None);
+        }
+    }
+}
+
+/// Execute a node future with its counters in scope.
+pub(crate) async fn scoped<F: std::future::Future>(
+    counters: Arc<NodeCounters>,
+    fut: F,
+) -> F::Output {
+    CURRENT_NODE.scope(counters, fut).await
+}
+
+#[derive(Default)]
+struct ProfilerStore {
+    runs: VecDeque<RunProfile>,
+    days: BTreeMap<NaiveDate, DailyProfile>,
+}
+
+/// In-memory store of recent run profiles and the daily rollup
+#[derive(Clone, Default)]
+pub struct GraphProfiler {
+    store: Arc<Mutex<ProfilerStore>>,
+}
+
+impl GraphProfiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn start(&self, run_id: String, session_id: String, mode: String) -> ActiveRun<'_> {
+        ActiveRun {
+            profiler: self,
+            trace: Some(RunTrace::new(run_id, session_id, mode)),
+        }
+    }
+
+    fn record(&self, trace: RunTrace, outcome: RunOutcome, error: Option<String>) {
+        let profile = trace.finish(outcome, error);
+        tracing::debug!(
+            run_id = %profile.run_id,
+            wall_ms = profile.totals.wall_ms,
+            tool_ms = profile.totals.tool_ms,
+            "Graph run profiled"
+        );
+        let mut store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+
+        let date = profile.started_at.date_naive();
+        let day = store.days.entry(date).or_insert_with(|| DailyProfile {
+            date,
+            runs: 0,
+            failed_runs: 0,
+            totals: ProfileTotals::default(),
+        });
+        day.runs += 1;
+        if profile.outcome != RunOutcome::Completed {
+            day.failed_runs += 1;
+        }
+        day.totals.add(&profile.totals);
+        while store.days.len() > MAX_DAYS {
+            store.days.pop_first();
+        }
+
+        store.runs.push_back(profile);
+        while store.runs.len() > MAX_RUNS {
+            store.runs.pop_front();
+        }
+    }
+
+    pub fn run(&self, run_id: &str) -> Option<RunProfile> {
+        let store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        store.runs.iter().find(|run| run.run_id == run_id).cloned()
+    }
+
+    /// Most recent first, optionally filtered by session
+    pub fn recent_runs(&self, session_id: Option<&str>, limit: usize) -> Vec<RunSummary> {
+        let store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        store
+            .runs
+            .iter()
+            .rev()
+            .filter(|run| session_id.is_none_or(|id| run.session_id == id))
+            .take(limit)
+            .map(|run| RunSummary {
+                run_id: run.run_id.clone(),
+                session_id: run.session_id.clone(),
+                mode: run.mode.clone(),
+                started_at: run.started_at,
+                outcome: run.outcome,
+                totals: run.totals.clone(),
+            })
+            .collect()
+    }
+
+    /// Per-LLM-call averages of `node_id` over the runs still in memory.
+    /// `None` until the node has completed at least once with an LLM call.
+    pub fn round_stats(&self, node_id: &str) -> Option<RoundStats> {
+        let store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        let mut samples = 0;
+        let mut totals = ProfileTotals::default();
+        for node in store.runs.iter().flat_map(|run| &run.nodes) {
+            if node.node_id == node_id && node.completed && node.totals.llm_calls > 0 {
+                samples += 1;
+                totals.add(&node.totals);
+            }
+        }
+        if samples == 0 {
+            return None;
+        }
+        let calls = totals.llm_calls as f64;
+        Some(RoundStats {
+            samples,
+            prompt_tokens: totals.prompt_tokens as f64 / calls,
+            completion_tokens: totals.completion_tokens as f64 / calls,
+            wall_ms: totals.wall_ms as f64 / calls,
+        })
+    }
+
+    pub fn daily(&self) -> Vec<DailyProfile> {
+        let store = self.store.lock().unwrap_or_else(|e| e.into_inner());
+        store.days.values().rev().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn usage_and_tool_time_are_attributed_to_the_scoped_node() {
+        let profiler = GraphProfiler::new();
+        let mut run = profiler.start("run-1".into(), "s1".into(), "agent".into());
+        let trace = run.trace();
+
+        let counters = trace.enter("planner", 0);
+        scoped(counters, async {
+            record_llm_usage(Some(&TokenUsage {
+                prompt_tokens: Some(120),
+                completion_tokens: Some(30),
+                ..Default::default()
+            }));
+            record_tool_call(Duration::from_millis(250));
+        })
+        .await;
+        trace.exit();
+
+        // Recorded outside any node: ignored
+        record_tool_call(Duration::from_millis(999));
+
+        let counters = trace.enter("agent_executor", 1);
+        scoped(counters, async {}).await;
+        run.finish(RunOutcome::Failed, Some("timeout".into()));
+
+        let run = profiler.run("run-1").expect("run kept");
+        assert_eq!(run.nodes.len(), 2);
+        assert_eq!(run.nodes[0].totals.prompt_tokens, 120);
+        assert_eq!(run.nodes[0].totals.tool_ms, 250);
+        assert!(run.nodes[0].completed);
+        assert!(!run.nodes[1].completed);
+        assert_eq!(run.totals.tool_ms, 250);
+        assert_eq!(run.totals.completion_tokens, 30);
+
+        let days = profiler.daily();
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].runs, 1);
+        assert_eq!(days[0].failed_runs, 1);
+        assert_eq!(profiler.recent_runs(Some("other"), 10).len(), 0);
+
+        let planner = profiler.round_stats("planner").expect("planner completed");
+        assert_eq!(planner.samples, 1);
+        assert_eq!(planner.prompt_tokens, 120.0);
+        // The executor never finished, so it has no history yet
+        assert!(profiler.round_stats("agent_executor").is_none());
+    }
+
+    #[test]
+    fn dropped_run_is_recorded_as_cancelled() {
+        let profiler = GraphProfiler::new();
+        {
+            let mut run = profiler.start("run-2".into(), "s1".into(), "chat".into());
+            run.trace().enter("chat", 0);
+        }
+        let run = profiler.run("run-2").expect("run kept");
+        assert_eq!(run.outcome, RunOutcome::Cancelled);
+        assert_eq!(run.nodes.len(), 1);
+        assert!(!run.nodes[0].completed);
+    }
+}
@@ -5,9 +5,16 @@ use petgraph::graph::{DiGraph, NodeIndex};
 use petgraph::visit::EdgeRef;
 use petgraph::Direction;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
 
 use super::node::{GraphError, Node, NodeContext, NodeOutput};
+use super::profiler::{self, GraphProfiler, RunOutcome, RunTrace};
 use super::state::AgentState;
+use crate::infrastructure::observability::latency;
+use crate::state::diagnostics::watch_first_token;
 
 /// Edge condition for graph routing
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -44,10 +51,72 @@ pub struct GraphRuntime {
     node_indices: HashMap<String, NodeIndex>,
     /// Entry point node ID
     entry_node_id: String,
+    /// Mode name -> entry node ID, overriding `entry_node_id` for that mode
+    entry_points: HashMap<String, String>,
     /// Maximum execution steps (recursion limit)
     max_steps: usize,
     /// Execution timeout
     execution_timeout: Option<std::time::Duration>,
+    /// Number of `run` calls currently executing
+    runs_in_flight: AtomicUsize,
+    /// Per-run cost/latency profiles
+    profiler: GraphProfiler,
+    /// Runs currently executing, keyed by run id, so they can be aborted from outside
+    active_runs: Mutex<HashMap<String, ActiveRun>>,
+}
+
+/// A run that can be aborted when its connection goes away or it outlives
+/// `app.stale_run_max_age_secs`
+struct ActiveRun {
+    session_id: String,
+    /// WebSocket connection that started the run
+    owner: Option<String>,
+    started_at: Instant,
+    /// Set to the abort reason; the run stops at its next await point
+    abort: watch::Sender<Option<String>>,
+}
+
+/// Removes the run from `active_runs` however the run ends
+struct RegisteredRun<'a> {
+    runs: &'a Mutex<HashMap<String, ActiveRun>>,
+    run_id: String,
+}
+
+impl Drop for RegisteredRun<'_> {
+    fn drop(&mut self) {
+        self.runs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.run_id);
+    }
+}
+
+/// Resolves with the abort reason once one is set
+async fn aborted(abort: &mut watch::Receiver<Option<String>>) -> String {
+    loop {
+        if let Some(reason) = abort.borrow_and_update().clone() {
+            return reason;
+        }
+        if abort.changed().await.is_err() {
+            return std::future::pending().await;
+        }
+    }
+}
+
+/// Decrements the in-flight counter even when a run is cancelled mid-await
+struct InFlightRun<'a>(&'a AtomicUsize);
+
+impl<'a> InFlightRun<'a> {
+    fn start(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self(counter)
+    }
+}
+
+impl Drop for InFlightRun<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 impl GraphRuntime {
@@ -57,11 +126,86 @@ impl GraphRuntime {
             graph: DiGraph::new(),
             node_indices: HashMap::new(),
             entry_node_id: String::new(),
+            entry_points: HashMap::new(),
             max_steps: 50,
             execution_timeout: None,
+            runs_in_flight: AtomicUsize::new(0),
+            profiler: GraphProfiler::new(),
+            active_runs: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Number of graph executions in progress (used to defer app updates)
+    pub fn runs_in_flight(&self) -> usize {
+        self.runs_in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Profiles of recent runs
+    pub fn profiler(&self) -> &GraphProfiler {
+        &self.profiler
+    }
+
+    /// Abort every run started by `connection_id`. Called when the WebSocket
+    /// disconnects mid-run so the run does not keep its queue slot and state alive.
+    pub fn abort_connection_runs(&self, connection_id: &str) -> usize {
+        self.abort_runs_where("connection closed", |run| {
+            run.owner.as_deref() == Some(connection_id)
+        })
+    }
+
+    /// Abort runs that have been executing for longer than `max_age`
+    pub fn abort_stale_runs(&self, max_age: Duration) -> usize {
+        let reason = format!("run exceeded the maximum age of {}s", max_age.as_secs());
+        self.abort_runs_where(&reason, |run| run.started_at.elapsed() >= max_age)
+    }
+
+    fn abort_runs_where(&self, reason: &str, matches: impl Fn(&ActiveRun) -> bool) -> usize {
+        let runs = self.active_runs.lock().unwrap_or_else(|e| e.into_inner());
+        let mut count = 0;
+        for (run_id, run) in runs.iter().filter(|(_, run)| matches(run)) {
+            if run.abort.borrow().is_some() {
+                continue;
+            }
+            tracing::warn!(
+                run_id = %run_id,
+                session_id = %run.session_id,
+                "Aborting graph run: {}",
+                reason
+            );
+            run.abort.send_replace(Some(reason.to_string()));
+            count += 1;
+        }
+        count
+    }
+
+    fn register_run(
+        &self,
+        run_id: &str,
+        session_id: &str,
+        owner: Option<&str>,
+    ) -> (RegisteredRun<'_>, watch::Receiver<Option<String>>) {
+        let (abort, receiver) = watch::channel(None);
+        self.active_runs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                run_id.to_string(),
+                ActiveRun {
+                    session_id: session_id.to_string(),
+                    owner: owner.map(str::to_string),
+                    started_at: Instant::now(),
+                    abort,
+                },
+            );
+        (
+            RegisteredRun {
+                runs: &self.active_runs,
+                run_id: run_id.to_string(),
+            },
+            receiver,
+        )
+    }
+
     /// Set maximum execution steps
     pub fn with_max_steps(mut self, max_steps: usize) -> Self {
         self.max_steps = max_steps;
@@ -80,6 +224,20 @@ impl GraphRuntime {
         self
     }
 
+    /// Start runs of `mode` at `node_id` instead of the default entry
+    pub fn with_entry_point(mut self, mode: impl Into<String>, node_id: impl Into<String>) -> Self {
+        self.entry_points.insert(mode.into(), node_id.into());
+        self
+    }
+
+    /// Entry node used for a run in `mode`
+    pub fn entry_for(&self, mode: &str) -> &str {
+        self.entry_points
+            .get(mode)
+            .map(String::as_str)
+            .unwrap_or(&self.entry_node_id)
+    }
+
     /// Add a node to the graph
     pub fn add_node(&mut self, node: Box<dyn Node>) -> NodeIndex {
         let id = node.id().to_string();
@@ -143,19 +301,91 @@ impl GraphRuntime {
         ctx: &mut NodeContext<'_>,
         timeout_override: Option<std::time::Duration>,
     ) -> Result<(), GraphError> {
+        self.run_owned(state, ctx, timeout_override, None).await
+    }
+
+    /// Execute the graph on behalf of a WebSocket connection, so the run is
+    /// aborted by `abort_connection_runs` if that connection disappears
+    pub async fn run_for_connection(
+        &self,
+        state: &mut AgentState,
+        ctx: &mut NodeContext<'_>,
+        timeout_override: Option<std::time::Duration>,
+        connection_id: &str,
+    ) -> Result<(), GraphError> {
+        self.run_owned(state, ctx, timeout_override, Some(connection_id))
+            .await
+    }
+
+    async fn run_owned(
+        &self,
+        state: &mut AgentState,
+        ctx: &mut NodeContext<'_>,
+        timeout_override: Option<std::time::Duration>,
+        owner: Option<&str>,
+    ) -> Result<(), GraphError> {
+        let _in_flight = InFlightRun::start(&self.runs_in_flight);
         let timeout = timeout_override.or(self.execution_timeout);
 
-        if let Some(timeout_duration) = timeout {
-            match tokio::time::timeout(timeout_duration, self.run_steps(state, ctx)).await {
-                Ok(result) => result,
-                Err(_) => Err(GraphError::new(
-                    "runtime",
-                    format!("Graph execution timed out after {:?}", timeout_duration),
-                )),
+        let run_id = uuid::Uuid::new_v4().to_string();
+        state.run_id = Some(run_id.clone());
+        let (_registered, mut abort) = self.register_run(&run_id, &state.session_id, owner);
+        let mut run = self.profiler.start(
+            run_id,
+            state.session_id.clone(),
+            state.mode.as_str().to_string(),
+        );
+        // 最初のトークンが遅ければ診断を採る。チャット要求のトレースがある実行だけ
+        let latency_trace = latency::current();
+        let watchdog = latency_trace
+            .as_ref()
+            .zip(latency::first_token_watchdog(ctx.config));
+        let app_state = ctx.app_state;
+
+        let execution = async {
+            if let Some(timeout_duration) = timeout {
+                match tokio::time::timeout(
+                    timeout_duration,
+                    self.run_steps(state, ctx, run.trace()),
+                )
+                .await
+                {
+                    Ok(result) => result,
+                    Err(_) => Err(GraphError::new(
+                        "runtime",
+                        format!("Graph execution timed out after {:?}", timeout_duration),
+                    )),
+                }
+            } else {
+                self.run_steps(state, ctx, run.trace()).await
             }
-        } else {
-            self.run_steps(state, ctx).await
+        };
+        let supervised = async {
+            match watchdog {
+                Some((trace, threshold)) => {
+                    tokio::pin!(execution);
+                    tokio::select! {
+                        result = &mut execution => result,
+                        _ = watch_first_token(app_state, trace, threshold) => execution.await,
+                    }
+                }
+                None => execution.await,
+            }
+        };
+        let result = tokio::select! {
+            result = supervised => result,
+            reason = aborted(&mut abort) => Err(GraphError::new(
+                "runtime",
+                format!("Graph run aborted: {}", reason),
+            )),
+        };
+        run.attach_diagnostics(latency_trace.and_then(|trace| trace.diagnostics().cloned()));
+
+        match &result {
+            Ok(()) => run.finish(RunOutcome::Completed, None),
+            Err(err) => run.finish(RunOutcome::Failed, Some(err.to_string())),
         }
+        result
     }
 
     /// Internal execution loop
@@ -163,15 +393,17 @@ impl GraphRuntime {
         &self,
         state: &mut AgentState,
         ctx: &mut NodeContext<'_>,
+        trace: &mut RunTrace,
     ) -> Result<(), GraphError> {
-        if self.entry_node_id.is_empty() {
+        let entry_node_id = self.entry_for(&state.requested_mode);
+        if entry_node_id.is_empty() {
             return Err(GraphError::new("runtime", "No entry node set"));
         }
 
-        let mut current_idx = *self.node_indices.get(&self.entry_node_id).ok_or_else(|| {
+        let mut current_idx = *self.node_indices.get(entry_node_id).ok_or_else(|| {
             GraphError::new(
                 "runtime",
-                format!("Entry node not found: {}", self.entry_node_id),
+                format!("Entry node not found: {}", entry_node_id),
             )
         })?;
 
@@ -198,7 +430,10 @@ impl GraphRuntime {
             tracing::debug!("Executing node: {} (step {})", node_id, step);
 
             let start = std::time::Instant::now();
-            let output = match node.execute(state, ctx).await {
+            let counters = trace.enter(node_id, step);
+            let result = profiler::scoped(counters, node.execute(state, ctx)).await;
+            trace.exit();
+            let output = match result {
                 Ok(o) => o,
                 Err(mut e) => {
                     // Attach the execution trace collected so far, then propagate.
@@ -451,6 +686,54 @@ mod tests {
         AgentState::new("test-session".to_string(), "hello".to_string(), Mode::Chat)
     }
 
+    // =======================================================================
+    // Active run registry tests
+    // =======================================================================
+
+    #[tokio::test]
+    async fn abort_connection_runs_only_signals_that_connection() {
+        let runtime = GraphRuntime::new();
+        let (_a, mut abort_a) = runtime.register_run("run-a", "s1", Some("conn-1"));
+        let (_b, abort_b) = runtime.register_run("run-b", "s2", Some("conn-2"));
+        let (_c, abort_c) = runtime.register_run("run-c", "s3", None);
+
+        assert_eq!(runtime.abort_connection_runs("conn-1"), 1);
+        assert_eq!(aborted(&mut abort_a).await, "connection closed");
+        assert!(abort_b.borrow().is_none());
+        assert!(abort_c.borrow().is_none());
+        // Already signalled runs are not counted twice
+        assert_eq!(runtime.abort_connection_runs("conn-1"), 0);
+    }
+
+    #[test]
+    fn abort_stale_runs_uses_run_age() {
+        let runtime = GraphRuntime::new();
+        let (_old, abort_old) = runtime.register_run("old", "s1", Some("conn-1"));
+        let (_new, abort_new) = runtime.register_run("new", "s2", Some("conn-1"));
+        runtime
+            .active_runs
+            .lock()
+            .unwrap()
+            .get_mut("old")
+            .unwrap()
+            .started_at -= Duration::from_secs(120);
+
+        assert_eq!(runtime.abort_stale_runs(Duration::from_secs(60)), 1);
+        assert!(abort_old.borrow().is_some());
+        assert!(abort_new.borrow().is_none());
+    }
+
+    #[test]
+    fn finished_runs_leave_the_registry() {
+        let runtime = GraphRuntime::new();
+        {
+            let _registered = runtime.register_run("run-a", "s1", Some("conn-1"));
+            assert_eq!(runtime.active_runs.lock().unwrap().len(), 1);
+        }
+        assert!(runtime.active_runs.lock().unwrap().is_empty());
+        assert_eq!(runtime.abort_connection_runs("conn-1"), 0);
+    }
+
     // =======================================================================
     // EdgeCondition tests
     // =======================================================================
@@ -625,6 +908,15 @@ mod tests {
         assert_eq!(runtime.entry_node_id, "start");
     }
 
+    #[test]
+    fn with_entry_point_overrides_entry_for_that_mode() {
+        let runtime = GraphRuntime::new()
+            .with_entry("router")
+            .with_entry_point("translate", "chat");
+        assert_eq!(runtime.entry_for("translate"), "chat");
+        assert_eq!(runtime.entry_for("chat"), "router");
+    }
+
     #[test]
     fn with_timeout_sets_execution_timeout() {
         let timeout = std::time::Duration::from_secs(5);
@@ -0,0 +1,455 @@
+//! ストリーミング出力の無害化。
+//!
+//! モデル出力（取り込んだ Web ページ由来の文字列を含む）を `chunk` として送る前に通し、
+//! 危険な HTML タグ、イベント属性、`javascript:` などのリンク先を無効化する。
+//! チャンク境界をまたぐタグ・リンク・インラインコードは閉じるまで保留する。
+//! コードの中身はそのまま通し、生成の終わりに閉じていないフェンスは閉じる。
+
+use serde_json::{json, Value};
+
+/// 開始タグ・終了タグとも `&lt;` にして本文として見せるタグ。
+const DANGEROUS_TAGS: &[&str] = &[
+    "script",
+    "style",
+    "iframe",
+    "frame",
+    "frameset",
+    "object",
+    "embed",
+    "applet",
+    "link",
+    "meta",
+    "base",
+    "form",
+    "input",
+    "button",
+    "textarea",
+    "select",
+    "svg",
+    "math",
+    "template",
+    "noscript",
+    "xmp",
+    "plaintext",
+    "portal",
+];
+const DANGEROUS_SCHEMES: &[&str] = &["javascript:", "vbscript:", "livescript:", "data:text/html"];
+/// 閉じを待って保留する上限（バイト）。超えたら閉じないものとして扱う
+const MAX_PENDING: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fence {
+    marker: char,
+    len: usize,
+}
+
+/// `<` から始まる部分の判定結果。
+enum Tag {
+    /// 続きが来るまで決められない
+    Hold,
+    /// 先頭からこのバイト数をそのまま出す
+    Keep(usize),
+    /// `<` を `&lt;` にする
+    Escape,
+}
+
+/// Markdown テキストのストリーム無害化。
+#[derive(Debug, Default)]
+pub struct MarkdownSanitizer {
+    /// まだ出していない末尾
+    pending: String,
+    fence: Option<Fence>,
+    /// 行頭（フェンス判定の前）でなければ `true`
+    mid_line: bool,
+}
+
+impl MarkdownSanitizer {
+    /// チャンクを受け取り、出してよい部分を返す。保留した分は次回以降に出る。
+    pub fn push(&mut self, chunk: &str) -> String {
+        self.pending.push_str(chunk);
+        self.drain(false)
+    }
+
+    /// ストリームの終わり。保留分を出し、開いたままのフェンスを閉じる。
+    pub fn finish(&mut self) -> String {
+        let mut out = self.drain(true);
+        if let Some(fence) = self.fence.take() {
+            if self.mid_line {
+                out.push('\n');
+            }
+            out.extend(std::iter::repeat_n(fence.marker, fence.len));
+            out.push('\n');
+        }
+        self.mid_line = false;
+        out
+    }
+
+    fn drain(&mut self, finishing: bool) -> String {
+        let text = std::mem::take(&mut self.pending);
+        let mut out = String::with_capacity(text.len());
+        let mut i = 0;
+        while i < text.len() {
+            let rest = &text[i..];
+            let can_hold = !finishing && rest.len() < MAX_PENDING;
+
+            if !self.mid_line {
+                let indent = rest.len() - rest.trim_start_matches(' ').len();
+                let after = &rest[indent..];
+                if after.is_empty() && can_hold {
+                    break;
+                }
+                if let Some(marker @ ('`' | '~')) = after.chars().next().filter(|_| indent <= 3) {
+                    let run = after.len() - after.trim_start_matches(marker).len();
+                    if run == after.len() && can_hold {
+                        // フェンス記号がまだ続くかもしれない
+                        break;
+                    }
+                    if run >= 3 {
+                        match self.fence {
+                            None => self.fence = Some(Fence { marker, len: run }),
+                            Some(open) if open.marker == marker && run >= open.len => {
+                                self.fence = None;
+                            }
+                            Some(_) => {}
+                        }
+                        out.push_str(&rest[..indent + run]);
+                        i += indent + run;
+                        self.mid_line = true;
+                        continue;
+                    }
+                }
+                self.mid_line = true;
+            }
+
+            if self.fence.is_some() {
+                let end = rest.find('\n').map(|pos| pos + 1).unwrap_or(rest.len());
+                out.push_str(&rest[..end]);
+                i += end;
+                self.mid_line = !rest[..end].ends_with('\n');
+                continue;
+            }
+
+            let Some(c) = rest.chars().next() else { break };
+            match c {
+                '\n' => {
+                    out.push('\n');
+                    i += 1;
+                    self.mid_line = false;
+                }
+                '`' => {
+                    let run = rest.len() - rest.trim_start_matches('`').len();
+                    let line_end = rest.find('\n').unwrap_or(rest.len());
+                    match find_backtick_run(&rest[run..line_end], run) {
+                        Some(close) => {
+                            // コードスパンの中は HTML として解釈されない
+                            let end = run + close + run;
+                            out.push_str(&rest[..end]);
+                            i += end;
+                        }
+                        None if line_end == rest.len() && can_hold => break,
+                        None => {
+                            out.push_str(&rest[..run]);
+                            i += run;
+                        }
+                    }
+                }
+                '<' => match scan_tag(rest, can_hold) {
+                    Tag::Hold => break,
+                    Tag::Keep(len) => {
+                        out.push_str(&rest[..len]);
+                        i += len;
+                    }
+                    Tag::Escape => {
+                        out.push_str("&lt;");
+                        i += 1;
+                    }
+                },
+                ']' if rest.len() == 1 && can_hold => break,
+                ']' if rest.starts_with("](") => {
+                    let target = &rest[2..];
+                    let end = link_target_end(target);
+                    if end.is_none() && can_hold {
+                        break;
+                    }
+                    let end = end.unwrap_or(target.len());
+                    out.push_str("](");
+                    if is_dangerous_url(&target[..end]) {
+                        out.push('#');
+                    } else {
+                        out.push_str(&target[..end]);
+                    }
+                    i += 2 + end;
+                }
+                _ => {
+                    out.push(c);
+                    i += c.len_utf8();
+                }
+            }
+        }
+        self.pending = text[i..].to_string();
+        out
+    }
+}
+
+/// リンク先の終わり（対応する `)` か改行）の位置。
+fn link_target_end(target: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (pos, c) in target.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(pos),
+            ')' => depth -= 1,
+            '\n' => return Some(pos),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// ちょうど `len` 個のバッククォートの並びの位置。
+fn find_backtick_run(text: &str, len: usize) -> Option<usize> {
+    let mut search = 0;
+    while let Some(offset) = text[search..].find('`') {
+        let start = search + offset;
+        let run = text[start..].len() - text[start..].trim_start_matches('`').len();
+        if run == len {
+            return Some(start);
+        }
+        search = start + run;
+    }
+    None
+}
+
+fn scan_tag(rest: &str, can_hold: bool) -> Tag {
+    let body = &rest[1..];
+    let closing = body.starts_with('/');
+    let name_part = if closing { &body[1..] } else { body };
+    let name_len = name_part
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+        .unwrap_or(name_part.len());
+    if name_len == name_part.len() && can_hold {
+        return Tag::Hold;
+    }
+    let name = name_part[..name_len].to_ascii_lowercase();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        // "a < b" や "<!-- -->" は HTML タグではない
+        return Tag::Keep(1);
+    }
+    if DANGEROUS_TAGS.contains(&name.as_str()) {
+        return Tag::Escape;
+    }
+    // タグは 1 行に収まるものだけ通す。閉じずに改行したら本文として見せる
+    let line_end = rest.find('\n').unwrap_or(rest.len());
+    match rest[..line_end].find('>') {
+        Some(end) => {
+            let inner = &rest[1..end];
+            let autolink = !closing && name_part[name_len..].starts_with(':');
+            let dangerous = if autolink {
+                is_dangerous_url(inner)
+            } else {
+                has_dangerous_attribute(inner)
+            };
+            if dangerous {
+                Tag::Escape
+            } else {
+                Tag::Keep(end + 1)
+            }
+        }
+        None if line_end == rest.len() && can_hold => Tag::Hold,
+        None => Tag::Escape,
+    }
+}
+
+/// `on*=` のイベント属性か、危険なスキームを含む属性値があるか。
+fn has_dangerous_attribute(tag: &str) -> bool {
+    let lower = tag.to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let event_handler = lower.match_indices("on").any(|(pos, _)| {
+        let boundary = pos > 0 && matches!(bytes[pos - 1], b' ' | b'\t' | b'/' | b'"' | b'\'');
+        let name_end = lower[pos + 2..]
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .map(|offset| pos + 2 + offset)
+            .unwrap_or(lower.len());
+        boundary && name_end > pos + 2 && lower[name_end..].trim_start().starts_with('=')
+    });
+    event_handler
+        || DANGEROUS_SCHEMES
+            .iter()
+            .any(|scheme| normalize_url(&lower).contains(scheme))
+}
+
+fn is_dangerous_url(target: &str) -> bool {
+    let url = normalize_url(target.trim().trim_start_matches('<'));
+    DANGEROUS_SCHEMES
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+}
+
+/// 空白・制御文字を除き、文字参照（`&#106;` / `&#x6a;` / `&colon;`）を戻して小文字にする。
+fn normalize_url(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '&' {
+            if let Some((decoded, len)) = decode_entity(rest) {
+                if !decoded.is_whitespace() && !decoded.is_control() {
+                    out.push(decoded.to_ascii_lowercase());
+                }
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        if !c.is_whitespace() && !c.is_control() {
+            out.push(c.to_ascii_lowercase());
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+fn decode_entity(text: &str) -> Option<(char, usize)> {
+    let end = text[..text.len().min(12)].find(';')?;
+    let entity = &text[1..end];
+    let decoded = match entity.strip_prefix('#') {
+        Some(number) => {
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)?
+        }
+        None => match entity.to_ascii_lowercase().as_str() {
+            "colon" => ':',
+            "tab" => '\t',
+            "newline" => '\n',
+            _ => return None,
+        },
+    };
+    Some((decoded, end + 1))
+}
+
+/// `chunk` イベント単位の無害化。保留分を最後に出すとき、直前の `chunk` の付帯情報を引き継ぐ。
+#[derive(Debug, Default)]
+pub struct ChunkSanitizer {
+    markdown: MarkdownSanitizer,
+    /// 直前の `chunk` から `message` を除いたもの
+    last_chunk: Option<Value>,
+}
+
+impl ChunkSanitizer {
+    /// 無害化した `chunk` を返す。全体を保留したときは `None`。
+    pub fn push(&mut self, mut payload: Value) -> Option<Value> {
+        let message = payload
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if message.is_empty() {
+            return Some(payload);
+        }
+        let safe = self.markdown.push(message);
+        let mut template = payload.clone();
+        if let Some(obj) = template.as_object_mut() {
+            obj.remove("message");
+        }
+        self.last_chunk = Some(template);
+        if safe.is_empty() {
+            return None;
+        }
+        payload["message"] = json!(safe);
+        Some(payload)
+    }
+
+    /// ストリームの終わりに送る残りの `chunk`。
+    pub fn finish(&mut self) -> Option<Value> {
+        let rest = self.markdown.finish();
+        let template = self.last_chunk.take();
+        if rest.is_empty() {
+            return None;
+        }
+        let mut payload = template.unwrap_or_else(|| json!({ "type": "chunk" }));
+        payload["message"] = json!(rest);
+        Some(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sanitize(chunks: &[&str]) -> String {
+        let mut sanitizer = MarkdownSanitizer::default();
+        let mut out = String::new();
+        for chunk in chunks {
+            out.push_str(&sanitizer.push(chunk));
+        }
+        out.push_str(&sanitizer.finish());
+        out
+    }
+
+    #[test]
+    fn dangerous_markup_is_neutralized_across_chunks() {
+        assert_eq!(
+            sanitize(&["Hi <scr", "ipt>alert(1)</script> there"]),
+            "Hi &lt;script>alert(1)&lt;/script> there"
+        );
+        assert_eq!(
+            sanitize(&["<img src=x one", "rror=alert(1)> and <b>bold</b>"]),
+            "&lt;img src=x onerror=alert(1)> and <b>bold</b>"
+        );
+        assert_eq!(
+            sanitize(&[
+                "[click](java",
+                "script:alert(1)) or [ok](https://example.com)"
+            ]),
+            "[click](#) or [ok](https://example.com)"
+        );
+        assert_eq!(
+            sanitize(&["[x](&#106;avascript:alert(1)) <javascript:alert(1)>"]),
+            "[x](#) &lt;javascript:alert(1)>"
+        );
+        assert_eq!(
+            sanitize(&["1 < 2 and a <b", "r> break, <!-- note -->"]),
+            "1 < 2 and a <br> break, <!-- note -->"
+        );
+    }
+
+    #[test]
+    fn code_is_kept_verbatim_and_open_fences_are_closed() {
+        assert_eq!(
+            sanitize(&[
+                "Use `<scr",
+                "ipt>` tags:\n``",
+                "`html\n<script>x()</script>\n```\ndone"
+            ]),
+            "Use `<script>` tags:\n```html\n<script>x()</script>\n```\ndone"
+        );
+        assert_eq!(
+            sanitize(&["~~~\n<iframe>\n", "still code"]),
+            "~~~\n<iframe>\nstill code\n~~~\n"
+        );
+        assert_eq!(
+            sanitize(&["```rust\nfn main() {}\n"]),
+            "```rust\nfn main() {}\n```\n"
+        );
+        // 閉じないバッククォートはコードではない
+        assert_eq!(sanitize(&["a ` <style>\nb"]), "a ` &lt;style>\nb");
+    }
+
+    #[test]
+    fn held_tail_keeps_chunk_metadata() {
+        let mut sanitizer = ChunkSanitizer::default();
+        let first = sanitizer
+            .push(json!({"type": "chunk", "message": "see <", "mode": "chat"}))
+            .unwrap();
+        assert_eq!(first["message"], "see ");
+        assert!(sanitizer
+            .push(json!({"type": "chunk", "message": "em", "mode": "chat"}))
+            .is_none());
+        let rest = sanitizer.finish().unwrap();
+        assert_eq!(
+            rest,
+            json!({"type": "chunk", "message": "&lt;em", "mode": "chat"})
+        );
+        assert!(sanitizer.finish().is_none());
+    }
+}
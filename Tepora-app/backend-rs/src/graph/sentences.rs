@@ -0,0 +1,255 @@
+//! 読み上げ用の文境界。
+//!
+//! `tts.enabled` のとき、ストリーミング中の `chunk` を文ごとに区切り、
+//! `sentence` イベントとして送る。フロントエンドは読み上げ中の文を強調表示できる。
+//! オフセットはアシスタントメッセージ全体に対する UTF-16 の位置（JS の文字列添字）。
+//! コードブロックは 1 つの区切りにまとめ、`code: true` を付ける（読み上げない想定）。
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::core::config::schema::TeporaConfig;
+
+const CLOSERS: &[char] = &[
+    '"', '\'', ')', ']', '」', '』', '）', '】', '”', '’', '》', '〉',
+];
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "no", "e.g", "i.e", "fig",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SentenceMark {
+    pub index: usize,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    pub code: bool,
+}
+
+/// チャンクを受け取り、確定した文から順に返す。
+#[derive(Debug, Default)]
+pub struct SentenceSegmenter {
+    /// まだ区切っていない本文
+    buffer: String,
+    /// `buffer` 先頭の UTF-16 オフセット
+    base: usize,
+    /// 走査済みの位置（バイト）
+    scanned: usize,
+    /// 現在の行の開始位置（バイト）。行頭が `buffer` にあるときだけ `Some`
+    line_start: Option<usize>,
+    in_code: bool,
+    next_index: usize,
+}
+
+impl SentenceSegmenter {
+    pub fn new() -> Self {
+        Self {
+            line_start: Some(0),
+            ..Self::default()
+        }
+    }
+
+    /// 読み上げが有効なときだけ作る。
+    pub fn for_config(config: &Value) -> Option<Self> {
+        TeporaConfig::from_value_or_default(config)
+            .tts
+            .enabled
+            .then(Self::new)
+    }
+
+    pub fn push(&mut self, chunk: &str) -> Vec<SentenceMark> {
+        self.buffer.push_str(chunk);
+        let mut marks = Vec::new();
+        self.scan(&mut marks, false);
+        marks
+    }
+
+    /// ストリームの終わり。残りを最後の文として返す。
+    pub fn finish(&mut self) -> Vec<SentenceMark> {
+        let mut marks = Vec::new();
+        self.scan(&mut marks, true);
+        let code = self.in_code;
+        self.emit(&mut marks, self.buffer.len(), code);
+        self.in_code = false;
+        marks
+    }
+
+    fn scan(&mut self, marks: &mut Vec<SentenceMark>, finishing: bool) {
+        while let Some(c) = self.buffer[self.scanned..].chars().next() {
+            let next = self.scanned + c.len_utf8();
+            if c == '\n' {
+                let fence = self.line_start.is_some_and(|start| {
+                    let line = self.buffer[start..self.scanned].trim_start();
+                    line.starts_with("```") || line.starts_with("~~~")
+                });
+                if fence && !self.in_code {
+                    // フェンスの前までを閉じ、フェンス行からをコードとして持つ
+                    let start = self.line_start.unwrap_or(0);
+                    self.emit(marks, start, false);
+                    self.in_code = true;
+                    self.scanned = next - start;
+                    self.line_start = Some(self.scanned);
+                } else if fence || !self.in_code {
+                    let code = self.in_code;
+                    self.in_code = false;
+                    self.emit(marks, next, code);
+                } else {
+                    self.scanned = next;
+                    self.line_start = Some(next);
+                }
+                continue;
+            }
+
+            if self.in_code || !is_terminator(c) {
+                self.scanned = next;
+                continue;
+            }
+            let end = next
+                + self.buffer[next..]
+                    .chars()
+                    .take_while(|c| CLOSERS.contains(c))
+                    .map(char::len_utf8)
+                    .sum::<usize>();
+            let following = self.buffer[end..].chars().next();
+            if following.is_none() && !finishing {
+                // 続きが来るまで決められない（"3." の後に "14" が来るかもしれない）
+                break;
+            }
+            let boundary = following.is_none_or(|f| is_cjk_terminator(c) || f.is_whitespace())
+                && !(c == '.' && self.is_abbreviation());
+            if boundary {
+                self.emit(marks, end, false);
+            } else {
+                self.scanned = end;
+            }
+        }
+    }
+
+    /// `.` の直前が略語・イニシャル・行頭の番号なら文末ではない。
+    fn is_abbreviation(&self) -> bool {
+        let before = &self.buffer[..self.scanned];
+        let word_start = before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace() || *c == '(')
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(0);
+        let word = &before[word_start..];
+        if word.chars().count() == 1 && word.chars().all(char::is_uppercase) {
+            return true;
+        }
+        if ABBREVIATIONS.contains(&word.to_lowercase().as_str()) {
+            return true;
+        }
+        !word.is_empty()
+            && word.chars().all(|c| c.is_ascii_digit())
+            && self
+                .line_start
+                .is_some_and(|start| before[start..word_start].trim().is_empty())
+    }
+
+    /// `buffer[..end]` を 1 つの区切りとして出し、`buffer` から外す。
+    fn emit(&mut self, marks: &mut Vec<SentenceMark>, end: usize, code: bool) {
+        let segment = &self.buffer[..end];
+        let text = segment.trim();
+        if !text.is_empty() {
+            let lead = segment.len() - segment.trim_start().len();
+            let start = self.base + utf16_len(&segment[..lead]);
+            marks.push(SentenceMark {
+                index: self.next_index,
+                start,
+                end: start + utf16_len(text),
+                text: text.to_string(),
+                code,
+            });
+            self.next_index += 1;
+        }
+        self.base += utf16_len(segment);
+        let at_line_start =
+            segment.ends_with('\n') || (segment.is_empty() && self.line_start == Some(0));
+        self.buffer.drain(..end);
+        self.scanned = 0;
+        self.line_start = at_line_start.then_some(0);
+    }
+}
+
+fn is_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?') || is_cjk_terminator(c)
+}
+
+fn is_cjk_terminator(c: char) -> bool {
+    matches!(c, '。' | '！' | '？' | '．')
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(chunks: &[&str]) -> Vec<SentenceMark> {
+        let mut segmenter = SentenceSegmenter::new();
+        let mut marks = Vec::new();
+        for chunk in chunks {
+            marks.extend(segmenter.push(chunk));
+        }
+        marks.extend(segmenter.finish());
+        marks
+    }
+
+    #[test]
+    fn sentences_split_across_chunks_with_utf16_offsets() {
+        let chunks = [
+            "Hello wor",
+            "ld. Pi is 3",
+            ".14, e.g. roughly",
+            "! 次は日本語。",
+            "「引用」です😀？ok",
+        ];
+        let full = chunks.concat();
+        let marks = segment(&chunks);
+        let texts = marks.iter().map(|m| m.text.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            vec![
+                "Hello world.",
+                "Pi is 3.14, e.g. roughly!",
+                "次は日本語。",
+                "「引用」です😀？",
+                "ok"
+            ]
+        );
+        let utf16 = full.encode_utf16().collect::<Vec<_>>();
+        for mark in &marks {
+            assert_eq!(
+                String::from_utf16(&utf16[mark.start..mark.end]).unwrap(),
+                mark.text
+            );
+        }
+        assert_eq!(marks.last().unwrap().index, 4);
+    }
+
+    #[test]
+    fn code_blocks_and_list_numbers_stay_whole() {
+        let marks = segment(&[
+            "Steps:\n1. Run it. Then wait.\n```sh\necho a. b\n",
+            "ls\n```\nDone",
+        ]);
+        let view = marks
+            .iter()
+            .map(|m| (m.text.as_str(), m.code))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            view,
+            vec![
+                ("Steps:", false),
+                ("1. Run it.", false),
+                ("Then wait.", false),
+                ("```sh\necho a. b\nls\n```", true),
+                ("Done", false),
+            ]
+        );
+    }
+}
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
+use crate::agent::plan::AgentPlan;
 use crate::context::pipeline_context::PipelineContext;
 use crate::llm::{ChatMessage, ImageData};
 use crate::search::{SearchEvidenceState, SearchMode};
@@ -39,6 +40,8 @@ pub enum Mode {
     Chat,
     Search,
     SearchAgentic,
+    /// 複数ラウンドの深掘りリサーチ
+    Research,
     Agent,
 }
 
@@ -47,6 +50,7 @@ impl Mode {
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "search_agentic" => Mode::SearchAgentic,
+            "research" | "deep_research" => Mode::Research,
             "search" => Mode::Search,
             "agent" => Mode::Agent,
             _ => Mode::Chat,
@@ -58,6 +62,7 @@ impl Mode {
             Mode::Chat => "chat",
             Mode::Search => "search",
             Mode::SearchAgentic => "search_agentic",
+            Mode::Research => "research",
             Mode::Agent => "agent",
         }
     }
@@ -98,6 +103,34 @@ impl AgentMode {
     }
 }
 
+/// SynthesizerNode の書き方
+///
+/// - `Single`: 一度に最終回答を書く
+/// - `Outline`: 先に見出しを決め、節ごとに資料を引き直して書き足す
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SynthesisMode {
+    #[default]
+    Single,
+    Outline,
+}
+
+impl SynthesisMode {
+    pub fn from_optional_str(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim().to_lowercase()).as_deref() {
+            Some("outline" | "draft") => SynthesisMode::Outline,
+            _ => SynthesisMode::Single,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SynthesisMode::Single => "single",
+            SynthesisMode::Outline => "outline",
+        }
+    }
+}
+
 /// Supervisor routing decisions
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -119,6 +152,9 @@ pub struct Artifact {
 pub struct SharedContext {
     /// Current execution plan from Planner
     pub current_plan: Option<String>,
+    /// Structured form of `current_plan`, after any user edits
+    #[serde(default)]
+    pub plan: Option<AgentPlan>,
     /// Artifacts (code snippets, search results, etc.)
     pub artifacts: Vec<Artifact>,
     /// Scratchpad notes for agents
@@ -132,10 +168,15 @@ pub struct SharedContext {
 pub struct AgentState {
     // Session identifier
     pub session_id: String,
+    /// Profiler run id, assigned by `GraphRuntime::run`
+    pub run_id: Option<String>,
 
     // Core input and history
     pub input: String,
     pub mode: Mode,
+    /// 要求されたモード名そのもの。`Mode` に無いカスタムモード（`translate` など）も残し、
+    /// グラフの入口ノードを選ぶのに使う
+    pub requested_mode: String,
     pub chat_history: Vec<ChatMessage>,
 
     // Hierarchical agent routing
@@ -153,6 +194,8 @@ pub struct AgentState {
     // Agent ReAct loop state
     pub agent_scratchpad: Vec<ChatMessage>,
     pub agent_outcome: Option<String>,
+    /// Full tool outputs keyed by result id, for `expand_tool_result`
+    pub tool_results: HashMap<String, String>,
 
     // Thinking mode (CoT)
     pub thinking_budget: u8,
@@ -167,6 +210,7 @@ pub struct AgentState {
     /// 画像添付ファイル（マルチモーダルLLM送信用）
     pub image_attachments: Vec<ImageAttachment>,
     pub skip_web_search: bool,
+    pub synthesis_mode: SynthesisMode,
 
     // Final output
     pub output: Option<String>,
@@ -177,8 +221,10 @@ impl AgentState {
     pub fn new(session_id: String, input: String, mode: Mode) -> Self {
         Self {
             session_id,
+            run_id: None,
             input,
             mode,
+            requested_mode: mode.as_str().to_string(),
             chat_history: Vec::new(),
             agent_id: None,
             agent_mode: AgentMode::Low,
@@ -188,6 +234,7 @@ impl AgentState {
             pipeline_context: None,
             agent_scratchpad: Vec::new(),
             agent_outcome: None,
+            tool_results: HashMap::new(),
             thinking_budget: 0,
             thought_process: None,
             search_mode: SearchMode::Quick,
@@ -197,11 +244,21 @@ impl AgentState {
             search_attachments: Vec::new(),
             image_attachments: Vec::new(),
             skip_web_search: false,
+            synthesis_mode: SynthesisMode::Single,
             output: None,
             error: None,
         }
     }
 
+    /// Query that seeds web searches: the user's message, translated into the
+    /// persona's search language when the context pipeline provided one.
+    pub fn search_query(&self) -> String {
+        self.pipeline_context
+            .as_ref()
+            .map(|ctx| ctx.search_query().to_string())
+            .unwrap_or_else(|| self.input.clone())
+    }
+
     /// Create state from WebSocket message data
     #[allow(clippy::too_many_arguments)]
     pub fn from_ws_message(
@@ -246,8 +303,10 @@ impl AgentState {
 
         Self {
             session_id,
+            run_id: None,
             input: message.to_string(),
             mode: Mode::from_str(mode),
+            requested_mode: mode.trim().to_lowercase(),
             chat_history,
             agent_id: agent_id.map(String::from),
             agent_mode: AgentMode::from_str(agent_mode),
@@ -257,6 +316,7 @@ impl AgentState {
             pipeline_context: None,
             agent_scratchpad: Vec::new(),
             agent_outcome: None,
+            tool_results: HashMap::new(),
             thinking_budget,
             thought_process: None,
             search_mode: SearchMode::from_optional_str(search_mode),
@@ -266,6 +326,7 @@ impl AgentState {
             search_attachments: text_attachments,
             image_attachments,
             skip_web_search,
+            synthesis_mode: SynthesisMode::Single,
             output: None,
             error: None,
         }
@@ -304,6 +365,12 @@ mod tests {
         assert_eq!(Mode::from_str("SEARCH_AGENTIC"), Mode::SearchAgentic);
     }
 
+    #[test]
+    fn mode_from_str_research() {
+        assert_eq!(Mode::from_str("research"), Mode::Research);
+        assert_eq!(Mode::from_str("deep_research"), Mode::Research);
+    }
+
     #[test]
     fn mode_from_str_agent() {
         assert_eq!(Mode::from_str("agent"), Mode::Agent);
@@ -325,7 +392,13 @@ mod tests {
         assert_eq!(Mode::Agent.as_str(), "agent");
 
         // Roundtrip: as_str → from_str → same variant
-        for mode in [Mode::Chat, Mode::Search, Mode::SearchAgentic, Mode::Agent] {
+        for mode in [
+            Mode::Chat,
+            Mode::Search,
+            Mode::SearchAgentic,
+            Mode::Research,
+            Mode::Agent,
+        ] {
             assert_eq!(Mode::from_str(mode.as_str()), mode);
         }
     }
@@ -398,6 +471,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn synthesis_mode_accepts_outline_and_draft() {
+        assert_eq!(SynthesisMode::default(), SynthesisMode::Single);
+        assert_eq!(
+            SynthesisMode::from_optional_str(Some(" Outline ")),
+            SynthesisMode::Outline
+        );
+        assert_eq!(
+            SynthesisMode::from_optional_str(Some("draft")),
+            SynthesisMode::Outline
+        );
+        assert_eq!(
+            SynthesisMode::from_optional_str(Some("unknown")),
+            SynthesisMode::Single
+        );
+        assert_eq!(
+            SynthesisMode::from_optional_str(None),
+            SynthesisMode::Single
+        );
+    }
+
     // =======================================================================
     // SupervisorRoute tests
     // =======================================================================
@@ -474,6 +568,7 @@ mod tests {
         assert!(state.search_evidence.results.is_empty());
         assert!(state.search_attachments.is_empty());
         assert!(!state.skip_web_search);
+        assert_eq!(state.synthesis_mode, SynthesisMode::Single);
         assert!(state.output.is_none());
         assert!(state.error.is_none());
     }
@@ -546,6 +641,25 @@ mod tests {
         assert_eq!(state.agent_mode, AgentMode::Low);
     }
 
+    #[test]
+    fn agent_state_from_ws_message_keeps_custom_mode_name() {
+        let state = AgentState::from_ws_message(
+            "s".to_string(),
+            "bonjour",
+            " Translate ",
+            None,
+            None,
+            None,
+            0,
+            false,
+            Vec::new(),
+            Vec::new(),
+        );
+
+        assert_eq!(state.mode, Mode::Chat);
+        assert_eq!(state.requested_mode, "translate");
+    }
+
     // =======================================================================
     // Artifact tests
     // =======================================================================
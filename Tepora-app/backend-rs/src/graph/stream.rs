@@ -10,22 +10,99 @@ use uuid::Uuid;
 use crate::actor::SessionEvent;
 use crate::core::errors::ApiError;
 use crate::core::security_controls::{ToolApprovalRequestPayload, ToolApprovalResponsePayload};
+use crate::graph::sanitize::ChunkSanitizer;
+use crate::graph::sentences::{SentenceMark, SentenceSegmenter};
+use crate::history::PartialMessage;
 
 pub enum GraphStreamer<'a> {
     WebSocket {
         ws: &'a mut SplitSink<WebSocket, Message>,
         request_id: Option<String>,
+        /// 送った `chunk` を履歴へ途中保存する。生成の終わりに呼び出し側が確定させる
+        partial: Option<PartialMessage>,
+        /// 読み上げが有効なときだけ。`chunk` の後に `sentence` を送る
+        sentences: Option<SentenceSegmenter>,
+        /// `chunk` の本文を送る前に無害化する
+        sanitizer: ChunkSanitizer,
     },
     Actor {
         session_id: String,
         tx: tokio::sync::broadcast::Sender<SessionEvent>,
+        partial: Option<PartialMessage>,
+        sentences: Option<SentenceSegmenter>,
+        sanitizer: ChunkSanitizer,
     },
 }
 
 impl<'a> GraphStreamer<'a> {
-    pub async fn send_json(&mut self, mut payload: Value) -> Result<(), ApiError> {
+    fn partial(&self) -> Option<&PartialMessage> {
         match self {
-            Self::WebSocket { ws, request_id } => {
+            Self::WebSocket { partial, .. } | Self::Actor { partial, .. } => partial.as_ref(),
+        }
+    }
+
+    fn sentences(&mut self) -> Option<&mut SentenceSegmenter> {
+        match self {
+            Self::WebSocket { sentences, .. } | Self::Actor { sentences, .. } => sentences.as_mut(),
+        }
+    }
+
+    async fn send_sentences(&mut self, marks: Vec<SentenceMark>) -> Result<(), ApiError> {
+        for mark in marks {
+            self.dispatch(json!({ "type": "sentence", "data": mark }))
+                .await?;
+        }
+        Ok(())
+    }
+
+    fn sanitizer(&mut self) -> &mut ChunkSanitizer {
+        match self {
+            Self::WebSocket { sanitizer, .. } | Self::Actor { sanitizer, .. } => sanitizer,
+        }
+    }
+
+    pub async fn send_json(&mut self, payload: Value) -> Result<(), ApiError> {
+        match payload.get("type").and_then(Value::as_str) {
+            Some("chunk") => match self.sanitizer().push(payload) {
+                Some(payload) => self.send_chunk(payload).await,
+                // タグやリンクの途中。続きのチャンクと一緒に送る
+                None => Ok(()),
+            },
+            Some("done" | "stopped") => {
+                if let Some(rest) = self.sanitizer().finish() {
+                    self.send_chunk(rest).await?;
+                }
+                // 最後の文は `done` より前に届ける
+                let marks = self.sentences().map(SentenceSegmenter::finish);
+                self.send_sentences(marks.unwrap_or_default()).await?;
+                self.dispatch(payload).await
+            }
+            _ => self.dispatch(payload).await,
+        }
+    }
+
+    async fn send_chunk(&mut self, payload: Value) -> Result<(), ApiError> {
+        let chunk = payload
+            .get("message")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        self.dispatch(payload).await?;
+        if let Some(chunk) = chunk.as_deref() {
+            let marks = self.sentences().map(|sentences| sentences.push(chunk));
+            self.send_sentences(marks.unwrap_or_default()).await?;
+        }
+        if let (Some(chunk), Some(partial)) = (chunk, self.partial()) {
+            // 途中保存の失敗で生成そのものは止めない
+            if let Err(err) = partial.push(&chunk).await {
+                tracing::warn!("Failed to persist partial assistant message: {}", err);
+            }
+        }
+        Ok(())
+    }
+
+    async fn dispatch(&mut self, mut payload: Value) -> Result<(), ApiError> {
+        match self {
+            Self::WebSocket { ws, request_id, .. } => {
                 if let (Some(rid), Some(obj)) = (request_id, payload.as_object_mut()) {
                     if !obj.contains_key("streamId") {
                         obj.insert("streamId".to_string(), json!(rid));
@@ -39,7 +116,7 @@ impl<'a> GraphStreamer<'a> {
                     .await
                     .map_err(ApiError::internal)?;
             }
-            Self::Actor { session_id, tx } => {
+            Self::Actor { session_id, tx, .. } => {
                 let msg_type = payload.get("type").and_then(|t| t.as_str()).unwrap_or("");
                 match msg_type {
                     "chunk" => {
@@ -92,8 +169,12 @@ impl<'a> GraphStreamer<'a> {
                         });
                     }
                     "done" | "stopped" => {
+                        let usage = payload
+                            .get("usage")
+                            .and_then(|usage| serde_json::from_value(usage.clone()).ok());
                         let _ = tx.send(SessionEvent::GenerationComplete {
                             session_id: session_id.clone(),
+                            usage,
                         });
                     }
                     _ => {
@@ -136,19 +217,54 @@ impl<'a> GraphStreamer<'a> {
         timeout_secs: u64,
     ) -> Result<ToolApprovalResponsePayload, ApiError> {
         let request_id = Uuid::new_v4().to_string();
-        let (tx, rx) = tokio::sync::oneshot::channel();
         request.request_id = request_id.clone();
+        let payload = json!({
+            "type": "tool_confirmation_request",
+            "data": request,
+        });
+        self.await_approval(pending, request_id, payload, timeout_secs)
+            .await
+    }
 
+    /// 実行計画の見積もり（`plan_estimate`）を送り、`tool_confirmation_response` で
+    /// 返る承認と `keepSteps` を待つ。
+    pub async fn request_plan_approval(
+        &mut self,
+        pending: Arc<
+            Mutex<HashMap<String, tokio::sync::oneshot::Sender<ToolApprovalResponsePayload>>>,
+        >,
+        mut estimate: Value,
+        timeout_secs: u64,
+    ) -> Result<ToolApprovalResponsePayload, ApiError> {
+        let request_id = Uuid::new_v4().to_string();
+        if let Some(obj) = estimate.as_object_mut() {
+            obj.insert("requestId".to_string(), json!(request_id));
+            obj.insert("awaitingApproval".to_string(), json!(true));
+        }
+        let payload = json!({
+            "type": "plan_estimate",
+            "data": estimate,
+        });
+        self.await_approval(pending, request_id, payload, timeout_secs)
+            .await
+    }
+
+    async fn await_approval(
+        &mut self,
+        pending: Arc<
+            Mutex<HashMap<String, tokio::sync::oneshot::Sender<ToolApprovalResponsePayload>>>,
+        >,
+        request_id: String,
+        payload: Value,
+        timeout_secs: u64,
+    ) -> Result<ToolApprovalResponsePayload, ApiError> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
         {
             let mut map = pending.lock().await;
             map.insert(request_id, tx);
         }
 
-        self.send_json(json!({
-            "type": "tool_confirmation_request",
-            "data": request,
-        }))
-        .await?;
+        self.send_json(payload).await?;
 
         let approval = tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), rx)
             .await
@@ -0,0 +1,174 @@
+//! 回帰テストの実行結果（`eval_runs` テーブル）。
+//!
+//! スイートを 1 回流すごとに 1 行。合格率の推移を出すために件数と
+//! プロンプトの改訂ハッシュを列で持ち、ケースごとの内訳は JSON で残す。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use super::HistoryStore;
+use crate::core::errors::ApiError;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EvalRunRecord {
+    pub id: i64,
+    pub suite: String,
+    /// agent / persona
+    pub target_kind: String,
+    pub target_id: String,
+    pub revision: String,
+    pub model_id: String,
+    pub passed: i64,
+    pub total: i64,
+    pub cases: Value,
+    pub created_at: String,
+}
+
+/// 追加する実行結果。ID と日時はストアが付ける。
+#[derive(Debug, Clone, Default)]
+pub struct NewEvalRun {
+    pub suite: String,
+    pub target_kind: String,
+    pub target_id: String,
+    pub revision: String,
+    pub model_id: String,
+    pub passed: i64,
+    pub total: i64,
+    pub cases: Value,
+}
+
+pub(super) async fn init_eval_runs_table(pool: &SqlitePool) -> Result<(), ApiError> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS eval_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            suite TEXT NOT NULL,
+            target_kind TEXT NOT NULL,
+            target_id TEXT NOT NULL,
+            revision TEXT NOT NULL,
+            model_id TEXT NOT NULL,
+            passed INTEGER NOT NULL,
+            total INTEGER NOT NULL,
+            cases TEXT NOT NULL DEFAULT '[]',
+            created_at TEXT NOT NULL
+        )",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Failed to init eval_runs table: {}", e)))?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_eval_runs_suite ON eval_runs(suite)")
+        .execute(pool)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to create eval_runs index: {}", e)))?;
+    Ok(())
+}
+
+impl HistoryStore {
+    pub async fn add_eval_run(&self, run: &NewEvalRun) -> Result<EvalRunRecord, ApiError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let cases = serde_json::to_string(&run.cases).map_err(ApiError::internal)?;
+        let inserted = sqlx::query(
+            "INSERT INTO eval_runs \
+             (suite, target_kind, target_id, revision, model_id, passed, total, cases, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&run.suite)
+        .bind(&run.target_kind)
+        .bind(&run.target_id)
+        .bind(&run.revision)
+        .bind(&run.model_id)
+        .bind(run.passed)
+        .bind(run.total)
+        .bind(cases)
+        .bind(&now)
+        .execute(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(EvalRunRecord {
+            id: inserted.last_insert_rowid(),
+            suite: run.suite.clone(),
+            target_kind: run.target_kind.clone(),
+            target_id: run.target_id.clone(),
+            revision: run.revision.clone(),
+            model_id: run.model_id.clone(),
+            passed: run.passed,
+            total: run.total,
+            cases: run.cases.clone(),
+            created_at: now,
+        })
+    }
+
+    /// 古い順。`suite` を渡すとそのスイートだけ。
+    pub async fn list_eval_runs(
+        &self,
+        suite: Option<&str>,
+    ) -> Result<Vec<EvalRunRecord>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT id, suite, target_kind, target_id, revision, model_id, passed, total, cases, \
+             created_at FROM eval_runs WHERE (? IS NULL OR suite = ?) ORDER BY id ASC",
+        )
+        .bind(suite)
+        .bind(suite)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(rows.iter().map(eval_run_from_row).collect())
+    }
+}
+
+fn eval_run_from_row(row: &SqliteRow) -> EvalRunRecord {
+    EvalRunRecord {
+        id: row.try_get("id").unwrap_or_default(),
+        suite: row.try_get("suite").unwrap_or_default(),
+        target_kind: row.try_get("target_kind").unwrap_or_default(),
+        target_id: row.try_get("target_id").unwrap_or_default(),
+        revision: row.try_get("revision").unwrap_or_default(),
+        model_id: row.try_get("model_id").unwrap_or_default(),
+        passed: row.try_get("passed").unwrap_or_default(),
+        total: row.try_get("total").unwrap_or_default(),
+        cases: row
+            .try_get::<String, _>("cases")
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_else(|| Value::Array(Vec::new())),
+        created_at: row.try_get("created_at").unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn runs_are_listed_oldest_first_and_filtered_by_suite() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history.db"))
+            .await
+            .unwrap();
+
+        for (suite, passed) in [("tone", 1), ("coder", 2), ("tone", 2)] {
+            store
+                .add_eval_run(&NewEvalRun {
+                    suite: suite.to_string(),
+                    target_kind: "persona".to_string(),
+                    target_id: "shigure".to_string(),
+                    revision: "abc".to_string(),
+                    model_id: "default".to_string(),
+                    passed,
+                    total: 2,
+                    cases: json!([{"id": "greets", "passed": true}]),
+                })
+                .await
+                .unwrap();
+        }
+
+        let tone = store.list_eval_runs(Some("tone")).await.unwrap();
+        assert_eq!(
+            tone.iter().map(|run| run.passed).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(tone[0].cases[0]["id"], "greets");
+        assert_eq!(store.list_eval_runs(None).await.unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,184 @@
+//! 単一ファイルの HTML。スタイルと添付画像を埋め込み、外部リソースは読まない。
+
+use super::markdown::{parse_blocks, Block, Inline};
+use super::{Transcript, TranscriptMessage, TranscriptRole};
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", "Hiragino Sans", "Noto Sans JP", sans-serif; color: #1f2328; background: #fff; margin: 0; }
+main { max-width: 820px; margin: 0 auto; padding: 32px 24px 64px; }
+header h1 { font-size: 1.6rem; margin: 0 0 4px; }
+header p { color: #656d76; font-size: 0.85rem; margin: 0 0 24px; }
+article { border-top: 1px solid #d0d7de; padding: 16px 0; page-break-inside: avoid; }
+.meta { font-size: 0.8rem; color: #656d76; margin-bottom: 8px; }
+.role { font-weight: 600; margin-right: 8px; }
+.role-user { color: #0969da; } .role-assistant { color: #8250df; } .role-system, .role-tool { color: #656d76; }
+.mode { border: 1px solid #d0d7de; border-radius: 10px; padding: 0 6px; margin-left: 6px; }
+pre { background: #f6f8fa; border-radius: 6px; padding: 12px; overflow-x: auto; font-size: 0.85rem; white-space: pre-wrap; word-break: break-word; }
+code { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; }
+:not(pre) > code { background: #f6f8fa; border-radius: 4px; padding: 1px 4px; }
+pre[data-lang]::before { content: attr(data-lang); display: block; color: #656d76; font-size: 0.75rem; margin-bottom: 6px; }
+blockquote { border-left: 3px solid #d0d7de; color: #656d76; margin: 8px 0; padding: 0 12px; }
+sup.citation { color: #0969da; font-size: 0.7em; }
+img.attachment { max-width: 100%; max-height: 360px; border-radius: 6px; margin: 8px 0; display: block; }
+@media print { main { padding: 0; } a { color: inherit; } }
+"#;
+
+pub(super) fn render(transcript: &Transcript) -> String {
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n", escape(&transcript.title)));
+    out.push_str(&format!(
+        "<style>{STYLE}</style>\n</head>\n<body>\n<main>\n"
+    ));
+    out.push_str(&format!(
+        "<header><h1>{}</h1><p>Session {} · exported {}</p></header>\n",
+        escape(&transcript.title),
+        escape(&transcript.session_id),
+        escape(&transcript.exported_at)
+    ));
+    for message in &transcript.messages {
+        render_message(&mut out, message);
+    }
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+fn render_message(out: &mut String, message: &TranscriptMessage) {
+    let role_class = match message.role {
+        TranscriptRole::User => "user",
+        TranscriptRole::Assistant => "assistant",
+        TranscriptRole::System => "system",
+        TranscriptRole::Tool => "tool",
+    };
+    out.push_str(&format!(
+        "<article class=\"message message-{role_class}\">\n<div class=\"meta\"><span class=\"role role-{role_class}\">{}</span><time>{}</time><span class=\"mode\">{}</span></div>\n",
+        message.role.label(),
+        escape(&message.timestamp),
+        escape(&message.mode)
+    ));
+    for image in &message.images {
+        out.push_str(&format!(
+            "<img class=\"attachment\" alt=\"{}\" src=\"data:{};base64,{}\">\n",
+            escape(&image.name),
+            escape(&image.mime_type),
+            escape(&image.base64)
+        ));
+    }
+    for block in parse_blocks(&message.content) {
+        render_block(out, &block);
+    }
+    out.push_str("</article>\n");
+}
+
+fn render_block(out: &mut String, block: &Block) {
+    match block {
+        Block::Heading(level, inlines) => {
+            // 文書タイトルの h1 より下に収める
+            let level = (level + 1).min(6);
+            out.push_str(&format!("<h{level}>{}</h{level}>\n", inlines_html(inlines)));
+        }
+        Block::Paragraph(inlines) => {
+            out.push_str(&format!("<p>{}</p>\n", inlines_html(inlines)));
+        }
+        Block::Code { language, text } => {
+            let lang_attr = language
+                .as_deref()
+                .map(|lang| format!(" data-lang=\"{}\"", escape(lang)))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "<pre{lang_attr}><code>{}</code></pre>\n",
+                escape(text)
+            ));
+        }
+        Block::List { ordered, items } => {
+            let tag = if *ordered { "ol" } else { "ul" };
+            out.push_str(&format!("<{tag}>\n"));
+            for item in items {
+                out.push_str(&format!("<li>{}</li>\n", inlines_html(item)));
+            }
+            out.push_str(&format!("</{tag}>\n"));
+        }
+        Block::Quote(inlines) => {
+            out.push_str(&format!(
+                "<blockquote><p>{}</p></blockquote>\n",
+                inlines_html(inlines)
+            ));
+        }
+        Block::Rule => out.push_str("<hr>\n"),
+    }
+}
+
+fn inlines_html(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => escape(text),
+            Inline::Strong(text) => format!("<strong>{}</strong>", escape(text)),
+            Inline::Emphasis(text) => format!("<em>{}</em>", escape(text)),
+            Inline::Code(text) => format!("<code>{}</code>", escape(text)),
+            Inline::Citation(id) => format!("<sup class=\"citation\">[{}]</sup>", escape(id)),
+            Inline::Link { text, url } if is_safe_url(url) => format!(
+                "<a href=\"{}\" rel=\"noopener noreferrer\">{}</a>",
+                escape(url),
+                escape(text)
+            ),
+            Inline::Link { text, .. } => escape(text),
+        })
+        .collect()
+}
+
+/// `javascript:` などは書き出さない
+fn is_safe_url(url: &str) -> bool {
+    let lowered = url.trim().to_ascii_lowercase();
+    ["http://", "https://", "mailto:"]
+        .iter()
+        .any(|scheme| lowered.starts_with(scheme))
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::export::TranscriptImage;
+
+    #[test]
+    fn html_escapes_content_and_keeps_formatting() {
+        let transcript = Transcript {
+            title: "<Report>".to_string(),
+            session_id: "s1".to_string(),
+            exported_at: "2024-01-01T00:00:00Z".to_string(),
+            messages: vec![TranscriptMessage {
+                role: TranscriptRole::Assistant,
+                timestamp: "t".to_string(),
+                mode: "research".to_string(),
+                content: "See [S2] and [docs](https://e.com) or [x](javascript:alert(1))\n\n```html\n<script>\n```".to_string(),
+                images: vec![TranscriptImage {
+                    name: "chart.png".to_string(),
+                    mime_type: "image/png".to_string(),
+                    base64: "AAAA".to_string(),
+                }],
+            }],
+        };
+        let html = render(&transcript);
+        assert!(html.contains("<title>&lt;Report&gt;</title>"));
+        assert!(html.contains("<sup class=\"citation\">[S2]</sup>"));
+        assert!(html.contains("<a href=\"https://e.com\""));
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("<pre data-lang=\"html\"><code>&lt;script&gt;</code></pre>"));
+        assert!(html.contains("src=\"data:image/png;base64,AAAA\""));
+    }
+}
@@ -0,0 +1,302 @@
+//! エクスポート用の小さな Markdown パーサー。
+//!
+//! 会話で実際に出てくる書式（見出し・段落・箇条書き・引用・コードブロック・
+//! 強調・インラインコード・リンク・`[S1]` 形式の出典）だけを扱い、HTML と PDF の
+//! 両方のレンダラーが同じ中間表現を使う。入れ子の強調や表には対応しない。
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Inline {
+    Text(String),
+    Strong(String),
+    Emphasis(String),
+    Code(String),
+    Link {
+        text: String,
+        url: String,
+    },
+    /// `[S1]` のような出典 ID
+    Citation(String),
+}
+
+impl Inline {
+    pub(super) fn text(&self) -> &str {
+        match self {
+            Inline::Text(text)
+            | Inline::Strong(text)
+            | Inline::Emphasis(text)
+            | Inline::Code(text)
+            | Inline::Citation(text) => text,
+            Inline::Link { text, .. } => text,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Block {
+    Heading(u8, Vec<Inline>),
+    Paragraph(Vec<Inline>),
+    Code {
+        language: Option<String>,
+        text: String,
+    },
+    List {
+        ordered: bool,
+        items: Vec<Vec<Inline>>,
+    },
+    Quote(Vec<Inline>),
+    Rule,
+}
+
+pub(super) fn parse_blocks(source: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut lines = source.lines().peekable();
+
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(parse_inlines(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    };
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if let Some(fence) = trimmed.strip_prefix("```") {
+            flush(&mut paragraph, &mut blocks);
+            let language = Some(fence.trim().to_string()).filter(|lang| !lang.is_empty());
+            let mut code = Vec::new();
+            for code_line in lines.by_ref() {
+                if code_line.trim_start().starts_with("```") {
+                    break;
+                }
+                code.push(code_line);
+            }
+            blocks.push(Block::Code {
+                language,
+                text: code.join("\n"),
+            });
+            continue;
+        }
+        if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+            continue;
+        }
+        if let Some((level, text)) = heading(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Heading(level, parse_inlines(text)));
+            continue;
+        }
+        if is_rule(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Rule);
+            continue;
+        }
+        if trimmed.starts_with('>') {
+            flush(&mut paragraph, &mut blocks);
+            let mut quoted = vec![quote_text(trimmed)];
+            while let Some(next) = lines.peek().map(|next| next.trim()) {
+                if !next.starts_with('>') {
+                    break;
+                }
+                quoted.push(quote_text(next));
+                lines.next();
+            }
+            blocks.push(Block::Quote(parse_inlines(&quoted.join(" "))));
+            continue;
+        }
+        if let Some((ordered, item)) = list_item(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            let mut items = vec![parse_inlines(item)];
+            while let Some(next) = lines.peek().map(|next| next.trim()) {
+                match list_item(next) {
+                    Some((next_ordered, item)) if next_ordered == ordered => {
+                        items.push(parse_inlines(item));
+                        lines.next();
+                    }
+                    _ => break,
+                }
+            }
+            blocks.push(Block::List { ordered, items });
+            continue;
+        }
+        paragraph.push(trimmed);
+    }
+    flush(&mut paragraph, &mut blocks);
+    blocks
+}
+
+fn heading(line: &str) -> Option<(u8, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let text = line[level..].strip_prefix(' ')?;
+    Some((level as u8, text.trim()))
+}
+
+fn is_rule(line: &str) -> bool {
+    let compact = line.replace(' ', "");
+    compact.len() >= 3
+        && ["-", "*", "_"]
+            .iter()
+            .any(|mark| compact.chars().all(|c| c.to_string() == *mark))
+}
+
+fn quote_text(line: &str) -> &str {
+    line.trim_start_matches('>').trim()
+}
+
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            return Some((false, item.trim()));
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &line[digits..];
+        if let Some(item) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return Some((true, item.trim()));
+        }
+    }
+    None
+}
+
+pub(super) fn parse_inlines(text: &str) -> Vec<Inline> {
+    let mut out = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+    let mut prev = None;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some((inline, consumed)) = inline_at(rest, prev) {
+            if !plain.is_empty() {
+                out.push(Inline::Text(std::mem::take(&mut plain)));
+            }
+            out.push(inline);
+            prev = rest[..consumed].chars().last();
+            rest = &rest[consumed..];
+            continue;
+        }
+        plain.push(c);
+        prev = Some(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    if !plain.is_empty() {
+        out.push(Inline::Text(plain));
+    }
+    out
+}
+
+/// `rest` の先頭にある書式を読み取り、読んだバイト数と一緒に返す。
+/// `prev` は直前の文字で、`snake_case` の `_` を強調と取り違えないために使う。
+fn inline_at(rest: &str, prev: Option<char>) -> Option<(Inline, usize)> {
+    if let Some(body) = rest.strip_prefix('`') {
+        let end = body.find('`')?;
+        return Some((Inline::Code(body[..end].to_string()), end + 2));
+    }
+    for (mark, strong) in [("**", true), ("__", true), ("*", false), ("_", false)] {
+        if mark.starts_with('_') && prev.is_some_and(char::is_alphanumeric) {
+            continue;
+        }
+        if let Some(body) = rest.strip_prefix(mark) {
+            let end = body.find(mark)?;
+            let inner = &body[..end];
+            if inner.is_empty() || inner.starts_with(' ') {
+                return None;
+            }
+            let inline = if strong {
+                Inline::Strong(inner.to_string())
+            } else {
+                Inline::Emphasis(inner.to_string())
+            };
+            return Some((inline, end + mark.len() * 2));
+        }
+    }
+    // 画像は外部 URL を読みに行かないよう、リンクとして扱う
+    let (link_start, offset) = match rest.strip_prefix("![") {
+        Some(after) => (after, 2),
+        None => (rest.strip_prefix('[')?, 1),
+    };
+    let close = link_start.find(']')?;
+    let label = &link_start[..close];
+    if let Some(target) = link_start[close + 1..].strip_prefix('(') {
+        let end = target.find(')')?;
+        let url = target[..end].trim().to_string();
+        return Some((
+            Inline::Link {
+                text: if label.is_empty() {
+                    url.clone()
+                } else {
+                    label.to_string()
+                },
+                url,
+            },
+            offset + close + 2 + end + 1,
+        ));
+    }
+    let is_citation = label
+        .strip_prefix('S')
+        .is_some_and(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()));
+    if offset == 1 && is_citation {
+        return Some((Inline::Citation(label.to_string()), close + 2));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_blocks_used_in_transcripts() {
+        let blocks = parse_blocks(
+            "## Summary\nRust is **fast** [S1].\nSecond line.\n\n```rust\nfn main() {}\n\n// done\n```\n- one\n- `two`\n1. first\n> quoted\n> more\n---",
+        );
+        assert_eq!(
+            blocks,
+            vec![
+                Block::Heading(2, vec![Inline::Text("Summary".to_string())]),
+                Block::Paragraph(vec![
+                    Inline::Text("Rust is ".to_string()),
+                    Inline::Strong("fast".to_string()),
+                    Inline::Text(" ".to_string()),
+                    Inline::Citation("S1".to_string()),
+                    Inline::Text(". Second line.".to_string()),
+                ]),
+                Block::Code {
+                    language: Some("rust".to_string()),
+                    text: "fn main() {}\n\n// done".to_string(),
+                },
+                Block::List {
+                    ordered: false,
+                    items: vec![
+                        vec![Inline::Text("one".to_string())],
+                        vec![Inline::Code("two".to_string())],
+                    ],
+                },
+                Block::List {
+                    ordered: true,
+                    items: vec![vec![Inline::Text("first".to_string())]],
+                },
+                Block::Quote(vec![Inline::Text("quoted more".to_string())]),
+                Block::Rule,
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_marks_and_brackets_stay_literal() {
+        assert_eq!(
+            parse_inlines("a * b snake_case_name [note] [x](https://e.com) 5*3"),
+            vec![
+                Inline::Text("a * b snake_case_name [note] ".to_string()),
+                Inline::Link {
+                    text: "x".to_string(),
+                    url: "https://e.com".to_string()
+                },
+                Inline::Text(" 5*3".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! 会話の書き出し（`GET /api/sessions/:id/export.html` / `export.pdf`）。
+//!
+//! 履歴を [`Transcript`] にまとめ、Markdown・コードブロック・出典・添付画像を
+//! 整形した HTML または PDF を組み立てる。外部のレンダラーやネットワークは使わない。
+//! 不具合報告に貼るための匿名化版（`POST /api/sessions/:id/anonymize-export`）は
+//! 本文を伏せ字にした Markdown にする。
+
+mod html;
+mod markdown;
+mod pdf;
+
+use serde_json::Value;
+
+use super::{HistoryMessage, SessionInfo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptRole {
+    User,
+    Assistant,
+    System,
+    Tool,
+}
+
+impl TranscriptRole {
+    fn from_message_type(message_type: &str) -> Self {
+        match message_type {
+            "ai" => TranscriptRole::Assistant,
+            "system" => TranscriptRole::System,
+            "tool" => TranscriptRole::Tool,
+            _ => TranscriptRole::User,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            TranscriptRole::User => "You",
+            TranscriptRole::Assistant => "Assistant",
+            TranscriptRole::System => "System",
+            TranscriptRole::Tool => "Tool",
+        }
+    }
+}
+
+/// ユーザーが添付した画像。データ URI や PDF にそのまま埋め込む。
+#[derive(Debug, Clone)]
+pub struct TranscriptImage {
+    pub name: String,
+    pub mime_type: String,
+    pub base64: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct TranscriptMessage {
+    pub role: TranscriptRole,
+    pub timestamp: String,
+    pub mode: String,
+    pub content: String,
+    pub images: Vec<TranscriptImage>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub title: String,
+    pub session_id: String,
+    pub exported_at: String,
+    pub messages: Vec<TranscriptMessage>,
+}
+
+impl Transcript {
+    pub fn from_history(session: &SessionInfo, messages: Vec<HistoryMessage>) -> Self {
+        let messages = messages
+            .into_iter()
+            .map(|message| {
+                let kwargs = message.additional_kwargs.as_ref();
+                let field = |key: &str| {
+                    kwargs
+                        .and_then(|kwargs| kwargs.get(key))
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                };
+                TranscriptMessage {
+                    role: TranscriptRole::from_message_type(&message.message_type),
+                    timestamp: field("timestamp").unwrap_or(message.created_at),
+                    mode: field("mode").unwrap_or_else(|| "chat".to_string()),
+                    images: kwargs
+                        .and_then(|kwargs| kwargs.get("attachments"))
+                        .and_then(Value::as_array)
+                        .map(|attachments| {
+                            attachments.iter().filter_map(image_attachment).collect()
+                        })
+                        .unwrap_or_default(),
+                    content: message.content,
+                }
+            })
+            .collect();
+
+        Self {
+            title: session
+                .title
+                .clone()
+                .filter(|title| !title.trim().is_empty())
+                .unwrap_or_else(|| "Conversation".to_string()),
+            session_id: session.id.clone(),
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            messages,
+        }
+    }
+
+    pub fn to_html(&self) -> String {
+        html::render(self)
+    }
+
+    pub fn to_pdf(&self) -> Vec<u8> {
+        pdf::render(self)
+    }
+
+    /// 本文とタイトルを `redact` に通し、伏せられない添付画像とセッション ID を落とす。
+    pub fn anonymize(&mut self, mut redact: impl FnMut(&str) -> String) {
+        self.title = redact(&self.title);
+        self.session_id = "anonymized".to_string();
+        for message in &mut self.messages {
+            message.content = redact(&message.content);
+            message.images.clear();
+        }
+    }
+
+    /// GitHub の Issue にそのまま貼れる Markdown。本文はすでに Markdown なので手を加えない。
+    pub fn to_markdown(&self) -> String {
+        let mut output = format!("# {}\n\n_Exported {}_\n", self.title, self.exported_at);
+        for message in &self.messages {
+            output.push_str(&format!(
+                "\n### {} · {} · {}\n\n{}\n",
+                message.role.label(),
+                message.mode,
+                message.timestamp,
+                message.content.trim_end()
+            ));
+        }
+        output
+    }
+
+    /// `Content-Disposition` に使うファイル名（拡張子なし）。
+    pub fn file_stem(&self) -> String {
+        let stem = self
+            .title
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect::<String>()
+            .split('-')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-")
+            .to_ascii_lowercase();
+        if stem.is_empty() {
+            format!("session-{}", self.session_id)
+        } else {
+            stem.chars().take(60).collect()
+        }
+    }
+}
+
+fn image_attachment(attachment: &Value) -> Option<TranscriptImage> {
+    let mime_type = attachment.get("type")?.as_str()?;
+    if !mime_type.starts_with("image/") {
+        return None;
+    }
+    let content = attachment.get("content")?.as_str()?;
+    // フロントエンドはデータ URI のまま送ってくることがある
+    let base64 = content
+        .split_once(";base64,")
+        .map(|(_, data)| data)
+        .unwrap_or(content);
+    if base64.is_empty() {
+        return None;
+    }
+    Some(TranscriptImage {
+        name: attachment
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("image")
+            .to_string(),
+        mime_type: mime_type.to_string(),
+        base64: base64.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn transcript_reads_roles_timestamps_and_image_attachments() {
+        let session = SessionInfo {
+            id: "abc".to_string(),
+            project_id: "default".to_string(),
+            title: Some("Rust 調査: async runtimes".to_string()),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            updated_at: "2024-01-01T00:00:00Z".to_string(),
+            metadata: None,
+            message_count: 2,
+            preview: None,
+        };
+        let message = |id, message_type: &str, content: &str, kwargs| HistoryMessage {
+            id,
+            session_id: "abc".to_string(),
+            message_type: message_type.to_string(),
+            content: content.to_string(),
+            created_at: "2024-01-01 00:00:00".to_string(),
+            additional_kwargs: kwargs,
+        };
+        let transcript = Transcript::from_history(
+            &session,
+            vec![
+                message(
+                    1,
+                    "human",
+                    "Compare tokio and smol",
+                    Some(json!({
+                        "timestamp": "2024-01-01T09:00:00Z",
+                        "mode": "research",
+                        "attachments": [
+                            {"name": "a.png", "type": "image/png", "content": "data:image/png;base64,AAAA"},
+                            {"name": "notes.txt", "type": "text/plain", "content": "hi"}
+                        ]
+                    })),
+                ),
+                message(2, "ai", "Tokio is larger [S1].", None),
+            ],
+        );
+
+        assert_eq!(transcript.messages[0].role, TranscriptRole::User);
+        assert_eq!(transcript.messages[0].timestamp, "2024-01-01T09:00:00Z");
+        assert_eq!(transcript.messages[0].images.len(), 1);
+        assert_eq!(transcript.messages[0].images[0].base64, "AAAA");
+        assert_eq!(transcript.messages[1].role, TranscriptRole::Assistant);
+        assert_eq!(transcript.messages[1].mode, "chat");
+        assert_eq!(transcript.file_stem(), "rust-async-runtimes");
+
+        let mut anonymized = transcript.clone();
+        anonymized.anonymize(|text| text.replace("tokio", "[X]"));
+        assert!(anonymized.messages[0].images.is_empty());
+        let markdown = anonymized.to_markdown();
+        assert!(markdown.starts_with("# Rust 調査: async runtimes\n"));
+        assert!(markdown
+            .contains("### You · research · 2024-01-01T09:00:00Z\n\nCompare [X] and smol\n"));
+        assert!(!markdown.contains("abc"));
+    }
+}
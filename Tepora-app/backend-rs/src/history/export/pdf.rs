@@ -0,0 +1,866 @@
+//! 依存ライブラリを使わない最小限の PDF 書き出し。
+//!
+//! 欧文は PDF 標準の Helvetica / Courier（WinAnsi）、それ以外の BMP の文字は
+//! 埋め込みなしの日本語 CID フォント（HeiseiKakuGo-W5, UniJIS-UCS2-H）で描く。
+//! 閲覧側のフォント代替に頼るため、日本語以外の CJK 文字は表示できないことがある。
+//! 添付画像は JPEG のみそのまま埋め込み、それ以外は名前だけを書く。
+
+use std::io::Write as _;
+
+use base64::Engine as _;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use super::markdown::{parse_blocks, Block, Inline};
+use super::{Transcript, TranscriptMessage, TranscriptRole};
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+const CONTENT_WIDTH: f32 = PAGE_WIDTH - MARGIN * 2.0;
+const BODY_SIZE: f32 = 10.5;
+const CODE_SIZE: f32 = 9.0;
+const LEADING: f32 = 1.45;
+const MAX_IMAGE_HEIGHT: f32 = 280.0;
+
+type Rgb = (f32, f32, f32);
+const TEXT: Rgb = (0.12, 0.14, 0.16);
+const MUTED: Rgb = (0.40, 0.43, 0.46);
+const ACCENT: Rgb = (0.04, 0.41, 0.85);
+const ASSISTANT: Rgb = (0.51, 0.31, 0.87);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Font {
+    Regular,
+    Bold,
+    Italic,
+    Mono,
+    Cjk,
+}
+
+impl Font {
+    const ALL: [Font; 5] = [
+        Font::Regular,
+        Font::Bold,
+        Font::Italic,
+        Font::Mono,
+        Font::Cjk,
+    ];
+
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+            Font::Italic => "F3",
+            Font::Mono => "F4",
+            Font::Cjk => "F5",
+        }
+    }
+
+    /// 1 文字の幅（em 単位）。Helvetica は概算値。
+    fn char_width(self, c: char) -> f32 {
+        match self {
+            Font::Mono => 0.6,
+            Font::Cjk => {
+                if ('\u{FF61}'..='\u{FF9F}').contains(&c) {
+                    0.5
+                } else {
+                    1.0
+                }
+            }
+            Font::Regular | Font::Italic | Font::Bold => {
+                let width = match c {
+                    ' ' | 'i' | 'j' | 'l' | 'I' | '.' | ',' | ':' | ';' | '\'' | '!' | '|' => 0.28,
+                    'f' | 't' | 'r' | '(' | ')' | '[' | ']' | '-' => 0.34,
+                    'm' | 'w' | 'M' | 'W' | '@' | '%' => 0.85,
+                    'A'..='Z' => 0.68,
+                    _ => 0.56,
+                };
+                if self == Font::Bold {
+                    width * 1.06
+                } else {
+                    width
+                }
+            }
+        }
+    }
+}
+
+/// 書式付きの文字列片
+#[derive(Debug, Clone)]
+struct Span {
+    text: String,
+    font: Font,
+    color: Rgb,
+}
+
+impl Span {
+    fn new(text: impl Into<String>, font: Font, color: Rgb) -> Self {
+        Self {
+            text: text.into(),
+            font,
+            color,
+        }
+    }
+}
+
+struct JpegImage {
+    width: u32,
+    height: u32,
+    components: u8,
+    data: Vec<u8>,
+}
+
+#[derive(Default)]
+struct Page {
+    content: Vec<u8>,
+    images: Vec<usize>,
+}
+
+struct Layout {
+    pages: Vec<Page>,
+    images: Vec<JpegImage>,
+    y: f32,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Self {
+            pages: vec![Page::default()],
+            images: Vec::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn page(&mut self) -> &mut Page {
+        self.pages.last_mut().expect("layout always has a page")
+    }
+
+    fn ops(&mut self, ops: &str) {
+        self.page().content.extend_from_slice(ops.as_bytes());
+    }
+
+    /// 残りの高さが足りなければ改ページする
+    fn ensure(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.pages.push(Page::default());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    fn space(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Rgb) {
+        self.ops(&format!(
+            "{:.2} {:.2} {:.2} rg {x:.2} {y:.2} {width:.2} {height:.2} re f\n",
+            color.0, color.1, color.2
+        ));
+    }
+
+    fn rule(&mut self, color: Rgb) {
+        self.ensure(8.0);
+        self.space(4.0);
+        let y = self.y;
+        self.fill_rect(MARGIN, y, CONTENT_WIDTH, 0.6, color);
+        self.space(4.0);
+    }
+
+    /// 折り返して描く。`prefix` は 1 行目の左（箇条書きの記号など）に置く。
+    fn paragraph(&mut self, spans: &[Span], size: f32, indent: f32, prefix: Option<Span>) {
+        let line_height = size * LEADING;
+        let lines = wrap(spans, size, CONTENT_WIDTH - indent);
+        for (index, line) in lines.iter().enumerate() {
+            self.ensure(line_height);
+            self.space(line_height);
+            let baseline = self.y + size * 0.3;
+            if index == 0 {
+                if let Some(prefix) = &prefix {
+                    self.draw_text(MARGIN + indent - 14.0, baseline, size, &split_fonts(prefix));
+                }
+            }
+            self.draw_text(MARGIN + indent, baseline, size, line);
+        }
+    }
+
+    fn quote(&mut self, spans: &[Span]) {
+        let line_height = BODY_SIZE * LEADING;
+        for line in wrap(spans, BODY_SIZE, CONTENT_WIDTH - 14.0) {
+            self.ensure(line_height);
+            self.space(line_height);
+            let y = self.y;
+            self.fill_rect(MARGIN + 2.0, y, 2.0, line_height, (0.82, 0.84, 0.87));
+            self.draw_text(MARGIN + 14.0, y + BODY_SIZE * 0.3, BODY_SIZE, &line);
+        }
+    }
+
+    fn code_block(&mut self, text: &str) {
+        let line_height = CODE_SIZE * 1.35;
+        let padding = 6.0;
+        let text = text.replace('\t', "    ");
+        let mut lines = Vec::new();
+        for source_line in text.split('\n') {
+            lines.extend(wrap_chars(
+                source_line,
+                CODE_SIZE,
+                CONTENT_WIDTH - padding * 2.0,
+            ));
+        }
+        self.space(2.0);
+        for line in lines {
+            self.ensure(line_height);
+            self.space(line_height);
+            let y = self.y;
+            self.fill_rect(MARGIN, y, CONTENT_WIDTH, line_height, (0.96, 0.97, 0.98));
+            self.draw_text(MARGIN + padding, y + CODE_SIZE * 0.35, CODE_SIZE, &line);
+        }
+        self.space(4.0);
+    }
+
+    fn image(&mut self, image: JpegImage) {
+        let scale = (CONTENT_WIDTH / image.width as f32)
+            .min(MAX_IMAGE_HEIGHT / image.height as f32)
+            .min(1.0);
+        let (width, height) = (image.width as f32 * scale, image.height as f32 * scale);
+        self.ensure(height + 6.0);
+        self.space(height + 6.0);
+        self.images.push(image);
+        let index = self.images.len() - 1;
+        self.page().images.push(index);
+        let y = self.y + 3.0;
+        self.ops(&format!(
+            "q {width:.2} 0 0 {height:.2} {MARGIN:.2} {y:.2} cm /Im{} Do Q\n",
+            index + 1
+        ));
+    }
+
+    fn draw_text(&mut self, x: f32, baseline: f32, size: f32, line: &[Span]) {
+        let mut x = x;
+        for span in line {
+            let page = self.page();
+            page.content.extend_from_slice(
+                format!(
+                    "BT /{} {size:.2} Tf {:.2} {:.2} {:.2} rg {x:.2} {baseline:.2} Td ",
+                    span.font.resource(),
+                    span.color.0,
+                    span.color.1,
+                    span.color.2
+                )
+                .as_bytes(),
+            );
+            encode_text(&mut page.content, span);
+            page.content.extend_from_slice(b" Tj ET\n");
+            x += text_width(&span.text, span.font, size);
+        }
+    }
+}
+
+/// WinAnsiEncoding での 1 バイト表現
+fn win_ansi(c: char) -> Option<u8> {
+    let byte = match c {
+        ' '..='~' | '\u{A0}'..='\u{FF}' => c as u32 as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        'ƒ' => 0x83,
+        '„' => 0x84,
+        '…' => 0x85,
+        '†' => 0x86,
+        '‡' => 0x87,
+        'ˆ' => 0x88,
+        '‰' => 0x89,
+        'Š' => 0x8A,
+        '‹' => 0x8B,
+        'Œ' => 0x8C,
+        'Ž' => 0x8E,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '˜' => 0x98,
+        '™' => 0x99,
+        'š' => 0x9A,
+        '›' => 0x9B,
+        'œ' => 0x9C,
+        'ž' => 0x9E,
+        'Ÿ' => 0x9F,
+        _ => return None,
+    };
+    Some(byte)
+}
+
+/// 欧文フォントで描けない文字は CJK フォントに回す。BMP 外の文字は `?` にする。
+fn split_fonts(span: &Span) -> Vec<Span> {
+    let mut out: Vec<Span> = Vec::new();
+    for c in span.text.chars() {
+        let c = if c == '\t' { ' ' } else { c };
+        let (c, font) = if win_ansi(c).is_some() {
+            (c, span.font)
+        } else if (c as u32) <= 0xFFFF && !c.is_control() {
+            (c, Font::Cjk)
+        } else {
+            ('?', span.font)
+        };
+        match out.last_mut() {
+            Some(last) if last.font == font => last.text.push(c),
+            _ => out.push(Span::new(c.to_string(), font, span.color)),
+        }
+    }
+    out
+}
+
+fn encode_text(out: &mut Vec<u8>, span: &Span) {
+    if span.font == Font::Cjk {
+        out.push(b'<');
+        for unit in span.text.encode_utf16() {
+            out.extend_from_slice(format!("{unit:04X}").as_bytes());
+        }
+        out.push(b'>');
+        return;
+    }
+    out.push(b'(');
+    for c in span.text.chars() {
+        match win_ansi(c).unwrap_or(b'?') {
+            byte @ (b'(' | b')' | b'\\') => out.extend_from_slice(&[b'\\', byte]),
+            byte => out.push(byte),
+        }
+    }
+    out.push(b')');
+}
+
+fn text_width(text: &str, font: Font, size: f32) -> f32 {
+    text.chars().map(|c| font.char_width(c)).sum::<f32>() * size
+}
+
+/// 単語単位で折り返す。CJK の文字はどこでも改行できる。
+fn wrap(spans: &[Span], size: f32, max_width: f32) -> Vec<Vec<Span>> {
+    let mut tokens: Vec<Span> = Vec::new();
+    for span in spans.iter().flat_map(split_fonts) {
+        for c in span.text.chars() {
+            let breakable = c == ' ' || span.font == Font::Cjk;
+            match tokens.last_mut() {
+                Some(last)
+                    if !breakable
+                        && last.font == span.font
+                        && last.color == span.color
+                        && !last.text.ends_with(' ')
+                        && last.font != Font::Cjk =>
+                {
+                    last.text.push(c)
+                }
+                _ => tokens.push(Span::new(c.to_string(), span.font, span.color)),
+            }
+        }
+    }
+
+    let mut lines: Vec<Vec<Span>> = Vec::new();
+    let mut line: Vec<Span> = Vec::new();
+    let mut width = 0.0;
+    for token in tokens {
+        let token_width = text_width(&token.text, token.font, size);
+        if token.text == " " && line.is_empty() {
+            continue;
+        }
+        if width + token_width > max_width && !line.is_empty() {
+            lines.push(merge_spans(std::mem::take(&mut line)));
+            width = 0.0;
+            if token.text == " " {
+                continue;
+            }
+        }
+        if token_width > max_width {
+            // 1 行に収まらない長い語（URL など）は文字単位で割る
+            for piece in wrap_chars(&token.text, size, max_width) {
+                let piece = piece
+                    .into_iter()
+                    .map(|span| Span::new(span.text, token.font, token.color))
+                    .collect::<Vec<_>>();
+                width = piece
+                    .iter()
+                    .map(|span| text_width(&span.text, span.font, size))
+                    .sum();
+                if !line.is_empty() {
+                    lines.push(merge_spans(std::mem::take(&mut line)));
+                }
+                line = piece;
+            }
+            continue;
+        }
+        width += token_width;
+        line.push(token);
+    }
+    if !line.is_empty() {
+        lines.push(merge_spans(line));
+    }
+    lines
+}
+
+/// 等幅のまま文字単位で折り返す（コードブロック用）。
+fn wrap_chars(text: &str, size: f32, max_width: f32) -> Vec<Vec<Span>> {
+    let mut lines = vec![Vec::new()];
+    let mut width = 0.0;
+    for span in split_fonts(&Span::new(text, Font::Mono, TEXT)) {
+        for c in span.text.chars() {
+            let char_width = span.font.char_width(c) * size;
+            if width + char_width > max_width && width > 0.0 {
+                lines.push(Vec::new());
+                width = 0.0;
+            }
+            width += char_width;
+            let line: &mut Vec<Span> = lines.last_mut().expect("at least one line");
+            match line.last_mut() {
+                Some(last) if last.font == span.font => last.text.push(c),
+                _ => line.push(Span::new(c.to_string(), span.font, span.color)),
+            }
+        }
+    }
+    lines
+}
+
+fn merge_spans(spans: Vec<Span>) -> Vec<Span> {
+    let mut out: Vec<Span> = Vec::new();
+    for span in spans {
+        match out.last_mut() {
+            Some(last) if last.font == span.font && last.color == span.color => {
+                last.text.push_str(&span.text)
+            }
+            _ => out.push(span),
+        }
+    }
+    out
+}
+
+fn inline_spans(inlines: &[Inline], base: Font, color: Rgb) -> Vec<Span> {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => Span::new(text.clone(), base, color),
+            Inline::Strong(text) => Span::new(text.clone(), Font::Bold, color),
+            Inline::Emphasis(text) => Span::new(text.clone(), Font::Italic, color),
+            Inline::Code(text) => Span::new(text.clone(), Font::Mono, color),
+            Inline::Citation(id) => Span::new(format!("[{id}]"), base, ACCENT),
+            Inline::Link { text, url } if text != url => {
+                Span::new(format!("{text} <{url}>"), base, ACCENT)
+            }
+            Inline::Link { .. } => Span::new(inline.text().to_string(), base, ACCENT),
+        })
+        .collect()
+}
+
+fn render_message(layout: &mut Layout, message: &TranscriptMessage) {
+    layout.space(6.0);
+    layout.rule((0.82, 0.84, 0.87));
+    layout.space(2.0);
+    let role_color = match message.role {
+        TranscriptRole::User => ACCENT,
+        TranscriptRole::Assistant => ASSISTANT,
+        TranscriptRole::System | TranscriptRole::Tool => MUTED,
+    };
+    layout.paragraph(
+        &[
+            Span::new(message.role.label(), Font::Bold, role_color),
+            Span::new(
+                format!("  {} · {}", message.timestamp, message.mode),
+                Font::Regular,
+                MUTED,
+            ),
+        ],
+        9.0,
+        0.0,
+        None,
+    );
+    layout.space(2.0);
+
+    for image in &message.images {
+        let jpeg = (image.mime_type == "image/jpeg" || image.mime_type == "image/jpg")
+            .then(|| {
+                base64::engine::general_purpose::STANDARD
+                    .decode(&image.base64)
+                    .ok()
+            })
+            .flatten()
+            .and_then(parse_jpeg);
+        match jpeg {
+            Some(jpeg) => layout.image(jpeg),
+            None => layout.paragraph(
+                &[Span::new(
+                    format!("[image: {}]", image.name),
+                    Font::Italic,
+                    MUTED,
+                )],
+                BODY_SIZE,
+                0.0,
+                None,
+            ),
+        }
+    }
+
+    for block in parse_blocks(&message.content) {
+        match block {
+            Block::Heading(level, inlines) => {
+                let size = match level {
+                    1 => 15.0,
+                    2 => 13.5,
+                    _ => 12.0,
+                };
+                layout.space(4.0);
+                layout.paragraph(&inline_spans(&inlines, Font::Bold, TEXT), size, 0.0, None);
+            }
+            Block::Paragraph(inlines) => {
+                layout.paragraph(
+                    &inline_spans(&inlines, Font::Regular, TEXT),
+                    BODY_SIZE,
+                    0.0,
+                    None,
+                );
+                layout.space(4.0);
+            }
+            Block::Code { text, .. } => layout.code_block(&text),
+            Block::List { ordered, items } => {
+                for (index, item) in items.iter().enumerate() {
+                    let marker = if ordered {
+                        format!("{}.", index + 1)
+                    } else {
+                        "•".to_string()
+                    };
+                    layout.paragraph(
+                        &inline_spans(item, Font::Regular, TEXT),
+                        BODY_SIZE,
+                        16.0,
+                        Some(Span::new(marker, Font::Regular, TEXT)),
+                    );
+                }
+                layout.space(4.0);
+            }
+            Block::Quote(inlines) => {
+                layout.quote(&inline_spans(&inlines, Font::Italic, MUTED));
+                layout.space(4.0);
+            }
+            Block::Rule => layout.rule((0.82, 0.84, 0.87)),
+        }
+    }
+}
+
+/// SOF マーカーから幅・高さ・色成分数を読む。
+fn parse_jpeg(data: Vec<u8>) -> Option<JpegImage> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut index = 2;
+    while index + 4 <= data.len() {
+        if data[index] != 0xFF {
+            return None;
+        }
+        let marker = data[index + 1];
+        if marker == 0xFF {
+            index += 1;
+            continue;
+        }
+        if matches!(marker, 0x01 | 0xD0..=0xD7) {
+            index += 2;
+            continue;
+        }
+        let length = u16::from_be_bytes([data[index + 2], data[index + 3]]) as usize;
+        let is_sof = matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+        if is_sof {
+            let header = data.get(index + 4..index + 10)?;
+            let height = u16::from_be_bytes([header[1], header[2]]) as u32;
+            let width = u16::from_be_bytes([header[3], header[4]]) as u32;
+            let components = header[5];
+            if width == 0 || height == 0 || !matches!(components, 1 | 3 | 4) {
+                return None;
+            }
+            return Some(JpegImage {
+                width,
+                height,
+                components,
+                data,
+            });
+        }
+        if marker == 0xDA {
+            return None;
+        }
+        index += 2 + length;
+    }
+    None
+}
+
+fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    // Vec への書き込みは失敗しない
+    let _ = encoder.write_all(data);
+    encoder.finish().unwrap_or_default()
+}
+
+/// PDF のテキスト文字列（UTF-16BE + BOM の 16 進表記）
+fn pdf_text_string(text: &str) -> String {
+    let mut out = String::from("<FEFF");
+    for unit in text.encode_utf16() {
+        out.push_str(&format!("{unit:04X}"));
+    }
+    out.push('>');
+    out
+}
+
+fn stream_object(dictionary: &str, data: &[u8]) -> Vec<u8> {
+    let mut out = format!("<< {dictionary} /Length {} >>\nstream\n", data.len()).into_bytes();
+    out.extend_from_slice(data);
+    out.extend_from_slice(b"\nendstream");
+    out
+}
+
+pub(super) fn render(transcript: &Transcript) -> Vec<u8> {
+    let mut layout = Layout::new();
+    layout.paragraph(
+        &[Span::new(transcript.title.clone(), Font::Bold, TEXT)],
+        18.0,
+        0.0,
+        None,
+    );
+    layout.paragraph(
+        &[Span::new(
+            format!(
+                "Session {} · exported {}",
+                transcript.session_id, transcript.exported_at
+            ),
+            Font::Regular,
+            MUTED,
+        )],
+        9.0,
+        0.0,
+        None,
+    );
+    layout.space(6.0);
+    for message in &transcript.messages {
+        render_message(&mut layout, message);
+    }
+
+    let total = layout.pages.len();
+    for (index, page) in layout.pages.iter_mut().enumerate() {
+        let footer = format!("{} / {}", index + 1, total);
+        let x = PAGE_WIDTH / 2.0 - text_width(&footer, Font::Regular, 8.0) / 2.0;
+        page.content.extend_from_slice(
+            format!(
+                "BT /F1 8 Tf {:.2} {:.2} {:.2} rg {x:.2} {:.2} Td ({footer}) Tj ET\n",
+                MUTED.0,
+                MUTED.1,
+                MUTED.2,
+                MARGIN / 2.0
+            )
+            .as_bytes(),
+        );
+    }
+
+    // 1: Catalog, 2: Pages, 3-7: フォント, 8-9: CID フォント, 10: Info, 以降: 画像とページ
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    let image_base = 11;
+    let page_base = image_base + layout.images.len();
+    let page_refs = (0..total)
+        .map(|index| format!("{} 0 R", page_base + index * 2))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    objects.push(format!("<< /Type /Pages /Kids [{page_refs}] /Count {total} >>").into_bytes());
+    for base_font in [
+        "Helvetica",
+        "Helvetica-Bold",
+        "Helvetica-Oblique",
+        "Courier",
+    ] {
+        objects.push(
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{base_font} /Encoding /WinAnsiEncoding >>"
+            )
+            .into_bytes(),
+        );
+    }
+    objects.push(
+        b"<< /Type /Font /Subtype /Type0 /BaseFont /HeiseiKakuGo-W5 /Encoding /UniJIS-UCS2-H /DescendantFonts [8 0 R] >>"
+            .to_vec(),
+    );
+    objects.push(
+        b"<< /Type /Font /Subtype /CIDFontType0 /BaseFont /HeiseiKakuGo-W5 /CIDSystemInfo << /Registry (Adobe) /Ordering (Japan1) /Supplement 2 >> /FontDescriptor 9 0 R /DW 1000 /W [327 389 500] >>"
+            .to_vec(),
+    );
+    objects.push(
+        b"<< /Type /FontDescriptor /FontName /HeiseiKakuGo-W5 /Flags 4 /FontBBox [-92 -250 1010 922] /ItalicAngle 0 /Ascent 752 /Descent -221 /CapHeight 737 /StemV 114 >>"
+            .to_vec(),
+    );
+    objects.push(
+        format!(
+            "<< /Title {} /Producer (Tepora) /CreationDate (D:{}) >>",
+            pdf_text_string(&transcript.title),
+            chrono::Utc::now().format("%Y%m%d%H%M%SZ")
+        )
+        .into_bytes(),
+    );
+    for image in &layout.images {
+        let (color_space, decode) = match image.components {
+            1 => ("/DeviceGray", ""),
+            // Adobe 形式の CMYK JPEG は反転して保存されている
+            4 => ("/DeviceCMYK", " /Decode [1 0 1 0 1 0 1 0]"),
+            _ => ("/DeviceRGB", ""),
+        };
+        objects.push(stream_object(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace {color_space} /BitsPerComponent 8 /Filter /DCTDecode{decode}",
+                image.width, image.height
+            ),
+            &image.data,
+        ));
+    }
+    let fonts = Font::ALL
+        .iter()
+        .enumerate()
+        .map(|(index, font)| format!("/{} {} 0 R", font.resource(), index + 3))
+        .collect::<Vec<_>>()
+        .join(" ");
+    for (index, page) in layout.pages.iter().enumerate() {
+        let content_ref = page_base + index * 2 + 1;
+        let images = page
+            .images
+            .iter()
+            .map(|image| format!("/Im{} {} 0 R", image + 1, image_base + image))
+            .collect::<Vec<_>>()
+            .join(" ");
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] /Resources << /Font << {fonts} >> /XObject << {images} >> >> /Contents {content_ref} 0 R >>"
+            )
+            .into_bytes(),
+        );
+        objects.push(stream_object(
+            "/Filter /FlateDecode",
+            &compress(&page.content),
+        ));
+    }
+
+    let mut out = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+        out.extend_from_slice(object);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = out.len();
+    out.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        out.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+    }
+    out.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info 10 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .as_bytes(),
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::export::TranscriptImage;
+
+    fn transcript(content: String, images: Vec<TranscriptImage>) -> Transcript {
+        Transcript {
+            title: "調査レポート".to_string(),
+            session_id: "s1".to_string(),
+            exported_at: "2024-01-01T00:00:00Z".to_string(),
+            messages: vec![TranscriptMessage {
+                role: TranscriptRole::Assistant,
+                timestamp: "t".to_string(),
+                mode: "research".to_string(),
+                content,
+                images,
+            }],
+        }
+    }
+
+    #[test]
+    fn pdf_has_valid_structure_and_paginates() {
+        let long = (0..120)
+            .map(|i| format!("Paragraph {i} about tokio (async) and 非同期処理 [S1]."))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        // 2x1 の最小限の JPEG ヘッダ（SOF0 まで）
+        let jpeg = [
+            0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC0, 0x00, 0x0B, 0x08, 0x00,
+            0x01, 0x00, 0x02, 0x03, 0x01, 0x11, 0x00, 0xFF, 0xD9,
+        ];
+        let image = TranscriptImage {
+            name: "photo.jpg".to_string(),
+            mime_type: "image/jpeg".to_string(),
+            base64: base64::engine::general_purpose::STANDARD.encode(jpeg),
+        };
+        let pdf = render(&transcript(
+            format!("```\n{}\n```\n{long}", "x".repeat(300)),
+            vec![image],
+        ));
+
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("/Subtype /Image /Width 2 /Height 1"));
+        let count = text
+            .split("/Count ")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .and_then(|count| count.parse::<usize>().ok())
+            .unwrap();
+        assert!(count > 1, "expected several pages, got {count}");
+
+        // xref のオフセットが各オブジェクトの先頭を指している
+        let startxref = text.rsplit("startxref\n").next().unwrap();
+        let xref_at: usize = startxref.lines().next().unwrap().parse().unwrap();
+        assert!(pdf[xref_at..].starts_with(b"xref"));
+        let xref_table = std::str::from_utf8(&pdf[xref_at..]).unwrap();
+        let first_offset: usize = xref_table.lines().nth(3).unwrap()[..10].parse().unwrap();
+        assert!(pdf[first_offset..].starts_with(b"1 0 obj"));
+    }
+
+    #[test]
+    fn text_is_split_between_latin_and_cjk_fonts_and_wrapped() {
+        let spans = split_fonts(&Span::new("Rust は (速い) 🚀", Font::Regular, TEXT));
+        let fonts = spans.iter().map(|span| span.font).collect::<Vec<_>>();
+        assert_eq!(
+            fonts,
+            vec![
+                Font::Regular,
+                Font::Cjk,
+                Font::Regular,
+                Font::Cjk,
+                Font::Regular
+            ]
+        );
+        assert_eq!(spans[4].text, ") ?");
+
+        let mut encoded = Vec::new();
+        encode_text(&mut encoded, &spans[2]);
+        assert_eq!(encoded, b"( \\()".to_vec());
+        encoded.clear();
+        encode_text(&mut encoded, &spans[1]);
+        assert_eq!(encoded, b"<306F>".to_vec());
+
+        let lines = wrap(
+            &[Span::new("word ".repeat(60), Font::Regular, TEXT)],
+            BODY_SIZE,
+            CONTENT_WIDTH,
+        );
+        assert!(lines.len() > 1);
+        for line in &lines {
+            let width: f32 = line
+                .iter()
+                .map(|span| text_width(span.text.trim_end(), span.font, BODY_SIZE))
+                .sum();
+            assert!(width <= CONTENT_WIDTH + 0.01);
+        }
+    }
+}
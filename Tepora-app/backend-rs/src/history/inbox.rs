@@ -0,0 +1,221 @@
+//! バックグラウンド実行の結果置き場（`inbox` テーブル）。
+//!
+//! 長時間のエージェント実行や定期取り込みの要約・成果物をチャットとは別に
+//! 残し、ユーザーが読んだかどうかを `read_at` で持つ。
+
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use super::HistoryStore;
+use crate::a2a::A2AArtifact;
+use crate::core::errors::ApiError;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InboxItem {
+    pub id: i64,
+    /// agent_run / feed_ingest
+    pub kind: String,
+    pub title: String,
+    pub summary: String,
+    pub success: bool,
+    pub session_id: Option<String>,
+    pub artifacts: Vec<A2AArtifact>,
+    pub created_at: String,
+    pub read_at: Option<String>,
+}
+
+/// 追加する項目。ID と日時はストアが付ける。
+#[derive(Debug, Clone, Default)]
+pub struct NewInboxItem {
+    pub kind: String,
+    pub title: String,
+    pub summary: String,
+    pub success: bool,
+    pub session_id: Option<String>,
+    pub artifacts: Vec<A2AArtifact>,
+}
+
+pub(super) async fn init_inbox_table(pool: &SqlitePool) -> Result<(), ApiError> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS inbox (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            title TEXT NOT NULL,
+            summary TEXT NOT NULL,
+            success INTEGER NOT NULL,
+            session_id TEXT,
+            artifacts TEXT NOT NULL DEFAULT '[]',
+            created_at TEXT NOT NULL,
+            read_at TEXT
+        )",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Failed to init inbox table: {}", e)))?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_inbox_read_at ON inbox(read_at)")
+        .execute(pool)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to create inbox index: {}", e)))?;
+    Ok(())
+}
+
+impl HistoryStore {
+    pub async fn add_inbox_item(&self, item: &NewInboxItem) -> Result<InboxItem, ApiError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let artifacts = serde_json::to_string(&item.artifacts).map_err(ApiError::internal)?;
+        let inserted = sqlx::query(
+            "INSERT INTO inbox (kind, title, summary, success, session_id, artifacts, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&item.kind)
+        .bind(&item.title)
+        .bind(&item.summary)
+        .bind(item.success as i64)
+        .bind(&item.session_id)
+        .bind(artifacts)
+        .bind(&now)
+        .execute(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(InboxItem {
+            id: inserted.last_insert_rowid(),
+            kind: item.kind.clone(),
+            title: item.title.clone(),
+            summary: item.summary.clone(),
+            success: item.success,
+            session_id: item.session_id.clone(),
+            artifacts: item.artifacts.clone(),
+            created_at: now,
+            read_at: None,
+        })
+    }
+
+    /// 新しい順。`before_id` より前の項目だけを返すとページングになる。
+    pub async fn list_inbox(
+        &self,
+        unread_only: bool,
+        limit: i64,
+        before_id: Option<i64>,
+    ) -> Result<Vec<InboxItem>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT id, kind, title, summary, success, session_id, artifacts, created_at, read_at \
+             FROM inbox WHERE (? = 0 OR read_at IS NULL) AND (? IS NULL OR id < ?) \
+             ORDER BY id DESC LIMIT ?",
+        )
+        .bind(unread_only as i64)
+        .bind(before_id)
+        .bind(before_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(rows.iter().map(inbox_item_from_row).collect())
+    }
+
+    pub async fn unread_inbox_count(&self) -> Result<i64, ApiError> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM inbox WHERE read_at IS NULL")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(ApiError::internal)
+    }
+
+    /// 既読にする。`ids` が `None` なら未読すべて。既読にした件数を返す。
+    pub async fn acknowledge_inbox(&self, ids: Option<&[i64]>) -> Result<u64, ApiError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let result = match ids {
+            None => sqlx::query("UPDATE inbox SET read_at = ? WHERE read_at IS NULL")
+                .bind(&now)
+                .execute(&self.pool)
+                .await
+                .map_err(ApiError::internal)?,
+            Some([]) => return Ok(0),
+            Some(ids) => {
+                let placeholders = vec!["?"; ids.len()].join(", ");
+                let sql = format!(
+                    "UPDATE inbox SET read_at = ? WHERE read_at IS NULL AND id IN ({})",
+                    placeholders
+                );
+                let mut query = sqlx::query(&sql).bind(&now);
+                for id in ids {
+                    query = query.bind(id);
+                }
+                query
+                    .execute(&self.pool)
+                    .await
+                    .map_err(ApiError::internal)?
+            }
+        };
+        Ok(result.rows_affected())
+    }
+}
+
+fn inbox_item_from_row(row: &SqliteRow) -> InboxItem {
+    InboxItem {
+        id: row.try_get("id").unwrap_or_default(),
+        kind: row.try_get("kind").unwrap_or_default(),
+        title: row.try_get("title").unwrap_or_default(),
+        summary: row.try_get("summary").unwrap_or_default(),
+        success: row.try_get::<i64, _>("success").unwrap_or(0) != 0,
+        session_id: row.try_get("session_id").ok().flatten(),
+        artifacts: row
+            .try_get::<String, _>("artifacts")
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default(),
+        created_at: row.try_get("created_at").unwrap_or_default(),
+        read_at: row.try_get("read_at").ok().flatten(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn items_list_newest_first_and_can_be_acknowledged() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history.db"))
+            .await
+            .unwrap();
+
+        let first = store
+            .add_inbox_item(&NewInboxItem {
+                kind: "agent_run".to_string(),
+                title: "Agent run finished".to_string(),
+                summary: "Wrote the report.".to_string(),
+                success: true,
+                session_id: Some("s1".to_string()),
+                artifacts: vec![A2AArtifact::text("report", "# Report")],
+            })
+            .await
+            .unwrap();
+        let second = store
+            .add_inbox_item(&NewInboxItem {
+                kind: "feed_ingest".to_string(),
+                title: "Feeds updated".to_string(),
+                success: true,
+                ..NewInboxItem::default()
+            })
+            .await
+            .unwrap();
+
+        let items = store.list_inbox(false, 10, None).await.unwrap();
+        assert_eq!(
+            items.iter().map(|item| item.id).collect::<Vec<_>>(),
+            vec![second.id, first.id]
+        );
+        assert_eq!(items[1].artifacts, first.artifacts);
+        assert_eq!(store.unread_inbox_count().await.unwrap(), 2);
+
+        assert_eq!(store.acknowledge_inbox(Some(&[first.id])).await.unwrap(), 1);
+        let unread = store.list_inbox(true, 10, None).await.unwrap();
+        assert_eq!(unread.len(), 1);
+        assert_eq!(unread[0].id, second.id);
+
+        assert_eq!(store.acknowledge_inbox(None).await.unwrap(), 1);
+        assert_eq!(store.unread_inbox_count().await.unwrap(), 0);
+        let paged = store.list_inbox(false, 10, Some(second.id)).await.unwrap();
+        assert_eq!(paged.len(), 1);
+        assert!(paged[0].read_at.is_some());
+    }
+}
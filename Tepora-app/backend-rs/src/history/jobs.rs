@@ -0,0 +1,231 @@
+//! バックグラウンドジョブの記録（`background_jobs` テーブル）。
+//!
+//! ダウンロードや圧縮、定期取り込みなどを起動した時点で `running` の行を作り、
+//! 終わったら状態を書き換える。プロセスが落ちると `running` のまま残るので、
+//! 次の起動時にそれを拾って再開するか失敗扱いにする。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use super::HistoryStore;
+use crate::core::errors::ApiError;
+
+pub const JOB_RUNNING: &str = "running";
+pub const JOB_COMPLETED: &str = "completed";
+pub const JOB_FAILED: &str = "failed";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JobRecord {
+    pub id: String,
+    /// model_download / binary_update / memory_compaction / feed_ingest / db_maintenance
+    pub kind: String,
+    pub label: String,
+    /// running / completed / failed
+    pub status: String,
+    /// 再開に必要な入力
+    pub payload: Value,
+    pub error: Option<String>,
+    /// 起動回数。再開するたびに増える
+    pub attempts: i64,
+    pub started_at: String,
+    pub updated_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// 起動するジョブ。日時と状態はストアが付ける。
+#[derive(Debug, Clone, Default)]
+pub struct NewJob {
+    pub id: String,
+    pub kind: String,
+    pub label: String,
+    pub payload: Value,
+}
+
+pub(super) async fn init_jobs_table(pool: &SqlitePool) -> Result<(), ApiError> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS background_jobs (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            label TEXT NOT NULL DEFAULT '',
+            status TEXT NOT NULL,
+            payload TEXT NOT NULL DEFAULT 'null',
+            error TEXT,
+            attempts INTEGER NOT NULL DEFAULT 1,
+            started_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            finished_at TEXT
+        )",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Failed to init background_jobs table: {}", e)))?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_background_jobs_status ON background_jobs(status)")
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            ApiError::internal(format!("Failed to create background_jobs index: {}", e))
+        })?;
+    Ok(())
+}
+
+const JOB_COLUMNS: &str =
+    "id, kind, label, status, payload, error, attempts, started_at, updated_at, finished_at";
+
+impl HistoryStore {
+    pub async fn start_job(&self, job: &NewJob) -> Result<JobRecord, ApiError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let payload = serde_json::to_string(&job.payload).map_err(ApiError::internal)?;
+        sqlx::query(
+            "INSERT INTO background_jobs \
+             (id, kind, label, status, payload, attempts, started_at, updated_at) \
+             VALUES (?, ?, ?, ?, ?, 1, ?, ?)",
+        )
+        .bind(&job.id)
+        .bind(&job.kind)
+        .bind(&job.label)
+        .bind(JOB_RUNNING)
+        .bind(payload)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(JobRecord {
+            id: job.id.clone(),
+            kind: job.kind.clone(),
+            label: job.label.clone(),
+            status: JOB_RUNNING.to_string(),
+            payload: job.payload.clone(),
+            error: None,
+            attempts: 1,
+            started_at: now.clone(),
+            updated_at: now,
+            finished_at: None,
+        })
+    }
+
+    /// `running` の行を閉じる。`error` があれば失敗、無ければ完了。
+    pub async fn finish_job(&self, id: &str, error: Option<&str>) -> Result<bool, ApiError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let status = if error.is_some() {
+            JOB_FAILED
+        } else {
+            JOB_COMPLETED
+        };
+        let result = sqlx::query(
+            "UPDATE background_jobs SET status = ?, error = ?, updated_at = ?, finished_at = ? \
+             WHERE id = ? AND status = ?",
+        )
+        .bind(status)
+        .bind(error)
+        .bind(&now)
+        .bind(&now)
+        .bind(id)
+        .bind(JOB_RUNNING)
+        .execute(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 再開する行の起動回数を増やす。
+    pub async fn retry_job(&self, id: &str) -> Result<(), ApiError> {
+        sqlx::query(
+            "UPDATE background_jobs SET attempts = attempts + 1, updated_at = ? \
+             WHERE id = ? AND status = ?",
+        )
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(id)
+        .bind(JOB_RUNNING)
+        .execute(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(())
+    }
+
+    /// 起動した順。`status` を渡すとその状態だけ。
+    pub async fn list_jobs(
+        &self,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<JobRecord>, ApiError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {JOB_COLUMNS} FROM background_jobs WHERE (? IS NULL OR status = ?) \
+             ORDER BY started_at DESC, rowid DESC LIMIT ?"
+        ))
+        .bind(status)
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        let mut jobs: Vec<JobRecord> = rows.iter().map(job_from_row).collect();
+        jobs.reverse();
+        Ok(jobs)
+    }
+}
+
+fn job_from_row(row: &SqliteRow) -> JobRecord {
+    JobRecord {
+        id: row.try_get("id").unwrap_or_default(),
+        kind: row.try_get("kind").unwrap_or_default(),
+        label: row.try_get("label").unwrap_or_default(),
+        status: row.try_get("status").unwrap_or_default(),
+        payload: row
+            .try_get::<String, _>("payload")
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or(Value::Null),
+        error: row.try_get("error").unwrap_or_default(),
+        attempts: row.try_get("attempts").unwrap_or(1),
+        started_at: row.try_get("started_at").unwrap_or_default(),
+        updated_at: row.try_get("updated_at").unwrap_or_default(),
+        finished_at: row.try_get("finished_at").unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn jobs_track_status_and_attempts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history.db"))
+            .await
+            .unwrap();
+
+        for id in ["a", "b", "c"] {
+            store
+                .start_job(&NewJob {
+                    id: id.to_string(),
+                    kind: "model_download".to_string(),
+                    label: format!("job {id}"),
+                    payload: json!({ "tasks": [id] }),
+                })
+                .await
+                .unwrap();
+        }
+        assert!(store.finish_job("a", None).await.unwrap());
+        assert!(store.finish_job("b", Some("boom")).await.unwrap());
+        // 閉じた行は書き換えない
+        assert!(!store.finish_job("b", None).await.unwrap());
+        store.retry_job("c").await.unwrap();
+
+        let running = store.list_jobs(Some(JOB_RUNNING), 10).await.unwrap();
+        assert_eq!(running.len(), 1);
+        assert_eq!(running[0].id, "c");
+        assert_eq!(running[0].attempts, 2);
+        assert_eq!(running[0].payload, json!({ "tasks": ["c"] }));
+
+        let all = store.list_jobs(None, 10).await.unwrap();
+        let ids: Vec<&str> = all.iter().map(|job| job.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        assert_eq!(all[0].status, JOB_COMPLETED);
+        assert_eq!(all[1].status, JOB_FAILED);
+        assert_eq!(all[1].error.as_deref(), Some("boom"));
+        assert!(all[1].finished_at.is_some());
+    }
+}
@@ -0,0 +1,319 @@
+//! 取り込み時の履歴マージ。
+//!
+//! バックアップなどから読み込んだセッションを、既存の履歴と突き合わせて
+//! 追記すべきメッセージだけを選ぶ。メッセージは種別と本文のハッシュで比べ、
+//! 同じ ID のセッションは足りない分だけ追記し、ID が違っても中身が同じセッションは
+//! 重複として取り込まない。食い違いは `conflicts` に残す（ローカル側を優先する）。
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{HistoryMessage, SessionInfo};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportMergeReport {
+    /// 新しく作るセッション
+    pub created: usize,
+    /// 既存セッションへ追記するもの
+    pub merged: usize,
+    /// 既存と同じか、既存に含まれているもの
+    pub unchanged: usize,
+    /// ID は違うが中身が既存セッションと同じもの
+    pub duplicates: usize,
+    pub messages_added: usize,
+    pub messages_skipped: usize,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergeConflict {
+    pub session_id: String,
+    /// `diverged`（双方に相手の無いメッセージがある）/ `title` / `duplicate`
+    pub kind: String,
+    pub detail: String,
+}
+
+/// 1 セッション分の書き込み内容。
+#[derive(Debug, Clone)]
+pub struct MergeAction {
+    pub session_id: String,
+    /// 付けるタイトル。ローカルにタイトルがあるときは `None`
+    pub title: Option<String>,
+    pub messages: Vec<HistoryMessage>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MergePlan {
+    pub actions: Vec<MergeAction>,
+    pub report: ImportMergeReport,
+}
+
+struct Indexed {
+    id: String,
+    title: Option<String>,
+    hashes: Vec<String>,
+    digest: String,
+}
+
+impl Indexed {
+    fn new(session: &SessionInfo, messages: &[HistoryMessage]) -> Self {
+        let hashes = messages.iter().map(message_hash).collect::<Vec<_>>();
+        let digest = session_digest(&hashes);
+        Self {
+            id: session.id.clone(),
+            title: session.title.clone(),
+            hashes,
+            digest,
+        }
+    }
+}
+
+/// 種別と本文から求めるメッセージのハッシュ。ID や時刻は含めない。
+pub fn message_hash(message: &HistoryMessage) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(message.message_type.as_bytes());
+    hasher.update([0]);
+    hasher.update(message.content.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+fn session_digest(hashes: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    for hash in hashes {
+        hasher.update(hash.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// `local` は既存のセッション、`incoming` は取り込むセッション（どちらもメッセージは古い順）。
+pub fn plan_merge(
+    local: &[(SessionInfo, Vec<HistoryMessage>)],
+    incoming: &[(SessionInfo, Vec<HistoryMessage>)],
+) -> MergePlan {
+    let mut known = local
+        .iter()
+        .map(|(session, messages)| Indexed::new(session, messages))
+        .collect::<Vec<_>>();
+    let mut plan = MergePlan::default();
+
+    for (session, messages) in incoming {
+        let theirs = Indexed::new(session, messages);
+        if messages.is_empty() {
+            plan.report.unchanged += 1;
+            continue;
+        }
+
+        let Some(position) = known.iter().position(|ours| ours.id == theirs.id) else {
+            if let Some(same) = known.iter().find(|ours| ours.digest == theirs.digest) {
+                plan.report.duplicates += 1;
+                plan.report.messages_skipped += messages.len();
+                plan.report.conflicts.push(MergeConflict {
+                    session_id: theirs.id.clone(),
+                    kind: "duplicate".to_string(),
+                    detail: format!("same messages as session {}", same.id),
+                });
+                continue;
+            }
+            plan.report.created += 1;
+            plan.report.messages_added += messages.len();
+            plan.actions.push(MergeAction {
+                session_id: theirs.id.clone(),
+                title: theirs.title.clone(),
+                messages: messages.clone(),
+            });
+            known.push(theirs);
+            continue;
+        };
+
+        let ours = &mut known[position];
+        let title = match (&ours.title, &theirs.title) {
+            (None, Some(title)) => Some(title.clone()),
+            (Some(local), Some(remote)) if local != remote => {
+                plan.report.conflicts.push(MergeConflict {
+                    session_id: theirs.id.clone(),
+                    kind: "title".to_string(),
+                    detail: format!("kept local title \"{}\" over \"{}\"", local, remote),
+                });
+                None
+            }
+            _ => None,
+        };
+
+        let missing = if theirs.hashes.starts_with(&ours.hashes) {
+            // ローカルが取り込む側の途中まで：残りをそのまま足す
+            (ours.hashes.len()..messages.len()).collect::<Vec<_>>()
+        } else if ours.hashes.starts_with(&theirs.hashes) {
+            Vec::new()
+        } else {
+            let mut available = HashMap::<&str, usize>::new();
+            for hash in &ours.hashes {
+                *available.entry(hash.as_str()).or_default() += 1;
+            }
+            let missing = theirs
+                .hashes
+                .iter()
+                .enumerate()
+                .filter(|(_, hash)| match available.get_mut(hash.as_str()) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        false
+                    }
+                    _ => true,
+                })
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                plan.report.conflicts.push(MergeConflict {
+                    session_id: theirs.id.clone(),
+                    kind: "diverged".to_string(),
+                    detail: format!(
+                        "appended {} message(s) missing locally after the existing {}",
+                        missing.len(),
+                        ours.hashes.len()
+                    ),
+                });
+            }
+            missing
+        };
+
+        plan.report.messages_skipped += messages.len() - missing.len();
+        if missing.is_empty() && title.is_none() {
+            plan.report.unchanged += 1;
+            continue;
+        }
+        plan.report.merged += 1;
+        plan.report.messages_added += missing.len();
+        ours.hashes
+            .extend(missing.iter().map(|index| theirs.hashes[*index].clone()));
+        ours.digest = session_digest(&ours.hashes);
+        if title.is_some() {
+            ours.title = title.clone();
+        }
+        plan.actions.push(MergeAction {
+            session_id: theirs.id.clone(),
+            title,
+            messages: missing
+                .into_iter()
+                .map(|index| messages[index].clone())
+                .collect(),
+        });
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(
+        id: &str,
+        title: Option<&str>,
+        messages: &[(&str, &str)],
+    ) -> (SessionInfo, Vec<HistoryMessage>) {
+        let info = SessionInfo {
+            id: id.to_string(),
+            project_id: "default".to_string(),
+            title: title.map(str::to_string),
+            created_at: String::new(),
+            updated_at: String::new(),
+            metadata: None,
+            message_count: messages.len() as i64,
+            preview: None,
+        };
+        let messages = messages
+            .iter()
+            .enumerate()
+            .map(|(index, (kind, content))| HistoryMessage {
+                id: index as i64 + 1,
+                session_id: id.to_string(),
+                message_type: kind.to_string(),
+                content: content.to_string(),
+                created_at: String::new(),
+                additional_kwargs: None,
+            })
+            .collect();
+        (info, messages)
+    }
+
+    #[test]
+    fn merges_by_content_instead_of_duplicating() {
+        let local = vec![
+            session("a", Some("Trip"), &[("human", "hi"), ("ai", "hello")]),
+            session("b", None, &[("human", "q"), ("ai", "x")]),
+            session("c", None, &[("human", "same")]),
+        ];
+        let incoming = vec![
+            // 続きがある
+            session(
+                "a",
+                Some("Trip"),
+                &[("human", "hi"), ("ai", "hello"), ("human", "more")],
+            ),
+            // 分岐している
+            session("b", Some("Quiz"), &[("human", "q"), ("ai", "y")]),
+            // 既存に含まれる
+            session("c", None, &[]),
+            // 中身が c と同じ
+            session("d", None, &[("human", "same")]),
+            // 新規。同じ取り込み内の重複も 1 つにまとめる
+            session("e", Some("New"), &[("human", "new"), ("human", "new")]),
+            session("f", None, &[("human", "new"), ("human", "new")]),
+        ];
+
+        let plan = plan_merge(&local, &incoming);
+        let report = &plan.report;
+        assert_eq!(
+            (
+                report.created,
+                report.merged,
+                report.unchanged,
+                report.duplicates
+            ),
+            (1, 2, 1, 2)
+        );
+        assert_eq!(report.messages_added, 4);
+        assert_eq!(report.messages_skipped, 6);
+        let kinds = report
+            .conflicts
+            .iter()
+            .map(|conflict| (conflict.session_id.as_str(), conflict.kind.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![("b", "diverged"), ("d", "duplicate"), ("f", "duplicate")]
+        );
+
+        let actions = plan
+            .actions
+            .iter()
+            .map(|action| {
+                (
+                    action.session_id.as_str(),
+                    action.title.as_deref(),
+                    action
+                        .messages
+                        .iter()
+                        .map(|message| message.content.as_str())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![
+                ("a", None, vec!["more"]),
+                ("b", Some("Quiz"), vec!["y"]),
+                ("e", Some("New"), vec!["new", "new"]),
+            ]
+        );
+
+        // 同じものをもう一度取り込んでも何も増えない
+        let mut after = local.clone();
+        after[0] = incoming[0].clone();
+        let again = plan_merge(&after, &incoming[..1]);
+        assert!(again.actions.is_empty());
+        assert_eq!(again.report.unchanged, 1);
+    }
+}
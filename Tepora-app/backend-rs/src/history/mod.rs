@@ -1,3 +1,14 @@
+mod eval_runs;
+mod export;
+mod inbox;
+mod jobs;
+mod merge;
+mod partial;
+mod projects;
+mod source_quality;
+mod topics;
+mod usage;
+
 use std::path::PathBuf;
 
 use crate::models::event::AgentEvent;
@@ -7,6 +18,17 @@ use sqlx::{sqlite::SqlitePoolOptions, Row, SqlitePool};
 
 use crate::core::errors::ApiError;
 
+pub use eval_runs::{EvalRunRecord, NewEvalRun};
+pub use export::Transcript;
+pub use inbox::{InboxItem, NewInboxItem};
+pub use jobs::{JobRecord, NewJob, JOB_RUNNING};
+pub use merge::{plan_merge, ImportMergeReport};
+pub use partial::PartialMessage;
+pub use projects::ProjectSettings;
+pub use source_quality::DomainQuality;
+pub use topics::{NewTopic, SessionDigest, TopicRecord};
+pub use usage::SessionTokenUsage;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionInfo {
     pub id: String,
@@ -32,6 +54,76 @@ pub struct HistoryMessage {
     pub additional_kwargs: Option<Value>,
 }
 
+impl HistoryMessage {
+    /// ストリーミング途中で保存されたまま確定していないメッセージは `false`。
+    pub fn is_complete(&self) -> bool {
+        self.additional_kwargs
+            .as_ref()
+            .and_then(|kwargs| kwargs.get("is_complete"))
+            .and_then(Value::as_bool)
+            .unwrap_or(true)
+    }
+}
+
+/// セッションごとのローリング要約（"conversation so far"）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub summary: String,
+    /// 要約に取り込み済みの最後のメッセージ ID
+    pub covered_message_id: i64,
+    pub updated_at: String,
+}
+
+/// ツール単位の呼び出し統計（`GET /api/tools/stats`）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolInvocationStats {
+    pub tool: String,
+    /// native / mcp / http
+    pub source: String,
+    pub calls: i64,
+    pub errors: i64,
+    pub error_rate: f64,
+    pub p50_ms: i64,
+    pub p95_ms: i64,
+    pub p99_ms: i64,
+    pub last_called_at: String,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<String>,
+}
+
+impl ToolInvocationStats {
+    /// 集計期間内に 3 回以上呼ばれ、半分以上失敗しているツール。
+    pub fn is_chronically_failing(&self) -> bool {
+        self.calls >= 3 && self.error_rate >= 0.5
+    }
+}
+
+/// 特権操作の監査記録（`GET /api/audit`）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdminAuditEntry {
+    pub id: i64,
+    pub created_at: String,
+    /// 操作した主体（API キーの指紋・Origin など）
+    pub actor: Value,
+    pub action: String,
+    pub target: Option<String>,
+    pub outcome: String,
+    /// 変更内容。秘密の値は伏せ字で入る
+    pub details: Value,
+}
+
+/// 呼び出し記録の保持期間
+const TOOL_INVOCATION_RETENTION_DAYS: i64 = 30;
+
+fn percentile(sorted: &[i64], pct: f64) -> i64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
 #[derive(Clone)]
 pub struct HistoryStore {
     pool: SqlitePool,
@@ -95,6 +187,9 @@ impl HistoryStore {
         )
         .execute(&pool)
         .await;
+        let _ = sqlx::query("ALTER TABLE sessions ADD COLUMN persona_id TEXT")
+            .execute(&pool)
+            .await;
 
         sqlx::query(
             "CREATE TABLE IF NOT EXISTS agent_events (
@@ -118,6 +213,91 @@ impl HistoryStore {
         .await
         .map_err(|e| ApiError::internal(format!("Failed to create events index: {}", e)))?;
 
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS session_summaries (
+                session_id TEXT PRIMARY KEY,
+                summary TEXT NOT NULL,
+                covered_message_id INTEGER NOT NULL DEFAULT 0,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            )",
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            ApiError::internal(format!("Failed to init session_summaries table: {}", e))
+        })?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS tool_invocations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                tool TEXT NOT NULL,
+                source TEXT NOT NULL,
+                success INTEGER NOT NULL,
+                latency_ms INTEGER NOT NULL,
+                error TEXT,
+                created_at TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to init tool_invocations table: {}", e)))?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_tool_invocations_created_at ON tool_invocations(created_at)",
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to create tool index: {}", e)))?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS admin_audit (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at TEXT NOT NULL,
+                actor TEXT NOT NULL,
+                action TEXT NOT NULL,
+                target TEXT,
+                outcome TEXT NOT NULL,
+                details TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to init admin_audit table: {}", e)))?;
+
+        // 追記専用: 更新・削除はデータベース側で拒否する
+        for (trigger, operation) in [
+            ("admin_audit_no_update", "UPDATE"),
+            ("admin_audit_no_delete", "DELETE"),
+        ] {
+            sqlx::query(&format!(
+                "CREATE TRIGGER IF NOT EXISTS {trigger} BEFORE {operation} ON admin_audit
+                 BEGIN SELECT RAISE(ABORT, 'admin_audit is append-only'); END"
+            ))
+            .execute(&pool)
+            .await
+            .map_err(|e| ApiError::internal(format!("Failed to create audit trigger: {}", e)))?;
+        }
+
+        // 書き込みできるかの確認専用。1 行だけを上書きし続ける
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS health_probe (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                checked_at TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to init health_probe table: {}", e)))?;
+
+        projects::init_projects_table(&pool).await?;
+        inbox::init_inbox_table(&pool).await?;
+        eval_runs::init_eval_runs_table(&pool).await?;
+        jobs::init_jobs_table(&pool).await?;
+        topics::init_topics_table(&pool).await?;
+        usage::init_usage_table(&pool).await?;
+        source_quality::init_source_quality_table(&pool).await?;
+
         Ok(Self { pool })
     }
 
@@ -345,6 +525,27 @@ impl HistoryStore {
         Ok(result.last_insert_rowid())
     }
 
+    pub async fn update_message(
+        &self,
+        message_id: i64,
+        content: &str,
+        additional_kwargs: Option<Value>,
+    ) -> Result<(), ApiError> {
+        sqlx::query("UPDATE messages SET content = ?, additional_kwargs = ? WHERE id = ?")
+            .bind(content)
+            .bind(additional_kwargs)
+            .bind(message_id)
+            .execute(&self.pool)
+            .await
+            .map_err(ApiError::internal)?;
+        Ok(())
+    }
+
+    /// ストリーミング中の応答を途中保存するための書き出し口。
+    pub fn partial_message(&self, session_id: &str, additional_kwargs: Value) -> PartialMessage {
+        PartialMessage::new(self.clone(), session_id.to_string(), additional_kwargs)
+    }
+
     pub async fn get_history(
         &self,
         session_id: &str,
@@ -384,6 +585,40 @@ impl HistoryStore {
         Ok(messages)
     }
 
+    /// `after_id < id < before_id` の範囲のメッセージを古い順に返す。
+    pub async fn get_messages_between(
+        &self,
+        session_id: &str,
+        after_id: i64,
+        before_id: i64,
+    ) -> Result<Vec<HistoryMessage>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT * FROM messages WHERE session_id = ? AND id > ? AND id < ? ORDER BY id ASC",
+        )
+        .bind(session_id)
+        .bind(after_id)
+        .bind(before_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+
+        let mut messages = Vec::new();
+        for row in rows {
+            messages.push(HistoryMessage {
+                id: row.try_get::<i64, _>("id").unwrap_or_default(),
+                session_id: row.try_get::<String, _>("session_id").unwrap_or_default(),
+                message_type: row.try_get::<String, _>("role").unwrap_or_default(),
+                content: row.try_get::<String, _>("content").unwrap_or_default(),
+                created_at: row.try_get::<String, _>("created_at").unwrap_or_default(),
+                additional_kwargs: row
+                    .try_get::<Option<Value>, _>("additional_kwargs")
+                    .unwrap_or(None),
+            });
+        }
+
+        Ok(messages)
+    }
+
     pub async fn get_last_user_message(
         &self,
         session_id: &str,
@@ -448,6 +683,180 @@ impl HistoryStore {
         Ok(count)
     }
 
+    /// データベースに書き込めるかを確かめる。
+    pub async fn probe_writable(&self) -> Result<(), ApiError> {
+        sqlx::query("INSERT OR REPLACE INTO health_probe (id, checked_at) VALUES (1, ?)")
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| ApiError::internal(format!("History db is not writable: {}", e)))?;
+        Ok(())
+    }
+
+    pub async fn record_tool_invocation(
+        &self,
+        tool: &str,
+        source: &str,
+        success: bool,
+        latency_ms: i64,
+        error: Option<&str>,
+    ) -> Result<(), ApiError> {
+        let now = chrono::Utc::now();
+        let inserted = sqlx::query(
+            "INSERT INTO tool_invocations (tool, source, success, latency_ms, error, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(tool)
+        .bind(source)
+        .bind(success as i64)
+        .bind(latency_ms)
+        .bind(error)
+        .bind(now.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+
+        // 古い記録はときどきまとめて捨てる
+        if inserted.last_insert_rowid() % 256 == 0 {
+            let cutoff = now - chrono::Duration::days(TOOL_INVOCATION_RETENTION_DAYS);
+            sqlx::query("DELETE FROM tool_invocations WHERE created_at < ?")
+                .bind(cutoff.to_rfc3339())
+                .execute(&self.pool)
+                .await
+                .map_err(ApiError::internal)?;
+        }
+        Ok(())
+    }
+
+    /// `since`（RFC3339）以降の呼び出しをツールごとに集計する。呼び出し数の多い順。
+    pub async fn tool_invocation_stats(
+        &self,
+        since: Option<&str>,
+    ) -> Result<Vec<ToolInvocationStats>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT tool, source, success, latency_ms, error, created_at
+             FROM tool_invocations WHERE created_at >= ? ORDER BY created_at ASC",
+        )
+        .bind(since.unwrap_or(""))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+
+        let mut order = Vec::<String>::new();
+        let mut grouped =
+            std::collections::HashMap::<String, (ToolInvocationStats, Vec<i64>)>::new();
+        for row in rows {
+            let tool: String = row.get("tool");
+            let success: i64 = row.get("success");
+            let latency_ms: i64 = row.get("latency_ms");
+            let created_at: String = row.get("created_at");
+            let entry = grouped.entry(tool.clone()).or_insert_with(|| {
+                order.push(tool.clone());
+                (
+                    ToolInvocationStats {
+                        tool: tool.clone(),
+                        source: row.get("source"),
+                        calls: 0,
+                        errors: 0,
+                        error_rate: 0.0,
+                        p50_ms: 0,
+                        p95_ms: 0,
+                        p99_ms: 0,
+                        last_called_at: String::new(),
+                        last_error: None,
+                        last_error_at: None,
+                    },
+                    Vec::new(),
+                )
+            });
+            entry.0.calls += 1;
+            entry.1.push(latency_ms);
+            if success == 0 {
+                entry.0.errors += 1;
+                entry.0.last_error = row.get("error");
+                entry.0.last_error_at = Some(created_at.clone());
+            }
+            entry.0.last_called_at = created_at;
+        }
+
+        let mut stats = order
+            .into_iter()
+            .filter_map(|tool| grouped.remove(&tool))
+            .map(|(mut stat, mut latencies)| {
+                latencies.sort_unstable();
+                stat.p50_ms = percentile(&latencies, 50.0);
+                stat.p95_ms = percentile(&latencies, 95.0);
+                stat.p99_ms = percentile(&latencies, 99.0);
+                stat.error_rate = stat.errors as f64 / stat.calls as f64;
+                stat
+            })
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.tool.cmp(&b.tool)));
+        Ok(stats)
+    }
+
+    pub async fn record_admin_audit(
+        &self,
+        actor: &Value,
+        action: &str,
+        target: Option<&str>,
+        outcome: &str,
+        details: &Value,
+    ) -> Result<i64, ApiError> {
+        let inserted = sqlx::query(
+            "INSERT INTO admin_audit (created_at, actor, action, target, outcome, details)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(actor.to_string())
+        .bind(action)
+        .bind(target)
+        .bind(outcome)
+        .bind(details.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(inserted.last_insert_rowid())
+    }
+
+    /// 新しい順に返す。`before_id` より古いものだけに絞ってページングする。
+    pub async fn list_admin_audit(
+        &self,
+        limit: i64,
+        before_id: Option<i64>,
+        action: Option<&str>,
+    ) -> Result<Vec<AdminAuditEntry>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT id, created_at, actor, action, target, outcome, details FROM admin_audit
+             WHERE id < ? AND (? IS NULL OR action = ?)
+             ORDER BY id DESC LIMIT ?",
+        )
+        .bind(before_id.unwrap_or(i64::MAX))
+        .bind(action)
+        .bind(action)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let actor: String = row.get("actor");
+                let details: String = row.get("details");
+                AdminAuditEntry {
+                    id: row.get("id"),
+                    created_at: row.get("created_at"),
+                    actor: serde_json::from_str(&actor).unwrap_or(Value::Null),
+                    action: row.get("action"),
+                    target: row.get("target"),
+                    outcome: row.get("outcome"),
+                    details: serde_json::from_str(&details).unwrap_or(Value::Null),
+                }
+            })
+            .collect())
+    }
+
     pub async fn save_agent_event(&self, event: &AgentEvent) -> Result<(), ApiError> {
         let created_at = event.created_at.to_rfc3339();
         let metadata_str = serde_json::to_string(&event.metadata).unwrap_or_else(|_| "{}".into());
@@ -477,37 +886,46 @@ impl HistoryStore {
                 .await
                 .map_err(ApiError::internal)?;
 
-        let mut events = Vec::new();
-        for row in rows {
-            let metadata_str = row
-                .try_get::<String, _>("metadata")
-                .unwrap_or_else(|_| "{}".to_string());
-            let metadata: serde_json::Value =
-                serde_json::from_str(&metadata_str).unwrap_or_else(|_| serde_json::json!({}));
-
-            let event_type_str = row
-                .try_get::<String, _>("event_type")
-                .unwrap_or_else(|_| "error".to_string());
-            let event_type = crate::models::event::AgentEventType::parse(&event_type_str)
-                .unwrap_or(crate::models::event::AgentEventType::Error);
-
-            let created_at_str = row
-                .try_get::<String, _>("created_at")
-                .unwrap_or_else(|_| chrono::Utc::now().to_rfc3339());
-            let created_at = chrono::DateTime::parse_from_rfc3339(&created_at_str)
-                .map(|dt| dt.with_timezone(&chrono::Utc))
-                .unwrap_or_else(|_| chrono::Utc::now());
-
-            events.push(AgentEvent {
-                id: row.try_get::<String, _>("id").unwrap_or_default(),
-                session_id: row.try_get::<String, _>("session_id").unwrap_or_default(),
-                node_name: row.try_get::<String, _>("node_name").unwrap_or_default(),
-                event_type,
-                metadata,
-                created_at,
-            });
-        }
-        Ok(events)
+        Ok(rows.iter().map(agent_event_from_row).collect())
+    }
+
+    /// Events of `event_type` whose metadata `agent_id` matches, oldest first.
+    pub async fn get_agent_events_for_agent(
+        &self,
+        agent_id: &str,
+        event_type: crate::models::event::AgentEventType,
+    ) -> Result<Vec<AgentEvent>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT * FROM agent_events
+             WHERE event_type = ? AND json_extract(metadata, '$.agent_id') = ?
+             ORDER BY created_at ASC",
+        )
+        .bind(event_type.as_str())
+        .bind(agent_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(rows.iter().map(agent_event_from_row).collect())
+    }
+
+    /// Latest prompt event that recorded a context composition report.
+    pub async fn latest_context_composition(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<AgentEvent>, ApiError> {
+        let row = sqlx::query(
+            "SELECT * FROM agent_events
+             WHERE session_id = ? AND event_type = ?
+               AND json_extract(metadata, '$.context_composition') IS NOT NULL
+             ORDER BY created_at DESC
+             LIMIT 1",
+        )
+        .bind(session_id)
+        .bind(crate::models::event::AgentEventType::PromptGenerated.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(row.as_ref().map(agent_event_from_row))
     }
 
     pub async fn touch_session(&self, session_id: &str) -> Result<(), ApiError> {
@@ -531,6 +949,118 @@ impl HistoryStore {
             .await
             .map_err(ApiError::internal)
     }
+
+    pub async fn get_session_persona(&self, session_id: &str) -> Result<Option<String>, ApiError> {
+        let persona: Option<Option<String>> =
+            sqlx::query_scalar("SELECT persona_id FROM sessions WHERE id = ?")
+                .bind(session_id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(ApiError::internal)?;
+        Ok(persona.flatten())
+    }
+
+    /// Pin a persona to the session (`None` falls back to `active_character`).
+    pub async fn set_session_persona(
+        &self,
+        session_id: &str,
+        persona_id: Option<&str>,
+    ) -> Result<(), ApiError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query(
+            "INSERT OR IGNORE INTO sessions (id, project_id, created_at, updated_at) VALUES (?, 'default', ?, ?)",
+        )
+        .bind(session_id)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        sqlx::query("UPDATE sessions SET persona_id = ? WHERE id = ?")
+            .bind(persona_id)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await
+            .map_err(ApiError::internal)?;
+        Ok(())
+    }
+
+    pub async fn get_session_summary(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<SessionSummary>, ApiError> {
+        let row = sqlx::query(
+            "SELECT session_id, summary, covered_message_id, updated_at FROM session_summaries WHERE session_id = ?",
+        )
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+
+        Ok(row.map(|row| SessionSummary {
+            session_id: row.try_get::<String, _>("session_id").unwrap_or_default(),
+            summary: row.try_get::<String, _>("summary").unwrap_or_default(),
+            covered_message_id: row
+                .try_get::<i64, _>("covered_message_id")
+                .unwrap_or_default(),
+            updated_at: row.try_get::<String, _>("updated_at").unwrap_or_default(),
+        }))
+    }
+
+    pub async fn save_session_summary(
+        &self,
+        session_id: &str,
+        summary: &str,
+        covered_message_id: i64,
+    ) -> Result<(), ApiError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query(
+            "INSERT INTO session_summaries (session_id, summary, covered_message_id, updated_at)
+             VALUES (?, ?, ?, ?)
+             ON CONFLICT(session_id) DO UPDATE SET
+                summary = excluded.summary,
+                covered_message_id = excluded.covered_message_id,
+                updated_at = excluded.updated_at",
+        )
+        .bind(session_id)
+        .bind(summary)
+        .bind(covered_message_id)
+        .bind(now)
+        .execute(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(())
+    }
+}
+
+fn agent_event_from_row(row: &sqlx::sqlite::SqliteRow) -> AgentEvent {
+    let metadata_str = row
+        .try_get::<String, _>("metadata")
+        .unwrap_or_else(|_| "{}".to_string());
+    let metadata: serde_json::Value =
+        serde_json::from_str(&metadata_str).unwrap_or_else(|_| serde_json::json!({}));
+
+    let event_type_str = row
+        .try_get::<String, _>("event_type")
+        .unwrap_or_else(|_| "error".to_string());
+    let event_type = crate::models::event::AgentEventType::parse(&event_type_str)
+        .unwrap_or(crate::models::event::AgentEventType::Error);
+
+    let created_at_str = row
+        .try_get::<String, _>("created_at")
+        .unwrap_or_else(|_| chrono::Utc::now().to_rfc3339());
+    let created_at = chrono::DateTime::parse_from_rfc3339(&created_at_str)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|_| chrono::Utc::now());
+
+    AgentEvent {
+        id: row.try_get::<String, _>("id").unwrap_or_default(),
+        session_id: row.try_get::<String, _>("session_id").unwrap_or_default(),
+        node_name: row.try_get::<String, _>("node_name").unwrap_or_default(),
+        event_type,
+        metadata,
+        created_at,
+    }
 }
 
 fn resolve_session_title(
@@ -575,3 +1105,198 @@ fn format_session_timestamp(value: &str) -> String {
         .map(|timestamp| timestamp.format("%Y-%m-%d %H:%M").to_string())
         .unwrap_or_else(|_| value.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn session_summary_roundtrip_and_message_range() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history.db"))
+            .await
+            .unwrap();
+
+        let mut ids = Vec::new();
+        for index in 0..5 {
+            ids.push(
+                store
+                    .add_message("s1", "human", &format!("message {index}"), None)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let between = store
+            .get_messages_between("s1", ids[0], ids[3])
+            .await
+            .unwrap();
+        assert_eq!(
+            between.iter().map(|m| m.id).collect::<Vec<_>>(),
+            vec![ids[1], ids[2]]
+        );
+
+        assert!(store.get_session_summary("s1").await.unwrap().is_none());
+        store
+            .save_session_summary("s1", "first", ids[1])
+            .await
+            .unwrap();
+        store
+            .save_session_summary("s1", "second", ids[2])
+            .await
+            .unwrap();
+
+        let summary = store.get_session_summary("s1").await.unwrap().unwrap();
+        assert_eq!(summary.summary, "second");
+        assert_eq!(summary.covered_message_id, ids[2]);
+
+        store.delete_session("s1").await.unwrap();
+        assert!(store.get_session_summary("s1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn agent_events_can_be_filtered_by_agent() {
+        use crate::models::event::AgentEventType;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history.db"))
+            .await
+            .unwrap();
+        store.add_message("s1", "human", "hi", None).await.unwrap();
+
+        for (agent_id, event_type) in [
+            ("coder", AgentEventType::Evaluation),
+            ("writer", AgentEventType::Evaluation),
+            ("coder", AgentEventType::ToolCall),
+        ] {
+            store
+                .save_agent_event(&AgentEvent {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    session_id: "s1".to_string(),
+                    node_name: "agent_evaluation".to_string(),
+                    event_type,
+                    metadata: serde_json::json!({"agent_id": agent_id, "score": 7}),
+                    created_at: chrono::Utc::now(),
+                })
+                .await
+                .unwrap();
+        }
+
+        let events = store
+            .get_agent_events_for_agent("coder", AgentEventType::Evaluation)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].metadata["score"], 7);
+    }
+
+    #[tokio::test]
+    async fn session_persona_can_be_pinned_and_cleared() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history.db"))
+            .await
+            .unwrap();
+
+        assert!(store.get_session_persona("s1").await.unwrap().is_none());
+        store
+            .set_session_persona("s1", Some("shigure"))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_session_persona("s1").await.unwrap().as_deref(),
+            Some("shigure")
+        );
+        assert!(store.get_session("s1").await.unwrap().is_some());
+
+        store.set_session_persona("s1", None).await.unwrap();
+        assert!(store.get_session_persona("s1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn tool_invocation_stats_aggregate_latency_and_errors() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history.db"))
+            .await
+            .unwrap();
+
+        for latency in [10, 20, 30, 40] {
+            store
+                .record_tool_invocation("native_search", "native", true, latency, None)
+                .await
+                .unwrap();
+        }
+        store
+            .record_tool_invocation("weather", "http", false, 500, Some("HTTP 502"))
+            .await
+            .unwrap();
+        store
+            .record_tool_invocation("weather", "http", false, 700, Some("timeout"))
+            .await
+            .unwrap();
+        store
+            .record_tool_invocation("weather", "http", true, 100, None)
+            .await
+            .unwrap();
+
+        let stats = store.tool_invocation_stats(None).await.unwrap();
+        assert_eq!(stats.len(), 2);
+        let search = &stats[0];
+        assert_eq!(search.tool, "native_search");
+        assert_eq!(search.calls, 4);
+        assert_eq!(search.p50_ms, 20);
+        assert_eq!(search.p95_ms, 40);
+        assert!(!search.is_chronically_failing());
+
+        let weather = &stats[1];
+        assert_eq!(weather.errors, 2);
+        assert_eq!(weather.last_error.as_deref(), Some("timeout"));
+        assert!(weather.is_chronically_failing());
+
+        let future = (chrono::Utc::now() + chrono::Duration::hours(1)).to_rfc3339();
+        assert!(store
+            .tool_invocation_stats(Some(&future))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn admin_audit_is_append_only_and_paginates() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history.db"))
+            .await
+            .unwrap();
+        let actor = serde_json::json!({"key_fingerprint": "abc"});
+
+        for action in ["config_update", "model_delete", "config_update"] {
+            store
+                .record_admin_audit(&actor, action, None, "success", &Value::Null)
+                .await
+                .unwrap();
+        }
+
+        let latest = store.list_admin_audit(2, None, None).await.unwrap();
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0].id, 3);
+        assert_eq!(latest[0].actor, actor);
+        let older = store
+            .list_admin_audit(10, Some(latest[1].id), None)
+            .await
+            .unwrap();
+        assert_eq!(older.len(), 1);
+        let config_only = store
+            .list_admin_audit(10, None, Some("config_update"))
+            .await
+            .unwrap();
+        assert_eq!(config_only.len(), 2);
+
+        assert!(sqlx::query("DELETE FROM admin_audit")
+            .execute(&store.pool)
+            .await
+            .is_err());
+        assert!(sqlx::query("UPDATE admin_audit SET outcome = 'x'")
+            .execute(&store.pool)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! ストリーミング中のアシスタント応答の途中保存。
+//!
+//! 生成途中の本文を一定間隔で `is_complete: false` 付きのメッセージとして書き出し、
+//! 完了時に最終出力で確定させる。途中でプロセスが落ちても、履歴には
+//! そこまでの回答が残る。
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use super::HistoryStore;
+use crate::core::errors::ApiError;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 書き出し口のハンドル。ストリーマーと生成を呼び出した側で共有できるよう、
+/// 複製しても同じ途中保存を指す。
+#[derive(Clone)]
+pub struct PartialMessage {
+    inner: Arc<Mutex<PartialState>>,
+}
+
+struct PartialState {
+    store: HistoryStore,
+    session_id: String,
+    kwargs: Value,
+    content: String,
+    /// 最初の書き出しで行を作る。チャンクが来なければ空の行は残さない
+    message_id: Option<i64>,
+    flushed_len: usize,
+    last_flush: Option<Instant>,
+    /// 確定後に届いたチャンクで上書きしないよう、以降の書き出しは止める
+    finished: bool,
+}
+
+impl PartialMessage {
+    pub(super) fn new(store: HistoryStore, session_id: String, kwargs: Value) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(PartialState {
+                store,
+                session_id,
+                kwargs,
+                content: String::new(),
+                message_id: None,
+                flushed_len: 0,
+                last_flush: None,
+                finished: false,
+            })),
+        }
+    }
+
+    pub async fn message_id(&self) -> Option<i64> {
+        self.inner.lock().await.message_id
+    }
+
+    /// チャンクを追記し、前回の書き出しから間隔が空いていれば保存する。
+    pub async fn push(&self, chunk: &str) -> Result<(), ApiError> {
+        let mut state = self.inner.lock().await;
+        if state.finished {
+            return Ok(());
+        }
+        state.content.push_str(chunk);
+        let due = state
+            .last_flush
+            .is_none_or(|last| last.elapsed() >= FLUSH_INTERVAL);
+        if due {
+            state.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// 溜まっている本文を未完了のまま保存する。
+    pub async fn flush(&self) -> Result<(), ApiError> {
+        self.inner.lock().await.flush().await
+    }
+
+    /// 最終出力で確定させ、メッセージ ID を返す。
+    pub async fn finish(&self, output: &str, kwargs: Value) -> Result<i64, ApiError> {
+        let mut state = self.inner.lock().await;
+        let kwargs = with_completion(&kwargs, true);
+        let id = match state.message_id {
+            Some(id) => {
+                state.store.update_message(id, output, Some(kwargs)).await?;
+                id
+            }
+            None => {
+                state
+                    .store
+                    .add_message(&state.session_id, "ai", output, Some(kwargs))
+                    .await?
+            }
+        };
+        state.message_id = Some(id);
+        state.finished = true;
+        Ok(id)
+    }
+}
+
+impl PartialState {
+    async fn flush(&mut self) -> Result<(), ApiError> {
+        if self.finished || self.content.len() == self.flushed_len {
+            return Ok(());
+        }
+        let kwargs = with_completion(&self.kwargs, false);
+        match self.message_id {
+            Some(id) => {
+                self.store
+                    .update_message(id, &self.content, Some(kwargs))
+                    .await?
+            }
+            None => {
+                self.message_id = Some(
+                    self.store
+                        .add_message(&self.session_id, "ai", &self.content, Some(kwargs))
+                        .await?,
+                );
+            }
+        }
+        self.flushed_len = self.content.len();
+        self.last_flush = Some(Instant::now());
+        Ok(())
+    }
+}
+
+fn with_completion(kwargs: &Value, complete: bool) -> Value {
+    let mut kwargs = match kwargs {
+        Value::Object(_) => kwargs.clone(),
+        _ => json!({}),
+    };
+    kwargs["is_complete"] = json!(complete);
+    kwargs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn partial_message_is_flushed_then_finalized() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history.db"))
+            .await
+            .unwrap();
+
+        let partial = store.partial_message("s1", json!({"mode": "chat"}));
+        partial.flush().await.unwrap();
+        assert!(partial.message_id().await.is_none());
+
+        partial.push("Hello").await.unwrap();
+        // 間隔内のチャンクはまだ書き出さない
+        partial.push(", wor").await.unwrap();
+        let saved = store.get_history("s1", 0).await.unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].content, "Hello");
+        assert!(!saved[0].is_complete());
+
+        // 落ちる直前の書き出しまでは残る
+        partial.flush().await.unwrap();
+        assert_eq!(
+            store.get_history("s1", 0).await.unwrap()[0].content,
+            "Hello, wor"
+        );
+
+        let id = partial
+            .finish("Hello, world", json!({"mode": "chat"}))
+            .await
+            .unwrap();
+        let saved = store.get_history("s1", 0).await.unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].id, id);
+        assert_eq!(saved[0].content, "Hello, world");
+        assert!(saved[0].is_complete());
+
+        // 確定後のチャンクは無視する
+        partial.push("!").await.unwrap();
+        partial.flush().await.unwrap();
+        assert_eq!(
+            store.get_history("s1", 0).await.unwrap()[0].content,
+            "Hello, world"
+        );
+    }
+}
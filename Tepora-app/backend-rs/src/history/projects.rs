@@ -0,0 +1,195 @@
+//! プロジェクトごとの紐付け（`projects` テーブル）。
+//!
+//! プロジェクト自体（ID・名前・フォルダ）は `WorkspaceManager` が管理し、
+//! ここにはセッションの外にある設定だけを置く。行が無いプロジェクトは既定値で扱う。
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use super::HistoryStore;
+use crate::core::config::schema::SessionDefaults;
+use crate::core::errors::ApiError;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ProjectSettings {
+    pub project_id: String,
+    /// このプロジェクトのセッションで毎回システムプロンプトに入れる文脈
+    pub pinned_context: String,
+    /// `rag_search` で参照できる追加のコレクション
+    pub rag_collections: Vec<String>,
+    /// ファイルツールをこのフォルダに絞る（`tools.workspace_roots` の内側に限る）
+    pub workspace_folders: Vec<String>,
+    pub default_agent_id: Option<String>,
+    pub default_model_id: Option<String>,
+    /// `session_defaults` のうち、このプロジェクトで差し替える項目
+    #[serde(default)]
+    pub session_defaults: SessionDefaults,
+    pub updated_at: Option<String>,
+}
+
+pub(super) async fn init_projects_table(pool: &SqlitePool) -> Result<(), ApiError> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS projects (
+            id TEXT PRIMARY KEY,
+            pinned_context TEXT NOT NULL DEFAULT '',
+            rag_collections TEXT NOT NULL DEFAULT '[]',
+            workspace_folders TEXT NOT NULL DEFAULT '[]',
+            default_agent_id TEXT,
+            default_model_id TEXT,
+            updated_at TEXT NOT NULL
+        )",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Failed to init projects table: {}", e)))?;
+    let _ =
+        sqlx::query("ALTER TABLE projects ADD COLUMN session_defaults TEXT NOT NULL DEFAULT '{}'")
+            .execute(pool)
+            .await;
+    Ok(())
+}
+
+impl HistoryStore {
+    pub async fn get_project_settings(
+        &self,
+        project_id: &str,
+    ) -> Result<ProjectSettings, ApiError> {
+        let row = sqlx::query(
+            "SELECT pinned_context, rag_collections, workspace_folders, default_agent_id, \
+             default_model_id, session_defaults, updated_at FROM projects WHERE id = ?",
+        )
+        .bind(project_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        let Some(row) = row else {
+            return Ok(ProjectSettings {
+                project_id: project_id.to_string(),
+                ..ProjectSettings::default()
+            });
+        };
+        Ok(ProjectSettings {
+            project_id: project_id.to_string(),
+            pinned_context: row.try_get("pinned_context").unwrap_or_default(),
+            rag_collections: json_list(row.try_get("rag_collections").ok()),
+            workspace_folders: json_list(row.try_get("workspace_folders").ok()),
+            default_agent_id: row.try_get("default_agent_id").ok().flatten(),
+            default_model_id: row.try_get("default_model_id").ok().flatten(),
+            session_defaults: row
+                .try_get::<String, _>("session_defaults")
+                .ok()
+                .and_then(|raw| serde_json::from_str(&raw).ok())
+                .unwrap_or_default(),
+            updated_at: row.try_get("updated_at").ok(),
+        })
+    }
+
+    pub async fn save_project_settings(
+        &self,
+        settings: &ProjectSettings,
+    ) -> Result<ProjectSettings, ApiError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let rag_collections =
+            serde_json::to_string(&settings.rag_collections).map_err(ApiError::internal)?;
+        let workspace_folders =
+            serde_json::to_string(&settings.workspace_folders).map_err(ApiError::internal)?;
+        let session_defaults =
+            serde_json::to_string(&settings.session_defaults).map_err(ApiError::internal)?;
+        sqlx::query(
+            "INSERT INTO projects (id, pinned_context, rag_collections, workspace_folders, \
+             default_agent_id, default_model_id, session_defaults, updated_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+             ON CONFLICT(id) DO UPDATE SET pinned_context = excluded.pinned_context, \
+             rag_collections = excluded.rag_collections, \
+             workspace_folders = excluded.workspace_folders, \
+             default_agent_id = excluded.default_agent_id, \
+             default_model_id = excluded.default_model_id, \
+             session_defaults = excluded.session_defaults, \
+             updated_at = excluded.updated_at",
+        )
+        .bind(&settings.project_id)
+        .bind(&settings.pinned_context)
+        .bind(rag_collections)
+        .bind(workspace_folders)
+        .bind(&settings.default_agent_id)
+        .bind(&settings.default_model_id)
+        .bind(session_defaults)
+        .bind(&now)
+        .execute(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(ProjectSettings {
+            updated_at: Some(now),
+            ..settings.clone()
+        })
+    }
+
+    /// セッションの所属を変える。セッションが無ければ `false`。
+    pub async fn move_session_to_project(
+        &self,
+        session_id: &str,
+        project_id: &str,
+    ) -> Result<bool, ApiError> {
+        let result = sqlx::query("UPDATE sessions SET project_id = ? WHERE id = ?")
+            .bind(project_id)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await
+            .map_err(ApiError::internal)?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn json_list(raw: Option<String>) -> Vec<String> {
+    raw.and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn settings_default_until_saved_and_sessions_can_move() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history.db"))
+            .await
+            .unwrap();
+
+        let empty = store.get_project_settings("p1").await.unwrap();
+        assert_eq!(empty.project_id, "p1");
+        assert!(empty.pinned_context.is_empty() && empty.updated_at.is_none());
+
+        let saved = store
+            .save_project_settings(&ProjectSettings {
+                project_id: "p1".to_string(),
+                pinned_context: "Use British spelling.".to_string(),
+                rag_collections: vec!["feeds".to_string()],
+                default_agent_id: Some("coder".to_string()),
+                session_defaults: SessionDefaults {
+                    mode: Some("search".to_string()),
+                    temperature: Some(0.2),
+                    ..SessionDefaults::default()
+                },
+                ..ProjectSettings::default()
+            })
+            .await
+            .unwrap();
+        let loaded = store.get_project_settings("p1").await.unwrap();
+        assert_eq!(loaded, saved);
+
+        let session_id = store.create_session(None, "default").await.unwrap();
+        assert!(store
+            .move_session_to_project(&session_id, "p1")
+            .await
+            .unwrap());
+        assert_eq!(
+            store.get_session_project_id(&session_id).await.unwrap(),
+            Some("p1".to_string())
+        );
+        assert!(!store
+            .move_session_to_project("missing", "p1")
+            .await
+            .unwrap());
+    }
+}
@@ -0,0 +1,159 @@
+//! 検索出典のドメインごとの質（`search_source_quality` テーブル）。
+//!
+//! 回答のプロンプトに入れた出典ごとに `injected` を、回答が `[S1]` の形で実際に
+//! 引用したものは `cited` も 1 つ増やす。検索結果の並べ替えはこの割合を使う。
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use super::HistoryStore;
+use crate::core::errors::ApiError;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DomainQuality {
+    pub domain: String,
+    pub injected: i64,
+    pub cited: i64,
+}
+
+impl DomainQuality {
+    /// 引用された割合。記録の少ないドメインが極端な値にならないよう、
+    /// 入れて 1 回引用されたものと 1 回されなかったものを足して数える（記録なしで 0.5）。
+    pub fn score(&self) -> f32 {
+        (self.cited.max(0) + 1) as f32 / (self.injected.max(0) + 2) as f32
+    }
+}
+
+pub(super) async fn init_source_quality_table(pool: &SqlitePool) -> Result<(), ApiError> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS search_source_quality (
+            domain TEXT PRIMARY KEY,
+            injected INTEGER NOT NULL DEFAULT 0,
+            cited INTEGER NOT NULL DEFAULT 0,
+            updated_at TEXT NOT NULL
+        )",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        ApiError::internal(format!("Failed to init search_source_quality table: {}", e))
+    })?;
+    Ok(())
+}
+
+impl HistoryStore {
+    /// 1 回の回答で入れた出典のドメインと、それが引用されたか。同じドメインが
+    /// 複数あればその数だけ数える。
+    pub async fn record_source_citations(
+        &self,
+        outcomes: &[(String, bool)],
+    ) -> Result<(), ApiError> {
+        let mut totals: BTreeMap<&str, (i64, i64)> = BTreeMap::new();
+        for (domain, cited) in outcomes {
+            let entry = totals.entry(domain.as_str()).or_default();
+            entry.0 += 1;
+            entry.1 += *cited as i64;
+        }
+        if totals.is_empty() {
+            return Ok(());
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await.map_err(ApiError::internal)?;
+        for (domain, (injected, cited)) in totals {
+            sqlx::query(
+                "INSERT INTO search_source_quality (domain, injected, cited, updated_at) \
+                 VALUES (?, ?, ?, ?) \
+                 ON CONFLICT(domain) DO UPDATE SET \
+                 injected = injected + excluded.injected, \
+                 cited = cited + excluded.cited, \
+                 updated_at = excluded.updated_at",
+            )
+            .bind(domain)
+            .bind(injected)
+            .bind(cited)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await
+            .map_err(ApiError::internal)?;
+        }
+        tx.commit().await.map_err(ApiError::internal)
+    }
+
+    /// `domains` のうち記録のあるものだけを返す。
+    pub async fn source_quality(
+        &self,
+        domains: &[String],
+    ) -> Result<HashMap<String, DomainQuality>, ApiError> {
+        if domains.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders = vec!["?"; domains.len()].join(", ");
+        let sql = format!(
+            "SELECT domain, injected, cited FROM search_source_quality WHERE domain IN ({})",
+            placeholders
+        );
+        let mut query = sqlx::query(&sql);
+        for domain in domains {
+            query = query.bind(domain);
+        }
+        let rows = query
+            .fetch_all(&self.pool)
+            .await
+            .map_err(ApiError::internal)?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let quality = DomainQuality {
+                    domain: row.try_get("domain").unwrap_or_default(),
+                    injected: row.try_get("injected").unwrap_or_default(),
+                    cited: row.try_get("cited").unwrap_or_default(),
+                };
+                (quality.domain.clone(), quality)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn citations_accumulate_per_domain() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history.db"))
+            .await
+            .unwrap();
+
+        let outcome = |domain: &str, cited| (domain.to_string(), cited);
+        store
+            .record_source_citations(&[
+                outcome("docs.rs", true),
+                outcome("docs.rs", false),
+                outcome("spam.example", false),
+            ])
+            .await
+            .unwrap();
+        store
+            .record_source_citations(&[outcome("docs.rs", true)])
+            .await
+            .unwrap();
+
+        let quality = store
+            .source_quality(&[
+                "docs.rs".to_string(),
+                "spam.example".to_string(),
+                "unseen.org".to_string(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(quality.len(), 2);
+        let docs = &quality["docs.rs"];
+        assert_eq!((docs.injected, docs.cited), (3, 2));
+        assert!((docs.score() - 0.6).abs() < 1e-6);
+        assert!(quality["spam.example"].score() < DomainQuality::default().score());
+    }
+}
@@ -0,0 +1,306 @@
+//! 月ごとの話題索引（`history_topics` テーブル）。
+//!
+//! 1 か月分のセッションを埋め込みでまとめ、LLM が付けたラベルと所属セッションを
+//! 1 話題 1 行で持つ。作り直すときは月単位で丸ごと置き換える。
+
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use super::HistoryStore;
+use crate::core::errors::ApiError;
+
+/// 要約が無いセッションで代わりに使う最初のユーザー発言の数
+const DIGEST_USER_MESSAGES: i64 = 3;
+const DIGEST_MAX_CHARS: usize = 1_500;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopicRecord {
+    /// `YYYY-MM`
+    pub month: String,
+    /// 月内の並び順。大きい話題ほど小さい
+    pub topic_index: i64,
+    pub label: String,
+    pub session_ids: Vec<String>,
+    pub message_count: i64,
+    pub created_at: String,
+}
+
+/// 保存する話題。月・順番・日時はストアが付ける。
+#[derive(Debug, Clone, Default)]
+pub struct NewTopic {
+    pub label: String,
+    pub session_ids: Vec<String>,
+    pub message_count: i64,
+}
+
+/// 話題分けの入力になる、セッション 1 件分の要旨。
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionDigest {
+    pub session_id: String,
+    pub title: Option<String>,
+    /// ローリング要約。無ければ最初のユーザー発言
+    pub text: String,
+    pub message_count: i64,
+}
+
+pub(super) async fn init_topics_table(pool: &SqlitePool) -> Result<(), ApiError> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS history_topics (
+            month TEXT NOT NULL,
+            topic_index INTEGER NOT NULL,
+            label TEXT NOT NULL,
+            session_ids TEXT NOT NULL DEFAULT '[]',
+            message_count INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (month, topic_index)
+        )",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Failed to init history_topics table: {}", e)))?;
+    Ok(())
+}
+
+impl HistoryStore {
+    /// セッションが作られた月（`YYYY-MM`）を古い順に。
+    pub async fn session_months(&self) -> Result<Vec<String>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT DISTINCT substr(created_at, 1, 7) AS month FROM sessions \
+             WHERE created_at IS NOT NULL ORDER BY month ASC",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(rows
+            .iter()
+            .filter_map(|row| row.try_get::<String, _>("month").ok())
+            .collect())
+    }
+
+    /// 話題索引を作り終えた月。
+    pub async fn indexed_topic_months(&self) -> Result<Vec<String>, ApiError> {
+        let rows = sqlx::query("SELECT DISTINCT month FROM history_topics ORDER BY month ASC")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(ApiError::internal)?;
+        Ok(rows
+            .iter()
+            .filter_map(|row| row.try_get::<String, _>("month").ok())
+            .collect())
+    }
+
+    /// `month` に作られたセッションのうち、メッセージのあるものの要旨。作成順。
+    pub async fn session_digests_for_month(
+        &self,
+        month: &str,
+    ) -> Result<Vec<SessionDigest>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT s.id, s.title, ss.summary, \
+             (SELECT COUNT(*) FROM messages WHERE session_id = s.id) AS msg_count \
+             FROM sessions s \
+             LEFT JOIN session_summaries ss ON ss.session_id = s.id \
+             WHERE substr(s.created_at, 1, 7) = ? \
+             ORDER BY s.created_at ASC, s.id ASC",
+        )
+        .bind(month)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+
+        let mut digests = Vec::with_capacity(rows.len());
+        for row in rows {
+            let message_count = row.try_get::<i64, _>("msg_count").unwrap_or(0);
+            if message_count == 0 {
+                continue;
+            }
+            let session_id = row.try_get::<String, _>("id").unwrap_or_default();
+            let title = row
+                .try_get::<Option<String>, _>("title")
+                .unwrap_or(None)
+                .filter(|title| !title.trim().is_empty());
+            let summary = row
+                .try_get::<Option<String>, _>("summary")
+                .unwrap_or(None)
+                .filter(|summary| !summary.trim().is_empty());
+            let body = match summary {
+                Some(summary) => summary,
+                None => self.first_user_messages(&session_id).await?.join("\n"),
+            };
+            let text = match &title {
+                Some(title) => format!("{}\n{}", title.trim(), body.trim()),
+                None => body.trim().to_string(),
+            };
+            if text.trim().is_empty() {
+                continue;
+            }
+            digests.push(SessionDigest {
+                session_id,
+                title,
+                text: text.chars().take(DIGEST_MAX_CHARS).collect(),
+                message_count,
+            });
+        }
+        Ok(digests)
+    }
+
+    async fn first_user_messages(&self, session_id: &str) -> Result<Vec<String>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT content FROM messages WHERE session_id = ? AND role = 'human' \
+             ORDER BY id ASC LIMIT ?",
+        )
+        .bind(session_id)
+        .bind(DIGEST_USER_MESSAGES)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(rows
+            .iter()
+            .filter_map(|row| row.try_get::<String, _>("content").ok())
+            .map(|content| content.trim().to_string())
+            .filter(|content| !content.is_empty())
+            .collect())
+    }
+
+    /// `month` の話題を `topics` の並びで置き換える。空なら月ごと消す。
+    pub async fn replace_month_topics(
+        &self,
+        month: &str,
+        topics: &[NewTopic],
+    ) -> Result<(), ApiError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await.map_err(ApiError::internal)?;
+        sqlx::query("DELETE FROM history_topics WHERE month = ?")
+            .bind(month)
+            .execute(&mut *tx)
+            .await
+            .map_err(ApiError::internal)?;
+        for (index, topic) in topics.iter().enumerate() {
+            let session_ids =
+                serde_json::to_string(&topic.session_ids).map_err(ApiError::internal)?;
+            sqlx::query(
+                "INSERT INTO history_topics \
+                 (month, topic_index, label, session_ids, message_count, created_at) \
+                 VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(month)
+            .bind(index as i64)
+            .bind(&topic.label)
+            .bind(session_ids)
+            .bind(topic.message_count)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await
+            .map_err(ApiError::internal)?;
+        }
+        tx.commit().await.map_err(ApiError::internal)
+    }
+
+    /// 新しい月から順に。`month` を渡すとその月だけ。
+    pub async fn list_topics(&self, month: Option<&str>) -> Result<Vec<TopicRecord>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT month, topic_index, label, session_ids, message_count, created_at \
+             FROM history_topics WHERE (? IS NULL OR month = ?) \
+             ORDER BY month DESC, topic_index ASC",
+        )
+        .bind(month)
+        .bind(month)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(rows.iter().map(topic_from_row).collect())
+    }
+}
+
+fn topic_from_row(row: &SqliteRow) -> TopicRecord {
+    TopicRecord {
+        month: row.try_get("month").unwrap_or_default(),
+        topic_index: row.try_get("topic_index").unwrap_or_default(),
+        label: row.try_get("label").unwrap_or_default(),
+        session_ids: row
+            .try_get::<String, _>("session_ids")
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default(),
+        message_count: row.try_get("message_count").unwrap_or_default(),
+        created_at: row.try_get("created_at").unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn digests_prefer_summaries_and_topics_replace_per_month() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history.db"))
+            .await
+            .unwrap();
+
+        let summarized = store
+            .create_session(Some("Trip".to_string()), "default")
+            .await
+            .unwrap();
+        store
+            .add_message(&summarized, "human", "Where should I go in Kyoto?", None)
+            .await
+            .unwrap();
+        store
+            .save_session_summary(&summarized, "Planning a Kyoto trip", 1)
+            .await
+            .unwrap();
+        let plain = store.create_session(None, "default").await.unwrap();
+        store
+            .add_message(&plain, "human", "Fix my Rust borrow error", None)
+            .await
+            .unwrap();
+        store
+            .add_message(&plain, "ai", "Clone the value first.", None)
+            .await
+            .unwrap();
+        // メッセージの無いセッションは対象外
+        store.create_session(None, "default").await.unwrap();
+
+        let month = chrono::Utc::now().format("%Y-%m").to_string();
+        assert_eq!(store.session_months().await.unwrap(), vec![month.clone()]);
+        let digests = store.session_digests_for_month(&month).await.unwrap();
+        assert_eq!(digests.len(), 2);
+        assert_eq!(digests[0].text, "Trip\nPlanning a Kyoto trip");
+        assert_eq!(digests[1].text, "Fix my Rust borrow error");
+        assert_eq!(digests[1].message_count, 2);
+
+        let topic = |label: &str, session: &str| NewTopic {
+            label: label.to_string(),
+            session_ids: vec![session.to_string()],
+            message_count: 1,
+        };
+        store
+            .replace_month_topics(
+                &month,
+                &[topic("Travel", &summarized), topic("Rust", &plain)],
+            )
+            .await
+            .unwrap();
+        store
+            .replace_month_topics("2020-01", &[topic("Old", &plain)])
+            .await
+            .unwrap();
+        store
+            .replace_month_topics(&month, &[topic("Programming", &plain)])
+            .await
+            .unwrap();
+
+        let topics = store.list_topics(None).await.unwrap();
+        let labels = topics
+            .iter()
+            .map(|topic| topic.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["Programming", "Old"]);
+        assert_eq!(topics[0].session_ids, vec![plain.clone()]);
+        assert_eq!(store.list_topics(Some("2020-01")).await.unwrap().len(), 1);
+        assert_eq!(
+            store.indexed_topic_months().await.unwrap(),
+            vec!["2020-01".to_string(), month]
+        );
+    }
+}
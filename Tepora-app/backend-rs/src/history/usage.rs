@@ -0,0 +1,159 @@
+//! セッションごとのトークン使用量（`token_usage` テーブル）。
+//!
+//! 応答 1 回につき 1 行。プロバイダーが使用量を返さなかった応答は見積もりで
+//! 記録し、`estimated` で区別する。
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use super::HistoryStore;
+use crate::core::errors::ApiError;
+use crate::llm::types::TurnUsage;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ModelTokenUsage {
+    pub model_id: String,
+    pub turns: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SessionTokenUsage {
+    pub session_id: String,
+    pub turns: i64,
+    /// 見積もりを含む応答の数
+    pub estimated_turns: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    pub by_model: Vec<ModelTokenUsage>,
+}
+
+pub(super) async fn init_usage_table(pool: &SqlitePool) -> Result<(), ApiError> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS token_usage (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            model_id TEXT NOT NULL,
+            prompt_tokens INTEGER NOT NULL,
+            completion_tokens INTEGER NOT NULL,
+            estimated INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| ApiError::internal(format!("Failed to init token_usage table: {}", e)))?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_token_usage_session_id ON token_usage(session_id)")
+        .execute(pool)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to create token_usage index: {}", e)))?;
+    Ok(())
+}
+
+impl HistoryStore {
+    pub async fn record_token_usage(
+        &self,
+        session_id: &str,
+        model_id: &str,
+        usage: &TurnUsage,
+    ) -> Result<(), ApiError> {
+        sqlx::query(
+            "INSERT INTO token_usage \
+             (session_id, model_id, prompt_tokens, completion_tokens, estimated, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(session_id)
+        .bind(model_id)
+        .bind(usage.prompt_tokens as i64)
+        .bind(usage.completion_tokens as i64)
+        .bind(usage.estimated as i64)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+        Ok(())
+    }
+
+    /// セッションの合計とモデル別の内訳。使ったモデルが多い順。
+    pub async fn session_token_usage(
+        &self,
+        session_id: &str,
+    ) -> Result<SessionTokenUsage, ApiError> {
+        let rows = sqlx::query(
+            "SELECT model_id, COUNT(*) AS turns, SUM(estimated) AS estimated_turns, \
+                    SUM(prompt_tokens) AS prompt_tokens, SUM(completion_tokens) AS completion_tokens \
+             FROM token_usage WHERE session_id = ? \
+             GROUP BY model_id ORDER BY turns DESC, model_id ASC",
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+
+        let mut usage = SessionTokenUsage {
+            session_id: session_id.to_string(),
+            ..Default::default()
+        };
+        for row in rows {
+            let model = ModelTokenUsage {
+                model_id: row.try_get("model_id").unwrap_or_default(),
+                turns: row.try_get("turns").unwrap_or_default(),
+                prompt_tokens: row.try_get("prompt_tokens").unwrap_or_default(),
+                completion_tokens: row.try_get("completion_tokens").unwrap_or_default(),
+            };
+            usage.turns += model.turns;
+            usage.estimated_turns += row.try_get::<i64, _>("estimated_turns").unwrap_or_default();
+            usage.prompt_tokens += model.prompt_tokens;
+            usage.completion_tokens += model.completion_tokens;
+            usage.by_model.push(model);
+        }
+        usage.total_tokens = usage.prompt_tokens + usage.completion_tokens;
+        Ok(usage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn usage_is_summed_per_session_and_model() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::new(temp_dir.path().join("history.db"))
+            .await
+            .unwrap();
+        let session_id = store.create_session(None, "default").await.unwrap();
+
+        let turn = |prompt, completion, estimated| TurnUsage {
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+            total_tokens: prompt + completion,
+            estimated,
+        };
+        for (model, usage) in [
+            ("gemma", turn(100, 20, false)),
+            ("gemma", turn(150, 30, true)),
+            ("qwen", turn(50, 10, false)),
+        ] {
+            store
+                .record_token_usage(&session_id, model, &usage)
+                .await
+                .unwrap();
+        }
+
+        let usage = store.session_token_usage(&session_id).await.unwrap();
+        assert_eq!(usage.turns, 3);
+        assert_eq!(usage.estimated_turns, 1);
+        assert_eq!(usage.prompt_tokens, 300);
+        assert_eq!(usage.total_tokens, 360);
+        assert_eq!(usage.by_model[0].model_id, "gemma");
+        assert_eq!(usage.by_model[0].completion_tokens, 50);
+
+        let empty = store.session_token_usage("missing").await.unwrap();
+        assert_eq!(empty.turns, 0);
+        assert!(empty.by_model.is_empty());
+    }
+}
@@ -18,6 +18,7 @@ use super::repository::MemoryRepository;
 use super::sentence::split_sentences;
 use super::service::{MemoryService, RetrievedMemory};
 use super::sqlite_repository::SqliteMemoryRepository;
+use super::temporal::TimeRange;
 use super::types::{
     DecayConfig, MemoryEdge, MemoryEdgeType, MemoryEvent, MemoryLayer, MemoryScope, SourceRole,
 };
@@ -34,6 +35,7 @@ pub trait MemoryAdapter: Send + Sync {
         text_model_id: &str,
         embedding_model_id: &str,
         legacy_enabled: bool,
+        namespace: Option<&str>,
     ) -> Result<(), ApiError>;
 
     #[allow(clippy::too_many_arguments)]
@@ -46,6 +48,8 @@ pub trait MemoryAdapter: Send + Sync {
         legacy_enabled: bool,
         mode: PipelineMode,
         stage: PipelineStage,
+        time_range: Option<&TimeRange>,
+        namespace: Option<&str>,
     ) -> Result<Vec<RetrievedMemory>, ApiError>;
 
     async fn ingest_summary(
@@ -148,12 +152,18 @@ impl MemoryAdapter for UnifiedMemoryAdapter {
         text_model_id: &str,
         embedding_model_id: &str,
         legacy_enabled: bool,
+        namespace: Option<&str>,
     ) -> Result<(), ApiError> {
         if !self.em_service.enabled() {
             return Ok(());
         }
 
         if legacy_enabled {
+            if namespace.is_some() {
+                // Legacy episodes carry no owner, so they cannot be kept apart.
+                tracing::debug!("Skipping legacy memory ingest for namespaced agent");
+                return Ok(());
+            }
             self.em_service
                 .ingest_interaction(
                     session_id,
@@ -206,7 +216,9 @@ impl MemoryAdapter for UnifiedMemoryAdapter {
                 }
             };
 
-            let active_character_id = self.resolve_active_character_id();
+            let active_character_id = namespace
+                .map(str::to_string)
+                .or_else(|| self.resolve_active_character_id());
             let episode_id = uuid::Uuid::new_v4().to_string();
             let source_turn_id = format!("{}-{}", session_id, chrono::Utc::now().timestamp());
             let mut v2_events = Vec::new();
@@ -281,6 +293,8 @@ impl MemoryAdapter for UnifiedMemoryAdapter {
         legacy_enabled: bool,
         mode: PipelineMode,
         stage: PipelineStage,
+        time_range: Option<&TimeRange>,
+        namespace: Option<&str>,
     ) -> Result<Vec<RetrievedMemory>, ApiError> {
         if !self.em_service.enabled() || query.trim().is_empty() {
             return Ok(Vec::new());
@@ -288,7 +302,14 @@ impl MemoryAdapter for UnifiedMemoryAdapter {
 
         if legacy_enabled {
             self.em_service
-                .retrieve_for_query(session_id, query, llm, embedding_model_id)
+                .retrieve_for_query(
+                    session_id,
+                    query,
+                    llm,
+                    embedding_model_id,
+                    time_range,
+                    namespace,
+                )
                 .await
         } else {
             let embeddings = llm
@@ -303,6 +324,8 @@ impl MemoryAdapter for UnifiedMemoryAdapter {
                     self.v2_repo.as_ref(),
                     mode,
                     stage,
+                    time_range,
+                    namespace,
                 )
                 .await
         }
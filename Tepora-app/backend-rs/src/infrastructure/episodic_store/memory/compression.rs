@@ -349,7 +349,7 @@ impl MemoryCompressor {
             },
         ];
 
-        let mut request = ChatRequest::new(messages);
+        let mut request = ChatRequest::new(messages).without_continuation();
         request.max_tokens = Some(256);
         llm.chat(request, model_id).await
     }
@@ -15,6 +15,7 @@ pub mod segmenter;
 pub mod sentence;
 pub mod service;
 pub mod sqlite_repository;
+pub mod temporal;
 pub mod types;
 
 #[cfg(test)]
@@ -30,8 +31,9 @@ pub use retrieval::EMTwoStageRetrieval;
 pub use segmenter::EMEventSegmenter;
 pub use service::{DecayCycleResult, MemoryService, MemoryStats, RetrievedMemory};
 pub use sqlite_repository::SqliteMemoryRepository;
+pub use temporal::{extract_time_range, TimeRange};
 pub use types::{
-    CompactionJob, CompactionMember, CompactionStatus, DecayConfig, EMConfig, EpisodicEvent,
-    LayerCounts, MemoryEdge, MemoryEdgeType, MemoryEvent, MemoryLayer, MemoryScope, ScopeStats,
-    SourceRole, TimeUnit,
+    in_recall_namespace, CompactionJob, CompactionMember, CompactionStatus, DecayConfig, EMConfig,
+    EpisodicEvent, LayerCounts, MemoryEdge, MemoryEdgeType, MemoryEvent, MemoryLayer, MemoryScope,
+    ScopeStats, SourceRole, TimeUnit, AGENT_MEMORY_NAMESPACE_PREFIX,
 };
@@ -8,6 +8,7 @@ use async_trait::async_trait;
 
 use crate::core::errors::ApiError;
 
+use super::temporal::TimeRange;
 use super::types::{
     CompactionJob, CompactionMember, CompactionStatus, LayerCounts, MemoryEdge, MemoryEdgeType,
     MemoryEvent, MemoryLayer, MemoryScope, ScopeStats,
@@ -47,15 +48,29 @@ pub trait MemoryRepository: Send + Sync {
     ) -> Result<Vec<MemoryEvent>, ApiError>;
 
     /// Embedding-based similarity search.  Returns top-`limit` events ordered
-    /// by cosine similarity, excluding soft-deleted events.
+    /// by cosine similarity, excluding soft-deleted events and events outside
+    /// `namespace` (see [`in_recall_namespace`](super::types::in_recall_namespace)).
     async fn retrieve_similar(
         &self,
         session_id: Option<&str>,
         scope: Option<MemoryScope>,
+        namespace: Option<&str>,
         query_embedding: &[f32],
         limit: usize,
     ) -> Result<Vec<ScoredEvent>, ApiError>;
 
+    /// Same as [`retrieve_similar`](Self::retrieve_similar), restricted to events
+    /// created within `range` before taking the top-`limit`.
+    async fn retrieve_similar_within(
+        &self,
+        session_id: Option<&str>,
+        scope: Option<MemoryScope>,
+        namespace: Option<&str>,
+        query_embedding: &[f32],
+        limit: usize,
+        range: &TimeRange,
+    ) -> Result<Vec<ScoredEvent>, ApiError>;
+
     /// Update the strength value of a single event.
     async fn update_strength(&self, id: &str, strength: f64) -> Result<(), ApiError>;
 
@@ -19,7 +19,8 @@ use super::decay::DecayEngine;
 use super::integrator::EMLLMIntegrator;
 use super::ranking::compute_retrieval_score;
 use super::sentence::split_sentences;
-use super::types::{DecayConfig, EpisodicEvent, MemoryLayer, TimeUnit};
+use super::temporal::TimeRange;
+use super::types::{in_recall_namespace, DecayConfig, EpisodicEvent, MemoryLayer, TimeUnit};
 
 const KEYRING_SERVICE: &str = "tepora-backend";
 
@@ -63,9 +64,9 @@ pub struct DecayCycleResult {
     pub pruned: usize,
 }
 
-#[derive(Clone)]
-pub struct MemoryService {
-    pub v2_store: Arc<SqliteMemoryRepository>,
+/// 設定から読む値。設定変更時に [`MemoryService::apply_config`] で差し替える。
+#[derive(Debug, Clone)]
+struct MemorySettings {
     enabled: bool,
     retrieval_limit: usize,
     min_score: f32,
@@ -73,12 +74,8 @@ pub struct MemoryService {
     decay_interval_hours: f64,
 }
 
-impl MemoryService {
-    pub async fn new(paths: &AppPaths, config_service: &ConfigService) -> Result<Self, ApiError> {
-        let config = config_service
-            .load_config()
-            .unwrap_or_else(|_| Value::Object(Default::default()));
-
+impl MemorySettings {
+    fn from_config(config: &Value) -> Self {
         let episodic_config = config
             .get("episodic_memory")
             .or_else(|| config.get("em_llm"));
@@ -100,13 +97,42 @@ impl MemoryService {
             .unwrap_or(0.15)
             .clamp(-1.0, 1.0) as f32;
 
-        let decay_config = parse_decay_config(&config);
         let decay_interval_hours = episodic_config
             .and_then(|v| v.get("decay_interval_hours"))
             .and_then(|v| v.as_f64())
             .unwrap_or(0.0)
             .clamp(0.0, 24.0);
 
+        Self {
+            enabled,
+            retrieval_limit,
+            min_score,
+            decay_config: parse_decay_config(config),
+            decay_interval_hours,
+        }
+    }
+}
+
+/// 無効化中のバックグラウンド減衰ワーカーが設定を見直す間隔
+const DECAY_WORKER_IDLE_POLL: std::time::Duration = std::time::Duration::from_secs(300);
+
+#[derive(Clone)]
+pub struct MemoryService {
+    pub v2_store: Arc<SqliteMemoryRepository>,
+    settings: Arc<std::sync::RwLock<MemorySettings>>,
+}
+
+impl MemoryService {
+    pub async fn new(paths: &AppPaths, config_service: &ConfigService) -> Result<Self, ApiError> {
+        let config = config_service
+            .load_config()
+            .unwrap_or_else(|_| Value::Object(Default::default()));
+
+        let settings = MemorySettings::from_config(&config);
+        let episodic_config = config
+            .get("episodic_memory")
+            .or_else(|| config.get("em_llm"));
+
         if let Some(memory_version) = episodic_config
             .and_then(|v| v.get("memory_version"))
             .and_then(|v| v.as_str())
@@ -151,11 +177,7 @@ impl MemoryService {
 
         let service = Self {
             v2_store: Arc::new(v2_repo),
-            enabled,
-            retrieval_limit,
-            min_score,
-            decay_config,
-            decay_interval_hours,
+            settings: Arc::new(std::sync::RwLock::new(settings)),
         };
 
         if let Err(err) = service.run_decay_cycle(None).await {
@@ -174,11 +196,13 @@ impl MemoryService {
     ) -> Self {
         Self {
             v2_store,
-            enabled,
-            retrieval_limit: retrieval_limit.clamp(1, 50),
-            min_score,
-            decay_config: DecayConfig::default(),
-            decay_interval_hours: 0.0,
+            settings: Arc::new(std::sync::RwLock::new(MemorySettings {
+                enabled,
+                retrieval_limit: retrieval_limit.clamp(1, 50),
+                min_score,
+                decay_config: DecayConfig::default(),
+                decay_interval_hours: 0.0,
+            })),
         }
     }
 
@@ -198,8 +222,21 @@ impl MemoryService {
         ))
     }
 
+    fn settings(&self) -> MemorySettings {
+        self.settings
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// 設定の変更を反映する。次の取得・減衰処理から新しい値が使われる。
+    pub fn apply_config(&self, config: &Value) {
+        let next = MemorySettings::from_config(config);
+        *self.settings.write().unwrap_or_else(|e| e.into_inner()) = next;
+    }
+
     pub fn enabled(&self) -> bool {
-        self.enabled
+        self.settings().enabled
     }
 
     pub async fn ingest_interaction(
@@ -211,7 +248,7 @@ impl MemoryService {
         text_model_id: &str,
         embedding_model_id: &str,
     ) -> Result<(), ApiError> {
-        if !self.enabled {
+        if !self.enabled() {
             return Ok(());
         }
 
@@ -307,7 +344,7 @@ impl MemoryService {
         let mut v2_edges = Vec::new();
         let mut prev_event_id: Option<String> = None;
 
-        let decay_engine = DecayEngine::new(self.decay_config.clone());
+        let decay_engine = DecayEngine::new(self.settings().decay_config);
 
         for (i, ev) in events.into_iter().enumerate() {
             let event_content = ev.tokens.join(" ");
@@ -371,8 +408,10 @@ impl MemoryService {
         query: &str,
         llm: &LlmService,
         embedding_model_id: &str,
+        time_range: Option<&TimeRange>,
+        namespace: Option<&str>,
     ) -> Result<Vec<RetrievedMemory>, ApiError> {
-        if !self.enabled || query.trim().is_empty() {
+        if !self.enabled() || query.trim().is_empty() {
             return Ok(Vec::new());
         }
 
@@ -388,8 +427,14 @@ impl MemoryService {
             return Ok(Vec::new());
         };
 
-        self.retrieve_for_query_with_embedding(session_id, query_embedding)
-            .await
+        self.retrieve_for_query_v2_within(
+            session_id,
+            query_embedding,
+            self.v2_store.as_ref(),
+            time_range,
+            namespace,
+        )
+        .await
     }
 
     pub async fn retrieve_for_query_with_embedding(
@@ -397,7 +442,7 @@ impl MemoryService {
         session_id: &str,
         query_embedding: &[f32],
     ) -> Result<Vec<RetrievedMemory>, ApiError> {
-        if !self.enabled {
+        if !self.enabled() {
             return Ok(Vec::new());
         }
 
@@ -411,8 +456,55 @@ impl MemoryService {
         query_embedding: &[f32],
         v2_store: &dyn MemoryRepository,
     ) -> Result<Vec<RetrievedMemory>, ApiError> {
-        let limit = self.retrieval_limit;
-        let ratio = self.decay_config.retrieval_similarity_ratio;
+        self.retrieve_for_query_v2_within(session_id, query_embedding, v2_store, None, None)
+            .await
+    }
+
+    /// `time_range` があれば、その期間に作られた記憶だけから選ぶ。
+    /// 期間内に何も無ければ、時間表現の読み違いに備えて絞り込み無しでやり直す。
+    /// `namespace` の扱いは [`in_recall_namespace`] と同じで、件数を絞る前に適用する。
+    pub async fn retrieve_for_query_v2_within(
+        &self,
+        session_id: &str,
+        query_embedding: &[f32],
+        v2_store: &dyn MemoryRepository,
+        time_range: Option<&TimeRange>,
+        namespace: Option<&str>,
+    ) -> Result<Vec<RetrievedMemory>, ApiError> {
+        if let Some(range) = time_range {
+            let results = self
+                .rank_for_query_v2(
+                    session_id,
+                    query_embedding,
+                    v2_store,
+                    Some(range),
+                    namespace,
+                )
+                .await?;
+            if !results.is_empty() {
+                return Ok(results);
+            }
+            tracing::debug!(
+                start = %range.start,
+                end = %range.end,
+                "No memories in the requested time range; retrying without it"
+            );
+        }
+        self.rank_for_query_v2(session_id, query_embedding, v2_store, None, namespace)
+            .await
+    }
+
+    async fn rank_for_query_v2(
+        &self,
+        session_id: &str,
+        query_embedding: &[f32],
+        v2_store: &dyn MemoryRepository,
+        time_range: Option<&TimeRange>,
+        namespace: Option<&str>,
+    ) -> Result<Vec<RetrievedMemory>, ApiError> {
+        let settings = self.settings();
+        let limit = settings.retrieval_limit;
+        let ratio = settings.decay_config.retrieval_similarity_ratio;
         let mut ks = (limit as f32 * ratio).ceil() as usize;
         if limit > 0 && ks == 0 {
             ks = 1;
@@ -422,17 +514,50 @@ impl MemoryService {
         let local_limit = ks.max(limit.saturating_sub(1)).max(1);
         let global_limit = ks.max(limit.saturating_sub(1)).max(1);
 
-        let session_local_events = v2_store
-            .retrieve_similar(
-                Some(session_id),
-                Some(MemoryScope::Char),
-                query_embedding,
-                local_limit,
-            )
-            .await?;
-        let global_events = v2_store
-            .retrieve_similar(None, Some(MemoryScope::Char), query_embedding, global_limit)
-            .await?;
+        let (session_local_events, global_events) = match time_range {
+            Some(range) => (
+                v2_store
+                    .retrieve_similar_within(
+                        Some(session_id),
+                        Some(MemoryScope::Char),
+                        namespace,
+                        query_embedding,
+                        local_limit,
+                        range,
+                    )
+                    .await?,
+                v2_store
+                    .retrieve_similar_within(
+                        None,
+                        Some(MemoryScope::Char),
+                        namespace,
+                        query_embedding,
+                        global_limit,
+                        range,
+                    )
+                    .await?,
+            ),
+            None => (
+                v2_store
+                    .retrieve_similar(
+                        Some(session_id),
+                        Some(MemoryScope::Char),
+                        namespace,
+                        query_embedding,
+                        local_limit,
+                    )
+                    .await?,
+                v2_store
+                    .retrieve_similar(
+                        None,
+                        Some(MemoryScope::Char),
+                        namespace,
+                        query_embedding,
+                        global_limit,
+                    )
+                    .await?,
+            ),
+        };
 
         let mut candidates: std::collections::HashMap<String, ScoredEvent> =
             std::collections::HashMap::new();
@@ -485,6 +610,15 @@ impl MemoryService {
                         }
                         if !candidates.contains_key(&edge.to_event_id) {
                             if let Ok(Some(adj_ev)) = v2_store.get_event(&edge.to_event_id).await {
+                                if time_range
+                                    .is_some_and(|range| !range.contains(adj_ev.created_at))
+                                    || !in_recall_namespace(
+                                        adj_ev.character_id.as_deref(),
+                                        namespace,
+                                    )
+                                {
+                                    continue;
+                                }
                                 let next_id = adj_ev.id.clone();
                                 next_layer.push(next_id.clone());
                                 contiguity_weights.insert(next_id.clone(), weight);
@@ -508,7 +642,7 @@ impl MemoryService {
 
         let now = Utc::now();
         let mut final_scored = Vec::new();
-        let decay_engine = DecayEngine::new(self.decay_config.clone());
+        let decay_engine = DecayEngine::new(settings.decay_config.clone());
 
         for (_, mut scored) in candidates {
             let semantic_score = if scored.score > 0.0 {
@@ -582,13 +716,13 @@ impl MemoryService {
             };
             let prompt_score = base_score + layer_bonus + session_bonus + recency_bonus;
 
-            if prompt_score >= self.min_score as f64 {
+            if prompt_score >= settings.min_score as f64 {
                 final_scored.push((scored, prompt_score));
             }
         }
 
         final_scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        final_scored.truncate(self.retrieval_limit);
+        final_scored.truncate(limit);
 
         let mut results = Vec::new();
         for (scored, score) in final_scored {
@@ -616,6 +750,7 @@ impl MemoryService {
         Ok(results)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn retrieve_for_query_v2_scoped(
         &self,
         session_id: &str,
@@ -623,9 +758,17 @@ impl MemoryService {
         v2_store: &dyn MemoryRepository,
         mode: PipelineMode,
         stage: PipelineStage,
+        time_range: Option<&TimeRange>,
+        namespace: Option<&str>,
     ) -> Result<Vec<RetrievedMemory>, ApiError> {
         let mut results = self
-            .retrieve_for_query_v2(session_id, query_embedding, v2_store)
+            .retrieve_for_query_v2_within(
+                session_id,
+                query_embedding,
+                v2_store,
+                time_range,
+                namespace,
+            )
             .await?;
 
         if !matches!(
@@ -644,12 +787,19 @@ impl MemoryService {
             .retrieve_similar(
                 Some(session_id),
                 Some(MemoryScope::Prof),
+                namespace,
                 query_embedding,
                 prof_limit,
             )
             .await?;
         let prof_global = v2_store
-            .retrieve_similar(None, Some(MemoryScope::Prof), query_embedding, prof_limit)
+            .retrieve_similar(
+                None,
+                Some(MemoryScope::Prof),
+                namespace,
+                query_embedding,
+                prof_limit,
+            )
             .await?;
 
         let scope_bonus = match stage {
@@ -716,7 +866,7 @@ impl MemoryService {
                 .partial_cmp(&a.relevance_score)
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
-        results.truncate(self.retrieval_limit);
+        results.truncate(self.settings().retrieval_limit);
         Ok(results)
     }
     pub async fn stats(&self) -> Result<MemoryStats, ApiError> {
@@ -741,11 +891,12 @@ impl MemoryService {
             .average_strength(None, Some(MemoryScope::Prof))
             .await?;
 
+        let settings = self.settings();
         Ok(MemoryStats {
-            enabled: self.enabled,
+            enabled: settings.enabled,
             total_events,
-            retrieval_limit: self.retrieval_limit,
-            min_score: self.min_score,
+            retrieval_limit: settings.retrieval_limit,
+            min_score: settings.min_score,
             lml_events: layer_counts.lml,
             sml_events: layer_counts.sml,
             mean_strength,
@@ -764,7 +915,8 @@ impl MemoryService {
         &self,
         session_id: Option<&str>,
     ) -> Result<DecayCycleResult, ApiError> {
-        if !self.enabled {
+        let settings = self.settings();
+        if !settings.enabled {
             return Ok(DecayCycleResult {
                 updated: 0,
                 promoted: 0,
@@ -773,7 +925,7 @@ impl MemoryService {
             });
         }
 
-        let decay_engine = DecayEngine::new(self.decay_config.clone());
+        let decay_engine = DecayEngine::new(settings.decay_config.clone());
         let now = Utc::now();
         let now_str = now.to_rfc3339();
         let v2_store = self.v2_store.as_ref();
@@ -831,7 +983,7 @@ impl MemoryService {
                 }
             }
 
-            if new_strength < self.decay_config.prune_threshold {
+            if new_strength < settings.decay_config.prune_threshold {
                 v2_soft_delete_ids.push(event.id.clone());
             }
         }
@@ -861,21 +1013,21 @@ impl MemoryService {
         Ok(result)
     }
 
-    /// Spawns the background decay worker if enabled.
+    /// Spawns the background decay worker.
+    /// The interval is re-read every cycle so config changes apply without a restart;
+    /// while memory is disabled or `decay_interval_hours` is 0.0 the worker only idles.
     pub fn spawn_background_worker(self: Arc<Self>) {
-        if !self.enabled {
-            return;
-        }
-        // decay_interval_hours == 0.0 means periodic decay is disabled.
-        if self.decay_interval_hours == 0.0 {
-            tracing::info!("Periodic decay disabled (decay_interval_hours=0.0).");
-            return;
-        }
-        let interval_duration =
-            std::time::Duration::from_secs_f64(self.decay_interval_hours * 3600.0);
         tokio::spawn(async move {
             loop {
-                tokio::time::sleep(interval_duration).await;
+                let settings = self.settings();
+                if !settings.enabled || settings.decay_interval_hours == 0.0 {
+                    tokio::time::sleep(DECAY_WORKER_IDLE_POLL).await;
+                    continue;
+                }
+                tokio::time::sleep(std::time::Duration::from_secs_f64(
+                    settings.decay_interval_hours * 3600.0,
+                ))
+                .await;
                 tracing::info!("Running scheduled background decay cycle...");
                 if let Err(e) = self.run_decay_cycle(None).await {
                     tracing::error!("Background decay cycle failed: {}", e);
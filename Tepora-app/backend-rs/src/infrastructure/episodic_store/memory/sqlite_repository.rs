@@ -16,9 +16,10 @@ use sqlx::{Row, SqlitePool};
 use crate::core::errors::ApiError;
 
 use super::repository::{MemoryRepository, ScoredEvent};
+use super::temporal::TimeRange;
 use super::types::{
-    CompactionJob, CompactionMember, CompactionStatus, LayerCounts, MemoryEdge, MemoryEdgeType,
-    MemoryEvent, MemoryLayer, MemoryScope, ScopeStats, SourceRole,
+    in_recall_namespace, CompactionJob, CompactionMember, CompactionStatus, LayerCounts,
+    MemoryEdge, MemoryEdgeType, MemoryEvent, MemoryLayer, MemoryScope, ScopeStats, SourceRole,
 };
 
 // ---------------------------------------------------------------------------
@@ -550,6 +551,76 @@ impl SqliteMemoryRepository {
             finished_at: parse_optional_dt(finished_at),
         }
     }
+
+    async fn rank_similar(
+        &self,
+        session_id: Option<&str>,
+        scope: Option<MemoryScope>,
+        namespace: Option<&str>,
+        query_embedding: &[f32],
+        limit: usize,
+        range: Option<&TimeRange>,
+    ) -> Result<Vec<ScoredEvent>, ApiError> {
+        if query_embedding.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        let rows = match (session_id, scope) {
+            (Some(sid), Some(scope)) => sqlx::query(
+                "SELECT * FROM memory_events
+                     WHERE session_id = ?1 AND scope = ?2 AND is_deleted = 0",
+            )
+            .bind(sid)
+            .bind(scope.as_str())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(ApiError::internal)?,
+            (Some(sid), None) => sqlx::query(
+                "SELECT * FROM memory_events
+                     WHERE session_id = ?1 AND is_deleted = 0",
+            )
+            .bind(sid)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(ApiError::internal)?,
+            (None, Some(scope)) => sqlx::query(
+                "SELECT * FROM memory_events
+                     WHERE scope = ?1 AND is_deleted = 0",
+            )
+            .bind(scope.as_str())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(ApiError::internal)?,
+            (None, None) => sqlx::query(
+                "SELECT * FROM memory_events
+                     WHERE is_deleted = 0",
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(ApiError::internal)?,
+        };
+        let mut scored: Vec<ScoredEvent> = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let event = Self::row_to_event(row, &self.encryption_key);
+            if event.embedding.is_empty()
+                || range.is_some_and(|range| !range.contains(event.created_at))
+                || !in_recall_namespace(event.character_id.as_deref(), namespace)
+            {
+                continue;
+            }
+            let sim = cosine_similarity(query_embedding, &event.embedding);
+            scored.push(ScoredEvent {
+                event,
+                score: sim as f64,
+            });
+        }
+        scored.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        scored.truncate(limit);
+        Ok(scored)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -652,66 +723,34 @@ impl MemoryRepository for SqliteMemoryRepository {
         &self,
         session_id: Option<&str>,
         scope: Option<MemoryScope>,
+        namespace: Option<&str>,
         query_embedding: &[f32],
         limit: usize,
     ) -> Result<Vec<ScoredEvent>, ApiError> {
-        if query_embedding.is_empty() || limit == 0 {
-            return Ok(Vec::new());
-        }
-        let rows = match (session_id, scope) {
-            (Some(sid), Some(scope)) => sqlx::query(
-                "SELECT * FROM memory_events
-                     WHERE session_id = ?1 AND scope = ?2 AND is_deleted = 0",
-            )
-            .bind(sid)
-            .bind(scope.as_str())
-            .fetch_all(&self.pool)
-            .await
-            .map_err(ApiError::internal)?,
-            (Some(sid), None) => sqlx::query(
-                "SELECT * FROM memory_events
-                     WHERE session_id = ?1 AND is_deleted = 0",
-            )
-            .bind(sid)
-            .fetch_all(&self.pool)
-            .await
-            .map_err(ApiError::internal)?,
-            (None, Some(scope)) => sqlx::query(
-                "SELECT * FROM memory_events
-                     WHERE scope = ?1 AND is_deleted = 0",
-            )
-            .bind(scope.as_str())
-            .fetch_all(&self.pool)
-            .await
-            .map_err(ApiError::internal)?,
-            (None, None) => sqlx::query(
-                "SELECT * FROM memory_events
-                     WHERE is_deleted = 0",
-            )
-            .fetch_all(&self.pool)
+        self.rank_similar(session_id, scope, namespace, query_embedding, limit, None)
             .await
-            .map_err(ApiError::internal)?,
-        };
-        let mut scored: Vec<ScoredEvent> = Vec::with_capacity(rows.len());
-        for row in &rows {
-            let event = Self::row_to_event(row, &self.encryption_key);
-            if event.embedding.is_empty() {
-                continue;
-            }
-            let sim = cosine_similarity(query_embedding, &event.embedding);
-            scored.push(ScoredEvent {
-                event,
-                score: sim as f64,
-            });
-        }
-        scored.sort_by(|a, b| {
-            b.score
-                .partial_cmp(&a.score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-        scored.truncate(limit);
-        Ok(scored)
     }
+
+    async fn retrieve_similar_within(
+        &self,
+        session_id: Option<&str>,
+        scope: Option<MemoryScope>,
+        namespace: Option<&str>,
+        query_embedding: &[f32],
+        limit: usize,
+        range: &TimeRange,
+    ) -> Result<Vec<ScoredEvent>, ApiError> {
+        self.rank_similar(
+            session_id,
+            scope,
+            namespace,
+            query_embedding,
+            limit,
+            Some(range),
+        )
+        .await
+    }
+
     async fn update_strength(&self, id: &str, strength: f64) -> Result<(), ApiError> {
         let now = Utc::now().to_rfc3339();
         sqlx::query("UPDATE memory_events SET strength = ?2, updated_at = ?3 WHERE id = ?1")
@@ -0,0 +1,283 @@
+//! 質問文の時間表現から記憶の検索期間を割り出す。
+//!
+//! 「先週の火曜日に話したこと」「what did we discuss yesterday」のような質問で、
+//! 類似度だけでなく作成日時でも記憶を絞り込むためのもの。日の境界は
+//! ユーザーのローカル時刻で数え、週は月曜始まり。
+
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, TimeZone, Utc, Weekday};
+use regex::{Captures, Regex};
+
+/// `start` 以上 `end` 未満の期間。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeRange {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl TimeRange {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Option<Self> {
+        (start < end).then_some(Self { start, end })
+    }
+
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.start <= at && at < self.end
+    }
+}
+
+/// 「最近」で遡る日数
+const RECENT_DAYS: i64 = 7;
+
+/// 質問文に時間表現があれば、その期間を返す。複数あるときは最初に見つかった規則を使う。
+pub fn extract_time_range(query: &str, now: DateTime<FixedOffset>) -> Option<TimeRange> {
+    let offset = *now.offset();
+    let today = now.date_naive();
+    let days =
+        |start: NaiveDate, count: i64| local_range(offset, start, start + Duration::days(count));
+
+    if let Some(caps) = pattern(&ISO_DATE, r"\b(\d{4})-(\d{1,2})-(\d{1,2})\b").captures(query) {
+        let date =
+            NaiveDate::from_ymd_opt(number(&caps, 1)?, number(&caps, 2)?, number(&caps, 3)?)?;
+        return days(date, 1);
+    }
+    if let Some(caps) = pattern(&JA_DATE, r"(\d{1,2})月(\d{1,2})日").captures(query) {
+        let (month, day) = (number(&caps, 1)?, number(&caps, 2)?);
+        let mut date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+        // 年を書かない日付は、まだ来ていなければ去年のこと
+        if date > today {
+            date = NaiveDate::from_ymd_opt(today.year() - 1, month, day)?;
+        }
+        return days(date, 1);
+    }
+    if pattern(
+        &DAY_BEFORE_YESTERDAY,
+        r"(?i)\bday before yesterday\b|一昨日|おととい",
+    )
+    .is_match(query)
+    {
+        return days(today - Duration::days(2), 1);
+    }
+    if pattern(&YESTERDAY, r"(?i)\byesterday\b|昨日|きのう").is_match(query) {
+        return days(today - Duration::days(1), 1);
+    }
+    if pattern(&TODAY, r"(?i)\btoday\b|\bthis morning\b|今日|きょう|今朝").is_match(query) {
+        return days(today, 1);
+    }
+    if let Some(caps) = pattern(
+        &DAYS_AGO,
+        r"(?i)\b(\d{1,3})\s*days?\s+ago\b|(\d{1,3})\s*日前",
+    )
+    .captures(query)
+    {
+        let count = first_number(&caps)?;
+        return days(today - Duration::days(count), 1);
+    }
+    if let Some(caps) = pattern(
+        &WEEKS_AGO,
+        r"(?i)\b(\d{1,2})\s*weeks?\s+ago\b|(\d{1,2})\s*週間前",
+    )
+    .captures(query)
+    {
+        let count = first_number(&caps)?;
+        return days(week_start(today) - Duration::weeks(count), 7);
+    }
+    if let Some(caps) =
+        pattern(&LAST_WEEKDAY_JA, r"先週の?(月|火|水|木|金|土|日)曜").captures(query)
+    {
+        let weekday = ja_weekday(caps.get(1)?.as_str())?;
+        let date = week_start(today) - Duration::weeks(1)
+            + Duration::days(weekday.num_days_from_monday() as i64);
+        return days(date, 1);
+    }
+    if let Some(caps) = pattern(
+        &WEEKDAY_EN,
+        r"(?i)\b(?:last|on)\s+(monday|tuesday|wednesday|thursday|friday|saturday|sunday)\b",
+    )
+    .captures(query)
+    {
+        let weekday = caps.get(1)?.as_str().parse::<Weekday>().ok()?;
+        return days(previous_weekday(today, weekday, false), 1);
+    }
+    if let Some(caps) = pattern(&WEEKDAY_JA, r"(月|火|水|木|金|土|日)曜日?に").captures(query)
+    {
+        let weekday = ja_weekday(caps.get(1)?.as_str())?;
+        return days(previous_weekday(today, weekday, true), 1);
+    }
+    if pattern(&LAST_WEEK, r"(?i)\blast week\b|先週").is_match(query) {
+        return days(week_start(today) - Duration::weeks(1), 7);
+    }
+    if pattern(&THIS_WEEK, r"(?i)\bthis week\b|今週").is_match(query) {
+        return days(week_start(today), 7);
+    }
+    if pattern(&LAST_MONTH, r"(?i)\blast month\b|先月").is_match(query) {
+        let this_month = today.with_day(1)?;
+        let previous = (this_month - Duration::days(1)).with_day(1)?;
+        return local_range(offset, previous, this_month);
+    }
+    if pattern(&THIS_MONTH, r"(?i)\bthis month\b|今月").is_match(query) {
+        let this_month = today.with_day(1)?;
+        return local_range(offset, this_month, next_month(this_month)?);
+    }
+    if pattern(&LAST_YEAR, r"(?i)\blast year\b|去年|昨年").is_match(query) {
+        let start = NaiveDate::from_ymd_opt(today.year() - 1, 1, 1)?;
+        return local_range(offset, start, NaiveDate::from_ymd_opt(today.year(), 1, 1)?);
+    }
+    if pattern(&RECENTLY, r"(?i)\brecently\b|\blately\b|最近|この間").is_match(query) {
+        return TimeRange::new(
+            (now - Duration::days(RECENT_DAYS)).with_timezone(&Utc),
+            now.with_timezone(&Utc) + Duration::seconds(1),
+        );
+    }
+    None
+}
+
+static ISO_DATE: OnceLock<Regex> = OnceLock::new();
+static JA_DATE: OnceLock<Regex> = OnceLock::new();
+static DAY_BEFORE_YESTERDAY: OnceLock<Regex> = OnceLock::new();
+static YESTERDAY: OnceLock<Regex> = OnceLock::new();
+static TODAY: OnceLock<Regex> = OnceLock::new();
+static DAYS_AGO: OnceLock<Regex> = OnceLock::new();
+static WEEKS_AGO: OnceLock<Regex> = OnceLock::new();
+static LAST_WEEKDAY_JA: OnceLock<Regex> = OnceLock::new();
+static WEEKDAY_EN: OnceLock<Regex> = OnceLock::new();
+static WEEKDAY_JA: OnceLock<Regex> = OnceLock::new();
+static LAST_WEEK: OnceLock<Regex> = OnceLock::new();
+static THIS_WEEK: OnceLock<Regex> = OnceLock::new();
+static LAST_MONTH: OnceLock<Regex> = OnceLock::new();
+static THIS_MONTH: OnceLock<Regex> = OnceLock::new();
+static LAST_YEAR: OnceLock<Regex> = OnceLock::new();
+static RECENTLY: OnceLock<Regex> = OnceLock::new();
+
+fn pattern(cell: &'static OnceLock<Regex>, source: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(source).expect("valid temporal regex"))
+}
+
+fn number<T: std::str::FromStr>(caps: &Captures<'_>, index: usize) -> Option<T> {
+    caps.get(index)?.as_str().parse().ok()
+}
+
+/// 英語と日本語の候補のうち、一致した方の数値。
+fn first_number(caps: &Captures<'_>) -> Option<i64> {
+    number(caps, 1).or_else(|| number(caps, 2))
+}
+
+fn ja_weekday(name: &str) -> Option<Weekday> {
+    Some(match name {
+        "月" => Weekday::Mon,
+        "火" => Weekday::Tue,
+        "水" => Weekday::Wed,
+        "木" => Weekday::Thu,
+        "金" => Weekday::Fri,
+        "土" => Weekday::Sat,
+        "日" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// 直近の `weekday`。`include_today` が偽なら今日は含めない（"last Tuesday"）。
+fn previous_weekday(today: NaiveDate, weekday: Weekday, include_today: bool) -> NaiveDate {
+    let mut back = (7 + today.weekday().num_days_from_monday() as i64
+        - weekday.num_days_from_monday() as i64)
+        % 7;
+    if back == 0 && !include_today {
+        back = 7;
+    }
+    today - Duration::days(back)
+}
+
+fn next_month(first_day: NaiveDate) -> Option<NaiveDate> {
+    if first_day.month() == 12 {
+        NaiveDate::from_ymd_opt(first_day.year() + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(first_day.year(), first_day.month() + 1, 1)
+    }
+}
+
+fn local_range(offset: FixedOffset, start: NaiveDate, end: NaiveDate) -> Option<TimeRange> {
+    let midnight = |date: NaiveDate| {
+        offset
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+            .single()
+            .map(|at| at.with_timezone(&Utc))
+    };
+    TimeRange::new(midnight(start)?, midnight(end)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2026-10-15 (木) 14:30 JST
+    fn now() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2026-10-15T14:30:00+09:00").unwrap()
+    }
+
+    fn local_day(query: &str) -> Option<String> {
+        let range = extract_time_range(query, now())?;
+        let start = range.start.with_timezone(now().offset());
+        let end = range.end.with_timezone(now().offset());
+        Some(format!("{}..{}", start.date_naive(), end.date_naive()))
+    }
+
+    #[test]
+    fn relative_days_and_weekdays_follow_local_calendar() {
+        assert_eq!(
+            local_day("what did we discuss yesterday?").unwrap(),
+            "2026-10-14..2026-10-15"
+        );
+        assert_eq!(local_day("今日の話").unwrap(), "2026-10-15..2026-10-16");
+        assert_eq!(
+            local_day("3日前に決めたこと").unwrap(),
+            "2026-10-12..2026-10-13"
+        );
+        assert_eq!(
+            local_day("what did we discuss last Tuesday").unwrap(),
+            "2026-10-13..2026-10-14"
+        );
+        // 今日と同じ曜日の "last" は一週間前
+        assert_eq!(
+            local_day("last thursday").unwrap(),
+            "2026-10-08..2026-10-09"
+        );
+        assert_eq!(
+            local_day("先週の火曜日に話したこと").unwrap(),
+            "2026-10-06..2026-10-07"
+        );
+        assert_eq!(
+            local_day("2026-09-30 のメモ").unwrap(),
+            "2026-09-30..2026-10-01"
+        );
+        assert_eq!(
+            local_day("12月24日の予定").unwrap(),
+            "2025-12-24..2025-12-25"
+        );
+    }
+
+    #[test]
+    fn weeks_months_and_years_cover_whole_periods() {
+        assert_eq!(local_day("last week").unwrap(), "2026-10-05..2026-10-12");
+        assert_eq!(local_day("今週").unwrap(), "2026-10-12..2026-10-19");
+        assert_eq!(
+            local_day("先月の振り返り").unwrap(),
+            "2026-09-01..2026-10-01"
+        );
+        assert_eq!(local_day("this month").unwrap(), "2026-10-01..2026-11-01");
+        assert_eq!(local_day("去年").unwrap(), "2025-01-01..2026-01-01");
+
+        let range = extract_time_range("最近の話題", now()).unwrap();
+        assert!(range.contains(now().with_timezone(&Utc)));
+        assert!(!range.contains((now() - Duration::days(8)).with_timezone(&Utc)));
+    }
+
+    #[test]
+    fn queries_without_time_expressions_are_unfiltered() {
+        assert!(extract_time_range("how do I configure MCP servers?", now()).is_none());
+        assert!(extract_time_range("毎日の習慣について", now()).is_none());
+        assert!(extract_time_range("2026-13-40", now()).is_none());
+    }
+}
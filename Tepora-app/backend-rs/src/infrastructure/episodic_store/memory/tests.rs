@@ -328,7 +328,13 @@ mod sqlite_repository_tests {
         .unwrap();
 
         let results = repo
-            .retrieve_similar(Some("s1"), Some(MemoryScope::Char), &[1.0, 0.0, 0.0], 2)
+            .retrieve_similar(
+                Some("s1"),
+                Some(MemoryScope::Char),
+                None,
+                &[1.0, 0.0, 0.0],
+                2,
+            )
             .await
             .unwrap();
         assert_eq!(results.len(), 2);
@@ -367,7 +373,7 @@ mod sqlite_repository_tests {
             .unwrap();
 
         let results = repo
-            .retrieve_similar(Some("s1"), None, &[1.0, 0.0], 10)
+            .retrieve_similar(Some("s1"), None, None, &[1.0, 0.0], 10)
             .await
             .unwrap();
         assert_eq!(results.len(), 1);
@@ -389,12 +395,41 @@ mod sqlite_repository_tests {
         .await
         .unwrap();
         let results = repo
-            .retrieve_similar(Some("s1"), None, &[], 10)
+            .retrieve_similar(Some("s1"), None, None, &[], 10)
             .await
             .unwrap();
         assert!(results.is_empty());
     }
 
+    #[tokio::test]
+    async fn retrieve_similar_filters_namespace_before_top_k() {
+        let repo = make_repo().await;
+        for (id, character_id, embedding) in [
+            ("shared1", None, [1.0, 0.0]),
+            ("shared2", Some("persona"), [0.99, 0.01]),
+            ("scratch", Some("agent:scratch"), [0.5, 0.5]),
+            ("other", Some("agent:other"), [1.0, 0.0]),
+        ] {
+            let mut event = make_event(id, "s1", MemoryScope::Char, id, 0, id, &embedding);
+            event.character_id = character_id.map(str::to_string);
+            repo.insert_event(&event).await.unwrap();
+        }
+
+        let namespaced = repo
+            .retrieve_similar(None, None, Some("agent:scratch"), &[1.0, 0.0], 1)
+            .await
+            .unwrap();
+        assert_eq!(namespaced.len(), 1);
+        assert_eq!(namespaced[0].event.id, "scratch");
+
+        let shared = repo
+            .retrieve_similar(None, None, None, &[1.0, 0.0], 10)
+            .await
+            .unwrap();
+        let ids: Vec<&str> = shared.iter().map(|hit| hit.event.id.as_str()).collect();
+        assert_eq!(ids, ["shared1", "shared2"]);
+    }
+
     // =================================================================
     // Soft delete
     // =================================================================
@@ -230,6 +230,19 @@ impl std::str::FromStr for MemoryScope {
     }
 }
 
+/// `character_id` prefix for episodic memory owned by an agent namespace.
+pub const AGENT_MEMORY_NAMESPACE_PREFIX: &str = "agent:";
+
+/// Whether an event stored under `character_id` is visible to a recall in
+/// `namespace`.
+///
+/// A namespaced recall (`Some("agent:<ns>")`) sees only that namespace; a
+/// shared recall (`None`) sees everything except agent namespaces.
+pub fn in_recall_namespace(character_id: Option<&str>, namespace: Option<&str>) -> bool {
+    let event_namespace = character_id.filter(|id| id.starts_with(AGENT_MEMORY_NAMESPACE_PREFIX));
+    event_namespace == namespace
+}
+
 /// Type of relationship between two memory events.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
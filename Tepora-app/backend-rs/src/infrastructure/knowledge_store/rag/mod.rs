@@ -7,8 +7,14 @@
 
 #[path = "../../../rag/context_builder.rs"]
 mod context_builder;
+#[path = "../../../rag/embedding_compare.rs"]
+pub mod embedding_compare;
 #[path = "../../../rag/engine.rs"]
 mod engine;
+#[path = "../../../rag/parquet_export.rs"]
+pub mod parquet_export;
+#[path = "../../../rag/session_index.rs"]
+pub mod session_index;
 #[path = "../../../rag/sqlite.rs"]
 pub mod sqlite;
 #[path = "../../../rag/store.rs"]
@@ -16,5 +22,6 @@ pub mod store;
 
 pub use context_builder::{ContextBuilderConfig, RAGContextBuilder};
 pub use engine::{RAGConfig, RAGEngine, TextChunk};
+pub use session_index::{SessionIndex, SessionMatch};
 pub use sqlite::SqliteRagStore;
 pub use store::{ChunkSearchResult, RagStore, StoredChunk};
@@ -0,0 +1,434 @@
+//! Per-message latency budget on the chat hot path.
+//!
+//! A `LatencyTrace` is created when a WebSocket message arrives and is carried
+//! as a task-local while the message is processed. The context pipeline and
+//! the LLM stream report into it, so the breakdown covers queueing, context
+//! build, first token and stream duration without threading it through every
+//! node. When the first token is late, a diagnostic snapshot can be attached
+//! to the trace so the slow run carries the state it happened in.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::{mpsc, Notify};
+
+use crate::core::config::schema::AppSettings;
+use crate::core::config::TeporaConfig;
+use crate::core::errors::ApiError;
+use crate::core::health::HealthReport;
+use crate::core::utilization::UtilizationSnapshot;
+use crate::llm::types::NormalizedStreamChunk;
+
+/// Number of recent messages kept for the percentile summary
+const MAX_SAMPLES: usize = 256;
+
+tokio::task_local! {
+    static CURRENT_TRACE: Arc<LatencyTrace>;
+}
+
+#[derive(Debug)]
+pub struct LatencyTrace {
+    pub request_id: String,
+    pub session_id: String,
+    received_at: Instant,
+    started_at: OnceLock<Instant>,
+    first_token_at: OnceLock<Instant>,
+    first_token: Notify,
+    context_build_ms: AtomicU64,
+    /// Estimated size of the most recently built context
+    context_tokens: AtomicU64,
+    stream_ms: AtomicU64,
+    diagnostics: OnceLock<FirstTokenDiagnostics>,
+}
+
+impl LatencyTrace {
+    pub fn new(
+        request_id: impl Into<String>,
+        session_id: impl Into<String>,
+        received_at: Instant,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            request_id: request_id.into(),
+            session_id: session_id.into(),
+            received_at,
+            started_at: OnceLock::new(),
+            first_token_at: OnceLock::new(),
+            first_token: Notify::new(),
+            context_build_ms: AtomicU64::new(0),
+            context_tokens: AtomicU64::new(0),
+            stream_ms: AtomicU64::new(0),
+            diagnostics: OnceLock::new(),
+        })
+    }
+
+    /// End of queueing: the message is now being processed.
+    pub fn mark_started(&self) {
+        let _ = self.started_at.set(Instant::now());
+    }
+
+    fn mark_first_token(&self) {
+        if self.first_token_at.set(Instant::now()).is_ok() {
+            self.first_token.notify_waiters();
+        }
+    }
+
+    /// Wait until the first token arrives or `limit` has passed since the
+    /// message was received. Returns whether the token arrived in time.
+    pub async fn wait_first_token(&self, limit: Duration) -> bool {
+        let deadline = tokio::time::Instant::from_std(self.received_at + limit);
+        tokio::time::timeout_at(deadline, async {
+            loop {
+                // Register before checking so a token set in between still wakes us
+                let notified = self.first_token.notified();
+                if self.first_token_at.get().is_some() {
+                    return;
+                }
+                notified.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+
+    pub fn context_tokens(&self) -> Option<u64> {
+        match self.context_tokens.load(Ordering::Relaxed) {
+            0 => None,
+            tokens => Some(tokens),
+        }
+    }
+
+    /// Keep the first snapshot only; later ones would describe a run that
+    /// has already produced output.
+    pub fn attach_diagnostics(&self, diagnostics: FirstTokenDiagnostics) {
+        let _ = self.diagnostics.set(diagnostics);
+    }
+
+    pub fn diagnostics(&self) -> Option<&FirstTokenDiagnostics> {
+        self.diagnostics.get()
+    }
+
+    pub fn breakdown(&self) -> LatencyBreakdown {
+        let since_received = |at: Instant| at.duration_since(self.received_at).as_millis() as u64;
+        LatencyBreakdown {
+            queue_ms: self
+                .started_at
+                .get()
+                .copied()
+                .map(since_received)
+                .unwrap_or(0),
+            context_build_ms: self.context_build_ms.load(Ordering::Relaxed),
+            first_token_ms: self.first_token_at.get().copied().map(since_received),
+            stream_ms: self.stream_ms.load(Ordering::Relaxed),
+            total_ms: self.received_at.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// Milliseconds per phase. `first_token_ms` is measured from arrival, so it
+/// is what the user waits before text appears.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct LatencyBreakdown {
+    pub queue_ms: u64,
+    pub context_build_ms: u64,
+    pub first_token_ms: Option<u64>,
+    pub stream_ms: u64,
+    pub total_ms: u64,
+}
+
+/// State captured when the first token is later than
+/// `app.first_token_watchdog_ms`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FirstTokenDiagnostics {
+    pub captured_at: DateTime<Utc>,
+    pub threshold_ms: u64,
+    /// Time since the message was received when the snapshot was taken
+    pub waited_ms: u64,
+    pub context_tokens: Option<u64>,
+    pub provider: ProviderProbe,
+    /// Latest result of the periodic subsystem health check
+    pub health: Option<HealthReport>,
+    /// `GET /slots` of llama-server, when it is the active loader and answers
+    pub llama_slots: Option<serde_json::Value>,
+    pub utilization: UtilizationSnapshot,
+}
+
+/// Reachability of the loader serving the active text model.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProviderProbe {
+    pub model_id: Option<String>,
+    pub provider: Option<String>,
+    pub ok: bool,
+    pub detail: String,
+    pub latency_ms: u64,
+}
+
+/// Run `fut` with `trace` as the current trace.
+pub async fn scoped<F: Future>(trace: Arc<LatencyTrace>, fut: F) -> F::Output {
+    CURRENT_TRACE.scope(trace, fut).await
+}
+
+pub fn current() -> Option<Arc<LatencyTrace>> {
+    CURRENT_TRACE.try_with(Arc::clone).ok()
+}
+
+/// Record one context pipeline run. Agent turns may build several contexts.
+pub fn record_context_build(elapsed: Duration) {
+    if let Some(trace) = current() {
+        trace
+            .context_build_ms
+            .fetch_add(elapsed.as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Record the size of a context that is about to be sent to the model.
+pub fn record_context_tokens(tokens: usize) {
+    if let Some(trace) = current() {
+        trace.context_tokens.store(tokens as u64, Ordering::Relaxed);
+    }
+}
+
+/// Relay a stream to time its first token and its duration. Outside a trace
+/// the receiver is returned untouched.
+pub fn observe_stream(
+    mut stream: mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>>,
+    buffer: usize,
+) -> mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>> {
+    let Some(trace) = current() else {
+        return stream;
+    };
+    let (tx, rx) = mpsc::channel(buffer.max(1));
+    tokio::spawn(async move {
+        let opened_at = Instant::now();
+        while let Some(item) = stream.recv().await {
+            if let Ok(chunk) = &item {
+                if !chunk.visible_text.is_empty() || !chunk.model_thinking.is_empty() {
+                    trace.mark_first_token();
+                }
+            }
+            if tx.send(item).await.is_err() {
+                break;
+            }
+        }
+        trace
+            .stream_ms
+            .fetch_add(opened_at.elapsed().as_millis() as u64, Ordering::Relaxed);
+    });
+    rx
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct PhaseLatency {
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+}
+
+impl PhaseLatency {
+    fn from_values(mut values: Vec<u64>) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        values.sort_unstable();
+        let pick = |fraction: f64| {
+            let index = ((values.len() - 1) as f64 * fraction).round() as usize;
+            values[index]
+        };
+        Self {
+            p50_ms: pick(0.50),
+            p95_ms: pick(0.95),
+            max_ms: values[values.len() - 1],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub samples: usize,
+    pub slow_total: u64,
+    pub queue: PhaseLatency,
+    pub context_build: PhaseLatency,
+    pub first_token: PhaseLatency,
+    pub stream: PhaseLatency,
+    pub total: PhaseLatency,
+}
+
+/// Recent breakdowns for `GET /api/metrics/runtime`.
+#[derive(Debug, Default)]
+pub struct LatencyStats {
+    samples: std::sync::Mutex<VecDeque<LatencyBreakdown>>,
+    slow_total: AtomicU64,
+}
+
+impl LatencyStats {
+    pub fn record(&self, breakdown: LatencyBreakdown, slow: bool) {
+        if slow {
+            self.slow_total.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Ok(mut samples) = self.samples.lock() {
+            if samples.len() == MAX_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(breakdown);
+        }
+    }
+
+    pub fn summary(&self) -> LatencySummary {
+        let samples = self
+            .samples
+            .lock()
+            .map(|samples| samples.iter().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        let phase = |pick: fn(&LatencyBreakdown) -> Option<u64>| {
+            PhaseLatency::from_values(samples.iter().filter_map(pick).collect())
+        };
+        LatencySummary {
+            samples: samples.len(),
+            slow_total: self.slow_total.load(Ordering::Relaxed),
+            queue: phase(|b| Some(b.queue_ms)),
+            context_build: phase(|b| Some(b.context_build_ms)),
+            first_token: phase(|b| b.first_token_ms),
+            stream: phase(|b| Some(b.stream_ms)),
+            total: phase(|b| Some(b.total_ms)),
+        }
+    }
+}
+
+pub fn slow_threshold_ms(config: &serde_json::Value) -> u64 {
+    TeporaConfig::from_value(config)
+        .map(|config| config.app.slow_request_warn_ms)
+        .unwrap_or_else(|_| AppSettings::default().slow_request_warn_ms)
+}
+
+/// First-token wait after which diagnostics are captured. `None` when the
+/// watchdog is disabled (`0`).
+pub fn first_token_watchdog(config: &serde_json::Value) -> Option<Duration> {
+    let ms = TeporaConfig::from_value(config)
+        .map(|config| config.app.first_token_watchdog_ms)
+        .unwrap_or_else(|_| AppSettings::default().first_token_watchdog_ms);
+    (ms > 0).then(|| Duration::from_millis(ms))
+}
+
+/// Log the finished trace and add it to `stats`. Messages slower than
+/// `slow_threshold_ms` end to end are logged as warnings.
+pub fn finish(trace: &LatencyTrace, stats: &LatencyStats, slow_threshold_ms: u64) {
+    let breakdown = trace.breakdown();
+    let slow = breakdown.total_ms >= slow_threshold_ms;
+    if slow {
+        tracing::warn!(
+            request_id = %trace.request_id,
+            session_id = %trace.session_id,
+            queue_ms = breakdown.queue_ms,
+            context_build_ms = breakdown.context_build_ms,
+            first_token_ms = ?breakdown.first_token_ms,
+            stream_ms = breakdown.stream_ms,
+            total_ms = breakdown.total_ms,
+            slow_threshold_ms,
+            first_token_diagnostics = trace.diagnostics().is_some(),
+            "Slow chat request"
+        );
+    } else {
+        tracing::info!(
+            request_id = %trace.request_id,
+            session_id = %trace.session_id,
+            queue_ms = breakdown.queue_ms,
+            context_build_ms = breakdown.context_build_ms,
+            first_token_ms = ?breakdown.first_token_ms,
+            stream_ms = breakdown.stream_ms,
+            total_ms = breakdown.total_ms,
+            "Chat request latency"
+        );
+    }
+    stats.record(breakdown, slow);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(text: &str) -> Result<NormalizedStreamChunk, ApiError> {
+        Ok(NormalizedStreamChunk {
+            visible_text: text.to_string(),
+            model_thinking: String::new(),
+            done: false,
+            usage: None,
+            finish_reason: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn scoped_trace_collects_context_and_stream_phases() {
+        let trace = LatencyTrace::new("r1", "s1", Instant::now());
+        let inner = trace.clone();
+        scoped(trace.clone(), async move {
+            inner.mark_started();
+            record_context_build(Duration::from_millis(40));
+            record_context_build(Duration::from_millis(2));
+            let (tx, rx) = mpsc::channel(4);
+            let mut observed = observe_stream(rx, 4);
+            tx.send(chunk("")).await.unwrap();
+            tx.send(chunk("hi")).await.unwrap();
+            drop(tx);
+            while observed.recv().await.is_some() {}
+        })
+        .await;
+
+        let breakdown = trace.breakdown();
+        assert_eq!(breakdown.context_build_ms, 42);
+        assert!(breakdown.first_token_ms.is_some());
+        assert!(breakdown.total_ms >= breakdown.queue_ms);
+
+        // outside a scope nothing is recorded and streams pass through
+        record_context_build(Duration::from_millis(100));
+        assert_eq!(trace.breakdown().context_build_ms, 42);
+    }
+
+    #[tokio::test]
+    async fn wait_first_token_reports_late_and_timely_tokens() {
+        let late = LatencyTrace::new("r1", "s1", Instant::now());
+        assert!(!late.wait_first_token(Duration::from_millis(20)).await);
+
+        let timely = LatencyTrace::new("r2", "s1", Instant::now());
+        let waiter = {
+            let trace = timely.clone();
+            tokio::spawn(async move { trace.wait_first_token(Duration::from_secs(5)).await })
+        };
+        scoped(timely.clone(), async {
+            record_context_tokens(1_200);
+            let (tx, rx) = mpsc::channel(4);
+            let mut observed = observe_stream(rx, 4);
+            tx.send(chunk("hi")).await.unwrap();
+            observed.recv().await;
+        })
+        .await;
+        assert!(waiter.await.unwrap());
+        assert_eq!(timely.context_tokens(), Some(1_200));
+        // already arrived: returns at once
+        assert!(timely.wait_first_token(Duration::ZERO).await);
+    }
+
+    #[test]
+    fn summary_reports_percentiles_and_slow_requests() {
+        let stats = LatencyStats::default();
+        for total_ms in [100, 200, 300, 400, 5_000] {
+            stats.record(
+                LatencyBreakdown {
+                    total_ms,
+                    first_token_ms: (total_ms < 5_000).then_some(total_ms / 2),
+                    ..LatencyBreakdown::default()
+                },
+                total_ms >= 5_000,
+            );
+        }
+        let summary = stats.summary();
+        assert_eq!(summary.samples, 5);
+        assert_eq!(summary.slow_total, 1);
+        assert_eq!(summary.total.p50_ms, 300);
+        assert_eq!(summary.total.max_ms, 5_000);
+        assert_eq!(summary.first_token.max_ms, 200);
+    }
+}
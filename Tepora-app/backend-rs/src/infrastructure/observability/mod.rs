@@ -1,3 +1,4 @@
+pub mod latency;
 mod runtime_metrics;
 #[allow(unused_imports)]
 pub use runtime_metrics::{RuntimeMetrics, RuntimeMetricsSnapshot, SessionBusyMetric};
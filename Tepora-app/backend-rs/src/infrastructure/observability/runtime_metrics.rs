@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
 
+use super::latency::{LatencyStats, LatencySummary};
+
 #[derive(Debug, Clone, Serialize)]
 pub struct SessionBusyMetric {
     pub session_id: String,
@@ -16,6 +18,7 @@ pub struct RuntimeMetricsSnapshot {
     pub too_many_sessions_total: u64,
     pub internal_error_total: u64,
     pub session_busy_top: Vec<SessionBusyMetric>,
+    pub latency: LatencySummary,
 }
 
 #[derive(Debug, Default)]
@@ -25,6 +28,7 @@ pub struct RuntimeMetrics {
     too_many_sessions_total: AtomicU64,
     internal_error_total: AtomicU64,
     session_busy_by_session: Mutex<HashMap<String, u64>>,
+    latency: LatencyStats,
 }
 
 impl RuntimeMetrics {
@@ -50,6 +54,10 @@ impl RuntimeMetrics {
         self.internal_error_total.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub fn latency(&self) -> &LatencyStats {
+        &self.latency
+    }
+
     pub fn snapshot(&self, top_n: usize) -> RuntimeMetricsSnapshot {
         let mut session_busy_top = self
             .session_busy_by_session
@@ -82,6 +90,7 @@ impl RuntimeMetrics {
             too_many_sessions_total: self.too_many_sessions_total.load(Ordering::Relaxed),
             internal_error_total: self.internal_error_total.load(Ordering::Relaxed),
             session_busy_top,
+            latency: self.latency.summary(),
         }
     }
 
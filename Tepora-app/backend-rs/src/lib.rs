@@ -17,8 +17,10 @@ pub mod mcp;
 #[path = "infrastructure/episodic_store/memory/mod.rs"]
 pub mod memory;
 pub mod models;
+pub mod plugins;
 #[path = "infrastructure/knowledge_store/rag/mod.rs"]
 pub mod rag;
+pub mod scripting;
 pub mod search;
 pub mod server;
 pub mod state;
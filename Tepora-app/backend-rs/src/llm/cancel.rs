@@ -0,0 +1,58 @@
+//! 生成の取り消し。
+//!
+//! セッションは 1 回の実行ごとに `CancellationToken` を作り、タスクローカルに載せて
+//! グラフを走らせる。`LlmService` はリクエストにトークンが無ければここから拾って
+//! `ChatRequest::cancel` に入れ、各プロバイダーのストリームはトークンが取り消されたら
+//! HTTP 接続を落として抜ける。llama-server も外部のサーバーも接続が切れると生成を
+//! やめるので、WS の `stop` でスロットが空く。
+
+use std::future::Future;
+
+use tokio_util::sync::CancellationToken;
+
+use crate::llm::types::ChatRequest;
+
+tokio::task_local! {
+    static CURRENT_CANCEL: CancellationToken;
+}
+
+/// `fut` の中で開いたストリームを `token` で取り消せるようにする。
+pub async fn scoped<F: Future>(token: CancellationToken, fut: F) -> F::Output {
+    CURRENT_CANCEL.scope(token, fut).await
+}
+
+pub fn current() -> Option<CancellationToken> {
+    CURRENT_CANCEL.try_with(CancellationToken::clone).ok()
+}
+
+/// 呼び出し側が指定していなければ、いまの実行のトークンを付ける。
+pub(crate) fn attach(mut request: ChatRequest) -> ChatRequest {
+    if request.cancel.is_none() {
+        request.cancel = current();
+    }
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn attach_uses_the_scoped_token_unless_one_is_set() {
+        assert!(attach(ChatRequest::new(Vec::new())).cancel.is_none());
+
+        let run = CancellationToken::new();
+        let own = CancellationToken::new();
+        let (scoped_request, explicit_request) = scoped(run.clone(), async {
+            (
+                attach(ChatRequest::new(Vec::new())),
+                attach(ChatRequest::new(Vec::new()).with_cancel(own.clone())),
+            )
+        })
+        .await;
+
+        run.cancel();
+        assert!(scoped_request.cancellation().is_cancelled());
+        assert!(!explicit_request.cancellation().is_cancelled());
+    }
+}
@@ -0,0 +1,340 @@
+//! プロバイダーごとのサーキットブレーカー。
+//!
+//! `LlmService` は一時的な失敗（繋がらない・タイムアウト・429・5xx）を
+//! `llm_manager.retry_*` の回数までジッター付きの指数バックオフで送り直し、
+//! 結果をここに記録する。同じプロバイダーで失敗が `circuit_failure_threshold` 回
+//! 続いたら、`circuit_open_ms` の間は送らずに `ProviderUnavailable` を返す。
+//! 時間が過ぎたら 1 件だけ通し、成功すれば元に戻し、失敗すればまた止める。
+//! 状態は `GET /api/llm/providers` で見られる。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::Serialize;
+
+use crate::core::errors::ApiError;
+use crate::core::net_retry::is_retryable_status;
+use crate::llm::gemini_provider::GEMINI_LOADER;
+use crate::llm::llama_service::LLAMA_CPP_LOADER;
+use crate::llm::openai_compatible_provider::OPENAI_COMPATIBLE_LOADER;
+use crate::llm::openrouter_provider::OPENROUTER_LOADER;
+
+/// まだ使っていなくても一覧に出すローダー
+const KNOWN_PROVIDERS: &[&str] = &[
+    LLAMA_CPP_LOADER,
+    "ollama",
+    "lmstudio",
+    OPENAI_COMPATIBLE_LOADER,
+    GEMINI_LOADER,
+    OPENROUTER_LOADER,
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct BreakerSettings {
+    pub failure_threshold: u32,
+    pub open_for: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    /// 送信を止めている
+    Open,
+    /// 止めていた時間が過ぎ、次の 1 件で戻すか決める
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderHealth {
+    pub provider: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub total_failures: u64,
+    pub total_successes: u64,
+    pub last_error: Option<String>,
+    pub last_failure_at: Option<DateTime<Utc>>,
+    /// 止めている間、次に試せるようになる時刻
+    pub retry_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    total_failures: u64,
+    total_successes: u64,
+    last_error: Option<String>,
+    last_failure_at: Option<DateTime<Utc>>,
+    /// 止めた時刻。`Some` の間は開いているか半開き
+    opened_at: Option<(Instant, DateTime<Utc>)>,
+    /// 半開きで通した試しの送信。終わらずに消えても `open_for` 後には次を通す
+    probe_started: Option<Instant>,
+}
+
+impl Breaker {
+    fn state(&self, now: Instant, settings: &BreakerSettings) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some((at, _)) if now.duration_since(at) < settings.open_for => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    fn health(&self, provider: &str, now: Instant, settings: &BreakerSettings) -> ProviderHealth {
+        let state = self.state(now, settings);
+        ProviderHealth {
+            provider: provider.to_string(),
+            state,
+            consecutive_failures: self.consecutive_failures,
+            total_failures: self.total_failures,
+            total_successes: self.total_successes,
+            last_error: self.last_error.clone(),
+            last_failure_at: self.last_failure_at,
+            retry_at: match (state, self.opened_at) {
+                (CircuitState::Open, Some((_, wall))) => {
+                    chrono::Duration::from_std(settings.open_for)
+                        .ok()
+                        .map(|open_for| wall + open_for)
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+/// ローダー名ごとのブレーカー。`LlmService` の複製どうしで共有する。
+#[derive(Clone, Default)]
+pub struct ProviderBreakers {
+    breakers: Arc<Mutex<HashMap<String, Breaker>>>,
+}
+
+impl ProviderBreakers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_breaker<T>(&self, provider: &str, f: impl FnOnce(&mut Breaker) -> T) -> T {
+        let mut breakers = self
+            .breakers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(breakers.entry(provider_key(provider)).or_default())
+    }
+
+    /// 送ってよいか。止めている間は `ProviderUnavailable` を返す。
+    pub(crate) fn admit(&self, provider: &str, settings: &BreakerSettings) -> Result<(), ApiError> {
+        let now = Instant::now();
+        self.with_breaker(provider, |breaker| match breaker.state(now, settings) {
+            CircuitState::Closed => Ok(()),
+            CircuitState::HalfOpen
+                if breaker
+                    .probe_started
+                    .is_none_or(|at| now.duration_since(at) >= settings.open_for) =>
+            {
+                breaker.probe_started = Some(now);
+                Ok(())
+            }
+            _ => Err(ApiError::ProviderUnavailable {
+                provider: provider.to_string(),
+                message: format!(
+                    "paused after {} consecutive failures (last: {})",
+                    breaker.consecutive_failures,
+                    breaker.last_error.as_deref().unwrap_or("unknown error")
+                ),
+            }),
+        })
+    }
+
+    pub(crate) fn record_success(&self, provider: &str) {
+        self.with_breaker(provider, |breaker| {
+            breaker.consecutive_failures = 0;
+            breaker.total_successes += 1;
+            breaker.opened_at = None;
+            breaker.probe_started = None;
+        });
+    }
+
+    pub(crate) fn record_failure(
+        &self,
+        provider: &str,
+        err: &ApiError,
+        settings: &BreakerSettings,
+    ) {
+        let now = Instant::now();
+        self.with_breaker(provider, |breaker| {
+            breaker.consecutive_failures = breaker.consecutive_failures.saturating_add(1);
+            breaker.total_failures += 1;
+            breaker.last_error = Some(err.to_string());
+            breaker.last_failure_at = Some(Utc::now());
+            let probe_failed = breaker.probe_started.take().is_some();
+            if probe_failed || breaker.consecutive_failures >= settings.failure_threshold {
+                if breaker.opened_at.is_none() || probe_failed {
+                    tracing::warn!(
+                        provider = %provider,
+                        failures = breaker.consecutive_failures,
+                        "Pausing requests to a failing provider"
+                    );
+                }
+                breaker.opened_at = Some((now, Utc::now()));
+            }
+        });
+    }
+
+    pub(crate) fn is_open(&self, provider: &str, settings: &BreakerSettings) -> bool {
+        let now = Instant::now();
+        self.with_breaker(provider, |breaker| {
+            breaker.state(now, settings) == CircuitState::Open
+        })
+    }
+
+    /// 既知のローダーと、これまでに送ったことのあるローダーの状態。名前順。
+    pub fn snapshot(&self, settings: &BreakerSettings) -> Vec<ProviderHealth> {
+        let now = Instant::now();
+        let breakers = self
+            .breakers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let idle = Breaker::default();
+        let mut names = KNOWN_PROVIDERS
+            .iter()
+            .map(|name| name.to_string())
+            .chain(breakers.keys().cloned())
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        names
+            .into_iter()
+            .map(|name| {
+                breakers
+                    .get(&name)
+                    .unwrap_or(&idle)
+                    .health(&name, now, settings)
+            })
+            .collect()
+    }
+}
+
+fn provider_key(provider: &str) -> String {
+    provider.trim().to_ascii_lowercase()
+}
+
+/// 送り直せば通る見込みのある失敗か。プロバイダーが内容を見て断ったもの
+/// （400 など）は含めない。
+pub(crate) fn is_transient(err: &ApiError) -> bool {
+    match err {
+        ApiError::ProviderUnavailable { .. }
+        | ApiError::ServiceUnavailable(_)
+        | ApiError::TooManyRequests => true,
+        ApiError::Internal(message) => {
+            message.contains("timed out")
+                || message.starts_with("Failed to reach")
+                || upstream_status(message).is_some_and(is_retryable_status)
+        }
+        _ => false,
+    }
+}
+
+/// クライアントのエラー文 `... failed (503 Service Unavailable): ...` から応答のステータスを拾う。
+fn upstream_status(message: &str) -> Option<StatusCode> {
+    message.match_indices('(').find_map(|(index, _)| {
+        let rest = &message[index + 1..];
+        let code = rest.get(..3)?;
+        let after = rest[3..].chars().next()?;
+        if !code.bytes().all(|b| b.is_ascii_digit()) || !matches!(after, ' ' | ')') {
+            return None;
+        }
+        StatusCode::from_u16(code.parse().ok()?).ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> BreakerSettings {
+        BreakerSettings {
+            failure_threshold: 2,
+            open_for: Duration::from_millis(50),
+        }
+    }
+
+    fn unreachable() -> ApiError {
+        ApiError::ProviderUnavailable {
+            provider: "ollama".to_string(),
+            message: "Cannot connect".to_string(),
+        }
+    }
+
+    #[test]
+    fn opens_after_repeated_failures_and_closes_after_a_successful_probe() {
+        let breakers = ProviderBreakers::new();
+        let settings = settings();
+
+        breakers.record_failure("ollama", &unreachable(), &settings);
+        assert!(breakers.admit("ollama", &settings).is_ok());
+        breakers.record_failure("Ollama", &unreachable(), &settings);
+        assert!(breakers.is_open("ollama", &settings));
+        assert!(matches!(
+            breakers.admit("ollama", &settings),
+            Err(ApiError::ProviderUnavailable { .. })
+        ));
+        let open = breakers
+            .snapshot(&settings)
+            .into_iter()
+            .find(|health| health.provider == "ollama")
+            .unwrap();
+        assert_eq!(open.state, CircuitState::Open);
+        assert!(open.retry_at.is_some());
+
+        std::thread::sleep(Duration::from_millis(60));
+        // 半開きでは 1 件だけ通す
+        assert!(breakers.admit("ollama", &settings).is_ok());
+        assert!(breakers.admit("ollama", &settings).is_err());
+        breakers.record_success("ollama");
+        assert!(breakers.admit("ollama", &settings).is_ok());
+
+        let health = breakers.snapshot(&settings);
+        assert!(health.iter().any(|health| health.provider == "gemini"));
+        let ollama = health
+            .iter()
+            .find(|health| health.provider == "ollama")
+            .unwrap();
+        assert_eq!(ollama.state, CircuitState::Closed);
+        assert_eq!(ollama.total_failures, 2);
+    }
+
+    #[test]
+    fn a_failed_probe_reopens_immediately() {
+        let breakers = ProviderBreakers::new();
+        let settings = settings();
+        breakers.record_failure("gemini", &unreachable(), &settings);
+        breakers.record_failure("gemini", &unreachable(), &settings);
+        std::thread::sleep(Duration::from_millis(60));
+
+        assert!(breakers.admit("gemini", &settings).is_ok());
+        breakers.record_failure("gemini", &unreachable(), &settings);
+        assert!(breakers.is_open("gemini", &settings));
+    }
+
+    #[test]
+    fn only_transient_errors_are_retried() {
+        assert!(is_transient(&unreachable()));
+        assert!(is_transient(&ApiError::Internal(
+            "gemini streaming request failed (503 Service Unavailable): overloaded".to_string()
+        )));
+        assert!(is_transient(&ApiError::Internal(
+            "openrouter chat request failed (429 Too Many Requests): slow down".to_string()
+        )));
+        assert!(is_transient(&ApiError::Internal(
+            "ollama request timed out after 120000 ms (http://127.0.0.1:11434/api/chat)"
+                .to_string()
+        )));
+        assert!(!is_transient(&ApiError::Internal(
+            "openai_compatible chat request failed (400 Bad Request): bad schema".to_string()
+        )));
+        assert!(!is_transient(&ApiError::BadRequest("nope".to_string())));
+    }
+}
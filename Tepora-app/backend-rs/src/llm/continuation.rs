@@ -0,0 +1,146 @@
+//! 最大トークン数で打ち切られた生成の続き。
+//!
+//! プロバイダーが長さ制限で止まったら、それまでの出力を assistant ターンとして渡して
+//! 続きを書かせる。モデルは続きの頭で直前の文を繰り返しがちなので、前の出力の末尾と
+//! 重なる部分は削ってからつなぐ。
+
+use crate::llm::types::{ChatMessage, ChatRequest};
+
+const CONTINUE_PROMPT: &str = "Your previous reply was cut off by the length limit. \
+Continue exactly where it stopped, without repeating earlier text or adding any preface.";
+
+/// 重なりを探す範囲（前の出力の末尾バイト数）
+const OVERLAP_WINDOW: usize = 200;
+/// これより短い一致は偶然とみなして削らない
+const MIN_OVERLAP: usize = 12;
+
+/// 長さ制限で止まったか。表記はプロバイダーごとに違う。
+pub(crate) fn stopped_at_token_limit(finish_reason: Option<&str>) -> bool {
+    finish_reason.is_some_and(|reason| {
+        ["length", "max_tokens", "limit", "maxPredictedTokensReached"]
+            .iter()
+            .any(|known| reason.eq_ignore_ascii_case(known))
+    })
+}
+
+/// 元の会話に、ここまでの出力と続きを促す指示を足したリクエスト。
+pub(crate) fn continuation_request(base: &ChatRequest, generated: &str) -> ChatRequest {
+    let mut request = base.clone();
+    request
+        .messages
+        .push(ChatMessage::new_text("assistant", generated));
+    request
+        .messages
+        .push(ChatMessage::new_text("user", CONTINUE_PROMPT));
+    request
+}
+
+/// 続きの先頭から、前の出力と重なる部分を取り除く。ストリームでは判定できるだけ
+/// 溜まるまで出力を保留する。
+#[derive(Debug)]
+pub(crate) struct OverlapTrimmer {
+    tail: String,
+    pending: String,
+    resolved: bool,
+}
+
+impl OverlapTrimmer {
+    pub(crate) fn new(previous: &str) -> Self {
+        let mut start = previous.len().saturating_sub(OVERLAP_WINDOW);
+        while !previous.is_char_boundary(start) {
+            start += 1;
+        }
+        Self {
+            tail: previous[start..].to_string(),
+            pending: String::new(),
+            resolved: false,
+        }
+    }
+
+    pub(crate) fn push(&mut self, text: &str) -> String {
+        if self.resolved {
+            return text.to_string();
+        }
+        self.pending.push_str(text);
+        if self.pending.len() < self.tail.len() {
+            return String::new();
+        }
+        self.resolve()
+    }
+
+    pub(crate) fn finish(&mut self) -> String {
+        if self.resolved {
+            return String::new();
+        }
+        self.resolve()
+    }
+
+    fn resolve(&mut self) -> String {
+        self.resolved = true;
+        let overlap = overlap_len(&self.tail, &self.pending);
+        self.pending.drain(..overlap);
+        std::mem::take(&mut self.pending)
+    }
+}
+
+/// `next` の先頭が `tail` の末尾と一致する最長のバイト数。
+fn overlap_len(tail: &str, next: &str) -> usize {
+    let max = tail.len().min(next.len());
+    (MIN_OVERLAP..=max)
+        .rev()
+        .find(|len| next.is_char_boundary(*len) && tail.ends_with(&next[..*len]))
+        .unwrap_or(0)
+}
+
+/// 一度に渡せる文字列をつなぐ（非ストリーム用）。
+pub(crate) fn stitch(previous: &str, next: &str) -> String {
+    let mut trimmer = OverlapTrimmer::new(previous);
+    let mut joined = trimmer.push(next);
+    joined.push_str(&trimmer.finish());
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_length_stops_from_each_provider() {
+        assert!(stopped_at_token_limit(Some("length")));
+        assert!(stopped_at_token_limit(Some("limit")));
+        assert!(stopped_at_token_limit(Some("maxPredictedTokensReached")));
+        assert!(!stopped_at_token_limit(Some("stop")));
+        assert!(!stopped_at_token_limit(Some("eos")));
+        assert!(!stopped_at_token_limit(None));
+    }
+
+    #[test]
+    fn continuation_appends_partial_answer_and_prompt() {
+        let base = ChatRequest::new(vec![ChatMessage::new_text("user", "write a long essay")]);
+        let request = continuation_request(&base, "The first half");
+        assert_eq!(request.messages.len(), 3);
+        assert_eq!(request.messages[1].role, "assistant");
+        assert_eq!(request.messages[1].content, "The first half");
+        assert_eq!(request.messages[2].role, "user");
+    }
+
+    #[test]
+    fn repeated_sentence_is_trimmed_at_the_seam() {
+        let previous = "fn main() {\n    println!(\"hello world\");\n    let total = ";
+        let next = "    println!(\"hello world\");\n    let total = 42;\n}";
+        assert_eq!(stitch(previous, next), "42;\n}");
+        // 短い偶然の一致は残す
+        assert_eq!(stitch("ends with a", "a new line"), "a new line");
+    }
+
+    #[test]
+    fn streamed_continuation_is_held_until_overlap_is_known() {
+        let previous = "日本語の文章が途中で切れてしまった";
+        let mut trimmer = OverlapTrimmer::new(previous);
+        assert_eq!(trimmer.push("途中で切れて"), "");
+        let mut output = trimmer.push("しまった。続きはこちら");
+        output.push_str(&trimmer.push("です。"));
+        output.push_str(&trimmer.finish());
+        assert_eq!(output, "。続きはこちらです。");
+    }
+}
@@ -1,117 +1,86 @@
 use std::time::Duration;
 
-use reqwest::Client;
 use serde_json::{json, Value};
 
+use crate::core::config::schema::LlmManagerSettings;
 use crate::core::config::ConfigService;
 use crate::core::errors::ApiError;
+use crate::core::net_retry::RetryPolicy;
+use crate::core::network::NetClient;
+use crate::llm::circuit_breaker::BreakerSettings;
+use crate::llm::openai_compatible_provider::OPENAI_COMPATIBLE_LOADER;
+use crate::llm::tool_calls::openai_tools_value;
 use crate::llm::types::{ChatRequest, TokenUsage};
 #[cfg(test)]
 use crate::llm::types::{NormalizedAssistantTurn, NormalizedStreamChunk};
 
-const DEFAULT_PROCESS_TERMINATE_TIMEOUT: Duration = Duration::from_secs(5);
-const DEFAULT_EXTERNAL_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
-const DEFAULT_EXTERNAL_STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
-const DEFAULT_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(15);
-const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_millis(500);
-const DEFAULT_STREAM_CHANNEL_BUFFER: usize = 128;
-const DEFAULT_STREAM_INTERNAL_BUFFER: usize = 100;
+fn llm_manager_settings(config: &ConfigService) -> LlmManagerSettings {
+    config
+        .load_typed()
+        .map(|typed| typed.llm_manager)
+        .unwrap_or_default()
+}
 
 pub(crate) fn process_terminate_timeout(config: &ConfigService) -> Duration {
-    if let Ok(config) = config.load_config() {
-        if let Some(val) = config
-            .get("llm_manager")
-            .and_then(|m| m.get("process_terminate_timeout"))
-            .and_then(|v| v.as_u64())
-        {
-            return Duration::from_millis(val);
-        }
-    }
-    DEFAULT_PROCESS_TERMINATE_TIMEOUT
+    llm_manager_settings(config).process_terminate_timeout()
 }
 
 pub(crate) fn external_loader_request_timeout(config: &ConfigService) -> Duration {
-    if let Ok(config) = config.load_config() {
-        if let Some(val) = config
-            .get("llm_manager")
-            .and_then(|m| {
-                m.get("external_request_timeout_ms")
-                    .or_else(|| m.get("health_check_timeout"))
-            })
-            .and_then(|v| v.as_u64())
-        {
-            return Duration::from_millis(val.max(1));
-        }
-    }
-    DEFAULT_EXTERNAL_REQUEST_TIMEOUT
+    llm_manager_settings(config).external_request_timeout()
 }
 
 pub(crate) fn external_loader_stream_idle_timeout(config: &ConfigService) -> Duration {
-    if let Ok(config) = config.load_config() {
-        if let Some(val) = config
-            .get("llm_manager")
-            .and_then(|m| m.get("stream_idle_timeout_ms"))
-            .and_then(|v| v.as_u64())
-        {
-            return Duration::from_millis(val.max(1));
-        }
-    }
-    DEFAULT_EXTERNAL_STREAM_IDLE_TIMEOUT
+    llm_manager_settings(config).stream_idle_timeout()
 }
 
 pub(crate) fn health_check_timeout(config: &ConfigService) -> Duration {
-    if let Ok(config) = config.load_config() {
-        if let Some(val) = config
-            .get("llm_manager")
-            .and_then(|m| m.get("health_check_timeout"))
-            .and_then(|v| v.as_u64())
-        {
-            return Duration::from_millis(val.max(1));
-        }
-    }
-    DEFAULT_HEALTH_CHECK_TIMEOUT
+    llm_manager_settings(config).health_check_timeout()
 }
 
 pub(crate) fn health_check_interval(config: &ConfigService) -> Duration {
-    if let Ok(config) = config.load_config() {
-        if let Some(val) = config
-            .get("llm_manager")
-            .and_then(|m| {
-                m.get("health_check_interval_ms")
-                    .or_else(|| m.get("health_check_interval"))
-            })
-            .and_then(|v| v.as_u64())
-        {
-            return Duration::from_millis(val.max(1));
-        }
-    }
-    DEFAULT_HEALTH_CHECK_INTERVAL
+    llm_manager_settings(config).health_check_interval()
 }
 
 pub(crate) fn stream_channel_buffer(config: &ConfigService) -> usize {
-    if let Ok(config) = config.load_config() {
-        if let Some(val) = config
-            .get("llm_manager")
-            .and_then(|m| m.get("stream_channel_buffer"))
-            .and_then(|v| v.as_u64())
-        {
-            return val.clamp(1, 65_536) as usize;
-        }
-    }
-    DEFAULT_STREAM_CHANNEL_BUFFER
+    llm_manager_settings(config).stream_channel_buffer()
 }
 
 pub(crate) fn stream_internal_buffer(config: &ConfigService) -> usize {
-    if let Ok(config) = config.load_config() {
-        if let Some(val) = config
-            .get("llm_manager")
-            .and_then(|m| m.get("stream_internal_buffer"))
-            .and_then(|v| v.as_u64())
-        {
-            return val.clamp(1, 65_536) as usize;
-        }
+    llm_manager_settings(config).stream_internal_buffer()
+}
+
+pub(crate) fn parallel_slots(config: &ConfigService) -> usize {
+    llm_manager_settings(config).parallel_slots()
+}
+
+pub(crate) fn max_continuation_rounds(config: &ConfigService) -> usize {
+    llm_manager_settings(config).max_continuation_rounds()
+}
+
+pub(crate) fn provider_retry_policy(config: &ConfigService) -> RetryPolicy {
+    let settings = llm_manager_settings(config);
+    RetryPolicy {
+        max_attempts: settings.retry_max_attempts(),
+        base_delay: settings.retry_base_delay(),
+        max_delay: settings.retry_max_delay(),
     }
-    DEFAULT_STREAM_INTERNAL_BUFFER
+}
+
+pub(crate) fn provider_breaker_settings(config: &ConfigService) -> BreakerSettings {
+    let settings = llm_manager_settings(config);
+    BreakerSettings {
+        failure_threshold: settings.circuit_failure_threshold(),
+        open_for: settings.circuit_open_duration(),
+    }
+}
+
+/// アイドル停止までの時間（無効なら `None`）と、埋め込みモデルを残すかどうか。
+pub(crate) fn idle_unload_settings(config: &ConfigService) -> (Option<Duration>, bool) {
+    let settings = llm_manager_settings(config);
+    (
+        settings.idle_unload_timeout(),
+        settings.keep_embedding_loaded,
+    )
 }
 
 pub(crate) fn build_openai_compatible_chat_body(
@@ -145,11 +114,14 @@ pub(crate) fn build_openai_compatible_chat_body(
         if let Some(v) = request.top_p {
             obj.insert("top_p".to_string(), json!(v));
         }
-        if let Some(v) = request.top_k {
-            obj.insert("top_k".to_string(), json!(v));
-        }
-        if let Some(v) = request.repeat_penalty {
-            obj.insert("repeat_penalty".to_string(), json!(v));
+        // クラウドの OpenAI 互換 API は未知のサンプリング引数を 400 で弾く
+        if !loader.eq_ignore_ascii_case(OPENAI_COMPATIBLE_LOADER) {
+            if let Some(v) = request.top_k {
+                obj.insert("top_k".to_string(), json!(v));
+            }
+            if let Some(v) = request.repeat_penalty {
+                obj.insert("repeat_penalty".to_string(), json!(v));
+            }
         }
         if let Some(v) = request.max_tokens {
             obj.insert("max_tokens".to_string(), json!(v));
@@ -179,6 +151,10 @@ pub(crate) fn build_openai_compatible_chat_body(
                 }),
             );
         }
+        // ストリームではツール呼び出しを組み立てないので、非ストリームの時だけ渡す
+        if !stream && !request.tools.is_empty() {
+            obj.insert("tools".to_string(), openai_tools_value(&request.tools));
+        }
         if !stream && loader.eq_ignore_ascii_case("lmstudio") {
             obj.insert(
                 "stream_options".to_string(),
@@ -191,14 +167,14 @@ pub(crate) fn build_openai_compatible_chat_body(
 }
 
 pub(crate) async fn post_json(
-    http: &Client,
+    http: &NetClient,
     endpoint: &str,
     body: &Value,
     loader: &str,
     base_url: &str,
     request_timeout: Duration,
 ) -> Result<reqwest::Response, ApiError> {
-    let response = http.post(endpoint).json(body);
+    let response = http.post(endpoint)?.json(body);
     tokio::time::timeout(request_timeout, response.send())
         .await
         .map_err(|_| loader_timeout_error(loader, endpoint, request_timeout, "request"))?
@@ -274,10 +250,7 @@ pub(crate) fn unreachable_loader_error(
     base_url: &str,
     err: reqwest::Error,
 ) -> ApiError {
-    ApiError::Internal(format!(
-        "Failed to reach '{}' loader at {}: {}",
-        loader, base_url, err
-    ))
+    crate::llm::outage::send_error(loader, base_url, err)
 }
 
 pub(crate) fn loader_timeout_error(
@@ -348,6 +321,7 @@ impl ThinkTagDecoder {
                             model_thinking: reasoning,
                             done: false,
                             usage: None,
+                            finish_reason: None,
                         });
                     }
                     self.buffer.drain(..end + "</think>".len());
@@ -368,6 +342,7 @@ impl ThinkTagDecoder {
                         model_thinking: reasoning,
                         done: false,
                         usage: None,
+                        finish_reason: None,
                     });
                 }
                 break;
@@ -381,6 +356,7 @@ impl ThinkTagDecoder {
                         model_thinking: String::new(),
                         done: false,
                         usage: None,
+                        finish_reason: None,
                     });
                 }
                 self.buffer.drain(..start + "<think>".len());
@@ -401,6 +377,7 @@ impl ThinkTagDecoder {
                     model_thinking: String::new(),
                     done: false,
                     usage: None,
+                    finish_reason: None,
                 });
             }
             break;
@@ -420,6 +397,7 @@ impl ThinkTagDecoder {
                 model_thinking: self.buffer.clone(),
                 done: true,
                 usage: None,
+                finish_reason: None,
             }
         } else {
             NormalizedStreamChunk {
@@ -427,6 +405,7 @@ impl ThinkTagDecoder {
                 model_thinking: String::new(),
                 done: true,
                 usage: None,
+                finish_reason: None,
             }
         };
         self.buffer.clear();
@@ -506,6 +485,22 @@ mod tests {
         assert_eq!(ollama_usage.cached_prompt_tokens, None);
     }
 
+    #[test]
+    fn cloud_chat_body_omits_local_only_sampling_options() {
+        let mut request = ChatRequest::new(vec![]);
+        request.top_k = Some(40);
+        request.repeat_penalty = Some(1.1);
+        request.temperature = Some(0.7);
+
+        let local = build_openai_compatible_chat_body("lmstudio", "m", request.clone(), false);
+        assert_eq!(local["top_k"], json!(40));
+
+        let cloud = build_openai_compatible_chat_body("openai_compatible", "m", request, false);
+        assert!(cloud.get("top_k").is_none());
+        assert!(cloud.get("repeat_penalty").is_none());
+        assert_eq!(cloud["temperature"], json!(0.7));
+    }
+
     #[test]
     fn compose_reasoned_content_wraps_think_block() {
         assert_eq!(
@@ -0,0 +1,551 @@
+//! Google Gemini API（`generateContent` / `streamGenerateContent`）。
+//!
+//! 接続先は `loaders.gemini.base_url`、キーは `loaders.gemini.api_key`（`x-goog-api-key`
+//! ヘッダーで送る）。`loaders.gemini.safety_settings` はそのまま `safetySettings` に渡す。
+//! `ChatRequest` の system メッセージは `systemInstruction` にまとめ、assistant は `model`、
+//! それ以外は `user` として送る。
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use serde_json::{json, Map, Value};
+use tokio::sync::mpsc;
+
+use crate::core::config::schema::SafetySetting;
+use crate::core::errors::ApiError;
+use crate::core::network::NetClient;
+use crate::llm::external_loader_common::post_json;
+use crate::llm::openai_compatible_provider::ProviderTimeouts;
+use crate::llm::provider::LlmProvider;
+use crate::llm::stream_framing::SseFramer;
+use crate::llm::types::{
+    ChatMessage, ChatRequest, NormalizedAssistantTurn, NormalizedStreamChunk, ProviderModel,
+    TokenUsage,
+};
+
+pub const GEMINI_LOADER: &str = "gemini";
+pub const DEFAULT_GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com";
+
+pub struct GeminiProvider {
+    http: NetClient,
+    base_url: String,
+    timeouts: ProviderTimeouts,
+    safety_settings: Vec<SafetySetting>,
+}
+
+impl GeminiProvider {
+    pub(crate) fn new(
+        http: NetClient,
+        base_url: &str,
+        timeouts: ProviderTimeouts,
+        safety_settings: Vec<SafetySetting>,
+    ) -> Self {
+        Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            timeouts,
+            safety_settings,
+        }
+    }
+
+    fn model_endpoint(&self, model_id: &str, method: &str) -> String {
+        format!(
+            "{}/v1beta/models/{}:{}",
+            self.base_url,
+            model_id.trim_start_matches("models/"),
+            method
+        )
+    }
+
+    async fn get_models(&self) -> Result<reqwest::Response, ApiError> {
+        let endpoint = format!("{}/v1beta/models?pageSize=1000", self.base_url);
+        self.http
+            .get(&endpoint)?
+            .timeout(self.timeouts.request)
+            .send()
+            .await
+            .map_err(|err| {
+                ApiError::internal(format!(
+                    "{} is unreachable at {}: {}",
+                    GEMINI_LOADER, self.base_url, err
+                ))
+            })
+    }
+
+    async fn post(&self, endpoint: &str, body: &Value) -> Result<reqwest::Response, ApiError> {
+        let response = post_json(
+            &self.http,
+            endpoint,
+            body,
+            GEMINI_LOADER,
+            &self.base_url,
+            self.timeouts.request,
+        )
+        .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(ApiError::Internal(format!(
+                "{} request failed ({}): {}",
+                GEMINI_LOADER, status, text
+            )));
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    fn name(&self) -> &str {
+        GEMINI_LOADER
+    }
+
+    async fn health_check(&self) -> Result<bool, ApiError> {
+        Ok(self.get_models().await?.status().is_success())
+    }
+
+    async fn list_models(&self) -> Result<Vec<ProviderModel>, ApiError> {
+        let response = self.get_models().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(ApiError::Internal(format!(
+                "{} model listing failed ({}): {}",
+                GEMINI_LOADER, status, text
+            )));
+        }
+        let payload: Value = response.json().await.map_err(ApiError::internal)?;
+        Ok(parse_model_list(&payload))
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest,
+        model_id: &str,
+    ) -> Result<NormalizedAssistantTurn, ApiError> {
+        let body = build_generate_body(&request, &self.safety_settings);
+        let endpoint = self.model_endpoint(model_id, "generateContent");
+        let payload: Value = self
+            .post(&endpoint, &body)
+            .await?
+            .json()
+            .await
+            .map_err(ApiError::internal)?;
+        let chunk = parse_generate_response(&payload)?;
+        Ok(NormalizedAssistantTurn {
+            visible_text: chunk.visible_text,
+            model_thinking: chunk.model_thinking,
+            finish_reason: chunk.finish_reason,
+            usage: chunk.usage,
+            tool_calls: Vec::new(),
+        })
+    }
+
+    async fn stream_chat(
+        &self,
+        request: ChatRequest,
+        model_id: &str,
+    ) -> Result<mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>>, ApiError> {
+        let body = build_generate_body(&request, &self.safety_settings);
+        let endpoint = format!(
+            "{}?alt=sse",
+            self.model_endpoint(model_id, "streamGenerateContent")
+        );
+        let response = self.post(&endpoint, &body).await?;
+
+        let (tx, rx) = mpsc::channel(self.timeouts.stream_buffer.max(1));
+        let stream_idle_timeout = self.timeouts.stream_idle;
+        let mut byte_stream = response.bytes_stream();
+        let cancel = request.cancellation();
+        tokio::spawn(async move {
+            let mut framer = SseFramer::default();
+            loop {
+                let next = tokio::time::timeout(stream_idle_timeout, byte_stream.next());
+                let Some(next) = cancel.run_until_cancelled(next).await else {
+                    return;
+                };
+                let next = match next {
+                    Ok(next) => next,
+                    Err(_) => {
+                        let _ = tx.send(Err(idle_timeout_error(stream_idle_timeout))).await;
+                        return;
+                    }
+                };
+                let Some(next) = next else {
+                    break;
+                };
+                let bytes = match next {
+                    Ok(bytes) => bytes,
+                    Err(err) => {
+                        let _ = tx
+                            .send(Err(ApiError::Internal(format!(
+                                "Streaming transport failed: {}",
+                                err
+                            ))))
+                            .await;
+                        return;
+                    }
+                };
+                for event in framer.push(&bytes) {
+                    if !emit_stream_event(&tx, &event.data).await {
+                        return;
+                    }
+                }
+            }
+            if let Some(event) = framer.finish() {
+                if !emit_stream_event(&tx, &event.data).await {
+                    return;
+                }
+            }
+            let _ = tx
+                .send(Ok(NormalizedStreamChunk {
+                    done: true,
+                    ..Default::default()
+                }))
+                .await;
+        });
+        Ok(rx)
+    }
+
+    async fn embed(&self, inputs: &[String], model_id: &str) -> Result<Vec<Vec<f32>>, ApiError> {
+        let model = format!("models/{}", model_id.trim_start_matches("models/"));
+        let requests = inputs
+            .iter()
+            .map(|input| json!({ "model": model, "content": { "parts": [{ "text": input }] } }))
+            .collect::<Vec<_>>();
+        let endpoint = self.model_endpoint(model_id, "batchEmbedContents");
+        let payload: Value = self
+            .post(&endpoint, &json!({ "requests": requests }))
+            .await?
+            .json()
+            .await
+            .map_err(ApiError::internal)?;
+        Ok(payload
+            .get("embeddings")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|item| {
+                item.get("values")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|value| value.as_f64().map(|f| f as f32))
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+fn idle_timeout_error(timeout: Duration) -> ApiError {
+    ApiError::Internal(format!(
+        "{} stream idle timeout after {} ms",
+        GEMINI_LOADER,
+        timeout.as_millis()
+    ))
+}
+
+/// SSE の 1 イベントを送る。受け手が閉じたか応答が壊れていたら `false`
+async fn emit_stream_event(
+    tx: &mpsc::Sender<Result<NormalizedStreamChunk, ApiError>>,
+    data: &str,
+) -> bool {
+    let data = data.trim();
+    if data.is_empty() {
+        return true;
+    }
+    let chunk = serde_json::from_str::<Value>(data)
+        .map_err(|err| ApiError::Internal(format!("Invalid streaming payload: {}", err)))
+        .and_then(|payload| parse_generate_response(&payload));
+    let failed = chunk.is_err();
+    tx.send(chunk).await.is_ok() && !failed
+}
+
+/// `ChatRequest` を `generateContent` の本文にする
+fn build_generate_body(request: &ChatRequest, safety_settings: &[SafetySetting]) -> Value {
+    let mut system = Vec::new();
+    let mut contents: Vec<Value> = Vec::new();
+    for message in &request.messages {
+        if message.role.eq_ignore_ascii_case("system") {
+            system.push(json!({ "text": message.content }));
+            continue;
+        }
+        let role = if message.role.eq_ignore_ascii_case("assistant") {
+            "model"
+        } else {
+            "user"
+        };
+        let parts = message_parts(message);
+        // Gemini は同じ役割が続くのを嫌うので、連続した分は 1 つにまとめる
+        match contents.last_mut() {
+            Some(last) if last["role"] == role => {
+                if let Some(existing) = last["parts"].as_array_mut() {
+                    existing.extend(parts);
+                }
+            }
+            _ => contents.push(json!({ "role": role, "parts": parts })),
+        }
+    }
+
+    let mut body = Map::new();
+    body.insert("contents".to_string(), Value::Array(contents));
+    if !system.is_empty() {
+        body.insert("systemInstruction".to_string(), json!({ "parts": system }));
+    }
+    let generation_config = generation_config(request);
+    if !generation_config.is_empty() {
+        body.insert(
+            "generationConfig".to_string(),
+            Value::Object(generation_config),
+        );
+    }
+    if !safety_settings.is_empty() {
+        body.insert(
+            "safetySettings".to_string(),
+            Value::Array(
+                safety_settings
+                    .iter()
+                    .map(|setting| {
+                        json!({ "category": setting.category, "threshold": setting.threshold })
+                    })
+                    .collect(),
+            ),
+        );
+    }
+    Value::Object(body)
+}
+
+fn message_parts(message: &ChatMessage) -> Vec<Value> {
+    let mut parts = Vec::new();
+    if !message.content.is_empty() {
+        parts.push(json!({ "text": message.content }));
+    }
+    for image in message.image_data_list() {
+        parts.push(json!({
+            "inlineData": { "mimeType": image.mime_type, "data": image.base64 }
+        }));
+    }
+    if parts.is_empty() {
+        parts.push(json!({ "text": "" }));
+    }
+    parts
+}
+
+fn generation_config(request: &ChatRequest) -> Map<String, Value> {
+    let mut config = Map::new();
+    let mut set = |key: &str, value: Option<Value>| {
+        if let Some(value) = value {
+            config.insert(key.to_string(), value);
+        }
+    };
+    set("temperature", request.temperature.map(Value::from));
+    set("topP", request.top_p.map(Value::from));
+    set("topK", request.top_k.map(Value::from));
+    set("maxOutputTokens", request.max_tokens.map(Value::from));
+    set("seed", request.seed.map(Value::from));
+    set("presencePenalty", request.presence_penalty.map(Value::from));
+    set(
+        "frequencyPenalty",
+        request.frequency_penalty.map(Value::from),
+    );
+    set(
+        "stopSequences",
+        request
+            .stop
+            .as_ref()
+            .filter(|stop| !stop.is_empty())
+            .map(|stop| json!(stop)),
+    );
+    if let Some(spec) = &request.structured_response {
+        set("responseMimeType", Some(json!("application/json")));
+        set("responseJsonSchema", Some(spec.schema.clone()));
+    }
+    config
+}
+
+/// `generateContent` の応答（ストリームでは 1 イベント分）を解釈する
+fn parse_generate_response(payload: &Value) -> Result<NormalizedStreamChunk, ApiError> {
+    if let Some(reason) = payload
+        .pointer("/promptFeedback/blockReason")
+        .and_then(Value::as_str)
+    {
+        return Err(ApiError::BadRequest(format!(
+            "{} blocked the prompt ({})",
+            GEMINI_LOADER, reason
+        )));
+    }
+
+    let candidate = payload
+        .get("candidates")
+        .and_then(Value::as_array)
+        .and_then(|candidates| candidates.first());
+    let mut visible_text = String::new();
+    let mut model_thinking = String::new();
+    for part in candidate
+        .and_then(|candidate| candidate.pointer("/content/parts"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let Some(text) = part.get("text").and_then(Value::as_str) else {
+            continue;
+        };
+        if part.get("thought").and_then(Value::as_bool) == Some(true) {
+            model_thinking.push_str(text);
+        } else {
+            visible_text.push_str(text);
+        }
+    }
+    let finish_reason = candidate
+        .and_then(|candidate| candidate.get("finishReason"))
+        .and_then(Value::as_str)
+        .filter(|reason| *reason != "FINISH_REASON_UNSPECIFIED")
+        .map(str::to_ascii_lowercase);
+    // ストリームでは使用量が毎回累計で届くので、最後のイベントの分だけ使う
+    let usage = finish_reason
+        .as_ref()
+        .and_then(|_| payload.get("usageMetadata"))
+        .map(parse_usage);
+
+    Ok(NormalizedStreamChunk {
+        visible_text,
+        model_thinking,
+        done: false,
+        usage,
+        finish_reason,
+    })
+}
+
+fn parse_usage(usage: &Value) -> TokenUsage {
+    let count = |key: &str| usage.get(key).and_then(Value::as_u64).map(|v| v as usize);
+    TokenUsage {
+        prompt_tokens: count("promptTokenCount"),
+        completion_tokens: count("candidatesTokenCount"),
+        total_tokens: count("totalTokenCount"),
+        cached_prompt_tokens: count("cachedContentTokenCount"),
+    }
+}
+
+/// `GET /v1beta/models` の `models[]`。生成にも埋め込みにも使えないモデルは除く。
+fn parse_model_list(payload: &Value) -> Vec<ProviderModel> {
+    payload
+        .get("models")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            let id = item
+                .get("name")
+                .and_then(Value::as_str)?
+                .trim()
+                .trim_start_matches("models/");
+            if id.is_empty() {
+                return None;
+            }
+            let usable = item
+                .get("supportedGenerationMethods")
+                .and_then(Value::as_array)
+                .is_some_and(|methods| {
+                    methods.iter().any(|method| {
+                        matches!(
+                            method.as_str(),
+                            Some("generateContent" | "embedContent" | "batchEmbedContents")
+                        )
+                    })
+                });
+            if !usable {
+                return None;
+            }
+            Some(ProviderModel {
+                id: id.to_string(),
+                name: item
+                    .get("displayName")
+                    .and_then(Value::as_str)
+                    .unwrap_or(id)
+                    .to_string(),
+                ctx: item
+                    .get("inputTokenLimit")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(0),
+                pricing: None,
+                capabilities: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_map_to_gemini_roles() {
+        let mut request = ChatRequest::new(vec![
+            ChatMessage::new_text("system", "Be brief."),
+            ChatMessage::new_text("user", "Hi"),
+            ChatMessage::new_text("assistant", "Hello"),
+            ChatMessage::new_text("tool", "result"),
+            ChatMessage::new_text("user", "Thanks"),
+        ]);
+        request.max_tokens = Some(64);
+        let safety = vec![SafetySetting {
+            category: "HARM_CATEGORY_HARASSMENT".to_string(),
+            threshold: "BLOCK_ONLY_HIGH".to_string(),
+        }];
+
+        let body = build_generate_body(&request, &safety);
+
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "Be brief.");
+        let contents = body["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[0]["role"], "user");
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(contents[2]["role"], "user");
+        assert_eq!(contents[2]["parts"].as_array().unwrap().len(), 2);
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 64);
+        assert_eq!(body["safetySettings"][0]["threshold"], "BLOCK_ONLY_HIGH");
+    }
+
+    #[test]
+    fn response_separates_thoughts_and_reports_usage_on_finish() {
+        let chunk = parse_generate_response(&json!({
+            "candidates": [{
+                "content": { "role": "model", "parts": [
+                    { "text": "thinking...", "thought": true },
+                    { "text": "Answer" }
+                ]},
+                "finishReason": "MAX_TOKENS"
+            }],
+            "usageMetadata": { "promptTokenCount": 10, "candidatesTokenCount": 5, "totalTokenCount": 15 }
+        }))
+        .unwrap();
+
+        assert_eq!(chunk.visible_text, "Answer");
+        assert_eq!(chunk.model_thinking, "thinking...");
+        assert_eq!(chunk.finish_reason.as_deref(), Some("max_tokens"));
+        assert_eq!(chunk.usage.unwrap().total_tokens, Some(15));
+
+        let blocked = parse_generate_response(&json!({
+            "promptFeedback": { "blockReason": "SAFETY" }
+        }));
+        assert!(blocked.is_err());
+    }
+
+    #[test]
+    fn model_list_keeps_generation_and_embedding_models() {
+        let models = parse_model_list(&json!({
+            "models": [
+                { "name": "models/gemini-2.0-flash", "displayName": "Gemini 2.0 Flash",
+                  "inputTokenLimit": 1048576, "supportedGenerationMethods": ["generateContent", "countTokens"] },
+                { "name": "models/text-embedding-004", "supportedGenerationMethods": ["embedContent"] },
+                { "name": "models/aqa", "supportedGenerationMethods": ["generateAnswer"] }
+            ]
+        }));
+
+        let ids: Vec<_> = models.iter().map(|model| model.id.as_str()).collect();
+        assert_eq!(ids, vec!["gemini-2.0-flash", "text-embedding-004"]);
+        assert_eq!(models[0].name, "Gemini 2.0 Flash");
+        assert_eq!(models[0].ctx, 1_048_576);
+    }
+}
@@ -0,0 +1,216 @@
+//! プロバイダーごとに共有する HTTP クライアント。
+//!
+//! ローダー名ごとに 1 つだけ `reqwest::Client` を作り、チャット・ストリーミング・
+//! 埋め込みで使い回す。接続プールと TCP keep-alive によって、ターンごとに
+//! TCP/TLS のハンドシェイクをやり直さずに済む。HTTPS の接続先とは ALPN で
+//! HTTP/2 を使う（ローカルのローダーは HTTP/1.1 のまま）。
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::{Client, ClientBuilder};
+
+use crate::core::config::schema::{LlmManagerSettings, NetworkSubsystem};
+use crate::core::config::ConfigService;
+use crate::core::errors::ApiError;
+use crate::core::network::NetClient;
+use crate::llm::gemini_provider::GEMINI_LOADER;
+
+/// 接続プールと keep-alive を設定済みの `ClientBuilder`。
+pub(crate) fn tuned_client_builder(settings: &LlmManagerSettings) -> ClientBuilder {
+    let keepalive = settings.http_tcp_keepalive();
+    Client::builder()
+        .pool_max_idle_per_host(settings.http_pool_max_idle_per_host())
+        .pool_idle_timeout(settings.http_pool_idle_timeout())
+        .tcp_keepalive(keepalive)
+        .tcp_nodelay(true)
+        .http2_keep_alive_interval(keepalive)
+        .http2_keep_alive_while_idle(true)
+        .http2_adaptive_window(true)
+}
+
+/// ローダー名 (`ollama`, `lmstudio`, クラウドのプロバイダー名など) ごとのクライアント。
+#[derive(Clone)]
+pub(crate) struct ProviderClients {
+    config: ConfigService,
+    clients: Arc<RwLock<HashMap<String, NetClient>>>,
+    /// API キー付きのクライアント。キーが変わったら作り直す。
+    authorized: Arc<RwLock<HashMap<String, (String, NetClient)>>>,
+}
+
+impl ProviderClients {
+    pub(crate) fn new(config: ConfigService) -> Self {
+        Self {
+            config,
+            clients: Arc::new(RwLock::new(HashMap::new())),
+            authorized: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// 初回呼び出し時にその時点の設定でクライアントを作る。
+    pub(crate) fn for_loader(&self, loader: &str) -> NetClient {
+        let key = loader.trim().to_ascii_lowercase();
+        if let Some(client) = self
+            .clients
+            .read()
+            .ok()
+            .and_then(|clients| clients.get(&key).cloned())
+        {
+            return client;
+        }
+
+        let settings = self
+            .config
+            .load_typed()
+            .map(|typed| typed.llm_manager)
+            .unwrap_or_default();
+        let client =
+            NetClient::for_provider(&key, tuned_client_builder(&settings)).unwrap_or_else(|err| {
+                tracing::warn!(
+                    "Failed to build tuned HTTP client for {}, using defaults: {}",
+                    key,
+                    err
+                );
+                NetClient::new(NetworkSubsystem::CloudProviders)
+            });
+
+        match self.clients.write() {
+            Ok(mut clients) => clients.entry(key).or_insert(client).clone(),
+            Err(_) => client,
+        }
+    }
+
+    /// API キーを既定ヘッダーに持つクライアント。キーがなければ `for_loader` と同じ。
+    pub(crate) fn for_cloud(&self, loader: &str, api_key: Option<&str>) -> NetClient {
+        let Some(api_key) = api_key.map(str::trim).filter(|key| !key.is_empty()) else {
+            return self.for_loader(loader);
+        };
+        let key = loader.trim().to_ascii_lowercase();
+        if let Some(client) = self.authorized.read().ok().and_then(|clients| {
+            clients
+                .get(&key)
+                .filter(|(cached_key, _)| cached_key == api_key)
+                .map(|(_, client)| client.clone())
+        }) {
+            return client;
+        }
+
+        let settings = self
+            .config
+            .load_typed()
+            .map(|typed| typed.llm_manager)
+            .unwrap_or_default();
+        let client = authorized_client(&key, &settings, api_key).unwrap_or_else(|err| {
+            tracing::warn!(
+                "Failed to build authorized HTTP client for {}: {}",
+                key,
+                err
+            );
+            NetClient::new(NetworkSubsystem::CloudProviders)
+        });
+
+        if let Ok(mut clients) = self.authorized.write() {
+            clients.insert(key, (api_key.to_string(), client.clone()));
+        }
+        client
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.clients
+            .read()
+            .map(|clients| clients.len())
+            .unwrap_or(0)
+    }
+}
+
+/// API キーを既定ヘッダーに載せたクラウドプロバイダー用クライアント。
+/// Gemini は `x-goog-api-key`、それ以外は `Authorization: Bearer`。
+pub(crate) fn authorized_client(
+    provider: &str,
+    settings: &LlmManagerSettings,
+    api_key: &str,
+) -> Result<NetClient, ApiError> {
+    let (name, value) = if provider == GEMINI_LOADER {
+        (
+            HeaderName::from_static("x-goog-api-key"),
+            api_key.trim().to_string(),
+        )
+    } else {
+        (AUTHORIZATION, format!("Bearer {}", api_key.trim()))
+    };
+    let mut value = HeaderValue::from_str(&value)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid API key for {}", provider)))?;
+    value.set_sensitive(true);
+    let mut headers = HeaderMap::new();
+    headers.insert(name, value);
+    NetClient::for_provider(
+        provider,
+        tuned_client_builder(settings).default_headers(headers),
+    )
+}
+
+/// llama.cpp サーバー用。設定がなければ既定値で作る。
+pub(crate) fn llama_cpp_client(config: Option<&ConfigService>) -> Client {
+    let settings = config
+        .and_then(|config| config.load_typed().ok())
+        .map(|typed| typed.llm_manager)
+        .unwrap_or_default();
+    tuned_client_builder(&settings)
+        .build()
+        .unwrap_or_else(|err| {
+            tracing::warn!(
+                "Failed to build tuned llama.cpp HTTP client, using defaults: {}",
+                err
+            );
+            Client::new()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::AppPaths;
+
+    #[test]
+    fn clients_are_shared_per_loader() {
+        let temp = tempfile::tempdir().unwrap();
+        let paths = Arc::new(AppPaths {
+            project_root: temp.path().to_path_buf(),
+            user_data_dir: temp.path().to_path_buf(),
+            log_dir: temp.path().join("logs"),
+            db_path: temp.path().join("tepora.db"),
+            secrets_path: temp.path().join("secrets.yaml"),
+        });
+        let clients = ProviderClients::new(ConfigService::new(paths));
+
+        clients.for_loader("ollama");
+        clients.for_loader("Ollama ");
+        clients.for_loader("lmstudio");
+        assert_eq!(clients.len(), 2);
+    }
+
+    #[test]
+    fn authorized_clients_are_rebuilt_when_the_key_changes() {
+        let temp = tempfile::tempdir().unwrap();
+        let paths = Arc::new(AppPaths {
+            project_root: temp.path().to_path_buf(),
+            user_data_dir: temp.path().to_path_buf(),
+            log_dir: temp.path().join("logs"),
+            db_path: temp.path().join("tepora.db"),
+            secrets_path: temp.path().join("secrets.yaml"),
+        });
+        let clients = ProviderClients::new(ConfigService::new(paths));
+
+        clients.for_cloud("openai_compatible", Some("sk-one"));
+        clients.for_cloud("openai_compatible", Some("sk-two"));
+        let cached = clients.authorized.read().unwrap();
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached["openai_compatible"].0, "sk-two");
+        drop(cached);
+
+        clients.for_cloud("openai_compatible", None);
+        assert_eq!(clients.len(), 1);
+    }
+}
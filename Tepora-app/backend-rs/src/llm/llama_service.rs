@@ -1,31 +1,97 @@
 use std::path::PathBuf;
 use std::process::Stdio;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 
+use chrono::{DateTime, Utc};
 use reqwest::Client;
+use serde::Serialize;
 use serde_json::{json, Value};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
+use tokio_util::sync::CancellationToken;
 
 use crate::core::config::{AppPaths, ConfigService};
 use crate::core::errors::ApiError;
 use crate::llm::external_loader_common::{
-    health_check_interval, health_check_timeout, process_terminate_timeout, stream_channel_buffer,
-    stream_internal_buffer,
+    health_check_interval, health_check_timeout, idle_unload_settings, parallel_slots,
+    process_terminate_timeout, stream_channel_buffer, stream_internal_buffer,
 };
+use crate::llm::http_pool::llama_cpp_client;
+use crate::llm::outage::send_error;
+use crate::llm::stream_framing::SseFramer;
 use crate::models::types::ModelRuntimeConfig;
 
+pub const LLAMA_CPP_LOADER: &str = "llama_cpp";
 const DEFAULT_SERVER_PORT: u16 = 8080;
+const SLOT_EVENT_CAPACITY: usize = 64;
+/// アイドル判定の間隔。タイムアウトそのものは `llm_manager.idle_unload_timeout_ms`
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const SLOT_STATUS_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelSlotStatus {
+    Loading,
+    Loaded,
+    Failed,
+    Unloaded,
+}
+
+/// llama-server holds one model at a time; every swap is announced here so
+/// clients can explain the pause.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelSlotEvent {
+    pub status: ModelSlotStatus,
+    pub model_id: Option<String>,
+    pub previous_model_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub at: DateTime<Utc>,
+}
 
 #[derive(Clone)]
 pub struct LlamaService {
     inner: Arc<Mutex<LlamaManager>>,
     client: Client,
     config: Option<ConfigService>,
+    slot_events: broadcast::Sender<ModelSlotEvent>,
+    activity: Arc<Activity>,
+}
+
+/// llama-server への処理中リクエスト数と最後に使われた時刻。
+struct Activity {
+    active: AtomicUsize,
+    last_used: std::sync::Mutex<Instant>,
+}
+
+impl Activity {
+    fn idle_for(&self) -> Option<Duration> {
+        if self.active.load(Ordering::SeqCst) > 0 {
+            return None;
+        }
+        Some(
+            self.last_used
+                .lock()
+                .map(|last| last.elapsed())
+                .unwrap_or_default(),
+        )
+    }
+}
+
+/// 生きている間はアイドル停止の対象にしない。手放した時点を最終利用時刻にする。
+struct ActivityGuard(Arc<Activity>);
+
+impl Drop for ActivityGuard {
+    fn drop(&mut self) {
+        if let Ok(mut last) = self.0.last_used.lock() {
+            *last = Instant::now();
+        }
+        self.0.active.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 struct LlamaManager {
@@ -34,11 +100,16 @@ struct LlamaManager {
     running: Arc<AtomicBool>,
     server_path: PathBuf,
     model_config: Option<ModelRuntimeConfig>,
+    /// `--parallel` value the running server was started with.
+    slots: usize,
+    /// 直近の利用が埋め込みだったか。`keep_embedding_loaded` の判定に使う
+    serves_embeddings: bool,
 }
 
 struct PendingLlamaProcess {
     child: Child,
     port: u16,
+    slots: usize,
 }
 
 impl Drop for LlamaManager {
@@ -51,7 +122,7 @@ impl Drop for LlamaManager {
     }
 }
 
-use crate::llm::types::{ChatMessage, NormalizedAssistantTurn, NormalizedStreamChunk};
+use crate::llm::types::{ChatMessage, NormalizedAssistantTurn, NormalizedStreamChunk, TokenUsage};
 
 // Removed duplicate struct ChatMessage
 
@@ -65,6 +136,7 @@ impl LlamaService {
         config: impl Into<Option<ConfigService>>,
     ) -> Result<Self, ApiError> {
         let server_path = Self::find_server_binary(&paths)?;
+        let config: Option<ConfigService> = config.into();
         Ok(Self {
             inner: Arc::new(Mutex::new(LlamaManager {
                 child_process: None,
@@ -72,12 +144,152 @@ impl LlamaService {
                 running: Arc::new(AtomicBool::new(false)),
                 server_path,
                 model_config: None,
+                slots: 1,
+                serves_embeddings: false,
             })),
-            client: Client::new(),
-            config: config.into(),
+            client: llama_cpp_client(config.as_ref()),
+            config,
+            slot_events: broadcast::channel(SLOT_EVENT_CAPACITY).0,
+            activity: Arc::new(Activity {
+                active: AtomicUsize::new(0),
+                last_used: std::sync::Mutex::new(Instant::now()),
+            }),
         })
     }
 
+    /// 一定時間使われていない llama-server を止める監視タスクを起動する。
+    /// 止めた後は次のリクエストの `ensure_running` で起動し直す。
+    pub fn spawn_idle_reaper(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(IDLE_CHECK_INTERVAL);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                ticker.tick().await;
+                let Some(config) = service.config.as_ref() else {
+                    return;
+                };
+                let (Some(idle_timeout), keep_embedding) = idle_unload_settings(config) else {
+                    continue;
+                };
+                service.reap_if_idle(idle_timeout, keep_embedding).await;
+            }
+        });
+    }
+
+    async fn reap_if_idle(&self, idle_timeout: Duration, keep_embedding: bool) -> bool {
+        let mut manager = self.inner.lock().await;
+        if !manager.running.load(Ordering::SeqCst) || (keep_embedding && manager.serves_embeddings)
+        {
+            return false;
+        }
+        // 利用中の判定はロックを握ったまま行う。リクエスト側は ensure_running より前に
+        // 利用開始を記録するので、ここで止めても次の ensure_running が起動し直す
+        if self
+            .activity
+            .idle_for()
+            .is_none_or(|idle| idle < idle_timeout)
+        {
+            return false;
+        }
+        let previous_model_id = manager.model_config.as_ref().map(|c| c.model_key.clone());
+        let timeout = resolved_shutdown_timeout(self.config.as_ref());
+        if let Err(err) = self.stop_internal(&mut manager, timeout).await {
+            tracing::warn!("Failed to stop idle llama-server: {}", err);
+            return false;
+        }
+        tracing::info!(
+            model = previous_model_id.as_deref().unwrap_or("unknown"),
+            idle_secs = idle_timeout.as_secs(),
+            "Stopped idle llama-server"
+        );
+        self.emit_slot_event(ModelSlotStatus::Unloaded, None, previous_model_id, None);
+        true
+    }
+
+    /// 利用開始を記録してからサーバーを起動（または再利用）する。
+    async fn acquire(
+        &self,
+        config: &ModelRuntimeConfig,
+        timeout: Duration,
+    ) -> Result<ActivityGuard, ApiError> {
+        self.activity.active.fetch_add(1, Ordering::SeqCst);
+        let guard = ActivityGuard(self.activity.clone());
+        self.ensure_running(config, timeout).await?;
+        Ok(guard)
+    }
+
+    pub fn subscribe_slot_events(&self) -> broadcast::Receiver<ModelSlotEvent> {
+        self.slot_events.subscribe()
+    }
+
+    /// Model key of the model currently served by llama-server, if any.
+    pub async fn loaded_model_id(&self) -> Option<String> {
+        let manager = self.inner.lock().await;
+        if !manager.running.load(Ordering::SeqCst) {
+            return None;
+        }
+        manager
+            .model_config
+            .as_ref()
+            .map(|config| config.model_key.clone())
+    }
+
+    /// 動いている llama-server のプロセス ID とモデルキー。
+    pub async fn running_process(&self) -> Option<(u32, Option<String>)> {
+        let manager = self.inner.lock().await;
+        if !manager.running.load(Ordering::SeqCst) {
+            return None;
+        }
+        let pid = manager.child_process.as_ref()?.id()?;
+        Some((
+            pid,
+            manager
+                .model_config
+                .as_ref()
+                .map(|config| config.model_key.clone()),
+        ))
+    }
+
+    /// llama-server の `GET /slots`（各スロットの処理中フラグやコンテキスト長）。
+    /// 動いていない、または `--slots` で無効にされていれば `None`。
+    pub async fn slot_status(&self) -> Option<Value> {
+        let manager = self.inner.lock().await;
+        if !manager.running.load(Ordering::SeqCst) {
+            return None;
+        }
+        let url = format!("http://localhost:{}/slots", manager.port);
+        drop(manager);
+        let response = self
+            .client
+            .get(&url)
+            .timeout(SLOT_STATUS_TIMEOUT)
+            .send()
+            .await
+            .ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        response.json().await.ok()
+    }
+
+    fn emit_slot_event(
+        &self,
+        status: ModelSlotStatus,
+        model_id: Option<String>,
+        previous_model_id: Option<String>,
+        error: Option<String>,
+    ) {
+        // No subscribers is the normal case when no WS client is connected.
+        let _ = self.slot_events.send(ModelSlotEvent {
+            status,
+            model_id,
+            previous_model_id,
+            error,
+            at: Utc::now(),
+        });
+    }
+
     fn find_server_binary(paths: &AppPaths) -> Result<PathBuf, ApiError> {
         let candidates = vec![
             paths
@@ -103,6 +315,13 @@ impl LlamaService {
         Ok(PathBuf::from("llama-server"))
     }
 
+    /// Resolved llama-server path and whether it can actually be launched.
+    pub async fn server_binary(&self) -> (PathBuf, bool) {
+        let path = self.inner.lock().await.server_path.clone();
+        let available = path.is_file() || which::which(&path).is_ok();
+        (path, available)
+    }
+
     pub async fn refresh_binary_path(&self, paths: &AppPaths) -> Result<(), ApiError> {
         let mut manager = self.inner.lock().await;
         manager.server_path = Self::find_server_binary(paths)?;
@@ -116,22 +335,53 @@ impl LlamaService {
     ) -> Result<(), ApiError> {
         let mut manager = self.inner.lock().await;
 
+        let mut previous_model_id = None;
         if manager.running.load(Ordering::SeqCst) {
             if let Some(current) = &manager.model_config {
                 if should_reuse_running_config(current, config) {
                     return Ok(());
                 }
+                previous_model_id = Some(current.model_key.clone());
             }
             self.stop_internal(&mut manager, timeout).await?;
         }
 
-        self.start_internal(&mut manager, config).await?;
-        Ok(())
+        let model_id = Some(config.model_key.clone());
+        self.emit_slot_event(
+            ModelSlotStatus::Loading,
+            model_id.clone(),
+            previous_model_id.clone(),
+            None,
+        );
+        match self.start_internal(&mut manager, config).await {
+            Ok(()) => {
+                self.emit_slot_event(ModelSlotStatus::Loaded, model_id, previous_model_id, None);
+                Ok(())
+            }
+            Err(err) => {
+                self.emit_slot_event(
+                    ModelSlotStatus::Failed,
+                    model_id,
+                    previous_model_id,
+                    Some(err.to_string()),
+                );
+                Err(err)
+            }
+        }
     }
 
     pub async fn stop(&self, timeout: Duration) -> Result<(), ApiError> {
         let mut manager = self.inner.lock().await;
-        self.stop_internal(&mut manager, timeout).await
+        let previous_model_id = manager
+            .running
+            .load(Ordering::SeqCst)
+            .then(|| manager.model_config.as_ref().map(|c| c.model_key.clone()))
+            .flatten();
+        self.stop_internal(&mut manager, timeout).await?;
+        if previous_model_id.is_some() {
+            self.emit_slot_event(ModelSlotStatus::Unloaded, None, previous_model_id, None);
+        }
+        Ok(())
     }
 
     async fn start_internal(
@@ -171,6 +421,11 @@ impl LlamaService {
             cmd.arg("-ngl").arg(config.n_gpu_layers.to_string());
         }
 
+        let slots = self.config.as_ref().map(parallel_slots).unwrap_or(1);
+        if slots > 1 {
+            cmd.arg("--parallel").arg(slots.to_string());
+        }
+
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
@@ -194,7 +449,7 @@ impl LlamaService {
             }
         });
 
-        Ok(PendingLlamaProcess { child, port })
+        Ok(PendingLlamaProcess { child, port, slots })
     }
 
     async fn complete_startup(
@@ -215,6 +470,7 @@ impl LlamaService {
         }
 
         manager.port = pending.port;
+        manager.slots = pending.slots;
         manager.model_config = Some(config.clone());
         manager.running.store(true, Ordering::SeqCst);
         manager.child_process = Some(pending.child);
@@ -231,6 +487,7 @@ impl LlamaService {
         }
         manager.running.store(false, Ordering::SeqCst);
         manager.model_config = None;
+        manager.serves_embeddings = false;
         Ok(())
     }
 
@@ -261,9 +518,10 @@ impl LlamaService {
         text: &str,
         timeout: Duration,
     ) -> Result<Vec<(String, f64)>, ApiError> {
-        self.ensure_running(config, timeout).await?;
+        let _activity = self.acquire(config, timeout).await?;
 
-        let manager = self.inner.lock().await;
+        let mut manager = self.inner.lock().await;
+        manager.serves_embeddings = false;
         let url = format!("http://localhost:{}/completion", manager.port);
         drop(manager);
 
@@ -281,7 +539,7 @@ impl LlamaService {
             .json(&body)
             .send()
             .await
-            .map_err(ApiError::internal)?;
+            .map_err(|err| send_error(LLAMA_CPP_LOADER, "llama-server", err))?;
 
         if !res.status().is_success() {
             let status = res.status();
@@ -343,10 +601,12 @@ impl LlamaService {
         messages: Vec<ChatMessage>,
         timeout: Duration,
     ) -> Result<NormalizedAssistantTurn, ApiError> {
-        self.ensure_running(config, timeout).await?;
+        let _activity = self.acquire(config, timeout).await?;
 
-        let manager = self.inner.lock().await;
+        let mut manager = self.inner.lock().await;
+        manager.serves_embeddings = false;
         let url = format!("http://localhost:{}/completion", manager.port);
+        let slots = manager.slots;
         drop(manager);
 
         let prompt = self.format_chat_prompt(messages);
@@ -406,8 +666,17 @@ impl LlamaService {
             if let Some(v) = config.n_keep {
                 obj.insert("n_keep".into(), json!(v));
             }
-            if let Some(v) = config.cache_prompt {
-                obj.insert("cache_prompt".into(), json!(v));
+            // Prompt caching is on unless explicitly disabled; with a stable
+            // prefix only the new turn needs to be evaluated.
+            obj.insert(
+                "cache_prompt".into(),
+                json!(config.cache_prompt.unwrap_or(true)),
+            );
+            if let Some(slot) = slot_for_key(config.cache_key.as_deref(), slots) {
+                obj.insert("id_slot".into(), json!(slot));
+            }
+            if let Some(schema) = &config.json_schema {
+                obj.insert("json_schema".into(), schema.clone());
             }
         }
 
@@ -417,7 +686,7 @@ impl LlamaService {
             .json(&body)
             .send()
             .await
-            .map_err(ApiError::internal)?;
+            .map_err(|err| send_error(LLAMA_CPP_LOADER, "llama-server", err))?;
 
         if !res.status().is_success() {
             return Err(ApiError::internal(format!(
@@ -433,11 +702,9 @@ impl LlamaService {
                 &data,
                 &["reasoning", "reasoning_content", "thinking"],
             ),
-            finish_reason: data
-                .get("stop_type")
-                .and_then(|value| value.as_str())
-                .map(str::to_string),
-            usage: None,
+            finish_reason: llama_stop_type(&data),
+            usage: llama_usage(&data),
+            tool_calls: Vec::new(),
         })
     }
 
@@ -459,11 +726,14 @@ impl LlamaService {
         config: &ModelRuntimeConfig,
         messages: Vec<ChatMessage>,
         timeout: Duration,
+        cancel: CancellationToken,
     ) -> Result<mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>>, ApiError> {
-        self.ensure_running(config, timeout).await?;
+        let activity = self.acquire(config, timeout).await?;
 
-        let manager = self.inner.lock().await;
+        let mut manager = self.inner.lock().await;
+        manager.serves_embeddings = false;
         let url = format!("http://localhost:{}/completion", manager.port);
+        let slots = manager.slots;
         drop(manager);
 
         let prompt = self.format_chat_prompt(messages);
@@ -514,8 +784,14 @@ impl LlamaService {
             if let Some(v) = config.n_keep {
                 obj.insert("n_keep".into(), json!(v));
             }
-            if let Some(v) = config.cache_prompt {
-                obj.insert("cache_prompt".into(), json!(v));
+            // Prompt caching is on unless explicitly disabled; with a stable
+            // prefix only the new turn needs to be evaluated.
+            obj.insert(
+                "cache_prompt".into(),
+                json!(config.cache_prompt.unwrap_or(true)),
+            );
+            if let Some(slot) = slot_for_key(config.cache_key.as_deref(), slots) {
+                obj.insert("id_slot".into(), json!(slot));
             }
         }
 
@@ -525,49 +801,55 @@ impl LlamaService {
             .map(stream_internal_buffer)
             .unwrap_or(100);
         let (tx, rx) = mpsc::channel(buffer_capacity);
-        let client = self.client.clone();
+        // 接続の失敗は呼び出し側で繋ぎ直せるよう、ストリームを渡す前に返す
+        let mut res = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|err| send_error(LLAMA_CPP_LOADER, "llama-server", err))?;
 
         tokio::spawn(async move {
-            let mut res = match client.post(&url).json(&body).send().await {
-                Ok(r) => r,
-                Err(e) => {
-                    let _ = tx.send(Err(ApiError::internal(e.to_string()))).await;
+            // ストリームが終わるまでアイドル停止させない
+            let _activity = activity;
+
+            let mut framer = SseFramer::default();
+            loop {
+                // 取り消されたら接続を落とす。llama-server は切断を見て生成をやめ、スロットを空ける
+                let Some(next) = cancel.run_until_cancelled(res.chunk()).await else {
                     return;
-                }
-            };
-
-            while let Some(chunk) = res.chunk().await.ok().flatten() {
-                let text = String::from_utf8_lossy(&chunk);
-                for line in text.lines() {
-                    if let Some(json_str) = line.strip_prefix("data: ") {
-                        if let Ok(val) = serde_json::from_str::<Value>(json_str) {
-                            let reasoning = extract_field_text(
-                                &val,
-                                &["reasoning", "reasoning_content", "thinking"],
-                            );
-                            let content =
-                                extract_field_text(&val, &["content", "text", "response"]);
-                            let done = val.get("stop").and_then(|value| value.as_bool())
-                                == Some(true)
-                                || val.get("stopped_eos").and_then(|value| value.as_bool())
-                                    == Some(true)
-                                || val.get("stopped_word").and_then(|value| value.as_bool())
-                                    == Some(true);
-
-                            if (!reasoning.is_empty() || !content.is_empty() || done)
-                                && tx
-                                    .send(Ok(NormalizedStreamChunk {
-                                        visible_text: content,
-                                        model_thinking: reasoning,
-                                        done,
-                                        usage: None,
-                                    }))
-                                    .await
-                                    .is_err()
-                            {
-                                return;
-                            }
-                        }
+                };
+                let Some(chunk) = next.ok().flatten() else {
+                    break;
+                };
+                for event in framer.push(&chunk) {
+                    let Ok(val) = serde_json::from_str::<Value>(&event.data) else {
+                        continue;
+                    };
+                    let reasoning =
+                        extract_field_text(&val, &["reasoning", "reasoning_content", "thinking"]);
+                    let content = extract_field_text(&val, &["content", "text", "response"]);
+                    let done = val.get("stop").and_then(|value| value.as_bool()) == Some(true)
+                        || val.get("stopped_eos").and_then(|value| value.as_bool()) == Some(true)
+                        || val.get("stopped_word").and_then(|value| value.as_bool()) == Some(true);
+
+                    let finish_reason = done.then(|| llama_stop_type(&val)).flatten();
+                    let usage = done.then(|| llama_usage(&val)).flatten();
+
+                    if (!reasoning.is_empty() || !content.is_empty() || done)
+                        && tx
+                            .send(Ok(NormalizedStreamChunk {
+                                visible_text: content,
+                                model_thinking: reasoning,
+                                done,
+                                usage,
+                                finish_reason,
+                            }))
+                            .await
+                            .is_err()
+                    {
+                        return;
                     }
                 }
             }
@@ -577,6 +859,7 @@ impl LlamaService {
                     model_thinking: String::new(),
                     done: true,
                     usage: None,
+                    finish_reason: None,
                 }))
                 .await;
         });
@@ -589,9 +872,10 @@ impl LlamaService {
         config: &ModelRuntimeConfig,
         messages: Vec<ChatMessage>,
         timeout: Duration,
+        cancel: CancellationToken,
     ) -> Result<mpsc::Receiver<Result<String, ApiError>>, ApiError> {
         let mut normalized = self
-            .stream_chat_normalized(config, messages, timeout)
+            .stream_chat_normalized(config, messages, timeout, cancel)
             .await?;
         let buffer_capacity = self
             .config
@@ -626,9 +910,10 @@ impl LlamaService {
         inputs: &[String],
         timeout: Duration,
     ) -> Result<Vec<Vec<f32>>, ApiError> {
-        self.ensure_running(config, timeout).await?;
+        let _activity = self.acquire(config, timeout).await?;
 
-        let manager = self.inner.lock().await;
+        let mut manager = self.inner.lock().await;
+        manager.serves_embeddings = true;
         let url = format!("http://localhost:{}/embedding", manager.port);
         drop(manager);
 
@@ -644,7 +929,7 @@ impl LlamaService {
                 .json(&body)
                 .send()
                 .await
-                .map_err(ApiError::internal)?;
+                .map_err(|err| send_error(LLAMA_CPP_LOADER, "llama-server", err))?;
 
             if !res.status().is_success() {
                 return Err(ApiError::internal(format!(
@@ -713,6 +998,53 @@ fn value_to_text(value: &Value) -> String {
     }
 }
 
+/// `/completion` の停止理由。古いサーバーは `stopped_limit` だけを返す。
+fn llama_stop_type(data: &Value) -> Option<String> {
+    data.get("stop_type")
+        .and_then(|value| value.as_str())
+        .map(str::to_string)
+        .or_else(|| {
+            (data.get("stopped_limit").and_then(|value| value.as_bool()) == Some(true))
+                .then(|| "limit".to_string())
+        })
+}
+
+/// `/completion` の最後の応答にある評価・生成トークン数。`timings` の方しか
+/// 返さない版もある。
+fn llama_usage(data: &Value) -> Option<TokenUsage> {
+    let count = |key: &str, timing: &str| {
+        data.get(key)
+            .or_else(|| data.pointer(&format!("/timings/{timing}")))
+            .and_then(Value::as_u64)
+            .map(|value| value as usize)
+    };
+    let prompt_tokens = count("tokens_evaluated", "prompt_n");
+    let completion_tokens = count("tokens_predicted", "predicted_n");
+    if prompt_tokens.is_none() && completion_tokens.is_none() {
+        return None;
+    }
+    Some(TokenUsage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens.zip(completion_tokens).map(|(p, c)| p + c),
+        cached_prompt_tokens: count("tokens_cached", "cache_n"),
+    })
+}
+
+/// Map a conversation key onto a fixed llama-server slot so consecutive
+/// turns of the same session land on the slot that holds their KV cache.
+fn slot_for_key(key: Option<&str>, slots: usize) -> Option<usize> {
+    let key = key.filter(|key| !key.is_empty())?;
+    if slots <= 1 {
+        return None;
+    }
+    // FNV-1a: stable across runs, unlike `DefaultHasher`.
+    let hash = key.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    Some((hash % slots as u64) as usize)
+}
+
 fn should_reuse_running_config(
     current: &ModelRuntimeConfig,
     requested: &ModelRuntimeConfig,
@@ -800,6 +1132,8 @@ mod tests {
             penalize_nl: None,
             n_keep: None,
             cache_prompt: None,
+            cache_key: None,
+            json_schema: None,
         }
     }
 
@@ -810,9 +1144,40 @@ mod tests {
             running: Arc::new(AtomicBool::new(false)),
             server_path: PathBuf::from("llama-server"),
             model_config: None,
+            slots: 1,
+            serves_embeddings: false,
         }
     }
 
+    #[test]
+    fn usage_is_read_from_counts_or_timings() {
+        let usage = llama_usage(&json!({
+            "tokens_evaluated": 120,
+            "tokens_predicted": 30,
+            "tokens_cached": 100
+        }))
+        .unwrap();
+        assert_eq!(usage.total_tokens, Some(150));
+        assert_eq!(usage.cached_prompt_tokens, Some(100));
+
+        let usage =
+            llama_usage(&json!({ "timings": { "prompt_n": 12, "predicted_n": 4 } })).unwrap();
+        assert_eq!(usage.prompt_tokens, Some(12));
+        assert_eq!(usage.completion_tokens, Some(4));
+        assert!(llama_usage(&json!({ "content": "hi" })).is_none());
+    }
+
+    #[test]
+    fn slot_for_key_is_stable_and_bounded() {
+        assert_eq!(slot_for_key(Some("session-1"), 1), None);
+        assert_eq!(slot_for_key(None, 4), None);
+        assert_eq!(slot_for_key(Some(""), 4), None);
+
+        let slot = slot_for_key(Some("session-1"), 4).unwrap();
+        assert!(slot < 4);
+        assert_eq!(slot_for_key(Some("session-1"), 4), Some(slot));
+    }
+
     #[test]
     fn should_reuse_running_config_only_checks_startup_fields() {
         let current = runtime_config();
@@ -851,7 +1216,11 @@ mod tests {
             .complete_startup(
                 &mut manager,
                 &config,
-                PendingLlamaProcess { child, port: 9 },
+                PendingLlamaProcess {
+                    child,
+                    port: 9,
+                    slots: 1,
+                },
                 Duration::from_millis(25),
                 Duration::from_millis(10),
                 Duration::from_millis(100),
@@ -883,7 +1252,11 @@ mod tests {
                 .complete_startup(
                     &mut manager,
                     &config,
-                    PendingLlamaProcess { child, port: 9 },
+                    PendingLlamaProcess {
+                        child,
+                        port: 9,
+                        slots: 1,
+                    },
                     Duration::from_millis(25),
                     Duration::from_millis(10),
                     Duration::from_millis(100),
@@ -896,4 +1269,42 @@ mod tests {
             assert!(manager.model_config.is_none());
         }
     }
+
+    #[tokio::test]
+    async fn idle_reaper_stops_unused_server_and_respects_activity() {
+        let paths = Arc::new(AppPaths::new());
+        let service = LlamaService::new(paths).unwrap();
+        let mut events = service.subscribe_slot_events();
+        {
+            let mut manager = service.inner.lock().await;
+            manager.child_process = Some(
+                Command::new("/bin/sh")
+                    .arg("-c")
+                    .arg("sleep 5")
+                    .spawn()
+                    .unwrap(),
+            );
+            manager.model_config = Some(runtime_config());
+            manager.running.store(true, Ordering::SeqCst);
+            manager.serves_embeddings = true;
+        }
+        let idle = Duration::from_millis(20);
+
+        // 使用中、または埋め込みを残す設定なら止めない
+        service.activity.active.fetch_add(1, Ordering::SeqCst);
+        let guard = ActivityGuard(service.activity.clone());
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert!(!service.reap_if_idle(idle, false).await);
+        drop(guard);
+        assert!(!service.reap_if_idle(idle, false).await);
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert!(!service.reap_if_idle(idle, true).await);
+
+        assert!(service.reap_if_idle(idle, false).await);
+        assert!(service.loaded_model_id().await.is_none());
+        assert!(service.inner.lock().await.child_process.is_none());
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.status, ModelSlotStatus::Unloaded);
+        assert_eq!(event.previous_model_id.as_deref(), Some("text_model"));
+    }
 }
@@ -1,16 +1,17 @@
 use std::time::Duration;
 
 use futures_util::StreamExt;
-use reqwest::Client;
 use serde_json::{json, Value};
 use tokio::sync::mpsc;
 
 use crate::core::errors::ApiError;
+use crate::core::network::NetClient;
 use crate::llm::external_loader_common::{extract_usage, post_json};
+use crate::llm::stream_framing::SseFramer;
 use crate::llm::types::{ChatRequest, NormalizedAssistantTurn, NormalizedStreamChunk};
 
 pub(crate) async fn chat(
-    http: &Client,
+    http: &NetClient,
     base_url: &str,
     model_name: &str,
     request: ChatRequest,
@@ -56,13 +57,14 @@ pub(crate) async fn chat(
     Ok(NormalizedAssistantTurn {
         visible_text: message_text,
         model_thinking: reasoning_text,
-        finish_reason: None,
+        finish_reason: stop_reason(&payload),
         usage: extract_usage(&payload),
+        tool_calls: Vec::new(),
     })
 }
 
 pub(crate) async fn stream_chat(
-    http: &Client,
+    http: &NetClient,
     base_url: &str,
     model_name: &str,
     request: ChatRequest,
@@ -71,6 +73,7 @@ pub(crate) async fn stream_chat(
     buffer_capacity: usize,
 ) -> Result<mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>>, ApiError> {
     let endpoint = format!("{}/api/v1/chat", base_url.trim_end_matches('/'));
+    let cancel = request.cancellation();
     let body = build_lmstudio_chat_body(model_name, request, true);
     let response = post_json(
         http,
@@ -94,9 +97,12 @@ pub(crate) async fn stream_chat(
     let (tx, rx) = mpsc::channel(buffer_capacity.max(1));
     let mut byte_stream = response.bytes_stream();
     tokio::spawn(async move {
-        let mut buffer = String::new();
+        let mut framer = SseFramer::default();
         loop {
-            let next = tokio::time::timeout(stream_idle_timeout, byte_stream.next()).await;
+            let next = tokio::time::timeout(stream_idle_timeout, byte_stream.next());
+            let Some(next) = cancel.run_until_cancelled(next).await else {
+                return;
+            };
             let next = match next {
                 Ok(value) => value,
                 Err(_) => {
@@ -116,26 +122,14 @@ pub(crate) async fn stream_chat(
 
             match next {
                 Ok(bytes) => {
-                    buffer.push_str(&String::from_utf8_lossy(&bytes));
-
-                    while let Some(double_newline) = buffer.find("\n\n") {
-                        let event_block = buffer[..double_newline].to_string();
-                        buffer = buffer[(double_newline + 2)..].to_string();
+                    for event in framer.push(&bytes) {
+                        let event_type = event.event.as_deref().unwrap_or_default().trim();
+                        let event_data = event.data.trim();
 
-                        let mut event_type = String::new();
-                        let mut event_data = String::new();
-                        for line in event_block.lines() {
-                            if let Some(t) = line.strip_prefix("event: ") {
-                                event_type = t.trim().to_string();
-                            } else if let Some(d) = line.strip_prefix("data: ") {
-                                event_data = d.trim().to_string();
-                            }
-                        }
-
-                        match event_type.as_str() {
+                        match event_type {
                             "reasoning.start" | "reasoning.end" => {}
                             "reasoning.delta" => {
-                                if let Ok(parsed) = serde_json::from_str::<Value>(&event_data) {
+                                if let Ok(parsed) = serde_json::from_str::<Value>(event_data) {
                                     let content = parsed
                                         .get("content")
                                         .and_then(|v| v.as_str())
@@ -147,6 +141,7 @@ pub(crate) async fn stream_chat(
                                                 model_thinking: content.to_string(),
                                                 done: false,
                                                 usage: extract_usage(&parsed),
+                                                finish_reason: None,
                                             }))
                                             .await
                                             .is_err()
@@ -156,7 +151,7 @@ pub(crate) async fn stream_chat(
                                 }
                             }
                             "message.delta" => {
-                                if let Ok(parsed) = serde_json::from_str::<Value>(&event_data) {
+                                if let Ok(parsed) = serde_json::from_str::<Value>(event_data) {
                                     let content = parsed
                                         .get("content")
                                         .and_then(|v| v.as_str())
@@ -168,6 +163,7 @@ pub(crate) async fn stream_chat(
                                                 model_thinking: String::new(),
                                                 done: false,
                                                 usage: extract_usage(&parsed),
+                                                finish_reason: None,
                                             }))
                                             .await
                                             .is_err()
@@ -177,21 +173,22 @@ pub(crate) async fn stream_chat(
                                 }
                             }
                             "chat.end" => {
-                                let usage = serde_json::from_str::<Value>(&event_data)
-                                    .ok()
-                                    .and_then(|payload| extract_usage(&payload));
+                                let payload = serde_json::from_str::<Value>(event_data).ok();
+                                let usage = payload.as_ref().and_then(extract_usage);
+                                let finish_reason = payload.as_ref().and_then(stop_reason);
                                 let _ = tx
                                     .send(Ok(NormalizedStreamChunk {
                                         visible_text: String::new(),
                                         model_thinking: String::new(),
                                         done: true,
                                         usage,
+                                        finish_reason,
                                     }))
                                     .await;
                                 return;
                             }
                             "error" => {
-                                if let Ok(parsed) = serde_json::from_str::<Value>(&event_data) {
+                                if let Ok(parsed) = serde_json::from_str::<Value>(event_data) {
                                     let err_msg = parsed
                                         .get("error")
                                         .and_then(|e| e.get("message"))
@@ -228,6 +225,7 @@ pub(crate) async fn stream_chat(
                 model_thinking: String::new(),
                 done: true,
                 usage: None,
+                finish_reason: None,
             }))
             .await;
     });
@@ -235,6 +233,17 @@ pub(crate) async fn stream_chat(
     Ok(rx)
 }
 
+/// `stats.stop_reason`（`chat.end` では `result` の下）
+fn stop_reason(payload: &Value) -> Option<String> {
+    payload
+        .get("result")
+        .unwrap_or(payload)
+        .get("stats")
+        .and_then(|stats| stats.get("stop_reason"))
+        .and_then(|value| value.as_str())
+        .map(str::to_string)
+}
+
 fn build_lmstudio_chat_body(model_name: &str, request: ChatRequest, stream: bool) -> Value {
     let mut system_parts: Vec<String> = Vec::new();
     let mut input_items: Vec<Value> = Vec::new();
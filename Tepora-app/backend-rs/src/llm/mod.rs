@@ -1,10 +1,22 @@
+mod continuation;
 mod external_loader_common;
+pub(crate) mod http_pool;
 mod lmstudio_native_client;
-mod model_resolution;
+pub(crate) mod model_resolution;
 mod ollama_native_client;
 mod openai_compatible_client;
+mod stream_framing;
+mod tool_calls;
 
+pub mod cancel;
+pub mod circuit_breaker;
+pub mod gemini_provider;
 pub mod llama_service;
+pub mod openai_compatible_provider;
+pub mod openrouter_provider;
+pub mod outage;
+pub mod provider;
+pub mod redaction;
 pub mod service;
 pub mod types;
 
@@ -5,20 +5,43 @@ use serde_json::Value;
 #[cfg(test)]
 use serde_json::json;
 
-use crate::core::config::ConfigService;
+use crate::core::config::{ConfigService, TeporaConfig};
 use crate::core::errors::ApiError;
+use crate::core::safe_mode;
+use crate::llm::gemini_provider::{DEFAULT_GEMINI_BASE_URL, GEMINI_LOADER};
+use crate::llm::llama_service::LLAMA_CPP_LOADER;
+use crate::llm::openai_compatible_provider::{DEFAULT_OPENAI_BASE_URL, OPENAI_COMPATIBLE_LOADER};
+use crate::llm::openrouter_provider::{DEFAULT_OPENROUTER_BASE_URL, OPENROUTER_LOADER};
+use crate::llm::tool_calls::grammar_schema;
 use crate::llm::types::ChatRequest;
-use crate::models::types::{ModelEntry, ModelRuntimeConfig};
+use crate::models::types::{ModelCapabilities, ModelEntry, ModelRuntimeConfig};
 use crate::models::ModelManager;
 
 #[derive(Debug)]
 pub(crate) enum ModelExecutionTarget {
-    LlamaCpp(ModelRuntimeConfig),
+    LlamaCpp(Box<ModelRuntimeConfig>),
     OpenAiCompatible {
         loader: String,
         base_url: String,
         model_name: String,
     },
+    /// `LlmProvider` 実装に委譲するクラウド API。API キーは呼び出し時に設定から読む。
+    Cloud {
+        loader: String,
+        base_url: String,
+        model_name: String,
+    },
+}
+
+impl ModelExecutionTarget {
+    /// ブレーカーや障害の案内で使うローダー名。
+    pub(crate) fn provider_name(&self) -> &str {
+        match self {
+            ModelExecutionTarget::LlamaCpp(_) => LLAMA_CPP_LOADER,
+            ModelExecutionTarget::OpenAiCompatible { loader, .. }
+            | ModelExecutionTarget::Cloud { loader, .. } => loader,
+        }
+    }
 }
 
 pub(crate) fn resolve_model_target(
@@ -30,8 +53,11 @@ pub(crate) fn resolve_model_target(
     let model_entry = models
         .get_model(model_id)?
         .ok_or_else(|| ApiError::BadRequest(format!("Model not found: {}", model_id)))?;
+    let model_entry = route_by_capabilities(models, model_entry, request)?;
+    let model_id = model_entry.id.as_str();
     let config = config_service.load_config().unwrap_or(Value::Null);
     let loader = normalize_loader_name(&model_entry);
+    safe_mode::ensure_loader_allowed(&loader)?;
 
     match loader.as_str() {
         "ollama" => {
@@ -64,17 +90,166 @@ pub(crate) fn resolve_model_target(
                 model_name,
             })
         }
+        OPENAI_COMPATIBLE_LOADER => {
+            let model_name = resolve_loader_model_name(&model_entry, "openai_compatible://")
+                .ok_or_else(|| {
+                    ApiError::BadRequest(format!(
+                        "Model '{}' has no resolvable OpenAI-compatible model name",
+                        model_id
+                    ))
+                })?;
+            let base_url = loader_base_url(&config, OPENAI_COMPATIBLE_LOADER, DEFAULT_OPENAI_BASE_URL);
+            Ok(ModelExecutionTarget::Cloud {
+                loader,
+                base_url,
+                model_name,
+            })
+        }
+        GEMINI_LOADER => {
+            let model_name =
+                resolve_loader_model_name(&model_entry, "gemini://").ok_or_else(|| {
+                    ApiError::BadRequest(format!(
+                        "Model '{}' has no resolvable Gemini model name",
+                        model_id
+                    ))
+                })?;
+            let base_url = loader_base_url(&config, GEMINI_LOADER, DEFAULT_GEMINI_BASE_URL);
+            Ok(ModelExecutionTarget::Cloud {
+                loader,
+                base_url,
+                model_name,
+            })
+        }
+        OPENROUTER_LOADER => {
+            let model_name =
+                resolve_loader_model_name(&model_entry, "openrouter://").ok_or_else(|| {
+                    ApiError::BadRequest(format!(
+                        "Model '{}' has no resolvable OpenRouter model name",
+                        model_id
+                    ))
+                })?;
+            let base_url = loader_base_url(&config, OPENROUTER_LOADER, DEFAULT_OPENROUTER_BASE_URL);
+            Ok(ModelExecutionTarget::Cloud {
+                loader,
+                base_url,
+                model_name,
+            })
+        }
         "llama_cpp" => {
             let model_config = resolve_llama_model_config(&model_entry, &config, request)?;
-            Ok(ModelExecutionTarget::LlamaCpp(model_config))
+            Ok(ModelExecutionTarget::LlamaCpp(Box::new(model_config)))
         }
         other => Err(ApiError::BadRequest(format!(
-            "Model '{}' has unsupported loader '{}'. Supported loaders are: llama_cpp, ollama, lmstudio",
+            "Model '{}' has unsupported loader '{}'. Supported loaders are: llama_cpp, ollama, lmstudio, openai_compatible, gemini, openrouter",
             model_id, other
         ))),
     }
 }
 
+/// 要求の中身が必要とする機能。画像があれば vision、ツール定義かツール結果があれば tool_use
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct RequiredCapabilities {
+    vision: bool,
+    tool_use: bool,
+}
+
+impl RequiredCapabilities {
+    fn of(request: &ChatRequest) -> Self {
+        Self {
+            vision: request.messages.iter().any(|message| message.has_images()),
+            tool_use: !request.tools.is_empty()
+                || request
+                    .messages
+                    .iter()
+                    .any(|message| message.role.eq_ignore_ascii_case("tool")),
+        }
+    }
+
+    /// 足りない機能の名前。機能が不明なモデル（`capabilities` なし）は何も欠けていない扱い
+    fn missing_in(&self, capabilities: Option<&ModelCapabilities>) -> Vec<String> {
+        let Some(capabilities) = capabilities else {
+            return Vec::new();
+        };
+        let mut missing = Vec::new();
+        if self.vision && !capabilities.vision {
+            missing.push("vision".to_string());
+        }
+        if self.tool_use && !capabilities.tool_use {
+            missing.push("tool_use".to_string());
+        }
+        missing
+    }
+}
+
+/// 対応していないと分かっているモデルに画像やツール結果を送らないよう、同じ役割で
+/// 対応しているモデルへ振り替える。なければ `MissingCapability` を返す。
+fn route_by_capabilities(
+    models: &ModelManager,
+    model_entry: ModelEntry,
+    request: &ChatRequest,
+) -> Result<ModelEntry, ApiError> {
+    let required = RequiredCapabilities::of(request);
+    let missing = required.missing_in(model_entry.capabilities.as_ref());
+    if missing.is_empty() {
+        return Ok(model_entry);
+    }
+    let registry = models.get_registry()?;
+    match capable_fallback(&registry.models, &model_entry, required) {
+        Some(fallback) => {
+            tracing::info!(
+                requested = %model_entry.id,
+                routed_to = %fallback.id,
+                missing = ?missing,
+                "Routing request to a model that supports its content"
+            );
+            Ok(fallback.clone())
+        }
+        None => Err(ApiError::MissingCapability {
+            model_id: model_entry.id,
+            missing,
+        }),
+    }
+}
+
+/// 登録順で最初の、同じ役割かつ必要な機能を持つと分かっているモデル。
+/// ローカルのモデルが指定されていたらクラウドへは振り替えない。
+fn capable_fallback<'a>(
+    candidates: &'a [ModelEntry],
+    requested: &ModelEntry,
+    required: RequiredCapabilities,
+) -> Option<&'a ModelEntry> {
+    let local_only = !is_cloud_loader(&normalize_loader_name(requested));
+    candidates.iter().find(|candidate| {
+        candidate.id != requested.id
+            && candidate.role == requested.role
+            && candidate.capabilities.is_some()
+            && required
+                .missing_in(candidate.capabilities.as_ref())
+                .is_empty()
+            && !(local_only && is_cloud_loader(&normalize_loader_name(candidate)))
+    })
+}
+
+/// `ChatRequest::tools` をそのまま渡せるモデルか。llama.cpp は文法で縛るので常に可、
+/// Gemini は未対応、それ以外はツール対応と分かっているモデルだけ。
+pub(crate) fn supports_native_tools(model: &ModelEntry) -> bool {
+    match normalize_loader_name(model).as_str() {
+        "llama_cpp" => model.role == "text",
+        GEMINI_LOADER => false,
+        _ => model
+            .capabilities
+            .as_ref()
+            .is_some_and(|capabilities| capabilities.tool_use),
+    }
+}
+
+pub(crate) fn is_cloud_loader(loader: &str) -> bool {
+    matches!(
+        loader,
+        OPENAI_COMPATIBLE_LOADER | GEMINI_LOADER | OPENROUTER_LOADER
+    )
+}
+
 fn resolve_llama_model_config(
     model_entry: &ModelEntry,
     app_config: &Value,
@@ -82,6 +257,9 @@ fn resolve_llama_model_config(
 ) -> Result<ModelRuntimeConfig, ApiError> {
     if model_entry.file_path.starts_with("ollama://")
         || model_entry.file_path.starts_with("lmstudio://")
+        || model_entry.file_path.starts_with("openai_compatible://")
+        || model_entry.file_path.starts_with("gemini://")
+        || model_entry.file_path.starts_with("openrouter://")
     {
         return Err(ApiError::BadRequest(format!(
             "Model '{}' points to remote URI '{}', but was routed to llama.cpp",
@@ -153,10 +331,12 @@ fn resolve_llama_model_config(
         penalize_nl: request.penalize_nl,
         n_keep: request.n_keep,
         cache_prompt: request.cache_prompt,
+        cache_key: request.cache_key.clone(),
+        json_schema: (!request.tools.is_empty()).then(|| grammar_schema(&request.tools)),
     })
 }
 
-fn normalize_loader_name(model: &ModelEntry) -> String {
+pub(crate) fn normalize_loader_name(model: &ModelEntry) -> String {
     let direct = model.loader.trim().to_ascii_lowercase();
     if !direct.is_empty() {
         return direct;
@@ -168,6 +348,15 @@ fn normalize_loader_name(model: &ModelEntry) -> String {
     if model.file_path.starts_with("lmstudio://") || model.source.eq_ignore_ascii_case("lmstudio") {
         return "lmstudio".to_string();
     }
+    if model.file_path.starts_with("openai_compatible://") {
+        return OPENAI_COMPATIBLE_LOADER.to_string();
+    }
+    if model.file_path.starts_with("gemini://") {
+        return GEMINI_LOADER.to_string();
+    }
+    if model.file_path.starts_with("openrouter://") {
+        return OPENROUTER_LOADER.to_string();
+    }
     "llama_cpp".to_string()
 }
 
@@ -198,15 +387,7 @@ fn resolve_loader_model_name(model: &ModelEntry, scheme_prefix: &str) -> Option<
 }
 
 fn loader_base_url(config: &Value, loader: &str, default_url: &str) -> String {
-    config
-        .get("loaders")
-        .and_then(|v| v.get(loader))
-        .and_then(|v| v.get("base_url"))
-        .and_then(|v| v.as_str())
-        .map(str::trim)
-        .filter(|value| !value.is_empty())
-        .map(|value| value.trim_end_matches('/').to_string())
-        .unwrap_or_else(|| default_url.to_string())
+    TeporaConfig::from_value_or_default(config).loader_base_url(loader, default_url)
 }
 
 #[cfg(test)]
@@ -241,6 +422,7 @@ mod tests {
             format: None,
             tokenizer_path: None,
             tokenizer_format: None,
+            pricing: None,
         }
     }
 
@@ -262,6 +444,70 @@ mod tests {
         assert_eq!(normalize_loader_name(&entry), "custom_loader");
     }
 
+    #[test]
+    fn normalize_loader_infers_cloud_uri_scheme() {
+        let entry = model_entry("", "openai_compatible", "openai_compatible://gpt-4o-mini");
+        assert_eq!(normalize_loader_name(&entry), "openai_compatible");
+        assert_eq!(
+            resolve_loader_model_name(&entry, "openai_compatible://").as_deref(),
+            Some("gpt-4o-mini")
+        );
+
+        let entry = model_entry("", "gemini", "gemini://gemini-2.0-flash");
+        assert_eq!(normalize_loader_name(&entry), "gemini");
+
+        let entry = model_entry("", "openrouter", "openrouter://openai/gpt-4o-mini");
+        assert_eq!(normalize_loader_name(&entry), "openrouter");
+        assert_eq!(
+            resolve_loader_model_name(&entry, "openrouter://").as_deref(),
+            Some("openai/gpt-4o-mini")
+        );
+    }
+
+    fn with_capabilities(mut entry: ModelEntry, id: &str, vision: bool) -> ModelEntry {
+        entry.id = id.to_string();
+        entry.capabilities = Some(ModelCapabilities {
+            completion: true,
+            tool_use: false,
+            vision,
+        });
+        entry
+    }
+
+    #[test]
+    fn image_requests_route_to_a_local_vision_model() {
+        let requested = with_capabilities(model_entry("ollama", "ollama", ""), "text-only", false);
+        let cloud = with_capabilities(
+            model_entry("gemini", "gemini", "gemini://gemini-2.0-flash"),
+            "cloud-vision",
+            true,
+        );
+        let local = with_capabilities(model_entry("ollama", "ollama", ""), "local-vision", true);
+        let mut unknown = model_entry("ollama", "ollama", "");
+        unknown.id = "unknown".to_string();
+        let candidates = vec![requested.clone(), unknown, cloud, local];
+
+        let required = RequiredCapabilities {
+            vision: true,
+            tool_use: false,
+        };
+        assert_eq!(
+            required.missing_in(requested.capabilities.as_ref()),
+            vec!["vision".to_string()]
+        );
+        assert!(required.missing_in(None).is_empty());
+        assert_eq!(
+            capable_fallback(&candidates, &requested, required).map(|m| m.id.as_str()),
+            Some("local-vision")
+        );
+
+        let tools = RequiredCapabilities {
+            vision: false,
+            tool_use: true,
+        };
+        assert!(capable_fallback(&candidates, &requested, tools).is_none());
+    }
+
     #[test]
     fn resolve_loader_model_name_prefers_loader_model_name() {
         let mut entry = model_entry("ollama", "ollama", "ollama://ignored");
@@ -1,16 +1,18 @@
 use std::time::Duration;
 
 use futures_util::StreamExt;
-use reqwest::Client;
 use serde_json::{json, Value};
 use tokio::sync::mpsc;
 
 use crate::core::errors::ApiError;
+use crate::core::network::NetClient;
 use crate::llm::external_loader_common::{extract_field_text, extract_usage, post_json};
+use crate::llm::stream_framing::LineFramer;
+use crate::llm::tool_calls::{openai_tools_value, parse_tool_calls};
 use crate::llm::types::{ChatRequest, NormalizedAssistantTurn, NormalizedStreamChunk};
 
 pub(crate) async fn chat(
-    http: &Client,
+    http: &NetClient,
     base_url: &str,
     model_name: &str,
     request: ChatRequest,
@@ -37,16 +39,14 @@ pub(crate) async fn chat(
             message,
             &["thinking", "reasoning", "reasoning_content"],
         ),
-        finish_reason: payload
-            .get("done_reason")
-            .and_then(|value| value.as_str())
-            .map(str::to_string),
+        finish_reason: done_reason(&payload),
         usage: extract_usage(&payload),
+        tool_calls: parse_tool_calls(message),
     })
 }
 
 pub(crate) async fn stream_chat(
-    http: &Client,
+    http: &NetClient,
     base_url: &str,
     model_name: &str,
     request: ChatRequest,
@@ -55,6 +55,7 @@ pub(crate) async fn stream_chat(
     buffer_capacity: usize,
 ) -> Result<mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>>, ApiError> {
     let endpoint = format!("{}/api/chat", base_url.trim_end_matches('/'));
+    let cancel = request.cancellation();
     let body = build_ollama_chat_body(model_name, request, true);
     let response = post_json(http, &endpoint, &body, "ollama", base_url, request_timeout).await?;
 
@@ -70,9 +71,12 @@ pub(crate) async fn stream_chat(
     let (tx, rx) = mpsc::channel(buffer_capacity.max(1));
     let mut byte_stream = response.bytes_stream();
     tokio::spawn(async move {
-        let mut buffer = String::new();
+        let mut framer = LineFramer::default();
         loop {
-            let next = tokio::time::timeout(stream_idle_timeout, byte_stream.next()).await;
+            let next = tokio::time::timeout(stream_idle_timeout, byte_stream.next());
+            let Some(next) = cancel.run_until_cancelled(next).await else {
+                return;
+            };
             let next = match next {
                 Ok(value) => value,
                 Err(_) => {
@@ -92,17 +96,13 @@ pub(crate) async fn stream_chat(
 
             match next {
                 Ok(bytes) => {
-                    buffer.push_str(&String::from_utf8_lossy(&bytes));
-
-                    while let Some(newline_index) = buffer.find('\n') {
-                        let line = buffer[..newline_index].trim().to_string();
-                        buffer = buffer[(newline_index + 1)..].to_string();
-
+                    for line in framer.push(&bytes) {
+                        let line = line.trim();
                         if line.is_empty() {
                             continue;
                         }
 
-                        let parsed = match serde_json::from_str::<Value>(&line) {
+                        let parsed = match serde_json::from_str::<Value>(line) {
                             Ok(value) => value,
                             Err(err) => {
                                 let _ = tx
@@ -135,6 +135,7 @@ pub(crate) async fn stream_chat(
                                     model_thinking: reasoning,
                                     done,
                                     usage: extract_usage(&parsed),
+                                    finish_reason: done_reason(&parsed),
                                 }))
                                 .await
                                 .is_err()
@@ -159,7 +160,8 @@ pub(crate) async fn stream_chat(
             }
         }
 
-        let trailing = buffer.trim();
+        let trailing = framer.finish().unwrap_or_default();
+        let trailing = trailing.trim();
         if !trailing.is_empty() {
             if let Ok(parsed) = serde_json::from_str::<Value>(trailing) {
                 let message = parsed.get("message").unwrap_or(&Value::Null);
@@ -175,6 +177,7 @@ pub(crate) async fn stream_chat(
                             model_thinking: reasoning,
                             done,
                             usage: extract_usage(&parsed),
+                            finish_reason: done_reason(&parsed),
                         }))
                         .await;
                 }
@@ -187,6 +190,7 @@ pub(crate) async fn stream_chat(
                 model_thinking: String::new(),
                 done: true,
                 usage: None,
+                finish_reason: None,
             }))
             .await;
     });
@@ -194,6 +198,13 @@ pub(crate) async fn stream_chat(
     Ok(rx)
 }
 
+fn done_reason(payload: &Value) -> Option<String> {
+    payload
+        .get("done_reason")
+        .and_then(|value| value.as_str())
+        .map(str::to_string)
+}
+
 fn build_ollama_chat_body(model_name: &str, request: ChatRequest, stream: bool) -> Value {
     // Ollamaは `messages[].content` にテキスト、`messages[].images` に Base64 配列を使う形式
     let messages: Vec<Value> = request
@@ -283,6 +294,9 @@ fn build_ollama_chat_body(model_name: &str, request: ChatRequest, stream: bool)
         if let Some(v) = request.stop {
             obj.insert("stop".to_string(), json!(v));
         }
+        if !stream && !request.tools.is_empty() {
+            obj.insert("tools".to_string(), openai_tools_value(&request.tools));
+        }
     }
 
     body
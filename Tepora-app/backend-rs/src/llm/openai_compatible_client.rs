@@ -1,18 +1,20 @@
 use std::time::Duration;
 
 use futures_util::StreamExt;
-use reqwest::Client;
 use serde_json::{json, Value};
 use tokio::sync::mpsc;
 
 use crate::core::errors::ApiError;
+use crate::core::network::NetClient;
 use crate::llm::external_loader_common::{
     build_openai_compatible_chat_body, extract_field_text, extract_usage, post_json,
 };
+use crate::llm::stream_framing::SseFramer;
+use crate::llm::tool_calls::parse_tool_calls;
 use crate::llm::types::{ChatRequest, NormalizedAssistantTurn, NormalizedStreamChunk};
 
 pub(crate) async fn chat(
-    http: &Client,
+    http: &NetClient,
     loader: &str,
     base_url: &str,
     model_name: &str,
@@ -52,12 +54,13 @@ pub(crate) async fn chat(
             .and_then(|value| value.as_str())
             .map(str::to_string),
         usage: extract_usage(&payload),
+        tool_calls: parse_tool_calls(message),
     })
 }
 
 #[allow(clippy::too_many_arguments)]
 pub(crate) async fn stream_chat(
-    http: &Client,
+    http: &NetClient,
     loader: &str,
     base_url: &str,
     model_name: &str,
@@ -67,6 +70,7 @@ pub(crate) async fn stream_chat(
     buffer_capacity: usize,
 ) -> Result<mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>>, ApiError> {
     let endpoint = format!("{}/v1/chat/completions", base_url.trim_end_matches('/'));
+    let cancel = request.cancellation();
     let body = build_openai_compatible_chat_body(loader, model_name, request, true);
     let response = post_json(http, &endpoint, &body, loader, base_url, request_timeout).await?;
 
@@ -83,9 +87,12 @@ pub(crate) async fn stream_chat(
     let mut byte_stream = response.bytes_stream();
     let loader_name = loader.to_string();
     tokio::spawn(async move {
-        let mut buffer = String::new();
+        let mut framer = SseFramer::default();
         loop {
-            let next = tokio::time::timeout(stream_idle_timeout, byte_stream.next()).await;
+            let next = tokio::time::timeout(stream_idle_timeout, byte_stream.next());
+            let Some(next) = cancel.run_until_cancelled(next).await else {
+                return;
+            };
             let next = match next {
                 Ok(value) => value,
                 Err(_) => {
@@ -106,30 +113,24 @@ pub(crate) async fn stream_chat(
 
             match next {
                 Ok(bytes) => {
-                    buffer.push_str(&String::from_utf8_lossy(&bytes));
-
-                    while let Some(newline_index) = buffer.find('\n') {
-                        let line = buffer[..newline_index].trim().to_string();
-                        buffer = buffer[(newline_index + 1)..].to_string();
-
-                        if line.is_empty() {
+                    for event in framer.push(&bytes) {
+                        let data = event.data.trim();
+                        if data.is_empty() {
                             continue;
                         }
-                        if line == "data: [DONE]" {
+                        if data == "[DONE]" {
                             let _ = tx
                                 .send(Ok(NormalizedStreamChunk {
                                     visible_text: String::new(),
                                     model_thinking: String::new(),
                                     done: true,
                                     usage: None,
+                                    finish_reason: None,
                                 }))
                                 .await;
                             return;
                         }
 
-                        let Some(data) = line.strip_prefix("data: ") else {
-                            continue;
-                        };
                         let parsed = match serde_json::from_str::<Value>(data) {
                             Ok(value) => value,
                             Err(err) => {
@@ -160,9 +161,9 @@ pub(crate) async fn stream_chat(
             }
         }
 
-        let trailing = buffer.trim();
-        if !trailing.is_empty() && trailing != "data: [DONE]" {
-            if let Some(data) = trailing.strip_prefix("data: ") {
+        if let Some(event) = framer.finish() {
+            let data = event.data.trim();
+            if data != "[DONE]" {
                 if let Ok(parsed) = serde_json::from_str::<Value>(data) {
                     let _ = emit_openai_stream_chunk(&tx, &parsed).await;
                 }
@@ -174,6 +175,7 @@ pub(crate) async fn stream_chat(
                 model_thinking: String::new(),
                 done: true,
                 usage: None,
+                finish_reason: None,
             }))
             .await;
     });
@@ -182,7 +184,7 @@ pub(crate) async fn stream_chat(
 }
 
 pub(crate) async fn embed(
-    http: &Client,
+    http: &NetClient,
     loader: &str,
     base_url: &str,
     model_name: &str,
@@ -226,7 +228,7 @@ pub(crate) async fn embed(
 }
 
 pub(crate) async fn get_logprobs(
-    http: &Client,
+    http: &NetClient,
     loader: &str,
     base_url: &str,
     model_name: &str,
@@ -286,11 +288,15 @@ async fn emit_openai_stream_chunk(
     tx: &mpsc::Sender<Result<NormalizedStreamChunk, ApiError>>,
     parsed: &Value,
 ) -> Result<(), ()> {
-    let delta = parsed
+    let choice = parsed
         .get("choices")
         .and_then(|v| v.as_array())
-        .and_then(|choices| choices.first())
-        .and_then(|choice| choice.get("delta"));
+        .and_then(|choices| choices.first());
+    let delta = choice.and_then(|choice| choice.get("delta"));
+    let finish_reason = choice
+        .and_then(|choice| choice.get("finish_reason"))
+        .and_then(|value| value.as_str())
+        .map(str::to_string);
 
     let reasoning = delta
         .map(|d| extract_field_text(d, &["reasoning", "reasoning_content", "thinking"]))
@@ -299,12 +305,13 @@ async fn emit_openai_stream_chunk(
         .map(|d| extract_field_text(d, &["content", "text"]))
         .unwrap_or_default();
 
-    if !reasoning.is_empty() || !content.is_empty() {
+    if !reasoning.is_empty() || !content.is_empty() || finish_reason.is_some() {
         tx.send(Ok(NormalizedStreamChunk {
             visible_text: content,
             model_thinking: reasoning,
             done: false,
             usage: extract_usage(parsed),
+            finish_reason,
         }))
         .await
         .map_err(|_| ())?;
@@ -312,3 +319,71 @@ async fn emit_openai_stream_chunk(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+    use tokio_util::sync::CancellationToken;
+
+    use super::*;
+    use crate::core::config::schema::NetworkSubsystem;
+    use crate::llm::types::ChatMessage;
+
+    #[tokio::test]
+    async fn cancelling_the_stream_closes_the_upstream_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let (closed_tx, closed_rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = socket.read(&mut buf).await.unwrap();
+            let event = "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n";
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n{:x}\r\n{}\r\n",
+                event.len(),
+                event
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            // 生成中のサーバーのように、切断されるまで何も送らない
+            while socket.read(&mut buf).await.unwrap_or(0) > 0 {}
+            let _ = closed_tx.send(());
+        });
+
+        let http = NetClient::from_builder(
+            NetworkSubsystem::CloudProviders,
+            reqwest::Client::builder().no_proxy(),
+        )
+        .unwrap();
+        let cancel = CancellationToken::new();
+        let request = ChatRequest::new(vec![ChatMessage::new_text("user", "Hello")])
+            .with_cancel(cancel.clone());
+        let mut stream = stream_chat(
+            &http,
+            "openai_compatible",
+            &base_url,
+            "test-model",
+            request,
+            Duration::from_secs(5),
+            Duration::from_secs(60),
+            8,
+        )
+        .await
+        .unwrap();
+
+        let first = stream.recv().await.unwrap().unwrap();
+        assert_eq!(first.visible_text, "Hi");
+
+        cancel.cancel();
+        let ended = tokio::time::timeout(Duration::from_secs(5), stream.recv())
+            .await
+            .unwrap();
+        assert!(ended.is_none());
+        tokio::time::timeout(Duration::from_secs(5), closed_rx)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,205 @@
+//! OpenAI 互換のクラウド API（OpenAI、Groq、各種ゲートウェイなど）。
+//!
+//! 接続先は `loaders.openai_compatible.base_url`、キーは `loaders.openai_compatible.api_key`。
+//! リクエストの組み立てとストリームの解釈は `openai_compatible_client` と共通で、
+//! キーはクライアントの既定ヘッダー（`Authorization: Bearer`）で送る。
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::core::errors::ApiError;
+use crate::core::network::NetClient;
+use crate::llm::openai_compatible_client;
+use crate::llm::provider::LlmProvider;
+use crate::llm::types::{
+    ChatRequest, NormalizedAssistantTurn, NormalizedStreamChunk, ProviderModel,
+};
+
+pub const OPENAI_COMPATIBLE_LOADER: &str = "openai_compatible";
+pub const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com";
+
+/// チャットにも埋め込みにも使えないモデル（音声・画像生成・モデレーション）
+const NON_TEXT_MODEL_HINTS: &[&str] = &["whisper", "tts", "dall-e", "moderation", "transcribe"];
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ProviderTimeouts {
+    pub request: Duration,
+    pub stream_idle: Duration,
+    pub stream_buffer: usize,
+}
+
+pub struct OpenAiCompatibleProvider {
+    http: NetClient,
+    base_url: String,
+    timeouts: ProviderTimeouts,
+}
+
+impl OpenAiCompatibleProvider {
+    pub(crate) fn new(http: NetClient, base_url: &str, timeouts: ProviderTimeouts) -> Self {
+        Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            timeouts,
+        }
+    }
+
+    async fn get_models(&self) -> Result<reqwest::Response, ApiError> {
+        let endpoint = format!("{}/v1/models", self.base_url);
+        self.http
+            .get(&endpoint)?
+            .timeout(self.timeouts.request)
+            .send()
+            .await
+            .map_err(|err| {
+                ApiError::internal(format!(
+                    "{} is unreachable at {}: {}",
+                    OPENAI_COMPATIBLE_LOADER, self.base_url, err
+                ))
+            })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn name(&self) -> &str {
+        OPENAI_COMPATIBLE_LOADER
+    }
+
+    async fn health_check(&self) -> Result<bool, ApiError> {
+        Ok(self.get_models().await?.status().is_success())
+    }
+
+    async fn list_models(&self) -> Result<Vec<ProviderModel>, ApiError> {
+        let response = self.get_models().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(ApiError::Internal(format!(
+                "{} model listing failed ({}): {}",
+                OPENAI_COMPATIBLE_LOADER, status, text
+            )));
+        }
+        let payload: Value = response.json().await.map_err(ApiError::internal)?;
+        Ok(parse_model_list(&payload))
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest,
+        model_id: &str,
+    ) -> Result<NormalizedAssistantTurn, ApiError> {
+        openai_compatible_client::chat(
+            &self.http,
+            OPENAI_COMPATIBLE_LOADER,
+            &self.base_url,
+            model_id,
+            request,
+            self.timeouts.request,
+        )
+        .await
+    }
+
+    async fn stream_chat(
+        &self,
+        request: ChatRequest,
+        model_id: &str,
+    ) -> Result<mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>>, ApiError> {
+        openai_compatible_client::stream_chat(
+            &self.http,
+            OPENAI_COMPATIBLE_LOADER,
+            &self.base_url,
+            model_id,
+            request,
+            self.timeouts.request,
+            self.timeouts.stream_idle,
+            self.timeouts.stream_buffer,
+        )
+        .await
+    }
+
+    async fn embed(&self, inputs: &[String], model_id: &str) -> Result<Vec<Vec<f32>>, ApiError> {
+        openai_compatible_client::embed(
+            &self.http,
+            OPENAI_COMPATIBLE_LOADER,
+            &self.base_url,
+            model_id,
+            inputs,
+            self.timeouts.request,
+        )
+        .await
+    }
+}
+
+/// `GET /v1/models` の `data[]`。`context_length`（OpenRouter など）や
+/// `context_window`（Groq）があれば拾い、なければ 0。
+fn parse_model_list(payload: &Value) -> Vec<ProviderModel> {
+    payload
+        .get("data")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            let id = item.get("id").and_then(Value::as_str)?.trim();
+            if id.is_empty() {
+                return None;
+            }
+            let lowered = id.to_ascii_lowercase();
+            if NON_TEXT_MODEL_HINTS
+                .iter()
+                .any(|hint| lowered.contains(hint))
+            {
+                return None;
+            }
+            let ctx = ["context_length", "context_window"]
+                .iter()
+                .find_map(|key| item.get(*key).and_then(Value::as_u64))
+                .unwrap_or(0);
+            Some(ProviderModel {
+                id: id.to_string(),
+                name: item
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or(id)
+                    .to_string(),
+                ctx,
+                pricing: None,
+                capabilities: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn model_list_skips_audio_and_image_models() {
+        let models = parse_model_list(&json!({
+            "object": "list",
+            "data": [
+                { "id": "gpt-4o-mini", "object": "model" },
+                { "id": "whisper-1", "object": "model" },
+                { "id": "text-embedding-3-small", "object": "model" },
+                { "id": "llama-3.3-70b-versatile", "context_window": 131072 },
+                { "id": "dall-e-3" }
+            ]
+        }));
+
+        let ids: Vec<_> = models.iter().map(|model| model.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "gpt-4o-mini",
+                "text-embedding-3-small",
+                "llama-3.3-70b-versatile"
+            ]
+        );
+        assert_eq!(models[0].ctx, 0);
+        assert_eq!(models[2].ctx, 131072);
+    }
+}
@@ -0,0 +1,251 @@
+//! OpenRouter（https://openrouter.ai）。多数のクラウドモデルを 1 つのキーで使える中継。
+//!
+//! 接続先は `loaders.openrouter.base_url`、キーは `loaders.openrouter.api_key`
+//! （`Authorization: Bearer`）。チャット・ストリーム・埋め込みは OpenAI 互換なので
+//! `openai_compatible_client` に任せ、ここではモデル一覧から料金・コンテキスト長・
+//! 対応機能を読み取ってレジストリへ渡す。
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::core::config::schema::ModelPricing;
+use crate::core::errors::ApiError;
+use crate::core::network::NetClient;
+use crate::llm::openai_compatible_client;
+use crate::llm::openai_compatible_provider::ProviderTimeouts;
+use crate::llm::provider::LlmProvider;
+use crate::llm::types::{
+    ChatRequest, NormalizedAssistantTurn, NormalizedStreamChunk, ProviderModel,
+};
+use crate::models::types::ModelCapabilities;
+
+pub const OPENROUTER_LOADER: &str = "openrouter";
+/// `/v1/...` を後ろに付けるので `/api` までを持つ
+pub const DEFAULT_OPENROUTER_BASE_URL: &str = "https://openrouter.ai/api";
+
+pub struct OpenRouterProvider {
+    http: NetClient,
+    base_url: String,
+    timeouts: ProviderTimeouts,
+}
+
+impl OpenRouterProvider {
+    pub(crate) fn new(http: NetClient, base_url: &str, timeouts: ProviderTimeouts) -> Self {
+        Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            timeouts,
+        }
+    }
+
+    async fn get_models(&self) -> Result<reqwest::Response, ApiError> {
+        let endpoint = format!("{}/v1/models", self.base_url);
+        self.http
+            .get(&endpoint)?
+            .timeout(self.timeouts.request)
+            .send()
+            .await
+            .map_err(|err| {
+                ApiError::internal(format!(
+                    "{} is unreachable at {}: {}",
+                    OPENROUTER_LOADER, self.base_url, err
+                ))
+            })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenRouterProvider {
+    fn name(&self) -> &str {
+        OPENROUTER_LOADER
+    }
+
+    async fn health_check(&self) -> Result<bool, ApiError> {
+        Ok(self.get_models().await?.status().is_success())
+    }
+
+    async fn list_models(&self) -> Result<Vec<ProviderModel>, ApiError> {
+        let response = self.get_models().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(ApiError::Internal(format!(
+                "{} model listing failed ({}): {}",
+                OPENROUTER_LOADER, status, text
+            )));
+        }
+        let payload: Value = response.json().await.map_err(ApiError::internal)?;
+        Ok(parse_model_catalog(&payload))
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest,
+        model_id: &str,
+    ) -> Result<NormalizedAssistantTurn, ApiError> {
+        openai_compatible_client::chat(
+            &self.http,
+            OPENROUTER_LOADER,
+            &self.base_url,
+            model_id,
+            request,
+            self.timeouts.request,
+        )
+        .await
+    }
+
+    async fn stream_chat(
+        &self,
+        request: ChatRequest,
+        model_id: &str,
+    ) -> Result<mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>>, ApiError> {
+        openai_compatible_client::stream_chat(
+            &self.http,
+            OPENROUTER_LOADER,
+            &self.base_url,
+            model_id,
+            request,
+            self.timeouts.request,
+            self.timeouts.stream_idle,
+            self.timeouts.stream_buffer,
+        )
+        .await
+    }
+
+    async fn embed(&self, inputs: &[String], model_id: &str) -> Result<Vec<Vec<f32>>, ApiError> {
+        openai_compatible_client::embed(
+            &self.http,
+            OPENROUTER_LOADER,
+            &self.base_url,
+            model_id,
+            inputs,
+            self.timeouts.request,
+        )
+        .await
+    }
+}
+
+/// `GET /v1/models` の `data[]`。テキストを出力しないモデル（画像生成など）は除く。
+fn parse_model_catalog(payload: &Value) -> Vec<ProviderModel> {
+    payload
+        .get("data")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            let id = item.get("id").and_then(Value::as_str)?.trim();
+            if id.is_empty() {
+                return None;
+            }
+            let architecture = item.get("architecture");
+            let modalities = |key: &str| -> Vec<&str> {
+                architecture
+                    .and_then(|arch| arch.get(key))
+                    .and_then(Value::as_array)
+                    .map(|values| values.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default()
+            };
+            let outputs = modalities("output_modalities");
+            if !outputs.is_empty() && !outputs.contains(&"text") {
+                return None;
+            }
+            let supports_tools = item
+                .get("supported_parameters")
+                .and_then(Value::as_array)
+                .is_some_and(|params| params.iter().any(|param| param.as_str() == Some("tools")));
+            let ctx = item
+                .get("context_length")
+                .or_else(|| item.pointer("/top_provider/context_length"))
+                .and_then(Value::as_u64)
+                .unwrap_or(0);
+            Some(ProviderModel {
+                id: id.to_string(),
+                name: item
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or(id)
+                    .to_string(),
+                ctx,
+                pricing: parse_pricing(item.get("pricing")),
+                capabilities: Some(ModelCapabilities {
+                    completion: true,
+                    tool_use: supports_tools,
+                    vision: modalities("input_modalities").contains(&"image"),
+                }),
+            })
+        })
+        .collect()
+}
+
+/// `pricing.prompt` / `pricing.completion` は 1 トークンあたりの USD（文字列）。
+/// ルーター系モデルの `-1`（都度決まる）は料金不明として扱う。
+fn parse_pricing(pricing: Option<&Value>) -> Option<ModelPricing> {
+    let per_token = |key: &str| -> Option<f64> {
+        let value = pricing?.get(key)?;
+        let price = match value {
+            Value::String(raw) => raw.trim().parse::<f64>().ok()?,
+            other => other.as_f64()?,
+        };
+        (price >= 0.0).then_some(price)
+    };
+    Some(ModelPricing {
+        input_per_million: per_token("prompt")? * 1_000_000.0,
+        output_per_million: per_token("completion")? * 1_000_000.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn catalog_carries_pricing_context_and_capabilities() {
+        let models = parse_model_catalog(&json!({
+            "data": [
+                {
+                    "id": "openai/gpt-4o-mini",
+                    "name": "OpenAI: GPT-4o-mini",
+                    "context_length": 128000,
+                    "architecture": {
+                        "input_modalities": ["text", "image"],
+                        "output_modalities": ["text"]
+                    },
+                    "pricing": { "prompt": "0.00000015", "completion": "0.0000006" },
+                    "supported_parameters": ["temperature", "tools", "tool_choice"]
+                },
+                {
+                    "id": "openrouter/auto",
+                    "name": "Auto Router",
+                    "top_provider": { "context_length": 2000000 },
+                    "pricing": { "prompt": "-1", "completion": "-1" }
+                },
+                {
+                    "id": "google/gemini-2.5-flash-image",
+                    "architecture": { "output_modalities": ["image"] }
+                }
+            ]
+        }));
+
+        assert_eq!(models.len(), 2);
+        let mini = &models[0];
+        assert_eq!(mini.ctx, 128000);
+        let pricing = mini.pricing.unwrap();
+        assert!((pricing.input_per_million - 0.15).abs() < 1e-9);
+        assert!((pricing.output_per_million - 0.6).abs() < 1e-9);
+        assert_eq!(
+            mini.capabilities,
+            Some(ModelCapabilities {
+                completion: true,
+                tool_use: true,
+                vision: true,
+            })
+        );
+
+        let auto = &models[1];
+        assert_eq!(auto.ctx, 2000000);
+        assert_eq!(auto.pricing, None);
+        assert_eq!(auto.capabilities.as_ref().map(|c| c.vision), Some(false));
+    }
+}
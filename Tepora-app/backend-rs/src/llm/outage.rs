@@ -0,0 +1,125 @@
+//! プロバイダーに繋がらないときの扱い。
+//!
+//! 接続そのものに失敗した送信は `ApiError::ProviderUnavailable` にする。`LlmService` は
+//! 一度だけ繋ぎ直し（llama-server は落ちたプロセスを片付けて起動し直す）、それでも
+//! 駄目なら WS に `provider_unavailable` を送って、ローダーごとの対処を案内する。
+
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::core::errors::ApiError;
+
+/// 外部プロバイダーへ繋ぎ直すまでの待ち時間（起動直後のサーバー向け）
+pub(crate) const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// 繋がらなかったプロバイダー
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProviderOutage {
+    pub provider: String,
+    pub message: String,
+}
+
+/// UI に出す対処。`action` で出し分け、`message` はそのまま表示できる文面
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RemediationHint {
+    /// start_ollama / start_lmstudio / start_server / rerun_setup / check_network / switch_model
+    pub action: String,
+    pub message: String,
+}
+
+impl ProviderOutage {
+    pub fn from_error(err: &ApiError) -> Option<Self> {
+        match err {
+            ApiError::ProviderUnavailable { provider, message } => Some(Self {
+                provider: provider.clone(),
+                message: message.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn hints(&self) -> Vec<RemediationHint> {
+        remediation_hints(&self.provider)
+    }
+}
+
+pub fn remediation_hints(provider: &str) -> Vec<RemediationHint> {
+    let first = match provider {
+        "ollama" => (
+            "start_ollama",
+            "Start Ollama (for example `ollama serve`) and send the message again.",
+        ),
+        "lmstudio" => (
+            "start_lmstudio",
+            "Open LM Studio and start its local server, then send the message again.",
+        ),
+        "llama_cpp" => (
+            "rerun_setup",
+            "llama-server stopped responding. Re-run setup to check the server binary and model files.",
+        ),
+        "openai_compatible" | "gemini" | "openrouter" => (
+            "check_network",
+            "Check your network connection and the provider's status page.",
+        ),
+        _ => (
+            "start_server",
+            "Make sure the server at the configured base URL is running.",
+        ),
+    };
+    [
+        first,
+        (
+            "switch_model",
+            "Switch to a model from another provider in the model settings.",
+        ),
+    ]
+    .into_iter()
+    .map(|(action, message)| RemediationHint {
+        action: action.to_string(),
+        message: message.to_string(),
+    })
+    .collect()
+}
+
+/// 送信時のエラーを振り分ける。接続できなかったときだけ `ProviderUnavailable`。
+pub(crate) fn send_error(provider: &str, target: &str, err: reqwest::Error) -> ApiError {
+    if err.is_connect() {
+        ApiError::ProviderUnavailable {
+            provider: provider.to_string(),
+            message: format!("Cannot connect to {}: {}", target, err),
+        }
+    } else {
+        ApiError::Internal(format!(
+            "Failed to reach '{}' loader at {}: {}",
+            provider, target, err
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hints_name_the_provider_specific_fix_and_a_model_switch() {
+        let actions = |provider: &str| {
+            remediation_hints(provider)
+                .into_iter()
+                .map(|hint| hint.action)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(actions("ollama"), vec!["start_ollama", "switch_model"]);
+        assert_eq!(actions("llama_cpp"), vec!["rerun_setup", "switch_model"]);
+        assert_eq!(actions("vllm"), vec!["start_server", "switch_model"]);
+
+        let outage = ProviderOutage::from_error(&ApiError::ProviderUnavailable {
+            provider: "lmstudio".to_string(),
+            message: "connection refused".to_string(),
+        })
+        .unwrap();
+        assert_eq!(outage.hints()[0].action, "start_lmstudio");
+        assert!(ProviderOutage::from_error(&ApiError::internal("boom")).is_none());
+    }
+}
@@ -1,12 +1,13 @@
 use async_trait::async_trait;
 use tokio::sync::mpsc;
 
+use super::types::{ChatRequest, NormalizedAssistantTurn, NormalizedStreamChunk, ProviderModel};
 use crate::core::errors::ApiError;
-use super::types::{ChatRequest, ProviderModel};
 
+/// クラウドの推論 API。`LlmService` は `ModelExecutionTarget::Cloud` をここへ委譲する。
 #[async_trait]
 pub trait LlmProvider: Send + Sync {
-    /// return the provider name (e.g. "ollama", "lmstudio", "llama_cpp")
+    /// return the provider name (e.g. "openai_compatible")
     fn name(&self) -> &str;
 
     /// check if the provider is healthy/reachable
@@ -16,14 +17,18 @@ pub trait LlmProvider: Send + Sync {
     async fn list_models(&self) -> Result<Vec<ProviderModel>, ApiError>;
 
     /// chat completion (non-streaming)
-    async fn chat(&self, request: ChatRequest, model_id: &str) -> Result<String, ApiError>;
+    async fn chat(
+        &self,
+        request: ChatRequest,
+        model_id: &str,
+    ) -> Result<NormalizedAssistantTurn, ApiError>;
 
     /// chat completion (streaming)
     async fn stream_chat(
         &self,
         request: ChatRequest,
         model_id: &str,
-    ) -> Result<mpsc::Receiver<Result<String, ApiError>>, ApiError>;
+    ) -> Result<mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>>, ApiError>;
 
     /// generate embeddings
     async fn embed(&self, inputs: &[String], model_id: &str) -> Result<Vec<Vec<f32>>, ApiError>;
@@ -0,0 +1,596 @@
+//! クラウドへ送るプロンプトの伏せ字処理。
+//!
+//! 送信先ごとに信頼度（`privacy.redaction.provider_trust`）を決め、信頼できない
+//! 送信先へは秘密・個人情報・ユーザーディレクトリを置換してから送る。
+//! llama.cpp やループバック／プライベートアドレス上のローカルローダーは既定で
+//! 信頼済みとし、何も変えない。
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::llm::types::ChatRequest;
+
+pub const REDACTION_TRUST_LEVELS: &[&str] = &["trusted", "partial", "untrusted"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustLevel {
+    /// 何も伏せない
+    Trusted,
+    /// 秘密（API キー・トークン等）だけ伏せる
+    Partial,
+    /// 有効なすべての規則を適用する
+    Untrusted,
+}
+
+impl TrustLevel {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "trusted" => Some(Self::Trusted),
+            "partial" => Some(Self::Partial),
+            "untrusted" => Some(Self::Untrusted),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionCategory {
+    Secret,
+    Email,
+    Phone,
+    CardNumber,
+    IpAddress,
+    UserPath,
+    Custom,
+}
+
+impl RedactionCategory {
+    fn placeholder(self) -> &'static str {
+        match self {
+            Self::Secret => "[REDACTED_SECRET]",
+            Self::Email => "[REDACTED_EMAIL]",
+            Self::Phone => "[REDACTED_PHONE]",
+            Self::CardNumber => "[REDACTED_CARD]",
+            Self::IpAddress => "[REDACTED_IP]",
+            Self::UserPath => "[USER_DIR]",
+            Self::Custom => "[REDACTED]",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RedactionPolicy {
+    pub enabled: bool,
+    pub secrets: bool,
+    pub pii: bool,
+    pub paths: bool,
+    pub custom_patterns: Vec<Regex>,
+    pub provider_trust: BTreeMap<String, TrustLevel>,
+}
+
+impl Default for RedactionPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            secrets: true,
+            pii: true,
+            paths: true,
+            custom_patterns: Vec::new(),
+            provider_trust: BTreeMap::new(),
+        }
+    }
+}
+
+impl RedactionPolicy {
+    pub fn from_config(config: &Value) -> Self {
+        let mut policy = Self::default();
+        let Some(section) = config
+            .get("privacy")
+            .and_then(|privacy| privacy.get("redaction"))
+            .and_then(Value::as_object)
+        else {
+            return policy;
+        };
+        let flag =
+            |key: &str, default: bool| section.get(key).and_then(Value::as_bool).unwrap_or(default);
+        policy.enabled = flag("enabled", policy.enabled);
+        policy.secrets = flag("secrets", policy.secrets);
+        policy.pii = flag("pii", policy.pii);
+        policy.paths = flag("paths", policy.paths);
+        policy.custom_patterns = section
+            .get("custom_patterns")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(err) => {
+                    tracing::warn!("Ignoring invalid redaction pattern: {}", err);
+                    None
+                }
+            })
+            .collect();
+        policy.provider_trust = section
+            .get("provider_trust")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter_map(|(provider, level)| {
+                Some((
+                    provider.to_ascii_lowercase(),
+                    TrustLevel::parse(level.as_str()?)?,
+                ))
+            })
+            .collect();
+        policy
+    }
+
+    /// 会話を外に貼るための書き出し用。設定の有効／無効に関わらずすべての規則を使い、
+    /// ユーザー定義パターンに加えて OS のユーザー名も伏せる。
+    pub fn for_export(config: &Value) -> Self {
+        let mut policy = Self::from_config(config);
+        policy.enabled = true;
+        policy.secrets = true;
+        policy.pii = true;
+        policy.paths = true;
+        let username = ["USER", "USERNAME"]
+            .iter()
+            .find_map(|key| std::env::var(key).ok())
+            .filter(|name| name.trim().chars().count() >= 3);
+        if let Some(name) = username {
+            if let Ok(pattern) = Regex::new(&format!(r"(?i)\b{}\b", regex::escape(name.trim()))) {
+                policy.custom_patterns.push(pattern);
+            }
+        }
+        policy
+    }
+
+    /// 送信先の信頼度。設定があればそれを、なければ接続先アドレスで判断する。
+    pub fn trust_for(&self, provider: &str, base_url: Option<&str>) -> TrustLevel {
+        if let Some(level) = self.provider_trust.get(&provider.to_ascii_lowercase()) {
+            return *level;
+        }
+        match base_url {
+            None => TrustLevel::Trusted,
+            Some(url) if is_local_endpoint(url) => TrustLevel::Trusted,
+            Some(_) => TrustLevel::Untrusted,
+        }
+    }
+}
+
+/// ループバック・プライベートアドレス・`.local` を「このマシン（LAN）内」とみなす。
+pub fn is_local_endpoint(base_url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(base_url) else {
+        return false;
+    };
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.eq_ignore_ascii_case("localhost") || host.ends_with(".local") {
+        return true;
+    }
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Ok(IpAddr::V6(ip)) => ip.is_loopback() || ip.is_unique_local(),
+        Err(_) => false,
+    }
+}
+
+/// 1 回の送信で何をいくつ伏せたか（ラン・トレース用）。本文は含めない。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionReport {
+    pub provider: String,
+    pub trust: Option<TrustLevel>,
+    pub counts: BTreeMap<RedactionCategory, usize>,
+    pub total: usize,
+}
+
+/// 呼び出し側が報告を受け取るための入れ物。`ChatRequest` に持たせる。
+pub type RedactionSink = Arc<Mutex<Option<RedactionReport>>>;
+
+pub fn new_redaction_sink() -> RedactionSink {
+    Arc::new(Mutex::new(None))
+}
+
+pub fn take_redaction_report(sink: &RedactionSink) -> Option<RedactionReport> {
+    sink.lock().unwrap_or_else(|e| e.into_inner()).take()
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid redaction regex"))
+}
+
+fn secret_patterns() -> [&'static Regex; 4] {
+    static PROVIDER_KEYS: OnceLock<Regex> = OnceLock::new();
+    static ASSIGNMENTS: OnceLock<Regex> = OnceLock::new();
+    static PRIVATE_KEYS: OnceLock<Regex> = OnceLock::new();
+    static JWT: OnceLock<Regex> = OnceLock::new();
+    [
+        regex(
+            &PRIVATE_KEYS,
+            r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----",
+        ),
+        regex(
+            &PROVIDER_KEYS,
+            r"\b(?:sk-(?:ant-|proj-)?[A-Za-z0-9_\-]{20,}|AKIA[0-9A-Z]{16}|gh[pousr]_[A-Za-z0-9]{30,}|xox[abprs]-[A-Za-z0-9\-]{10,}|AIza[0-9A-Za-z_\-]{35})\b",
+        ),
+        regex(
+            &JWT,
+            r"\beyJ[A-Za-z0-9_\-]{8,}\.[A-Za-z0-9_\-]{8,}\.[A-Za-z0-9_\-]{8,}\b",
+        ),
+        regex(
+            &ASSIGNMENTS,
+            r#"(?i)((?:api[_-]?key|secret|password|passwd|token)["']?\s*[:=]\s*["']?|bearer\s+)([A-Za-z0-9_\-\./+=]{12,})"#,
+        ),
+    ]
+}
+
+fn email_pattern() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    regex(&RE, r"\b[A-Za-z0-9._%+\-]+@[A-Za-z0-9.\-]+\.[A-Za-z]{2,}\b")
+}
+
+fn card_pattern() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    regex(&RE, r"\b(?:\d[ \-]?){12,18}\d\b")
+}
+
+fn phone_pattern() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    regex(
+        &RE,
+        r"(?:\+\d{1,3}[ \-]?)?(?:\(\d{2,4}\)[ \-]?|\b\d{2,4}[ \-])\d{2,4}[ \-]\d{3,4}\b",
+    )
+}
+
+fn ipv4_pattern() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    regex(&RE, r"\b(?:\d{1,3}\.){3}\d{1,3}\b")
+}
+
+fn user_path_patterns() -> [&'static Regex; 2] {
+    static WINDOWS: OnceLock<Regex> = OnceLock::new();
+    static UNIX: OnceLock<Regex> = OnceLock::new();
+    [
+        regex(&WINDOWS, r"(?i)\b[a-z]:\\users\\[^\\/\s]+"),
+        regex(&UNIX, r"/(?:home|Users)/[^/\s]+"),
+    ]
+}
+
+/// 一致の直前が区切りかどうか。URL や長い数字列の途中から始まる一致を除く。
+fn starts_at_boundary(haystack: &str, start: usize, joiners: &str) -> bool {
+    haystack[..start]
+        .chars()
+        .next_back()
+        .is_none_or(|c| !c.is_alphanumeric() && !joiners.contains(c))
+}
+
+/// 一致の直後で数字列が続いていないかどうか。
+fn ends_at_boundary(haystack: &str, end: usize, joiners: &str) -> bool {
+    let mut rest = haystack[end..].chars();
+    match rest.next() {
+        None => true,
+        Some(c) if c.is_alphanumeric() => false,
+        Some(c) if joiners.contains(c) => !rest.next().is_some_and(|n| n.is_ascii_digit()),
+        Some(_) => true,
+    }
+}
+
+/// 9 桁に満たない数字の並び（`10 20 300` など）は電話番号とみなさない。
+fn standalone_phone(haystack: &str, candidate: regex::Match<'_>) -> bool {
+    let digits = candidate
+        .as_str()
+        .chars()
+        .filter(char::is_ascii_digit)
+        .count();
+    digits >= 9
+        && starts_at_boundary(haystack, candidate.start(), "+-./")
+        && ends_at_boundary(haystack, candidate.end(), "-./")
+}
+
+/// URL の一部（`https://host/home/...`）は除き、`file://` の後ろは伏せる。
+fn standalone_user_path(haystack: &str, candidate: regex::Match<'_>) -> bool {
+    let start = candidate.start();
+    haystack[..start].ends_with("file://") || starts_at_boundary(haystack, start, "/._-~%")
+}
+
+fn luhn_valid(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, digit)| {
+            if index % 2 == 1 {
+                let doubled = digit * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                *digit
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+fn replace_counted(
+    text: &str,
+    regex: &Regex,
+    category: RedactionCategory,
+    counts: &mut BTreeMap<RedactionCategory, usize>,
+    accept: impl Fn(&regex::Captures) -> bool,
+) -> String {
+    let mut hits = 0usize;
+    let replaced = regex.replace_all(text, |caps: &regex::Captures| {
+        if !accept(caps) {
+            return caps[0].to_string();
+        }
+        hits += 1;
+        // `key=value` 形式はキー名を残して値だけ伏せる
+        match caps.get(2) {
+            Some(_) if category == RedactionCategory::Secret => {
+                format!("{}{}", &caps[1], category.placeholder())
+            }
+            _ => category.placeholder().to_string(),
+        }
+    });
+    if hits > 0 {
+        *counts.entry(category).or_default() += hits;
+    }
+    replaced.into_owned()
+}
+
+/// 信頼度に応じてテキストを伏せ字にする。
+pub fn redact_text(
+    text: &str,
+    policy: &RedactionPolicy,
+    trust: TrustLevel,
+    counts: &mut BTreeMap<RedactionCategory, usize>,
+) -> String {
+    if !policy.enabled || trust == TrustLevel::Trusted || text.is_empty() {
+        return text.to_string();
+    }
+    let mut output = text.to_string();
+    if policy.secrets {
+        for pattern in secret_patterns() {
+            output = replace_counted(&output, pattern, RedactionCategory::Secret, counts, |_| {
+                true
+            });
+        }
+    }
+    if trust == TrustLevel::Partial {
+        return output;
+    }
+    if policy.pii {
+        output = replace_counted(
+            &output,
+            email_pattern(),
+            RedactionCategory::Email,
+            counts,
+            |_| true,
+        );
+        output = replace_counted(
+            &output,
+            card_pattern(),
+            RedactionCategory::CardNumber,
+            counts,
+            |caps| luhn_valid(&caps[0]),
+        );
+        output = replace_counted(
+            &output,
+            ipv4_pattern(),
+            RedactionCategory::IpAddress,
+            counts,
+            |caps| caps[0].parse::<std::net::Ipv4Addr>().is_ok(),
+        );
+        output = replace_counted(
+            &output,
+            phone_pattern(),
+            RedactionCategory::Phone,
+            counts,
+            |caps| caps.get(0).is_some_and(|m| standalone_phone(&output, m)),
+        );
+    }
+    if policy.paths {
+        for pattern in user_path_patterns() {
+            output = replace_counted(
+                &output,
+                pattern,
+                RedactionCategory::UserPath,
+                counts,
+                |caps| {
+                    caps.get(0)
+                        .is_some_and(|m| standalone_user_path(&output, m))
+                },
+            );
+        }
+    }
+    for pattern in &policy.custom_patterns {
+        output = replace_counted(&output, pattern, RedactionCategory::Custom, counts, |_| {
+            true
+        });
+    }
+    output
+}
+
+/// リクエスト内のメッセージ（マルチモーダルのテキスト部分を含む）を伏せ字にする。
+pub fn redact_request(
+    request: &mut ChatRequest,
+    policy: &RedactionPolicy,
+    provider: &str,
+    trust: TrustLevel,
+) -> RedactionReport {
+    let mut counts = BTreeMap::new();
+    for message in request.messages.iter_mut() {
+        message.content = redact_text(&message.content, policy, trust, &mut counts);
+        if let Some(parts) = message.multimodal_parts.as_mut() {
+            for part in parts.iter_mut() {
+                if let Some(Value::String(text)) = part.get_mut("text") {
+                    *text = redact_text(text, policy, trust, &mut counts);
+                }
+            }
+        }
+    }
+    let total = counts.values().sum();
+    RedactionReport {
+        provider: provider.to_string(),
+        trust: Some(trust),
+        counts,
+        total,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::types::ChatMessage;
+    use serde_json::json;
+
+    fn redact(text: &str, trust: TrustLevel) -> (String, BTreeMap<RedactionCategory, usize>) {
+        let mut counts = BTreeMap::new();
+        let output = redact_text(text, &RedactionPolicy::default(), trust, &mut counts);
+        (output, counts)
+    }
+
+    #[test]
+    fn untrusted_providers_get_secrets_pii_and_paths_removed() {
+        let (output, counts) = redact(
+            "key sk-abcdefghijklmnopqrstuvwx, mail me at taro@example.com, \
+             card 4111 1111 1111 1111, file /home/taro/notes.md, host 192.168.1.20",
+            TrustLevel::Untrusted,
+        );
+        assert!(!output.contains("sk-abcdef"));
+        assert!(!output.contains("taro@example.com"));
+        assert!(!output.contains("4111"));
+        assert!(!output.contains("/home/taro"));
+        assert!(output.contains("[USER_DIR]/notes.md"));
+        assert!(!output.contains("192.168.1.20"));
+        assert_eq!(counts[&RedactionCategory::Secret], 1);
+        assert_eq!(counts[&RedactionCategory::Email], 1);
+        assert_eq!(counts[&RedactionCategory::CardNumber], 1);
+    }
+
+    #[test]
+    fn partial_trust_only_removes_secrets() {
+        let (output, _) = redact(
+            "password: hunter2hunter2 for taro@example.com",
+            TrustLevel::Partial,
+        );
+        assert_eq!(output, "password: [REDACTED_SECRET] for taro@example.com");
+        let (trusted, counts) = redact("sk-abcdefghijklmnopqrstuvwx", TrustLevel::Trusted);
+        assert_eq!(trusted, "sk-abcdefghijklmnopqrstuvwx");
+        assert!(counts.is_empty());
+    }
+
+    #[test]
+    fn secret_keywords_in_prose_are_kept() {
+        let (output, counts) = redact(
+            "the password verification step uses Bearer abcdefghijklmnop",
+            TrustLevel::Partial,
+        );
+        assert_eq!(
+            output,
+            "the password verification step uses Bearer [REDACTED_SECRET]"
+        );
+        assert_eq!(counts[&RedactionCategory::Secret], 1);
+    }
+
+    #[test]
+    fn numbers_that_are_not_cards_are_kept() {
+        let (output, counts) = redact("order 1234567890123456 shipped", TrustLevel::Untrusted);
+        assert!(output.contains("1234567890123456"));
+        assert!(!counts.contains_key(&RedactionCategory::CardNumber));
+    }
+
+    #[test]
+    fn urls_and_digit_runs_are_not_taken_for_paths_or_phones() {
+        let text = "see https://example.com/home/taro/docs and http://host:8080/Users/list, \
+                    build 2024-10-1234-56, ratios 10 20 300, id 123456-78-9012";
+        let (output, counts) = redact(text, TrustLevel::Untrusted);
+        assert_eq!(output, text);
+        assert!(counts.is_empty(), "{:?}", counts);
+
+        let (output, counts) = redact(
+            "call 090-1234-5678 or +81 90-1234-5678, open file:///home/taro/a.txt",
+            TrustLevel::Untrusted,
+        );
+        assert_eq!(
+            output,
+            "call [REDACTED_PHONE] or [REDACTED_PHONE], open file://[USER_DIR]/a.txt"
+        );
+        assert_eq!(counts[&RedactionCategory::Phone], 2);
+        assert_eq!(counts[&RedactionCategory::UserPath], 1);
+    }
+
+    #[test]
+    fn export_policy_ignores_disabled_settings() {
+        let policy = RedactionPolicy::for_export(&json!({
+            "privacy": { "redaction": { "enabled": false, "pii": false } }
+        }));
+        let mut counts = BTreeMap::new();
+        let output = redact_text(
+            "mail taro@example.com",
+            &policy,
+            TrustLevel::Untrusted,
+            &mut counts,
+        );
+        assert_eq!(output, "mail [REDACTED_EMAIL]");
+    }
+
+    #[test]
+    fn trust_defaults_follow_the_endpoint_address() {
+        let policy = RedactionPolicy::from_config(&json!({
+            "privacy": { "redaction": { "provider_trust": { "openai": "partial" } } }
+        }));
+        assert_eq!(policy.trust_for("llama_cpp", None), TrustLevel::Trusted);
+        assert_eq!(
+            policy.trust_for("ollama", Some("http://localhost:11434")),
+            TrustLevel::Trusted
+        );
+        assert_eq!(
+            policy.trust_for("lmstudio", Some("http://192.168.0.5:1234")),
+            TrustLevel::Trusted
+        );
+        assert_eq!(
+            policy.trust_for("custom", Some("https://api.example.com/v1")),
+            TrustLevel::Untrusted
+        );
+        assert_eq!(
+            policy.trust_for("OpenAI", Some("https://api.openai.com/v1")),
+            TrustLevel::Partial
+        );
+    }
+
+    #[test]
+    fn request_report_covers_multimodal_text_parts() {
+        let mut request = ChatRequest::new(vec![
+            ChatMessage::new_text("system", "You are helpful."),
+            ChatMessage::new_multimodal("user", "contact taro@example.com", &[]),
+        ]);
+        let report = redact_request(
+            &mut request,
+            &RedactionPolicy::default(),
+            "openai",
+            TrustLevel::Untrusted,
+        );
+        assert_eq!(report.total, 2);
+        assert_eq!(request.messages[1].content, "contact [REDACTED_EMAIL]");
+        let parts = request.messages[1].multimodal_parts.as_ref().unwrap();
+        assert_eq!(parts[0]["text"], "contact [REDACTED_EMAIL]");
+    }
+}
@@ -1,28 +1,53 @@
-use reqwest::Client;
+use std::collections::BTreeMap;
+
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 use tokio::sync::mpsc;
 
 use crate::core::config::ConfigService;
 use crate::core::errors::ApiError;
+use crate::infrastructure::observability::latency;
+use crate::llm::cancel;
+use crate::llm::circuit_breaker::{is_transient, ProviderBreakers, ProviderHealth};
+use crate::llm::continuation::{
+    continuation_request, stitch, stopped_at_token_limit, OverlapTrimmer,
+};
 use crate::llm::external_loader_common::{
-    external_loader_request_timeout, external_loader_stream_idle_timeout,
-    process_terminate_timeout, stream_channel_buffer, stream_internal_buffer,
+    external_loader_request_timeout, external_loader_stream_idle_timeout, max_continuation_rounds,
+    process_terminate_timeout, provider_breaker_settings, provider_retry_policy,
+    stream_channel_buffer, stream_internal_buffer,
 };
-use crate::llm::llama_service::LlamaService;
+use crate::llm::gemini_provider::{GeminiProvider, GEMINI_LOADER};
+use crate::llm::http_pool::ProviderClients;
+use crate::llm::llama_service::{LlamaService, LLAMA_CPP_LOADER};
 use crate::llm::lmstudio_native_client;
-use crate::llm::model_resolution::{resolve_model_target, ModelExecutionTarget};
+use crate::llm::model_resolution::{
+    resolve_model_target, supports_native_tools, ModelExecutionTarget,
+};
 use crate::llm::ollama_native_client;
 use crate::llm::openai_compatible_client;
+use crate::llm::openai_compatible_provider::{
+    OpenAiCompatibleProvider, ProviderTimeouts, OPENAI_COMPATIBLE_LOADER,
+};
+use crate::llm::openrouter_provider::{OpenRouterProvider, OPENROUTER_LOADER};
+use crate::llm::outage::RECONNECT_DELAY;
+use crate::llm::provider::LlmProvider;
+use crate::llm::redaction::{
+    is_local_endpoint, redact_request, redact_text, RedactionPolicy, TrustLevel,
+};
+use crate::llm::tool_calls::decode_grammar_reply;
 use crate::llm::types::{ChatMessage, ChatRequest, NormalizedAssistantTurn, NormalizedStreamChunk};
 use crate::models::ModelManager;
 
+const PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
 #[derive(Clone)]
 pub struct LlmService {
     models: ModelManager,
     llama: LlamaService,
     config: ConfigService,
-    http: Client,
+    clients: ProviderClients,
+    breakers: ProviderBreakers,
 }
 
 impl LlmService {
@@ -30,29 +55,109 @@ impl LlmService {
         Self {
             models,
             llama,
+            clients: ProviderClients::new(config.clone()),
             config,
-            http: Client::new(),
+            breakers: ProviderBreakers::new(),
         }
     }
 
+    /// プロバイダーごとのブレーカーの状態。
+    pub fn provider_health(&self) -> Vec<ProviderHealth> {
+        self.breakers
+            .snapshot(&provider_breaker_settings(&self.config))
+    }
+
+    /// `ChatRequest::with_tools` でツールを渡し、`tool_calls` で受け取れるモデルか。
+    pub fn supports_native_tools(&self, model_id: &str) -> bool {
+        matches!(self.models.get_model(model_id), Ok(Some(model)) if supports_native_tools(&model))
+    }
+
     pub async fn chat(&self, request: ChatRequest, model_id: &str) -> Result<String, ApiError> {
         Ok(self.chat_normalized(request, model_id).await?.visible_text)
     }
 
+    /// 最大トークン数で止まったら `llm_manager.max_continuation_rounds` 回まで続きを書かせ、
+    /// ひとつの応答につないで返す。
     pub async fn chat_normalized(
         &self,
         request: ChatRequest,
         model_id: &str,
+    ) -> Result<NormalizedAssistantTurn, ApiError> {
+        let max_rounds = self.continuation_rounds(&request);
+        if max_rounds == 0 {
+            return self.chat_round(request, model_id).await;
+        }
+        let mut turn = self.chat_round(request.clone(), model_id).await?;
+        for round in 1..=max_rounds {
+            if !stopped_at_token_limit(turn.finish_reason.as_deref())
+                || turn.visible_text.trim().is_empty()
+            {
+                break;
+            }
+            tracing::info!(model_id = %model_id, round, "Continuing reply cut off at the token limit");
+            let next = match self
+                .chat_round(continuation_request(&request, &turn.visible_text), model_id)
+                .await
+            {
+                Ok(next) => next,
+                Err(err) => {
+                    tracing::warn!(
+                        "Continuation round failed, returning partial reply: {}",
+                        err
+                    );
+                    break;
+                }
+            };
+            let stitched = stitch(&turn.visible_text, &next.visible_text);
+            turn.visible_text.push_str(&stitched);
+            if !next.model_thinking.is_empty() {
+                if !turn.model_thinking.is_empty() {
+                    turn.model_thinking.push('\n');
+                }
+                turn.model_thinking.push_str(&next.model_thinking);
+            }
+            turn.finish_reason = next.finish_reason;
+            turn.usage = next.usage;
+        }
+        Ok(turn)
+    }
+
+    async fn chat_round(
+        &self,
+        request: ChatRequest,
+        model_id: &str,
     ) -> Result<NormalizedAssistantTurn, ApiError> {
         let request = normalize_request(request);
         let message_count = request.messages.len();
         let target = resolve_model_target(&self.models, &self.config, model_id, &request)?;
-        let result = match target {
+        let request = self.redact_for_target(request, &target);
+        let result = self
+            .with_retry(model_id, &target, || {
+                self.chat_on_target(&target, request.clone())
+            })
+            .await?;
+        trace_chat_usage(model_id, message_count, &result);
+        crate::graph::profiler::record_llm_usage(result.usage.as_ref());
+        Ok(result)
+    }
+
+    async fn chat_on_target(
+        &self,
+        target: &ModelExecutionTarget,
+        request: ChatRequest,
+    ) -> Result<NormalizedAssistantTurn, ApiError> {
+        match target {
             ModelExecutionTarget::LlamaCpp(config) => {
                 let timeout = process_terminate_timeout(&self.config);
-                self.llama
-                    .chat_normalized(&config, clone_messages(&request), timeout)
-                    .await
+                let turn = self
+                    .llama
+                    .chat_normalized(config, clone_messages(&request), timeout)
+                    .await;
+                if request.tools.is_empty() {
+                    turn
+                } else {
+                    turn.map(decode_grammar_reply)
+                }
             }
             ModelExecutionTarget::OpenAiCompatible {
                 loader,
@@ -62,9 +167,9 @@ impl LlmService {
                 let request_timeout = external_loader_request_timeout(&self.config);
                 if loader.eq_ignore_ascii_case("ollama") {
                     match ollama_native_client::chat(
-                        &self.http,
-                        &base_url,
-                        &model_name,
+                        &self.clients.for_loader(loader),
+                        base_url,
+                        model_name,
                         request.clone(),
                         request_timeout,
                     )
@@ -77,21 +182,22 @@ impl LlmService {
                                 err
                             );
                             openai_compatible_client::chat(
-                                &self.http,
-                                &loader,
-                                &base_url,
-                                &model_name,
+                                &self.clients.for_loader(loader),
+                                loader,
+                                base_url,
+                                model_name,
                                 request,
                                 request_timeout,
                             )
                             .await
                         }
                     }
-                } else if loader.eq_ignore_ascii_case("lmstudio") {
+                } else if loader.eq_ignore_ascii_case("lmstudio") && request.tools.is_empty() {
+                    // ネイティブ API には関数ツールの口が無いので、ツール付きは OpenAI 互換側へ
                     match lmstudio_native_client::chat(
-                        &self.http,
-                        &base_url,
-                        &model_name,
+                        &self.clients.for_loader(loader),
+                        base_url,
+                        model_name,
                         request.clone(),
                         request_timeout,
                     )
@@ -104,10 +210,10 @@ impl LlmService {
                                 err
                             );
                             openai_compatible_client::chat(
-                                &self.http,
-                                &loader,
-                                &base_url,
-                                &model_name,
+                                &self.clients.for_loader(loader),
+                                loader,
+                                base_url,
+                                model_name,
                                 request,
                                 request_timeout,
                             )
@@ -116,19 +222,26 @@ impl LlmService {
                     }
                 } else {
                     openai_compatible_client::chat(
-                        &self.http,
-                        &loader,
-                        &base_url,
-                        &model_name,
+                        &self.clients.for_loader(loader),
+                        loader,
+                        base_url,
+                        model_name,
                         request,
                         request_timeout,
                     )
                     .await
                 }
             }
-        }?;
-        trace_chat_usage(model_id, message_count, &result);
-        Ok(result)
+            ModelExecutionTarget::Cloud {
+                loader,
+                base_url,
+                model_name,
+            } => {
+                self.cloud_provider(loader, base_url)?
+                    .chat(request, model_name)
+                    .await
+            }
+        }
     }
 
     pub async fn stream_chat(
@@ -162,14 +275,217 @@ impl LlmService {
         &self,
         request: ChatRequest,
         model_id: &str,
+    ) -> Result<mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>>, ApiError> {
+        let request = cancel::attach(request);
+        let stream = self.open_continued_stream(request, model_id).await?;
+        let buffer = stream_channel_buffer(&self.config);
+        let stream = crate::graph::profiler::observe_stream(stream, buffer);
+        Ok(latency::observe_stream(stream, buffer))
+    }
+
+    /// 長さ制限で止まったストリームの後ろに続きのストリームをつなぐ。途中の `done` は
+    /// 伏せ、最後にまとめて一度だけ送る。
+    async fn open_continued_stream(
+        &self,
+        request: ChatRequest,
+        model_id: &str,
+    ) -> Result<mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>>, ApiError> {
+        let max_rounds = self.continuation_rounds(&request);
+        if max_rounds == 0 {
+            return self.open_normalized_stream(request, model_id).await;
+        }
+        let mut stream = self
+            .open_normalized_stream(request.clone(), model_id)
+            .await?;
+        let (tx, rx) = mpsc::channel(stream_channel_buffer(&self.config));
+        let service = self.clone();
+        let model_id = model_id.to_string();
+
+        tokio::spawn(async move {
+            let mut generated = String::new();
+            let mut trimmer: Option<OverlapTrimmer> = None;
+            let mut round = 0;
+            loop {
+                let mut finish_reason = None;
+                while let Some(item) = stream.recv().await {
+                    let mut chunk = match item {
+                        Ok(chunk) => chunk,
+                        Err(err) => {
+                            let _ = tx.send(Err(err)).await;
+                            return;
+                        }
+                    };
+                    if chunk.finish_reason.is_some() {
+                        finish_reason = chunk.finish_reason.take();
+                    }
+                    if let Some(trimmer) = trimmer.as_mut() {
+                        chunk.visible_text = trimmer.push(&chunk.visible_text);
+                    }
+                    generated.push_str(&chunk.visible_text);
+                    chunk.done = false;
+                    let empty = chunk.visible_text.is_empty()
+                        && chunk.model_thinking.is_empty()
+                        && chunk.usage.is_none();
+                    if !empty && tx.send(Ok(chunk)).await.is_err() {
+                        return;
+                    }
+                }
+                if let Some(held) = trimmer.as_mut().map(OverlapTrimmer::finish) {
+                    if !held.is_empty() {
+                        generated.push_str(&held);
+                        let chunk = NormalizedStreamChunk {
+                            visible_text: held,
+                            ..Default::default()
+                        };
+                        if tx.send(Ok(chunk)).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+
+                let next = if round < max_rounds
+                    && stopped_at_token_limit(finish_reason.as_deref())
+                    && !generated.trim().is_empty()
+                {
+                    round += 1;
+                    tracing::info!(model_id = %model_id, round, "Continuing stream cut off at the token limit");
+                    match service
+                        .open_normalized_stream(
+                            continuation_request(&request, &generated),
+                            &model_id,
+                        )
+                        .await
+                    {
+                        Ok(next) => Some(next),
+                        Err(err) => {
+                            tracing::warn!("Continuation round failed, ending stream: {}", err);
+                            None
+                        }
+                    }
+                } else {
+                    None
+                };
+                let Some(next) = next else {
+                    let _ = tx
+                        .send(Ok(NormalizedStreamChunk {
+                            done: true,
+                            finish_reason,
+                            ..Default::default()
+                        }))
+                        .await;
+                    return;
+                };
+                stream = next;
+                trimmer = Some(OverlapTrimmer::new(&generated));
+            }
+        });
+
+        Ok(rx)
+    }
+
+    /// 構造化出力とツール呼び出しは途中でつなぐと JSON が壊れるので続きを書かせない。
+    fn continuation_rounds(&self, request: &ChatRequest) -> usize {
+        if !request.continue_on_length
+            || request.structured_response.is_some()
+            || !request.tools.is_empty()
+        {
+            0
+        } else {
+            max_continuation_rounds(&self.config)
+        }
+    }
+
+    async fn open_normalized_stream(
+        &self,
+        request: ChatRequest,
+        model_id: &str,
     ) -> Result<mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>>, ApiError> {
         let request = normalize_request(request);
         let target = resolve_model_target(&self.models, &self.config, model_id, &request)?;
+        let request = self.redact_for_target(request, &target);
+        self.with_retry(model_id, &target, || {
+            self.open_stream_on_target(&target, request.clone())
+        })
+        .await
+    }
+
+    /// 一時的な失敗なら `llm_manager.retry_max_attempts` 回まで送り直し、結果を
+    /// プロバイダーのブレーカーに記録する。送信を止めているプロバイダーには送らない。
+    async fn with_retry<T, F, Fut>(
+        &self,
+        model_id: &str,
+        target: &ModelExecutionTarget,
+        mut send: F,
+    ) -> Result<T, ApiError>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, ApiError>>,
+    {
+        let provider = target.provider_name();
+        let policy = provider_retry_policy(&self.config);
+        let settings = provider_breaker_settings(&self.config);
+        let mut attempt = 0;
+        loop {
+            self.breakers.admit(provider, &settings)?;
+            let err = match send().await {
+                Ok(value) => {
+                    self.breakers.record_success(provider);
+                    return Ok(value);
+                }
+                Err(err) if !is_transient(&err) => {
+                    // 中身を見て断ったのなら、プロバイダー自体は応答している
+                    self.breakers.record_success(provider);
+                    return Err(err);
+                }
+                Err(err) => err,
+            };
+            self.breakers.record_failure(provider, &err, &settings);
+            attempt += 1;
+            if attempt >= policy.max_attempts || self.breakers.is_open(provider, &settings) {
+                return Err(err);
+            }
+            self.prepare_retry(model_id, &err, policy.backoff(attempt - 1))
+                .await;
+        }
+    }
+
+    /// 送り直す前の後始末。繋がらなかった llama-server は落ちたプロセスを片付けて
+    /// 次の呼び出しで起動し直させ、それ以外は待つ。外部のサーバーには起動直後の
+    /// 猶予として少なくとも `RECONNECT_DELAY` 待つ。
+    async fn prepare_retry(&self, model_id: &str, err: &ApiError, backoff: std::time::Duration) {
+        tracing::warn!(model_id = %model_id, "{}; retrying", err);
+        match err {
+            ApiError::ProviderUnavailable { provider, .. } if provider == LLAMA_CPP_LOADER => {
+                let timeout = process_terminate_timeout(&self.config);
+                if let Err(stop_err) = self.llama.stop(timeout).await {
+                    tracing::warn!(
+                        "Failed to clean up llama-server before restart: {}",
+                        stop_err
+                    );
+                }
+            }
+            ApiError::ProviderUnavailable { .. } => {
+                tokio::time::sleep(backoff.max(RECONNECT_DELAY)).await
+            }
+            _ => tokio::time::sleep(backoff).await,
+        }
+    }
+
+    async fn open_stream_on_target(
+        &self,
+        target: &ModelExecutionTarget,
+        request: ChatRequest,
+    ) -> Result<mpsc::Receiver<Result<NormalizedStreamChunk, ApiError>>, ApiError> {
         match target {
             ModelExecutionTarget::LlamaCpp(config) => {
                 let timeout = process_terminate_timeout(&self.config);
                 self.llama
-                    .stream_chat_normalized(&config, clone_messages(&request), timeout)
+                    .stream_chat_normalized(
+                        config,
+                        clone_messages(&request),
+                        timeout,
+                        request.cancellation(),
+                    )
                     .await
             }
             ModelExecutionTarget::OpenAiCompatible {
@@ -182,9 +498,9 @@ impl LlmService {
                 let internal_buffer = stream_internal_buffer(&self.config);
                 if loader.eq_ignore_ascii_case("ollama") {
                     match ollama_native_client::stream_chat(
-                        &self.http,
-                        &base_url,
-                        &model_name,
+                        &self.clients.for_loader(loader),
+                        base_url,
+                        model_name,
                         request.clone(),
                         request_timeout,
                         stream_idle_timeout,
@@ -199,10 +515,10 @@ impl LlmService {
                                 err
                             );
                             openai_compatible_client::stream_chat(
-                                &self.http,
-                                &loader,
-                                &base_url,
-                                &model_name,
+                                &self.clients.for_loader(loader),
+                                loader,
+                                base_url,
+                                model_name,
                                 request,
                                 request_timeout,
                                 stream_idle_timeout,
@@ -213,9 +529,9 @@ impl LlmService {
                     }
                 } else if loader.eq_ignore_ascii_case("lmstudio") {
                     match lmstudio_native_client::stream_chat(
-                        &self.http,
-                        &base_url,
-                        &model_name,
+                        &self.clients.for_loader(loader),
+                        base_url,
+                        model_name,
                         request.clone(),
                         request_timeout,
                         stream_idle_timeout,
@@ -230,10 +546,10 @@ impl LlmService {
                                 err
                             );
                             openai_compatible_client::stream_chat(
-                                &self.http,
-                                &loader,
-                                &base_url,
-                                &model_name,
+                                &self.clients.for_loader(loader),
+                                loader,
+                                base_url,
+                                model_name,
                                 request,
                                 request_timeout,
                                 stream_idle_timeout,
@@ -244,10 +560,10 @@ impl LlmService {
                     }
                 } else {
                     openai_compatible_client::stream_chat(
-                        &self.http,
-                        &loader,
-                        &base_url,
-                        &model_name,
+                        &self.clients.for_loader(loader),
+                        loader,
+                        base_url,
+                        model_name,
                         request,
                         request_timeout,
                         stream_idle_timeout,
@@ -256,6 +572,15 @@ impl LlmService {
                     .await
                 }
             }
+            ModelExecutionTarget::Cloud {
+                loader,
+                base_url,
+                model_name,
+            } => {
+                self.cloud_provider(loader, base_url)?
+                    .stream_chat(request, model_name)
+                    .await
+            }
         }
     }
 
@@ -270,10 +595,20 @@ impl LlmService {
             model_id,
             &ChatRequest::new(vec![]),
         )?;
+        let inputs = self.redact_inputs_for_target(inputs, &target);
+        self.with_retry(model_id, &target, || self.embed_on_target(&target, &inputs))
+            .await
+    }
+
+    async fn embed_on_target(
+        &self,
+        target: &ModelExecutionTarget,
+        inputs: &[String],
+    ) -> Result<Vec<Vec<f32>>, ApiError> {
         match target {
             ModelExecutionTarget::LlamaCpp(config) => {
                 let timeout = process_terminate_timeout(&self.config);
-                self.llama.embed(&config, inputs, timeout).await
+                self.llama.embed(config, inputs, timeout).await
             }
             ModelExecutionTarget::OpenAiCompatible {
                 loader,
@@ -282,16 +617,165 @@ impl LlmService {
             } => {
                 let request_timeout = external_loader_request_timeout(&self.config);
                 openai_compatible_client::embed(
-                    &self.http,
-                    &loader,
-                    &base_url,
-                    &model_name,
+                    &self.clients.for_loader(loader),
+                    loader,
+                    base_url,
+                    model_name,
                     inputs,
                     request_timeout,
                 )
                 .await
             }
+            ModelExecutionTarget::Cloud {
+                loader,
+                base_url,
+                model_name,
+            } => {
+                self.cloud_provider(loader, base_url)?
+                    .embed(inputs, model_name)
+                    .await
+            }
+        }
+    }
+
+    /// `batch_size` 件ずつ `embed` に渡し、入力と同じ順で返す。
+    /// 一度に大量の入力を送ってサーバーやプロバイダーの上限に当たるのを避ける
+    pub async fn embed_batched(
+        &self,
+        inputs: &[String],
+        model_id: &str,
+        batch_size: usize,
+    ) -> Result<Vec<Vec<f32>>, ApiError> {
+        let mut embeddings = Vec::with_capacity(inputs.len());
+        for batch in inputs.chunks(batch_size.max(1)) {
+            let vectors = self.embed(batch, model_id).await?;
+            if vectors.len() != batch.len() {
+                return Err(ApiError::internal(format!(
+                    "Embedding model returned {} vectors for {} inputs",
+                    vectors.len(),
+                    batch.len()
+                )));
+            }
+            embeddings.extend(vectors);
+        }
+        Ok(embeddings)
+    }
+
+    /// クラウドのローダー名に対応する `LlmProvider`。API キーなどは毎回設定から読む。
+    fn cloud_provider(
+        &self,
+        loader: &str,
+        base_url: &str,
+    ) -> Result<Box<dyn LlmProvider>, ApiError> {
+        let typed = self.config.load_typed().unwrap_or_default();
+        let api_key = typed.loader_api_key(loader);
+        let timeouts = ProviderTimeouts {
+            request: external_loader_request_timeout(&self.config),
+            stream_idle: external_loader_stream_idle_timeout(&self.config),
+            stream_buffer: stream_internal_buffer(&self.config),
+        };
+        let http = self.clients.for_cloud(loader, api_key.as_deref());
+        match loader {
+            OPENAI_COMPATIBLE_LOADER => Ok(Box::new(OpenAiCompatibleProvider::new(
+                http, base_url, timeouts,
+            ))),
+            GEMINI_LOADER => Ok(Box::new(GeminiProvider::new(
+                http,
+                base_url,
+                timeouts,
+                typed.loader_safety_settings(loader),
+            ))),
+            OPENROUTER_LOADER => Ok(Box::new(OpenRouterProvider::new(http, base_url, timeouts))),
+            other => Err(ApiError::BadRequest(format!(
+                "Unknown cloud provider '{}'",
+                other
+            ))),
+        }
+    }
+
+    /// 送信先に適用する伏せ字の設定と、そのローダー名・信頼度。
+    fn redaction_for_target<'a>(
+        &self,
+        target: &'a ModelExecutionTarget,
+    ) -> (RedactionPolicy, &'a str, TrustLevel) {
+        let config = self.config.load_config().unwrap_or(Value::Null);
+        let policy = RedactionPolicy::from_config(&config);
+        let (provider, trust) = match target {
+            ModelExecutionTarget::LlamaCpp(_) => ("llama_cpp", policy.trust_for("llama_cpp", None)),
+            ModelExecutionTarget::OpenAiCompatible {
+                loader, base_url, ..
+            }
+            | ModelExecutionTarget::Cloud {
+                loader, base_url, ..
+            } => (
+                loader.as_str(),
+                policy.trust_for(loader, Some(base_url.as_str())),
+            ),
+        };
+        (policy, provider, trust)
+    }
+
+    /// 送信先の信頼度に応じてメッセージを伏せ字にし、レポートを `redaction_sink` に渡す。
+    fn redact_for_target(
+        &self,
+        mut request: ChatRequest,
+        target: &ModelExecutionTarget,
+    ) -> ChatRequest {
+        let (policy, provider, trust) = self.redaction_for_target(target);
+        let report = redact_request(&mut request, &policy, provider, trust);
+        if report.total > 0 {
+            tracing::info!(
+                provider,
+                redacted = report.total,
+                "Redacted sensitive content before sending prompt"
+            );
+        }
+        if let Some(sink) = &request.redaction_sink {
+            *sink.lock().unwrap_or_else(|e| e.into_inner()) = Some(report);
         }
+        request
+    }
+
+    /// 埋め込みの入力（RAG のチャンクや記憶の本文）もチャットと同じ規則で伏せ字にする。
+    fn redact_inputs_for_target(
+        &self,
+        inputs: &[String],
+        target: &ModelExecutionTarget,
+    ) -> Vec<String> {
+        let (policy, provider, trust) = self.redaction_for_target(target);
+        let mut counts = BTreeMap::new();
+        let redacted = inputs
+            .iter()
+            .map(|input| redact_text(input, &policy, trust, &mut counts))
+            .collect();
+        let total: usize = counts.values().sum();
+        if total > 0 {
+            tracing::info!(
+                provider,
+                redacted = total,
+                "Redacted sensitive content before sending embedding inputs"
+            );
+        }
+        redacted
+    }
+
+    /// モデルの送信先ローダー名と、それがこのマシン（LAN）の外かどうか。
+    pub fn provider_for(&self, model_id: &str) -> Result<(String, bool), ApiError> {
+        let request = ChatRequest::new(vec![]);
+        Ok(
+            match resolve_model_target(&self.models, &self.config, model_id, &request)? {
+                ModelExecutionTarget::LlamaCpp(_) => ("llama_cpp".to_string(), false),
+                ModelExecutionTarget::OpenAiCompatible {
+                    loader, base_url, ..
+                }
+                | ModelExecutionTarget::Cloud {
+                    loader, base_url, ..
+                } => {
+                    let remote = !is_local_endpoint(&base_url);
+                    (loader, remote)
+                }
+            },
+        )
     }
 
     pub async fn get_logprobs(
@@ -313,7 +797,7 @@ impl LlmService {
             } => {
                 let request_timeout = external_loader_request_timeout(&self.config);
                 openai_compatible_client::get_logprobs(
-                    &self.http,
+                    &self.clients.for_loader(&loader),
                     &loader,
                     &base_url,
                     &model_name,
@@ -322,6 +806,83 @@ impl LlmService {
                 )
                 .await
             }
+            // クラウド API の多くは `echo` 付きの logprobs を返さない
+            ModelExecutionTarget::Cloud { loader, .. } => Err(ApiError::BadRequest(format!(
+                "{} does not provide prompt logprobs",
+                loader
+            ))),
+        }
+    }
+
+    /// Start llama-server with `model_id` ahead of the first request.
+    /// Returns false for external loaders, which manage residency themselves.
+    pub async fn preload(&self, model_id: &str) -> Result<bool, ApiError> {
+        let request = ChatRequest::new(vec![]);
+        match resolve_model_target(&self.models, &self.config, model_id, &request)? {
+            ModelExecutionTarget::LlamaCpp(config) => {
+                let timeout = process_terminate_timeout(&self.config);
+                self.llama.ensure_running(&config, timeout).await?;
+                Ok(true)
+            }
+            ModelExecutionTarget::OpenAiCompatible { .. } | ModelExecutionTarget::Cloud { .. } => {
+                Ok(false)
+            }
+        }
+    }
+
+    /// Check that `model_id` can serve requests without loading it.
+    /// llama.cpp models are started on demand, so only the weights file is checked;
+    /// external loaders must answer on their model listing endpoint.
+    pub async fn probe_model(&self, model_id: &str) -> Result<String, ApiError> {
+        let request = ChatRequest::new(vec![]);
+        match resolve_model_target(&self.models, &self.config, model_id, &request)? {
+            ModelExecutionTarget::LlamaCpp(config) => {
+                if !config.model_path.is_file() {
+                    return Err(ApiError::internal(format!(
+                        "Model file is missing: {}",
+                        config.model_path.display()
+                    )));
+                }
+                if self.llama.loaded_model_id().await.as_deref() == Some(&config.model_key) {
+                    Ok("loaded in llama-server".to_string())
+                } else {
+                    Ok("llama-server starts on demand".to_string())
+                }
+            }
+            ModelExecutionTarget::OpenAiCompatible {
+                loader, base_url, ..
+            } => {
+                let endpoint = format!("{}/v1/models", base_url.trim_end_matches('/'));
+                let response = self
+                    .clients
+                    .for_loader(&loader)
+                    .get(&endpoint)?
+                    .timeout(PROBE_TIMEOUT)
+                    .send()
+                    .await
+                    .map_err(|err| {
+                        ApiError::internal(format!("{loader} is unreachable at {base_url}: {err}"))
+                    })?;
+                if !response.status().is_success() {
+                    return Err(ApiError::internal(format!(
+                        "{loader} at {base_url} answered {}",
+                        response.status()
+                    )));
+                }
+                Ok(format!("{loader} reachable at {base_url}"))
+            }
+            ModelExecutionTarget::Cloud {
+                loader, base_url, ..
+            } => {
+                let provider = self.cloud_provider(&loader, &base_url)?;
+                if !provider.health_check().await? {
+                    return Err(ApiError::internal(format!(
+                        "{} at {base_url} rejected the model listing request (check the API key)",
+                        provider.name()
+                    )));
+                }
+                Ok(format!("{} reachable at {base_url}", provider.name()))
+            }
         }
     }
 
@@ -518,4 +1079,30 @@ mod tests {
 
         assert_eq!(parsed, vec!["alpha".to_string(), "beta".to_string()]);
     }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn embedding_inputs_are_redacted_for_cloud_targets() {
+        use crate::test_support::{init_state_with_config, ENV_LOCK};
+
+        let _lock = ENV_LOCK.lock();
+        let (_sandbox, _env_guard, state) = init_state_with_config("{}\n").await;
+        let llm = &state.ai().llm;
+        let inputs = vec!["key sk-abcdefghijklmnopqrstuvwx in /home/taro/notes.md".to_string()];
+
+        let cloud = ModelExecutionTarget::Cloud {
+            loader: OPENAI_COMPATIBLE_LOADER.to_string(),
+            base_url: "https://api.example.com/v1".to_string(),
+            model_name: "text-embedding-3-small".to_string(),
+        };
+        let redacted = llm.redact_inputs_for_target(&inputs, &cloud);
+        assert_eq!(redacted, ["key [REDACTED_SECRET] in [USER_DIR]/notes.md"]);
+
+        let local = ModelExecutionTarget::OpenAiCompatible {
+            loader: "ollama".to_string(),
+            base_url: "http://127.0.0.1:11434".to_string(),
+            model_name: "nomic-embed-text".to_string(),
+        };
+        assert_eq!(llm.redact_inputs_for_target(&inputs, &local), inputs);
+    }
 }
@@ -0,0 +1,224 @@
+//! ストリーミング応答のフレーミング層。
+//!
+//! TCP の読み取り単位は行や JSON の境界とも UTF-8 の文字境界とも一致しない。
+//! バイト列のまま改行まで溜め、完結した行だけを文字列に戻すことで、
+//! 長い応答でチャンクが欠けたり文字化けしたりしないようにする。
+//! NDJSON (Ollama) は [`LineFramer`]、SSE (OpenAI 互換 / LM Studio / llama.cpp)
+//! は [`SseFramer`] を使う。
+
+/// 改行区切りのフレーマ。`\r\n` も受け付ける。
+#[derive(Debug, Default)]
+pub(crate) struct LineFramer {
+    pending: Vec<u8>,
+}
+
+impl LineFramer {
+    /// 読み取ったバイト列を追加し、完結した行を返す。空行も返す。
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(bytes);
+        let Some(last_newline) = self.pending.iter().rposition(|b| *b == b'\n') else {
+            return Vec::new();
+        };
+        // `\n` は UTF-8 のマルチバイト文字の途中には現れないので、ここで切れば
+        // 文字が分断されることはない
+        let rest = self.pending.split_off(last_newline + 1);
+        let complete = std::mem::replace(&mut self.pending, rest);
+        complete[..last_newline]
+            .split(|b| *b == b'\n')
+            .map(decode_line)
+            .collect()
+    }
+
+    /// ストリーム終端で、改行で終わらなかった最後の行を取り出す。
+    pub(crate) fn finish(&mut self) -> Option<String> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let line = decode_line(&self.pending);
+        self.pending.clear();
+        Some(line)
+    }
+}
+
+fn decode_line(line: &[u8]) -> String {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    String::from_utf8_lossy(line).into_owned()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SseEvent {
+    /// `event:` フィールド。省略時は `None`。
+    pub event: Option<String>,
+    /// `data:` フィールド。複数行は `\n` で連結する。
+    pub data: String,
+}
+
+/// Server-Sent Events のフレーマ。空行でイベントを確定する。
+#[derive(Debug, Default)]
+pub(crate) struct SseFramer {
+    lines: LineFramer,
+    event: Option<String>,
+    data: Option<String>,
+}
+
+impl SseFramer {
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        for line in self.lines.push(bytes) {
+            if let Some(event) = self.feed_line(&line) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// ストリーム終端で、空行で閉じられなかった最後のイベントを取り出す。
+    pub(crate) fn finish(&mut self) -> Option<SseEvent> {
+        if let Some(line) = self.lines.finish() {
+            if let Some(event) = self.feed_line(&line) {
+                return Some(event);
+            }
+        }
+        self.dispatch()
+    }
+
+    fn feed_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        // data のないイベントは仕様上捨てる
+        let data = self.data.take()?;
+        Some(SseEvent { event, data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn split_randomly(payload: &[u8], rng: &mut StdRng) -> Vec<Vec<u8>> {
+        let mut chunks = Vec::new();
+        let mut rest = payload;
+        while !rest.is_empty() {
+            let len = rng.random_range(1..=rest.len().min(17));
+            chunks.push(rest[..len].to_vec());
+            rest = &rest[len..];
+        }
+        chunks
+    }
+
+    fn collect_sse(chunks: &[Vec<u8>]) -> Vec<SseEvent> {
+        let mut framer = SseFramer::default();
+        let mut events: Vec<SseEvent> = chunks.iter().flat_map(|c| framer.push(c)).collect();
+        events.extend(framer.finish());
+        events
+    }
+
+    fn collect_lines(chunks: &[Vec<u8>]) -> Vec<String> {
+        let mut framer = LineFramer::default();
+        let mut lines: Vec<String> = chunks.iter().flat_map(|c| framer.push(c)).collect();
+        lines.extend(framer.finish());
+        lines
+    }
+
+    #[test]
+    fn sse_framer_handles_fields_comments_and_crlf() {
+        let payload = b": keep-alive\r\nevent: message.delta\r\ndata: {\"a\":1}\r\n\r\n\
+data:line1\ndata: line2\n\nevent: chat.end\n\ndata: [DONE]";
+        let events = collect_sse(&[payload.to_vec()]);
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: Some("message.delta".to_string()),
+                    data: "{\"a\":1}".to_string(),
+                },
+                SseEvent {
+                    event: None,
+                    data: "line1\nline2".to_string(),
+                },
+                SseEvent {
+                    event: None,
+                    data: "[DONE]".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn line_framer_keeps_unterminated_tail_until_finish() {
+        let mut framer = LineFramer::default();
+        assert!(framer.push(b"{\"a\":").is_empty());
+        assert_eq!(framer.push(b"1}\n{\"b\""), vec!["{\"a\":1}".to_string()]);
+        assert_eq!(framer.finish(), Some("{\"b\"".to_string()));
+        assert_eq!(framer.finish(), None);
+    }
+
+    #[test]
+    fn fuzz_random_splits_match_unsplit_parse() {
+        let mut rng = StdRng::seed_from_u64(0x2961);
+        let texts = [
+            "こんにちは",
+            "naïve café",
+            "emoji 🎉🚀",
+            "plain",
+            "改行\\nあり",
+        ];
+
+        for _ in 0..300 {
+            let mut sse = Vec::new();
+            let mut ndjson = Vec::new();
+            for _ in 0..rng.random_range(1..12) {
+                let text = texts[rng.random_range(0..texts.len())];
+                let newline: &[u8] = if rng.random_bool(0.3) { b"\r\n" } else { b"\n" };
+                let json = format!("{{\"content\":\"{text}\"}}");
+                sse.extend_from_slice(b"data: ");
+                sse.extend_from_slice(json.as_bytes());
+                sse.extend_from_slice(newline);
+                sse.extend_from_slice(newline);
+                ndjson.extend_from_slice(json.as_bytes());
+                ndjson.extend_from_slice(newline);
+            }
+
+            let expected_events = collect_sse(&[sse.clone()]);
+            let events = collect_sse(&split_randomly(&sse, &mut rng));
+            assert_eq!(events, expected_events);
+            for event in &events {
+                let value: serde_json::Value = serde_json::from_str(&event.data).unwrap();
+                assert!(value["content"].is_string());
+            }
+
+            let expected_lines = collect_lines(&[ndjson.clone()]);
+            assert_eq!(
+                collect_lines(&split_randomly(&ndjson, &mut rng)),
+                expected_lines
+            );
+            assert!(expected_lines.iter().all(|line| !line.contains('\u{FFFD}')));
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! ツール呼び出しの受け渡し。
+//!
+//! OpenAI 互換 API と Ollama は `tools` を受け取り `message.tool_calls` で返すので、
+//! その形に揃えて送り、返ってきた呼び出しを `ToolCall` に直す。llama.cpp には
+//! ツールの口が無いため、「どれか 1 つのツールを呼ぶ」か「答える」かの JSON Schema を
+//! 文法として渡し、生成された JSON を同じ `ToolCall` に読み替える。
+
+use serde_json::{json, Value};
+
+use crate::llm::types::{NormalizedAssistantTurn, ToolCall, ToolSpec};
+
+/// 文法経由の呼び出しで最終回答を入れるキー
+const GRAMMAR_ANSWER_KEY: &str = "answer";
+
+/// OpenAI 形式の `tools` 配列（Ollama も同じ形を受け付ける）。
+pub(crate) fn openai_tools_value(tools: &[ToolSpec]) -> Value {
+    Value::Array(
+        tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "type": "function",
+                    "function": {
+                        "name": tool.name,
+                        "description": tool.description,
+                        "parameters": object_schema(&tool.parameters),
+                    }
+                })
+            })
+            .collect(),
+    )
+}
+
+/// `message.tool_calls[]` を読む。OpenAI は `arguments` が JSON 文字列、Ollama は
+/// オブジェクトで返す。名前の無いものは捨て、ID が無ければ順番で振る。
+pub(crate) fn parse_tool_calls(message: &Value) -> Vec<ToolCall> {
+    message
+        .get("tool_calls")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|call| {
+            let function = call.get("function").unwrap_or(call);
+            let name = function.get("name").and_then(Value::as_str)?.trim();
+            if name.is_empty() {
+                return None;
+            }
+            Some((name.to_string(), call, function))
+        })
+        .enumerate()
+        .map(|(index, (name, call, function))| ToolCall {
+            id: call
+                .get("id")
+                .and_then(Value::as_str)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("call_{}", index)),
+            name,
+            arguments: parse_arguments(function.get("arguments")),
+        })
+        .collect()
+}
+
+/// llama.cpp の `json_schema` に渡す、ツール呼び出しか回答のどちらかを強制するスキーマ。
+pub(crate) fn grammar_schema(tools: &[ToolSpec]) -> Value {
+    let mut choices: Vec<Value> = tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "type": "object",
+                "properties": {
+                    "tool": { "const": tool.name },
+                    "arguments": object_schema(&tool.parameters),
+                },
+                "required": ["tool", "arguments"],
+            })
+        })
+        .collect();
+    choices.push(json!({
+        "type": "object",
+        "properties": { GRAMMAR_ANSWER_KEY: { "type": "string" } },
+        "required": [GRAMMAR_ANSWER_KEY],
+    }));
+    json!({ "oneOf": choices })
+}
+
+/// 文法で縛った生成結果を読み替える。ツールなら `tool_calls` へ移して本文を空にし、
+/// 回答なら本文を中身だけにする。JSON として読めなければそのまま返す。
+pub(crate) fn decode_grammar_reply(mut turn: NormalizedAssistantTurn) -> NormalizedAssistantTurn {
+    let Ok(Value::Object(reply)) = serde_json::from_str::<Value>(turn.visible_text.trim()) else {
+        return turn;
+    };
+    if let Some(name) = reply.get("tool").and_then(Value::as_str) {
+        turn.tool_calls = vec![ToolCall {
+            id: "call_0".to_string(),
+            name: name.to_string(),
+            arguments: parse_arguments(reply.get("arguments")),
+        }];
+        turn.visible_text.clear();
+        turn.finish_reason = Some("tool_calls".to_string());
+    } else if let Some(answer) = reply.get(GRAMMAR_ANSWER_KEY).and_then(Value::as_str) {
+        turn.visible_text = answer.to_string();
+    }
+    turn
+}
+
+fn parse_arguments(raw: Option<&Value>) -> Value {
+    match raw {
+        Some(Value::String(text)) if text.trim().is_empty() => json!({}),
+        Some(Value::String(text)) => match serde_json::from_str::<Value>(text) {
+            Ok(value @ Value::Object(_)) => value,
+            _ => json!({ "input": text }),
+        },
+        Some(value @ Value::Object(_)) => value.clone(),
+        _ => json!({}),
+    }
+}
+
+/// 引数の無いツールでも `parameters` はオブジェクトのスキーマにしておく。
+fn object_schema(parameters: &Value) -> Value {
+    if parameters.is_object() {
+        parameters.clone()
+    } else {
+        json!({ "type": "object", "properties": {} })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_openai_and_ollama_tool_calls() {
+        let openai = parse_tool_calls(&json!({
+            "content": null,
+            "tool_calls": [{
+                "id": "call_abc",
+                "type": "function",
+                "function": { "name": "web_fetch", "arguments": "{\"url\":\"https://example.com\"}" }
+            }]
+        }));
+        assert_eq!(
+            openai,
+            vec![ToolCall {
+                id: "call_abc".to_string(),
+                name: "web_fetch".to_string(),
+                arguments: json!({ "url": "https://example.com" }),
+            }]
+        );
+
+        let ollama = parse_tool_calls(&json!({
+            "tool_calls": [
+                { "function": { "name": "" } },
+                { "function": { "name": "search", "arguments": { "query": "rust" } } }
+            ]
+        }));
+        assert_eq!(ollama.len(), 1);
+        assert_eq!(ollama[0].id, "call_0");
+        assert_eq!(ollama[0].arguments, json!({ "query": "rust" }));
+    }
+
+    #[test]
+    fn grammar_reply_becomes_a_tool_call_or_an_answer() {
+        let tools = vec![ToolSpec {
+            name: "search".to_string(),
+            description: "Search the web".to_string(),
+            parameters: json!({ "type": "object", "properties": { "query": { "type": "string" } } }),
+        }];
+        let schema = grammar_schema(&tools);
+        assert_eq!(schema["oneOf"].as_array().unwrap().len(), 2);
+        assert_eq!(schema["oneOf"][0]["properties"]["tool"]["const"], "search");
+
+        let call = decode_grammar_reply(NormalizedAssistantTurn {
+            visible_text: r#"{"tool":"search","arguments":{"query":"rust"}}"#.to_string(),
+            ..Default::default()
+        });
+        assert!(call.visible_text.is_empty());
+        assert_eq!(call.finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(call.tool_calls[0].name, "search");
+        assert_eq!(call.tool_calls[0].arguments, json!({ "query": "rust" }));
+
+        let answer = decode_grammar_reply(NormalizedAssistantTurn {
+            visible_text: r#"{"answer":"Done."}"#.to_string(),
+            ..Default::default()
+        });
+        assert_eq!(answer.visible_text, "Done.");
+        assert!(answer.tool_calls.is_empty());
+    }
+}
@@ -1,5 +1,10 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+
+use crate::core::config::schema::ModelPricing;
+use crate::models::types::ModelCapabilities;
 
 /// 画像データ（Base64エンコード済み）
 #[derive(Debug, Clone)]
@@ -123,6 +128,23 @@ pub struct StructuredResponseSpec {
     pub description: Option<String>,
 }
 
+/// モデルに渡すツール定義。`parameters` は引数の JSON Schema。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolSpec {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+/// モデルが返したツール呼び出し。`arguments` は JSON オブジェクトに直してある。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
 #[derive(Debug, Clone)]
 pub struct ChatRequest {
     pub messages: Vec<ChatMessage>,
@@ -149,10 +171,20 @@ pub struct ChatRequest {
     // --- llama.cpp specific ---
     pub n_keep: Option<i32>,
     pub cache_prompt: Option<bool>,
+    /// Conversation key used to pin requests to a llama-server slot.
+    pub cache_key: Option<String>,
     // --- Ollama specific ---
     pub num_ctx: Option<i32>,
     // --- Structured outputs ---
     pub structured_response: Option<StructuredResponseSpec>,
+    /// 呼び出してよいツール。空ならツール無しの通常の会話（非ストリームのみ対応）
+    pub tools: Vec<ToolSpec>,
+    /// クラウド送信時の伏せ字レポートの受け取り先（ラン・トレース用）
+    pub redaction_sink: Option<crate::llm::redaction::RedactionSink>,
+    /// 最大トークン数で止まったとき続きを書かせるか。長さを意図して絞る要約などでは切る
+    pub continue_on_length: bool,
+    /// 取り消されたらストリームを閉じ、プロバイダー側の生成も止めさせる
+    pub cancel: Option<CancellationToken>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -162,12 +194,46 @@ pub struct TokenUsage {
     pub total_tokens: Option<usize>,
     pub cached_prompt_tokens: Option<usize>,
 }
+
+/// 1 回の応答で使ったトークン数。プロバイダーが返さなかった値は見積もりで埋め、
+/// そのときは `estimated` を立てる。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TurnUsage {
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+    pub total_tokens: usize,
+    pub estimated: bool,
+}
+
+impl TurnUsage {
+    pub fn resolve(
+        reported: Option<&TokenUsage>,
+        estimate_prompt: impl FnOnce() -> usize,
+        estimate_completion: impl FnOnce() -> usize,
+    ) -> Self {
+        let prompt = reported.and_then(|usage| usage.prompt_tokens);
+        let completion = reported.and_then(|usage| usage.completion_tokens);
+        let estimated = prompt.is_none() || completion.is_none();
+        let prompt_tokens = prompt.unwrap_or_else(estimate_prompt);
+        let completion_tokens = completion.unwrap_or_else(estimate_completion);
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            estimated,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NormalizedAssistantTurn {
     pub visible_text: String,
     pub model_thinking: String,
     pub finish_reason: Option<String>,
     pub usage: Option<TokenUsage>,
+    /// `ChatRequest::tools` を渡したときにモデルが選んだ呼び出し
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCall>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -176,6 +242,9 @@ pub struct NormalizedStreamChunk {
     pub model_thinking: String,
     pub done: bool,
     pub usage: Option<TokenUsage>,
+    /// 生成を止めた理由（プロバイダーの表記のまま）。最後のチャンクにだけ入る
+    #[serde(default)]
+    pub finish_reason: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -184,6 +253,9 @@ pub struct ProviderModel {
     pub id: String,
     pub name: String,
     pub ctx: u64,
+    /// 一覧に料金が載っているプロバイダー（OpenRouter）だけ
+    pub pricing: Option<ModelPricing>,
+    pub capabilities: Option<ModelCapabilities>,
 }
 
 impl ChatRequest {
@@ -209,8 +281,13 @@ impl ChatRequest {
             penalize_nl: None,
             n_keep: None,
             cache_prompt: None,
+            cache_key: None,
             num_ctx: None,
             structured_response: None,
+            tools: Vec::new(),
+            redaction_sink: None,
+            continue_on_length: true,
+            cancel: None,
         }
     }
 
@@ -219,6 +296,36 @@ impl ChatRequest {
         self
     }
 
+    pub fn with_tools(mut self, tools: Vec<ToolSpec>) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    pub fn with_redaction_sink(mut self, sink: crate::llm::redaction::RedactionSink) -> Self {
+        self.redaction_sink = Some(sink);
+        self
+    }
+
+    pub fn without_continuation(mut self) -> Self {
+        self.continue_on_length = false;
+        self
+    }
+
+    pub fn with_cancel(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// 取り消し用のトークン。指定が無ければ取り消されないトークンを返す。
+    pub(crate) fn cancellation(&self) -> CancellationToken {
+        self.cancel.clone().unwrap_or_default()
+    }
+
+    pub fn with_cache_key(mut self, key: impl Into<String>) -> Self {
+        self.cache_key = Some(key.into());
+        self
+    }
+
     pub fn with_config(mut self, config: &serde_json::Value) -> Self {
         if let Some(defaults) = config.get("llm_defaults") {
             self.apply_sampling_config(defaults);
@@ -233,6 +340,15 @@ impl ChatRequest {
             self.apply_sampling_config(cfg);
         }
 
+        // Session defaults and the message itself take precedence over the model
+        if let Some(temperature) = config
+            .get("session")
+            .and_then(|session| session.get("temperature"))
+            .and_then(|v| v.as_f64())
+        {
+            self.temperature = Some(temperature);
+        }
+
         self
     }
 
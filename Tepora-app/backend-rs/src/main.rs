@@ -21,8 +21,10 @@ mod mcp;
 #[path = "infrastructure/episodic_store/memory/mod.rs"]
 mod memory;
 mod models;
+mod plugins;
 #[path = "infrastructure/knowledge_store/rag/mod.rs"]
 mod rag;
+mod scripting;
 mod search;
 mod server;
 mod state;
@@ -53,6 +55,15 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("Starting Tepora backend (Rust)...");
 
+    if let Some(source) = core::safe_mode::detect(
+        std::env::args().skip(1),
+        std::env::var(core::safe_mode::SAFE_MODE_ENV)
+            .ok()
+            .as_deref(),
+    ) {
+        core::safe_mode::activate(source);
+    }
+
     let app_state = AppState::initialize().await?;
 
     if let Err(e) = app_state.integration.mcp.initialize().await {
@@ -64,8 +75,14 @@ async fn main() -> anyhow::Result<()> {
 
     let app = server::router(app_state.clone());
 
-    let host = resolve_server_host();
-    let port = resolve_server_port();
+    let server_config = app_state
+        .core()
+        .config
+        .load_typed()
+        .map(|config| config.server)
+        .unwrap_or_default();
+    let host = resolve_server_host(server_config.host.as_deref());
+    let port = resolve_server_port(server_config.port);
     let addr = format!("{}:{}", host, port);
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
@@ -100,8 +117,10 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn resolve_server_host() -> String {
-    resolve_server_host_from_value(std::env::var("TEPORA_HOST").ok().as_deref())
+/// `TEPORA_HOST` > 設定の `server.host`（`TEPORA__SERVER__HOST` でも上書きできる）
+fn resolve_server_host(config_host: Option<&str>) -> String {
+    let env_host = std::env::var("TEPORA_HOST").ok();
+    resolve_server_host_from_value(env_host.as_deref().or(config_host))
 }
 
 fn resolve_server_host_from_value(raw: Option<&str>) -> String {
@@ -115,16 +134,22 @@ fn resolve_server_host_from_value(raw: Option<&str>) -> String {
     }
 }
 
-fn resolve_server_port() -> u16 {
+fn resolve_server_port(config_port: Option<u16>) -> u16 {
     resolve_server_port_from_values(
         std::env::var("TEPORA_PORT").ok().as_deref(),
         std::env::var("PORT").ok().as_deref(),
+        config_port,
     )
 }
 
-fn resolve_server_port_from_values(tepora_port: Option<&str>, port: Option<&str>) -> u16 {
+fn resolve_server_port_from_values(
+    tepora_port: Option<&str>,
+    port: Option<&str>,
+    config_port: Option<u16>,
+) -> u16 {
     parse_port_value("TEPORA_PORT", tepora_port)
         .or_else(|| parse_port_value("PORT", port))
+        .or(config_port)
         .unwrap_or(3001)
 }
 
@@ -189,22 +214,28 @@ mod tests {
 
     #[test]
     fn resolve_server_port_prefers_tepora_port() {
-        let port = resolve_server_port_from_values(Some("3002"), Some("9000"));
+        let port = resolve_server_port_from_values(Some("3002"), Some("9000"), Some(4000));
         assert_eq!(port, 3002);
     }
 
     #[test]
     fn resolve_server_port_falls_back_to_port_env() {
-        let port = resolve_server_port_from_values(None, Some("8080"));
+        let port = resolve_server_port_from_values(None, Some("8080"), None);
         assert_eq!(port, 8080);
     }
 
     #[test]
     fn resolve_server_port_uses_default_when_all_invalid() {
-        let port = resolve_server_port_from_values(Some("invalid"), Some(""));
+        let port = resolve_server_port_from_values(Some("invalid"), Some(""), None);
         assert_eq!(port, 3001);
     }
 
+    #[test]
+    fn resolve_server_port_uses_config_before_default() {
+        let port = resolve_server_port_from_values(None, Some("bad"), Some(4000));
+        assert_eq!(port, 4000);
+    }
+
     #[test]
     fn parse_port_value_accepts_trimmed_numeric_input() {
         let parsed = parse_port_value("TEST_PORT", Some(" 5173 "));
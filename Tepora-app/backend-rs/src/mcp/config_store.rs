@@ -4,12 +4,16 @@ use std::sync::{Arc, RwLock as StdRwLock};
 
 use serde_json::{json, Map, Value};
 
+use crate::core::config::secrets::{is_keyring_reference, is_sensitive_env_key, SecretStore};
 use crate::core::config::{AppPaths, ConfigService};
 use crate::core::errors::ApiError;
+use crate::core::snapshots::write_with_snapshot;
 
 use super::state::McpRuntimeState;
 use super::types::McpToolsConfig;
 
+pub(crate) const ENV_REDACT_PLACEHOLDER: &str = "****";
+
 #[derive(Clone)]
 pub(crate) struct McpConfigStore {
     paths: Arc<AppPaths>,
@@ -88,7 +92,7 @@ impl McpConfigStore {
             *runtime.config.write().await = empty.clone();
             return Ok(empty);
         }
-        let parsed = match serde_json::from_str::<McpToolsConfig>(&contents) {
+        let mut parsed = match serde_json::from_str::<McpToolsConfig>(&contents) {
             Ok(config) => config,
             Err(e) => {
                 tracing::warn!(
@@ -102,20 +106,31 @@ impl McpConfigStore {
                 return Ok(runtime.config.read().await.clone());
             }
         };
+        // 平文で書かれた秘密の環境変数はキーリングへ移してから読み込む
+        if has_plaintext_env_secrets(&parsed) {
+            self.save_tools_config(&parsed)?;
+        }
+        resolve_env_secrets(&mut parsed, self.config_service.secret_store());
         *runtime.config.write().await = parsed.clone();
         Ok(parsed)
     }
 
+    /// `config` は平文の環境変数を持つ実行時の形。秘密はキーリング参照に置き換えて保存する。
     pub(crate) fn save_tools_config(&self, config: &McpToolsConfig) -> Result<(), ApiError> {
-        let config_path = self.config_path();
-        if let Some(parent) = config_path.parent() {
-            let _ = fs::create_dir_all(parent);
-        }
-        let data = serde_json::to_string_pretty(config).map_err(ApiError::internal)?;
-        fs::write(config_path, data).map_err(ApiError::internal)?;
+        let previous = self.load_raw_config().unwrap_or_else(|_| json!({}));
+        let mut stored = config.clone();
+        store_env_secrets(&mut stored, &previous, self.config_service.secret_store())?;
+        let data = serde_json::to_value(&stored).map_err(ApiError::internal)?;
+        self.save_raw_config(&data)?;
+        delete_orphaned_env_secrets(&previous, &data, self.config_service.secret_store());
         Ok(())
     }
 
+    /// サーバー削除などの後に、どこからも参照されなくなった秘密を消す。
+    pub(crate) fn forget_env_secrets(&self, previous: &Value, current: &Value) {
+        delete_orphaned_env_secrets(previous, current, self.config_service.secret_store());
+    }
+
     pub(crate) fn load_raw_config(&self) -> Result<Value, ApiError> {
         let config_path = self.config_path();
         ensure_config_file(&config_path)?;
@@ -127,13 +142,140 @@ impl McpConfigStore {
     }
 
     pub(crate) fn save_raw_config(&self, config: &Value) -> Result<(), ApiError> {
-        let config_path = self.config_path();
-        if let Some(parent) = config_path.parent() {
-            let _ = fs::create_dir_all(parent);
-        }
         let data = serde_json::to_string_pretty(config).map_err(ApiError::internal)?;
-        fs::write(config_path, data).map_err(ApiError::internal)?;
-        Ok(())
+        write_with_snapshot(&self.config_path(), &data)
+    }
+}
+
+fn has_plaintext_env_secrets(config: &McpToolsConfig) -> bool {
+    config.mcp_servers.values().any(|server| {
+        server
+            .env
+            .iter()
+            .any(|(key, value)| is_sensitive_env_key(key) && !is_keyring_reference(value))
+    })
+}
+
+fn stored_env_reference<'a>(previous: &'a Value, server: &str, key: &str) -> Option<&'a str> {
+    previous
+        .get("mcpServers")?
+        .get(server)?
+        .get("env")?
+        .get(key)?
+        .as_str()
+        .filter(|value| is_keyring_reference(value))
+}
+
+pub(super) fn store_env_secrets(
+    config: &mut McpToolsConfig,
+    previous: &Value,
+    secret_store: &dyn SecretStore,
+) -> Result<(), ApiError> {
+    for (server_name, server) in config.mcp_servers.iter_mut() {
+        for (key, value) in server.env.iter_mut() {
+            if !is_sensitive_env_key(key) || value.is_empty() || is_keyring_reference(value) {
+                continue;
+            }
+            // 値が変わっていなければ既存の参照を使い回す
+            if let Some(reference) = stored_env_reference(previous, server_name, key) {
+                if secret_store.read_secret(reference)?.as_deref() == Some(value.as_str()) {
+                    *value = reference.to_string();
+                    continue;
+                }
+            }
+            let path = format!("mcp.{server_name}.env.{key}");
+            *value = secret_store.store_secret(&path, value)?;
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn resolve_env_secrets(config: &mut McpToolsConfig, secret_store: &dyn SecretStore) {
+    for (server_name, server) in config.mcp_servers.iter_mut() {
+        server.env.retain(|key, value| {
+            if !is_keyring_reference(value) {
+                return true;
+            }
+            match secret_store.read_secret(value) {
+                Ok(Some(secret)) => {
+                    *value = secret;
+                    true
+                }
+                Ok(None) => {
+                    tracing::warn!(
+                        server = %server_name,
+                        env = %key,
+                        "MCP environment secret is missing from the secret store"
+                    );
+                    false
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        server = %server_name,
+                        env = %key,
+                        "Failed to read MCP environment secret: {}",
+                        err
+                    );
+                    false
+                }
+            }
+        });
+    }
+}
+
+fn env_references(config: &Value) -> Vec<String> {
+    config
+        .get("mcpServers")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flat_map(|servers| servers.values())
+        .filter_map(|server| server.get("env").and_then(Value::as_object))
+        .flat_map(|env| env.values())
+        .filter_map(Value::as_str)
+        .filter(|value| is_keyring_reference(value))
+        .map(str::to_string)
+        .collect()
+}
+
+fn delete_orphaned_env_secrets(previous: &Value, current: &Value, secret_store: &dyn SecretStore) {
+    let still_used = env_references(current);
+    for reference in env_references(previous) {
+        if still_used.contains(&reference) {
+            continue;
+        }
+        if let Err(err) = secret_store.delete_secret(&reference) {
+            tracing::warn!("Failed to delete MCP environment secret: {}", err);
+        }
+    }
+}
+
+/// API に返す前に秘密の環境変数を伏せる。
+pub(crate) fn redact_env_secrets(config: &mut McpToolsConfig) {
+    for server in config.mcp_servers.values_mut() {
+        for (key, value) in server.env.iter_mut() {
+            if is_sensitive_env_key(key) && !value.is_empty() {
+                *value = ENV_REDACT_PLACEHOLDER.to_string();
+            }
+        }
+    }
+}
+
+/// 伏せ字のまま送り返された値を現在の値で埋め戻す。
+pub(crate) fn restore_redacted_env(config: &mut McpToolsConfig, current: &McpToolsConfig) {
+    for (server_name, server) in config.mcp_servers.iter_mut() {
+        let current_env = current.mcp_servers.get(server_name).map(|s| &s.env);
+        server.env.retain(|key, value| {
+            if value != ENV_REDACT_PLACEHOLDER {
+                return true;
+            }
+            match current_env.and_then(|env| env.get(key)) {
+                Some(original) => {
+                    *value = original.clone();
+                    true
+                }
+                None => false,
+            }
+        });
     }
 }
 
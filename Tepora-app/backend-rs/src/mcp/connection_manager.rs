@@ -1,23 +1,70 @@
 use std::collections::HashMap;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
 use chrono::Utc;
+use rmcp::model::{ClientInfo, ListRootsResult, Root, RootsCapabilities};
+use rmcp::service::{RequestContext, RoleClient};
+use rmcp::transport::streamable_http_client::StreamableHttpClientTransportConfig;
 use rmcp::transport::{ConfigureCommandExt, StreamableHttpClientTransport, TokioChildProcess};
-use rmcp::ServiceExt;
+use rmcp::{ClientHandler, ErrorData as McpError, ServiceExt};
 use serde_json::{json, Map, Value};
 use tokio::process::Command;
 
+use crate::core::config::schema::NetworkSubsystem;
 use crate::core::config::{AppPaths, ConfigService};
 use crate::core::errors::ApiError;
+use crate::core::network::NetClient;
 use crate::core::security_controls::SecurityControls;
 #[cfg(feature = "redesign_sandbox")]
 use crate::sandbox::build_wasm_launch_spec;
+use crate::tools::filesystem::workspace_roots;
 
+use super::limits::{apply_process_limits, limited_argv};
 use super::policy_manager::McpPolicyManager;
-use super::state::{McpClientEntry, McpRuntimeState};
+use super::state::{McpClientEntry, McpRuntimeState, SafeMcpService};
 use super::types::{McpServerConfig, McpServerStatus, McpToolsConfig};
 
+/// Client handler that answers `roots/list` with the user's workspace roots,
+/// the same directories the native file tools are confined to.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WorkspaceRootsHandler {
+    roots: Vec<Root>,
+}
+
+impl WorkspaceRootsHandler {
+    pub(crate) fn from_config(config: &Value) -> Self {
+        let roots = workspace_roots(config)
+            .into_iter()
+            .filter_map(|root| {
+                let uri = reqwest::Url::from_directory_path(&root.path).ok()?;
+                Some(Root {
+                    uri: uri.to_string(),
+                    name: Some(root.label),
+                })
+            })
+            .collect();
+        Self { roots }
+    }
+}
+
+impl ClientHandler for WorkspaceRootsHandler {
+    fn list_roots(
+        &self,
+        _context: RequestContext<RoleClient>,
+    ) -> impl std::future::Future<Output = Result<ListRootsResult, McpError>> + Send + '_ {
+        std::future::ready(Ok(ListRootsResult {
+            roots: self.roots.clone(),
+        }))
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        let mut info = ClientInfo::default();
+        info.capabilities.roots = Some(RootsCapabilities::default());
+        info
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct McpConnectionManager {
     paths: Arc<AppPaths>,
@@ -81,6 +128,7 @@ impl McpConnectionManager {
                             last_connected: Some(Utc::now().to_rfc3339()),
                         },
                     );
+                    self.schedule_recycle(name, server, &entry);
                     new_clients.insert(name.clone(), entry);
                 }
                 Err(err) => {
@@ -102,6 +150,78 @@ impl McpConnectionManager {
         Ok(())
     }
 
+    /// `limits.max_lifetime_secs` を過ぎたら stdio サーバーを作り直す。
+    fn schedule_recycle(&self, name: &str, server: &McpServerConfig, entry: &McpClientEntry) {
+        let Some(lifetime) = server.limits.max_lifetime() else {
+            return;
+        };
+        if !is_stdio_transport(&server.transport) {
+            return;
+        }
+        let manager = self.clone();
+        let name = name.to_string();
+        let server = server.clone();
+        // 再読み込みで置き換わったプロセスを延命させないよう弱参照で持つ
+        let service = Arc::downgrade(&entry.service);
+        tokio::spawn(async move {
+            tokio::time::sleep(lifetime).await;
+            manager.recycle_server(&name, &server, service).await;
+        });
+    }
+
+    async fn recycle_server(
+        &self,
+        name: &str,
+        server: &McpServerConfig,
+        expired: Weak<dyn SafeMcpService>,
+    ) {
+        {
+            let mut clients = self.runtime.clients.write().await;
+            let current = clients
+                .get(name)
+                .map(|entry| Arc::downgrade(&entry.service));
+            if !current.is_some_and(|current| current.ptr_eq(&expired)) {
+                return;
+            }
+            // 実行中のツール呼び出しが参照を手放した時点で旧プロセスは終了する
+            clients.remove(name);
+        }
+        tracing::info!(
+            target: "mcp",
+            server = %name,
+            "Restarting MCP server after reaching its max lifetime"
+        );
+
+        let status = match self.connect_server(name, server).await {
+            Ok(entry) => {
+                let status = McpServerStatus {
+                    status: "connected".to_string(),
+                    tools_count: entry.tools.len(),
+                    error_message: None,
+                    last_connected: Some(Utc::now().to_rfc3339()),
+                };
+                self.schedule_recycle(name, server, &entry);
+                self.runtime
+                    .clients
+                    .write()
+                    .await
+                    .insert(name.to_string(), entry);
+                status
+            }
+            Err(err) => McpServerStatus {
+                status: "error".to_string(),
+                tools_count: 0,
+                error_message: Some(err),
+                last_connected: None,
+            },
+        };
+        self.runtime
+            .status
+            .write()
+            .await
+            .insert(name.to_string(), status);
+    }
+
     async fn connect_server(
         &self,
         name: &str,
@@ -119,13 +239,19 @@ impl McpConnectionManager {
             sandbox_mcp_enabled,
             "Connecting MCP server"
         );
-        let service = if transport_name == "stdio" || transport_name.is_empty() {
+        let handler = self
+            .config_service
+            .load_config()
+            .map(|config| WorkspaceRootsHandler::from_config(&config))
+            .unwrap_or_default();
+        let service = if is_stdio_transport(&transport_name) {
             let cmd = self.build_stdio_command(name, server, sandbox_mcp_enabled)?;
             let transport = TokioChildProcess::new(cmd.configure(|cmd| {
                 let _ = cmd;
             }))
             .map_err(|err| format!("Failed to spawn MCP server '{}': {}", name, err))?;
-            ().serve(transport)
+            handler
+                .serve(transport)
                 .await
                 .map_err(|err| format!("Failed to connect MCP server '{}': {}", name, err))?
         } else if transport_name == "streamable_http"
@@ -139,8 +265,17 @@ impl McpConnectionManager {
                 .filter(|s| !s.is_empty())
                 .ok_or_else(|| "MCP server URL is required for HTTP transport".to_string())?;
 
-            let transport = StreamableHttpClientTransport::from_uri(url);
-            ().serve(transport)
+            let target = reqwest::Url::parse(url)
+                .map_err(|err| format!("Invalid MCP server URL for '{}': {}", name, err))?;
+            let client = NetClient::new(NetworkSubsystem::McpServers)
+                .client_for(&target)
+                .map_err(|err| format!("Cannot connect MCP server '{}': {}", name, err))?;
+            let transport = StreamableHttpClientTransport::with_client(
+                client,
+                StreamableHttpClientTransportConfig::with_uri(url),
+            );
+            handler
+                .serve(transport)
                 .await
                 .map_err(|err| format!("Failed to connect MCP server '{}': {}", name, err))?
         } else {
@@ -176,9 +311,6 @@ impl McpConnectionManager {
         server: &McpServerConfig,
         sandbox_mcp_enabled: bool,
     ) -> Result<Command, String> {
-        #[cfg(not(feature = "redesign_sandbox"))]
-        let _ = server_name;
-
         let command = server.command.trim();
         if command.is_empty() {
             return Err("MCP command is required for stdio transport".to_string());
@@ -213,6 +345,7 @@ impl McpConnectionManager {
                 if !spec.env.is_empty() {
                     cmd.envs(&spec.env);
                 }
+                apply_process_limits(&mut cmd, &server.limits, false);
                 tracing::info!(
                     target: "mcp",
                     server = %server_name,
@@ -234,11 +367,22 @@ impl McpConnectionManager {
             }
         }
 
-        let mut cmd = Command::new(command);
-        cmd.args(&server.args);
+        let (program, args, memory_in_cgroup) = limited_argv(command, &server.args, &server.limits);
+        let mut cmd = Command::new(program);
+        cmd.args(&args);
         if !server.env.is_empty() {
             cmd.envs(&server.env);
         }
+        apply_process_limits(&mut cmd, &server.limits, memory_in_cgroup);
+        if !server.limits.is_unset() {
+            tracing::info!(
+                target: "mcp",
+                server = %server_name,
+                limits = ?server.limits,
+                memory_in_cgroup,
+                "Launching MCP server with resource limits"
+            );
+        }
         Ok(cmd)
     }
 
@@ -303,6 +447,11 @@ impl McpConnectionManager {
     }
 }
 
+fn is_stdio_transport(transport: &str) -> bool {
+    let transport = transport.trim();
+    transport.is_empty() || transport.eq_ignore_ascii_case("stdio")
+}
+
 fn looks_like_wasm_server_command(command: &str) -> bool {
     let trimmed = command.trim();
     if trimmed.is_empty() {
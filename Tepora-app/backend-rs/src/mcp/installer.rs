@@ -109,6 +109,7 @@ pub fn generate_config(
             description: server.description.clone(),
             icon: server.icon.clone(),
         }),
+        limits: Default::default(),
     })
 }
 
@@ -0,0 +1,201 @@
+//! stdio MCP サーバーの資源制限。
+//!
+//! 暴走したサーバーがマシンごと巻き込まないよう、起動時に CPU 優先度とメモリ上限を掛ける。
+//! Linux で systemd のユーザーセッションがあれば `systemd-run --scope` の cgroup で
+//! 実メモリを縛り、無ければ `RLIMIT_DATA` で代用する（V8 などは仮想アドレスを大きく
+//! 予約するので `RLIMIT_AS` は使わない）。Windows は優先度クラスのみ対応。
+//! 寿命の上限は接続側がプロセスを作り直して守る。
+
+use std::time::Duration;
+
+use tokio::process::Command;
+
+use super::types::McpResourceLimits;
+
+const MAX_NICE: i32 = 19;
+const SYSTEMD_RUN: &str = "systemd-run";
+
+#[cfg(windows)]
+const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+#[cfg(windows)]
+const IDLE_PRIORITY_CLASS: u32 = 0x0000_0040;
+
+impl McpResourceLimits {
+    /// 実際に掛ける nice 値。優先度を上げる指定と 0 は無視する。
+    pub(crate) fn niceness(&self) -> Option<i32> {
+        self.nice
+            .map(|nice| nice.clamp(0, MAX_NICE))
+            .filter(|nice| *nice > 0)
+    }
+
+    pub(crate) fn memory_bytes(&self) -> Option<u64> {
+        self.memory_max_mb
+            .filter(|mb| *mb > 0)
+            .map(|mb| mb.saturating_mul(1024 * 1024))
+    }
+
+    pub(crate) fn max_lifetime(&self) -> Option<Duration> {
+        self.max_lifetime_secs
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+}
+
+/// 制限付きで起動するプログラムと引数。cgroup が使えるときは `systemd-run` で包む。
+/// 戻り値の真偽はメモリ上限を cgroup に任せたかどうか。
+pub(crate) fn limited_argv(
+    program: &str,
+    args: &[String],
+    limits: &McpResourceLimits,
+) -> (String, Vec<String>, bool) {
+    match limits.memory_bytes() {
+        Some(bytes) if cgroup_scope_available() => (
+            SYSTEMD_RUN.to_string(),
+            scope_args(bytes, program, args),
+            true,
+        ),
+        _ => (program.to_string(), args.to_vec(), false),
+    }
+}
+
+fn scope_args(memory_bytes: u64, program: &str, args: &[String]) -> Vec<String> {
+    let mut argv = vec![
+        "--user".to_string(),
+        "--scope".to_string(),
+        "--quiet".to_string(),
+        "--collect".to_string(),
+        "-p".to_string(),
+        format!("MemoryMax={}", memory_bytes),
+        "-p".to_string(),
+        "MemorySwapMax=0".to_string(),
+        "--".to_string(),
+        program.to_string(),
+    ];
+    argv.extend(args.iter().cloned());
+    argv
+}
+
+#[cfg(target_os = "linux")]
+fn cgroup_scope_available() -> bool {
+    std::env::var_os("XDG_RUNTIME_DIR").is_some() && which::which(SYSTEMD_RUN).is_ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn cgroup_scope_available() -> bool {
+    false
+}
+
+/// 子プロセスに優先度とメモリ上限を設定する。`memory_in_cgroup` なら上限は cgroup 側に任せる。
+pub(crate) fn apply_process_limits(
+    cmd: &mut Command,
+    limits: &McpResourceLimits,
+    memory_in_cgroup: bool,
+) {
+    #[cfg(unix)]
+    {
+        let nice = limits.niceness();
+        let memory = limits.memory_bytes().filter(|_| !memory_in_cgroup);
+        if nice.is_none() && memory.is_none() {
+            return;
+        }
+        // SAFETY: fork 後の子プロセスでは async-signal-safe な呼び出ししか行わない
+        unsafe {
+            cmd.pre_exec(move || {
+                if let Some(nice) = nice {
+                    // 失敗しても起動は続ける（優先度は努力目標）
+                    libc::setpriority(libc::PRIO_PROCESS, 0, nice);
+                }
+                if let Some(bytes) = memory {
+                    let limit = libc::rlimit {
+                        rlim_cur: bytes as libc::rlim_t,
+                        rlim_max: bytes as libc::rlim_t,
+                    };
+                    if libc::setrlimit(libc::RLIMIT_DATA, &limit) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = memory_in_cgroup;
+        if let Some(nice) = limits.niceness() {
+            cmd.creation_flags(if nice >= 10 {
+                IDLE_PRIORITY_CLASS
+            } else {
+                BELOW_NORMAL_PRIORITY_CLASS
+            });
+        }
+        if limits.memory_bytes().is_some() {
+            tracing::warn!(
+                target: "mcp",
+                "MCP memory limits are not enforced on Windows; only the priority is applied"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_ignore_unset_and_priority_raising_values() {
+        let limits = McpResourceLimits {
+            nice: Some(-5),
+            memory_max_mb: Some(0),
+            max_lifetime_secs: Some(0),
+        };
+        assert_eq!(limits.niceness(), None);
+        assert_eq!(limits.memory_bytes(), None);
+        assert_eq!(limits.max_lifetime(), None);
+
+        let limits = McpResourceLimits {
+            nice: Some(40),
+            memory_max_mb: Some(512),
+            max_lifetime_secs: Some(3600),
+        };
+        assert_eq!(limits.niceness(), Some(MAX_NICE));
+        assert_eq!(limits.memory_bytes(), Some(512 * 1024 * 1024));
+        assert_eq!(limits.max_lifetime(), Some(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn scope_wraps_original_command_after_separator() {
+        let argv = scope_args(1024, "npx", &["-y".to_string(), "server".to_string()]);
+        assert!(argv.contains(&"MemoryMax=1024".to_string()));
+        let separator = argv.iter().position(|arg| arg == "--").unwrap();
+        assert_eq!(&argv[separator + 1..], ["npx", "-y", "server"]);
+
+        let (program, args, in_cgroup) = limited_argv(
+            "npx",
+            &["server".to_string()],
+            &McpResourceLimits::default(),
+        );
+        assert_eq!(program, "npx");
+        assert_eq!(args, ["server"]);
+        assert!(!in_cgroup);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn limited_child_runs_with_lower_priority() {
+        let limits = McpResourceLimits {
+            nice: Some(5),
+            memory_max_mb: None,
+            max_lifetime_secs: None,
+        };
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "nice"]);
+        apply_process_limits(&mut cmd, &limits, false);
+        let output = cmd.output().await.unwrap();
+        let current: i32 = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .unwrap();
+        assert!(current >= 5);
+    }
+}
@@ -4,14 +4,18 @@ use serde_json::{json, Value};
 
 use crate::core::config::{AppPaths, ConfigService};
 use crate::core::errors::ApiError;
+use crate::core::safe_mode;
 
-use super::config_store::McpConfigStore;
+use super::config_store::{redact_env_secrets, restore_redacted_env, McpConfigStore};
 use super::connection_manager::McpConnectionManager;
 use super::policy_manager::McpPolicyManager;
 use super::state::McpRuntimeState;
 use super::tool_executor::McpToolExecutor;
 use super::types::{McpPolicy, McpServerStatus, McpToolInfo, McpToolsConfig};
 
+/// セーフモード中に `init_error` として見せる案内
+const SAFE_MODE_NOTICE: &str = "Safe mode is active: MCP servers are not started";
+
 /// Manages Model Context Protocol (MCP) servers and tools.
 ///
 /// Handles:
@@ -71,20 +75,25 @@ impl McpManager {
         self.config_store.refresh_paths_from(&config);
 
         let tools_config = self.config_store.load_tools_config(&self.runtime).await?;
-        let init_error = self
-            .connection_manager
-            .connect_all(&tools_config)
-            .await
-            .err()
-            .map(|err| err.to_string());
-        *self.runtime.init_error.write().await = init_error;
+        if let Err(err) = self.connect_all(&tools_config).await {
+            *self.runtime.init_error.write().await = Some(err.to_string());
+        }
         self.runtime.set_initialized(true);
         Ok(())
     }
 
     pub async fn reload(&self) -> Result<(), ApiError> {
         let tools_config = self.config_store.load_tools_config(&self.runtime).await?;
-        self.connection_manager.connect_all(&tools_config).await?;
+        self.connect_all(&tools_config).await
+    }
+
+    /// セーフモード中は設定を読むだけで接続しない（設定の修正はできるようにする）。
+    async fn connect_all(&self, tools_config: &McpToolsConfig) -> Result<(), ApiError> {
+        if safe_mode::is_active() {
+            *self.runtime.init_error.write().await = Some(SAFE_MODE_NOTICE.to_string());
+            return Ok(());
+        }
+        self.connection_manager.connect_all(tools_config).await?;
         *self.runtime.init_error.write().await = None;
         Ok(())
     }
@@ -93,6 +102,13 @@ impl McpManager {
         self.runtime.config.read().await.clone()
     }
 
+    /// 秘密の環境変数を伏せた設定（API 応答用）
+    pub async fn get_redacted_config(&self) -> McpToolsConfig {
+        let mut config = self.get_config().await;
+        redact_env_secrets(&mut config);
+        config
+    }
+
     pub async fn status_snapshot(&self) -> std::collections::HashMap<String, McpServerStatus> {
         self.runtime.status.read().await.clone()
     }
@@ -115,14 +131,13 @@ impl McpManager {
             .cloned()
             .unwrap_or_else(|| payload.clone());
         let config_value = json!({ "mcpServers": servers_value });
-        let parsed: McpToolsConfig = serde_json::from_value(config_value)
+        let mut parsed: McpToolsConfig = serde_json::from_value(config_value)
             .map_err(|e| ApiError::BadRequest(format!("Invalid MCP config: {}", e)))?;
+        restore_redacted_env(&mut parsed, &*self.runtime.config.read().await);
 
         self.config_store.save_tools_config(&parsed)?;
         *self.runtime.config.write().await = parsed.clone();
-        self.connection_manager.connect_all(&parsed).await?;
-        *self.runtime.init_error.write().await = None;
-        Ok(())
+        self.connect_all(&parsed).await
     }
 
     pub async fn set_server_enabled(
@@ -153,11 +168,13 @@ impl McpManager {
             .and_then(|v| v.as_object_mut())
             .ok_or_else(|| ApiError::BadRequest("Invalid MCP config format".to_string()))?;
 
-        if servers.remove(server_name).is_none() {
+        let Some(removed) = servers.remove(server_name) else {
             return Ok(false);
-        }
+        };
 
         self.config_store.save_raw_config(&raw)?;
+        self.config_store
+            .forget_env_secrets(&json!({ "mcpServers": { server_name: removed } }), &raw);
         self.reload().await?;
         Ok(true)
     }
@@ -1,6 +1,7 @@
 mod config_store;
 mod connection_manager;
 pub mod installer;
+mod limits;
 mod manager;
 mod policy_manager;
 pub mod registry;
@@ -13,6 +14,6 @@ mod types;
 pub use manager::McpManager;
 #[allow(unused_imports)]
 pub use types::{
-    McpPolicy, McpServerConfig, McpServerMetadata, McpServerPermission, McpServerStatus,
-    McpToolInfo, McpToolsConfig,
+    McpPolicy, McpResourceLimits, McpServerConfig, McpServerMetadata, McpServerPermission,
+    McpServerStatus, McpToolInfo, McpToolsConfig,
 };
@@ -6,6 +6,7 @@ use reqwest::Url;
 use serde_json::Value;
 
 use crate::core::errors::ApiError;
+use crate::core::snapshots::write_with_snapshot;
 
 use super::config_store::McpConfigStore;
 use super::types::{McpPolicy, McpServerConfig, McpServerPermission};
@@ -34,13 +35,8 @@ impl McpPolicyManager {
     }
 
     pub(crate) fn save_policy(&self, policy: &McpPolicy) -> Result<(), ApiError> {
-        let path = self.config_store.policy_path();
-        if let Some(parent) = path.parent() {
-            let _ = fs::create_dir_all(parent);
-        }
         let serialized = serde_json::to_string_pretty(policy).map_err(ApiError::internal)?;
-        fs::write(path, serialized).map_err(ApiError::internal)?;
-        Ok(())
+        write_with_snapshot(&self.config_store.policy_path(), &serialized)
     }
 
     pub(crate) fn update_policy(&self, payload: &Value) -> Result<McpPolicy, ApiError> {
@@ -1,19 +1,29 @@
+//! MCP ストアの一覧。公式レジストリ、設定で追加したレジストリ、組み込みの厳選リスト
+//! （`curated.json`）を 1 つにまとめる。同じ ID は新しいバージョンを残し、
+//! 厳選リストのカテゴリとおすすめ指定は引き継ぐ。
+
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
-use reqwest::Client;
 use semver::Version;
 use serde_json::Value;
 use tokio::sync::RwLock;
 
+use crate::core::config::schema::{McpMarketplaceSettings, NetworkSubsystem};
 use crate::core::config::AppPaths;
 use crate::core::errors::ApiError;
+use crate::core::net_retry::{send_with_retry, RetryPolicy};
+use crate::core::network::NetClient;
 
 const REGISTRY_API_URL: &str = "https://registry.modelcontextprotocol.io/v0.1/servers";
 const CACHE_DURATION: Duration = Duration::from_secs(60 * 60);
 const OFFICIAL_REGISTRY_MAX_LIMIT: usize = 100;
 const DEFAULT_VERSION_FILTER: &str = "latest";
+const CURATED_SERVERS: &str = include_str!("curated.json");
+
+pub const OFFICIAL_SOURCE: &str = "official";
+pub const CURATED_SOURCE: &str = "curated";
 
 #[derive(Debug, Clone)]
 pub struct McpEnvVar {
@@ -65,74 +75,95 @@ pub struct McpRegistryServer {
     pub environment_variables: Vec<McpEnvVar>,
     pub icon: Option<String>,
     pub category: Option<String>,
+    /// 掲載元（`official` / `curated` / 設定のソース名）
+    pub source: String,
+    pub recommended: bool,
+}
+
+struct CachedListing {
+    key: String,
+    servers: Vec<McpRegistryServer>,
+    fetched_at: Instant,
 }
 
 #[derive(Clone)]
 pub struct McpRegistry {
-    client: Client,
+    client: NetClient,
     seed_path: PathBuf,
-    cache: std::sync::Arc<RwLock<Vec<McpRegistryServer>>>,
-    cache_time: std::sync::Arc<RwLock<Option<Instant>>>,
+    cache: std::sync::Arc<RwLock<Option<CachedListing>>>,
 }
 
 impl McpRegistry {
     pub fn new(paths: &AppPaths) -> Self {
         let seed_path = resolve_seed_path(paths);
         Self {
-            client: Client::new(),
+            client: NetClient::new(NetworkSubsystem::McpRegistry),
             seed_path,
-            cache: std::sync::Arc::new(RwLock::new(Vec::new())),
-            cache_time: std::sync::Arc::new(RwLock::new(None)),
+            cache: std::sync::Arc::new(RwLock::new(None)),
         }
     }
 
+    /// 有効な掲載元をすべて集めた一覧。取得に失敗したソースは飛ばす
+    /// （公式だけは同梱の seed に切り替える）。
     pub async fn fetch_servers(
         &self,
+        settings: &McpMarketplaceSettings,
         force_refresh: bool,
         search: Option<&str>,
         version: Option<&str>,
     ) -> Result<Vec<McpRegistryServer>, ApiError> {
         let version = version.unwrap_or(DEFAULT_VERSION_FILTER);
-        if !force_refresh && version == DEFAULT_VERSION_FILTER && self.is_cache_valid().await {
-            let cached = self.cache.read().await.clone();
-            return Ok(search_servers_local(cached, search));
+        let key = cache_key(settings);
+        let cacheable = version == DEFAULT_VERSION_FILTER;
+        if !force_refresh && cacheable {
+            if let Some(cached) = self.cached(&key).await {
+                return Ok(search_servers_local(cached, search));
+            }
         }
 
-        match self.fetch_from_api(search, version).await {
-            Ok(mut servers) => {
-                if version == DEFAULT_VERSION_FILTER {
-                    self.update_cache(&servers).await;
-                }
-                if search.is_some() {
-                    servers = search_servers_local(servers, search);
+        let mut lists = Vec::new();
+        if settings.official {
+            let servers = match self.fetch_from_api(REGISTRY_API_URL, search, version).await {
+                Ok(servers) => servers,
+                Err(err) => {
+                    tracing::warn!("Official MCP registry unavailable, using seed: {}", err);
+                    self.load_from_seed().await.unwrap_or_default()
                 }
-                Ok(servers)
-            }
-            Err(_err) => {
-                let servers = self.load_from_seed().await.unwrap_or_default();
-                if version == DEFAULT_VERSION_FILTER {
-                    self.update_cache(&servers).await;
+            };
+            lists.push((OFFICIAL_SOURCE.to_string(), servers));
+        }
+        for source in &settings.sources {
+            match self.fetch_from_api(&source.url, search, version).await {
+                Ok(servers) => lists.push((source.name.clone(), servers)),
+                Err(err) => {
+                    tracing::warn!("MCP registry source '{}' failed: {}", source.name, err)
                 }
-                let filtered = if search.is_some() {
-                    search_servers_local(servers, search)
-                } else {
-                    servers
-                };
-                Ok(filtered)
             }
         }
+        if settings.curated {
+            lists.push((CURATED_SOURCE.to_string(), curated_servers()));
+        }
+
+        let servers = merge_sources(lists, &settings.recommended);
+        // 検索語付きの取得は API 側で絞り込まれているので、全件として残さない
+        if cacheable && search.is_none() {
+            self.update_cache(key, &servers).await;
+        }
+        Ok(search_servers_local(servers, search))
     }
 
     pub async fn get_server_by_id(
         &self,
+        settings: &McpMarketplaceSettings,
         server_id: &str,
     ) -> Result<Option<McpRegistryServer>, ApiError> {
-        let servers = self.fetch_servers(false, None, None).await?;
+        let servers = self.fetch_servers(settings, false, None, None).await?;
         Ok(servers.into_iter().find(|s| s.id == server_id))
     }
 
     async fn fetch_from_api(
         &self,
+        url: &str,
         search: Option<&str>,
         version: &str,
     ) -> Result<Vec<McpRegistryServer>, ApiError> {
@@ -155,13 +186,10 @@ impl McpRegistry {
                 params.push(("cursor", cursor_value));
             }
 
-            let response = self
-                .client
-                .get(REGISTRY_API_URL)
-                .query(&params)
-                .send()
-                .await
-                .map_err(ApiError::internal)?;
+            let response = send_with_retry(&RetryPolicy::default(), "mcp_registry", || {
+                Ok(self.client.get(url)?.query(&params))
+            })
+            .await?;
             let response = response.error_for_status().map_err(ApiError::internal)?;
             let data: Value = response.json().await.map_err(ApiError::internal)?;
 
@@ -217,26 +245,116 @@ impl McpRegistry {
         Ok(dedupe_latest(servers))
     }
 
-    async fn is_cache_valid(&self) -> bool {
+    async fn cached(&self, key: &str) -> Option<Vec<McpRegistryServer>> {
         let cache = self.cache.read().await;
-        let cache_time = self.cache_time.read().await;
-        if cache.is_empty() {
-            return false;
-        }
-        cache_time
+        cache
             .as_ref()
-            .map(|time| time.elapsed() < CACHE_DURATION)
-            .unwrap_or(false)
+            .filter(|cached| {
+                cached.key == key
+                    && !cached.servers.is_empty()
+                    && cached.fetched_at.elapsed() < CACHE_DURATION
+            })
+            .map(|cached| cached.servers.clone())
     }
 
-    async fn update_cache(&self, servers: &[McpRegistryServer]) {
-        let mut cache = self.cache.write().await;
-        let mut cache_time = self.cache_time.write().await;
-        *cache = servers.to_vec();
-        *cache_time = Some(Instant::now());
+    async fn update_cache(&self, key: String, servers: &[McpRegistryServer]) {
+        *self.cache.write().await = Some(CachedListing {
+            key,
+            servers: servers.to_vec(),
+            fetched_at: Instant::now(),
+        });
     }
 }
 
+/// 掲載元の設定が変わったらキャッシュを捨てる。
+fn cache_key(settings: &McpMarketplaceSettings) -> String {
+    serde_json::to_string(settings).unwrap_or_default()
+}
+
+pub(super) fn curated_servers() -> Vec<McpRegistryServer> {
+    serde_json::from_str::<Value>(CURATED_SERVERS)
+        .ok()
+        .and_then(|value| value.get("servers").and_then(Value::as_array).cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(parse_server)
+        .collect()
+}
+
+/// 先に並んだ掲載元を優先してまとめる。同じ ID はバージョンが新しい方を残す。
+pub(super) fn merge_sources(
+    lists: Vec<(String, Vec<McpRegistryServer>)>,
+    extra_recommended: &[String],
+) -> Vec<McpRegistryServer> {
+    let mut merged: Vec<McpRegistryServer> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for (source, servers) in lists {
+        for mut server in servers {
+            server.source = source.clone();
+            let Some(&position) = index.get(&server.id) else {
+                index.insert(server.id.clone(), merged.len());
+                merged.push(server);
+                continue;
+            };
+            let existing = &mut merged[position];
+            let recommended = existing.recommended || server.recommended;
+            let category = existing.category.clone().or(server.category.clone());
+            if should_replace(existing, &server) {
+                *existing = server;
+            }
+            existing.recommended = recommended;
+            existing.category = existing.category.take().or(category);
+        }
+    }
+    for server in &mut merged {
+        server.recommended |= extra_recommended.contains(&server.id);
+        if server.category.is_none() {
+            server.category = infer_category(server).map(str::to_string);
+        }
+    }
+    merged
+}
+
+/// カテゴリを持たないサーバーは名前と説明のキーワードで振り分ける。
+fn infer_category(server: &McpRegistryServer) -> Option<&'static str> {
+    const RULES: &[(&str, &[&str])] = &[
+        (
+            "database",
+            &[
+                "sql", "postgres", "mysql", "sqlite", "mongo", "redis", "database",
+            ],
+        ),
+        (
+            "development",
+            &["git", "github", "gitlab", "docker", "kubernetes", "ci/cd"],
+        ),
+        ("web", &["browser", "fetch", "scrap", "crawl", "web search"]),
+        (
+            "files",
+            &["filesystem", "file system", "files", "drive", "storage"],
+        ),
+        (
+            "productivity",
+            &["calendar", "mail", "notion", "slack", "todo", "task"],
+        ),
+        (
+            "knowledge",
+            &["memory", "knowledge", "wiki", "docs", "notes"],
+        ),
+    ];
+    let haystack = format!(
+        "{} {} {}",
+        server.id,
+        server.name,
+        server.description.as_deref().unwrap_or_default()
+    )
+    .to_lowercase();
+    RULES
+        .iter()
+        .find(|(_, keywords)| keywords.iter().any(|keyword| haystack.contains(keyword)))
+        .map(|(category, _)| *category)
+}
+
 fn resolve_seed_path(paths: &AppPaths) -> PathBuf {
     let candidate = paths
         .project_root
@@ -258,7 +376,7 @@ fn resolve_seed_path(paths: &AppPaths) -> PathBuf {
     paths.project_root.join("config").join("seed.json")
 }
 
-fn parse_server(data: &Value) -> Option<McpRegistryServer> {
+pub(super) fn parse_server(data: &Value) -> Option<McpRegistryServer> {
     let server_name = get_str(data, "name")
         .or_else(|| get_str(data, "id"))
         .unwrap_or_default();
@@ -327,6 +445,11 @@ fn parse_server(data: &Value) -> Option<McpRegistryServer> {
         environment_variables: env_vars.into_values().collect(),
         icon,
         category: get_str(data, "category"),
+        source: String::new(),
+        recommended: data
+            .get("recommended")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
     })
 }
 
@@ -4,6 +4,7 @@ use std::sync::Arc;
 
 use rmcp::model::{CallToolRequestParams, CallToolResult};
 use rmcp::service::{RoleClient, RunningService};
+use rmcp::ClientHandler;
 use serde_json::Value;
 use tokio::sync::RwLock;
 
@@ -22,7 +23,7 @@ pub(crate) trait SafeMcpService: Send + Sync {
     >;
 }
 
-impl SafeMcpService for RunningService<RoleClient, ()> {
+impl<H: ClientHandler> SafeMcpService for RunningService<RoleClient, H> {
     fn call_tool_boxed(
         &self,
         params: CallToolRequestParams,
@@ -1,7 +1,13 @@
 use rmcp::model::{Annotated, CallToolResult, Content, RawContent, RawTextContent};
 use serde_json::json;
 
+use super::config_store::{
+    redact_env_secrets, resolve_env_secrets, restore_redacted_env, store_env_secrets,
+};
+use super::registry::{curated_servers, merge_sources, parse_server, CURATED_SOURCE};
 use super::tool_executor::{format_tool_result, mcp_tool_info_from_value};
+use super::McpToolsConfig;
+use crate::core::config::secrets::{is_keyring_reference, MemorySecretStore};
 
 fn make_text_content(text: &str) -> Content {
     Annotated {
@@ -114,3 +120,98 @@ fn test_format_tool_result_multiple_contents() {
     let output = format_tool_result(&result);
     assert_eq!(output, "Line 1\nLine 2");
 }
+
+#[test]
+fn test_mcp_env_secrets_are_stored_as_references() {
+    let store = MemorySecretStore::default();
+    let runtime: McpToolsConfig = serde_json::from_value(json!({
+        "mcpServers": {
+            "search": {
+                "command": "npx",
+                "env": { "BRAVE_API_KEY": "brave-secret", "LOG_LEVEL": "info" }
+            }
+        }
+    }))
+    .unwrap();
+
+    let mut stored = runtime.clone();
+    store_env_secrets(&mut stored, &json!({}), &store).unwrap();
+    let env = &stored.mcp_servers["search"].env;
+    assert!(is_keyring_reference(&env["BRAVE_API_KEY"]));
+    assert_eq!(env["LOG_LEVEL"], "info");
+
+    // 値が同じなら保存し直しても参照は変わらない
+    let previous = serde_json::to_value(&stored).unwrap();
+    let mut again = runtime.clone();
+    store_env_secrets(&mut again, &previous, &store).unwrap();
+    assert_eq!(
+        again.mcp_servers["search"].env["BRAVE_API_KEY"],
+        env["BRAVE_API_KEY"]
+    );
+
+    let mut resolved = stored.clone();
+    resolve_env_secrets(&mut resolved, &store);
+    assert_eq!(
+        resolved.mcp_servers["search"].env["BRAVE_API_KEY"],
+        "brave-secret"
+    );
+    assert!(!format!("{:?}", resolved).contains("brave-secret"));
+
+    let mut redacted = resolved.clone();
+    redact_env_secrets(&mut redacted);
+    assert_eq!(redacted.mcp_servers["search"].env["BRAVE_API_KEY"], "****");
+    restore_redacted_env(&mut redacted, &resolved);
+    assert_eq!(
+        redacted.mcp_servers["search"].env["BRAVE_API_KEY"],
+        "brave-secret"
+    );
+}
+
+#[test]
+fn registry_sources_merge_with_curated_categories_and_recommendations() {
+    let official = vec![
+        parse_server(&json!({
+            "name": "io.github.modelcontextprotocol/server-git",
+            "version": "1.0.0",
+            "description": "Git tools"
+        }))
+        .unwrap(),
+        parse_server(&json!({"name": "acme/postgres", "description": "Query a Postgres DB"}))
+            .unwrap(),
+    ];
+    let team = vec![
+        parse_server(&json!({"name": "acme/postgres", "version": "2.0.0"})).unwrap(),
+        parse_server(&json!({"name": "acme/notes", "category": "internal"})).unwrap(),
+    ];
+    let curated = curated_servers();
+    assert!(curated.iter().any(|server| server.recommended));
+
+    let merged = merge_sources(
+        vec![
+            ("official".to_string(), official),
+            ("team".to_string(), team),
+            (CURATED_SOURCE.to_string(), curated),
+        ],
+        &["acme/notes".to_string()],
+    );
+    let find = |id: &str| merged.iter().find(|server| server.id == id).unwrap();
+
+    let git = find("io.github.modelcontextprotocol/server-git");
+    assert_eq!(git.source, "official");
+    assert_eq!(git.version.as_deref(), Some("1.0.0"));
+    assert_eq!(git.category.as_deref(), Some("development"));
+    assert!(git.recommended);
+
+    let postgres = find("acme/postgres");
+    assert_eq!(postgres.source, "team");
+    assert_eq!(postgres.category.as_deref(), Some("database"));
+    assert!(!postgres.recommended);
+
+    let notes = find("acme/notes");
+    assert_eq!(notes.category.as_deref(), Some("internal"));
+    assert!(notes.recommended);
+    assert_eq!(
+        find("io.github.modelcontextprotocol/server-time").source,
+        "curated"
+    );
+}
@@ -16,7 +16,7 @@ pub struct McpServerMetadata {
     pub icon: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct McpServerConfig {
     pub command: String,
     #[serde(default)]
@@ -31,6 +31,28 @@ pub struct McpServerConfig {
     pub url: Option<String>,
     #[serde(default)]
     pub metadata: Option<McpServerMetadata>,
+    #[serde(default, skip_serializing_if = "McpResourceLimits::is_unset")]
+    pub limits: McpResourceLimits,
+}
+
+/// stdio サーバーのプロセスに掛ける制限。未指定の項目は制限しない。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct McpResourceLimits {
+    /// CPU の優先度 (0-19)。大きいほど譲る。優先度を上げる指定は無視する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>,
+    /// メモリ上限 (MiB)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_max_mb: Option<u64>,
+    /// この秒数を過ぎたらプロセスを作り直す
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_lifetime_secs: Option<u64>,
+}
+
+impl McpResourceLimits {
+    pub fn is_unset(&self) -> bool {
+        self == &Self::default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,6 +113,23 @@ pub struct McpToolInfo {
     pub input_schema: Option<Value>,
 }
 
+// 環境変数には API キーが入るため、ログに値を出さない
+impl std::fmt::Debug for McpServerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let env_keys: Vec<&String> = self.env.keys().collect();
+        f.debug_struct("McpServerConfig")
+            .field("command", &self.command)
+            .field("args", &self.args)
+            .field("env", &env_keys)
+            .field("enabled", &self.enabled)
+            .field("transport", &self.transport)
+            .field("url", &self.url)
+            .field("metadata", &self.metadata)
+            .field("limits", &self.limits)
+            .finish()
+    }
+}
+
 fn default_enabled() -> bool {
     true
 }
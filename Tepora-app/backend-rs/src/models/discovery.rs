@@ -3,13 +3,25 @@ use std::path::PathBuf;
 
 use reqwest::Client;
 
-use crate::core::config::ConfigService;
+use crate::core::config::schema::ModelPricing;
+use crate::core::config::{ConfigService, TeporaConfig};
 use crate::core::errors::ApiError;
+use crate::core::network::NetClient;
+use crate::llm::gemini_provider::{GeminiProvider, DEFAULT_GEMINI_BASE_URL, GEMINI_LOADER};
+use crate::llm::http_pool::{authorized_client, tuned_client_builder};
+use crate::llm::openai_compatible_provider::{
+    OpenAiCompatibleProvider, ProviderTimeouts, DEFAULT_OPENAI_BASE_URL, OPENAI_COMPATIBLE_LOADER,
+};
+use crate::llm::openrouter_provider::{
+    OpenRouterProvider, DEFAULT_OPENROUTER_BASE_URL, OPENROUTER_LOADER,
+};
+use crate::llm::provider::LlmProvider;
+use crate::llm::types::ProviderModel;
 
+use super::gguf_cache::GgufMetadataCache;
 use super::metadata::{
     determine_ollama_role, extract_architecture_from_model_info, extract_context_length,
     has_embedding_name_hint, infer_role_from_gguf_metadata, parse_ollama_parameters,
-    read_gguf_metadata,
 };
 use super::types::{
     LmStudioV1Response, ModelCapabilities, ModelEntry, OllamaShowResponse, OllamaTagsResponse,
@@ -40,6 +52,7 @@ pub(crate) struct DiscoveredModel {
     pub format: Option<String>,
     pub tokenizer_path: Option<String>,
     pub tokenizer_format: Option<String>,
+    pub pricing: Option<ModelPricing>,
 }
 
 impl DiscoveredModel {
@@ -71,6 +84,7 @@ impl DiscoveredModel {
             format: self.format,
             tokenizer_path: self.tokenizer_path,
             tokenizer_format: self.tokenizer_format,
+            pricing: self.pricing,
         }
     }
 }
@@ -82,19 +96,20 @@ trait InferenceDiscoveryLayer: Send + Sync {
 
 #[derive(Clone)]
 struct OllamaDiscoveryLayer {
-    client: Client,
+    client: NetClient,
     base_url: String,
 }
 
 #[derive(Clone)]
 struct LmStudioDiscoveryLayer {
-    client: Client,
+    client: NetClient,
     base_url: String,
 }
 
 #[derive(Clone)]
 struct LlamaCppDiscoveryLayer {
     models: Vec<ModelEntry>,
+    gguf_cache: GgufMetadataCache,
 }
 
 #[async_trait::async_trait]
@@ -102,7 +117,7 @@ impl InferenceDiscoveryLayer for OllamaDiscoveryLayer {
     async fn discover(&self) -> Result<Vec<DiscoveredModel>, ApiError> {
         let res = self
             .client
-            .get(format!("{}/api/tags", self.base_url))
+            .get(format!("{}/api/tags", self.base_url))?
             .send()
             .await;
         let Ok(response) = res else {
@@ -122,13 +137,16 @@ impl InferenceDiscoveryLayer for OllamaDiscoveryLayer {
 
             fetch_tasks.push(async move {
                 let show = {
-                    let res = client
-                        .post(format!("{}/api/show", base_url))
-                        .json(&serde_json::json!({ "name": model.name }))
-                        .send()
-                        .await;
+                    let res = match client.post(format!("{}/api/show", base_url)) {
+                        Ok(request) => request
+                            .json(&serde_json::json!({ "name": model.name }))
+                            .send()
+                            .await
+                            .ok(),
+                        Err(_) => None,
+                    };
                     match res {
-                        Ok(r) if r.status().is_success() => {
+                        Some(r) if r.status().is_success() => {
                             r.json::<OllamaShowResponse>().await.ok()
                         }
                         _ => None,
@@ -182,6 +200,7 @@ impl InferenceDiscoveryLayer for OllamaDiscoveryLayer {
                     format: details.format.clone(),
                     tokenizer_path: None,
                     tokenizer_format: None,
+                    pricing: None,
                 }
             });
         }
@@ -196,7 +215,7 @@ impl InferenceDiscoveryLayer for LmStudioDiscoveryLayer {
     async fn discover(&self) -> Result<Vec<DiscoveredModel>, ApiError> {
         let res = self
             .client
-            .get(format!("{}/api/v1/models", self.base_url))
+            .get(format!("{}/api/v1/models", self.base_url))?
             .send()
             .await;
 
@@ -253,6 +272,7 @@ impl InferenceDiscoveryLayer for LmStudioDiscoveryLayer {
                 format: model.format,
                 tokenizer_path: None,
                 tokenizer_format: None,
+                pricing: None,
             });
         }
 
@@ -280,7 +300,7 @@ impl InferenceDiscoveryLayer for LlamaCppDiscoveryLayer {
             let mut format = model.format.clone().or_else(|| Some("gguf".to_string()));
 
             if path.exists() && path.extension().and_then(|e| e.to_str()) == Some("gguf") {
-                if let Ok(model_info) = read_gguf_metadata(&path) {
+                if let Ok(model_info) = self.gguf_cache.read(&path) {
                     if let Some(inferred) =
                         infer_role_from_gguf_metadata(&model.filename, &model_info)
                     {
@@ -313,6 +333,7 @@ impl InferenceDiscoveryLayer for LlamaCppDiscoveryLayer {
                 architecture,
                 tokenizer_path: model.tokenizer_path.clone(),
                 tokenizer_format: model.tokenizer_format.clone(),
+                pricing: model.pricing,
                 chat_template: model.chat_template.clone(),
                 stop_tokens: model.stop_tokens.clone(),
                 default_temperature: model.default_temperature,
@@ -331,10 +352,10 @@ pub(crate) async fn refresh_ollama_models(
     config: &ConfigService,
 ) -> Result<Vec<DiscoveredModel>, ApiError> {
     let layer = OllamaDiscoveryLayer {
-        client: Client::builder()
-            .timeout(std::time::Duration::from_secs(5))
-            .build()
-            .map_err(ApiError::internal)?,
+        client: NetClient::for_provider(
+            "ollama",
+            Client::builder().timeout(std::time::Duration::from_secs(5)),
+        )?,
         base_url: get_loader_url(config, "ollama", "http://localhost:11434"),
     };
     layer.discover().await
@@ -344,31 +365,150 @@ pub(crate) async fn refresh_lmstudio_models(
     config: &ConfigService,
 ) -> Result<Vec<DiscoveredModel>, ApiError> {
     let layer = LmStudioDiscoveryLayer {
-        client: Client::builder()
-            .timeout(std::time::Duration::from_secs(5))
-            .build()
-            .map_err(ApiError::internal)?,
+        client: NetClient::for_provider(
+            "lmstudio",
+            Client::builder().timeout(std::time::Duration::from_secs(5)),
+        )?,
         base_url: get_loader_url(config, "lmstudio", "http://localhost:1234"),
     };
     layer.discover().await
 }
 
+/// `loaders.openai_compatible` に接続先かキーがあるときだけ `/v1/models` を読む。
+/// 設定がなければ何も問い合わせず空を返す。
+pub(crate) async fn refresh_openai_compatible_models(
+    config: &ConfigService,
+) -> Result<Vec<DiscoveredModel>, ApiError> {
+    refresh_cloud_models(
+        config,
+        OPENAI_COMPATIBLE_LOADER,
+        "OpenAI-compatible",
+        DEFAULT_OPENAI_BASE_URL,
+        |http, base_url, timeouts, _| {
+            Box::new(OpenAiCompatibleProvider::new(http, base_url, timeouts))
+        },
+    )
+    .await
+}
+
+/// `loaders.gemini` に接続先かキーがあるときだけ `/v1beta/models` を読む。
+pub(crate) async fn refresh_gemini_models(
+    config: &ConfigService,
+) -> Result<Vec<DiscoveredModel>, ApiError> {
+    refresh_cloud_models(
+        config,
+        GEMINI_LOADER,
+        "Gemini",
+        DEFAULT_GEMINI_BASE_URL,
+        |http, base_url, timeouts, typed| {
+            Box::new(GeminiProvider::new(
+                http,
+                base_url,
+                timeouts,
+                typed.loader_safety_settings(GEMINI_LOADER),
+            ))
+        },
+    )
+    .await
+}
+
+/// `loaders.openrouter` に接続先かキーがあるときだけ、料金付きのカタログを読む。
+pub(crate) async fn refresh_openrouter_models(
+    config: &ConfigService,
+) -> Result<Vec<DiscoveredModel>, ApiError> {
+    refresh_cloud_models(
+        config,
+        OPENROUTER_LOADER,
+        "OpenRouter",
+        DEFAULT_OPENROUTER_BASE_URL,
+        |http, base_url, timeouts, _| Box::new(OpenRouterProvider::new(http, base_url, timeouts)),
+    )
+    .await
+}
+
+/// クラウドプロバイダーのモデル一覧。問い合わせに失敗したら警告だけ出して空を返す
+/// （起動時の一括更新を止めないため）。
+async fn refresh_cloud_models(
+    config: &ConfigService,
+    loader: &str,
+    label: &str,
+    default_base_url: &str,
+    build: impl FnOnce(NetClient, &str, ProviderTimeouts, &TeporaConfig) -> Box<dyn LlmProvider>,
+) -> Result<Vec<DiscoveredModel>, ApiError> {
+    let Ok(typed) = config.load_typed() else {
+        return Ok(Vec::new());
+    };
+    if !typed.loader_configured(loader) {
+        return Ok(Vec::new());
+    }
+    let base_url = typed.loader_base_url(loader, default_base_url);
+    let http = match typed.loader_api_key(loader) {
+        Some(api_key) => authorized_client(loader, &typed.llm_manager, &api_key)?,
+        None => NetClient::for_provider(loader, tuned_client_builder(&typed.llm_manager))?,
+    };
+    let timeouts = ProviderTimeouts {
+        request: std::time::Duration::from_secs(10),
+        stream_idle: typed.llm_manager.stream_idle_timeout(),
+        stream_buffer: typed.llm_manager.stream_internal_buffer(),
+    };
+    let provider = build(http, &base_url, timeouts, &typed);
+    match provider.list_models().await {
+        Ok(models) => Ok(models
+            .into_iter()
+            .map(|model| discovered_cloud_model(loader, label, model))
+            .collect()),
+        Err(err) => {
+            tracing::warn!("{} model listing failed: {}", label, err);
+            Ok(Vec::new())
+        }
+    }
+}
+
+fn discovered_cloud_model(loader: &str, label: &str, model: ProviderModel) -> DiscoveredModel {
+    let role = if has_embedding_name_hint(&model.id) {
+        "embedding"
+    } else {
+        "text"
+    };
+    DiscoveredModel {
+        id: format!("{}-{}", loader, model.id),
+        display_name: format!("{} ({})", model.name, label),
+        role: role.to_string(),
+        file_size: 0,
+        filename: model.id.clone(),
+        source: loader.to_string(),
+        file_path: format!("{}://{}", loader, model.id),
+        loader: loader.to_string(),
+        loader_model_name: Some(model.id),
+        sha256: None,
+        parameter_size: None,
+        quantization: None,
+        context_length: (model.ctx > 0).then_some(model.ctx),
+        architecture: None,
+        chat_template: None,
+        stop_tokens: None,
+        default_temperature: None,
+        capabilities: model.capabilities,
+        publisher: None,
+        description: None,
+        format: None,
+        tokenizer_path: None,
+        tokenizer_format: None,
+        pricing: model.pricing,
+    }
+}
+
 pub(crate) async fn refresh_llama_cpp_models(
     models: Vec<ModelEntry>,
+    gguf_cache: GgufMetadataCache,
 ) -> Result<Vec<DiscoveredModel>, ApiError> {
-    let layer = LlamaCppDiscoveryLayer { models };
+    let layer = LlamaCppDiscoveryLayer { models, gguf_cache };
     layer.discover().await
 }
 
 fn get_loader_url(config: &ConfigService, loader: &str, default: &str) -> String {
-    if let Ok(config) = config.load_config() {
-        if let Some(loaders) = config.get("loaders") {
-            if let Some(loader_config) = loaders.get(loader) {
-                if let Some(url) = loader_config.get("base_url").and_then(|v| v.as_str()) {
-                    return url.trim_end_matches('/').to_string();
-                }
-            }
-        }
-    }
-    default.to_string()
+    config
+        .load_typed()
+        .map(|typed| typed.loader_base_url(loader, default))
+        .unwrap_or_else(|_| default.to_string())
 }
@@ -1,15 +1,13 @@
 use std::collections::HashSet;
 use std::fs;
-use std::io::Write;
 use std::path::{Path, PathBuf};
 
-use futures_util::StreamExt;
 use reqwest::header::HeaderMap;
-use reqwest::Client;
 use serde_json::Value;
-use sha2::{Digest, Sha256};
 
 use crate::core::errors::ApiError;
+use crate::core::net_retry::{download_with_resume, send_with_retry, RetryPolicy};
+use crate::core::network::NetClient;
 
 use super::types::ModelDownloadPolicy;
 
@@ -103,71 +101,75 @@ pub(crate) fn evaluate_download_policy_from_config(
 
 #[allow(clippy::type_complexity)]
 pub(crate) async fn download_model_file(
-    client: &Client,
+    client: &NetClient,
     url: &str,
     target_path: &Path,
     expected_sha256: Option<&str>,
     progress_cb: Option<&(dyn Fn(f32, &str) + Sync)>,
 ) -> Result<DownloadedModelFile, ApiError> {
-    let response = client
-        .get(url)
-        .send()
-        .await
-        .map_err(ApiError::internal)?
-        .error_for_status()
-        .map_err(ApiError::internal)?;
-
-    let total = response.content_length().unwrap_or(0);
-    let mut stream = response.bytes_stream();
-    let mut file = fs::File::create(target_path).map_err(ApiError::internal)?;
-    let mut downloaded: u64 = 0;
-    let mut hasher = Sha256::new();
-
-    while let Some(chunk) = stream.next().await {
-        let data = chunk.map_err(ApiError::internal)?;
-        file.write_all(&data).map_err(ApiError::internal)?;
-        hasher.update(&data);
-        downloaded += data.len() as u64;
-        if let Some(cb) = progress_cb {
-            let progress = if total > 0 {
-                downloaded as f32 / total as f32
-            } else {
-                0.0
-            };
-            cb(progress, "Downloading model...");
-        }
-    }
+    // 書き終わるまでは `.part` に置き、落ちても壊れたモデルが本体の名前で残らないようにする
+    let partial_path = partial_download_path(target_path);
+    let downloaded = download_with_resume(
+        &RetryPolicy::default(),
+        "huggingface",
+        || client.get(url),
+        &partial_path,
+        |downloaded, total| {
+            if let Some(cb) = progress_cb {
+                let progress = if total > 0 {
+                    downloaded as f32 / total as f32
+                } else {
+                    0.0
+                };
+                cb(progress, "Downloading model...");
+            }
+        },
+    )
+    .await
+    .inspect_err(|_| {
+        let _ = fs::remove_file(&partial_path);
+    })?;
 
-    let file_size = fs::metadata(target_path).map_err(ApiError::internal)?.len();
-    let actual_sha256 = hex::encode(hasher.finalize());
     if let Some(expected_hash) = normalize_sha256(expected_sha256) {
-        if actual_sha256 != expected_hash {
-            let _ = fs::remove_file(target_path);
+        if downloaded.sha256 != expected_hash {
+            let _ = fs::remove_file(&partial_path);
             return Err(ApiError::BadRequest(
                 "Downloaded file SHA256 did not match expected value".to_string(),
             ));
         }
     }
 
+    fs::rename(&partial_path, target_path).map_err(ApiError::internal)?;
+
     Ok(DownloadedModelFile {
         path: target_path.to_path_buf(),
-        file_size,
-        sha256: actual_sha256,
+        file_size: downloaded.size,
+        sha256: downloaded.sha256,
     })
 }
 
+/// ダウンロード途中のファイル名。`model.gguf` なら `model.gguf.part`。
+pub(crate) fn partial_download_path(target_path: &Path) -> PathBuf {
+    let mut name = target_path.as_os_str().to_os_string();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
 pub(crate) async fn get_remote_file_size(
-    client: &Client,
+    client: &NetClient,
     repo_id: &str,
     filename: &str,
 ) -> Result<Option<u64>, ApiError> {
     let url = hf_resolve_url(repo_id, filename, None);
-    let response = client.head(url).send().await.map_err(ApiError::internal)?;
+    let response = send_with_retry(&RetryPolicy::default(), "huggingface", || {
+        client.head(url.as_str())
+    })
+    .await?;
     Ok(content_length(response.headers()))
 }
 
 pub(crate) async fn check_update(
-    client: &Client,
+    client: &NetClient,
     repo_id: &str,
     filename: &str,
     revision: Option<&str>,
@@ -175,7 +177,10 @@ pub(crate) async fn check_update(
     current_size: Option<u64>,
 ) -> Result<Value, ApiError> {
     let url = hf_resolve_url(repo_id, filename, revision);
-    let response = client.head(url).send().await.map_err(ApiError::internal)?;
+    let response = send_with_retry(&RetryPolicy::default(), "huggingface", || {
+        client.head(url.as_str())
+    })
+    .await?;
     let headers = response.headers();
     let remote_size = content_length(headers);
     let remote_etag = headers
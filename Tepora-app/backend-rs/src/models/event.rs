@@ -16,6 +16,8 @@ pub enum AgentEventType {
     ToolCall,
     /// Actor queue saturation signal (session busy / too many sessions)
     QueueSaturated,
+    /// Rubric score for a finished agent run
+    Evaluation,
     /// When an error occurs during reasoning or tool execution
     Error,
 }
@@ -28,6 +30,7 @@ impl AgentEventType {
             Self::PromptGenerated => "prompt_generated",
             Self::ToolCall => "tool_call",
             Self::QueueSaturated => "queue_saturated",
+            Self::Evaluation => "evaluation",
             Self::Error => "error",
         }
     }
@@ -39,6 +42,7 @@ impl AgentEventType {
             "prompt_generated" => Some(Self::PromptGenerated),
             "tool_call" => Some(Self::ToolCall),
             "queue_saturated" => Some(Self::QueueSaturated),
+            "evaluation" => Some(Self::Evaluation),
             "error" => Some(Self::Error),
             _ => None,
         }
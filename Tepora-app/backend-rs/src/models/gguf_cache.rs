@@ -0,0 +1,186 @@
+//! GGUF メタデータのキャッシュ。
+//!
+//! レジストリを更新するたびに全モデルのヘッダを読み直すと、大きなモデルが
+//! 並ぶディレクトリでは起動が遅くなる。解析結果を (パス, 更新時刻, サイズ) を
+//! キーにして `models.json` の隣の JSON に残し、ファイルが変わっていなければ
+//! それを返す。
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::core::errors::ApiError;
+
+use super::metadata::read_gguf_metadata;
+
+const CACHE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct CacheEntry {
+    modified_ms: u64,
+    size: u64,
+    metadata: HashMap<String, Value>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    entries: HashMap<String, CacheEntry>,
+}
+
+#[derive(Clone)]
+pub(crate) struct GgufMetadataCache {
+    path: PathBuf,
+    /// 初回アクセスで読み込む
+    entries: Arc<Mutex<Option<HashMap<String, CacheEntry>>>>,
+}
+
+impl GgufMetadataCache {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            entries: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// キャッシュが新しければそれを、そうでなければ解析して保存した結果を返す。
+    pub(crate) fn read(&self, model_path: &Path) -> Result<HashMap<String, Value>, ApiError> {
+        let stat = fs::metadata(model_path).map_err(ApiError::internal)?;
+        let modified_ms = stat
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+        let size = stat.len();
+        let key = model_path.to_string_lossy().to_string();
+
+        let mut guard = self
+            .entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let entries = guard.get_or_insert_with(|| self.load());
+        if let Some(entry) = entries.get(&key) {
+            if entry.modified_ms == modified_ms && entry.size == size {
+                return Ok(entry.metadata.clone());
+            }
+        }
+
+        let metadata = read_gguf_metadata(model_path)?;
+        entries.insert(
+            key,
+            CacheEntry {
+                modified_ms,
+                size,
+                metadata: metadata.clone(),
+            },
+        );
+        // 消えたモデルの分はここで捨てる
+        entries.retain(|path, _| Path::new(path).exists());
+        if let Err(err) = self.save(entries) {
+            tracing::warn!("Failed to save GGUF metadata cache: {}", err);
+        }
+        Ok(metadata)
+    }
+
+    fn load(&self) -> HashMap<String, CacheEntry> {
+        let Ok(contents) = fs::read_to_string(&self.path) else {
+            return HashMap::new();
+        };
+        match serde_json::from_str::<CacheFile>(&contents) {
+            Ok(file) if file.version == CACHE_VERSION => file.entries,
+            Ok(_) => HashMap::new(),
+            Err(err) => {
+                tracing::debug!("Ignoring unreadable GGUF metadata cache: {}", err);
+                HashMap::new()
+            }
+        }
+    }
+
+    fn save(&self, entries: &HashMap<String, CacheEntry>) -> Result<(), ApiError> {
+        let file = CacheFile {
+            version: CACHE_VERSION,
+            entries: entries.clone(),
+        };
+        let data = serde_json::to_string(&file).map_err(ApiError::internal)?;
+        if let Some(parent) = self.path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        // 書きかけのファイルを次回読まないよう、一時ファイルから置き換える
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, data).map_err(ApiError::internal)?;
+        fs::rename(&tmp, &self.path).map_err(ApiError::internal)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_gguf(path: &Path, general_type: &str) {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"GGUF");
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&2u64.to_le_bytes());
+        let key = "general.type";
+        bytes.extend_from_slice(&(key.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(key.as_bytes());
+        bytes.extend_from_slice(&8u32.to_le_bytes());
+        bytes.extend_from_slice(&(general_type.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(general_type.as_bytes());
+        // 語彙表のような長い配列は読み飛ばされる
+        let key = "tokenizer.ggml.scores";
+        bytes.extend_from_slice(&(key.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(key.as_bytes());
+        bytes.extend_from_slice(&9u32.to_le_bytes());
+        bytes.extend_from_slice(&6u32.to_le_bytes());
+        bytes.extend_from_slice(&4_096u64.to_le_bytes());
+        bytes.extend(std::iter::repeat_n(0u8, 4 * 4_096));
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn cache_survives_restart_and_invalidates_on_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = dir.path().join("model.gguf");
+        let cache_path = dir.path().join("gguf_metadata_cache.json");
+        write_gguf(&model, "embedding");
+
+        let cache = GgufMetadataCache::new(cache_path.clone());
+        let metadata = cache.read(&model).unwrap();
+        assert_eq!(metadata["general.type"], "embedding");
+        assert!(!metadata.contains_key("tokenizer.ggml.scores"));
+        assert!(cache_path.exists());
+
+        // 解析せずに返せることを、中身を壊したモデルで確かめる
+        let stat = fs::metadata(&model).unwrap();
+        let mut bytes = fs::read(&model).unwrap();
+        bytes[0] = b'X';
+        fs::write(&model, &bytes).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&model)
+            .unwrap()
+            .set_modified(stat.modified().unwrap())
+            .unwrap();
+        let reopened = GgufMetadataCache::new(cache_path.clone());
+        assert_eq!(reopened.read(&model).unwrap()["general.type"], "embedding");
+
+        // サイズが変われば読み直す
+        write_gguf(&model, "text-generation");
+        let mut bytes = fs::read(&model).unwrap();
+        bytes.push(0);
+        fs::write(&model, &bytes).unwrap();
+        assert_eq!(
+            reopened.read(&model).unwrap()["general.type"],
+            "text-generation"
+        );
+    }
+}
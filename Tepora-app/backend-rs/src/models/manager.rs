@@ -2,17 +2,21 @@ use std::fs;
 use std::path::{Path, PathBuf};
 
 use chrono::Utc;
-use reqwest::Client;
 use serde_json::Value;
 
+use crate::core::config::schema::NetworkSubsystem;
 use crate::core::config::{AppPaths, ConfigService};
 use crate::core::errors::ApiError;
+use crate::core::network::NetClient;
+use crate::llm::gemini_provider::GEMINI_LOADER;
+use crate::llm::openai_compatible_provider::OPENAI_COMPATIBLE_LOADER;
+use crate::llm::openrouter_provider::OPENROUTER_LOADER;
 
 use super::discovery;
 use super::download;
 use super::metadata::{
     extract_architecture_from_model_info, extract_context_length, infer_role_from_gguf_metadata,
-    read_gguf_metadata, sanitize_model_filename,
+    sanitize_model_filename,
 };
 use super::registry::ModelRegistryStore;
 use super::selection;
@@ -22,7 +26,7 @@ use super::types::{ModelDownloadPolicy, ModelDownloadResult, ModelEntry, ModelRe
 pub struct ModelManager {
     paths: AppPaths,
     config: ConfigService,
-    client: Client,
+    client: NetClient,
     store: ModelRegistryStore,
 }
 
@@ -32,7 +36,7 @@ impl ModelManager {
         Self {
             paths: paths.clone(),
             config,
-            client: Client::new(),
+            client: NetClient::new(NetworkSubsystem::Models),
             store,
         }
     }
@@ -80,7 +84,7 @@ impl ModelManager {
             .and_then(|v| v.to_str())
             .unwrap_or_default()
             .to_string();
-        let gguf_model_info = read_gguf_metadata(file_path).ok();
+        let gguf_model_info = self.store.gguf_cache().read(file_path).ok();
         let inferred_role = gguf_model_info
             .as_ref()
             .and_then(|info| infer_role_from_gguf_metadata(&filename, info))
@@ -124,6 +128,7 @@ impl ModelManager {
             format: Some("gguf".to_string()),
             tokenizer_path: None,
             tokenizer_format: None,
+            pricing: None,
         };
 
         self.store.insert_model(entry)
@@ -252,6 +257,20 @@ impl ModelManager {
         .await
     }
 
+    /// 設定の `model_roles`（プロファイルで上書きできる）を割り当てに重ねたレジストリ。
+    /// 登録されていないモデル ID は無視する。
+    fn load_registry_for_resolution(&self) -> Result<ModelRegistry, ApiError> {
+        let mut registry = self.store.load()?;
+        if let Ok(typed) = self.config.load_typed() {
+            for (assignment_key, model_id) in typed.model_roles {
+                if registry.models.iter().any(|model| model.id == model_id) {
+                    registry.role_assignments.insert(assignment_key, model_id);
+                }
+            }
+        }
+        Ok(registry)
+    }
+
     pub fn set_assignment_model(
         &self,
         assignment_key: &str,
@@ -264,7 +283,7 @@ impl ModelManager {
         &self,
         assignment_key: &str,
     ) -> Result<Option<ModelEntry>, ApiError> {
-        let registry = self.store.load()?;
+        let registry = self.load_registry_for_resolution()?;
         selection::resolve_assignment_model_from_registry(&registry, assignment_key)
     }
 
@@ -272,7 +291,7 @@ impl ModelManager {
         &self,
         assignment_key: &str,
     ) -> Result<Option<String>, ApiError> {
-        let registry = self.store.load()?;
+        let registry = self.load_registry_for_resolution()?;
         selection::resolve_assignment_model_id_from_registry(&registry, assignment_key)
     }
 
@@ -280,7 +299,7 @@ impl ModelManager {
         &self,
         active_character_id: Option<&str>,
     ) -> Result<Option<ModelEntry>, ApiError> {
-        let registry = self.store.load()?;
+        let registry = self.load_registry_for_resolution()?;
         Ok(selection::resolve_character_model(
             &registry,
             active_character_id,
@@ -291,7 +310,7 @@ impl ModelManager {
         &self,
         active_character_id: Option<&str>,
     ) -> Result<Option<String>, ApiError> {
-        let registry = self.store.load()?;
+        let registry = self.load_registry_for_resolution()?;
         Ok(selection::resolve_character_model_id_from_registry(
             &registry,
             active_character_id,
@@ -302,19 +321,19 @@ impl ModelManager {
         &self,
         agent_id: Option<&str>,
     ) -> Result<Option<String>, ApiError> {
-        let registry = self.store.load()?;
+        let registry = self.load_registry_for_resolution()?;
         Ok(selection::resolve_agent_model_id_from_registry(
             &registry, agent_id,
         ))
     }
 
     pub fn resolve_embedding_model(&self) -> Result<Option<ModelEntry>, ApiError> {
-        let registry = self.store.load()?;
+        let registry = self.load_registry_for_resolution()?;
         Ok(selection::resolve_embedding_model(&registry))
     }
 
     pub fn resolve_embedding_model_id(&self) -> Result<Option<String>, ApiError> {
-        let registry = self.store.load()?;
+        let registry = self.load_registry_for_resolution()?;
         Ok(selection::resolve_embedding_model_id_from_registry(
             &registry,
         ))
@@ -352,6 +371,9 @@ impl ModelManager {
         count += self.refresh_llama_cpp_models().await?;
         count += self.refresh_ollama_models().await?;
         count += self.refresh_lmstudio_models().await?;
+        count += self.refresh_openai_compatible_models().await?;
+        count += self.refresh_gemini_models().await?;
+        count += self.refresh_openrouter_models().await?;
         Ok(count)
     }
 
@@ -365,12 +387,39 @@ impl ModelManager {
         self.store.apply_discovered_models("lmstudio", discovered)
     }
 
+    pub async fn refresh_openai_compatible_models(&self) -> Result<usize, ApiError> {
+        let discovered = discovery::refresh_openai_compatible_models(&self.config).await?;
+        self.store
+            .apply_discovered_models(OPENAI_COMPATIBLE_LOADER, discovered)
+    }
+
+    pub async fn refresh_gemini_models(&self) -> Result<usize, ApiError> {
+        let discovered = discovery::refresh_gemini_models(&self.config).await?;
+        self.store
+            .apply_discovered_models(GEMINI_LOADER, discovered)
+    }
+
+    pub async fn refresh_openrouter_models(&self) -> Result<usize, ApiError> {
+        let discovered = discovery::refresh_openrouter_models(&self.config).await?;
+        self.store
+            .apply_discovered_models(OPENROUTER_LOADER, discovered)
+    }
+
     pub async fn refresh_llama_cpp_models(&self) -> Result<usize, ApiError> {
         let registry = self.store.load()?;
-        let discovered = discovery::refresh_llama_cpp_models(registry.models).await?;
+        let discovered =
+            discovery::refresh_llama_cpp_models(registry.models, self.store.gguf_cache().clone())
+                .await?;
         self.store.apply_discovered_models("llama_cpp", discovered)
     }
 
+    /// `download_from_huggingface` が書きかけを置く `.part` のパス。
+    pub fn partial_download_path(&self, role: &str, filename: &str) -> Option<PathBuf> {
+        self.model_storage_path(role, filename)
+            .ok()
+            .map(|path| download::partial_download_path(&path))
+    }
+
     fn model_storage_path(&self, role: &str, filename: &str) -> Result<PathBuf, ApiError> {
         let safe_role = role.to_lowercase();
         let base = self.paths.user_data_dir.join("models").join(safe_role);
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use std::fs;
-use std::io::Read;
+use std::io::{BufReader, Read, Seek};
 use std::path::Path;
 
 use serde_json::Value;
@@ -127,17 +127,30 @@ pub(crate) fn determine_ollama_role(
     }
 }
 
+/// メタデータ部の読み取りに使うバッファ。ヘッダは先頭付近にまとまっている
+const GGUF_READ_BUFFER: usize = 64 * 1024;
+/// これより長い配列（語彙表など）は読み飛ばして結果に含めない
+const MAX_RETAINED_ARRAY_LEN: u64 = 1_024;
+/// 要素数がこれを超える配列は壊れたファイルとして扱う
+const MAX_ARRAY_LEN: u64 = 16_000_000;
+
+/// GGUF のメタデータを読む。テンソル本体には触れず、大きな配列はシークで
+/// 読み飛ばすので、数 GB のモデルでも読むのはヘッダ付近の数 MB に収まる。
 pub(crate) fn read_gguf_metadata(path: &Path) -> Result<HashMap<String, Value>, ApiError> {
-    let mut file = fs::File::open(path).map_err(ApiError::internal)?;
+    let file = fs::File::open(path).map_err(ApiError::internal)?;
+    parse_gguf_metadata(BufReader::with_capacity(GGUF_READ_BUFFER, file))
+}
+
+fn parse_gguf_metadata<R: Read + Seek>(mut reader: R) -> Result<HashMap<String, Value>, ApiError> {
     let mut magic = [0u8; 4];
-    file.read_exact(&mut magic).map_err(ApiError::internal)?;
+    reader.read_exact(&mut magic).map_err(ApiError::internal)?;
     if &magic != b"GGUF" {
         return Err(ApiError::BadRequest(
             "Invalid GGUF magic header".to_string(),
         ));
     }
 
-    let version = read_u32_le(&mut file)?;
+    let version = read_u32_le(&mut reader)?;
     if !(1..=3).contains(&version) {
         return Err(ApiError::BadRequest(format!(
             "Unsupported GGUF version: {}",
@@ -145,20 +158,101 @@ pub(crate) fn read_gguf_metadata(path: &Path) -> Result<HashMap<String, Value>,
         )));
     }
 
-    let _ = read_gguf_count(&mut file, version)?;
-    let kv_count = read_gguf_count(&mut file, version)?;
+    let _ = read_gguf_count(&mut reader, version)?;
+    let kv_count = read_gguf_count(&mut reader, version)?;
 
     let mut model_info = HashMap::new();
     for _ in 0..kv_count {
-        let key = read_gguf_string(&mut file, version)?;
-        let value_type = read_u32_le(&mut file)?;
-        let value = read_gguf_value(&mut file, version, value_type)?;
-        model_info.insert(key, value);
+        let key = read_gguf_string(&mut reader, version)?;
+        let value_type = read_u32_le(&mut reader)?;
+        if let Some(value) = read_gguf_entry(&mut reader, version, value_type)? {
+            model_info.insert(key, value);
+        }
     }
 
     Ok(model_info)
 }
 
+/// 値を読む。長い配列は読み飛ばして `None` を返す。
+fn read_gguf_entry<R: Read + Seek>(
+    reader: &mut R,
+    version: u32,
+    value_type: u32,
+) -> Result<Option<Value>, ApiError> {
+    if value_type != 9 {
+        return read_gguf_value(reader, version, value_type).map(Some);
+    }
+    let array_type = read_u32_le(reader)?;
+    let len = read_gguf_count(reader, version)?;
+    if len > MAX_ARRAY_LEN {
+        return Err(ApiError::BadRequest(
+            "GGUF array length is too large".to_string(),
+        ));
+    }
+    if len > MAX_RETAINED_ARRAY_LEN {
+        skip_gguf_array(reader, version, array_type, len)?;
+        return Ok(None);
+    }
+    let mut values = Vec::with_capacity(len as usize);
+    for _ in 0..len {
+        values.push(read_gguf_value(reader, version, array_type)?);
+    }
+    Ok(Some(Value::Array(values)))
+}
+
+/// 読み飛ばしは `seek_relative` で行う。`BufReader::seek` はバッファを捨てるので、
+/// 短い文字列が並ぶ語彙表では 1 要素ごとに読み直しになる。
+fn skip_gguf_array<R: Read + Seek>(
+    reader: &mut R,
+    version: u32,
+    array_type: u32,
+    len: u64,
+) -> Result<(), ApiError> {
+    if let Some(width) = gguf_scalar_width(array_type) {
+        let bytes = width
+            .checked_mul(len)
+            .and_then(|bytes| i64::try_from(bytes).ok())
+            .ok_or_else(|| ApiError::BadRequest("GGUF array is too large".to_string()))?;
+        return reader.seek_relative(bytes).map_err(ApiError::internal);
+    }
+    for _ in 0..len {
+        match array_type {
+            8 => {
+                let str_len = read_gguf_count(reader, version)?;
+                let bytes = i64::try_from(str_len).map_err(ApiError::internal)?;
+                reader.seek_relative(bytes).map_err(ApiError::internal)?;
+            }
+            9 => {
+                let nested_type = read_u32_le(reader)?;
+                let nested_len = read_gguf_count(reader, version)?;
+                if nested_len > MAX_ARRAY_LEN {
+                    return Err(ApiError::BadRequest(
+                        "GGUF array length is too large".to_string(),
+                    ));
+                }
+                skip_gguf_array(reader, version, nested_type, nested_len)?;
+            }
+            other => {
+                return Err(ApiError::BadRequest(format!(
+                    "Unsupported GGUF value type: {}",
+                    other
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn gguf_scalar_width(value_type: u32) -> Option<u64> {
+    match value_type {
+        0 | 1 | 7 => Some(1),
+        2..=3 => Some(2),
+        4..=6 => Some(4),
+        10..=12 => Some(8),
+        _ => None,
+    }
+}
+
 fn read_gguf_count<R: Read>(reader: &mut R, version: u32) -> Result<u64, ApiError> {
     if version == 1 {
         Ok(read_u32_le(reader)? as u64)
@@ -427,19 +521,19 @@ mod tests {
         assert_eq!(role, Some("embedding".to_string()));
     }
 
+    fn push_u32(buf: &mut Vec<u8>, v: u32) {
+        buf.extend_from_slice(&v.to_le_bytes());
+    }
+    fn push_u64(buf: &mut Vec<u8>, v: u64) {
+        buf.extend_from_slice(&v.to_le_bytes());
+    }
+    fn push_gguf_string(buf: &mut Vec<u8>, s: &str) {
+        push_u64(buf, s.len() as u64);
+        buf.extend_from_slice(s.as_bytes());
+    }
+
     #[test]
     fn read_gguf_metadata_parses_basic_string_entry() {
-        fn push_u32(buf: &mut Vec<u8>, v: u32) {
-            buf.extend_from_slice(&v.to_le_bytes());
-        }
-        fn push_u64(buf: &mut Vec<u8>, v: u64) {
-            buf.extend_from_slice(&v.to_le_bytes());
-        }
-        fn push_gguf_string(buf: &mut Vec<u8>, s: &str) {
-            push_u64(buf, s.len() as u64);
-            buf.extend_from_slice(s.as_bytes());
-        }
-
         let mut bytes = Vec::new();
         bytes.extend_from_slice(b"GGUF");
         push_u32(&mut bytes, 3);
@@ -463,6 +557,88 @@ mod tests {
         );
     }
 
+    /// 下のリーダーに届いた読み取り・シークの回数を数える
+    struct CountingReader<R> {
+        inner: R,
+        reads: usize,
+        seeks: usize,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reads += 1;
+            self.inner.read(buf)
+        }
+    }
+
+    impl<R: Seek> Seek for CountingReader<R> {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.seeks += 1;
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn skipping_a_large_string_array_reads_through_the_buffer() {
+        const TOKENS: usize = 50_000;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"GGUF");
+        push_u32(&mut bytes, 3);
+        push_u64(&mut bytes, 0);
+        push_u64(&mut bytes, 2);
+        push_gguf_string(&mut bytes, "tokenizer.ggml.tokens");
+        push_u32(&mut bytes, 9);
+        push_u32(&mut bytes, 8);
+        push_u64(&mut bytes, TOKENS as u64);
+        for i in 0..TOKENS {
+            push_gguf_string(&mut bytes, &format!("tok{i}"));
+        }
+        push_gguf_string(&mut bytes, "general.architecture");
+        push_u32(&mut bytes, 8);
+        push_gguf_string(&mut bytes, "llama");
+
+        let total = bytes.len();
+        let mut counting = CountingReader {
+            inner: std::io::Cursor::new(bytes),
+            reads: 0,
+            seeks: 0,
+        };
+        let metadata =
+            parse_gguf_metadata(BufReader::with_capacity(GGUF_READ_BUFFER, &mut counting))
+                .expect("metadata should parse");
+
+        assert_eq!(metadata["general.architecture"], "llama");
+        assert!(!metadata.contains_key("tokenizer.ggml.tokens"));
+        // 1 要素ごとに読み直していれば読み取りは要素数だけ増える
+        let refills = total / GGUF_READ_BUFFER + 2;
+        assert!(
+            counting.reads + counting.seeks <= refills * 2,
+            "reads={} seeks={} for {} bytes",
+            counting.reads,
+            counting.seeks,
+            total
+        );
+    }
+
+    #[test]
+    fn oversized_nested_arrays_are_rejected_while_skipping() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"GGUF");
+        push_u32(&mut bytes, 3);
+        push_u64(&mut bytes, 0);
+        push_u64(&mut bytes, 1);
+        push_gguf_string(&mut bytes, "tokenizer.ggml.merges");
+        push_u32(&mut bytes, 9);
+        push_u32(&mut bytes, 9);
+        push_u64(&mut bytes, MAX_RETAINED_ARRAY_LEN + 1);
+        // u64 要素 2^61 個ぶんのバイト数は u64 に収まらない
+        push_u32(&mut bytes, 10);
+        push_u64(&mut bytes, 1 << 61);
+
+        let err = parse_gguf_metadata(std::io::Cursor::new(bytes)).unwrap_err();
+        assert!(err.to_string().contains("too large"), "{}", err);
+    }
+
     #[test]
     fn extract_context_length_uses_arch_specific_key() {
         let mut info = HashMap::new();
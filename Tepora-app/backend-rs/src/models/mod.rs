@@ -1,6 +1,7 @@
 pub(crate) mod discovery;
 pub(crate) mod download;
 pub mod event;
+pub(crate) mod gguf_cache;
 pub mod manager;
 pub(crate) mod metadata;
 pub(crate) mod registry;
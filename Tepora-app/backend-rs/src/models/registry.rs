@@ -5,23 +5,36 @@ use chrono::Utc;
 
 use crate::core::config::AppPaths;
 use crate::core::errors::ApiError;
+use crate::core::snapshots::write_with_snapshot;
 
 use super::discovery::DiscoveredModel;
+use super::gguf_cache::GgufMetadataCache;
 use super::metadata::{
     extract_architecture_from_model_info, extract_context_length, has_embedding_name_hint,
-    infer_role_from_gguf_metadata, read_gguf_metadata,
+    infer_role_from_gguf_metadata,
 };
 use super::selection::validate_assignment_role;
 use super::types::{ModelEntry, ModelRegistry};
 
+const GGUF_METADATA_CACHE_FILE: &str = "gguf_metadata_cache.json";
+
 #[derive(Clone)]
 pub(crate) struct ModelRegistryStore {
     path: PathBuf,
+    gguf_cache: GgufMetadataCache,
 }
 
 impl ModelRegistryStore {
     pub(crate) fn new(path: PathBuf) -> Self {
-        Self { path }
+        let cache_path = path.with_file_name(GGUF_METADATA_CACHE_FILE);
+        Self {
+            path,
+            gguf_cache: GgufMetadataCache::new(cache_path),
+        }
+    }
+
+    pub(crate) fn gguf_cache(&self) -> &GgufMetadataCache {
+        &self.gguf_cache
     }
 
     pub(crate) fn load(&self) -> Result<ModelRegistry, ApiError> {
@@ -42,11 +55,7 @@ impl ModelRegistryStore {
 
     pub(crate) fn save(&self, registry: &ModelRegistry) -> Result<(), ApiError> {
         let data = serde_json::to_string_pretty(registry).map_err(ApiError::internal)?;
-        if let Some(parent) = self.path.parent() {
-            let _ = fs::create_dir_all(parent);
-        }
-        fs::write(&self.path, data).map_err(ApiError::internal)?;
-        Ok(())
+        write_with_snapshot(&self.path, &data)
     }
 
     pub(crate) fn list_models(&self) -> Result<Vec<ModelEntry>, ApiError> {
@@ -83,7 +92,7 @@ impl ModelRegistryStore {
     ) -> Result<ModelEntry, ApiError> {
         let mut registry = self.load()?;
         let file_path_str = path.to_string_lossy().to_string();
-        let gguf_model_info = read_gguf_metadata(path).ok();
+        let gguf_model_info = self.gguf_cache.read(path).ok();
         let effective_role = gguf_model_info
             .as_ref()
             .and_then(|info| infer_role_from_gguf_metadata(filename, info))
@@ -141,6 +150,7 @@ impl ModelRegistryStore {
             format: Some("gguf".to_string()),
             tokenizer_path: None,
             tokenizer_format: None,
+            pricing: None,
         };
 
         registry.models.push(entry.clone());
@@ -274,7 +284,8 @@ impl ModelRegistryStore {
                     || existing.capabilities != discovered_model.capabilities
                     || existing.publisher != discovered_model.publisher
                     || existing.description != discovered_model.description
-                    || existing.format != discovered_model.format;
+                    || existing.format != discovered_model.format
+                    || existing.pricing != discovered_model.pricing;
 
                 existing.display_name = discovered_model.display_name;
                 existing.role = discovered_model.role;
@@ -296,6 +307,7 @@ impl ModelRegistryStore {
                 existing.publisher = discovered_model.publisher;
                 existing.description = discovered_model.description;
                 existing.format = discovered_model.format;
+                existing.pricing = discovered_model.pricing;
 
                 if changed {
                     count += 1;
@@ -405,6 +417,7 @@ mod tests {
             format: Some("gguf".to_string()),
             tokenizer_path: None,
             tokenizer_format: None,
+            pricing: None,
         }
     }
 
@@ -491,6 +504,7 @@ mod tests {
                     format: Some("gguf".to_string()),
                     tokenizer_path: None,
                     tokenizer_format: None,
+                    pricing: None,
                 }],
             )
             .expect("apply discovered");
@@ -540,6 +554,7 @@ mod tests {
                         format: Some("gguf".to_string()),
                         tokenizer_path: None,
                         tokenizer_format: None,
+                        pricing: None,
                     },
                     ModelEntry {
                         id: "b".to_string(),
@@ -568,6 +583,7 @@ mod tests {
                         format: Some("gguf".to_string()),
                         tokenizer_path: None,
                         tokenizer_format: None,
+                        pricing: None,
                     },
                 ],
                 ..Default::default()
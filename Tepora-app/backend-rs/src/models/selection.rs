@@ -269,6 +269,7 @@ mod tests {
             format: Some("gguf".to_string()),
             tokenizer_path: None,
             tokenizer_format: None,
+            pricing: None,
         }
     }
 
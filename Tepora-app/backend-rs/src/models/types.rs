@@ -3,6 +3,8 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
+use crate::core::config::schema::ModelPricing;
+
 // ---------------------------------------------------------------------------
 // Ollama API types
 // ---------------------------------------------------------------------------
@@ -167,6 +169,10 @@ pub struct ModelEntry {
     pub tokenizer_path: Option<String>,
     #[serde(default)]
     pub tokenizer_format: Option<String>,
+
+    // --- 料金（USD / 100 万トークン。カタログに載っているクラウドモデルだけ） ---
+    #[serde(default)]
+    pub pricing: Option<ModelPricing>,
 }
 
 /// モデルレジストリ（models.json ルート）
@@ -236,6 +242,11 @@ pub struct ModelRuntimeConfig {
     pub penalize_nl: Option<bool>,
     pub n_keep: Option<i32>,
     pub cache_prompt: Option<bool>,
+    #[serde(default)]
+    pub cache_key: Option<String>,
+    /// 出力を縛る JSON Schema（llama-server が文法に直す）。ツール呼び出しで使う
+    #[serde(default)]
+    pub json_schema: Option<serde_json::Value>,
 }
 
 impl ModelRuntimeConfig {
@@ -337,6 +348,8 @@ impl ModelRuntimeConfig {
             penalize_nl: read_config_bool(model_cfg, llm_defaults, "penalize_nl"),
             n_keep: read_config_i32(model_cfg, llm_defaults, "n_keep"),
             cache_prompt: read_config_bool(model_cfg, llm_defaults, "cache_prompt"),
+            cache_key: None,
+            json_schema: None,
         })
     }
 }
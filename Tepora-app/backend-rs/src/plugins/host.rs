@@ -0,0 +1,305 @@
+//! wasmtime 上でプラグインモジュールを動かすホスト側。
+//!
+//! 呼び出しのたびに新しい `Store` を作るので、呼び出し間で状態は残らない
+//! （残したい場合は `storage` 権限のデータディレクトリを使う）。fuel とメモリ上限で
+//! 暴走を止め、WASI は引数・環境変数なし、標準入出力なしで渡す。
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+/// 1 回の呼び出しに与える資源
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(feature = "plugins"), allow(dead_code))]
+pub struct HostLimits {
+    pub fuel: u64,
+    pub max_memory_bytes: usize,
+}
+
+pub const HOST_AVAILABLE: bool = cfg!(feature = "plugins");
+/// ゲストが返す JSON の上限
+#[cfg_attr(not(feature = "plugins"), allow(dead_code))]
+const MAX_RESULT_BYTES: usize = 16 * 1024 * 1024;
+/// `tepora.log` 1 回分の上限。超えた分は切り捨てる
+#[cfg_attr(not(feature = "plugins"), allow(dead_code))]
+const MAX_LOG_BYTES: usize = 64 * 1024;
+
+#[cfg(feature = "plugins")]
+mod enabled {
+    use super::*;
+
+    use wasmtime::{
+        Caller, Config, Engine, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder,
+    };
+    use wasmtime_wasi::p1::{self, WasiP1Ctx};
+    use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};
+
+    const GUEST_DATA_DIR: &str = "/data";
+
+    struct HostCtx {
+        wasi: WasiP1Ctx,
+        limits: StoreLimits,
+        plugin_id: String,
+    }
+
+    pub struct PluginHost {
+        engine: Engine,
+        module: Module,
+        linker: Linker<HostCtx>,
+        limits: HostLimits,
+        storage_dir: Option<PathBuf>,
+        plugin_id: String,
+    }
+
+    impl PluginHost {
+        pub fn load(
+            plugin_id: &str,
+            module_path: &Path,
+            limits: HostLimits,
+            storage_dir: Option<PathBuf>,
+        ) -> Result<Self, String> {
+            let mut config = Config::new();
+            config.consume_fuel(true);
+            config.max_wasm_stack(512 * 1024);
+            let engine =
+                Engine::new(&config).map_err(|e| format!("failed to create engine: {e}"))?;
+            let module = Module::from_file(&engine, module_path).map_err(|e| {
+                format!(
+                    "failed to load plugin module '{}': {e}",
+                    module_path.display()
+                )
+            })?;
+
+            let mut linker = Linker::<HostCtx>::new(&engine);
+            p1::add_to_linker_sync(&mut linker, |ctx: &mut HostCtx| &mut ctx.wasi)
+                .map_err(|e| format!("failed to configure WASI: {e}"))?;
+            linker
+                .func_wrap(
+                    "tepora",
+                    "log",
+                    |mut caller: Caller<'_, HostCtx>, level: i32, ptr: i32, len: i32| {
+                        let message = read_guest_string(&mut caller, ptr, len).unwrap_or_default();
+                        let plugin_id = caller.data().plugin_id.clone();
+                        match level {
+                            0 => tracing::debug!(plugin = %plugin_id, "{}", message),
+                            1 => tracing::info!(plugin = %plugin_id, "{}", message),
+                            _ => tracing::warn!(plugin = %plugin_id, "{}", message),
+                        }
+                    },
+                )
+                .map_err(|e| format!("failed to define host API: {e}"))?;
+
+            Ok(Self {
+                engine,
+                module,
+                linker,
+                limits,
+                storage_dir,
+                plugin_id: plugin_id.to_string(),
+            })
+        }
+
+        pub fn register(&self) -> Result<Value, String> {
+            let (mut store, instance) = self.instantiate()?;
+            let register = instance
+                .get_typed_func::<(), i64>(&mut store, "tepora_register")
+                .map_err(|e| format!("missing export 'tepora_register': {e}"))?;
+            let packed = register
+                .call(&mut store, ())
+                .map_err(|e| format!("tepora_register failed: {e}"))?;
+            read_json_result(&mut store, &instance, packed)
+        }
+
+        pub fn invoke(&self, request: &Value) -> Result<Value, String> {
+            let (mut store, instance) = self.instantiate()?;
+            let payload = serde_json::to_vec(request).map_err(|e| e.to_string())?;
+            let alloc = instance
+                .get_typed_func::<i32, i32>(&mut store, "tepora_alloc")
+                .map_err(|e| format!("missing export 'tepora_alloc': {e}"))?;
+            let invoke = instance
+                .get_typed_func::<(i32, i32), i64>(&mut store, "tepora_invoke")
+                .map_err(|e| format!("missing export 'tepora_invoke': {e}"))?;
+            let memory = instance
+                .get_memory(&mut store, "memory")
+                .ok_or_else(|| "missing export 'memory'".to_string())?;
+
+            let len = i32::try_from(payload.len()).map_err(|_| "request too large".to_string())?;
+            let ptr = alloc
+                .call(&mut store, len)
+                .map_err(|e| format!("tepora_alloc failed: {e}"))?;
+            memory
+                .write(&mut store, ptr as u32 as usize, &payload)
+                .map_err(|e| format!("failed to write request: {e}"))?;
+            let packed = invoke
+                .call(&mut store, (ptr, len))
+                .map_err(|e| format!("tepora_invoke failed: {e}"))?;
+            read_json_result(&mut store, &instance, packed)
+        }
+
+        fn instantiate(&self) -> Result<(Store<HostCtx>, Instance), String> {
+            let mut wasi = WasiCtxBuilder::new();
+            if let Some(dir) = &self.storage_dir {
+                std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                wasi.preopened_dir(dir, GUEST_DATA_DIR, DirPerms::all(), FilePerms::all())
+                    .map_err(|e| format!("failed to mount plugin data dir: {e}"))?;
+            }
+            let ctx = HostCtx {
+                wasi: wasi.build_p1(),
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(self.limits.max_memory_bytes)
+                    .instances(1)
+                    .build(),
+                plugin_id: self.plugin_id.clone(),
+            };
+            let mut store = Store::new(&self.engine, ctx);
+            store.limiter(|ctx| &mut ctx.limits);
+            store
+                .set_fuel(self.limits.fuel)
+                .map_err(|e| format!("failed to set fuel: {e}"))?;
+            let instance = self
+                .linker
+                .instantiate(&mut store, &self.module)
+                .map_err(|e| format!("failed to instantiate plugin: {e}"))?;
+            // WASI reactor として作られたモジュールは初期化関数を持つ
+            if let Ok(init) = instance.get_typed_func::<(), ()>(&mut store, "_initialize") {
+                init.call(&mut store, ())
+                    .map_err(|e| format!("_initialize failed: {e}"))?;
+            }
+            Ok((store, instance))
+        }
+    }
+
+    fn read_guest_string(caller: &mut Caller<'_, HostCtx>, ptr: i32, len: i32) -> Option<String> {
+        let memory = caller.get_export("memory")?.into_memory()?;
+        let data = memory.data(&*caller);
+        let len = (len.max(0) as usize).min(MAX_LOG_BYTES);
+        let range = super::guest_range(ptr as u32 as usize, len, data.len(), MAX_LOG_BYTES).ok()?;
+        Some(String::from_utf8_lossy(&data[range]).into_owned())
+    }
+
+    fn read_json_result(
+        store: &mut Store<HostCtx>,
+        instance: &Instance,
+        packed: i64,
+    ) -> Result<Value, String> {
+        let (ptr, len) = super::unpack(packed);
+        let memory = instance
+            .get_memory(&mut *store, "memory")
+            .ok_or_else(|| "missing export 'memory'".to_string())?;
+        let data = memory.data(&*store);
+        let range = super::guest_range(ptr, len, data.len(), MAX_RESULT_BYTES)?;
+        serde_json::from_slice(&data[range])
+            .map_err(|e| format!("plugin returned invalid JSON: {e}"))
+    }
+}
+
+#[cfg(feature = "plugins")]
+pub use enabled::PluginHost;
+
+#[cfg(not(feature = "plugins"))]
+pub struct PluginHost;
+
+#[cfg(not(feature = "plugins"))]
+impl PluginHost {
+    pub fn load(
+        _plugin_id: &str,
+        _module_path: &Path,
+        _limits: HostLimits,
+        _storage_dir: Option<PathBuf>,
+    ) -> Result<Self, String> {
+        Err("WASM plugins require a backend build with '--features plugins'".to_string())
+    }
+
+    pub fn register(&self) -> Result<Value, String> {
+        unreachable!("PluginHost cannot be constructed without the plugins feature")
+    }
+
+    pub fn invoke(&self, _request: &Value) -> Result<Value, String> {
+        unreachable!("PluginHost cannot be constructed without the plugins feature")
+    }
+}
+
+/// 戻り値の i64 は上位 32 ビットがポインタ、下位 32 ビットが長さ。
+#[cfg_attr(not(feature = "plugins"), allow(dead_code))]
+fn unpack(packed: i64) -> (usize, usize) {
+    let packed = packed as u64;
+    ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize)
+}
+
+/// ゲストが申告した位置と長さを、読む前にメモリの大きさと上限で確かめる。
+/// 長さはゲストが好きに返せるので、そのまま確保に使わない。
+#[cfg_attr(not(feature = "plugins"), allow(dead_code))]
+fn guest_range(
+    ptr: usize,
+    len: usize,
+    memory_size: usize,
+    max: usize,
+) -> Result<Range<usize>, String> {
+    if len > max {
+        return Err(format!(
+            "plugin result is too large ({len} bytes, limit {max})"
+        ));
+    }
+    ptr.checked_add(len)
+        .filter(|end| *end <= memory_size)
+        .map(|end| ptr..end)
+        .ok_or_else(|| "plugin result points outside its memory".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_results_split_into_pointer_and_length() {
+        let packed = ((1024u64 << 32) | 77) as i64;
+        assert_eq!(unpack(packed), (1024, 77));
+    }
+
+    #[test]
+    fn guest_ranges_are_checked_before_reading() {
+        assert_eq!(guest_range(16, 8, 64, 32), Ok(16..24));
+        assert!(guest_range(0, 33, 64, 32).is_err());
+        assert!(guest_range(60, 8, 64, 32).is_err());
+        assert!(guest_range(usize::MAX, 1, 64, 32).is_err());
+    }
+
+    #[cfg(feature = "plugins")]
+    #[test]
+    fn module_registers_and_answers_invocations() {
+        let register = r#"{"tools":[{"name":"echo"}]}"#;
+        let reply = r#"{"ok":true,"output":"pong"}"#;
+        let wat = format!(
+            r#"(module
+                (import "tepora" "log" (func $log (param i32 i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{register}")
+                (data (i32.const 512) "{reply}")
+                (func (export "tepora_alloc") (param i32) (result i32) (i32.const 4096))
+                (func (export "tepora_register") (result i64)
+                    (i64.const {register_len}))
+                (func (export "tepora_invoke") (param i32 i32) (result i64)
+                    (call $log (i32.const 1) (local.get 0) (local.get 1))
+                    (i64.or (i64.shl (i64.const 512) (i64.const 32)) (i64.const {reply_len}))))"#,
+            register = register.replace('"', "\\\""),
+            reply = reply.replace('"', "\\\""),
+            register_len = register.len(),
+            reply_len = reply.len(),
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plugin.wat");
+        std::fs::write(&path, wat).unwrap();
+
+        let limits = HostLimits {
+            fuel: 10_000_000,
+            max_memory_bytes: 16 * 1024 * 1024,
+        };
+        let host = PluginHost::load("echo", &path, limits, None).unwrap();
+        assert_eq!(host.register().unwrap()["tools"][0]["name"], "echo");
+        let response = host
+            .invoke(&serde_json::json!({"kind": "tool", "name": "echo", "input": {}}))
+            .unwrap();
+        assert_eq!(response["output"], "pong");
+    }
+}
@@ -0,0 +1,192 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::core::config::schema::PluginCapability;
+use crate::core::errors::ApiError;
+
+pub const MANIFEST_FILE: &str = "plugin.json";
+const MAX_ID_LEN: usize = 48;
+
+/// `plugins/<dir>/plugin.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// マニフェストからの相対パス
+    #[serde(default = "default_module")]
+    pub module: String,
+    /// プラグインが要求する権限。付与されるかはユーザー次第。
+    #[serde(default)]
+    pub capabilities: Vec<PluginCapability>,
+}
+
+fn default_module() -> String {
+    "plugin.wasm".to_string()
+}
+
+impl PluginManifest {
+    pub fn load(dir: &Path) -> Result<Self, ApiError> {
+        let raw = std::fs::read_to_string(dir.join(MANIFEST_FILE)).map_err(ApiError::internal)?;
+        let manifest: PluginManifest = serde_json::from_str(&raw)
+            .map_err(|err| ApiError::BadRequest(format!("Invalid plugin manifest: {}", err)))?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<(), ApiError> {
+        if !is_valid_plugin_id(&self.id) {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid plugin id '{}': use up to {} lowercase letters, digits, '-' or '_'",
+                self.id, MAX_ID_LEN
+            )));
+        }
+        let module = Path::new(&self.module);
+        if module.is_absolute()
+            || module
+                .components()
+                .any(|part| matches!(part, std::path::Component::ParentDir))
+        {
+            return Err(ApiError::BadRequest(format!(
+                "Plugin '{}' module must stay inside the plugin directory",
+                self.id
+            )));
+        }
+        Ok(())
+    }
+}
+
+pub fn is_valid_plugin_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && !id.contains("__")
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// `tepora_register` の戻り値。モジュールが提供するものの宣言。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginRegistration {
+    #[serde(default)]
+    pub tools: Vec<PluginToolDef>,
+    #[serde(default)]
+    pub context_workers: Vec<PluginWorkerDef>,
+    #[serde(default)]
+    pub nodes: Vec<PluginNodeDef>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginToolDef {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub input_schema: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginWorkerDef {
+    pub name: String,
+    /// システムプロンプト内の並び順（大きいほど前）
+    #[serde(default = "default_worker_priority")]
+    pub priority: u8,
+}
+
+fn default_worker_priority() -> u8 {
+    50
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginNodeDef {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+impl PluginRegistration {
+    /// 要求かつ付与された権限の範囲に絞る。名前が不正なものも落とす。
+    pub fn restrict_to(mut self, effective: &[PluginCapability]) -> Self {
+        if !effective.contains(&PluginCapability::Tools) {
+            self.tools.clear();
+        }
+        if !effective.contains(&PluginCapability::ContextWorkers) {
+            self.context_workers.clear();
+        }
+        if !effective.contains(&PluginCapability::GraphNodes) {
+            self.nodes.clear();
+        }
+        self.tools.retain(|tool| is_valid_entry_name(&tool.name));
+        self.context_workers
+            .retain(|worker| is_valid_entry_name(&worker.name));
+        self.nodes.retain(|node| is_valid_entry_name(&node.name));
+        self
+    }
+}
+
+fn is_valid_entry_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// LLM に見せるツール名。プラグイン ID で名前空間を分ける。
+pub fn qualified_tool_name(plugin_id: &str, tool: &str) -> String {
+    format!("{}{}__{}", super::TOOL_PREFIX, plugin_id, tool)
+}
+
+pub fn split_tool_name(name: &str) -> Option<(&str, &str)> {
+    name.strip_prefix(super::TOOL_PREFIX)?.split_once("__")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn manifest_rejects_bad_ids_and_escaping_modules() {
+        let ok: PluginManifest =
+            serde_json::from_value(json!({"id": "word-count", "name": "Word Count"})).unwrap();
+        assert!(ok.validate().is_ok());
+        assert_eq!(ok.module, "plugin.wasm");
+
+        let bad_id: PluginManifest =
+            serde_json::from_value(json!({"id": "Word Count", "name": "x"})).unwrap();
+        assert!(bad_id.validate().is_err());
+
+        let escaping: PluginManifest = serde_json::from_value(
+            json!({"id": "x", "name": "x", "module": "../other/plugin.wasm"}),
+        )
+        .unwrap();
+        assert!(escaping.validate().is_err());
+    }
+
+    #[test]
+    fn registration_is_limited_to_effective_capabilities() {
+        let registration: PluginRegistration = serde_json::from_value(json!({
+            "tools": [{"name": "count"}, {"name": "bad name"}],
+            "context_workers": [{"name": "glossary"}],
+            "nodes": [{"name": "classify"}]
+        }))
+        .unwrap();
+        let restricted = registration.restrict_to(&[PluginCapability::Tools]);
+        assert_eq!(restricted.tools.len(), 1);
+        assert!(restricted.context_workers.is_empty());
+        assert!(restricted.nodes.is_empty());
+    }
+
+    #[test]
+    fn tool_names_round_trip() {
+        let name = qualified_tool_name("word-count", "count_words");
+        assert_eq!(split_tool_name(&name), Some(("word-count", "count_words")));
+        assert_eq!(split_tool_name("web_search"), None);
+    }
+}
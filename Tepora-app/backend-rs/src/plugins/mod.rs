@@ -0,0 +1,455 @@
+//! WASM プラグイン。
+//!
+//! `<user_data_dir>/plugins/<dir>/plugin.json` とモジュールを置くと読み込む。
+//! モジュールは次のホスト API を実装する（値はすべて UTF-8 の JSON）。
+//!
+//! - export `memory`
+//! - export `tepora_alloc(len: i32) -> i32` — ホストが要求を書き込む領域を確保する
+//! - export `tepora_register() -> i64` — 提供するツール・コンテキストワーカー・
+//!   グラフノードを [`manifest::PluginRegistration`] の形で返す
+//! - export `tepora_invoke(ptr: i32, len: i32) -> i64` — `{"kind", "name", "input"}` を受け、
+//!   `{"ok": true, "output": ...}` か `{"ok": false, "error": "..."}` を返す
+//! - import `tepora.log(level: i32, ptr: i32, len: i32)`
+//!
+//! i64 の戻り値は上位 32 ビットがポインタ、下位 32 ビットが長さ。
+//! マニフェストで要求した権限のうち、ユーザーが `plugins.grants` で付与したものだけが
+//! 有効になり、登録内容もその範囲に絞られる。
+
+pub mod host;
+pub mod manifest;
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::core::config::schema::{PluginCapability, PluginsSettings};
+use crate::core::config::{AppPaths, ConfigService};
+use crate::core::errors::ApiError;
+use host::{HostLimits, PluginHost};
+use manifest::{
+    qualified_tool_name, split_tool_name, PluginManifest, PluginRegistration, PluginWorkerDef,
+    MANIFEST_FILE,
+};
+
+/// プラグインのツール名の接頭辞（`plugin__<id>__<tool>`）
+pub const TOOL_PREFIX: &str = "plugin__";
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginStatus {
+    Disabled,
+    Loaded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub requested: Vec<PluginCapability>,
+    pub granted: Vec<PluginCapability>,
+    pub status: PluginStatus,
+    pub error: Option<String>,
+    pub tools: Vec<String>,
+    pub context_workers: Vec<String>,
+    pub nodes: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PluginToolInfo {
+    pub name: String,
+    pub description: String,
+    pub input_schema: Option<Value>,
+}
+
+struct LoadedPlugin {
+    manifest: PluginManifest,
+    granted: Vec<PluginCapability>,
+    status: PluginStatus,
+    error: Option<String>,
+    registration: PluginRegistration,
+    host: Option<PluginHost>,
+    call_timeout: Duration,
+}
+
+impl LoadedPlugin {
+    fn info(&self) -> PluginInfo {
+        PluginInfo {
+            id: self.manifest.id.clone(),
+            name: self.manifest.name.clone(),
+            version: self.manifest.version.clone(),
+            description: self.manifest.description.clone(),
+            requested: self.manifest.capabilities.clone(),
+            granted: self.granted.clone(),
+            status: self.status,
+            error: self.error.clone(),
+            tools: self
+                .registration
+                .tools
+                .iter()
+                .map(|tool| qualified_tool_name(&self.manifest.id, &tool.name))
+                .collect(),
+            context_workers: self
+                .registration
+                .context_workers
+                .iter()
+                .map(|worker| worker.name.clone())
+                .collect(),
+            nodes: self
+                .registration
+                .nodes
+                .iter()
+                .map(|node| node.name.clone())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PluginManager {
+    root: PathBuf,
+    data_root: PathBuf,
+    config: ConfigService,
+    plugins: Arc<RwLock<Vec<Arc<LoadedPlugin>>>>,
+}
+
+impl PluginManager {
+    pub fn new(paths: &AppPaths, config: ConfigService) -> Self {
+        Self {
+            root: paths.user_data_dir.join("plugins"),
+            data_root: paths.user_data_dir.join("plugin_data"),
+            config,
+            plugins: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    /// プラグインを読み直す。起動時と `plugins` 設定の変更時に呼ぶ。
+    /// セーフモード中は何も読まない。
+    pub async fn reload(&self) -> Result<(), ApiError> {
+        if crate::core::safe_mode::is_active() {
+            self.plugins
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .clear();
+            return Ok(());
+        }
+        let settings = self.config.load_typed()?.plugins;
+        let root = self.root.clone();
+        let data_root = self.data_root.clone();
+        let loaded = tokio::task::spawn_blocking(move || scan(&root, &data_root, &settings))
+            .await
+            .map_err(ApiError::internal)?;
+        tracing::info!(
+            count = loaded.len(),
+            loaded = loaded
+                .iter()
+                .filter(|p| p.status == PluginStatus::Loaded)
+                .count(),
+            "Plugins reloaded"
+        );
+        *self.plugins.write().unwrap_or_else(|e| e.into_inner()) = loaded;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<PluginInfo> {
+        self.snapshot().iter().map(|plugin| plugin.info()).collect()
+    }
+
+    pub fn tools(&self) -> Vec<PluginToolInfo> {
+        self.snapshot()
+            .iter()
+            .flat_map(|plugin| {
+                plugin
+                    .registration
+                    .tools
+                    .iter()
+                    .map(|tool| PluginToolInfo {
+                        name: qualified_tool_name(&plugin.manifest.id, &tool.name),
+                        description: tool.description.clone(),
+                        input_schema: tool.input_schema.clone(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    pub fn context_workers(&self) -> Vec<(String, PluginWorkerDef)> {
+        self.snapshot()
+            .iter()
+            .flat_map(|plugin| {
+                plugin
+                    .registration
+                    .context_workers
+                    .iter()
+                    .map(|worker| (plugin.manifest.id.clone(), worker.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    pub async fn call_tool(&self, tool_name: &str, args: &Value) -> Result<String, ApiError> {
+        let (plugin_id, tool) = split_tool_name(tool_name)
+            .ok_or_else(|| ApiError::BadRequest(format!("Unknown tool: {}", tool_name)))?;
+        let plugin = self.find(plugin_id)?;
+        if !plugin.registration.tools.iter().any(|t| t.name == tool) {
+            return Err(ApiError::BadRequest(format!("Unknown tool: {}", tool_name)));
+        }
+        let output = invoke(plugin, "tool", tool, args.clone()).await?;
+        Ok(match output {
+            Value::String(text) => text,
+            other => other.to_string(),
+        })
+    }
+
+    /// 返されたテキストをシステムプロンプトに加える。`None` なら何も足さない。
+    pub async fn run_context_worker(
+        &self,
+        plugin_id: &str,
+        worker: &str,
+        input: Value,
+    ) -> Result<Option<String>, ApiError> {
+        let plugin = self.find(plugin_id)?;
+        let output = invoke(plugin, "context", worker, input).await?;
+        Ok(output
+            .get("text")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .map(str::to_string))
+    }
+
+    pub async fn run_node(
+        &self,
+        plugin_id: &str,
+        node: &str,
+        input: Value,
+    ) -> Result<Value, ApiError> {
+        let plugin = self.find(plugin_id)?;
+        if !plugin.registration.nodes.iter().any(|n| n.name == node) {
+            return Err(ApiError::NotFound(format!(
+                "Plugin '{}' does not provide node '{}'",
+                plugin_id, node
+            )));
+        }
+        invoke(plugin, "node", node, input).await
+    }
+
+    fn snapshot(&self) -> Vec<Arc<LoadedPlugin>> {
+        self.plugins
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn find(&self, plugin_id: &str) -> Result<Arc<LoadedPlugin>, ApiError> {
+        self.snapshot()
+            .into_iter()
+            .find(|plugin| plugin.manifest.id == plugin_id && plugin.status == PluginStatus::Loaded)
+            .ok_or_else(|| ApiError::NotFound(format!("Plugin '{}' is not loaded", plugin_id)))
+    }
+}
+
+async fn invoke(
+    plugin: Arc<LoadedPlugin>,
+    kind: &str,
+    name: &str,
+    input: Value,
+) -> Result<Value, ApiError> {
+    let plugin_id = plugin.manifest.id.clone();
+    let timeout = plugin.call_timeout;
+    let request = json!({ "kind": kind, "name": name, "input": input });
+    let task = tokio::task::spawn_blocking(move || match &plugin.host {
+        Some(host) => host.invoke(&request),
+        None => Err("plugin is not loaded".to_string()),
+    });
+    // タイムアウト後もスレッドは fuel を使い切るまで走るが、結果は捨てる
+    let response = match tokio::time::timeout(timeout, task).await {
+        Err(_) => {
+            return Err(ApiError::Internal(format!(
+                "Plugin '{}' timed out after {:?}",
+                plugin_id, timeout
+            )))
+        }
+        Ok(joined) => joined
+            .map_err(ApiError::internal)?
+            .map_err(|err| ApiError::Internal(format!("Plugin '{}': {}", plugin_id, err)))?,
+    };
+    unwrap_response(&plugin_id, response)
+}
+
+fn unwrap_response(plugin_id: &str, response: Value) -> Result<Value, ApiError> {
+    if response.get("ok").and_then(Value::as_bool) == Some(true) {
+        return Ok(response.get("output").cloned().unwrap_or(Value::Null));
+    }
+    let error = response
+        .get("error")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown error");
+    Err(ApiError::BadRequest(format!(
+        "Plugin '{}' failed: {}",
+        plugin_id, error
+    )))
+}
+
+/// 要求と付与の両方にある権限
+fn effective_capabilities(
+    manifest: &PluginManifest,
+    settings: &PluginsSettings,
+) -> Vec<PluginCapability> {
+    let granted = settings.grants.get(&manifest.id);
+    manifest
+        .capabilities
+        .iter()
+        .copied()
+        .filter(|cap| granted.is_some_and(|granted| granted.contains(cap)))
+        .collect()
+}
+
+fn scan(root: &Path, data_root: &Path, settings: &PluginsSettings) -> Vec<Arc<LoadedPlugin>> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.join(MANIFEST_FILE).is_file())
+        .collect();
+    dirs.sort();
+
+    let mut plugins: Vec<Arc<LoadedPlugin>> = Vec::new();
+    for dir in dirs {
+        let manifest = match PluginManifest::load(&dir) {
+            Ok(manifest) => manifest,
+            Err(err) => {
+                tracing::warn!("Skipping plugin at {}: {}", dir.display(), err);
+                continue;
+            }
+        };
+        if plugins.iter().any(|p| p.manifest.id == manifest.id) {
+            tracing::warn!("Skipping duplicate plugin id '{}'", manifest.id);
+            continue;
+        }
+        plugins.push(Arc::new(load_plugin(&dir, data_root, manifest, settings)));
+    }
+    plugins
+}
+
+fn load_plugin(
+    dir: &Path,
+    data_root: &Path,
+    manifest: PluginManifest,
+    settings: &PluginsSettings,
+) -> LoadedPlugin {
+    let granted = effective_capabilities(&manifest, settings);
+    let mut plugin = LoadedPlugin {
+        granted: granted.clone(),
+        status: PluginStatus::Disabled,
+        error: None,
+        registration: PluginRegistration::default(),
+        host: None,
+        call_timeout: Duration::from_millis(settings.call_timeout_ms),
+        manifest,
+    };
+    if !settings.enabled {
+        return plugin;
+    }
+
+    let limits = HostLimits {
+        fuel: settings.fuel_per_call,
+        max_memory_bytes: (settings.max_memory_mb as usize).saturating_mul(1024 * 1024),
+    };
+    let storage_dir = granted
+        .contains(&PluginCapability::Storage)
+        .then(|| data_root.join(&plugin.manifest.id));
+    let loaded = PluginHost::load(
+        &plugin.manifest.id,
+        &dir.join(&plugin.manifest.module),
+        limits,
+        storage_dir,
+    )
+    .and_then(|host| {
+        let registration = host.register()?;
+        let registration: PluginRegistration = serde_json::from_value(registration)
+            .map_err(|err| format!("invalid registration: {}", err))?;
+        Ok((host, registration.restrict_to(&granted)))
+    });
+    match loaded {
+        Ok((host, registration)) => {
+            plugin.status = PluginStatus::Loaded;
+            plugin.host = Some(host);
+            plugin.registration = registration;
+        }
+        Err(err) => {
+            tracing::warn!("Failed to load plugin '{}': {}", plugin.manifest.id, err);
+            plugin.status = PluginStatus::Failed;
+            plugin.error = Some(err);
+        }
+    }
+    plugin
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_plugin(root: &Path, dir: &str, manifest: Value) {
+        let path = root.join(dir);
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join(MANIFEST_FILE), manifest.to_string()).unwrap();
+    }
+
+    #[test]
+    fn scan_reports_requested_and_granted_capabilities() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("plugins");
+        write_plugin(
+            &root,
+            "word-count",
+            json!({"id": "word-count", "name": "Word Count", "capabilities": ["tools", "storage"]}),
+        );
+        write_plugin(&root, "broken", json!({"id": "Not Valid", "name": "x"}));
+
+        let mut settings = PluginsSettings::default();
+        settings.grants.insert(
+            "word-count".to_string(),
+            vec![PluginCapability::Tools, PluginCapability::GraphNodes],
+        );
+        let plugins = scan(&root, &temp.path().join("data"), &settings);
+        assert_eq!(plugins.len(), 1);
+        let info = plugins[0].info();
+        assert_eq!(info.status, PluginStatus::Disabled);
+        assert_eq!(info.requested.len(), 2);
+        // 要求していない権限は付与されていても無効
+        assert_eq!(info.granted, vec![PluginCapability::Tools]);
+    }
+
+    #[test]
+    fn failed_loads_are_listed_with_their_error() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("plugins");
+        write_plugin(&root, "a", json!({"id": "a", "name": "A"}));
+        let settings = PluginsSettings {
+            enabled: true,
+            ..Default::default()
+        };
+        let plugins = scan(&root, &temp.path().join("data"), &settings);
+        assert_eq!(plugins[0].status, PluginStatus::Failed);
+        assert!(plugins[0].error.is_some());
+        assert!(plugins[0].host.is_none());
+    }
+
+    #[test]
+    fn plugin_errors_are_surfaced() {
+        assert_eq!(
+            unwrap_response("a", json!({"ok": true, "output": "hi"})).unwrap(),
+            json!("hi")
+        );
+        let err = unwrap_response("a", json!({"ok": false, "error": "boom"})).unwrap_err();
+        assert!(err.to_string().contains("boom"));
+    }
+}
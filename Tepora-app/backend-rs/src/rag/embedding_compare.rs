@@ -0,0 +1,307 @@
+//! 埋め込みモデルの A/B 比較（`POST /api/rag/compare-embeddings`）。
+//!
+//! 同じ評価用コーパスを 2 つのモデルで埋め込み、正解ラベル付きのクエリに対する
+//! recall@k・MRR・nDCG@k を並べる。既存の RAG コレクションには触れないので、
+//! 乗り換え（再埋め込み）の前に気軽に試せる。
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::errors::ApiError;
+use crate::tools::vector_math::rank_descending_by_cosine;
+
+/// 評価結果に差がないとみなす幅。MRR の差がこれ未満なら乗り換えを勧めない
+const SIGNIFICANT_DELTA: f64 = 0.05;
+
+const MAX_DOCUMENTS: usize = 500;
+const MAX_QUERIES: usize = 200;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EvalDocument {
+    pub id: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EvalQuery {
+    pub query: String,
+    /// 正解とみなす文書 ID
+    pub relevant: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EvalCorpus {
+    pub documents: Vec<EvalDocument>,
+    pub queries: Vec<EvalQuery>,
+}
+
+impl EvalCorpus {
+    /// コーパスを指定しなかったときに使う小さな日英混在のサンプル。
+    pub fn sample() -> Self {
+        let documents = [
+            ("rust-ownership", "Rust enforces memory safety through ownership: each value has a single owner and is dropped when the owner goes out of scope."),
+            ("rust-async", "Async Rust uses futures that are polled by an executor such as Tokio; .await yields control until the future is ready."),
+            ("sqlite-wal", "SQLite's write-ahead log lets readers proceed concurrently with a single writer and improves write throughput."),
+            ("http-cache", "HTTP caching relies on Cache-Control, ETag and Last-Modified headers to avoid re-downloading unchanged resources."),
+            ("photosynthesis", "Photosynthesis converts light energy, water and carbon dioxide into glucose and oxygen inside chloroplasts."),
+            ("coffee-brewing", "Pour-over coffee tastes best with water just below boiling and a medium-fine grind brewed for about three minutes."),
+            ("ja-sakura", "桜の開花は気温の積算で決まり、東京では例年三月下旬に見頃を迎える。"),
+            ("ja-onsen", "温泉は泉温や含まれる成分によって分類され、硫黄泉や炭酸水素塩泉などがある。"),
+            ("ja-embedding", "埋め込みモデルは文章を高次元のベクトルに変換し、意味の近い文章ほどベクトル同士の距離が近くなる。"),
+            ("ja-tokyo-rail", "東京の鉄道網は JR と私鉄、地下鉄が相互に乗り入れており、朝の通勤時間帯は非常に混雑する。"),
+        ];
+        let queries: [(&str, &[&str]); 7] = [
+            (
+                "How does Rust free memory without a garbage collector?",
+                &["rust-ownership"],
+            ),
+            ("What runs futures in async Rust?", &["rust-async"]),
+            (
+                "database concurrency with one writer and many readers",
+                &["sqlite-wal"],
+            ),
+            (
+                "avoid downloading the same file twice from a web server",
+                &["http-cache"],
+            ),
+            ("植物が光からエネルギーを作る仕組み", &["photosynthesis"]),
+            ("お花見に行くならいつ頃がいいですか", &["ja-sakura"]),
+            (
+                "how do vector embeddings represent meaning",
+                &["ja-embedding"],
+            ),
+        ];
+        Self {
+            documents: documents
+                .iter()
+                .map(|(id, text)| EvalDocument {
+                    id: id.to_string(),
+                    text: text.to_string(),
+                })
+                .collect(),
+            queries: queries
+                .iter()
+                .map(|(query, relevant)| EvalQuery {
+                    query: query.to_string(),
+                    relevant: relevant.iter().map(|id| id.to_string()).collect(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if self.documents.is_empty() || self.queries.is_empty() {
+            return Err(ApiError::BadRequest(
+                "Corpus needs at least one document and one query".to_string(),
+            ));
+        }
+        if self.documents.len() > MAX_DOCUMENTS || self.queries.len() > MAX_QUERIES {
+            return Err(ApiError::BadRequest(format!(
+                "Corpus is limited to {MAX_DOCUMENTS} documents and {MAX_QUERIES} queries"
+            )));
+        }
+        let mut ids = HashSet::new();
+        for document in &self.documents {
+            if document.text.trim().is_empty() {
+                return Err(ApiError::BadRequest(format!(
+                    "Document '{}' is empty",
+                    document.id
+                )));
+            }
+            if !ids.insert(document.id.as_str()) {
+                return Err(ApiError::BadRequest(format!(
+                    "Duplicate document id: {}",
+                    document.id
+                )));
+            }
+        }
+        for query in &self.queries {
+            if query.query.trim().is_empty() || query.relevant.is_empty() {
+                return Err(ApiError::BadRequest(
+                    "Each query needs text and at least one relevant document id".to_string(),
+                ));
+            }
+            if let Some(unknown) = query.relevant.iter().find(|id| !ids.contains(id.as_str())) {
+                return Err(ApiError::BadRequest(format!(
+                    "Query '{}' references unknown document id: {unknown}",
+                    query.query
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResult {
+    pub query: String,
+    /// 最初の正解文書の順位（1 始まり）。上位 k 件に無ければ `None`
+    pub first_relevant_rank: Option<usize>,
+    pub top_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetrievalMetrics {
+    pub k: usize,
+    pub recall_at_k: f64,
+    pub mrr: f64,
+    pub ndcg_at_k: f64,
+    pub queries: Vec<QueryResult>,
+}
+
+/// 埋め込み済みのベクトルから指標を計算する。`documents` と `queries` の並びは
+/// コーパスと同じでなければならない。
+pub fn evaluate(
+    corpus: &EvalCorpus,
+    documents: &[Vec<f32>],
+    queries: &[Vec<f32>],
+    k: usize,
+) -> Result<RetrievalMetrics, ApiError> {
+    if documents.len() != corpus.documents.len() || queries.len() != corpus.queries.len() {
+        return Err(ApiError::Internal(
+            "Embedding count does not match the corpus".to_string(),
+        ));
+    }
+    let k = k.clamp(1, corpus.documents.len());
+    let mut recall_sum = 0.0;
+    let mut rr_sum = 0.0;
+    let mut ndcg_sum = 0.0;
+    let mut results = Vec::with_capacity(corpus.queries.len());
+
+    for (query, embedding) in corpus.queries.iter().zip(queries) {
+        let relevant = query
+            .relevant
+            .iter()
+            .map(String::as_str)
+            .collect::<HashSet<_>>();
+        let top_ids = rank_descending_by_cosine(embedding, documents)?
+            .into_iter()
+            .take(k)
+            .map(|(idx, _)| corpus.documents[idx].id.clone())
+            .collect::<Vec<_>>();
+
+        let hits = top_ids
+            .iter()
+            .filter(|id| relevant.contains(id.as_str()))
+            .count();
+        recall_sum += hits as f64 / relevant.len() as f64;
+
+        let first_relevant_rank = top_ids
+            .iter()
+            .position(|id| relevant.contains(id.as_str()))
+            .map(|pos| pos + 1);
+        rr_sum += first_relevant_rank.map_or(0.0, |rank| 1.0 / rank as f64);
+
+        let dcg = top_ids
+            .iter()
+            .enumerate()
+            .filter(|(_, id)| relevant.contains(id.as_str()))
+            .map(|(pos, _)| 1.0 / (pos as f64 + 2.0).log2())
+            .sum::<f64>();
+        let ideal = (0..relevant.len().min(k))
+            .map(|pos| 1.0 / (pos as f64 + 2.0).log2())
+            .sum::<f64>();
+        ndcg_sum += dcg / ideal;
+
+        results.push(QueryResult {
+            query: query.query.clone(),
+            first_relevant_rank,
+            top_ids,
+        });
+    }
+
+    let count = corpus.queries.len() as f64;
+    Ok(RetrievalMetrics {
+        k,
+        recall_at_k: recall_sum / count,
+        mrr: rr_sum / count,
+        ndcg_at_k: ndcg_sum / count,
+        queries: results,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recommendation {
+    /// 候補モデルの方が明らかに良い。再埋め込みする価値がある
+    Switch,
+    /// 現在のモデルの方が良い
+    Keep,
+    /// 差が小さく、再埋め込みのコストに見合わない
+    Inconclusive,
+}
+
+/// 候補 (`candidate`) が現在のモデル (`baseline`) をどれだけ上回るかで判定する。
+pub fn recommend(baseline: &RetrievalMetrics, candidate: &RetrievalMetrics) -> Recommendation {
+    let delta = candidate.mrr - baseline.mrr;
+    if delta >= SIGNIFICANT_DELTA && candidate.recall_at_k >= baseline.recall_at_k {
+        Recommendation::Switch
+    } else if delta <= -SIGNIFICANT_DELTA {
+        Recommendation::Keep
+    } else {
+        Recommendation::Inconclusive
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corpus() -> EvalCorpus {
+        EvalCorpus {
+            documents: ["a", "b", "c"]
+                .iter()
+                .map(|id| EvalDocument {
+                    id: id.to_string(),
+                    text: format!("doc {id}"),
+                })
+                .collect(),
+            queries: vec![
+                EvalQuery {
+                    query: "find a".to_string(),
+                    relevant: vec!["a".to_string()],
+                },
+                EvalQuery {
+                    query: "find c".to_string(),
+                    relevant: vec!["c".to_string()],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn metrics_reflect_rank_of_relevant_documents() {
+        let documents = vec![vec![1.0, 0.0], vec![0.7, 0.7], vec![0.0, 1.0]];
+        // 1 問目は正解が 1 位、2 問目は正解 c が 2 位
+        let queries = vec![vec![1.0, 0.1], vec![0.3, 0.6]];
+        let metrics = evaluate(&corpus(), &documents, &queries, 2).unwrap();
+
+        assert_eq!(metrics.queries[0].first_relevant_rank, Some(1));
+        assert_eq!(metrics.queries[1].top_ids, vec!["b", "c"]);
+        assert!((metrics.recall_at_k - 1.0).abs() < 1e-9);
+        assert!((metrics.mrr - 0.75).abs() < 1e-9);
+        let expected_ndcg = (1.0 + 1.0 / 3f64.log2()) / 2.0;
+        assert!((metrics.ndcg_at_k - expected_ndcg).abs() < 1e-9);
+
+        let strict = evaluate(&corpus(), &documents, &queries, 1).unwrap();
+        assert!((strict.recall_at_k - 0.5).abs() < 1e-9);
+        assert_eq!(strict.queries[1].first_relevant_rank, None);
+        assert_eq!(recommend(&strict, &metrics), Recommendation::Switch);
+        assert_eq!(recommend(&metrics, &strict), Recommendation::Keep);
+        assert_eq!(recommend(&metrics, &metrics), Recommendation::Inconclusive);
+    }
+
+    #[test]
+    fn validation_rejects_unknown_and_duplicate_ids() {
+        assert!(EvalCorpus::sample().validate().is_ok());
+
+        let mut unknown = corpus();
+        unknown.queries[0].relevant = vec!["z".to_string()];
+        assert!(unknown.validate().is_err());
+
+        let mut duplicate = corpus();
+        duplicate.documents[1].id = "a".to_string();
+        assert!(duplicate.validate().is_err());
+    }
+}
@@ -7,6 +7,9 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::core::config::schema::NetworkSubsystem;
+use crate::core::network::NetClient;
+
 /// Configuration for the RAG engine.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RAGConfig {
@@ -70,11 +73,13 @@ impl RAGEngine {
     ///
     /// Fetches the web content and splits it into chunks.
     pub async fn collect_from_url(&self, url: &str) -> anyhow::Result<Vec<TextChunk>> {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(self.config.web_timeout_secs))
-            .build()?;
+        let client = NetClient::from_builder(
+            NetworkSubsystem::WebFetch,
+            reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(self.config.web_timeout_secs)),
+        )?;
 
-        let response = client.get(url).send().await?;
+        let response = client.get(url)?.send().await?;
         let text = response.text().await?;
 
         // Strip HTML tags (simple approach)
@@ -0,0 +1,168 @@
+//! RAG コレクションの Parquet 書き出し。
+//!
+//! チャンク本文と埋め込みを 1 行ずつ並べ、分析ツールや別のベクトル DB へ
+//! そのまま読み込めるようにする。メタデータは JSON 文字列のまま入れ、
+//! 埋め込みモデルとコレクション名はファイルのキー・値メタデータに残す。
+
+use std::sync::Arc;
+
+use arrow_array::builder::{Float32Builder, ListBuilder};
+use arrow_array::{ArrayRef, RecordBatch, StringArray, UInt32Array};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::WriterProperties;
+
+use super::store::StoredChunk;
+use crate::core::errors::ApiError;
+
+pub const PARQUET_CONTENT_TYPE: &str = "application/vnd.apache.parquet";
+
+fn export_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("chunk_id", DataType::Utf8, false),
+        Field::new("collection", DataType::Utf8, false),
+        Field::new("source", DataType::Utf8, false),
+        Field::new("content", DataType::Utf8, false),
+        Field::new("metadata", DataType::Utf8, true),
+        Field::new("embedding_dim", DataType::UInt32, false),
+        Field::new(
+            "embedding",
+            DataType::List(Arc::new(Field::new("item", DataType::Float32, true))),
+            false,
+        ),
+    ])
+}
+
+/// `chunks` を 1 つの Parquet ファイルにする。
+pub fn chunks_to_parquet(
+    collection: &str,
+    embedding_model: Option<&str>,
+    chunks: &[(StoredChunk, Vec<f32>)],
+) -> Result<Vec<u8>, ApiError> {
+    let schema = Arc::new(export_schema());
+    let mut embeddings = ListBuilder::new(Float32Builder::new());
+    for (_, embedding) in chunks {
+        embeddings.values().append_slice(embedding);
+        embeddings.append(true);
+    }
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            chunks.iter().map(|(chunk, _)| chunk.chunk_id.as_str()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            chunks.iter().map(|(chunk, _)| chunk.session_id.as_str()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            chunks.iter().map(|(chunk, _)| chunk.source.as_str()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            chunks.iter().map(|(chunk, _)| chunk.content.as_str()),
+        )),
+        Arc::new(StringArray::from(
+            chunks
+                .iter()
+                .map(|(chunk, _)| chunk.metadata.as_ref().map(|value| value.to_string()))
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(UInt32Array::from_iter_values(
+            chunks.iter().map(|(_, embedding)| embedding.len() as u32),
+        )),
+        Arc::new(embeddings.finish()),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(), columns).map_err(ApiError::internal)?;
+
+    let mut key_values = vec![KeyValue::new(
+        "tepora.collection".to_string(),
+        collection.to_string(),
+    )];
+    if let Some(model) = embedding_model {
+        key_values.push(KeyValue::new(
+            "tepora.embedding_model".to_string(),
+            model.to_string(),
+        ));
+    }
+    let props = WriterProperties::builder()
+        .set_key_value_metadata(Some(key_values))
+        .build();
+
+    let mut buffer = Vec::new();
+    let mut writer =
+        ArrowWriter::try_new(&mut buffer, schema, Some(props)).map_err(ApiError::internal)?;
+    writer.write(&batch).map_err(ApiError::internal)?;
+    writer.close().map_err(ApiError::internal)?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Float32Type;
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::io::Write;
+
+    #[test]
+    fn parquet_round_trips_chunks_and_embeddings() {
+        let chunks = vec![
+            (
+                StoredChunk {
+                    chunk_id: "c1".to_string(),
+                    content: "こんにちは".to_string(),
+                    source: "notes.md".to_string(),
+                    session_id: "feeds".to_string(),
+                    metadata: Some(serde_json::json!({ "start_offset": 0 })),
+                },
+                vec![0.5, -1.0, 2.0],
+            ),
+            (
+                StoredChunk {
+                    chunk_id: "c2".to_string(),
+                    content: "world".to_string(),
+                    source: String::new(),
+                    session_id: "feeds".to_string(),
+                    metadata: None,
+                },
+                Vec::new(),
+            ),
+        ];
+        let bytes = chunks_to_parquet("feeds", Some("nomic-embed"), &chunks).unwrap();
+
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&bytes).unwrap();
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        let key_values = builder
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .cloned()
+            .unwrap_or_default();
+        assert!(key_values.iter().any(|kv| {
+            kv.key == "tepora.embedding_model" && kv.value.as_deref() == Some("nomic-embed")
+        }));
+
+        let batches = builder
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+
+        let content = batch.column_by_name("content").unwrap().as_string::<i32>();
+        assert_eq!(content.value(0), "こんにちは");
+        let metadata = batch.column_by_name("metadata").unwrap().as_string::<i32>();
+        assert_eq!(metadata.value(0), r#"{"start_offset":0}"#);
+        assert!(metadata.is_null(1));
+
+        let embedding = batch.column_by_name("embedding").unwrap().as_list::<i32>();
+        let first = embedding.value(0);
+        assert_eq!(
+            first.as_primitive::<Float32Type>().values().to_vec(),
+            vec![0.5, -1.0, 2.0]
+        );
+        assert_eq!(embedding.value(1).len(), 0);
+    }
+}
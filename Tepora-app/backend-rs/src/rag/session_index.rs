@@ -0,0 +1,325 @@
+//! Rolling per-session embeddings for semantic session search.
+//!
+//! Each session owns exactly one vector in a dedicated `RagStore`
+//! (`session_index.db`). After every exchange the exchange text is embedded
+//! and blended into the session vector, so the vector drifts toward what the
+//! conversation is currently about while still remembering how it started.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde_json::json;
+
+use super::store::{RagStore, StoredChunk};
+use super::SqliteRagStore;
+use crate::core::errors::ApiError;
+use crate::llm::LlmService;
+
+/// Weight floor for the newest exchange. Early exchanges are averaged evenly;
+/// after that the newest exchange always contributes at least this much.
+const RECENT_WEIGHT: f32 = 0.25;
+/// Characters of the exchange sent to the embedding model
+const MAX_EMBED_CHARS: usize = 2000;
+const MAX_PREVIEW_CHARS: usize = 200;
+const SOURCE: &str = "session";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionMatch {
+    pub session_id: String,
+    pub score: f32,
+    /// Latest user message that was folded into the vector
+    pub preview: String,
+    pub exchanges: u64,
+    pub updated_at: Option<String>,
+}
+
+pub struct SessionIndex {
+    store: Arc<dyn RagStore>,
+}
+
+impl SessionIndex {
+    pub fn new(store: Arc<dyn RagStore>) -> Self {
+        Self { store }
+    }
+
+    pub async fn with_path(db_path: PathBuf) -> Result<Self, ApiError> {
+        let store = SqliteRagStore::with_path(db_path).await?;
+        Ok(Self::new(Arc::new(store)))
+    }
+
+    /// Embeds one exchange and folds it into the session vector.
+    pub async fn record_exchange(
+        &self,
+        llm: &LlmService,
+        embedding_model_id: &str,
+        session_id: &str,
+        user_message: &str,
+        assistant_message: &str,
+    ) -> Result<(), ApiError> {
+        let text = truncate_chars(
+            &format!("{}\n{}", user_message.trim(), assistant_message.trim()),
+            MAX_EMBED_CHARS,
+        );
+        if text.trim().is_empty() {
+            return Ok(());
+        }
+        let embedding = llm
+            .embed(&[text], embedding_model_id)
+            .await?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        if embedding.is_empty() {
+            return Ok(());
+        }
+        self.fold(session_id, user_message, embedding_model_id, embedding)
+            .await
+    }
+
+    /// Runs [`Self::record_exchange`] on a background task so the chat
+    /// turn does not wait on the embedding model. Failures are only logged.
+    pub fn record_exchange_in_background(
+        self: &Arc<Self>,
+        llm: &LlmService,
+        embedding_model_id: &str,
+        session_id: &str,
+        user_message: &str,
+        assistant_message: &str,
+    ) {
+        let index = self.clone();
+        let llm = llm.clone();
+        let embedding_model_id = embedding_model_id.to_string();
+        let session_id = session_id.to_string();
+        let user_message = user_message.to_string();
+        let assistant_message = assistant_message.to_string();
+        tokio::spawn(async move {
+            if let Err(err) = index
+                .record_exchange(
+                    &llm,
+                    &embedding_model_id,
+                    &session_id,
+                    &user_message,
+                    &assistant_message,
+                )
+                .await
+            {
+                tracing::debug!(session_id = %session_id, "Failed to update session index: {}", err);
+            }
+        });
+    }
+
+    /// Blends `embedding` into the stored vector. A change of embedding
+    /// model or dimension restarts the vector from this exchange.
+    pub async fn fold(
+        &self,
+        session_id: &str,
+        user_message: &str,
+        embedding_model_id: &str,
+        embedding: Vec<f32>,
+    ) -> Result<(), ApiError> {
+        let previous = self
+            .store
+            .export_chunks(session_id)
+            .await?
+            .into_iter()
+            .next();
+        let (exchanges, vector) = match previous {
+            Some((chunk, old))
+                if old.len() == embedding.len()
+                    && chunk_model(&chunk) == Some(embedding_model_id) =>
+            {
+                let exchanges = chunk_exchanges(&chunk) + 1;
+                let weight = (1.0 / exchanges as f32).max(RECENT_WEIGHT);
+                let blended = old
+                    .iter()
+                    .zip(&embedding)
+                    .map(|(old, new)| old * (1.0 - weight) + new * weight)
+                    .collect();
+                (exchanges, normalize(blended))
+            }
+            _ => (1, normalize(embedding)),
+        };
+
+        let chunk = StoredChunk {
+            chunk_id: chunk_id(session_id),
+            content: truncate_chars(user_message.trim(), MAX_PREVIEW_CHARS),
+            source: SOURCE.to_string(),
+            session_id: session_id.to_string(),
+            metadata: Some(json!({
+                "exchanges": exchanges,
+                "embedding_model": embedding_model_id,
+                "updated_at": chrono::Utc::now().to_rfc3339(),
+            })),
+        };
+        self.store.insert(chunk, vector).await
+    }
+
+    /// Sessions whose vector is closest to `query`, best first.
+    pub async fn search(
+        &self,
+        llm: &LlmService,
+        embedding_model_id: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SessionMatch>, ApiError> {
+        let embedding = llm
+            .embed(&[query.to_string()], embedding_model_id)
+            .await?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        self.search_by_embedding(&embedding, limit).await
+    }
+
+    pub async fn search_by_embedding(
+        &self,
+        embedding: &[f32],
+        limit: usize,
+    ) -> Result<Vec<SessionMatch>, ApiError> {
+        if embedding.is_empty() {
+            return Ok(Vec::new());
+        }
+        let hits = self.store.search(embedding, limit, None).await?;
+        Ok(hits
+            .into_iter()
+            .map(|hit| SessionMatch {
+                exchanges: chunk_exchanges(&hit.chunk),
+                updated_at: hit
+                    .chunk
+                    .metadata
+                    .as_ref()
+                    .and_then(|meta| meta.get("updated_at"))
+                    .and_then(|value| value.as_str())
+                    .map(str::to_string),
+                session_id: hit.chunk.session_id,
+                score: hit.score,
+                preview: hit.chunk.content,
+            })
+            .collect())
+    }
+
+    pub async fn remove(&self, session_id: &str) -> Result<(), ApiError> {
+        self.store.delete_session(session_id).await.map(|_| ())
+    }
+}
+
+fn chunk_id(session_id: &str) -> String {
+    format!("session:{session_id}")
+}
+
+fn chunk_exchanges(chunk: &StoredChunk) -> u64 {
+    chunk
+        .metadata
+        .as_ref()
+        .and_then(|meta| meta.get("exchanges"))
+        .and_then(|value| value.as_u64())
+        .unwrap_or(1)
+}
+
+fn chunk_model(chunk: &StoredChunk) -> Option<&str> {
+    chunk
+        .metadata
+        .as_ref()
+        .and_then(|meta| meta.get("embedding_model"))
+        .and_then(|value| value.as_str())
+}
+
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm > f32::EPSILON {
+        vector.iter_mut().for_each(|value| *value /= norm);
+    }
+    vector
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_index() -> (tempfile::TempDir, SessionIndex) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let index = SessionIndex::with_path(temp_dir.path().join("session_index.db"))
+            .await
+            .unwrap();
+        (temp_dir, index)
+    }
+
+    #[tokio::test]
+    async fn rolling_vector_follows_the_conversation() {
+        let (_dir, index) = test_index().await;
+        index
+            .fold(
+                "cooking",
+                "How long to boil eggs?",
+                "embed",
+                vec![1.0, 0.0, 0.0],
+            )
+            .await
+            .unwrap();
+        index
+            .fold(
+                "travel",
+                "Trains from Tokyo to Kyoto",
+                "embed",
+                vec![0.0, 1.0, 0.0],
+            )
+            .await
+            .unwrap();
+        for _ in 0..6 {
+            index
+                .fold(
+                    "travel",
+                    "Best ramen near Kyoto station",
+                    "embed",
+                    vec![0.0, 0.0, 1.0],
+                )
+                .await
+                .unwrap();
+        }
+
+        let hits = index
+            .search_by_embedding(&[0.0, 0.0, 1.0], 5)
+            .await
+            .unwrap();
+        assert_eq!(hits[0].session_id, "travel");
+        assert_eq!(hits[0].exchanges, 7);
+        assert_eq!(hits[0].preview, "Best ramen near Kyoto station");
+
+        let hits = index
+            .search_by_embedding(&[1.0, 0.0, 0.0], 1)
+            .await
+            .unwrap();
+        assert_eq!(hits[0].session_id, "cooking");
+    }
+
+    #[tokio::test]
+    async fn model_change_restarts_the_vector() {
+        let (_dir, index) = test_index().await;
+        index
+            .fold("s1", "first", "old-model", vec![1.0, 0.0])
+            .await
+            .unwrap();
+        index
+            .fold("s1", "second", "new-model", vec![0.0, 1.0, 0.0])
+            .await
+            .unwrap();
+        let hits = index
+            .search_by_embedding(&[0.0, 1.0, 0.0], 1)
+            .await
+            .unwrap();
+        assert_eq!(hits[0].exchanges, 1);
+        assert!((hits[0].score - 1.0).abs() < 1e-5);
+
+        index.remove("s1").await.unwrap();
+        assert!(index
+            .search_by_embedding(&[0.0, 1.0, 0.0], 1)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
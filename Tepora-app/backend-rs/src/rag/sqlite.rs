@@ -422,6 +422,40 @@ impl RagStore for SqliteRagStore {
         Ok(count as usize)
     }
 
+    async fn export_chunks(
+        &self,
+        session_id: &str,
+    ) -> Result<Vec<(StoredChunk, Vec<f32>)>, ApiError> {
+        let rows = sqlx::query(
+            "SELECT chunk_id, content, source, session_id, metadata, embedding
+             FROM rag_chunks
+             WHERE session_id = ?1
+             ORDER BY created_at ASC, chunk_id ASC",
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(ApiError::internal)?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let blob: Option<Vec<u8>> = row.get("embedding");
+                let embedding = blob
+                    .map(|bytes| Self::deserialize_embedding(&bytes))
+                    .unwrap_or_default();
+                (Self::row_to_chunk(row), embedding)
+            })
+            .collect())
+    }
+
+    async fn embedding_model(&self) -> Result<Option<String>, ApiError> {
+        sqlx::query_scalar("SELECT value FROM rag_meta WHERE key = 'embedding_model'")
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(ApiError::internal)
+    }
+
     async fn reindex_with_model(&self, embedding_model: &str) -> Result<(), ApiError> {
         sqlx::query("DELETE FROM rag_chunks")
             .execute(&self.pool)
@@ -61,6 +61,15 @@ pub trait RagStore: Send + Sync {
 
     async fn count(&self, session_id: Option<&str>) -> Result<usize, ApiError>;
 
+    /// All chunks of a collection with their embeddings, oldest first
+    async fn export_chunks(
+        &self,
+        session_id: &str,
+    ) -> Result<Vec<(StoredChunk, Vec<f32>)>, ApiError>;
+
+    /// Embedding model the stored vectors were built with, if recorded
+    async fn embedding_model(&self) -> Result<Option<String>, ApiError>;
+
     async fn reindex_with_model(&self, embedding_model: &str) -> Result<(), ApiError>;
 
     async fn reindex(&self) -> Result<(), ApiError> {
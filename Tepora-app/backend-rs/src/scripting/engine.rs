@@ -0,0 +1,286 @@
+//! スクリプトから見えるホスト API と、呼び出しごとの Rhai エンジン。
+//!
+//! - `config(path)` — 秘密値を伏せた設定をドット区切りのパスで読む
+//! - `add_context(text)` — `before_prompt_build` でシステムプロンプトに足す
+//! - `call_tool(name, args)` — `scripting.allowed_tools` にあるツールだけ呼べる
+//! - `print` / `debug` — ログに出る
+//!
+//! ファイル・モジュールの読み込みと `eval` は使えない。演算数と実行時間に上限がある。
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Scope, AST};
+use serde_json::Value;
+
+use crate::agent::policy::CustomToolPolicy;
+use crate::core::config::schema::ScriptingSettings;
+use crate::core::errors::ApiError;
+use crate::core::native_tools::{native_tool_capability, resolve_tool_alias, ToolCapability};
+use crate::state::AppState;
+use crate::tools::dispatcher::execute_tool_with_policy;
+
+use super::ScriptHook;
+
+const MAX_STRING_SIZE: usize = 1024 * 1024;
+const MAX_COLLECTION_SIZE: usize = 10_000;
+
+/// コンパイルと実行で共通の制限をかけたエンジン
+pub(super) fn base_engine(max_operations: u64) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_modules(0)
+        .set_max_operations(max_operations)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .disable_symbol("eval");
+    engine
+}
+
+/// スクリプトからのツール呼び出し。確認ダイアログを出せないので、
+/// 許可リストにあっても外部プロセスの起動は通さない。
+pub(super) struct ToolAccess {
+    state: AppState,
+    handle: tokio::runtime::Handle,
+    session_id: Option<String>,
+    policy: CustomToolPolicy,
+}
+
+impl ToolAccess {
+    pub(super) fn new(
+        state: AppState,
+        session_id: Option<String>,
+        settings: &ScriptingSettings,
+    ) -> Self {
+        let mut policy = CustomToolPolicy::allow_all_policy();
+        policy.allow_all = false;
+        policy.allowed_tools = settings
+            .allowed_tools
+            .iter()
+            .map(|tool| resolve_tool_alias(tool))
+            .collect::<HashSet<_>>();
+        Self {
+            state,
+            handle: tokio::runtime::Handle::current(),
+            session_id,
+            policy,
+        }
+    }
+
+    fn call(&self, name: &str, args: &Value) -> Result<String, String> {
+        if native_tool_capability(&resolve_tool_alias(name)) == Some(ToolCapability::Process) {
+            return Err(format!("Tool '{}' cannot be called from scripts", name));
+        }
+        let config = self
+            .state
+            .core()
+            .config
+            .load_config()
+            .map_err(|err| err.to_string())?;
+        let call = execute_tool_with_policy(
+            &self.policy,
+            Some(&self.state),
+            &config,
+            Some(&self.state.integration().mcp),
+            self.session_id.as_deref(),
+            name,
+            args,
+        );
+        match self.handle.block_on(super::IN_SCRIPT.scope((), call)) {
+            Ok(execution) => Ok(execution.output),
+            Err(ApiError::Forbidden) => {
+                Err(format!("Tool '{}' is not in scripting.allowed_tools", name))
+            }
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+pub(super) struct ScriptRuntime {
+    settings: ScriptingSettings,
+    config: Arc<Value>,
+    tools: Option<Arc<ToolAccess>>,
+    context: Arc<Mutex<Vec<String>>>,
+}
+
+impl ScriptRuntime {
+    pub(super) fn new(
+        settings: ScriptingSettings,
+        redacted_config: Value,
+        tools: Option<ToolAccess>,
+    ) -> Self {
+        Self {
+            settings,
+            config: Arc::new(redacted_config),
+            tools: tools.map(Arc::new),
+            context: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// フック関数を呼ぶ。スクリプトのトップレベルは実行しない。
+    pub(super) fn call(
+        &self,
+        script_id: &str,
+        ast: &AST,
+        hook: ScriptHook,
+        args: impl FuncArgs,
+    ) -> Result<Dynamic, String> {
+        let engine = self.engine(script_id);
+        let mut scope = Scope::new();
+        engine
+            .call_fn_with_options(
+                CallFnOptions::new().eval_ast(false),
+                &mut scope,
+                ast,
+                hook.function_name(),
+                args,
+            )
+            .map_err(|err| err.to_string())
+    }
+
+    /// 直前の呼び出しで `add_context` されたテキストを取り出す
+    pub(super) fn take_context(&self) -> Vec<String> {
+        std::mem::take(&mut *self.context.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn engine(&self, script_id: &str) -> Engine {
+        let mut engine = base_engine(self.settings.max_operations);
+
+        let deadline = Instant::now() + Duration::from_millis(self.settings.timeout_ms);
+        engine.on_progress(move |_| {
+            (Instant::now() > deadline).then(|| Dynamic::from("script timed out"))
+        });
+
+        let id = script_id.to_string();
+        engine.on_print(move |text| tracing::info!(script = %id, "{}", text));
+        let id = script_id.to_string();
+        engine.on_debug(move |text, _, _| tracing::debug!(script = %id, "{}", text));
+
+        let config = self.config.clone();
+        engine.register_fn("config", move |path: &str| -> Dynamic {
+            lookup(&config, path)
+                .and_then(|value| rhai::serde::to_dynamic(value).ok())
+                .unwrap_or(Dynamic::UNIT)
+        });
+
+        let context = self.context.clone();
+        engine.register_fn("add_context", move |text: &str| {
+            let text = text.trim();
+            if !text.is_empty() {
+                context
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(text.to_string());
+            }
+        });
+
+        let tools = self.tools.clone();
+        engine.register_fn(
+            "call_tool",
+            move |name: &str, args: rhai::Map| -> Result<String, Box<EvalAltResult>> {
+                let Some(tools) = &tools else {
+                    return Err("call_tool is not available here".into());
+                };
+                let args: Value = rhai::serde::from_dynamic(&Dynamic::from_map(args))?;
+                tools.call(name, &args).map_err(Into::into)
+            },
+        );
+        engine
+    }
+}
+
+fn lookup<'a>(config: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|part| !part.is_empty())
+        .try_fold(config, |value, key| match value {
+            Value::Object(map) => map.get(key),
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn runtime(config: Value) -> ScriptRuntime {
+        ScriptRuntime::new(ScriptingSettings::default(), config, None)
+    }
+
+    fn compile(source: &str) -> AST {
+        base_engine(1_000_000).compile(source).unwrap()
+    }
+
+    #[test]
+    fn config_reads_nested_values() {
+        let config = json!({"app": {"language": "ja"}, "tags": ["a", "b"]});
+        assert_eq!(lookup(&config, "app.language"), Some(&json!("ja")));
+        assert_eq!(lookup(&config, "tags.1"), Some(&json!("b")));
+        assert_eq!(lookup(&config, "app.missing"), None);
+
+        let ast = compile(r#"fn on_message_received(message) { config("app.language") }"#);
+        let result = runtime(config)
+            .call(
+                "t",
+                &ast,
+                ScriptHook::OnMessageReceived,
+                ("hi".to_string(),),
+            )
+            .unwrap();
+        assert_eq!(result.into_string().unwrap(), "ja");
+    }
+
+    #[test]
+    fn add_context_collects_text() {
+        let ast = compile(
+            r#"fn before_prompt_build(input) { add_context("mode: " + input.mode); add_context("  "); }"#,
+        );
+        let runtime = runtime(json!({}));
+        let input = rhai::serde::to_dynamic(json!({"mode": "chat"})).unwrap();
+        let returned = runtime
+            .call("t", &ast, ScriptHook::BeforePromptBuild, (input,))
+            .unwrap();
+        assert!(returned.is_unit());
+        assert_eq!(runtime.take_context(), vec!["mode: chat".to_string()]);
+        assert!(runtime.take_context().is_empty());
+    }
+
+    #[test]
+    fn runaway_scripts_are_stopped() {
+        let ast = compile(r#"fn on_message_received(message) { loop {} }"#);
+        let err = runtime(json!({}))
+            .call("t", &ast, ScriptHook::OnMessageReceived, ("x".to_string(),))
+            .unwrap_err();
+        assert!(!err.is_empty());
+    }
+
+    #[test]
+    fn eval_and_imports_are_unavailable() {
+        assert!(base_engine(1_000).compile(r#"eval("1")"#).is_err());
+        let ast = compile(r#"fn on_message_received(m) { import "x" as x; m }"#);
+        assert!(runtime(json!({}))
+            .call("t", &ast, ScriptHook::OnMessageReceived, ("x".to_string(),))
+            .is_err());
+    }
+
+    #[test]
+    fn call_tool_is_refused_without_tool_access() {
+        let ast = compile(r#"fn after_tool_result(tool, result) { call_tool("calculate", #{}) }"#);
+        let err = runtime(json!({}))
+            .call(
+                "t",
+                &ast,
+                ScriptHook::AfterToolResult,
+                ("search".to_string(), "r".to_string()),
+            )
+            .unwrap_err();
+        assert!(err.contains("not available"));
+    }
+}
@@ -0,0 +1,420 @@
+//! Rhai スクリプトによる軽量な自動化。
+//!
+//! スクリプトは `<user_data_dir>/scripts/<id>.rhai` に置き、次の名前の関数を定義すると
+//! 対応するタイミングで呼ばれる（有効なスクリプトを ID 順に）。
+//!
+//! - `on_message_received(message)` — 文字列を返すと送信されたメッセージを置き換える
+//! - `before_prompt_build(input)` — `add_context` したテキストをシステムプロンプトに足す。
+//!   `input` は `#{session_id, mode, user_input}`
+//! - `after_tool_result(tool, result)` — 文字列を返すとツールの結果を置き換える
+//!
+//! フックが失敗しても会話は止めず、ログに残して次のスクリプトへ進む。
+//! 使える API は [`engine`] を参照。
+
+mod engine;
+pub mod store;
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use rhai::{Dynamic, AST};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::core::config::{AppPaths, ConfigService};
+use crate::core::errors::ApiError;
+use crate::state::AppState;
+use engine::{base_engine, ScriptRuntime, ToolAccess};
+use store::{ScriptMeta, ScriptStore};
+
+tokio::task_local! {
+    /// スクリプト内からのツール呼び出し中。`after_tool_result` を再帰させない。
+    static IN_SCRIPT: ();
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptHook {
+    OnMessageReceived,
+    BeforePromptBuild,
+    AfterToolResult,
+}
+
+impl ScriptHook {
+    const ALL: [ScriptHook; 3] = [
+        ScriptHook::OnMessageReceived,
+        ScriptHook::BeforePromptBuild,
+        ScriptHook::AfterToolResult,
+    ];
+
+    pub fn function_name(self) -> &'static str {
+        match self {
+            ScriptHook::OnMessageReceived => "on_message_received",
+            ScriptHook::BeforePromptBuild => "before_prompt_build",
+            ScriptHook::AfterToolResult => "after_tool_result",
+        }
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            ScriptHook::OnMessageReceived | ScriptHook::BeforePromptBuild => 1,
+            ScriptHook::AfterToolResult => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptInfo {
+    #[serde(flatten)]
+    pub meta: ScriptMeta,
+    pub hooks: Vec<ScriptHook>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptDetail {
+    #[serde(flatten)]
+    pub info: ScriptInfo,
+    pub source: String,
+}
+
+/// 保存時の入力。`enabled` は既存スクリプトなら引き継ぎ、新規は無効で作る。
+#[derive(Debug, Clone)]
+pub struct ScriptInput {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub source: String,
+}
+
+struct CompiledScript {
+    id: String,
+    ast: AST,
+    hooks: Vec<ScriptHook>,
+}
+
+#[derive(Default)]
+struct Loaded {
+    scripts: Vec<Arc<CompiledScript>>,
+    errors: HashMap<String, String>,
+}
+
+#[derive(Clone)]
+pub struct ScriptManager {
+    store: ScriptStore,
+    config: ConfigService,
+    loaded: Arc<RwLock<Loaded>>,
+}
+
+impl ScriptManager {
+    pub fn new(paths: &AppPaths, config: ConfigService) -> Self {
+        Self {
+            store: ScriptStore::new(paths.user_data_dir.join("scripts")),
+            config,
+            loaded: Arc::new(RwLock::new(Loaded::default())),
+        }
+    }
+
+    /// 有効なスクリプトをコンパイルし直す。起動時とスクリプトの変更後に呼ぶ。
+    pub fn reload(&self) -> Result<(), ApiError> {
+        let engine = base_engine(u64::MAX);
+        let mut loaded = Loaded::default();
+        let mut metas = self.store.list()?;
+        metas.sort_by(|a, b| a.id.cmp(&b.id));
+        for meta in metas.into_iter().filter(|meta| meta.enabled) {
+            let compiled = self
+                .store
+                .read_source(&meta.id)
+                .map_err(|err| err.to_string())
+                .and_then(|source| engine.compile(&source).map_err(|err| err.to_string()));
+            match compiled {
+                Ok(ast) => loaded.scripts.push(Arc::new(CompiledScript {
+                    id: meta.id,
+                    hooks: detect_hooks(&ast),
+                    ast,
+                })),
+                Err(err) => {
+                    tracing::warn!(script = %meta.id, "Failed to compile script: {}", err);
+                    loaded.errors.insert(meta.id, err);
+                }
+            }
+        }
+        *self.loaded.write().unwrap_or_else(|e| e.into_inner()) = loaded;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Result<Vec<ScriptInfo>, ApiError> {
+        let loaded = self.loaded.read().unwrap_or_else(|e| e.into_inner());
+        Ok(self
+            .store
+            .list()?
+            .into_iter()
+            .map(|meta| info(&loaded, meta))
+            .collect())
+    }
+
+    pub fn get(&self, id: &str) -> Result<ScriptDetail, ApiError> {
+        let meta = self
+            .store
+            .get(id)?
+            .ok_or_else(|| ApiError::NotFound(format!("Script '{}' not found", id)))?;
+        let source = self.store.read_source(id)?;
+        let loaded = self.loaded.read().unwrap_or_else(|e| e.into_inner());
+        let mut info = info(&loaded, meta);
+        drop(loaded);
+        if info.hooks.is_empty() {
+            // 無効なスクリプトもどのフックを持つかは見せる
+            if let Ok(ast) = base_engine(u64::MAX).compile(&source) {
+                info.hooks = detect_hooks(&ast);
+            }
+        }
+        Ok(ScriptDetail { info, source })
+    }
+
+    /// コンパイルできないソースは保存しない
+    pub fn save(&self, input: ScriptInput) -> Result<ScriptDetail, ApiError> {
+        let name = input.name.trim();
+        if name.is_empty() {
+            return Err(ApiError::BadRequest("Script name is required".to_string()));
+        }
+        base_engine(u64::MAX)
+            .compile(&input.source)
+            .map_err(|err| ApiError::BadRequest(format!("Script does not compile: {}", err)))?;
+        let enabled = self
+            .store
+            .get(&input.id)?
+            .is_some_and(|existing| existing.enabled);
+        self.store.save(
+            ScriptMeta {
+                id: input.id.clone(),
+                name: name.to_string(),
+                description: input.description.trim().to_string(),
+                enabled,
+                updated_at: chrono::Utc::now().to_rfc3339(),
+            },
+            &input.source,
+        )?;
+        self.reload()?;
+        self.get(&input.id)
+    }
+
+    pub fn set_enabled(&self, id: &str, enabled: bool) -> Result<ScriptInfo, ApiError> {
+        let meta = self.store.set_enabled(id, enabled)?;
+        self.reload()?;
+        let loaded = self.loaded.read().unwrap_or_else(|e| e.into_inner());
+        Ok(info(&loaded, meta))
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), ApiError> {
+        if !self.store.delete(id)? {
+            return Err(ApiError::NotFound(format!("Script '{}' not found", id)));
+        }
+        self.reload()
+    }
+
+    /// 置き換え後のメッセージ。どのスクリプトも置き換えなければ `None`。
+    pub async fn on_message_received(
+        &self,
+        state: &AppState,
+        session_id: &str,
+        message: &str,
+    ) -> Option<String> {
+        let hook = ScriptHook::OnMessageReceived;
+        let (runtime, scripts) = self.prepare(state, hook, Some(session_id))?;
+        let original = message.to_string();
+        let rewritten = run_blocking(move || {
+            let mut message = original.clone();
+            for script in &scripts {
+                if let Some(text) = string_result(
+                    &script.id,
+                    hook,
+                    runtime.call(&script.id, &script.ast, hook, (message.clone(),)),
+                ) {
+                    message = text;
+                }
+            }
+            (message != original).then_some(message)
+        })
+        .await;
+        rewritten.flatten()
+    }
+
+    /// `(script_id, text)` の一覧。スクリプトが文字列を返した場合もそれを加える。
+    pub async fn before_prompt_build(
+        &self,
+        state: &AppState,
+        session_id: &str,
+        input: Value,
+    ) -> Vec<(String, String)> {
+        let hook = ScriptHook::BeforePromptBuild;
+        let Some((runtime, scripts)) = self.prepare(state, hook, Some(session_id)) else {
+            return Vec::new();
+        };
+        run_blocking(move || {
+            let input = rhai::serde::to_dynamic(input).unwrap_or(Dynamic::UNIT);
+            let mut parts = Vec::new();
+            for script in &scripts {
+                let returned = string_result(
+                    &script.id,
+                    hook,
+                    runtime.call(&script.id, &script.ast, hook, (input.clone(),)),
+                );
+                let mut texts = runtime.take_context();
+                texts.extend(returned.filter(|text| !text.trim().is_empty()));
+                if !texts.is_empty() {
+                    parts.push((script.id.clone(), texts.join("\n")));
+                }
+            }
+            parts
+        })
+        .await
+        .unwrap_or_default()
+    }
+
+    /// 置き換え後のツール結果。スクリプト自身のツール呼び出しには適用しない。
+    pub async fn after_tool_result(
+        &self,
+        state: &AppState,
+        session_id: Option<&str>,
+        tool_name: &str,
+        output: &str,
+    ) -> Option<String> {
+        if IN_SCRIPT.try_with(|_| ()).is_ok() {
+            return None;
+        }
+        let hook = ScriptHook::AfterToolResult;
+        let (runtime, scripts) = self.prepare(state, hook, session_id)?;
+        let tool_name = tool_name.to_string();
+        let original = output.to_string();
+        let rewritten = run_blocking(move || {
+            let mut output = original.clone();
+            for script in &scripts {
+                if let Some(text) = string_result(
+                    &script.id,
+                    hook,
+                    runtime.call(
+                        &script.id,
+                        &script.ast,
+                        hook,
+                        (tool_name.clone(), output.clone()),
+                    ),
+                ) {
+                    output = text;
+                }
+            }
+            (output != original).then_some(output)
+        })
+        .await;
+        rewritten.flatten()
+    }
+
+    fn prepare(
+        &self,
+        state: &AppState,
+        hook: ScriptHook,
+        session_id: Option<&str>,
+    ) -> Option<(ScriptRuntime, Vec<Arc<CompiledScript>>)> {
+        let settings = self.config.load_typed().ok()?.scripting;
+        if !settings.enabled {
+            return None;
+        }
+        let scripts: Vec<Arc<CompiledScript>> = self
+            .loaded
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .scripts
+            .iter()
+            .filter(|script| script.hooks.contains(&hook))
+            .cloned()
+            .collect();
+        if scripts.is_empty() {
+            return None;
+        }
+        let config = self.config.load_config().ok()?;
+        let redacted = self.config.redact_sensitive_values(&config);
+        let tools = ToolAccess::new(state.clone(), session_id.map(str::to_string), &settings);
+        Some((ScriptRuntime::new(settings, redacted, Some(tools)), scripts))
+    }
+}
+
+fn info(loaded: &Loaded, meta: ScriptMeta) -> ScriptInfo {
+    let hooks = loaded
+        .scripts
+        .iter()
+        .find(|script| script.id == meta.id)
+        .map(|script| script.hooks.clone())
+        .unwrap_or_default();
+    let error = loaded.errors.get(&meta.id).cloned();
+    ScriptInfo { meta, hooks, error }
+}
+
+fn detect_hooks(ast: &AST) -> Vec<ScriptHook> {
+    ScriptHook::ALL
+        .into_iter()
+        .filter(|hook| {
+            ast.iter_functions()
+                .any(|f| f.name == hook.function_name() && f.params.len() == hook.arity())
+        })
+        .collect()
+}
+
+/// 文字列の戻り値だけを採用する。エラーはログに残して無視する。
+fn string_result(
+    script_id: &str,
+    hook: ScriptHook,
+    result: Result<Dynamic, String>,
+) -> Option<String> {
+    match result {
+        Ok(value) if value.is_string() => value.into_string().ok(),
+        Ok(_) => None,
+        Err(err) => {
+            tracing::warn!(
+                script = %script_id,
+                hook = hook.function_name(),
+                "Script hook failed: {}",
+                err
+            );
+            None
+        }
+    }
+}
+
+async fn run_blocking<T: Send + 'static>(task: impl FnOnce() -> T + Send + 'static) -> Option<T> {
+    match tokio::task::spawn_blocking(task).await {
+        Ok(value) => Some(value),
+        Err(err) => {
+            tracing::warn!("Script hook task failed: {}", err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hooks_are_detected_by_name_and_arity() {
+        let ast = base_engine(1_000)
+            .compile(
+                r#"
+                fn on_message_received(message) { message }
+                fn after_tool_result(result) { result }
+                fn helper() {}
+                "#,
+            )
+            .unwrap();
+        assert_eq!(detect_hooks(&ast), vec![ScriptHook::OnMessageReceived]);
+    }
+
+    #[test]
+    fn only_string_results_are_used() {
+        let hook = ScriptHook::OnMessageReceived;
+        assert_eq!(
+            string_result("t", hook, Ok(Dynamic::from("x".to_string()))),
+            Some("x".to_string())
+        );
+        assert_eq!(string_result("t", hook, Ok(Dynamic::UNIT)), None);
+        assert_eq!(string_result("t", hook, Err("boom".to_string())), None);
+    }
+}
@@ -0,0 +1,175 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::errors::ApiError;
+
+const INDEX_FILE: &str = "scripts.json";
+const MAX_ID_LEN: usize = 64;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScriptMeta {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub enabled: bool,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScriptIndex {
+    #[serde(default)]
+    scripts: Vec<ScriptMeta>,
+}
+
+/// `<user_data_dir>/scripts/` に `<id>.rhai` と一覧の `scripts.json` を置く。
+#[derive(Debug, Clone)]
+pub struct ScriptStore {
+    root: PathBuf,
+}
+
+impl ScriptStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn list(&self) -> Result<Vec<ScriptMeta>, ApiError> {
+        Ok(self.load_index()?.scripts)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<ScriptMeta>, ApiError> {
+        Ok(self.list()?.into_iter().find(|meta| meta.id == id))
+    }
+
+    pub fn read_source(&self, id: &str) -> Result<String, ApiError> {
+        fs::read_to_string(self.source_path(id)?).map_err(ApiError::internal)
+    }
+
+    pub fn save(&self, meta: ScriptMeta, source: &str) -> Result<ScriptMeta, ApiError> {
+        let path = self.source_path(&meta.id)?;
+        fs::create_dir_all(&self.root).map_err(ApiError::internal)?;
+        fs::write(path, source).map_err(ApiError::internal)?;
+        let mut index = self.load_index()?;
+        match index.scripts.iter_mut().find(|item| item.id == meta.id) {
+            Some(existing) => *existing = meta.clone(),
+            None => index.scripts.push(meta.clone()),
+        }
+        self.save_index(&index)?;
+        Ok(meta)
+    }
+
+    pub fn set_enabled(&self, id: &str, enabled: bool) -> Result<ScriptMeta, ApiError> {
+        let mut index = self.load_index()?;
+        let meta = index
+            .scripts
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| ApiError::NotFound(format!("Script '{}' not found", id)))?;
+        meta.enabled = enabled;
+        let meta = meta.clone();
+        self.save_index(&index)?;
+        Ok(meta)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<bool, ApiError> {
+        let path = self.source_path(id)?;
+        let mut index = self.load_index()?;
+        let before = index.scripts.len();
+        index.scripts.retain(|item| item.id != id);
+        if index.scripts.len() == before {
+            return Ok(false);
+        }
+        self.save_index(&index)?;
+        if path.exists() {
+            fs::remove_file(path).map_err(ApiError::internal)?;
+        }
+        Ok(true)
+    }
+
+    fn source_path(&self, id: &str) -> Result<PathBuf, ApiError> {
+        if !is_valid_script_id(id) {
+            return Err(ApiError::BadRequest(format!(
+                "Invalid script id '{}': use up to {} lowercase letters, digits, '-' or '_'",
+                id, MAX_ID_LEN
+            )));
+        }
+        Ok(self.root.join(format!("{}.rhai", id)))
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.root.join(INDEX_FILE)
+    }
+
+    fn load_index(&self) -> Result<ScriptIndex, ApiError> {
+        let path = self.index_path();
+        if !path.exists() {
+            return Ok(ScriptIndex::default());
+        }
+        let contents = fs::read_to_string(&path).map_err(ApiError::internal)?;
+        if contents.trim().is_empty() {
+            return Ok(ScriptIndex::default());
+        }
+        serde_json::from_str(&contents).map_err(ApiError::internal)
+    }
+
+    fn save_index(&self, index: &ScriptIndex) -> Result<(), ApiError> {
+        let data = serde_json::to_string_pretty(index).map_err(ApiError::internal)?;
+        fs::create_dir_all(&self.root).map_err(ApiError::internal)?;
+        fs::write(self.index_path(), data).map_err(ApiError::internal)
+    }
+
+    #[cfg(test)]
+    pub fn root(&self) -> &std::path::Path {
+        &self.root
+    }
+}
+
+pub fn is_valid_script_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(id: &str) -> ScriptMeta {
+        ScriptMeta {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            enabled: false,
+            updated_at: "2026-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn save_toggle_and_delete_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = ScriptStore::new(temp.path().join("scripts"));
+        store.save(meta("greet"), "fn x() {}").unwrap();
+        assert!(store.root().join("greet.rhai").is_file());
+        assert_eq!(store.read_source("greet").unwrap(), "fn x() {}");
+
+        assert!(store.set_enabled("greet", true).unwrap().enabled);
+        assert!(store.get("greet").unwrap().unwrap().enabled);
+
+        assert!(store.delete("greet").unwrap());
+        assert!(!store.delete("greet").unwrap());
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn ids_cannot_escape_the_scripts_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = ScriptStore::new(temp.path().to_path_buf());
+        assert!(store.save(meta("../evil"), "").is_err());
+        assert!(!is_valid_script_id("Greet"));
+    }
+}
@@ -0,0 +1,315 @@
+//! Web 検索結果の整理と証拠パックの組み立て。
+//!
+//! 検索エンジンは同じページを URL の表記違い（`www.`、トラッキング用の
+//! クエリ、末尾の `/` など）で何度も返し、転載記事は本文がほぼ同じになる。
+//! 正規化した URL で重複を除き、似たスニペットをまとめてから、取得した本文の
+//! 要点と出典 ID (`[S1]` など) を付けた短い証拠パックにする。
+//! SearchNode の回答と SynthesizerNode はこのパックを根拠として使う。
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use reqwest::Url;
+use serde::Serialize;
+
+use crate::tools::search::SearchResult;
+
+/// 取り除くトラッキング用クエリ
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "msclkid", "ref", "ref_src", "spm"];
+/// この値以上の語の重なりがあるスニペットは同じ内容とみなす
+const SNIPPET_SIMILARITY_THRESHOLD: f32 = 0.6;
+const EXCERPT_MAX_CHARS: usize = 600;
+
+/// 重複判定に使う URL。解釈できない URL は前後の空白だけ除いて返す。
+pub fn canonical_url(raw: &str) -> String {
+    let Ok(mut url) = Url::parse(raw.trim()) else {
+        return raw.trim().to_string();
+    };
+    url.set_fragment(None);
+    if let Some(host) = url.host_str() {
+        let host = host.to_ascii_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+        let _ = url.set_host(Some(&host));
+    }
+    if url.port() == url.port_or_known_default() {
+        let _ = url.set_port(None);
+    }
+    let mut params = url
+        .query_pairs()
+        .filter(|(key, _)| {
+            let key = key.to_ascii_lowercase();
+            !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_str())
+        })
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    params.sort();
+    if params.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(params);
+    }
+    // http と https の違いは同じページとして扱う
+    let _ = url.set_scheme("https");
+
+    let mut canonical = url.to_string();
+    if url.query().is_none() && canonical.ends_with('/') {
+        canonical.pop();
+    }
+    canonical
+}
+
+/// 接続先ドメイン。取得の同時実行数をドメイン単位で抑えるのに使う。
+pub fn url_domain(raw: &str) -> String {
+    Url::parse(raw.trim())
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_ascii_lowercase()))
+        .map(|host| host.strip_prefix("www.").unwrap_or(&host).to_string())
+        .unwrap_or_default()
+}
+
+/// 正規化 URL が同じものを除く。先に出たもの（順位が高いもの）を残す。
+pub fn dedupe_by_canonical_url(results: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut seen = HashSet::new();
+    results
+        .into_iter()
+        .filter(|result| seen.insert(canonical_url(&result.url)))
+        .collect()
+}
+
+/// ほぼ同じスニペットを持つ結果のまとまり。
+#[derive(Debug, Clone)]
+pub struct SnippetCluster {
+    pub representative: SearchResult,
+    /// 代表以外で同じ内容を載せていた URL
+    pub duplicates: Vec<String>,
+}
+
+/// スニペットの語の重なり（Jaccard 係数）でまとめる。順位の高いものが代表になる。
+pub fn cluster_snippets(results: Vec<SearchResult>) -> Vec<SnippetCluster> {
+    let mut clusters: Vec<(SnippetCluster, HashSet<String>)> = Vec::new();
+    for result in results {
+        let terms = snippet_terms(&format!("{} {}", result.title, result.snippet));
+        let matched = clusters.iter_mut().find(|(_, cluster_terms)| {
+            jaccard(cluster_terms, &terms) >= SNIPPET_SIMILARITY_THRESHOLD
+        });
+        match matched {
+            Some((cluster, _)) => cluster.duplicates.push(result.url),
+            None => clusters.push((
+                SnippetCluster {
+                    representative: result,
+                    duplicates: Vec::new(),
+                },
+                terms,
+            )),
+        }
+    }
+    clusters.into_iter().map(|(cluster, _)| cluster).collect()
+}
+
+fn snippet_terms(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| term.chars().count() > 1)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn jaccard(left: &HashSet<String>, right: &HashSet<String>) -> f32 {
+    if left.is_empty() || right.is_empty() {
+        return 0.0;
+    }
+    let shared = left.intersection(right).count();
+    shared as f32 / (left.len() + right.len() - shared) as f32
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvidenceSource {
+    /// 回答中で引用に使う ID (`S1`, `S2`, ...)
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+    /// 取得した本文のうち質問に最も関係する段落
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+    /// 同じ内容を載せていた別の URL
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub also_reported_by: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EvidencePack {
+    pub query: String,
+    pub sources: Vec<EvidenceSource>,
+}
+
+impl EvidencePack {
+    /// `pages` は URL ごとの取得済み本文。
+    pub fn build(
+        query: &str,
+        clusters: &[SnippetCluster],
+        pages: &HashMap<String, String>,
+    ) -> Self {
+        let query_terms = snippet_terms(query);
+        let sources = clusters
+            .iter()
+            .enumerate()
+            .map(|(index, cluster)| {
+                let result = &cluster.representative;
+                EvidenceSource {
+                    id: format!("S{}", index + 1),
+                    title: result.title.clone(),
+                    url: result.url.clone(),
+                    snippet: result.snippet.clone(),
+                    excerpt: pages
+                        .get(&result.url)
+                        .and_then(|page| best_excerpt(page, &query_terms)),
+                    also_reported_by: cluster.duplicates.clone(),
+                }
+            })
+            .collect();
+        Self {
+            query: query.to_string(),
+            sources,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// プロンプトに入れる形。`max_chars` を超える出典は落とす。
+    pub fn render(&self, max_chars: usize) -> String {
+        self.render_budgeted(max_chars).0
+    }
+
+    /// `render` と同じ文字列と、入りきった先頭からの出典の数。
+    pub fn render_budgeted(&self, max_chars: usize) -> (String, usize) {
+        let mut out = format!("Evidence pack for: {}\n", self.query);
+        let mut included = 0;
+        for source in &self.sources {
+            let mut block = format!("[{}] {} <{}>\n", source.id, source.title, source.url);
+            let body = source.excerpt.as_deref().unwrap_or(&source.snippet);
+            if !body.trim().is_empty() {
+                block.push_str(body.trim());
+                block.push('\n');
+            }
+            if !source.also_reported_by.is_empty() {
+                block.push_str(&format!(
+                    "(also reported by {} other source(s))\n",
+                    source.also_reported_by.len()
+                ));
+            }
+            if out.chars().count() + block.chars().count() > max_chars {
+                break;
+            }
+            out.push_str(&block);
+            included += 1;
+        }
+        (out, included)
+    }
+}
+
+/// 質問の語を最も多く含む段落を切り出す。
+fn best_excerpt(page: &str, query_terms: &HashSet<String>) -> Option<String> {
+    let best = page
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| paragraph.chars().count() >= 40)
+        // 同点なら先に出てくる段落
+        .min_by_key(|paragraph| {
+            Reverse(snippet_terms(paragraph).intersection(query_terms).count())
+        })?;
+    let mut excerpt = best.chars().take(EXCERPT_MAX_CHARS).collect::<String>();
+    if best.chars().count() > EXCERPT_MAX_CHARS {
+        excerpt.push_str("...");
+    }
+    Some(excerpt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(title: &str, url: &str, snippet: &str) -> SearchResult {
+        SearchResult {
+            title: title.to_string(),
+            url: url.to_string(),
+            snippet: snippet.to_string(),
+        }
+    }
+
+    #[test]
+    fn canonical_url_ignores_tracking_and_cosmetic_differences() {
+        let canonical = canonical_url("https://example.com/post?id=3");
+        for variant in [
+            "http://www.Example.com/post?utm_source=x&id=3#top",
+            "https://example.com:443/post?id=3&fbclid=abc",
+        ] {
+            assert_eq!(canonical_url(variant), canonical);
+        }
+        assert_eq!(
+            canonical_url("https://example.com/docs/"),
+            canonical_url("https://example.com/docs")
+        );
+        assert_ne!(canonical_url("https://example.com/post?id=4"), canonical);
+        assert_eq!(url_domain("https://www.Example.com/a"), "example.com");
+    }
+
+    #[test]
+    fn dedupe_and_cluster_keep_highest_ranked_result() {
+        let results = dedupe_by_canonical_url(vec![
+            result(
+                "Rust 1.80 released",
+                "https://blog.rust-lang.org/1.80",
+                "Rust 1.80 stabilizes LazyCell and LazyLock types",
+            ),
+            result(
+                "dup",
+                "https://blog.rust-lang.org/1.80/?utm_medium=rss",
+                "same page",
+            ),
+            result(
+                "Rust 1.80 released!",
+                "https://news.example.com/rust",
+                "Rust 1.80 stabilizes LazyCell and LazyLock types today",
+            ),
+            result(
+                "Other topic",
+                "https://example.org/go",
+                "Go 1.23 adds range over func",
+            ),
+        ]);
+        assert_eq!(results.len(), 3);
+
+        let clusters = cluster_snippets(results);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(
+            clusters[0].representative.url,
+            "https://blog.rust-lang.org/1.80"
+        );
+        assert_eq!(
+            clusters[0].duplicates,
+            vec!["https://news.example.com/rust"]
+        );
+
+        let mut pages = HashMap::new();
+        pages.insert(
+            "https://blog.rust-lang.org/1.80".to_string(),
+            "Intro paragraph that is long enough to count as a paragraph.\n\n\
+             LazyCell and LazyLock are now stable, replacing once_cell for most uses."
+                .to_string(),
+        );
+        let pack = EvidencePack::build("LazyLock stable", &clusters, &pages);
+        assert_eq!(pack.sources[0].id, "S1");
+        assert!(pack.sources[0]
+            .excerpt
+            .as_deref()
+            .unwrap()
+            .starts_with("LazyCell"));
+        let rendered = pack.render(2_000);
+        assert!(rendered.contains("[S1] Rust 1.80 released"));
+        assert!(rendered.contains("[S2] Other topic"));
+        assert!(!pack.render(80).contains("[S2]"));
+        assert_eq!(pack.render_budgeted(2_000).1, 2);
+    }
+}
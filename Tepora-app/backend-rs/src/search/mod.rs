@@ -1,3 +1,7 @@
+pub mod evidence;
+pub mod research;
+pub mod source_quality;
+
 use serde::{Deserialize, Serialize};
 
 use crate::tools::search::SearchResult;
@@ -0,0 +1,224 @@
+//! 深掘りリサーチ (`research` モード) の予算と報告書。
+//!
+//! クエリ生成 → 検索 → 読解 → 不足点の洗い出し → クエリの見直し、を予算の
+//! 範囲で繰り返し、最後に `[S1]` 形式の出典付き報告書にまとめる。
+//! グラフ上の手順は `DeepResearchNode` が持ち、ここには状態を持たない部品だけを置く。
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::evidence::{EvidencePack, EvidenceSource};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResearchBudget {
+    pub max_rounds: usize,
+    pub queries_per_round: usize,
+    /// 集める出典の上限。超えた時点で調査を打ち切る
+    pub max_sources: usize,
+}
+
+impl Default for ResearchBudget {
+    fn default() -> Self {
+        Self {
+            max_rounds: 3,
+            queries_per_round: 3,
+            max_sources: 12,
+        }
+    }
+}
+
+impl ResearchBudget {
+    /// `search.research_max_rounds` などを読む。範囲外の値は丸める。
+    pub fn from_config(config: &Value) -> Self {
+        let defaults = Self::default();
+        let read = |key: &str, default: usize, max: usize| {
+            config
+                .get("search")
+                .and_then(|v| v.get(key))
+                .and_then(|v| v.as_u64())
+                .map(|v| (v as usize).clamp(1, max))
+                .unwrap_or(default)
+        };
+        Self {
+            max_rounds: read("research_max_rounds", defaults.max_rounds, 6),
+            queries_per_round: read("research_queries_per_round", defaults.queries_per_round, 5),
+            max_sources: read("research_max_sources", defaults.max_sources, 30),
+        }
+    }
+}
+
+/// 各ラウンドの終わりにモデルが返す見直し結果。
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GapReview {
+    #[serde(default)]
+    pub gaps: Vec<String>,
+    #[serde(default)]
+    pub next_queries: Vec<String>,
+    #[serde(default)]
+    pub sufficient: bool,
+}
+
+/// まだ投げていないクエリだけを `limit` 件まで残す。大文字小文字と前後の空白は区別しない。
+pub fn next_queries(candidates: Vec<String>, asked: &HashSet<String>, limit: usize) -> Vec<String> {
+    let mut seen = asked.clone();
+    candidates
+        .into_iter()
+        .map(|query| query.trim().to_string())
+        .filter(|query| !query.is_empty() && seen.insert(query.to_lowercase()))
+        .take(limit)
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResearchRound {
+    pub round: usize,
+    pub queries: Vec<String>,
+    /// このラウンドで新しく見つかった出典の数
+    pub new_sources: usize,
+    pub gaps: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResearchReport {
+    pub question: String,
+    pub rounds: Vec<ResearchRound>,
+    pub sources: Vec<EvidenceSource>,
+    /// 本文で実際に引用された出典 ID（出現順）
+    pub cited: Vec<String>,
+    pub body: String,
+}
+
+impl ResearchReport {
+    pub fn new(pack: EvidencePack, rounds: Vec<ResearchRound>, body: String) -> Self {
+        let known = pack
+            .sources
+            .iter()
+            .map(|source| source.id.as_str())
+            .collect::<HashSet<_>>();
+        let cited = cited_source_ids(&body)
+            .into_iter()
+            .filter(|id| known.contains(id.as_str()))
+            .collect();
+        Self {
+            question: pack.query,
+            rounds,
+            sources: pack.sources,
+            cited,
+            body,
+        }
+    }
+
+    /// 本文の後ろに、引用された出典の一覧を付けた Markdown。
+    pub fn render_markdown(&self) -> String {
+        let mut out = self.body.trim_end().to_string();
+        let cited = self
+            .sources
+            .iter()
+            .filter(|source| self.cited.contains(&source.id))
+            .collect::<Vec<_>>();
+        if !cited.is_empty() {
+            out.push_str("\n\n## Sources\n");
+            for source in cited {
+                out.push_str(&format!(
+                    "- [{}] {} <{}>\n",
+                    source.id, source.title, source.url
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// `[S1]` や `[S1, S3]` の形で書かれた出典 ID を出現順に重複なく返す。
+pub fn cited_source_ids(text: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find(']') else {
+            break;
+        };
+        let inner = &rest[..end];
+        let parsed = inner
+            .split(',')
+            .map(str::trim)
+            .map(|part| {
+                part.strip_prefix('S')
+                    .filter(|digits| {
+                        !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+                    })
+                    .map(|_| part.to_string())
+            })
+            .collect::<Option<Vec<_>>>();
+        for id in parsed.unwrap_or_default() {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn source(id: &str) -> EvidenceSource {
+        EvidenceSource {
+            id: id.to_string(),
+            title: format!("title {id}"),
+            url: format!("https://example.com/{id}"),
+            snippet: String::new(),
+            excerpt: None,
+            also_reported_by: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn budget_reads_and_clamps_config() {
+        let budget = ResearchBudget::from_config(&json!({
+            "search": { "research_max_rounds": 50, "research_queries_per_round": 0 }
+        }));
+        assert_eq!(budget.max_rounds, 6);
+        assert_eq!(budget.queries_per_round, 1);
+        assert_eq!(budget.max_sources, ResearchBudget::default().max_sources);
+    }
+
+    #[test]
+    fn refined_queries_skip_ones_already_asked() {
+        let asked = HashSet::from(["rust async".to_string()]);
+        let queries = next_queries(
+            vec![
+                " Rust Async ".to_string(),
+                "tokio runtime".to_string(),
+                "Tokio runtime".to_string(),
+                "".to_string(),
+                "async-std".to_string(),
+            ],
+            &asked,
+            1,
+        );
+        assert_eq!(queries, vec!["tokio runtime"]);
+    }
+
+    #[test]
+    fn report_keeps_only_known_citations_in_order() {
+        assert_eq!(
+            cited_source_ids("A [S2]. B [S1, S2]. not [S] or [see S3] or [1]"),
+            vec!["S2", "S1"]
+        );
+
+        let pack = EvidencePack {
+            query: "q".to_string(),
+            sources: vec![source("S1"), source("S2"), source("S3")],
+        };
+        let report = ResearchReport::new(pack, Vec::new(), "Claim [S3] and [S9].".to_string());
+        assert_eq!(report.cited, vec!["S3"]);
+        let markdown = report.render_markdown();
+        assert!(markdown.contains("## Sources\n- [S3] title S3 <https://example.com/S3>"));
+        assert!(!markdown.contains("[S1]"));
+    }
+}
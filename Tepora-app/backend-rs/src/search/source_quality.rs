@@ -0,0 +1,189 @@
+//! 引用から学ぶ出典ドメインの順位付け。
+//!
+//! 回答が証拠パックのどの出典を `[S1]` の形で引用したかを、プロンプトに入った
+//! 出典（文字数の上限で落ちたものは除く）についてドメインごとに数えて履歴 DB に
+//! 貯める。検索結果は、元の順位とドメインの引用率を `search.source_quality_weight`
+//! の割合で混ぜて並べ替える。すべて手元の記録だけで完結する。
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::evidence::{url_domain, EvidenceSource};
+use super::research::cited_source_ids;
+use crate::history::DomainQuality;
+use crate::state::AppState;
+use crate::tools::search::SearchResult;
+
+const DEFAULT_QUALITY_WEIGHT: f32 = 0.3;
+
+/// 0 なら学習した質を並べ替えに使わない（記録は続ける）。
+pub fn source_quality_weight(config: &Value) -> f32 {
+    config
+        .get("search")
+        .and_then(|v| v.get("source_quality_weight"))
+        .and_then(|v| v.as_f64())
+        .map(|v| v as f32)
+        .unwrap_or(DEFAULT_QUALITY_WEIGHT)
+        .clamp(0.0, 1.0)
+}
+
+/// プロンプトに入れた出典のドメインと、回答がそれを引用したか。
+pub fn citation_outcomes(injected: &[EvidenceSource], answer: &str) -> Vec<(String, bool)> {
+    let cited = cited_source_ids(answer);
+    injected
+        .iter()
+        .filter_map(|source| {
+            let domain = url_domain(&source.url);
+            (!domain.is_empty()).then(|| (domain, cited.contains(&source.id)))
+        })
+        .collect()
+}
+
+/// 元の順位（先頭が 1、末尾が 1/n）と引用率を `weight` で混ぜて並べ替える。
+/// 記録のないドメインは引用率 0.5 とみなす。同点なら元の順。
+pub fn rank_by_quality(
+    results: Vec<SearchResult>,
+    quality: &HashMap<String, f32>,
+    weight: f32,
+) -> Vec<SearchResult> {
+    if weight <= 0.0 || results.len() < 2 {
+        return results;
+    }
+    let count = results.len() as f32;
+    let neutral = DomainQuality::default().score();
+    let mut scored = results
+        .into_iter()
+        .enumerate()
+        .map(|(index, result)| {
+            let position = 1.0 - index as f32 / count;
+            let learned = quality
+                .get(&url_domain(&result.url))
+                .copied()
+                .unwrap_or(neutral);
+            ((1.0 - weight) * position + weight * learned, result)
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by(|(left, _), (right, _)| right.total_cmp(left));
+    scored.into_iter().map(|(_, result)| result).collect()
+}
+
+/// 履歴 DB の引用率で `results` を並べ替える。読めなければ元の順のまま。
+pub async fn rank_with_learned_quality(
+    app_state: &AppState,
+    config: &Value,
+    results: Vec<SearchResult>,
+) -> Vec<SearchResult> {
+    let weight = source_quality_weight(config);
+    if weight <= 0.0 || results.len() < 2 {
+        return results;
+    }
+    let mut domains = results
+        .iter()
+        .map(|result| url_domain(&result.url))
+        .filter(|domain| !domain.is_empty())
+        .collect::<Vec<_>>();
+    domains.sort();
+    domains.dedup();
+    match app_state.runtime().history.source_quality(&domains).await {
+        Ok(quality) => {
+            let scores = quality
+                .into_iter()
+                .map(|(domain, quality)| (domain, quality.score()))
+                .collect();
+            rank_by_quality(results, &scores, weight)
+        }
+        Err(err) => {
+            tracing::debug!("Source quality ranking skipped: {}", err);
+            results
+        }
+    }
+}
+
+/// 回答が引用した出典を記録する。失敗しても回答には影響させない。
+pub async fn record_cited_sources(app_state: &AppState, injected: &[EvidenceSource], answer: &str) {
+    let outcomes = citation_outcomes(injected, answer);
+    if let Err(err) = app_state
+        .runtime()
+        .history
+        .record_source_citations(&outcomes)
+        .await
+    {
+        tracing::warn!("Failed to record cited search sources: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn result(url: &str) -> SearchResult {
+        SearchResult {
+            title: url.to_string(),
+            url: url.to_string(),
+            snippet: String::new(),
+        }
+    }
+
+    fn source(id: &str, url: &str) -> EvidenceSource {
+        EvidenceSource {
+            id: id.to_string(),
+            title: String::new(),
+            url: url.to_string(),
+            snippet: String::new(),
+            excerpt: None,
+            also_reported_by: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn outcomes_mark_only_cited_sources() {
+        let injected = [
+            source("S1", "https://www.docs.rs/tokio"),
+            source("S2", "https://spam.example/post"),
+            source("S3", "not a url"),
+        ];
+        let outcomes = citation_outcomes(&injected, "Use a runtime [S1]. Also [S1, S4].");
+        assert_eq!(
+            outcomes,
+            vec![
+                ("docs.rs".to_string(), true),
+                ("spam.example".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn learned_quality_lifts_often_cited_domains() {
+        let results = vec![
+            result("https://spam.example/a"),
+            result("https://docs.rs/b"),
+            result("https://unseen.org/c"),
+        ];
+        let quality = HashMap::from([
+            ("spam.example".to_string(), 0.1),
+            ("docs.rs".to_string(), 0.9),
+        ]);
+
+        let ranked = rank_by_quality(results.clone(), &quality, 0.5);
+        let urls = ranked.iter().map(|r| r.url.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            vec![
+                "https://docs.rs/b",
+                "https://spam.example/a",
+                "https://unseen.org/c"
+            ]
+        );
+
+        let unchanged = rank_by_quality(results.clone(), &quality, 0.0);
+        assert_eq!(unchanged[0].url, results[0].url);
+        assert_eq!(
+            source_quality_weight(&json!({"search": {"source_quality_weight": 3}})),
+            1.0
+        );
+        assert_eq!(source_quality_weight(&json!({})), DEFAULT_QUALITY_WEIGHT);
+    }
+}
@@ -0,0 +1,116 @@
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::core::errors::ApiError;
+use crate::state::{AppState, AppStateRead};
+
+const DEFAULT_AUDIT_LIMIT: i64 = 100;
+const MAX_AUDIT_LIMIT: i64 = 500;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub before_id: Option<i64>,
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+pub async fn list_audit(
+    State(state): State<AppStateRead>,
+    Query(query): Query<AuditQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_AUDIT_LIMIT)
+        .clamp(1, MAX_AUDIT_LIMIT);
+    let entries = state
+        .runtime()
+        .history
+        .list_admin_audit(limit, query.before_id, query.action.as_deref())
+        .await?;
+    let next_before_id = if entries.len() as i64 == limit {
+        entries.last().map(|entry| entry.id)
+    } else {
+        None
+    };
+    Ok(Json(json!({
+        "entries": entries,
+        "next_before_id": next_before_id,
+    })))
+}
+
+/// リクエストを出した主体。キーそのものは残さず、指紋だけを記録する。
+pub fn audit_actor(headers: &HeaderMap) -> Value {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.chars().take(200).collect::<String>())
+    };
+    let key_fingerprint = header("x-api-key")
+        .filter(|key| !key.is_empty())
+        .map(|key| hex::encode(Sha256::digest(key.as_bytes()))[..12].to_string());
+    json!({
+        "key_fingerprint": key_fingerprint,
+        "origin": header("origin"),
+        "user_agent": header("user-agent"),
+    })
+}
+
+/// 特権操作を監査テーブルへ追記する。記録の失敗で操作自体は止めない。
+pub async fn record_admin_action<T>(
+    state: &AppState,
+    headers: &HeaderMap,
+    action: &str,
+    target: Option<&str>,
+    result: &Result<T, ApiError>,
+    details: Value,
+) {
+    let (outcome, details) = match result {
+        Ok(_) => ("success", details),
+        Err(err) => {
+            let mut details = details;
+            if let Some(map) = details.as_object_mut() {
+                map.insert("error".to_string(), Value::String(err.to_string()));
+            } else {
+                details = json!({ "error": err.to_string() });
+            }
+            ("error", details)
+        }
+    };
+    if let Err(err) = state
+        .runtime()
+        .history
+        .record_admin_audit(&audit_actor(headers), action, target, outcome, &details)
+        .await
+    {
+        tracing::warn!(action, "Failed to record admin audit entry: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn audit_actor_fingerprints_the_api_key() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", HeaderValue::from_static("session-secret"));
+        headers.insert("origin", HeaderValue::from_static("tauri://localhost"));
+
+        let actor = audit_actor(&headers);
+        let fingerprint = actor["key_fingerprint"].as_str().unwrap();
+        assert_eq!(fingerprint.len(), 12);
+        assert!(!actor.to_string().contains("session-secret"));
+        assert_eq!(actor["origin"], "tauri://localhost");
+        assert!(actor["user_agent"].is_null());
+    }
+}
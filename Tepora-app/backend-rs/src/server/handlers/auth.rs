@@ -1,8 +1,13 @@
+use axum::http::HeaderMap;
 use axum::{extract::State, Json};
+use serde::Deserialize;
 use serde_json::json;
 
+use crate::core::config::paths::tepora_home_dir;
+use crate::core::config::users::UserRegistry;
 use crate::core::errors::ApiError;
-use crate::state::AppStateRead;
+use crate::server::handlers::audit::record_admin_action;
+use crate::state::{AppStateRead, AppStateWrite};
 
 pub async fn refresh_token(
     State(state): State<AppStateRead>,
@@ -19,6 +24,90 @@ pub async fn refresh_token(
     let expires_at = token_lock.expires_at().to_rfc3339();
 
     Ok(Json(
-        json!({ "token": new_token, "expires_at": expires_at }),
+        json!({ "token": new_token, "expires_at": expires_at, "user": token_lock.scope() }),
     ))
 }
+
+/// このマシンのユーザー一覧と、このバックエンドが動いているユーザー。
+/// 切り替えはデスクトップシェルが再起動して行う。
+pub async fn list_users(
+    State(state): State<AppStateRead>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let active = state.core().session_token.read().await.scope().to_string();
+    let registry = UserRegistry::load(&tepora_home_dir());
+    Ok(Json(json!({
+        "active": active,
+        "users": registry.profiles(),
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateUserRequest {
+    pub id: String,
+    #[serde(default)]
+    pub display_name: String,
+}
+
+pub async fn create_user(
+    State(state): State<AppStateWrite>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateUserRequest>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let id = payload.id.trim();
+    let home = tepora_home_dir();
+    let mut registry = UserRegistry::load(&home);
+    let result = state
+        .core()
+        .security
+        .ensure_lockdown_disabled("user_create")
+        .and_then(|_| registry.create(id, &payload.display_name))
+        .and_then(|user| registry.save(&home).map(|_| user));
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "user_create",
+        Some(id),
+        &result,
+        json!({}),
+    )
+    .await;
+    Ok(Json(json!({ "user": result? })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SelectUserRequest {
+    pub id: String,
+}
+
+/// 次回起動時のユーザーを選ぶ。実行中のバックエンドのデータディレクトリは変わらない。
+pub async fn select_user(
+    State(state): State<AppStateWrite>,
+    headers: HeaderMap,
+    Json(payload): Json<SelectUserRequest>,
+) -> Result<impl axum::response::IntoResponse, ApiError> {
+    let id = payload.id.trim();
+    let home = tepora_home_dir();
+    let mut registry = UserRegistry::load(&home);
+    let result = state
+        .core()
+        .security
+        .ensure_lockdown_disabled("user_select")
+        .and_then(|_| registry.select(id))
+        .and_then(|_| registry.save(&home));
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "user_select",
+        Some(id),
+        &result,
+        json!({}),
+    )
+    .await;
+    result?;
+    let active = state.core().session_token.read().await.scope().to_string();
+    Ok(Json(json!({
+        "status": "success",
+        "active": active,
+        "restart_required": active != id,
+    })))
+}
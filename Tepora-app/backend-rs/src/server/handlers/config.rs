@@ -1,51 +1,153 @@
 use axum::extract::State;
+use axum::http::HeaderMap;
 use axum::response::IntoResponse;
 use axum::Json;
+use serde::Deserialize;
 use serde_json::{json, Value};
 
+use crate::core::config::profiles::profile_names;
+use crate::core::config::schema::config_json_schema;
+use crate::core::config::service::config_diff;
 use crate::core::errors::ApiError;
+use crate::server::handlers::audit::record_admin_action;
 use crate::server::handlers::utils::absolutize_mcp_path;
 use crate::state::{AppStateRead, AppStateWrite};
 
+/// 編集用にプロファイルを重ねる前の設定を返す（重ねた値を保存し直さないため）。
 pub async fn get_config(State(state): State<AppStateRead>) -> Result<impl IntoResponse, ApiError> {
-    let config = state.core().config.load_config()?;
+    let config = state.core().config.load_base_config()?;
     let mut redacted = state.core().config.redact_sensitive_values(&config);
     absolutize_mcp_path(&mut redacted, &state.core().paths);
     Ok(Json(redacted))
 }
 
+/// 設定の JSON Schema。エディタ補完や UI のフォーム生成に使う。
+pub async fn get_config_schema() -> impl IntoResponse {
+    Json(config_json_schema())
+}
+
 pub async fn update_config(
     State(state): State<AppStateWrite>,
+    headers: HeaderMap,
     Json(payload): Json<Value>,
 ) -> Result<impl IntoResponse, ApiError> {
-    state
-        .core()
-        .security
-        .ensure_lockdown_disabled("config_update")?;
-    state.core().config.update_config(payload, false)?;
+    apply_config_update(&state, &headers, "config_update", payload, false).await?;
     Ok(Json(json!({"status": "success"})))
 }
 
 pub async fn patch_config(
     State(state): State<AppStateWrite>,
+    headers: HeaderMap,
     Json(payload): Json<Value>,
 ) -> Result<impl IntoResponse, ApiError> {
-    state
+    apply_config_update(&state, &headers, "config_patch", payload, true).await?;
+    Ok(Json(json!({"status": "success"})))
+}
+
+async fn apply_config_update(
+    state: &AppStateWrite,
+    headers: &HeaderMap,
+    action: &str,
+    payload: Value,
+    merge: bool,
+) -> Result<(), ApiError> {
+    let before = state
+        .core()
+        .config
+        .load_base_config()
+        .unwrap_or(Value::Null);
+    let result = state
         .core()
         .security
-        .ensure_lockdown_disabled("config_patch")?;
-    state.core().config.update_config(payload, true)?;
-    Ok(Json(json!({"status": "success"})))
+        .ensure_lockdown_disabled(action)
+        .and_then(|_| state.core().config.update_config(payload, merge));
+    let after = match &result {
+        Ok(()) => state
+            .core()
+            .config
+            .load_base_config()
+            .unwrap_or(Value::Null),
+        Err(_) => before.clone(),
+    };
+    record_admin_action(
+        &state.shared(),
+        headers,
+        action,
+        None,
+        &result,
+        json!({ "changes": config_diff(&before, &after) }),
+    )
+    .await;
+    result
+}
+
+pub async fn list_profiles(
+    State(state): State<AppStateRead>,
+) -> Result<impl IntoResponse, ApiError> {
+    let config = state.core().config.load_base_config()?;
+    Ok(Json(json!({
+        "active": state.core().config.active_profile(),
+        "profiles": profile_names(&config),
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SelectProfileRequest {
+    /// `null` で基本設定に戻す
+    pub name: Option<String>,
+}
+
+pub async fn select_profile(
+    State(state): State<AppStateWrite>,
+    headers: HeaderMap,
+    Json(payload): Json<SelectProfileRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let name = payload
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    let previous = state.core().config.active_profile();
+    let result = state
+        .core()
+        .security
+        .ensure_lockdown_disabled("config_profile_select")
+        .and_then(|_| state.core().config.select_profile(name));
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "config_profile_select",
+        name,
+        &result,
+        json!({ "previous": previous.map(|active| active.name) }),
+    )
+    .await;
+    result?;
+    Ok(Json(json!({
+        "status": "success",
+        "active": state.core().config.active_profile(),
+    })))
 }
 
 pub async fn rotate_secrets(
     State(state): State<AppStateWrite>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, ApiError> {
-    state
+    let result = state
         .core()
         .security
-        .ensure_lockdown_disabled("secret_rotation")?;
-    let rotated = state.core().config.rotate_secrets()?;
+        .ensure_lockdown_disabled("secret_rotation")
+        .and_then(|_| state.core().config.rotate_secrets());
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "secret_rotation",
+        None,
+        &result,
+        json!({ "rotated": result.as_ref().ok() }),
+    )
+    .await;
+    let rotated = result?;
     state.core().security.record_audit(
         "secrets_rotated",
         "success",
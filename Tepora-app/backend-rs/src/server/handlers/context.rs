@@ -0,0 +1,202 @@
+use axum::extract::{Path, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::context::controller::{ContextController, TokenEstimator};
+use crate::context::pipeline::ContextPipeline;
+use crate::context::pipeline_context::PipelineMode;
+use crate::core::errors::ApiError;
+use crate::state::AppStateRead;
+
+#[derive(Debug, Deserialize)]
+pub struct ContextPreviewRequest {
+    pub session_id: Option<String>,
+    #[serde(alias = "message")]
+    pub query: String,
+    #[serde(default)]
+    pub mode: Option<PipelineMode>,
+    #[serde(default)]
+    pub skip_web_search: bool,
+}
+
+/// Assemble the prompt for a hypothetical turn without calling the LLM.
+pub async fn preview_context(
+    State(state): State<AppStateRead>,
+    Json(payload): Json<ContextPreviewRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let session_id = payload
+        .session_id
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "default".to_string());
+    let mode = payload.mode.unwrap_or(PipelineMode::Chat);
+
+    let pipeline_ctx = ContextPipeline::build_preview(
+        &state.shared(),
+        &session_id,
+        &payload.query,
+        mode,
+        payload.skip_web_search,
+    )
+    .await?;
+    let (messages, composition) =
+        ContextController::new(&pipeline_ctx).render_with_report(&pipeline_ctx);
+
+    let estimator = TokenEstimator::new(pipeline_ctx.tokenizer_spec.clone());
+    let rendered = messages
+        .iter()
+        .map(|message| {
+            json!({
+                "role": message.role,
+                "content": message.content,
+                "tokens": estimator.count_text(&message.content).tokens,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(json!({
+        "session_id": session_id,
+        "mode": mode,
+        "messages": rendered,
+        "total_tokens": composition.rendered_prompt_tokens,
+        "token_budget": pipeline_ctx.token_budget,
+        "composition": composition,
+    })))
+}
+
+/// Composition of the most recent prompt sent for the session, so the UI can
+/// show where the budget went and what was cut.
+pub async fn get_session_context_usage(
+    State(state): State<AppStateRead>,
+    Path(session_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let history = &state.runtime().history;
+    if history.get_session(&session_id).await?.is_none() {
+        return Err(ApiError::NotFound("Session not found".to_string()));
+    }
+    let summary = history.get_session_summary(&session_id).await?;
+    let conversation_summary = summary.map(|summary| {
+        json!({
+            "covered_message_id": summary.covered_message_id,
+            "updated_at": summary.updated_at,
+        })
+    });
+
+    let Some(event) = history.latest_context_composition(&session_id).await? else {
+        return Ok(Json(json!({
+            "session_id": session_id,
+            "recorded_at": Value::Null,
+            "model_id": Value::Null,
+            "composition": Value::Null,
+            "shares": [],
+            "conversation_summary": conversation_summary,
+        })));
+    };
+    let composition = event
+        .metadata
+        .get("context_composition")
+        .cloned()
+        .unwrap_or(Value::Null);
+
+    Ok(Json(json!({
+        "session_id": session_id,
+        "recorded_at": event.created_at,
+        "model_id": event.metadata.get("model_id").cloned().unwrap_or(Value::Null),
+        "shares": usage_shares(&composition),
+        "composition": composition,
+        "conversation_summary": conversation_summary,
+    })))
+}
+
+/// Token share of each system part and worker section in the rendered prompt.
+/// Whatever is left over (user input, message framing) is reported as `other`.
+fn usage_shares(composition: &Value) -> Vec<Value> {
+    let total = composition
+        .get("rendered_prompt_tokens")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    let entries = |key: &str, name_key: &str, tokens_key: &str| {
+        composition
+            .get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.get(name_key)?.as_str()?.to_string();
+                let tokens = entry.get(tokens_key)?.as_u64()?;
+                Some((name, tokens))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut parts = entries("system_parts", "label", "tokens")
+        .into_iter()
+        .map(|(name, tokens)| (name, "system_part", tokens))
+        .chain(
+            entries("sections", "section", "used_tokens")
+                .into_iter()
+                .map(|(name, tokens)| (name, "section", tokens)),
+        )
+        .filter(|(_, _, tokens)| *tokens > 0)
+        .collect::<Vec<_>>();
+    let accounted: u64 = parts.iter().map(|(_, _, tokens)| tokens).sum();
+    if total > accounted {
+        parts.push(("other".to_string(), "other", total - accounted));
+    }
+
+    parts
+        .into_iter()
+        .map(|(name, kind, tokens)| {
+            let share = if total == 0 {
+                0.0
+            } else {
+                tokens as f64 / total as f64
+            };
+            json!({ "name": name, "kind": kind, "tokens": tokens, "share": share })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_request_accepts_message_alias_and_mode() {
+        let request: ContextPreviewRequest = serde_json::from_value(json!({
+            "message": "what did we decide?",
+            "mode": "search_fast"
+        }))
+        .unwrap();
+        assert_eq!(request.query, "what did we decide?");
+        assert_eq!(request.mode, Some(PipelineMode::SearchFast));
+        assert!(!request.skip_web_search);
+        assert!(request.session_id.is_none());
+    }
+
+    #[test]
+    fn usage_shares_cover_system_parts_sections_and_remainder() {
+        let shares = usage_shares(&json!({
+            "rendered_prompt_tokens": 200,
+            "system_parts": [
+                { "label": "base_system", "priority": 200, "tokens": 50 },
+                { "label": "empty", "priority": 10, "tokens": 0 }
+            ],
+            "sections": [
+                { "section": "memory", "used_tokens": 30 },
+                { "section": "rag", "used_tokens": 100 }
+            ]
+        }));
+
+        let names = shares
+            .iter()
+            .map(|share| share["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["base_system", "memory", "rag", "other"]);
+        assert_eq!(shares[2]["share"], json!(0.5));
+        assert_eq!(shares[3]["kind"], "other");
+        assert_eq!(shares[3]["tokens"], 20);
+        assert!(usage_shares(&Value::Null).is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::agent::evaluation::{aggregate_evaluations, prompt_revision};
+use crate::agent::portable::{export_agent, import_agent};
+use crate::core::errors::ApiError;
+use crate::models::event::AgentEventType;
+use crate::state::{AppStateRead, AppStateWrite};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+pub async fn export_custom_agent(
+    State(state): State<AppStateRead>,
+    Path(agent_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let file = export_agent(&state.shared(), &agent_id).await?;
+    Ok(Json(file))
+}
+
+pub async fn import_custom_agent(
+    State(state): State<AppStateWrite>,
+    Query(query): Query<ImportQuery>,
+    Json(payload): Json<Value>,
+) -> Result<impl IntoResponse, ApiError> {
+    state
+        .core()
+        .security
+        .ensure_lockdown_disabled("custom_agent_import")?;
+    let outcome = import_agent(&state.shared(), payload, query.overwrite).await?;
+    Ok(Json(json!({
+        "success": true,
+        "skill": outcome.skill,
+        "warnings": outcome.warnings,
+    })))
+}
+
+pub async fn list_exclusive_agents(
+    State(state): State<AppStateRead>,
+) -> Result<impl IntoResponse, ApiError> {
+    let shared = state.shared();
+    let agents = shared.ai().exclusive_agents.list(&shared).await;
+    let loaded_model_id = shared.ai().llama.loaded_model_id().await;
+    Ok(Json(json!({
+        "agents": agents,
+        "loaded_model_id": loaded_model_id,
+    })))
+}
+
+pub async fn preload_exclusive_agent(
+    State(state): State<AppStateWrite>,
+    Path(agent_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let shared = state.shared();
+    let status = shared
+        .ai()
+        .exclusive_agents
+        .preload(&shared, &agent_id)
+        .await?;
+    Ok(Json(json!({"success": true, "agent": status})))
+}
+
+pub async fn unload_exclusive_agent(
+    State(state): State<AppStateWrite>,
+    Path(agent_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let shared = state.shared();
+    let status = shared
+        .ai()
+        .exclusive_agents
+        .unload(&shared, &agent_id)
+        .await?;
+    Ok(Json(json!({"success": true, "agent": status})))
+}
+
+pub async fn get_custom_agent_stats(
+    State(state): State<AppStateRead>,
+    Path(agent_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let current_revision = state
+        .ai()
+        .skill_registry
+        .get(&agent_id)
+        .map(|skill| prompt_revision(&skill.skill_body));
+    let events = state
+        .runtime()
+        .history
+        .get_agent_events_for_agent(&agent_id, AgentEventType::Evaluation)
+        .await?;
+    if current_revision.is_none() && events.is_empty() {
+        return Err(ApiError::NotFound("Agent Skill not found".to_string()));
+    }
+    let stats = aggregate_evaluations(&agent_id, current_revision, &events);
+    Ok(Json(json!({
+        "stats": stats,
+        "last_used_at": state.ai().exclusive_agents.last_used(&agent_id),
+    })))
+}
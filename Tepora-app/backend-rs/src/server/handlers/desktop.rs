@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket};
+use axum::extract::{State, WebSocketUpgrade};
+use axum::http::{header, HeaderMap};
+use axum::response::IntoResponse;
+use axum::Json;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::core::desktop_bridge::{
+    DesktopBridgeReply, DesktopCapability, DesktopReply, PendingDesktopRequest,
+    DESKTOP_BRIDGE_TOKEN_HEADER,
+};
+use crate::core::errors::ApiError;
+use crate::core::security::verify_desktop_bridge_token;
+use crate::state::{AppState, AppStateRead};
+use crate::tools::desktop::request_desktop;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DesktopCaptureRequest {
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+pub async fn desktop_status(State(state): State<AppStateRead>) -> impl IntoResponse {
+    Json(json!({ "attached": state.integration().desktop.is_attached() }))
+}
+
+async fn capture(
+    state: &AppStateRead,
+    capability: DesktopCapability,
+    body: Option<Json<DesktopCaptureRequest>>,
+) -> Result<DesktopReply, ApiError> {
+    let Json(body) = body.unwrap_or_default();
+    let shared = state.shared();
+    request_desktop(
+        &shared,
+        &shared.integration().desktop,
+        capability,
+        body.reason,
+        body.session_id.as_deref(),
+    )
+    .await
+}
+
+pub async fn read_clipboard(
+    State(state): State<AppStateRead>,
+    body: Option<Json<DesktopCaptureRequest>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let reply = capture(&state, DesktopCapability::ClipboardText, body).await?;
+    Ok(Json(reply))
+}
+
+pub async fn capture_screenshot(
+    State(state): State<AppStateRead>,
+    body: Option<Json<DesktopCaptureRequest>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let reply = capture(&state, DesktopCapability::Screenshot, body).await?;
+    Ok(Json(reply))
+}
+
+/// デスクトップシェル専用の接続口。繋がっている間だけクリップボードと
+/// スクリーンショットの要求がシェルに届く。
+pub async fn desktop_bridge(
+    ws: WebSocketUpgrade,
+    State(state): State<AppStateRead>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    // ブラウザの WebSocket はヘッダーを足せず Origin を必ず付けるので、シェルではない
+    if headers.contains_key(header::ORIGIN) {
+        return Err(ApiError::Forbidden);
+    }
+    let presented = headers
+        .get(DESKTOP_BRIDGE_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let user_id = state.core().session_token.read().await.scope().to_string();
+    if !verify_desktop_bridge_token(&user_id, presented) {
+        tracing::warn!("Desktop bridge connection rejected: invalid token");
+        return Err(ApiError::Unauthorized);
+    }
+    let state = state.shared();
+    Ok(ws.on_upgrade(move |socket| serve_desktop_bridge(socket, state)))
+}
+
+async fn serve_desktop_bridge(socket: WebSocket, state: Arc<AppState>) {
+    let mut requests = state.integration().desktop.attach();
+    let (mut sink, mut stream) = socket.split();
+    // 返事を待っている要求。切断で落ちると要求側には失敗として返る
+    let mut waiting = HashMap::<String, PendingDesktopRequest>::new();
+    tracing::info!("Desktop shell attached");
+
+    loop {
+        tokio::select! {
+            pending = requests.recv() => {
+                // 別のシェルが繋ぎ直すと古い受信口は閉じる
+                let Some(pending) = pending else { break };
+                let Ok(text) = serde_json::to_string(&pending.request) else { continue };
+                if sink.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+                waiting.insert(pending.request.id.clone(), pending);
+            }
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    match serde_json::from_str::<DesktopBridgeReply>(&text) {
+                        Ok(DesktopBridgeReply { id, reply }) => {
+                            if let Some(pending) = waiting.remove(&id) {
+                                pending.respond(reply);
+                            }
+                        }
+                        Err(err) => tracing::warn!("Ignoring malformed desktop reply: {}", err),
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+    tracing::info!("Desktop shell detached");
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::Message as ClientMessage;
+
+    use super::*;
+    use crate::core::desktop_bridge::DesktopRequest;
+    use crate::core::security::issue_desktop_bridge_token;
+    use crate::test_support::{init_state_with_config, serve_router, ENV_LOCK};
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn clipboard_requests_reach_the_shell_through_the_sidecar() {
+        let _lock = ENV_LOCK.lock();
+        let (sandbox, mut env_guard, state) = init_state_with_config("{}\n").await;
+        // トークンファイルを一時ディレクトリに置く
+        env_guard.set_var("HOME", sandbox.path().to_string_lossy());
+        env_guard.set_var("USERPROFILE", sandbox.path().to_string_lossy());
+        let user_id = state.core().session_token.read().await.scope().to_string();
+        let api_key = state.core().session_token.read().await.value().to_string();
+        let address = serve_router(state.clone()).await;
+        let bridge_url = format!("ws://{}/api/desktop/bridge", address);
+
+        // 鍵がない・違う・ブラウザからの接続は断る
+        let bridge_token = issue_desktop_bridge_token(&user_id).unwrap();
+        assert!(tokio_tungstenite::connect_async(bridge_url.as_str())
+            .await
+            .is_err());
+        let mut wrong = bridge_url.as_str().into_client_request().unwrap();
+        wrong
+            .headers_mut()
+            .insert(DESKTOP_BRIDGE_TOKEN_HEADER, "0000".parse().unwrap());
+        assert!(tokio_tungstenite::connect_async(wrong).await.is_err());
+        let mut from_browser = bridge_url.as_str().into_client_request().unwrap();
+        from_browser
+            .headers_mut()
+            .insert(DESKTOP_BRIDGE_TOKEN_HEADER, bridge_token.parse().unwrap());
+        from_browser
+            .headers_mut()
+            .insert("origin", "http://localhost:5173".parse().unwrap());
+        assert!(tokio_tungstenite::connect_async(from_browser)
+            .await
+            .is_err());
+
+        let mut request = bridge_url.as_str().into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert(DESKTOP_BRIDGE_TOKEN_HEADER, bridge_token.parse().unwrap());
+        let (mut shell, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+        tokio::spawn(async move {
+            while let Some(Ok(ClientMessage::Text(text))) = shell.next().await {
+                let request: DesktopRequest = serde_json::from_str(&text).unwrap();
+                let reply = DesktopBridgeReply {
+                    id: request.id,
+                    reply: DesktopReply::ClipboardText {
+                        text: "from the shell".to_string(),
+                    },
+                };
+                let text = serde_json::to_string(&reply).unwrap();
+                shell.send(ClientMessage::Text(text.into())).await.unwrap();
+            }
+        });
+        // 接続が受信口を登録するまで待つ
+        for _ in 0..50 {
+            if state.integration().desktop.is_attached() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        let response = reqwest::Client::new()
+            .post(format!("http://{}/api/desktop/clipboard", address))
+            .header("x-api-key", api_key)
+            .header("origin", "tauri://localhost")
+            .json(&json!({"reason": "test"}))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success(), "{}", response.status());
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["status"], "clipboard_text");
+        assert_eq!(body["text"], "from the shell");
+    }
+}
@@ -0,0 +1,171 @@
+//! 任意テキストの埋め込み。フロントエンド側でのセッションのクラスタリングなどに使う。
+
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::core::errors::ApiError;
+use crate::state::AppStateRead;
+use crate::tools::vector_math::l2_normalize;
+
+/// 1 リクエストで受け付ける入力数
+const MAX_INPUTS: usize = 512;
+/// `LlmService::embed` へ一度に渡す件数
+const EMBED_BATCH_SIZE: usize = 32;
+const DEFAULT_MAX_CHARS: usize = 8_192;
+
+#[derive(Debug, Deserialize)]
+pub struct EmbeddingsRequest {
+    pub input: Vec<String>,
+    /// 省略時は現在の `embedding` 割り当て
+    pub model_id: Option<String>,
+    /// 各ベクトルを長さ 1 に揃える
+    #[serde(default)]
+    pub normalize: bool,
+    #[serde(default)]
+    pub truncate: TruncateStrategy,
+    /// 1 入力あたりの最大文字数。省略時は 8192
+    pub max_chars: Option<usize>,
+}
+
+/// `max_chars` を超えた入力の扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncateStrategy {
+    /// 末尾を削って先頭を残す
+    #[default]
+    End,
+    /// 先頭を削って末尾を残す
+    Start,
+    /// 切り詰めずに拒否する
+    None,
+}
+
+#[derive(Debug, Serialize)]
+struct EmbeddingItem {
+    index: usize,
+    embedding: Vec<f32>,
+    truncated: bool,
+}
+
+pub async fn create_embeddings(
+    State(state): State<AppStateRead>,
+    Json(payload): Json<EmbeddingsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if payload.input.is_empty() {
+        return Err(ApiError::BadRequest("input must not be empty".to_string()));
+    }
+    if payload.input.len() > MAX_INPUTS {
+        return Err(ApiError::BadRequest(format!(
+            "input accepts at most {} items",
+            MAX_INPUTS
+        )));
+    }
+    let max_chars = payload.max_chars.unwrap_or(DEFAULT_MAX_CHARS);
+    if max_chars == 0 {
+        return Err(ApiError::BadRequest(
+            "max_chars must be at least 1".to_string(),
+        ));
+    }
+
+    let mut inputs = Vec::with_capacity(payload.input.len());
+    let mut truncated = Vec::with_capacity(payload.input.len());
+    for (index, text) in payload.input.iter().enumerate() {
+        if text.trim().is_empty() {
+            return Err(ApiError::BadRequest(format!(
+                "input[{}] must not be blank",
+                index
+            )));
+        }
+        let (text, was_truncated) =
+            truncate_input(text, max_chars, payload.truncate).ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "input[{}] exceeds {} characters and truncate is \"none\"",
+                    index, max_chars
+                ))
+            })?;
+        inputs.push(text);
+        truncated.push(was_truncated);
+    }
+
+    let model_id = match payload.model_id {
+        Some(id) if !id.trim().is_empty() => id.trim().to_string(),
+        _ => state
+            .ai()
+            .models
+            .resolve_assignment_model_id("embedding")?
+            .ok_or_else(|| {
+                ApiError::BadRequest("No embedding model is assigned; pass model_id".to_string())
+            })?,
+    };
+
+    let mut embeddings = state
+        .ai()
+        .llm
+        .embed_batched(&inputs, &model_id, EMBED_BATCH_SIZE)
+        .await?;
+    if payload.normalize {
+        for vector in &mut embeddings {
+            l2_normalize(vector);
+        }
+    }
+
+    let dimensions = embeddings.first().map(Vec::len).unwrap_or(0);
+    let data = embeddings
+        .into_iter()
+        .zip(truncated)
+        .enumerate()
+        .map(|(index, (embedding, truncated))| EmbeddingItem {
+            index,
+            embedding,
+            truncated,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(serde_json::json!({
+        "model_id": model_id,
+        "dimensions": dimensions,
+        "normalized": payload.normalize,
+        "data": data,
+    })))
+}
+
+/// 文字数で切り詰める。`None` 戦略で上限を超えたら `None`
+fn truncate_input(
+    text: &str,
+    max_chars: usize,
+    strategy: TruncateStrategy,
+) -> Option<(String, bool)> {
+    let chars = text.chars().count();
+    if chars <= max_chars {
+        return Some((text.to_string(), false));
+    }
+    match strategy {
+        TruncateStrategy::End => Some((text.chars().take(max_chars).collect(), true)),
+        TruncateStrategy::Start => Some((text.chars().skip(chars - max_chars).collect(), true)),
+        TruncateStrategy::None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_strategies_keep_the_requested_side() {
+        assert_eq!(
+            truncate_input("こんにちは世界", 5, TruncateStrategy::End),
+            Some(("こんにちは".to_string(), true))
+        );
+        assert_eq!(
+            truncate_input("こんにちは世界", 2, TruncateStrategy::Start),
+            Some(("世界".to_string(), true))
+        );
+        assert_eq!(
+            truncate_input("short", 10, TruncateStrategy::None),
+            Some(("short".to_string(), false))
+        );
+        assert_eq!(truncate_input("too long", 3, TruncateStrategy::None), None);
+    }
+}
@@ -0,0 +1,102 @@
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::agent::regression::{load_suites, pass_rate_history, run_suite, suites_dir};
+use crate::core::errors::ApiError;
+use crate::state::{AppStateRead, AppStateWrite};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RunEvalsRequest {
+    /// 省略時はすべてのスイート
+    #[serde(default)]
+    pub suite: Option<String>,
+    /// 指定したケースだけを流す
+    #[serde(default)]
+    pub cases: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EvalHistoryQuery {
+    #[serde(default)]
+    pub suite: Option<String>,
+}
+
+pub async fn list_eval_suites(
+    State(state): State<AppStateRead>,
+) -> Result<impl IntoResponse, ApiError> {
+    let dir = suites_dir(state.as_ref());
+    let (suites, errors) = load_suites(&dir);
+    let suites = suites
+        .iter()
+        .map(|suite| {
+            json!({
+                "name": suite.name,
+                "description": suite.description,
+                "agent": suite.agent,
+                "persona": suite.persona,
+                "cases": suite.cases.iter().map(|case| case.id.as_str()).collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let errors = errors
+        .into_iter()
+        .map(|(file, message)| json!({"file": file, "message": message}))
+        .collect::<Vec<_>>();
+    Ok(Json(json!({
+        "directory": dir,
+        "suites": suites,
+        "errors": errors,
+    })))
+}
+
+pub async fn run_evals(
+    State(state): State<AppStateWrite>,
+    Json(payload): Json<RunEvalsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (suites, _) = load_suites(&suites_dir(state.as_ref()));
+    let selected = suites
+        .iter()
+        .filter(|suite| {
+            payload
+                .suite
+                .as_deref()
+                .is_none_or(|name| suite.name == name)
+        })
+        .collect::<Vec<_>>();
+    if selected.is_empty() {
+        return Err(ApiError::NotFound(match payload.suite {
+            Some(name) => format!("Eval suite '{}' not found", name),
+            None => "No eval suites found".to_string(),
+        }));
+    }
+
+    let config = state.core().config.load_config()?;
+    let mut runs = Vec::new();
+    for suite in selected {
+        runs.push(run_suite(state.as_ref(), &config, suite, payload.cases.as_deref()).await?);
+    }
+    let passed = runs.iter().map(|run| run.passed).sum::<usize>();
+    let total = runs.iter().map(|run| run.total).sum::<usize>();
+    Ok(Json(json!({
+        "runs": runs,
+        "passed": passed,
+        "total": total,
+    })))
+}
+
+pub async fn get_eval_history(
+    State(state): State<AppStateRead>,
+    Query(query): Query<EvalHistoryQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let records = state
+        .runtime()
+        .history
+        .list_eval_runs(query.suite.as_deref())
+        .await?;
+    Ok(Json(json!({
+        "suites": pass_rate_history(&records),
+    })))
+}
@@ -1,10 +1,15 @@
 use axum::extract::State;
+use axum::http::HeaderMap;
 use axum::response::IntoResponse;
 use axum::Json;
 use serde_json::json;
 use std::time::Duration;
 
 use crate::core::errors::ApiError;
+use crate::core::safe_mode;
+use crate::server::handlers::audit::record_admin_action;
+use crate::server::handlers::tools::failing_tool_names;
+use crate::state::health::text_assignment_key;
 use crate::state::AppStateRead;
 
 fn resolve_overall_health(llm_status: &str, db_status: &str, mcp_status: &str) -> &'static str {
@@ -17,18 +22,7 @@ fn resolve_overall_health(llm_status: &str, db_status: &str, mcp_status: &str) -
 
 pub async fn health(State(state): State<AppStateRead>) -> impl IntoResponse {
     // Check LLM availability via role_assignments
-    let active_character = state.core().config.load_config().ok().and_then(|config| {
-        config
-            .get("active_character")
-            .or_else(|| config.get("active_agent_profile"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-    });
-
-    let assignment_key = active_character
-        .as_deref()
-        .map(|value| format!("character:{value}"))
-        .unwrap_or_else(|| "character".to_string());
+    let assignment_key = text_assignment_key(&state.shared());
     let (llm_status, llm_model) = match state
         .ai()
         .models
@@ -89,7 +83,19 @@ pub async fn health(State(state): State<AppStateRead>) -> impl IntoResponse {
     }))
 }
 
-pub async fn shutdown(State(state): State<AppStateRead>) -> Result<impl IntoResponse, ApiError> {
+pub async fn shutdown(
+    State(state): State<AppStateRead>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "shutdown",
+        None,
+        &Ok::<(), ApiError>(()),
+        json!({}),
+    )
+    .await;
     if let Err(err) = state.ai().llm.shutdown().await {
         tracing::warn!("Failed to stop llama server via shutdown endpoint: {}", err);
     }
@@ -110,11 +116,28 @@ pub async fn get_status(State(state): State<AppStateRead>) -> Result<impl IntoRe
         .await
         .unwrap_or(0);
     let memory_stats = state.memory().memory_service.stats().await?;
+    // 直近 24 時間で失敗続きのツール（壊れた連携）
+    let since = (chrono::Utc::now() - chrono::Duration::hours(24)).to_rfc3339();
+    let failing_tools = state
+        .runtime()
+        .history
+        .tool_invocation_stats(Some(&since))
+        .await
+        .map(|stats| failing_tool_names(&stats))
+        .unwrap_or_default();
+    // 最初の確認が終わるまでは未初期化として返す
+    let health = state.core().health.latest();
+    let degraded = health.as_ref().is_some_and(|report| report.is_degraded());
+    let integrity = &state.core().integrity;
     Ok(Json(json!({
-        "initialized": true,
+        "initialized": health.is_some(),
         "core_version": "v2",
         "episodic_memory_enabled": memory_stats.enabled,
-        "degraded": false,
+        "degraded": degraded || !failing_tools.is_empty() || integrity.is_degraded(),
+        "health": health,
+        "failing_tools": failing_tools,
+        "integrity": integrity,
+        "safe_mode": safe_mode::status(),
         "total_messages": total_messages,
         "memory_events": memory_stats.total_events,
         "retrieval": {
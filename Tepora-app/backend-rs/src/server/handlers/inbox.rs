@@ -0,0 +1,62 @@
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::core::errors::ApiError;
+use crate::state::{AppStateRead, AppStateWrite};
+
+const DEFAULT_INBOX_LIMIT: i64 = 50;
+const MAX_INBOX_LIMIT: i64 = 200;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct InboxQuery {
+    #[serde(default)]
+    pub unread: bool,
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub before_id: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AcknowledgeInboxRequest {
+    /// 省略すると未読すべて
+    #[serde(default)]
+    pub ids: Option<Vec<i64>>,
+}
+
+pub async fn list_inbox(
+    State(state): State<AppStateRead>,
+    Query(query): Query<InboxQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_INBOX_LIMIT)
+        .clamp(1, MAX_INBOX_LIMIT);
+    let inbox = &state.runtime().inbox;
+    let items = inbox.list(query.unread, limit, query.before_id).await?;
+    let next_before_id = if items.len() as i64 == limit {
+        items.last().map(|item| item.id)
+    } else {
+        None
+    };
+    Ok(Json(json!({
+        "items": items,
+        "unread": inbox.unread_count().await?,
+        "next_before_id": next_before_id,
+    })))
+}
+
+pub async fn acknowledge_inbox(
+    State(state): State<AppStateWrite>,
+    Json(payload): Json<AcknowledgeInboxRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let inbox = &state.runtime().inbox;
+    let acknowledged = inbox.acknowledge(payload.ids.as_deref()).await?;
+    Ok(Json(json!({
+        "acknowledged": acknowledged,
+        "unread": inbox.unread_count().await?,
+    })))
+}
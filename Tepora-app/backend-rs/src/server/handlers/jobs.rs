@@ -0,0 +1,38 @@
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::core::errors::ApiError;
+use crate::state::AppStateRead;
+
+const DEFAULT_JOBS_LIMIT: i64 = 50;
+const MAX_JOBS_LIMIT: i64 = 200;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct JobsQuery {
+    /// running / completed / failed
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+/// 直近のバックグラウンドジョブを起動した順に返す。
+pub async fn list_jobs(
+    State(state): State<AppStateRead>,
+    Query(query): Query<JobsQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_JOBS_LIMIT)
+        .clamp(1, MAX_JOBS_LIMIT);
+    let status = query
+        .status
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+    let jobs = state.runtime().history.list_jobs(status, limit).await?;
+    Ok(Json(json!({ "jobs": jobs })))
+}
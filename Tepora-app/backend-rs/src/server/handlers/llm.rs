@@ -0,0 +1,13 @@
+//! LLM プロバイダーの状態。再試行を使い切って送信を止めているプロバイダーを UI に出す。
+
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde_json::json;
+
+use crate::state::AppStateRead;
+
+/// ローダーごとのブレーカーの状態（`closed` / `open` / `half_open`）と失敗の記録。
+pub async fn list_providers(State(state): State<AppStateRead>) -> impl IntoResponse {
+    Json(json!({ "providers": state.ai().llm.provider_health() }))
+}
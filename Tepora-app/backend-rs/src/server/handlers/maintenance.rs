@@ -0,0 +1,29 @@
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde_json::json;
+
+use crate::core::db_maintenance::run_maintenance;
+use crate::core::errors::ApiError;
+use crate::state::AppStateWrite;
+
+/// 全 DB の整合性チェック・空き領域の回収・`ANALYZE` を実行し、DB ごとの結果を返す。
+pub async fn run_db_maintenance(
+    State(state): State<AppStateWrite>,
+) -> Result<impl IntoResponse, ApiError> {
+    let report = run_maintenance(&state.core().paths).await;
+    state.core().security.record_audit(
+        "db_maintenance",
+        if report.healthy() {
+            "success"
+        } else {
+            "failed"
+        },
+        json!({ "databases": report.databases.len() }),
+    )?;
+    Ok(Json(json!({
+        "status": "success",
+        "healthy": report.healthy(),
+        "result": report,
+    })))
+}
@@ -1,4 +1,5 @@
 use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
 use axum::response::IntoResponse;
 use axum::Json;
 use chrono::{Duration as ChronoDuration, Utc};
@@ -8,10 +9,13 @@ use std::collections::HashMap;
 use std::sync::{Mutex, OnceLock};
 use uuid::Uuid;
 
+use crate::core::config::schema::McpMarketplaceSettings;
+use crate::core::config::service::config_diff;
 use crate::core::errors::ApiError;
 use crate::core::security_controls::{ApprovalDecision, PermissionScopeKind};
 use crate::mcp::installer as mcp_installer;
-use crate::mcp::registry::McpRegistryServer;
+use crate::mcp::registry::{McpRegistryServer, CURATED_SOURCE, OFFICIAL_SOURCE};
+use crate::server::handlers::audit::record_admin_action;
 use crate::state::{AppStateRead, AppStateWrite};
 
 #[derive(Debug, Deserialize, Default)]
@@ -22,6 +26,11 @@ pub struct McpStoreQuery {
     pub page_size: Option<i64>,
     pub runtime: Option<String>,
     pub refresh: Option<bool>,
+    pub category: Option<String>,
+    /// 掲載元の名前（`official` / `curated` / 追加したソース）
+    pub source: Option<String>,
+    /// `true` ならおすすめだけ
+    pub recommended: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -89,7 +98,7 @@ pub async fn mcp_status(State(state): State<AppStateRead>) -> Result<impl IntoRe
 }
 
 pub async fn mcp_config(State(state): State<AppStateRead>) -> Result<impl IntoResponse, ApiError> {
-    let config = state.integration().mcp.get_config().await;
+    let config = state.integration().mcp.get_redacted_config().await;
     let config_value =
         serde_json::to_value(&config).unwrap_or_else(|_| json!({ "mcpServers": {} }));
     let servers = config_value
@@ -108,13 +117,31 @@ pub async fn mcp_config(State(state): State<AppStateRead>) -> Result<impl IntoRe
 
 pub async fn mcp_update_config(
     State(state): State<AppStateWrite>,
+    headers: HeaderMap,
     Json(payload): Json<Value>,
 ) -> Result<impl IntoResponse, ApiError> {
-    state
-        .core()
-        .security
-        .ensure_lockdown_disabled("mcp_config_update")?;
-    state.integration().mcp.update_config(&payload).await?;
+    let before = state.integration().mcp.get_redacted_config().await;
+    let result = async {
+        state
+            .core()
+            .security
+            .ensure_lockdown_disabled("mcp_config_update")?;
+        state.integration().mcp.update_config(&payload).await
+    }
+    .await;
+    let before_servers: Vec<&String> = before.mcp_servers.keys().collect();
+    let after = state.integration().mcp.get_redacted_config().await;
+    let after_servers: Vec<&String> = after.mcp_servers.keys().collect();
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "mcp_config_update",
+        None,
+        &result,
+        json!({ "servers_before": before_servers, "servers_after": after_servers }),
+    )
+    .await;
+    result?;
     Ok(Json(json!({"success": true})))
 }
 
@@ -131,10 +158,11 @@ pub async fn mcp_store(
     let refresh = params.refresh.unwrap_or(false);
     let search = params.search.as_deref();
 
+    let marketplace = state.core().config.load_typed()?.mcp_marketplace;
     let mut servers = state
         .integration()
         .mcp_registry
-        .fetch_servers(refresh, search, None)
+        .fetch_servers(&marketplace, refresh, search, None)
         .await
         .unwrap_or_default();
 
@@ -150,7 +178,30 @@ pub async fn mcp_store(
         });
     }
 
-    servers.sort_by_key(|a| a.name.to_lowercase());
+    if let Some(source) = params.source.as_deref() {
+        servers.retain(|server| server.source == source);
+    }
+    if params.recommended.unwrap_or(false) {
+        servers.retain(|server| server.recommended);
+    }
+
+    // カテゴリの件数は絞り込む前に数える（フィルタの選択肢に使う）
+    let mut category_counts: std::collections::BTreeMap<String, usize> = Default::default();
+    for server in &servers {
+        let category = server.category.as_deref().unwrap_or("other");
+        *category_counts.entry(category.to_string()).or_default() += 1;
+    }
+    if let Some(category) = params.category.as_deref() {
+        servers.retain(|server| {
+            server
+                .category
+                .as_deref()
+                .unwrap_or("other")
+                .eq_ignore_ascii_case(category)
+        });
+    }
+
+    servers.sort_by_key(|a| (!a.recommended, a.name.to_lowercase()));
 
     let total = servers.len() as i64;
     let start = (page - 1) * page_size;
@@ -182,6 +233,8 @@ pub async fn mcp_store(
                 environment_variables,
                 icon,
                 category,
+                source,
+                recommended,
                 license: _,
             } = server;
 
@@ -221,6 +274,8 @@ pub async fn mcp_store(
                 "environmentVariables": env_json,
                 "icon": icon,
                 "category": category,
+                "source": source,
+                "recommended": recommended,
                 "sourceUrl": source_url,
                 "homepage": homepage,
                 "websiteUrl": website_url,
@@ -235,10 +290,27 @@ pub async fn mcp_store(
         "total": total,
         "page": page,
         "page_size": page_size,
-        "has_more": has_more
+        "has_more": has_more,
+        "categories": category_counts
+            .into_iter()
+            .map(|(name, count)| json!({"name": name, "count": count}))
+            .collect::<Vec<_>>(),
+        "sources": marketplace_source_names(&marketplace),
     })))
 }
 
+fn marketplace_source_names(settings: &McpMarketplaceSettings) -> Vec<String> {
+    let mut names = Vec::new();
+    if settings.official {
+        names.push(OFFICIAL_SOURCE.to_string());
+    }
+    names.extend(settings.sources.iter().map(|source| source.name.clone()));
+    if settings.curated {
+        names.push(CURATED_SOURCE.to_string());
+    }
+    names
+}
+
 pub async fn mcp_install_preview(
     State(state): State<AppStateRead>,
     Json(payload): Json<McpInstallPreviewRequest>,
@@ -250,7 +322,10 @@ pub async fn mcp_install_preview(
     let server = state
         .integration()
         .mcp_registry
-        .get_server_by_id(&payload.server_id)
+        .get_server_by_id(
+            &state.core().config.load_typed()?.mcp_marketplace,
+            &payload.server_id,
+        )
         .await?
         .ok_or_else(|| ApiError::NotFound("Server not found".to_string()))?;
 
@@ -295,8 +370,38 @@ pub async fn mcp_install_preview(
 
 pub async fn mcp_install_confirm(
     State(state): State<AppStateWrite>,
+    headers: HeaderMap,
     Json(payload): Json<McpInstallConfirmRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
+    let result = install_confirmed(&state, &payload).await;
+    let details = match &result {
+        Ok((server_name, server_id)) => {
+            json!({ "server_id": server_id, "server_name": server_name })
+        }
+        Err(_) => json!({ "consent_id": payload.consent_id }),
+    };
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "mcp_install",
+        result.as_ref().ok().map(|(name, _)| name.as_str()),
+        &result,
+        details,
+    )
+    .await;
+    let (server_name, _) = result?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "server_name": server_name,
+        "message": format!("Server '{}' installed successfully with consent", server_name)
+    })))
+}
+
+async fn install_confirmed(
+    state: &AppStateWrite,
+    payload: &McpInstallConfirmRequest,
+) -> Result<(String, String), ApiError> {
     state
         .core()
         .security
@@ -342,18 +447,37 @@ pub async fn mcp_install_confirm(
         .update_config(&json!({ "mcpServers": servers }))
         .await?;
 
-    Ok(Json(json!({
-        "status": "success",
-        "server_name": server_name,
-        "message": format!("Server '{}' installed successfully with consent", server_name)
-    })))
+    Ok((server_name, pending.request.server_id))
 }
 
 pub async fn mcp_approve_server(
     State(state): State<AppStateWrite>,
+    headers: HeaderMap,
     Path(server_name): Path<String>,
     Json(payload): Json<McpApproveRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
+    let result = approve_server(&state, &server_name, payload).await;
+    let details = result
+        .as_ref()
+        .map(|response| json!({ "decision": response["decision"] }))
+        .unwrap_or(Value::Null);
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "mcp_approve_server",
+        Some(&server_name),
+        &result,
+        details,
+    )
+    .await;
+    Ok(Json(result?))
+}
+
+async fn approve_server(
+    state: &AppStateWrite,
+    server_name: &str,
+    payload: McpApproveRequest,
+) -> Result<Value, ApiError> {
     state
         .core()
         .security
@@ -368,107 +492,147 @@ pub async fn mcp_approve_server(
         ApprovalDecision::Deny => {
             state.core().security.persist_permission(
                 PermissionScopeKind::McpServer,
-                &server_name,
+                server_name,
                 ApprovalDecision::Deny,
                 None,
             )?;
-            let (policy, _) = state.integration().mcp.revoke_server(&server_name)?;
-            Ok(Json(
-                json!({"success": true, "policy": policy, "decision": "deny"}),
-            ))
+            let (policy, _) = state.integration().mcp.revoke_server(server_name)?;
+            Ok(json!({"success": true, "policy": policy, "decision": "deny"}))
         }
         ApprovalDecision::Once => {
             let policy = state
                 .integration()
                 .mcp
-                .approve_server(&server_name, payload.transport_types)?;
-            Ok(Json(
-                json!({"success": true, "policy": policy, "decision": "once"}),
-            ))
+                .approve_server(server_name, payload.transport_types)?;
+            Ok(json!({"success": true, "policy": policy, "decision": "once"}))
         }
         ApprovalDecision::AlwaysUntilExpiry => {
             state.core().security.persist_permission(
                 PermissionScopeKind::McpServer,
-                &server_name,
+                server_name,
                 ApprovalDecision::AlwaysUntilExpiry,
                 payload.ttl_seconds,
             )?;
             let policy = state
                 .integration()
                 .mcp
-                .approve_server(&server_name, payload.transport_types)?;
-            Ok(Json(
+                .approve_server(server_name, payload.transport_types)?;
+            Ok(
                 json!({"success": true, "policy": policy, "decision": "always_until_expiry", "ttl_seconds": payload.ttl_seconds}),
-            ))
+            )
         }
     }
 }
 
 pub async fn mcp_revoke_server(
     State(state): State<AppStateWrite>,
+    headers: HeaderMap,
     Path(server_name): Path<String>,
 ) -> Result<impl IntoResponse, ApiError> {
-    state
-        .core()
-        .security
-        .ensure_lockdown_disabled("mcp_revoke_server")?;
-    let (policy, removed) = state.integration().mcp.revoke_server(&server_name)?;
-    if !removed {
-        return Err(ApiError::NotFound("Server not found".to_string()));
-    }
-    Ok(Json(json!({"success": true, "policy": policy})))
+    let result = (|| {
+        state
+            .core()
+            .security
+            .ensure_lockdown_disabled("mcp_revoke_server")?;
+        let (policy, removed) = state.integration().mcp.revoke_server(&server_name)?;
+        if !removed {
+            return Err(ApiError::NotFound("Server not found".to_string()));
+        }
+        Ok(policy)
+    })();
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "mcp_revoke_server",
+        Some(&server_name),
+        &result,
+        Value::Null,
+    )
+    .await;
+    Ok(Json(json!({"success": true, "policy": result?})))
 }
 
 pub async fn mcp_enable_server(
     State(state): State<AppStateWrite>,
+    headers: HeaderMap,
     Path(server_name): Path<String>,
 ) -> Result<impl IntoResponse, ApiError> {
-    state
-        .core()
-        .security
-        .ensure_lockdown_disabled("mcp_enable_server")?;
-    let ok = state
-        .integration()
-        .mcp
-        .set_server_enabled(&server_name, true)
-        .await?;
-    if !ok {
-        return Err(ApiError::NotFound("Server not found".to_string()));
-    }
+    set_server_enabled(&state, &headers, &server_name, true).await?;
     Ok(Json(json!({"success": true})))
 }
 
 pub async fn mcp_disable_server(
     State(state): State<AppStateWrite>,
+    headers: HeaderMap,
     Path(server_name): Path<String>,
 ) -> Result<impl IntoResponse, ApiError> {
-    state
-        .core()
-        .security
-        .ensure_lockdown_disabled("mcp_disable_server")?;
-    let ok = state
-        .integration()
-        .mcp
-        .set_server_enabled(&server_name, false)
-        .await?;
-    if !ok {
-        return Err(ApiError::NotFound("Server not found".to_string()));
-    }
+    set_server_enabled(&state, &headers, &server_name, false).await?;
     Ok(Json(json!({"success": true})))
 }
 
+async fn set_server_enabled(
+    state: &AppStateWrite,
+    headers: &HeaderMap,
+    server_name: &str,
+    enabled: bool,
+) -> Result<(), ApiError> {
+    let action = if enabled {
+        "mcp_enable_server"
+    } else {
+        "mcp_disable_server"
+    };
+    let result = async {
+        state.core().security.ensure_lockdown_disabled(action)?;
+        let ok = state
+            .integration()
+            .mcp
+            .set_server_enabled(server_name, enabled)
+            .await?;
+        if !ok {
+            return Err(ApiError::NotFound("Server not found".to_string()));
+        }
+        Ok(())
+    }
+    .await;
+    record_admin_action(
+        &state.shared(),
+        headers,
+        action,
+        Some(server_name),
+        &result,
+        Value::Null,
+    )
+    .await;
+    result
+}
+
 pub async fn mcp_delete_server(
     State(state): State<AppStateWrite>,
+    headers: HeaderMap,
     Path(server_name): Path<String>,
 ) -> Result<impl IntoResponse, ApiError> {
-    state
-        .core()
-        .security
-        .ensure_lockdown_disabled("mcp_delete_server")?;
-    let ok = state.integration().mcp.delete_server(&server_name).await?;
-    if !ok {
-        return Err(ApiError::NotFound("Server not found".to_string()));
+    let result = async {
+        state
+            .core()
+            .security
+            .ensure_lockdown_disabled("mcp_delete_server")?;
+        let ok = state.integration().mcp.delete_server(&server_name).await?;
+        if !ok {
+            return Err(ApiError::NotFound("Server not found".to_string()));
+        }
+        Ok(())
     }
+    .await;
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "mcp_delete_server",
+        Some(&server_name),
+        &result,
+        Value::Null,
+    )
+    .await;
+    result?;
     Ok(Json(json!({"success": true})))
 }
 
@@ -479,12 +643,34 @@ pub async fn mcp_policy(State(state): State<AppStateRead>) -> Result<impl IntoRe
 
 pub async fn mcp_update_policy(
     State(state): State<AppStateWrite>,
+    headers: HeaderMap,
     Json(payload): Json<Value>,
 ) -> Result<impl IntoResponse, ApiError> {
-    state
+    let before = state
+        .integration()
+        .mcp
+        .load_policy()
+        .ok()
+        .and_then(|policy| serde_json::to_value(policy).ok())
+        .unwrap_or(Value::Null);
+    let result = state
         .core()
         .security
-        .ensure_lockdown_disabled("mcp_update_policy")?;
-    let policy = state.integration().mcp.update_policy(&payload)?;
-    Ok(Json(json!({"success": true, "policy": policy})))
+        .ensure_lockdown_disabled("mcp_update_policy")
+        .and_then(|_| state.integration().mcp.update_policy(&payload));
+    let after = result
+        .as_ref()
+        .ok()
+        .and_then(|policy| serde_json::to_value(policy).ok())
+        .unwrap_or_else(|| before.clone());
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "mcp_update_policy",
+        None,
+        &result,
+        json!({ "changes": config_diff(&before, &after) }),
+    )
+    .await;
+    Ok(Json(json!({"success": true, "policy": result?})))
 }
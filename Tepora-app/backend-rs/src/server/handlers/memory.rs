@@ -7,7 +7,7 @@ use serde_json::json;
 
 use crate::core::errors::ApiError;
 use crate::infrastructure::episodic_store::{CompactionJob, CompactionStatus, MemoryScope};
-use crate::state::AppStateWrite;
+use crate::state::{jobs, AppStateWrite};
 
 #[derive(Debug, Deserialize, Default)]
 pub struct CompressMemoriesRequest {
@@ -77,17 +77,33 @@ pub async fn compress_memories(
     let bg_job_id = job_id.clone();
     let bg_session_id = session_id.clone();
 
+    let bg_state = state.shared();
+
     tokio::spawn(async move {
-        if let Err(e) = bg_service
+        jobs::begin(
+            &bg_state,
+            &bg_job_id,
+            jobs::KIND_MEMORY_COMPACTION,
+            &format!("Memory compaction ({bg_session_id})"),
+            json!({ "session_id": bg_session_id, "scope": scope.as_str() }),
+        )
+        .await;
+        let result = bg_service
             .compress_memories_as_job(&bg_session_id, &bg_llm, &model_id, &bg_job_id, scope)
-            .await
-        {
+            .await;
+        if let Err(e) = &result {
             tracing::error!("Background compaction job {} failed: {}", bg_job_id, e);
             // Mark the job as failed.
             bg_service
                 .fail_compaction_job(&bg_session_id, &bg_job_id)
                 .await;
         }
+        jobs::finish(
+            &bg_state,
+            &bg_job_id,
+            result.err().map(|e| e.to_string()).as_deref(),
+        )
+        .await;
     });
 
     Ok((
@@ -1,8 +1,11 @@
 use axum::extract::{Path, State};
 use axum::Json;
 
+use crate::core::utilization::UtilizationSnapshot;
+use crate::history::SessionTokenUsage;
 use crate::infrastructure::observability::RuntimeMetricsSnapshot;
 use crate::models::event::AgentEvent;
+use crate::state::utilization::sample_utilization;
 use crate::state::AppStateRead;
 
 #[derive(serde::Serialize)]
@@ -22,6 +25,19 @@ pub async fn get_session_metrics(
     Ok(Json(MetricsResponse { events }))
 }
 
+/// セッションのトークン使用量（合計とモデル別）
+pub async fn get_session_usage(
+    State(state): State<AppStateRead>,
+    Path(session_id): Path<String>,
+) -> Result<Json<SessionTokenUsage>, crate::core::errors::ApiError> {
+    let usage = state
+        .runtime()
+        .history
+        .session_token_usage(&session_id)
+        .await?;
+    Ok(Json(usage))
+}
+
 pub async fn get_runtime_metrics(
     State(state): State<AppStateRead>,
 ) -> Result<Json<RuntimeMetricsSnapshot>, crate::core::errors::ApiError> {
@@ -29,3 +45,11 @@ pub async fn get_runtime_metrics(
         state.runtime().actor_manager.runtime_metrics_snapshot(),
     ))
 }
+
+/// CPU・メモリ・GPU と llama-server の使用率。継続して見る場合は WS の
+/// `subscribe_utilization` を使う。
+pub async fn get_system_utilization(
+    State(state): State<AppStateRead>,
+) -> Json<UtilizationSnapshot> {
+    Json(sample_utilization(state.as_ref()).await)
+}
@@ -1,19 +1,38 @@
+pub mod audit;
 pub mod auth;
 pub mod config;
+pub mod context;
+pub mod custom_agents;
+pub mod desktop;
+pub mod embeddings;
+pub mod evals;
 pub mod health;
+pub mod inbox;
+pub mod jobs;
+pub mod llm;
 pub mod logs;
+pub mod maintenance;
 pub mod mcp;
 pub mod memory;
 pub mod metrics;
+pub mod network;
+pub mod personas;
+pub mod plugins;
+pub mod profiler;
+pub mod rag;
+pub mod runs;
+pub mod scripts;
 pub mod security;
 pub mod sessions;
 pub mod setup;
 mod setup_binary;
 mod setup_catalog;
 mod setup_flow;
-mod setup_models;
+pub(crate) mod setup_models;
 mod setup_roles;
 pub mod skills;
 pub mod tools;
+pub mod topics;
+pub mod updates;
 pub mod utils;
 pub mod workspace;
@@ -0,0 +1,133 @@
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::core::config::schema::{NetworkSettings, NetworkSubsystem};
+use crate::core::errors::ApiError;
+use crate::core::network;
+use crate::server::handlers::audit::record_admin_action;
+use crate::state::{AppStateRead, AppStateWrite};
+
+/// オフラインかどうかと、サブシステムごとの通信可否。
+pub async fn network_status(State(_state): State<AppStateRead>) -> impl IntoResponse {
+    Json(network::status())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetOfflineRequest {
+    pub offline: bool,
+}
+
+pub async fn set_offline(
+    State(state): State<AppStateWrite>,
+    headers: HeaderMap,
+    Json(payload): Json<SetOfflineRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    // オフラインに入るのは止めない。外へ出られるようにする方だけ Lockdown で止める
+    let result = if payload.offline {
+        Ok(())
+    } else {
+        state
+            .core()
+            .security
+            .ensure_lockdown_disabled("network_offline")
+    }
+    .and_then(|_| save_network_settings(&state, |settings| settings.offline = payload.offline));
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "network_offline",
+        None,
+        &result,
+        json!({ "offline": payload.offline }),
+    )
+    .await;
+    result?;
+    Ok(Json(network::status()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetOverrideRequest {
+    pub allowed: bool,
+}
+
+/// オフライン中に止められたサブシステムを、ユーザーの確認を経て個別に通す（または戻す）。
+pub async fn set_offline_override(
+    State(state): State<AppStateWrite>,
+    headers: HeaderMap,
+    Path(subsystem): Path<String>,
+    Json(payload): Json<SetOverrideRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let subsystem = parse_subsystem(&subsystem)?;
+    let result = state
+        .core()
+        .security
+        .ensure_lockdown_disabled("network_offline_override")
+        .and_then(|_| {
+            save_network_settings(&state, |settings| {
+                settings
+                    .allow_while_offline
+                    .retain(|item| *item != subsystem);
+                if payload.allowed {
+                    settings.allow_while_offline.push(subsystem);
+                }
+            })
+        });
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "network_offline_override",
+        Some(subsystem.as_str()),
+        &result,
+        json!({ "allowed": payload.allowed }),
+    )
+    .await;
+    result?;
+    Ok(Json(network::status()))
+}
+
+fn parse_subsystem(raw: &str) -> Result<NetworkSubsystem, ApiError> {
+    NetworkSubsystem::ALL
+        .into_iter()
+        .find(|subsystem| subsystem.as_str() == raw)
+        .ok_or_else(|| ApiError::NotFound(format!("Unknown network subsystem '{}'", raw)))
+}
+
+/// 設定ファイルに書き戻し、変更の通知を待たずにすぐ反映する。
+fn save_network_settings(
+    state: &AppStateWrite,
+    apply: impl FnOnce(&mut NetworkSettings),
+) -> Result<(), ApiError> {
+    let config = &state.core().config;
+    let mut settings = config.load_typed()?.network;
+    apply(&mut settings);
+    let section = serde_json::to_value(&settings).map_err(ApiError::internal)?;
+    config.modify_config(|root| {
+        let root = root
+            .as_object_mut()
+            .ok_or_else(|| ApiError::Internal("Config root is not an object".to_string()))?;
+        let network = root
+            .entry("network")
+            .or_insert_with(|| Value::Object(Map::new()));
+        *network = section;
+        Ok(())
+    })?;
+    network::apply_settings(&settings);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subsystem_names_round_trip() {
+        for subsystem in NetworkSubsystem::ALL {
+            assert_eq!(parse_subsystem(subsystem.as_str()).unwrap(), subsystem);
+        }
+        assert!(parse_subsystem("everything").is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::core::config::personas::{active_persona_id, Persona};
+use crate::core::errors::ApiError;
+use crate::state::{AppStateRead, AppStateWrite};
+
+pub async fn list_personas(
+    State(state): State<AppStateRead>,
+) -> Result<impl IntoResponse, ApiError> {
+    let config = state.core().config.load_config()?;
+    Ok(Json(json!({
+        "active_persona": active_persona_id(&config),
+        "personas": state.core().config.list_personas()?,
+    })))
+}
+
+pub async fn get_persona(
+    State(state): State<AppStateRead>,
+    Path(persona_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let persona = state
+        .core()
+        .config
+        .get_persona(&persona_id)?
+        .ok_or_else(|| ApiError::NotFound("Persona not found".to_string()))?;
+    Ok(Json(json!(persona)))
+}
+
+pub async fn create_persona(
+    State(state): State<AppStateWrite>,
+    Json(payload): Json<Persona>,
+) -> Result<impl IntoResponse, ApiError> {
+    state
+        .core()
+        .security
+        .ensure_lockdown_disabled("persona_save")?;
+    if !payload.id.trim().is_empty() && state.core().config.get_persona(&payload.id)?.is_some() {
+        return Err(ApiError::Conflict(format!(
+            "Persona '{}' already exists",
+            payload.id
+        )));
+    }
+    let persona = state.core().config.save_persona(payload)?;
+    Ok(Json(json!({ "success": true, "persona": persona })))
+}
+
+pub async fn update_persona(
+    State(state): State<AppStateWrite>,
+    Path(persona_id): Path<String>,
+    Json(mut payload): Json<Persona>,
+) -> Result<impl IntoResponse, ApiError> {
+    state
+        .core()
+        .security
+        .ensure_lockdown_disabled("persona_save")?;
+    if state.core().config.get_persona(&persona_id)?.is_none() {
+        return Err(ApiError::NotFound("Persona not found".to_string()));
+    }
+    payload.id = persona_id;
+    let persona = state.core().config.save_persona(payload)?;
+    Ok(Json(json!({ "success": true, "persona": persona })))
+}
+
+pub async fn delete_persona(
+    State(state): State<AppStateWrite>,
+    Path(persona_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    state
+        .core()
+        .security
+        .ensure_lockdown_disabled("persona_delete")?;
+    let removed = state.core().config.delete_persona(&persona_id)?;
+    if !removed {
+        return Err(ApiError::NotFound("Persona not found".to_string()));
+    }
+    Ok(Json(json!({ "success": true })))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportCardQuery {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Import a SillyTavern / Tavern character card (PNG or JSON body).
+pub async fn import_persona_card(
+    State(state): State<AppStateWrite>,
+    Query(query): Query<ImportCardQuery>,
+    body: Bytes,
+) -> Result<impl IntoResponse, ApiError> {
+    state
+        .core()
+        .security
+        .ensure_lockdown_disabled("persona_import")?;
+    if body.is_empty() {
+        return Err(ApiError::BadRequest(
+            "Character card body is empty".to_string(),
+        ));
+    }
+    let persona =
+        state
+            .core()
+            .config
+            .import_character_card(&body, query.id.as_deref(), query.overwrite)?;
+    Ok(Json(json!({ "success": true, "persona": persona })))
+}
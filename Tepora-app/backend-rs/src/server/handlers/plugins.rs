@@ -0,0 +1,117 @@
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::core::config::schema::PluginCapability;
+use crate::core::errors::ApiError;
+use crate::plugins::host::HOST_AVAILABLE;
+use crate::plugins::manifest::is_valid_plugin_id;
+use crate::plugins::PluginInfo;
+use crate::server::handlers::audit::record_admin_action;
+use crate::state::{AppStateRead, AppStateWrite};
+
+#[derive(Debug, Serialize)]
+pub struct PluginsResponse {
+    pub enabled: bool,
+    /// WASM ランタイム付きでビルドされているか
+    pub host_available: bool,
+    pub plugins: Vec<PluginInfo>,
+}
+
+fn plugins_response(state: &crate::state::AppState) -> Result<PluginsResponse, ApiError> {
+    Ok(PluginsResponse {
+        enabled: state.core().config.load_typed()?.plugins.enabled,
+        host_available: HOST_AVAILABLE,
+        plugins: state.integration().plugins.list(),
+    })
+}
+
+pub async fn list_plugins(
+    State(state): State<AppStateRead>,
+) -> Result<impl IntoResponse, ApiError> {
+    Ok(Json(plugins_response(&state.shared())?))
+}
+
+pub async fn reload_plugins(
+    State(state): State<AppStateWrite>,
+) -> Result<impl IntoResponse, ApiError> {
+    state.integration().plugins.reload().await?;
+    Ok(Json(plugins_response(&state.shared())?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateGrantsRequest {
+    pub capabilities: Vec<PluginCapability>,
+}
+
+/// プラグインに与える権限を置き換える。マニフェストで要求されていない権限を
+/// 付与しても有効にはならない。
+pub async fn update_plugin_grants(
+    State(state): State<AppStateWrite>,
+    headers: HeaderMap,
+    Path(plugin_id): Path<String>,
+    Json(payload): Json<UpdateGrantsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !is_valid_plugin_id(&plugin_id) {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid plugin id '{}'",
+            plugin_id
+        )));
+    }
+    let mut capabilities = payload.capabilities.clone();
+    capabilities.sort_by_key(|cap| cap.as_str());
+    capabilities.dedup();
+
+    let result = state
+        .core()
+        .security
+        .ensure_lockdown_disabled("plugin_grants")
+        .and_then(|_| save_grants(&state, &plugin_id, &capabilities));
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "plugin_grants",
+        Some(&plugin_id),
+        &result,
+        json!({ "capabilities": capabilities }),
+    )
+    .await;
+    result?;
+
+    state.integration().plugins.reload().await?;
+    Ok(Json(plugins_response(&state.shared())?))
+}
+
+fn save_grants(
+    state: &AppStateWrite,
+    plugin_id: &str,
+    capabilities: &[PluginCapability],
+) -> Result<(), ApiError> {
+    let granted = serde_json::to_value(capabilities).map_err(ApiError::internal)?;
+    state.core().config.modify_config(|root| {
+        let root = root
+            .as_object_mut()
+            .ok_or_else(|| ApiError::Internal("Config root is not an object".to_string()))?;
+        let plugins = root
+            .entry("plugins")
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(|| ApiError::BadRequest("'plugins' must be an object".to_string()))?;
+        let grants = plugins
+            .entry("grants")
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(|| {
+                ApiError::BadRequest("'plugins.grants' must be an object".to_string())
+            })?;
+        if capabilities.is_empty() {
+            grants.remove(plugin_id);
+        } else {
+            grants.insert(plugin_id.to_string(), granted);
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,58 @@
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::core::errors::ApiError;
+use crate::graph::profiler::{DailyProfile, RunProfile, RunSummary};
+use crate::state::AppStateRead;
+
+const DEFAULT_RUN_LIMIT: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct RunListQuery {
+    pub session_id: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct RunListResponse {
+    pub runs: Vec<RunSummary>,
+}
+
+#[derive(Serialize)]
+pub struct DailyResponse {
+    pub days: Vec<DailyProfile>,
+}
+
+/// 直近の実行の一覧（新しい順）。
+pub async fn list_runs(
+    State(state): State<AppStateRead>,
+    Query(query): Query<RunListQuery>,
+) -> Json<RunListResponse> {
+    let runs = state.runtime().graph_runtime.profiler().recent_runs(
+        query.session_id.as_deref(),
+        query.limit.unwrap_or(DEFAULT_RUN_LIMIT),
+    );
+    Json(RunListResponse { runs })
+}
+
+/// 1 回の実行のノード別内訳。
+pub async fn get_run(
+    State(state): State<AppStateRead>,
+    Path(run_id): Path<String>,
+) -> Result<Json<RunProfile>, ApiError> {
+    state
+        .runtime()
+        .graph_runtime
+        .profiler()
+        .run(&run_id)
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("Run profile not found: {}", run_id)))
+}
+
+/// 日ごとの集計（新しい日付順）。
+pub async fn daily(State(state): State<AppStateRead>) -> Json<DailyResponse> {
+    Json(DailyResponse {
+        days: state.runtime().graph_runtime.profiler().daily(),
+    })
+}
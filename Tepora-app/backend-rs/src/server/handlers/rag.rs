@@ -0,0 +1,191 @@
+use std::time::Instant;
+
+use axum::extract::{Path, Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::core::errors::ApiError;
+use crate::rag::embedding_compare::{evaluate, recommend, EvalCorpus, RetrievalMetrics};
+use crate::rag::parquet_export::{chunks_to_parquet, PARQUET_CONTENT_TYPE};
+use crate::rag::{RagStore, SqliteRagStore};
+use crate::state::{AppState, AppStateRead};
+
+const DEFAULT_K: usize = 5;
+
+#[derive(Debug, Deserialize)]
+pub struct CompareEmbeddingsRequest {
+    /// 比較の基準になるモデル。省略時は現在の `embedding` 割り当て
+    pub baseline_model_id: Option<String>,
+    pub candidate_model_id: String,
+    /// 省略時は組み込みのサンプルコーパス
+    pub corpus: Option<EvalCorpus>,
+    pub k: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct ExportCollectionQuery {
+    /// 省略時は現在のプロジェクト
+    pub project_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ModelReport {
+    model_id: String,
+    dimensions: usize,
+    embed_latency_ms: u64,
+    metrics: RetrievalMetrics,
+}
+
+pub async fn compare_embeddings(
+    State(state): State<AppStateRead>,
+    Json(payload): Json<CompareEmbeddingsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let candidate_model_id = payload.candidate_model_id.trim().to_string();
+    if candidate_model_id.is_empty() {
+        return Err(ApiError::BadRequest(
+            "candidate_model_id is required".to_string(),
+        ));
+    }
+    let baseline_model_id = match payload.baseline_model_id {
+        Some(id) if !id.trim().is_empty() => id.trim().to_string(),
+        _ => state
+            .ai()
+            .models
+            .resolve_assignment_model_id("embedding")?
+            .ok_or_else(|| {
+                ApiError::BadRequest(
+                    "No embedding model is assigned; pass baseline_model_id".to_string(),
+                )
+            })?,
+    };
+    let sample = payload.corpus.is_none();
+    let corpus = payload.corpus.unwrap_or_else(EvalCorpus::sample);
+    corpus.validate()?;
+    let k = payload.k.unwrap_or(DEFAULT_K);
+
+    // 同じ llama.cpp プロセスを取り合わないよう、1 モデルずつ順に測る
+    let baseline = run_model(state.as_ref(), &baseline_model_id, &corpus, k).await?;
+    let candidate = run_model(state.as_ref(), &candidate_model_id, &corpus, k).await?;
+    let recommendation = recommend(&baseline.metrics, &candidate.metrics);
+
+    Ok(Json(json!({
+        "sample_corpus": sample,
+        "documents": corpus.documents.len(),
+        "queries": corpus.queries.len(),
+        "baseline": baseline,
+        "candidate": candidate,
+        "delta": {
+            "recall_at_k": candidate.metrics.recall_at_k - baseline.metrics.recall_at_k,
+            "mrr": candidate.metrics.mrr - baseline.metrics.mrr,
+            "ndcg_at_k": candidate.metrics.ndcg_at_k - baseline.metrics.ndcg_at_k,
+        },
+        "recommendation": recommendation,
+    })))
+}
+
+async fn run_model(
+    state: &AppState,
+    model_id: &str,
+    corpus: &EvalCorpus,
+    k: usize,
+) -> Result<ModelReport, ApiError> {
+    let document_texts = corpus
+        .documents
+        .iter()
+        .map(|document| document.text.clone())
+        .collect::<Vec<_>>();
+    let query_texts = corpus
+        .queries
+        .iter()
+        .map(|query| query.query.clone())
+        .collect::<Vec<_>>();
+
+    let started = Instant::now();
+    let llm = &state.ai().llm;
+    let documents = llm.embed(&document_texts, model_id).await?;
+    let queries = llm.embed(&query_texts, model_id).await?;
+    let embed_latency_ms = started.elapsed().as_millis() as u64;
+
+    Ok(ModelReport {
+        model_id: model_id.to_string(),
+        dimensions: documents.first().map(Vec::len).unwrap_or(0),
+        embed_latency_ms,
+        metrics: evaluate(corpus, &documents, &queries, k)?,
+    })
+}
+
+/// コレクションのチャンクと埋め込みを Parquet で返す。
+pub async fn export_collection_parquet(
+    State(state): State<AppStateRead>,
+    Path(collection): Path<String>,
+    Query(query): Query<ExportCollectionQuery>,
+) -> Result<Response, ApiError> {
+    let manager = &state.workspace().manager;
+    let project_id = match query.project_id {
+        Some(project_id) => {
+            // パスに使うので、実在するプロジェクトだけを受け付ける
+            if !manager
+                .list_projects()?
+                .iter()
+                .any(|project| project.id == project_id)
+            {
+                return Err(ApiError::NotFound(format!(
+                    "Project not found: {}",
+                    project_id
+                )));
+            }
+            project_id
+        }
+        None => manager.current_project_id().await,
+    };
+    let db_path = state.core().paths.project_rag_db_path(&project_id);
+    if !db_path.is_file() {
+        return Err(ApiError::NotFound(format!(
+            "RAG collection not found: {}",
+            collection
+        )));
+    }
+
+    let store = SqliteRagStore::with_path(db_path).await?;
+    let chunks = store.export_chunks(&collection).await?;
+    if chunks.is_empty() {
+        return Err(ApiError::NotFound(format!(
+            "RAG collection not found: {}",
+            collection
+        )));
+    }
+    let embedding_model = store.embedding_model().await?;
+    let file_name = format!("{}-{}.parquet", project_id, sanitize_file_stem(&collection));
+    let body = tokio::task::spawn_blocking(move || {
+        chunks_to_parquet(&collection, embedding_model.as_deref(), &chunks)
+    })
+    .await
+    .map_err(ApiError::internal)??;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, PARQUET_CONTENT_TYPE.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+fn sanitize_file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,18 @@
+use axum::extract::{Path, State};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde_json::json;
+
+use crate::agent::plan::AgentPlan;
+use crate::core::errors::ApiError;
+use crate::state::AppStateWrite;
+
+/// 実行前の計画を編集版に差し替える。プランナーが計画を送ってから実行に移るまでの間だけ受け付ける。
+pub async fn update_run_plan(
+    State(state): State<AppStateWrite>,
+    Path(run_id): Path<String>,
+    Json(plan): Json<AgentPlan>,
+) -> Result<impl IntoResponse, ApiError> {
+    let plan = state.runtime().plan_edits.submit(&run_id, plan).await?;
+    Ok(Json(json!({ "run_id": run_id, "plan": plan })))
+}
@@ -0,0 +1,136 @@
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::core::errors::ApiError;
+use crate::scripting::{ScriptInfo, ScriptInput};
+use crate::server::handlers::audit::record_admin_action;
+use crate::state::{AppStateRead, AppStateWrite};
+
+#[derive(Debug, Serialize)]
+pub struct ScriptsResponse {
+    /// `scripting.enabled`。無効ならどのフックも呼ばれない
+    pub enabled: bool,
+    pub scripts: Vec<ScriptInfo>,
+}
+
+pub async fn list_scripts(
+    State(state): State<AppStateRead>,
+) -> Result<impl IntoResponse, ApiError> {
+    Ok(Json(ScriptsResponse {
+        enabled: state.core().config.load_typed()?.scripting.enabled,
+        scripts: state.integration().scripts.list()?,
+    }))
+}
+
+pub async fn get_script(
+    State(state): State<AppStateRead>,
+    Path(script_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    Ok(Json(state.integration().scripts.get(&script_id)?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveScriptRequest {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub source: String,
+}
+
+/// 作成と更新を兼ねる。新しいスクリプトは無効の状態で保存される。
+pub async fn save_script(
+    State(state): State<AppStateWrite>,
+    headers: HeaderMap,
+    Json(payload): Json<SaveScriptRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let script_id = payload.id.clone();
+    let result = state
+        .core()
+        .security
+        .ensure_lockdown_disabled("script_save")
+        .and_then(|_| {
+            state.integration().scripts.save(ScriptInput {
+                id: payload.id,
+                name: payload.name,
+                description: payload.description,
+                source: payload.source,
+            })
+        });
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "script_save",
+        Some(&script_id),
+        &result,
+        json!({}),
+    )
+    .await;
+    Ok(Json(result?))
+}
+
+pub async fn delete_script(
+    State(state): State<AppStateWrite>,
+    headers: HeaderMap,
+    Path(script_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let result = state
+        .core()
+        .security
+        .ensure_lockdown_disabled("script_delete")
+        .and_then(|_| state.integration().scripts.delete(&script_id));
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "script_delete",
+        Some(&script_id),
+        &result,
+        json!({}),
+    )
+    .await;
+    result?;
+    Ok(Json(json!({ "success": true })))
+}
+
+pub async fn enable_script(
+    state: State<AppStateWrite>,
+    headers: HeaderMap,
+    script_id: Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    set_enabled(state, headers, script_id, true).await
+}
+
+pub async fn disable_script(
+    state: State<AppStateWrite>,
+    headers: HeaderMap,
+    script_id: Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    set_enabled(state, headers, script_id, false).await
+}
+
+async fn set_enabled(
+    State(state): State<AppStateWrite>,
+    headers: HeaderMap,
+    Path(script_id): Path<String>,
+    enabled: bool,
+) -> Result<Json<ScriptInfo>, ApiError> {
+    let result = state
+        .core()
+        .security
+        .ensure_lockdown_disabled("script_toggle")
+        .and_then(|_| state.integration().scripts.set_enabled(&script_id, enabled));
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "script_toggle",
+        Some(&script_id),
+        &result,
+        json!({ "enabled": enabled }),
+    )
+    .await;
+    Ok(Json(result?))
+}
@@ -1,13 +1,19 @@
 use axum::extract::{Path, Query, State};
-use axum::response::IntoResponse;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde::Deserialize;
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
+use crate::context::workers::project_worker::{apply_session_project, session_defaults_for};
 use crate::core::errors::ApiError;
-use crate::state::{AppStateRead, AppStateWrite};
+use crate::history::{HistoryMessage, Transcript};
+use crate::llm::redaction::{redact_text, RedactionPolicy, TrustLevel};
+use crate::state::{AppState, AppStateRead, AppStateWrite};
+use crate::tools::code_blocks::{extract_code_blocks, CodeBlock};
+use crate::tools::filesystem::execute_file_tool;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateSessionRequest {
@@ -19,6 +25,41 @@ pub struct UpdateSessionRequest {
     pub title: String,
 }
 
+const DEFAULT_SEMANTIC_SEARCH_LIMIT: usize = 10;
+const MAX_SEMANTIC_SEARCH_LIMIT: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct SemanticSearchQuery {
+    pub q: String,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MoveSessionRequest {
+    pub project_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApplyCodeRequest {
+    pub blocks: Vec<ApplyCodeBlock>,
+    /// `true` なら差分だけ返して書き込まない
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApplyCodeBlock {
+    pub message_id: i64,
+    pub index: usize,
+    /// 省略時はブロックから読み取ったファイル名
+    #[serde(default)]
+    pub path: Option<String>,
+    /// overwrite / append / create
+    #[serde(default)]
+    pub mode: Option<String>,
+}
+
 pub async fn list_sessions(
     State(state): State<AppStateRead>,
 ) -> Result<impl IntoResponse, ApiError> {
@@ -40,6 +81,50 @@ pub async fn list_sessions(
     Ok(Json(json!({"sessions": result})))
 }
 
+/// 会話の意味で近いセッションを探す。セッションのベクトルはやり取りごとに更新している。
+pub async fn semantic_search_sessions(
+    State(state): State<AppStateRead>,
+    Query(query): Query<SemanticSearchQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let text = query.q.trim();
+    if text.is_empty() {
+        return Err(ApiError::BadRequest("q is required".to_string()));
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SEMANTIC_SEARCH_LIMIT)
+        .clamp(1, MAX_SEMANTIC_SEARCH_LIMIT);
+    let embedding_model_id = state
+        .ai()
+        .models
+        .resolve_assignment_model_id("embedding")?
+        .unwrap_or_else(|| "default".to_string());
+    let index = &state.memory().session_index;
+    let matches = index
+        .search(&state.ai().llm, &embedding_model_id, text, limit)
+        .await?;
+
+    let mut results = Vec::with_capacity(matches.len());
+    for hit in matches {
+        let Some(session) = state.runtime().history.get_session(&hit.session_id).await? else {
+            // 履歴から消えたセッションの残り
+            let _ = index.remove(&hit.session_id).await;
+            continue;
+        };
+        results.push(json!({
+            "session_id": hit.session_id,
+            "project_id": session.project_id,
+            "title": session.title,
+            "updated_at": session.updated_at,
+            "message_count": session.message_count,
+            "score": hit.score,
+            "matched_preview": hit.preview,
+            "indexed_exchanges": hit.exchanges,
+        }));
+    }
+    Ok(Json(json!({ "query": text, "results": results })))
+}
+
 pub async fn create_session(
     State(state): State<AppStateWrite>,
     Json(payload): Json<CreateSessionRequest>,
@@ -114,6 +199,7 @@ pub async fn get_session_messages(
         .history
         .get_history(&session_id, limit)
         .await?;
+    let defaults = session_defaults_for(state.as_ref(), &session_id).await;
 
     let formatted: Vec<Value> = messages
         .into_iter()
@@ -134,7 +220,7 @@ pub async fn get_session_messages(
                 .as_ref()
                 .and_then(|k| k.get("mode"))
                 .and_then(|v| v.as_str())
-                .unwrap_or("chat");
+                .unwrap_or(defaults.mode());
 
             json!({
                 "id": Uuid::new_v4().to_string(),
@@ -142,7 +228,7 @@ pub async fn get_session_messages(
                 "content": msg.content,
                 "timestamp": timestamp,
                 "mode": mode,
-                "isComplete": true
+                "isComplete": msg.is_complete()
             })
         })
         .collect();
@@ -150,6 +236,202 @@ pub async fn get_session_messages(
     Ok(Json(json!({"messages": formatted})))
 }
 
+pub async fn export_session_html(
+    State(state): State<AppStateRead>,
+    Path(session_id): Path<String>,
+) -> Result<Response, ApiError> {
+    let transcript = load_transcript(&state, &session_id).await?;
+    Ok(export_response(
+        "text/html; charset=utf-8",
+        &format!("{}.html", transcript.file_stem()),
+        transcript.to_html().into_bytes(),
+    ))
+}
+
+pub async fn export_session_pdf(
+    State(state): State<AppStateRead>,
+    Path(session_id): Path<String>,
+) -> Result<Response, ApiError> {
+    let transcript = load_transcript(&state, &session_id).await?;
+    // レイアウトは CPU だけを使うが、長い会話では数十ミリ秒かかる
+    let file_name = format!("{}.pdf", transcript.file_stem());
+    let pdf = tokio::task::spawn_blocking(move || transcript.to_pdf())
+        .await
+        .map_err(ApiError::internal)?;
+    Ok(export_response("application/pdf", &file_name, pdf))
+}
+
+/// 個人情報・パス・秘密を伏せた Markdown。不具合報告に添付するためのもので、
+/// 何をいくつ伏せたかも返す。添付画像は含めない。
+pub async fn anonymize_export_session(
+    State(state): State<AppStateRead>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let mut transcript = load_transcript(&state, &session_id).await?;
+    let policy = RedactionPolicy::for_export(&state.core().config.load_config()?);
+    let mut counts = BTreeMap::new();
+    transcript.anonymize(|text| redact_text(text, &policy, TrustLevel::Untrusted, &mut counts));
+    let total: usize = counts.values().sum();
+    Ok(Json(json!({
+        "file_name": format!("{}.md", transcript.file_stem()),
+        "markdown": transcript.to_markdown(),
+        "redactions": { "counts": counts, "total": total },
+    })))
+}
+
+pub async fn list_session_code_blocks(
+    State(state): State<AppStateRead>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let messages = load_assistant_messages(state.as_ref(), &session_id).await?;
+    let blocks = messages
+        .iter()
+        .flat_map(|message| {
+            extract_code_blocks(&message.content)
+                .into_iter()
+                .map(|block| code_block_payload(message.id, &block))
+        })
+        .collect::<Vec<_>>();
+    Ok(Json(json!({ "blocks": blocks })))
+}
+
+/// 選んだコードブロックを `file_write` ツール経由で書き込む。書き込み先は
+/// `tools.workspace_roots`（プロジェクトのワークスペースフォルダがあればその内側）に限られる。
+pub async fn apply_session_code(
+    State(state): State<AppStateWrite>,
+    Path(session_id): Path<String>,
+    Json(payload): Json<ApplyCodeRequest>,
+) -> Result<Json<Value>, ApiError> {
+    state
+        .core()
+        .security
+        .ensure_lockdown_disabled("apply_code")?;
+    if payload.blocks.is_empty() {
+        return Err(ApiError::BadRequest("No code blocks selected".to_string()));
+    }
+    let messages = load_assistant_messages(state.as_ref(), &session_id).await?;
+    let mut config = state.core().config.load_config()?;
+    apply_session_project(state.as_ref(), &session_id, &mut config).await;
+
+    let mut results = Vec::new();
+    for selected in &payload.blocks {
+        let block = messages
+            .iter()
+            .find(|message| message.id == selected.message_id)
+            .and_then(|message| {
+                extract_code_blocks(&message.content)
+                    .into_iter()
+                    .nth(selected.index)
+            })
+            .ok_or_else(|| {
+                ApiError::NotFound(format!(
+                    "Code block {} not found in message {}",
+                    selected.index, selected.message_id
+                ))
+            })?;
+        let path = selected
+            .path
+            .as_deref()
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .map(str::to_string)
+            .or(block.filename)
+            .ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "Code block {} in message {} has no target file; pass a path",
+                    selected.index, selected.message_id
+                ))
+            })?;
+        let args = json!({
+            "path": path,
+            "content": block.code,
+            "mode": selected.mode.as_deref().unwrap_or("overwrite"),
+            "dry_run": payload.dry_run,
+        });
+        let outcome = execute_file_tool(
+            Some(state.as_ref()),
+            &config,
+            Some(&session_id),
+            "file_write",
+            &args,
+        )
+        .await;
+        results.push(match outcome {
+            Ok(execution) => json!({
+                "message_id": selected.message_id,
+                "index": selected.index,
+                "path": path,
+                "applied": !payload.dry_run,
+                "preview": execution.output,
+            }),
+            Err(err) => json!({
+                "message_id": selected.message_id,
+                "index": selected.index,
+                "path": path,
+                "applied": false,
+                "error": err.to_string(),
+            }),
+        });
+    }
+    Ok(Json(json!({
+        "dry_run": payload.dry_run,
+        "results": results,
+    })))
+}
+
+async fn load_assistant_messages(
+    state: &AppState,
+    session_id: &str,
+) -> Result<Vec<HistoryMessage>, ApiError> {
+    state
+        .runtime()
+        .history
+        .get_session(session_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Session not found".to_string()))?;
+    let messages = state.runtime().history.get_history(session_id, 0).await?;
+    Ok(messages
+        .into_iter()
+        .filter(|message| message.message_type == "ai" && message.is_complete())
+        .collect())
+}
+
+fn code_block_payload(message_id: i64, block: &CodeBlock) -> Value {
+    json!({
+        "message_id": message_id,
+        "index": block.index,
+        "language": block.language,
+        "filename": block.filename,
+        "code": block.code,
+        "lines": block.code.lines().count(),
+    })
+}
+
+async fn load_transcript(state: &AppStateRead, session_id: &str) -> Result<Transcript, ApiError> {
+    let session = state
+        .runtime()
+        .history
+        .get_session(session_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Session not found".to_string()))?;
+    let messages = state.runtime().history.get_history(session_id, 0).await?;
+    Ok(Transcript::from_history(&session, messages))
+}
+
+fn export_response(content_type: &'static str, file_name: &str, body: Vec<u8>) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
 pub async fn update_session(
     State(state): State<AppStateWrite>,
     Path(session_id): Path<String>,
@@ -170,5 +452,29 @@ pub async fn delete_session(
 ) -> Result<impl IntoResponse, ApiError> {
     state.runtime().history.delete_session(&session_id).await?;
     // if !success check removed
+    if let Err(err) = state.memory().session_index.remove(&session_id).await {
+        tracing::warn!(session_id = %session_id, "Failed to drop session index entry: {}", err);
+    }
     Ok(Json(json!({"success": true})))
 }
+
+pub async fn move_session(
+    State(state): State<AppStateWrite>,
+    Path(session_id): Path<String>,
+    Json(payload): Json<MoveSessionRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !state.workspace().manager.has_project(&payload.project_id) {
+        return Err(ApiError::NotFound("Project not found".to_string()));
+    }
+    let moved = state
+        .runtime()
+        .history
+        .move_session_to_project(&session_id, &payload.project_id)
+        .await?;
+    if !moved {
+        return Err(ApiError::NotFound("Session not found".to_string()));
+    }
+    Ok(Json(
+        json!({"success": true, "session_id": session_id, "project_id": payload.project_id}),
+    ))
+}
@@ -1,16 +1,20 @@
 use std::collections::HashMap;
 
 use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde::Deserialize;
 use serde_json::{json, Value};
 use uuid::Uuid;
 
+use super::audit::record_admin_action;
 use super::setup_binary::{fetch_binary_update_info, install_latest_llama_binary};
 use super::setup_catalog::{
     check_model, check_model_update, delete_model, models_payload, queue_model_download,
-    refresh_lmstudio_models, refresh_ollama_models, register_local_model, reorder_models,
+    refresh_gemini_models, refresh_lmstudio_models, refresh_ollama_models,
+    refresh_openai_compatible_models, refresh_openrouter_models, register_local_model,
+    reorder_models,
 };
 use super::setup_flow::{
     default_models_payload, finish_setup, init_setup, preflight_payload, progress_payload,
@@ -23,7 +27,8 @@ use super::setup_roles::{
     set_character_specific_role, set_professional_role,
 };
 use crate::core::errors::ApiError;
-use crate::state::{AppStateRead, AppStateWrite};
+use crate::core::notifications::{BackgroundNotification, NotificationKind};
+use crate::state::{jobs, AppStateRead, AppStateWrite};
 
 #[derive(Debug, Deserialize)]
 pub struct SetupInitRequest {
@@ -303,9 +308,20 @@ pub async fn setup_register_local_model(
 
 pub async fn setup_delete_model(
     State(state): State<AppStateWrite>,
+    headers: HeaderMap,
     Path(model_id): Path<String>,
 ) -> Result<impl IntoResponse, ApiError> {
-    delete_model(&state, &model_id)?;
+    let result = delete_model(&state, &model_id);
+    record_admin_action(
+        &state.shared(),
+        &headers,
+        "model_delete",
+        Some(&model_id),
+        &result,
+        Value::Null,
+    )
+    .await;
+    result?;
     Ok(Json(json!({"success": true})))
 }
 
@@ -323,6 +339,27 @@ pub async fn setup_refresh_lmstudio_models(
     Ok(Json(json!({"success": true, "count": count})))
 }
 
+pub async fn setup_refresh_openai_compatible_models(
+    State(state): State<AppStateWrite>,
+) -> Result<impl IntoResponse, ApiError> {
+    let count = refresh_openai_compatible_models(&state).await?;
+    Ok(Json(json!({"success": true, "count": count})))
+}
+
+pub async fn setup_refresh_gemini_models(
+    State(state): State<AppStateWrite>,
+) -> Result<impl IntoResponse, ApiError> {
+    let count = refresh_gemini_models(&state).await?;
+    Ok(Json(json!({"success": true, "count": count})))
+}
+
+pub async fn setup_refresh_openrouter_models(
+    State(state): State<AppStateWrite>,
+) -> Result<impl IntoResponse, ApiError> {
+    let count = refresh_openrouter_models(&state).await?;
+    Ok(Json(json!({"success": true, "count": count})))
+}
+
 pub async fn setup_model_update_check(
     State(state): State<AppStateRead>,
     Query(params): Query<HashMap<String, String>>,
@@ -361,23 +398,56 @@ pub async fn setup_binary_update(
         .update_progress("pending", 0.0, "Starting binary update...")?;
 
     let state_clone = state.clone();
+    let bg_job_id = job_id.clone();
     tokio::spawn(async move {
+        jobs::begin(
+            state_clone.as_ref(),
+            &bg_job_id,
+            jobs::KIND_BINARY_UPDATE,
+            "llama.cpp binary update",
+            json!({ "variant": requested_variant }),
+        )
+        .await;
         let result =
             install_latest_llama_binary(state_clone.shared(), requested_variant.as_deref()).await;
+        jobs::finish(
+            state_clone.as_ref(),
+            &bg_job_id,
+            result.as_ref().err().map(|err| err.to_string()).as_deref(),
+        )
+        .await;
         match result {
             Ok(version) => {
-                let _ = state_clone.core().setup.update_progress(
-                    "completed",
-                    1.0,
-                    &format!("Updated llama.cpp binary to {}", version),
-                );
+                let message = format!("Updated llama.cpp binary to {}", version);
+                let _ = state_clone
+                    .core()
+                    .setup
+                    .update_progress("completed", 1.0, &message);
+                state_clone
+                    .core()
+                    .notifications
+                    .publish(BackgroundNotification::new(
+                        NotificationKind::BinaryUpdate,
+                        true,
+                        "llama.cpp updated",
+                        message,
+                    ));
             }
             Err(err) => {
-                let _ = state_clone.core().setup.update_progress(
-                    "failed",
-                    0.0,
-                    &format!("Update failed: {}", err),
-                );
+                let message = format!("Update failed: {}", err);
+                let _ = state_clone
+                    .core()
+                    .setup
+                    .update_progress("failed", 0.0, &message);
+                state_clone
+                    .core()
+                    .notifications
+                    .publish(BackgroundNotification::new(
+                        NotificationKind::BinaryUpdate,
+                        false,
+                        "llama.cpp update failed",
+                        message,
+                    ));
             }
         }
         let _ = state_clone.core().setup.set_job_id(None);
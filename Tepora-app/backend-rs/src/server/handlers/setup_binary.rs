@@ -1,5 +1,4 @@
 use std::fs;
-use std::io::Write;
 use std::path::{Component, Path as FsPath, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
@@ -7,15 +6,16 @@ use std::time::Duration;
 use axum::http::header;
 use chrono::Utc;
 use flate2::read::GzDecoder;
-use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use tar::Archive;
 use uuid::Uuid;
 use zip::ZipArchive;
 
+use crate::core::config::schema::NetworkSubsystem;
 use crate::core::config::AppPaths;
 use crate::core::errors::ApiError;
+use crate::core::net_retry::{download_with_resume, send_with_retry, RetryPolicy};
+use crate::core::network::NetClient;
 use crate::state::AppState;
 
 const LLAMA_RELEASE_LATEST_URL: &str =
@@ -378,19 +378,19 @@ fn normalize_sha256_hex(value: &str) -> Option<String> {
 }
 
 async fn fetch_latest_llama_release() -> Result<GithubRelease, ApiError> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build()
-        .map_err(ApiError::internal)?;
-    let response = client
-        .get(LLAMA_RELEASE_LATEST_URL)
-        .header(header::ACCEPT, "application/vnd.github+json")
-        .header(header::USER_AGENT, LLAMA_RELEASE_USER_AGENT)
-        .send()
-        .await
-        .map_err(ApiError::internal)?
-        .error_for_status()
-        .map_err(ApiError::internal)?;
+    let client = NetClient::from_builder(
+        NetworkSubsystem::BinaryUpdates,
+        reqwest::Client::builder().timeout(Duration::from_secs(30)),
+    )?;
+    let response = send_with_retry(&RetryPolicy::default(), "github_release", || {
+        Ok(client
+            .get(LLAMA_RELEASE_LATEST_URL)?
+            .header(header::ACCEPT, "application/vnd.github+json")
+            .header(header::USER_AGENT, LLAMA_RELEASE_USER_AGENT))
+    })
+    .await?
+    .error_for_status()
+    .map_err(ApiError::internal)?;
     response
         .json::<GithubRelease>()
         .await
@@ -402,49 +402,46 @@ async fn download_release_asset(
     target_path: &FsPath,
     mut progress_cb: impl FnMut(f32, &str),
 ) -> Result<String, ApiError> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(600))
-        .build()
-        .map_err(ApiError::internal)?;
-    let response = client
-        .get(&asset.browser_download_url)
-        .header(header::ACCEPT, "application/octet-stream")
-        .header(header::USER_AGENT, LLAMA_RELEASE_USER_AGENT)
-        .send()
-        .await
-        .map_err(ApiError::internal)?
-        .error_for_status()
-        .map_err(ApiError::internal)?;
-
-    let total = response.content_length().or(asset.size).unwrap_or(0);
-    let mut stream = response.bytes_stream();
-    let mut file = fs::File::create(target_path).map_err(ApiError::internal)?;
-    let mut downloaded: u64 = 0;
-    let mut hasher = Sha256::new();
-
-    while let Some(chunk) = stream.next().await {
-        let data = chunk.map_err(ApiError::internal)?;
-        file.write_all(&data).map_err(ApiError::internal)?;
-        hasher.update(&data);
-        downloaded += data.len() as u64;
-        let progress = if total > 0 {
-            downloaded as f32 / total as f32
-        } else {
-            0.0
-        };
-        let message = if total > 0 {
-            format!(
-                "Downloading binary... {:.1} MB / {:.1} MB",
-                downloaded as f64 / (1024_f64 * 1024_f64),
-                total as f64 / (1024_f64 * 1024_f64)
-            )
-        } else {
-            "Downloading binary...".to_string()
-        };
-        progress_cb(progress, &message);
-    }
+    let client = NetClient::from_builder(
+        NetworkSubsystem::BinaryUpdates,
+        reqwest::Client::builder().timeout(Duration::from_secs(600)),
+    )?;
+    let downloaded = download_with_resume(
+        &RetryPolicy::default(),
+        "github_release",
+        || {
+            Ok(client
+                .get(&asset.browser_download_url)?
+                .header(header::ACCEPT, "application/octet-stream")
+                .header(header::USER_AGENT, LLAMA_RELEASE_USER_AGENT))
+        },
+        target_path,
+        |downloaded, total| {
+            let total = if total > 0 {
+                total
+            } else {
+                asset.size.unwrap_or(0)
+            };
+            let progress = if total > 0 {
+                downloaded as f32 / total as f32
+            } else {
+                0.0
+            };
+            let message = if total > 0 {
+                format!(
+                    "Downloading binary... {:.1} MB / {:.1} MB",
+                    downloaded as f64 / (1024_f64 * 1024_f64),
+                    total as f64 / (1024_f64 * 1024_f64)
+                )
+            } else {
+                "Downloading binary...".to_string()
+            };
+            progress_cb(progress, &message);
+        },
+    )
+    .await?;
 
-    Ok(hex::encode(hasher.finalize()))
+    Ok(downloaded.sha256)
 }
 
 fn normalize_archive_member_path(raw: &FsPath) -> Option<PathBuf> {
@@ -7,7 +7,9 @@ use serde_json::{json, Value};
 use uuid::Uuid;
 
 use super::setup::ModelUpdateCheckTarget;
-use super::setup_models::{normalize_model_update_check_response, run_download_job, DownloadTask};
+use super::setup_models::{
+    normalize_model_update_check_response, spawn_download_job, DownloadTask,
+};
 use crate::core::errors::ApiError;
 use crate::state::{AppStateRead, AppStateWrite};
 
@@ -141,8 +143,7 @@ pub async fn queue_model_download(
         .setup
         .update_progress("pending", 0.0, "Starting download...")?;
 
-    let state_clone = state.clone();
-    tokio::spawn(run_download_job(state_clone, vec![task]));
+    spawn_download_job(state, vec![task], job_id.clone());
 
     Ok(Json(json!({"success": true, "job_id": job_id})).into_response())
 }
@@ -181,6 +182,18 @@ pub async fn refresh_lmstudio_models(state: &AppStateWrite) -> Result<usize, Api
     state.ai().models.refresh_lmstudio_models().await
 }
 
+pub async fn refresh_openai_compatible_models(state: &AppStateWrite) -> Result<usize, ApiError> {
+    state.ai().models.refresh_openai_compatible_models().await
+}
+
+pub async fn refresh_gemini_models(state: &AppStateWrite) -> Result<usize, ApiError> {
+    state.ai().models.refresh_gemini_models().await
+}
+
+pub async fn refresh_openrouter_models(state: &AppStateWrite) -> Result<usize, ApiError> {
+    state.ai().models.refresh_openrouter_models().await
+}
+
 pub async fn check_model_update(
     state: &AppStateRead,
     target: ModelUpdateCheckTarget<'_>,
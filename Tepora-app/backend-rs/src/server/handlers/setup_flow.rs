@@ -6,7 +6,7 @@ use axum::Json;
 use serde_json::{json, Value};
 use uuid::Uuid;
 
-use super::setup_models::{build_target_models, download_tasks_from_specs, run_download_job};
+use super::setup_models::{build_target_models, download_tasks_from_specs, spawn_download_job};
 use super::utils::ensure_object_path;
 use crate::core::errors::ApiError;
 use crate::state::{AppStateRead, AppStateWrite};
@@ -194,8 +194,7 @@ pub fn start_setup_run(
 
     let dl_tasks = download_tasks_from_specs(target_models, acknowledge_warnings.unwrap_or(false));
 
-    let state_clone = state.clone();
-    tokio::spawn(run_download_job(state_clone, dl_tasks));
+    spawn_download_job(state, dl_tasks, job_id.clone());
 
     Ok(Json(json!({"success": true, "job_id": job_id})).into_response())
 }
@@ -1,9 +1,11 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
 use crate::core::errors::ApiError;
-use crate::state::AppStateWrite;
+use crate::core::notifications::{BackgroundNotification, NotificationKind};
+use crate::state::{jobs, AppStateWrite};
 
 use crate::server::handlers::setup::{DownloadModelRequest, ModelUpdateCheckTarget};
 
@@ -18,7 +20,7 @@ pub struct ModelDownloadSpec {
     pub sha256: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DownloadTask {
     pub repo_id: String,
     pub filename: String,
@@ -30,8 +32,31 @@ pub struct DownloadTask {
     pub consent: bool,
 }
 
-pub async fn run_download_job(state: AppStateWrite, tasks: Vec<DownloadTask>) {
+/// ダウンロードをジョブとして記録してから裏で走らせる。
+pub fn spawn_download_job(state: &AppStateWrite, tasks: Vec<DownloadTask>, job_id: String) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        let label = tasks
+            .iter()
+            .map(|task| task.display_name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        jobs::begin(
+            state.as_ref(),
+            &job_id,
+            jobs::KIND_MODEL_DOWNLOAD,
+            &label,
+            json!({ "tasks": tasks }),
+        )
+        .await;
+        run_download_job(state, tasks, job_id).await;
+    });
+}
+
+/// `job_id` はジョブ記録と `SetupState` の ID。再開時も同じ ID で呼ぶ。
+pub async fn run_download_job(state: AppStateWrite, tasks: Vec<DownloadTask>, job_id: String) {
     let total = tasks.len().max(1) as f32;
+    let names: Vec<String> = tasks.iter().map(|task| task.display_name.clone()).collect();
     for (idx, task) in tasks.into_iter().enumerate() {
         let base_progress = idx as f32 / total;
         let progress_cb = |p: f32, message: &str| {
@@ -57,7 +82,7 @@ pub async fn run_download_job(state: AppStateWrite, tasks: Vec<DownloadTask>) {
             )
             .await;
 
-        match result {
+        match &result {
             Ok(dl_result) if dl_result.success => {
                 if let (Some(model_id), Some(assignment_key)) = (
                     dl_result.model_id.as_deref(),
@@ -83,6 +108,23 @@ pub async fn run_download_job(state: AppStateWrite, tasks: Vec<DownloadTask>) {
                     .setup
                     .update_progress("failed", 0.0, "Download failed");
                 let _ = state.core().setup.set_job_id(None);
+                let error = match &result {
+                    Ok(dl_result) => dl_result
+                        .error_message
+                        .clone()
+                        .unwrap_or_else(|| dl_result.warnings.join("; ")),
+                    Err(err) => err.to_string(),
+                };
+                jobs::finish(state.as_ref(), &job_id, Some(&error)).await;
+                state
+                    .core()
+                    .notifications
+                    .publish(BackgroundNotification::new(
+                        NotificationKind::Download,
+                        false,
+                        "Download failed",
+                        format!("Could not download {}", task.display_name),
+                    ));
                 return;
             }
         }
@@ -93,6 +135,16 @@ pub async fn run_download_job(state: AppStateWrite, tasks: Vec<DownloadTask>) {
         .setup
         .update_progress("completed", 1.0, "Download completed!");
     let _ = state.core().setup.set_job_id(None);
+    jobs::finish(state.as_ref(), &job_id, None).await;
+    state
+        .core()
+        .notifications
+        .publish(BackgroundNotification::new(
+            NotificationKind::Download,
+            true,
+            "Download completed",
+            names.join(", "),
+        ));
 }
 
 pub fn download_tasks_from_specs(
@@ -1,20 +1,25 @@
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::response::IntoResponse;
 use axum::Json;
 use schemars::JsonSchema;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::core::errors::ApiError;
-use crate::core::native_tools::{NativeTool, NATIVE_TOOLS};
+use crate::history::ToolInvocationStats;
 use crate::mcp::McpToolInfo;
+use crate::plugins::PluginToolInfo;
 use crate::state::AppStateRead;
+use crate::tools::http_api::{http_tool_definitions, HttpToolDefinition};
+use crate::tools::registry::{NativeToolSpec, NATIVE_TOOLS};
 
 #[derive(Debug, Clone, Serialize, JsonSchema, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum ToolSource {
     Native,
     Mcp,
+    Http,
+    Plugin,
 }
 
 #[derive(Debug, Clone, Serialize, JsonSchema, PartialEq)]
@@ -31,12 +36,12 @@ pub struct ToolsListResponse {
     pub tools: Vec<ToolDescriptor>,
 }
 
-fn native_tool_descriptor(tool: &NativeTool) -> ToolDescriptor {
+fn native_tool_descriptor(tool: &NativeToolSpec) -> ToolDescriptor {
     ToolDescriptor {
         name: tool.name.to_string(),
         description: tool.description.to_string(),
         source: ToolSource::Native,
-        input_schema: None,
+        input_schema: Some(tool.input_schema()),
     }
 }
 
@@ -49,38 +54,103 @@ fn mcp_tool_descriptor(tool: McpToolInfo) -> ToolDescriptor {
     }
 }
 
+fn http_tool_descriptor(tool: HttpToolDefinition) -> ToolDescriptor {
+    ToolDescriptor {
+        name: tool.name,
+        description: tool.description,
+        source: ToolSource::Http,
+        input_schema: Some(tool.params_schema),
+    }
+}
+
+fn plugin_tool_descriptor(tool: PluginToolInfo) -> ToolDescriptor {
+    ToolDescriptor {
+        name: tool.name,
+        description: tool.description,
+        source: ToolSource::Plugin,
+        input_schema: tool.input_schema,
+    }
+}
+
 pub fn build_tools_response(
-    native_tools: &[NativeTool],
+    native_tools: &[NativeToolSpec],
     mcp_tools: Vec<McpToolInfo>,
+    http_tools: Vec<HttpToolDefinition>,
 ) -> ToolsListResponse {
     let mut tools = native_tools
         .iter()
         .map(native_tool_descriptor)
         .collect::<Vec<_>>();
     tools.extend(mcp_tools.into_iter().map(mcp_tool_descriptor));
+    tools.extend(http_tools.into_iter().map(http_tool_descriptor));
     ToolsListResponse { tools }
 }
 
 pub async fn list_tools(State(state): State<AppStateRead>) -> Result<impl IntoResponse, ApiError> {
     let mcp_tools = state.integration().mcp.list_tools().await;
-    Ok(Json(build_tools_response(NATIVE_TOOLS, mcp_tools)))
+    let config = state.core().config.load_config()?;
+    let mut response =
+        build_tools_response(NATIVE_TOOLS, mcp_tools, http_tool_definitions(&config));
+    response.tools.extend(
+        state
+            .integration()
+            .plugins
+            .tools()
+            .into_iter()
+            .map(plugin_tool_descriptor),
+    );
+    Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ToolStatsQuery {
+    /// 集計期間（時間）。省略時は保持期間全体。
+    pub since_hours: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ToolStatsResponse {
+    pub tools: Vec<ToolInvocationStats>,
+    /// 直近で失敗し続けているツール名
+    pub failing: Vec<String>,
+}
+
+pub fn failing_tool_names(stats: &[ToolInvocationStats]) -> Vec<String> {
+    stats
+        .iter()
+        .filter(|stat| stat.is_chronically_failing())
+        .map(|stat| stat.tool.clone())
+        .collect()
+}
+
+pub async fn tool_stats(
+    State(state): State<AppStateRead>,
+    Query(query): Query<ToolStatsQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let since = query.since_hours.map(|hours| {
+        (chrono::Utc::now() - chrono::Duration::hours(hours.min(24 * 365) as i64)).to_rfc3339()
+    });
+    let tools = state
+        .runtime()
+        .history
+        .tool_invocation_stats(since.as_deref())
+        .await?;
+    let failing = failing_tool_names(&tools);
+    Ok(Json(ToolStatsResponse { tools, failing }))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::native_tools::NATIVE_SEARCH;
+    use crate::tools::registry::find;
     use schemars::schema_for;
     use serde_json::json;
 
     #[test]
     fn native_tools_response_contract_is_stable() {
-        let response = build_tools_response(
-            &[NativeTool {
-                name: "native_search",
-                description: "Search the web",
-            }],
-            Vec::new(),
-        );
+        let response =
+            build_tools_response(&[*find(NATIVE_SEARCH).unwrap()], Vec::new(), Vec::new());
 
         assert_eq!(
             serde_json::to_value(response).unwrap(),
@@ -89,7 +159,14 @@ mod tests {
                     {
                         "name": "native_search",
                         "description": "Search the web",
-                        "source": "native"
+                        "source": "native",
+                        "inputSchema": {
+                            "type": "object",
+                            "properties": {
+                                "query": { "type": "string" }
+                            },
+                            "required": ["query"]
+                        }
                     }
                 ]
             })
@@ -111,6 +188,7 @@ mod tests {
                     "required": ["message"]
                 })),
             }],
+            Vec::new(),
         );
 
         assert_eq!(
@@ -0,0 +1,112 @@
+//! 会話履歴の月ごとの話題索引。
+//!
+//! 索引は `core::history_topics` のジョブが作る。ここでは作ったものを月ごとに返し、
+//! 手動での作り直しを受け付ける。
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::core::errors::ApiError;
+use crate::core::history_topics::{is_valid_month, run_topic_index_job};
+use crate::history::TopicRecord;
+use crate::state::{AppStateRead, AppStateWrite};
+
+#[derive(Debug, Deserialize)]
+pub struct TopicsQuery {
+    /// `YYYY-MM`。省略時は全期間
+    pub month: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RebuildTopicsRequest {
+    /// 省略時はセッションのある全ての月
+    #[serde(default)]
+    pub months: Vec<String>,
+}
+
+/// 新しい月から順に、月ごとの話題（大きい順）を返す。
+pub async fn list_topics(
+    State(state): State<AppStateRead>,
+    Query(query): Query<TopicsQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let month = query.month.as_deref().map(str::trim);
+    if let Some(month) = month {
+        if !is_valid_month(month) {
+            return Err(ApiError::BadRequest(
+                "month must be formatted as YYYY-MM".to_string(),
+            ));
+        }
+    }
+    let topics = state.runtime().history.list_topics(month).await?;
+    Ok(Json(json!({ "months": group_by_month(topics) })))
+}
+
+/// 話題索引をバックグラウンドで作り直す。進み具合は `GET /api/jobs` で見る。
+pub async fn rebuild_topics(
+    State(state): State<AppStateWrite>,
+    payload: Option<Json<RebuildTopicsRequest>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut months = payload.map(|Json(body)| body.months).unwrap_or_default();
+    if let Some(invalid) = months.iter().find(|month| !is_valid_month(month)) {
+        return Err(ApiError::BadRequest(format!(
+            "Invalid month '{}': expected YYYY-MM",
+            invalid
+        )));
+    }
+    if months.is_empty() {
+        months = state.runtime().history.session_months().await?;
+    }
+    months.sort();
+    months.dedup();
+    if state
+        .ai()
+        .models
+        .resolve_assignment_model_id("embedding")?
+        .is_none()
+    {
+        return Err(ApiError::BadRequest(
+            "No embedding model is assigned".to_string(),
+        ));
+    }
+
+    let job_id = uuid::Uuid::new_v4().to_string();
+    let shared = state.shared();
+    let bg_job_id = job_id.clone();
+    let bg_months = months.clone();
+    tokio::spawn(async move {
+        run_topic_index_job(&shared, &bg_job_id, &bg_months).await;
+    });
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "status": "queued",
+            "job_id": job_id,
+            "months": months,
+        })),
+    ))
+}
+
+fn group_by_month(topics: Vec<TopicRecord>) -> Vec<serde_json::Value> {
+    let mut months: Vec<(String, Vec<TopicRecord>)> = Vec::new();
+    for topic in topics {
+        match months.last_mut() {
+            Some((month, group)) if *month == topic.month => group.push(topic),
+            _ => months.push((topic.month.clone(), vec![topic])),
+        }
+    }
+    months
+        .into_iter()
+        .map(|(month, topics)| {
+            let sessions = topics
+                .iter()
+                .map(|topic| topic.session_ids.len())
+                .sum::<usize>();
+            json!({ "month": month, "sessions": sessions, "topics": topics })
+        })
+        .collect()
+}
@@ -0,0 +1,84 @@
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Serialize;
+
+use crate::core::config::schema::{UpdateChannel, UpdateSettings};
+use crate::core::errors::ApiError;
+use crate::state::AppStateRead;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum UpdateBlocker {
+    AgentRun { count: usize },
+    Download { job_id: String },
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdateReadiness {
+    /// 今すぐ再起動して更新してよいか
+    pub ready: bool,
+    pub blockers: Vec<UpdateBlocker>,
+    pub channel: UpdateChannel,
+    pub auto_check: bool,
+}
+
+fn build_readiness(
+    settings: UpdateSettings,
+    runs_in_flight: usize,
+    setup_job_id: Option<String>,
+) -> UpdateReadiness {
+    let mut blockers = Vec::new();
+    if runs_in_flight > 0 {
+        blockers.push(UpdateBlocker::AgentRun {
+            count: runs_in_flight,
+        });
+    }
+    if let Some(job_id) = setup_job_id {
+        blockers.push(UpdateBlocker::Download { job_id });
+    }
+    UpdateReadiness {
+        ready: blockers.is_empty(),
+        blockers,
+        channel: settings.channel,
+        auto_check: settings.auto_check,
+    }
+}
+
+/// デスクトップ版の更新タイミングを決める。実行中の処理があれば `ready: false`。
+pub async fn update_readiness(
+    State(state): State<AppStateRead>,
+) -> Result<impl IntoResponse, ApiError> {
+    let settings = state.core().config.load_typed()?.updates;
+    let runs_in_flight = state.runtime().graph_runtime.runs_in_flight();
+    let setup_job_id = state.core().setup.snapshot()?.job_id;
+    Ok(Json(build_readiness(
+        settings,
+        runs_in_flight,
+        setup_job_id,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_work_defers_the_update() {
+        let readiness = build_readiness(UpdateSettings::default(), 0, None);
+        assert!(readiness.ready);
+        assert_eq!(readiness.channel, UpdateChannel::Stable);
+
+        let readiness = build_readiness(UpdateSettings::default(), 2, Some("job-1".into()));
+        assert!(!readiness.ready);
+        assert_eq!(
+            readiness.blockers,
+            vec![
+                UpdateBlocker::AgentRun { count: 2 },
+                UpdateBlocker::Download {
+                    job_id: "job-1".into()
+                },
+            ]
+        );
+    }
+}
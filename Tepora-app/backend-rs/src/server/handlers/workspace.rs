@@ -4,7 +4,10 @@ use axum::Json;
 use serde::Deserialize;
 use serde_json::json;
 
+use crate::context::workers::project_worker::is_within_workspace_roots;
+use crate::core::config::schema::SessionDefaults;
 use crate::core::errors::ApiError;
+use crate::history::ProjectSettings;
 use crate::state::{AppStateRead, AppStateWrite};
 use crate::workspace::CreateProjectRequest;
 
@@ -18,6 +21,20 @@ pub struct WorkspaceRenamePayload {
     pub new_path: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ProjectSettingsPayload {
+    #[serde(default)]
+    pub pinned_context: String,
+    #[serde(default)]
+    pub rag_collections: Vec<String>,
+    #[serde(default)]
+    pub workspace_folders: Vec<String>,
+    pub default_agent_id: Option<String>,
+    pub default_model_id: Option<String>,
+    #[serde(default)]
+    pub session_defaults: SessionDefaults,
+}
+
 pub async fn list_projects(
     State(state): State<AppStateRead>,
 ) -> Result<impl IntoResponse, ApiError> {
@@ -55,6 +72,90 @@ pub async fn set_current_project(
     Ok(Json(json!({ "success": true, "project_id": project_id })))
 }
 
+pub async fn get_project_settings(
+    State(state): State<AppStateRead>,
+    Path(project_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !state.workspace().manager.has_project(&project_id) {
+        return Err(ApiError::NotFound("Project not found".to_string()));
+    }
+    let settings = state
+        .runtime()
+        .history
+        .get_project_settings(&project_id)
+        .await?;
+    Ok(Json(json!({ "settings": settings })))
+}
+
+pub async fn update_project_settings(
+    State(state): State<AppStateWrite>,
+    Path(project_id): Path<String>,
+    Json(payload): Json<ProjectSettingsPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !state.workspace().manager.has_project(&project_id) {
+        return Err(ApiError::NotFound("Project not found".to_string()));
+    }
+    let config = state.core().config.load_config()?;
+    if let Some(folder) = payload
+        .workspace_folders
+        .iter()
+        .find(|folder| !is_within_workspace_roots(&config, folder))
+    {
+        return Err(ApiError::BadRequest(format!(
+            "Workspace folder must exist inside tools.workspace_roots: {}",
+            folder
+        )));
+    }
+    if payload
+        .session_defaults
+        .temperature
+        .is_some_and(|temperature| !(0.0..=2.0).contains(&temperature))
+    {
+        return Err(ApiError::BadRequest(
+            "session_defaults.temperature must be between 0 and 2".to_string(),
+        ));
+    }
+    let session_defaults = payload.session_defaults;
+    let settings = ProjectSettings {
+        project_id,
+        pinned_context: payload.pinned_context.trim().to_string(),
+        rag_collections: clean_list(payload.rag_collections),
+        workspace_folders: clean_list(payload.workspace_folders),
+        default_agent_id: clean_optional(payload.default_agent_id),
+        default_model_id: clean_optional(payload.default_model_id),
+        session_defaults: SessionDefaults {
+            mode: clean_optional(session_defaults.mode),
+            persona_id: clean_optional(session_defaults.persona_id),
+            rag_collections: clean_list(session_defaults.rag_collections),
+            temperature: session_defaults.temperature,
+        },
+        updated_at: None,
+    };
+    let settings = state
+        .runtime()
+        .history
+        .save_project_settings(&settings)
+        .await?;
+    Ok(Json(json!({ "settings": settings })))
+}
+
+fn clean_list(values: Vec<String>) -> Vec<String> {
+    let mut cleaned = Vec::<String>::new();
+    for value in values {
+        let value = value.trim();
+        if !value.is_empty() && !cleaned.iter().any(|existing| existing == value) {
+            cleaned.push(value.to_string());
+        }
+    }
+    cleaned
+}
+
+fn clean_optional(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
 pub async fn get_current_tree(
     State(state): State<AppStateRead>,
 ) -> Result<impl IntoResponse, ApiError> {
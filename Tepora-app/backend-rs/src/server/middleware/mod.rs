@@ -1,3 +1,4 @@
 pub mod auth;
+pub mod origin;
 pub mod rate_limit;
 pub mod tracing;
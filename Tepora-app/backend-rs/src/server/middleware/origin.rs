@@ -0,0 +1,278 @@
+//! Origin 検証（CSRF 対策）。
+//!
+//! CORS はブラウザにレスポンスを読ませないだけで、フォーム送信や
+//! `no-cors` の fetch による状態変更そのものは防げない。そこで状態を変える
+//! リクエストでは、CORS とは別にサーバー側で Origin を照合する。
+//! Origin を送らない呼び出し元（CLI やスクリプト）は `x-api-key` による
+//! トークン認証だけで通す。
+
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, Method};
+use axum::middleware::Next;
+use axum::response::Response;
+use serde_json::Value;
+
+use crate::core::errors::ApiError;
+use crate::state::AppState;
+
+pub fn default_local_origins() -> Vec<String> {
+    vec![
+        "tauri://localhost".to_string(),
+        "https://tauri.localhost".to_string(),
+        "http://tauri.localhost".to_string(),
+        "http://localhost".to_string(),
+        "http://localhost:3000".to_string(),
+        "http://localhost:5173".to_string(),
+        "http://127.0.0.1".to_string(),
+        "http://127.0.0.1:3000".to_string(),
+        "http://127.0.0.1:5173".to_string(),
+        "http://127.0.0.1:8000".to_string(),
+    ]
+}
+
+fn origin_list(config: &Value, keys: &[&str]) -> Vec<String> {
+    let Some(server) = config.get("server").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    keys.iter()
+        .find_map(|key| server.get(*key))
+        .and_then(Value::as_array)
+        .map(|list| {
+            list.iter()
+                .filter_map(Value::as_str)
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `server.cors_allowed_origins`（旧名 `allowed_origins`）。未設定ならローカルの既定値。
+pub fn allowed_origins(config: &Value) -> Vec<String> {
+    let origins = origin_list(
+        config,
+        &[
+            "cors_allowed_origins",
+            "allowed_origins",
+            "ws_allowed_origins",
+        ],
+    );
+    if origins.is_empty() {
+        default_local_origins()
+    } else {
+        origins
+    }
+}
+
+/// WebSocket 用。`server.ws_allowed_origins` があればそれを優先する。
+pub fn ws_allowed_origins(config: &Value) -> Vec<String> {
+    let origins = origin_list(
+        config,
+        &[
+            "ws_allowed_origins",
+            "cors_allowed_origins",
+            "allowed_origins",
+        ],
+    );
+    if origins.is_empty() {
+        default_local_origins()
+    } else {
+        origins
+    }
+}
+
+pub fn is_production() -> bool {
+    std::env::var("TEPORA_ENV").unwrap_or_else(|_| "production".to_string()) == "production"
+}
+
+fn normalize_origin(origin: &str) -> String {
+    origin.trim().trim_end_matches('/').to_ascii_lowercase()
+}
+
+/// Origin が許可リストと完全一致するか。開発環境ではローカルホストの全ポートも許す。
+pub fn is_origin_allowed(origin: &str, allowed: &[String], dev_mode: bool) -> bool {
+    let origin = normalize_origin(origin);
+    if origin.is_empty() || origin == "null" {
+        return false;
+    }
+    if allowed
+        .iter()
+        .any(|candidate| normalize_origin(candidate) == origin)
+    {
+        return true;
+    }
+    dev_mode
+        && ["http://localhost:", "http://127.0.0.1:"]
+            .iter()
+            .any(|prefix| {
+                origin.strip_prefix(prefix).is_some_and(|port| {
+                    !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit())
+                })
+            })
+}
+
+/// Referer からスキーム・ホスト・ポートだけを取り出す。
+fn referer_origin(referer: &str) -> Option<String> {
+    let url = reqwest::Url::parse(referer).ok()?;
+    Some(url.origin().ascii_serialization()).filter(|origin| origin != "null")
+}
+
+fn is_state_changing(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// 状態変更リクエストの出所を判定する。`Ok(())` なら通してよい。
+pub fn check_request_origin(
+    method: &Method,
+    headers: &HeaderMap,
+    allowed: &[String],
+    dev_mode: bool,
+) -> Result<(), String> {
+    if !is_state_changing(method) {
+        return Ok(());
+    }
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+    if let Some(origin) = header("origin") {
+        return if is_origin_allowed(origin, allowed, dev_mode) {
+            Ok(())
+        } else {
+            Err(format!("Origin '{}' is not allowed", origin))
+        };
+    }
+    if let Some(referer) = header("referer") {
+        return match referer_origin(referer) {
+            Some(origin) if is_origin_allowed(&origin, allowed, dev_mode) => Ok(()),
+            _ => Err("Referer is not an allowed origin".to_string()),
+        };
+    }
+    // Origin も Referer も無いのに、ブラウザが別サイトからの要求だと申告している
+    if header("sec-fetch-site").is_some_and(|site| site.eq_ignore_ascii_case("cross-site")) {
+        return Err("Cross-site request without Origin".to_string());
+    }
+    Ok(())
+}
+
+pub async fn require_trusted_origin_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if is_state_changing(request.method()) {
+        let config = state.core().config.load_config().unwrap_or(Value::Null);
+        let allowed = allowed_origins(&config);
+        if let Err(reason) = check_request_origin(
+            request.method(),
+            request.headers(),
+            &allowed,
+            !is_production(),
+        ) {
+            tracing::warn!(
+                method = %request.method(),
+                path = %request.uri().path(),
+                "Rejected state-changing request: {}",
+                reason
+            );
+            return Err(ApiError::Forbidden);
+        }
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use serde_json::json;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_static(value));
+        }
+        map
+    }
+
+    #[test]
+    fn origin_matching_is_exact() {
+        let allowed = vec!["http://localhost:5173".to_string()];
+        assert!(is_origin_allowed("http://localhost:5173", &allowed, false));
+        assert!(is_origin_allowed("HTTP://LOCALHOST:5173/", &allowed, false));
+        assert!(!is_origin_allowed(
+            "http://localhost:5173.evil.com",
+            &allowed,
+            false
+        ));
+        assert!(!is_origin_allowed("http://localhost:9999", &allowed, false));
+        assert!(is_origin_allowed("http://localhost:9999", &allowed, true));
+        assert!(!is_origin_allowed("http://localhost:99x", &allowed, true));
+        assert!(!is_origin_allowed("null", &allowed, true));
+    }
+
+    #[test]
+    fn state_changing_requests_need_an_allowed_origin() {
+        let allowed = default_local_origins();
+        let post = Method::POST;
+
+        assert!(check_request_origin(
+            &Method::GET,
+            &headers(&[("origin", "https://evil.example")]),
+            &allowed,
+            false
+        )
+        .is_ok());
+        assert!(check_request_origin(
+            &post,
+            &headers(&[("origin", "https://evil.example")]),
+            &allowed,
+            false
+        )
+        .is_err());
+        assert!(check_request_origin(
+            &post,
+            &headers(&[("origin", "tauri://localhost")]),
+            &allowed,
+            false
+        )
+        .is_ok());
+        assert!(check_request_origin(
+            &post,
+            &headers(&[("referer", "http://localhost:5173/settings?tab=1")]),
+            &allowed,
+            false
+        )
+        .is_ok());
+        assert!(check_request_origin(
+            &post,
+            &headers(&[("referer", "https://evil.example/x")]),
+            &allowed,
+            false
+        )
+        .is_err());
+        assert!(check_request_origin(
+            &post,
+            &headers(&[("sec-fetch-site", "cross-site")]),
+            &allowed,
+            false
+        )
+        .is_err());
+        // CLI などの非ブラウザ呼び出しはトークン認証に任せる
+        assert!(check_request_origin(&post, &HeaderMap::new(), &allowed, false).is_ok());
+    }
+
+    #[test]
+    fn ws_origins_prefer_dedicated_list() {
+        let config = json!({
+            "server": {
+                "cors_allowed_origins": ["https://app.example"],
+                "ws_allowed_origins": ["https://ws.example"]
+            }
+        });
+        assert_eq!(allowed_origins(&config), vec!["https://app.example"]);
+        assert_eq!(ws_allowed_origins(&config), vec!["https://ws.example"]);
+        assert_eq!(allowed_origins(&json!({})), default_local_origins());
+    }
+}
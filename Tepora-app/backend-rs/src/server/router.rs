@@ -1,27 +1,38 @@
+use axum::extract::DefaultBodyLimit;
 use axum::http::{header, HeaderValue, Method};
 use axum::middleware;
-use axum::routing::{delete, get, post};
+use axum::routing::{delete, get, patch, post, put};
 use axum::Router;
-use serde_json::Value;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::broadcast;
 use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 
+use crate::core::config::watch::ConfigChangeEvent;
+use crate::core::config::ConfigService;
 use crate::server::handlers::{
-    auth, config, health, logs, mcp, memory, metrics, security, sessions, setup, skills, tools,
-    workspace,
+    audit, auth, config, context, custom_agents, desktop, embeddings, evals, health, inbox, jobs,
+    llm, logs, maintenance, mcp, memory, metrics, network, personas, plugins, profiler, rag, runs,
+    scripts, security, sessions, setup, skills, tools, topics, updates, workspace,
 };
 use crate::server::middleware::auth::require_api_key_middleware;
+use crate::server::middleware::origin::{
+    allowed_origins, default_local_origins, is_origin_allowed, require_trusted_origin_middleware,
+};
 use crate::server::middleware::rate_limit::rate_limit_middleware;
 use crate::server::ws::handler::ws_handler;
+use crate::server::ws::schema::get_ws_schema;
 use crate::state::AppState;
 
+/// PNG character cards carry the full portrait, so allow more than axum's 2 MB default.
+const CHARACTER_CARD_BODY_LIMIT: usize = 16 * 1024 * 1024;
+
 /// Creates the main application router with all routes and middleware.
 ///
 /// This function sets up:
 /// - CORS middleware
 /// - Health check endpoints
-/// - API endpoints (config, logs, sessions, custom agents, tools, setup, mcp)
+/// - API endpoints (config, logs, sessions, personas, custom agents, tools, setup, mcp)
 /// - WebSocket handler
 ///
 /// # Arguments
@@ -33,6 +44,7 @@ pub fn router(state: Arc<AppState>) -> Router {
         .route("/health", get(health::health))
         .merge(api_routes(state.clone()))
         .route("/ws", get(ws_handler))
+        .route("/api/desktop/bridge", get(desktop::desktop_bridge))
         .with_state(state)
         .layer(cors_layer)
         .layer(TraceLayer::new_for_http())
@@ -43,13 +55,27 @@ fn api_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
         .route("/api/status", get(health::get_status))
         .route("/api/shutdown", post(health::shutdown))
         .route("/api/auth/refresh", post(auth::refresh_token))
+        .route(
+            "/api/auth/users",
+            get(auth::list_users).post(auth::create_user),
+        )
+        .route("/api/auth/user", put(auth::select_user))
+        .route("/api/audit", get(audit::list_audit))
+        .route("/api/inbox", get(inbox::list_inbox))
+        .route("/api/inbox/ack", post(inbox::acknowledge_inbox))
+        .route("/api/jobs", get(jobs::list_jobs))
         .route(
             "/api/config",
             get(config::get_config)
                 .post(config::update_config)
                 .patch(config::patch_config),
         )
+        .route("/api/config/schema", get(config::get_config_schema))
+        .route("/api/ws/schema", get(get_ws_schema))
+        .route("/api/config/profiles", get(config::list_profiles))
+        .route("/api/config/profile", put(config::select_profile))
         .route("/api/config/secrets/rotate", post(config::rotate_secrets))
+        .route("/api/context/preview", post(context::preview_context))
         .route("/api/security/lockdown", post(security::set_lockdown))
         .route("/api/security/permissions", get(security::list_permissions))
         .route(
@@ -79,6 +105,10 @@ fn api_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
             "/api/workspace/projects/:project_id/select",
             post(workspace::set_current_project),
         )
+        .route(
+            "/api/workspace/projects/:project_id/settings",
+            get(workspace::get_project_settings).put(workspace::update_project_settings),
+        )
         .route("/api/workspace/tree", get(workspace::get_current_tree))
         .route(
             "/api/workspace/document/*path",
@@ -90,21 +120,63 @@ fn api_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
         )
         .route("/api/workspace/rename/*path", post(workspace::rename_path))
         .route("/api/workspace/path/*path", delete(workspace::delete_path))
+        .route(
+            "/api/sessions/semantic-search",
+            get(sessions::semantic_search_sessions),
+        )
+        .route("/api/history/topics", get(topics::list_topics))
+        .route("/api/history/topics/rebuild", post(topics::rebuild_topics))
         .route(
             "/api/sessions/:session_id",
             get(sessions::get_session)
                 .patch(sessions::update_session)
                 .delete(sessions::delete_session),
         )
+        .route(
+            "/api/sessions/:session_id/project",
+            put(sessions::move_session),
+        )
         .route(
             "/api/sessions/:session_id/messages",
             get(sessions::get_session_messages),
         )
+        .route(
+            "/api/sessions/:session_id/export.html",
+            get(sessions::export_session_html),
+        )
+        .route(
+            "/api/sessions/:session_id/export.pdf",
+            get(sessions::export_session_pdf),
+        )
+        .route(
+            "/api/sessions/:session_id/anonymize-export",
+            post(sessions::anonymize_export_session),
+        )
+        .route(
+            "/api/sessions/:session_id/code-blocks",
+            get(sessions::list_session_code_blocks),
+        )
+        .route(
+            "/api/sessions/:session_id/apply-code",
+            post(sessions::apply_session_code),
+        )
         .route(
             "/api/sessions/:session_id/metrics",
             get(metrics::get_session_metrics),
         )
+        .route(
+            "/api/sessions/:session_id/usage",
+            get(metrics::get_session_usage),
+        )
+        .route(
+            "/api/sessions/:session_id/context-usage",
+            get(context::get_session_context_usage),
+        )
         .route("/api/metrics/runtime", get(metrics::get_runtime_metrics))
+        .route(
+            "/api/system/utilization",
+            get(metrics::get_system_utilization),
+        )
         .route(
             "/api/agent-skills",
             get(skills::list_agent_skills).post(skills::save_agent_skill),
@@ -113,13 +185,102 @@ fn api_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
             "/api/agent-skills/:skill_id",
             get(skills::get_agent_skill).delete(skills::delete_agent_skill),
         )
+        .route(
+            "/api/custom-agents/import",
+            post(custom_agents::import_custom_agent),
+        )
+        .route(
+            "/api/custom-agents/:agent_id/export",
+            get(custom_agents::export_custom_agent),
+        )
+        .route(
+            "/api/custom-agents/:agent_id/stats",
+            get(custom_agents::get_custom_agent_stats),
+        )
+        .route(
+            "/api/custom-agents/exclusive",
+            get(custom_agents::list_exclusive_agents),
+        )
+        .route(
+            "/api/custom-agents/:agent_id/preload",
+            post(custom_agents::preload_exclusive_agent),
+        )
+        .route(
+            "/api/custom-agents/:agent_id/unload",
+            post(custom_agents::unload_exclusive_agent),
+        )
+        .route("/api/evals", get(evals::list_eval_suites))
+        .route("/api/evals/run", post(evals::run_evals))
+        .route("/api/evals/history", get(evals::get_eval_history))
+        .route(
+            "/api/personas",
+            get(personas::list_personas).post(personas::create_persona),
+        )
+        .route(
+            "/api/personas/import",
+            post(personas::import_persona_card)
+                .layer(DefaultBodyLimit::max(CHARACTER_CARD_BODY_LIMIT)),
+        )
+        .route(
+            "/api/personas/:persona_id",
+            get(personas::get_persona)
+                .put(personas::update_persona)
+                .delete(personas::delete_persona),
+        )
         .route("/api/tools", get(tools::list_tools))
+        .route("/api/tools/stats", get(tools::tool_stats))
+        .route("/api/desktop/status", get(desktop::desktop_status))
+        .route("/api/updates/readiness", get(updates::update_readiness))
+        .route(
+            "/api/network",
+            get(network::network_status).patch(network::set_offline),
+        )
+        .route(
+            "/api/network/overrides/:subsystem",
+            patch(network::set_offline_override),
+        )
+        .route("/api/plugins", get(plugins::list_plugins))
+        .route("/api/plugins/reload", post(plugins::reload_plugins))
+        .route(
+            "/api/plugins/:plugin_id/grants",
+            patch(plugins::update_plugin_grants),
+        )
+        .route(
+            "/api/scripts",
+            get(scripts::list_scripts).post(scripts::save_script),
+        )
+        .route(
+            "/api/scripts/:script_id",
+            get(scripts::get_script).delete(scripts::delete_script),
+        )
+        .route(
+            "/api/scripts/:script_id/enable",
+            post(scripts::enable_script),
+        )
+        .route(
+            "/api/scripts/:script_id/disable",
+            post(scripts::disable_script),
+        )
+        .route("/api/profiler/runs", get(profiler::list_runs))
+        .route("/api/profiler/runs/:run_id", get(profiler::get_run))
+        .route("/api/runs/:run_id/plan", post(runs::update_run_plan))
+        .route("/api/profiler/daily", get(profiler::daily))
+        .route("/api/desktop/clipboard", post(desktop::read_clipboard))
+        .route("/api/desktop/screenshot", post(desktop::capture_screenshot))
         .route("/api/memory/compress", post(memory::compress_memories))
         .route(
             "/api/memory/compaction_jobs",
             get(memory::list_compaction_jobs),
         )
         .route("/api/memory/decay", post(memory::run_decay_cycle))
+        .route("/api/maintenance/db", post(maintenance::run_db_maintenance))
+        .route("/api/embeddings", post(embeddings::create_embeddings))
+        .route("/api/llm/providers", get(llm::list_providers))
+        .route("/api/rag/compare-embeddings", post(rag::compare_embeddings))
+        .route(
+            "/api/rag/collections/:collection_id/export.parquet",
+            get(rag::export_collection_parquet),
+        )
         .route("/api/setup/requirements", get(setup::setup_requirements))
         .route(
             "/api/setup/default-models",
@@ -182,6 +343,18 @@ fn api_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
             "/api/setup/models/lmstudio/refresh",
             post(setup::setup_refresh_lmstudio_models),
         )
+        .route(
+            "/api/setup/models/openai_compatible/refresh",
+            post(setup::setup_refresh_openai_compatible_models),
+        )
+        .route(
+            "/api/setup/models/gemini/refresh",
+            post(setup::setup_refresh_gemini_models),
+        )
+        .route(
+            "/api/setup/models/openrouter/refresh",
+            post(setup::setup_refresh_openrouter_models),
+        )
         .route(
             "/api/setup/model/update-check",
             get(setup::setup_model_update_check),
@@ -227,6 +400,10 @@ fn api_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
             state.clone(),
             require_api_key_middleware,
         ))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_trusted_origin_middleware,
+        ))
         .route_layer(middleware::from_fn_with_state(
             state.clone(),
             rate_limit_middleware,
@@ -237,32 +414,67 @@ fn api_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
         ))
 }
 
-fn build_cors_layer(state: &Arc<AppState>) -> CorsLayer {
-    let config = match state.core().config.load_config() {
-        Ok(value) => value,
+fn load_cors_origins(config_service: &ConfigService) -> Vec<String> {
+    match config_service.load_config() {
+        Ok(config) => allowed_origins(&config),
         Err(err) => {
             tracing::warn!(
-                "Failed to load config while building CORS layer: {}; using local defaults",
+                "Failed to load config while building CORS origins: {}; using local defaults",
                 err
             );
-            Value::Null
+            default_local_origins()
         }
-    };
-    let allowed_origins = resolve_allowed_origins(&config)
-        .into_iter()
-        .filter_map(|origin| HeaderValue::from_str(&origin).ok())
-        .collect::<Vec<_>>();
+    }
+}
 
-    let allow_origin = if allowed_origins.is_empty() {
-        AllowOrigin::list(
-            default_local_origins()
-                .into_iter()
-                .filter_map(|origin| HeaderValue::from_str(&origin).ok())
-                .collect::<Vec<_>>(),
-        )
-    } else {
-        AllowOrigin::list(allowed_origins)
-    };
+/// 設定変更を取り込みながら CORS の許可リストを保持する。
+/// `server` セクションが変わったときだけ読み直す。
+struct LiveCorsOrigins {
+    config: ConfigService,
+    origins: RwLock<Vec<String>>,
+    changes: Mutex<broadcast::Receiver<ConfigChangeEvent>>,
+}
+
+impl LiveCorsOrigins {
+    fn new(config: ConfigService) -> Self {
+        Self {
+            origins: RwLock::new(load_cors_origins(&config)),
+            changes: Mutex::new(config.subscribe_changes()),
+            config,
+        }
+    }
+
+    fn refresh_if_changed(&self) {
+        let mut changes = self.changes.lock().unwrap_or_else(|e| e.into_inner());
+        let mut reload = false;
+        loop {
+            match changes.try_recv() {
+                Ok(event) => {
+                    reload |= event.changed_sections.iter().any(|s| s == "server");
+                }
+                Err(broadcast::error::TryRecvError::Lagged(_)) => reload = true,
+                Err(_) => break,
+            }
+        }
+        if reload {
+            *self.origins.write().unwrap_or_else(|e| e.into_inner()) =
+                load_cors_origins(&self.config);
+        }
+    }
+
+    fn allows(&self, origin: &HeaderValue) -> bool {
+        self.refresh_if_changed();
+        let Ok(origin) = origin.to_str() else {
+            return false;
+        };
+        let origins = self.origins.read().unwrap_or_else(|e| e.into_inner());
+        is_origin_allowed(origin, &origins, false)
+    }
+}
+
+fn build_cors_layer(state: &Arc<AppState>) -> CorsLayer {
+    let origins = Arc::new(LiveCorsOrigins::new(state.core().config.clone()));
+    let allow_origin = AllowOrigin::predicate(move |origin, _| origins.allows(origin));
 
     CorsLayer::new()
         .allow_origin(allow_origin)
@@ -280,46 +492,3 @@ fn build_cors_layer(state: &Arc<AppState>) -> CorsLayer {
             header::HeaderName::from_static("x-api-key"),
         ])
 }
-
-fn resolve_allowed_origins(config: &Value) -> Vec<String> {
-    let origins = config
-        .get("server")
-        .and_then(|v| v.as_object())
-        .and_then(|server| {
-            server
-                .get("cors_allowed_origins")
-                .or_else(|| server.get("allowed_origins"))
-                .or_else(|| server.get("ws_allowed_origins"))
-        })
-        .and_then(|value| value.as_array())
-        .map(|list| {
-            list.iter()
-                .filter_map(|item| item.as_str())
-                .map(str::trim)
-                .filter(|item| !item.is_empty())
-                .map(|item| item.to_string())
-                .collect::<Vec<_>>()
-        })
-        .unwrap_or_default();
-
-    if origins.is_empty() {
-        return default_local_origins();
-    }
-
-    origins
-}
-
-fn default_local_origins() -> Vec<String> {
-    vec![
-        "tauri://localhost".to_string(),
-        "https://tauri.localhost".to_string(),
-        "http://tauri.localhost".to_string(),
-        "http://localhost".to_string(),
-        "http://localhost:3000".to_string(),
-        "http://localhost:5173".to_string(),
-        "http://127.0.0.1".to_string(),
-        "http://127.0.0.1:3000".to_string(),
-        "http://127.0.0.1:5173".to_string(),
-        "http://127.0.0.1:8000".to_string(),
-    ]
-}
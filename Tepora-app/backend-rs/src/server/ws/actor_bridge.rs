@@ -6,33 +6,43 @@ use futures_util::SinkExt;
 use serde_json::{json, Value};
 use uuid::Uuid;
 
+use crate::actor::messages::ClientPresence;
 use crate::actor::ActorDispatchError;
 use crate::core::errors::ApiError;
+use crate::infrastructure::observability::latency::LatencyTrace;
 use crate::models::event::{AgentEvent, AgentEventType};
 use crate::state::AppState;
 
 use super::request::GenerationRequest;
+use super::schema::WsServerEvent;
 
 pub async fn route_via_actor_model(
     sender: &mut SplitSink<WebSocket, Message>,
     state: &Arc<AppState>,
     request: &GenerationRequest,
+    trace: Arc<LatencyTrace>,
 ) -> Result<(), ApiError> {
     tracing::info!(
         "Routing message for session {} via Actor Model",
         request.session_id
     );
 
+    let client = ClientPresence::attached();
     let command = crate::actor::messages::SessionCommand::ProcessMessage {
         session_id: request.session_id.clone(),
         message: request.message_text.clone(),
         mode: request.mode.clone(),
         attachments: request.attachments.clone(),
+        artifacts: request.artifacts.clone(),
         search_mode: request.search_mode.clone(),
         thinking_budget: request.thinking_budget,
         agent_id: request.requested_agent_id.clone(),
         agent_mode: request.requested_agent_mode.clone(),
         skip_web_search: request.skip_search,
+        synthesis_mode: request.synthesis_mode.clone(),
+        session_overrides: request.session_overrides.clone(),
+        latency: Some(trace),
+        client: client.clone(),
     };
 
     let mut rx = state.runtime().actor_manager.subscribe();
@@ -54,9 +64,16 @@ pub async fn route_via_actor_model(
                 session_id: ev_session,
                 text,
             } if ev_session == request.session_id => {
-                let _ = send_json_with_raw_payload(
+                send_to_client(
                     sender,
-                    json!({ "type": "chunk", "message": text }),
+                    &client,
+                    WsServerEvent::Chunk {
+                        message: Some(text),
+                        mode: None,
+                        node_id: None,
+                        agent_name: None,
+                    }
+                    .to_value(),
                     request.request_id.as_ref(),
                 )
                 .await;
@@ -65,9 +82,10 @@ pub async fn route_via_actor_model(
                 session_id: ev_session,
                 content,
             } if ev_session == request.session_id => {
-                let _ = send_json_with_raw_payload(
+                send_to_client(
                     sender,
-                    json!({ "type": "thought", "content": content }),
+                    &client,
+                    WsServerEvent::Thought { content }.to_value(),
                     request.request_id.as_ref(),
                 )
                 .await;
@@ -76,9 +94,10 @@ pub async fn route_via_actor_model(
                 session_id: ev_session,
                 message,
             } if ev_session == request.session_id => {
-                let _ = send_json_with_raw_payload(
+                send_to_client(
                     sender,
-                    json!({ "type": "status", "message": message }),
+                    &client,
+                    WsServerEvent::Status { message }.to_value(),
                     request.request_id.as_ref(),
                 )
                 .await;
@@ -88,9 +107,10 @@ pub async fn route_via_actor_model(
                 node_id,
                 output,
             } if ev_session == request.session_id => {
-                let _ = send_json_with_raw_payload(
+                send_to_client(
                     sender,
-                    json!({ "type": "node_completed", "nodeId": node_id, "output": output }),
+                    &client,
+                    WsServerEvent::NodeCompleted { node_id, output }.to_value(),
                     request.request_id.as_ref(),
                 )
                 .await;
@@ -99,9 +119,14 @@ pub async fn route_via_actor_model(
                 session_id: ev_session,
                 status,
             } if ev_session == request.session_id => {
-                let _ = send_json_with_raw_payload(
+                send_to_client(
                     sender,
-                    json!({ "type": "memory_generation", "status": status }),
+                    &client,
+                    WsServerEvent::MemoryGeneration {
+                        status,
+                        session_id: None,
+                    }
+                    .to_value(),
                     request.request_id.as_ref(),
                 )
                 .await;
@@ -110,25 +135,50 @@ pub async fn route_via_actor_model(
                 session_id: ev_session,
                 message,
             } if ev_session == request.session_id => {
-                let _ = send_json_with_raw_payload(
+                send_to_client(
                     sender,
-                    json!({ "type": "error", "message": message }),
+                    &client,
+                    WsServerEvent::Error { message }.to_value(),
+                    request.request_id.as_ref(),
+                )
+                .await;
+            }
+            SessionEvent::ProviderUnavailable {
+                session_id: ev_session,
+                outage,
+            } if ev_session == request.session_id => {
+                send_to_client(
+                    sender,
+                    &client,
+                    WsServerEvent::ProviderUnavailable {
+                        hints: outage.hints(),
+                        provider: outage.provider,
+                        message: outage.message,
+                    }
+                    .to_value(),
                     request.request_id.as_ref(),
                 )
                 .await;
             }
             SessionEvent::GenerationComplete {
                 session_id: ev_session,
+                usage,
             } if ev_session == request.session_id => {
-                let _ = send_json_with_raw_payload(
+                send_to_client(
                     sender,
-                    json!({"type": "done"}),
+                    &client,
+                    WsServerEvent::Done { usage }.to_value(),
                     request.request_id.as_ref(),
                 )
                 .await;
-                let _ = send_json_with_raw_payload(
+                send_to_client(
                     sender,
-                    json!({"type": "interaction_complete", "sessionId": request.session_id}),
+                    &client,
+                    WsServerEvent::InteractionComplete {
+                        session_id: request.session_id.clone(),
+                        run_id: None,
+                    }
+                    .to_value(),
                     request.request_id.as_ref(),
                 )
                 .await;
@@ -141,6 +191,21 @@ pub async fn route_via_actor_model(
     Ok(())
 }
 
+/// 送れなければ接続が切れたとみなし、実行を切り離す。
+async fn send_to_client(
+    sender: &mut SplitSink<WebSocket, Message>,
+    client: &ClientPresence,
+    payload: Value,
+    request_id: Option<&String>,
+) {
+    if send_json_with_raw_payload(sender, payload, request_id)
+        .await
+        .is_err()
+    {
+        client.detach();
+    }
+}
+
 async fn send_json_with_raw_payload(
     sender: &mut SplitSink<WebSocket, Message>,
     mut payload: Value,
@@ -1,69 +1,22 @@
 use axum::http::HeaderMap;
 use serde_json::Value;
 
+use crate::server::middleware::origin::{is_origin_allowed, is_production, ws_allowed_origins};
 use crate::state::AppState;
 
 use super::protocol::WS_TOKEN_PREFIX;
 
+/// WebSocket ハンドシェイクの Origin 検証。ブラウザは WebSocket に CORS を
+/// 適用しないため、ここで弾かないと任意のサイトから接続できてしまう。
 pub fn validate_origin(headers: &HeaderMap, state: &AppState) -> bool {
-    let origin = headers.get("origin").and_then(|v| v.to_str().ok());
-    if let Some(o) = origin {
-        tracing::debug!("Checking Origin: {}", o);
-    } else {
+    let Some(origin) = headers.get("origin").and_then(|v| v.to_str().ok()) else {
         tracing::debug!("No Origin header found");
-    }
-
-    if origin.is_none() {
-        let env = std::env::var("TEPORA_ENV").unwrap_or_else(|_| "production".to_string());
-        return env != "production";
-    }
-
-    let allowed = state
-        .core()
-        .config
-        .load_config()
-        .ok()
-        .and_then(|cfg| {
-            cfg.get("server")
-                .and_then(|server| server.as_object())
-                .and_then(|server| {
-                    server
-                        .get("ws_allowed_origins")
-                        .or_else(|| server.get("cors_allowed_origins"))
-                        .or_else(|| server.get("allowed_origins"))
-                        .cloned()
-                })
-        })
-        .and_then(|list| list.as_array().cloned())
-        .unwrap_or_else(|| {
-            vec![
-                Value::String("tauri://localhost".to_string()),
-                Value::String("https://tauri.localhost".to_string()),
-                Value::String("http://tauri.localhost".to_string()),
-                Value::String("http://localhost".to_string()),
-                Value::String("http://localhost:5173".to_string()),
-                Value::String("http://localhost:3000".to_string()),
-                Value::String("http://127.0.0.1:5173".to_string()),
-                Value::String("http://127.0.0.1:3000".to_string()),
-                Value::String("http://127.0.0.1:8000".to_string()),
-                Value::String("http://127.0.0.1".to_string()),
-            ]
-        });
-
-    let origin = origin.unwrap_or("");
-    for entry in allowed {
-        if let Some(allowed_origin) = entry.as_str() {
-            if origin == allowed_origin || origin.starts_with(&format!("{}/", allowed_origin)) {
-                return true;
-            }
-        }
-    }
+        return !is_production();
+    };
+    tracing::debug!("Checking Origin: {}", origin);
 
-    // 開発環境のみローカルホスト全ポートを許可（production環境では明示的ホワイトリストのみ）
-    let env = std::env::var("TEPORA_ENV").unwrap_or_else(|_| "production".to_string());
-    if env != "production"
-        && (origin.starts_with("http://localhost:") || origin.starts_with("http://127.0.0.1:"))
-    {
+    let config = state.core().config.load_config().unwrap_or(Value::Null);
+    if is_origin_allowed(origin, &ws_allowed_origins(&config), !is_production()) {
         return true;
     }
 
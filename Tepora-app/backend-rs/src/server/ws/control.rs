@@ -2,12 +2,16 @@ use std::sync::Arc;
 
 use serde_json::json;
 
+use crate::context::workers::project_worker::session_defaults_for;
+use crate::core::config::personas::{active_persona_id, Persona};
 use crate::core::errors::ApiError;
 use crate::core::security_controls::{ApprovalDecision, ToolApprovalResponsePayload};
 use crate::state::AppState;
 
 use super::handler::{send_history, send_json, JsonPayloadSink, PendingApprovals};
 use super::protocol::WsIncomingMessage;
+use super::runs::ConnectionRuns;
+use super::schema::WsServerEvent;
 
 pub(super) enum ControlDispatch {
     Handled,
@@ -29,29 +33,8 @@ pub(super) async fn handle_control_message<S: JsonPayloadSink + ?Sized>(
 
     match msg_type {
         "stop" => {
-            send_json(sender, json!({"type": "stopped"})).await?;
-            if state.is_redesign_enabled("actor_model") {
-                let session_id = data
-                    .session_id
-                    .clone()
-                    .unwrap_or_else(|| current_session_id.clone());
-                if !session_id.is_empty() {
-                    if let Err(err) = state
-                        .runtime()
-                        .actor_manager
-                        .dispatch(
-                            &session_id,
-                            state.clone(),
-                            crate::actor::messages::SessionCommand::StopGeneration {
-                                session_id: session_id.clone(),
-                            },
-                        )
-                        .await
-                    {
-                        tracing::warn!("Failed to dispatch stop command for {session_id}: {err}");
-                    }
-                }
-            }
+            // 取り消しは読み取り側のタスクが済ませている（`intercept_control_message`）
+            send_json(sender, WsServerEvent::Stopped.to_value()).await?;
             Ok(ControlDispatch::Handled)
         }
         "get_stats" => {
@@ -113,9 +96,14 @@ pub(super) async fn handle_control_message<S: JsonPayloadSink + ?Sized>(
                     .history
                     .sync_current_project_with_session(current_session_id)
                     .await;
+                let defaults = session_defaults_for(state, current_session_id).await;
                 send_json(
                     sender,
-                    json!({"type": "session_changed", "sessionId": current_session_id}),
+                    WsServerEvent::SessionChanged {
+                        session_id: current_session_id.clone(),
+                        defaults,
+                    }
+                    .to_value(),
                 )
                 .await?;
                 send_history(sender, state, current_session_id).await?;
@@ -124,41 +112,25 @@ pub(super) async fn handle_control_message<S: JsonPayloadSink + ?Sized>(
         }
         "tool_confirmation_response" => {
             if let Some(request_id) = data.request_id.clone() {
-                let approval = normalized_approval(&data);
-                if state.is_redesign_enabled("actor_model") {
-                    let session_id = data
-                        .session_id
-                        .clone()
-                        .unwrap_or_else(|| current_session_id.clone());
-                    if !session_id.is_empty() {
-                        if let Err(err) = state
-                            .runtime()
-                            .actor_manager
-                            .dispatch(
-                                &session_id,
-                                state.clone(),
-                                crate::actor::messages::SessionCommand::ToolApprovalResponse {
-                                    session_id: session_id.clone(),
-                                    request_id,
-                                    approval,
-                                },
-                            )
-                            .await
-                        {
-                            tracing::warn!(
-                                "Failed to dispatch tool approval response for {session_id}: {err}"
-                            );
-                        }
-                    }
-                } else {
-                    let mut map = pending.lock().await;
-                    if let Some(reply_to) = map.remove(&request_id) {
-                        let _ = reply_to.send(approval);
-                    }
-                }
+                let session_id = data
+                    .session_id
+                    .clone()
+                    .unwrap_or_else(|| current_session_id.clone());
+                deliver_tool_approval(
+                    state,
+                    &pending,
+                    &[session_id],
+                    request_id,
+                    normalized_approval(&data),
+                )
+                .await;
             }
             Ok(ControlDispatch::Handled)
         }
+        "switch_persona" => {
+            handle_switch_persona(sender, state, current_session_id.as_str(), data).await
+        }
+        "switch_project" => handle_switch_project(sender, state, current_session_id, data).await,
         "regenerate" => handle_regenerate(sender, state, current_session_id.as_str(), data).await,
         _ => Ok(ControlDispatch::Forward {
             data: Box::new(data),
@@ -167,6 +139,98 @@ pub(super) async fn handle_control_message<S: JsonPayloadSink + ?Sized>(
     }
 }
 
+/// メインループは生成が終わるまで次のメッセージを読まないので、生成中に効かなければ
+/// ならないものは読み取り側のタスクでここを通す。`stop` は実行を取り消したうえで、
+/// `stopped` を返すためにメインループへも回す。承認を渡し終えたら true を返す。
+pub(super) async fn intercept_control_message(
+    state: &Arc<AppState>,
+    runs: &ConnectionRuns,
+    pending: &PendingApprovals,
+    data: &WsIncomingMessage,
+) -> bool {
+    match data.msg_type.as_deref() {
+        Some("stop") => {
+            let stopped = runs.cancel(data.session_id.as_deref());
+            if state.is_redesign_enabled("actor_model") {
+                let sessions = data.session_id.clone().map_or(stopped, |id| vec![id]);
+                for session_id in sessions {
+                    if let Err(err) = state
+                        .runtime()
+                        .actor_manager
+                        .dispatch(
+                            &session_id,
+                            state.clone(),
+                            crate::actor::messages::SessionCommand::StopGeneration {
+                                session_id: session_id.clone(),
+                            },
+                        )
+                        .await
+                    {
+                        tracing::warn!("Failed to dispatch stop command for {session_id}: {err}");
+                    }
+                }
+            }
+            false
+        }
+        Some("tool_confirmation_response") => {
+            let Some(request_id) = data.request_id.clone() else {
+                return false;
+            };
+            let sessions = match data.session_id.clone() {
+                Some(session_id) => vec![session_id],
+                None => runs.sessions(),
+            };
+            // アクターに渡すにはセッションが要る。分からなければメインループに任せる
+            if sessions.is_empty() && state.is_redesign_enabled("actor_model") {
+                return false;
+            }
+            deliver_tool_approval(
+                state,
+                pending,
+                &sessions,
+                request_id,
+                normalized_approval(data),
+            )
+            .await;
+            true
+        }
+        _ => false,
+    }
+}
+
+async fn deliver_tool_approval(
+    state: &Arc<AppState>,
+    pending: &PendingApprovals,
+    session_ids: &[String],
+    request_id: String,
+    approval: ToolApprovalResponsePayload,
+) {
+    if !state.is_redesign_enabled("actor_model") {
+        if let Some(reply_to) = pending.lock().await.remove(&request_id) {
+            let _ = reply_to.send(approval);
+        }
+        return;
+    }
+    for session_id in session_ids.iter().filter(|id| !id.is_empty()) {
+        if let Err(err) = state
+            .runtime()
+            .actor_manager
+            .dispatch(
+                session_id,
+                state.clone(),
+                crate::actor::messages::SessionCommand::ToolApprovalResponse {
+                    session_id: session_id.clone(),
+                    request_id: request_id.clone(),
+                    approval: approval.clone(),
+                },
+            )
+            .await
+        {
+            tracing::warn!("Failed to dispatch tool approval response for {session_id}: {err}");
+        }
+    }
+}
+
 fn normalized_approval(data: &WsIncomingMessage) -> ToolApprovalResponsePayload {
     if data.approval.approved.is_some()
         || !matches!(data.approval.decision, ApprovalDecision::Once)
@@ -184,6 +248,122 @@ fn normalized_approval(data: &WsIncomingMessage) -> ToolApprovalResponsePayload
     }
 }
 
+async fn handle_switch_persona<S: JsonPayloadSink + ?Sized>(
+    sender: &mut S,
+    state: &Arc<AppState>,
+    current_session_id: &str,
+    data: WsIncomingMessage,
+) -> Result<ControlDispatch, ApiError> {
+    let session_id = data
+        .session_id
+        .clone()
+        .unwrap_or_else(|| current_session_id.to_owned());
+    if session_id.is_empty() {
+        return Err(ApiError::BadRequest(
+            "switch_persona requires an active session".to_string(),
+        ));
+    }
+
+    // personaId を省略するとセッション固定を解除して active_character に戻る
+    let requested = data
+        .persona_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+    let persona =
+        match requested {
+            Some(persona_id) => Some(state.core().config.get_persona(persona_id)?.ok_or_else(
+                || ApiError::NotFound(format!("Persona '{}' not found", persona_id)),
+            )?),
+            None => None,
+        };
+
+    state
+        .runtime()
+        .history
+        .set_session_persona(&session_id, persona.as_ref().map(|p| p.id.as_str()))
+        .await?;
+
+    let persona = match persona {
+        Some(persona) => persona,
+        None => {
+            let config = state.core().config.load_config()?;
+            let active_id = active_persona_id(&config).to_string();
+            state
+                .core()
+                .config
+                .get_persona(&active_id)?
+                .unwrap_or(Persona {
+                    id: active_id.clone(),
+                    name: active_id,
+                    ..Persona::default()
+                })
+        }
+    };
+
+    send_json(
+        sender,
+        WsServerEvent::PersonaChanged {
+            session_id,
+            persona_id: persona.id,
+            name: persona.name,
+            greeting: persona.greeting,
+        }
+        .to_value(),
+    )
+    .await?;
+    Ok(ControlDispatch::Handled)
+}
+
+/// Make `projectId` the current project. The socket moves to the project's
+/// most recent session, or a fresh one when the project has none, so the next
+/// turn cannot pick up another project's context.
+async fn handle_switch_project<S: JsonPayloadSink + ?Sized>(
+    sender: &mut S,
+    state: &Arc<AppState>,
+    current_session_id: &mut String,
+    data: WsIncomingMessage,
+) -> Result<ControlDispatch, ApiError> {
+    let project_id = data
+        .project_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| ApiError::BadRequest("switch_project requires projectId".to_string()))?;
+    let manager = &state.workspace().manager;
+    if !manager.has_project(project_id) {
+        return Err(ApiError::NotFound(format!(
+            "Project '{}' not found",
+            project_id
+        )));
+    }
+    manager.set_current_project(project_id).await?;
+
+    let history = &state.runtime().history;
+    let sessions = history.list_project_sessions(project_id).await?;
+    *current_session_id = match sessions.first() {
+        Some(session) => session.id.clone(),
+        None => history.create_session(None).await?,
+    };
+    let settings = history.get_project_settings(project_id).await?;
+    let defaults = session_defaults_for(state, current_session_id).await;
+
+    send_json(
+        sender,
+        WsServerEvent::ProjectChanged {
+            project_id: project_id.to_string(),
+            session_id: current_session_id.clone(),
+            settings: json!(settings),
+            sessions: json!(sessions),
+            defaults,
+        }
+        .to_value(),
+    )
+    .await?;
+    send_history(sender, state, current_session_id).await?;
+    Ok(ControlDispatch::Handled)
+}
+
 async fn handle_regenerate<S: JsonPayloadSink + ?Sized>(
     sender: &mut S,
     state: &Arc<AppState>,
@@ -198,7 +378,7 @@ async fn handle_regenerate<S: JsonPayloadSink + ?Sized>(
         return Ok(ControlDispatch::Handled);
     }
 
-    let _ = send_json(sender, json!({"type": "regenerate_started"})).await;
+    let _ = send_json(sender, WsServerEvent::RegenerateStarted.to_value()).await;
 
     let last_user_message = state
         .runtime()
@@ -241,6 +421,13 @@ async fn handle_regenerate<S: JsonPayloadSink + ?Sized>(
             if let Some(search_mode) = kwargs.get("search_mode").and_then(|v| v.as_str()) {
                 new_data.search_mode = Some(search_mode.to_string());
             }
+            if let Some(synthesis_mode) = kwargs.get("synthesis_mode").and_then(|v| v.as_str()) {
+                new_data.synthesis_mode = Some(synthesis_mode.to_string());
+            }
+            new_data.temperature = kwargs.get("temperature").and_then(|v| v.as_f64());
+            new_data.rag_collections = kwargs
+                .get("rag_collections")
+                .and_then(|v| serde_json::from_value(v.clone()).ok());
         }
 
         new_data.msg_type = None;
@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::Mutex;
 
 use axum::extract::ws::{Message, WebSocket};
@@ -10,18 +11,34 @@ use futures_util::future::BoxFuture;
 use futures_util::stream::SplitSink;
 use futures_util::{SinkExt, StreamExt};
 use serde_json::{json, Value};
-
+use tokio::sync::broadcast;
+
+use crate::a2a::{store_incoming, CLIENT_SOURCE};
+use crate::context::workers::persona_worker::apply_session_persona;
+use crate::context::workers::project_worker::{
+    apply_session_project, apply_session_settings, session_defaults_for,
+};
+use crate::core::chat_queue::{ChatTicket, QueueLimits, QueueStep};
 use crate::core::errors::ApiError;
 use crate::core::security_controls::ToolApprovalResponsePayload;
+use crate::core::utilization::UtilizationSnapshot;
+use crate::graph::sanitize::ChunkSanitizer;
+use crate::graph::sentences::SentenceSegmenter;
+use crate::graph::state::SynthesisMode;
 use crate::graph::{AgentState, NodeContext};
+use crate::infrastructure::observability::latency::{self, LatencyTrace};
+use crate::llm::cancel as llm_cancel;
+use crate::state::utilization::{sample_utilization, subscribe_utilization};
 use crate::state::{AppState, AppStateWrite};
 
 use super::actor_bridge::route_via_actor_model;
 use super::auth::{validate_origin, validate_token};
-use super::control::{handle_control_message, ControlDispatch};
+use super::control::{handle_control_message, intercept_control_message, ControlDispatch};
 use super::protocol::{WsIncomingMessage, WS_APP_PROTOCOL};
-use super::request::build_generation_request;
-use super::session::{build_history_payload, persist_graph_interaction};
+use super::request::{build_generation_request, GenerationRequest};
+use super::runs::ConnectionRuns;
+use super::schema::WsServerEvent;
+use super::session::{assistant_kwargs, build_history_payload, persist_graph_interaction};
 
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
@@ -46,37 +63,106 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     tracing::info!("WebSocket connection upgraded");
     let (mut sender, mut receiver) = socket.split();
 
-    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<WsIncomingMessage>();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<(Instant, WsIncomingMessage)>();
     let pending = Arc::new(Mutex::new(HashMap::<
         String,
         tokio::sync::oneshot::Sender<ToolApprovalResponsePayload>,
     >::new()));
-
+    // このソケットが始めたグラフ実行の持ち主 ID
+    let connection_id = uuid::Uuid::new_v4().to_string();
+    let runs = ConnectionRuns::default();
+
+    let reader_state = state.clone();
+    let reader_pending = pending.clone();
+    let reader_connection_id = connection_id.clone();
+    let reader_runs = runs.clone();
     tokio::spawn(async move {
         while let Some(Ok(msg)) = receiver.next().await {
             match msg {
                 Message::Text(text) => {
                     if let Ok(incoming) = serde_json::from_str::<WsIncomingMessage>(&text) {
-                        let _ = tx.send(incoming);
+                        if intercept_control_message(
+                            &reader_state,
+                            &reader_runs,
+                            &reader_pending,
+                            &incoming,
+                        )
+                        .await
+                        {
+                            continue;
+                        }
+                        let _ = tx.send((Instant::now(), incoming));
                     }
                 }
                 Message::Close(_) => break,
                 _ => {}
             }
         }
+        // 実行中に切断されたら、その実行と承認待ちを片付ける。
+        // 送信側を落とすと承認待ちのツールはすぐ拒否扱いで戻る
+        let aborted = reader_state
+            .runtime()
+            .graph_runtime
+            .abort_connection_runs(&reader_connection_id);
+        reader_pending.lock().await.clear();
+        if aborted > 0 {
+            tracing::info!(aborted, "WebSocket closed mid-run; aborted its graph runs");
+        }
     });
 
     let mut current_session_id = "default".to_string();
     let approved_mcp_tools = Arc::new(Mutex::new(HashSet::<String>::new()));
 
+    let mut slot_events = state.ai().llama.subscribe_slot_events();
+    let mut slot_events_open = true;
+    let mut config_changes = state.core().config.subscribe_changes();
+    let mut config_changes_open = true;
+    let mut notifications = state.core().notifications.subscribe();
+    let mut notifications_open = true;
+    let mut inbox_updates = state.runtime().inbox.subscribe();
+    let mut inbox_updates_open = true;
+    let mut health_changes = state.core().health.subscribe();
+    let mut health_changes_open = true;
+    // 使用率はクライアントが `subscribe_utilization` を送ったときだけ流す
+    let mut utilization: Option<broadcast::Receiver<UtilizationSnapshot>> = None;
+
+    // バッジの初期値。以降は inbox イベントで更新する
+    if let Ok(unread) = state.runtime().inbox.unread_count().await {
+        let _ = send_json(
+            &mut sender,
+            json!({"type": "inbox", "data": {"unread": unread}}),
+        )
+        .await;
+    }
+
     let mut heartbeat_interval = tokio::time::interval(std::time::Duration::from_secs(10));
     heartbeat_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
     loop {
         tokio::select! {
-            Some(incoming) = rx.recv() => {
+            Some((received_at, incoming)) = rx.recv() => {
                 use tracing::Instrument;
 
+                match incoming.msg_type.as_deref() {
+                    Some("subscribe_utilization") => {
+                        if utilization.is_none() {
+                            utilization = Some(subscribe_utilization(&state));
+                        }
+                        let snapshot = sample_utilization(&state).await;
+                        let _ = send_json(
+                            &mut sender,
+                            json!({"type": "utilization", "data": snapshot}),
+                        )
+                        .await;
+                        continue;
+                    }
+                    Some("unsubscribe_utilization") => {
+                        utilization = None;
+                        continue;
+                    }
+                    _ => {}
+                }
+
                 let request_id = uuid::Uuid::new_v4().to_string();
                 let span = tracing::info_span!(
                     "ws_message",
@@ -88,9 +174,12 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                     &mut sender,
                     &state,
                     &mut current_session_id,
+                    &connection_id,
+                    &runs,
                     pending.clone(),
                     approved_mcp_tools.clone(),
                     incoming,
+                    received_at,
                 )
                 .instrument(span)
                 .await
@@ -102,6 +191,108 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
                     .await;
                 }
             }
+            slot_event = slot_events.recv(), if slot_events_open => {
+                match slot_event {
+                    Ok(event) => {
+                        let _ = send_json(
+                            &mut sender,
+                            json!({"type": "model_slot", "data": event}),
+                        )
+                        .await;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!(skipped, "WebSocket lagged behind model slot events");
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        slot_events_open = false;
+                    }
+                }
+            }
+            config_change = config_changes.recv(), if config_changes_open => {
+                match config_change {
+                    Ok(event) => {
+                        let _ = send_json(
+                            &mut sender,
+                            json!({"type": "config_changed", "data": event}),
+                        )
+                        .await;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!(skipped, "WebSocket lagged behind config change events");
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        config_changes_open = false;
+                    }
+                }
+            }
+            notification = notifications.recv(), if notifications_open => {
+                match notification {
+                    Ok(event) => {
+                        let _ = send_json(
+                            &mut sender,
+                            json!({"type": "notification", "data": event}),
+                        )
+                        .await;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!(skipped, "WebSocket lagged behind notifications");
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        notifications_open = false;
+                    }
+                }
+            }
+            inbox_update = inbox_updates.recv(), if inbox_updates_open => {
+                match inbox_update {
+                    Ok(event) => {
+                        let _ = send_json(
+                            &mut sender,
+                            json!({"type": "inbox", "data": event}),
+                        )
+                        .await;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!(skipped, "WebSocket lagged behind inbox updates");
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        inbox_updates_open = false;
+                    }
+                }
+            }
+            health_change = health_changes.recv(), if health_changes_open => {
+                match health_change {
+                    Ok(event) => {
+                        let _ = send_json(
+                            &mut sender,
+                            json!({"type": "health", "data": event}),
+                        )
+                        .await;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!(skipped, "WebSocket lagged behind health changes");
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        health_changes_open = false;
+                    }
+                }
+            }
+            snapshot = recv_utilization(&mut utilization), if utilization.is_some() => {
+                match snapshot {
+                    Ok(snapshot) => {
+                        let _ = send_json(
+                            &mut sender,
+                            json!({"type": "utilization", "data": snapshot}),
+                        )
+                        .await;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!(skipped, "WebSocket lagged behind utilization samples");
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        utilization = None;
+                    }
+                }
+            }
             _ = heartbeat_interval.tick() => {
                 if sender.send(Message::Ping(vec![])).await.is_err() {
                      tracing::warn!("Failed to send heartbeat, closing connection");
@@ -124,6 +315,63 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     tracing::info!("WebSocket connection closed");
 }
 
+async fn recv_utilization(
+    receiver: &mut Option<broadcast::Receiver<UtilizationSnapshot>>,
+) -> Result<UtilizationSnapshot, broadcast::error::RecvError> {
+    match receiver {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn wait_for_generation_slot(
+    sender: &mut SplitSink<WebSocket, Message>,
+    state: &Arc<AppState>,
+    request: &GenerationRequest,
+) -> Result<ChatTicket, ApiError> {
+    let limits = state
+        .core()
+        .config
+        .load_typed()
+        .map(|typed| QueueLimits::from(&typed.app))
+        .unwrap_or_default();
+    let mut queued = state
+        .runtime()
+        .chat_queue
+        .enqueue(&request.session_id, limits)?;
+    loop {
+        match queued.next().await {
+            QueueStep::Waiting(position) => {
+                let _ = send_json(
+                    sender,
+                    WsServerEvent::QueuePosition {
+                        session_id: request.session_id.clone(),
+                        request_id: request.request_id.clone(),
+                        data: json!(position),
+                    }
+                    .to_value(),
+                )
+                .await;
+            }
+            QueueStep::Ready(ticket) => {
+                if queued.was_queued() {
+                    let _ = send_json(
+                        sender,
+                        WsServerEvent::QueuePosition {
+                            session_id: request.session_id.clone(),
+                            request_id: request.request_id.clone(),
+                            data: json!({"scope": "started", "position": 0}),
+                        }
+                        .to_value(),
+                    )
+                    .await;
+                }
+                return Ok(ticket);
+            }
+        }
+    }
+}
+
 pub(super) type PendingApprovals =
     Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<ToolApprovalResponsePayload>>>>;
 
@@ -151,13 +399,17 @@ impl JsonPayloadSink for SplitSink<WebSocket, Message> {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_message<S: JsonPayloadSink + ?Sized>(
     sender: &mut S,
     state: &Arc<AppState>,
     current_session_id: &mut String,
+    connection_id: &str,
+    runs: &ConnectionRuns,
     pending: PendingApprovals,
     approved_mcp_tools: Arc<Mutex<HashSet<String>>>,
     data: WsIncomingMessage,
+    received_at: Instant,
 ) -> Result<(), ApiError> {
     let control = handle_control_message(
         sender,
@@ -187,30 +439,78 @@ async fn handle_message<S: JsonPayloadSink + ?Sized>(
         websocket_sender,
         state,
         current_session_id,
+        connection_id,
+        runs,
         pending,
         approved_mcp_tools,
         data,
         is_regenerate,
+        received_at,
     )
     .await
 }
 
-#[allow(clippy::ptr_arg)]
+#[allow(clippy::ptr_arg, clippy::too_many_arguments)]
 async fn handle_message_internal(
     sender: &mut SplitSink<WebSocket, Message>,
     state: &Arc<AppState>,
     current_session_id: &mut String,
+    connection_id: &str,
+    runs: &ConnectionRuns,
     pending: Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<ToolApprovalResponsePayload>>>>,
     approved_mcp_tools: Arc<Mutex<HashSet<String>>>,
     data: WsIncomingMessage,
     is_regenerate: bool,
+    received_at: Instant,
 ) -> Result<(), ApiError> {
-    let request = build_generation_request(state, current_session_id, data)?;
+    let target_session_id = data
+        .session_id
+        .clone()
+        .unwrap_or_else(|| current_session_id.clone());
+    let defaults = session_defaults_for(state, &target_session_id).await;
+    let mut request = build_generation_request(state, current_session_id, data, &defaults)?;
     if request.message_text.is_empty() && request.attachments.is_empty() {
         return Ok(());
     }
+    let trace = LatencyTrace::new(
+        request
+            .request_id
+            .clone()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        request.session_id.clone(),
+        received_at,
+    );
+    // 同じセッションの先行メッセージが終わるまで待つ。履歴への書き込みもその後にする
+    let _ticket = wait_for_generation_slot(sender, state, &request).await?;
 
-    let config = state.core().config.load_config()?;
+    // 再生成では保存済みのメッセージをそのまま使う
+    if !is_regenerate {
+        if let Some(rewritten) = state
+            .integration()
+            .scripts
+            .on_message_received(state, &request.session_id, &request.message_text)
+            .await
+        {
+            request.message_text = rewritten;
+        }
+    }
+
+    let mut config = state.core().config.load_config()?;
+    let project = apply_session_project(state, &request.session_id, &mut config).await;
+    apply_session_settings(&mut config, &request.session_overrides);
+    let persona_id = apply_session_persona(state, &request.session_id, &mut config).await;
+    if let Some(kwargs) = request.user_kwargs.as_object_mut() {
+        kwargs.insert("persona_id".to_string(), json!(persona_id));
+    }
+    request.persona_id = Some(persona_id);
+    if request.requested_agent_id.is_none() {
+        if let Some(agent_id) = project.and_then(|project| project.default_agent_id) {
+            if let Some(kwargs) = request.user_kwargs.as_object_mut() {
+                kwargs.insert("agent_id".to_string(), json!(agent_id));
+            }
+            request.requested_agent_id = Some(agent_id);
+        }
+    }
 
     if !is_regenerate {
         state
@@ -230,10 +530,20 @@ async fn handle_message_internal(
             .await;
     }
 
+    // ここから先は読み取り側のタスクに届いた `stop` で取り消せる
+    let active_run = runs.start(&request.session_id);
+    let cancel = active_run.token();
+
     if state.is_redesign_enabled("actor_model") {
-        route_via_actor_model(sender, state, &request).await?;
+        // キュー待ちはアクターが処理を始めた時点で締める。止めたらアクターは
+        // 完了を送らないので、待つのをやめる
+        cancel
+            .run_until_cancelled(route_via_actor_model(sender, state, &request, trace))
+            .await
+            .transpose()?;
         return Ok(());
     }
+    trace.mark_started();
 
     let mut graph_state = AgentState::from_ws_message(
         request.session_id.clone(),
@@ -247,10 +557,24 @@ async fn handle_message_internal(
         request.attachments.clone(),
         Vec::new(),
     );
+    graph_state.synthesis_mode =
+        SynthesisMode::from_optional_str(request.synthesis_mode.as_deref());
+    store_incoming(
+        &mut graph_state.shared_context,
+        CLIENT_SOURCE,
+        &request.artifacts,
+    );
 
+    let partial = state
+        .runtime()
+        .history
+        .partial_message(&request.session_id, assistant_kwargs(&request));
     let mut graph_streamer = crate::graph::stream::GraphStreamer::WebSocket {
         ws: sender,
         request_id: request.request_id.clone(),
+        partial: Some(partial.clone()),
+        sentences: SentenceSegmenter::for_config(&config),
+        sanitizer: ChunkSanitizer::default(),
     };
 
     let mut node_ctx = NodeContext {
@@ -261,34 +585,52 @@ async fn handle_message_internal(
         approved_mcp_tools,
     };
 
+    let run = latency::scoped(
+        trace.clone(),
+        state.runtime().graph_runtime.run_for_connection(
+            &mut graph_state,
+            &mut node_ctx,
+            request.timeout_override,
+            connection_id,
+        ),
+    );
+    let run_result = llm_cancel::scoped(cancel.clone(), cancel.run_until_cancelled(run)).await;
     state
         .runtime()
-        .graph_runtime
-        .run(&mut graph_state, &mut node_ctx, request.timeout_override)
-        .await
-        .map_err(ApiError::from)?;
+        .actor_manager
+        .record_latency(&trace, &config);
+    let Some(run_result) = run_result else {
+        // `stop` で止めた。そこまでの出力は未完了のまま残し、`stopped` はメインループが返す
+        let _ = partial.flush().await;
+        return Ok(());
+    };
+    if let Err(err) = run_result {
+        // 失敗しても、そこまでの出力は未完了のまま履歴に残す
+        let _ = partial.flush().await;
+        return Err(ApiError::from(err));
+    }
 
     let assistant_output = graph_state.output.clone().unwrap_or_default();
 
     let _ = send_json(
         sender,
-        json!({
-            "type": "memory_generation",
-            "status": "started",
-            "sessionId": request.session_id,
-        }),
+        WsServerEvent::MemoryGeneration {
+            status: "started".to_string(),
+            session_id: Some(request.session_id.clone()),
+        }
+        .to_value(),
     )
     .await;
 
-    persist_graph_interaction(state, &request, &assistant_output).await?;
+    persist_graph_interaction(state, &request, &assistant_output, &partial).await?;
 
     let _ = send_json(
         sender,
-        json!({
-            "type": "memory_generation",
-            "status": "completed",
-            "sessionId": request.session_id,
-        }),
+        WsServerEvent::MemoryGeneration {
+            status: "completed".to_string(),
+            session_id: Some(request.session_id.clone()),
+        }
+        .to_value(),
     )
     .await;
 
@@ -296,10 +638,11 @@ async fn handle_message_internal(
     // This allows the frontend to refresh its session list and see the updated message count.
     let _ = send_json(
         sender,
-        json!({
-            "type": "interaction_complete",
-            "sessionId": request.session_id,
-        }),
+        WsServerEvent::InteractionComplete {
+            session_id: request.session_id.clone(),
+            run_id: graph_state.run_id.clone(),
+        }
+        .to_value(),
     )
     .await;
 
@@ -333,49 +676,10 @@ fn is_perf_probe_enabled() -> bool {
 mod tests {
     use super::*;
 
-    use crate::test_support::ENV_LOCK;
-    use std::env;
-    use std::fs;
-    use std::path::Path;
+    use crate::test_support::{init_state_with_config, serve_router, EnvGuard, ENV_LOCK};
 
     use serde_json::json;
-    use tempfile::{tempdir, TempDir};
-
-    struct EnvGuard {
-        originals: Vec<(String, Option<String>)>,
-    }
-
-    impl EnvGuard {
-        fn new() -> Self {
-            Self {
-                originals: Vec::new(),
-            }
-        }
-
-        fn set_var(&mut self, key: &str, value: impl AsRef<str>) {
-            let key_string = key.to_string();
-            if !self
-                .originals
-                .iter()
-                .any(|(existing, _)| existing == &key_string)
-            {
-                self.originals
-                    .push((key_string.clone(), env::var(&key_string).ok()));
-            }
-            env::set_var(key, value.as_ref());
-        }
-    }
-
-    impl Drop for EnvGuard {
-        fn drop(&mut self) {
-            for (key, previous) in self.originals.iter().rev() {
-                match previous {
-                    Some(value) => env::set_var(key, value),
-                    None => env::remove_var(key),
-                }
-            }
-        }
-    }
+    use tempfile::TempDir;
 
     #[derive(Default)]
     struct ReplaySink {
@@ -392,39 +696,10 @@ mod tests {
     }
 
     async fn init_replay_state() -> (TempDir, EnvGuard, Arc<AppState>) {
-        let sandbox = tempdir().expect("failed to create tempdir");
-        let project_root = sandbox.path().join("project");
-        let data_dir = sandbox.path().join("data");
-        fs::create_dir_all(&project_root).expect("failed to create project root");
-        fs::create_dir_all(&data_dir).expect("failed to create data dir");
-
-        let config_path = project_root.join("config.yml");
-        fs::write(
-            &config_path,
-            "features:\n  redesign:\n    actor_model: false\n",
-        )
-        .expect("failed to write config");
-
-        let mut env_guard = EnvGuard::new();
-        configure_replay_environment(&mut env_guard, &project_root, &data_dir, &config_path);
-
-        let state = AppState::initialize()
-            .await
-            .expect("AppState should initialize for ws replay tests");
-
-        (sandbox, env_guard, state)
-    }
-
-    fn configure_replay_environment(
-        env_guard: &mut EnvGuard,
-        project_root: &Path,
-        data_dir: &Path,
-        config_path: &Path,
-    ) {
-        env_guard.set_var("TEPORA_ROOT", project_root.to_string_lossy());
-        env_guard.set_var("TEPORA_DATA_DIR", data_dir.to_string_lossy());
-        env_guard.set_var("TEPORA_CONFIG_PATH", config_path.to_string_lossy());
+        let (sandbox, mut env_guard, state) =
+            init_state_with_config("features:\n  redesign:\n    actor_model: false\n").await;
         env_guard.set_var("TEPORA_PERF_PROBE_ENABLED", "1");
+        (sandbox, env_guard, state)
     }
 
     async fn replay_session_messages(
@@ -444,9 +719,12 @@ mod tests {
                 &mut sink,
                 &state,
                 &mut current_session_id,
+                "replay",
+                &ConnectionRuns::default(),
                 pending.clone(),
                 approved_mcp_tools.clone(),
                 message,
+                Instant::now(),
             )
             .await?;
         }
@@ -499,7 +777,16 @@ mod tests {
             .expect("second replay should succeed");
 
         let expected = vec![
-            json!({"type": "session_changed", "sessionId": session_id}),
+            json!({
+                "type": "session_changed",
+                "sessionId": session_id,
+                "defaults": {
+                    "mode": null,
+                    "persona_id": null,
+                    "rag_collections": [],
+                    "temperature": null
+                }
+            }),
             json!({
                 "type": "history",
                 "messages": [{
@@ -518,6 +805,11 @@ mod tests {
 
         assert_eq!(first, expected);
         assert_eq!(second, expected);
+        // 送ったイベントはすべて公開スキーマの型に収まる
+        for payload in first {
+            serde_json::from_value::<WsServerEvent>(payload.clone())
+                .unwrap_or_else(|err| panic!("{payload} does not match WsServerEvent: {err}"));
+        }
     }
 
     #[tokio::test]
@@ -572,4 +864,184 @@ mod tests {
 
         assert_eq!(ids, vec![first_id.to_string(), second_id.to_string()]);
     }
+
+    /// `/v1/models` に 1 件返し、ストリームは最初のチャンクを送ったあと切断される
+    /// まで黙っている OpenAI 互換の上流。ストリームの接続が切れたら `closed` に知らせる。
+    async fn spawn_stalling_upstream() -> (String, tokio::sync::oneshot::Receiver<()>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let (closed_tx, closed_rx) = tokio::sync::oneshot::channel();
+        let closed_tx = Arc::new(std::sync::Mutex::new(Some(closed_tx)));
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let closed_tx = closed_tx.clone();
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 4096];
+                    // ヘッダーと本文がそろうまで読む
+                    loop {
+                        let read = socket.read(&mut buf).await.unwrap_or(0);
+                        if read == 0 {
+                            return;
+                        }
+                        request.extend_from_slice(&buf[..read]);
+                        let text = String::from_utf8_lossy(&request);
+                        if let Some(end) = text.find("\r\n\r\n") {
+                            let length = text[..end]
+                                .lines()
+                                .find_map(|line| {
+                                    let (name, value) = line.split_once(':')?;
+                                    name.eq_ignore_ascii_case("content-length")
+                                        .then(|| value.trim().parse::<usize>().ok())
+                                        .flatten()
+                                })
+                                .unwrap_or(0);
+                            if request.len() >= end + 4 + length {
+                                break;
+                            }
+                        }
+                    }
+                    let request = String::from_utf8_lossy(&request).to_string();
+                    if request.starts_with("GET") {
+                        let body = r#"{"data":[{"id":"stall"}]}"#;
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                            body.len(),
+                            body
+                        );
+                        let _ = socket.write_all(response.as_bytes()).await;
+                        return;
+                    }
+                    if !request.contains(r#""stream":true"#) {
+                        let body = r#"{"choices":[{"message":{"role":"assistant","content":"ok"},"finish_reason":"stop"}]}"#;
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                            body.len(),
+                            body
+                        );
+                        let _ = socket.write_all(response.as_bytes()).await;
+                        return;
+                    }
+                    let event = "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n";
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n{:x}\r\n{}\r\n",
+                        event.len(),
+                        event
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                    while socket.read(&mut buf).await.unwrap_or(0) > 0 {}
+                    if let Some(closed) = closed_tx.lock().unwrap().take() {
+                        let _ = closed.send(());
+                    }
+                });
+            }
+        });
+        (base_url, closed_rx)
+    }
+
+    async fn next_client_event<S>(socket: &mut S) -> Value
+    where
+        S: futures_util::Stream<
+                Item = Result<
+                    tokio_tungstenite::tungstenite::Message,
+                    tokio_tungstenite::tungstenite::Error,
+                >,
+            > + Unpin,
+    {
+        loop {
+            let message = tokio::time::timeout(std::time::Duration::from_secs(30), socket.next())
+                .await
+                .expect("timed out waiting for a WebSocket event")
+                .expect("WebSocket closed")
+                .unwrap();
+            if let tokio_tungstenite::tungstenite::Message::Text(text) = message {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn ws_stop_mid_stream_closes_the_upstream_connection() {
+        use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+        use tokio_tungstenite::tungstenite::Message as ClientMessage;
+
+        let _lock = ENV_LOCK.lock();
+        let (base_url, upstream_closed) = spawn_stalling_upstream().await;
+        let (_sandbox, _env_guard, state) = init_state_with_config(&format!(
+            "features:\n  redesign:\n    actor_model: false\nloaders:\n  openai_compatible:\n    base_url: {}\n",
+            base_url
+        ))
+        .await;
+        assert!(
+            state
+                .ai()
+                .models
+                .refresh_openai_compatible_models()
+                .await
+                .unwrap()
+                > 0
+        );
+        state
+            .ai()
+            .models
+            .set_assignment_model("character", "openai_compatible-stall")
+            .unwrap();
+
+        let address = serve_router(state.clone()).await;
+
+        let token = state.core().session_token.read().await.value().to_string();
+        let mut request = format!("ws://{}/ws", address)
+            .into_client_request()
+            .unwrap();
+        request.headers_mut().insert(
+            "sec-websocket-protocol",
+            format!(
+                "{}, {}{}",
+                WS_APP_PROTOCOL,
+                super::super::protocol::WS_TOKEN_PREFIX,
+                hex::encode(token)
+            )
+            .parse()
+            .unwrap(),
+        );
+        request
+            .headers_mut()
+            .insert("origin", "tauri://localhost".parse().unwrap());
+        let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+
+        let send = |payload: Value| ClientMessage::Text(payload.to_string().into());
+        socket
+            .send(send(json!({
+                "message": "Hello",
+                "mode": "chat",
+                "sessionId": "stop-session",
+                "skipWebSearch": true
+            })))
+            .await
+            .unwrap();
+
+        loop {
+            let event = next_client_event(&mut socket).await;
+            assert_ne!(event["type"], "error", "{event}");
+            if event["type"] == "chunk" {
+                break;
+            }
+        }
+
+        socket.send(send(json!({"type": "stop"}))).await.unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(5), upstream_closed)
+            .await
+            .expect("upstream connection was not closed after stop")
+            .unwrap();
+        loop {
+            let event = next_client_event(&mut socket).await;
+            assert_ne!(event["type"], "error", "{event}");
+            if event["type"] == "stopped" {
+                break;
+            }
+        }
+    }
 }
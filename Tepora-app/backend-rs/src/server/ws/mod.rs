@@ -4,4 +4,6 @@ mod control;
 pub mod handler;
 pub mod protocol;
 mod request;
+mod runs;
+pub mod schema;
 mod session;
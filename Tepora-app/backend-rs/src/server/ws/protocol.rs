@@ -1,14 +1,20 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json::Value;
 
+use super::schema::WsClientMessageType;
+use crate::a2a::A2AArtifact;
 use crate::core::security_controls::ToolApprovalResponsePayload;
 
 pub const WS_APP_PROTOCOL: &str = "tepora.v1";
 pub const WS_TOKEN_PREFIX: &str = "tepora-token.";
 
-#[derive(Debug, Deserialize, Default, Clone)]
+/// クライアントから届くメッセージ。チャットの本文は `type` を付けずに送り、
+/// 操作は `type`（`WsClientMessageType`）で指定する。
+#[derive(Debug, Deserialize, JsonSchema, Default, Clone)]
 pub struct WsIncomingMessage {
     #[serde(rename = "type")]
+    #[schemars(with = "Option<WsClientMessageType>")]
     pub msg_type: Option<String>,
     pub message: Option<String>,
     pub mode: Option<String>,
@@ -20,6 +26,9 @@ pub struct WsIncomingMessage {
     pub search_mode: Option<String>,
     #[serde(rename = "thinkingBudget")]
     pub thinking_budget: Option<u8>,
+    /// `"outline"` で SynthesizerNode を見出し→節ごとの二段階にする
+    #[serde(rename = "synthesisMode")]
+    pub synthesis_mode: Option<String>,
     #[serde(rename = "agentId")]
     pub agent_id: Option<String>,
     #[serde(rename = "agentMode")]
@@ -32,6 +41,18 @@ pub struct WsIncomingMessage {
     #[serde(flatten)]
     pub approval: ToolApprovalResponsePayload,
     pub timeout: Option<u64>,
+    #[serde(rename = "personaId")]
+    pub persona_id: Option<String>,
+    #[serde(rename = "projectId")]
+    pub project_id: Option<String>,
+    /// このメッセージだけの温度。未指定ならセッションの既定値
+    pub temperature: Option<f64>,
+    /// このメッセージで `rag_search` に追加で許すコレクション
+    #[serde(rename = "ragCollections")]
+    pub rag_collections: Option<Vec<String>>,
+    /// 委任元から渡された成果物（A2A）。実行の共有コンテキストに入る
+    #[serde(default)]
+    pub artifacts: Vec<A2AArtifact>,
 }
 
 #[cfg(test)]
@@ -44,7 +65,8 @@ mod tests {
             "message": "compare these documents",
             "mode": "search",
             "searchMode": "deep",
-            "thinkingBudget": 2
+            "thinkingBudget": 2,
+            "synthesisMode": "outline"
         }"#;
 
         let message: WsIncomingMessage = serde_json::from_str(payload).unwrap();
@@ -52,5 +74,24 @@ mod tests {
         assert_eq!(message.mode.as_deref(), Some("search"));
         assert_eq!(message.search_mode.as_deref(), Some("deep"));
         assert_eq!(message.thinking_budget, Some(2));
+        assert_eq!(message.synthesis_mode.as_deref(), Some("outline"));
+        assert!(message.artifacts.is_empty());
+    }
+
+    #[test]
+    fn deserializes_delegated_artifacts() {
+        let payload = r#"{
+            "message": "summarize the attached table",
+            "artifacts": [{
+                "id": "a1",
+                "name": "table",
+                "parts": [{"kind": "data", "data": {"rows": 3}}]
+            }]
+        }"#;
+
+        let message: WsIncomingMessage = serde_json::from_str(payload).unwrap();
+
+        assert_eq!(message.artifacts.len(), 1);
+        assert_eq!(message.artifacts[0].name, "table");
     }
 }
@@ -2,6 +2,9 @@ use std::time::Duration;
 
 use serde_json::{json, Value};
 
+use crate::a2a::A2AArtifact;
+use crate::core::config::schema::SessionDefaults;
+use crate::core::config::TeporaConfig;
 use crate::core::errors::ApiError;
 use crate::core::security_controls::detect_pii_in_attachments;
 use crate::state::AppState;
@@ -13,25 +16,37 @@ pub struct GenerationRequest {
     pub request_id: Option<String>,
     pub message_text: String,
     pub attachments: Vec<Value>,
+    /// Artifacts delegated with the message, stored into the run's shared context.
+    pub artifacts: Vec<A2AArtifact>,
     pub mode: String,
     pub thinking_budget: u8,
     pub search_mode: Option<String>,
     pub requested_agent_id: Option<String>,
     pub requested_agent_mode: Option<String>,
     pub skip_search: bool,
+    pub synthesis_mode: Option<String>,
     pub timestamp: String,
     pub user_kwargs: Value,
     pub timeout_override: Option<Duration>,
+    /// Persona active for the turn; resolved after the session is known.
+    pub persona_id: Option<String>,
+    /// Temperature and RAG collections the message asked for, applied over
+    /// the session defaults.
+    pub session_overrides: SessionDefaults,
 }
 
+/// `defaults` are the session defaults of the target session; the mode falls
+/// back to them when the message does not name one.
 pub fn build_generation_request(
     state: &AppState,
     current_session_id: &str,
     data: WsIncomingMessage,
+    defaults: &SessionDefaults,
 ) -> Result<GenerationRequest, ApiError> {
     let request_id = data.request_id.clone();
     let message_text = data.message.unwrap_or_default();
     let attachments = data.attachments;
+    let artifacts = data.artifacts;
 
     if state.core().security.is_lockdown_enabled() {
         return Err(ApiError::Conflict(
@@ -82,14 +97,31 @@ pub fn build_generation_request(
     let session_id = data
         .session_id
         .unwrap_or_else(|| current_session_id.to_string());
-    let mode = data.mode.unwrap_or_else(|| "chat".to_string());
+    let mode = data
+        .mode
+        .filter(|mode| !mode.trim().is_empty())
+        .unwrap_or_else(|| defaults.mode().to_string());
     let thinking_budget = std::cmp::min(data.thinking_budget.unwrap_or(0), 3);
     let search_mode = data.search_mode;
     let requested_agent_id = data.agent_id;
     let requested_agent_mode = data.agent_mode;
     let skip_search = data.skip_web_search.unwrap_or(false);
+    let synthesis_mode = data.synthesis_mode;
     let timestamp = chrono::Utc::now().to_rfc3339();
     let timeout_override = data.timeout.map(Duration::from_millis);
+    if data
+        .temperature
+        .is_some_and(|temperature| !(0.0..=2.0).contains(&temperature))
+    {
+        return Err(ApiError::BadRequest(
+            "temperature must be between 0 and 2".to_string(),
+        ));
+    }
+    let session_overrides = SessionDefaults {
+        temperature: data.temperature,
+        rag_collections: data.rag_collections.unwrap_or_default(),
+        ..SessionDefaults::default()
+    };
 
     validate_message_text(state, &message_text)?;
 
@@ -102,6 +134,9 @@ pub fn build_generation_request(
         "agent_id": requested_agent_id.clone(),
         "agent_mode": requested_agent_mode.clone(),
         "skip_web_search": Some(skip_search),
+        "synthesis_mode": synthesis_mode.clone(),
+        "temperature": session_overrides.temperature,
+        "rag_collections": session_overrides.rag_collections.clone(),
     });
 
     Ok(GenerationRequest {
@@ -109,27 +144,25 @@ pub fn build_generation_request(
         request_id,
         message_text,
         attachments,
+        artifacts,
         mode,
         thinking_budget,
         search_mode,
         requested_agent_id,
         requested_agent_mode,
         skip_search,
+        synthesis_mode,
         timestamp,
         user_kwargs,
         timeout_override,
+        persona_id: None,
+        session_overrides,
     })
 }
 
 fn validate_message_text(state: &AppState, message_text: &str) -> Result<(), ApiError> {
     let config = state.core().config.load_config()?;
-
-    let max_input_length = config
-        .get("app")
-        .and_then(|app| app.get("max_input_length"))
-        .and_then(|v| v.as_u64())
-        .map(|v| v as usize)
-        .unwrap_or(4096);
+    let max_input_length = TeporaConfig::from_value(&config)?.app.max_input_length as usize;
 
     if message_text.len() > max_input_length {
         return Err(ApiError::BadRequest(format!(
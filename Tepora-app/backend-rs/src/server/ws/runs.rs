@@ -0,0 +1,112 @@
+//! ソケットごとの実行中の生成。
+//!
+//! メインループは生成が終わるまで次のメッセージを読まないので、`stop` と
+//! ツールの承認は読み取り側のタスクがここを見てすぐに処理する。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio_util::sync::CancellationToken;
+
+#[derive(Clone, Default)]
+pub(super) struct ConnectionRuns {
+    inner: Arc<RunsInner>,
+}
+
+#[derive(Default)]
+struct RunsInner {
+    next_id: AtomicU64,
+    /// セッション ID ごとの実行。同じセッションはキューで 1 件ずつしか走らない
+    runs: Mutex<HashMap<String, (u64, CancellationToken)>>,
+}
+
+/// 生成 1 回分。落とすと一覧から外れる。
+pub(super) struct ActiveRun {
+    inner: Arc<RunsInner>,
+    id: u64,
+    session_id: String,
+    token: CancellationToken,
+}
+
+impl ConnectionRuns {
+    pub(super) fn start(&self, session_id: &str) -> ActiveRun {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let token = CancellationToken::new();
+        self.lock()
+            .insert(session_id.to_string(), (id, token.clone()));
+        ActiveRun {
+            inner: self.inner.clone(),
+            id,
+            session_id: session_id.to_string(),
+            token,
+        }
+    }
+
+    /// `session_id` の実行を、指定がなければこのソケットの実行すべてを取り消す。
+    /// 取り消したセッションの ID を返す。
+    pub(super) fn cancel(&self, session_id: Option<&str>) -> Vec<String> {
+        let runs = self.lock();
+        runs.iter()
+            .filter(|(session, _)| session_id.is_none_or(|target| target == session.as_str()))
+            .map(|(session, (_, token))| {
+                token.cancel();
+                session.clone()
+            })
+            .collect()
+    }
+
+    pub(super) fn sessions(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, (u64, CancellationToken)>> {
+        self.inner
+            .runs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ActiveRun {
+    pub(super) fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+}
+
+impl Drop for ActiveRun {
+    fn drop(&mut self) {
+        let mut runs = self
+            .inner
+            .runs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if runs
+            .get(&self.session_id)
+            .is_some_and(|(id, _)| *id == self.id)
+        {
+            runs.remove(&self.session_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stop_cancels_only_the_targeted_session_and_finished_runs_leave() {
+        let runs = ConnectionRuns::default();
+        let first = runs.start("s1");
+        let second = runs.start("s2");
+
+        assert_eq!(runs.cancel(Some("s1")), vec!["s1".to_string()]);
+        assert!(first.token().is_cancelled());
+        assert!(!second.token().is_cancelled());
+
+        drop(first);
+        assert_eq!(runs.sessions(), vec!["s2".to_string()]);
+        assert_eq!(runs.cancel(None), vec!["s2".to_string()]);
+        assert!(second.token().is_cancelled());
+    }
+}
@@ -0,0 +1,512 @@
+//! WebSocket プロトコルの型定義。
+//!
+//! サーバーが送るイベントは `WsServerEvent`、クライアントが送るメッセージは
+//! `WsIncomingMessage`（`type` の値は `WsClientMessageType`）にまとめる。
+//! JSON Schema は `/api/ws/schema` で公開し、同じスキーマから TypeScript の型を
+//! 組み立てて `frontend/src/shared/contracts/ws.generated.ts` に置く。
+//! フロントエンドとの食い違いはこのファイルと生成物の差分で見つける。
+
+use axum::extract::Query;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::protocol::{WsIncomingMessage, WS_APP_PROTOCOL};
+use crate::agent::plan::AgentPlan;
+use crate::core::config::schema::SessionDefaults;
+use crate::llm::outage::RemediationHint;
+use crate::llm::types::TurnUsage;
+
+/// どのイベントにも付きうる配送情報。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct WsEnvelope {
+    /// 生成のストリーム ID（`requestId` と同じ値）
+    #[serde(rename = "streamId", default, skip_serializing_if = "Option::is_none")]
+    pub stream_id: Option<String>,
+    #[serde(rename = "requestId", default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ActivityPayload {
+    pub id: String,
+    /// processing / done / error
+    pub status: String,
+    pub message: String,
+    #[serde(rename = "agentName", default, skip_serializing_if = "Option::is_none")]
+    pub agent_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HistoryMessage {
+    pub id: String,
+    /// user / assistant / system
+    pub role: String,
+    pub content: String,
+    pub timestamp: String,
+    pub mode: String,
+    #[serde(rename = "isComplete")]
+    pub is_complete: bool,
+    #[serde(rename = "personaId", default, skip_serializing_if = "Option::is_none")]
+    pub persona_id: Option<String>,
+}
+
+/// サーバーから送るイベント。`type` で判別する。
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsServerEvent {
+    /// 応答テキストの断片
+    Chunk {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mode: Option<String>,
+        #[serde(rename = "nodeId", default, skip_serializing_if = "Option::is_none")]
+        node_id: Option<String>,
+        #[serde(rename = "agentName", default, skip_serializing_if = "Option::is_none")]
+        agent_name: Option<String>,
+    },
+    Thought {
+        content: String,
+    },
+    /// 文の区切り（読み上げ用）
+    Sentence {
+        data: Value,
+    },
+    /// 応答の終わり。使ったトークン数が分かれば `usage` を付ける
+    Done {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<TurnUsage>,
+    },
+    Stopped,
+    RegenerateStarted,
+    Status {
+        message: String,
+    },
+    Error {
+        message: String,
+    },
+    /// モデルのプロバイダーに繋がらない。サーバー側で一度繋ぎ直しても駄目だったときに送る
+    ProviderUnavailable {
+        /// ローダー名（ollama / lmstudio / llama_cpp など）
+        provider: String,
+        message: String,
+        hints: Vec<RemediationHint>,
+    },
+    Activity {
+        data: ActivityPayload,
+    },
+    History {
+        messages: Vec<HistoryMessage>,
+    },
+    SearchResults {
+        data: Value,
+    },
+    ResearchReport {
+        data: Value,
+    },
+    /// 実行計画の見積もり。`tool_confirmation_response` で承認を返す
+    PlanEstimate {
+        data: Value,
+    },
+    /// 構造化された実行計画。`editable` の間は `POST /api/runs/:id/plan` で差し替えられる
+    Plan {
+        #[serde(rename = "runId", default, skip_serializing_if = "Option::is_none")]
+        run_id: Option<String>,
+        data: AgentPlan,
+        editable: bool,
+    },
+    ToolConfirmationRequest {
+        data: Value,
+    },
+    NodeCompleted {
+        #[serde(rename = "nodeId")]
+        node_id: String,
+        output: Value,
+    },
+    QueuePosition {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        #[serde(rename = "requestId", default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+        data: Value,
+    },
+    MemoryGeneration {
+        /// started / completed / error
+        status: String,
+        #[serde(rename = "sessionId", default, skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
+    },
+    InteractionComplete {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        #[serde(rename = "runId", default, skip_serializing_if = "Option::is_none")]
+        run_id: Option<String>,
+    },
+    SessionChanged {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        defaults: SessionDefaults,
+    },
+    PersonaChanged {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        #[serde(rename = "personaId")]
+        persona_id: String,
+        name: String,
+        #[serde(default)]
+        greeting: Option<String>,
+    },
+    ProjectChanged {
+        #[serde(rename = "projectId")]
+        project_id: String,
+        #[serde(rename = "sessionId")]
+        session_id: String,
+        settings: Value,
+        sessions: Value,
+        defaults: SessionDefaults,
+    },
+    Stats {
+        data: Value,
+    },
+    Inbox {
+        data: Value,
+    },
+    Notification {
+        data: Value,
+    },
+    Health {
+        data: Value,
+    },
+    Utilization {
+        data: Value,
+    },
+    ModelSlot {
+        data: Value,
+    },
+    ConfigChanged {
+        data: Value,
+    },
+}
+
+impl WsServerEvent {
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+}
+
+/// クライアントが送る `type`。チャットの本文は `type` を付けずに送る。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WsClientMessageType {
+    Stop,
+    GetStats,
+    PerfProbe,
+    SetSession,
+    ToolConfirmationResponse,
+    SwitchPersona,
+    SwitchProject,
+    Regenerate,
+    SubscribeUtilization,
+    UnsubscribeUtilization,
+}
+
+/// `/api/ws/schema` の本体。
+pub fn ws_json_schema() -> Value {
+    json!({
+        "protocol": WS_APP_PROTOCOL,
+        "server": schemars::schema_for!(WsServerEvent),
+        "envelope": schemars::schema_for!(WsEnvelope),
+        "client": schemars::schema_for!(WsIncomingMessage),
+        "client_types": schemars::schema_for!(WsClientMessageType),
+    })
+}
+
+/// `ws_json_schema` から組み立てた TypeScript の型定義。
+pub fn ws_typescript() -> String {
+    let mut out = String::from(
+        "// このファイルは backend-rs の `server::ws::schema` から生成される。手で編集しないこと。\n\
+         // 更新: TEPORA_UPDATE_WS_TYPES=1 cargo test ws_typescript_definitions_are_current\n\n",
+    );
+    out.push_str(&format!(
+        "export const WS_APP_PROTOCOL = {};\n\n",
+        Value::String(WS_APP_PROTOCOL.to_string())
+    ));
+    let schema = ws_json_schema();
+    let roots = [
+        ("WsServerEvent", "server"),
+        ("WsEnvelope", "envelope"),
+        ("WsClientMessage", "client"),
+        ("WsClientMessageType", "client_types"),
+    ];
+    let mut definitions = Map::new();
+    for (name, key) in roots {
+        let root = &schema[key];
+        if let Some(defs) = root.get("$defs").and_then(Value::as_object) {
+            // ルートとして出す型が他のルートの `$defs` にも現れるので二重に出さない
+            for (def_name, def) in defs {
+                if roots.iter().all(|(root_name, _)| root_name != def_name) {
+                    definitions.insert(def_name.clone(), def.clone());
+                }
+            }
+        }
+        out.push_str(&render_doc(root, ""));
+        out.push_str(&format!("export type {} = {};\n\n", name, ts_type(root, 0)));
+    }
+    for (name, def) in &definitions {
+        out.push_str(&render_doc(def, ""));
+        out.push_str(&format!("export type {} = {};\n\n", name, ts_type(def, 0)));
+    }
+    out.push_str("export type WsServerMessage = WsServerEvent & WsEnvelope;\n");
+    out
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct WsSchemaQuery {
+    /// `typescript` で型定義をそのまま返す。省略時は JSON Schema
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+/// `GET /api/ws/schema`
+pub async fn get_ws_schema(Query(query): Query<WsSchemaQuery>) -> Response {
+    match query.format.as_deref() {
+        Some("typescript" | "ts") => (
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            ws_typescript(),
+        )
+            .into_response(),
+        _ => Json(ws_json_schema()).into_response(),
+    }
+}
+
+fn render_doc(schema: &Value, indent: &str) -> String {
+    match schema.get("description").and_then(Value::as_str) {
+        Some(description) if !description.trim().is_empty() => {
+            let lines: Vec<&str> = description.lines().collect();
+            if lines.len() == 1 {
+                format!("{indent}/** {} */\n", lines[0].trim())
+            } else {
+                let mut doc = format!("{indent}/**\n");
+                for line in lines {
+                    doc.push_str(&format!("{indent} * {}\n", line.trim()));
+                }
+                doc.push_str(&format!("{indent} */\n"));
+                doc
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+/// JSON Schema の 1 ノードを TypeScript の型式にする。スキーマが表せる範囲
+/// （schemars が serde の型から出すもの）だけを扱い、それ以外は `unknown`。
+fn ts_type(schema: &Value, depth: usize) -> String {
+    let Some(object) = schema.as_object() else {
+        return "unknown".to_string();
+    };
+    if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+        return reference
+            .rsplit('/')
+            .next()
+            .unwrap_or("unknown")
+            .to_string();
+    }
+    if let Some(constant) = object.get("const") {
+        return constant.to_string();
+    }
+    if let Some(values) = object.get("enum").and_then(Value::as_array) {
+        return union(values.iter().map(Value::to_string).collect());
+    }
+    for key in ["oneOf", "anyOf"] {
+        if let Some(variants) = object.get(key).and_then(Value::as_array) {
+            return union(
+                variants
+                    .iter()
+                    .map(|variant| ts_type(variant, depth))
+                    .collect(),
+            );
+        }
+    }
+    if let Some(parts) = object.get("allOf").and_then(Value::as_array) {
+        let parts: Vec<String> = parts.iter().map(|part| ts_type(part, depth)).collect();
+        return parts.join(" & ");
+    }
+    match object.get("type") {
+        Some(Value::Array(types)) => union(
+            types
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|kind| ts_primitive(kind, object, depth))
+                .collect(),
+        ),
+        Some(Value::String(kind)) => ts_primitive(kind, object, depth),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn ts_primitive(kind: &str, object: &Map<String, Value>, depth: usize) -> String {
+    match kind {
+        "string" => "string".to_string(),
+        "integer" | "number" => "number".to_string(),
+        "boolean" => "boolean".to_string(),
+        "null" => "null".to_string(),
+        "array" => {
+            let item = object
+                .get("items")
+                .map(|items| ts_type(items, depth))
+                .unwrap_or_else(|| "unknown".to_string());
+            if item.contains(' ') {
+                format!("Array<{item}>")
+            } else {
+                format!("{item}[]")
+            }
+        }
+        "object" => ts_object(object, depth),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn ts_object(object: &Map<String, Value>, depth: usize) -> String {
+    let properties = object.get("properties").and_then(Value::as_object);
+    let Some(properties) = properties.filter(|properties| !properties.is_empty()) else {
+        let value = match object.get("additionalProperties") {
+            Some(Value::Object(_)) => ts_type(&object["additionalProperties"], depth),
+            _ => "unknown".to_string(),
+        };
+        return format!("Record<string, {value}>");
+    };
+    let required: Vec<&str> = object
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let indent = "\t".repeat(depth + 1);
+    let mut out = String::from("{\n");
+    for (name, property) in properties {
+        out.push_str(&render_doc(property, &indent));
+        let optional = if required.contains(&name.as_str()) {
+            ""
+        } else {
+            "?"
+        };
+        out.push_str(&format!(
+            "{indent}{}{optional}: {};\n",
+            property_name(name),
+            ts_type(property, depth + 1)
+        ));
+    }
+    out.push_str(&"\t".repeat(depth));
+    out.push('}');
+    out
+}
+
+fn property_name(name: &str) -> String {
+    let plain = name
+        .chars()
+        .enumerate()
+        .all(|(idx, ch)| ch == '_' || ch.is_ascii_alphabetic() || (idx > 0 && ch.is_ascii_digit()));
+    if plain {
+        name.to_string()
+    } else {
+        Value::String(name.to_string()).to_string()
+    }
+}
+
+fn union(mut members: Vec<String>) -> String {
+    members.dedup();
+    match members.len() {
+        0 => "never".to_string(),
+        1 => members.remove(0),
+        _ => members.join(" | "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// TypeScript 定義の置き場（`backend-rs` からの相対パス）
+    const TYPESCRIPT_OUTPUT: &str = "../frontend/src/shared/contracts/ws.generated.ts";
+
+    #[test]
+    fn server_events_serialize_with_type_tag() {
+        let event = WsServerEvent::InteractionComplete {
+            session_id: "s1".to_string(),
+            run_id: None,
+        };
+        assert_eq!(
+            event.to_value(),
+            json!({"type": "interaction_complete", "sessionId": "s1"})
+        );
+        assert_eq!(
+            WsServerEvent::Done { usage: None }.to_value(),
+            json!({"type": "done"})
+        );
+
+        // 配送情報が付いていても読める
+        let parsed: WsServerEvent = serde_json::from_value(json!({
+            "type": "chunk",
+            "message": "hi",
+            "streamId": "r1",
+            "requestId": "r1"
+        }))
+        .unwrap();
+        assert!(
+            matches!(parsed, WsServerEvent::Chunk { message: Some(ref text), .. } if text == "hi")
+        );
+    }
+
+    #[test]
+    fn typescript_covers_every_server_event() {
+        let typescript = ws_typescript();
+        for name in [
+            "chunk",
+            "session_changed",
+            "plan_estimate",
+            "model_slot",
+            "done",
+        ] {
+            assert!(
+                typescript.contains(&format!("type: \"{name}\"")),
+                "missing {name}"
+            );
+        }
+        assert!(typescript.contains("export type WsClientMessage = {"));
+        assert!(typescript.contains("export type SessionDefaults = {"));
+    }
+
+    #[test]
+    fn typescript_exports_each_name_once() {
+        let typescript = ws_typescript();
+        let mut seen = std::collections::HashSet::new();
+        for line in typescript.lines() {
+            let Some(rest) = line.strip_prefix("export type ") else {
+                continue;
+            };
+            let name = rest.split([' ', '=']).next().unwrap_or_default();
+            assert!(seen.insert(name.to_string()), "{name} is exported twice");
+        }
+        assert!(seen.contains("WsClientMessageType"));
+    }
+
+    /// 生成物が古ければ落ちる。`TEPORA_UPDATE_WS_TYPES=1` で書き直す。
+    #[test]
+    fn ws_typescript_definitions_are_current() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(TYPESCRIPT_OUTPUT);
+        let generated = ws_typescript();
+        if std::env::var("TEPORA_UPDATE_WS_TYPES").is_ok_and(|value| value == "1") {
+            std::fs::write(&path, &generated).unwrap();
+            return;
+        }
+        let committed = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            committed == generated,
+            "{} is stale; run TEPORA_UPDATE_WS_TYPES=1 cargo test ws_typescript_definitions_are_current",
+            path.display()
+        );
+    }
+}
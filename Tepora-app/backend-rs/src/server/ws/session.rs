@@ -1,12 +1,17 @@
 use serde_json::{json, Value};
 
+use crate::agent::execution::resolve_agent_memory_policy;
+use crate::context::workers::project_worker::session_defaults_for;
 use crate::core::errors::ApiError;
+use crate::history::PartialMessage;
 use crate::state::AppState;
 
 use super::request::GenerationRequest;
 
 pub async fn build_history_payload(state: &AppState, session_id: &str) -> Result<Value, ApiError> {
     let messages = state.runtime().history.get_history(session_id, 100).await?;
+    // 古いメッセージは mode を持たないので、セッションの既定モードで表示する
+    let defaults = session_defaults_for(state, session_id).await;
     let formatted: Vec<Value> = messages
         .into_iter()
         .map(|msg| {
@@ -27,44 +32,52 @@ pub async fn build_history_payload(state: &AppState, session_id: &str) -> Result
                 .as_ref()
                 .and_then(|k| k.get("mode"))
                 .and_then(|v| v.as_str())
-                .unwrap_or("chat")
+                .unwrap_or(defaults.mode())
                 .to_string();
 
-            json!({
+            let mut payload = json!({
                 "id": msg.id.to_string(),
                 "role": role,
                 "content": msg.content,
                 "timestamp": timestamp,
                 "mode": mode,
-                "isComplete": true
-            })
+                "isComplete": msg.is_complete()
+            });
+            if let Some(persona_id) = msg
+                .additional_kwargs
+                .as_ref()
+                .and_then(|k| k.get("persona_id"))
+                .and_then(|v| v.as_str())
+            {
+                payload["personaId"] = json!(persona_id);
+            }
+            payload
         })
         .collect();
 
     Ok(json!({"type": "history", "messages": formatted}))
 }
 
-pub async fn persist_graph_interaction(
-    state: &AppState,
-    request: &GenerationRequest,
-    assistant_output: &str,
-) -> Result<(), ApiError> {
-    let assistant_kwargs = json!({
+pub fn assistant_kwargs(request: &GenerationRequest) -> Value {
+    json!({
         "timestamp": request.timestamp,
         "mode": request.mode.clone(),
         "thinking_budget": request.thinking_budget,
         "agent_id": request.requested_agent_id.clone(),
         "agent_mode": request.requested_agent_mode.clone(),
-    });
-    state
-        .runtime()
-        .history
-        .add_message(
-            &request.session_id,
-            "ai",
-            assistant_output,
-            Some(assistant_kwargs),
-        )
+        "persona_id": request.persona_id.clone(),
+    })
+}
+
+/// 途中保存していた応答を最終出力で確定させ、記憶に取り込む。
+pub async fn persist_graph_interaction(
+    state: &AppState,
+    request: &GenerationRequest,
+    assistant_output: &str,
+    partial: &PartialMessage,
+) -> Result<(), ApiError> {
+    partial
+        .finish(assistant_output, assistant_kwargs(request))
         .await?;
 
     let text_model_id = state
@@ -82,7 +95,20 @@ pub async fn persist_graph_interaction(
         .flatten()
         .unwrap_or_else(|| "default".to_string());
     let embedding_model_id = resolve_embedding_model_id(state);
+    state.memory().session_index.record_exchange_in_background(
+        &state.ai().llm,
+        &embedding_model_id,
+        &request.session_id,
+        &request.message_text,
+        assistant_output,
+    );
+
+    let memory_policy = resolve_agent_memory_policy(state, request.requested_agent_id.as_deref());
+    if !memory_policy.ingests() {
+        return Ok(());
+    }
     let legacy_enabled = state.is_redesign_enabled("legacy_memory");
+    let namespace = memory_policy.namespace_character_id();
 
     let _ = state
         .memory()
@@ -95,6 +121,7 @@ pub async fn persist_graph_interaction(
             &text_model_id,
             &embedding_model_id,
             legacy_enabled,
+            namespace.as_deref(),
         )
         .await;
 
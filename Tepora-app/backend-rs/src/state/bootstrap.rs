@@ -1,12 +1,24 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::actor::ActorManager;
+use crate::agent::exclusive::ExclusiveAgentManager;
+use crate::agent::plan::PlanEdits;
 use crate::agent::skill_registry::SkillRegistry;
 use crate::application::episodic_memory::EpisodicMemoryUseCase;
 use crate::application::knowledge::KnowledgeUseCase;
+use crate::core::chat_queue::ChatQueue;
+use crate::core::config::env_overrides::process_env_overrides;
+use crate::core::config::secrets::FallbackSecretStore;
 use crate::core::config::{AppPaths, ConfigService};
+use crate::core::health::HealthMonitor;
+use crate::core::inbox::Inbox;
+use crate::core::network;
+use crate::core::notifications::NotificationHub;
 use crate::core::security::init_session_token;
 use crate::core::security_controls::SecurityControls;
+use crate::core::snapshots::IntegrityReport;
+use crate::core::utilization::UtilizationMonitor;
 use crate::domain::episodic_memory::EpisodicMemoryPort;
 use crate::domain::knowledge::KnowledgePort;
 use crate::graph::build_tepora_graph;
@@ -17,6 +29,9 @@ use crate::mcp::registry::McpRegistry;
 use crate::mcp::McpManager;
 use crate::memory::MemoryService;
 use crate::models::ModelManager;
+use crate::plugins::PluginManager;
+use crate::rag::SessionIndex;
+use crate::scripting::ScriptManager;
 use crate::server::middleware::rate_limit::RateLimiters;
 use crate::workspace::{ProjectHistoryStore, ProjectKnowledgePort, WorkspaceManager};
 
@@ -27,6 +42,7 @@ use super::{
 };
 
 const DEFAULT_STARTUP_AUTO_BACKUP_LIMIT: usize = 10;
+const RUN_JANITOR_INTERVAL: Duration = Duration::from_secs(60);
 
 impl AppState {
     /// Initializes the application state.
@@ -39,7 +55,29 @@ impl AppState {
     /// 5. Building the agent execution graph
     pub async fn initialize() -> Result<Arc<Self>, InitializationError> {
         let paths = Arc::new(AppPaths::new());
+        match FallbackSecretStore::for_data_dir(&paths.user_data_dir).migrate_to_keyring() {
+            Ok(0) => {}
+            Ok(count) => tracing::info!(
+                "Moved {} secret(s) from the fallback file into the OS keyring",
+                count
+            ),
+            Err(err) => tracing::warn!("Failed to migrate fallback secrets: {}", err),
+        }
         let config = ConfigService::new(paths.clone());
+        // 読み込む前に、書きかけで壊れた設定をスナップショットから戻す
+        let mut integrity = IntegrityReport::default();
+        integrity.check(&[
+            config.config_path(),
+            config.secrets_path(),
+            paths.user_data_dir.join("models.json"),
+        ]);
+        let env_overrides = process_env_overrides();
+        if !env_overrides.is_empty() {
+            tracing::info!(
+                keys = ?env_overrides.iter().map(|item| item.dotted_path()).collect::<Vec<_>>(),
+                "Config keys overridden by TEPORA__* environment variables"
+            );
+        }
         let startup_config = config.load_config().unwrap_or_default();
         let security = Arc::new(SecurityControls::new(paths.clone(), config.clone()));
         let session_token = Arc::new(tokio::sync::RwLock::new(init_session_token()));
@@ -59,7 +97,13 @@ impl AppState {
             .map_err(|e| InitializationError::Llm(e.into()))?;
 
         let mcp = McpManager::new(paths.clone(), config.clone());
+        integrity.check(&[mcp.config_path(), mcp.policy_path()]);
         let mcp_registry = McpRegistry::new(&paths);
+        let plugins = PluginManager::new(&paths, config.clone());
+        let scripts = ScriptManager::new(&paths, config.clone());
+        if let Err(err) = scripts.reload() {
+            tracing::warn!("Failed to load scripts: {}", err);
+        }
         let models = ModelManager::new(&paths, config.clone());
         let setup = SetupState::new(&paths);
         let skill_registry = SkillRegistry::new(
@@ -150,6 +194,11 @@ impl AppState {
         let episodic_memory_use_case =
             Arc::new(EpisodicMemoryUseCase::new(episodic_memory.clone()));
         let knowledge_use_case = Arc::new(KnowledgeUseCase::new(knowledge.clone()));
+        let session_index = Arc::new(
+            SessionIndex::with_path(paths.user_data_dir.join("session_index.db"))
+                .await
+                .map_err(|e| InitializationError::Rag(e.into()))?,
+        );
 
         let core = Arc::new(AppCoreState {
             paths: paths.clone(),
@@ -157,22 +206,33 @@ impl AppState {
             session_token: session_token.clone(),
             setup: setup.clone(),
             security: security.clone(),
+            notifications: NotificationHub::new(config.clone()),
+            health: HealthMonitor::new(),
+            utilization: UtilizationMonitor::new(),
+            integrity,
         });
         let ai = Arc::new(AppAiState {
             llama: llama.clone(),
             llm: llm.clone(),
             models: models.clone(),
             skill_registry: skill_registry.clone(),
+            exclusive_agents: ExclusiveAgentManager::new(),
         });
         let integration = Arc::new(AppIntegrationState {
             mcp: mcp.clone(),
             mcp_registry: mcp_registry.clone(),
+            plugins: plugins.clone(),
+            scripts,
+            desktop: crate::core::desktop_bridge::DesktopBridge::new(),
         });
         let runtime = Arc::new(AppRuntimeState {
             history: history.clone(),
             graph_runtime: graph_runtime.clone(),
             rate_limiters: rate_limiters.clone(),
             actor_manager: actor_manager.clone(),
+            inbox: Inbox::new(history.clone()),
+            chat_queue: ChatQueue::new(),
+            plan_edits: PlanEdits::new(),
         });
         let memory = Arc::new(AppMemoryState {
             memory_service: memory_service.clone(),
@@ -181,6 +241,7 @@ impl AppState {
             knowledge: knowledge.clone(),
             episodic_memory_use_case: episodic_memory_use_case.clone(),
             knowledge_use_case: knowledge_use_case.clone(),
+            session_index,
         });
         let workspace = Arc::new(AppWorkspaceState {
             manager: workspace_manager.clone(),
@@ -195,24 +256,131 @@ impl AppState {
             workspace,
         ));
         app_state.runtime().actor_manager.clone().start_gc();
+        app_state.ai().llama.spawn_idle_reaper();
+        // セーフモードでは中断ジョブの再開と定期実行を止める（次の通常起動で再開する）
+        let safe_mode = crate::core::safe_mode::is_active();
+        if !safe_mode {
+            let recovery = super::jobs::recover_interrupted_jobs(&app_state).await;
+            if !recovery.resumed.is_empty()
+                || !recovery.failed.is_empty()
+                || recovery.removed_partials > 0
+            {
+                tracing::info!(
+                    resumed = recovery.resumed.len(),
+                    failed = recovery.failed.len(),
+                    removed_partials = recovery.removed_partials,
+                    "Recovered background jobs from the previous run"
+                );
+            }
+            crate::tools::feeds::spawn_feed_ingestion(app_state.clone());
+            crate::core::db_maintenance::spawn_db_maintenance(app_state.clone());
+            crate::core::history_topics::spawn_topic_indexing(app_state.clone());
+
+            app_state
+                .memory()
+                .memory_service
+                .clone()
+                .spawn_background_worker();
+        }
 
-        app_state
-            .memory()
-            .memory_service
-            .clone()
-            .spawn_background_worker();
+        network::apply_settings(
+            &app_state
+                .core()
+                .config
+                .load_typed()
+                .unwrap_or_default()
+                .network,
+        );
+
+        if let Err(err) = app_state.core().config.start_watching() {
+            tracing::warn!("Config file watching is unavailable: {}", err);
+        }
+        spawn_config_reload(app_state.clone());
+        spawn_run_janitor(app_state.clone());
+        super::health::spawn_health_monitor(app_state.clone());
 
-        let models_clone = app_state.ai().models.clone();
         tokio::spawn(async move {
-            if let Err(e) = models_clone.refresh_all_loader_models().await {
-                tracing::warn!("Failed to refresh loader models on startup: {}", e);
+            if let Err(err) = plugins.reload().await {
+                tracing::warn!("Failed to load plugins: {}", err);
             }
         });
 
+        // llama.cpp 以外のローダーは使わないので一覧も取りに行かない
+        if !safe_mode {
+            let models_clone = app_state.ai().models.clone();
+            tokio::spawn(async move {
+                if let Err(e) = models_clone.refresh_all_loader_models().await {
+                    tracing::warn!("Failed to refresh loader models on startup: {}", e);
+                }
+            });
+        }
+
         Ok(app_state)
     }
 }
 
+/// `app.stale_run_max_age_secs` を超えたグラフ実行を定期的に打ち切る。
+/// 設定は毎回読み直すので、変更は次の巡回から効く。
+fn spawn_run_janitor(app_state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(RUN_JANITOR_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let max_age = app_state
+                .core()
+                .config
+                .load_typed()
+                .map(|config| config.app.stale_run_max_age_secs)
+                .unwrap_or_default();
+            if max_age == 0 {
+                continue;
+            }
+            app_state
+                .runtime()
+                .graph_runtime
+                .abort_stale_runs(Duration::from_secs(max_age));
+        }
+    });
+}
+
+/// 設定変更を、起動時に値を取り込んだサブシステムへ反映する。
+/// プロバイダーの base URL などリクエストごとに設定を読む箇所は対象外。
+fn spawn_config_reload(app_state: Arc<AppState>) {
+    let mut changes = app_state.core().config.subscribe_changes();
+    tokio::spawn(async move {
+        loop {
+            let sections = match changes.recv().await {
+                Ok(event) => event.changed_sections,
+                // 取りこぼしたら全部読み直す
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => Vec::new(),
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            };
+            let touches = |names: &[&str]| {
+                sections.is_empty() || sections.iter().any(|s| names.contains(&s.as_str()))
+            };
+            if touches(&["network"]) {
+                match app_state.core().config.load_typed() {
+                    Ok(config) => network::apply_settings(&config.network),
+                    Err(err) => tracing::warn!("Failed to reload network settings: {}", err),
+                }
+            }
+            if touches(&["plugins"]) {
+                if let Err(err) = app_state.integration().plugins.reload().await {
+                    tracing::warn!("Failed to reload plugins: {}", err);
+                }
+            }
+            if !touches(&["episodic_memory", "em_llm"]) {
+                continue;
+            }
+            match app_state.core().config.load_config() {
+                Ok(config) => app_state.memory().memory_service.apply_config(&config),
+                Err(err) => tracing::warn!("Failed to reload memory settings: {}", err),
+            }
+        }
+    });
+}
+
 fn backup_sqlite_databases(paths: &AppPaths, config: &serde_json::Value) {
     let backup_limit = startup_auto_backup_limit(config);
     let timestamp = chrono::Utc::now().format("%Y%m%d%H%M%S").to_string();
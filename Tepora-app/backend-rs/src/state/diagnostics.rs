@@ -0,0 +1,98 @@
+//! 最初のトークンが遅いときの診断採取。
+//!
+//! 「遅い気がする」を後から調べられるよう、待ち時間が `app.first_token_watchdog_ms`
+//! を超えた時点のプロバイダーの応答、llama-server のスロット、コンテキストの
+//! トークン数、使用率をまとめて `LatencyTrace` に付ける。
+
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+
+use crate::infrastructure::observability::latency::{
+    FirstTokenDiagnostics, LatencyTrace, ProviderProbe,
+};
+
+use super::health::text_assignment_key;
+use super::utilization::sample_utilization;
+use super::AppState;
+
+/// `threshold` までに最初のトークンが来なければ診断を採取して `trace` に付ける。
+/// 間に合えばすぐ戻る。実行と並べて待ち、実行が終われば捨てられる前提。
+pub async fn watch_first_token(state: &AppState, trace: &LatencyTrace, threshold: Duration) {
+    if trace.wait_first_token(threshold).await {
+        return;
+    }
+    let diagnostics = capture_first_token_diagnostics(state, trace, threshold).await;
+    tracing::warn!(
+        request_id = %trace.request_id,
+        session_id = %trace.session_id,
+        waited_ms = diagnostics.waited_ms,
+        context_tokens = ?diagnostics.context_tokens,
+        provider = ?diagnostics.provider.provider,
+        provider_ok = diagnostics.provider.ok,
+        cpu_percent = diagnostics.utilization.cpu_percent,
+        "First token is late; captured diagnostics"
+    );
+    trace.attach_diagnostics(diagnostics);
+}
+
+pub async fn capture_first_token_diagnostics(
+    state: &AppState,
+    trace: &LatencyTrace,
+    threshold: Duration,
+) -> FirstTokenDiagnostics {
+    let waited_ms = trace.breakdown().total_ms;
+    let (provider, llama_slots, utilization) = tokio::join!(
+        probe_provider(state),
+        state.ai().llama.slot_status(),
+        sample_utilization(state)
+    );
+    FirstTokenDiagnostics {
+        captured_at: Utc::now(),
+        threshold_ms: threshold.as_millis() as u64,
+        waited_ms,
+        context_tokens: trace.context_tokens(),
+        provider,
+        health: state.core().health.latest(),
+        llama_slots,
+        utilization,
+    }
+}
+
+async fn probe_provider(state: &AppState) -> ProviderProbe {
+    let model_id = match state
+        .ai()
+        .models
+        .resolve_assignment_model_id(&text_assignment_key(state))
+    {
+        Ok(Some(model_id)) => model_id,
+        Ok(None) => {
+            return ProviderProbe {
+                detail: "no text model is assigned".to_string(),
+                ..ProviderProbe::default()
+            }
+        }
+        Err(err) => {
+            return ProviderProbe {
+                detail: err.to_string(),
+                ..ProviderProbe::default()
+            }
+        }
+    };
+    let llm = &state.ai().llm;
+    let provider = llm.provider_for(&model_id).ok().map(|(loader, _)| loader);
+    let started = Instant::now();
+    let result = llm.probe_model(&model_id).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let (ok, detail) = match result {
+        Ok(detail) => (true, detail),
+        Err(err) => (false, err.to_string()),
+    };
+    ProviderProbe {
+        model_id: Some(model_id),
+        provider,
+        ok,
+        detail,
+        latency_ms,
+    }
+}
@@ -0,0 +1,169 @@
+//! サブシステムの稼働確認。結果は `core::health::HealthMonitor` に集める。
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::core::health::{HealthReport, HealthStatus, SubsystemHealth};
+
+use super::AppState;
+
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 起動直後に一度確認し、以降は一定間隔で確認し直す。
+pub(super) fn spawn_health_monitor(app_state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let report = check_health(&app_state).await;
+            app_state.core().health.record(report);
+        }
+    });
+}
+
+/// アクティブなキャラクターの割り当てキー。未設定なら既定の `character`。
+pub fn text_assignment_key(state: &AppState) -> String {
+    state
+        .core()
+        .config
+        .load_config()
+        .ok()
+        .and_then(|config| {
+            config
+                .get("active_character")
+                .or_else(|| config.get("active_agent_profile"))
+                .and_then(|v| v.as_str())
+                .map(|value| format!("character:{value}"))
+        })
+        .unwrap_or_else(|| "character".to_string())
+}
+
+pub async fn check_health(state: &AppState) -> HealthReport {
+    let (database, llama_binary, mcp) = tokio::join!(
+        check_database(state),
+        check_llama_binary(state),
+        check_mcp(state)
+    );
+    let (models, embedding_model_id) = check_models(state);
+    let embedding_server = check_embedding_server(state, embedding_model_id.as_deref()).await;
+    HealthReport::new(vec![database, models, llama_binary, embedding_server, mcp])
+}
+
+async fn check_database(state: &AppState) -> SubsystemHealth {
+    let started = Instant::now();
+    let result = state.runtime().history.probe_writable().await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(()) => SubsystemHealth::new("database", HealthStatus::Ok, "writable", true),
+        Err(err) => SubsystemHealth::new("database", HealthStatus::Down, err.to_string(), true),
+    }
+    .with_latency(latency_ms)
+}
+
+async fn check_llama_binary(state: &AppState) -> SubsystemHealth {
+    let (path, available) = state.ai().llama.server_binary().await;
+    if available {
+        SubsystemHealth::new(
+            "llama_binary",
+            HealthStatus::Ok,
+            path.display().to_string(),
+            false,
+        )
+    } else {
+        SubsystemHealth::new(
+            "llama_binary",
+            HealthStatus::Down,
+            format!("llama-server not found ({})", path.display()),
+            false,
+        )
+    }
+}
+
+/// テキストモデルが決まらなければ会話できないので致命扱い。
+/// 埋め込みモデルがないだけなら記憶・RAG が止まるのみ。
+fn check_models(state: &AppState) -> (SubsystemHealth, Option<String>) {
+    let models = &state.ai().models;
+    let text = models.resolve_assignment_model_id(&text_assignment_key(state));
+    let embedding = models.resolve_embedding_model_id();
+    let embedding_model_id = embedding.as_ref().ok().cloned().flatten();
+
+    let health = match (text, embedding) {
+        (Err(err), _) => SubsystemHealth::new("models", HealthStatus::Down, err.to_string(), true),
+        (Ok(None), _) => SubsystemHealth::new(
+            "models",
+            HealthStatus::Down,
+            "no text model is assigned",
+            true,
+        ),
+        (Ok(Some(text)), Ok(Some(embedding))) => SubsystemHealth::new(
+            "models",
+            HealthStatus::Ok,
+            format!("text: {text}, embedding: {embedding}"),
+            true,
+        ),
+        (Ok(Some(text)), _) => SubsystemHealth::new(
+            "models",
+            HealthStatus::Degraded,
+            format!("text: {text}, no embedding model is assigned"),
+            true,
+        ),
+    };
+    (health, embedding_model_id)
+}
+
+async fn check_embedding_server(state: &AppState, model_id: Option<&str>) -> SubsystemHealth {
+    let Some(model_id) = model_id else {
+        return SubsystemHealth::new(
+            "embedding_server",
+            HealthStatus::Degraded,
+            "no embedding model is assigned",
+            false,
+        );
+    };
+    let started = Instant::now();
+    let result = state.ai().llm.probe_model(model_id).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(detail) => SubsystemHealth::new("embedding_server", HealthStatus::Ok, detail, false),
+        Err(err) => SubsystemHealth::new(
+            "embedding_server",
+            HealthStatus::Down,
+            err.to_string(),
+            false,
+        ),
+    }
+    .with_latency(latency_ms)
+}
+
+async fn check_mcp(state: &AppState) -> SubsystemHealth {
+    let mcp = &state.integration().mcp;
+    if let Some(err) = mcp.init_error().await {
+        return SubsystemHealth::new("mcp", HealthStatus::Down, err, false);
+    }
+    if !mcp.initialized() {
+        return SubsystemHealth::new("mcp", HealthStatus::Degraded, "initializing", false);
+    }
+    let statuses = mcp.status_snapshot().await;
+    let mut failed = statuses
+        .iter()
+        .filter(|(_, status)| status.status == "error")
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    failed.sort_unstable();
+    if failed.is_empty() {
+        SubsystemHealth::new(
+            "mcp",
+            HealthStatus::Ok,
+            format!("{} server(s)", statuses.len()),
+            false,
+        )
+    } else {
+        SubsystemHealth::new(
+            "mcp",
+            HealthStatus::Degraded,
+            format!("failed: {}", failed.join(", ")),
+            false,
+        )
+    }
+}
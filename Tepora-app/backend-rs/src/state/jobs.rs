@@ -0,0 +1,253 @@
+//! バックグラウンドジョブの記録と、異常終了後の後始末。
+//!
+//! ダウンロード・記憶の圧縮・定期取り込みなどは起動時に `background_jobs` へ
+//! 行を作り、終わったら閉じる。プロセスが落ちると行は `running` のまま残るので、
+//! 次の起動時に `recover_interrupted_jobs` がそれを拾う。モデルのダウンロードは
+//! 記録した入力から再開し、それ以外は失敗として閉じる。書きかけの `.part` と
+//! `SetupState` に残ったジョブ ID もここで片付ける。
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+use serde_json::Value;
+
+use crate::history::{NewInboxItem, NewJob, JOB_RUNNING};
+use crate::server::handlers::setup_models::{run_download_job, DownloadTask};
+
+use super::{AppState, AppStateWrite};
+
+pub const KIND_MODEL_DOWNLOAD: &str = "model_download";
+pub const KIND_BINARY_UPDATE: &str = "binary_update";
+pub const KIND_MEMORY_COMPACTION: &str = "memory_compaction";
+pub const KIND_FEED_INGEST: &str = "feed_ingest";
+pub const KIND_DB_MAINTENANCE: &str = "db_maintenance";
+pub const KIND_TOPIC_INDEX: &str = "topic_index";
+
+/// 再開を諦めるまでの起動回数。再開直後に落ち続けるのを防ぐ
+const MAX_ATTEMPTS: i64 = 3;
+/// 一度に拾う `running` 行の上限
+const RECOVERY_LIMIT: i64 = 500;
+const INTERRUPTED_ERROR: &str = "Interrupted by backend restart";
+
+/// ジョブの開始を記録する。記録に失敗してもジョブ自体は止めない。
+pub async fn begin(state: &AppState, id: &str, kind: &str, label: &str, payload: Value) {
+    let job = NewJob {
+        id: id.to_string(),
+        kind: kind.to_string(),
+        label: label.to_string(),
+        payload,
+    };
+    if let Err(err) = state.runtime().history.start_job(&job).await {
+        tracing::warn!(job_id = %id, kind, "Failed to record background job: {}", err);
+    }
+}
+
+/// ジョブの終了を記録する。`error` があれば失敗。
+pub async fn finish(state: &AppState, id: &str, error: Option<&str>) {
+    if let Err(err) = state.runtime().history.finish_job(id, error).await {
+        tracing::warn!(job_id = %id, "Failed to close background job: {}", err);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RecoveryReport {
+    pub resumed: Vec<String>,
+    pub failed: Vec<String>,
+    pub removed_partials: usize,
+}
+
+/// 前回のプロセスで終わらなかったジョブを再開するか失敗にする。起動時に 1 回だけ呼ぶ。
+pub async fn recover_interrupted_jobs(state: &Arc<AppState>) -> RecoveryReport {
+    let mut report = RecoveryReport::default();
+    let history = &state.runtime().history;
+    let jobs = match history.list_jobs(Some(JOB_RUNNING), RECOVERY_LIMIT).await {
+        Ok(jobs) => jobs,
+        Err(err) => {
+            tracing::warn!("Failed to load interrupted background jobs: {}", err);
+            Vec::new()
+        }
+    };
+
+    let mut resumable = Vec::new();
+    let mut interrupted = Vec::new();
+    for job in jobs {
+        if job.kind == KIND_MODEL_DOWNLOAD && job.attempts < MAX_ATTEMPTS {
+            if let Some(tasks) = resumable_downloads(state, &job.payload) {
+                resumable.push((job, tasks));
+                continue;
+            }
+        }
+        interrupted.push(job);
+    }
+
+    // 再開するダウンロードの `.part` は続きから書くので残し、それ以外を消す
+    let keep: HashSet<PathBuf> = resumable
+        .iter()
+        .flat_map(|(_, tasks)| tasks)
+        .filter_map(|task| {
+            state
+                .ai()
+                .models
+                .partial_download_path(&task.modality, &task.filename)
+        })
+        .collect();
+    report.removed_partials = remove_partial_downloads(&state.core().paths.user_data_dir, &keep);
+
+    for (job, tasks) in resumable {
+        if let Err(err) = history.retry_job(&job.id).await {
+            tracing::warn!(job_id = %job.id, "Failed to record job retry: {}", err);
+        }
+        tracing::info!(job_id = %job.id, attempt = job.attempts + 1, "Resuming interrupted download");
+        let _ = state.core().setup.set_job_id(Some(job.id.clone()));
+        let _ =
+            state
+                .core()
+                .setup
+                .update_progress("pending", 0.0, "Resuming interrupted download...");
+        tokio::spawn(run_download_job(
+            AppStateWrite::from_ref(state),
+            tasks,
+            job.id.clone(),
+        ));
+        report.resumed.push(job.id);
+    }
+
+    for job in interrupted {
+        finish(state, &job.id, Some(INTERRUPTED_ERROR)).await;
+        if job.kind == KIND_MEMORY_COMPACTION {
+            if let Some(session_id) = job.payload.get("session_id").and_then(Value::as_str) {
+                state
+                    .memory()
+                    .memory_service
+                    .fail_compaction_job(session_id, &job.id)
+                    .await;
+            }
+        }
+        tracing::warn!(job_id = %job.id, kind = %job.kind, "Marked interrupted background job as failed");
+        report.failed.push(job.label);
+    }
+
+    clear_stale_setup_job(state, &report.resumed);
+
+    if !report.failed.is_empty() {
+        let item = NewInboxItem {
+            kind: "job_recovery".to_string(),
+            title: "Background jobs were interrupted".to_string(),
+            summary: format!(
+                "{} job(s) did not finish before the backend stopped: {}",
+                report.failed.len(),
+                report.failed.join(", ")
+            ),
+            success: false,
+            ..NewInboxItem::default()
+        };
+        if let Err(err) = state.runtime().inbox.deposit(item).await {
+            tracing::warn!("Failed to add job recovery to inbox: {}", err);
+        }
+    }
+    report
+}
+
+/// 記録した入力から再開できるダウンロードを組み立てる。ロックダウン中は再開しない。
+fn resumable_downloads(state: &AppState, payload: &Value) -> Option<Vec<DownloadTask>> {
+    if state.core().security.is_lockdown_enabled() {
+        return None;
+    }
+    let tasks: Vec<DownloadTask> = serde_json::from_value(payload.get("tasks")?.clone()).ok()?;
+    (!tasks.is_empty()).then_some(tasks)
+}
+
+/// 再開しなかったジョブの ID が `SetupState` に残っていれば外し、進捗を失敗にする。
+fn clear_stale_setup_job(state: &AppState, resumed: &[String]) {
+    let setup = &state.core().setup;
+    let Ok(snapshot) = setup.snapshot() else {
+        return;
+    };
+    let Some(job_id) = snapshot.job_id else {
+        return;
+    };
+    if resumed.contains(&job_id) {
+        return;
+    }
+    let _ = setup.set_job_id(None);
+    if !matches!(
+        snapshot.progress.status.as_str(),
+        "idle" | "completed" | "failed"
+    ) {
+        let _ = setup.update_progress("failed", 0.0, INTERRUPTED_ERROR);
+    }
+}
+
+/// モデルとバイナリの置き場に残った書きかけの `.part` を消し、消した数を返す。
+/// 起動直後は書き込み中のダウンロードが無いので、`keep` に無いものはすべて孤児。
+pub fn remove_partial_downloads(user_data_dir: &Path, keep: &HashSet<PathBuf>) -> usize {
+    fn sweep(dir: &Path, keep: &HashSet<PathBuf>) -> usize {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return 0;
+        };
+        let mut removed = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                removed += sweep(&path, keep);
+            } else if path.extension().is_some_and(|ext| ext == "part") && !keep.contains(&path) {
+                match std::fs::remove_file(&path) {
+                    Ok(()) => removed += 1,
+                    Err(err) => tracing::warn!(
+                        path = %path.display(),
+                        "Failed to remove partial download: {}",
+                        err
+                    ),
+                }
+            }
+        }
+        removed
+    }
+    sweep(&user_data_dir.join("models"), keep) + sweep(&user_data_dir.join("bin"), keep)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_downloads_are_removed_from_model_and_binary_dirs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let models = root.join("models").join("text");
+        let downloads = root.join("bin").join("llama.cpp").join("downloads");
+        std::fs::create_dir_all(&models).unwrap();
+        std::fs::create_dir_all(&downloads).unwrap();
+        std::fs::write(models.join("model.gguf"), b"ok").unwrap();
+        std::fs::write(models.join("other.gguf.part"), b"half").unwrap();
+        std::fs::write(downloads.join("llama.zip.part"), b"half").unwrap();
+        std::fs::write(root.join("notes.part"), b"unrelated").unwrap();
+
+        assert_eq!(remove_partial_downloads(root, &HashSet::new()), 2);
+        assert!(models.join("model.gguf").exists());
+        assert!(!models.join("other.gguf.part").exists());
+        assert!(!downloads.join("llama.zip.part").exists());
+        assert!(root.join("notes.part").exists());
+    }
+
+    #[test]
+    fn partial_downloads_being_resumed_are_kept() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let models = root.join("models").join("text");
+        std::fs::create_dir_all(&models).unwrap();
+        let resumed = models.join("resumed.gguf.part");
+        std::fs::write(&resumed, b"half").unwrap();
+        std::fs::write(models.join("orphan.gguf.part"), b"half").unwrap();
+
+        let keep = HashSet::from([resumed.clone()]);
+        assert_eq!(remove_partial_downloads(root, &keep), 1);
+        assert!(resumed.exists());
+        assert!(!models.join("orphan.gguf.part").exists());
+    }
+}
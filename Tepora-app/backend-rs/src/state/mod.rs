@@ -4,12 +4,21 @@ use std::sync::Arc;
 use axum::extract::FromRef;
 
 use crate::actor::ActorManager;
+use crate::agent::exclusive::ExclusiveAgentManager;
+use crate::agent::plan::PlanEdits;
 use crate::agent::skill_registry::SkillRegistry;
 use crate::application::episodic_memory::EpisodicMemoryUseCase;
 use crate::application::knowledge::KnowledgeUseCase;
+use crate::core::chat_queue::ChatQueue;
 use crate::core::config::{AppPaths, ConfigService};
+use crate::core::desktop_bridge::DesktopBridge;
+use crate::core::health::HealthMonitor;
+use crate::core::inbox::Inbox;
+use crate::core::notifications::NotificationHub;
 use crate::core::security::SessionToken;
 use crate::core::security_controls::SecurityControls;
+use crate::core::snapshots::IntegrityReport;
+use crate::core::utilization::UtilizationMonitor;
 use crate::domain::episodic_memory::EpisodicMemoryPort;
 use crate::domain::knowledge::KnowledgePort;
 use crate::graph::GraphRuntime;
@@ -19,12 +28,19 @@ use crate::mcp::registry::McpRegistry;
 use crate::mcp::McpManager;
 use crate::memory::MemoryService;
 use crate::models::ModelManager;
+use crate::plugins::PluginManager;
+use crate::rag::SessionIndex;
+use crate::scripting::ScriptManager;
 use crate::server::middleware::rate_limit::RateLimiters;
 use crate::workspace::{ProjectHistoryStore, WorkspaceManager};
 
 mod bootstrap;
+pub mod diagnostics;
 pub mod error;
+pub mod health;
+pub mod jobs;
 pub mod setup;
+pub mod utilization;
 
 use setup::SetupState;
 
@@ -182,6 +198,12 @@ pub struct AppCoreState {
     pub session_token: Arc<tokio::sync::RwLock<SessionToken>>,
     pub setup: SetupState,
     pub security: Arc<SecurityControls>,
+    pub notifications: NotificationHub,
+    pub health: HealthMonitor,
+    /// CPU・GPU 使用率の採取と配信
+    pub utilization: UtilizationMonitor,
+    /// 起動時の設定ファイル確認とスナップショットからの復元
+    pub integrity: IntegrityReport,
 }
 
 #[derive(Clone)]
@@ -190,12 +212,18 @@ pub struct AppAiState {
     pub llm: LlmService,
     pub models: ModelManager,
     pub skill_registry: SkillRegistry,
+    pub exclusive_agents: ExclusiveAgentManager,
 }
 
 #[derive(Clone)]
 pub struct AppIntegrationState {
     pub mcp: McpManager,
     pub mcp_registry: McpRegistry,
+    pub plugins: PluginManager,
+    /// ユーザーの Rhai スクリプト（フック）
+    pub scripts: ScriptManager,
+    /// Tauri シェルへのクリップボード・画面取得要求
+    pub desktop: DesktopBridge,
 }
 
 #[derive(Clone)]
@@ -204,6 +232,12 @@ pub struct AppRuntimeState {
     pub graph_runtime: Arc<GraphRuntime>,
     pub rate_limiters: Arc<RateLimiters>,
     pub actor_manager: Arc<ActorManager>,
+    /// バックグラウンド実行の結果置き場
+    pub inbox: Inbox,
+    /// セッションごとの生成待ち行列
+    pub chat_queue: ChatQueue,
+    /// 実行前の計画に対するユーザー編集の受付
+    pub plan_edits: PlanEdits,
 }
 
 #[derive(Clone)]
@@ -214,6 +248,8 @@ pub struct AppMemoryState {
     pub knowledge: Arc<dyn KnowledgePort>,
     pub episodic_memory_use_case: Arc<EpisodicMemoryUseCase>,
     pub knowledge_use_case: Arc<KnowledgeUseCase>,
+    /// セッションごとの会話ベクトル（意味でのセッション検索）
+    pub session_index: Arc<SessionIndex>,
 }
 
 #[derive(Clone)]
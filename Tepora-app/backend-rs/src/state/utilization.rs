@@ -0,0 +1,44 @@
+//! 使用率の採取と、購読している WebSocket への定期配信。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+
+use crate::core::utilization::UtilizationSnapshot;
+
+use super::AppState;
+
+const UTILIZATION_STREAM_INTERVAL: Duration = Duration::from_secs(2);
+
+pub async fn sample_utilization(state: &AppState) -> UtilizationSnapshot {
+    let llama_server = state.ai().llama.running_process().await;
+    state.core().utilization.sample(llama_server).await
+}
+
+/// 配信を購読する。購読者がいる間だけ採取タスクが動き、全員が抜けたら止まる。
+pub fn subscribe_utilization(state: &Arc<AppState>) -> broadcast::Receiver<UtilizationSnapshot> {
+    let monitor = &state.core().utilization;
+    let receiver = monitor.subscribe();
+    if monitor.try_start_stream() {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let monitor = state.core().utilization.clone();
+            let mut ticker = tokio::time::interval(UTILIZATION_STREAM_INTERVAL);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                ticker.tick().await;
+                if !monitor.has_subscribers() {
+                    monitor.stream_stopped();
+                    // 止めた直後に購読した接続がいれば、このタスクが引き続き配信する
+                    if monitor.has_subscribers() && monitor.try_start_stream() {
+                        continue;
+                    }
+                    return;
+                }
+                monitor.publish(sample_utilization(&state).await);
+            }
+        });
+    }
+    receiver
+}
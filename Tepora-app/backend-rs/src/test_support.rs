@@ -1,3 +1,77 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
 use parking_lot::Mutex;
+use tempfile::{tempdir, TempDir};
+
+use crate::state::AppState;
 
 pub static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+/// 設定した環境変数を、落とすときに元の値へ戻す。`ENV_LOCK` を持ったまま使う。
+#[derive(Default)]
+pub struct EnvGuard {
+    originals: Vec<(String, Option<String>)>,
+}
+
+impl EnvGuard {
+    pub fn set_var(&mut self, key: &str, value: impl AsRef<str>) {
+        let key_string = key.to_string();
+        if !self
+            .originals
+            .iter()
+            .any(|(existing, _)| existing == &key_string)
+        {
+            self.originals
+                .push((key_string.clone(), env::var(&key_string).ok()));
+        }
+        env::set_var(key, value.as_ref());
+    }
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        for (key, previous) in self.originals.iter().rev() {
+            match previous {
+                Some(value) => env::set_var(key, value),
+                None => env::remove_var(key),
+            }
+        }
+    }
+}
+
+/// 一時ディレクトリに `config` を書いて `AppState` を起こす。
+pub async fn init_state_with_config(config: &str) -> (TempDir, EnvGuard, Arc<AppState>) {
+    let sandbox = tempdir().expect("failed to create tempdir");
+    let project_root = sandbox.path().join("project");
+    let data_dir = sandbox.path().join("data");
+    fs::create_dir_all(&project_root).expect("failed to create project root");
+    fs::create_dir_all(&data_dir).expect("failed to create data dir");
+
+    let config_path: PathBuf = project_root.join("config.yml");
+    fs::write(&config_path, config).expect("failed to write config");
+
+    let mut env_guard = EnvGuard::default();
+    env_guard.set_var("TEPORA_ROOT", project_root.to_string_lossy());
+    env_guard.set_var("TEPORA_DATA_DIR", data_dir.to_string_lossy());
+    env_guard.set_var("TEPORA_CONFIG_PATH", config_path.to_string_lossy());
+
+    let state = AppState::initialize()
+        .await
+        .expect("AppState should initialize for tests");
+
+    (sandbox, env_guard, state)
+}
+
+/// ルーター全体を空いているポートで立ち上げ、そのアドレスを返す。
+pub async fn serve_router(state: Arc<AppState>) -> std::net::SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let app = crate::server::router(state);
+    tokio::spawn(async move {
+        let _ = axum::serve(listener, app).await;
+    });
+    address
+}
@@ -0,0 +1,782 @@
+//! `calculate` — deterministic arithmetic, unit conversion and date math.
+//!
+//! 小さいモデルに計算を推測させないためのツール。`eval` は使わず、
+//! 四則演算・関数・定数だけを解釈する再帰下降パーサで評価する。
+//!
+//! - `2 * (3 + 4) ^ 2`, `sqrt(2)`, `10!`
+//! - `5 km to mi`, `72 f in c`, `1.5 GiB to MB`
+//! - `2026-10-15 + 3 weeks`, `days between 2026-01-01 and 2026-12-25`
+
+use std::sync::LazyLock;
+
+use chrono::{Datelike, Days, Local, Months, NaiveDate};
+use regex::Regex;
+use serde_json::Value;
+
+use crate::core::errors::ApiError;
+
+use super::dispatcher::ToolExecution;
+
+const MAX_EXPRESSION_LEN: usize = 500;
+const MAX_DEPTH: usize = 64;
+
+pub fn execute_calculate(args: &Value) -> Result<ToolExecution, ApiError> {
+    let expression = args
+        .get("expression")
+        .or_else(|| args.get("expr"))
+        .or_else(|| args.get("input"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| ApiError::BadRequest("expression missing".to_string()))?;
+    let output = calculate(expression, Local::now().date_naive())?;
+    Ok(ToolExecution {
+        output,
+        search_results: None,
+        images: Vec::new(),
+    })
+}
+
+/// 日付計算 → 単位変換 → 数式の順に解釈を試みる。
+pub fn calculate(expression: &str, today: NaiveDate) -> Result<String, ApiError> {
+    if expression.len() > MAX_EXPRESSION_LEN {
+        return Err(ApiError::BadRequest(format!(
+            "expression is longer than {} characters",
+            MAX_EXPRESSION_LEN
+        )));
+    }
+    if let Some(result) = date_math(expression, today)? {
+        return Ok(result);
+    }
+    if let Some(result) = convert_units(expression)? {
+        return Ok(result);
+    }
+    let value = evaluate(expression)?;
+    Ok(format!("{} = {}", expression.trim(), format_number(value)))
+}
+
+fn invalid(message: impl Into<String>) -> ApiError {
+    ApiError::BadRequest(message.into())
+}
+
+pub fn format_number(value: f64) -> String {
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    if value.abs() >= 1e15 || value.abs() < 1e-6 {
+        return format!("{:e}", value);
+    }
+    let rounded = format!("{:.10}", value);
+    rounded
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+// --- 数式 ---
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ApiError> {
+    let chars = input.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '0'..='9' | '.' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_ascii_digit() || matches!(chars[i], '.' | '_'))
+                {
+                    i += 1;
+                }
+                // 指数表記 (1e-3)
+                if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                    let mut j = i + 1;
+                    if j < chars.len() && matches!(chars[j], '+' | '-') {
+                        j += 1;
+                    }
+                    if j < chars.len() && chars[j].is_ascii_digit() {
+                        i = j;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                let text = chars[start..i]
+                    .iter()
+                    .filter(|c| **c != '_')
+                    .collect::<String>();
+                let value = text
+                    .parse::<f64>()
+                    .map_err(|_| invalid(format!("invalid number '{}'", text)))?;
+                tokens.push(Token::Num(value));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(
+                    chars[start..i].iter().collect::<String>().to_lowercase(),
+                ));
+            }
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                tokens.push(Token::Op('^'));
+                i += 2;
+            }
+            '+' | '-' | '*' | '/' | '%' | '^' | '!' => {
+                tokens.push(Token::Op(c));
+                i += 1;
+            }
+            '×' => {
+                tokens.push(Token::Op('*'));
+                i += 1;
+            }
+            '÷' => {
+                tokens.push(Token::Op('/'));
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            other => return Err(invalid(format!("unexpected character '{}'", other))),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_op(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), ApiError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(invalid("expression is nested too deeply"));
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self) -> Result<f64, ApiError> {
+        self.enter()?;
+        let mut value = self.term()?;
+        loop {
+            if self.eat_op('+') {
+                value += self.term()?;
+            } else if self.eat_op('-') {
+                value -= self.term()?;
+            } else {
+                break;
+            }
+        }
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<f64, ApiError> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat_op('*') {
+                value *= self.unary()?;
+            } else if self.eat_op('/') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err(invalid("division by zero"));
+                }
+                value /= divisor;
+            } else if self.eat_op('%') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err(invalid("division by zero"));
+                }
+                value %= divisor;
+            } else {
+                break;
+            }
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<f64, ApiError> {
+        if self.eat_op('-') {
+            self.enter()?;
+            let value = -self.unary()?;
+            self.depth -= 1;
+            return Ok(value);
+        }
+        if self.eat_op('+') {
+            self.enter()?;
+            let value = self.unary()?;
+            self.depth -= 1;
+            return Ok(value);
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<f64, ApiError> {
+        let base = self.postfix()?;
+        if self.eat_op('^') {
+            self.enter()?;
+            let exponent = self.unary()?;
+            self.depth -= 1;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    fn postfix(&mut self) -> Result<f64, ApiError> {
+        let mut value = self.primary()?;
+        while self.eat_op('!') {
+            value = factorial(value)?;
+        }
+        Ok(value)
+    }
+
+    fn primary(&mut self) -> Result<f64, ApiError> {
+        match self.next() {
+            Some(Token::Num(value)) => Ok(value),
+            Some(Token::LParen) => {
+                let value = self.expr()?;
+                if self.next() != Some(Token::RParen) {
+                    return Err(invalid("missing closing parenthesis"));
+                }
+                Ok(value)
+            }
+            Some(Token::Ident(name)) => {
+                if self.peek() == Some(&Token::LParen) {
+                    self.pos += 1;
+                    let mut args = Vec::new();
+                    if self.peek() != Some(&Token::RParen) {
+                        loop {
+                            args.push(self.expr()?);
+                            if self.peek() == Some(&Token::Comma) {
+                                self.pos += 1;
+                                continue;
+                            }
+                            break;
+                        }
+                    }
+                    if self.next() != Some(Token::RParen) {
+                        return Err(invalid("missing closing parenthesis"));
+                    }
+                    call_function(&name, &args)
+                } else {
+                    constant(&name)
+                }
+            }
+            Some(token) => Err(invalid(format!("unexpected token {:?}", token))),
+            None => Err(invalid("unexpected end of expression")),
+        }
+    }
+}
+
+fn constant(name: &str) -> Result<f64, ApiError> {
+    match name {
+        "pi" | "π" => Ok(std::f64::consts::PI),
+        "e" => Ok(std::f64::consts::E),
+        "tau" => Ok(std::f64::consts::TAU),
+        _ => Err(invalid(format!("unknown identifier '{}'", name))),
+    }
+}
+
+fn call_function(name: &str, args: &[f64]) -> Result<f64, ApiError> {
+    let unary = |f: fn(f64) -> f64| -> Result<f64, ApiError> {
+        match args {
+            [x] => Ok(f(*x)),
+            _ => Err(invalid(format!("{}() takes one argument", name))),
+        }
+    };
+    match name {
+        "sqrt" => unary(f64::sqrt),
+        "cbrt" => unary(f64::cbrt),
+        "abs" => unary(f64::abs),
+        "ln" => unary(f64::ln),
+        "log" | "log10" => unary(f64::log10),
+        "log2" => unary(f64::log2),
+        "exp" => unary(f64::exp),
+        "sin" => unary(f64::sin),
+        "cos" => unary(f64::cos),
+        "tan" => unary(f64::tan),
+        "asin" => unary(f64::asin),
+        "acos" => unary(f64::acos),
+        "atan" => unary(f64::atan),
+        "floor" => unary(f64::floor),
+        "ceil" => unary(f64::ceil),
+        "round" => match args {
+            [x] => Ok(x.round()),
+            [x, digits] => {
+                let scale = 10f64.powi(digits.clamp(0.0, 15.0) as i32);
+                Ok((x * scale).round() / scale)
+            }
+            _ => Err(invalid("round() takes one or two arguments")),
+        },
+        "pow" => match args {
+            [x, y] => Ok(x.powf(*y)),
+            _ => Err(invalid("pow() takes two arguments")),
+        },
+        "min" | "max" if !args.is_empty() => {
+            let fold: fn(f64, f64) -> f64 = if name == "min" { f64::min } else { f64::max };
+            Ok(args.iter().copied().fold(args[0], fold))
+        }
+        "fact" | "factorial" => match args {
+            [x] => factorial(*x),
+            _ => Err(invalid("factorial() takes one argument")),
+        },
+        _ => Err(invalid(format!("unknown function '{}'", name))),
+    }
+}
+
+fn factorial(value: f64) -> Result<f64, ApiError> {
+    if value < 0.0 || value.fract() != 0.0 || value > 170.0 {
+        return Err(invalid("factorial needs an integer between 0 and 170"));
+    }
+    Ok((1..=value as u64).fold(1.0, |acc, n| acc * n as f64))
+}
+
+pub fn evaluate(expression: &str) -> Result<f64, ApiError> {
+    let tokens = tokenize(expression)?;
+    if tokens.is_empty() {
+        return Err(invalid("expression missing"));
+    }
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.expr()?;
+    if let Some(token) = parser.peek() {
+        return Err(invalid(format!("unexpected token {:?}", token)));
+    }
+    if !value.is_finite() {
+        return Err(invalid("result is not a finite number"));
+    }
+    Ok(value)
+}
+
+// --- 単位変換 ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Mass,
+    Time,
+    Data,
+    Volume,
+    Area,
+    Speed,
+    Temperature,
+}
+
+struct Unit {
+    names: &'static [&'static str],
+    dimension: Dimension,
+    /// 基準単位への係数（温度は別扱い）
+    factor: f64,
+}
+
+impl Unit {
+    const fn new(names: &'static [&'static str], dimension: Dimension, factor: f64) -> Self {
+        Self {
+            names,
+            dimension,
+            factor,
+        }
+    }
+}
+
+const UNITS: &[Unit] = &[
+    Unit::new(
+        &["m", "meter", "meters", "metre", "metres"],
+        Dimension::Length,
+        1.0,
+    ),
+    Unit::new(
+        &["km", "kilometer", "kilometers"],
+        Dimension::Length,
+        1000.0,
+    ),
+    Unit::new(
+        &["cm", "centimeter", "centimeters"],
+        Dimension::Length,
+        0.01,
+    ),
+    Unit::new(
+        &["mm", "millimeter", "millimeters"],
+        Dimension::Length,
+        0.001,
+    ),
+    Unit::new(&["mi", "mile", "miles"], Dimension::Length, 1609.344),
+    Unit::new(&["yd", "yard", "yards"], Dimension::Length, 0.9144),
+    Unit::new(&["ft", "foot", "feet"], Dimension::Length, 0.3048),
+    Unit::new(&["in", "inch", "inches"], Dimension::Length, 0.0254),
+    Unit::new(&["nmi", "nautical_mile"], Dimension::Length, 1852.0),
+    Unit::new(&["kg", "kilogram", "kilograms"], Dimension::Mass, 1.0),
+    Unit::new(&["g", "gram", "grams"], Dimension::Mass, 0.001),
+    Unit::new(&["mg", "milligram", "milligrams"], Dimension::Mass, 1e-6),
+    Unit::new(&["t", "tonne", "tonnes"], Dimension::Mass, 1000.0),
+    Unit::new(
+        &["lb", "lbs", "pound", "pounds"],
+        Dimension::Mass,
+        0.453_592_37,
+    ),
+    Unit::new(
+        &["oz", "ounce", "ounces"],
+        Dimension::Mass,
+        0.028_349_523_125,
+    ),
+    Unit::new(&["s", "sec", "second", "seconds"], Dimension::Time, 1.0),
+    Unit::new(
+        &["ms", "millisecond", "milliseconds"],
+        Dimension::Time,
+        0.001,
+    ),
+    Unit::new(&["min", "minute", "minutes"], Dimension::Time, 60.0),
+    Unit::new(&["h", "hr", "hour", "hours"], Dimension::Time, 3600.0),
+    Unit::new(&["d", "day", "days"], Dimension::Time, 86_400.0),
+    Unit::new(&["week", "weeks"], Dimension::Time, 604_800.0),
+    Unit::new(&["bit", "bits"], Dimension::Data, 0.125),
+    Unit::new(&["b", "byte", "bytes"], Dimension::Data, 1.0),
+    Unit::new(&["kb"], Dimension::Data, 1e3),
+    Unit::new(&["mb"], Dimension::Data, 1e6),
+    Unit::new(&["gb"], Dimension::Data, 1e9),
+    Unit::new(&["tb"], Dimension::Data, 1e12),
+    Unit::new(&["kib"], Dimension::Data, 1024.0),
+    Unit::new(&["mib"], Dimension::Data, 1_048_576.0),
+    Unit::new(&["gib"], Dimension::Data, 1_073_741_824.0),
+    Unit::new(&["tib"], Dimension::Data, 1_099_511_627_776.0),
+    Unit::new(
+        &["l", "liter", "liters", "litre", "litres"],
+        Dimension::Volume,
+        1.0,
+    ),
+    Unit::new(
+        &["ml", "milliliter", "milliliters"],
+        Dimension::Volume,
+        0.001,
+    ),
+    Unit::new(
+        &["gal", "gallon", "gallons"],
+        Dimension::Volume,
+        3.785_411_784,
+    ),
+    Unit::new(&["floz", "fl_oz"], Dimension::Volume, 0.029_573_529_562_5),
+    Unit::new(&["cup", "cups"], Dimension::Volume, 0.236_588_236_5),
+    Unit::new(&["m2", "sqm"], Dimension::Area, 1.0),
+    Unit::new(&["km2", "sqkm"], Dimension::Area, 1e6),
+    Unit::new(&["ha", "hectare", "hectares"], Dimension::Area, 1e4),
+    Unit::new(&["acre", "acres"], Dimension::Area, 4_046.856_422_4),
+    Unit::new(&["ft2", "sqft"], Dimension::Area, 0.092_903_04),
+    Unit::new(&["m/s", "mps"], Dimension::Speed, 1.0),
+    Unit::new(&["km/h", "kmh", "kph"], Dimension::Speed, 1.0 / 3.6),
+    Unit::new(&["mph"], Dimension::Speed, 0.447_04),
+    Unit::new(&["kn", "knot", "knots"], Dimension::Speed, 0.514_444),
+    Unit::new(&["c", "°c", "celsius"], Dimension::Temperature, 1.0),
+    Unit::new(&["f", "°f", "fahrenheit"], Dimension::Temperature, 1.0),
+    Unit::new(&["k", "kelvin"], Dimension::Temperature, 1.0),
+];
+
+fn find_unit(name: &str) -> Option<&'static Unit> {
+    let name = name.trim().to_lowercase();
+    UNITS
+        .iter()
+        .find(|unit| unit.names.contains(&name.as_str()))
+}
+
+fn to_kelvin(unit: &Unit, value: f64) -> f64 {
+    match unit.names[0] {
+        "c" => value + 273.15,
+        "f" => (value - 32.0) * 5.0 / 9.0 + 273.15,
+        _ => value,
+    }
+}
+
+fn from_kelvin(unit: &Unit, value: f64) -> f64 {
+    match unit.names[0] {
+        "c" => value - 273.15,
+        "f" => (value - 273.15) * 9.0 / 5.0 + 32.0,
+        _ => value,
+    }
+}
+
+static CONVERSION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(.+?)\s*([a-z°µ][a-z0-9°_/]*)\s+(?:to|in|as|into)\s+([a-z°µ][a-z0-9°_/]*)$")
+        .expect("valid regex")
+});
+
+fn convert_units(expression: &str) -> Result<Option<String>, ApiError> {
+    let Some(captures) = CONVERSION_RE.captures(expression.trim()) else {
+        return Ok(None);
+    };
+    let (Some(from), Some(to)) = (find_unit(&captures[2]), find_unit(&captures[3])) else {
+        return Ok(None);
+    };
+    if from.dimension != to.dimension {
+        return Err(invalid(format!(
+            "cannot convert {} to {}",
+            &captures[2], &captures[3]
+        )));
+    }
+    let quantity = evaluate(&captures[1])?;
+    let converted = if from.dimension == Dimension::Temperature {
+        from_kelvin(to, to_kelvin(from, quantity))
+    } else {
+        quantity * from.factor / to.factor
+    };
+    Ok(Some(format!(
+        "{} {} = {} {}",
+        format_number(quantity),
+        &captures[2],
+        format_number(converted),
+        &captures[3]
+    )))
+}
+
+// --- 日付計算 ---
+
+static DAYS_BETWEEN_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(days|weeks)\s+(?:between|from)\s+(.+?)\s+(?:and|to|until)\s+(.+)$")
+        .expect("valid regex")
+});
+static DATE_OFFSET_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\s*([+-])\s*(\d+)\s*(days?|weeks?|months?|years?)\b").expect("valid regex")
+});
+
+fn parse_date(raw: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match raw.trim().to_lowercase().as_str() {
+        "today" | "now" => Some(today),
+        "tomorrow" => today.succ_opt(),
+        "yesterday" => today.pred_opt(),
+        other => NaiveDate::parse_from_str(other, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(other, "%Y/%m/%d"))
+            .ok(),
+    }
+}
+
+fn render_date(date: NaiveDate) -> String {
+    format!("{} ({})", date.format("%Y-%m-%d"), date.weekday())
+}
+
+fn apply_offset(
+    date: NaiveDate,
+    sign: &str,
+    amount: u32,
+    unit: &str,
+) -> Result<NaiveDate, ApiError> {
+    let unit = unit.to_lowercase();
+    let forward = sign == "+";
+    let shifted = if unit.starts_with("day") || unit.starts_with("week") {
+        let days = Days::new(if unit.starts_with("week") {
+            amount as u64 * 7
+        } else {
+            amount as u64
+        });
+        if forward {
+            date.checked_add_days(days)
+        } else {
+            date.checked_sub_days(days)
+        }
+    } else {
+        let months = Months::new(if unit.starts_with("year") {
+            amount
+                .checked_mul(12)
+                .ok_or_else(|| invalid("date offset is too large"))?
+        } else {
+            amount
+        });
+        if forward {
+            date.checked_add_months(months)
+        } else {
+            date.checked_sub_months(months)
+        }
+    };
+    shifted.ok_or_else(|| invalid("date is out of range"))
+}
+
+fn date_math(expression: &str, today: NaiveDate) -> Result<Option<String>, ApiError> {
+    let trimmed = expression.trim();
+    if let Some(captures) = DAYS_BETWEEN_RE.captures(trimmed) {
+        let (Some(start), Some(end)) = (
+            parse_date(&captures[2], today),
+            parse_date(&captures[3], today),
+        ) else {
+            return Err(invalid(
+                "dates must be YYYY-MM-DD, today, tomorrow or yesterday",
+            ));
+        };
+        let days = (end - start).num_days();
+        let answer = if captures[1].eq_ignore_ascii_case("weeks") {
+            format_number(days as f64 / 7.0)
+        } else {
+            days.to_string()
+        };
+        return Ok(Some(format!(
+            "{} between {} and {}: {}",
+            captures[1].to_lowercase(),
+            render_date(start),
+            render_date(end),
+            answer
+        )));
+    }
+
+    // 先頭の日付を探し、残りを ± N 単位 の列（または別の日付との差）として解釈する
+    let head_end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+    let (head, mut rest) = trimmed.split_at(head_end);
+    let Some(mut date) = parse_date(head, today) else {
+        return Ok(None);
+    };
+
+    if let Some(other) = rest
+        .trim()
+        .strip_prefix('-')
+        .and_then(|tail| parse_date(tail, today))
+    {
+        return Ok(Some(format!(
+            "{} - {} = {} days",
+            render_date(date),
+            render_date(other),
+            (date - other).num_days()
+        )));
+    }
+
+    while !rest.trim().is_empty() {
+        let Some(captures) = DATE_OFFSET_RE.captures(rest) else {
+            return Err(invalid(
+                "date offsets look like '+ 3 days', '- 2 weeks' or '+ 1 month'",
+            ));
+        };
+        let whole = captures.get(0).expect("match");
+        if whole.start() != 0 {
+            return Err(invalid(format!("unexpected text '{}'", rest.trim())));
+        }
+        let amount = captures[2]
+            .parse::<u32>()
+            .map_err(|_| invalid("date offset is too large"))?;
+        date = apply_offset(date, &captures[1], amount, &captures[3])?;
+        rest = &rest[whole.end()..];
+    }
+    Ok(Some(render_date(date)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 15).unwrap()
+    }
+
+    #[test]
+    fn evaluates_arithmetic_with_precedence_and_functions() {
+        assert_eq!(evaluate("2 + 3 * 4").unwrap(), 14.0);
+        assert_eq!(evaluate("(2 + 3) * 4").unwrap(), 20.0);
+        assert_eq!(evaluate("-2 ^ 2").unwrap(), -4.0);
+        assert_eq!(evaluate("2 ** 3 ^ 2").unwrap(), 512.0);
+        assert_eq!(evaluate("5!").unwrap(), 120.0);
+        assert_eq!(evaluate("max(1, 7, 3) + sqrt(16)").unwrap(), 11.0);
+        assert_eq!(evaluate("round(2 / 3, 2)").unwrap(), 0.67);
+        assert_eq!(evaluate("1_000 * 1e-3").unwrap(), 1.0);
+        assert_eq!(format_number(0.1 + 0.2), "0.3");
+    }
+
+    #[test]
+    fn rejects_invalid_or_unsafe_input() {
+        assert!(evaluate("1 / 0").is_err());
+        assert!(evaluate("system(1)").is_err());
+        assert!(evaluate("2 +").is_err());
+        assert!(evaluate("(1").is_err());
+        assert!(evaluate(&format!("{}1{}", "(".repeat(100), ")".repeat(100))).is_err());
+        assert!(calculate(&"1+".repeat(300), today()).is_err());
+    }
+
+    #[test]
+    fn converts_units_within_a_dimension() {
+        assert_eq!(calculate("5 km to m", today()).unwrap(), "5 km = 5000 m");
+        assert_eq!(calculate("212 F in C", today()).unwrap(), "212 F = 100 C");
+        assert_eq!(
+            calculate("1 GiB to MB", today()).unwrap(),
+            "1 GiB = 1073.741824 MB"
+        );
+        assert_eq!(
+            calculate("2 * 30 min to h", today()).unwrap(),
+            "60 min = 1 h"
+        );
+        assert!(calculate("3 kg to m", today()).is_err());
+    }
+
+    #[test]
+    fn date_math_adds_offsets_and_counts_days() {
+        assert_eq!(
+            calculate("2026-10-15 + 3 weeks", today()).unwrap(),
+            "2026-11-05 (Thu)"
+        );
+        assert_eq!(
+            calculate("today + 1 month - 2 days", today()).unwrap(),
+            "2026-11-13 (Fri)"
+        );
+        assert_eq!(
+            calculate("2024-01-31 + 1 month", today()).unwrap(),
+            "2024-02-29 (Thu)"
+        );
+        assert_eq!(
+            calculate("days between 2026-01-01 and 2026-12-25", today()).unwrap(),
+            "days between 2026-01-01 (Thu) and 2026-12-25 (Fri): 358"
+        );
+        assert_eq!(
+            calculate("2026-12-25 - 2026-10-15", today()).unwrap(),
+            "2026-12-25 (Fri) - 2026-10-15 (Thu) = 71 days"
+        );
+    }
+
+    #[test]
+    fn huge_year_offsets_are_rejected() {
+        let err = calculate("today + 400000000 years", today()).unwrap_err();
+        assert!(err.to_string().contains("too large"), "{}", err);
+        let err = calculate("today - 4000000 years", today()).unwrap_err();
+        assert!(err.to_string().contains("out of range"), "{}", err);
+    }
+}
@@ -0,0 +1,241 @@
+//! アシスタント応答のコードブロック抽出。
+//!
+//! フェンス付きコードブロックを取り出し、書き込み先のファイル名が書かれていれば
+//! 拾う。ファイル名は次の順に探す:
+//! 1. 情報文字列（```` ```rust title="src/main.rs" ```` / ```` ```rust:src/main.rs ```` / ```` ```main.py ````）
+//! 2. 1 行目のコメント（`// src/main.rs`、`# file: app.py`、`<!-- index.html -->`）
+//! 3. 直前の行（`` `src/main.rs`: ``、`**File: src/main.rs**`、`### src/main.rs`）
+//!
+//! 書き込みは `POST /api/sessions/:id/apply-code` がファイルツール経由で行う。
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CodeBlock {
+    /// メッセージ内での順番（0 始まり）
+    pub index: usize,
+    pub language: Option<String>,
+    pub filename: Option<String>,
+    pub code: String,
+}
+
+/// Closed fenced blocks in `markdown`, in order. An unclosed trailing block
+/// (a reply cut off mid-block) is skipped so it cannot be applied half-written.
+pub fn extract_code_blocks(markdown: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut previous_line = "";
+    let mut lines = markdown.lines();
+    while let Some(line) = lines.next() {
+        let Some((fence, info)) = opening_fence(line) else {
+            if !line.trim().is_empty() {
+                previous_line = line;
+            }
+            continue;
+        };
+
+        let mut body = Vec::new();
+        let mut closed = false;
+        for inner in lines.by_ref() {
+            if is_closing_fence(inner, &fence) {
+                closed = true;
+                break;
+            }
+            body.push(inner);
+        }
+        if !closed {
+            break;
+        }
+
+        let (language, info_filename) = parse_info(info);
+        let code = if body.is_empty() {
+            String::new()
+        } else {
+            format!("{}\n", body.join("\n"))
+        };
+        let filename = info_filename
+            .or_else(|| body.first().and_then(|first| comment_filename(first)))
+            .or_else(|| prose_filename(previous_line));
+        blocks.push(CodeBlock {
+            index: blocks.len(),
+            language,
+            filename,
+            code,
+        });
+        previous_line = "";
+    }
+    blocks
+}
+
+fn opening_fence(line: &str) -> Option<(String, &str)> {
+    let trimmed = line.trim_start();
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let count = trimmed.chars().take_while(|c| *c == marker).count();
+    if count < 3 {
+        return None;
+    }
+    let info = &trimmed[count..];
+    // バッククォートのフェンスの情報文字列にはバッククォートを含められない
+    if marker == '`' && info.contains('`') {
+        return None;
+    }
+    Some((marker.to_string().repeat(count), info.trim()))
+}
+
+fn is_closing_fence(line: &str, fence: &str) -> bool {
+    let trimmed = line.trim();
+    let marker = fence.chars().next().unwrap_or('`');
+    trimmed.len() >= fence.len() && trimmed.chars().all(|c| c == marker)
+}
+
+fn path_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^(?:\./)?(?:[\w.@-]+[/\\])*[\w@-][\w.@-]*\.[A-Za-z0-9]{1,8}$")
+            .expect("valid path pattern")
+    })
+}
+
+fn as_path(candidate: &str) -> Option<String> {
+    let candidate = candidate
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'' || c == '`');
+    path_pattern()
+        .is_match(candidate)
+        .then(|| candidate.trim_start_matches("./").to_string())
+}
+
+fn parse_info(info: &str) -> (Option<String>, Option<String>) {
+    let mut tokens = info.split_whitespace();
+    let Some(first) = tokens.next() else {
+        return (None, None);
+    };
+    let mut filename = None;
+    let language = match first.split_once(':') {
+        Some((language, path)) => {
+            filename = as_path(path);
+            Some(language)
+        }
+        None => match as_path(first) {
+            Some(path) => {
+                filename = Some(path);
+                None
+            }
+            None => Some(first),
+        },
+    }
+    .filter(|language| !language.is_empty() && !language.contains('='))
+    .map(|language| language.to_ascii_lowercase());
+
+    for token in std::iter::once(first).chain(tokens) {
+        if let Some((key, value)) = token.split_once('=') {
+            if matches!(key, "title" | "file" | "filename" | "path") {
+                filename = filename.or_else(|| as_path(value));
+            }
+        }
+    }
+    (language, filename)
+}
+
+fn comment_filename(line: &str) -> Option<String> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        Regex::new(
+            r"(?i)^\s*(?://|#|--|;|<!--|/\*)\s*(?:(?:file(?:name)?|path)\s*:\s*)?(\S+?)\s*(?:-->|\*/)?\s*$",
+        )
+        .expect("valid comment pattern")
+    });
+    as_path(pattern.captures(line)?.get(1)?.as_str())
+}
+
+fn prose_filename(line: &str) -> Option<String> {
+    let stripped = line
+        .trim()
+        .trim_start_matches('#')
+        .trim()
+        .trim_matches(|c| c == '*' || c == '_')
+        .trim()
+        .trim_end_matches(':')
+        .trim_matches(|c| c == '*' || c == '_');
+    let stripped = ["File:", "file:", "Filename:", "ファイル:", "ファイル："]
+        .iter()
+        .find_map(|label| stripped.strip_prefix(label))
+        .unwrap_or(stripped)
+        .trim();
+    as_path(stripped.trim_end_matches(':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filenames_come_from_info_comment_or_preceding_line() {
+        let markdown = r#"Here is the fix.
+
+```rust title="src/main.rs"
+fn main() {}
+```
+
+`src/lib.rs`:
+
+```rust
+pub fn lib() {}
+```
+
+```python
+# app/server.py
+print("hi")
+```
+
+```rust:src/bin/tool.rs
+fn tool() {}
+```
+
+```Cargo.toml
+[package]
+```
+
+```sh
+cargo run
+```
+"#;
+        let blocks = extract_code_blocks(markdown);
+        let names = blocks
+            .iter()
+            .map(|block| (block.language.as_deref(), block.filename.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                (Some("rust"), Some("src/main.rs")),
+                (Some("rust"), Some("src/lib.rs")),
+                (Some("python"), Some("app/server.py")),
+                (Some("rust"), Some("src/bin/tool.rs")),
+                (None, Some("Cargo.toml")),
+                (Some("sh"), None),
+            ]
+        );
+        assert_eq!(blocks[0].code, "fn main() {}\n");
+        assert_eq!(blocks[2].code, "# app/server.py\nprint(\"hi\")\n");
+        assert_eq!(blocks[5].index, 5);
+    }
+
+    #[test]
+    fn prose_and_comments_that_are_not_paths_are_ignored() {
+        let markdown = "Run this:\n\n```python\n# install deps first\nimport os\n```\n\n**File: web/index.html**\n~~~~html\n<!-- page header -->\n```nested```\n~~~~\n";
+        let blocks = extract_code_blocks(markdown);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].filename, None);
+        assert_eq!(blocks[1].filename.as_deref(), Some("web/index.html"));
+        assert!(blocks[1].code.contains("```nested```"));
+    }
+
+    #[test]
+    fn unclosed_trailing_block_is_skipped() {
+        let blocks = extract_code_blocks("```js\nconsole.log(1)\n```\n\n```js\nconsole.lo");
+        assert_eq!(blocks.len(), 1);
+    }
+}
@@ -0,0 +1,122 @@
+//! `read_clipboard` / `screenshot` — デスクトップシェル経由のツール。
+//!
+//! 実際の取得と同意確認は [`DesktopBridge`] の向こう側（Tauri シェル）が行う。
+
+use serde_json::{json, Value};
+
+use crate::core::desktop_bridge::{DesktopBridge, DesktopCapability, DesktopReply};
+use crate::core::errors::ApiError;
+use crate::llm::types::ImageData;
+use crate::state::AppState;
+
+use super::dispatcher::ToolExecution;
+
+/// クリップボード本文をモデルに渡す上限
+const CLIPBOARD_MAX_CHARS: usize = 20_000;
+
+fn reason_arg(args: &Value) -> Option<String> {
+    args.get("reason")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty())
+        .map(|reason| reason.chars().take(200).collect())
+}
+
+/// 拒否・失敗を API エラーにそろえる。
+pub fn reply_error(reply: DesktopReply) -> ApiError {
+    match reply {
+        DesktopReply::Denied => ApiError::Forbidden,
+        DesktopReply::Failed { message } => ApiError::Internal(message),
+        _ => ApiError::internal("Unexpected desktop reply"),
+    }
+}
+
+fn record_desktop_audit(
+    state: &AppState,
+    capability: DesktopCapability,
+    session_id: Option<&str>,
+    result: &Result<DesktopReply, ApiError>,
+) {
+    let outcome = match result {
+        Ok(DesktopReply::Denied) => "denied",
+        Ok(DesktopReply::Failed { .. }) | Err(_) => "error",
+        Ok(_) => "success",
+    };
+    if let Err(err) = state.core().security.record_audit(
+        "desktop_capture",
+        outcome,
+        json!({"capability": capability, "session_id": session_id}),
+    ) {
+        tracing::warn!("Failed to record desktop audit entry: {}", err);
+    }
+}
+
+/// ブリッジへ要求して監査ログを残す。HTTP ハンドラからも使う。
+pub async fn request_desktop(
+    state: &AppState,
+    bridge: &DesktopBridge,
+    capability: DesktopCapability,
+    reason: Option<String>,
+    session_id: Option<&str>,
+) -> Result<DesktopReply, ApiError> {
+    let result = bridge
+        .request(capability, reason, session_id.map(str::to_string))
+        .await;
+    record_desktop_audit(state, capability, session_id, &result);
+    match result? {
+        reply @ (DesktopReply::ClipboardText { .. } | DesktopReply::Screenshot { .. }) => Ok(reply),
+        other => Err(reply_error(other)),
+    }
+}
+
+pub async fn execute_desktop_tool(
+    state: Option<&AppState>,
+    session_id: Option<&str>,
+    capability: DesktopCapability,
+    args: &Value,
+) -> Result<ToolExecution, ApiError> {
+    let state = state.ok_or_else(|| {
+        ApiError::ServiceUnavailable("Desktop tools require application state".to_string())
+    })?;
+    let reply = request_desktop(
+        state,
+        &state.integration().desktop,
+        capability,
+        reason_arg(args),
+        session_id,
+    )
+    .await?;
+
+    match reply {
+        DesktopReply::ClipboardText { text } => {
+            let output = match text.char_indices().nth(CLIPBOARD_MAX_CHARS) {
+                Some((index, _)) => format!(
+                    "{}\n\n[truncated: clipboard exceeded {} characters]",
+                    &text[..index],
+                    CLIPBOARD_MAX_CHARS
+                ),
+                None if text.trim().is_empty() => "Clipboard is empty.".to_string(),
+                None => text,
+            };
+            Ok(ToolExecution {
+                output,
+                search_results: None,
+                images: Vec::new(),
+            })
+        }
+        DesktopReply::Screenshot {
+            mime_type,
+            base64,
+            width,
+            height,
+        } => Ok(ToolExecution {
+            output: format!(
+                "Captured a {}x{} screenshot. The image is attached to this observation.",
+                width, height
+            ),
+            search_results: None,
+            images: vec![ImageData { mime_type, base64 }],
+        }),
+        other => Err(reply_error(other)),
+    }
+}
@@ -1,20 +1,23 @@
+use std::time::{Duration, Instant};
+
 use serde_json::Value;
 
+use crate::agent::policy::CustomToolPolicy;
 use crate::core::errors::ApiError;
+use crate::core::native_tools::{native_tool_capability, resolve_tool_alias};
 use crate::mcp::McpManager;
 use crate::state::AppState;
 
-use super::rag::{
-    execute_rag_clear_session, execute_rag_get_chunk, execute_rag_get_chunk_window,
-    execute_rag_ingest, execute_rag_reindex, execute_rag_search, execute_rag_text_search,
-};
-use super::web::{execute_search, execute_web_fetch};
+use super::http_api::{execute_http_tool_audited, find_http_tool};
+use super::registry::{self, ToolCall};
 use super::web_security::is_isolation_mode;
 
 #[derive(Debug, Clone)]
 pub struct ToolExecution {
     pub output: String,
     pub search_results: Option<Vec<super::search::SearchResult>>,
+    /// モデルに画像として渡す結果（スクリーンショット等）
+    pub images: Vec<crate::llm::types::ImageData>,
 }
 
 pub async fn execute_tool(
@@ -25,40 +28,148 @@ pub async fn execute_tool(
     tool_name: &str,
     args: &Value,
 ) -> Result<ToolExecution, ApiError> {
-    match tool_name {
-        "native_web_fetch" | "native_fetch" | "web_fetch" => execute_web_fetch(config, args).await,
-        "native_google_search" | "native_duckduckgo" | "native_search" | "search" => {
-            execute_search(config, args).await
-        }
-        "rag_search" | "native_rag_search" => {
-            execute_rag_search(state, config, session_id, args).await
-        }
-        "rag_ingest" | "native_rag_ingest" => execute_rag_ingest(state, session_id, args).await,
-        "rag_text_search" | "native_rag_text_search" => {
-            execute_rag_text_search(state, config, session_id, args).await
-        }
-        "rag_get_chunk" | "native_rag_get_chunk" => execute_rag_get_chunk(state, args).await,
-        "rag_get_chunk_window" | "native_rag_get_chunk_window" => {
-            execute_rag_get_chunk_window(state, config, session_id, args).await
-        }
-        "rag_clear_session" | "native_rag_clear_session" => {
-            execute_rag_clear_session(state, session_id, args).await
-        }
-        "rag_reindex" | "native_rag_reindex" => execute_rag_reindex(state, args).await,
-        _ => {
-            if is_isolation_mode(config) {
-                return Err(ApiError::Forbidden);
-            }
-            if let Some(manager) = mcp {
-                let output = manager.execute_tool(tool_name, args).await?;
-                return Ok(ToolExecution {
-                    output,
-                    search_results: None,
-                });
+    let started = Instant::now();
+    let mut result = dispatch_tool(state, config, mcp, session_id, tool_name, args).await;
+    let elapsed = started.elapsed();
+    crate::graph::profiler::record_tool_call(elapsed);
+    if let Some(state) = state {
+        record_invocation(state, config, tool_name, &result, elapsed).await;
+        if let Ok(execution) = &mut result {
+            if let Some(output) = state
+                .integration()
+                .scripts
+                .after_tool_result(state, session_id, tool_name, &execution.output)
+                .await
+            {
+                execution.output = output;
             }
-            Err(ApiError::BadRequest(format!("Unknown tool: {}", tool_name)))
         }
     }
+    result
+}
+
+/// native / http / mcp の区別（統計用）
+fn tool_source(config: &Value, tool_name: &str) -> &'static str {
+    if native_tool_capability(tool_name).is_some() || tool_name.starts_with("native_") {
+        "native"
+    } else if find_http_tool(config, tool_name).is_some() {
+        "http"
+    } else if tool_name.starts_with(crate::plugins::TOOL_PREFIX) {
+        "plugin"
+    } else {
+        "mcp"
+    }
+}
+
+/// 呼び出し結果を統計に残す。存在しないツール名や権限拒否は
+/// 連携の故障ではないので数えない。
+async fn record_invocation(
+    state: &AppState,
+    config: &Value,
+    tool_name: &str,
+    result: &Result<ToolExecution, ApiError>,
+    elapsed: Duration,
+) {
+    let error = match result {
+        Ok(_) => None,
+        Err(ApiError::Forbidden) => return,
+        Err(ApiError::BadRequest(message)) if message.starts_with("Unknown tool") => return,
+        Err(err) => Some(err.to_string()),
+    };
+    let source = tool_source(config, tool_name);
+    let tool = if source == "native" {
+        resolve_tool_alias(tool_name)
+    } else {
+        tool_name.to_string()
+    };
+    if let Err(err) = state
+        .runtime()
+        .history
+        .record_tool_invocation(
+            &tool,
+            source,
+            error.is_none(),
+            elapsed.as_millis() as i64,
+            error.as_deref(),
+        )
+        .await
+    {
+        tracing::debug!("Failed to record tool invocation: {}", err);
+    }
+}
+
+async fn dispatch_tool(
+    state: Option<&AppState>,
+    config: &Value,
+    mcp: Option<&McpManager>,
+    session_id: Option<&str>,
+    tool_name: &str,
+    args: &Value,
+) -> Result<ToolExecution, ApiError> {
+    // `mcp:` 付きの名前は MCP サーバーのツールとして扱う
+    if !tool_name.starts_with("mcp:") {
+        if let Some(tool) = registry::find(&resolve_tool_alias(tool_name)) {
+            let Some(handler) = tool.handler else {
+                return Err(ApiError::BadRequest(format!(
+                    "{} is only available inside agent runs",
+                    tool.name
+                )));
+            };
+            return handler(ToolCall {
+                state,
+                config,
+                session_id,
+                args,
+            })
+            .await;
+        }
+    }
+
+    if is_isolation_mode(config) {
+        return Err(ApiError::Forbidden);
+    }
+    if let Some(tool) = find_http_tool(config, tool_name) {
+        return execute_http_tool_audited(state, config, session_id, &tool, args).await;
+    }
+    if tool_name.starts_with(crate::plugins::TOOL_PREFIX) {
+        let Some(state) = state else {
+            return Err(ApiError::BadRequest(format!("Unknown tool: {}", tool_name)));
+        };
+        let output = state.integration.plugins.call_tool(tool_name, args).await?;
+        return Ok(ToolExecution {
+            output,
+            search_results: None,
+            images: Vec::new(),
+        });
+    }
+    if let Some(manager) = mcp {
+        let output = manager.execute_tool(tool_name, args).await?;
+        return Ok(ToolExecution {
+            output,
+            search_results: None,
+            images: Vec::new(),
+        });
+    }
+    Err(ApiError::BadRequest(format!("Unknown tool: {}", tool_name)))
+}
+
+/// Dispatch a call made on behalf of an agent. The agent's tool policy and
+/// capability grants are checked here as well as in the calling node, so a
+/// hallucinated call cannot reach a native tool or MCP server it was not granted.
+pub async fn execute_tool_with_policy(
+    policy: &CustomToolPolicy,
+    state: Option<&AppState>,
+    config: &Value,
+    mcp: Option<&McpManager>,
+    session_id: Option<&str>,
+    tool_name: &str,
+    args: &Value,
+) -> Result<ToolExecution, ApiError> {
+    if !policy.is_tool_allowed(tool_name) {
+        tracing::warn!("Blocked tool call outside agent policy: {}", tool_name);
+        return Err(ApiError::Forbidden);
+    }
+    execute_tool(state, config, mcp, session_id, tool_name, args).await
 }
 
 #[cfg(test)]
@@ -88,4 +199,21 @@ mod tests {
         .await;
         assert!(matches!(result, Err(ApiError::Forbidden)));
     }
+
+    #[tokio::test]
+    async fn policy_blocks_tools_before_dispatch() {
+        let mut policy = CustomToolPolicy::allow_all_policy();
+        policy.grants.network = false;
+        let result = execute_tool_with_policy(
+            &policy,
+            None,
+            &json!({}),
+            None,
+            None,
+            "web_search",
+            &json!({"query": "x"}),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::Forbidden)));
+    }
 }
@@ -0,0 +1,683 @@
+//! RSS/Atom フィード。
+//!
+//! `fetch_feed` ツールは単一 URL か、`tools.feeds.subscriptions` の購読一覧を
+//! まとめて取得し、出典付きの項目一覧を返す。`tools.feeds.ingest.enabled` が
+//! true のときはバックグラウンドで定期的に新着項目を RAG コレクション
+//! （既定 `feeds`）に取り込み、朝のブリーフィングのような定期エージェントが
+//! `rag_search` の `collection` 引数で参照できるようにする。
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::core::errors::ApiError;
+use crate::domain::knowledge::KnowledgeSource;
+use crate::history::NewInboxItem;
+use crate::state::{jobs, AppState};
+
+use super::dispatcher::ToolExecution;
+use super::readability::decode_entities;
+use super::search::SearchResult;
+use super::web::fetch_text;
+use super::web_security::allow_web_search;
+
+pub const DEFAULT_FEED_COLLECTION: &str = "feeds";
+const DEFAULT_ITEM_LIMIT: usize = 10;
+const MAX_ITEM_LIMIT: usize = 50;
+const SUMMARY_MAX_CHARS: usize = 400;
+/// フィードごとに覚えておく既読 ID の上限
+const SEEN_IDS_PER_FEED: usize = 500;
+const INGEST_TICK: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeedItem {
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    pub published: Option<DateTime<Utc>>,
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Feed {
+    pub title: String,
+    pub items: Vec<FeedItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedSubscription {
+    pub url: String,
+    pub title: Option<String>,
+}
+
+// --- パース ---
+
+static ITEM_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<(item|entry)\b[^>]*>(.*?)</(?:item|entry)>").unwrap());
+static LINK_TAG_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<link\b([^>]*?)/?>").unwrap());
+static HREF_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?is)\bhref\s*=\s*["']([^"']+)["']"#).unwrap());
+static REL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?is)\brel\s*=\s*["']([^"']+)["']"#).unwrap());
+static TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<[^>]+>").unwrap());
+static SPACE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+").unwrap());
+
+/// `tag_text` で読むタグ。名前空間付きタグもそのまま書ける。
+const TEXT_TAGS: [&str; 12] = [
+    "title",
+    "link",
+    "guid",
+    "id",
+    "pubDate",
+    "published",
+    "updated",
+    "dc:date",
+    "description",
+    "summary",
+    "content:encoded",
+    "content",
+];
+static TEXT_TAG_RES: LazyLock<HashMap<&'static str, Regex>> = LazyLock::new(|| {
+    TEXT_TAGS
+        .iter()
+        .map(|tag| {
+            let pattern = format!(
+                r"(?is)<{tag}\b[^>]*?(?:/>|>(.*?)</{tag}>)",
+                tag = regex::escape(tag)
+            );
+            (*tag, Regex::new(&pattern).unwrap())
+        })
+        .collect()
+});
+
+/// `<tag>...</tag>` の中身（CDATA は展開）。`tag` は `TEXT_TAGS` のどれか。
+fn tag_text(xml: &str, tag: &str) -> Option<String> {
+    let captures = TEXT_TAG_RES.get(tag)?.captures(xml)?;
+    let raw = captures.get(1)?.as_str().trim();
+    let unwrapped = raw
+        .strip_prefix("<![CDATA[")
+        .and_then(|inner| inner.strip_suffix("]]>"))
+        .map(str::to_string)
+        .unwrap_or_else(|| decode_entities(raw));
+    Some(unwrapped.trim().to_string()).filter(|text| !text.is_empty())
+}
+
+fn plain_text(html: &str) -> String {
+    let stripped = TAG_RE.replace_all(html, " ");
+    let decoded = decode_entities(&stripped);
+    SPACE_RE.replace_all(decoded.trim(), " ").to_string()
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}…", text[..index].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+/// Atom の `<link href>`（rel が無いか alternate のもの）を優先し、なければ RSS の `<link>` 本文。
+fn item_link(xml: &str) -> Option<String> {
+    for captures in LINK_TAG_RE.captures_iter(xml) {
+        let attrs = &captures[1];
+        let rel = REL_RE
+            .captures(attrs)
+            .map(|rel| rel[1].to_ascii_lowercase());
+        if !matches!(rel.as_deref(), None | Some("alternate")) {
+            continue;
+        }
+        if let Some(href) = HREF_RE.captures(attrs) {
+            return Some(decode_entities(&href[1]));
+        }
+    }
+    tag_text(xml, "link")
+}
+
+fn parse_date(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(raw)
+        .or_else(|_| DateTime::parse_from_rfc3339(raw))
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+pub fn parse_feed(xml: &str) -> Result<Feed, ApiError> {
+    let lower_head = xml
+        .chars()
+        .take(2048)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if !lower_head.contains("<rss")
+        && !lower_head.contains("<feed")
+        && !lower_head.contains("<rdf:rdf")
+    {
+        return Err(ApiError::BadRequest(
+            "Response is not an RSS or Atom feed".to_string(),
+        ));
+    }
+
+    let first_item = ITEM_RE.find(xml).map(|m| m.start()).unwrap_or(xml.len());
+    let title = tag_text(&xml[..first_item], "title")
+        .map(|title| plain_text(&title))
+        .unwrap_or_default();
+
+    let items = ITEM_RE
+        .captures_iter(xml)
+        .filter_map(|captures| {
+            let body = captures.get(2)?.as_str();
+            let title = tag_text(body, "title")
+                .map(|t| plain_text(&t))
+                .unwrap_or_default();
+            let link = item_link(body).unwrap_or_default();
+            if title.is_empty() && link.is_empty() {
+                return None;
+            }
+            let published = ["pubDate", "published", "updated", "dc:date"]
+                .iter()
+                .find_map(|tag| tag_text(body, tag))
+                .and_then(|raw| parse_date(&raw));
+            let summary = ["description", "summary", "content:encoded", "content"]
+                .iter()
+                .find_map(|tag| tag_text(body, tag))
+                .map(|raw| truncate_chars(&plain_text(&raw), SUMMARY_MAX_CHARS))
+                .unwrap_or_default();
+            let id = tag_text(body, "guid")
+                .or_else(|| tag_text(body, "id"))
+                .unwrap_or_else(|| {
+                    if link.is_empty() {
+                        title.clone()
+                    } else {
+                        link.clone()
+                    }
+                });
+            Some(FeedItem {
+                id,
+                title,
+                link,
+                published,
+                summary,
+            })
+        })
+        .collect();
+
+    Ok(Feed { title, items })
+}
+
+// --- 設定 ---
+
+fn feeds_config(config: &Value) -> Option<&Value> {
+    config.get("tools").and_then(|tools| tools.get("feeds"))
+}
+
+pub fn feed_subscriptions(config: &Value) -> Vec<FeedSubscription> {
+    feeds_config(config)
+        .and_then(|feeds| feeds.get("subscriptions"))
+        .and_then(Value::as_array)
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| match entry {
+                    Value::String(url) => Some(FeedSubscription {
+                        url: url.trim().to_string(),
+                        title: None,
+                    }),
+                    Value::Object(map) => {
+                        if map.get("enabled").and_then(Value::as_bool) == Some(false) {
+                            return None;
+                        }
+                        Some(FeedSubscription {
+                            url: map.get("url")?.as_str()?.trim().to_string(),
+                            title: map.get("title").and_then(Value::as_str).map(str::to_string),
+                        })
+                    }
+                    _ => None,
+                })
+                .filter(|subscription| !subscription.url.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn feed_collection(config: &Value) -> String {
+    feeds_config(config)
+        .and_then(|feeds| feeds.get("ingest"))
+        .and_then(|ingest| ingest.get("collection"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(DEFAULT_FEED_COLLECTION)
+        .to_string()
+}
+
+fn ingest_settings(config: &Value) -> Option<Duration> {
+    let ingest = feeds_config(config)?.get("ingest")?;
+    if !ingest
+        .get("enabled")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+    {
+        return None;
+    }
+    let minutes = ingest
+        .get("interval_minutes")
+        .and_then(Value::as_u64)
+        .unwrap_or(60)
+        .clamp(5, 24 * 60);
+    Some(Duration::from_secs(minutes * 60))
+}
+
+// --- ツール ---
+
+async fn fetch_feed(config: &Value, url: &str) -> Result<Feed, ApiError> {
+    let (_, _, text) = fetch_text(config, url).await?;
+    parse_feed(&text)
+}
+
+fn render_items(entries: &[(String, FeedItem)]) -> String {
+    if entries.is_empty() {
+        return "No feed items found.".to_string();
+    }
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, (feed_title, item))| {
+            let mut line = format!("[{}] {}", index + 1, item.title);
+            if !feed_title.is_empty() {
+                line.push_str(&format!(" — {}", feed_title));
+            }
+            if let Some(published) = item.published {
+                line.push_str(&format!(" ({})", published.format("%Y-%m-%d %H:%M UTC")));
+            }
+            if !item.link.is_empty() {
+                line.push_str(&format!("\n    {}", item.link));
+            }
+            if !item.summary.is_empty() {
+                line.push_str(&format!("\n    {}", item.summary));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `url` 指定時はそのフィード、省略時は購読中の全フィードを新しい順に返す。
+/// `since_hours` で公開日時を絞り込める。
+pub async fn execute_fetch_feed(config: &Value, args: &Value) -> Result<ToolExecution, ApiError> {
+    if !allow_web_search(config) {
+        return Err(ApiError::Forbidden);
+    }
+    let limit = args
+        .get("limit")
+        .and_then(Value::as_u64)
+        .map(|limit| limit as usize)
+        .unwrap_or(DEFAULT_ITEM_LIMIT)
+        .clamp(1, MAX_ITEM_LIMIT);
+    let since = args
+        .get("since_hours")
+        .and_then(Value::as_u64)
+        .map(|hours| Utc::now() - chrono::Duration::hours(hours.min(24 * 365) as i64));
+
+    let targets = match args
+        .get("url")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+    {
+        Some(url) => vec![FeedSubscription {
+            url: url.to_string(),
+            title: None,
+        }],
+        None => feed_subscriptions(config),
+    };
+    if targets.is_empty() {
+        return Err(ApiError::BadRequest(
+            "url missing and no feeds are subscribed in tools.feeds.subscriptions".to_string(),
+        ));
+    }
+
+    let mut entries = Vec::new();
+    let mut failures = Vec::new();
+    for target in &targets {
+        match fetch_feed(config, &target.url).await {
+            Ok(feed) => {
+                let feed_title = target.title.clone().unwrap_or(feed.title);
+                entries.extend(
+                    feed.items
+                        .into_iter()
+                        .map(|item| (feed_title.clone(), item)),
+                );
+            }
+            // 単一 URL のときはエラーをそのまま返す
+            Err(err) if targets.len() == 1 => return Err(err),
+            Err(err) => failures.push(format!("{}: {}", target.url, err)),
+        }
+    }
+    if let Some(since) = since {
+        entries.retain(|(_, item)| item.published.is_none_or(|published| published >= since));
+    }
+    entries.sort_by_key(|(_, item)| std::cmp::Reverse(item.published));
+    entries.truncate(limit);
+
+    let mut output = render_items(&entries);
+    if !failures.is_empty() {
+        output.push_str(&format!("\n\nFailed feeds:\n{}", failures.join("\n")));
+    }
+    let search_results = entries
+        .iter()
+        .filter(|(_, item)| !item.link.is_empty())
+        .map(|(_, item)| SearchResult {
+            title: item.title.clone(),
+            url: item.link.clone(),
+            snippet: item.summary.clone(),
+        })
+        .collect();
+
+    Ok(ToolExecution {
+        output,
+        search_results: Some(search_results),
+        images: Vec::new(),
+    })
+}
+
+// --- 定期取り込み ---
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FeedIngestState {
+    #[serde(default)]
+    last_run: Option<DateTime<Utc>>,
+    /// フィード URL → 取り込み済み項目 ID（新しいものが末尾）
+    #[serde(default)]
+    seen: HashMap<String, Vec<String>>,
+}
+
+fn ingest_state_path(user_data_dir: &Path) -> PathBuf {
+    user_data_dir.join("feeds_state.json")
+}
+
+fn load_ingest_state(path: &Path) -> FeedIngestState {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_ingest_state(path: &Path, state: &FeedIngestState) -> Result<(), ApiError> {
+    let serialized = serde_json::to_string_pretty(state).map_err(ApiError::internal)?;
+    std::fs::write(path, serialized).map_err(ApiError::internal)
+}
+
+/// 未取り込みの項目だけを返す。既読にするのは取り込めてから（`mark_seen`）。
+fn new_items(state: &FeedIngestState, feed_url: &str, items: Vec<FeedItem>) -> Vec<FeedItem> {
+    let known = state
+        .seen
+        .get(feed_url)
+        .map(|seen| seen.iter().collect::<HashSet<_>>())
+        .unwrap_or_default();
+    items
+        .into_iter()
+        .filter(|item| !known.contains(&item.id))
+        .collect()
+}
+
+/// 取り込めた項目を既読一覧に足す。`ids` はフィードの並び（新しい順）。
+fn mark_seen(state: &mut FeedIngestState, feed_url: &str, ids: Vec<String>) {
+    let seen = state.seen.entry(feed_url.to_string()).or_default();
+    seen.extend(ids.into_iter().rev());
+    if seen.len() > SEEN_IDS_PER_FEED {
+        let overflow = seen.len() - SEEN_IDS_PER_FEED;
+        seen.drain(..overflow);
+    }
+}
+
+/// 購読フィードを一巡して新着を RAG に取り込む。取り込んだ項目数を返す。
+pub async fn ingest_subscribed_feeds(state: &AppState) -> Result<usize, ApiError> {
+    let config = state.core().config.load_config()?;
+    if !allow_web_search(&config) {
+        return Ok(0);
+    }
+    let collection = feed_collection(&config);
+    let state_path = ingest_state_path(&state.core().paths.user_data_dir);
+    let mut ingest_state = load_ingest_state(&state_path);
+    let mut ingested = 0;
+
+    for subscription in feed_subscriptions(&config) {
+        let feed = match fetch_feed(&config, &subscription.url).await {
+            Ok(feed) => feed,
+            Err(err) => {
+                tracing::warn!("Feed ingestion failed for {}: {}", subscription.url, err);
+                continue;
+            }
+        };
+        let feed_title = subscription.title.clone().unwrap_or(feed.title);
+        let mut ingested_ids = Vec::new();
+        for item in new_items(&ingest_state, &subscription.url, feed.items) {
+            let content = format!("{}\n\n{}", item.title, item.summary);
+            let source = if item.link.is_empty() {
+                subscription.url.clone()
+            } else {
+                item.link.clone()
+            };
+            let metadata = json!({
+                "kind": "feed_item",
+                "feed": feed_title,
+                "feed_url": subscription.url,
+                "title": item.title,
+                "link": item.link,
+                "published": item.published.map(|date| date.to_rfc3339()),
+            });
+            match state
+                .memory()
+                .knowledge_use_case
+                .ingest(
+                    KnowledgeSource::Text {
+                        content,
+                        source,
+                        metadata: Some(metadata),
+                    },
+                    &collection,
+                )
+                .await
+            {
+                Ok(_) => {
+                    ingested += 1;
+                    ingested_ids.push(item.id);
+                }
+                // 既読にしないので次の巡回で取り込み直す
+                Err(err) => tracing::warn!("Failed to ingest feed item {}: {}", item.id, err),
+            }
+        }
+        mark_seen(&mut ingest_state, &subscription.url, ingested_ids);
+    }
+
+    ingest_state.last_run = Some(Utc::now());
+    save_ingest_state(&state_path, &ingest_state)?;
+    Ok(ingested)
+}
+
+/// `tools.feeds.ingest` の間隔で取り込みを回す常駐タスク。設定は毎回読み直すので
+/// 有効化・間隔変更は再起動なしで反映される。
+pub fn spawn_feed_ingestion(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(INGEST_TICK);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let Ok(config) = state.core().config.load_config() else {
+                continue;
+            };
+            let Some(interval) = ingest_settings(&config) else {
+                continue;
+            };
+            let state_path = ingest_state_path(&state.core().paths.user_data_dir);
+            let due = load_ingest_state(&state_path).last_run.is_none_or(|last| {
+                Utc::now()
+                    .signed_duration_since(last)
+                    .to_std()
+                    .unwrap_or_default()
+                    >= interval
+            });
+            if !due {
+                continue;
+            }
+            let job_id = uuid::Uuid::new_v4().to_string();
+            jobs::begin(
+                &state,
+                &job_id,
+                jobs::KIND_FEED_INGEST,
+                "Feed ingestion",
+                Value::Null,
+            )
+            .await;
+            let result = ingest_subscribed_feeds(&state).await;
+            jobs::finish(
+                &state,
+                &job_id,
+                result.as_ref().err().map(|err| err.to_string()).as_deref(),
+            )
+            .await;
+            let item = match result {
+                Ok(0) => continue,
+                Ok(count) => {
+                    tracing::info!(count, "Ingested new feed items");
+                    NewInboxItem {
+                        kind: "feed_ingest".to_string(),
+                        title: "Feeds updated".to_string(),
+                        summary: format!(
+                            "Added {} new item(s) to the '{}' collection.",
+                            count,
+                            feed_collection(&config)
+                        ),
+                        success: true,
+                        ..NewInboxItem::default()
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!("Feed ingestion failed: {}", err);
+                    NewInboxItem {
+                        kind: "feed_ingest".to_string(),
+                        title: "Feed ingestion failed".to_string(),
+                        summary: err.to_string(),
+                        success: false,
+                        ..NewInboxItem::default()
+                    }
+                }
+            };
+            if let Err(err) = state.runtime().inbox.deposit(item).await {
+                tracing::warn!("Failed to add feed ingestion to inbox: {}", err);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel>
+  <title>Example News</title>
+  <link>https://news.example.com/</link>
+  <item>
+    <title>Rust 2.0 &amp; friends</title>
+    <link>https://news.example.com/rust</link>
+    <guid>rust-2</guid>
+    <pubDate>Tue, 13 Oct 2026 08:00:00 +0000</pubDate>
+    <description><![CDATA[<p>Big <b>news</b> today.</p>]]></description>
+  </item>
+  <item>
+    <title>Second</title>
+    <link>https://news.example.com/second</link>
+  </item>
+</channel></rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Dev Blog</title>
+  <link href="https://blog.example.com/" rel="alternate"/>
+  <entry>
+    <title type="html">Hello</title>
+    <link rel="edit" href="https://blog.example.com/api/1"/>
+    <link href="https://blog.example.com/hello"/>
+    <id>tag:blog.example.com,2026:1</id>
+    <updated>2026-10-14T09:30:00Z</updated>
+    <summary>First &lt;em&gt;post&lt;/em&gt;</summary>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn parses_rss_items_with_cdata_and_dates() {
+        let feed = parse_feed(RSS).unwrap();
+        assert_eq!(feed.title, "Example News");
+        assert_eq!(feed.items.len(), 2);
+        let first = &feed.items[0];
+        assert_eq!(first.title, "Rust 2.0 & friends");
+        assert_eq!(first.link, "https://news.example.com/rust");
+        assert_eq!(first.id, "rust-2");
+        assert_eq!(first.summary, "Big news today.");
+        assert_eq!(
+            first.published.unwrap().to_rfc3339(),
+            "2026-10-13T08:00:00+00:00"
+        );
+        assert_eq!(feed.items[1].id, "https://news.example.com/second");
+    }
+
+    #[test]
+    fn parses_atom_alternate_links() {
+        let feed = parse_feed(ATOM).unwrap();
+        assert_eq!(feed.title, "Dev Blog");
+        let entry = &feed.items[0];
+        assert_eq!(entry.link, "https://blog.example.com/hello");
+        assert_eq!(entry.id, "tag:blog.example.com,2026:1");
+        assert_eq!(entry.summary, "First post");
+        assert!(entry.published.is_some());
+        assert!(parse_feed("<html><body>nope</body></html>").is_err());
+    }
+
+    #[test]
+    fn new_items_are_only_ingested_once() {
+        let mut state = FeedIngestState::default();
+        let items = parse_feed(RSS).unwrap().items;
+        let fresh = new_items(&state, "u", items.clone());
+        assert_eq!(fresh.len(), 2);
+        mark_seen(
+            &mut state,
+            "u",
+            fresh.into_iter().map(|item| item.id).collect(),
+        );
+        assert!(new_items(&state, "u", items).is_empty());
+    }
+
+    #[test]
+    fn items_that_failed_to_ingest_are_offered_again() {
+        let mut state = FeedIngestState::default();
+        let items = parse_feed(RSS).unwrap().items;
+        let fresh = new_items(&state, "u", items.clone());
+        // 1 件目だけ取り込めた
+        mark_seen(&mut state, "u", vec![fresh[0].id.clone()]);
+        let retry = new_items(&state, "u", items);
+        assert_eq!(retry.len(), 1);
+        assert_eq!(retry[0].id, fresh[1].id);
+    }
+
+    #[test]
+    fn subscriptions_accept_strings_and_objects() {
+        let config = json!({"tools": {"feeds": {
+            "subscriptions": [
+                "https://a.example.com/rss",
+                {"url": "https://b.example.com/atom", "title": "B"},
+                {"url": "https://c.example.com/rss", "enabled": false}
+            ],
+            "ingest": {"enabled": true, "interval_minutes": 1}
+        }}});
+        let subscriptions = feed_subscriptions(&config);
+        assert_eq!(subscriptions.len(), 2);
+        assert_eq!(subscriptions[1].title.as_deref(), Some("B"));
+        assert_eq!(ingest_settings(&config), Some(Duration::from_secs(300)));
+        assert_eq!(feed_collection(&config), DEFAULT_FEED_COLLECTION);
+    }
+}
@@ -0,0 +1,640 @@
+//! Sandboxed filesystem tools (`file_read` / `file_write` / `file_list`).
+//!
+//! アクセスできるのは `tools.workspace_roots` でユーザーが許可したディレクトリ
+//! のみ。パスは正規化（シンボリックリンク解決込み）してからルート配下か
+//! 確認する。同じルート一覧は MCP サーバーにも `roots/list` として渡す。
+
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::core::errors::ApiError;
+use crate::state::AppState;
+
+use super::dispatcher::ToolExecution;
+
+const DEFAULT_MAX_READ_BYTES: u64 = 1024 * 1024;
+const MAX_LIST_ENTRIES: usize = 500;
+const MAX_LIST_DEPTH: usize = 5;
+const MAX_PREVIEW_LINES: usize = 80;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkspaceRoot {
+    /// Canonical path.
+    pub path: PathBuf,
+    pub label: String,
+    pub read_only: bool,
+}
+
+/// Enabled roots that exist on disk, canonicalized.
+pub fn workspace_roots(config: &Value) -> Vec<WorkspaceRoot> {
+    config
+        .get("tools")
+        .and_then(|tools| tools.get("workspace_roots"))
+        .and_then(Value::as_array)
+        .map(|entries| {
+            entries
+                .iter()
+                .filter(|entry| {
+                    entry
+                        .get("enabled")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(true)
+                })
+                .filter_map(|entry| {
+                    let raw = entry.get("path").and_then(Value::as_str)?.trim();
+                    let path = fs::canonicalize(raw).ok().filter(|path| path.is_dir())?;
+                    let label = entry
+                        .get("label")
+                        .and_then(Value::as_str)
+                        .map(str::trim)
+                        .filter(|label| !label.is_empty())
+                        .map(str::to_string)
+                        .or_else(|| {
+                            path.file_name()
+                                .map(|name| name.to_string_lossy().to_string())
+                        })
+                        .unwrap_or_else(|| path.to_string_lossy().to_string());
+                    Some(WorkspaceRoot {
+                        path,
+                        label,
+                        read_only: entry
+                            .get("read_only")
+                            .and_then(Value::as_bool)
+                            .unwrap_or(false),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn max_read_bytes(config: &Value) -> u64 {
+    config
+        .get("tools")
+        .and_then(|tools| tools.get("file_read_max_bytes"))
+        .and_then(Value::as_u64)
+        .unwrap_or(DEFAULT_MAX_READ_BYTES)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write,
+}
+
+/// Resolve `raw` to a canonical path inside one of the roots. Relative paths
+/// are taken from the root whose label matches the first component, else the
+/// first root. Paths that do not exist yet (writes) are checked through their
+/// nearest existing ancestor.
+fn resolve_path<'a>(
+    roots: &'a [WorkspaceRoot],
+    raw: &str,
+    access: Access,
+) -> Result<(PathBuf, &'a WorkspaceRoot), ApiError> {
+    let Some(default_root) = roots.first() else {
+        return Err(ApiError::BadRequest(
+            "No workspace roots are configured (tools.workspace_roots)".to_string(),
+        ));
+    };
+    let raw = raw.trim();
+    let requested = Path::new(raw);
+    let candidate = if requested.is_absolute() {
+        requested.to_path_buf()
+    } else {
+        let mut components = requested.components();
+        let first = components
+            .next()
+            .map(|component| component.as_os_str().to_string_lossy().to_string());
+        match roots
+            .iter()
+            .find(|root| first.as_deref() == Some(root.label.as_str()))
+        {
+            Some(root) => root.path.join(components.as_path()),
+            None => default_root.path.join(requested),
+        }
+    };
+
+    let resolved = if candidate.exists() {
+        fs::canonicalize(&candidate).map_err(ApiError::internal)?
+    } else {
+        if access == Access::Read {
+            return Err(ApiError::NotFound(format!("Path not found: {}", raw)));
+        }
+        let mut existing = candidate.as_path();
+        let mut rest = Vec::new();
+        while !existing.exists() {
+            // `..` を含む未作成パスは正規化できないので拒否する
+            let Some(name) = existing.file_name() else {
+                return Err(ApiError::Forbidden);
+            };
+            rest.push(name.to_os_string());
+            existing = existing.parent().unwrap_or(Path::new(""));
+        }
+        let mut resolved = fs::canonicalize(existing).map_err(ApiError::internal)?;
+        for name in rest.iter().rev() {
+            resolved.push(name);
+        }
+        resolved
+    };
+    if resolved
+        .components()
+        .any(|component| matches!(component, Component::ParentDir))
+    {
+        return Err(ApiError::Forbidden);
+    }
+
+    let root = roots
+        .iter()
+        .filter(|root| resolved.starts_with(&root.path))
+        .max_by_key(|root| root.path.components().count())
+        .ok_or(ApiError::Forbidden)?;
+    if access == Access::Write && root.read_only {
+        return Err(ApiError::Forbidden);
+    }
+    Ok((resolved, root))
+}
+
+/// Existing directory inside a workspace root, e.g. a command's working dir.
+pub(crate) fn resolve_workspace_dir(
+    roots: &[WorkspaceRoot],
+    raw: &str,
+) -> Result<PathBuf, ApiError> {
+    let (path, _) = resolve_path(roots, raw, Access::Read)?;
+    if !path.is_dir() {
+        return Err(ApiError::BadRequest(format!(
+            "{} is not a directory",
+            path.display()
+        )));
+    }
+    Ok(path)
+}
+
+fn required_path(args: &Value) -> Result<&str, ApiError> {
+    args.get("path")
+        .or_else(|| args.get("file"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .ok_or_else(|| ApiError::BadRequest("path missing".to_string()))
+}
+
+fn text_output(output: String) -> ToolExecution {
+    ToolExecution {
+        output,
+        search_results: None,
+        images: Vec::new(),
+    }
+}
+
+pub fn execute_file_read(config: &Value, args: &Value) -> Result<ToolExecution, ApiError> {
+    let roots = workspace_roots(config);
+    let (path, _) = resolve_path(&roots, required_path(args)?, Access::Read)?;
+    if !path.is_file() {
+        return Err(ApiError::BadRequest(format!(
+            "{} is not a file",
+            path.display()
+        )));
+    }
+    let size = fs::metadata(&path).map_err(ApiError::internal)?.len();
+    let max_bytes = max_read_bytes(config);
+    if size > max_bytes {
+        return Err(ApiError::BadRequest(format!(
+            "File is {} bytes; the read limit is {} bytes",
+            size, max_bytes
+        )));
+    }
+    let bytes = fs::read(&path).map_err(ApiError::internal)?;
+    if bytes.contains(&0) {
+        return Err(ApiError::BadRequest(
+            "Binary files cannot be read as text".to_string(),
+        ));
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let lines = text.lines().collect::<Vec<_>>();
+    let offset = args
+        .get("offset")
+        .and_then(Value::as_u64)
+        .unwrap_or(1)
+        .max(1) as usize;
+    let limit = args
+        .get("limit")
+        .and_then(Value::as_u64)
+        .map(|limit| limit as usize)
+        .unwrap_or(lines.len());
+    let start = (offset - 1).min(lines.len());
+    let end = start.saturating_add(limit).min(lines.len());
+
+    Ok(text_output(format!(
+        "{} (lines {}-{} of {})\n\n{}",
+        path.display(),
+        start + 1,
+        end,
+        lines.len(),
+        lines[start..end].join("\n")
+    )))
+}
+
+#[derive(Debug, Serialize)]
+struct ListEntry {
+    path: String,
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+}
+
+pub fn execute_file_list(config: &Value, args: &Value) -> Result<ToolExecution, ApiError> {
+    let roots = workspace_roots(config);
+    let requested = args
+        .get("path")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|path| !path.is_empty());
+    let Some(requested) = requested else {
+        // ルート未指定ならルート一覧を返す
+        let listing = roots
+            .iter()
+            .map(|root| {
+                json!({
+                    "label": root.label,
+                    "path": root.path.display().to_string(),
+                    "read_only": root.read_only,
+                })
+            })
+            .collect::<Vec<_>>();
+        return Ok(text_output(
+            serde_json::to_string_pretty(&json!({ "roots": listing })).unwrap_or_default(),
+        ));
+    };
+    let (dir, _) = resolve_path(&roots, requested, Access::Read)?;
+    if !dir.is_dir() {
+        return Err(ApiError::BadRequest(format!(
+            "{} is not a directory",
+            dir.display()
+        )));
+    }
+    let recursive = args
+        .get("recursive")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
+    let mut entries = Vec::new();
+    let mut truncated = false;
+    collect_entries(
+        &dir,
+        &dir,
+        if recursive { MAX_LIST_DEPTH } else { 1 },
+        &mut entries,
+        &mut truncated,
+    )?;
+    Ok(text_output(
+        serde_json::to_string_pretty(&json!({
+            "directory": dir.display().to_string(),
+            "entries": entries,
+            "truncated": truncated,
+        }))
+        .unwrap_or_default(),
+    ))
+}
+
+fn collect_entries(
+    base: &Path,
+    dir: &Path,
+    depth: usize,
+    entries: &mut Vec<ListEntry>,
+    truncated: &mut bool,
+) -> Result<(), ApiError> {
+    let mut children = fs::read_dir(dir)
+        .map_err(ApiError::internal)?
+        .filter_map(Result::ok)
+        .collect::<Vec<_>>();
+    children.sort_by_key(|entry| entry.file_name());
+    for child in children {
+        if entries.len() >= MAX_LIST_ENTRIES {
+            *truncated = true;
+            return Ok(());
+        }
+        // symlink はたどらない（ルート外に出る可能性があるため）
+        let Ok(file_type) = child.file_type() else {
+            continue;
+        };
+        let path = child.path();
+        let relative = path
+            .strip_prefix(base)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        if file_type.is_dir() {
+            entries.push(ListEntry {
+                path: format!("{}/", relative),
+                kind: "dir",
+                size: None,
+            });
+            if depth > 1 {
+                collect_entries(base, &path, depth - 1, entries, truncated)?;
+            }
+        } else if file_type.is_file() {
+            entries.push(ListEntry {
+                path: relative,
+                kind: "file",
+                size: child.metadata().ok().map(|meta| meta.len()),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WriteMode {
+    Overwrite,
+    Append,
+    Create,
+}
+
+pub fn execute_file_write(config: &Value, args: &Value) -> Result<ToolExecution, ApiError> {
+    let roots = workspace_roots(config);
+    let (path, _) = resolve_path(&roots, required_path(args)?, Access::Write)?;
+    let content = args
+        .get("content")
+        .and_then(Value::as_str)
+        .ok_or_else(|| ApiError::BadRequest("content missing".to_string()))?;
+    let mode = match args
+        .get("mode")
+        .and_then(Value::as_str)
+        .unwrap_or("overwrite")
+    {
+        "overwrite" => WriteMode::Overwrite,
+        "append" => WriteMode::Append,
+        "create" => WriteMode::Create,
+        other => {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown write mode '{}': use overwrite, append or create",
+                other
+            )))
+        }
+    };
+    let dry_run = args
+        .get("dry_run")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
+    if path.is_dir() {
+        return Err(ApiError::BadRequest(format!(
+            "{} is a directory",
+            path.display()
+        )));
+    }
+    let existing = if path.exists() {
+        if mode == WriteMode::Create {
+            return Err(ApiError::Conflict(format!(
+                "{} already exists",
+                path.display()
+            )));
+        }
+        Some(String::from_utf8_lossy(&fs::read(&path).map_err(ApiError::internal)?).into_owned())
+    } else {
+        None
+    };
+    let before = existing.as_deref().unwrap_or("");
+    let after = match mode {
+        WriteMode::Append => format!("{}{}", before, content),
+        WriteMode::Overwrite | WriteMode::Create => content.to_string(),
+    };
+    let preview = line_diff_preview(before, &after);
+
+    if dry_run {
+        return Ok(text_output(format!(
+            "[dry run] {} {} ({} bytes)\n\n{}",
+            if existing.is_some() {
+                "would update"
+            } else {
+                "would create"
+            },
+            path.display(),
+            after.len(),
+            preview
+        )));
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(ApiError::internal)?;
+    }
+    match mode {
+        WriteMode::Append => {
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(ApiError::internal)?;
+            file.write_all(content.as_bytes())
+                .map_err(ApiError::internal)?;
+        }
+        WriteMode::Overwrite | WriteMode::Create => {
+            fs::write(&path, content).map_err(ApiError::internal)?;
+        }
+    }
+    Ok(text_output(format!(
+        "{} {} ({} bytes)\n\n{}",
+        if existing.is_some() {
+            "Updated"
+        } else {
+            "Created"
+        },
+        path.display(),
+        after.len(),
+        preview
+    )))
+}
+
+/// Compact diff: common leading/trailing lines are skipped and the changed
+/// middle is shown as `-`/`+` lines.
+fn line_diff_preview(before: &str, after: &str) -> String {
+    let old = before.lines().collect::<Vec<_>>();
+    let new = after.lines().collect::<Vec<_>>();
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let removed = &old[prefix..old.len() - suffix];
+    let added = &new[prefix..new.len() - suffix];
+    if removed.is_empty() && added.is_empty() {
+        return "(no changes)".to_string();
+    }
+
+    let mut lines = vec![format!(
+        "@@ line {}: -{} +{} @@",
+        prefix + 1,
+        removed.len(),
+        added.len()
+    )];
+    lines.extend(removed.iter().map(|line| format!("-{}", line)));
+    lines.extend(added.iter().map(|line| format!("+{}", line)));
+    if lines.len() > MAX_PREVIEW_LINES {
+        let hidden = lines.len() - MAX_PREVIEW_LINES;
+        lines.truncate(MAX_PREVIEW_LINES);
+        lines.push(format!("... ({} more lines)", hidden));
+    }
+    lines.join("\n")
+}
+
+/// Run a file tool and append an audit entry for the call, whatever the outcome.
+///
+/// The file operation itself runs on the blocking pool so large files or slow
+/// disks don't stall the async workers.
+pub async fn execute_file_tool(
+    state: Option<&AppState>,
+    config: &Value,
+    session_id: Option<&str>,
+    tool_name: &str,
+    args: &Value,
+) -> Result<ToolExecution, ApiError> {
+    let task = {
+        let (config, args, tool_name) = (config.clone(), args.clone(), tool_name.to_string());
+        move || match tool_name.as_str() {
+            "file_read" => execute_file_read(&config, &args),
+            "file_write" => execute_file_write(&config, &args),
+            "file_list" => execute_file_list(&config, &args),
+            other => Err(ApiError::BadRequest(format!("Unknown tool: {}", other))),
+        }
+    };
+    let result = match tokio::task::spawn_blocking(task).await {
+        Ok(result) => result,
+        Err(err) => Err(ApiError::internal(err)),
+    };
+
+    if let Some(state) = state {
+        let outcome = match &result {
+            Ok(_) => "success",
+            Err(ApiError::Forbidden) => "denied",
+            Err(_) => "error",
+        };
+        let payload = json!({
+            "tool": tool_name,
+            "path": args.get("path").and_then(Value::as_str),
+            "session_id": session_id,
+            "dry_run": args.get("dry_run").and_then(Value::as_bool).unwrap_or(false),
+            "bytes": args.get("content").and_then(Value::as_str).map(str::len),
+            "error": result.as_ref().err().map(ToString::to_string),
+        });
+        if let Err(err) = state
+            .core()
+            .security
+            .record_audit("file_tool", outcome, payload)
+        {
+            tracing::warn!("Failed to record file tool audit entry: {}", err);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_for(root: &Path, read_only: bool) -> Value {
+        json!({
+            "tools": {
+                "workspace_roots": [
+                    { "path": root.to_string_lossy(), "label": "ws", "read_only": read_only }
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn paths_outside_roots_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("ws");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(dir.path().join("secret.txt"), "nope").unwrap();
+        let config = config_for(&root, false);
+
+        let escape = execute_file_read(&config, &json!({"path": "../secret.txt"}));
+        assert!(matches!(escape, Err(ApiError::Forbidden)));
+        let absolute = execute_file_read(
+            &config,
+            &json!({"path": dir.path().join("secret.txt").to_string_lossy()}),
+        );
+        assert!(matches!(absolute, Err(ApiError::Forbidden)));
+        let write_escape = execute_file_write(
+            &config,
+            &json!({"path": "new/../../evil.txt", "content": "x"}),
+        );
+        assert!(matches!(write_escape, Err(ApiError::Forbidden)));
+        assert!(!dir.path().join("evil.txt").exists());
+
+        let no_roots = execute_file_read(&json!({}), &json!({"path": "a.txt"}));
+        assert!(matches!(no_roots, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn write_dry_run_previews_without_touching_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.md"), "a\nb\nc\n").unwrap();
+        let config = config_for(dir.path(), false);
+
+        let preview = execute_file_write(
+            &config,
+            &json!({"path": "notes.md", "content": "a\nB\nc\n", "dry_run": true}),
+        )
+        .unwrap();
+        assert!(preview.output.starts_with("[dry run] would update"));
+        assert!(preview.output.contains("@@ line 2: -1 +1 @@\n-b\n+B"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("notes.md")).unwrap(),
+            "a\nb\nc\n"
+        );
+
+        execute_file_write(
+            &config,
+            &json!({"path": "ws/sub/new.txt", "content": "hello", "mode": "create"}),
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("sub/new.txt")).unwrap(),
+            "hello"
+        );
+        let again = execute_file_write(
+            &config,
+            &json!({"path": "sub/new.txt", "content": "x", "mode": "create"}),
+        );
+        assert!(matches!(again, Err(ApiError::Conflict(_))));
+    }
+
+    #[test]
+    fn read_only_roots_allow_read_and_list_only() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "fn main() {}\n// end\n").unwrap();
+        let config = config_for(dir.path(), true);
+
+        let read =
+            execute_file_read(&config, &json!({"path": "src/main.rs", "offset": 2})).unwrap();
+        assert!(read.output.contains("(lines 2-2 of 2)"));
+        assert!(read.output.ends_with("// end"));
+
+        let list = execute_file_list(&config, &json!({"path": ".", "recursive": true})).unwrap();
+        let listing: Value = serde_json::from_str(&list.output).unwrap();
+        assert_eq!(listing["entries"][0]["path"], "src/");
+        assert_eq!(listing["entries"][1]["path"], "src/main.rs");
+
+        let write = execute_file_write(&config, &json!({"path": "src/main.rs", "content": ""}));
+        assert!(matches!(write, Err(ApiError::Forbidden)));
+    }
+
+    #[test]
+    fn diff_preview_reports_no_changes() {
+        assert_eq!(line_diff_preview("same\n", "same\n"), "(no changes)");
+        assert_eq!(line_diff_preview("", "new"), "@@ line 1: -0 +1 @@\n+new");
+    }
+}
@@ -0,0 +1,615 @@
+//! User-defined HTTP API tools.
+//!
+//! `tools.http_tools.<name>` に宣言した REST エンドポイントをツールとして
+//! 公開する。MCP サーバーを書くほどでもない単純な連携向け。
+//!
+//! ```yaml
+//! tools:
+//!   http_tools:
+//!     weather_now:
+//!       description: Current weather for a city
+//!       method: GET
+//!       url: https://api.example.com/v1/weather/{city}
+//!       headers:
+//!         Authorization: "Bearer {{secret.weather_key}}"
+//!       params_schema:
+//!         type: object
+//!         properties: { city: { type: string } }
+//!         required: [city]
+//!   http_tool_secrets:
+//!     weather_key: "..."
+//! ```
+//!
+//! URL 中の `{param}` は引数で置換され、残りの引数は GET/DELETE ではクエリ、
+//! それ以外では JSON ボディとして送られる。`http_tool_secrets` は機密キー
+//! としてキーリングに保存される。
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use reqwest::{Client, Method, Url};
+use serde_json::{json, Map, Value};
+
+use crate::core::config::schema::NetworkSubsystem;
+use crate::core::errors::ApiError;
+use crate::core::native_tools::native_tool_capability;
+use crate::core::network::NetClient;
+use crate::state::AppState;
+
+use super::dispatcher::ToolExecution;
+
+pub const HTTP_TOOL_METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE"];
+pub const HTTP_TOOL_BODY_MODES: &[&str] = &["json", "query", "none"];
+
+const DEFAULT_TIMEOUT_SECS: u64 = 20;
+const DEFAULT_MAX_RESPONSE_CHARS: usize = 8000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyMode {
+    Json,
+    Query,
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpToolDefinition {
+    pub name: String,
+    pub description: String,
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub params_schema: Value,
+    pub body: BodyMode,
+    pub timeout_secs: u64,
+    pub max_response_chars: usize,
+}
+
+/// ツール名として使える形か。ネイティブツール名・エイリアスとは衝突させない。
+pub fn is_valid_http_tool_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && !name.starts_with("native_")
+        && native_tool_capability(name).is_none()
+}
+
+fn parse_definition(name: &str, entry: &Value) -> Option<HttpToolDefinition> {
+    if !is_valid_http_tool_name(name) {
+        tracing::warn!("Ignoring HTTP tool with invalid name: {}", name);
+        return None;
+    }
+    let entry = entry.as_object()?;
+    if entry.get("enabled").and_then(Value::as_bool) == Some(false) {
+        return None;
+    }
+    let url = entry.get("url").and_then(Value::as_str)?.trim().to_string();
+    if url.is_empty() {
+        return None;
+    }
+    let method = entry
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or("GET")
+        .to_ascii_uppercase();
+    if !HTTP_TOOL_METHODS.contains(&method.as_str()) {
+        tracing::warn!("Ignoring HTTP tool {} with method {}", name, method);
+        return None;
+    }
+    let method = Method::from_bytes(method.as_bytes()).ok()?;
+    let body = match entry.get("body").and_then(Value::as_str) {
+        Some("json") => BodyMode::Json,
+        Some("query") => BodyMode::Query,
+        Some("none") => BodyMode::None,
+        _ if method == Method::GET || method == Method::DELETE => BodyMode::Query,
+        _ => BodyMode::Json,
+    };
+    let headers = entry
+        .get("headers")
+        .and_then(Value::as_object)
+        .map(|headers| {
+            headers
+                .iter()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(HttpToolDefinition {
+        name: name.to_string(),
+        description: entry
+            .get("description")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{} {}", method, url)),
+        params_schema: entry
+            .get("params_schema")
+            .cloned()
+            .unwrap_or_else(|| json!({"type": "object"})),
+        timeout_secs: entry
+            .get("timeout_secs")
+            .and_then(Value::as_u64)
+            .unwrap_or(DEFAULT_TIMEOUT_SECS)
+            .clamp(1, 300),
+        max_response_chars: entry
+            .get("max_response_chars")
+            .and_then(Value::as_u64)
+            .map(|value| value.clamp(256, 200_000) as usize)
+            .unwrap_or(DEFAULT_MAX_RESPONSE_CHARS),
+        method,
+        url,
+        headers,
+        body,
+    })
+}
+
+/// 有効な HTTP ツール定義を名前順で返す。
+pub fn http_tool_definitions(config: &Value) -> Vec<HttpToolDefinition> {
+    let Some(entries) = config
+        .get("tools")
+        .and_then(|tools| tools.get("http_tools"))
+        .and_then(Value::as_object)
+    else {
+        return Vec::new();
+    };
+    let mut definitions = entries
+        .iter()
+        .filter_map(|(name, entry)| parse_definition(name, entry))
+        .collect::<Vec<_>>();
+    definitions.sort_by(|a, b| a.name.cmp(&b.name));
+    definitions
+}
+
+pub fn find_http_tool(config: &Value, name: &str) -> Option<HttpToolDefinition> {
+    let name = name.trim();
+    let entry = config
+        .get("tools")?
+        .get("http_tools")?
+        .as_object()?
+        .get(name)?;
+    parse_definition(name, entry)
+}
+
+/// キーリングから戻った値は JSON 文字列になっていることがあるので両方受け付ける。
+fn http_tool_secrets(config: &Value) -> Map<String, Value> {
+    match config
+        .get("tools")
+        .and_then(|tools| tools.get("http_tool_secrets"))
+    {
+        Some(Value::Object(map)) => map.clone(),
+        Some(Value::String(raw)) => serde_json::from_str::<Value>(raw)
+            .ok()
+            .and_then(|value| value.as_object().cloned())
+            .unwrap_or_default(),
+        _ => Map::new(),
+    }
+}
+
+/// `{{secret.NAME}}` を `http_tool_secrets.NAME` で置換する。
+fn expand_secret_refs(template: &str, secrets: &Map<String, Value>) -> Result<String, ApiError> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{secret.") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + "{{secret.".len()..];
+        let Some(end) = after.find("}}") else {
+            return Err(ApiError::BadRequest(
+                "Unterminated secret reference in HTTP tool".to_string(),
+            ));
+        };
+        let key = after[..end].trim();
+        let value = secrets
+            .get(key)
+            .and_then(Value::as_str)
+            .ok_or_else(|| ApiError::BadRequest(format!("Secret '{}' is not configured", key)))?;
+        output.push_str(value);
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn param_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+/// URL テンプレートの `{param}` を置換し、使った引数名を返す。
+/// `{{secret.*}}` はここでは触らない。
+fn render_url_template(
+    template: &str,
+    args: &Value,
+) -> Result<(String, HashSet<String>), ApiError> {
+    let mut output = String::with_capacity(template.len());
+    let mut used = HashSet::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start..];
+        if after.starts_with("{{") {
+            let end = after.find("}}").map(|i| i + 2).unwrap_or(after.len());
+            output.push_str(&after[..end]);
+            rest = &after[end..];
+            continue;
+        }
+        let Some(end) = after.find('}') else {
+            output.push_str(after);
+            rest = "";
+            break;
+        };
+        let key = &after[1..end];
+        let value = args
+            .get(key)
+            .and_then(param_to_string)
+            .ok_or_else(|| ApiError::BadRequest(format!("Missing URL parameter '{}'", key)))?;
+        output.push_str(&urlencoding::encode(&value));
+        used.insert(key.to_string());
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    Ok((output, used))
+}
+
+fn validate_params(schema: &Value, args: &Value) -> Result<(), ApiError> {
+    let validator = jsonschema::validator_for(schema).map_err(|err| {
+        ApiError::BadRequest(format!("Invalid params_schema for HTTP tool: {}", err))
+    })?;
+    let errors = validator
+        .iter_errors(args)
+        .map(|err| {
+            let path = err.instance_path().to_string();
+            if path.is_empty() {
+                err.to_string()
+            } else {
+                format!("{}: {}", path, err)
+            }
+        })
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::BadRequest(format!(
+            "Invalid arguments: {}",
+            errors.join("; ")
+        )))
+    }
+}
+
+/// 送信直前のリクエスト内容（テスト・監査用に分離）。
+#[derive(Debug, Clone, PartialEq)]
+struct PreparedRequest {
+    url: Url,
+    headers: Vec<(String, String)>,
+    body: Option<Value>,
+}
+
+fn prepare_request(
+    config: &Value,
+    tool: &HttpToolDefinition,
+    args: &Value,
+) -> Result<PreparedRequest, ApiError> {
+    let args = if args.is_null() {
+        json!({})
+    } else {
+        args.clone()
+    };
+    validate_params(&tool.params_schema, &args)?;
+
+    let secrets = http_tool_secrets(config);
+    let (rendered, used) = render_url_template(&tool.url, &args)?;
+    let mut url = Url::parse(&expand_secret_refs(&rendered, &secrets)?)
+        .map_err(|err| ApiError::BadRequest(format!("Invalid HTTP tool URL: {}", err)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(ApiError::BadRequest(
+            "HTTP tools only support http and https URLs".to_string(),
+        ));
+    }
+
+    let remaining = args
+        .as_object()
+        .map(|map| {
+            map.iter()
+                .filter(|(key, _)| !used.contains(key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect::<Map<_, _>>()
+        })
+        .unwrap_or_default();
+
+    let mut body = None;
+    match tool.body {
+        BodyMode::Query => {
+            let mut pairs = url.query_pairs_mut();
+            for (key, value) in &remaining {
+                if let Some(text) = param_to_string(value) {
+                    pairs.append_pair(key, &text);
+                } else if !value.is_null() {
+                    pairs.append_pair(key, &value.to_string());
+                }
+            }
+        }
+        BodyMode::Json => body = Some(Value::Object(remaining)),
+        BodyMode::None => {}
+    }
+    if url.query() == Some("") {
+        url.set_query(None);
+    }
+
+    let headers = tool
+        .headers
+        .iter()
+        .map(|(key, value)| Ok((key.clone(), expand_secret_refs(value, &secrets)?)))
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    Ok(PreparedRequest { url, headers, body })
+}
+
+/// 応答本文を `max_bytes` まで読んで打ち切る。巨大な応答や終わらない応答を
+/// メモリに溜め込まないため。打ち切ったかどうかも返す。
+async fn read_capped_body(
+    response: &mut reqwest::Response,
+    max_bytes: usize,
+) -> Result<(Vec<u8>, bool), reqwest::Error> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        let room = max_bytes - body.len();
+        if chunk.len() > room {
+            body.extend_from_slice(&chunk[..room]);
+            return Ok((body, true));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok((body, false))
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}\n...[truncated]", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+pub async fn execute_http_tool(
+    config: &Value,
+    tool: &HttpToolDefinition,
+    args: &Value,
+) -> Result<ToolExecution, ApiError> {
+    let request = prepare_request(config, tool, args)?;
+    let client = NetClient::from_builder(
+        NetworkSubsystem::HttpTools,
+        Client::builder()
+            .timeout(Duration::from_secs(tool.timeout_secs))
+            .connect_timeout(Duration::from_secs(tool.timeout_secs.min(30))),
+    )?;
+
+    let mut builder = client.request(tool.method.clone(), request.url)?;
+    for (key, value) in &request.headers {
+        builder = builder.header(key.as_str(), value.as_str());
+    }
+    if let Some(body) = &request.body {
+        builder = builder.json(body);
+    }
+
+    // URL には `{{secret.*}}` を展開したクエリが入りうるので、エラーには含めない
+    let request_failed = |err: reqwest::Error| {
+        ApiError::Internal(format!(
+            "HTTP tool '{}' request failed: {}",
+            tool.name,
+            err.without_url()
+        ))
+    };
+    let mut response = builder.send().await.map_err(request_failed)?;
+    let status = response.status();
+    // 1 文字は UTF-8 で最大 4 バイトなので、この上限で切れば必ず文字数でも切り詰められる
+    let max_bytes = tool.max_response_chars.saturating_add(1).saturating_mul(4);
+    let (body, cut_short) = read_capped_body(&mut response, max_bytes)
+        .await
+        .map_err(request_failed)?;
+    let text = String::from_utf8_lossy(&body).into_owned();
+    let rendered = if cut_short {
+        text
+    } else {
+        serde_json::from_str::<Value>(&text)
+            .ok()
+            .and_then(|value| serde_json::to_string_pretty(&value).ok())
+            .unwrap_or(text)
+    };
+
+    Ok(ToolExecution {
+        output: format!(
+            "HTTP {}\n{}",
+            status,
+            truncate_chars(&rendered, tool.max_response_chars)
+        ),
+        search_results: None,
+        images: Vec::new(),
+    })
+}
+
+/// 呼び出しごとに監査ログを残す。秘密値は記録しない。
+pub async fn execute_http_tool_audited(
+    state: Option<&AppState>,
+    config: &Value,
+    session_id: Option<&str>,
+    tool: &HttpToolDefinition,
+    args: &Value,
+) -> Result<ToolExecution, ApiError> {
+    let result = execute_http_tool(config, tool, args).await;
+    if let Some(state) = state {
+        let outcome = if result.is_ok() { "success" } else { "error" };
+        let payload = json!({
+            "tool": tool.name,
+            "method": tool.method.as_str(),
+            "url_template": tool.url,
+            "session_id": session_id,
+            "error": result.as_ref().err().map(ToString::to_string),
+        });
+        if let Err(err) = state
+            .core()
+            .security
+            .record_audit("http_tool", outcome, payload)
+        {
+            tracing::warn!("Failed to record http_tool audit entry: {}", err);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Value {
+        json!({
+            "tools": {
+                "http_tools": {
+                    "weather_now": {
+                        "description": "Current weather",
+                        "url": "https://api.example.com/v1/weather/{city}",
+                        "headers": { "Authorization": "Bearer {{secret.weather_key}}" },
+                        "params_schema": {
+                            "type": "object",
+                            "properties": {
+                                "city": { "type": "string" },
+                                "units": { "type": "string", "enum": ["metric", "imperial"] }
+                            },
+                            "required": ["city"]
+                        }
+                    },
+                    "create_note": {
+                        "method": "post",
+                        "url": "https://notes.example.com/api/{folder}/notes"
+                    },
+                    "disabled_tool": { "url": "https://example.com", "enabled": false },
+                    "native_search": { "url": "https://example.com" }
+                },
+                "http_tool_secrets": "{\"weather_key\":\"k-123\"}"
+            }
+        })
+    }
+
+    #[test]
+    fn definitions_skip_disabled_and_reserved_names() {
+        let names = http_tool_definitions(&config())
+            .into_iter()
+            .map(|tool| tool.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["create_note", "weather_now"]);
+
+        let note = find_http_tool(&config(), "create_note").unwrap();
+        assert_eq!(note.method, Method::POST);
+        assert_eq!(note.body, BodyMode::Json);
+        assert!(!is_valid_http_tool_name("web_search"));
+    }
+
+    #[test]
+    fn get_request_fills_template_query_and_secret_headers() {
+        let config = config();
+        let tool = find_http_tool(&config, "weather_now").unwrap();
+        let request = prepare_request(
+            &config,
+            &tool,
+            &json!({"city": "New York", "units": "metric"}),
+        )
+        .unwrap();
+
+        assert_eq!(
+            request.url.as_str(),
+            "https://api.example.com/v1/weather/New%20York?units=metric"
+        );
+        assert_eq!(
+            request.headers,
+            vec![("Authorization".to_string(), "Bearer k-123".to_string())]
+        );
+        assert_eq!(request.body, None);
+    }
+
+    #[test]
+    fn post_request_sends_unused_args_as_json() {
+        let config = config();
+        let tool = find_http_tool(&config, "create_note").unwrap();
+        let request = prepare_request(
+            &config,
+            &tool,
+            &json!({"folder": "inbox", "title": "hi", "tags": ["a"]}),
+        )
+        .unwrap();
+
+        assert_eq!(
+            request.url.as_str(),
+            "https://notes.example.com/api/inbox/notes"
+        );
+        assert_eq!(request.body, Some(json!({"title": "hi", "tags": ["a"]})));
+    }
+
+    #[test]
+    fn schema_and_secret_errors_are_reported() {
+        let config = config();
+        let tool = find_http_tool(&config, "weather_now").unwrap();
+        let err = prepare_request(&config, &tool, &json!({"units": "kelvin"})).unwrap_err();
+        assert!(
+            matches!(err, ApiError::BadRequest(message) if message.contains("Invalid arguments"))
+        );
+
+        let mut missing_secret = config.clone();
+        missing_secret["tools"]["http_tool_secrets"] = json!({});
+        let err = prepare_request(&missing_secret, &tool, &json!({"city": "Tokyo"})).unwrap_err();
+        assert!(matches!(err, ApiError::BadRequest(message) if message.contains("weather_key")));
+    }
+
+    #[tokio::test]
+    async fn endless_responses_are_cut_and_errors_omit_the_url() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\n\r\n")
+                .await
+                .unwrap();
+            let chunk = vec![b'x'; 64 * 1024];
+            while socket.write_all(&chunk).await.is_ok() {}
+        });
+
+        let config = json!({
+            "tools": {
+                "http_tools": {
+                    "stream": {
+                        "url": format!("http://{addr}/stream?key={{{{secret.api_key}}}}"),
+                        "max_response_chars": 256
+                    }
+                },
+                "http_tool_secrets": { "api_key": "k-123" }
+            }
+        });
+        let tool = find_http_tool(&config, "stream").unwrap();
+        let result = execute_http_tool(&config, &tool, &json!({})).await.unwrap();
+        assert!(result.output.starts_with("HTTP 200 OK\n"));
+        assert!(result.output.ends_with("...[truncated]"));
+        assert!(result.output.len() < 300);
+
+        // 接続先が無ければエラーになるが、秘密値の入った URL は出さない
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed_addr = closed.local_addr().unwrap();
+        drop(closed);
+        let mut config = config;
+        config["tools"]["http_tools"]["stream"]["url"] = json!(format!(
+            "http://{closed_addr}/stream?key={{{{secret.api_key}}}}"
+        ));
+        let tool = find_http_tool(&config, "stream").unwrap();
+        let err = execute_http_tool(&config, &tool, &json!({}))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("request failed"), "{}", err);
+        assert!(!err.contains("k-123"), "{}", err);
+    }
+}
@@ -1,11 +1,22 @@
+pub mod calculator;
+pub mod code_blocks;
+pub mod desktop;
 pub mod dispatcher;
+pub mod feeds;
+pub mod filesystem;
+pub mod http_api;
 pub mod rag;
+pub mod readability;
+pub mod registry;
 pub mod reranker;
 pub mod search;
+pub mod shell;
 pub mod vector_math;
 pub mod web;
 pub mod web_security;
+pub mod wiki;
 
 pub use dispatcher::execute_tool;
+pub use dispatcher::execute_tool_with_policy;
 #[allow(unused_imports)]
 pub use dispatcher::ToolExecution;
@@ -1,5 +1,6 @@
 use serde_json::Value;
 
+use crate::context::workers::project_worker::project_rag_collections;
 use crate::core::errors::ApiError;
 use crate::domain::errors::DomainError;
 use crate::domain::knowledge::KnowledgeSource;
@@ -8,6 +9,7 @@ use crate::rag::{ChunkSearchResult, StoredChunk};
 use crate::state::AppState;
 
 use super::dispatcher::ToolExecution;
+use super::feeds::feed_collection;
 
 pub async fn execute_rag_search(
     state: Option<&AppState>,
@@ -34,7 +36,21 @@ pub async fn execute_rag_search(
         .and_then(|v| v.as_u64())
         .unwrap_or_else(|| rag_search_default_limit(config) as u64)
         .clamp(1, 20) as usize;
-    let sid = session_id.unwrap_or("default");
+    // 他セッションは覗かせない。共有コレクションはフィード取り込み先と
+    // セッションが属するプロジェクトで選んだものだけ
+    let feed_collection = feed_collection(config);
+    let project_collections = project_rag_collections(config);
+    let sid = match args.get("collection").and_then(|v| v.as_str()) {
+        Some(collection) if collection == feed_collection => feed_collection.as_str(),
+        Some(collection) if project_collections.contains(&collection) => collection,
+        Some(collection) => {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown RAG collection: {}",
+                collection
+            )))
+        }
+        None => session_id.unwrap_or("default"),
+    };
 
     let model_cfg = ModelRuntimeConfig::for_embedding(config)?;
     let embeddings = state
@@ -74,6 +90,7 @@ pub async fn execute_rag_search(
     Ok(ToolExecution {
         output,
         search_results: None,
+        images: Vec::new(),
     })
 }
 
@@ -133,6 +150,7 @@ pub async fn execute_rag_ingest(
     Ok(ToolExecution {
         output,
         search_results: None,
+        images: Vec::new(),
     })
 }
 
@@ -185,6 +203,7 @@ pub async fn execute_rag_text_search(
     Ok(ToolExecution {
         output,
         search_results: None,
+        images: Vec::new(),
     })
 }
 
@@ -224,6 +243,7 @@ pub async fn execute_rag_get_chunk(
     Ok(ToolExecution {
         output,
         search_results: None,
+        images: Vec::new(),
     })
 }
 
@@ -277,6 +297,7 @@ pub async fn execute_rag_get_chunk_window(
     Ok(ToolExecution {
         output,
         search_results: None,
+        images: Vec::new(),
     })
 }
 
@@ -311,6 +332,7 @@ pub async fn execute_rag_clear_session(
     Ok(ToolExecution {
         output,
         search_results: None,
+        images: Vec::new(),
     })
 }
 
@@ -344,6 +366,7 @@ pub async fn execute_rag_reindex(
     Ok(ToolExecution {
         output,
         search_results: None,
+        images: Vec::new(),
     })
 }
 
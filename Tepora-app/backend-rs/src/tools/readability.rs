@@ -0,0 +1,608 @@
+//! Readability-style article extraction for `native_web_fetch`.
+//!
+//! HTML をそのまま返すとコンテキストを浪費するため、本文候補
+//! （`<article>` / `<main>` / `<body>`）を選んでナビゲーション等を除去し、
+//! Markdown に変換する。リンク密度の高いブロックは定型部分として捨てる。
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use reqwest::Url;
+use serde::Serialize;
+
+/// Blocks whose visible text is mostly link text are treated as boilerplate.
+const MAX_LINK_DENSITY: f64 = 0.5;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ArticleMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub published: Option<String>,
+    pub site_name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Article {
+    pub metadata: ArticleMetadata,
+    pub markdown: String,
+}
+
+impl Article {
+    /// Metadata header followed by the article body.
+    pub fn render(&self, source_url: &str) -> String {
+        let meta = &self.metadata;
+        let mut out = String::new();
+        if let Some(title) = &meta.title {
+            out.push_str(&format!("# {}\n\n", title));
+        }
+        out.push_str(&format!("Source: {}\n", source_url));
+        for (label, value) in [
+            ("Site", &meta.site_name),
+            ("Author", &meta.author),
+            ("Published", &meta.published),
+        ] {
+            if let Some(value) = value {
+                out.push_str(&format!("{}: {}\n", label, value));
+            }
+        }
+        out.push_str("\n---\n\n");
+        if self.markdown.trim().is_empty() {
+            if let Some(description) = &meta.description {
+                out.push_str(description);
+            }
+        } else {
+            out.push_str(&self.markdown);
+        }
+        out
+    }
+}
+
+pub fn looks_like_html(content_type: Option<&str>, body: &str) -> bool {
+    if let Some(content_type) = content_type {
+        let content_type = content_type.to_ascii_lowercase();
+        if content_type.contains("html") {
+            return true;
+        }
+        if !content_type.starts_with("text/plain") && !content_type.is_empty() {
+            return false;
+        }
+    }
+    let head = body
+        .trim_start()
+        .chars()
+        .take(64)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    head.starts_with("<!doctype html") || head.starts_with("<html")
+}
+
+pub fn extract_article(html: &str, base_url: Option<&Url>) -> Article {
+    let metadata = extract_metadata(html);
+    let cleaned = strip_noise(html);
+    let root = select_content_root(&cleaned);
+    let markdown = prune_boilerplate(&html_to_markdown(root, base_url));
+    Article { metadata, markdown }
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid regex"))
+}
+
+pub fn decode_entities(text: &str) -> String {
+    static NUMERIC: OnceLock<Regex> = OnceLock::new();
+    let numeric = regex(&NUMERIC, r"&#(x[0-9a-fA-F]+|[0-9]+);");
+    let decoded = numeric.replace_all(text, |captures: &regex::Captures| {
+        let raw = &captures[1];
+        let code = match raw.strip_prefix('x') {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => raw.parse::<u32>().ok(),
+        };
+        code.and_then(char::from_u32)
+            .map(|ch| ch.to_string())
+            .unwrap_or_default()
+    });
+    decoded
+        .replace("&nbsp;", " ")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&mdash;", "—")
+        .replace("&ndash;", "–")
+        .replace("&hellip;", "…")
+        .replace("&amp;", "&")
+}
+
+fn parse_attributes(raw: &str) -> HashMap<String, String> {
+    static ATTR: OnceLock<Regex> = OnceLock::new();
+    let attr = regex(
+        &ATTR,
+        r#"([a-zA-Z_:][-a-zA-Z0-9_:.]*)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#,
+    );
+    attr.captures_iter(raw)
+        .map(|captures| {
+            let value = captures
+                .get(2)
+                .or_else(|| captures.get(3))
+                .or_else(|| captures.get(4))
+                .map(|m| decode_entities(m.as_str()))
+                .unwrap_or_default();
+            (captures[1].to_ascii_lowercase(), value)
+        })
+        .collect()
+}
+
+fn clean_text(text: &str) -> Option<String> {
+    let text = decode_entities(&strip_tags(text));
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+fn strip_tags(text: &str) -> String {
+    static TAGS: OnceLock<Regex> = OnceLock::new();
+    regex(&TAGS, r"<[^>]+>").replace_all(text, "").into_owned()
+}
+
+fn extract_metadata(html: &str) -> ArticleMetadata {
+    static META: OnceLock<Regex> = OnceLock::new();
+    static TITLE: OnceLock<Regex> = OnceLock::new();
+    static H1: OnceLock<Regex> = OnceLock::new();
+    static TIME: OnceLock<Regex> = OnceLock::new();
+    static LD_DATE: OnceLock<Regex> = OnceLock::new();
+    static LD_AUTHOR: OnceLock<Regex> = OnceLock::new();
+
+    let mut meta = HashMap::new();
+    for tag in regex(&META, r"(?is)<meta\b([^>]*)>").captures_iter(html) {
+        let attrs = parse_attributes(&tag[1]);
+        let key = attrs
+            .get("property")
+            .or_else(|| attrs.get("name"))
+            .or_else(|| attrs.get("itemprop"));
+        if let (Some(key), Some(content)) = (key, attrs.get("content")) {
+            let content = content.trim();
+            if !content.is_empty() {
+                meta.entry(key.to_ascii_lowercase())
+                    .or_insert_with(|| content.to_string());
+            }
+        }
+    }
+    let first = |keys: &[&str]| keys.iter().find_map(|key| meta.get(*key).cloned());
+
+    let title = first(&["og:title", "twitter:title"])
+        .or_else(|| {
+            regex(&TITLE, r"(?is)<title[^>]*>(.*?)</title>")
+                .captures(html)
+                .and_then(|captures| clean_text(&captures[1]))
+        })
+        .or_else(|| {
+            regex(&H1, r"(?is)<h1[^>]*>(.*?)</h1>")
+                .captures(html)
+                .and_then(|captures| clean_text(&captures[1]))
+        });
+    let author = first(&["author", "article:author", "parsely-author", "dc.creator"])
+        .filter(|author| !author.starts_with("http"))
+        .or_else(|| {
+            regex(
+                &LD_AUTHOR,
+                r#"(?s)"author"\s*:\s*(?:\[\s*)?\{[^}]*?"name"\s*:\s*"([^"]+)""#,
+            )
+            .captures(html)
+            .map(|captures| captures[1].to_string())
+        });
+    let published = first(&[
+        "article:published_time",
+        "og:published_time",
+        "datepublished",
+        "pubdate",
+        "date",
+        "dc.date",
+    ])
+    .or_else(|| {
+        regex(&LD_DATE, r#""datePublished"\s*:\s*"([^"]+)""#)
+            .captures(html)
+            .map(|captures| captures[1].to_string())
+    })
+    .or_else(|| {
+        regex(&TIME, r#"(?is)<time\b[^>]*datetime\s*=\s*["']([^"']+)["']"#)
+            .captures(html)
+            .map(|captures| captures[1].to_string())
+    });
+
+    ArticleMetadata {
+        title,
+        author,
+        published,
+        site_name: first(&["og:site_name", "application-name"]),
+        description: first(&["og:description", "description", "twitter:description"]),
+    }
+}
+
+/// Drop elements that never carry article text.
+fn strip_noise(html: &str) -> String {
+    static COMMENTS: OnceLock<Regex> = OnceLock::new();
+    static NOISE: OnceLock<Vec<Regex>> = OnceLock::new();
+    let mut text = regex(&COMMENTS, r"(?s)<!--.*?-->")
+        .replace_all(html, "")
+        .into_owned();
+    let noise = NOISE.get_or_init(|| {
+        [
+            "script", "style", "noscript", "svg", "template", "iframe", "form", "button", "nav",
+            "header", "footer", "aside", "select", "canvas",
+        ]
+        .iter()
+        .map(|tag| Regex::new(&format!(r"(?is)<{tag}\b[^>]*>.*?</{tag}\s*>")).expect("valid regex"))
+        .collect()
+    });
+    for pattern in noise {
+        text = pattern.replace_all(&text, "").into_owned();
+    }
+    text
+}
+
+/// Largest `<article>`, then `<main>` / `role="main"`, then `<body>`.
+fn select_content_root(html: &str) -> &str {
+    let articles = element_ranges(html, "article", |_| true);
+    if let Some((start, end)) = articles
+        .into_iter()
+        .max_by_key(|(start, end)| strip_tags(&html[*start..*end]).trim().len())
+    {
+        return &html[start..end];
+    }
+    if let Some((start, end)) = element_ranges(html, "main", |_| true).into_iter().next() {
+        return &html[start..end];
+    }
+    if let Some((start, end)) = element_ranges(html, "div", |attrs| {
+        attrs.get("role").map(String::as_str) == Some("main")
+    })
+    .into_iter()
+    .next()
+    {
+        return &html[start..end];
+    }
+    if let Some((start, end)) = element_ranges(html, "body", |_| true).into_iter().next() {
+        return &html[start..end];
+    }
+    html
+}
+
+/// Byte ranges of top-level `<tag>` elements whose attributes pass `filter`,
+/// tracking nesting so inner closing tags do not end the match early.
+fn element_ranges(
+    html: &str,
+    tag: &str,
+    filter: impl Fn(&HashMap<String, String>) -> bool,
+) -> Vec<(usize, usize)> {
+    let pattern = Regex::new(&format!(r"(?i)<(/?){}\b([^>]*)>", tag)).expect("valid regex");
+    let mut ranges = Vec::new();
+    let mut depth = 0usize;
+    let mut open: Option<usize> = None;
+    for captures in pattern.captures_iter(html) {
+        let whole = captures.get(0).expect("match");
+        let closing = !captures[1].is_empty();
+        match (closing, open) {
+            (false, None) => {
+                if filter(&parse_attributes(&captures[2])) {
+                    open = Some(whole.end());
+                    depth = 1;
+                }
+            }
+            (false, Some(_)) => depth += 1,
+            (true, Some(start)) => {
+                depth -= 1;
+                if depth == 0 {
+                    ranges.push((start, whole.start()));
+                    open = None;
+                }
+            }
+            (true, None) => {}
+        }
+    }
+    if let Some(start) = open {
+        ranges.push((start, html.len()));
+    }
+    ranges
+}
+
+struct MarkdownWriter<'a> {
+    out: String,
+    base_url: Option<&'a Url>,
+    in_pre: bool,
+    lists: Vec<Option<usize>>,
+    links: Vec<(usize, Option<String>)>,
+}
+
+impl<'a> MarkdownWriter<'a> {
+    fn block_break(&mut self) {
+        let trimmed = self.out.trim_end_matches([' ', '\n']).len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() {
+            self.out.push_str("\n\n");
+        }
+    }
+
+    fn line_break(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn text(&mut self, raw: &str) {
+        let decoded = decode_entities(raw);
+        if self.in_pre {
+            self.out.push_str(&decoded);
+            return;
+        }
+        let starts_with_space = decoded.starts_with(char::is_whitespace);
+        let ends_with_space = decoded.ends_with(char::is_whitespace);
+        let words = decoded.split_whitespace().collect::<Vec<_>>().join(" ");
+        let at_line_start = self.out.is_empty() || self.out.ends_with(['\n', ' ']);
+        if starts_with_space && !at_line_start {
+            self.out.push(' ');
+        }
+        self.out.push_str(&words);
+        if ends_with_space && !words.is_empty() {
+            self.out.push(' ');
+        }
+    }
+
+    fn resolve_href(&self, href: &str) -> Option<String> {
+        let href = href.trim();
+        if href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") {
+            return None;
+        }
+        let url = match self.base_url {
+            Some(base) => base.join(href).ok()?,
+            None => Url::parse(href).ok()?,
+        };
+        matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+    }
+
+    fn open(&mut self, name: &str, attrs: &str) {
+        match name {
+            "p" | "div" | "section" | "article" | "main" | "table" | "figure" | "dl" => {
+                self.block_break()
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.block_break();
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+            }
+            "br" => self.line_break(),
+            "hr" => {
+                self.block_break();
+                self.out.push_str("---");
+                self.block_break();
+            }
+            "tr" | "dt" | "dd" | "figcaption" => self.line_break(),
+            "td" | "th" if !self.out.ends_with('\n') && !self.out.is_empty() => {
+                self.out.push_str(" | ")
+            }
+            "ul" => {
+                self.line_break();
+                self.lists.push(None);
+            }
+            "ol" => {
+                self.line_break();
+                self.lists.push(Some(0));
+            }
+            "li" => {
+                self.line_break();
+                let depth = self.lists.len().saturating_sub(1);
+                self.out.push_str(&"  ".repeat(depth));
+                match self.lists.last_mut() {
+                    Some(Some(counter)) => {
+                        *counter += 1;
+                        self.out.push_str(&format!("{}. ", counter));
+                    }
+                    _ => self.out.push_str("- "),
+                }
+            }
+            "blockquote" => {
+                self.block_break();
+                self.out.push_str("> ");
+            }
+            "pre" => {
+                self.block_break();
+                self.out.push_str("```\n");
+                self.in_pre = true;
+            }
+            "code" if !self.in_pre => self.out.push('`'),
+            "strong" | "b" => self.out.push_str("**"),
+            "em" | "i" => self.out.push('*'),
+            "a" => {
+                let href = parse_attributes(attrs)
+                    .get("href")
+                    .and_then(|href| self.resolve_href(href));
+                self.links.push((self.out.len(), href));
+            }
+            _ => {}
+        }
+    }
+
+    fn close(&mut self, name: &str) {
+        match name {
+            "p" | "div" | "section" | "article" | "main" | "table" | "figure" | "dl"
+            | "blockquote" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => self.block_break(),
+            "ul" | "ol" => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.block_break();
+                }
+            }
+            "pre" => {
+                self.in_pre = false;
+                if !self.out.ends_with('\n') {
+                    self.out.push('\n');
+                }
+                self.out.push_str("```");
+                self.block_break();
+            }
+            "code" if !self.in_pre => self.out.push('`'),
+            "strong" | "b" => self.out.push_str("**"),
+            "em" | "i" => self.out.push('*'),
+            "a" => {
+                if let Some((start, Some(href))) = self.links.pop() {
+                    let label = self.out[start..].trim().to_string();
+                    if !label.is_empty() && !self.in_pre {
+                        self.out.truncate(start);
+                        let leading = if self.out.ends_with(['\n', ' ']) || self.out.is_empty() {
+                            ""
+                        } else {
+                            " "
+                        };
+                        self.out
+                            .push_str(&format!("{}[{}]({})", leading, label, href));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn html_to_markdown(html: &str, base_url: Option<&Url>) -> String {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let tag = regex(&TAG, r"(?s)<(/?)([a-zA-Z][a-zA-Z0-9]*)\b([^>]*)>");
+    let mut writer = MarkdownWriter {
+        out: String::new(),
+        base_url,
+        in_pre: false,
+        lists: Vec::new(),
+        links: Vec::new(),
+    };
+    let mut cursor = 0;
+    for captures in tag.captures_iter(html) {
+        let whole = captures.get(0).expect("match");
+        writer.text(&html[cursor..whole.start()]);
+        cursor = whole.end();
+        let name = captures[2].to_ascii_lowercase();
+        if captures[1].is_empty() {
+            writer.open(&name, &captures[3]);
+        } else {
+            writer.close(&name);
+        }
+    }
+    writer.text(&html[cursor..]);
+
+    writer
+        .out
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Remove link farms (menus, tag clouds, related-post lists) block by block.
+fn prune_boilerplate(markdown: &str) -> String {
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    let link = regex(&LINK, r"\[([^\]]*)\]\([^)]*\)");
+    let mut kept = Vec::new();
+    let mut in_fence = false;
+    for block in markdown.split("\n\n") {
+        let fences = block.matches("```").count();
+        let keep = if in_fence || block.starts_with("```") || block.starts_with('#') {
+            true
+        } else {
+            let visible = link.replace_all(block, "$1");
+            let visible_len = visible.trim().chars().count();
+            let link_len = link
+                .captures_iter(block)
+                .map(|captures| captures[1].chars().count())
+                .sum::<usize>();
+            visible_len > 0 && (link_len as f64) / (visible_len as f64) <= MAX_LINK_DENSITY
+        };
+        if fences % 2 == 1 {
+            in_fence = !in_fence;
+        }
+        if keep {
+            kept.push(block);
+        }
+    }
+    kept.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r##"<!DOCTYPE html>
+<html><head>
+  <title>Fallback Title</title>
+  <meta property="og:title" content="Local LLMs &amp; You">
+  <meta name="author" content="Aiko Tanaka">
+  <meta property="article:published_time" content="2024-05-01T09:00:00Z">
+  <meta property="og:site_name" content="Example Blog">
+  <script>var tracking = "<p>not content</p>";</script>
+  <style>p { color: red; }</style>
+</head>
+<body>
+  <nav><a href="/">Home</a> <a href="/about">About</a></nav>
+  <article>
+    <h1>Local LLMs &amp; You</h1>
+    <p>Running models <strong>locally</strong> keeps data on your machine.
+       See the <a href="/docs/setup">setup guide</a> for details.</p>
+    <ul><li>Privacy</li><li>Offline use</li></ul>
+    <pre><code>cargo run --release
+</code></pre>
+    <p><a href="/tag/a">a</a> <a href="/tag/b">b</a> <a href="/tag/c">c</a></p>
+  </article>
+  <footer>Copyright</footer>
+</body></html>"##;
+
+    #[test]
+    fn extracts_metadata_from_meta_tags() {
+        let article = extract_article(PAGE, None);
+        assert_eq!(article.metadata.title.as_deref(), Some("Local LLMs & You"));
+        assert_eq!(article.metadata.author.as_deref(), Some("Aiko Tanaka"));
+        assert_eq!(
+            article.metadata.published.as_deref(),
+            Some("2024-05-01T09:00:00Z")
+        );
+        assert_eq!(article.metadata.site_name.as_deref(), Some("Example Blog"));
+    }
+
+    #[test]
+    fn converts_article_body_to_markdown_without_chrome() {
+        let base = Url::parse("https://blog.example.com/posts/llm").unwrap();
+        let article = extract_article(PAGE, Some(&base));
+        let md = &article.markdown;
+
+        assert!(md.starts_with("# Local LLMs & You"));
+        assert!(md.contains("Running models **locally** keeps data on your machine."));
+        assert!(md.contains("[setup guide](https://blog.example.com/docs/setup)"));
+        assert!(md.contains("- Privacy\n- Offline use"));
+        assert!(md.contains("```\ncargo run --release\n```"));
+        assert!(!md.contains("Home"));
+        assert!(!md.contains("Copyright"));
+        assert!(!md.contains("not content"));
+        // tag cloud is all links and gets pruned
+        assert!(!md.contains("/tag/a"));
+    }
+
+    #[test]
+    fn nested_articles_use_outer_range_and_body_is_fallback() {
+        let html = "<body><article><p>outer</p><article><p>inner</p></article><p>tail</p></article></body>";
+        assert_eq!(
+            extract_article(html, None).markdown,
+            "outer\n\ninner\n\ntail"
+        );
+        let plain = "<html><body><div><p>Hello <em>there</em></p></div></body></html>";
+        assert_eq!(extract_article(plain, None).markdown, "Hello *there*");
+    }
+
+    #[test]
+    fn detects_html_bodies() {
+        assert!(looks_like_html(Some("text/html; charset=utf-8"), ""));
+        assert!(!looks_like_html(Some("application/json"), "<html>"));
+        assert!(looks_like_html(None, "  <!DOCTYPE html><html>"));
+        assert!(!looks_like_html(Some("text/plain"), "just text"));
+        assert_eq!(decode_entities("&#x27;a&#39; &amp;lt;"), "'a' &lt;");
+    }
+}
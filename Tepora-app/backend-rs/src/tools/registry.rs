@@ -0,0 +1,614 @@
+//! ネイティブツールの宣言的レジストリ。
+//!
+//! 名前・説明・必要権限・グループ・引数スキーマ・実行関数をツールごとに一か所で登録する。
+//! ディスパッチャ（`ToolNode` 経由の実行）、`GET /api/tools` の一覧、`ToolWorker` の
+//! プロンプト用ツール定義はすべてここを参照する。引数スキーマは MCP の `inputSchema` /
+//! OpenAI 互換の `parameters` と同じ JSON Schema 形式で返す。
+
+use std::future::{ready, Future};
+use std::pin::Pin;
+
+use schemars::JsonSchema;
+use serde_json::Value;
+
+use crate::core::desktop_bridge::DesktopCapability;
+use crate::core::errors::ApiError;
+use crate::core::native_tools::{
+    ToolCapability, NATIVE_CALCULATE, NATIVE_EXPAND_TOOL_RESULT, NATIVE_FETCH_FEED,
+    NATIVE_FILE_LIST, NATIVE_FILE_READ, NATIVE_FILE_WRITE, NATIVE_RAG_CLEAR_SESSION,
+    NATIVE_RAG_GET_CHUNK, NATIVE_RAG_GET_CHUNK_WINDOW, NATIVE_RAG_INGEST, NATIVE_RAG_REINDEX,
+    NATIVE_RAG_SEARCH, NATIVE_RAG_TEXT_SEARCH, NATIVE_READ_CLIPBOARD, NATIVE_RUN_COMMAND,
+    NATIVE_SCREENSHOT, NATIVE_SEARCH, NATIVE_WEB_FETCH, NATIVE_WIKI_LOOKUP,
+};
+use crate::state::AppState;
+
+use super::calculator::execute_calculate;
+use super::desktop::execute_desktop_tool;
+use super::dispatcher::ToolExecution;
+use super::feeds::execute_fetch_feed;
+use super::filesystem::execute_file_tool;
+use super::rag::{
+    execute_rag_clear_session, execute_rag_get_chunk, execute_rag_get_chunk_window,
+    execute_rag_ingest, execute_rag_reindex, execute_rag_search, execute_rag_text_search,
+};
+use super::shell::execute_run_command_audited;
+use super::web::{execute_search, execute_web_fetch};
+use super::wiki::execute_wiki_lookup;
+
+/// 実行関数に渡す呼び出し文脈
+#[derive(Clone, Copy)]
+pub struct ToolCall<'a> {
+    pub state: Option<&'a AppState>,
+    pub config: &'a Value,
+    pub session_id: Option<&'a str>,
+    pub args: &'a Value,
+}
+
+pub type ToolFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<ToolExecution, ApiError>> + Send + 'a>>;
+
+pub type ToolHandler = for<'a> fn(ToolCall<'a>) -> ToolFuture<'a>;
+
+/// プロンプトに載せるかをモードごとに決めるための分類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolGroup {
+    Web,
+    Rag,
+    Files,
+    Shell,
+    Desktop,
+    /// どのモードでも使える補助ツール
+    Utility,
+}
+
+/// ネイティブツール 1 件の登録内容
+#[derive(Clone, Copy)]
+pub struct NativeToolSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub capability: ToolCapability,
+    pub group: ToolGroup,
+    pub parameters: fn() -> Value,
+    /// `None` はグラフ側が実行するツール（`expand_tool_result` は折りたたみ結果を持つエージェント実行ノードが扱う）
+    pub handler: Option<ToolHandler>,
+}
+
+impl NativeToolSpec {
+    /// 引数の JSON Schema（`type: object`）
+    pub fn input_schema(&self) -> Value {
+        (self.parameters)()
+    }
+
+    /// プロンプト用の 1 行定義。`name(arg: type, opt?: type) — 説明`
+    pub fn prompt_signature(&self) -> String {
+        let schema = self.input_schema();
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|names| names.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let properties = schema.get("properties").and_then(Value::as_object);
+        // 必須引数を先に並べる（プロパティの並びはスキーマ生成側で保証されない）
+        let mut names: Vec<&str> = required.clone();
+        names.extend(
+            properties
+                .into_iter()
+                .flat_map(|properties| properties.keys())
+                .map(String::as_str)
+                .filter(|name| !required.contains(name)),
+        );
+        let params = names
+            .into_iter()
+            .map(|name| {
+                let kind = properties
+                    .and_then(|properties| properties.get(name))
+                    .map(schema_type)
+                    .unwrap_or("any");
+                let optional = if required.contains(&name) { "" } else { "?" };
+                format!("{name}{optional}: {kind}")
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{}({}) — {}", self.name, params, self.description)
+    }
+}
+
+fn schema_type(property: &Value) -> &str {
+    match property.get("type") {
+        Some(Value::String(kind)) => kind,
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null")
+            .unwrap_or("any"),
+        _ => "any",
+    }
+}
+
+fn schema<T: JsonSchema>() -> Value {
+    let mut schema = schemars::schema_for!(T).to_value();
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("$schema");
+        object.remove("title");
+    }
+    schema
+}
+
+/// 正準名で探す。エイリアスは呼び出し側で `resolve_tool_alias` してから渡す。
+pub fn find(name: &str) -> Option<&'static NativeToolSpec> {
+    NATIVE_TOOLS.iter().find(|tool| tool.name == name)
+}
+
+// --- 引数スキーマ ---
+// 実行関数は従来どおり `Value` から読む（`q` や `input` などの別名も受け付ける）。
+// ここの型はモデルと外部クライアントに見せる正式な引数を表す。
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct WebFetchParams {
+    /// Absolute http(s) URL
+    url: String,
+    /// Return the unprocessed body instead of extracted markdown
+    raw: Option<bool>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct SearchParams {
+    query: String,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct FetchFeedParams {
+    /// Feed URL; all subscribed feeds when omitted
+    url: Option<String>,
+    /// Maximum number of items
+    limit: Option<u32>,
+    /// Only items published within this many hours
+    since_hours: Option<u32>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct WikiLookupParams {
+    /// Article title or word
+    query: String,
+    /// Section heading to read instead of the lead
+    section: Option<String>,
+    /// wikipedia (default) or wiktionary
+    project: Option<String>,
+    /// Language edition, e.g. en or ja
+    lang: Option<String>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct RagSearchParams {
+    query: String,
+    /// 1-20
+    limit: Option<u32>,
+    /// Shared collection (feeds or the session's project); the session when omitted
+    collection: Option<String>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct RagIngestParams {
+    content: String,
+    /// Where the content came from
+    source: Option<String>,
+    metadata: Option<Value>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct RagTextSearchParams {
+    pattern: String,
+    /// 1-50
+    limit: Option<u32>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct RagChunkParams {
+    chunk_id: String,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct RagChunkWindowParams {
+    chunk_id: String,
+    /// Characters of context around the chunk
+    chars: Option<u32>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct RagClearSessionParams {
+    /// Defaults to the current session
+    session_id: Option<String>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct RagReindexParams {
+    /// Embedding model ID; the configured one when omitted
+    embedding_model: Option<String>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct ExpandToolResultParams {
+    result_id: String,
+    /// JSON pointer (/items/3) or line range (lines:40-80)
+    section: String,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct FileReadParams {
+    path: String,
+    /// First line to return (1-based)
+    offset: Option<u32>,
+    /// Number of lines to return
+    limit: Option<u32>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct FileListParams {
+    /// Directory inside a workspace root; lists the roots when omitted
+    path: Option<String>,
+    recursive: Option<bool>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct FileWriteParams {
+    path: String,
+    content: String,
+    /// overwrite (default), append or create
+    mode: Option<String>,
+    /// Show the diff without writing
+    dry_run: Option<bool>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct RunCommandParams {
+    /// Command line, e.g. "git status -s"
+    command: String,
+    /// Working directory inside a workspace root
+    cwd: Option<String>,
+    timeout_secs: Option<u32>,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct CalculateParams {
+    /// e.g. 2^10 / 3, 5 km to mi, 2026-10-15 + 3 weeks
+    expression: String,
+}
+
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct DesktopParams {
+    /// Shown to the user in the confirmation dialog
+    reason: Option<String>,
+}
+
+// --- 実行関数 ---
+
+fn web_fetch(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_web_fetch(call.config, call.args))
+}
+
+fn search(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_search(call.config, call.args))
+}
+
+fn fetch_feed(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_fetch_feed(call.config, call.args))
+}
+
+fn wiki_lookup(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_wiki_lookup(call.config, call.args))
+}
+
+fn rag_search(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_rag_search(
+        call.state,
+        call.config,
+        call.session_id,
+        call.args,
+    ))
+}
+
+fn rag_ingest(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_rag_ingest(call.state, call.session_id, call.args))
+}
+
+fn rag_text_search(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_rag_text_search(
+        call.state,
+        call.config,
+        call.session_id,
+        call.args,
+    ))
+}
+
+fn rag_get_chunk(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_rag_get_chunk(call.state, call.args))
+}
+
+fn rag_get_chunk_window(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_rag_get_chunk_window(
+        call.state,
+        call.config,
+        call.session_id,
+        call.args,
+    ))
+}
+
+fn rag_clear_session(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_rag_clear_session(
+        call.state,
+        call.session_id,
+        call.args,
+    ))
+}
+
+fn rag_reindex(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_rag_reindex(call.state, call.args))
+}
+
+fn file_tool<'a>(call: ToolCall<'a>, tool: &'a str) -> ToolFuture<'a> {
+    Box::pin(execute_file_tool(
+        call.state,
+        call.config,
+        call.session_id,
+        tool,
+        call.args,
+    ))
+}
+
+fn file_read(call: ToolCall<'_>) -> ToolFuture<'_> {
+    file_tool(call, "file_read")
+}
+
+fn file_list(call: ToolCall<'_>) -> ToolFuture<'_> {
+    file_tool(call, "file_list")
+}
+
+fn file_write(call: ToolCall<'_>) -> ToolFuture<'_> {
+    file_tool(call, "file_write")
+}
+
+fn run_command(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_run_command_audited(
+        call.state,
+        call.config,
+        call.session_id,
+        call.args,
+    ))
+}
+
+fn calculate(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(ready(execute_calculate(call.args)))
+}
+
+fn read_clipboard(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_desktop_tool(
+        call.state,
+        call.session_id,
+        DesktopCapability::ClipboardText,
+        call.args,
+    ))
+}
+
+fn screenshot(call: ToolCall<'_>) -> ToolFuture<'_> {
+    Box::pin(execute_desktop_tool(
+        call.state,
+        call.session_id,
+        DesktopCapability::Screenshot,
+        call.args,
+    ))
+}
+
+/// 全ネイティブツール（定義順）
+pub const NATIVE_TOOLS: &[NativeToolSpec] = &[
+    NativeToolSpec {
+        name: NATIVE_WEB_FETCH,
+        description: "Fetch a URL as readable markdown (raw=true for the unprocessed body)",
+        capability: ToolCapability::Network,
+        group: ToolGroup::Web,
+        parameters: schema::<WebFetchParams>,
+        handler: Some(web_fetch),
+    },
+    NativeToolSpec {
+        name: NATIVE_SEARCH,
+        description: "Search the web",
+        capability: ToolCapability::Network,
+        group: ToolGroup::Web,
+        parameters: schema::<SearchParams>,
+        handler: Some(search),
+    },
+    NativeToolSpec {
+        name: NATIVE_FETCH_FEED,
+        description: "Read an RSS/Atom feed, or all subscribed feeds when url is omitted",
+        capability: ToolCapability::Network,
+        group: ToolGroup::Web,
+        parameters: schema::<FetchFeedParams>,
+        handler: Some(fetch_feed),
+    },
+    NativeToolSpec {
+        name: NATIVE_WIKI_LOOKUP,
+        description:
+            "Look up a Wikipedia or Wiktionary entry: lead and section list, or one section",
+        capability: ToolCapability::Network,
+        group: ToolGroup::Web,
+        parameters: schema::<WikiLookupParams>,
+        handler: Some(wiki_lookup),
+    },
+    NativeToolSpec {
+        name: NATIVE_RAG_SEARCH,
+        description: "Search RAG by embedding similarity",
+        capability: ToolCapability::Local,
+        group: ToolGroup::Rag,
+        parameters: schema::<RagSearchParams>,
+        handler: Some(rag_search),
+    },
+    NativeToolSpec {
+        name: NATIVE_RAG_INGEST,
+        description: "Ingest text into RAG",
+        capability: ToolCapability::Local,
+        group: ToolGroup::Rag,
+        parameters: schema::<RagIngestParams>,
+        handler: Some(rag_ingest),
+    },
+    NativeToolSpec {
+        name: NATIVE_RAG_TEXT_SEARCH,
+        description: "Search RAG by text pattern",
+        capability: ToolCapability::Local,
+        group: ToolGroup::Rag,
+        parameters: schema::<RagTextSearchParams>,
+        handler: Some(rag_text_search),
+    },
+    NativeToolSpec {
+        name: NATIVE_RAG_GET_CHUNK,
+        description: "Get one RAG chunk by ID",
+        capability: ToolCapability::Local,
+        group: ToolGroup::Rag,
+        parameters: schema::<RagChunkParams>,
+        handler: Some(rag_get_chunk),
+    },
+    NativeToolSpec {
+        name: NATIVE_RAG_GET_CHUNK_WINDOW,
+        description: "Get neighboring RAG chunks around one chunk",
+        capability: ToolCapability::Local,
+        group: ToolGroup::Rag,
+        parameters: schema::<RagChunkWindowParams>,
+        handler: Some(rag_get_chunk_window),
+    },
+    NativeToolSpec {
+        name: NATIVE_RAG_CLEAR_SESSION,
+        description: "Clear all RAG chunks for a session",
+        capability: ToolCapability::Local,
+        group: ToolGroup::Rag,
+        parameters: schema::<RagClearSessionParams>,
+        handler: Some(rag_clear_session),
+    },
+    NativeToolSpec {
+        name: NATIVE_RAG_REINDEX,
+        description: "Reindex RAG with a specific embedding model",
+        capability: ToolCapability::Local,
+        group: ToolGroup::Rag,
+        parameters: schema::<RagReindexParams>,
+        handler: Some(rag_reindex),
+    },
+    NativeToolSpec {
+        name: NATIVE_EXPAND_TOOL_RESULT,
+        description: "Show one section of a folded tool result",
+        capability: ToolCapability::Local,
+        group: ToolGroup::Utility,
+        parameters: schema::<ExpandToolResultParams>,
+        handler: None,
+    },
+    NativeToolSpec {
+        name: NATIVE_FILE_READ,
+        description: "Read a text file inside a workspace root",
+        capability: ToolCapability::FilesystemRead,
+        group: ToolGroup::Files,
+        parameters: schema::<FileReadParams>,
+        handler: Some(file_read),
+    },
+    NativeToolSpec {
+        name: NATIVE_FILE_LIST,
+        description: "List workspace roots, or a directory inside one",
+        capability: ToolCapability::FilesystemRead,
+        group: ToolGroup::Files,
+        parameters: schema::<FileListParams>,
+        handler: Some(file_list),
+    },
+    NativeToolSpec {
+        name: NATIVE_FILE_WRITE,
+        description: "Write a file inside a workspace root",
+        capability: ToolCapability::FilesystemWrite,
+        group: ToolGroup::Files,
+        parameters: schema::<FileWriteParams>,
+        handler: Some(file_write),
+    },
+    NativeToolSpec {
+        name: NATIVE_RUN_COMMAND,
+        description: "Run a whitelisted command in a workspace root",
+        capability: ToolCapability::Process,
+        group: ToolGroup::Shell,
+        parameters: schema::<RunCommandParams>,
+        handler: Some(run_command),
+    },
+    NativeToolSpec {
+        name: NATIVE_CALCULATE,
+        description:
+            "Evaluate arithmetic, convert units (5 km to mi) or do date math (2026-10-15 + 3 weeks)",
+        capability: ToolCapability::Local,
+        group: ToolGroup::Utility,
+        parameters: schema::<CalculateParams>,
+        handler: Some(calculate),
+    },
+    NativeToolSpec {
+        name: NATIVE_READ_CLIPBOARD,
+        description: "Read clipboard text from the desktop app; the user confirms each use",
+        capability: ToolCapability::Local,
+        group: ToolGroup::Desktop,
+        parameters: schema::<DesktopParams>,
+        handler: Some(read_clipboard),
+    },
+    NativeToolSpec {
+        name: NATIVE_SCREENSHOT,
+        description:
+            "Capture the screen as an image from the desktop app; the user confirms each use",
+        capability: ToolCapability::Local,
+        group: ToolGroup::Desktop,
+        parameters: schema::<DesktopParams>,
+        handler: Some(screenshot),
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn all_native_tool_names_are_unique() {
+        let names: Vec<_> = NATIVE_TOOLS.iter().map(|t| t.name).collect();
+        let mut unique = names.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(
+            names.len(),
+            unique.len(),
+            "Duplicate native tool names found"
+        );
+    }
+
+    #[test]
+    fn every_tool_has_an_object_schema_and_a_signature() {
+        for tool in NATIVE_TOOLS {
+            let schema = tool.input_schema();
+            assert_eq!(schema["type"], json!("object"), "{}", tool.name);
+            assert!(schema.get("$schema").is_none());
+            assert!(tool.prompt_signature().starts_with(tool.name));
+        }
+
+        let fetch = find(NATIVE_WEB_FETCH).unwrap();
+        assert_eq!(fetch.input_schema()["required"], json!(["url"]));
+        assert_eq!(
+            fetch.prompt_signature(),
+            "native_web_fetch(url: string, raw?: boolean) — Fetch a URL as readable markdown (raw=true for the unprocessed body)"
+        );
+        assert!(find(NATIVE_EXPAND_TOOL_RESULT).unwrap().handler.is_none());
+        assert!(find("web_search").is_none());
+    }
+}
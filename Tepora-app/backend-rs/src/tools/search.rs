@@ -1,7 +1,30 @@
+//! Web search providers.
+//!
+//! `tools.search.providers` の順に問い合わせ、未設定・失敗・結果 0 件の
+//! プロバイダはスキップして次へフォールバックする。リストが無い場合は
+//! 旧来の `tools.search_provider` → DuckDuckGo の順になる。
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::Client;
 use serde::Serialize;
 use serde_json::Value;
 
+use super::readability::decode_entities;
+use crate::core::config::schema::NetworkSubsystem;
 use crate::core::errors::ApiError;
+use crate::core::network::NetClient;
+
+pub const SEARCH_PROVIDER_NAMES: &[&str] = &["searxng", "brave", "duckduckgo", "google", "bing"];
+
+const DEFAULT_MAX_RESULTS: usize = 10;
+const DEFAULT_TIMEOUT_SECS: u64 = 15;
+const DUCKDUCKGO_HTML_URL: &str = "https://html.duckduckgo.com/html/";
+const BROWSER_USER_AGENT: &str =
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0 Safari/537.36";
 
 #[derive(Debug, Clone, Serialize)]
 pub struct SearchResult {
@@ -10,281 +33,465 @@ pub struct SearchResult {
     pub snippet: String,
 }
 
+#[async_trait]
+pub trait SearchProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn search(&self, client: &NetClient, query: &str) -> Result<Vec<SearchResult>, ApiError>;
+}
+
 pub async fn perform_search(config: &Value, query: &str) -> Result<Vec<SearchResult>, ApiError> {
-    let provider = config
-        .get("tools")
-        .and_then(|v| v.get("search_provider"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("google");
-
-    match provider {
-        "brave" => {
-            let api_key = config
-                .get("tools")
-                .and_then(|v| v.get("brave_search_api_key"))
-                .and_then(|v| v.as_str())
-                .unwrap_or("");
-            if !api_key.is_empty() {
-                return brave_search(query, api_key).await;
+    let providers = configured_providers(config);
+    let max_results = search_setting_u64(config, "max_results")
+        .map(|value| value.clamp(1, 50) as usize)
+        .unwrap_or(DEFAULT_MAX_RESULTS);
+    let timeout_secs = search_setting_u64(config, "timeout_secs")
+        .map(|value| value.clamp(1, 120))
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+    let client = NetClient::from_builder(
+        NetworkSubsystem::Search,
+        Client::builder().timeout(Duration::from_secs(timeout_secs)),
+    )?;
+
+    let mut last_error = None;
+    let mut blocked_offline = None;
+    for provider in &providers {
+        match provider.search(&client, query).await {
+            Ok(mut results) if !results.is_empty() => {
+                results.truncate(max_results);
+                return Ok(results);
             }
-        }
-        "bing" => {
-            let api_key = config
-                .get("tools")
-                .and_then(|v| v.get("bing_search_api_key"))
-                .and_then(|v| v.as_str())
-                .unwrap_or("");
-            if !api_key.is_empty() {
-                return bing_search(query, api_key).await;
+            Ok(_) => {
+                tracing::debug!("Search provider '{}' returned no results", provider.name());
+            }
+            Err(ApiError::Offline(subsystem)) => {
+                blocked_offline = Some(subsystem);
+            }
+            Err(err) => {
+                tracing::warn!("Search provider '{}' failed: {}", provider.name(), err);
+                last_error = Some(err);
             }
         }
-        "google" => {
-            let api_key = config
-                .get("tools")
-                .and_then(|v| v.get("google_search_api_key"))
-                .and_then(|v| v.as_str())
-                .unwrap_or("");
-            let engine_id = config
-                .get("tools")
-                .and_then(|v| v.get("google_search_engine_id"))
-                .and_then(|v| v.as_str())
-                .unwrap_or("");
-
-            if !api_key.is_empty() && !engine_id.is_empty() {
-                if let Ok(results) = google_search(query, api_key, engine_id).await {
-                    if !results.is_empty() {
-                        return Ok(results);
-                    }
+    }
+
+    // ローカルの SearXNG などは通る。結果が得られず止めたものがあれば、個別許可を促せるようそちらを返す
+    if let Some(subsystem) = blocked_offline {
+        return Err(ApiError::Offline(subsystem));
+    }
+    match last_error {
+        Some(err) if providers.len() == 1 => Err(err),
+        Some(err) => Err(ApiError::Internal(format!(
+            "All search providers failed (last error: {})",
+            err
+        ))),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Providers in fallback order, skipping ones that lack credentials.
+pub fn configured_providers(config: &Value) -> Vec<Box<dyn SearchProvider>> {
+    provider_order(config)
+        .iter()
+        .filter_map(|name| build_provider(config, name))
+        .collect()
+}
+
+fn provider_order(config: &Value) -> Vec<String> {
+    let mut order = Vec::new();
+    let listed = config
+        .get("tools")
+        .and_then(|tools| tools.get("search"))
+        .and_then(|search| search.get("providers"))
+        .and_then(Value::as_array);
+    match listed {
+        Some(items) => {
+            for name in items.iter().filter_map(Value::as_str) {
+                let name = name.trim().to_ascii_lowercase();
+                if !order.contains(&name) {
+                    order.push(name);
                 }
             }
         }
-        _ => {}
+        None => {
+            let legacy = tools_str(config, "search_provider").unwrap_or("google");
+            order.push(legacy.to_ascii_lowercase());
+            if legacy != "duckduckgo" {
+                order.push("duckduckgo".to_string());
+            }
+        }
+    }
+    order
+}
+
+fn build_provider(config: &Value, name: &str) -> Option<Box<dyn SearchProvider>> {
+    match name {
+        "searxng" => {
+            let base_url = search_setting_str(config, "searxng_url")?;
+            Some(Box::new(SearxngProvider {
+                base_url: base_url.trim_end_matches('/').to_string(),
+            }))
+        }
+        "brave" => Some(Box::new(BraveProvider {
+            api_key: tools_str(config, "brave_search_api_key")?.to_string(),
+        })),
+        "duckduckgo" => Some(Box::new(DuckDuckGoHtmlProvider)),
+        "google" => Some(Box::new(GoogleProvider {
+            api_key: tools_str(config, "google_search_api_key")?.to_string(),
+            engine_id: tools_str(config, "google_search_engine_id")?.to_string(),
+        })),
+        "bing" => Some(Box::new(BingProvider {
+            api_key: tools_str(config, "bing_search_api_key")?.to_string(),
+        })),
+        _ => None,
     }
+}
+
+fn tools_str<'a>(config: &'a Value, key: &str) -> Option<&'a str> {
+    config
+        .get("tools")
+        .and_then(|tools| tools.get(key))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
 
-    // Fallback or default
-    duckduckgo_search(query).await
+fn search_setting_str<'a>(config: &'a Value, key: &str) -> Option<&'a str> {
+    config
+        .get("tools")
+        .and_then(|tools| tools.get("search"))
+        .and_then(|search| search.get(key))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
 }
 
-async fn google_search(
-    query: &str,
-    api_key: &str,
-    engine_id: &str,
-) -> Result<Vec<SearchResult>, ApiError> {
-    let url = format!(
-        "https://www.googleapis.com/customsearch/v1?key={}&cx={}&q={}",
-        api_key,
-        engine_id,
-        urlencoding::encode(query)
-    );
-
-    let response = reqwest::Client::new()
-        .get(url)
-        .send()
-        .await
-        .map_err(ApiError::internal)?;
+fn search_setting_u64(config: &Value, key: &str) -> Option<u64> {
+    config
+        .get("tools")
+        .and_then(|tools| tools.get("search"))
+        .and_then(|search| search.get(key))
+        .and_then(Value::as_u64)
+}
 
+async fn fetch_json(provider: &str, request: reqwest::RequestBuilder) -> Result<Value, ApiError> {
+    let response = request.send().await.map_err(ApiError::internal)?;
     if !response.status().is_success() {
         return Err(ApiError::Internal(format!(
-            "Google search failed: {}",
+            "{} search failed: {}",
+            provider,
             response.status()
         )));
     }
+    response.json().await.map_err(ApiError::internal)
+}
 
-    let payload: Value = response.json().await.map_err(ApiError::internal)?;
-    let items = payload
-        .get("items")
-        .and_then(|v| v.as_array())
-        .cloned()
-        .unwrap_or_default();
+fn collect_results(
+    items: &[Value],
+    title_key: &str,
+    url_key: &str,
+    snippet_key: &str,
+) -> Vec<SearchResult> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let title = item.get(title_key).and_then(Value::as_str).unwrap_or("");
+            let url = item.get(url_key).and_then(Value::as_str).unwrap_or("");
+            let snippet = item.get(snippet_key).and_then(Value::as_str).unwrap_or("");
+            (!title.is_empty() && !url.is_empty()).then(|| SearchResult {
+                title: title.to_string(),
+                url: url.to_string(),
+                snippet: snippet.to_string(),
+            })
+        })
+        .collect()
+}
 
-    let mut results = Vec::new();
-    for item in items {
-        let title = item
-            .get("title")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-        let url = item
-            .get("link")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-        let snippet = item
-            .get("snippet")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-        if !title.is_empty() && !url.is_empty() {
-            results.push(SearchResult {
-                title,
-                url,
-                snippet,
-            });
-        }
+struct SearxngProvider {
+    base_url: String,
+}
+
+#[async_trait]
+impl SearchProvider for SearxngProvider {
+    fn name(&self) -> &'static str {
+        "searxng"
     }
 
-    Ok(results)
+    async fn search(&self, client: &NetClient, query: &str) -> Result<Vec<SearchResult>, ApiError> {
+        let url = format!(
+            "{}/search?q={}&format=json",
+            self.base_url,
+            urlencoding::encode(query)
+        );
+        let payload = fetch_json("SearXNG", client.get(url)?).await?;
+        Ok(parse_searxng(&payload))
+    }
 }
 
-async fn duckduckgo_search(query: &str) -> Result<Vec<SearchResult>, ApiError> {
-    let url = format!(
-        "https://api.duckduckgo.com/?q={}&format=json&no_redirect=1&no_html=1",
-        urlencoding::encode(query)
-    );
+fn parse_searxng(payload: &Value) -> Vec<SearchResult> {
+    payload
+        .get("results")
+        .and_then(Value::as_array)
+        .map(|items| collect_results(items, "title", "url", "content"))
+        .unwrap_or_default()
+}
 
-    let response = reqwest::Client::new()
-        .get(url)
-        .send()
-        .await
-        .map_err(ApiError::internal)?;
+struct BraveProvider {
+    api_key: String,
+}
 
-    if !response.status().is_success() {
-        return Err(ApiError::Internal(format!(
-            "DuckDuckGo search failed: {}",
-            response.status()
-        )));
+#[async_trait]
+impl SearchProvider for BraveProvider {
+    fn name(&self) -> &'static str {
+        "brave"
     }
 
-    let payload: Value = response.json().await.map_err(ApiError::internal)?;
-    let mut results = Vec::new();
-
-    if let Some(abstract_text) = payload.get("AbstractText").and_then(|v| v.as_str()) {
-        if let Some(url) = payload.get("AbstractURL").and_then(|v| v.as_str()) {
-            if !abstract_text.is_empty() && !url.is_empty() {
-                results.push(SearchResult {
-                    title: abstract_text
-                        .split(" - ")
-                        .next()
-                        .unwrap_or(abstract_text)
-                        .to_string(),
-                    url: url.to_string(),
-                    snippet: abstract_text.to_string(),
-                });
-            }
-        }
+    async fn search(&self, client: &NetClient, query: &str) -> Result<Vec<SearchResult>, ApiError> {
+        let url = format!(
+            "https://api.search.brave.com/res/v1/web/search?q={}",
+            urlencoding::encode(query)
+        );
+        let request = client
+            .get(url)?
+            .header("X-Subscription-Token", &self.api_key)
+            .header("Accept", "application/json");
+        let payload = fetch_json("Brave", request).await?;
+        Ok(payload
+            .get("web")
+            .and_then(|web| web.get("results"))
+            .and_then(Value::as_array)
+            .map(|items| collect_results(items, "title", "url", "description"))
+            .unwrap_or_default())
     }
+}
+
+struct GoogleProvider {
+    api_key: String,
+    engine_id: String,
+}
 
-    if let Some(items) = payload.get("Results").and_then(|v| v.as_array()) {
-        extract_ddg_topics(items, &mut results);
+#[async_trait]
+impl SearchProvider for GoogleProvider {
+    fn name(&self) -> &'static str {
+        "google"
     }
-    if let Some(items) = payload.get("RelatedTopics").and_then(|v| v.as_array()) {
-        extract_ddg_topics(items, &mut results);
+
+    async fn search(&self, client: &NetClient, query: &str) -> Result<Vec<SearchResult>, ApiError> {
+        let url = format!(
+            "https://www.googleapis.com/customsearch/v1?key={}&cx={}&q={}",
+            self.api_key,
+            self.engine_id,
+            urlencoding::encode(query)
+        );
+        let payload = fetch_json("Google", client.get(url)?).await?;
+        Ok(payload
+            .get("items")
+            .and_then(Value::as_array)
+            .map(|items| collect_results(items, "title", "link", "snippet"))
+            .unwrap_or_default())
     }
+}
 
-    Ok(results)
+struct BingProvider {
+    api_key: String,
 }
 
-fn extract_ddg_topics(items: &[Value], results: &mut Vec<SearchResult>) {
-    for item in items {
-        if let Some(topics) = item.get("Topics").and_then(|v| v.as_array()) {
-            extract_ddg_topics(topics, results);
-            continue;
-        }
-        let text = item.get("Text").and_then(|v| v.as_str()).unwrap_or("");
-        let url = item.get("FirstURL").and_then(|v| v.as_str()).unwrap_or("");
-        if text.is_empty() || url.is_empty() {
-            continue;
-        }
-        results.push(SearchResult {
-            title: text.split(" - ").next().unwrap_or(text).to_string(),
-            url: url.to_string(),
-            snippet: text.to_string(),
-        });
+#[async_trait]
+impl SearchProvider for BingProvider {
+    fn name(&self) -> &'static str {
+        "bing"
+    }
+
+    async fn search(&self, client: &NetClient, query: &str) -> Result<Vec<SearchResult>, ApiError> {
+        let url = format!(
+            "https://api.bing.microsoft.com/v7.0/search?q={}",
+            urlencoding::encode(query)
+        );
+        let request = client
+            .get(url)?
+            .header("Ocp-Apim-Subscription-Key", &self.api_key);
+        let payload = fetch_json("Bing", request).await?;
+        Ok(payload
+            .get("webPages")
+            .and_then(|pages| pages.get("value"))
+            .and_then(Value::as_array)
+            .map(|items| collect_results(items, "name", "url", "snippet"))
+            .unwrap_or_default())
     }
 }
 
-async fn brave_search(query: &str, api_key: &str) -> Result<Vec<SearchResult>, ApiError> {
-    let url = format!(
-        "https://api.search.brave.com/res/v1/web/search?q={}",
-        urlencoding::encode(query)
-    );
+/// Keyless fallback that scrapes the DuckDuckGo HTML endpoint.
+struct DuckDuckGoHtmlProvider;
 
-    let response = reqwest::Client::new()
-        .get(url)
-        .header("X-Subscription-Token", api_key)
-        .header("Accept", "application/json")
-        .send()
-        .await
-        .map_err(ApiError::internal)?;
+#[async_trait]
+impl SearchProvider for DuckDuckGoHtmlProvider {
+    fn name(&self) -> &'static str {
+        "duckduckgo"
+    }
 
-    if !response.status().is_success() {
-        return Err(ApiError::Internal(format!(
-            "Brave search failed: {}",
-            response.status()
-        )));
+    async fn search(&self, client: &NetClient, query: &str) -> Result<Vec<SearchResult>, ApiError> {
+        let response = client
+            .get(DUCKDUCKGO_HTML_URL)?
+            .query(&[("q", query)])
+            .header("User-Agent", BROWSER_USER_AGENT)
+            .send()
+            .await
+            .map_err(ApiError::internal)?;
+        if !response.status().is_success() {
+            return Err(ApiError::Internal(format!(
+                "DuckDuckGo search failed: {}",
+                response.status()
+            )));
+        }
+        let html = response.text().await.map_err(ApiError::internal)?;
+        Ok(parse_duckduckgo_html(&html))
     }
+}
 
-    let payload: Value = response.json().await.map_err(ApiError::internal)?;
+fn parse_duckduckgo_html(html: &str) -> Vec<SearchResult> {
+    static BLOCK: OnceLock<Regex> = OnceLock::new();
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    static SNIPPET: OnceLock<Regex> = OnceLock::new();
+    let block = BLOCK.get_or_init(|| Regex::new(r#"class="[^"]*\bresult\b[^"]*""#).unwrap());
+    let link = LINK.get_or_init(|| {
+        Regex::new(r#"(?s)<a[^>]*class="[^"]*result__a[^"]*"[^>]*href="([^"]*)"[^>]*>(.*?)</a>"#)
+            .unwrap()
+    });
+    let snippet = SNIPPET.get_or_init(|| {
+        Regex::new(r#"(?s)class="[^"]*result__snippet[^"]*"[^>]*>(.*?)</(?:a|div|td)>"#).unwrap()
+    });
+
+    // 結果ブロックごとに区切って、タイトルとスニペットの対応を崩さない
+    let starts = block.find_iter(html).map(|m| m.start()).collect::<Vec<_>>();
     let mut results = Vec::new();
-
-    if let Some(items) = payload
-        .get("web")
-        .and_then(|w| w.get("results"))
-        .and_then(|v| v.as_array())
-    {
-        for item in items {
-            let title = item.get("title").and_then(|v| v.as_str()).unwrap_or("");
-            let url = item.get("url").and_then(|v| v.as_str()).unwrap_or("");
-            let snippet = item
-                .get("description")
-                .and_then(|v| v.as_str())
-                .unwrap_or("");
-
-            if !title.is_empty() && !url.is_empty() {
-                results.push(SearchResult {
-                    title: title.to_string(),
-                    url: url.to_string(),
-                    snippet: snippet.to_string(),
-                });
-            }
+    for (index, start) in starts.iter().enumerate() {
+        let end = starts.get(index + 1).copied().unwrap_or(html.len());
+        let section = &html[*start..end];
+        let Some(captures) = link.captures(section) else {
+            continue;
+        };
+        let Some(url) = resolve_duckduckgo_href(&captures[1]) else {
+            continue;
+        };
+        let title = html_to_text(&captures[2]);
+        if title.is_empty() || results.iter().any(|r: &SearchResult| r.url == url) {
+            continue;
         }
+        let snippet = snippet
+            .captures(section)
+            .map(|captures| html_to_text(&captures[1]))
+            .unwrap_or_default();
+        results.push(SearchResult {
+            title,
+            url,
+            snippet,
+        });
     }
+    results
+}
 
-    Ok(results)
+/// Unwrap `//duckduckgo.com/l/?uddg=<target>` redirects; ad links are dropped.
+fn resolve_duckduckgo_href(href: &str) -> Option<String> {
+    let href = href.replace("&amp;", "&");
+    if href.contains("duckduckgo.com/y.js") {
+        return None;
+    }
+    if let Some(position) = href.find("uddg=") {
+        let encoded = href[position + 5..].split('&').next().unwrap_or("");
+        return urlencoding::decode(encoded)
+            .ok()
+            .map(|url| url.into_owned())
+            .filter(|url| url.starts_with("http"));
+    }
+    href.starts_with("http").then_some(href)
 }
 
-async fn bing_search(query: &str, api_key: &str) -> Result<Vec<SearchResult>, ApiError> {
-    let url = format!(
-        "https://api.bing.microsoft.com/v7.0/search?q={}",
-        urlencoding::encode(query)
-    );
+fn html_to_text(fragment: &str) -> String {
+    static TAGS: OnceLock<Regex> = OnceLock::new();
+    let tags = TAGS.get_or_init(|| Regex::new(r"<[^>]+>").unwrap());
+    let text = decode_entities(&tags.replace_all(fragment, ""));
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
 
-    let response = reqwest::Client::new()
-        .get(url)
-        .header("Ocp-Apim-Subscription-Key", api_key)
-        .send()
-        .await
-        .map_err(ApiError::internal)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
 
-    if !response.status().is_success() {
-        return Err(ApiError::Internal(format!(
-            "Bing search failed: {}",
-            response.status()
-        )));
+    fn names(config: &Value) -> Vec<&'static str> {
+        configured_providers(config)
+            .iter()
+            .map(|provider| provider.name())
+            .collect()
     }
 
-    let payload: Value = response.json().await.map_err(ApiError::internal)?;
-    let mut results = Vec::new();
+    #[test]
+    fn provider_order_follows_config_and_skips_unconfigured() {
+        let config = json!({
+            "tools": {
+                "brave_search_api_key": "key",
+                "search": {
+                    "providers": ["searxng", "brave", "google", "duckduckgo", "brave"],
+                    "searxng_url": "http://localhost:8888/"
+                }
+            }
+        });
+        assert_eq!(names(&config), vec!["searxng", "brave", "duckduckgo"]);
 
-    if let Some(items) = payload
-        .get("webPages")
-        .and_then(|wp| wp.get("value"))
-        .and_then(|v| v.as_array())
-    {
-        for item in items {
-            let title = item.get("name").and_then(|v| v.as_str()).unwrap_or("");
-            let url = item.get("url").and_then(|v| v.as_str()).unwrap_or("");
-            let snippet = item.get("snippet").and_then(|v| v.as_str()).unwrap_or("");
-
-            if !title.is_empty() && !url.is_empty() {
-                results.push(SearchResult {
-                    title: title.to_string(),
-                    url: url.to_string(),
-                    snippet: snippet.to_string(),
-                });
+        // searxng without a URL and google without an engine id are skipped
+        let config = json!({
+            "tools": {
+                "google_search_api_key": "key",
+                "search": { "providers": ["searxng", "google", "duckduckgo"] }
             }
-        }
+        });
+        assert_eq!(names(&config), vec!["duckduckgo"]);
+    }
+
+    #[test]
+    fn legacy_provider_falls_back_to_duckduckgo() {
+        let config = json!({
+            "tools": { "search_provider": "brave", "brave_search_api_key": "key" }
+        });
+        assert_eq!(names(&config), vec!["brave", "duckduckgo"]);
+        assert_eq!(names(&json!({})), vec!["duckduckgo"]);
+    }
+
+    #[test]
+    fn parses_duckduckgo_html_results() {
+        let html = r##"
+            <div class="result results_links results_links_deep web-result">
+              <h2 class="result__title">
+                <a rel="nofollow" class="result__a" href="//duckduckgo.com/l/?uddg=https%3A%2F%2Fwww.rust-lang.org%2F&amp;rut=abc">The <b>Rust</b> Programming Language</a>
+              </h2>
+              <a class="result__snippet" href="#">A language empowering everyone &amp; more.</a>
+            </div>
+            <div class="result result--ad">
+              <a class="result__a" href="https://duckduckgo.com/y.js?ad_provider=x">Ad</a>
+            </div>
+            <div class="result results_links web-result">
+              <a class="result__a" href="https://doc.rust-lang.org/book/">The Book</a>
+            </div>
+        "##;
+
+        let results = parse_duckduckgo_html(html);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].url, "https://www.rust-lang.org/");
+        assert_eq!(results[0].title, "The Rust Programming Language");
+        assert_eq!(results[0].snippet, "A language empowering everyone & more.");
+        assert_eq!(results[1].url, "https://doc.rust-lang.org/book/");
+        assert!(results[1].snippet.is_empty());
     }
 
-    Ok(results)
+    #[test]
+    fn parses_searxng_json() {
+        let payload = json!({
+            "results": [
+                { "title": "Tepora", "url": "https://example.com", "content": "local agent" },
+                { "title": "", "url": "https://skip.example.com" }
+            ]
+        });
+        let results = parse_searxng(&payload);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].snippet, "local agent");
+    }
 }
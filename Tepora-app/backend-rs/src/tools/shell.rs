@@ -0,0 +1,425 @@
+//! `run_command` — whitelisted binaries, no shell.
+//!
+//! `tools.shell.allowed_binaries` に載っているプログラムだけを直接起動する
+//! （シェル経由ではないのでパイプ・リダイレクトは使えない）。実行は毎回
+//! 承認フローを通り、`tools.shell.auto_approve_patterns` に一致するコマンド
+//! だけが承認なしで走る。作業ディレクトリはワークスペースルート配下に限る。
+
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+
+use crate::core::errors::ApiError;
+use crate::state::AppState;
+
+use super::dispatcher::ToolExecution;
+use super::filesystem::{resolve_workspace_dir, workspace_roots};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl ParsedCommand {
+    /// Normalized command line used for pattern matching and display.
+    pub fn display(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .map(|part| shlex::try_quote(part).map_or_else(|_| part.to_string(), |q| q.into()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Lowercased program name without `.exe`. Paths are not accepted so a
+    /// whitelisted name cannot point at an arbitrary binary.
+    fn binary_name(&self) -> Option<String> {
+        if self.program.contains(['/', '\\']) {
+            return None;
+        }
+        let name = self.program.to_ascii_lowercase();
+        Some(
+            name.strip_suffix(".exe")
+                .map(str::to_string)
+                .unwrap_or(name),
+        )
+    }
+}
+
+/// Accepts `{"command": "git status -s"}` or `{"program": "git", "args": [...]}`.
+pub fn parse_command_args(args: &Value) -> Result<ParsedCommand, ApiError> {
+    if let Some(program) = args.get("program").and_then(Value::as_str) {
+        let program = program.trim();
+        if program.is_empty() {
+            return Err(ApiError::BadRequest("program missing".to_string()));
+        }
+        let extra = match args.get("args") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| {
+                    item.as_str().map(str::to_string).ok_or_else(|| {
+                        ApiError::BadRequest("args must be an array of strings".to_string())
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(_) => {
+                return Err(ApiError::BadRequest(
+                    "args must be an array of strings".to_string(),
+                ))
+            }
+        };
+        return Ok(ParsedCommand {
+            program: program.to_string(),
+            args: extra,
+        });
+    }
+
+    let line = args
+        .get("command")
+        .or_else(|| args.get("cmd"))
+        .or_else(|| args.get("input"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .ok_or_else(|| ApiError::BadRequest("command missing".to_string()))?;
+    let mut parts = shlex::split(line)
+        .ok_or_else(|| ApiError::BadRequest("command has unbalanced quotes".to_string()))?
+        .into_iter();
+    let program = parts
+        .next()
+        .ok_or_else(|| ApiError::BadRequest("command missing".to_string()))?;
+    Ok(ParsedCommand {
+        program,
+        args: parts.collect(),
+    })
+}
+
+fn shell_setting<'a>(config: &'a Value, key: &str) -> Option<&'a Value> {
+    config
+        .get("tools")
+        .and_then(|tools| tools.get("shell"))
+        .and_then(|shell| shell.get(key))
+}
+
+fn shell_string_list(config: &Value, key: &str) -> Vec<String> {
+    shell_setting(config, key)
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn is_binary_allowed(config: &Value, command: &ParsedCommand) -> bool {
+    let Some(binary) = command.binary_name() else {
+        return false;
+    };
+    shell_string_list(config, "allowed_binaries")
+        .iter()
+        .any(|allowed| allowed.to_ascii_lowercase() == binary)
+}
+
+/// True when the command may skip the approval prompt.
+pub fn command_auto_approved(config: &Value, args: &Value) -> bool {
+    let Ok(command) = parse_command_args(args) else {
+        return false;
+    };
+    if !is_binary_allowed(config, &command) {
+        return false;
+    }
+    let line = command.display();
+    shell_string_list(config, "auto_approve_patterns")
+        .iter()
+        .any(|pattern| wildcard_match(pattern, &line))
+}
+
+/// `*` matches any run of characters, `?` one character; everything else is literal.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&ch) if ch == '?' || ch == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|ch| *ch == '*')
+}
+
+async fn read_capped<R: AsyncRead + Unpin>(mut reader: R, max_bytes: usize) -> (Vec<u8>, bool) {
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut buffer = [0u8; 8192];
+    loop {
+        match reader.read(&mut buffer).await {
+            Ok(0) | Err(_) => break,
+            Ok(read) => {
+                let room = max_bytes.saturating_sub(kept.len());
+                if read > room {
+                    truncated = true;
+                }
+                // 上限を超えても読み続けて子プロセスのパイプ詰まりを防ぐ
+                kept.extend_from_slice(&buffer[..read.min(room)]);
+            }
+        }
+    }
+    (kept, truncated)
+}
+
+fn render_stream(label: &str, bytes: &[u8], truncated: bool) -> String {
+    let mut text = String::from_utf8_lossy(bytes).trim_end().to_string();
+    if truncated {
+        text.push_str("\n[output truncated]");
+    }
+    format!("--- {} ---\n{}", label, text)
+}
+
+pub async fn execute_run_command(config: &Value, args: &Value) -> Result<ToolExecution, ApiError> {
+    let command = parse_command_args(args)?;
+    if !is_binary_allowed(config, &command) {
+        return Err(ApiError::BadRequest(format!(
+            "'{}' is not in tools.shell.allowed_binaries",
+            command.program
+        )));
+    }
+
+    let roots = workspace_roots(config);
+    let cwd = resolve_workspace_dir(
+        &roots,
+        args.get("cwd").and_then(Value::as_str).unwrap_or("."),
+    )?;
+    let timeout_secs = args
+        .get("timeout_secs")
+        .and_then(Value::as_u64)
+        .or_else(|| shell_setting(config, "timeout_secs").and_then(Value::as_u64))
+        .unwrap_or(DEFAULT_TIMEOUT_SECS)
+        .clamp(1, 600);
+    let max_output = shell_setting(config, "max_output_bytes")
+        .and_then(Value::as_u64)
+        .map(|value| value as usize)
+        .unwrap_or(DEFAULT_MAX_OUTPUT_BYTES);
+
+    let mut child = Command::new(&command.program)
+        .args(&command.args)
+        .current_dir(&cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| {
+            ApiError::BadRequest(format!("Failed to start '{}': {}", command.program, err))
+        })?;
+    let stdout = child.stdout.take().expect("piped stdout");
+    let stderr = child.stderr.take().expect("piped stderr");
+    let stdout_task = tokio::spawn(read_capped(stdout, max_output));
+    let stderr_task = tokio::spawn(read_capped(stderr, max_output));
+
+    let started = Instant::now();
+    let status = match tokio::time::timeout(Duration::from_secs(timeout_secs), child.wait()).await {
+        Ok(status) => Some(status.map_err(ApiError::internal)?),
+        Err(_) => {
+            let _ = child.kill().await;
+            None
+        }
+    };
+    let (stdout, stdout_truncated) = stdout_task.await.unwrap_or_default();
+    let (stderr, stderr_truncated) = stderr_task.await.unwrap_or_default();
+
+    let header = match status {
+        Some(status) => format!(
+            "$ {}\nexit code: {} ({} ms)",
+            command.display(),
+            status
+                .code()
+                .map_or_else(|| "signal".to_string(), |code| code.to_string()),
+            started.elapsed().as_millis()
+        ),
+        None => format!(
+            "$ {}\ntimed out after {} s (process killed)",
+            command.display(),
+            timeout_secs
+        ),
+    };
+    Ok(ToolExecution {
+        output: format!(
+            "{}\n{}\n{}",
+            header,
+            render_stream("stdout", &stdout, stdout_truncated),
+            render_stream("stderr", &stderr, stderr_truncated)
+        ),
+        search_results: None,
+        images: Vec::new(),
+    })
+}
+
+/// Run the command and write an audit entry for it.
+pub async fn execute_run_command_audited(
+    state: Option<&AppState>,
+    config: &Value,
+    session_id: Option<&str>,
+    args: &Value,
+) -> Result<ToolExecution, ApiError> {
+    let result = execute_run_command(config, args).await;
+    if let Some(state) = state {
+        let outcome = if result.is_ok() { "success" } else { "error" };
+        let payload = json!({
+            "command": parse_command_args(args).ok().map(|command| command.display()),
+            "cwd": args.get("cwd").and_then(Value::as_str),
+            "session_id": session_id,
+            "auto_approved": command_auto_approved(config, args),
+            "error": result.as_ref().err().map(ToString::to_string),
+        });
+        if let Err(err) = state
+            .core()
+            .security
+            .record_audit("run_command", outcome, payload)
+        {
+            tracing::warn!("Failed to record run_command audit entry: {}", err);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell_config(root: &std::path::Path) -> Value {
+        json!({
+            "tools": {
+                "workspace_roots": [{ "path": root.to_string_lossy() }],
+                "shell": {
+                    "allowed_binaries": ["git", "cargo"],
+                    "auto_approve_patterns": ["git status*", "git log --oneline *"]
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn parses_command_line_and_program_forms() {
+        let parsed = parse_command_args(&json!({"command": "git commit -m 'a b'"})).unwrap();
+        assert_eq!(parsed.program, "git");
+        assert_eq!(parsed.args, vec!["commit", "-m", "a b"]);
+        assert_eq!(parsed.display(), "git commit -m 'a b'");
+
+        let parsed =
+            parse_command_args(&json!({"program": "cargo", "args": ["test", "-q"]})).unwrap();
+        assert_eq!(parsed.display(), "cargo test -q");
+        assert!(parse_command_args(&json!({"command": "echo 'open"})).is_err());
+    }
+
+    #[test]
+    fn auto_approval_requires_whitelist_and_pattern() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = shell_config(dir.path());
+
+        assert!(command_auto_approved(
+            &config,
+            &json!({"command": "git status"})
+        ));
+        assert!(command_auto_approved(
+            &config,
+            &json!({"command": "git log --oneline -5"})
+        ));
+        assert!(!command_auto_approved(
+            &config,
+            &json!({"command": "git push"})
+        ));
+        // 許可リスト外のバイナリはパターンに一致しても自動承認しない
+        let mut config = config;
+        config["tools"]["shell"]["auto_approve_patterns"] = json!(["*"]);
+        assert!(!command_auto_approved(
+            &config,
+            &json!({"command": "rm -rf ."})
+        ));
+        assert!(command_auto_approved(
+            &config,
+            &json!({"command": "GIT.exe push"})
+        ));
+        assert!(!command_auto_approved(
+            &config,
+            &json!({"command": "/tmp/git status"})
+        ));
+    }
+
+    #[test]
+    fn wildcard_matching() {
+        assert!(wildcard_match("git status*", "git status"));
+        assert!(wildcard_match("cargo ? -q", "cargo t -q"));
+        assert!(wildcard_match("*test*", "cargo test --lib"));
+        assert!(!wildcard_match("git status", "git status -s"));
+    }
+
+    #[tokio::test]
+    async fn rejects_binaries_outside_whitelist() {
+        let dir = tempfile::tempdir().unwrap();
+        let result =
+            execute_run_command(&shell_config(dir.path()), &json!({"command": "ls"})).await;
+        assert!(
+            matches!(result, Err(ApiError::BadRequest(message)) if message.contains("allowed_binaries"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn captures_output_and_enforces_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = json!({
+            "tools": {
+                "workspace_roots": [{ "path": dir.path().to_string_lossy() }],
+                "shell": { "allowed_binaries": ["sh"], "max_output_bytes": 4 }
+            }
+        });
+
+        let output = execute_run_command(
+            &config,
+            &json!({"program": "sh", "args": ["-c", "echo hello; echo oops >&2; exit 3"]}),
+        )
+        .await
+        .unwrap()
+        .output;
+        assert!(output.contains("exit code: 3"));
+        assert!(output.contains("--- stdout ---\nhell\n[output truncated]"));
+        assert!(output.contains("--- stderr ---\noops"));
+
+        let output = execute_run_command(
+            &config,
+            &json!({"program": "sh", "args": ["-c", "exec sleep 5"], "timeout_secs": 1}),
+        )
+        .await
+        .unwrap()
+        .output;
+        assert!(output.contains("timed out after 1 s"));
+    }
+}
@@ -33,6 +33,17 @@ pub fn cosine_similarity(query: &[f32], candidate: &[f32]) -> Result<f32, ApiErr
     Ok(dot / denom)
 }
 
+/// 長さ 1 に揃える。ゼロベクトルはそのまま
+pub fn l2_normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm <= f32::EPSILON {
+        return;
+    }
+    for value in vector.iter_mut() {
+        *value /= norm;
+    }
+}
+
 pub fn rank_descending_by_cosine(
     query: &[f32],
     candidates: &[Vec<f32>],
@@ -68,6 +79,18 @@ mod tests {
         assert!(approx_eq(score, 0.0));
     }
 
+    #[test]
+    fn l2_normalize_scales_to_unit_length() {
+        let mut vec = vec![3.0, 4.0];
+        l2_normalize(&mut vec);
+        assert!(approx_eq(vec[0], 0.6));
+        assert!(approx_eq(vec[1], 0.8));
+
+        let mut zero = vec![0.0, 0.0];
+        l2_normalize(&mut zero);
+        assert_eq!(zero, vec![0.0, 0.0]);
+    }
+
     #[test]
     fn ranking_returns_highest_similarity_first() {
         let query = vec![1.0, 0.0];
@@ -1,12 +1,15 @@
 use std::time::Duration;
 
 use futures_util::StreamExt;
-use reqwest::{redirect::Policy, Client};
+use reqwest::Client;
 use serde_json::Value;
 
+use crate::core::config::schema::NetworkSubsystem;
 use crate::core::errors::ApiError;
+use crate::core::network::NetClient;
 
 use super::dispatcher::ToolExecution;
+use super::readability::{extract_article, looks_like_html};
 use super::search::perform_search;
 use super::web_security::{
     allow_web_search, validate_fetch_target, web_fetch_max_bytes, web_fetch_max_chars,
@@ -37,6 +40,7 @@ pub async fn execute_search(config: &Value, args: &Value) -> Result<ToolExecutio
     Ok(ToolExecution {
         output,
         search_results: Some(results),
+        images: Vec::new(),
     })
 }
 
@@ -57,7 +61,38 @@ pub async fn execute_web_fetch(config: &Value, args: &Value) -> Result<ToolExecu
         return Err(ApiError::BadRequest("URL missing".to_string()));
     }
 
-    let parsed = reqwest::Url::parse(&url).map_err(ApiError::internal)?;
+    let (final_url, content_type, text) = fetch_text(config, &url).await?;
+    let max_chars = web_fetch_max_chars(config);
+    // raw=true で従来どおり本文をそのまま返す
+    let raw = args.get("raw").and_then(Value::as_bool).unwrap_or(false);
+    let text = if !raw && looks_like_html(content_type.as_deref(), &text) {
+        extract_article(&text, Some(&final_url)).render(final_url.as_str())
+    } else {
+        text
+    };
+    let truncated = match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!(
+            "{}\n\n[truncated: content exceeded {} characters]",
+            &text[..idx],
+            max_chars
+        ),
+        None => text,
+    };
+
+    Ok(ToolExecution {
+        output: truncated,
+        search_results: None,
+        images: Vec::new(),
+    })
+}
+
+/// URL 検証（スキーム・資格情報・SSRF）を行ったうえで本文を取得する。
+/// 戻り値は (最終 URL, Content-Type, 本文)。
+pub(crate) async fn fetch_text(
+    config: &Value,
+    url: &str,
+) -> Result<(reqwest::Url, Option<String>, String), ApiError> {
+    let parsed = reqwest::Url::parse(url).map_err(ApiError::internal)?;
     let scheme = parsed.scheme();
     if scheme != "http" && scheme != "https" {
         return Err(ApiError::BadRequest(
@@ -71,24 +106,28 @@ pub async fn execute_web_fetch(config: &Value, args: &Value) -> Result<ToolExecu
     }
 
     let resolution = validate_fetch_target(config, &parsed).await?;
-    let max_chars = web_fetch_max_chars(config);
     let max_bytes = web_fetch_max_bytes(config);
     let timeout_secs = web_fetch_timeout_secs(config);
 
     let mut client_builder = Client::builder()
-        .redirect(Policy::none())
         .timeout(Duration::from_secs(timeout_secs))
         .connect_timeout(Duration::from_secs(timeout_secs.min(30)));
     if let Some((host, addrs)) = resolution.pinned_dns() {
         client_builder = client_builder.resolve_to_addrs(host, addrs);
     }
-    let client = client_builder.build().map_err(ApiError::internal)?;
+    let client = NetClient::without_redirects(NetworkSubsystem::WebFetch, client_builder)?;
 
+    let final_url = parsed.clone();
     let response = client
-        .get(parsed)
+        .get(parsed)?
         .send()
         .await
         .map_err(ApiError::internal)?;
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
     if !response.status().is_success() {
         return Err(ApiError::Internal(format!(
             "Fetch failed: {}",
@@ -117,15 +156,9 @@ pub async fn execute_web_fetch(config: &Value, args: &Value) -> Result<ToolExecu
         bytes.extend_from_slice(&chunk);
     }
 
-    let text = String::from_utf8_lossy(&bytes).to_string();
-    let truncated = if text.chars().count() > max_chars {
-        text.chars().take(max_chars).collect::<String>()
-    } else {
-        text
-    };
-
-    Ok(ToolExecution {
-        output: truncated,
-        search_results: None,
-    })
+    Ok((
+        final_url,
+        content_type,
+        String::from_utf8_lossy(&bytes).to_string(),
+    ))
 }
@@ -0,0 +1,455 @@
+//! Wikipedia / Wiktionary の項目引き。
+//!
+//! `wiki_lookup` ツールは MediaWiki API で見出し語を探し、本文を節ごとに分けて返す。
+//! 節を指定しなければ導入部と節の一覧だけを返すので、Web 検索とページ取得を
+//! 組み合わせるより短いコンテキストで小さなモデルに事実の裏付けを渡せる。
+//! 言語は引数 `lang`、なければ `app.language` に従う。
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::core::errors::ApiError;
+
+use super::dispatcher::ToolExecution;
+use super::readability::extract_article;
+use super::search::SearchResult;
+use super::web::fetch_text;
+use super::web_security::{allow_web_search, web_fetch_max_chars};
+
+const DEFAULT_LANGUAGE: &str = "en";
+/// 節を指定しないときに返す導入部の上限
+const LEAD_MAX_CHARS: usize = 1500;
+
+static LANGUAGE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[a-z]{2,3}$").unwrap());
+/// TextExtracts の `== 見出し ==` と、Markdown の `## 見出し`
+static HEADING_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:(={2,6})\s*(.+?)\s*={2,6}|(#{1,6})\s+(.+?))\s*$").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WikiProject {
+    Wikipedia,
+    Wiktionary,
+}
+
+impl WikiProject {
+    fn parse(raw: Option<&str>) -> Result<Self, ApiError> {
+        match raw
+            .map(|value| value.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("wikipedia") => Ok(Self::Wikipedia),
+            Some("wiktionary") | Some("dictionary") => Ok(Self::Wiktionary),
+            Some(other) => Err(ApiError::BadRequest(format!(
+                "Unknown wiki project: {} (use wikipedia or wiktionary)",
+                other
+            ))),
+        }
+    }
+
+    fn host(self, lang: &str) -> String {
+        match self {
+            Self::Wikipedia => format!("{}.wikipedia.org", lang),
+            Self::Wiktionary => format!("{}.wiktionary.org", lang),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Section {
+    heading: String,
+    body: String,
+}
+
+/// 引数 → `app.language` → 英語。`ja-JP` のような指定は主言語に丸める。
+fn resolve_language(config: &Value, requested: Option<&str>) -> String {
+    let normalize = |raw: &str| {
+        let primary = raw
+            .trim()
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        LANGUAGE_RE.is_match(&primary).then_some(primary)
+    };
+    requested
+        .and_then(normalize)
+        .or_else(|| {
+            config
+                .get("app")
+                .and_then(|app| app.get("language"))
+                .and_then(Value::as_str)
+                .and_then(normalize)
+        })
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string())
+}
+
+fn api_url(host: &str, params: &[(&str, &str)]) -> Result<reqwest::Url, ApiError> {
+    let mut url =
+        reqwest::Url::parse(&format!("https://{}/w/api.php", host)).map_err(ApiError::internal)?;
+    url.query_pairs_mut()
+        .append_pair("format", "json")
+        .append_pair("formatversion", "2")
+        .extend_pairs(params);
+    Ok(url)
+}
+
+async fn api_get(config: &Value, host: &str, params: &[(&str, &str)]) -> Result<Value, ApiError> {
+    let url = api_url(host, params)?;
+    let (_, _, body) = fetch_text(config, url.as_str()).await?;
+    let json: Value = serde_json::from_str(&body).map_err(ApiError::internal)?;
+    if let Some(error) = json.get("error") {
+        let info = error
+            .get("info")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown error");
+        return Err(ApiError::BadRequest(format!(
+            "MediaWiki API error: {}",
+            info
+        )));
+    }
+    Ok(json)
+}
+
+/// 全文検索で一番近い項目名。見つからなければ `None`。
+async fn search_title(config: &Value, host: &str, query: &str) -> Result<Option<String>, ApiError> {
+    let json = api_get(
+        config,
+        host,
+        &[
+            ("action", "query"),
+            ("list", "search"),
+            ("srsearch", query),
+            ("srlimit", "1"),
+            ("srprop", ""),
+        ],
+    )
+    .await?;
+    Ok(json
+        .pointer("/query/search/0/title")
+        .and_then(Value::as_str)
+        .map(str::to_string))
+}
+
+/// Wikipedia は TextExtracts のプレーンテキスト。`(項目名, 本文)`。
+async fn wikipedia_text(
+    config: &Value,
+    host: &str,
+    title: &str,
+) -> Result<Option<(String, String)>, ApiError> {
+    let json = api_get(
+        config,
+        host,
+        &[
+            ("action", "query"),
+            ("prop", "extracts"),
+            ("explaintext", "1"),
+            ("exsectionformat", "wiki"),
+            ("redirects", "1"),
+            ("titles", title),
+        ],
+    )
+    .await?;
+    let Some(page) = json.pointer("/query/pages/0") else {
+        return Ok(None);
+    };
+    if page.get("missing").and_then(Value::as_bool) == Some(true) {
+        return Ok(None);
+    }
+    let extract = page
+        .get("extract")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if extract.trim().is_empty() {
+        return Ok(None);
+    }
+    let title = page
+        .get("title")
+        .and_then(Value::as_str)
+        .unwrap_or(title)
+        .to_string();
+    Ok(Some((title, extract.to_string())))
+}
+
+/// Wiktionary には TextExtracts が無いので、描画済み HTML を Markdown に直す。
+async fn wiktionary_text(
+    config: &Value,
+    host: &str,
+    title: &str,
+) -> Result<Option<(String, String)>, ApiError> {
+    let json = match api_get(
+        config,
+        host,
+        &[
+            ("action", "parse"),
+            ("page", title),
+            ("prop", "text"),
+            ("redirects", "1"),
+            ("disableeditsection", "1"),
+            ("disabletoc", "1"),
+        ],
+    )
+    .await
+    {
+        Ok(json) => json,
+        // 存在しない項目は `missingtitle` エラーになる
+        Err(ApiError::BadRequest(_)) => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let Some(html) = json.pointer("/parse/text").and_then(Value::as_str) else {
+        return Ok(None);
+    };
+    let title = json
+        .pointer("/parse/title")
+        .and_then(Value::as_str)
+        .unwrap_or(title)
+        .to_string();
+    Ok(Some((title, extract_article(html, None).markdown)))
+}
+
+/// 見出しで本文を分ける。最初の見出しより前は導入部（見出しは空）。
+fn split_sections(text: &str) -> Vec<Section> {
+    let mut sections = vec![Section {
+        heading: String::new(),
+        body: String::new(),
+    }];
+    for line in text.lines() {
+        if let Some(captures) = HEADING_RE.captures(line) {
+            let heading = captures
+                .get(2)
+                .or_else(|| captures.get(4))
+                .map(|heading| heading.as_str().trim().to_string())
+                .unwrap_or_default();
+            sections.push(Section {
+                heading,
+                body: String::new(),
+            });
+            continue;
+        }
+        let current = sections.last_mut().expect("at least the lead section");
+        current.body.push_str(line);
+        current.body.push('\n');
+    }
+    for section in &mut sections {
+        section.body = section.body.trim().to_string();
+    }
+    // 小見出しだけで本文の無い節（「脚注」など）は一覧から落とす
+    sections
+        .into_iter()
+        .enumerate()
+        .filter(|(index, section)| *index == 0 || !section.body.is_empty())
+        .map(|(_, section)| section)
+        .collect()
+}
+
+/// 見出しの完全一致を優先し、なければ部分一致。
+fn find_section<'a>(sections: &'a [Section], wanted: &str) -> Option<&'a Section> {
+    let wanted = wanted.trim().to_lowercase();
+    sections
+        .iter()
+        .find(|section| section.heading.to_lowercase() == wanted)
+        .or_else(|| {
+            sections
+                .iter()
+                .filter(|section| !section.heading.is_empty())
+                .find(|section| section.heading.to_lowercase().contains(&wanted))
+        })
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}…", text[..index].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+fn render(
+    title: &str,
+    url: &str,
+    sections: &[Section],
+    requested: Option<&str>,
+    max_chars: usize,
+) -> Result<(String, String), ApiError> {
+    let headings = sections
+        .iter()
+        .filter(|section| !section.heading.is_empty())
+        .map(|section| section.heading.as_str())
+        .collect::<Vec<_>>();
+    let mut output = format!("# {}\nSource: {}\n\n", title, url);
+    let body = match requested {
+        Some(wanted) => {
+            let section = find_section(sections, wanted).ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "Section '{}' not found. Available sections: {}",
+                    wanted,
+                    headings.join(", ")
+                ))
+            })?;
+            output.push_str(&format!("## {}\n\n", section.heading));
+            truncate_chars(&section.body, max_chars)
+        }
+        None => {
+            let lead = sections
+                .first()
+                .map(|lead| truncate_chars(&lead.body, LEAD_MAX_CHARS.min(max_chars)))
+                .unwrap_or_default();
+            if !headings.is_empty() {
+                output.push_str(&lead);
+                output.push_str(&format!(
+                    "\n\nSections (pass `section` to read one): {}",
+                    headings.join(", ")
+                ));
+                return Ok((output, lead));
+            }
+            lead
+        }
+    };
+    output.push_str(&body);
+    Ok((output, body))
+}
+
+/// `query` を引き、`section` があればその節、なければ導入部と節の一覧を返す。
+/// `project` は `wikipedia`（既定）か `wiktionary`。
+pub async fn execute_wiki_lookup(config: &Value, args: &Value) -> Result<ToolExecution, ApiError> {
+    if !allow_web_search(config) {
+        return Err(ApiError::Forbidden);
+    }
+    let query = args
+        .get("query")
+        .or_else(|| args.get("title"))
+        .or_else(|| args.get("word"))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .unwrap_or_default();
+    if query.is_empty() {
+        return Err(ApiError::BadRequest("Wiki query missing".to_string()));
+    }
+    let project = WikiProject::parse(args.get("project").and_then(Value::as_str))?;
+    let lang = resolve_language(config, args.get("lang").and_then(Value::as_str));
+    let section = args
+        .get("section")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|section| !section.is_empty());
+    let host = project.host(&lang);
+
+    let fetch = |title: String| {
+        let host = host.clone();
+        async move {
+            match project {
+                WikiProject::Wikipedia => wikipedia_text(config, &host, &title).await,
+                WikiProject::Wiktionary => wiktionary_text(config, &host, &title).await,
+            }
+        }
+    };
+    // 項目名そのものを先に試し、無ければ検索で一番近い項目にする
+    let page = match fetch(query.to_string()).await? {
+        Some(page) => Some(page),
+        None => match search_title(config, &host, query).await? {
+            Some(title) => fetch(title).await?,
+            None => None,
+        },
+    };
+    let Some((title, text)) = page else {
+        return Ok(ToolExecution {
+            output: format!("No {} entry found for '{}' ({}).", host, query, lang),
+            search_results: None,
+            images: Vec::new(),
+        });
+    };
+
+    let url = format!(
+        "https://{}/wiki/{}",
+        host,
+        urlencoding::encode(&title.replace(' ', "_"))
+    );
+    let sections = split_sections(&text);
+    let (output, snippet) = render(
+        &title,
+        &url,
+        &sections,
+        section,
+        web_fetch_max_chars(config),
+    )?;
+    Ok(ToolExecution {
+        output,
+        search_results: Some(vec![SearchResult {
+            title,
+            url,
+            snippet: truncate_chars(&snippet, 300),
+        }]),
+        images: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const EXTRACT: &str = "Rust is a programming language.\nIt focuses on safety.\n\n\
+== History ==\nStarted at Mozilla in 2006.\n\n=== Early years ===\nGraydon Hoare.\n\n\
+== Notes ==\n\n== Syntax and features ==\nRust has traits.\n";
+
+    #[test]
+    fn language_follows_argument_then_app_setting() {
+        let config = json!({"app": {"language": "ja"}});
+        assert_eq!(resolve_language(&config, None), "ja");
+        assert_eq!(resolve_language(&config, Some("de")), "de");
+        assert_eq!(resolve_language(&config, Some("ja_JP")), "ja");
+        assert_eq!(resolve_language(&json!({}), Some("../etc")), "en");
+        assert_eq!(resolve_language(&json!({}), None), "en");
+    }
+
+    #[test]
+    fn extracts_are_split_into_sections() {
+        let sections = split_sections(EXTRACT);
+        assert_eq!(sections[0].heading, "");
+        assert_eq!(
+            sections[0].body,
+            "Rust is a programming language.\nIt focuses on safety."
+        );
+        let headings = sections
+            .iter()
+            .skip(1)
+            .map(|section| section.heading.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(headings, ["History", "Early years", "Syntax and features"]);
+
+        let markdown = split_sections("intro\n## Noun\na word\n### Etymology\nfrom Latin");
+        assert_eq!(markdown[1].heading, "Noun");
+        assert_eq!(markdown[2].body, "from Latin");
+    }
+
+    #[test]
+    fn render_returns_lead_with_outline_or_requested_section() {
+        let sections = split_sections(EXTRACT);
+        let url = "https://en.wikipedia.org/wiki/Rust";
+        let (lead, _) = render("Rust", url, &sections, None, 6000).unwrap();
+        assert!(lead.starts_with("# Rust\nSource: https://en.wikipedia.org/wiki/Rust\n\n"));
+        assert!(lead.contains("Sections (pass `section` to read one): History, Early years"));
+        assert!(!lead.contains("Mozilla"));
+
+        let (history, snippet) = render("Rust", url, &sections, Some("syntax"), 6000).unwrap();
+        assert!(history.contains("## Syntax and features\n\nRust has traits."));
+        assert_eq!(snippet, "Rust has traits.");
+
+        let missing = render("Rust", url, &sections, Some("Reception"), 6000);
+        assert!(
+            matches!(missing, Err(ApiError::BadRequest(message)) if message.contains("History"))
+        );
+    }
+
+    #[tokio::test]
+    async fn lookup_requires_web_access() {
+        let result = execute_wiki_lookup(&json!({}), &json!({"query": "Rust"})).await;
+        assert!(matches!(result, Err(ApiError::Forbidden)));
+        let result = execute_wiki_lookup(
+            &json!({"privacy": {"allow_web_search": true}}),
+            &json!({"query": "Rust", "project": "wikidata"}),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+    }
+}
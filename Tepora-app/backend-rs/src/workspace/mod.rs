@@ -159,6 +159,13 @@ impl WorkspaceManager {
         self.project_info(&project_id)
     }
 
+    /// Whether `project_id` names an existing project (without creating it).
+    pub fn has_project(&self, project_id: &str) -> bool {
+        project_id == DEFAULT_PROJECT_ID
+            || (is_valid_project_id(project_id)
+                && self.paths.project_workspace_dir(project_id).is_dir())
+    }
+
     pub fn project_dir(&self, project_id: &str) -> PathBuf {
         self.paths.project_dir(project_id)
     }
@@ -344,6 +351,14 @@ impl ProjectHistoryStore {
             .await
     }
 
+    pub fn partial_message(
+        &self,
+        session_id: &str,
+        additional_kwargs: serde_json::Value,
+    ) -> crate::history::PartialMessage {
+        self.inner.partial_message(session_id, additional_kwargs)
+    }
+
     pub async fn get_history(
         &self,
         session_id: &str,
@@ -352,6 +367,17 @@ impl ProjectHistoryStore {
         self.inner.get_history(session_id, limit).await
     }
 
+    pub async fn get_messages_between(
+        &self,
+        session_id: &str,
+        after_id: i64,
+        before_id: i64,
+    ) -> Result<Vec<crate::history::HistoryMessage>, ApiError> {
+        self.inner
+            .get_messages_between(session_id, after_id, before_id)
+            .await
+    }
+
     pub async fn touch_session(&self, session_id: &str) -> Result<(), ApiError> {
         self.inner.touch_session(session_id).await
     }
@@ -386,9 +412,257 @@ impl ProjectHistoryStore {
         self.inner.get_agent_events(session_id).await
     }
 
+    pub async fn get_agent_events_for_agent(
+        &self,
+        agent_id: &str,
+        event_type: crate::models::event::AgentEventType,
+    ) -> Result<Vec<crate::models::event::AgentEvent>, ApiError> {
+        self.inner
+            .get_agent_events_for_agent(agent_id, event_type)
+            .await
+    }
+
+    pub async fn latest_context_composition(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<crate::models::event::AgentEvent>, ApiError> {
+        self.inner.latest_context_composition(session_id).await
+    }
+
+    pub async fn record_token_usage(
+        &self,
+        session_id: &str,
+        model_id: &str,
+        usage: &crate::llm::types::TurnUsage,
+    ) -> Result<(), ApiError> {
+        self.inner
+            .record_token_usage(session_id, model_id, usage)
+            .await
+    }
+
+    pub async fn session_token_usage(
+        &self,
+        session_id: &str,
+    ) -> Result<crate::history::SessionTokenUsage, ApiError> {
+        self.inner.session_token_usage(session_id).await
+    }
+
+    pub async fn record_source_citations(
+        &self,
+        outcomes: &[(String, bool)],
+    ) -> Result<(), ApiError> {
+        self.inner.record_source_citations(outcomes).await
+    }
+
+    pub async fn source_quality(
+        &self,
+        domains: &[String],
+    ) -> Result<HashMap<String, crate::history::DomainQuality>, ApiError> {
+        self.inner.source_quality(domains).await
+    }
+
     pub async fn get_total_message_count(&self) -> Result<i64, ApiError> {
         self.inner.get_total_message_count().await
     }
+
+    pub async fn probe_writable(&self) -> Result<(), ApiError> {
+        self.inner.probe_writable().await
+    }
+
+    pub async fn record_tool_invocation(
+        &self,
+        tool: &str,
+        source: &str,
+        success: bool,
+        latency_ms: i64,
+        error: Option<&str>,
+    ) -> Result<(), ApiError> {
+        self.inner
+            .record_tool_invocation(tool, source, success, latency_ms, error)
+            .await
+    }
+
+    pub async fn tool_invocation_stats(
+        &self,
+        since: Option<&str>,
+    ) -> Result<Vec<crate::history::ToolInvocationStats>, ApiError> {
+        self.inner.tool_invocation_stats(since).await
+    }
+
+    pub async fn record_admin_audit(
+        &self,
+        actor: &serde_json::Value,
+        action: &str,
+        target: Option<&str>,
+        outcome: &str,
+        details: &serde_json::Value,
+    ) -> Result<i64, ApiError> {
+        self.inner
+            .record_admin_audit(actor, action, target, outcome, details)
+            .await
+    }
+
+    pub async fn list_admin_audit(
+        &self,
+        limit: i64,
+        before_id: Option<i64>,
+        action: Option<&str>,
+    ) -> Result<Vec<crate::history::AdminAuditEntry>, ApiError> {
+        self.inner.list_admin_audit(limit, before_id, action).await
+    }
+
+    pub async fn get_session_persona(&self, session_id: &str) -> Result<Option<String>, ApiError> {
+        self.inner.get_session_persona(session_id).await
+    }
+
+    pub async fn set_session_persona(
+        &self,
+        session_id: &str,
+        persona_id: Option<&str>,
+    ) -> Result<(), ApiError> {
+        self.inner.set_session_persona(session_id, persona_id).await
+    }
+
+    pub async fn add_inbox_item(
+        &self,
+        item: &crate::history::NewInboxItem,
+    ) -> Result<crate::history::InboxItem, ApiError> {
+        self.inner.add_inbox_item(item).await
+    }
+
+    pub async fn list_inbox(
+        &self,
+        unread_only: bool,
+        limit: i64,
+        before_id: Option<i64>,
+    ) -> Result<Vec<crate::history::InboxItem>, ApiError> {
+        self.inner.list_inbox(unread_only, limit, before_id).await
+    }
+
+    pub async fn unread_inbox_count(&self) -> Result<i64, ApiError> {
+        self.inner.unread_inbox_count().await
+    }
+
+    pub async fn acknowledge_inbox(&self, ids: Option<&[i64]>) -> Result<u64, ApiError> {
+        self.inner.acknowledge_inbox(ids).await
+    }
+
+    pub async fn start_job(
+        &self,
+        job: &crate::history::NewJob,
+    ) -> Result<crate::history::JobRecord, ApiError> {
+        self.inner.start_job(job).await
+    }
+
+    pub async fn finish_job(&self, id: &str, error: Option<&str>) -> Result<bool, ApiError> {
+        self.inner.finish_job(id, error).await
+    }
+
+    pub async fn retry_job(&self, id: &str) -> Result<(), ApiError> {
+        self.inner.retry_job(id).await
+    }
+
+    pub async fn list_jobs(
+        &self,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<crate::history::JobRecord>, ApiError> {
+        self.inner.list_jobs(status, limit).await
+    }
+
+    pub async fn add_eval_run(
+        &self,
+        run: &crate::history::NewEvalRun,
+    ) -> Result<crate::history::EvalRunRecord, ApiError> {
+        self.inner.add_eval_run(run).await
+    }
+
+    pub async fn list_eval_runs(
+        &self,
+        suite: Option<&str>,
+    ) -> Result<Vec<crate::history::EvalRunRecord>, ApiError> {
+        self.inner.list_eval_runs(suite).await
+    }
+
+    /// 話題索引はプロジェクトをまたいで履歴全体を対象にする。
+    pub async fn session_months(&self) -> Result<Vec<String>, ApiError> {
+        self.inner.session_months().await
+    }
+
+    pub async fn indexed_topic_months(&self) -> Result<Vec<String>, ApiError> {
+        self.inner.indexed_topic_months().await
+    }
+
+    pub async fn session_digests_for_month(
+        &self,
+        month: &str,
+    ) -> Result<Vec<crate::history::SessionDigest>, ApiError> {
+        self.inner.session_digests_for_month(month).await
+    }
+
+    pub async fn replace_month_topics(
+        &self,
+        month: &str,
+        topics: &[crate::history::NewTopic],
+    ) -> Result<(), ApiError> {
+        self.inner.replace_month_topics(month, topics).await
+    }
+
+    pub async fn list_topics(
+        &self,
+        month: Option<&str>,
+    ) -> Result<Vec<crate::history::TopicRecord>, ApiError> {
+        self.inner.list_topics(month).await
+    }
+
+    pub async fn get_project_settings(
+        &self,
+        project_id: &str,
+    ) -> Result<crate::history::ProjectSettings, ApiError> {
+        self.inner.get_project_settings(project_id).await
+    }
+
+    pub async fn save_project_settings(
+        &self,
+        settings: &crate::history::ProjectSettings,
+    ) -> Result<crate::history::ProjectSettings, ApiError> {
+        self.inner.save_project_settings(settings).await
+    }
+
+    pub async fn move_session_to_project(
+        &self,
+        session_id: &str,
+        project_id: &str,
+    ) -> Result<bool, ApiError> {
+        self.inner
+            .move_session_to_project(session_id, project_id)
+            .await
+    }
+
+    pub async fn list_project_sessions(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<SessionInfo>, ApiError> {
+        self.inner.list_sessions(Some(project_id)).await
+    }
+
+    pub async fn get_session_summary(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<crate::history::SessionSummary>, ApiError> {
+        self.inner.get_session_summary(session_id).await
+    }
+
+    pub async fn save_session_summary(
+        &self,
+        session_id: &str,
+        summary: &str,
+        covered_message_id: i64,
+    ) -> Result<(), ApiError> {
+        self.inner
+            .save_session_summary(session_id, summary, covered_message_id)
+            .await
+    }
 }
 
 #[derive(Clone)]
@@ -663,6 +937,13 @@ fn build_tree(section: &str, root: &Path, current: &Path) -> Result<WorkspaceEnt
     })
 }
 
+fn is_valid_project_id(project_id: &str) -> bool {
+    !project_id.is_empty()
+        && project_id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+}
+
 fn ensure_project_layout(paths: &AppPaths, project_id: &str) -> Result<(), ApiError> {
     let project_dir = paths.project_dir(project_id);
     for dir in [
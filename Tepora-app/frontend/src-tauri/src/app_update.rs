@@ -0,0 +1,114 @@
+//! アプリ本体の自動更新。
+//!
+//! 更新ファイルの署名は tauri-plugin-updater が検証する。公開鍵はリポジトリに
+//! 置かず、リリースビルドで `TEPORA_UPDATER_PUBKEY` から埋め込む。鍵の無い
+//! ビルドは更新を確認しない。
+//! いつ入れるか（エージェント実行やダウンロード中は待つ）はフロントエンドが
+//! バックエンドの `/api/updates/readiness` を見て決め、ここはチャネルごとの
+//! 確認とインストール、再起動直前の確認だけを受け持つ。
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Url};
+use tauri_plugin_updater::{Updater, UpdaterExt};
+
+use crate::backend_http;
+use crate::quick_ask::backend_port;
+
+/// 更新ファイルの署名を検証する minisign の公開鍵
+const UPDATER_PUBKEY: Option<&str> = option_env!("TEPORA_UPDATER_PUBKEY");
+/// 入れ替えたあと、実行中の処理が終わるのを待つ間隔
+const RESTART_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+const STABLE_ENDPOINT: &str =
+    "https://github.com/coco4atJP/Tepora/releases/latest/download/latest.json";
+const BETA_ENDPOINT: &str =
+    "https://github.com/coco4atJP/Tepora/releases/download/beta/latest.json";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailableUpdate {
+    pub version: String,
+    pub current_version: String,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+}
+
+fn endpoint_for(channel: &str) -> Result<&'static str, String> {
+    match channel {
+        "stable" => Ok(STABLE_ENDPOINT),
+        "beta" => Ok(BETA_ENDPOINT),
+        other => Err(format!("Unknown update channel '{}'", other)),
+    }
+}
+
+fn updater_for(app: &AppHandle, channel: &str) -> Result<Updater, String> {
+    let Some(pubkey) = UPDATER_PUBKEY.filter(|key| !key.trim().is_empty()) else {
+        return Err("Updates are disabled: this build has no updater public key".to_string());
+    };
+    let endpoint = endpoint_for(channel)?
+        .parse::<Url>()
+        .map_err(|err| err.to_string())?;
+    app.updater_builder()
+        .pubkey(pubkey)
+        .endpoints(vec![endpoint])
+        .map_err(|err| err.to_string())?
+        .build()
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub async fn check_app_update(
+    app: AppHandle,
+    channel: String,
+) -> Result<Option<AvailableUpdate>, String> {
+    let update = updater_for(&app, &channel)?
+        .check()
+        .await
+        .map_err(|err| err.to_string())?;
+    Ok(update.map(|update| AvailableUpdate {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        notes: update.body.clone(),
+        date: update.date.map(|date| date.to_string()),
+    }))
+}
+
+/// バックエンドが、実行中の処理が無く今すぐ再起動してよいと答えるか。
+async fn backend_ready(app: &AppHandle) -> bool {
+    let Some(port) = backend_port(app) else {
+        return false;
+    };
+    let request = backend_http::client().get(backend_http::url(port, "/api/updates/readiness"));
+    backend_http::fetch_json(backend_http::authorized(request))
+        .await
+        .and_then(|readiness| readiness["ready"].as_bool())
+        .unwrap_or(false)
+}
+
+/// 署名を検証してから入れ替え、再起動する。確認ダイアログの間に処理が
+/// 始まっていれば入れ替えず、入れ替えたあとに始まったものは終わるまで待つ。
+#[tauri::command]
+pub async fn install_app_update(app: AppHandle, channel: String) -> Result<(), String> {
+    if !backend_ready(&app).await {
+        return Err("Work started before the update; try again later".to_string());
+    }
+    let Some(update) = updater_for(&app, &channel)?
+        .check()
+        .await
+        .map_err(|err| err.to_string())?
+    else {
+        return Err("No update available".to_string());
+    };
+    log::info!("Installing update {} ({} channel)", update.version, channel);
+    update
+        .download_and_install(|_, _| {}, || {})
+        .await
+        .map_err(|err| err.to_string())?;
+    while !backend_ready(&app).await {
+        log::info!("Update installed; waiting for active work before restarting");
+        tokio::time::sleep(RESTART_POLL_INTERVAL).await;
+    }
+    app.restart();
+}
@@ -0,0 +1,38 @@
+//! シェルからサイドカーへの HTTP 要求。
+//! ポートはメインウィンドウから `set_backend_port` で届いたものを使い、
+//! 認証はウィンドウと同じセッショントークンで行う。
+
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::read_session_token;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+/// サイドカーが信頼する Origin のうちシェル自身のもの
+const SHELL_ORIGIN: &str = "tauri://localhost";
+
+pub fn client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+pub fn url(port: u16, path: &str) -> String {
+    format!("http://127.0.0.1:{}{}", port, path)
+}
+
+/// セッショントークンと Origin を付ける（状態を変える要求は Origin 必須）。
+pub fn authorized(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    let request = request.header("origin", SHELL_ORIGIN);
+    match read_session_token() {
+        Some(token) => request.header("x-api-key", token),
+        None => request,
+    }
+}
+
+pub async fn fetch_json(request: reqwest::RequestBuilder) -> Option<Value> {
+    let response = request.send().await.ok()?.error_for_status().ok()?;
+    response.json().await.ok()
+}
@@ -0,0 +1,119 @@
+//! `tepora://` リンクと二重起動の扱い。
+//!
+//! 2 つ目の起動はシングルインスタンスプラグインが止め、その引数に含まれる
+//! リンクを既存のウィンドウへ渡す（バックエンドが SQLite やポートを奪い合わないように）。
+//! 起動直後に届いたリンクはフロントエンドの準備前に失われないよう、
+//! `take_pending_deep_link` で取り出せるまで保持する。
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::session_window;
+
+const SCHEME: &str = "tepora";
+const MAIN_WINDOW: &str = "main";
+pub const DEEP_LINK_EVENT: &str = "deep_link";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeepLink {
+    Session {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+    },
+}
+
+#[derive(Default)]
+pub struct PendingDeepLink(Mutex<Option<DeepLink>>);
+
+/// `tepora://session/<id>` を解釈する。対応していない形なら `None`。
+pub fn parse_deep_link(raw: &str) -> Option<DeepLink> {
+    let rest = raw.trim().strip_prefix(SCHEME)?.strip_prefix("://")?;
+    let rest = rest.split(['?', '#']).next().unwrap_or_default();
+    let mut segments = rest.split('/').filter(|segment| !segment.is_empty());
+    match (segments.next(), segments.next(), segments.next()) {
+        (Some("session"), Some(id), None) if is_valid_session_id(id) => Some(DeepLink::Session {
+            session_id: id.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_valid_session_id(id: &str) -> bool {
+    id.len() <= 128
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[tauri::command]
+pub fn take_pending_deep_link(state: tauri::State<'_, PendingDeepLink>) -> Option<DeepLink> {
+    state.0.lock().unwrap_or_else(|e| e.into_inner()).take()
+}
+
+/// 既に起動しているインスタンスで 2 回目の起動を受けたとき。
+pub fn handle_second_instance(app: &AppHandle, args: Vec<String>) {
+    let links: Vec<_> = args.iter().filter_map(|arg| parse_deep_link(arg)).collect();
+    if links.is_empty() {
+        focus_main_window(app);
+    }
+    for link in links {
+        dispatch(app, link);
+    }
+}
+
+pub fn setup_deep_links(app: &AppHandle) {
+    app.manage(PendingDeepLink::default());
+
+    // インストーラーを通さない開発ビルドでもスキームを使えるようにする
+    #[cfg(all(debug_assertions, any(target_os = "linux", windows)))]
+    if let Err(err) = app.deep_link().register_all() {
+        log::warn!("Failed to register deep link schemes: {}", err);
+    }
+
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls {
+            if let Some(link) = parse_deep_link(url.as_str()) {
+                store_pending(app, link);
+            }
+        }
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            if let Some(link) = parse_deep_link(url.as_str()) {
+                dispatch(&handle, link);
+            }
+        }
+    });
+}
+
+/// 専用ウィンドウで開いているセッションならそちらを前に出し、
+/// それ以外はメインウィンドウで開く。
+fn dispatch(app: &AppHandle, link: DeepLink) {
+    let DeepLink::Session { session_id } = &link;
+    if session_window::focus_session_window(app, session_id) {
+        return;
+    }
+    focus_main_window(app);
+    store_pending(app, link.clone());
+    let _ = app.emit(DEEP_LINK_EVENT, link);
+}
+
+fn store_pending(app: &AppHandle, link: DeepLink) {
+    if let Some(state) = app.try_state::<PendingDeepLink>() {
+        *state.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(link);
+    }
+}
+
+fn focus_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
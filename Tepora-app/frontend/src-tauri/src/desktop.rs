@@ -0,0 +1,180 @@
+//! サイドカーのデスクトップ要求（クリップボード・スクリーンショット）に応える。
+//!
+//! サイドカーの `/api/desktop/bridge` に WebSocket で繋ぎ、起動ごとに作る鍵で
+//! シェルであることを示す。要求ごとにネイティブの確認ダイアログを出し、
+//! 許可されたときだけ取得して返す。サイドカーが再起動しても繋ぎ直す。
+
+use std::time::Duration;
+
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tepora_backend::core::desktop_bridge::{
+    DesktopBridgeReply, DesktopCapability, DesktopReply, DesktopRequest,
+    DESKTOP_BRIDGE_TOKEN_HEADER,
+};
+use tepora_backend::core::security::issue_desktop_bridge_token;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::quick_ask::backend_port;
+use crate::users::current_user;
+
+/// モデルに渡す画像の最大幅（これより大きい画面は縮小する）
+const MAX_SCREENSHOT_WIDTH: u32 = 1920;
+/// ポートが分かるまで・切断されたあとに待つ間隔
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+pub fn spawn_desktop_bridge(app: AppHandle) {
+    let token = match issue_desktop_bridge_token(&current_user()) {
+        Ok(token) => token,
+        Err(err) => {
+            log::warn!("Desktop bridge disabled: {}", err);
+            return;
+        }
+    };
+    tauri::async_runtime::spawn(async move {
+        loop {
+            // ポートはメインウィンドウがサイドカーを起動したあとに `set_backend_port` で届く
+            if let Some(port) = backend_port(&app) {
+                if let Err(err) = serve_sidecar(&app, port, &token).await {
+                    log::debug!("Desktop bridge disconnected: {}", err);
+                }
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
+
+/// 切断されるまでサイドカーからの要求に応える。
+async fn serve_sidecar(app: &AppHandle, port: u16, token: &str) -> Result<(), String> {
+    let mut request = format!("ws://127.0.0.1:{}/api/desktop/bridge", port)
+        .into_client_request()
+        .map_err(|err| err.to_string())?;
+    request.headers_mut().insert(
+        DESKTOP_BRIDGE_TOKEN_HEADER,
+        token.parse().map_err(|_| "invalid bridge token".to_string())?,
+    );
+    let (socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|err| err.to_string())?;
+    log::info!("Desktop bridge connected to the backend on port {}", port);
+    let (mut sink, mut stream) = socket.split();
+    let (replies_tx, mut replies) = tokio::sync::mpsc::unbounded_channel::<DesktopBridgeReply>();
+
+    loop {
+        tokio::select! {
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let Ok(request) = serde_json::from_str::<DesktopRequest>(&text) else {
+                        continue;
+                    };
+                    let app = app.clone();
+                    let replies_tx = replies_tx.clone();
+                    // 確認ダイアログを待つ間も次の要求を読めるように分ける
+                    tauri::async_runtime::spawn(async move {
+                        let id = request.id.clone();
+                        let reply = tauri::async_runtime::spawn_blocking(move || {
+                            handle_request(&app, &request)
+                        })
+                        .await
+                        .unwrap_or_else(|err| DesktopReply::Failed {
+                            message: err.to_string(),
+                        });
+                        let _ = replies_tx.send(DesktopBridgeReply { id, reply });
+                    });
+                }
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(err.to_string()),
+                None => return Ok(()),
+            },
+            Some(reply) = replies.recv() => {
+                let text = serde_json::to_string(&reply).map_err(|err| err.to_string())?;
+                sink.send(Message::Text(text.into()))
+                    .await
+                    .map_err(|err| err.to_string())?;
+            }
+        }
+    }
+}
+
+fn ask_consent(app: &AppHandle, request: &DesktopRequest) -> bool {
+    let mut message = format!("Tepora wants to {}.", request.capability.label());
+    if let Some(reason) = &request.reason {
+        message.push_str(&format!("\n\nReason: {}", reason));
+    }
+    app.dialog()
+        .message(message)
+        .title("Allow desktop access?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Allow once".to_string(),
+            "Deny".to_string(),
+        ))
+        .blocking_show()
+}
+
+fn handle_request(app: &AppHandle, request: &DesktopRequest) -> DesktopReply {
+    if !ask_consent(app, request) {
+        return DesktopReply::Denied;
+    }
+    let result = match request.capability {
+        DesktopCapability::ClipboardText => app
+            .clipboard()
+            .read_text()
+            .map(|text| DesktopReply::ClipboardText { text })
+            .map_err(|err| err.to_string()),
+        DesktopCapability::Screenshot => capture_primary_monitor(),
+    };
+    result.unwrap_or_else(|message| DesktopReply::Failed { message })
+}
+
+fn capture_primary_monitor() -> Result<DesktopReply, String> {
+    let monitors = xcap::Monitor::all().map_err(|err| err.to_string())?;
+    let monitor = monitors
+        .iter()
+        .find(|monitor| monitor.is_primary().unwrap_or(false))
+        .or_else(|| monitors.first())
+        .ok_or_else(|| "No monitor found".to_string())?;
+    let image = monitor.capture_image().map_err(|err| err.to_string())?;
+    let (width, height, rgba) = downscale(image.width(), image.height(), image.as_raw());
+
+    let mut png_bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png_bytes, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|err| err.to_string())?;
+        writer
+            .write_image_data(&rgba)
+            .map_err(|err| err.to_string())?;
+    }
+
+    Ok(DesktopReply::Screenshot {
+        mime_type: "image/png".to_string(),
+        base64: base64::engine::general_purpose::STANDARD.encode(png_bytes),
+        width,
+        height,
+    })
+}
+
+/// 最近傍法で幅を `MAX_SCREENSHOT_WIDTH` 以下に縮める。
+fn downscale(width: u32, height: u32, rgba: &[u8]) -> (u32, u32, Vec<u8>) {
+    if width <= MAX_SCREENSHOT_WIDTH {
+        return (width, height, rgba.to_vec());
+    }
+    let new_width = MAX_SCREENSHOT_WIDTH;
+    let new_height = ((height as u64 * new_width as u64) / width as u64).max(1) as u32;
+    let mut output = Vec::with_capacity((new_width * new_height * 4) as usize);
+    for y in 0..new_height {
+        let src_y = (y as u64 * height as u64 / new_height as u64) as usize;
+        for x in 0..new_width {
+            let src_x = (x as u64 * width as u64 / new_width as u64) as usize;
+            let offset = (src_y * width as usize + src_x) * 4;
+            output.extend_from_slice(&rgba[offset..offset + 4]);
+        }
+    }
+    (new_width, new_height, output)
+}
@@ -1,15 +1,25 @@
-use std::path::PathBuf;
+mod app_update;
+mod backend_http;
+mod deep_link;
+mod desktop;
+mod quick_ask;
+mod session_window;
+#[cfg(desktop)]
+mod tray;
+mod users;
+
 use tauri::{RunEvent, AppHandle, Emitter, Manager};
 use tauri_plugin_log::{Target, TargetKind};
 use tepora_backend::state::AppState;
 use tepora_backend::actor::ActorDispatchError;
 use tepora_backend::actor::messages::{SessionCommand, SessionEvent};
+use tepora_backend::core::security::session_token_path;
 use tepora_backend::core::security_controls::ToolApprovalResponsePayload;
 use tepora_backend::server::ws::protocol::WsIncomingMessage;
 use std::sync::Arc;
 use serde_json::json;
 
-struct BackendState(Arc<AppState>);
+pub(crate) struct BackendState(pub(crate) Arc<AppState>);
 
 fn map_actor_dispatch_error(err: ActorDispatchError) -> String {
     match err {
@@ -244,10 +254,7 @@ fn read_session_token() -> Option<String> {
         }
     }
 
-    let home_dir = std::env::var_os("HOME")
-        .or_else(|| std::env::var_os("USERPROFILE"))
-        .map(PathBuf::from)?;
-    let token_path = home_dir.join(".tepora").join(".session_token");
+    let token_path = session_token_path(&users::current_user());
     let token = std::fs::read_to_string(token_path).ok()?;
     let token = token.trim().to_string();
     if token.is_empty() {
@@ -259,22 +266,49 @@ fn read_session_token() -> Option<String> {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    users::pin_startup_user();
     let app = tauri::Builder::default()
+        // 二重起動を防ぐため最初に登録する
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            deep_link::handle_second_instance(app, args);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .setup(|app| {
-            tauri::async_runtime::block_on(async {
-                if let Ok(app_state) = AppState::initialize().await {
-                    app.manage(BackendState(app_state));
-                } else {
-                    log::error!("Failed to initialize Tepora AppState");
+            deep_link::setup_deep_links(app.handle());
+            let backend = tauri::async_runtime::block_on(async {
+                match AppState::initialize().await {
+                    Ok(app_state) => {
+                        app.manage(BackendState(app_state.clone()));
+                        Some(app_state)
+                    }
+                    Err(_) => {
+                        log::error!("Failed to initialize Tepora AppState");
+                        None
+                    }
                 }
             });
+            desktop::spawn_desktop_bridge(app.handle().clone());
+            #[cfg(desktop)]
+            if let Err(err) = tray::setup_tray(app.handle()) {
+                log::warn!("Failed to create tray icon: {}", err);
+            }
+            quick_ask::setup_quick_ask(app.handle(), backend);
+            session_window::setup_session_windows(app.handle());
             Ok(())
         })
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
-
+        .plugin(
+            // サイズと位置はメイン・セッションウィンドウとも閉じたときに保存して次回復元する
+            tauri_plugin_window_state::Builder::default()
+                .with_denylist(&[quick_ask::QUICK_ASK_WINDOW])
+                .build(),
+        )
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(
             tauri_plugin_log::Builder::new()
                 .level(log::LevelFilter::Info)
@@ -283,7 +317,20 @@ pub fn run() {
                 }))
                 .build(),
         )
-        .invoke_handler(tauri::generate_handler![read_session_token, chat_command])
+        .invoke_handler(tauri::generate_handler![
+            read_session_token,
+            chat_command,
+            quick_ask::set_backend_port,
+            quick_ask::hide_quick_ask,
+            session_window::open_session_window,
+            session_window::list_session_windows,
+            deep_link::take_pending_deep_link,
+            app_update::check_app_update,
+            app_update::install_app_update,
+            users::list_local_users,
+            users::create_local_user,
+            users::login_as
+        ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
 
@@ -0,0 +1,142 @@
+//! グローバルショートカットで開く小さな質問ウィンドウ。
+//!
+//! ショートカットは設定の `desktop.quick_ask_shortcut` で変えられ、設定ファイルの
+//! 変更にも追従する。ウィンドウはメインとは別の WS セッションでバックエンドに繋ぐため、
+//! バックエンドのポートはメインウィンドウから `set_backend_port` で受け取っておく。
+
+use std::sync::{Arc, Mutex};
+
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+use tepora_backend::core::config::schema::TeporaConfig;
+use tepora_backend::state::AppState;
+
+pub const QUICK_ASK_WINDOW: &str = "quick-ask";
+const WINDOW_WIDTH: f64 = 640.0;
+const WINDOW_HEIGHT: f64 = 180.0;
+
+#[derive(Default)]
+pub struct QuickAskState {
+    backend_port: Mutex<Option<u16>>,
+    shortcut: Mutex<Option<String>>,
+}
+
+#[tauri::command]
+pub fn set_backend_port(state: tauri::State<'_, QuickAskState>, port: u16) {
+    *state.backend_port.lock().unwrap_or_else(|e| e.into_inner()) = Some(port);
+}
+
+#[tauri::command]
+pub fn hide_quick_ask(app: AppHandle) {
+    if let Some(window) = app.get_webview_window(QUICK_ASK_WINDOW) {
+        let _ = window.hide();
+    }
+}
+
+pub fn setup_quick_ask(app: &AppHandle, app_state: Option<Arc<AppState>>) {
+    app.manage(QuickAskState::default());
+    let Some(app_state) = app_state else {
+        apply_shortcut(app, &TeporaConfig::default());
+        return;
+    };
+    apply_shortcut(app, &load_config(&app_state));
+
+    let app = app.clone();
+    let mut changes = app_state.core.config.subscribe_changes();
+    tauri::async_runtime::spawn(async move {
+        while let Ok(event) = changes.recv().await {
+            if event
+                .changed_sections
+                .iter()
+                .any(|section| section == "desktop")
+            {
+                apply_shortcut(&app, &load_config(&app_state));
+            }
+        }
+    });
+}
+
+/// メインウィンドウから受け取ったバックエンドのポート。別ウィンドウを開くときに使う。
+pub fn backend_port(app: &AppHandle) -> Option<u16> {
+    app.try_state::<QuickAskState>()
+        .and_then(|state| *state.backend_port.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+fn load_config(app_state: &AppState) -> TeporaConfig {
+    app_state.core.config.load_typed().unwrap_or_default()
+}
+
+/// 登録済みのショートカットと違えば付け替える。
+fn apply_shortcut(app: &AppHandle, config: &TeporaConfig) {
+    let Some(state) = app.try_state::<QuickAskState>() else {
+        return;
+    };
+    let desired = config.desktop.quick_ask_shortcut().map(str::to_string);
+    let mut current = state.shortcut.lock().unwrap_or_else(|e| e.into_inner());
+    if *current == desired {
+        return;
+    }
+
+    let shortcuts = app.global_shortcut();
+    if let Some(previous) = current.take() {
+        if let Err(err) = shortcuts.unregister(previous.as_str()) {
+            log::warn!(
+                "Failed to unregister quick-ask shortcut '{}': {}",
+                previous,
+                err
+            );
+        }
+    }
+    let Some(shortcut) = desired else {
+        log::info!("Quick-ask shortcut disabled");
+        return;
+    };
+    let result = shortcuts.on_shortcut(shortcut.as_str(), |app, _shortcut, event| {
+        if event.state == ShortcutState::Pressed {
+            toggle_quick_ask(app);
+        }
+    });
+    match result {
+        Ok(()) => {
+            log::info!("Quick-ask shortcut registered: {}", shortcut);
+            *current = Some(shortcut);
+        }
+        Err(err) => log::warn!(
+            "Failed to register quick-ask shortcut '{}': {}",
+            shortcut,
+            err
+        ),
+    }
+}
+
+fn toggle_quick_ask(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(QUICK_ASK_WINDOW) {
+        if window.is_visible().unwrap_or(false) {
+            let _ = window.hide();
+        } else {
+            let _ = window.center();
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+        return;
+    }
+
+    let Some(port) = backend_port(app) else {
+        log::warn!("Quick-ask requested before the backend port is known");
+        return;
+    };
+    let url = format!("index.html?view={}&port={}", QUICK_ASK_WINDOW, port);
+    let built = WebviewWindowBuilder::new(app, QUICK_ASK_WINDOW, WebviewUrl::App(url.into()))
+        .title("Tepora Quick Ask")
+        .inner_size(WINDOW_WIDTH, WINDOW_HEIGHT)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .focused(true)
+        .build();
+    if let Err(err) = built {
+        log::warn!("Failed to open quick-ask window: {}", err);
+    }
+}
@@ -0,0 +1,143 @@
+//! セッションを専用ウィンドウで開く。
+//!
+//! ウィンドウのラベルは `session-<id>` で、ラベルとセッションの対応はここで持つ。
+//! 各ウィンドウは IPC ではなく自前の WS 接続でバックエンドに繋ぐので、
+//! ストリームはウィンドウごとに独立する。同じセッションを二重に開こうとしたときは
+//! 既存のウィンドウを前面に出す。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
+
+use crate::deep_link::is_valid_session_id;
+use crate::quick_ask;
+
+const LABEL_PREFIX: &str = "session-";
+pub const SESSION_VIEW: &str = "session";
+const WINDOW_WIDTH: f64 = 960.0;
+const WINDOW_HEIGHT: f64 = 720.0;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionWindowInfo {
+    pub label: String,
+    pub session_id: String,
+}
+
+/// ウィンドウのラベル → セッション ID
+#[derive(Default)]
+pub struct SessionWindows(Mutex<HashMap<String, String>>);
+
+impl SessionWindows {
+    fn label_for(&self, session_id: &str) -> Option<String> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|(_, id)| id.as_str() == session_id)
+            .map(|(label, _)| label.clone())
+    }
+
+    fn insert(&self, label: String, session_id: String) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(label, session_id);
+    }
+
+    fn remove(&self, label: &str) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(label);
+    }
+}
+
+pub fn window_label(session_id: &str) -> String {
+    format!("{}{}", LABEL_PREFIX, session_id)
+}
+
+pub fn setup_session_windows(app: &AppHandle) {
+    app.manage(SessionWindows::default());
+}
+
+/// セッションを専用ウィンドウで開き、そのラベルを返す。
+#[tauri::command]
+pub fn open_session_window(app: AppHandle, session_id: String) -> Result<String, String> {
+    if !is_valid_session_id(&session_id) {
+        return Err(format!("Invalid session id '{}'", session_id));
+    }
+    if focus_session_window(&app, &session_id) {
+        return Ok(window_label(&session_id));
+    }
+    let Some(port) = quick_ask::backend_port(&app) else {
+        return Err("Backend port is not known yet".to_string());
+    };
+
+    let label = window_label(&session_id);
+    let url = format!(
+        "index.html?view={}&session={}&port={}",
+        SESSION_VIEW, session_id, port
+    );
+    let window = WebviewWindowBuilder::new(&app, &label, WebviewUrl::App(url.into()))
+        .title("Tepora")
+        .inner_size(WINDOW_WIDTH, WINDOW_HEIGHT)
+        .min_inner_size(480.0, 360.0)
+        .focused(true)
+        .build()
+        .map_err(|err| err.to_string())?;
+    track(&app, &window, session_id);
+    Ok(label)
+}
+
+#[tauri::command]
+pub fn list_session_windows(state: tauri::State<'_, SessionWindows>) -> Vec<SessionWindowInfo> {
+    let mut windows: Vec<_> = state
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|(label, session_id)| SessionWindowInfo {
+            label: label.clone(),
+            session_id: session_id.clone(),
+        })
+        .collect();
+    windows.sort_by(|a, b| a.label.cmp(&b.label));
+    windows
+}
+
+/// そのセッションのウィンドウが開いていれば前面に出して `true`。
+pub fn focus_session_window(app: &AppHandle, session_id: &str) -> bool {
+    let Some(label) = app
+        .try_state::<SessionWindows>()
+        .and_then(|state| state.label_for(session_id))
+    else {
+        return false;
+    };
+    let Some(window) = app.get_webview_window(&label) else {
+        return false;
+    };
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+    true
+}
+
+fn track(app: &AppHandle, window: &WebviewWindow, session_id: String) {
+    let Some(state) = app.try_state::<SessionWindows>() else {
+        return;
+    };
+    state.insert(window.label().to_string(), session_id);
+
+    let app = app.clone();
+    let label = window.label().to_string();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            if let Some(state) = app.try_state::<SessionWindows>() {
+                state.remove(&label);
+            }
+        }
+    });
+}
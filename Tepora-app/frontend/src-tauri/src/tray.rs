@@ -0,0 +1,188 @@
+//! タスクトレイ。ウィンドウを隠したまま常駐させるための入口。
+//! メニューにはバックエンドの状態と使用中のモデルを表示し、定期的に更新する。
+//! 状態はウィンドウと同じくサイドカーのポートに HTTP で問い合わせる。
+
+use std::time::Duration;
+
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+
+use crate::backend_http;
+use crate::quick_ask::backend_port;
+
+const TRAY_ID: &str = "tepora-tray";
+const MAIN_WINDOW: &str = "main";
+/// フロントエンドへ通知するイベント名（payload はアクション名）
+pub const TRAY_ACTION_EVENT: &str = "tray_action";
+const STATUS_REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+/// フロントエンドの終了処理（サイドカー停止）を待つ上限
+const QUIT_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+const MENU_TOGGLE_WINDOW: &str = "toggle_window";
+const MENU_NEW_CHAT: &str = "new_chat";
+const MENU_QUIT: &str = "quit";
+
+struct TrayStatus {
+    backend: MenuItem<Wry>,
+    model: MenuItem<Wry>,
+}
+
+pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
+    let backend = MenuItem::with_id(
+        app,
+        "backend_status",
+        "Backend: starting…",
+        false,
+        None::<&str>,
+    )?;
+    let model = MenuItem::with_id(app, "active_model", "Model: -", false, None::<&str>)?;
+    let toggle = MenuItem::with_id(
+        app,
+        MENU_TOGGLE_WINDOW,
+        "Show / Hide Tepora",
+        true,
+        None::<&str>,
+    )?;
+    let new_chat = MenuItem::with_id(app, MENU_NEW_CHAT, "New chat", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, MENU_QUIT, "Quit Tepora", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &backend,
+            &model,
+            &PredefinedMenuItem::separator(app)?,
+            &toggle,
+            &new_chat,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Tepora")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(handle_menu_event)
+        .on_tray_icon_event(handle_tray_icon_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    spawn_status_refresh(app.clone(), TrayStatus { backend, model });
+    Ok(())
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        MENU_TOGGLE_WINDOW => toggle_main_window(app),
+        MENU_NEW_CHAT => {
+            show_main_window(app);
+            let _ = app.emit(TRAY_ACTION_EVENT, MENU_NEW_CHAT);
+        }
+        MENU_QUIT => quit_gracefully(app.clone()),
+        _ => {}
+    }
+}
+
+fn handle_tray_icon_event(tray: &TrayIcon, event: TrayIconEvent) {
+    if let TrayIconEvent::Click {
+        button: MouseButton::Left,
+        button_state: MouseButtonState::Up,
+        ..
+    } = event
+    {
+        toggle_main_window(tray.app_handle());
+    }
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn toggle_main_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return;
+    };
+    let visible = window.is_visible().unwrap_or(false) && !window.is_minimized().unwrap_or(false);
+    if visible {
+        let _ = window.hide();
+    } else {
+        show_main_window(app);
+    }
+}
+
+/// サイドカーに `/api/shutdown` を送って llama-server ごと止めてから、ウィンドウを
+/// 閉じてフロントエンドの終了処理に任せる。応答が無ければ猶予の後に強制終了する。
+fn quit_gracefully(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Some(port) = backend_port(&app) {
+            if let Err(err) = request_shutdown(port).await {
+                log::warn!("Failed to stop the backend before quitting: {}", err);
+            }
+        }
+        match app.get_webview_window(MAIN_WINDOW) {
+            Some(window) => {
+                let _ = window.close();
+                tokio::time::sleep(QUIT_GRACE_PERIOD).await;
+                log::warn!("Frontend did not exit in time, forcing quit");
+                app.exit(0);
+            }
+            None => app.exit(0),
+        }
+    });
+}
+
+async fn request_shutdown(port: u16) -> Result<(), reqwest::Error> {
+    backend_http::authorized(backend_http::client().post(backend_http::url(port, "/api/shutdown")))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+fn spawn_status_refresh(app: AppHandle, status: TrayStatus) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let (backend_label, model_label) = match backend_port(&app) {
+                Some(port) => describe_status(port).await,
+                None => ("Backend: starting…".to_string(), "Model: -".to_string()),
+            };
+            let _ = status.backend.set_text(backend_label);
+            let _ = status.model.set_text(model_label);
+            tokio::time::sleep(STATUS_REFRESH_INTERVAL).await;
+        }
+    });
+}
+
+/// サイドカーの `/health` と `/api/status` から表示を組み立てる。
+async fn describe_status(port: u16) -> (String, String) {
+    let client = backend_http::client();
+    let Some(health) =
+        backend_http::fetch_json(client.get(backend_http::url(port, "/health"))).await
+    else {
+        return ("Backend: unavailable".to_string(), "Model: -".to_string());
+    };
+    let degraded = health["status"].as_str() != Some("ok")
+        || backend_http::fetch_json(backend_http::authorized(
+            client.get(backend_http::url(port, "/api/status")),
+        ))
+        .await
+        .is_none_or(|status| status["degraded"].as_bool().unwrap_or(false));
+    let backend = if degraded {
+        "Backend: degraded"
+    } else {
+        "Backend: running"
+    };
+    let model = health["components"]["llm"]["model"]
+        .as_str()
+        .filter(|model| !model.is_empty())
+        .map(|model| format!("Model: {}", model))
+        .unwrap_or_else(|| "Model: not assigned".to_string());
+    (backend.to_string(), model)
+}
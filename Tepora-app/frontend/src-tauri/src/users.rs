@@ -0,0 +1,63 @@
+//! ログイン時のユーザー選択。
+//!
+//! 起動時に使うユーザーを決めて `TEPORA_USER` に固定し、同じプロセス内の
+//! バックエンドとサイドカー、トークンの読み出し先を揃える。ユーザーを切り替えるときは
+//! `users.json` の `last_user` を書き換えてアプリごと再起動する。
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tepora_backend::core::config::paths::tepora_home_dir;
+use tepora_backend::core::config::users::{
+    active_user_id, UserProfile, UserRegistry, USER_ENV_VAR,
+};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalUsers {
+    pub active: String,
+    pub users: Vec<UserProfile>,
+}
+
+/// バックエンドを初期化する前に呼ぶ。
+pub fn pin_startup_user() {
+    std::env::set_var(USER_ENV_VAR, active_user_id());
+}
+
+pub fn current_user() -> String {
+    active_user_id()
+}
+
+#[tauri::command]
+pub fn list_local_users() -> LocalUsers {
+    LocalUsers {
+        active: current_user(),
+        users: UserRegistry::load(&tepora_home_dir()).profiles(),
+    }
+}
+
+#[tauri::command]
+pub fn create_local_user(id: String, display_name: Option<String>) -> Result<UserProfile, String> {
+    let home = tepora_home_dir();
+    let mut registry = UserRegistry::load(&home);
+    let user = registry
+        .create(id.trim(), display_name.as_deref().unwrap_or(""))
+        .map_err(|err| err.to_string())?;
+    registry.save(&home).map_err(|err| err.to_string())?;
+    Ok(user)
+}
+
+/// 選んだユーザーで起動し直す。今のユーザーなら何もしない。
+#[tauri::command]
+pub fn login_as(app: AppHandle, id: String) -> Result<(), String> {
+    let id = id.trim();
+    if id == current_user() {
+        return Ok(());
+    }
+    let home = tepora_home_dir();
+    let mut registry = UserRegistry::load(&home);
+    registry.select(id).map_err(|err| err.to_string())?;
+    registry.save(&home).map_err(|err| err.to_string())?;
+    // 固定した環境変数は再起動後のプロセスにも引き継がれるので外しておく
+    std::env::remove_var(USER_ENV_VAR);
+    app.restart();
+}
@@ -1,5 +1,6 @@
 import { useEffect, useMemo, useState } from "react";
 import { useNavigate } from "react-router-dom";
+import { NetworkStatusBanner } from "./components/NetworkStatusBanner";
 import { WorkspaceExplorerPanel } from "./components/WorkspaceExplorerPanel";
 import { WorkspacePreviewPanel } from "./components/WorkspacePreviewPanel";
 import { WorkspaceSettingsPanel } from "./components/WorkspaceSettingsPanel";
@@ -297,6 +298,7 @@ export function Workspace({ isSettingsOpen = false }: WorkspaceProps) {
 
 	return (
 		<div className="relative h-screen w-screen overflow-hidden">
+			<NetworkStatusBanner />
 			<AppShellLayout
 				leftSidebar={<SessionSidebar />}
 				rightSidebar={<WorkspaceSettingsPanel />}
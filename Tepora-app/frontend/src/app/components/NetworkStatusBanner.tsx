@@ -0,0 +1,116 @@
+import { useState } from "react";
+import { useTranslation } from "react-i18next";
+import type { NetworkSubsystem } from "../../shared/contracts";
+import { ConfirmDialog } from "../../shared/ui/ConfirmDialog";
+import {
+	useNetworkStatusQuery,
+	useSetOfflineMutation,
+	useSetOfflineOverrideMutation,
+} from "../model/networkQueries";
+
+const SUBSYSTEM_LABELS: Record<NetworkSubsystem, string> = {
+	models: "Model downloads",
+	mcp_registry: "MCP registry",
+	search: "Web search",
+	web_fetch: "Web fetch",
+	http_tools: "HTTP tools",
+	cloud_providers: "Cloud providers",
+	binary_updates: "llama.cpp updates",
+	mcp_servers: "MCP servers (HTTP)",
+};
+
+/**
+ * オフラインモード中だけ表示する。止まっているサブシステムはここから確認付きで個別に許可できる。
+ */
+export function NetworkStatusBanner() {
+	const { t } = useTranslation();
+	const statusQuery = useNetworkStatusQuery();
+	const setOffline = useSetOfflineMutation();
+	const setOverride = useSetOfflineOverrideMutation();
+	const [pendingAllow, setPendingAllow] = useState<NetworkSubsystem | null>(null);
+	const [expanded, setExpanded] = useState(false);
+
+	const status = statusQuery.data;
+	if (!status?.offline) {
+		return null;
+	}
+
+	const label = (subsystem: NetworkSubsystem) =>
+		t(`v2.network.subsystems.${subsystem}`, SUBSYSTEM_LABELS[subsystem]);
+
+	return (
+		<div className="pointer-events-none absolute inset-x-0 top-3 z-30 flex justify-center">
+			<div className="pointer-events-auto rounded-[20px] border border-amber-400/30 bg-bg/95 px-4 py-2 text-sm text-text-main shadow-[0_12px_30px_rgba(59,38,20,0.12)] backdrop-blur-xl">
+				<div className="flex items-center gap-3">
+					<span className="h-2 w-2 rounded-full bg-amber-400" aria-hidden="true" />
+					<button
+						type="button"
+						className="text-left"
+						aria-expanded={expanded}
+						onClick={() => setExpanded((current) => !current)}
+					>
+						{t("v2.network.offlineSummary", "Offline mode — {{count}} blocked", {
+							count: status.blocked.length,
+						})}
+					</button>
+					<button
+						type="button"
+						className="rounded-full border border-white/10 px-3 py-1 text-xs text-text-muted transition-colors hover:text-primary disabled:opacity-50"
+						disabled={setOffline.isPending}
+						onClick={() => setOffline.mutate(false)}
+					>
+						{t("v2.network.goOnline", "Go online")}
+					</button>
+				</div>
+				{expanded ? (
+					<ul className="mt-2 space-y-1">
+						{status.blocked.map((subsystem) => (
+							<li key={subsystem} className="flex items-center justify-between gap-4">
+								<span className="text-text-muted">{label(subsystem)}</span>
+								<button
+									type="button"
+									className="text-xs text-primary disabled:opacity-50"
+									disabled={setOverride.isPending}
+									onClick={() => setPendingAllow(subsystem)}
+								>
+									{t("v2.network.allow", "Allow")}
+								</button>
+							</li>
+						))}
+						{status.allowed.map((subsystem) => (
+							<li key={subsystem} className="flex items-center justify-between gap-4">
+								<span>{label(subsystem)}</span>
+								<button
+									type="button"
+									className="text-xs text-text-muted disabled:opacity-50"
+									disabled={setOverride.isPending}
+									onClick={() => setOverride.mutate({ subsystem, allowed: false })}
+								>
+									{t("v2.network.block", "Block")}
+								</button>
+							</li>
+						))}
+					</ul>
+				) : null}
+			</div>
+			<ConfirmDialog
+				isOpen={pendingAllow !== null}
+				title={t("v2.network.allowTitle", "Allow network access?")}
+				message={t(
+					"v2.network.allowMessage",
+					"{{name}} will be able to reach the internet while offline mode stays on.",
+					{ name: pendingAllow ? label(pendingAllow) : "" },
+				)}
+				confirmLabel={t("v2.network.allow", "Allow")}
+				variant="warning"
+				onConfirm={() => {
+					if (pendingAllow) {
+						setOverride.mutate({ subsystem: pendingAllow, allowed: true });
+					}
+					setPendingAllow(null);
+				}}
+				onCancel={() => setPendingAllow(null)}
+			/>
+		</div>
+	);
+}
@@ -0,0 +1,55 @@
+import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
+import {
+	networkStatusResponseSchema,
+	type NetworkStatusResponse,
+	type NetworkSubsystem,
+} from "../../shared/contracts";
+import { v2ApiClient } from "../../shared/lib/api-client";
+import { v2DynamicQueryOptions } from "../../shared/lib/queryClient";
+import { v2SettingsQueryKeys } from "../../features/settings/model/queries";
+
+export const networkQueryKeys = {
+	status: () => ["v2", "network", "status"] as const,
+};
+
+export function useNetworkStatusQuery() {
+	return useQuery(
+		v2DynamicQueryOptions({
+			queryKey: networkQueryKeys.status(),
+			queryFn: () => v2ApiClient.get("/api/network", networkStatusResponseSchema),
+			refetchInterval: 30000,
+		}),
+	);
+}
+
+function useNetworkMutation<T>(request: (payload: T) => Promise<NetworkStatusResponse>) {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: request,
+		onSuccess: (status) => {
+			queryClient.setQueryData(networkQueryKeys.status(), status);
+		},
+		onSettled: () => {
+			// 設定画面の network セクションも書き換わっている
+			void queryClient.invalidateQueries({ queryKey: v2SettingsQueryKeys.config() });
+		},
+	});
+}
+
+export function useSetOfflineMutation() {
+	return useNetworkMutation((offline: boolean) =>
+		v2ApiClient.patch("/api/network", networkStatusResponseSchema, { offline }),
+	);
+}
+
+export function useSetOfflineOverrideMutation() {
+	return useNetworkMutation(
+		({ subsystem, allowed }: { subsystem: NetworkSubsystem; allowed: boolean }) =>
+			v2ApiClient.patch(
+				`/api/network/overrides/${subsystem}`,
+				networkStatusResponseSchema,
+				{ allowed },
+			),
+	);
+}
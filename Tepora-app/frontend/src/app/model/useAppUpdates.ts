@@ -0,0 +1,75 @@
+import { useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { ask } from "@tauri-apps/plugin-dialog";
+import { updateReadinessResponseSchema } from "../../shared/contracts";
+import { v2ApiClient } from "../../shared/lib/api-client";
+import { isDesktop } from "../../utils/api";
+import { logger } from "../../utils/logger";
+
+const CHECK_INTERVAL_MS = 6 * 60 * 60 * 1000;
+// エージェント実行やダウンロードが終わるのを待つ間隔
+const READINESS_POLL_MS = 60 * 1000;
+
+interface AvailableUpdate {
+	version: string;
+	currentVersion: string;
+	notes: string | null;
+}
+
+function fetchReadiness() {
+	return v2ApiClient.get("/api/updates/readiness", updateReadinessResponseSchema);
+}
+
+function delay(ms: number) {
+	return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
+/**
+ * 設定のチャネルで更新を確認し、バックエンドが実行中の処理が無いと答えたら
+ * 確認ダイアログを出してから入れ替える。
+ */
+export function useAppUpdates() {
+	useEffect(() => {
+		if (!isDesktop()) return;
+		let disposed = false;
+		let running = false;
+
+		const checkOnce = async () => {
+			if (running) return;
+			running = true;
+			try {
+				let readiness = await fetchReadiness();
+				if (!readiness.auto_check) return;
+				const channel = readiness.channel;
+				const update = await invoke<AvailableUpdate | null>("check_app_update", { channel });
+				if (!update || disposed) return;
+
+				while (!readiness.ready) {
+					logger.log("[Updater] Deferring update while work is active", readiness.blockers);
+					await delay(READINESS_POLL_MS);
+					if (disposed) return;
+					readiness = await fetchReadiness();
+				}
+
+				const accepted = await ask(
+					`Tepora ${update.version} is available (current: ${update.currentVersion}).\n\n${update.notes ?? ""}\n\nRestart and install now?`,
+					{ title: "Update available", kind: "info" },
+				);
+				if (accepted && !disposed) {
+					await invoke("install_app_update", { channel });
+				}
+			} catch (error) {
+				logger.warn("[Updater] Update check failed", error);
+			} finally {
+				running = false;
+			}
+		};
+
+		void checkOnce();
+		const timer = setInterval(() => void checkOnce(), CHECK_INTERVAL_MS);
+		return () => {
+			disposed = true;
+			clearInterval(timer);
+		};
+	}, []);
+}
@@ -0,0 +1,84 @@
+import { useEffect } from "react";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import {
+	isPermissionGranted,
+	requestPermission,
+	sendNotification,
+} from "@tauri-apps/plugin-notification";
+import { getWsBase, isDesktop } from "../../utils/api";
+import { logger } from "../../utils/logger";
+import { getSessionToken } from "../../utils/sessionToken";
+import { buildWebSocketProtocols } from "../../utils/wsAuth";
+
+const RECONNECT_DELAY_MS = 5000;
+
+interface NotificationPayload {
+	kind: "agent_run" | "download" | "binary_update";
+	title: string;
+	body: string;
+	success: boolean;
+}
+
+async function isWindowHidden(): Promise<boolean> {
+	const appWindow = getCurrentWindow();
+	const [visible, minimized, focused] = await Promise.all([
+		appWindow.isVisible(),
+		appWindow.isMinimized(),
+		appWindow.isFocused(),
+	]);
+	return !visible || minimized || !focused;
+}
+
+async function ensurePermission(): Promise<boolean> {
+	if (await isPermissionGranted()) return true;
+	return (await requestPermission()) === "granted";
+}
+
+async function showNotification(payload: NotificationPayload) {
+	if (!(await isWindowHidden())) return;
+	if (!(await ensurePermission())) return;
+	sendNotification({ title: payload.title, body: payload.body });
+}
+
+/**
+ * バックエンドの `notification` イベントを受け、ウィンドウが隠れているときだけ
+ * OS の通知を出す。種類ごとの送信可否はバックエンドの設定で絞り込み済み。
+ */
+export function useBackgroundNotifications() {
+	useEffect(() => {
+		if (!isDesktop()) return;
+		let disposed = false;
+		let socket: WebSocket | null = null;
+		let reconnectTimer: ReturnType<typeof setTimeout> | undefined;
+
+		const connect = async () => {
+			const token = await getSessionToken();
+			if (disposed) return;
+			socket = new WebSocket(`${getWsBase()}/ws`, buildWebSocketProtocols(token));
+			socket.onmessage = (event) => {
+				let data: { type?: string; data?: NotificationPayload };
+				try {
+					data = JSON.parse(String(event.data));
+				} catch {
+					return;
+				}
+				if (data.type === "notification" && data.data) {
+					showNotification(data.data).catch((error) => {
+						logger.warn("[Notifications] Failed to show notification", error);
+					});
+				}
+			};
+			socket.onclose = () => {
+				if (disposed) return;
+				reconnectTimer = setTimeout(() => void connect(), RECONNECT_DELAY_MS);
+			};
+		};
+
+		void connect();
+		return () => {
+			disposed = true;
+			if (reconnectTimer) clearTimeout(reconnectTimer);
+			socket?.close();
+		};
+	}, []);
+}
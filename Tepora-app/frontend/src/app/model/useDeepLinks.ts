@@ -0,0 +1,55 @@
+import { useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { isDesktop } from "../../utils/api";
+import { logger } from "../../utils/logger";
+import { useWorkspaceStore } from "./workspaceStore";
+
+// src-tauri/src/deep_link.rs の DEEP_LINK_EVENT と揃える
+const DEEP_LINK_EVENT = "deep_link";
+
+type DeepLink = { kind: "session"; sessionId: string };
+
+function openDeepLink(link: DeepLink | null) {
+	if (!link) return;
+	if (link.kind === "session") {
+		const store = useWorkspaceStore.getState();
+		store.setSelectedSessionId(link.sessionId);
+		store.setMobilePane("chat");
+	}
+}
+
+async function consumePendingDeepLink() {
+	openDeepLink(await invoke<DeepLink | null>("take_pending_deep_link"));
+}
+
+/**
+ * `tepora://session/<id>` で開いたセッションを選択する。
+ * 起動前に届いたリンクも Rust 側に保持されているので、マウント時に取り出す。
+ */
+export function useDeepLinks() {
+	useEffect(() => {
+		if (!isDesktop()) return;
+		let unlisten: (() => void) | undefined;
+		let disposed = false;
+
+		consumePendingDeepLink().catch((error) => {
+			logger.warn("[DeepLink] Failed to read pending link", error);
+		});
+		listen(DEEP_LINK_EVENT, () => {
+			consumePendingDeepLink().catch((error) => {
+				logger.warn("[DeepLink] Failed to open link", error);
+			});
+		})
+			.then((fn) => {
+				if (disposed) fn();
+				else unlisten = fn;
+			})
+			.catch(console.error);
+
+		return () => {
+			disposed = true;
+			if (unlisten) unlisten();
+		};
+	}, []);
+}
@@ -0,0 +1,13 @@
+// main.tsx が `?view=` で振り分ける別ウィンドウの種類。メインウィンドウは null
+export type WindowView = "quick-ask" | "session";
+
+export function getWindowView(): WindowView | null {
+	if (typeof window === "undefined") return null;
+	const view = new URLSearchParams(window.location.search).get("view");
+	return view === "quick-ask" || view === "session" ? view : null;
+}
+
+/** 通知・ディープリンク・自動更新などアプリ全体で一つだけ動かす処理の担当か */
+export function isMainWindow(): boolean {
+	return getWindowView() === null;
+}
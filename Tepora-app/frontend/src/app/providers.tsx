@@ -8,6 +8,10 @@ import { Component, useEffect } from "react";
 import { useTranslation } from "react-i18next";
 import { useV2ConfigQuery } from "../features/settings/model/queries";
 import { createV2QueryClient } from "../shared/lib/queryClient";
+import { useAppUpdates } from "./model/useAppUpdates";
+import { useBackgroundNotifications } from "./model/useBackgroundNotifications";
+import { useDeepLinks } from "./model/useDeepLinks";
+import { isMainWindow } from "./model/windowView";
 
 interface AppProvidersProps {
 	children: ReactNode;
@@ -122,6 +126,15 @@ function AppEnvironmentSync() {
 	return null;
 }
 
+// セッション用の別ウィンドウで重複して動かないよう、メインウィンドウだけにマウントする
+function MainWindowServices() {
+	useBackgroundNotifications();
+	useDeepLinks();
+	useAppUpdates();
+
+	return null;
+}
+
 const v2QueryClient = createV2QueryClient();
 
 export function AppProviders({ children }: AppProvidersProps) {
@@ -131,6 +144,7 @@ export function AppProviders({ children }: AppProvidersProps) {
 				<AppRootBoundary onReset={reset}>
 					<QueryClientProvider client={v2QueryClient}>
 						<AppEnvironmentSync />
+						{isMainWindow() ? <MainWindowServices /> : null}
 						{children}
 						<ReactQueryDevtools initialIsOpen={false} />
 					</QueryClientProvider>
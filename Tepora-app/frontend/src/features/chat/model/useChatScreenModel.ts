@@ -10,6 +10,7 @@ import { useChatComposerActions } from "./useChatComposerActions";
 import { useChatScreenState } from "./useChatScreenState";
 import { useChatSessionLifecycle } from "./useChatSessionLifecycle";
 import { useChatTransportLifecycle } from "./useChatTransportLifecycle";
+import { useTrayActions } from "./useTrayActions";
 import { useV2SetupModelsQuery } from "../../settings/model/queries";
 
 export function useChatScreenModel(): ChatScreenViewProps & {
@@ -104,6 +105,8 @@ export function useChatScreenModel(): ChatScreenViewProps & {
 		createSession: createSessionMutation.mutateAsync,
 	});
 
+	useTrayActions({ onNewChat: handleCreateSession });
+
 	const shellState = resolveShellState(
 		sessionsQuery.isLoading,
 		sessionsQuery.error,
@@ -0,0 +1,40 @@
+import { useEffect, useRef } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { isDesktop } from "../../../utils/api";
+import { logger } from "../../../utils/logger";
+
+// src-tauri/src/tray.rs の TRAY_ACTION_EVENT と揃える
+const TRAY_ACTION_EVENT = "tray_action";
+
+interface UseTrayActionsParams {
+	onNewChat: () => Promise<void>;
+}
+
+export function useTrayActions({ onNewChat }: UseTrayActionsParams) {
+	// listen し直さずに最新のハンドラを呼ぶ
+	const onNewChatRef = useRef(onNewChat);
+	onNewChatRef.current = onNewChat;
+
+	useEffect(() => {
+		if (!isDesktop()) return;
+		let unlisten: (() => void) | undefined;
+		let disposed = false;
+		listen<string>(TRAY_ACTION_EVENT, (event) => {
+			if (event.payload === "new_chat") {
+				onNewChatRef.current().catch((error) => {
+					logger.error("[Tray] Failed to start a new chat", error);
+				});
+			}
+		})
+			.then((fn) => {
+				if (disposed) fn();
+				else unlisten = fn;
+			})
+			.catch(console.error);
+
+		return () => {
+			disposed = true;
+			if (unlisten) unlisten();
+		};
+	}, []);
+}
@@ -0,0 +1,96 @@
+import { useCallback, useEffect, useRef, useState } from "react";
+import { getWsBase } from "../../../utils/api";
+import { logger } from "../../../utils/logger";
+import { getSessionToken } from "../../../utils/sessionToken";
+import { buildWebSocketProtocols } from "../../../utils/wsAuth";
+
+// メインウィンドウの会話と混ざらないよう専用セッションを使う
+export const QUICK_ASK_SESSION_ID = "quick-ask";
+
+export type QuickAskStatus = "connecting" | "idle" | "streaming" | "error";
+
+interface QuickAskIncoming {
+	type?: string;
+	message?: string;
+}
+
+export function useQuickAskSession() {
+	const socketRef = useRef<WebSocket | null>(null);
+	const [status, setStatus] = useState<QuickAskStatus>("connecting");
+	const [answer, setAnswer] = useState("");
+	const [error, setError] = useState<string | null>(null);
+
+	useEffect(() => {
+		let disposed = false;
+		let socket: WebSocket | null = null;
+
+		void getSessionToken().then((token) => {
+			if (disposed) return;
+			socket = new WebSocket(`${getWsBase()}/ws`, buildWebSocketProtocols(token));
+			socketRef.current = socket;
+			socket.onopen = () => {
+				socket?.send(JSON.stringify({ type: "set_session", sessionId: QUICK_ASK_SESSION_ID }));
+				setStatus("idle");
+			};
+			socket.onmessage = (event) => {
+				let data: QuickAskIncoming;
+				try {
+					data = JSON.parse(String(event.data)) as QuickAskIncoming;
+				} catch {
+					return;
+				}
+				switch (data.type) {
+					case "chunk":
+						setAnswer((previous) => previous + (data.message ?? ""));
+						break;
+					case "done":
+					case "stopped":
+						setStatus("idle");
+						break;
+					case "error":
+						setError(data.message ?? "Unknown error");
+						setStatus("error");
+						break;
+				}
+			};
+			socket.onclose = () => {
+				if (!disposed) setStatus("error");
+			};
+			socket.onerror = (event) => {
+				logger.error("[QuickAsk] WebSocket error", event);
+			};
+		});
+
+		return () => {
+			disposed = true;
+			socket?.close();
+			socketRef.current = null;
+		};
+	}, []);
+
+	const ask = useCallback((question: string) => {
+		const socket = socketRef.current;
+		const message = question.trim();
+		if (!socket || socket.readyState !== WebSocket.OPEN || message.length === 0) {
+			return;
+		}
+		setAnswer("");
+		setError(null);
+		setStatus("streaming");
+		socket.send(
+			JSON.stringify({
+				clientMessageId: crypto.randomUUID(),
+				sessionId: QUICK_ASK_SESSION_ID,
+				message,
+				mode: "chat",
+				skipWebSearch: true,
+			}),
+		);
+	}, []);
+
+	const stop = useCallback(() => {
+		socketRef.current?.send(JSON.stringify({ type: "stop", sessionId: QUICK_ASK_SESSION_ID }));
+	}, []);
+
+	return { status, answer, error, ask, stop };
+}
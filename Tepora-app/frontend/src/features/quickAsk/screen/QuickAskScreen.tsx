@@ -0,0 +1,49 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { KeyboardEvent } from "react";
+import { useState } from "react";
+import { useQuickAskSession } from "../model/useQuickAskSession";
+
+function hideWindow() {
+	invoke("hide_quick_ask").catch(console.error);
+}
+
+export function QuickAskScreen() {
+	const [question, setQuestion] = useState("");
+	const { status, answer, error, ask, stop } = useQuickAskSession();
+
+	const handleKeyDown = (event: KeyboardEvent<HTMLInputElement>) => {
+		if (event.key === "Escape") {
+			event.preventDefault();
+			if (status === "streaming") stop();
+			else hideWindow();
+			return;
+		}
+		if (event.key === "Enter" && !event.nativeEvent.isComposing) {
+			event.preventDefault();
+			ask(question);
+			setQuestion("");
+		}
+	};
+
+	return (
+		<div
+			data-tauri-drag-region
+			className="flex h-screen w-screen flex-col gap-3 rounded-[20px] border border-[color:var(--glass-border)] bg-[var(--glass-bg)] p-4 backdrop-blur-[30px]"
+		>
+			<input
+				autoFocus
+				value={question}
+				onChange={(event) => setQuestion(event.target.value)}
+				onKeyDown={handleKeyDown}
+				disabled={status === "connecting"}
+				placeholder={status === "connecting" ? "Connecting…" : "Ask Tepora…"}
+				className="w-full border-none bg-transparent text-[1.05rem] font-medium text-text-main outline-none placeholder:font-light placeholder:text-text-muted/55"
+			/>
+			{(answer || error) && (
+				<div className="custom-scrollbar flex-1 overflow-y-auto whitespace-pre-wrap text-sm text-text-main">
+					{error ? <span className="text-red-400">{error}</span> : answer}
+				</div>
+			)}
+		</div>
+	);
+}
@@ -1,5 +1,9 @@
 import { startTransition, useEffect, useMemo, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { isMainWindow } from "../../../app/model/windowView";
 import { useWorkspaceStore } from "../../../app/model/workspaceStore";
+import { isDesktop } from "../../../utils/api";
+import { logger } from "../../../utils/logger";
 import type { SessionSidebarViewProps } from "../view/props";
 import {
 	useCreateSessionMutation,
@@ -85,5 +89,13 @@ export function useSessionSidebarModel(): SessionSidebarViewProps {
 				setPendingSessionId((current) => (current === sessionId ? null : current));
 			}
 		},
+		onOpenSessionWindow:
+			isDesktop() && isMainWindow()
+				? (sessionId) => {
+						invoke("open_session_window", { sessionId }).catch((error) => {
+							logger.warn("[Session] Failed to open session window", error);
+						});
+					}
+				: undefined,
 	};
 }
@@ -26,6 +26,7 @@ export const SessionSidebarView: React.FC<SessionSidebarViewProps> = ({
 	onCreateSession,
 	onRenameSession,
 	onDeleteSession,
+	onOpenSessionWindow,
 }) => {
 	const { t } = useTranslation();
 	const [editingSessionId, setEditingSessionId] = useState<string | null>(null);
@@ -181,6 +182,19 @@ export const SessionSidebarView: React.FC<SessionSidebarViewProps> = ({
 													>
 														{t("v2.session.rename", "Rename")}
 													</button>
+													{onOpenSessionWindow ? (
+														<button
+															type="button"
+															role="menuitem"
+															className="flex w-full items-center rounded-[14px] px-3 py-2 text-left text-sm text-text-main transition-colors hover:bg-surface/60"
+															onClick={() => {
+																onOpenSessionWindow(session.id);
+																setOpenMenuId(null);
+															}}
+														>
+															{t("v2.session.openInWindow", "Open in new window")}
+														</button>
+													) : null}
 													<button
 														type="button"
 														role="menuitem"
@@ -16,4 +16,6 @@ export interface SessionSidebarViewProps {
 	onCreateSession: () => Promise<void>;
 	onRenameSession: (sessionId: string, title: string) => Promise<void>;
 	onDeleteSession: (sessionId: string) => Promise<void>;
+	/** デスクトップ版のみ。セッションを専用ウィンドウで開く */
+	onOpenSessionWindow?: (sessionId: string) => void;
 }
@@ -84,7 +84,7 @@ const DEFAULT_EDITOR_CONFIG: SettingsRecord = {
 		loader: "ollama",
 		process_terminate_timeout: 5000,
 		health_check_timeout: 10000,
-		health_check_interval: 1000,
+		health_check_interval_ms: 1000,
 	},
 	loaders: {
 		ollama: {
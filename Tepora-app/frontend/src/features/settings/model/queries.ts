@@ -10,10 +10,15 @@ import {
 	mcpInstallPreviewResponseSchema,
 	mcpStatusResponseSchema,
 	mcpStoreResponseSchema,
+	pluginsResponseSchema,
 	saveAgentSkillResponseSchema,
+	scriptDetailSchema,
+	scriptInfoSchema,
+	scriptsResponseSchema,
 	setupModelsResponseSchema,
 	successResponseSchema,
 	type McpServerConfig,
+	type PluginCapability,
 	type SaveAgentSkillRequest,
 	type V2Config,
 } from "../../../shared/contracts";
@@ -32,6 +37,9 @@ export const v2SettingsQueryKeys = {
 	credentials: () => ["v2", "settings", "credentials"] as const,
 	agentSkills: () => ["v2", "settings", "agentSkills"] as const,
 	agentSkill: (skillId: string) => ["v2", "settings", "agentSkills", skillId] as const,
+	plugins: () => ["v2", "settings", "plugins"] as const,
+	scripts: () => ["v2", "settings", "scripts"] as const,
+	script: (scriptId: string) => ["v2", "settings", "scripts", scriptId] as const,
 	mcpConfig: () => ["v2", "settings", "mcp", "config"] as const,
 	mcpStatus: () => ["v2", "settings", "mcp", "status"] as const,
 	mcpStore: (params: { search?: string; page?: number; pageSize?: number; runtime?: string }) =>
@@ -143,6 +151,118 @@ export function useRotateCredentialMutation() {
 	});
 }
 
+export function usePluginsQuery() {
+	return useQuery(
+		v2StaticQueryOptions({
+			queryKey: v2SettingsQueryKeys.plugins(),
+			queryFn: () => v2ApiClient.get("/api/plugins", pluginsResponseSchema),
+		}),
+	);
+}
+
+export function useUpdatePluginGrantsMutation() {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: (payload: { pluginId: string; capabilities: PluginCapability[] }) =>
+			v2ApiClient.patch(
+				`/api/plugins/${encodeURIComponent(payload.pluginId)}/grants`,
+				pluginsResponseSchema,
+				{ capabilities: payload.capabilities },
+			),
+		onSuccess: (data) => {
+			queryClient.setQueryData(v2SettingsQueryKeys.plugins(), data);
+			void queryClient.invalidateQueries({
+				queryKey: v2SettingsQueryKeys.config(),
+			});
+		},
+	});
+}
+
+export function useReloadPluginsMutation() {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: () => v2ApiClient.post("/api/plugins/reload", pluginsResponseSchema, {}),
+		onSuccess: (data) => {
+			queryClient.setQueryData(v2SettingsQueryKeys.plugins(), data);
+		},
+	});
+}
+
+export function useScriptsQuery() {
+	return useQuery(
+		v2StaticQueryOptions({
+			queryKey: v2SettingsQueryKeys.scripts(),
+			queryFn: () => v2ApiClient.get("/api/scripts", scriptsResponseSchema),
+		}),
+	);
+}
+
+export function useScriptQuery(scriptId: string | null) {
+	return useQuery(
+		v2StaticQueryOptions({
+			queryKey: v2SettingsQueryKeys.script(scriptId ?? ""),
+			queryFn: () =>
+				v2ApiClient.get(
+					`/api/scripts/${encodeURIComponent(scriptId ?? "")}`,
+					scriptDetailSchema,
+				),
+			enabled: Boolean(scriptId),
+		}),
+	);
+}
+
+export function useSaveScriptMutation() {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: (payload: { id: string; name: string; description: string; source: string }) =>
+			v2ApiClient.post("/api/scripts", scriptDetailSchema, payload),
+		onSuccess: (data) => {
+			queryClient.setQueryData(v2SettingsQueryKeys.script(data.id), data);
+			void queryClient.invalidateQueries({
+				queryKey: v2SettingsQueryKeys.scripts(),
+			});
+		},
+	});
+}
+
+export function useSetScriptEnabledMutation() {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: (payload: { scriptId: string; enabled: boolean }) =>
+			v2ApiClient.post(
+				`/api/scripts/${encodeURIComponent(payload.scriptId)}/${payload.enabled ? "enable" : "disable"}`,
+				scriptInfoSchema,
+				{},
+			),
+		onSuccess: () => {
+			void queryClient.invalidateQueries({
+				queryKey: v2SettingsQueryKeys.scripts(),
+			});
+		},
+	});
+}
+
+export function useDeleteScriptMutation() {
+	const queryClient = useQueryClient();
+
+	return useMutation({
+		mutationFn: (scriptId: string) =>
+			v2ApiClient.delete(
+				`/api/scripts/${encodeURIComponent(scriptId)}`,
+				successResponseSchema,
+			),
+		onSuccess: () => {
+			void queryClient.invalidateQueries({
+				queryKey: v2SettingsQueryKeys.scripts(),
+			});
+		},
+	});
+}
+
 export function useAgentSkillsQuery() {
 	return useQuery(
 		v2StaticQueryOptions({
@@ -0,0 +1,128 @@
+import { invoke } from "@tauri-apps/api/core";
+import React, { useCallback, useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { Button } from "../../../../shared/ui/Button";
+import { SelectionDot } from "../../../../shared/ui/SelectionDot";
+import { SettingsRow } from "../../../../shared/ui/SettingsRow";
+import { SettingsSectionGroup } from "../../../../shared/ui/SettingsSectionGroup";
+import { TextField } from "../../../../shared/ui/TextField";
+import { isDesktop } from "../../../../utils/api";
+
+interface LocalUserProfile {
+	id: string;
+	display_name: string;
+	created_at: string;
+}
+
+interface LocalUsers {
+	active: string;
+	users: LocalUserProfile[];
+}
+
+/**
+ * ローカルユーザーの一覧と切り替え。切り替えるとアプリごと再起動する。
+ */
+export const LocalProfilesPanel: React.FC = () => {
+	const { t } = useTranslation();
+	const [localUsers, setLocalUsers] = useState<LocalUsers | null>(null);
+	const [newId, setNewId] = useState("");
+	const [newName, setNewName] = useState("");
+	const [errorMessage, setErrorMessage] = useState<string | null>(null);
+
+	const refresh = useCallback(async () => {
+		try {
+			setLocalUsers(await invoke<LocalUsers>("list_local_users"));
+		} catch (error) {
+			setErrorMessage(String(error));
+		}
+	}, []);
+
+	useEffect(() => {
+		if (isDesktop()) void refresh();
+	}, [refresh]);
+
+	if (!isDesktop()) {
+		return (
+			<SettingsSectionGroup title={t("v2.settings.profiles", "Profiles")}>
+				<p className="text-sm text-text-muted">
+					{t("v2.settings.profilesDesktopOnly", "Local profiles are available in the desktop app.")}
+				</p>
+			</SettingsSectionGroup>
+		);
+	}
+
+	const switchTo = async (id: string) => {
+		setErrorMessage(null);
+		try {
+			await invoke("login_as", { id });
+		} catch (error) {
+			setErrorMessage(String(error));
+		}
+	};
+
+	const create = async () => {
+		setErrorMessage(null);
+		try {
+			await invoke("create_local_user", {
+				id: newId.trim(),
+				displayName: newName.trim() || null,
+			});
+			setNewId("");
+			setNewName("");
+			await refresh();
+		} catch (error) {
+			setErrorMessage(String(error));
+		}
+	};
+
+	return (
+		<div className="flex flex-col">
+			{errorMessage ? (
+				<div className="mb-4 rounded-2xl border border-red-500/20 bg-red-500/10 px-4 py-3 text-sm text-red-700 dark:text-red-200">
+					{errorMessage}
+				</div>
+			) : null}
+
+			<SettingsSectionGroup title={t("v2.settings.profiles", "Profiles")}>
+				<SettingsRow
+					label={t("v2.settings.activeProfile", "Active Profile")}
+					description={t(
+						"v2.settings.activeProfileDescription",
+						"Switching profiles restarts Tepora with that user's settings and data.",
+					)}
+				>
+					<div className="flex flex-col gap-3">
+						{(localUsers?.users ?? []).map((user) => (
+							<SelectionDot
+								key={user.id}
+								label={user.display_name || user.id}
+								selected={localUsers?.active === user.id}
+								onClick={() => void switchTo(user.id)}
+							/>
+						))}
+					</div>
+				</SettingsRow>
+				<SettingsRow
+					label={t("v2.settings.newProfile", "New Profile")}
+					description={t("v2.settings.newProfileDescription", "Create another local user on this device.")}
+				>
+					<div className="flex w-full max-w-xs flex-col gap-2">
+						<TextField
+							value={newId}
+							onChange={(event) => setNewId(event.target.value)}
+							placeholder={t("v2.settings.profileId", "Profile ID")}
+						/>
+						<TextField
+							value={newName}
+							onChange={(event) => setNewName(event.target.value)}
+							placeholder={t("v2.settings.profileDisplayName", "Display name (optional)")}
+						/>
+						<Button variant="secondary" disabled={!newId.trim()} onClick={() => void create()}>
+							{t("v2.settings.createProfile", "Create")}
+						</Button>
+					</div>
+				</SettingsRow>
+			</SettingsSectionGroup>
+		</div>
+	);
+};
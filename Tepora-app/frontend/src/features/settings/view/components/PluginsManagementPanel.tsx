@@ -0,0 +1,173 @@
+import { useState } from "react";
+import { useTranslation } from "react-i18next";
+import type { PluginCapability, PluginInfo } from "../../../../shared/contracts";
+import { Button } from "../../../../shared/ui/Button";
+import { ConfirmDialog } from "../../../../shared/ui/ConfirmDialog";
+import { MinToggle } from "../../../../shared/ui/MinToggle";
+import { SettingsRow } from "../../../../shared/ui/SettingsRow";
+import { SettingsSectionGroup } from "../../../../shared/ui/SettingsSectionGroup";
+import { useSettingsEditor } from "../../model/editor";
+import {
+	usePluginsQuery,
+	useReloadPluginsMutation,
+	useUpdatePluginGrantsMutation,
+} from "../../model/queries";
+
+const CAPABILITY_LABELS: Record<PluginCapability, string> = {
+	tools: "Register tools",
+	context_workers: "Add context to prompts",
+	graph_nodes: "Provide workflow nodes",
+	storage: "Private data folder",
+};
+
+const STATUS_TONE: Record<PluginInfo["status"], string> = {
+	loaded: "border-emerald-500/20 bg-emerald-500/10 text-emerald-200",
+	disabled: "border-primary/10 bg-primary/5 text-text-muted",
+	failed: "border-red-500/20 bg-red-500/10 text-red-200",
+};
+
+interface PendingGrant {
+	plugin: PluginInfo;
+	capability: PluginCapability;
+}
+
+export function PluginsManagementPanel() {
+	const { t } = useTranslation();
+	const editor = useSettingsEditor();
+	const pluginsQuery = usePluginsQuery();
+	const updateGrants = useUpdatePluginGrantsMutation();
+	const reloadPlugins = useReloadPluginsMutation();
+	const [pendingGrant, setPendingGrant] = useState<PendingGrant | null>(null);
+	const enabled = editor.readBoolean("plugins.enabled", false);
+	const data = pluginsQuery.data;
+
+	const capabilityLabel = (capability: PluginCapability) =>
+		t(`v2.settings.plugins.capabilities.${capability}`, CAPABILITY_LABELS[capability]);
+
+	const setGrant = (plugin: PluginInfo, capability: PluginCapability, granted: boolean) => {
+		const capabilities = granted
+			? [...plugin.granted, capability]
+			: plugin.granted.filter((item) => item !== capability);
+		updateGrants.mutate({ pluginId: plugin.id, capabilities });
+	};
+
+	return (
+		<div className="flex flex-col">
+			<SettingsSectionGroup title={t("v2.settings.plugins.title", "Plugins")}>
+				<SettingsRow
+					label={t("v2.settings.plugins.enabled", "Enable plugins")}
+					description={t(
+						"v2.settings.plugins.enabledDescription",
+						"Load WASM plugins from the plugins folder. Each plugin runs sandboxed and only gets the permissions you grant.",
+					)}
+				>
+					<MinToggle
+						checked={enabled}
+						onChange={(checked) => editor.updateField("plugins.enabled", checked)}
+						label={enabled ? "Enabled" : "Disabled"}
+					/>
+				</SettingsRow>
+				{data && !data.host_available ? (
+					<div className="rounded-[24px] border border-amber-500/20 bg-amber-500/10 px-6 py-5 text-sm text-amber-200">
+						{t(
+							"v2.settings.plugins.hostUnavailable",
+							"This build does not include the WASM runtime, so plugins cannot be loaded.",
+						)}
+					</div>
+				) : null}
+				<div>
+					<Button
+						variant="secondary"
+						disabled={reloadPlugins.isPending}
+						onClick={() => reloadPlugins.mutate()}
+					>
+						{t("v2.settings.plugins.reload", "Reload plugins")}
+					</Button>
+				</div>
+				{data && data.plugins.length === 0 ? (
+					<div className="text-sm text-text-muted">
+						{t("v2.settings.plugins.empty", "No plugins installed.")}
+					</div>
+				) : null}
+				<div className="grid gap-4 xl:grid-cols-2">
+					{(data?.plugins ?? []).map((plugin) => (
+						<div
+							key={plugin.id}
+							className="rounded-[24px] border border-primary/10 bg-white/55 p-5"
+						>
+							<div className="flex items-start justify-between gap-3">
+								<div>
+									<div className="text-base font-medium text-text-main">
+										{plugin.name}
+										{plugin.version ? (
+											<span className="ml-2 text-sm text-text-muted">
+												{plugin.version}
+											</span>
+										) : null}
+									</div>
+									{plugin.description ? (
+										<div className="mt-1 text-sm text-text-muted">
+											{plugin.description}
+										</div>
+									) : null}
+								</div>
+								<div
+									className={`rounded-full border px-3 py-1 text-xs uppercase tracking-[0.16em] ${STATUS_TONE[plugin.status]}`}
+								>
+									{t(`v2.settings.plugins.status.${plugin.status}`, plugin.status)}
+								</div>
+							</div>
+							{plugin.error ? (
+								<div className="mt-3 text-sm text-red-200">{plugin.error}</div>
+							) : null}
+							<div className="mt-5 flex flex-col gap-3">
+								{plugin.requested.map((capability) => {
+									const granted = plugin.granted.includes(capability);
+									return (
+										<SettingsRow key={capability} label={capabilityLabel(capability)}>
+											<MinToggle
+												checked={granted}
+												onChange={(checked) =>
+													checked
+														? setPendingGrant({ plugin, capability })
+														: setGrant(plugin, capability, false)
+												}
+												label={granted ? "Granted" : "Not granted"}
+											/>
+										</SettingsRow>
+									);
+								})}
+							</div>
+							{plugin.tools.length > 0 ? (
+								<div className="mt-4 text-xs text-text-muted">
+									{t("v2.settings.plugins.tools", "Tools")}: {plugin.tools.join(", ")}
+								</div>
+							) : null}
+						</div>
+					))}
+				</div>
+			</SettingsSectionGroup>
+			<ConfirmDialog
+				isOpen={pendingGrant !== null}
+				title={t("v2.settings.plugins.grantTitle", "Grant permission?")}
+				message={t(
+					"v2.settings.plugins.grantMessage",
+					"{{plugin}} will be allowed to: {{capability}}.",
+					{
+						plugin: pendingGrant?.plugin.name ?? "",
+						capability: pendingGrant ? capabilityLabel(pendingGrant.capability) : "",
+					},
+				)}
+				confirmLabel={t("v2.settings.plugins.grant", "Grant")}
+				variant="warning"
+				onConfirm={() => {
+					if (pendingGrant) {
+						setGrant(pendingGrant.plugin, pendingGrant.capability, true);
+					}
+					setPendingGrant(null);
+				}}
+				onCancel={() => setPendingGrant(null)}
+			/>
+		</div>
+	);
+}
@@ -0,0 +1,253 @@
+import { useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import type { ScriptInfo } from "../../../../shared/contracts";
+import { Button } from "../../../../shared/ui/Button";
+import { ConfirmDialog } from "../../../../shared/ui/ConfirmDialog";
+import { MinToggle } from "../../../../shared/ui/MinToggle";
+import { SettingsRow } from "../../../../shared/ui/SettingsRow";
+import { SettingsSectionGroup } from "../../../../shared/ui/SettingsSectionGroup";
+import { TextField } from "../../../../shared/ui/TextField";
+import { useSettingsEditor } from "../../model/editor";
+import {
+	useDeleteScriptMutation,
+	useSaveScriptMutation,
+	useScriptQuery,
+	useScriptsQuery,
+	useSetScriptEnabledMutation,
+} from "../../model/queries";
+
+const NEW_SCRIPT_SOURCE = `// Return a string to replace the incoming message.
+fn on_message_received(message) {
+	message
+}
+
+// Call add_context(text) to append to the system prompt.
+fn before_prompt_build(input) {
+}
+
+// Return a string to replace a tool result.
+fn after_tool_result(tool, result) {
+	result
+}
+`;
+
+interface ScriptDraft {
+	id: string;
+	name: string;
+	description: string;
+	source: string;
+	isNew: boolean;
+}
+
+export function ScriptsManagementPanel() {
+	const { t } = useTranslation();
+	const editor = useSettingsEditor();
+	const scriptsQuery = useScriptsQuery();
+	const saveScript = useSaveScriptMutation();
+	const setScriptEnabled = useSetScriptEnabledMutation();
+	const deleteScript = useDeleteScriptMutation();
+	const [selectedId, setSelectedId] = useState<string | null>(null);
+	const [draft, setDraft] = useState<ScriptDraft | null>(null);
+	const [pendingDelete, setPendingDelete] = useState<ScriptInfo | null>(null);
+	const [actionError, setActionError] = useState<string | null>(null);
+	const selectedQuery = useScriptQuery(selectedId);
+	const enabled = editor.readBoolean("scripting.enabled", false);
+	const scripts = scriptsQuery.data?.scripts ?? [];
+
+	useEffect(() => {
+		if (selectedQuery.data) {
+			const { id, name, description, source } = selectedQuery.data;
+			setDraft({ id, name, description, source, isNew: false });
+		}
+	}, [selectedQuery.data]);
+
+	const startNewScript = () => {
+		setActionError(null);
+		setSelectedId(null);
+		setDraft({
+			id: "",
+			name: "",
+			description: "",
+			source: NEW_SCRIPT_SOURCE,
+			isNew: true,
+		});
+	};
+
+	const saveDraft = async () => {
+		if (!draft) {
+			return;
+		}
+		try {
+			setActionError(null);
+			const saved = await saveScript.mutateAsync({
+				id: draft.id.trim(),
+				name: draft.name,
+				description: draft.description,
+				source: draft.source,
+			});
+			setSelectedId(saved.id);
+			setDraft({ ...draft, id: saved.id, isNew: false });
+		} catch (error) {
+			setActionError(error instanceof Error ? error.message : "Failed to save script.");
+		}
+	};
+
+	const confirmDelete = async () => {
+		const target = pendingDelete;
+		setPendingDelete(null);
+		if (!target) {
+			return;
+		}
+		try {
+			setActionError(null);
+			await deleteScript.mutateAsync(target.id);
+			if (selectedId === target.id) {
+				setSelectedId(null);
+				setDraft(null);
+			}
+		} catch (error) {
+			setActionError(error instanceof Error ? error.message : "Failed to delete script.");
+		}
+	};
+
+	return (
+		<div className="flex flex-col gap-8">
+			<SettingsSectionGroup title={t("v2.settings.scripts.title", "Scripts")}>
+				<SettingsRow
+					label={t("v2.settings.scripts.enabled", "Run script hooks")}
+					description={t(
+						"v2.settings.scripts.enabledDescription",
+						"Rhai scripts can rewrite messages, add prompt context and post-process tool results. Scripts may only call tools listed in scripting.allowed_tools.",
+					)}
+				>
+					<MinToggle
+						checked={enabled}
+						onChange={(checked) => editor.updateField("scripting.enabled", checked)}
+						label={enabled ? "Enabled" : "Disabled"}
+					/>
+				</SettingsRow>
+				<div>
+					<Button variant="secondary" onClick={startNewScript}>
+						{t("v2.settings.scripts.new", "New script")}
+					</Button>
+				</div>
+				{actionError ? <div className="text-sm text-red-200">{actionError}</div> : null}
+				{scriptsQuery.data && scripts.length === 0 ? (
+					<div className="text-sm text-text-muted">
+						{t("v2.settings.scripts.empty", "No scripts yet.")}
+					</div>
+				) : null}
+				<div className="grid gap-4 xl:grid-cols-2">
+					{scripts.map((script) => (
+						<div
+							key={script.id}
+							className="rounded-[24px] border border-primary/10 bg-white/55 p-5"
+						>
+							<div className="flex items-start justify-between gap-3">
+								<div>
+									<div className="text-base font-medium text-text-main">
+										{script.name}
+										<span className="ml-2 text-sm text-text-muted">{script.id}</span>
+									</div>
+									{script.description ? (
+										<div className="mt-1 text-sm text-text-muted">
+											{script.description}
+										</div>
+									) : null}
+								</div>
+								<MinToggle
+									checked={script.enabled}
+									onChange={(checked) =>
+										setScriptEnabled.mutate({ scriptId: script.id, enabled: checked })
+									}
+									label={script.enabled ? "Enabled" : "Disabled"}
+								/>
+							</div>
+							{script.hooks.length > 0 ? (
+								<div className="mt-3 text-xs text-text-muted">
+									{t("v2.settings.scripts.hooks", "Hooks")}: {script.hooks.join(", ")}
+								</div>
+							) : null}
+							{script.error ? (
+								<div className="mt-3 text-sm text-red-200">{script.error}</div>
+							) : null}
+							<div className="mt-4 flex gap-2">
+								<Button
+									variant="secondary"
+									onClick={() => {
+										setActionError(null);
+										setSelectedId(script.id);
+									}}
+								>
+									{t("v2.settings.scripts.edit", "Edit")}
+								</Button>
+								<Button variant="secondary" onClick={() => setPendingDelete(script)}>
+									{t("v2.settings.scripts.delete", "Delete")}
+								</Button>
+							</div>
+						</div>
+					))}
+				</div>
+			</SettingsSectionGroup>
+			{draft ? (
+				<SettingsSectionGroup
+					title={
+						draft.isNew
+							? t("v2.settings.scripts.new", "New script")
+							: t("v2.settings.scripts.editing", "Edit {{name}}", { name: draft.name })
+					}
+				>
+					<div className="grid gap-3 lg:grid-cols-2">
+						<TextField
+							value={draft.id}
+							disabled={!draft.isNew}
+							onChange={(event) => setDraft({ ...draft, id: event.target.value })}
+							placeholder="script-id"
+						/>
+						<TextField
+							value={draft.name}
+							onChange={(event) => setDraft({ ...draft, name: event.target.value })}
+							placeholder={t("v2.settings.scripts.name", "Name")}
+						/>
+					</div>
+					<TextField
+						value={draft.description}
+						onChange={(event) => setDraft({ ...draft, description: event.target.value })}
+						placeholder={t("v2.settings.scripts.description", "Description")}
+					/>
+					<textarea
+						value={draft.source}
+						onChange={(event) => setDraft({ ...draft, source: event.target.value })}
+						className="min-h-[320px] w-full rounded-md border border-border bg-surface px-3 py-2 font-mono text-sm text-text-main transition-colors duration-200 ease-out focus:border-primary focus:outline-none focus:ring-1 focus:ring-primary"
+						spellCheck={false}
+					/>
+					<div className="flex gap-2">
+						<Button disabled={saveScript.isPending} onClick={() => void saveDraft()}>
+							{t("v2.settings.scripts.save", "Save script")}
+						</Button>
+						<Button
+							variant="secondary"
+							onClick={() => {
+								setSelectedId(null);
+								setDraft(null);
+							}}
+						>
+							{t("v2.settings.scripts.close", "Close")}
+						</Button>
+					</div>
+				</SettingsSectionGroup>
+			) : null}
+			<ConfirmDialog
+				isOpen={pendingDelete !== null}
+				title={t("v2.settings.scripts.deleteTitle", "Delete script?")}
+				message={t("v2.settings.scripts.deleteMessage", "{{name}} will be removed.", {
+					name: pendingDelete?.name ?? "",
+				})}
+				confirmLabel={t("v2.settings.scripts.delete", "Delete")}
+				variant="danger"
+				onConfirm={() => void confirmDelete()}
+				onCancel={() => setPendingDelete(null)}
+			/>
+		</div>
+	);
+}
@@ -7,6 +7,8 @@ import { TextField } from "../../../../shared/ui/TextField";
 import { useSettingsEditor } from "../../model/editor";
 import { CredentialsManagementPanel } from "../components/CredentialsManagementPanel";
 import { McpManagementPanel } from "../components/McpManagementPanel";
+import { PluginsManagementPanel } from "../components/PluginsManagementPanel";
+import { ScriptsManagementPanel } from "../components/ScriptsManagementPanel";
 
 const PROVIDER_OPTIONS = [
 	{ label: "DuckDuckGo", value: "duckduckgo" },
@@ -30,6 +32,14 @@ export const CapabilitiesSettings: React.FC<CapabilitiesSettingsProps> = ({
 		return <McpManagementPanel />;
 	}
 
+	if (activeTab === "Plugins") {
+		return <PluginsManagementPanel />;
+	}
+
+	if (activeTab === "Scripts") {
+		return <ScriptsManagementPanel />;
+	}
+
 	if (activeTab === "Credentials") {
 		return <CredentialsManagementPanel />;
 	}
@@ -6,6 +6,7 @@ import { SelectionDot } from "../../../../shared/ui/SelectionDot";
 import { SettingsRow } from "../../../../shared/ui/SettingsRow";
 import { SettingsSectionGroup } from "../../../../shared/ui/SettingsSectionGroup";
 import { readNestedValue, useSettingsEditor } from "../../model/editor";
+import { LocalProfilesPanel } from "../components/LocalProfilesPanel";
 
 const LANGUAGE_OPTIONS = [
 	{ label: "English", value: "en" },
@@ -45,6 +46,10 @@ export const GeneralSettings: React.FC<GeneralSettingsProps> = ({
 	const searchThinking = editor.readBoolean("thinking.search_default", false);
 	const historyLimit = editor.readNumber("app.history_limit", 6);
 
+	if (activeTab === "Profiles") {
+		return <LocalProfilesPanel />;
+	}
+
 	if (activeTab === "Thinking") {
 		return (
 			<div className="flex flex-col">
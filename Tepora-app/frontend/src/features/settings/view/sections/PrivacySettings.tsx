@@ -33,6 +33,7 @@ export const PrivacySettings: React.FC<PrivacySettingsProps> = ({
 		"privacy.url_policy_preset",
 		"balanced",
 	);
+	const offline = editor.readBoolean("network.offline", false);
 	const urlDenylist = editor.readStringList("privacy.url_denylist", []).join(", ");
 	const quarantineEnabled = editor.readBoolean("quarantine.enabled", false);
 	const quarantineRequired = editor.readBoolean("quarantine.required", false);
@@ -116,6 +117,16 @@ export const PrivacySettings: React.FC<PrivacySettingsProps> = ({
 	) : (
 		<div className="flex flex-col">
 			<SettingsSectionGroup title="Privacy">
+				<SettingsRow
+					label="Offline Mode"
+					description="Block every outbound request except local loaders; blocked features can be allowed individually from the status banner"
+				>
+					<MinToggle
+						checked={offline}
+						onChange={(checked) => editor.updateField("network.offline", checked)}
+						label={offline ? "Offline" : "Online"}
+					/>
+				</SettingsRow>
 				<SettingsRow
 					label="Web Search Allowed"
 					description="Permit backend-driven web access for search and fetch tools"
@@ -18,7 +18,7 @@ export interface SettingsCategoryDefinition {
 }
 
 export const SETTINGS_CATEGORIES: SettingsCategoryDefinition[] = [
-	{ id: "General", label: "General", tabs: ["Basics", "Deliberate"] },
+	{ id: "General", label: "General", tabs: ["Basics", "Deliberate", "Profiles"] },
 	{
 		id: "Appearance",
 		label: "Appearance",
@@ -46,7 +46,7 @@ export const SETTINGS_CATEGORIES: SettingsCategoryDefinition[] = [
 	{
 		id: "Capabilities",
 		label: "Capabilities",
-		tabs: ["MCP Servers", "Web Search", "Credentials"],
+		tabs: ["MCP Servers", "Plugins", "Scripts", "Web Search", "Credentials"],
 	},
 	{
 		id: "Advanced",
@@ -36,7 +36,7 @@ vi.mock("../../../context/SettingsContext", () => ({
 			llm_manager: {
 				process_terminate_timeout: 5,
 				health_check_timeout: 10,
-				health_check_interval: 30,
+				health_check_interval_ms: 30,
 				tokenizer_model_key: "default",
 				cache_size: 1,
 			},
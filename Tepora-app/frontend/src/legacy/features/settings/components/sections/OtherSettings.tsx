@@ -116,15 +116,15 @@ const OtherSettings: React.FC = () => {
 									t("settings.models_settings.global_manager.health_check_interval") ||
 									"Health Check Interval"
 								}
-								isDirty={isLlmDirty("health_check_interval")}
+								isDirty={isLlmDirty("health_check_interval_ms")}
 							>
 								<FormInput
 									type="number"
-									value={llmConfig.health_check_interval}
+									value={llmConfig.health_check_interval_ms}
 									onChange={(v) =>
-										updateLlmManager("health_check_interval", v as number)
+										updateLlmManager("health_check_interval_ms", v as number)
 									}
-									step={0.1}
+									min={1}
 								/>
 							</FormGroup>
 							<FormGroup
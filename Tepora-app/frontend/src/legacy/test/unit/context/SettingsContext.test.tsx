@@ -30,7 +30,7 @@ const mockConfig = {
 	llm_manager: {
 		process_terminate_timeout: 5,
 		health_check_timeout: 5,
-		health_check_interval: 60,
+		health_check_interval_ms: 60,
 		tokenizer_model_key: "default",
 		cache_size: 1,
 	},
@@ -7,10 +7,72 @@ import { AppEntry } from "./app/entry";
 import { getSessionToken } from "./utils/sessionToken";
 import { logger } from "./utils/logger";
 
+import { useWorkspaceStore } from "./app/model/workspaceStore";
+import { setDynamicPort } from "./utils/api";
 import { backendReady, isDesktop, startSidecar } from "./utils/sidecar";
 
+// src-tauri/src/quick_ask.rs が開く小窓。サイドカーは起動せず、渡されたポートに繋ぐ
+const QUICK_ASK_VIEW = "quick-ask";
+
+async function initQuickAsk(rootElement: HTMLElement, params: URLSearchParams) {
+	const port = Number(params.get("port"));
+	if (Number.isInteger(port) && port > 0) {
+		setDynamicPort(port);
+	}
+	const { QuickAskScreen } = await import("./features/quickAsk/screen/QuickAskScreen");
+	ReactDOM.createRoot(rootElement).render(
+		<React.StrictMode>
+			<QuickAskScreen />
+		</React.StrictMode>,
+	);
+}
+
+// src-tauri/src/session_window.rs が開くセッション専用ウィンドウ。
+// メインとは別の WS 接続を持たせるため、IPC ではなく websocket で繋ぐ
+const SESSION_VIEW = "session";
+
+async function initSessionWindow(rootElement: HTMLElement, params: URLSearchParams) {
+	const port = Number(params.get("port"));
+	if (Number.isInteger(port) && port > 0) {
+		setDynamicPort(port);
+	}
+	window.__TRANSPORT_MODE__ = "websocket";
+	const sessionId = params.get("session");
+	if (sessionId) {
+		const store = useWorkspaceStore.getState();
+		store.setSelectedSessionId(sessionId);
+		store.setMobilePane("chat");
+	}
+
+	try {
+		await getSessionToken();
+	} catch (error) {
+		logger.warn("[Main] Failed to load session token:", error);
+	}
+
+	ReactDOM.createRoot(rootElement).render(
+		<React.StrictMode>
+			<AppEntry />
+		</React.StrictMode>,
+	);
+}
+
 // Start the backend sidecar and wait for it before mounting React
 async function init() {
+	const params = new URLSearchParams(window.location.search);
+	if (params.get("view") === QUICK_ASK_VIEW) {
+		const quickAskRoot = document.getElementById("root");
+		if (!quickAskRoot) throw new Error("Failed to find the root element");
+		await initQuickAsk(quickAskRoot, params);
+		return;
+	}
+	if (params.get("view") === SESSION_VIEW) {
+		const sessionRoot = document.getElementById("root");
+		if (!sessionRoot) throw new Error("Failed to find the root element");
+		await initSessionWindow(sessionRoot, params);
+		return;
+	}
+
 	// Initialize transport mode early so websocketStore.connect() chooses
 	// IPC-first in desktop mode before SettingsContext config fetch completes.
 	if (typeof window !== "undefined" && !window.__TRANSPORT_MODE__) {
@@ -24,8 +86,12 @@ async function init() {
 	// This ensures we get the fresh token generated by the backend
 	if (isDesktop()) {
 		logger.log("[Main] Desktop mode detected, waiting for backend ready...");
-		await backendReady;
+		const port = await backendReady;
 		logger.log("[Main] Backend ready, loading session token...");
+		// グローバルショートカットの小窓が同じバックエンドに繋げるように渡しておく
+		import("@tauri-apps/api/core")
+			.then(({ invoke }) => invoke("set_backend_port", { port }))
+			.catch((error) => logger.warn("[Main] Failed to share backend port:", error));
 	}
 
 	try {
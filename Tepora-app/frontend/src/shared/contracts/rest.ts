@@ -541,6 +541,87 @@ export const workspaceDocumentSchema = z.object({
 	editable: z.boolean(),
 });
 
+export const updateReadinessResponseSchema = z.object({
+	ready: z.boolean(),
+	blockers: z.array(
+		z.discriminatedUnion("kind", [
+			z.object({ kind: z.literal("agent_run"), count: z.number().int() }),
+			z.object({ kind: z.literal("download"), job_id: z.string() }),
+		]),
+	),
+	channel: z.enum(["stable", "beta"]),
+	auto_check: z.boolean(),
+});
+
+export const networkSubsystemSchema = z.enum([
+	"models",
+	"mcp_registry",
+	"search",
+	"web_fetch",
+	"http_tools",
+	"cloud_providers",
+	"binary_updates",
+	"mcp_servers",
+]);
+
+export const networkStatusResponseSchema = z.object({
+	offline: z.boolean(),
+	allowed: z.array(networkSubsystemSchema),
+	blocked: z.array(networkSubsystemSchema),
+});
+
+export const pluginCapabilitySchema = z.enum([
+	"tools",
+	"context_workers",
+	"graph_nodes",
+	"storage",
+]);
+
+export const pluginInfoSchema = z.object({
+	id: z.string(),
+	name: z.string(),
+	version: z.string(),
+	description: z.string(),
+	requested: z.array(pluginCapabilitySchema),
+	granted: z.array(pluginCapabilitySchema),
+	status: z.enum(["disabled", "loaded", "failed"]),
+	error: z.string().nullable().optional(),
+	tools: z.array(z.string()),
+	context_workers: z.array(z.string()),
+	nodes: z.array(z.string()),
+});
+
+export const pluginsResponseSchema = z.object({
+	enabled: z.boolean(),
+	host_available: z.boolean(),
+	plugins: z.array(pluginInfoSchema),
+});
+
+export const scriptHookSchema = z.enum([
+	"on_message_received",
+	"before_prompt_build",
+	"after_tool_result",
+]);
+
+export const scriptInfoSchema = z.object({
+	id: z.string(),
+	name: z.string(),
+	description: z.string(),
+	enabled: z.boolean(),
+	updated_at: z.string(),
+	hooks: z.array(scriptHookSchema),
+	error: z.string().nullable().optional(),
+});
+
+export const scriptDetailSchema = scriptInfoSchema.extend({
+	source: z.string(),
+});
+
+export const scriptsResponseSchema = z.object({
+	enabled: z.boolean(),
+	scripts: z.array(scriptInfoSchema),
+});
+
 export type ChatMode = z.infer<typeof chatModeSchema>;
 export type AgentMode = z.infer<typeof agentModeSchema>;
 export type SearchMode = z.infer<typeof searchModeSchema>;
@@ -585,3 +666,13 @@ export type WorkspaceProject = z.infer<typeof workspaceProjectSchema>;
 export type WorkspaceProjectsResponse = z.infer<typeof workspaceProjectsResponseSchema>;
 export type WorkspaceTreeResponse = z.infer<typeof workspaceTreeResponseSchema>;
 export type WorkspaceDocument = z.infer<typeof workspaceDocumentSchema>;
+export type UpdateReadinessResponse = z.infer<typeof updateReadinessResponseSchema>;
+export type NetworkSubsystem = z.infer<typeof networkSubsystemSchema>;
+export type NetworkStatusResponse = z.infer<typeof networkStatusResponseSchema>;
+export type PluginCapability = z.infer<typeof pluginCapabilitySchema>;
+export type PluginInfo = z.infer<typeof pluginInfoSchema>;
+export type PluginsResponse = z.infer<typeof pluginsResponseSchema>;
+export type ScriptHook = z.infer<typeof scriptHookSchema>;
+export type ScriptInfo = z.infer<typeof scriptInfoSchema>;
+export type ScriptDetail = z.infer<typeof scriptDetailSchema>;
+export type ScriptsResponse = z.infer<typeof scriptsResponseSchema>;
@@ -0,0 +1,260 @@
+// このファイルは backend-rs の `server::ws::schema` から生成される。手で編集しないこと。
+// 更新: TEPORA_UPDATE_WS_TYPES=1 cargo test ws_typescript_definitions_are_current
+
+export const WS_APP_PROTOCOL = "tepora.v1";
+
+/** サーバーから送るイベント。`type` で判別する。 */
+export type WsServerEvent = {
+	agentName?: string | null;
+	message?: string | null;
+	mode?: string | null;
+	nodeId?: string | null;
+	type: "chunk";
+} | {
+	content: string;
+	type: "thought";
+} | {
+	data: unknown;
+	type: "sentence";
+} | {
+	type: "done";
+	usage?: TurnUsage | null;
+} | {
+	type: "stopped";
+} | {
+	type: "regenerate_started";
+} | {
+	message: string;
+	type: "status";
+} | {
+	message: string;
+	type: "error";
+} | {
+	hints: RemediationHint[];
+	message: string;
+	/** ローダー名（ollama / lmstudio / llama_cpp など） */
+	provider: string;
+	type: "provider_unavailable";
+} | {
+	data: ActivityPayload;
+	type: "activity";
+} | {
+	messages: HistoryMessage[];
+	type: "history";
+} | {
+	data: unknown;
+	type: "search_results";
+} | {
+	data: unknown;
+	type: "research_report";
+} | {
+	data: unknown;
+	type: "plan_estimate";
+} | {
+	data: AgentPlan;
+	editable: boolean;
+	runId?: string | null;
+	type: "plan";
+} | {
+	data: unknown;
+	type: "tool_confirmation_request";
+} | {
+	nodeId: string;
+	output: unknown;
+	type: "node_completed";
+} | {
+	data: unknown;
+	requestId?: string | null;
+	sessionId: string;
+	type: "queue_position";
+} | {
+	sessionId?: string | null;
+	/** started / completed / error */
+	status: string;
+	type: "memory_generation";
+} | {
+	runId?: string | null;
+	sessionId: string;
+	type: "interaction_complete";
+} | {
+	defaults: SessionDefaults;
+	sessionId: string;
+	type: "session_changed";
+} | {
+	greeting?: string | null;
+	name: string;
+	personaId: string;
+	sessionId: string;
+	type: "persona_changed";
+} | {
+	defaults: SessionDefaults;
+	projectId: string;
+	sessionId: string;
+	sessions: unknown;
+	settings: unknown;
+	type: "project_changed";
+} | {
+	data: unknown;
+	type: "stats";
+} | {
+	data: unknown;
+	type: "inbox";
+} | {
+	data: unknown;
+	type: "notification";
+} | {
+	data: unknown;
+	type: "health";
+} | {
+	data: unknown;
+	type: "utilization";
+} | {
+	data: unknown;
+	type: "model_slot";
+} | {
+	data: unknown;
+	type: "config_changed";
+};
+
+/** どのイベントにも付きうる配送情報。 */
+export type WsEnvelope = {
+	requestId?: string | null;
+	/** 生成のストリーム ID（`requestId` と同じ値） */
+	streamId?: string | null;
+};
+
+/**
+ * クライアントから届くメッセージ。チャットの本文は `type` を付けずに送り、
+ * 操作は `type`（`WsClientMessageType`）で指定する。
+ */
+export type WsClientMessage = {
+	agentId?: string | null;
+	agentMode?: string | null;
+	approved?: boolean | null;
+	/** 委任元から渡された成果物（A2A）。実行の共有コンテキストに入る */
+	artifacts?: A2AArtifact[];
+	attachments?: unknown[];
+	decision?: ApprovalDecision;
+	/** `plan_estimate` への返答で残すステップ（0 始まり）。省略時はすべて残す */
+	keepSteps?: number[] | null;
+	message?: string | null;
+	mode?: string | null;
+	personaId?: string | null;
+	projectId?: string | null;
+	/** このメッセージで `rag_search` に追加で許すコレクション */
+	ragCollections?: string[] | null;
+	requestId?: string | null;
+	searchMode?: string | null;
+	sessionId?: string | null;
+	skipWebSearch?: boolean | null;
+	/** `"outline"` で SynthesizerNode を見出し→節ごとの二段階にする */
+	synthesisMode?: string | null;
+	/** このメッセージだけの温度。未指定ならセッションの既定値 */
+	temperature?: number | null;
+	thinkingBudget?: number | null;
+	timeout?: number | null;
+	ttlSeconds?: number | null;
+	type?: WsClientMessageType | null;
+};
+
+/** クライアントが送る `type`。チャットの本文は `type` を付けずに送る。 */
+export type WsClientMessageType = "stop" | "get_stats" | "perf_probe" | "set_session" | "tool_confirmation_response" | "switch_persona" | "switch_project" | "regenerate" | "subscribe_utilization" | "unsubscribe_utilization";
+
+/** An artifact exchanged between agents. */
+export type A2AArtifact = {
+	/** Optional description */
+	description?: string | null;
+	/** Unique artifact identifier */
+	id: string;
+	/** Arbitrary metadata */
+	metadata?: Record<string, unknown>;
+	/** Human readable name */
+	name: string;
+	/** Content parts */
+	parts: ArtifactPart[];
+};
+
+export type ActivityPayload = {
+	agentName?: string | null;
+	id: string;
+	message: string;
+	/** processing / done / error */
+	status: string;
+};
+
+export type AgentPlan = {
+	/** ユーザーが編集するたびに増える */
+	revision?: number;
+	steps: PlanStep[];
+};
+
+export type ApprovalDecision = "deny" | "once" | "always_until_expiry";
+
+/** One part of an A2A artifact. */
+export type ArtifactPart = {
+	kind: "text";
+	text: string;
+} | {
+	bytes_base64?: string | null;
+	kind: "file";
+	mime_type: string;
+	name: string;
+	uri?: string | null;
+} | {
+	data: unknown;
+	kind: "data";
+};
+
+export type HistoryMessage = {
+	content: string;
+	id: string;
+	isComplete: boolean;
+	mode: string;
+	personaId?: string | null;
+	/** user / assistant / system */
+	role: string;
+	timestamp: string;
+};
+
+export type PlanStep = {
+	/** 先に終えておくステップの ID */
+	depends_on?: string[];
+	id: string;
+	text: string;
+	/** 使いそうなツール名。実行を縛るものではない */
+	tool_hints?: string[];
+};
+
+/** UI に出す対処。`action` で出し分け、`message` はそのまま表示できる文面 */
+export type RemediationHint = {
+	/** start_ollama / start_lmstudio / start_server / rerun_setup / check_network / switch_model */
+	action: string;
+	message: string;
+};
+
+/**
+ * セッションの既定のモード・ペルソナ・RAG コレクション・温度。
+ * メッセージ側で指定した値がさらに優先される。
+ */
+export type SessionDefaults = {
+	/** 未設定なら `chat` */
+	mode?: string | null;
+	/** セッションでペルソナを固定していないときに使う。未設定なら `active_character` */
+	persona_id?: string | null;
+	/** `rag_search` で参照できる追加のコレクション */
+	rag_collections?: string[];
+	temperature?: number | null;
+};
+
+/**
+ * 1 回の応答で使ったトークン数。プロバイダーが返さなかった値は見積もりで埋め、
+ * そのときは `estimated` を立てる。
+ */
+export type TurnUsage = {
+	completion_tokens: number;
+	estimated: boolean;
+	prompt_tokens: number;
+	total_tokens: number;
+};
+
+export type WsServerMessage = WsServerEvent & WsEnvelope;
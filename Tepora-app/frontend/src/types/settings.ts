@@ -50,7 +50,7 @@ export interface Config {
         loader?: string;
         process_terminate_timeout: number;
         health_check_timeout: number;
-        health_check_interval: number;
+        health_check_interval_ms: number;
         tokenizer_model_key: string;
         cache_size: number;
     };